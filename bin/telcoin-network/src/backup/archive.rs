@@ -0,0 +1,354 @@
+//! Copy the data directory to and from a backup with a checksum manifest.
+//!
+//! A backup directory contains the copied files under `data/` and a `manifest.json` describing
+//! every file. The manifest is written last, so a backup without one is incomplete.
+
+use crate::version::SHORT_VERSION;
+use blake2::Digest as _;
+use eyre::{ensure, eyre, Context};
+use reth_db::lockfile::StorageLock;
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{Read as _, Write as _},
+    path::{Component, Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tn_config::{write_file_atomic, TelcoinDirs as _};
+use tn_node::dirs::{DataDirChainPath, VALIDATOR_KEYS_DIR};
+use tn_types::{DefaultHashFunction, B256};
+use tracing::warn;
+
+/// The name of the manifest file in a backup directory.
+pub const MANIFEST_FILE: &str = "manifest.json";
+
+/// The subdirectory of a backup that holds the copied files.
+const DATA_DIR: &str = "data";
+
+/// Lock files are specific to the running process and never copied.
+///
+/// The first is the lock the node holds on the execution database directory.
+const LOCK_FILES: [&str; 2] = ["lock", "mdbx.lck"];
+
+/// The size of the buffer used to copy and hash files.
+const BUFFER_SIZE: usize = 1024 * 1024;
+
+/// Describes the contents of a backup.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    /// The version of telcoin-network that created the backup.
+    pub version: String,
+    /// The chain id of the node that was backed up.
+    pub chain_id: u64,
+    /// The unix timestamp (seconds) when the backup was created.
+    pub created_at: u64,
+    /// Every file in the backup.
+    pub files: Vec<BackupFile>,
+}
+
+impl BackupManifest {
+    /// The total size of all files in bytes.
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|file| file.size).sum()
+    }
+}
+
+/// A file in a backup.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BackupFile {
+    /// The path relative to the data directory.
+    pub path: PathBuf,
+    /// The size of the file in bytes.
+    pub size: u64,
+    /// The blake2b-256 checksum of the file's contents.
+    pub checksum: B256,
+}
+
+/// Copy the data directory to `output`, excluding validator keys.
+pub(super) fn create(
+    datadir: &DataDirChainPath,
+    output: &Path,
+    chain_id: u64,
+) -> eyre::Result<BackupManifest> {
+    let root = datadir.as_ref();
+    ensure!(root.is_dir(), "data directory {root:?} does not exist");
+    ensure!(!has_entries(output)?, "backup directory {output:?} is not empty");
+    fs::create_dir_all(output)?;
+    ensure!(
+        !output.canonicalize()?.starts_with(root.canonicalize()?),
+        "backup directory {output:?} can not be inside the data directory"
+    );
+
+    // hold the lock so the node can not start while files are copied
+    let _lock = lock_datadir(datadir, "backup or restore")?;
+
+    let data = output.join(DATA_DIR);
+    let mut files = Vec::new();
+    for path in collect_files(root)? {
+        let (size, checksum) = copy_with_checksum(&root.join(&path), Some(&data.join(&path)))?;
+        files.push(BackupFile { path, size, checksum });
+    }
+
+    let manifest = BackupManifest {
+        version: SHORT_VERSION.to_string(),
+        chain_id,
+        created_at: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        files,
+    };
+    write_file_atomic(output.join(MANIFEST_FILE), serde_json::to_string_pretty(&manifest)?)?;
+
+    Ok(manifest)
+}
+
+/// Verify the backup at `backup` against its manifest and copy it into the data directory.
+///
+/// Existing data is only replaced if `force` is set. Validator keys are left untouched.
+pub(super) fn restore(
+    backup: &Path,
+    datadir: &DataDirChainPath,
+    chain_id: u64,
+    force: bool,
+) -> eyre::Result<BackupManifest> {
+    let manifest_path = backup.join(MANIFEST_FILE);
+    let manifest: BackupManifest = serde_json::from_slice(
+        &fs::read(&manifest_path).with_context(|| format!("missing manifest {manifest_path:?}"))?,
+    )?;
+    ensure!(
+        manifest.chain_id == chain_id,
+        "backup is for chain {} but the node is running chain {chain_id}",
+        manifest.chain_id
+    );
+
+    // verify the entire backup before changing anything
+    let data = backup.join(DATA_DIR);
+    for file in manifest.files.iter() {
+        ensure!(
+            file.path.components().all(|c| matches!(c, Component::Normal(_))),
+            "invalid path in manifest: {:?}",
+            file.path
+        );
+        let (size, checksum) = copy_with_checksum(&data.join(&file.path), None)?;
+        ensure!(
+            size == file.size && checksum == file.checksum,
+            "backup file {:?} does not match the manifest",
+            file.path
+        );
+    }
+
+    let root = datadir.as_ref();
+    // hold the lock for the entire restore so the node can not start with partial data
+    fs::create_dir_all(datadir.db())?;
+    let _lock = lock_datadir(datadir, "backup or restore")?;
+    let existing = existing_data(datadir)?;
+    if !existing.is_empty() {
+        ensure!(force, "data directory {root:?} already contains data, use --force to replace it");
+        for path in existing {
+            if path.is_dir() {
+                fs::remove_dir_all(path)?;
+            } else {
+                fs::remove_file(path)?;
+            }
+        }
+    }
+
+    for file in manifest.files.iter() {
+        let (_, checksum) =
+            copy_with_checksum(&data.join(&file.path), Some(&root.join(&file.path)))?;
+        ensure!(checksum == file.checksum, "backup file {:?} changed during restore", file.path);
+    }
+
+    if !datadir.validator_keys_path().exists() {
+        warn!(
+            target: "tn::backup",
+            path = ?datadir.validator_keys_path(),
+            "validator keys are not included in backups - restore them before starting the node"
+        );
+    }
+
+    Ok(manifest)
+}
+
+/// Lock the execution database so the node can not run while the data directory is in use.
+///
+/// The node only releases the lock after both databases are flushed during shutdown.
+pub(crate) fn lock_datadir(
+    datadir: &DataDirChainPath,
+    command: &str,
+) -> eyre::Result<Option<StorageLock>> {
+    let db = datadir.db();
+    if !db.exists() {
+        return Ok(None);
+    }
+
+    StorageLock::try_acquire(&db)
+        .map(Some)
+        .map_err(|e| eyre!("the node must be stopped before {command}: {e}"))
+}
+
+/// Return the relative paths of every file to back up, sorted.
+fn collect_files(root: &Path) -> eyre::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let mut dirs = vec![PathBuf::new()];
+    while let Some(dir) = dirs.pop() {
+        for entry in fs::read_dir(root.join(&dir))? {
+            let entry = entry?;
+            let path = dir.join(entry.file_name());
+            if path == Path::new(VALIDATOR_KEYS_DIR) {
+                continue;
+            }
+
+            if entry.file_type()?.is_dir() {
+                dirs.push(path);
+            } else if !LOCK_FILES.iter().any(|lock| entry.file_name() == *lock) {
+                files.push(path);
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Return the entries in the data directory that a restore replaces.
+///
+/// The execution database directory is not replaced, only its contents, so the lock held during
+/// the restore is kept.
+fn existing_data(datadir: &DataDirChainPath) -> eyre::Result<Vec<PathBuf>> {
+    let db = datadir.db();
+    let mut existing = Vec::new();
+    for entry in fs::read_dir(datadir.as_ref())? {
+        let entry = entry?;
+        if entry.path() == db {
+            for db_entry in fs::read_dir(&db)? {
+                let db_entry = db_entry?;
+                if db_entry.file_name() != LOCK_FILES[0] {
+                    existing.push(db_entry.path());
+                }
+            }
+        } else if entry.file_name() != VALIDATOR_KEYS_DIR {
+            existing.push(entry.path());
+        }
+    }
+
+    Ok(existing)
+}
+
+/// Returns true if the path is a directory with at least one entry.
+fn has_entries(path: &Path) -> eyre::Result<bool> {
+    if !path.exists() {
+        return Ok(false);
+    }
+
+    Ok(fs::read_dir(path)?.next().is_some())
+}
+
+/// Hash a file, copying it to `dest` if provided.
+///
+/// Returns the size and checksum of the file.
+fn copy_with_checksum(src: &Path, dest: Option<&Path>) -> eyre::Result<(u64, B256)> {
+    let mut reader = File::open(src).with_context(|| format!("failed to open {src:?}"))?;
+    let mut writer = match dest {
+        Some(dest) => {
+            if let Some(parent) = dest.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            Some(File::create(dest).with_context(|| format!("failed to create {dest:?}"))?)
+        }
+        None => None,
+    };
+
+    let mut hasher = DefaultHashFunction::new();
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut size = 0;
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+        if let Some(writer) = writer.as_mut() {
+            writer.write_all(&buffer[..read])?;
+        }
+        size += read as u64;
+    }
+
+    if let Some(writer) = writer {
+        writer.sync_all()?;
+    }
+
+    Ok((size, B256::from_slice(&hasher.finalize())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth::dirs::MaybePlatformPath;
+    use reth_chainspec::Chain;
+    use std::str::FromStr as _;
+    use tempfile::tempdir;
+    use tn_node::dirs::{default_datadir_args, DataDirPath};
+
+    fn datadir(path: &Path) -> DataDirChainPath {
+        MaybePlatformPath::<DataDirPath>::from_str(path.to_str().expect("valid path"))
+            .expect("valid datadir")
+            .unwrap_or_chain_default(Chain::from_id(2017), default_datadir_args())
+            .into()
+    }
+
+    #[test]
+    fn test_backup_roundtrip() {
+        let tmp = tempdir().expect("tempdir created");
+        let source = datadir(&tmp.path().join("source"));
+        let files = [
+            ("db/mdbx.dat", "execution"),
+            ("db/mdbx.lck", "lock"),
+            ("consensus-db/data", "consensus"),
+            ("telcoin-network.yaml", "config"),
+            ("validator-keys/bls.key", "secret"),
+        ];
+        for (path, contents) in files {
+            let path = source.as_ref().join(path);
+            fs::create_dir_all(path.parent().expect("parent")).expect("dir created");
+            fs::write(path, contents).expect("file written");
+        }
+
+        let backup = tmp.path().join("backup");
+        let manifest = create(&source, &backup, 2017).expect("backup created");
+        let paths: Vec<_> = manifest.files.iter().map(|f| f.path.clone()).collect();
+        assert_eq!(
+            paths,
+            ["consensus-db/data", "db/mdbx.dat", "telcoin-network.yaml"].map(PathBuf::from)
+        );
+
+        // backups are never written over
+        assert!(create(&source, &backup, 2017).is_err());
+
+        let target = datadir(&tmp.path().join("target"));
+        assert!(restore(&backup, &target, 1, false).is_err(), "wrong chain");
+        restore(&backup, &target, 2017, false).expect("backup restored");
+        assert_eq!(
+            fs::read_to_string(target.as_ref().join("db/mdbx.dat")).expect("file read"),
+            "execution"
+        );
+        assert!(!target.validator_keys_path().exists());
+
+        // existing data requires force
+        assert!(restore(&backup, &target, 2017, false).is_err());
+        fs::write(target.as_ref().join("stale"), "stale").expect("file written");
+        fs::write(target.db().join("stale"), "stale").expect("file written");
+        restore(&backup, &target, 2017, true).expect("backup restored with force");
+        assert!(!target.as_ref().join("stale").exists());
+        assert!(!target.db().join("stale").exists());
+        // the lock is released once the restore is finished
+        assert!(!target.db().join(LOCK_FILES[0]).exists());
+
+        // corrupt backups are rejected before the data directory is changed
+        fs::write(backup.join(DATA_DIR).join("db/mdbx.dat"), "corrupt").expect("file written");
+        assert!(restore(&backup, &target, 2017, true).is_err());
+        assert_eq!(
+            fs::read_to_string(target.as_ref().join("db/mdbx.dat")).expect("file read"),
+            "execution"
+        );
+    }
+}
@@ -0,0 +1,118 @@
+//! Backup command.
+//!
+//! Snapshot a validator's data directory and restore it for disaster recovery.
+
+mod archive;
+pub(crate) use archive::lock_datadir;
+pub use archive::{BackupFile, BackupManifest, MANIFEST_FILE};
+
+use crate::args::clap_genesis_parser;
+use clap::{Args, Subcommand};
+use reth::dirs::MaybePlatformPath;
+use reth_chainspec::ChainSpec;
+use std::{path::PathBuf, sync::Arc};
+use tn_node::dirs::{default_datadir_args, DataDirChainPath, DataDirPath};
+use tracing::info;
+
+/// Create and restore backups of the node's data directory.
+///
+/// The node must be stopped so both databases are flushed and checkpointed before they are copied.
+/// Validator keys are never included in a backup and must be kept in secure storage separately.
+#[derive(Debug, Args)]
+pub struct BackupArgs {
+    /// The path to the data dir for all telcoin-network files and subdirectories.
+    ///
+    /// Defaults to the OS-specific data directory:
+    ///
+    /// - Linux: `$XDG_DATA_HOME/telcoin-network/` or `$HOME/.local/share/telcoin-network/`
+    /// - Windows: `{FOLDERID_RoamingAppData}/telcoin-network/`
+    /// - macOS: `$HOME/Library/Application Support/telcoin-network/`
+    #[arg(long, value_name = "DATA_DIR", verbatim_doc_comment, default_value_t, global = true)]
+    pub datadir: MaybePlatformPath<DataDirPath>,
+
+    /// The chain this node is running.
+    #[arg(
+        long,
+        value_name = "CHAIN_OR_PATH",
+        default_value = "adiri",
+        value_parser = clap_genesis_parser,
+        required = false,
+        global = true,
+    )]
+    pub chain: Arc<ChainSpec>,
+
+    /// The backup command to run.
+    #[command(subcommand)]
+    pub command: BackupSubcommand,
+}
+
+/// Backup subcommands.
+#[derive(Debug, Subcommand)]
+pub enum BackupSubcommand {
+    /// Snapshot the data directory with a checksum manifest.
+    #[command(name = "create")]
+    Create(CreateArgs),
+    /// Verify a backup and restore it into the data directory.
+    #[command(name = "restore")]
+    Restore(RestoreArgs),
+}
+
+/// Create a backup.
+#[derive(Debug, Args)]
+pub struct CreateArgs {
+    /// The directory to write the backup to. Must not exist or be empty.
+    #[arg(long, value_name = "DIR")]
+    pub output: PathBuf,
+}
+
+/// Restore a backup.
+#[derive(Debug, Args)]
+pub struct RestoreArgs {
+    /// The directory of the backup to restore.
+    #[arg(long, value_name = "DIR")]
+    pub from: PathBuf,
+
+    /// Replace existing data in the data directory.
+    ///
+    /// Everything in the data directory except the validator keys is removed before restoring.
+    #[arg(long)]
+    pub force: bool,
+}
+
+impl BackupArgs {
+    /// Execute command
+    pub fn execute(&self) -> eyre::Result<()> {
+        let datadir = self.data_dir();
+        let chain_id = self.chain.chain.id();
+
+        match &self.command {
+            BackupSubcommand::Create(args) => {
+                let manifest = archive::create(&datadir, &args.output, chain_id)?;
+                info!(
+                    target: "tn::backup",
+                    path = ?args.output,
+                    files = manifest.files.len(),
+                    bytes = manifest.total_size(),
+                    "backup created"
+                );
+            }
+            BackupSubcommand::Restore(args) => {
+                let manifest = archive::restore(&args.from, &datadir, chain_id, args.force)?;
+                info!(
+                    target: "tn::backup",
+                    path = ?datadir,
+                    files = manifest.files.len(),
+                    created_at = manifest.created_at,
+                    "backup restored"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the chain specific path to the data dir.
+    fn data_dir(&self) -> DataDirChainPath {
+        self.datadir.unwrap_or_chain_default(self.chain.chain, default_datadir_args()).into()
+    }
+}
@@ -0,0 +1,26 @@
+//! Run a scripted scenario against a local network of validators.
+//!
+//! Exits with 1 if a step failed and 2 if the scenario could not run.
+
+use clap::Parser as _;
+use telcoin_network::harness::ItArgs;
+
+fn main() {
+    let args = ItArgs::parse();
+    // the harness runs every validator as a child process of this binary
+    if args.node.is_some() {
+        if let Err(err) = args.run_node() {
+            eprintln!("Error: {err:?}");
+            std::process::exit(2);
+        }
+        return;
+    }
+    match args.execute() {
+        Ok(suite) if suite.passed() => {}
+        Ok(_) => std::process::exit(1),
+        Err(err) => {
+            eprintln!("Error: {err:?}");
+            std::process::exit(2);
+        }
+    }
+}
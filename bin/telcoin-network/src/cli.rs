@@ -1,16 +1,19 @@
 //! CLI definition and entrypoint to executable
 use crate::{
     args::clap_genesis_parser,
-    genesis, keytool, node,
+    backup, committee, db, devnet, genesis, keytool,
+    logs::{self, LogFilterReloader},
+    node, status,
     version::{LONG_VERSION, SHORT_VERSION},
 };
-use clap::{value_parser, Parser, Subcommand};
+use clap::{value_parser, Command, CommandFactory, FromArgMatches, Parser, Subcommand};
 use reth_chainspec::ChainSpec;
 use reth_cli_commands::node::NoArgs;
 use reth_db::DatabaseEnv;
 use reth_node_core::args::LogArgs;
-use reth_tracing::FileWorkerGuard;
-use std::{ffi::OsString, fmt, sync::Arc};
+use reth_tracing::{FileWorkerGuard, LogFormat};
+use std::{collections::HashMap, ffi::OsString, fmt, sync::Arc};
+use tn_config::ENV_PREFIX;
 use tn_node::{dirs::DataDirChainPath, engine::TnBuilder};
 
 /// The main TN cli interface.
@@ -51,9 +54,21 @@ pub struct Cli<Ext: clap::Args + fmt::Debug = NoArgs> {
     /// - AUTH_PORT: default + `instance` * 100 - 100
     /// - HTTP_RPC_PORT: default - `instance` + 1
     /// - WS_RPC_PORT: default + `instance` * 2 - 2
-    #[arg(long, value_name = "INSTANCE", global = true, default_value_t = 1, value_parser = value_parser!(u16).range(..=200))]
+    /// - METRICS_PORT: `--metrics` + `instance` - 1
+    /// - CONSENSUS_METRICS_PORT: `--consensus-metrics` + `instance` - 1
+    ///
+    /// Use the same `--instance` with `keytool generate` to offset the consensus network ports.
+    #[arg(long, value_name = "INSTANCE", global = true, default_value_t = 1, value_parser = value_parser!(u16).range(1..=200))]
     pub instance: u16,
 
+    /// The format for stdout and file logs.
+    ///
+    /// Overrides `--log.stdout.format` and `--log.file.format`. With `json`, every line includes
+    /// the fields of the spans it was logged in, such as the node id, round, sub-dag index, and
+    /// the correlation id of network messages, so logs from several nodes can be matched up.
+    #[arg(long = "log.format", value_name = "FORMAT", global = true)]
+    pub log_format: Option<LogFormat>,
+
     /// The log configuration.
     #[clap(flatten)]
     pub logs: LogArgs,
@@ -76,6 +91,35 @@ impl Cli {
 }
 
 impl<Ext: clap::Args + fmt::Debug> Cli<Ext> {
+    /// Parse the CLI arguments, falling back to `TN__` environment variables.
+    ///
+    /// Exits the process with clap's usage message if parsing fails.
+    pub fn parse_with_env() -> Self {
+        Self::try_parse_with_env_from(std::env::args_os(), std::env::vars_os())
+            .unwrap_or_else(|e| e.exit())
+    }
+
+    /// Parse the given arguments, falling back to the `TN__` variables in `vars`.
+    ///
+    /// Every named argument can be set with an environment variable derived from its long name:
+    /// the name is uppercased, `.` becomes `__`, and `-` becomes `_`. For example, `--http.port`
+    /// reads `TN__HTTP__PORT` and `--rpc-url` reads `TN__RPC_URL`. Arguments passed on the command
+    /// line take precedence.
+    pub fn try_parse_with_env_from<I, T, V>(itr: I, vars: V) -> Result<Self, clap::error::Error>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+        V: IntoIterator<Item = (OsString, OsString)>,
+    {
+        let vars = vars
+            .into_iter()
+            .filter_map(|(key, value)| Some((key.into_string().ok()?, value)))
+            .filter(|(key, _)| key.starts_with(ENV_PREFIX))
+            .collect();
+        let mut matches = with_env_overrides(Self::command(), &vars).try_get_matches_from(itr)?;
+        Self::from_arg_matches_mut(&mut matches)
+    }
+
     /// Execute the configured cli command.
     ///
     /// This accepts a closure that is used to launch the node via the
@@ -125,26 +169,67 @@ impl<Ext: clap::Args + fmt::Debug> Cli<Ext> {
         // add network name to logs dir
         self.logs.log_file_directory =
             self.logs.log_file_directory.join(self.chain.chain.to_string());
+        if let Some(format) = self.log_format {
+            self.logs.log_stdout_format = format;
+            self.logs.log_file_format = format;
+        }
 
-        let _guard = self.init_tracing()?;
+        let (_guard, log_filter) = self.init_tracing()?;
 
         match self.command {
             Commands::Genesis(command) => command.execute(),
-            Commands::Node(command) => command.execute(true, launcher),
+            Commands::Node(command) => command.execute(true, move |mut builder, ext, datadir| {
+                builder.log_filter = Some(Arc::new(log_filter));
+                launcher(builder, ext, datadir)
+            }),
             Commands::Keytool(command) => command.execute(),
+            Commands::Status(command) => command.execute(),
+            Commands::Devnet(command) => command.execute(),
+            Commands::Committee(command) => command.execute(),
+            Commands::Backup(command) => command.execute(),
+            Commands::Db(command) => command.execute(),
         }
     }
 
     /// Initializes tracing with the configured options.
     ///
     /// If file logging is enabled, this function returns a guard that must be kept alive to ensure
-    /// that all logs are flushed to disk.
-    pub fn init_tracing(&self) -> eyre::Result<Option<FileWorkerGuard>> {
-        let guard = self.logs.init_tracing()?;
-        Ok(guard)
+    /// that all logs are flushed to disk. The returned [LogFilterReloader] changes the stdout log
+    /// filter while the node runs.
+    pub fn init_tracing(&self) -> eyre::Result<(Option<FileWorkerGuard>, LogFilterReloader)> {
+        logs::init_tracing(&self.logs)
     }
 }
 
+/// Use the `TN__` variable for a named argument as its default, for every argument in the command
+/// tree.
+///
+/// Arguments that already read from an environment variable are unchanged. An argument set by a
+/// variable is no longer required on the command line.
+fn with_env_overrides(cmd: Command, vars: &HashMap<String, OsString>) -> Command {
+    let subcommands: Vec<String> =
+        cmd.get_subcommands().map(|sub| sub.get_name().to_string()).collect();
+    let mut cmd = cmd.mut_args(|arg| {
+        let value = arg
+            .get_long()
+            .filter(|long| arg.get_env().is_none() && !matches!(*long, "help" | "version"))
+            .and_then(|long| vars.get(&env_var_name(long)));
+        match value {
+            Some(value) => arg.default_value(value.clone()).required(false),
+            None => arg,
+        }
+    });
+    for name in subcommands {
+        cmd = cmd.mut_subcommand(name, |sub| with_env_overrides(sub, vars));
+    }
+    cmd
+}
+
+/// The environment variable name for an argument's long name.
+fn env_var_name(long: &str) -> String {
+    format!("{ENV_PREFIX}{}", long.replace('.', "__").replace('-', "_").to_uppercase())
+}
+
 /// Commands to be executed
 #[derive(Debug, Subcommand)]
 pub enum Commands<Ext: clap::Args + fmt::Debug = NoArgs> {
@@ -160,6 +245,26 @@ pub enum Commands<Ext: clap::Args + fmt::Debug = NoArgs> {
     /// Start the node
     #[command(name = "node")]
     Node(Box<node::NodeCommand<Ext>>),
+
+    /// Query the status of a running node.
+    #[command(name = "status")]
+    Status(status::StatusArgs),
+
+    /// Run a local multi-validator network in this process for development.
+    #[command(name = "devnet")]
+    Devnet(devnet::DevnetArgs),
+
+    /// Prepare for committee changes between epochs.
+    #[command(name = "committee")]
+    Committee(committee::CommitteeArgs),
+
+    /// Back up or restore the node's data directory.
+    #[command(name = "backup")]
+    Backup(backup::BackupArgs),
+
+    /// Maintain the node's databases.
+    #[command(name = "db")]
+    Db(db::DbArgs),
 }
 
 #[cfg(test)]
@@ -168,6 +273,208 @@ mod tests {
     use clap::CommandFactory;
     use reth::args::ColorMode;
 
+    #[test]
+    fn parse_log_format() {
+        let tn = Cli::try_parse_args_from(["tn", "node", "--log.format", "json"]).unwrap();
+        assert_eq!(tn.log_format, Some(LogFormat::Json));
+    }
+
+    #[test]
+    fn parse_dev_ephemeral() {
+        let tn = Cli::try_parse_args_from(["tn", "node", "--dev.ephemeral"]).unwrap();
+        let Commands::Node(command) = tn.command else { panic!("expected node command") };
+        assert!(command.dev_ephemeral);
+    }
+
+    #[test]
+    fn parse_authorized_builders() {
+        let first = tn_types::Address::with_last_byte(1);
+        let second = tn_types::Address::with_last_byte(2);
+        let tn = Cli::try_parse_args_from([
+            "tn".to_string(),
+            "node".to_string(),
+            format!("--builder.authorized={first},{second}"),
+        ])
+        .unwrap();
+        let Commands::Node(command) = tn.command else { panic!("expected node command") };
+        assert_eq!(command.authorized_builders, vec![first, second]);
+    }
+
+    #[test]
+    fn parse_inclusion_promises() {
+        let tn = Cli::try_parse_args_from(["tn", "node"]).unwrap();
+        let Commands::Node(command) = tn.command else { panic!("expected node command") };
+        assert!(!command.inclusion_promises);
+
+        let tn = Cli::try_parse_args_from(["tn", "node", "--worker.inclusion-promises"]).unwrap();
+        let Commands::Node(command) = tn.command else { panic!("expected node command") };
+        assert!(command.inclusion_promises);
+    }
+
+    #[test]
+    fn parse_batch_mode() {
+        let tn = Cli::try_parse_args_from([
+            "tn",
+            "node",
+            "--batch.mode",
+            "hybrid",
+            "--batch.interval",
+            "250ms",
+            "--batch.gas-target",
+            "30000000",
+        ])
+        .unwrap();
+        let Commands::Node(command) = tn.command else { panic!("expected node command") };
+        let mode = command
+            .batch_mode
+            .expect("batch mode set")
+            .mining_mode(command.batch_interval, command.batch_gas_target, None)
+            .unwrap();
+        assert_eq!(
+            mode,
+            tn_config::MiningMode::Hybrid {
+                interval: std::time::Duration::from_millis(250),
+                gas_target: Some(30_000_000),
+                size_target: None
+            }
+        );
+
+        // targets only apply to hybrid mode
+        let tn = Cli::try_parse_args_from([
+            "tn",
+            "node",
+            "--batch.mode",
+            "interval",
+            "--batch.size-target",
+            "1000",
+        ])
+        .unwrap();
+        let Commands::Node(command) = tn.command else { panic!("expected node command") };
+        assert!(command
+            .batch_mode
+            .expect("batch mode set")
+            .mining_mode(command.batch_interval, None, command.batch_size_target)
+            .is_err());
+    }
+
+    #[test]
+    fn parse_rpc_replica() {
+        let tn = Cli::try_parse_args_from(["tn", "node", "--rpc.replica"]).unwrap();
+        let Commands::Node(command) = tn.command else { panic!("expected node command") };
+        assert!(command.rpc_replica);
+
+        // replicas read another node's data, they can not be ephemeral
+        let res = Cli::try_parse_args_from(["tn", "node", "--rpc.replica", "--dev.ephemeral"]);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn parse_storage_encryption_secret_file() {
+        let args = ["tn", "node", "--storage.encryption-secret-file", "/run/secrets/storage"];
+        let tn = Cli::try_parse_args_from(args).unwrap();
+        let Commands::Node(command) = tn.command else { panic!("expected node command") };
+        assert_eq!(
+            command.storage_encryption_secret_file,
+            Some(std::path::PathBuf::from("/run/secrets/storage"))
+        );
+
+        // ephemeral nodes never write consensus data to disk
+        let res = Cli::try_parse_args_from([&args[..], &["--dev.ephemeral"]].concat());
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn parse_grpc_addr() {
+        let tn = Cli::try_parse_args_from(["tn", "node"]).unwrap();
+        let Commands::Node(command) = tn.command else { panic!("expected node command") };
+        assert!(command.grpc.is_none());
+
+        let tn =
+            Cli::try_parse_args_from(["tn", "node", "--grpc.addr", "127.0.0.1:50051"]).unwrap();
+        let Commands::Node(command) = tn.command else { panic!("expected node command") };
+        assert_eq!(command.grpc, Some("127.0.0.1:50051".parse().unwrap()));
+
+        // replicas do not run consensus
+        let res =
+            Cli::try_parse_args_from(["tn", "node", "--rpc.replica", "--grpc.addr", ":50051"]);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn parse_audit() {
+        let tn = Cli::try_parse_args_from(["tn", "node", "--audit"]).unwrap();
+        let Commands::Node(command) = tn.command else { panic!("expected node command") };
+        assert!(command.audit);
+        assert!(command.audit_dir.is_none());
+
+        let tn = Cli::try_parse_args_from(["tn", "node", "--audit", "--audit.dir", "/tmp/audit"])
+            .unwrap();
+        let Commands::Node(command) = tn.command else { panic!("expected node command") };
+        assert_eq!(command.audit_dir, Some("/tmp/audit".into()));
+
+        // the directory is only used in audit mode
+        let res = Cli::try_parse_args_from(["tn", "node", "--audit.dir", "/tmp/audit"]);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn parse_address_index() {
+        let tn = Cli::try_parse_args_from(["tn", "node"]).unwrap();
+        let Commands::Node(command) = tn.command else { panic!("expected node command") };
+        assert!(!command.address_index);
+
+        let tn = Cli::try_parse_args_from(["tn", "node", "--index.addresses"]).unwrap();
+        let Commands::Node(command) = tn.command else { panic!("expected node command") };
+        assert!(command.address_index);
+    }
+
+    #[test]
+    fn parse_bootnodes() {
+        let peer_id = "12D3KooWEyoppNCUx8Yx66oV9fJnriXwCcXwDDUA2kj6vnc6iDEp";
+        let bootnode = format!("/ip4/10.0.0.1/udp/44894/quic-v1/p2p/{peer_id}");
+        let tn =
+            Cli::try_parse_args_from(["tn", "node", "--consensus.bootnodes", &bootnode]).unwrap();
+        let Commands::Node(command) = tn.command else { panic!("expected node command") };
+        assert_eq!(command.bootnodes, vec![bootnode.parse().unwrap()]);
+
+        // bootnodes must include the peer id
+        let res = Cli::try_parse_args_from([
+            "tn",
+            "node",
+            "--consensus.bootnodes",
+            "/ip4/10.0.0.1/udp/44894/quic-v1",
+        ]);
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn parse_db_migrate() {
+        let tn = Cli::try_parse_args_from(["tn", "db", "migrate", "--check"]).unwrap();
+        let Commands::Db(command) = tn.command else { panic!("expected db command") };
+        let db::DbSubcommand::Migrate(args) = command.command else {
+            panic!("expected migrate command")
+        };
+        assert!(args.check);
+    }
+
+    #[test]
+    fn parse_db_get() {
+        let tn = Cli::try_parse_args_from(["tn", "db", "get", "consensus-block", "7"]).unwrap();
+        let Commands::Db(command) = tn.command else { panic!("expected db command") };
+        let db::DbSubcommand::Get(db::GetCommand::ConsensusBlock { number }) = command.command
+        else {
+            panic!("expected get consensus-block command")
+        };
+        assert_eq!(number, 7);
+
+        let digest = format!("0x{}", "ab".repeat(32));
+        let tn = Cli::try_parse_args_from(["tn", "db", "get", "batch", &digest]).unwrap();
+        let Commands::Db(command) = tn.command else { panic!("expected db command") };
+        assert!(matches!(command.command, db::DbSubcommand::Get(db::GetCommand::Batch { .. })));
+
+        assert!(Cli::try_parse_args_from(["tn", "db", "get", "certificate", "nope"]).is_err());
+    }
+
     #[test]
     fn parse_color_mode() {
         let tn = Cli::try_parse_args_from(["tn", "node", "--color", "always"]).unwrap();
@@ -207,6 +514,54 @@ mod tests {
         assert!(log_dir.as_ref().ends_with(end), "{log_dir:?}");
     }
 
+    #[test]
+    fn parse_status_args() {
+        let tn = Cli::try_parse_args_from([
+            "tn",
+            "status",
+            "--rpc-url",
+            "http://127.0.0.1:8546",
+            "--json",
+        ])
+        .unwrap();
+        match tn.command {
+            Commands::Status(args) => {
+                assert_eq!(args.rpc_url, "http://127.0.0.1:8546");
+                assert!(args.json);
+            }
+            _ => panic!("expected status command"),
+        }
+    }
+
+    #[test]
+    fn parse_env_overrides() {
+        assert_eq!(env_var_name("http.port"), "TN__HTTP__PORT");
+        assert_eq!(env_var_name("log.file.max-size"), "TN__LOG__FILE__MAX_SIZE");
+
+        let vars = || {
+            [("TN__RPC_URL", "http://127.0.0.1:9000"), ("TN__JSON", "true"), ("RPC_URL", "ignored")]
+                .map(|(key, value)| (OsString::from(key), OsString::from(value)))
+        };
+        let from_env = Cli::<NoArgs>::try_parse_with_env_from(["tn", "status"], vars());
+        let from_args = Cli::<NoArgs>::try_parse_with_env_from(
+            ["tn", "status", "--rpc-url", "http://127.0.0.1:8546"],
+            vars(),
+        );
+
+        match from_env.unwrap().command {
+            Commands::Status(args) => {
+                assert_eq!(args.rpc_url, "http://127.0.0.1:9000");
+                assert!(args.json);
+            }
+            _ => panic!("expected status command"),
+        }
+        // command line arguments take precedence
+        match from_args.unwrap().command {
+            Commands::Status(args) => assert_eq!(args.rpc_url, "http://127.0.0.1:8546"),
+            _ => panic!("expected status command"),
+        }
+    }
+
     #[tokio::test]
     async fn parse_env_filter_directives() {
         let temp_dir = tempfile::tempdir().unwrap();
@@ -0,0 +1,32 @@
+//! Committee command.
+//!
+//! Tools for operators to prepare for committee changes between epochs.
+
+mod rehearse;
+use self::rehearse::RehearseArgs;
+use clap::{Args, Subcommand};
+
+/// Manage committee changes.
+#[derive(Debug, Args)]
+pub struct CommitteeArgs {
+    /// The committee command to run.
+    #[command(subcommand)]
+    pub command: CommitteeSubcommand,
+}
+
+/// Committee subcommands.
+#[derive(Debug, Subcommand)]
+pub enum CommitteeSubcommand {
+    /// Rehearse the handover to a proposed committee without changing any state.
+    #[command(name = "rehearse")]
+    Rehearse(RehearseArgs),
+}
+
+impl CommitteeArgs {
+    /// Execute command
+    pub fn execute(&self) -> eyre::Result<()> {
+        match &self.command {
+            CommitteeSubcommand::Rehearse(args) => args.execute(),
+        }
+    }
+}
@@ -0,0 +1,459 @@
+//! Dry-run a committee handover.
+//!
+//! Loads a proposed next-epoch committee and checks it against the current committee and the
+//! running node without committing anything.
+
+use crate::args::clap_genesis_parser;
+use clap::Args;
+use jsonrpsee::http_client::HttpClientBuilder;
+use reth::dirs::MaybePlatformPath;
+use reth_chainspec::ChainSpec;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{BTreeSet, HashSet},
+    fmt,
+    path::PathBuf,
+    sync::Arc,
+    time::Duration,
+};
+use tn_config::{Config, ConfigFmt, ConfigTrait, TelcoinDirs as _, ValidatorInfo};
+use tn_node::dirs::{default_datadir_args, DataDirChainPath, DataDirPath};
+use tn_rpc::{TelcoinNetworkAdminApiClient as _, ValidatorConnectivity};
+use tn_types::{BlsPublicKey, Committee, CommitteeBuilder, Epoch, VotingPower};
+use tracing::info;
+
+/// The validators proposed for the next epoch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProposedCommittee {
+    /// The epoch the committee will serve.
+    pub epoch: Epoch,
+    /// The validators and their stake.
+    pub validators: Vec<ProposedValidator>,
+}
+
+/// A validator proposed for the next epoch.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProposedValidator {
+    /// The information for the validator, as produced by `genesis add-validator`.
+    #[serde(flatten)]
+    pub info: ValidatorInfo,
+    /// The validator's stake, used as its voting power in the proposed committee.
+    pub stake: VotingPower,
+}
+
+/// Rehearse the handover to a proposed committee.
+///
+/// Verifies each validator's proof of possession, dials every primary and worker from the local
+/// node's networks (requires the `tnAdmin` RPC namespace), and simulates the change against the
+/// current committee. Nothing is written to disk and the node's state is unchanged.
+///
+/// Exits with an error if the handover is not ready.
+#[derive(Debug, Args)]
+pub struct RehearseArgs {
+    /// The path to the proposed committee (YAML).
+    #[arg(long, value_name = "FILE")]
+    pub proposed: PathBuf,
+
+    /// The path to the data dir for all telcoin-network files and subdirectories.
+    ///
+    /// Defaults to the OS-specific data directory:
+    ///
+    /// - Linux: `$XDG_DATA_HOME/telcoin-network/` or `$HOME/.local/share/telcoin-network/`
+    /// - Windows: `{FOLDERID_RoamingAppData}/telcoin-network/`
+    /// - macOS: `$HOME/Library/Application Support/telcoin-network/`
+    #[arg(long, value_name = "DATA_DIR", verbatim_doc_comment, default_value_t)]
+    pub datadir: MaybePlatformPath<DataDirPath>,
+
+    /// The path to the configuration file to use.
+    #[arg(long, value_name = "FILE")]
+    pub config: Option<PathBuf>,
+
+    /// The chain this node is running.
+    #[arg(
+        long,
+        value_name = "CHAIN_OR_PATH",
+        default_value = "adiri",
+        value_parser = clap_genesis_parser,
+        required = false,
+    )]
+    pub chain: Arc<ChainSpec>,
+
+    /// The url for the local node's HTTP RPC.
+    #[arg(long, value_name = "URL", default_value = "http://127.0.0.1:8545")]
+    pub rpc_url: String,
+
+    /// The amount of time to wait for the node to check each validator's connectivity.
+    #[arg(long, value_name = "DURATION", default_value = "60s", value_parser = humantime::parse_duration)]
+    pub timeout: Duration,
+
+    /// Print the report as JSON instead of a human-readable format.
+    #[arg(long)]
+    pub json: bool,
+}
+
+impl RehearseArgs {
+    /// Execute `committee rehearse` command.
+    pub fn execute(&self) -> eyre::Result<()> {
+        let datadir: DataDirChainPath =
+            self.datadir.unwrap_or_chain_default(self.chain.chain, default_datadir_args()).into();
+        let config_path = self.config.clone().unwrap_or(datadir.node_config_path());
+        let config: Config =
+            Config::load_from_path(&config_path, ConfigFmt::from_path(&config_path))?;
+        let current: Committee = serde_yaml::from_slice(&std::fs::read(datadir.committee_path())?)?;
+        current.load();
+        let proposed: ProposedCommittee = serde_yaml::from_slice(&std::fs::read(&self.proposed)?)?;
+        info!(target: "tn::committee", epoch = proposed.epoch, "rehearsing committee handover");
+
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+        let (node_synced, connectivity) = runtime.block_on(async {
+            let client =
+                HttpClientBuilder::default().request_timeout(self.timeout).build(&self.rpc_url)?;
+            let status = client.node_status().await?;
+            let mut connectivity = Vec::with_capacity(proposed.validators.len());
+            for validator in proposed.validators.iter() {
+                let result = client
+                    .check_connectivity(validator.info.primary_info.clone())
+                    .await
+                    .map_err(|e| e.to_string());
+                connectivity.push(result);
+            }
+            eyre::Ok((status.sync_distance == 0, connectivity))
+        })?;
+
+        let report = HandoverReport::new(&config, &current, &proposed, connectivity, node_synced);
+        if self.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            println!("{report}");
+        }
+
+        eyre::ensure!(report.ready, "committee handover is not ready");
+        Ok(())
+    }
+}
+
+/// The readiness of one validator in the proposed committee.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidatorReadiness {
+    /// The validator's name.
+    pub name: String,
+    /// The validator's BLS public key.
+    pub bls_public_key: BlsPublicKey,
+    /// Whether the validator's proof of possession is valid for this chain.
+    pub proof_of_possession: bool,
+    /// Connectivity from the local node, or the reason it could not be checked.
+    pub connectivity: Result<ValidatorConnectivity, String>,
+    /// Whether the validator is new to the committee.
+    pub added: bool,
+}
+
+impl ValidatorReadiness {
+    /// The validator is ready if its proof is valid and every endpoint is reachable.
+    pub fn is_ready(&self) -> bool {
+        self.proof_of_possession
+            && self.connectivity.as_ref().is_ok_and(|connectivity| connectivity.all_reachable())
+    }
+}
+
+/// The outcome of a committee handover rehearsal.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HandoverReport {
+    /// The epoch of the current committee.
+    pub current_epoch: Epoch,
+    /// The epoch of the proposed committee.
+    pub proposed_epoch: Epoch,
+    /// The readiness of each proposed validator.
+    pub validators: Vec<ValidatorReadiness>,
+    /// The number of current validators that are not in the proposed committee.
+    pub removed: usize,
+    /// Problems with the proposed committee itself.
+    pub problems: Vec<String>,
+    /// Whether the local node is in the proposed committee.
+    pub local_node_in_committee: bool,
+    /// Whether the local node is caught up with consensus.
+    pub local_node_synced: bool,
+    /// The voting power of ready validators.
+    pub ready_voting_power: VotingPower,
+    /// The voting power required for the proposed committee to reach quorum.
+    pub quorum_threshold: VotingPower,
+    /// The percentage of the proposed committee's voting power that is ready.
+    pub readiness_score: f64,
+    /// Whether the handover can proceed.
+    pub ready: bool,
+}
+
+impl HandoverReport {
+    /// Simulate the handover from the current committee to the proposed committee.
+    fn new(
+        config: &Config,
+        current: &Committee,
+        proposed: &ProposedCommittee,
+        connectivity: Vec<Result<ValidatorConnectivity, String>>,
+        local_node_synced: bool,
+    ) -> Self {
+        let chain = config.chain_spec();
+        let mut problems = Vec::new();
+        if proposed.epoch != current.epoch() + 1 {
+            problems.push(format!(
+                "proposed epoch {} does not follow current epoch {}",
+                proposed.epoch,
+                current.epoch()
+            ));
+        }
+
+        let mut bls_keys = BTreeSet::new();
+        let mut network_keys = HashSet::new();
+        let mut builder = CommitteeBuilder::new(proposed.epoch);
+        let mut validators = Vec::with_capacity(proposed.validators.len());
+        for (validator, connectivity) in proposed.validators.iter().zip(connectivity) {
+            let info = &validator.info;
+            if !bls_keys.insert(info.bls_public_key) {
+                problems.push(format!("duplicate BLS key for {}", info.name));
+            }
+            if !network_keys.insert(info.primary_info.network_key.clone()) {
+                problems.push(format!("duplicate network key for {}", info.name));
+            }
+            if validator.stake == 0 {
+                problems.push(format!("{} has no stake", info.name));
+            }
+            builder.add_authority(
+                info.bls_public_key,
+                validator.stake,
+                info.primary_info.network_address.clone(),
+                info.execution_address,
+                info.primary_info.network_key.clone(),
+                info.name.clone(),
+            );
+
+            validators.push(ValidatorReadiness {
+                name: info.name.clone(),
+                bls_public_key: info.bls_public_key,
+                proof_of_possession: info.verify_proof_of_possession(&chain).is_ok(),
+                connectivity,
+                added: current.authority_by_key(&info.bls_public_key).is_none(),
+            });
+        }
+
+        // committees smaller than two can not be built
+        let (ready_voting_power, quorum_threshold, total_voting_power) = if bls_keys.len() > 1 {
+            let committee = builder.build();
+            let ready = validators
+                .iter()
+                .filter(|v| v.is_ready())
+                .map(|v| committee.voting_power(&v.bls_public_key))
+                .sum();
+            (ready, committee.quorum_threshold(), committee.total_voting_power())
+        } else {
+            problems.push("proposed committee must have more than one validator".to_string());
+            (0, 0, 0)
+        };
+
+        let removed = current
+            .authorities()
+            .iter()
+            .filter(|authority| !bls_keys.contains(authority.protocol_key()))
+            .count();
+        let readiness_score = if total_voting_power == 0 {
+            0.0
+        } else {
+            ready_voting_power as f64 * 100.0 / total_voting_power as f64
+        };
+        let ready = problems.is_empty()
+            && local_node_synced
+            && quorum_threshold > 0
+            && ready_voting_power >= quorum_threshold;
+
+        Self {
+            current_epoch: current.epoch(),
+            proposed_epoch: proposed.epoch,
+            validators,
+            removed,
+            problems,
+            local_node_in_committee: bls_keys.contains(config.validator_info.public_key()),
+            local_node_synced,
+            ready_voting_power,
+            quorum_threshold,
+            readiness_score,
+            ready,
+        }
+    }
+}
+
+impl fmt::Display for HandoverReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "epoch:              {} -> {}", self.current_epoch, self.proposed_epoch)?;
+        let added = self.validators.iter().filter(|v| v.added).count();
+        writeln!(f, "committee changes:  {added} added, {} removed", self.removed)?;
+        writeln!(
+            f,
+            "local node:         in committee: {}, synced: {}",
+            self.local_node_in_committee, self.local_node_synced
+        )?;
+        writeln!(f, "validators:")?;
+        for v in self.validators.iter() {
+            let connectivity = match &v.connectivity {
+                Ok(c) if c.all_reachable() => "reachable".to_string(),
+                Ok(c) => {
+                    let unreachable = c.workers.values().filter(|w| !w.reachable).count();
+                    format!(
+                        "unreachable (primary: {}, workers down: {unreachable})",
+                        c.primary.error.as_deref().unwrap_or("ok")
+                    )
+                }
+                Err(e) => format!("not checked ({e})"),
+            };
+            let pop = if v.proof_of_possession { "valid" } else { "INVALID" };
+            writeln!(f, "  {}: proof of possession {pop}, {connectivity}", v.name)?;
+        }
+        for problem in self.problems.iter() {
+            writeln!(f, "problem:            {problem}")?;
+        }
+        writeln!(
+            f,
+            "ready voting power: {} (quorum {})",
+            self.ready_voting_power, self.quorum_threshold
+        )?;
+        writeln!(f, "readiness score:    {:.1}%", self.readiness_score)?;
+        write!(f, "ready:              {}", self.ready)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HandoverReport, ProposedCommittee, ProposedValidator};
+    use rand::{rngs::StdRng, SeedableRng};
+    use std::collections::BTreeMap;
+    use tn_config::{Config, KeyConfig};
+    use tn_rpc::{PeerConnectivity, ValidatorConnectivity};
+    use tn_types::{Address, Committee, CommitteeBuilder, Multiaddr, VotingPower};
+
+    /// A validator with a valid proof of possession for the default chain.
+    fn validator(config: &Config, seed: u64, stake: VotingPower) -> ProposedValidator {
+        let key_config = KeyConfig::with_random(&mut StdRng::seed_from_u64(seed));
+        let mut config = config.clone();
+        config.validator_info.name = format!("validator-{seed}");
+        config.update_protocol_key(key_config.primary_public_key()).unwrap();
+        config.update_primary_network_key(key_config.primary_network_public_key()).unwrap();
+        config.update_worker_network_key(key_config.worker_network_public_key()).unwrap();
+        config.update_execution_address(Address::with_last_byte(seed as u8)).unwrap();
+        let proof = key_config
+            .generate_proof_of_possession_bls(
+                config.execution_address(),
+                &config.validator_info.primary_info,
+                &config.chain_spec(),
+            )
+            .unwrap();
+        config.update_proof_of_possession(proof).unwrap();
+        ProposedValidator { info: config.validator_info, stake }
+    }
+
+    /// The current committee of the given validators, at epoch 0.
+    fn committee(validators: &[ProposedValidator]) -> Committee {
+        let mut builder = CommitteeBuilder::new(0);
+        for validator in validators.iter().map(|v| &v.info) {
+            builder.add_authority(
+                validator.bls_public_key,
+                1,
+                Multiaddr::empty(),
+                validator.execution_address,
+                validator.primary_info.network_key.clone(),
+                validator.name.clone(),
+            );
+        }
+        let committee = builder.build();
+        committee.load();
+        committee
+    }
+
+    fn connectivity(reachable: bool) -> Result<ValidatorConnectivity, String> {
+        let peer = if reachable {
+            PeerConnectivity::reachable()
+        } else {
+            PeerConnectivity { reachable: false, error: Some("timed out".to_string()) }
+        };
+        Ok(ValidatorConnectivity { primary: peer, workers: BTreeMap::new() })
+    }
+
+    #[test]
+    fn test_rehearse_weights_readiness_by_stake() {
+        let config = Config::default();
+        let proposed = ProposedCommittee {
+            epoch: 1,
+            validators: vec![
+                validator(&config, 1, 100),
+                validator(&config, 2, 1),
+                validator(&config, 3, 1),
+                validator(&config, 4, 1),
+            ],
+        };
+        let current = committee(&proposed.validators);
+
+        // three of four validators are reachable, but they hold too little stake for quorum
+        let report = HandoverReport::new(
+            &config,
+            &current,
+            &proposed,
+            vec![connectivity(false), connectivity(true), connectivity(true), connectivity(true)],
+            true,
+        );
+        assert!(report.problems.is_empty(), "{:?}", report.problems);
+        assert_eq!(report.ready_voting_power, 3);
+        assert!(!report.ready);
+
+        // the validator with most of the stake is enough
+        let report = HandoverReport::new(
+            &config,
+            &current,
+            &proposed,
+            vec![connectivity(true), connectivity(false), connectivity(false), connectivity(true)],
+            true,
+        );
+        assert_eq!(report.ready_voting_power, 101);
+        assert!(report.ready_voting_power >= report.quorum_threshold);
+        assert!(report.ready);
+        assert_eq!(report.removed, 0);
+        assert!(report.validators.iter().all(|v| !v.added));
+
+        // the local node must be synced
+        let report = HandoverReport::new(
+            &config,
+            &current,
+            &proposed,
+            vec![connectivity(true), connectivity(true), connectivity(true), connectivity(true)],
+            false,
+        );
+        assert!(!report.ready);
+    }
+
+    #[test]
+    fn test_rehearse_reports_problems() {
+        let config = Config::default();
+        let current_validators = vec![validator(&config, 1, 1), validator(&config, 2, 1)];
+        let current = committee(&current_validators);
+
+        let mut invalid = validator(&config, 3, 10);
+        invalid.info.execution_address = Address::with_last_byte(99);
+        let proposed = ProposedCommittee {
+            epoch: 2,
+            validators: vec![validator(&config, 1, 10), validator(&config, 4, 0), invalid],
+        };
+        let report = HandoverReport::new(
+            &config,
+            &current,
+            &proposed,
+            vec![connectivity(true), connectivity(true), connectivity(true)],
+            true,
+        );
+        assert_eq!(report.removed, 1);
+        assert!(!report.validators[0].added);
+        assert!(report.validators[1].added);
+        // the proof of possession commits to the execution address
+        assert!(!report.validators[2].proof_of_possession);
+        assert_eq!(report.problems.len(), 2, "{:?}", report.problems);
+        assert!(report.problems.iter().any(|p| p.contains("does not follow current epoch")));
+        assert!(report.problems.iter().any(|p| p.contains("has no stake")));
+        assert!(!report.ready);
+    }
+}
@@ -0,0 +1,194 @@
+//! Database command.
+//!
+//! Maintain and inspect the consensus and execution databases in the node's data directory.
+
+use crate::{args::clap_genesis_parser, backup::lock_datadir};
+use clap::{Args, Subcommand};
+use reth::{args::DatabaseArgs, dirs::MaybePlatformPath};
+use reth_chainspec::ChainSpec;
+use reth_db::{init_db, open_db_read_only};
+use std::{path::PathBuf, sync::Arc};
+use tn_config::TelcoinDirs as _;
+use tn_node::{
+    dirs::{default_datadir_args, DataDirChainPath, DataDirPath},
+    migrations::{migrate, ConsensusStore, ExecutionStore, MigrationReport},
+};
+use tn_storage::{
+    open_db, open_encrypted_db, open_read_only_db, read_only_get,
+    tables::{Batches, Certificates, ConsensusBlocks},
+    StorageCipher,
+};
+use tn_types::{BlockHash, CertificateDigest, B256};
+
+/// Manage the node's databases.
+///
+/// The node must be stopped before migrating its databases.
+#[derive(Debug, Args)]
+pub struct DbArgs {
+    /// The path to the data dir for all telcoin-network files and subdirectories.
+    ///
+    /// Defaults to the OS-specific data directory:
+    ///
+    /// - Linux: `$XDG_DATA_HOME/telcoin-network/` or `$HOME/.local/share/telcoin-network/`
+    /// - Windows: `{FOLDERID_RoamingAppData}/telcoin-network/`
+    /// - macOS: `$HOME/Library/Application Support/telcoin-network/`
+    #[arg(long, value_name = "DATA_DIR", verbatim_doc_comment, default_value_t, global = true)]
+    pub datadir: MaybePlatformPath<DataDirPath>,
+
+    /// The chain this node is running.
+    #[arg(
+        long,
+        value_name = "CHAIN_OR_PATH",
+        default_value = "adiri",
+        value_parser = clap_genesis_parser,
+        required = false,
+        global = true,
+    )]
+    pub chain: Arc<ChainSpec>,
+
+    /// The file with the secret the consensus database is encrypted with.
+    ///
+    /// Required for databases of nodes started with `--storage.encryption-secret-file`.
+    #[arg(long = "storage.encryption-secret-file", value_name = "PATH", global = true)]
+    pub storage_encryption_secret_file: Option<PathBuf>,
+
+    /// The database command to run.
+    #[command(subcommand)]
+    pub command: DbSubcommand,
+}
+
+/// Database subcommands.
+#[derive(Debug, Subcommand)]
+pub enum DbSubcommand {
+    /// Migrate the databases to the schema of this version of the node.
+    ///
+    /// The node also migrates its databases on startup.
+    #[command(name = "migrate")]
+    Migrate(MigrateArgs),
+    /// Print a decoded record from the consensus database.
+    ///
+    /// Opens the database read-only so it can be used while the node is running.
+    #[command(name = "get", subcommand)]
+    Get(GetCommand),
+}
+
+/// Migrate the databases.
+#[derive(Debug, Args)]
+pub struct MigrateArgs {
+    /// List pending migrations without changing the databases.
+    ///
+    /// Fails if a database was written by a newer version of the node.
+    #[arg(long)]
+    pub check: bool,
+}
+
+/// The consensus database records to inspect.
+#[derive(Debug, Subcommand)]
+pub enum GetCommand {
+    /// A certificate by its hex encoded digest.
+    Certificate {
+        /// The certificate digest.
+        digest: B256,
+    },
+    /// A batch by its hex encoded digest.
+    Batch {
+        /// The batch digest.
+        digest: BlockHash,
+    },
+    /// A consensus chain block by number.
+    #[command(name = "consensus-block")]
+    ConsensusBlock {
+        /// The consensus block number.
+        number: u64,
+    },
+}
+
+impl DbArgs {
+    /// Execute command
+    pub fn execute(&self) -> eyre::Result<()> {
+        let datadir = self.data_dir();
+        let cipher = self
+            .storage_encryption_secret_file
+            .as_ref()
+            .map(StorageCipher::from_secret_file)
+            .transpose()?;
+
+        match &self.command {
+            DbSubcommand::Migrate(args) => {
+                // a running node must not see its databases change underneath it
+                let _lock = if args.check { None } else { lock_datadir(&datadir, "migrating")? };
+
+                let consensus_db_path = datadir.consensus_db_path();
+                if consensus_db_path.exists() {
+                    let db = if args.check {
+                        open_read_only_db(&consensus_db_path, cipher)?
+                    } else if let Some(cipher) = cipher {
+                        open_encrypted_db(&consensus_db_path, cipher)?
+                    } else {
+                        open_db(&consensus_db_path)
+                    };
+                    let report = migrate(&ConsensusStore::new(db), args.check)?;
+                    print_report(&report);
+                } else {
+                    println!("consensus: no database at {consensus_db_path:?}");
+                }
+
+                let db_path = datadir.db();
+                if db_path.exists() {
+                    let db_args = DatabaseArgs::default().database_args();
+                    let database = if args.check {
+                        open_db_read_only(&db_path, db_args)?
+                    } else {
+                        init_db(&db_path, db_args)?
+                    };
+                    print_report(&migrate(&ExecutionStore::new(database, &db_path), args.check)?);
+                } else {
+                    println!("execution: no database at {db_path:?}");
+                }
+            }
+            DbSubcommand::Get(command) => {
+                let path = datadir.consensus_db_path();
+                let record = match command {
+                    GetCommand::Certificate { digest } => read_only_get::<Certificates, _>(
+                        &path,
+                        cipher,
+                        &CertificateDigest::new(digest.0),
+                    )?
+                    .map(|certificate| format!("{certificate:#?}")),
+                    GetCommand::Batch { digest } => {
+                        read_only_get::<Batches, _>(&path, cipher, digest)?
+                            .map(|batch| format!("{batch:#?}"))
+                    }
+                    GetCommand::ConsensusBlock { number } => {
+                        read_only_get::<ConsensusBlocks, _>(&path, cipher, number)?
+                            .map(|header| format!("digest: {}\n{header:#?}", header.digest()))
+                    }
+                };
+                let record = record.ok_or_else(|| eyre::eyre!("{command:?} not found"))?;
+                println!("{record}");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns the chain specific path to the data dir.
+    fn data_dir(&self) -> DataDirChainPath {
+        self.datadir.unwrap_or_chain_default(self.chain.chain, default_datadir_args()).into()
+    }
+}
+
+/// Print the result of migrating a database.
+fn print_report(report: &MigrationReport) {
+    let MigrationReport { store, from, to, pending, applied } = report;
+    if report.is_current() {
+        println!("{store}: schema version {to} is current");
+    } else if *applied {
+        println!("{store}: migrated schema version {from} to {to}");
+    } else {
+        println!("{store}: schema version {from}, migrating to {to}");
+    }
+    for (version, description) in pending {
+        println!("  {version}: {description}");
+    }
+}
@@ -0,0 +1,202 @@
+//! Devnet command
+//!
+//! Start a local network of validators in a single process for development.
+use crate::{genesis::account_from_word, node::NodeCommand};
+use clap::{value_parser, Args, Parser as _};
+use eyre::eyre;
+use reth_cli_commands::node::NoArgs;
+use reth_db::DatabaseEnv;
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+use tn_config::{Config, ConfigFmt, ConfigTrait, KeyConfig, NetworkGenesis, TelcoinDirs as _};
+use tn_node::{dirs::DataDirChainPath, engine::TnBuilder, launch_node};
+use tn_types::{Address, GenesisAccount, U256};
+use tracing::info;
+
+/// Generate ephemeral keys and a genesis, then run every validator in this process.
+///
+/// Validator `N` uses `--instance N` ports, so the first validator serves RPC on the default
+/// HTTP port. This is ONLY for development, never use these keys for other chains.
+#[derive(Debug, Args)]
+pub struct DevnetArgs {
+    /// The number of validators in the committee.
+    ///
+    /// Nodes wait for a quorum of peers before starting, so at least 4 validators are required.
+    #[arg(long, value_name = "COUNT", default_value_t = 4, value_parser = value_parser!(u16).range(4..=200))]
+    pub validators: u16,
+
+    /// The root directory for the validators' data.
+    ///
+    /// Each validator uses `<DATA_DIR>/validator-<N>`. Defaults to a new directory in the OS temp
+    /// directory.
+    #[arg(long, value_name = "DATA_DIR")]
+    pub datadir: Option<PathBuf>,
+
+    /// Accounts funded with one billion TEL at genesis.
+    ///
+    /// Accepts an address or a simple text string. Text strings derive the account's key
+    /// deterministically, the same as `genesis init --dev-funded-account`.
+    #[arg(long = "dev-funded-account", value_name = "ACCOUNT", default_value = "test-source")]
+    pub dev_funded_accounts: Vec<String>,
+
+    /// The delay between headers.
+    ///
+    /// Shorter delays commit rounds and produce blocks faster.
+    #[arg(long, value_name = "DURATION", default_value = "1s", value_parser = humantime::parse_duration)]
+    pub header_delay: Duration,
+}
+
+impl DevnetArgs {
+    /// Execute `devnet` command
+    ///
+    /// Blocks until a validator exits.
+    pub fn execute(&self) -> eyre::Result<()> {
+        let root = self.datadir.clone().unwrap_or_else(|| {
+            std::env::temp_dir().join(format!("telcoin-devnet-{}", std::process::id()))
+        });
+        info!(target: "tn::devnet", path = ?root, "configuring devnet");
+        let datadirs = self.configure(&root)?;
+
+        let mut handles = Vec::with_capacity(datadirs.len());
+        for (i, datadir) in datadirs.into_iter().enumerate() {
+            let instance = (i + 1).to_string();
+            let command = NodeCommand::<NoArgs>::try_parse_from([
+                "tn",
+                "--datadir",
+                datadir.to_str().ok_or_else(|| eyre!("invalid datadir {datadir:?}"))?,
+                "--instance",
+                &instance,
+                "--http",
+                "--disable-discovery",
+            ])?;
+            // rpc ports are offset by the instance when the node launches
+            let http_port = command.rpc.http_port - i as u16;
+            info!(target: "tn::devnet", validator = instance, http_port, "starting validator");
+
+            let handle = std::thread::Builder::new()
+                .name(format!("devnet-validator-{instance}"))
+                .spawn(move || command.execute(true, launch_validator))?;
+            handles.push(handle);
+        }
+
+        for account in self.dev_funded_accounts.iter() {
+            let address = account_from_word(account);
+            info!(target: "tn::devnet", account, ?address, "funded dev account");
+        }
+
+        for handle in handles {
+            handle.join().map_err(|_| eyre!("devnet validator panicked"))??;
+        }
+
+        Ok(())
+    }
+
+    /// Generate keys, genesis, committee, and config files for each validator.
+    ///
+    /// Returns the data directory for each validator in instance order.
+    pub(crate) fn configure(&self, root: &Path) -> eyre::Result<Vec<PathBuf>> {
+        let mut config = Config::default();
+        for account in self.dev_funded_accounts.iter() {
+            config.genesis.alloc.insert(
+                account_from_word(account),
+                GenesisAccount::default().with_balance(U256::from(10).pow(U256::from(27))), // One Billion TEL
+            );
+        }
+        config.parameters.max_header_delay = self.header_delay;
+        config.parameters.min_header_delay = self.header_delay;
+
+        let chain = config.chain_spec();
+        let mut network_genesis = NetworkGenesis::with_chain_spec(chain.clone());
+        let mut validators = Vec::with_capacity(self.validators as usize);
+        for instance in 1..=self.validators {
+            let datadir = root.join(format!("validator-{instance}"));
+            std::fs::create_dir_all(datadir.validator_keys_path())?;
+
+            let key_config = KeyConfig::generate_and_save(&datadir)?;
+            let mut validator_config = config.clone();
+            validator_config.validator_info.name = format!("validator-{instance}");
+            validator_config.update_protocol_key(key_config.primary_public_key())?;
+            validator_config.update_primary_network_key(key_config.primary_network_public_key())?;
+            validator_config.update_worker_network_key(key_config.worker_network_public_key())?;
+            validator_config.update_execution_address(Address::with_last_byte(instance as u8))?;
+            validator_config.adjust_instance_ports(instance)?;
+            let proof = key_config.generate_proof_of_possession_bls(
+                validator_config.execution_address(),
+                &validator_config.validator_info.primary_info,
+                &chain,
+            )?;
+            validator_config.update_proof_of_possession(proof)?;
+
+            network_genesis.add_validator(validator_config.validator_info.clone());
+            validators.push((datadir, validator_config));
+        }
+
+        network_genesis.validate()?;
+        network_genesis.construct_registry_genesis_accounts(None);
+        let genesis = network_genesis.chain_info().genesis().clone();
+        let committee = network_genesis.create_committee()?;
+        let worker_cache = network_genesis.create_worker_cache()?;
+
+        let mut datadirs = Vec::with_capacity(validators.len());
+        for (datadir, mut validator_config) in validators {
+            validator_config.genesis = genesis.clone();
+            Config::store_path(datadir.genesis_file_path(), &genesis, ConfigFmt::JSON)?;
+            Config::store_path(datadir.committee_path(), &committee, ConfigFmt::YAML)?;
+            Config::store_path(datadir.worker_cache_path(), &worker_cache, ConfigFmt::YAML)?;
+            Config::store_path(datadir.node_config_path(), validator_config, ConfigFmt::YAML)?;
+            datadirs.push(datadir);
+        }
+
+        Ok(datadirs)
+    }
+}
+
+/// Launch a devnet validator.
+fn launch_validator(
+    builder: TnBuilder<Arc<DatabaseEnv>>,
+    _: NoArgs,
+    tn_datadir: DataDirChainPath,
+) -> eyre::Result<()> {
+    launch_node(builder, tn_datadir)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::{Cli, Commands};
+    use clap::Parser;
+    use reth_cli_commands::node::NoArgs;
+    use tempfile::tempdir;
+    use tn_config::{Config, ConfigFmt, ConfigTrait, TelcoinDirs as _};
+    use tn_types::Committee;
+
+    #[test]
+    fn test_devnet_configure() {
+        let tempdir = tempdir().expect("tempdir created");
+        let tn = Cli::<NoArgs>::try_parse_from(["tn", "devnet", "--validators", "5"])
+            .expect("cli parsed");
+        let Commands::Devnet(args) = tn.command else { panic!("expected devnet command") };
+
+        let datadirs = args.configure(tempdir.path()).expect("devnet configured");
+        assert_eq!(datadirs.len(), 5);
+
+        let committee: Committee =
+            Config::load_from_path(datadirs[0].committee_path(), ConfigFmt::YAML)
+                .expect("committee loaded");
+        assert_eq!(committee.size(), 5);
+
+        // every validator shares the same genesis with distinct network addresses
+        let configs: Vec<Config> = datadirs
+            .iter()
+            .map(|dir| {
+                Config::load_from_path(dir.node_config_path(), ConfigFmt::YAML)
+                    .expect("config loaded")
+            })
+            .collect();
+        assert!(configs.windows(2).all(|pair| pair[0].genesis == pair[1].genesis
+            && pair[0].validator_info.primary_info.network_address
+                != pair[1].validator_info.primary_info.network_address));
+    }
+}
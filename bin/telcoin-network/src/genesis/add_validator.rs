@@ -115,7 +115,8 @@ impl AddValidator {
 
     /// Loads the reth config with the given datadir root
     fn load_config(&self, config_path: PathBuf) -> eyre::Result<Config> {
-        Config::load_from_path::<Config>(config_path.clone(), ConfigFmt::YAML)
+        let fmt = ConfigFmt::from_path(&config_path);
+        Config::load_from_path::<Config>(config_path.clone(), fmt)
             .wrap_err_with(|| format!("Could not load config file {:?}", config_path))
     }
 
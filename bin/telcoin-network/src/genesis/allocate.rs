@@ -0,0 +1,55 @@
+//! Add a token distribution to genesis.
+
+use clap::Args;
+use eyre::ensure;
+use std::path::{Path, PathBuf};
+use tn_config::{deployed_bytecode_from_path, Config, ConfigFmt, ConfigTrait, Distribution};
+use tracing::info;
+
+/// Fund accounts at genesis from a distribution file.
+#[derive(Debug, Clone, Args)]
+pub struct AllocateArgs {
+    /// The path to the distribution file.
+    ///
+    /// Files ending in `.csv` use the columns `address,amount[,start,cliff,duration]`.
+    /// Other files are JSON lists of `{ address, amount, vesting: { start, cliff, duration } }`.
+    /// Amounts are in wei. Vesting times are in seconds.
+    #[arg(long, value_name = "DISTRIBUTION_FILE", verbatim_doc_comment)]
+    pub distribution: PathBuf,
+
+    /// The path to the standard json artifact of the vesting contract.
+    ///
+    /// Required if any allocation vests.
+    #[arg(long, value_name = "CONTRACT_FILE", verbatim_doc_comment)]
+    pub vesting_contract: Option<PathBuf>,
+}
+
+impl AllocateArgs {
+    /// Add the distribution's accounts to the genesis in the node config.
+    ///
+    /// Fails without changing the config if any account is already funded.
+    pub(super) fn execute(&self, config_path: &Path) -> eyre::Result<()> {
+        let distribution = Distribution::load_from_path(&self.distribution)?;
+        let vesting_contract =
+            self.vesting_contract.as_deref().map(deployed_bytecode_from_path).transpose()?;
+        let accounts = distribution.genesis_accounts(vesting_contract.as_ref())?;
+
+        let config_fmt = ConfigFmt::from_path(config_path);
+        let mut tn_config: Config = Config::load_from_path(config_path, config_fmt)?;
+        for address in accounts.keys() {
+            ensure!(
+                !tn_config.genesis.alloc.contains_key(address),
+                "{address} is already funded at genesis"
+            );
+        }
+
+        info!(
+            target: "genesis::allocate",
+            accounts = accounts.len(),
+            total = %distribution.total(),
+            "adding distribution to genesis"
+        );
+        tn_config.genesis.alloc.extend(accounts);
+        Config::store_path(config_path, tn_config, config_fmt)
+    }
+}
@@ -7,6 +7,7 @@ use reth_chainspec::ChainSpec;
 use std::{path::PathBuf, sync::Arc};
 use tn_config::{Config, ConfigFmt, ConfigTrait, NetworkGenesis, TelcoinDirs as _};
 use tn_node::dirs::{default_datadir_args, DataDirChainPath, DataDirPath};
+use tn_types::B256;
 use tracing::info;
 
 /// Add the validator to the node
@@ -56,6 +57,34 @@ pub struct CreateCommitteeArgs {
     /// The path to the consensus registry storage yaml file.
     #[arg(long, value_name = "CONSENSUS_REGISTRY_PATH", verbatim_doc_comment)]
     pub consensus_registry: Option<PathBuf>,
+
+    /// The path to the compiled bridge contract (standard json) deployed at genesis.
+    #[arg(long, value_name = "BRIDGE_ARTIFACT_PATH", verbatim_doc_comment)]
+    pub bridge: Option<PathBuf>,
+
+    /// The path to the bridge storage yaml file.
+    #[arg(long, value_name = "BRIDGE_STORAGE_PATH", verbatim_doc_comment, requires = "bridge")]
+    pub bridge_storage: Option<PathBuf>,
+
+    /// The path to the compiled fee treasury contract (standard json) deployed at genesis.
+    #[arg(long, value_name = "FEE_TREASURY_ARTIFACT_PATH", verbatim_doc_comment)]
+    pub fee_treasury: Option<PathBuf>,
+
+    /// The path to the fee treasury storage yaml file.
+    #[arg(
+        long,
+        value_name = "FEE_TREASURY_STORAGE_PATH",
+        verbatim_doc_comment,
+        requires = "fee_treasury"
+    )]
+    pub fee_treasury_storage: Option<PathBuf>,
+
+    /// The expected hash of the system contracts deployed at genesis.
+    ///
+    /// Validators compare the hash out of band to confirm every node starts from identical
+    /// system state. The command fails without writing any files if the hash doesn't match.
+    #[arg(long, value_name = "HASH", verbatim_doc_comment)]
+    pub system_contracts_hash: Option<B256>,
 }
 
 impl CreateCommitteeArgs {
@@ -83,17 +112,38 @@ impl CreateCommitteeArgs {
 
         // updated genesis with registry information
         network_genesis.construct_registry_genesis_accounts(self.consensus_registry.clone());
+        if let Some(artifact) = &self.bridge {
+            network_genesis
+                .construct_bridge_genesis_account(artifact, self.bridge_storage.as_deref())?;
+        }
+        if let Some(artifact) = &self.fee_treasury {
+            network_genesis.construct_fee_treasury_genesis_account(
+                artifact,
+                self.fee_treasury_storage.as_deref(),
+            )?;
+        }
+
+        // ensure system state matches the other validators
+        if let Some(expected) = self.system_contracts_hash {
+            network_genesis.verify_system_contracts(expected)?;
+        }
+        info!(
+            target: "genesis::create-committee",
+            hash = ?network_genesis.system_contracts_hash(),
+            "system contracts deployed at genesis"
+        );
 
         // update the config with new genesis information
         let config_path = self.config.clone().unwrap_or(data_dir.node_config_path());
-        let mut tn_config: Config = Config::load_from_path(&config_path, ConfigFmt::YAML)?;
+        let config_fmt = ConfigFmt::from_path(&config_path);
+        let mut tn_config: Config = Config::load_from_path(&config_path, config_fmt)?;
         tn_config.genesis = network_genesis.chain_info().genesis().clone();
 
         // write genesis and config to file
         //
         // NOTE: CLI parser only supports JSON format for genesis
         Config::store_path(data_dir.genesis_file_path(), tn_config.genesis(), ConfigFmt::JSON)?;
-        Config::store_path(config_path, tn_config, ConfigFmt::YAML)?;
+        Config::store_path(config_path, tn_config, config_fmt)?;
 
         // generate committee and worker cache
         let committee = network_genesis.create_committee()?;
@@ -3,17 +3,19 @@
 //! The genesis ceremony is how networks are started.
 
 mod add_validator;
+mod allocate;
 mod create_committee;
 mod validate;
 use self::{
-    add_validator::AddValidator, create_committee::CreateCommitteeArgs, validate::ValidateArgs,
+    add_validator::AddValidator, allocate::AllocateArgs, create_committee::CreateCommitteeArgs,
+    validate::ValidateArgs,
 };
 use crate::args::clap_genesis_parser;
 use clap::{Args, Subcommand};
 use rand::{rngs::StdRng, SeedableRng};
 use reth::dirs::MaybePlatformPath;
 use reth_chainspec::ChainSpec;
-use secp256k1::Secp256k1;
+use secp256k1::{PublicKey, Secp256k1, SecretKey};
 use std::{path::PathBuf, sync::Arc, time::Duration};
 use tn_config::{Config, ConfigFmt, ConfigTrait, NetworkGenesis, TelcoinDirs as _};
 use tn_node::dirs::{default_datadir_args, DataDirChainPath, DataDirPath};
@@ -70,6 +72,9 @@ pub enum CeremonySubcommand {
     /// Add validator to committee.
     #[command(name = "add-validator")]
     AddValidator(AddValidator),
+    /// Fund accounts at genesis from a distribution file.
+    #[command(name = "allocate")]
+    Allocate(AllocateArgs),
     /// Verify the current validators.
     #[command(name = "validate")]
     Validate(ValidateArgs),
@@ -109,14 +114,11 @@ impl Initialize {
 /// Take a string and return the deterministic account derived from it.  This is be used
 /// with similiar functionality in the test client to allow easy testing using simple strings
 /// for accounts.
-fn account_from_word(key_word: &str) -> Address {
+pub(crate) fn account_from_word(key_word: &str) -> Address {
     if key_word.starts_with("0x") {
         key_word.parse().expect("not a valid account!")
     } else {
-        let seed = keccak256(key_word.as_bytes());
-        let mut rand = <StdRng as SeedableRng>::from_seed(seed.0);
-        let secp = Secp256k1::new();
-        let (_, public_key) = secp.generate_keypair(&mut rand);
+        let (_, public_key) = keypair_from_word(key_word);
         // strip out the first byte because that should be the SECP256K1_TAG_PUBKEY_UNCOMPRESSED
         // tag returned by libsecp's uncompressed pubkey serialization
         let hash = keccak256(&public_key.serialize_uncompressed()[1..]);
@@ -124,6 +126,13 @@ fn account_from_word(key_word: &str) -> Address {
     }
 }
 
+/// The deterministic secp256k1 keypair for an account derived with [account_from_word].
+pub(crate) fn keypair_from_word(key_word: &str) -> (SecretKey, PublicKey) {
+    let seed = keccak256(key_word.as_bytes());
+    let mut rand = <StdRng as SeedableRng>::from_seed(seed.0);
+    Secp256k1::new().generate_keypair(&mut rand)
+}
+
 impl GenesisArgs {
     /// Execute command
     pub fn execute(&self) -> eyre::Result<()> {
@@ -143,7 +152,8 @@ impl GenesisArgs {
                 // TODO: use config or CLI chain spec?
                 let config_path = self.config.clone().unwrap_or(datadir.node_config_path());
 
-                let mut tn_config: Config = Config::load_from_path(&config_path, ConfigFmt::YAML)?;
+                let config_fmt = ConfigFmt::from_path(&config_path);
+                let mut tn_config: Config = Config::load_from_path(&config_path, config_fmt)?;
                 if !init.is_empty() {
                     // Changed a default config setting so update and save.
                     if let Some(acct_str) = &init.dev_funded_account {
@@ -162,12 +172,20 @@ impl GenesisArgs {
                         tn_config.parameters.min_header_delay =
                             Duration::from_millis(min_header_delay_ms);
                     }
-                    Config::store_path(config_path, tn_config.clone(), ConfigFmt::YAML)?;
+                    Config::store_path(&config_path, tn_config.clone(), config_fmt)?;
                 }
 
                 let network_genesis = NetworkGenesis::with_chain_spec(tn_config.chain_spec());
                 network_genesis.write_to_path(datadir.genesis_path())?;
             }
+            CeremonySubcommand::Allocate(args) => {
+                let datadir: DataDirChainPath = self
+                    .datadir
+                    .unwrap_or_chain_default(self.chain.chain, default_datadir_args())
+                    .into();
+                let config_path = self.config.clone().unwrap_or(datadir.node_config_path());
+                args.execute(&config_path)?;
+            }
             // add validator to the committee file
             CeremonySubcommand::AddValidator(args) => {
                 args.execute()?;
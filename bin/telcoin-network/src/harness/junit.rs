@@ -0,0 +1,146 @@
+//! JUnit XML reports for scenario runs.
+//!
+//! Each scenario is a test suite and each step a test case, so CI systems can show which step of a
+//! scenario failed.
+
+use std::{fmt::Write as _, time::Duration};
+
+/// The outcome of a step.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    /// The step succeeded.
+    Passed,
+    /// The step failed with this message.
+    Failed(String),
+    /// The step did not run because an earlier step failed.
+    Skipped,
+}
+
+/// A step's result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TestCase {
+    /// The step's name.
+    pub name: String,
+    /// How long the step ran.
+    pub time: Duration,
+    /// The outcome of the step.
+    pub outcome: Outcome,
+}
+
+/// The results of a scenario.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TestSuite {
+    /// The scenario's name.
+    pub name: String,
+    /// The result of each step in order.
+    pub cases: Vec<TestCase>,
+}
+
+impl TestSuite {
+    /// Create an empty suite for the scenario `name`.
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), cases: Vec::new() }
+    }
+
+    /// Record the result of the next step.
+    pub fn push(&mut self, name: impl Into<String>, time: Duration, outcome: Outcome) {
+        self.cases.push(TestCase { name: name.into(), time, outcome });
+    }
+
+    /// True if no step failed.
+    pub fn passed(&self) -> bool {
+        self.failures() == 0
+    }
+
+    /// The number of failed steps.
+    pub fn failures(&self) -> usize {
+        self.cases.iter().filter(|case| matches!(case.outcome, Outcome::Failed(_))).count()
+    }
+
+    /// The number of skipped steps.
+    pub fn skipped(&self) -> usize {
+        self.cases.iter().filter(|case| case.outcome == Outcome::Skipped).count()
+    }
+
+    /// Render the suite as a JUnit XML document.
+    pub fn to_xml(&self) -> String {
+        let time: Duration = self.cases.iter().map(|case| case.time).sum();
+        let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        // writing to a string never fails
+        let _ = writeln!(
+            xml,
+            "<testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" skipped=\"{}\" time=\"{:.3}\">",
+            escape(&self.name),
+            self.cases.len(),
+            self.failures(),
+            self.skipped(),
+            time.as_secs_f64()
+        );
+        for case in self.cases.iter() {
+            let _ = write!(
+                xml,
+                "  <testcase classname=\"{}\" name=\"{}\" time=\"{:.3}\"",
+                escape(&self.name),
+                escape(&case.name),
+                case.time.as_secs_f64()
+            );
+            match &case.outcome {
+                Outcome::Passed => xml.push_str("/>\n"),
+                Outcome::Failed(message) => {
+                    let _ = writeln!(
+                        xml,
+                        ">\n    <failure message=\"{0}\">{0}</failure>\n  </testcase>",
+                        escape(message)
+                    );
+                }
+                Outcome::Skipped => xml.push_str(">\n    <skipped/>\n  </testcase>\n"),
+            }
+        }
+        xml.push_str("</testsuite>\n");
+        xml
+    }
+}
+
+/// Escape text for XML attributes and content.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Outcome, TestSuite};
+    use std::time::Duration;
+
+    #[test]
+    fn test_junit_xml() {
+        let mut suite = TestSuite::new("restart");
+        suite.push("stop node 2", Duration::from_millis(1_500), Outcome::Passed);
+        suite.push(
+            "assert 3 new blocks",
+            Duration::from_secs(60),
+            Outcome::Failed("node 1 stuck at block 4 < 7".to_string()),
+        );
+        suite.push("start node 2", Duration::ZERO, Outcome::Skipped);
+        assert!(!suite.passed());
+
+        let xml = suite.to_xml();
+        assert!(xml.contains(
+            "<testsuite name=\"restart\" tests=\"3\" failures=\"1\" skipped=\"1\" time=\"61.500\">"
+        ));
+        assert!(xml.contains("name=\"stop node 2\" time=\"1.500\"/>"));
+        assert!(xml.contains("<failure message=\"node 1 stuck at block 4 &lt; 7\">"));
+        assert!(xml.contains("<skipped/>"));
+        assert!(xml.ends_with("</testsuite>\n"));
+    }
+}
@@ -0,0 +1,508 @@
+//! Integration test harness.
+//!
+//! Launches a network of validators, runs a scripted [Scenario] against it, and reports every
+//! step as a JUnit test case. This fills the gap between unit tests and manual devnets: scenarios
+//! can stop, kill and restart validators while asserting the network keeps making progress.
+//! Enabled with the `it` feature, see the `tn-it` binary.
+//!
+//! Every validator runs in a child `tn-it --node` process rather than in the harness process. A
+//! node in the harness process can only be asked to shutdown, its tasks, databases and file locks
+//! live as long as the process. A child process can be killed outright, so `kill_node` leaves the
+//! node's data exactly as a crash would and the restart tests crash recovery. `stop_node` closes
+//! the child's stdin, which asks the node to shutdown gracefully, and fails the step unless the
+//! process exits cleanly.
+
+mod junit;
+mod scenario;
+
+pub use junit::{Outcome, TestCase, TestSuite};
+pub use scenario::{Scenario, Step};
+
+use crate::{devnet::DevnetArgs, genesis::keypair_from_word, logs};
+use alloy::{
+    network::{EthereumWallet, TransactionBuilder as _},
+    primitives::{TxHash, B256},
+    providers::{Provider as _, ProviderBuilder},
+    rpc::types::TransactionRequest,
+    signers::local::PrivateKeySigner,
+    transports::http::reqwest::Url,
+};
+use clap::Parser;
+use fdlimit::raise_fd_limit;
+use reth::args::RpcServerArgs;
+use reth_node_core::args::LogArgs;
+use std::{
+    io::ErrorKind,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    time::{Duration, Instant},
+};
+use tn_node_api::TelcoinNodeBuilder;
+use tn_types::{Address, U256};
+use tokio::runtime::Runtime;
+use tracing::{error, info};
+
+/// The account funded at genesis that sends the scenario's transactions.
+const FUNDED_ACCOUNT: &str = "test-source";
+/// The account that receives the scenario's transactions.
+const RECIPIENT: Address = Address::with_last_byte(0xaa);
+/// The value of each transfer, one TEL.
+const TRANSFER_VALUE: u128 = 1_000_000_000_000_000_000;
+/// How long to wait for a node to launch.
+const NODE_START_TIMEOUT: Duration = Duration::from_secs(60);
+/// How often to poll nodes while waiting for a condition.
+const POLL_INTERVAL: Duration = Duration::from_millis(500);
+/// The file in a validator's data dir its process writes the RPC address to once it runs.
+const RPC_ADDRESS_FILE: &str = "it-rpc-address";
+
+/// Run a scenario against a local network and report the results.
+#[derive(Debug, Parser)]
+#[command(name = "tn-it", about = "Run a scripted scenario against a local network")]
+pub struct ItArgs {
+    /// The scenario file to run.
+    #[arg(value_name = "SCENARIO", required_unless_present = "node")]
+    pub scenario: Option<PathBuf>,
+
+    /// Write the results as a JUnit XML report to this file.
+    #[arg(long, value_name = "FILE")]
+    pub junit: Option<PathBuf>,
+
+    /// The root directory for the validators' data.
+    ///
+    /// Defaults to a temporary directory that is removed after the run.
+    #[arg(long, value_name = "DATA_DIR")]
+    pub datadir: Option<PathBuf>,
+
+    /// Run the validator in this data dir until stdin is closed instead of a scenario.
+    ///
+    /// The harness launches every validator as a child process with this option.
+    #[arg(long, value_name = "DATA_DIR", hide = true, requires = "instance")]
+    pub node: Option<PathBuf>,
+
+    /// The instance number of the validator run with `--node`.
+    #[arg(long, hide = true)]
+    pub instance: Option<u16>,
+
+    /// The log configuration.
+    #[clap(flatten)]
+    pub logs: LogArgs,
+}
+
+impl ItArgs {
+    /// Run the scenario and write the JUnit report.
+    ///
+    /// Returns the results, check [TestSuite::passed] for the outcome. Errors are only returned if
+    /// the scenario could not run at all.
+    pub fn execute(&self) -> eyre::Result<TestSuite> {
+        let (_guard, _) = logs::init_tracing(&self.logs)?;
+        // every validator opens its own databases and sockets
+        raise_fd_limit()?;
+        let path = self.scenario.as_ref().ok_or_else(|| eyre::eyre!("no scenario to run"))?;
+        let scenario = Scenario::load(path)?;
+
+        let tempdir = tempfile::tempdir()?;
+        let root = self.datadir.clone().unwrap_or_else(|| tempdir.path().to_path_buf());
+        let suite = run_scenario(&scenario, &root)?;
+
+        if let Some(path) = &self.junit {
+            std::fs::write(path, suite.to_xml())?;
+            info!(target: "tn::it", ?path, "wrote junit report");
+        }
+        info!(
+            target: "tn::it",
+            scenario = scenario.name,
+            steps = suite.cases.len(),
+            failures = suite.failures(),
+            skipped = suite.skipped(),
+            "scenario finished"
+        );
+        Ok(suite)
+    }
+
+    /// Run the validator passed with `--node` until the harness closes stdin.
+    pub fn run_node(&self) -> eyre::Result<()> {
+        let (Some(datadir), Some(instance)) = (&self.node, self.instance) else {
+            eyre::bail!("--node requires --instance");
+        };
+        let (_guard, _) = logs::init_tracing(&self.logs)?;
+        raise_fd_limit()?;
+        run_node(datadir, instance)
+    }
+}
+
+/// Launch the scenario's network in `root`, run every step, and shutdown the network.
+///
+/// Validators run in child processes of the current executable, which must be `tn-it`. Steps
+/// after the first failure are skipped.
+pub fn run_scenario(scenario: &Scenario, root: &Path) -> eyre::Result<TestSuite> {
+    let mut runner = ScenarioRunner::new(scenario, root)?;
+    let mut suite = TestSuite::new(&scenario.name);
+
+    let start = Instant::now();
+    let launched = runner.start_network();
+    suite.push("launch network", start.elapsed(), outcome(launched));
+
+    for step in scenario.steps.iter() {
+        if !suite.passed() {
+            suite.push(step.name(), Duration::ZERO, Outcome::Skipped);
+            continue;
+        }
+        info!(target: "tn::it", step = step.name(), "running step");
+        let start = Instant::now();
+        let res = runner.run_step(step);
+        suite.push(step.name(), start.elapsed(), outcome(res));
+    }
+
+    runner.shutdown();
+    Ok(suite)
+}
+
+/// Convert a step's result to its outcome.
+fn outcome(res: eyre::Result<()>) -> Outcome {
+    match res {
+        Ok(()) => Outcome::Passed,
+        Err(e) => {
+            error!(target: "tn::it", ?e, "step failed");
+            Outcome::Failed(format!("{e:#}"))
+        }
+    }
+}
+
+/// Run the validator in `datadir` until stdin is closed.
+///
+/// This is the child process of a validator launched by [run_scenario]. The node writes its RPC
+/// address to [RPC_ADDRESS_FILE] once it runs.
+fn run_node(datadir: &Path, instance: u16) -> eyre::Result<()> {
+    let mut rpc = RpcServerArgs::default();
+    rpc.http = true;
+    let handle = TelcoinNodeBuilder::new(datadir).with_rpc(rpc).with_instance(instance).launch()?;
+
+    // the harness closes stdin to stop the node gracefully
+    let node_handle = handle.node_handle().clone();
+    std::thread::spawn(move || {
+        let _ = std::io::copy(&mut std::io::stdin(), &mut std::io::sink());
+        node_handle.shutdown();
+    });
+
+    while !handle.node_handle().is_running() {
+        if handle.is_finished() {
+            return handle.wait();
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+    let address = handle
+        .node_handle()
+        .rpc_address()
+        .ok_or_else(|| eyre::eyre!("node {instance} is not serving RPC"))?;
+    // renamed into place so the harness never reads a partial address
+    let tmp = datadir.join(format!("{RPC_ADDRESS_FILE}.tmp"));
+    std::fs::write(&tmp, address.to_string())?;
+    std::fs::rename(tmp, datadir.join(RPC_ADDRESS_FILE))?;
+
+    handle.wait()
+}
+
+/// A validator running in a child process.
+struct NodeProcess {
+    /// The validator's process.
+    child: Child,
+    /// The node's RPC address, `None` until it runs.
+    rpc_address: Option<SocketAddr>,
+}
+
+impl Drop for NodeProcess {
+    fn drop(&mut self) {
+        // never leave a validator behind if the harness fails
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Runs the steps of a scenario against the network.
+struct ScenarioRunner {
+    /// Runtime for RPC requests to the nodes.
+    runtime: Runtime,
+    /// The data directory for each validator.
+    datadirs: Vec<PathBuf>,
+    /// The process of each validator, `None` while stopped.
+    nodes: Vec<Option<NodeProcess>>,
+    /// Signs the scenario's transactions.
+    signer: PrivateKeySigner,
+    /// The nonce for the next transaction, read from the chain for the first transaction.
+    nonce: Option<u64>,
+    /// Transactions submitted so far.
+    submitted: Vec<TxHash>,
+}
+
+impl ScenarioRunner {
+    /// Configure a new network for `scenario` in `root`.
+    fn new(scenario: &Scenario, root: &Path) -> eyre::Result<Self> {
+        let devnet = DevnetArgs {
+            validators: scenario.validators,
+            datadir: Some(root.to_path_buf()),
+            dev_funded_accounts: vec![FUNDED_ACCOUNT.to_string()],
+            header_delay: scenario.header_delay,
+        };
+        let datadirs = devnet.configure(root)?;
+        let nodes = datadirs.iter().map(|_| None).collect();
+        let (secret, _) = keypair_from_word(FUNDED_ACCOUNT);
+        let signer = PrivateKeySigner::from_bytes(&B256::from(secret.secret_bytes()))?;
+        let runtime = tokio::runtime::Builder::new_multi_thread().enable_all().build()?;
+
+        Ok(Self { runtime, datadirs, nodes, signer, nonce: None, submitted: Vec::new() })
+    }
+
+    /// Run one step.
+    fn run_step(&mut self, step: &Step) -> eyre::Result<()> {
+        match step {
+            Step::WaitForBlocks { blocks, timeout } => {
+                self.wait_for_blocks(|_| *blocks, *timeout)?;
+            }
+            Step::SubmitTransactions { count, node } => {
+                self.submit_transactions(*count, *node)?;
+            }
+            Step::AssertIncluded { timeout } => self.assert_included(*timeout)?,
+            Step::StopNode { node } => self.stop_node(*node)?,
+            Step::KillNode { node } => self.kill_node(*node)?,
+            Step::StartNode { node } => self.start_node(*node)?,
+            Step::AssertProgress { blocks, timeout } => {
+                let heights = self.block_heights()?;
+                self.wait_for_blocks(
+                    |node| heights.iter().find(|(n, _)| *n == node).map_or(0, |(_, h)| *h) + blocks,
+                    *timeout,
+                )?;
+            }
+            Step::Sleep { duration } => std::thread::sleep(*duration),
+        }
+        Ok(())
+    }
+
+    /// Launch every node, then wait until they all run.
+    ///
+    /// Nodes wait for a quorum of peers while starting, so they must launch together.
+    fn start_network(&mut self) -> eyre::Result<()> {
+        for node in 1..=self.nodes.len() {
+            self.launch_node(node)?;
+        }
+        for node in 1..=self.nodes.len() {
+            self.wait_until_running(node)?;
+        }
+        Ok(())
+    }
+
+    /// Launch `node` with its existing data and wait until it runs.
+    fn start_node(&mut self, node: usize) -> eyre::Result<()> {
+        self.launch_node(node)?;
+        self.wait_until_running(node)
+    }
+
+    /// Launch `node` with its existing data in a child process.
+    ///
+    /// The child re-runs the current executable with `--node`, see the module docs for why nodes
+    /// are not run in the harness process. It writes the node's RPC address to [RPC_ADDRESS_FILE]
+    /// once the node runs and shuts the node down when its stdin is closed.
+    fn launch_node(&mut self, node: usize) -> eyre::Result<()> {
+        eyre::ensure!(self.nodes[node - 1].is_none(), "node {node} is already running");
+
+        // the address file of a previous run is stale
+        let datadir = &self.datadirs[node - 1];
+        match std::fs::remove_file(datadir.join(RPC_ADDRESS_FILE)) {
+            Err(e) if e.kind() != ErrorKind::NotFound => return Err(e.into()),
+            _ => {}
+        }
+        let child = Command::new(std::env::current_exe()?)
+            .arg("--node")
+            .arg(datadir)
+            .arg("--instance")
+            .arg(node.to_string())
+            .stdin(Stdio::piped())
+            .spawn()?;
+        self.nodes[node - 1] = Some(NodeProcess { child, rpc_address: None });
+        Ok(())
+    }
+
+    /// Wait until a launched `node` runs and read its RPC address.
+    fn wait_until_running(&mut self, node: usize) -> eyre::Result<()> {
+        let address_file = self.datadirs[node - 1].join(RPC_ADDRESS_FILE);
+        let process = self.nodes[node - 1]
+            .as_mut()
+            .ok_or_else(|| eyre::eyre!("node {node} was not launched"))?;
+        poll_until(NODE_START_TIMEOUT, || {
+            if let Some(status) = process.child.try_wait()? {
+                eyre::bail!("node {node} exited with {status} while starting");
+            }
+            match std::fs::read_to_string(&address_file) {
+                Ok(address) => {
+                    process.rpc_address = Some(address.trim().parse()?);
+                    Ok(None)
+                }
+                Err(e) if e.kind() == ErrorKind::NotFound => {
+                    Ok(Some(format!("node {node} is not running")))
+                }
+                Err(e) => Err(e.into()),
+            }
+        })?;
+        info!(target: "tn::it", node, "node running");
+        Ok(())
+    }
+
+    /// Shutdown `node` gracefully and wait for its process to exit.
+    fn stop_node(&mut self, node: usize) -> eyre::Result<()> {
+        let mut process =
+            self.nodes[node - 1].take().ok_or_else(|| eyre::eyre!("node {node} is not running"))?;
+        // closing stdin asks the node to shutdown
+        drop(process.child.stdin.take());
+        let status = process.child.wait()?;
+        eyre::ensure!(status.success(), "node {node} exited with {status}");
+        info!(target: "tn::it", node, "node stopped");
+        Ok(())
+    }
+
+    /// Kill `node`'s process without a graceful shutdown, as if it crashed.
+    fn kill_node(&mut self, node: usize) -> eyre::Result<()> {
+        let mut process =
+            self.nodes[node - 1].take().ok_or_else(|| eyre::eyre!("node {node} is not running"))?;
+        process.child.kill()?;
+        process.child.wait()?;
+        info!(target: "tn::it", node, "node killed");
+        Ok(())
+    }
+
+    /// Shutdown every running node.
+    fn shutdown(&mut self) {
+        for node in 1..=self.nodes.len() {
+            if self.nodes[node - 1].is_some() {
+                if let Err(e) = self.stop_node(node) {
+                    error!(target: "tn::it", node, ?e, "node failed during shutdown");
+                }
+            }
+        }
+    }
+
+    /// The RPC address of every running node.
+    ///
+    /// Fails if a node stopped without being asked to.
+    fn rpc_addresses(&mut self) -> eyre::Result<Vec<(usize, SocketAddr)>> {
+        let mut addresses = Vec::new();
+        for (i, process) in self.nodes.iter_mut().enumerate() {
+            let Some(process) = process else { continue };
+            if let Some(status) = process.child.try_wait()? {
+                eyre::bail!("node {} exited unexpectedly with {status}", i + 1);
+            }
+            let address = process
+                .rpc_address
+                .ok_or_else(|| eyre::eyre!("node {} is not serving RPC", i + 1))?;
+            addresses.push((i + 1, address));
+        }
+        Ok(addresses)
+    }
+
+    /// The latest block number of every running node.
+    fn block_heights(&mut self) -> eyre::Result<Vec<(usize, u64)>> {
+        let addresses = self.rpc_addresses()?;
+        self.runtime.block_on(async {
+            let mut heights = Vec::with_capacity(addresses.len());
+            for (node, address) in addresses {
+                let provider = ProviderBuilder::new().on_http(rpc_url(address)?);
+                heights.push((node, provider.get_block_number().await?));
+            }
+            eyre::Ok(heights)
+        })
+    }
+
+    /// Wait until every running node reaches the block returned by `target` for it.
+    fn wait_for_blocks(
+        &mut self,
+        target: impl Fn(usize) -> u64,
+        timeout: Duration,
+    ) -> eyre::Result<()> {
+        poll_until(timeout, || {
+            let lagging: Vec<String> = self
+                .block_heights()?
+                .into_iter()
+                .filter(|(node, height)| *height < target(*node))
+                .map(|(node, height)| format!("node {node} at block {height} < {}", target(node)))
+                .collect();
+            Ok(if lagging.is_empty() { None } else { Some(lagging.join(", ")) })
+        })
+    }
+
+    /// Submit `count` transfers from the funded account through `node`.
+    fn submit_transactions(&mut self, count: u64, node: usize) -> eyre::Result<()> {
+        let address = self
+            .rpc_addresses()?
+            .into_iter()
+            .find_map(|(n, address)| (n == node).then_some(address))
+            .ok_or_else(|| eyre::eyre!("node {node} is not running"))?;
+        let provider = ProviderBuilder::new()
+            .with_recommended_fillers()
+            .wallet(EthereumWallet::from(self.signer.clone()))
+            .on_http(rpc_url(address)?);
+        let sender = self.signer.address();
+
+        let mut nonce = self.nonce;
+        let submitted = &mut self.submitted;
+        let res = self.runtime.block_on(async {
+            for _ in 0..count {
+                let next = match nonce {
+                    Some(nonce) => nonce,
+                    None => provider.get_transaction_count(sender).await?,
+                };
+                let tx = TransactionRequest::default()
+                    .with_to(RECIPIENT)
+                    .with_value(U256::from(TRANSFER_VALUE))
+                    .with_nonce(next);
+                let pending = provider.send_transaction(tx).await?;
+                submitted.push(*pending.tx_hash());
+                nonce = Some(next + 1);
+            }
+            eyre::Ok(())
+        });
+        // keep the nonce of transactions sent before a failure
+        self.nonce = nonce;
+        res
+    }
+
+    /// Wait until every submitted transaction has a receipt on every running node.
+    fn assert_included(&mut self, timeout: Duration) -> eyre::Result<()> {
+        poll_until(timeout, || {
+            let addresses = self.rpc_addresses()?;
+            self.runtime.block_on(async {
+                for (node, address) in addresses {
+                    let provider = ProviderBuilder::new().on_http(rpc_url(address)?);
+                    for hash in self.submitted.iter() {
+                        if provider.get_transaction_receipt(*hash).await?.is_none() {
+                            return Ok(Some(format!("{hash} not included on node {node}")));
+                        }
+                    }
+                }
+                eyre::Ok(None)
+            })
+        })
+    }
+}
+
+/// Call `check` until it returns `None` or `timeout` passes.
+///
+/// `check` returns why the condition is not met yet, which is the error after the timeout.
+fn poll_until(
+    timeout: Duration,
+    mut check: impl FnMut() -> eyre::Result<Option<String>>,
+) -> eyre::Result<()> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let Some(reason) = check()? else {
+            return Ok(());
+        };
+        if Instant::now() >= deadline {
+            eyre::bail!("timed out after {timeout:?}: {reason}");
+        }
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+/// The HTTP URL for a node's RPC address.
+fn rpc_url(address: SocketAddr) -> eyre::Result<Url> {
+    Ok(format!("http://{address}").parse()?)
+}
@@ -0,0 +1,216 @@
+//! Scenario files for the integration harness.
+//!
+//! A scenario is a YAML file with the network to launch and the steps to run against it:
+//!
+//! ```yaml
+//! name: restart-validator
+//! validators: 4
+//! steps:
+//!   - action: wait_for_blocks
+//!     blocks: 2
+//!   - action: submit_transactions
+//!     count: 5
+//!   - action: kill_node
+//!     node: 2
+//!   - action: assert_progress
+//!     blocks: 3
+//!     timeout: 90s
+//! ```
+//!
+//! Nodes are numbered from 1, the same as the devnet's `validator-<N>` directories.
+
+use eyre::WrapErr as _;
+use serde::Deserialize;
+use std::{path::Path, time::Duration};
+
+/// A scripted run against a local network.
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct Scenario {
+    /// The name of the scenario, used for the JUnit test suite.
+    pub name: String,
+    /// The number of validators in the committee.
+    #[serde(default = "Scenario::default_validators")]
+    pub validators: u16,
+    /// The delay between headers.
+    #[serde(with = "humantime_serde", default = "Scenario::default_header_delay")]
+    pub header_delay: Duration,
+    /// The steps to run in order.
+    pub steps: Vec<Step>,
+}
+
+impl Scenario {
+    /// Load a scenario from a YAML file.
+    pub fn load(path: &Path) -> eyre::Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .wrap_err_with(|| format!("failed to read scenario {}", path.display()))?;
+        let scenario: Self = serde_yaml::from_str(&contents)
+            .wrap_err_with(|| format!("invalid scenario {}", path.display()))?;
+        scenario.validate()?;
+        Ok(scenario)
+    }
+
+    /// Ensure every step refers to a node in the network.
+    pub fn validate(&self) -> eyre::Result<()> {
+        eyre::ensure!(self.validators >= 4, "scenarios need at least 4 validators");
+        for step in self.steps.iter() {
+            if let Some(node) = step.node() {
+                eyre::ensure!(
+                    (1..=self.validators as usize).contains(&node),
+                    "step `{}` refers to node {node} but the network has {} validators",
+                    step.name(),
+                    self.validators
+                );
+            }
+        }
+        Ok(())
+    }
+
+    fn default_validators() -> u16 {
+        4
+    }
+
+    fn default_header_delay() -> Duration {
+        Duration::from_secs(1)
+    }
+}
+
+/// One step of a [Scenario].
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum Step {
+    /// Wait until every running node executed `blocks` blocks.
+    WaitForBlocks {
+        /// The block number to reach.
+        blocks: u64,
+        /// How long to wait.
+        #[serde(with = "humantime_serde", default = "Step::default_timeout")]
+        timeout: Duration,
+    },
+    /// Submit transfers from the funded `test-source` account through a node's RPC.
+    SubmitTransactions {
+        /// The number of transactions to submit.
+        count: u64,
+        /// The node that receives the transactions.
+        #[serde(default = "Step::default_node")]
+        node: usize,
+    },
+    /// Wait until every submitted transaction is included on every running node.
+    AssertIncluded {
+        /// How long to wait.
+        #[serde(with = "humantime_serde", default = "Step::default_timeout")]
+        timeout: Duration,
+    },
+    /// Shutdown a node gracefully.
+    StopNode {
+        /// The node to stop.
+        node: usize,
+    },
+    /// Kill a node's process without a graceful shutdown, as if it crashed.
+    KillNode {
+        /// The node to kill.
+        node: usize,
+    },
+    /// Launch a stopped node with its existing data.
+    StartNode {
+        /// The node to start.
+        node: usize,
+    },
+    /// Wait until every running node executes `blocks` more blocks.
+    AssertProgress {
+        /// The number of new blocks.
+        blocks: u64,
+        /// How long to wait.
+        #[serde(with = "humantime_serde", default = "Step::default_timeout")]
+        timeout: Duration,
+    },
+    /// Do nothing for a while.
+    Sleep {
+        /// How long to sleep.
+        #[serde(with = "humantime_serde")]
+        duration: Duration,
+    },
+}
+
+impl Step {
+    /// A short description of the step for reports.
+    pub fn name(&self) -> String {
+        match self {
+            Self::WaitForBlocks { blocks, .. } => format!("wait for block {blocks}"),
+            Self::SubmitTransactions { count, node } => {
+                format!("submit {count} transactions to node {node}")
+            }
+            Self::AssertIncluded { .. } => "assert transactions included".to_string(),
+            Self::StopNode { node } => format!("stop node {node}"),
+            Self::KillNode { node } => format!("kill node {node}"),
+            Self::StartNode { node } => format!("start node {node}"),
+            Self::AssertProgress { blocks, .. } => format!("assert {blocks} new blocks"),
+            Self::Sleep { duration } => format!("sleep {}", humantime::format_duration(*duration)),
+        }
+    }
+
+    /// The node the step targets, if any.
+    fn node(&self) -> Option<usize> {
+        match self {
+            Self::SubmitTransactions { node, .. }
+            | Self::StopNode { node }
+            | Self::KillNode { node }
+            | Self::StartNode { node } => Some(*node),
+            _ => None,
+        }
+    }
+
+    fn default_timeout() -> Duration {
+        Duration::from_secs(60)
+    }
+
+    fn default_node() -> usize {
+        1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Scenario, Step};
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_scenario() {
+        let yaml = r#"
+name: restart
+steps:
+  - action: wait_for_blocks
+    blocks: 2
+  - action: submit_transactions
+    count: 5
+  - action: stop_node
+    node: 2
+  - action: kill_node
+    node: 3
+  - action: assert_progress
+    blocks: 3
+    timeout: 90s
+  - action: sleep
+    duration: 500ms
+"#;
+        let scenario: Scenario = serde_yaml::from_str(yaml).expect("scenario parsed");
+        assert_eq!(scenario.validators, 4);
+        assert_eq!(scenario.header_delay, Duration::from_secs(1));
+        assert_eq!(
+            scenario.steps,
+            vec![
+                Step::WaitForBlocks { blocks: 2, timeout: Duration::from_secs(60) },
+                Step::SubmitTransactions { count: 5, node: 1 },
+                Step::StopNode { node: 2 },
+                Step::KillNode { node: 3 },
+                Step::AssertProgress { blocks: 3, timeout: Duration::from_secs(90) },
+                Step::Sleep { duration: Duration::from_millis(500) },
+            ]
+        );
+        scenario.validate().expect("valid scenario");
+
+        // nodes outside the network are rejected
+        let mut invalid = scenario.clone();
+        invalid.steps.push(Step::StartNode { node: 5 });
+        assert!(invalid.validate().is_err());
+    }
+}
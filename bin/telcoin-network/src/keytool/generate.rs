@@ -1,14 +1,16 @@
 //! Generate subcommand
 
+use super::mnemonic::ValidatorMnemonic;
 use crate::args::{clap_address_parser, clap_genesis_parser};
 use clap::{value_parser, Args, Subcommand};
 use reth::dirs::MaybePlatformPath;
 use reth_chainspec::ChainSpec;
-use std::sync::Arc;
+use std::{path::PathBuf, sync::Arc};
 use tn_config::{Config, KeyConfig, TelcoinDirs};
 use tn_node::dirs::DataDirPath;
 use tn_types::Address;
-use tracing::info;
+use tracing::{info, warn};
+use zeroize::Zeroizing;
 
 /// Generate keypairs and save them to a file.
 #[derive(Debug, Clone, Args)]
@@ -75,15 +77,29 @@ pub struct ValidatorArgs {
     /// The validator uses this address when producing batches and blocks.
     /// Validators can pass "0" to use the zero address.
     /// Address doesn't have to start with "0x", but the CLI supports the "0x" format too.
+    /// Defaults to the address of the mnemonic's execution key with `--mnemonic` or `--recover`.
     #[arg(
         long = "address",
         alias = "execution-address",
         help_heading = "The address that should receive block rewards. Pass `0` to use the zero address.",
         env = "EXECUTION_ADDRESS", // TODO: this doesn't work like it should
         value_parser = clap_address_parser,
+        required_unless_present_any = ["mnemonic", "recover"],
         verbatim_doc_comment
     )]
-    pub address: Address,
+    pub address: Option<Address>,
+
+    /// Derive the keys from a new 24 word BIP-39 mnemonic and write it to FILE as a backup phrase.
+    ///
+    /// The execution key uses the BIP-44 path m/44'/60'/0'/0/0 and the BLS key uses the
+    /// EIP-2333 path m/12381/3600/0/0/0. FILE must not exist and is only readable by its owner.
+    /// Store the phrase offline and delete FILE- it recovers every key.
+    #[arg(long, value_name = "FILE", conflicts_with = "recover", verbatim_doc_comment)]
+    pub mnemonic: Option<PathBuf>,
+
+    /// Recover the keys from the BIP-39 mnemonic in FILE.
+    #[arg(long, value_name = "FILE", verbatim_doc_comment)]
+    pub recover: Option<PathBuf>,
 }
 
 impl ValidatorArgs {
@@ -95,10 +111,31 @@ impl ValidatorArgs {
     ) -> eyre::Result<()> {
         info!(target: "tn::generate_keys", "generating keys for full validator node");
 
-        let key_config = KeyConfig::generate_and_save(tn_datadir)?;
-        let proof = key_config.generate_proof_of_possession_bls(&self.chain)?;
+        let mnemonic = match &self.recover {
+            Some(path) => {
+                let phrase = Zeroizing::new(std::fs::read_to_string(path)?);
+                Some(ValidatorMnemonic::from_phrase(&phrase)?)
+            }
+            None => match &self.mnemonic {
+                Some(path) => {
+                    // never generate keys without a backup
+                    let mnemonic = ValidatorMnemonic::generate()?;
+                    mnemonic.write_new(path)?;
+                    warn!(
+                        target: "tn::generate_keys",
+                        ?path,
+                        "store the backup phrase offline and delete the file"
+                    );
+                    Some(mnemonic)
+                }
+                None => None,
+            },
+        };
+        let key_config = match &mnemonic {
+            Some(mnemonic) => KeyConfig::save_keypair(tn_datadir, mnemonic.bls_keypair()?)?,
+            None => KeyConfig::generate_and_save(tn_datadir)?,
+        };
         config.update_protocol_key(key_config.primary_public_key())?;
-        config.update_proof_of_possession(proof)?;
 
         // network keypair for authority
         let network_publickey = key_config.primary_network_public_key();
@@ -109,7 +146,20 @@ impl ValidatorArgs {
         config.update_worker_network_key(network_publickey)?;
 
         // add execution address
-        config.update_execution_address(self.address)?;
+        let address = match (self.address, &mnemonic) {
+            (Some(address), _) => address,
+            (None, Some(mnemonic)) => mnemonic.execution_address()?,
+            (None, None) => eyre::bail!("an execution address is required without a mnemonic"),
+        };
+        config.update_execution_address(address)?;
+
+        // the proof commits to the keys and address above
+        let proof = key_config.generate_proof_of_possession_bls(
+            config.execution_address(),
+            &config.validator_info.primary_info,
+            &self.chain,
+        )?;
+        config.update_proof_of_possession(proof)?;
 
         Ok(())
     }
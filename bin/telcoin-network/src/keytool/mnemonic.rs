@@ -0,0 +1,128 @@
+//! Backup phrases for validator keys.
+//!
+//! A validator's keys can be generated from, and recovered with, a 24 word BIP-39 mnemonic. The
+//! mnemonic is converted to a BIP-39 seed without a passphrase and each key is derived from it:
+//! - execution key (secp256k1): BIP-44 path `m/44'/60'/0'/0/0`, so any Ethereum wallet recovers the
+//!   same execution address
+//! - BLS key (bls12381): EIP-2333 key tree with the EIP-2334 signing key path `m/12381/3600/0/0/0`
+//!
+//! The network keys are derived from the BLS key with the default seeds. Network keys rotated with
+//! `keytool rotate-network-key` are not recovered and must be rotated again.
+
+use alloy::signers::local::{
+    coins_bip39::{English, Mnemonic},
+    MnemonicBuilder,
+};
+use eyre::WrapErr as _;
+use std::{fs::OpenOptions, io::Write as _, path::Path};
+use tn_types::{Address, BlsKeypair, EIP2334_SIGNING_KEY_PATH};
+use zeroize::Zeroizing;
+
+/// The number of words in a generated mnemonic.
+const MNEMONIC_WORDS: usize = 24;
+
+/// The BIP-44 derivation path of the execution key.
+const EXECUTION_KEY_PATH: &str = "m/44'/60'/0'/0/0";
+
+/// The mnemonic that backs up a validator's keys.
+pub(crate) struct ValidatorMnemonic {
+    mnemonic: Mnemonic<English>,
+}
+
+impl ValidatorMnemonic {
+    /// Generate a new random mnemonic.
+    pub(crate) fn generate() -> eyre::Result<Self> {
+        let mnemonic =
+            Mnemonic::<English>::new_with_count(&mut rand::thread_rng(), MNEMONIC_WORDS)?;
+        Ok(Self { mnemonic })
+    }
+
+    /// Parse a mnemonic from its phrase.
+    ///
+    /// Words may be separated by any whitespace.
+    pub(crate) fn from_phrase(phrase: &str) -> eyre::Result<Self> {
+        let phrase = Zeroizing::new(phrase.split_whitespace().collect::<Vec<_>>().join(" "));
+        let mnemonic = Mnemonic::<English>::new_from_phrase(&phrase)?;
+        Ok(Self { mnemonic })
+    }
+
+    /// The words of the mnemonic separated by spaces.
+    pub(crate) fn phrase(&self) -> Zeroizing<String> {
+        Zeroizing::new(self.mnemonic.to_phrase())
+    }
+
+    /// Write the phrase to a new file at `path` that only its owner can read.
+    ///
+    /// Fails if the file exists so a phrase is never overwritten.
+    pub(crate) fn write_new(&self, path: &Path) -> eyre::Result<()> {
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options
+            .open(path)
+            .with_context(|| format!("failed to create mnemonic file {}", path.display()))?;
+        file.write_all(self.phrase().as_bytes())?;
+        file.sync_all()?;
+        Ok(())
+    }
+
+    /// Derive the BLS key.
+    pub(crate) fn bls_keypair(&self) -> eyre::Result<BlsKeypair> {
+        let seed = Zeroizing::new(self.mnemonic.to_seed(None)?);
+        BlsKeypair::derive_eip2333(seed.as_ref(), &EIP2334_SIGNING_KEY_PATH)
+    }
+
+    /// Derive the execution address from the execution key.
+    pub(crate) fn execution_address(&self) -> eyre::Result<Address> {
+        let signer = MnemonicBuilder::<English>::default()
+            .phrase(self.phrase().as_str())
+            .derivation_path(EXECUTION_KEY_PATH)?
+            .build()?;
+        Ok(signer.address())
+    }
+}
+
+impl std::fmt::Debug for ValidatorMnemonic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ValidatorMnemonic").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr as _;
+
+    #[test]
+    fn test_validator_mnemonic() {
+        // the well-known development mnemonic
+        let phrase = "test test test test test test test test test test test\n  junk ";
+        let mnemonic = ValidatorMnemonic::from_phrase(phrase).expect("valid mnemonic");
+        assert_eq!(
+            mnemonic.execution_address().expect("address derived"),
+            Address::from_str("0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266").expect("valid address")
+        );
+
+        // keys are recovered from a generated phrase
+        let generated = ValidatorMnemonic::generate().expect("mnemonic generated");
+        assert_eq!(generated.phrase().split(' ').count(), MNEMONIC_WORDS);
+        let recovered = ValidatorMnemonic::from_phrase(&generated.phrase()).expect("recovered");
+        assert_eq!(
+            generated.bls_keypair().expect("bls key").public(),
+            recovered.bls_keypair().expect("bls key").public()
+        );
+        assert_eq!(
+            generated.execution_address().expect("address"),
+            recovered.execution_address().expect("address")
+        );
+        assert_ne!(
+            generated.bls_keypair().expect("bls key").public(),
+            mnemonic.bls_keypair().expect("bls key").public()
+        );
+
+        // invalid words and checksums are rejected
+        assert!(ValidatorMnemonic::from_phrase("test test test").is_err());
+        assert!(ValidatorMnemonic::from_phrase(&"test ".repeat(12)).is_err());
+    }
+}
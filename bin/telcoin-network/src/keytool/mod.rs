@@ -1,6 +1,7 @@
 //! Key command to generate all keys for running a full validator node.
 
 mod generate;
+mod mnemonic;
 use self::generate::NodeType;
 use crate::args::clap_genesis_parser;
 use clap::{value_parser, Args, Subcommand};
@@ -13,8 +14,9 @@ use std::{
     path::{Path, PathBuf},
     sync::Arc,
 };
-use tn_config::{Config, ConfigFmt, ConfigTrait, TelcoinDirs as _};
+use tn_config::{write_file_atomic, Config, ConfigFmt, ConfigTrait, KeyConfig, TelcoinDirs as _};
 use tn_node::dirs::{default_datadir_args, DataDirChainPath, DataDirPath};
+use tn_types::Epoch;
 use tracing::{debug, info, warn};
 
 /// Generate keypairs and save them to a file.
@@ -63,12 +65,10 @@ pub struct KeyArgs {
     /// Max number of instances is 200. It is chosen in a way so that it's not possible to have
     /// port numbers that conflict with each other.
     ///
-    /// Changes to the following port numbers:
-    /// - DISCOVERY_PORT: default + `instance` - 1
-    /// - AUTH_PORT: default + `instance` * 100 - 100
-    /// - HTTP_RPC_PORT: default - `instance` + 1
-    /// - WS_RPC_PORT: default + `instance` * 2 - 2
-    #[arg(long, value_name = "INSTANCE", global = true, default_value_t = 1, value_parser = value_parser!(u16).range(..=200))]
+    /// The validator's consensus network addresses use the following ports:
+    /// - PRIMARY_PORT: default + `instance` * 10 - 10
+    /// - WORKER_PORT: default + `instance` * 10 - 10 + `worker_id`
+    #[arg(long, value_name = "INSTANCE", global = true, default_value_t = 1, value_parser = value_parser!(u16).range(1..=200))]
     pub instance: u16,
 }
 
@@ -81,6 +81,33 @@ pub enum KeySubcommand {
     /// Read public keys from file.
     #[command(name = "read")]
     Read,
+    /// Generate new network keys and write a signed rotation announcement.
+    #[command(name = "rotate-network-key")]
+    RotateNetworkKey(RotateNetworkKey),
+}
+
+/// Rotate the node's primary and worker network keys.
+///
+/// The new keys are derived from the node's BLS key and fresh random seeds. The seeds and the
+/// node's config are updated atomically and a rotation announcement signed by the BLS key is
+/// written to file. Distribute the announcement to peers so they can update their committee and
+/// worker cache files before `epoch` starts.
+///
+/// If a previous rotation was interrupted, the config is updated to the committed keys and no new
+/// keys are generated.
+#[derive(Debug, Clone, Args)]
+pub struct RotateNetworkKey {
+    /// The epoch the new network keys take effect.
+    ///
+    /// Peers reject the announcement once this epoch has started.
+    #[arg(long, value_name = "EPOCH")]
+    pub epoch: Epoch,
+
+    /// The path to write the signed rotation announcement (JSON).
+    ///
+    /// Defaults to `<DATA_DIR>/validator/network-key-rotation.json`.
+    #[arg(long, value_name = "FILE")]
+    pub announcement: Option<PathBuf>,
 }
 
 /// Read arg that reads from file.
@@ -105,15 +132,90 @@ impl KeyArgs {
                         self.init_path(&authority_key_path, args.force)?;
                         // execute and store keypath
                         args.execute(&mut config, &datadir)?;
+                        // assign network ports so local instances do not clash
+                        config.adjust_instance_ports(self.instance)?;
 
                         debug!("{config:?}");
-                        Config::store_path(self.config_path(), config, ConfigFmt::YAML)?;
+                        let config_path = self.config_path();
+                        Config::store_path(
+                            &config_path,
+                            config,
+                            ConfigFmt::from_path(&config_path),
+                        )?;
                     }
                 }
             }
 
             // read public key from file
             KeySubcommand::Read => todo!(),
+
+            // rotate network keys
+            KeySubcommand::RotateNetworkKey(args) => {
+                let key_config = KeyConfig::read_config(&datadir)?;
+                let config_path = self.config_path();
+
+                // a rotation that stopped after the seeds were committed leaves the config behind
+                let primary_network_key = key_config.primary_network_public_key();
+                let worker_network_key = key_config.worker_network_public_key();
+                if config.validator_info.primary_info.network_key != primary_network_key
+                    || config.validator_info.primary_info.worker_network_key != worker_network_key
+                {
+                    config.update_primary_network_key(primary_network_key)?;
+                    config.update_worker_network_key(worker_network_key)?;
+                    let proof = key_config.generate_proof_of_possession_bls(
+                        config.execution_address(),
+                        &config.validator_info.primary_info,
+                        &config.chain_spec(),
+                    )?;
+                    config.update_proof_of_possession(proof)?;
+                    Config::store_path_atomic(
+                        &config_path,
+                        config,
+                        ConfigFmt::from_path(&config_path),
+                    )?;
+                    warn!(
+                        target: "tn::cli",
+                        "finished an interrupted network key rotation, run again to rotate keys"
+                    );
+                    return Ok(());
+                }
+
+                let (rotated, seeds, rotation) = key_config.rotate_network_keys(args.epoch)?;
+                config.update_primary_network_key(rotated.primary_network_public_key())?;
+                config.update_worker_network_key(rotated.worker_network_public_key())?;
+
+                // the proof of possession commits to the network keys
+                let proof = rotated.generate_proof_of_possession_bls(
+                    config.execution_address(),
+                    &config.validator_info.primary_info,
+                    &config.chain_spec(),
+                )?;
+                config.update_proof_of_possession(proof)?;
+
+                // write the announcement first so a failure leaves the local keys unchanged
+                let announcement_path = args.announcement.clone().unwrap_or_else(|| {
+                    datadir.validator_info_path().join("network-key-rotation.json")
+                });
+                if let Some(parent) = announcement_path.parent() {
+                    std::fs::create_dir_all(parent)?;
+                }
+                write_file_atomic(&announcement_path, serde_json::to_string_pretty(&rotation)?)?;
+
+                KeyConfig::save_network_seeds(&datadir, &seeds)?;
+                Config::store_path_atomic(
+                    &config_path,
+                    config,
+                    ConfigFmt::from_path(&config_path),
+                )?;
+
+                info!(
+                    target: "tn::cli",
+                    primary = ?rotation.new_primary_network_key,
+                    worker = ?rotation.new_worker_network_key,
+                    announcement = ?announcement_path,
+                    "network keys rotated",
+                );
+            }
         }
 
         Ok(())
@@ -186,7 +288,8 @@ impl KeyArgs {
         let config_path = self.config_path();
         debug!(?config_path);
         let config =
-            Config::load_from_path::<Config>(&config_path, ConfigFmt::YAML).unwrap_or_default();
+            Config::load_from_path::<Config>(&config_path, ConfigFmt::from_path(&config_path))
+                .unwrap_or_default();
         debug!("{:?}", config);
 
         info!(target: "tn::cli", path = ?config_path, "Configuration loaded");
@@ -202,6 +305,7 @@ mod tests {
     use reth_cli_commands::node::NoArgs;
     use tempfile::tempdir;
     use tn_config::{Config, ConfigFmt, ConfigTrait};
+    use tn_types::NetworkKeyRotation;
 
     /// Test that generate keys command works.
     /// This test also ensures that confy is able to
@@ -235,4 +339,160 @@ mod tests {
         )
         .expect("config loaded yaml okay");
     }
+
+    /// Test that keys recovered from a mnemonic are the same for every recovery.
+    #[tokio::test]
+    async fn test_recover_keys_from_mnemonic() {
+        let mnemonic = tempdir().expect("tempdir created").into_path().join("mnemonic.txt");
+        std::fs::write(&mnemonic, "test test test test test test test test test test test junk")
+            .expect("mnemonic written");
+        let recover = |datadir: &std::path::Path| {
+            let tn = Cli::<NoArgs>::try_parse_from([
+                "telcoin-network",
+                "keytool",
+                "generate",
+                "validator",
+                "--datadir",
+                datadir.to_str().expect("tempdir path clean"),
+                "--recover",
+                mnemonic.to_str().expect("mnemonic path clean"),
+            ])
+            .expect("cli parsed");
+            tn.run(|_, _, _| Ok(())).expect("recover keys command");
+            Config::load_from_path::<Config>(
+                datadir.join("telcoin-network.yaml").as_path(),
+                ConfigFmt::YAML,
+            )
+            .expect("config loaded yaml okay")
+        };
+
+        let first = recover(&tempdir().expect("tempdir created").into_path());
+        let second = recover(&tempdir().expect("tempdir created").into_path());
+        assert_eq!(
+            first.execution_address().to_string(),
+            "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266"
+        );
+        assert_eq!(first.primary_bls_key(), second.primary_bls_key());
+        assert_eq!(
+            first.validator_info.primary_info.network_key,
+            second.validator_info.primary_info.network_key
+        );
+    }
+
+    /// Test that a generated mnemonic is only written to a new file its owner can read.
+    #[tokio::test]
+    async fn test_generate_keys_with_mnemonic() {
+        let mnemonic = tempdir().expect("tempdir created").into_path().join("mnemonic.txt");
+        let generate = |datadir: &std::path::Path| {
+            Cli::<NoArgs>::try_parse_from([
+                "telcoin-network",
+                "keytool",
+                "generate",
+                "validator",
+                "--datadir",
+                datadir.to_str().expect("tempdir path clean"),
+                "--mnemonic",
+                mnemonic.to_str().expect("mnemonic path clean"),
+            ])
+            .expect("cli parsed")
+            .run(|_, _, _| Ok(()))
+        };
+
+        let datadir = tempdir().expect("tempdir created").into_path();
+        generate(&datadir).expect("generate keys command");
+        let phrase = std::fs::read_to_string(&mnemonic).expect("mnemonic written");
+        assert_eq!(phrase.split(' ').count(), 24);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt as _;
+            let mode = std::fs::metadata(&mnemonic).expect("mnemonic file").permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+
+        // an existing phrase is never overwritten
+        assert!(generate(&tempdir().expect("tempdir created").into_path()).is_err());
+        assert_eq!(std::fs::read_to_string(&mnemonic).expect("mnemonic kept"), phrase);
+
+        // the file recovers the generated keys
+        let recovered = tempdir().expect("tempdir created").into_path();
+        Cli::<NoArgs>::try_parse_from([
+            "telcoin-network",
+            "keytool",
+            "generate",
+            "validator",
+            "--datadir",
+            recovered.to_str().expect("tempdir path clean"),
+            "--recover",
+            mnemonic.to_str().expect("mnemonic path clean"),
+        ])
+        .expect("cli parsed")
+        .run(|_, _, _| Ok(()))
+        .expect("recover keys command");
+        let load = |datadir: &std::path::Path| {
+            Config::load_from_path::<Config>(
+                datadir.join("telcoin-network.yaml").as_path(),
+                ConfigFmt::YAML,
+            )
+            .expect("config loaded yaml okay")
+        };
+        let (generated, recovered) = (load(&datadir), load(&recovered));
+        assert_eq!(generated.execution_address(), recovered.execution_address());
+        assert_eq!(generated.primary_bls_key(), recovered.primary_bls_key());
+    }
+
+    /// Test that rotating network keys updates the config and writes a valid announcement.
+    #[tokio::test]
+    async fn test_rotate_network_key() {
+        let tempdir = tempdir().expect("tempdir created").into_path();
+        let datadir = tempdir.to_str().expect("tempdir path clean");
+        let generate = Cli::<NoArgs>::try_parse_from([
+            "telcoin-network",
+            "keytool",
+            "generate",
+            "validator",
+            "--workers",
+            "1",
+            "--datadir",
+            datadir,
+            "--address",
+            "0",
+        ])
+        .expect("cli parsed");
+        generate.run(|_, _, _| Ok(())).expect("generate keys command");
+        let config_path = tempdir.join("telcoin-network.yaml");
+        let before = Config::load_from_path::<Config>(&config_path, ConfigFmt::YAML)
+            .expect("config loaded yaml okay");
+
+        let announcement = tempdir.join("rotation.json");
+        let rotate = Cli::<NoArgs>::try_parse_from([
+            "telcoin-network",
+            "keytool",
+            "rotate-network-key",
+            "--epoch",
+            "2",
+            "--datadir",
+            datadir,
+            "--announcement",
+            announcement.to_str().expect("announcement path clean"),
+        ])
+        .expect("cli parsed");
+        rotate.run(|_, _, _| Ok(())).expect("rotate network key command");
+
+        let after = Config::load_from_path::<Config>(&config_path, ConfigFmt::YAML)
+            .expect("config loaded yaml okay");
+        let rotation: NetworkKeyRotation =
+            serde_json::from_slice(&std::fs::read(&announcement).expect("announcement written"))
+                .expect("announcement decodes");
+        assert!(rotation.verify_for_next_epoch(1));
+        assert_eq!(rotation.epoch, 2);
+        assert_eq!(
+            rotation.old_primary_network_key,
+            before.validator_info.primary_info.network_key
+        );
+        assert_eq!(rotation.new_primary_network_key, after.validator_info.primary_info.network_key);
+        assert_ne!(
+            before.validator_info.primary_info.worker_network_key,
+            after.validator_info.primary_info.worker_network_key
+        );
+    }
 }
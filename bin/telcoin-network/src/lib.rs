@@ -8,6 +8,7 @@
 //!   calls to the logging component is made.
 //! - `min-debug-logs`: Disables all logs below `debug` level.
 //! - `min-trace-logs`: Disables all logs below `trace` level.
+//! - `it`: Builds the `tn-it` binary to run scripted scenarios against a local network.
 
 #![doc(
     html_logo_url = "https://www.telco.in/logos/TEL.svg",
@@ -19,8 +20,16 @@
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 
 pub mod args;
+pub mod backup;
 pub mod cli;
+pub mod committee;
+pub mod db;
+pub mod devnet;
 pub mod genesis;
+#[cfg(feature = "it")]
+pub mod harness;
 pub mod keytool;
+pub mod logs;
 pub mod node;
+pub mod status;
 pub mod version;
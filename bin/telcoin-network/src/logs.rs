@@ -0,0 +1,120 @@
+//! Tracing setup with a log filter that can change at runtime.
+//!
+//! The stdout filter is wrapped in a reload layer so operators can change it through the
+//! `tnAdmin_setLogFilter` RPC while debugging a live node. File and journald logs keep the filters
+//! they were started with.
+
+use reth_node_core::args::{ColorMode, LogArgs};
+use reth_tracing::{
+    tracing_subscriber::{
+        filter::Directive, fmt, layer::SubscriberExt as _, reload, util::SubscriberInitExt as _,
+        EnvFilter, Layer as _, Registry,
+    },
+    FileInfo, FileWorkerGuard, Layers, LogFormat,
+};
+use std::io::IsTerminal as _;
+use tn_rpc::{LogFilterHandle, TNRpcError, TelcoinNetworkRpcResult};
+
+/// Convert megabytes to bytes for the log file size.
+const MB_TO_BYTES: u64 = 1024 * 1024;
+
+/// Changes the stdout log filter of the running process.
+#[derive(Debug, Clone)]
+pub struct LogFilterReloader {
+    /// The handle to the reloadable stdout filter.
+    handle: reload::Handle<EnvFilter, Registry>,
+    /// The directive for targets the filter doesn't mention (from `-v` flags).
+    default_directive: Directive,
+}
+
+impl LogFilterHandle for LogFilterReloader {
+    fn set_log_filter(&self, filter: &str) -> TelcoinNetworkRpcResult<()> {
+        let filter = EnvFilter::builder()
+            .with_default_directive(self.default_directive.clone())
+            .parse(filter)
+            .map_err(|e| TNRpcError::InvalidLogFilter(e.to_string()))?;
+        self.handle.reload(filter).map_err(|e| TNRpcError::LogFilterUnavailable(e.to_string()))
+    }
+}
+
+/// Initialize tracing for the process.
+///
+/// Returns a guard that must be kept alive to flush file logs and the handle to change the stdout
+/// filter.
+pub(crate) fn init_tracing(
+    logs: &LogArgs,
+) -> eyre::Result<(Option<FileWorkerGuard>, LogFilterReloader)> {
+    let default_directive = logs.verbosity.directive();
+    let filter = EnvFilter::builder()
+        .with_default_directive(default_directive.clone())
+        .parse(&logs.log_stdout_filter)?;
+    let (filter, handle) = reload::Layer::new(filter);
+
+    let ansi = match logs.color {
+        ColorMode::Always => true,
+        ColorMode::Never => false,
+        ColorMode::Auto => std::io::stdout().is_terminal(),
+    };
+    let stdout = match logs.log_stdout_format {
+        // include the fields of every span the event is in (node id, round, correlation id)
+        LogFormat::Json => fmt::layer()
+            .json()
+            .flatten_event(true)
+            .with_current_span(false)
+            .with_span_list(true)
+            .with_filter(filter)
+            .boxed(),
+        LogFormat::LogFmt => tracing_logfmt::layer().with_filter(filter).boxed(),
+        LogFormat::Terminal => fmt::layer().with_ansi(ansi).with_filter(filter).boxed(),
+    };
+
+    let mut layers = Layers::new();
+    if logs.journald {
+        layers.journald(&logs.journald_filter)?;
+    }
+    let guard = if logs.log_file_max_files > 0 {
+        let info = FileInfo::new(
+            logs.log_file_directory.clone().into(),
+            logs.log_file_max_size * MB_TO_BYTES,
+            logs.log_file_max_files,
+        );
+        Some(layers.file(logs.log_file_format, &logs.log_file_filter, info)?)
+    } else {
+        None
+    };
+
+    let mut layers = layers.into_inner();
+    layers.push(stdout);
+    Registry::default().with(layers).try_init()?;
+
+    Ok((guard, LogFilterReloader { handle, default_directive }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LogFilterReloader;
+    use reth_tracing::tracing_subscriber::{filter::LevelFilter, reload, EnvFilter};
+    use tn_rpc::{LogFilterHandle as _, TNRpcError};
+
+    #[test]
+    fn test_set_log_filter() {
+        let (layer, handle) = reload::Layer::new(EnvFilter::new("info"));
+        let reloader = LogFilterReloader { handle, default_directive: LevelFilter::INFO.into() };
+
+        reloader.set_log_filter("telcoin::node=debug,tn_primary=trace").expect("valid filter");
+        let filter = layer.handle().with_current(ToString::to_string).expect("filter set");
+        assert!(filter.contains("tn_primary=trace"));
+
+        assert!(matches!(
+            reloader.set_log_filter("tn_primary=loud"),
+            Err(TNRpcError::InvalidLogFilter(_))
+        ));
+
+        // the filter can't change once the layer is gone
+        drop(layer);
+        assert!(matches!(
+            reloader.set_log_filter("debug"),
+            Err(TNRpcError::LogFilterUnavailable(_))
+        ));
+    }
+}
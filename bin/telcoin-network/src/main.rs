@@ -1,30 +1,39 @@
-use clap::{Args, Parser as _};
+use clap::Args;
 #[cfg(feature = "faucet")]
 use tn_faucet::FaucetArgs;
-use tn_node::launch_node;
+use tn_node::{launch_node, CrashLoopError, CRASH_LOOP_EXIT_CODE};
 
 /// No Additional arguments
 #[derive(Debug, Clone, Copy, Default, Args)]
 #[non_exhaustive]
 pub struct NoArgs;
 
+/// The process exit code for an error, supervisors can tell a crash loop apart.
+fn exit_code(err: &eyre::Report) -> i32 {
+    if err.downcast_ref::<CrashLoopError>().is_some() {
+        CRASH_LOOP_EXIT_CODE
+    } else {
+        1
+    }
+}
+
 fn main() {
     #[cfg(not(feature = "faucet"))]
-    if let Err(err) = telcoin_network::cli::Cli::<NoArgs>::parse()
+    if let Err(err) = telcoin_network::cli::Cli::<NoArgs>::parse_with_env()
         .run(|builder, _, tn_datadir| launch_node(builder, tn_datadir))
     {
         eprintln!("Error: {err:?}");
-        std::process::exit(1);
+        std::process::exit(exit_code(&err));
     }
 
     #[cfg(feature = "faucet")]
-    if let Err(err) =
-        telcoin_network::cli::Cli::<FaucetArgs>::parse().run(|mut builder, faucet, tn_datadir| {
+    if let Err(err) = telcoin_network::cli::Cli::<FaucetArgs>::parse_with_env().run(
+        |mut builder, faucet, tn_datadir| {
             builder.opt_faucet_args = Some(faucet);
             launch_node(builder, tn_datadir)
-        })
-    {
+        },
+    ) {
         eprintln!("Error: {err:?}");
-        std::process::exit(1);
+        std::process::exit(exit_code(&err));
     }
 }
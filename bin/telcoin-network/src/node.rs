@@ -2,7 +2,7 @@
 //!
 //! Starts the client
 use crate::{args::clap_genesis_parser, version::SHORT_VERSION};
-use clap::{value_parser, Parser};
+use clap::{value_parser, Parser, ValueEnum};
 use core::fmt;
 use fdlimit::raise_fd_limit;
 use rayon::ThreadPoolBuilder;
@@ -18,19 +18,31 @@ use reth::{
 use reth_chainspec::ChainSpec;
 use reth_cli_commands::node::NoArgs;
 use reth_cli_util::parse_socket_address;
-use reth_db::{init_db, DatabaseEnv};
-use std::{net::SocketAddr, path::PathBuf, sync::Arc, thread::available_parallelism};
-use tn_config::{Config, ConfigFmt, ConfigTrait, TelcoinDirs as _};
+use reth_db::{test_utils::tempdir_path, DatabaseEnv};
+use std::{
+    net::SocketAddr, path::PathBuf, sync::Arc, thread::available_parallelism, time::Duration,
+};
+use tn_config::{
+    apply_env_overrides, bootnode_peer_id, Config, ConfigFmt, ConfigTrait, MiningMode,
+    TelcoinDirs as _,
+};
 use tn_node::{
     dirs::{default_datadir_args, DataDirChainPath, DataDirPath},
     engine::TnBuilder,
+    migrations::open_execution_db,
 };
+use tn_storage::StorageCipher;
+use tn_types::{Address, Multiaddr};
 use tracing::*;
 
 /// Start the node
 #[derive(Debug, Parser)]
 pub struct NodeCommand<Ext: clap::Args + fmt::Debug = NoArgs> {
     /// The path to the configuration file to use.
+    ///
+    /// The format is chosen by file extension: `.toml`, `.json`, or YAML for all others.
+    /// Values can be overridden with `TN__` environment variables, ie:
+    /// `TN__PARAMETERS__MAX_BATCH_DELAY=500ms`.
     #[arg(long, value_name = "FILE", verbatim_doc_comment)]
     pub config: Option<PathBuf>,
 
@@ -75,7 +87,11 @@ pub struct NodeCommand<Ext: clap::Args + fmt::Debug = NoArgs> {
     /// - AUTH_PORT: default + `instance` * 100 - 100
     /// - HTTP_RPC_PORT: default - `instance` + 1
     /// - WS_RPC_PORT: default + `instance` * 2 - 2
-    #[arg(long, value_name = "INSTANCE", global = true, default_value_t = 1, value_parser = value_parser!(u16).range(..=200))]
+    /// - METRICS_PORT: `--metrics` + `instance` - 1
+    /// - CONSENSUS_METRICS_PORT: `--consensus-metrics` + `instance` - 1
+    ///
+    /// Use the same `--instance` with `keytool generate` to offset the consensus network ports.
+    #[arg(long, value_name = "INSTANCE", global = true, default_value_t = 1, value_parser = value_parser!(u16).range(1..=200))]
     pub instance: u16,
 
     /// Is this an observer node?  True if set, an observer will never be in the committee
@@ -90,6 +106,162 @@ pub struct NodeCommand<Ext: clap::Args + fmt::Debug = NoArgs> {
     #[arg(long, conflicts_with = "instance", global = true)]
     pub with_unused_ports: bool,
 
+    /// Stop the node cleanly after executing this consensus sub-dag.
+    ///
+    /// The node exits without executing later output, so every validator halted at the same
+    /// sub-dag has identical state. Use this to coordinate upgrades across the committee. The
+    /// target can also be set on a running node with the `tnAdmin_haltAt` RPC method, which is
+    /// saved until the node reaches it. This flag takes precedence over a saved target.
+    #[arg(long, value_name = "SUB_DAG")]
+    pub halt_at_sub_dag: Option<u64>,
+
+    /// Run the node without persisting chain data.
+    ///
+    /// Consensus data is kept in memory and execution data is written to a reth test database in
+    /// a temporary directory that is removed when the node exits. Keys and config are still read
+    /// from the data dir. Useful for CI and local experiments.
+    #[arg(long = "dev.ephemeral", help_heading = "Dev testnet", verbatim_doc_comment)]
+    pub dev_ephemeral: bool,
+
+    /// Serve the `tn_dev` RPC namespace to mine blocks and change state on demand.
+    ///
+    /// `tn_dev_mineBlock` seals the pending transactions and proposes headers until the block
+    /// executes, for contract test suites that expect instant mining. The namespace also sets
+    /// balances and code, impersonates accounts, and reverts to snapshots like anvil. Only allowed
+    /// for single-node committees.
+    #[arg(
+        long = "dev.mining",
+        help_heading = "Dev testnet",
+        conflicts_with_all = ["rpc_replica", "observer"],
+        verbatim_doc_comment
+    )]
+    pub dev_mining: bool,
+
+    /// Accept batches from an external builder with this address.
+    ///
+    /// Authorized builders submit signed batch candidates through the `tnBuilder_submitBatch`
+    /// RPC method. The worker validates and seals them instead of building from its own pool.
+    /// Accepts a comma-separated list or can be repeated.
+    #[arg(long = "builder.authorized", value_name = "ADDRESS", value_delimiter = ',')]
+    pub authorized_builders: Vec<Address>,
+
+    /// Sign a promise for each transaction in a batch sealed by this node's worker.
+    ///
+    /// The promise is signed by the worker's network key over the transaction hash, the batch
+    /// digest, and the primary's round. Clients read it with the `tn_getInclusionPromise` RPC
+    /// method as evidence the transaction was accepted before it is committed.
+    #[arg(long = "worker.inclusion-promises", verbatim_doc_comment)]
+    pub inclusion_promises: bool,
+
+    /// When the worker seals a batch of pending transactions.
+    ///
+    /// `instant` seals as soon as transactions are pending. `interval` seals every
+    /// `--batch.interval`. `hybrid` seals every `--batch.interval`, or sooner once the pending
+    /// transactions reach `--batch.gas-target` or `--batch.size-target`. Overrides the
+    /// `mining_mode` in the config file.
+    #[arg(long = "batch.mode", value_name = "MODE", value_enum, verbatim_doc_comment)]
+    pub batch_mode: Option<BatchMode>,
+
+    /// The time between batches in `interval` and `hybrid` mode.
+    #[arg(
+        long = "batch.interval",
+        value_name = "DURATION",
+        default_value = "1s",
+        value_parser = humantime::parse_duration
+    )]
+    pub batch_interval: Duration,
+
+    /// Seal once the pending transactions' gas limits add up to this much gas in `hybrid` mode.
+    #[arg(long = "batch.gas-target", value_name = "GAS")]
+    pub batch_gas_target: Option<u64>,
+
+    /// Seal once the pending transactions add up to this many bytes in `hybrid` mode.
+    #[arg(long = "batch.size-target", value_name = "BYTES")]
+    pub batch_size_target: Option<usize>,
+
+    /// Serve RPC only, from the consensus and execution databases opened read-only.
+    ///
+    /// The node does not join the network or run consensus. Point the data dir at a snapshot
+    /// volume of another node to scale read traffic. Transactions sent to a replica are
+    /// rejected.
+    #[arg(long = "rpc.replica", conflicts_with = "dev_ephemeral", verbatim_doc_comment)]
+    pub rpc_replica: bool,
+
+    /// Serve committed sub-dags, certificates, and node status over gRPC at this address.
+    ///
+    /// Indexers subscribe to server streaming calls of the `tn.consensus.v1.ConsensusData`
+    /// service instead of polling RPC. Not available on RPC replicas.
+    #[arg(
+        long = "grpc.addr",
+        value_name = "SOCKET",
+        value_parser = parse_socket_address,
+        conflicts_with = "rpc_replica",
+        verbatim_doc_comment
+    )]
+    pub grpc: Option<SocketAddr>,
+
+    /// Trace every executed transaction and write the call frames and state changes to disk.
+    ///
+    /// Traces are appended as JSON lines to gzip files of 10,000 blocks each in `--audit.dir` for
+    /// forensic analysis of incidents. Tracing slows execution considerably.
+    #[arg(long, conflicts_with = "rpc_replica", verbatim_doc_comment)]
+    pub audit: bool,
+
+    /// The directory for audit traces.
+    ///
+    /// Defaults to `audit` in the data dir.
+    #[arg(long = "audit.dir", value_name = "PATH", requires = "audit", verbatim_doc_comment)]
+    pub audit_dir: Option<PathBuf>,
+
+    /// Index the sender and recipient of every executed transaction.
+    ///
+    /// Wallets read an address's history with the `tn_getTransactionsByAddress` RPC method
+    /// instead of running an external indexer. Only transactions executed while the index is
+    /// enabled are indexed. RPC replicas serve the index written by the node that owns the
+    /// databases.
+    #[arg(long = "index.addresses", verbatim_doc_comment)]
+    pub address_index: bool,
+
+    /// Dial these peers on the consensus network in addition to the committee.
+    ///
+    /// Each address must end with the peer's id, ie
+    /// `/ip4/10.0.0.1/udp/44894/quic-v1/p2p/<PEER_ID>`. Added to the `bootnodes` in the config
+    /// file. Observers use bootnodes to join the network and validators use them to reach peers
+    /// whose committee addresses are stale. Accepts a comma-separated list or can be repeated.
+    ///
+    /// Named `consensus.bootnodes` because `--bootnodes` configures the execution network.
+    #[arg(
+        long = "consensus.bootnodes",
+        value_name = "MULTIADDR",
+        value_delimiter = ',',
+        value_parser = parse_bootnode,
+        verbatim_doc_comment
+    )]
+    pub bootnodes: Vec<Multiaddr>,
+
+    /// Randomly delay and drop outbound network messages and slow down database commits.
+    ///
+    /// Only for staging networks, so the recovery paths are exercised continuously. Rates and
+    /// delays are read from the `chaos` section of the node config and clamped to safe bounds.
+    /// Requires a node built with the `chaos` feature.
+    #[cfg(feature = "chaos")]
+    #[arg(long)]
+    pub chaos: bool,
+
+    /// Encrypt the payload and batch tables of the consensus DB with a key derived from the secret
+    /// in this file.
+    ///
+    /// Values are encrypted with AES-256-GCM. The secret must be at least 32 bytes and should be
+    /// random, ie `openssl rand -hex 32`. Once a DB is encrypted the node must always start with
+    /// the same secret. An existing unencrypted DB can not be encrypted.
+    #[arg(
+        long = "storage.encryption-secret-file",
+        value_name = "PATH",
+        conflicts_with = "dev_ephemeral",
+        verbatim_doc_comment
+    )]
+    pub storage_encryption_secret_file: Option<PathBuf>,
+
     // TODO: this is painful to maintain
     // need a better way to overwrite reth DataDirPath
     /// The path to the data dir for all telcoin-network files and subdirectories.
@@ -177,13 +349,20 @@ impl<Ext: clap::Args + fmt::Debug> NodeCommand<Ext> {
         // TODO: use config or CLI chain spec?
         let config_path = self.config.clone().unwrap_or(tn_datadir.node_config_path());
 
-        let mut tn_config: Config = Config::load_from_path(&config_path, ConfigFmt::YAML)?;
+        let mut tn_config: Config =
+            Config::load_from_path(&config_path, ConfigFmt::from_path(&config_path))?;
+        tn_config = apply_env_overrides(tn_config)?;
         if load_config {
             // Make sure we are using the chain from config not just the default.
             self.chain = Arc::new(tn_config.chain_spec());
             info!(target: "telcoin::cli", validator = ?tn_config.validator_info.name, "config loaded");
         }
 
+        // fail before starting anything if the node or chain parameters are invalid
+        tn_config.parameters.validate()?;
+        let tn_chain_spec = tn_config.tn_chain_spec()?;
+        info!(target: "telcoin::cli", ?tn_chain_spec, "chain parameters loaded");
+
         // get the worker's transaction address from the config
         let Self {
             datadir: _, // Used above
@@ -203,13 +382,67 @@ impl<Ext: clap::Args + fmt::Debug> NodeCommand<Ext> {
             pruning,
             ext,
             observer,
+            halt_at_sub_dag,
+            dev_ephemeral,
+            dev_mining,
+            authorized_builders,
+            inclusion_promises,
+            batch_mode,
+            batch_interval,
+            batch_gas_target,
+            batch_size_target,
+            rpc_replica,
+            grpc,
+            audit,
+            audit_dir,
+            address_index,
+            bootnodes,
+            #[cfg(feature = "chaos")]
+            chaos,
+            storage_encryption_secret_file,
         } = self;
 
         tn_config.observer = observer; // Set observer mode from the config.
+        if let Some(batch_mode) = batch_mode {
+            tn_config.parameters.mining_mode =
+                batch_mode.mining_mode(batch_interval, batch_gas_target, batch_size_target)?;
+        } else {
+            eyre::ensure!(
+                batch_gas_target.is_none() && batch_size_target.is_none(),
+                "--batch.gas-target and --batch.size-target require --batch.mode hybrid"
+            );
+        }
+        for bootnode in bootnodes {
+            if !tn_config.bootnodes.contains(&bootnode) {
+                tn_config.bootnodes.push(bootnode);
+            }
+        }
+        #[cfg(feature = "chaos")]
+        if chaos {
+            tn_config.chaos.enabled = true;
+        }
+
+        // fail before starting anything if the secret can not be read
+        let storage_cipher =
+            storage_encryption_secret_file.map(StorageCipher::from_secret_file).transpose()?;
+
+        // offset metrics ports so local instances do not clash
+        let metrics = metrics.map(|socket| with_instance_port(socket, instance));
+        let consensus_metrics =
+            consensus_metrics.map(|socket| with_instance_port(socket, instance));
+        let grpc = grpc.map(|socket| with_instance_port(socket, instance));
+        let audit_dir = audit
+            .then(|| audit_dir.unwrap_or_else(|| PathBuf::from(tn_datadir.clone()).join("audit")));
+
+        // ephemeral nodes write execution data to reth's test database dir, removed after the node
+        // exits
+        let ephemeral_dir = dev_ephemeral.then(tempdir_path);
+        let execution_datadir =
+            ephemeral_dir.clone().unwrap_or_else(|| PathBuf::from(tn_datadir.clone()));
 
         // create a reth DatadirArgs from tn datadir
         let datadir = DatadirArgs {
-            datadir: MaybePlatformPath::from(PathBuf::from(tn_datadir.clone())),
+            datadir: MaybePlatformPath::from(execution_datadir.clone()),
             static_files_path: None,
         };
 
@@ -247,20 +480,85 @@ impl<Ext: clap::Args + fmt::Debug> NodeCommand<Ext> {
         // but more work is needed to ensure proper metric collection
         let _ = install_prometheus_recorder();
 
-        let db_path = tn_datadir.db();
-        info!(target: "tn::engine", path = ?db_path, "opening database");
-        let database =
-            Arc::new(init_db(db_path.clone(), node_config.db.database_args())?.with_metrics());
+        let db_path = if dev_ephemeral { execution_datadir.join("db") } else { tn_datadir.db() };
+        // replicas can not migrate another node's data
+        let database = open_execution_db(&db_path, node_config.db.database_args(), rpc_replica)?;
 
         // TODO: temporary solution until upstream reth supports public rpc hooks
         let builder = TnBuilder {
-            database,
-            node_config,
-            tn_config,
-            opt_faucet_args: None,
             consensus_metrics,
+            halt_at_sub_dag,
+            ephemeral: dev_ephemeral,
+            authorized_builders,
+            inclusion_promises,
+            rpc_replica,
+            grpc,
+            audit_dir,
+            dev_mining,
+            address_index,
+            storage_cipher,
+            software_version: env!("CARGO_PKG_VERSION").to_string(),
+            ..TnBuilder::new(database, node_config, tn_config)
         };
 
-        launcher(builder, ext, tn_datadir)
+        let result = launcher(builder, ext, tn_datadir);
+        if let Some(dir) = ephemeral_dir {
+            if let Err(e) = std::fs::remove_dir_all(&dir) {
+                warn!(target: "tn::cli", ?dir, ?e, "failed to remove ephemeral execution data");
+            }
+        }
+        result
+    }
+}
+
+/// The `--batch.mode` values.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum BatchMode {
+    /// Seal as soon as transactions are pending.
+    Instant,
+    /// Seal on an interval.
+    Interval,
+    /// Seal on an interval or once a target is reached.
+    Hybrid,
+}
+
+impl BatchMode {
+    /// The [MiningMode] for this mode and the `--batch.*` arguments.
+    pub(crate) fn mining_mode(
+        self,
+        interval: Duration,
+        gas_target: Option<u64>,
+        size_target: Option<usize>,
+    ) -> eyre::Result<MiningMode> {
+        if self != Self::Hybrid {
+            eyre::ensure!(
+                gas_target.is_none() && size_target.is_none(),
+                "--batch.gas-target and --batch.size-target require --batch.mode hybrid"
+            );
+        }
+        eyre::ensure!(!interval.is_zero(), "--batch.interval must be greater than zero");
+        Ok(match self {
+            Self::Instant => MiningMode::Instant,
+            Self::Interval => MiningMode::Interval { interval },
+            Self::Hybrid => MiningMode::Hybrid { interval, gas_target, size_target },
+        })
     }
 }
+
+/// Parse a bootnode address that ends with the peer's id.
+fn parse_bootnode(value: &str) -> eyre::Result<Multiaddr> {
+    let addr: Multiaddr = value.parse()?;
+    eyre::ensure!(
+        bootnode_peer_id(&addr).is_some(),
+        "bootnode {addr} must end with /p2p/<PEER_ID>"
+    );
+    Ok(addr)
+}
+
+/// Offset a socket's port by the node's instance.
+///
+/// Instance 1 uses the port as configured.
+fn with_instance_port(mut socket: SocketAddr, instance: u16) -> SocketAddr {
+    socket.set_port(socket.port().saturating_add(instance.saturating_sub(1)));
+    socket
+}
@@ -0,0 +1,58 @@
+//! Status command
+//!
+//! Query a running node's admin RPC and print the node's status or metrics.
+use clap::Args;
+use jsonrpsee::http_client::HttpClientBuilder;
+use std::time::Duration;
+use tn_rpc::TelcoinNetworkAdminApiClient as _;
+
+/// Query the local node's `tnAdmin` RPC namespace for the node's status.
+///
+/// The node must enable the admin module on the RPC transport (ie `--http.api admin`).
+#[derive(Debug, Args)]
+pub struct StatusArgs {
+    /// The url for the node's HTTP RPC.
+    #[arg(long, value_name = "URL", default_value = "http://127.0.0.1:8545")]
+    pub rpc_url: String,
+
+    /// Print the status as JSON instead of a human-readable format.
+    #[arg(long)]
+    pub json: bool,
+
+    /// Print a JSON snapshot of the node's Prometheus metrics instead of the status.
+    ///
+    /// Only metrics whose names start with `PREFIX` are printed, if set.
+    #[arg(long, value_name = "PREFIX", num_args = 0..=1, default_missing_value = "")]
+    pub metrics: Option<String>,
+
+    /// The amount of time to wait for the node to respond.
+    #[arg(long, value_name = "DURATION", default_value = "10s", value_parser = humantime::parse_duration)]
+    pub timeout: Duration,
+}
+
+impl StatusArgs {
+    /// Execute `status` command.
+    pub fn execute(&self) -> eyre::Result<()> {
+        let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+        runtime.block_on(async {
+            let client =
+                HttpClientBuilder::default().request_timeout(self.timeout).build(&self.rpc_url)?;
+            if let Some(prefix) = &self.metrics {
+                let prefix = (!prefix.is_empty()).then(|| prefix.clone());
+                let metrics = client.metrics_snapshot(prefix).await?;
+                println!("{}", serde_json::to_string_pretty(&metrics)?);
+                return Ok(());
+            }
+
+            let status = client.node_status().await?;
+
+            if self.json {
+                println!("{}", serde_json::to_string_pretty(&status)?);
+            } else {
+                println!("{status}");
+            }
+
+            Ok(())
+        })
+    }
+}
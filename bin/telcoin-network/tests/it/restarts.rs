@@ -128,11 +128,12 @@ fn run_restart_tests2(client_urls: &[String; 4]) -> eyre::Result<()> {
     if 20 * WEI_PER_TEL != bal {
         return Err(Report::msg(format!("Expected a balance of {} got {bal}!", 20 * WEI_PER_TEL)));
     }
-    send_tel(&client_urls[0], &key, to_account, 10 * WEI_PER_TEL, 250, 21000, 2)?;
+    let tx_hash = send_tel(&client_urls[0], &key, to_account, 10 * WEI_PER_TEL, 250, 21000, 2)?;
     let bal = get_balance_above_with_retry(&client_urls[3], &to_account.to_string(), bal)?;
     if 30 * WEI_PER_TEL != bal {
         return Err(Report::msg(format!("Expected a balance of {} got {bal}!", 30 * WEI_PER_TEL)));
     }
+    test_state_diff(&client_urls[3], &tx_hash, to_account, 20 * WEI_PER_TEL, bal)?;
     test_blocks_same(client_urls)?;
     Ok(())
 }
@@ -243,6 +244,91 @@ fn test_restarts_delayed() -> eyre::Result<()> {
     do_restarts(70)
 }
 
+/// Launch an RPC replica of a validator's data and check it follows the validator.
+#[test]
+fn test_rpc_replica() -> eyre::Result<()> {
+    let _guard = IT_TEST_MUTEX.lock();
+    init_test_tracing();
+    let tmp_guard = tempfile::TempDir::new().expect("tempdir is okay");
+    let temp_path = tmp_guard.path().to_path_buf();
+    let rt = Runtime::new()?;
+    rt.block_on(config_local_testnet(temp_path.clone())).expect("failed to config");
+    let mut exe_path =
+        PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").expect("Missing CARGO_MANIFEST_DIR!"));
+    exe_path.push("../../target/debug/telcoin-network");
+
+    let mut children = Vec::new();
+    let mut client_urls = Vec::new();
+    for i in 0..4 {
+        let rpc_port = get_available_tcp_port("127.0.0.1")
+            .expect("Failed to get an ephemeral rpc port for child!");
+        client_urls.push(format!("http://127.0.0.1:{rpc_port}"));
+        children.push(start_validator(i, &exe_path, &temp_path, rpc_port));
+    }
+    // Let the nodes start- we should consider an admin port or some way to indicate this is done.
+    std::thread::sleep(Duration::from_secs(10));
+
+    let replica_port = get_available_tcp_port("127.0.0.1")
+        .expect("Failed to get an ephemeral rpc port for the replica!");
+    let replica_url = format!("http://127.0.0.1:{replica_port}");
+    let mut replica = start_replica(&exe_path, &temp_path, replica_port);
+    let res = run_replica_tests(&client_urls[0], &replica_url);
+
+    kill_child(&mut replica);
+    for child in children.iter_mut() {
+        kill_child(child);
+    }
+    res
+}
+
+/// Send transfers through a validator and check the replica serves them.
+fn run_replica_tests(validator_url: &str, replica_url: &str) -> eyre::Result<()> {
+    let key = get_key("test-source");
+    let to_account = address_from_word("replica");
+    send_tel(validator_url, &key, to_account, 10 * WEI_PER_TEL, 250, 21000, 0)?;
+    let bal = get_positive_balance_with_retry(replica_url, &to_account.to_string())?;
+    if 10 * WEI_PER_TEL != bal {
+        return Err(Report::msg(format!("Expected a balance of {} got {bal}!", 10 * WEI_PER_TEL)));
+    }
+
+    // consensus data written after the replica started is served once it refreshes
+    send_tel(validator_url, &key, to_account, 10 * WEI_PER_TEL, 250, 21000, 1)?;
+    get_balance_above_with_retry(validator_url, &to_account.to_string(), bal)?;
+    let block = get_block(validator_url, None)?;
+    let params = RawValue::from_string(format!("[{}]", block["hash"]))?;
+    for _ in 0..30 {
+        let provenance = call_rpc(replica_url, "tn_getBlockProvenance", Some(&params), 5)?;
+        if provenance != "null" && !provenance.is_empty() {
+            return Ok(());
+        }
+        std::thread::sleep(Duration::from_secs(1));
+    }
+    Err(Report::msg(format!("Replica never served the provenance of block {}", block["hash"])))
+}
+
+/// Start a process serving RPC from the first validator's data.
+fn start_replica(exe_path: &Path, base_dir: &Path, rpc_port: u16) -> Child {
+    let data_dir = base_dir.join("validator-1");
+    let mut command = Command::new(exe_path);
+    command
+        .arg("node")
+        .arg("--datadir")
+        .arg(&*data_dir.to_string_lossy())
+        .arg("--chain")
+        .arg("adiri")
+        .arg("--rpc.replica")
+        .arg("--http")
+        .arg("--http.port")
+        .arg(format!("{rpc_port}"));
+
+    #[cfg(feature = "faucet")]
+    command
+        .arg("--public-key") // If the binary is built with the faucet need this to start...
+        .arg("0223382261d641424b8d8b63497a811c56f85ee89574f9853474c3e9ab0d690d99");
+
+    command.spawn().expect("failed to execute")
+}
+
 /// Start a process running a validator node.
 fn start_validator(instance: usize, exe_path: &Path, base_dir: &Path, mut rpc_port: u16) -> Child {
     let data_dir = base_dir.join(format!("validator-{}", instance + 1));
@@ -382,7 +468,51 @@ fn account_from_word(key_word: &str) -> (String, String, String) {
     (address.to_string(), const_hex::encode(pubkey), const_hex::encode(secret))
 }
 
+/// Check `tn_getStateDiff` for the block that executed `tx_hash` shows the balance of `account`
+/// changing from `from` to `to`.
+fn test_state_diff(
+    node: &str,
+    tx_hash: &str,
+    account: Address,
+    from: u128,
+    to: u128,
+) -> eyre::Result<()> {
+    let params = RawValue::from_string(format!("[\"{tx_hash}\"]"))?;
+    let receipt: HashMap<String, Value> =
+        serde_json::from_str(&call_rpc(node, "eth_getTransactionReceipt", Some(&params), 5)?)?;
+    let number = receipt
+        .get("blockNumber")
+        .and_then(Value::as_str)
+        .ok_or_else(|| Report::msg(format!("No receipt for {tx_hash}")))?;
+    let params = RawValue::from_string(format!("[{}]", u64::from_str_radix(&number[2..], 16)?))?;
+    let diff: Value = serde_json::from_str(&call_rpc(node, "tn_getStateDiff", Some(&params), 5)?)?;
+    let change = diff["accounts"]
+        .as_object()
+        .and_then(|accounts| {
+            accounts.iter().find(|(address, _)| address.eq_ignore_ascii_case(&account.to_string()))
+        })
+        .map(|(_, change)| change)
+        .ok_or_else(|| Report::msg(format!("State diff for block {number} misses {account}")))?;
+    let balance = |state: &Value| {
+        let balance = state["balance"].as_str().unwrap_or("0x0");
+        u128::from_str_radix(&balance[2..], 16).unwrap_or_default()
+    };
+    if balance(&change["from"]) != from || balance(&change["to"]) != to {
+        return Err(Report::msg(format!("Unexpected balance change for {account}: {change}")));
+    }
+
+    // genesis has no state diff
+    if call_rpc(node, "tn_getStateDiff", Some(&RawValue::from_string("[0]".to_string())?), 5)
+        .is_ok()
+    {
+        return Err(Report::msg("Got a state diff for genesis!".to_string()));
+    }
+    Ok(())
+}
+
 /// Create, sign and submit a TXN to transfer TEL from key's account to to_account.
+///
+/// Returns the transaction hash.
 fn send_tel(
     node: &str,
     key: &str,
@@ -391,7 +521,7 @@ fn send_tel(
     gas_price: u128,
     gas: u128,
     nonce: u128,
-) -> eyre::Result<()> {
+) -> eyre::Result<String> {
     let mut to_addr = [0_u8; 20];
     //const_hex::decode_to_slice(to_account, &mut to_addr[..])?;
     to_addr.copy_from_slice(to_account.as_slice());
@@ -413,7 +543,7 @@ fn send_tel(
     let params = RawValue::from_string(format!("[\"{}\"]", const_hex::encode(transaction_bytes)))?;
     let res_str = call_rpc(node, "eth_sendRawTransaction", Some(&params), 5)?;
     info!(target: "restart-test", "Submitted TEL transfer from {from_account} to {to_account} for {amount}: {res_str}");
-    Ok(())
+    Ok(res_str)
 }
 
 /// Decode a secret key into it's public key and account.
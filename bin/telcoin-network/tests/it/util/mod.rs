@@ -9,7 +9,9 @@ use tracing::error;
 pub static IT_TEST_MUTEX: std::sync::Mutex<()> = std::sync::Mutex::new(());
 
 /// Execute genesis ceremony inside tempdir
-pub fn create_validator_info(datadir: &str, address: &str) -> eyre::Result<()> {
+///
+/// The instance assigns the validator's consensus network ports.
+pub fn create_validator_info(datadir: &str, address: &str, instance: &str) -> eyre::Result<()> {
     // init genesis
     // Note, we speed up block times for tests.
     let init_command = CommandParser::<GenesisArgs>::parse_from([
@@ -35,6 +37,8 @@ pub fn create_validator_info(datadir: &str, address: &str) -> eyre::Result<()> {
         datadir,
         "--address",
         address,
+        "--instance",
+        instance,
     ]);
     keys_command.args.execute()?;
 
@@ -62,8 +66,9 @@ pub async fn config_local_testnet(temp_path: PathBuf) -> eyre::Result<()> {
     for (v, addr) in validators.into_iter() {
         let dir = temp_path.join(v);
         let datadir = dir.to_str().expect("validator temp dir");
+        let instance = v.chars().last().expect("validator instance").to_string();
         // init genesis ceremony to create committee / worker_cache files
-        create_validator_info(datadir, addr)?;
+        create_validator_info(datadir, addr, &instance)?;
 
         // copy to shared genesis dir
         let copy = dir.join("genesis/validators");
@@ -114,8 +119,9 @@ pub fn spawn_local_testnet(chain: Arc<ChainSpec>, contract_address: &str) -> eyr
     for v in validators.into_iter() {
         let dir = temp_path.join(v);
         let datadir = dir.to_str().expect("validator temp dir");
+        let instance = v.chars().last().expect("validator instance").to_string();
         // init genesis ceremony to create committee / worker_cache files
-        create_validator_info(datadir, "0")?;
+        create_validator_info(datadir, "0", &instance)?;
 
         // copy to shared genesis dir
         let copy = dir.join("genesis/validators");
@@ -0,0 +1,79 @@
+//! Configuration for archiving epoch summaries to an object store.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// The environment variable with the access key id for the archive.
+pub const ARCHIVE_ACCESS_KEY_ID_ENV: &str = "AWS_ACCESS_KEY_ID";
+/// The environment variable with the secret access key for the archive.
+pub const ARCHIVE_SECRET_ACCESS_KEY_ENV: &str = "AWS_SECRET_ACCESS_KEY";
+
+/// An S3 compatible object store that epoch summaries are uploaded to.
+///
+/// Objects are addressed path style, `<endpoint>/<bucket>/<prefix>/<epoch>.json`, which works with
+/// AWS and self-hosted stores. Credentials are read from [ARCHIVE_ACCESS_KEY_ID_ENV] and
+/// [ARCHIVE_SECRET_ACCESS_KEY_ENV] so they are not written to the node's config file.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArchiveConfig {
+    /// The base url of the object store, ie `https://s3.us-east-1.amazonaws.com`.
+    pub endpoint: String,
+    /// The region used to sign requests.
+    #[serde(default = "ArchiveConfig::default_region")]
+    pub region: String,
+    /// The bucket that summaries are uploaded to.
+    pub bucket: String,
+    /// The key prefix for summaries in the bucket.
+    #[serde(default = "ArchiveConfig::default_prefix")]
+    pub prefix: String,
+    /// The time to wait before uploading again after a failure.
+    #[serde(with = "humantime_serde", default = "ArchiveConfig::default_retry_interval")]
+    pub retry_interval: Duration,
+}
+
+impl ArchiveConfig {
+    /// The default signing region.
+    fn default_region() -> String {
+        "us-east-1".to_string()
+    }
+
+    /// The default key prefix.
+    fn default_prefix() -> String {
+        "epochs".to_string()
+    }
+
+    /// The default time between retries.
+    fn default_retry_interval() -> Duration {
+        Duration::from_secs(30)
+    }
+
+    /// The object key for the summary of `epoch`.
+    ///
+    /// Epochs are zero padded so keys sort in epoch order.
+    pub fn epoch_key(&self, epoch: u64) -> String {
+        let prefix = self.prefix.trim_matches('/');
+        if prefix.is_empty() {
+            format!("{epoch:020}.json")
+        } else {
+            format!("{prefix}/{epoch:020}.json")
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ArchiveConfig;
+    use std::time::Duration;
+
+    #[test]
+    fn test_archive_config_defaults() {
+        let config: ArchiveConfig =
+            serde_yaml::from_str("endpoint: http://localhost:9000\nbucket: telcoin\n")
+                .expect("config");
+        assert_eq!(config.region, "us-east-1");
+        assert_eq!(config.retry_interval, Duration::from_secs(30));
+        assert_eq!(config.epoch_key(12), "epochs/00000000000000000012.json");
+
+        let config = ArchiveConfig { prefix: "/".to_string(), ..config };
+        assert_eq!(config.epoch_key(12), "00000000000000000012.json");
+    }
+}
@@ -0,0 +1,332 @@
+//! Telcoin Network parameters in the chain spec.
+//!
+//! Parameters every node must agree on are stored in the genesis `config` object under
+//! [TN_CHAIN_SPEC_FIELD], so the genesis file is the only source for them. Missing fields use the
+//! values the network launched with.
+
+use crate::{GOVERNANCE_MAX_BATCH_GAS_SLOT, GOVERNED_MAX_BATCH_GAS};
+use eyre::{ensure, Context};
+use serde::{Deserialize, Serialize};
+use tn_types::{
+    max_batch_gas, Address, BatchGasSchedule, Epoch, Forks, Genesis, GovernedBatchGas, SystemCall,
+    TimestampPolicy, DEFAULT_BAD_NODES_STAKE_THRESHOLD, DEFAULT_SUB_DAGS_PER_SCHEDULE,
+    MAX_BAD_NODES_STAKE_THRESHOLD, MAX_SYSTEM_CALL_GAS,
+};
+
+/// The field in the genesis `config` object that holds the [TnChainSpec].
+pub const TN_CHAIN_SPEC_FIELD: &str = "telcoin";
+
+/// Telcoin Network parameters shared by every node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TnChainSpec {
+    /// The number of consensus outputs in each epoch.
+    pub epoch_length: u64,
+    /// The maximum amount of gas for transactions in a batch.
+    ///
+    /// Also the gas limit of executed blocks, unless the governance contract sets one.
+    pub max_batch_gas: u64,
+    /// Where governed consensus parameters are read from.
+    pub parameter_source: ParameterSource,
+    /// How executed blocks are timestamped from the committed sub-dag.
+    ///
+    /// Blocks use the commit timestamp until [Forks::timestamp_policy] activates the policy.
+    pub timestamp_policy: TimestampPolicy,
+    /// How the leader schedule adapts to reputation scores.
+    ///
+    /// Consensus uses the defaults until [Forks::leader_schedule] activates these parameters, see
+    /// [Self::leader_schedule_at].
+    pub leader_schedule: LeaderScheduleParameters,
+    /// Contract calls made at the start or end of every committed sub-dag, in order.
+    pub system_calls: Vec<SystemCall>,
+    /// The epochs that activate protocol changes.
+    pub forks: Forks,
+    /// Workers seal an empty batch every max batch delay while their transaction pool is idle.
+    ///
+    /// Peers reject empty batches unless this is set, so networks that want a steady block
+    /// cadence enable it in genesis.
+    pub seal_empty_batches: bool,
+}
+
+impl TnChainSpec {
+    /// The default number of consensus outputs in each epoch.
+    pub const DEFAULT_EPOCH_LENGTH: u64 = 86_400;
+
+    /// Read the parameters from genesis.
+    ///
+    /// Returns the defaults if genesis doesn't contain the field.
+    pub fn from_genesis(genesis: &Genesis) -> eyre::Result<Self> {
+        let spec = match genesis.config.extra_fields.get_deserialized::<Self>(TN_CHAIN_SPEC_FIELD) {
+            Some(spec) => spec.context("invalid telcoin chain spec in genesis")?,
+            None => Self::default(),
+        };
+        spec.validate()?;
+        Ok(spec)
+    }
+
+    /// Write the parameters to genesis, replacing any existing parameters.
+    pub fn write_to_genesis(&self, genesis: &mut Genesis) -> eyre::Result<()> {
+        self.validate()?;
+        genesis.config.extra_fields.insert_value(TN_CHAIN_SPEC_FIELD.to_string(), self)?;
+        Ok(())
+    }
+
+    /// Ensure the parameters are usable.
+    pub fn validate(&self) -> eyre::Result<()> {
+        ensure!(self.epoch_length > 0, "epoch length must be greater than zero");
+        ensure!(self.max_batch_gas > 0, "max batch gas must be greater than zero");
+        self.leader_schedule.validate()?;
+        for call in self.system_calls.iter() {
+            ensure!(
+                call.gas_limit > 0 && call.gas_limit <= MAX_SYSTEM_CALL_GAS,
+                "system call to {} must have a gas limit from 1 to {MAX_SYSTEM_CALL_GAS}",
+                call.address
+            );
+        }
+        Ok(())
+    }
+
+    /// The last consensus output of the epoch before `output`'s epoch.
+    ///
+    /// Returns `None` during the first epoch.
+    pub fn epoch_boundary(&self, output: u64) -> Option<u64> {
+        let epoch = output / self.epoch_length.max(1);
+        (epoch > 0).then(|| epoch * self.epoch_length - 1)
+    }
+
+    /// True if the chain spec's leader schedule parameters apply to consensus output `output`.
+    pub fn leader_schedule_active(&self, output: u64) -> bool {
+        let epoch = output / self.epoch_length.max(1);
+        self.forks.leader_schedule_active(epoch.try_into().unwrap_or(Epoch::MAX))
+    }
+
+    /// The leader schedule parameters for consensus output `output`.
+    ///
+    /// Every node must use the same parameters, so they only change at the epoch boundary
+    /// [Forks::leader_schedule] activates.
+    pub fn leader_schedule_at(&self, output: u64) -> LeaderScheduleParameters {
+        if self.leader_schedule_active(output) {
+            self.leader_schedule
+        } else {
+            LeaderScheduleParameters::default()
+        }
+    }
+
+    /// How the executor sets the gas limit of executed blocks once [Forks::batch_gas] activates.
+    pub fn batch_gas_schedule(&self) -> BatchGasSchedule {
+        let governance = match self.parameter_source {
+            ParameterSource::Local => None,
+            ParameterSource::Governance { address } => Some(GovernedBatchGas {
+                address,
+                slot: GOVERNANCE_MAX_BATCH_GAS_SLOT,
+                min: *GOVERNED_MAX_BATCH_GAS.start(),
+                max: *GOVERNED_MAX_BATCH_GAS.end(),
+            }),
+        };
+        BatchGasSchedule {
+            max_batch_gas: self.max_batch_gas,
+            governance,
+            epoch_length: self.epoch_length,
+        }
+    }
+}
+
+impl Default for TnChainSpec {
+    fn default() -> Self {
+        Self {
+            epoch_length: Self::DEFAULT_EPOCH_LENGTH,
+            max_batch_gas: max_batch_gas(0),
+            parameter_source: ParameterSource::default(),
+            timestamp_policy: TimestampPolicy::default(),
+            leader_schedule: LeaderScheduleParameters::default(),
+            system_calls: Vec::new(),
+            forks: Forks::default(),
+            seal_empty_batches: false,
+        }
+    }
+}
+
+/// Where governed consensus parameters are read from.
+///
+/// See [crate::GovernedParameters].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum ParameterSource {
+    /// Every node uses its local parameters.
+    #[default]
+    Local,
+    /// Parameters set in the governance contract override local parameters from the start of the
+    /// epoch after the next one.
+    Governance {
+        /// The address of the governance contract.
+        address: Address,
+    },
+}
+
+/// How the leader schedule adapts to reputation scores.
+///
+/// At the end of each schedule the authorities with the lowest reputation scores, up to
+/// `bad_nodes_stake_threshold` percent of the stake, are replaced as leaders by the authorities
+/// with the highest scores.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LeaderScheduleParameters {
+    /// The percentage of stake swapped out of the leader schedule, from 0 to 33.
+    pub bad_nodes_stake_threshold: u64,
+    /// The number of committed sub-dags in each schedule.
+    pub sub_dags_per_schedule: u32,
+}
+
+impl LeaderScheduleParameters {
+    /// Ensure the parameters are usable.
+    pub fn validate(&self) -> eyre::Result<()> {
+        ensure!(
+            self.bad_nodes_stake_threshold <= MAX_BAD_NODES_STAKE_THRESHOLD,
+            "bad nodes stake threshold must be at most {MAX_BAD_NODES_STAKE_THRESHOLD}"
+        );
+        ensure!(self.sub_dags_per_schedule > 0, "sub-dags per schedule must be greater than zero");
+        Ok(())
+    }
+}
+
+impl Default for LeaderScheduleParameters {
+    fn default() -> Self {
+        Self {
+            bad_nodes_stake_threshold: DEFAULT_BAD_NODES_STAKE_THRESHOLD,
+            sub_dags_per_schedule: DEFAULT_SUB_DAGS_PER_SCHEDULE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{LeaderScheduleParameters, ParameterSource, TnChainSpec};
+    use crate::GOVERNANCE_MAX_BATCH_GAS_SLOT;
+    use tn_types::{adiri_genesis, Address, Forks, SystemCall, SystemCallPhase, TimestampPolicy};
+
+    #[test]
+    fn test_tn_chain_spec_roundtrip() {
+        let mut genesis = adiri_genesis();
+        assert_eq!(TnChainSpec::from_genesis(&genesis).expect("defaults"), TnChainSpec::default());
+
+        let spec = TnChainSpec {
+            epoch_length: 100,
+            parameter_source: ParameterSource::Governance { address: Address::random() },
+            timestamp_policy: TimestampPolicy::StrictlyIncreasing,
+            leader_schedule: LeaderScheduleParameters {
+                bad_nodes_stake_threshold: 20,
+                sub_dags_per_schedule: 100,
+            },
+            system_calls: vec![SystemCall {
+                address: Address::random(),
+                selector: [1, 2, 3, 4].into(),
+                phase: SystemCallPhase::SubDagEnd,
+                gas_limit: 1_000_000,
+            }],
+            forks: Forks {
+                payload_root: Some(5),
+                timestamp_policy: Some(7),
+                leader_schedule: Some(9),
+                batch_gas: Some(11),
+            },
+            seal_empty_batches: true,
+            ..Default::default()
+        };
+        spec.write_to_genesis(&mut genesis).expect("spec written");
+
+        // the spec survives serializing genesis
+        let genesis = serde_json::from_str(&serde_json::to_string(&genesis).expect("serialize"))
+            .expect("deserialize");
+        assert_eq!(TnChainSpec::from_genesis(&genesis).expect("spec read"), spec);
+    }
+
+    #[test]
+    fn test_tn_chain_spec_partial_and_invalid() {
+        let mut genesis = adiri_genesis();
+        genesis
+            .config
+            .extra_fields
+            .insert_value("telcoin".to_string(), serde_json::json!({ "epochLength": 10 }))
+            .expect("field inserted");
+        let spec = TnChainSpec::from_genesis(&genesis).expect("partial spec");
+        assert_eq!(spec, TnChainSpec { epoch_length: 10, ..Default::default() });
+        assert_eq!(spec.timestamp_policy, TimestampPolicy::CommitTimestamp);
+
+        genesis
+            .config
+            .extra_fields
+            .insert_value("telcoin".to_string(), serde_json::json!({ "epochLength": 0 }))
+            .expect("field inserted");
+        assert!(TnChainSpec::from_genesis(&genesis).is_err());
+
+        // the leader schedule is validated
+        for leader_schedule in [
+            serde_json::json!({ "badNodesStakeThreshold": 34 }),
+            serde_json::json!({ "subDagsPerSchedule": 0 }),
+        ] {
+            genesis
+                .config
+                .extra_fields
+                .insert_value(
+                    "telcoin".to_string(),
+                    serde_json::json!({ "leaderSchedule": leader_schedule }),
+                )
+                .expect("field inserted");
+            assert!(TnChainSpec::from_genesis(&genesis).is_err());
+        }
+
+        // system calls need gas
+        let call = serde_json::json!({
+            "address": Address::ZERO,
+            "selector": "0x01020304",
+            "phase": "subDagStart",
+            "gasLimit": 0,
+        });
+        genesis
+            .config
+            .extra_fields
+            .insert_value("telcoin".to_string(), serde_json::json!({ "systemCalls": [call] }))
+            .expect("field inserted");
+        assert!(TnChainSpec::from_genesis(&genesis).is_err());
+    }
+
+    #[test]
+    fn test_epoch_boundary() {
+        let spec = TnChainSpec { epoch_length: 100, ..Default::default() };
+        assert_eq!(spec.epoch_boundary(0), None);
+        assert_eq!(spec.epoch_boundary(99), None);
+        assert_eq!(spec.epoch_boundary(100), Some(99));
+        assert_eq!(spec.epoch_boundary(250), Some(199));
+    }
+
+    #[test]
+    fn test_leader_schedule_at() {
+        let leader_schedule =
+            LeaderScheduleParameters { bad_nodes_stake_threshold: 20, sub_dags_per_schedule: 100 };
+        let spec = TnChainSpec { epoch_length: 100, leader_schedule, ..Default::default() };
+        assert_eq!(spec.leader_schedule_at(1_000), LeaderScheduleParameters::default());
+
+        // the parameters switch at the first output of the activation epoch
+        let spec = TnChainSpec { forks: Forks { leader_schedule: Some(2), ..Forks::NONE }, ..spec };
+        assert_eq!(spec.leader_schedule_at(199), LeaderScheduleParameters::default());
+        assert_eq!(spec.leader_schedule_at(200), leader_schedule);
+    }
+
+    #[test]
+    fn test_batch_gas_schedule() {
+        let spec = TnChainSpec { max_batch_gas: 20_000_000, ..Default::default() };
+        assert_eq!(spec.batch_gas_schedule().max_batch_gas, 20_000_000);
+        assert_eq!(spec.batch_gas_schedule().governance, None);
+
+        let address = Address::random();
+        let spec = TnChainSpec {
+            epoch_length: 100,
+            parameter_source: ParameterSource::Governance { address },
+            ..Default::default()
+        };
+        let governance = spec.batch_gas_schedule().governance.expect("governed");
+        assert_eq!(governance.address, address);
+        assert_eq!(governance.slot, GOVERNANCE_MAX_BATCH_GAS_SLOT);
+        assert_eq!(spec.batch_gas_schedule().epoch_length, 100);
+    }
+}
@@ -0,0 +1,124 @@
+//! Configuration for fault injection on staging networks.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Randomly disrupts the node so staging networks continuously exercise recovery paths.
+///
+/// When enabled, outbound network messages are delayed and a fraction of them dropped, and some
+/// database commits are slowed down. Values are clamped to [ChaosConfig::bounded] so a
+/// misconfigured node degrades but never stalls the committee.
+///
+/// Only nodes built with the `chaos` feature can enable it, others refuse to start. This must
+/// never be enabled on a production network.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChaosConfig {
+    /// Inject faults. Disabled by default.
+    #[serde(default)]
+    pub enabled: bool,
+    /// The largest random delay added to an outbound network message.
+    #[serde(with = "humantime_serde", default = "ChaosConfig::default_max_message_delay")]
+    pub max_message_delay: Duration,
+    /// The fraction of outbound network messages dropped.
+    #[serde(default = "ChaosConfig::default_message_drop_rate")]
+    pub message_drop_rate: f64,
+    /// The fraction of database commits that are slowed down.
+    #[serde(default = "ChaosConfig::default_slow_commit_rate")]
+    pub slow_commit_rate: f64,
+    /// The largest random delay added to a slow database commit.
+    #[serde(with = "humantime_serde", default = "ChaosConfig::default_max_commit_delay")]
+    pub max_commit_delay: Duration,
+}
+
+impl ChaosConfig {
+    /// The largest allowed message delay.
+    pub const MAX_MESSAGE_DELAY: Duration = Duration::from_secs(2);
+    /// The largest allowed message drop rate.
+    pub const MAX_MESSAGE_DROP_RATE: f64 = 0.1;
+    /// The largest allowed commit delay.
+    pub const MAX_COMMIT_DELAY: Duration = Duration::from_secs(1);
+
+    /// The config with every value clamped to safe bounds, or `None` if chaos is disabled.
+    pub fn bounded(&self) -> Option<Self> {
+        if !self.enabled {
+            return None;
+        }
+
+        Some(Self {
+            enabled: true,
+            max_message_delay: self.max_message_delay.min(Self::MAX_MESSAGE_DELAY),
+            message_drop_rate: clamp_rate(self.message_drop_rate, Self::MAX_MESSAGE_DROP_RATE),
+            slow_commit_rate: clamp_rate(self.slow_commit_rate, 1.0),
+            max_commit_delay: self.max_commit_delay.min(Self::MAX_COMMIT_DELAY),
+        })
+    }
+
+    /// The default largest message delay.
+    fn default_max_message_delay() -> Duration {
+        Duration::from_millis(200)
+    }
+
+    /// The default message drop rate.
+    fn default_message_drop_rate() -> f64 {
+        0.01
+    }
+
+    /// The default slow commit rate.
+    fn default_slow_commit_rate() -> f64 {
+        0.05
+    }
+
+    /// The default largest commit delay.
+    fn default_max_commit_delay() -> Duration {
+        Duration::from_millis(100)
+    }
+}
+
+impl Default for ChaosConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_message_delay: Self::default_max_message_delay(),
+            message_drop_rate: Self::default_message_drop_rate(),
+            slow_commit_rate: Self::default_slow_commit_rate(),
+            max_commit_delay: Self::default_max_commit_delay(),
+        }
+    }
+}
+
+/// Clamp a rate to `0..=max`, treating NaN as zero.
+fn clamp_rate(rate: f64, max: f64) -> f64 {
+    if rate.is_nan() {
+        0.0
+    } else {
+        rate.clamp(0.0, max)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ChaosConfig;
+    use std::time::Duration;
+
+    #[test]
+    fn test_chaos_config_bounds() {
+        let config: ChaosConfig =
+            serde_yaml::from_str("message_drop_rate: 0.05\n").expect("config");
+        assert!(!config.enabled);
+        assert_eq!(config.message_drop_rate, 0.05);
+        assert_eq!(config.bounded(), None);
+
+        let config = ChaosConfig {
+            enabled: true,
+            max_message_delay: Duration::from_secs(30),
+            message_drop_rate: 0.9,
+            slow_commit_rate: f64::NAN,
+            max_commit_delay: Duration::from_millis(10),
+        };
+        let bounded = config.bounded().expect("enabled");
+        assert_eq!(bounded.max_message_delay, ChaosConfig::MAX_MESSAGE_DELAY);
+        assert_eq!(bounded.message_drop_rate, ChaosConfig::MAX_MESSAGE_DROP_RATE);
+        assert_eq!(bounded.slow_commit_rate, 0.0);
+        assert_eq!(bounded.max_commit_delay, Duration::from_millis(10));
+    }
+}
@@ -0,0 +1,80 @@
+//! Configuration for monitoring the local clock.
+
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// Compares the local clock with NTP servers.
+///
+/// Headers and batches are timestamped with the local clock, so a node whose clock runs ahead of
+/// the committee produces headers that peers wait on, and a node that runs behind sleeps before
+/// proposing. The offset is reported as a metric and an error is logged when it exceeds
+/// `max_skew`.
+///
+/// Monitoring is opt-in, nodes only send NTP queries if `ntp_servers` is set.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClockConfig {
+    /// The NTP servers to query, as `host:port`.
+    ///
+    /// Servers are tried in order until one responds, ie `pool.ntp.org:123`. Monitoring is
+    /// disabled if empty.
+    #[serde(default)]
+    pub ntp_servers: Vec<String>,
+    /// The time between checks.
+    #[serde(with = "humantime_serde", default = "ClockConfig::default_check_interval")]
+    pub check_interval: Duration,
+    /// The time to wait for a server to respond.
+    #[serde(with = "humantime_serde", default = "ClockConfig::default_query_timeout")]
+    pub query_timeout: Duration,
+    /// The largest offset from NTP time that is not reported as an error.
+    #[serde(with = "humantime_serde", default = "ClockConfig::default_max_skew")]
+    pub max_skew: Duration,
+}
+
+impl ClockConfig {
+    /// The default time between checks.
+    fn default_check_interval() -> Duration {
+        Duration::from_secs(300)
+    }
+
+    /// The default time to wait for a server.
+    fn default_query_timeout() -> Duration {
+        Duration::from_secs(5)
+    }
+
+    /// The default largest offset.
+    ///
+    /// Header timestamps have a resolution of one second.
+    fn default_max_skew() -> Duration {
+        Duration::from_millis(500)
+    }
+}
+
+impl Default for ClockConfig {
+    fn default() -> Self {
+        Self {
+            ntp_servers: Vec::new(),
+            check_interval: Self::default_check_interval(),
+            query_timeout: Self::default_query_timeout(),
+            max_skew: Self::default_max_skew(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ClockConfig;
+    use std::time::Duration;
+
+    #[test]
+    fn test_clock_config_defaults() {
+        let config: ClockConfig = serde_yaml::from_str("max_skew: 2s\n").expect("config");
+        assert_eq!(config.max_skew, Duration::from_secs(2));
+        assert_eq!(config, ClockConfig { max_skew: Duration::from_secs(2), ..Default::default() });
+
+        assert!(config.ntp_servers.is_empty());
+
+        let config: ClockConfig =
+            serde_yaml::from_str("ntp_servers: [pool.ntp.org:123]\n").expect("config");
+        assert_eq!(config.ntp_servers, ["pool.ntp.org:123"]);
+    }
+}
@@ -2,16 +2,22 @@
 use crate::{
     Config, ConfigFmt, ConfigTrait as _, KeyConfig, NetworkConfig, Parameters, TelcoinDirs,
 };
+use eyre::ensure;
 use libp2p::PeerId;
+use parking_lot::RwLock;
 use std::{
     collections::{HashMap, HashSet},
+    path::PathBuf,
     sync::Arc,
+    time::Duration,
 };
 use tn_network_types::local::LocalNetwork;
+use tn_storage::{PeerLatencyStore as _, PEER_LATENCY_TTL};
 use tn_types::{
     Authority, AuthorityIdentifier, Certificate, CertificateDigest, Committee, Database, Hash as _,
-    Multiaddr, Notifier, WorkerCache, WorkerId,
+    Multiaddr, Notifier, SharedClock, SystemClock, WorkerCache, WorkerId, WorkerInfoUpdate,
 };
+use tokio::sync::watch;
 
 #[derive(Debug)]
 struct ConsensusConfigInner<DB> {
@@ -23,13 +29,26 @@ struct ConsensusConfigInner<DB> {
     local_network: LocalNetwork,
     network_config: NetworkConfig,
     genesis: HashMap<CertificateDigest, Certificate>,
+    /// The current worker cache.
+    ///
+    /// Updated at runtime when authorities announce changes to their workers.
+    worker_cache: watch::Sender<WorkerCache>,
+    /// The file the worker cache is persisted to after updates.
+    ///
+    /// Updates are only kept in memory if this is `None`.
+    worker_cache_path: Option<PathBuf>,
+    /// The committee member running each worker in the worker cache.
+    ///
+    /// Rebuilt with the worker cache.
+    worker_authorities: RwLock<HashMap<PeerId, AuthorityIdentifier>>,
 }
 
 #[derive(Debug, Clone)]
 pub struct ConsensusConfig<DB> {
     inner: Arc<ConsensusConfigInner<DB>>,
-    worker_cache: WorkerCache,
     shutdown: Notifier,
+    /// The time source for header and batch timestamps.
+    clock: SharedClock,
 }
 
 impl<DB> ConsensusConfig<DB>
@@ -68,7 +87,14 @@ where
         );
 
         tracing::info!(target: "telcoin::consensus_config", "worker cache loaded");
-        Self::new_with_committee(config, node_storage, key_config, committee, worker_cache)
+        Self::new_with_committee(
+            config,
+            node_storage,
+            key_config,
+            committee,
+            worker_cache,
+            Some(tn_datadir.worker_cache_path()),
+        )
     }
 
     /// Create a new config with a committe.
@@ -81,7 +107,7 @@ where
         committee: Committee,
         worker_cache: WorkerCache,
     ) -> eyre::Result<Self> {
-        Self::new_with_committee(config, node_storage, key_config, committee, worker_cache)
+        Self::new_with_committee(config, node_storage, key_config, committee, worker_cache, None)
     }
 
     /// Create a new config with a committe.
@@ -93,6 +119,7 @@ where
         key_config: KeyConfig,
         committee: Committee,
         worker_cache: WorkerCache,
+        worker_cache_path: Option<PathBuf>,
     ) -> eyre::Result<Self> {
         let local_network =
             LocalNetwork::new_from_public_key(&key_config.primary_network_public_key());
@@ -111,6 +138,7 @@ where
             .into_iter()
             .map(|cert| (cert.digest(), cert))
             .collect();
+        let worker_authorities = RwLock::new(worker_authorities(&committee, &worker_cache));
 
         Ok(Self {
             inner: Arc::new(ConsensusConfigInner {
@@ -122,12 +150,26 @@ where
                 local_network,
                 network_config,
                 genesis,
+                worker_cache: watch::Sender::new(worker_cache),
+                worker_cache_path,
+                worker_authorities,
             }),
-            worker_cache,
             shutdown,
+            clock: SystemClock::shared(),
         })
     }
 
+    /// Replace the time source used for header and batch timestamps.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// The time source for header and batch timestamps.
+    pub fn clock(&self) -> &SharedClock {
+        &self.clock
+    }
+
     /// Returns a reference to the shutdown Noticer.
     /// Can use this subscribe to the shutdown event or send it.
     pub fn shutdown(&self) -> &Notifier {
@@ -146,12 +188,45 @@ where
         &self.inner.committee
     }
 
-    pub fn worker_cache(&self) -> &WorkerCache {
-        &self.worker_cache
+    /// The current worker cache.
+    pub fn worker_cache(&self) -> WorkerCache {
+        self.inner.worker_cache.borrow().clone()
     }
 
     pub fn worker_cache_clone(&self) -> WorkerCache {
-        self.worker_cache.clone()
+        self.worker_cache()
+    }
+
+    /// Subscribe to changes to the worker cache.
+    pub fn subscribe_worker_cache(&self) -> watch::Receiver<WorkerCache> {
+        self.inner.worker_cache.subscribe()
+    }
+
+    /// Apply a signed [WorkerInfoUpdate] from a committee member.
+    ///
+    /// The updated worker cache is persisted before subscribers are notified. The cache is
+    /// unchanged if the update is invalid or can't be persisted.
+    pub fn update_worker_cache(&self, update: &WorkerInfoUpdate) -> eyre::Result<()> {
+        ensure!(
+            self.inner.committee.authority_by_key(&update.bls_public_key).is_some(),
+            "worker info update from authority outside the committee"
+        );
+
+        let mut res: eyre::Result<()> = Ok(());
+        self.inner.worker_cache.send_if_modified(|worker_cache| {
+            res =
+                worker_cache.apply_update(update).map_err(eyre::Report::from).and_then(|updated| {
+                    if let Some(path) = &self.inner.worker_cache_path {
+                        Config::store_path_atomic(path, &updated, ConfigFmt::YAML)?;
+                    }
+                    *self.inner.worker_authorities.write() =
+                        worker_authorities(&self.inner.committee, &updated);
+                    *worker_cache = updated;
+                    Ok(())
+                });
+            res.is_ok()
+        });
+        res
     }
 
     pub fn node_storage(&self) -> &DB {
@@ -187,6 +262,31 @@ where
         self.inner.committee.is_authority(id)
     }
 
+    /// Return the committee member running the worker with this peer id.
+    pub fn worker_authority(&self, peer_id: &PeerId) -> Option<AuthorityIdentifier> {
+        self.inner.worker_authorities.read().get(peer_id).cloned()
+    }
+
+    /// The round trip times to other authorities sampled by the worker's latency probes.
+    ///
+    /// The times are persisted, so they are known before any peer is connected. Each failed ping
+    /// or dial since an authority was sampled doubles its time. Authorities that were never
+    /// sampled, or not within [PEER_LATENCY_TTL], are missing.
+    pub fn authority_latencies(&self) -> HashMap<AuthorityIdentifier, Duration> {
+        let now = self.clock.now();
+        self.node_storage().read_peer_latencies(now, PEER_LATENCY_TTL).unwrap_or_else(|error| {
+            tracing::warn!(target: "telcoin::consensus_config", ?error, "failed to read peer latencies");
+            HashMap::new()
+        })
+    }
+
+    /// Record a failed ping or dial to the authority, see [Self::authority_latencies].
+    pub fn record_authority_failure(&self, authority: &AuthorityIdentifier) {
+        if let Err(error) = self.node_storage().record_peer_failure(authority) {
+            tracing::warn!(target: "telcoin::consensus_config", ?error, %authority, "failed to record peer failure");
+        }
+    }
+
     /// Retrieve the worker's network address by id.
     /// Note, will panic if id is not valid (not found in our worker cache).
     pub fn worker_address(&self, id: &WorkerId) -> Multiaddr {
@@ -196,3 +296,18 @@ where
             .worker_address
     }
 }
+
+/// Map the peer id of each worker in the cache to the committee member running it.
+fn worker_authorities(
+    committee: &Committee,
+    worker_cache: &WorkerCache,
+) -> HashMap<PeerId, AuthorityIdentifier> {
+    committee
+        .authorities()
+        .into_iter()
+        .flat_map(|authority| {
+            let workers = worker_cache.our_workers(authority.protocol_key()).unwrap_or_default();
+            workers.into_iter().map(move |worker| (worker.name.to_peer_id(), authority.id()))
+        })
+        .collect()
+}
@@ -0,0 +1,256 @@
+//! Token distributions allocated at genesis.
+//!
+//! A distribution file lists the accounts funded at genesis. Allocations with a vesting schedule
+//! are deployed as vesting contracts holding the tokens for the beneficiary. Distributions are
+//! either JSON (a list of [Allocation]) or CSV with the columns
+//! `address,amount[,start,cliff,duration]`.
+//!
+//! Vesting contracts are placed at addresses derived from the allocation's position in the file and
+//! the beneficiary, so the same file always produces the same genesis.
+
+use crate::ContractStandardJson;
+use eyre::{ensure, eyre, Context};
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, ffi::OsStr, fs, path::Path};
+use tn_types::{hex, keccak256, Address, Bytes, GenesisAccount, B256, U256};
+
+/// The storage slot of the vesting contract's beneficiary.
+pub const VESTING_BENEFICIARY_SLOT: B256 = B256::ZERO;
+/// The storage slot of the unix timestamp (seconds) when vesting starts.
+pub const VESTING_START_SLOT: B256 = B256::with_last_byte(1);
+/// The storage slot of the number of seconds after the start before any tokens vest.
+pub const VESTING_CLIFF_SLOT: B256 = B256::with_last_byte(2);
+/// The storage slot of the number of seconds until all tokens are vested.
+pub const VESTING_DURATION_SLOT: B256 = B256::with_last_byte(3);
+
+/// Linear vesting for an allocation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VestingSchedule {
+    /// The unix timestamp (seconds) when vesting starts.
+    pub start: u64,
+    /// The number of seconds after `start` before any tokens vest.
+    pub cliff: u64,
+    /// The number of seconds after `start` until all tokens are vested.
+    pub duration: u64,
+}
+
+impl VestingSchedule {
+    /// The vesting contract's storage for this schedule.
+    ///
+    /// Vesting contracts must use the layout described by the `VESTING_*_SLOT` constants.
+    fn storage(&self, beneficiary: Address) -> BTreeMap<B256, B256> {
+        BTreeMap::from([
+            (VESTING_BENEFICIARY_SLOT, beneficiary.into_word()),
+            (VESTING_START_SLOT, U256::from(self.start).into()),
+            (VESTING_CLIFF_SLOT, U256::from(self.cliff).into()),
+            (VESTING_DURATION_SLOT, U256::from(self.duration).into()),
+        ])
+    }
+}
+
+/// An account funded at genesis.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Allocation {
+    /// The account receiving the tokens.
+    ///
+    /// This is the beneficiary if the allocation vests.
+    pub address: Address,
+    /// The amount in wei.
+    pub amount: U256,
+    /// The vesting schedule, if the tokens are locked in a vesting contract.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vesting: Option<VestingSchedule>,
+}
+
+/// The accounts funded at genesis.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Distribution {
+    /// The allocations in the order they appear in the distribution file.
+    pub allocations: Vec<Allocation>,
+}
+
+impl Distribution {
+    /// Read a distribution file.
+    ///
+    /// Files with a `.csv` extension are parsed as CSV, everything else as JSON.
+    pub fn load_from_path(path: &Path) -> eyre::Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("failed to read distribution {}", path.display()))?;
+        if path.extension().and_then(OsStr::to_str) == Some("csv") {
+            Self::from_csv(&contents)
+        } else {
+            serde_json::from_str(&contents)
+                .with_context(|| format!("failed to parse distribution {}", path.display()))
+        }
+    }
+
+    /// Parse a CSV distribution.
+    ///
+    /// An optional header row starting with `address` is skipped, as are blank lines and lines
+    /// starting with `#`. The vesting columns are either all empty or all set.
+    pub fn from_csv(contents: &str) -> eyre::Result<Self> {
+        let mut allocations = Vec::new();
+        for (index, line) in contents.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with("address") {
+                continue;
+            }
+
+            let allocation = parse_csv_row(line)
+                .with_context(|| format!("invalid row on line {}", index + 1))?;
+            allocations.push(allocation);
+        }
+
+        Ok(Self { allocations })
+    }
+
+    /// The total amount allocated in wei, including vesting allocations.
+    pub fn total(&self) -> U256 {
+        self.allocations
+            .iter()
+            .fold(U256::ZERO, |total, allocation| total.saturating_add(allocation.amount))
+    }
+
+    /// Return the genesis accounts for the distribution.
+    ///
+    /// `vesting_contract` is the deployed bytecode for vesting contracts and is required if any
+    /// allocation vests. Accounts that appear more than once are rejected.
+    pub fn genesis_accounts(
+        &self,
+        vesting_contract: Option<&Bytes>,
+    ) -> eyre::Result<BTreeMap<Address, GenesisAccount>> {
+        let mut accounts = BTreeMap::new();
+        for (index, allocation) in self.allocations.iter().enumerate() {
+            ensure!(!allocation.amount.is_zero(), "allocation {index} has no tokens");
+
+            let (address, account) = match allocation.vesting {
+                Some(vesting) => {
+                    ensure!(
+                        vesting.cliff <= vesting.duration,
+                        "allocation {index} has a cliff after the end of vesting"
+                    );
+                    let code = vesting_contract.ok_or_else(|| {
+                        eyre!("allocation {index} vests but no vesting contract was provided")
+                    })?;
+                    let account = GenesisAccount::default()
+                        .with_balance(allocation.amount)
+                        .with_code(Some(code.clone()))
+                        .with_storage(Some(vesting.storage(allocation.address)));
+                    (vesting_contract_address(index, allocation.address), account)
+                }
+                None => {
+                    (allocation.address, GenesisAccount::default().with_balance(allocation.amount))
+                }
+            };
+
+            ensure!(
+                accounts.insert(address, account).is_none(),
+                "allocation {index} funds {address} more than once"
+            );
+        }
+
+        Ok(accounts)
+    }
+}
+
+/// The address of the vesting contract for the allocation at `index`.
+pub fn vesting_contract_address(index: usize, beneficiary: Address) -> Address {
+    let mut preimage = b"TN_VESTING".to_vec();
+    preimage.extend_from_slice(&(index as u64).to_be_bytes());
+    preimage.extend_from_slice(beneficiary.as_slice());
+    Address::from_word(keccak256(preimage))
+}
+
+/// Read the deployed bytecode from a contract's standard json artifact.
+pub fn deployed_bytecode_from_path(path: &Path) -> eyre::Result<Bytes> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("failed to read contract {}", path.display()))?;
+    let contract: ContractStandardJson = serde_json::from_str(&contents)
+        .with_context(|| format!("failed to parse contract {}", path.display()))?;
+    let bytecode = hex::decode(contract.deployed_bytecode.object)?;
+    ensure!(!bytecode.is_empty(), "contract {} has no deployed bytecode", path.display());
+    Ok(bytecode.into())
+}
+
+/// Parse `address,amount[,start,cliff,duration]`.
+fn parse_csv_row(line: &str) -> eyre::Result<Allocation> {
+    let columns: Vec<_> = line.split(',').map(str::trim).collect();
+    let (address, amount, vesting) = match columns.as_slice() {
+        [address, amount] | [address, amount, "", "", ""] => (address, amount, None),
+        [address, amount, start, cliff, duration] => {
+            let vesting = VestingSchedule {
+                start: start.parse().context("invalid vesting start")?,
+                cliff: cliff.parse().context("invalid vesting cliff")?,
+                duration: duration.parse().context("invalid vesting duration")?,
+            };
+            (address, amount, Some(vesting))
+        }
+        _ => eyre::bail!("expected 2 or 5 columns, found {}", columns.len()),
+    };
+
+    Ok(Allocation {
+        address: address.parse().context("invalid address")?,
+        amount: amount.parse().context("invalid amount")?,
+        vesting,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{vesting_contract_address, Distribution, VESTING_BENEFICIARY_SLOT};
+    use tn_types::{Address, Bytes, U256};
+
+    const CSV: &str = "address,amount,start,cliff,duration
+# team
+0x1111111111111111111111111111111111111111,1000,,,
+0x2222222222222222222222222222222222222222,2000,1700000000,100,400
+";
+
+    #[test]
+    fn test_distribution_csv_and_json_match() {
+        let csv = Distribution::from_csv(CSV).expect("valid csv");
+        let json: Distribution = serde_json::from_str(
+            r#"[
+                {"address": "0x1111111111111111111111111111111111111111", "amount": "1000"},
+                {
+                    "address": "0x2222222222222222222222222222222222222222",
+                    "amount": "2000",
+                    "vesting": {"start": 1700000000, "cliff": 100, "duration": 400}
+                }
+            ]"#,
+        )
+        .expect("valid json");
+
+        assert_eq!(csv, json);
+        assert_eq!(csv.total(), U256::from(3000));
+    }
+
+    #[test]
+    fn test_distribution_genesis_accounts() {
+        let distribution = Distribution::from_csv(CSV).expect("valid csv");
+        assert!(distribution.genesis_accounts(None).is_err(), "vesting requires a contract");
+
+        let code = Bytes::from_static(&[0x60, 0x00]);
+        let accounts = distribution.genesis_accounts(Some(&code)).expect("valid distribution");
+        assert_eq!(accounts, distribution.genesis_accounts(Some(&code)).expect("reproducible"));
+
+        let direct = Address::repeat_byte(0x11);
+        assert_eq!(accounts[&direct].balance, U256::from(1000));
+        assert!(accounts[&direct].code.is_none());
+
+        let beneficiary = Address::repeat_byte(0x22);
+        assert!(!accounts.contains_key(&beneficiary));
+        let vesting = &accounts[&vesting_contract_address(1, beneficiary)];
+        assert_eq!(vesting.balance, U256::from(2000));
+        assert_eq!(vesting.code, Some(code));
+        let storage = vesting.storage.as_ref().expect("vesting storage");
+        assert_eq!(storage[&VESTING_BENEFICIARY_SLOT], beneficiary.into_word());
+        assert_eq!(storage.len(), 4);
+
+        // accounts can only be funded once
+        let duplicate = format!("{CSV}0x1111111111111111111111111111111111111111,1,,,\n");
+        let distribution = Distribution::from_csv(&duplicate).expect("valid csv");
+        assert!(distribution.genesis_accounts(Some(&code)).is_err());
+    }
+}
@@ -0,0 +1,159 @@
+//! Environment variable overrides for configuration.
+//!
+//! Variables prefixed with [ENV_PREFIX] override config values. Nested fields are separated by a
+//! double underscore and matched case-insensitively against the serialized field names. For
+//! example, `TN__PARAMETERS__MAX_BATCH_DELAY=500ms` overrides `parameters.max_batch_delay`.
+//!
+//! Values are parsed as JSON and fall back to the raw string, so `TN__JOURNAL__DIR=/data/journal`
+//! needs no quotes. Optional sections that are unset, like `journal`, are created by their first
+//! override and filled with defaults for the fields that are not overridden.
+//!
+//! The same prefix is used for CLI arguments, so variables that do not match a config field are
+//! ignored here.
+
+use eyre::Context;
+use serde::{de::DeserializeOwned, Serialize};
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use tracing::info;
+
+/// The prefix for environment variables that override configuration values.
+pub const ENV_PREFIX: &str = "TN__";
+
+/// Apply `TN__` environment variable overrides to a config.
+pub fn apply_env_overrides<T: Serialize + DeserializeOwned>(cfg: T) -> eyre::Result<T> {
+    apply_overrides(cfg, std::env::vars())
+}
+
+/// Apply `TN__` overrides from the provided key/value pairs to a config.
+///
+/// Only fields that already exist in the serialized config, or belong to an unset optional section,
+/// are overridden. String fields use the raw value and all other fields parse the value as JSON,
+/// falling back to the raw value if it is not valid JSON.
+pub fn apply_overrides<T, I>(cfg: T, vars: I) -> eyre::Result<T>
+where
+    T: Serialize + DeserializeOwned,
+    I: IntoIterator<Item = (String, String)>,
+{
+    let mut value = serde_json::to_value(cfg).with_context(|| "Failed to serialize config")?;
+    // paths of unset sections created by an override
+    let mut created = HashSet::new();
+
+    for (key, raw) in vars {
+        let Some(path) = key.strip_prefix(ENV_PREFIX) else { continue };
+        let segments: Vec<String> = path.split("__").map(str::to_lowercase).collect();
+        let Some(field) = lookup_mut(&mut value, &segments, &mut created) else { continue };
+
+        *field = match field {
+            Value::String(_) => Value::String(raw),
+            _ => serde_json::from_str(&raw).unwrap_or(Value::String(raw)),
+        };
+        info!(target: "tn::config", var = key, "config value overridden by environment");
+    }
+
+    serde_json::from_value(value).with_context(|| "Invalid config after environment overrides")
+}
+
+/// Return the nested field for the path, if it exists.
+///
+/// Unset sections (`null`) on the path are replaced with an empty object and recorded in `created`.
+/// Missing fields are only added to created sections.
+fn lookup_mut<'a>(
+    value: &'a mut Value,
+    segments: &[String],
+    created: &mut HashSet<Vec<String>>,
+) -> Option<&'a mut Value> {
+    let mut value = value;
+    for (depth, segment) in segments.iter().enumerate() {
+        let parent = &segments[..depth];
+        if value.is_null() {
+            *value = Value::Object(Map::new());
+            created.insert(parent.to_vec());
+        }
+        let object = value.as_object_mut()?;
+        value = if created.contains(parent) {
+            object.entry(segment.clone()).or_insert(Value::Null)
+        } else {
+            object.get_mut(segment)?
+        };
+    }
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Config, StreamSinkConfig};
+    use std::time::Duration;
+
+    #[test]
+    fn test_config_env_overrides() {
+        let vars = [
+            ("TN__PARAMETERS__MAX_BATCH_DELAY", "250ms"),
+            ("TN__PARAMETERS__GC_DEPTH", "100"),
+            ("TN__OBSERVER", "true"),
+            ("TN__VALIDATOR_INFO__NAME", "validator-1"),
+            // cli arguments are ignored
+            ("TN__HTTP__PORT", "8545"),
+            ("RUST_LOG", "info"),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string()));
+
+        let config = apply_overrides(Config::default(), vars).expect("overrides applied");
+        assert_eq!(config.parameters.max_batch_delay, Duration::from_millis(250));
+        assert_eq!(config.parameters.gc_depth, 100);
+        assert!(config.observer);
+        assert_eq!(config.validator_info.name, "validator-1");
+
+        let invalid = [("TN__PARAMETERS__GC_DEPTH".to_string(), "many".to_string())];
+        assert!(apply_overrides(Config::default(), invalid).is_err());
+    }
+
+    #[test]
+    fn test_config_env_overrides_toml_roundtrip() {
+        let toml = toml::to_string(&Config::default()).expect("config serializes");
+        let config: Config = toml::from_str(&toml).expect("config deserializes");
+        assert!(config.journal.is_none());
+        assert!(config.stream.is_none());
+        assert!(config.archive.is_none());
+
+        let vars = [
+            // unset sections are created and unquoted strings are taken as is
+            ("TN__JOURNAL__DIR", "/data/journal"),
+            ("TN__JOURNAL__BATCHES", "true"),
+            ("TN__STREAM__SINK__TYPE", "kafka"),
+            ("TN__STREAM__SINK__BROKERS", "broker1:9092,broker2:9092"),
+            ("TN__STREAM__START_BLOCK", "100"),
+            ("TN__ARCHIVE__ENDPOINT", "https://s3.us-east-1.amazonaws.com"),
+            ("TN__ARCHIVE__BUCKET", "tn-archive"),
+            ("TN__PARAMETERS__MAX_BATCH_DELAY", "250ms"),
+            // cli arguments are still ignored
+            ("TN__HTTP__PORT", "8545"),
+        ]
+        .map(|(k, v)| (k.to_string(), v.to_string()));
+        let config = apply_overrides(config, vars).expect("overrides applied");
+
+        let journal = config.journal.as_ref().expect("journal configured");
+        assert_eq!(journal.dir, std::path::PathBuf::from("/data/journal"));
+        assert!(journal.batches);
+        let stream = config.stream.as_ref().expect("stream configured");
+        assert_eq!(
+            stream.sink,
+            StreamSinkConfig::Kafka {
+                brokers: "broker1:9092,broker2:9092".to_string(),
+                properties: Default::default(),
+            }
+        );
+        assert_eq!(stream.start_block, Some(100));
+        assert_eq!(stream.topic_prefix, "telcoin");
+        let archive = config.archive.as_ref().expect("archive configured");
+        assert_eq!(archive.bucket, "tn-archive");
+        assert_eq!(archive.region, "us-east-1");
+        assert_eq!(config.parameters.max_batch_delay, Duration::from_millis(250));
+
+        // the overridden config survives another toml round trip
+        let toml = toml::to_string(&config).expect("config serializes");
+        let decoded: Config = toml::from_str(&toml).expect("config deserializes");
+        assert_eq!(toml::to_string(&decoded).expect("config serializes"), toml);
+    }
+}
@@ -1,5 +1,5 @@
 //! Genesis information used when configuring a node.
-use crate::{Config, ConfigFmt, ConfigTrait, TelcoinDirs};
+use crate::{Config, ConfigFmt, ConfigTrait, TelcoinDirs, TnChainSpec};
 use eyre::Context;
 use reth_chainspec::ChainSpec;
 use serde::{Deserialize, Serialize};
@@ -12,9 +12,9 @@ use std::{
     sync::Arc,
 };
 use tn_types::{
-    adiri_genesis, hex, keccak256, verify_proof_of_possession_bls, Address, BlsPublicKey,
-    BlsSignature, Committee, CommitteeBuilder, Epoch, FromHex as _, GenesisAccount, Intent,
-    IntentMessage, Multiaddr, NetworkPublicKey, PrimaryInfo, ProtocolSignature, Signer,
+    adiri_genesis, hex, hex_literal, keccak256, verify_proof_of_possession_bls, Address,
+    BlsPublicKey, BlsSignature, Bytes, Committee, CommitteeBuilder, Epoch, Genesis, GenesisAccount,
+    Intent, IntentMessage, Multiaddr, NetworkPublicKey, PrimaryInfo, ProtocolSignature, Signer,
     WorkerCache, WorkerIndex, B256,
 };
 use tracing::{info, warn};
@@ -36,6 +36,102 @@ const CONSENSUS_REGISTRY: &str =
 const ERC1967_PROXY: &str =
     from_utf8(include_bytes!("../../../tn-contracts/artifacts/ERC1967Proxy.json"));
 
+/// The address of the consensus registry proxy.
+pub const CONSENSUS_REGISTRY_ADDRESS: Address =
+    Address::new(hex_literal::hex!("07e17e17e17e17e17e17e17e17e17e17e17e17e1"));
+/// The address of the consensus registry implementation behind the proxy.
+pub const CONSENSUS_REGISTRY_IMPL_ADDRESS: Address =
+    Address::new(hex_literal::hex!("07e17e17e17e17e17e17e17e17e17e17e17e17e0"));
+/// The address of the bridge contract.
+pub const BRIDGE_ADDRESS: Address =
+    Address::new(hex_literal::hex!("07e17e17e17e17e17e17e17e17e17e17e17e17e2"));
+/// The address of the fee treasury contract.
+pub const FEE_TREASURY_ADDRESS: Address =
+    Address::new(hex_literal::hex!("07e17e17e17e17e17e17e17e17e17e17e17e17e3"));
+
+/// The addresses reserved for system contracts, in the order they are hashed.
+pub const SYSTEM_CONTRACT_ADDRESSES: [Address; 4] = [
+    CONSENSUS_REGISTRY_ADDRESS,
+    CONSENSUS_REGISTRY_IMPL_ADDRESS,
+    BRIDGE_ADDRESS,
+    FEE_TREASURY_ADDRESS,
+];
+
+/// A contract deployed at genesis that the protocol depends on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SystemContract {
+    /// The name used in errors and logs.
+    pub name: String,
+    /// The address the contract is deployed at.
+    pub address: Address,
+    /// The deployed bytecode.
+    pub code: Bytes,
+    /// The contract's storage at genesis.
+    pub storage: BTreeMap<B256, B256>,
+    /// The expected keccak256 hash of `code`, if known.
+    pub code_hash: Option<B256>,
+}
+
+impl SystemContract {
+    /// Create a new instance of [Self] without storage.
+    pub fn new(name: impl Into<String>, address: Address, code: Bytes) -> Self {
+        Self { name: name.into(), address, code, storage: Default::default(), code_hash: None }
+    }
+
+    /// Set the contract's storage at genesis.
+    pub fn with_storage(mut self, storage: BTreeMap<B256, B256>) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    /// Require the deployed bytecode to hash to `code_hash`.
+    pub fn with_code_hash(mut self, code_hash: B256) -> Self {
+        self.code_hash = Some(code_hash);
+        self
+    }
+
+    /// Create a new instance of [Self] from a compiled contract artifact.
+    ///
+    /// The artifact is the standard json output of the compiler and the deployed bytecode is used.
+    /// The storage file maps storage slots to values as yaml, like the consensus registry storage.
+    pub fn from_artifact(
+        name: impl Into<String>,
+        address: Address,
+        artifact: &Path,
+        storage: Option<&Path>,
+    ) -> eyre::Result<Self> {
+        let name = name.into();
+        let contents = fs::read_to_string(artifact)
+            .with_context(|| format!("failed to read {name} artifact {}", artifact.display()))?;
+        let contract: ContractStandardJson = serde_json::from_str(&contents)
+            .with_context(|| format!("invalid {name} artifact {}", artifact.display()))?;
+        let code = hex::decode(contract.deployed_bytecode.object)
+            .with_context(|| format!("invalid {name} bytecode"))?;
+        let mut system_contract = Self::new(name, address, code.into());
+
+        if let Some(path) = storage {
+            let contents = fs::read_to_string(path).with_context(|| {
+                format!("failed to read {} storage {}", system_contract.name, path.display())
+            })?;
+            let storage: BTreeMap<String, String> = serde_yaml::from_str(&contents)
+                .with_context(|| format!("invalid {} storage", system_contract.name))?;
+            let storage = storage
+                .into_iter()
+                .map(|(k, v)| -> eyre::Result<(B256, B256)> { Ok((k.parse()?, v.parse()?)) })
+                .collect::<eyre::Result<_>>()
+                .with_context(|| format!("invalid {} storage slot", system_contract.name))?;
+            system_contract = system_contract.with_storage(storage);
+        }
+        Ok(system_contract)
+    }
+
+    /// The genesis account for the contract.
+    fn genesis_account(&self) -> GenesisAccount {
+        let storage = (!self.storage.is_empty()).then(|| self.storage.clone());
+        GenesisAccount::default().with_code(Some(self.code.clone())).with_storage(storage)
+    }
+}
+
 /// The struct for starting a network at genesis.
 pub struct NetworkGenesis {
     // /// The committee
@@ -102,31 +198,108 @@ impl NetworkGenesis {
             PubkeyFlags::overwrite_if_flag(val, &pubkey_flags, &validator_info);
         }
 
-        let registry_impl = Address::random();
         let registry_standard_json = CONSENSUS_REGISTRY;
         let registry_contract: ContractStandardJson =
             serde_json::from_str(registry_standard_json).expect("json parsing failure");
         let registry_bytecode = hex::decode(registry_contract.deployed_bytecode.object)
             .expect("invalid bytecode hexstring");
-        let registry_proxy = Address::from_hex("0x07e17e17e17e17e17e17e17e17e17e17e17e17e1")
-            .expect("invalid hex address");
         let proxy_standard_json = ERC1967_PROXY;
         let proxy_contract: ContractStandardJson =
             serde_json::from_str(proxy_standard_json).expect("json parsing failure");
         let proxy_bytecode = hex::decode(proxy_contract.deployed_bytecode.object)
             .expect("invalid bytecode hexstring");
-        let registry_genesis_accounts = vec![
-            (registry_impl, GenesisAccount::default().with_code(Some(registry_bytecode.into()))),
-            (
-                registry_proxy,
-                GenesisAccount::default()
-                    .with_code(Some(proxy_bytecode.into()))
-                    .with_storage(Some(registry_storage_cfg)),
+        let registry_contracts = [
+            SystemContract::new(
+                "consensus registry implementation",
+                CONSENSUS_REGISTRY_IMPL_ADDRESS,
+                registry_bytecode.into(),
             ),
+            SystemContract::new(
+                "consensus registry",
+                CONSENSUS_REGISTRY_ADDRESS,
+                proxy_bytecode.into(),
+            )
+            .with_storage(registry_storage_cfg),
         ];
+        for contract in registry_contracts {
+            self.add_system_contract(contract).expect("valid consensus registry");
+        }
+    }
+
+    /// Deploy the bridge contract at [BRIDGE_ADDRESS] from its compiled artifact and optional
+    /// storage file.
+    pub fn construct_bridge_genesis_account(
+        &mut self,
+        artifact: &Path,
+        storage: Option<&Path>,
+    ) -> eyre::Result<()> {
+        let bridge = SystemContract::from_artifact("bridge", BRIDGE_ADDRESS, artifact, storage)?;
+        self.add_system_contract(bridge)
+    }
+
+    /// Deploy the fee treasury contract at [FEE_TREASURY_ADDRESS] from its compiled artifact and
+    /// optional storage file.
+    pub fn construct_fee_treasury_genesis_account(
+        &mut self,
+        artifact: &Path,
+        storage: Option<&Path>,
+    ) -> eyre::Result<()> {
+        let treasury =
+            SystemContract::from_artifact("fee treasury", FEE_TREASURY_ADDRESS, artifact, storage)?;
+        self.add_system_contract(treasury)
+    }
+
+    /// Deploy a system contract at genesis.
+    ///
+    /// Fails if the bytecode doesn't match the expected hash or a different account already exists
+    /// at the contract's address. Adding the same contract again has no effect.
+    pub fn add_system_contract(&mut self, contract: SystemContract) -> eyre::Result<()> {
+        if let Some(expected) = contract.code_hash {
+            let actual = keccak256(&contract.code);
+            eyre::ensure!(
+                actual == expected,
+                "{} bytecode hash {actual} does not match expected {expected}",
+                contract.name
+            );
+        }
+
+        let account = contract.genesis_account();
+        match self.chain.genesis.alloc.get(&contract.address) {
+            Some(existing) if *existing == account => return Ok(()),
+            Some(_) => eyre::bail!(
+                "{} can not be deployed to {} because the account already exists",
+                contract.name,
+                contract.address
+            ),
+            None => (),
+        }
+
+        info!(
+            target: "genesis::ceremony",
+            name = %contract.name,
+            address = ?contract.address,
+            "adding system contract"
+        );
+        self.chain =
+            self.chain.genesis.clone().extend_accounts([(contract.address, account)]).into();
+        Ok(())
+    }
+
+    /// Hash the code and storage of every system contract in genesis.
+    ///
+    /// Validators compare this hash out of band to confirm they start from identical system state.
+    pub fn system_contracts_hash(&self) -> B256 {
+        system_contracts_hash(&self.chain.genesis)
+    }
 
-        // update chain with new genesis
-        self.chain = self.chain.genesis.clone().extend_accounts(registry_genesis_accounts).into();
+    /// Verify the system contracts in genesis hash to `expected`.
+    pub fn verify_system_contracts(&self, expected: B256) -> eyre::Result<()> {
+        let actual = self.system_contracts_hash();
+        eyre::ensure!(
+            actual == expected,
+            "system contracts hash {actual} does not match expected {expected}"
+        );
+        Ok(())
     }
 
     /// Generate a [NetworkGenesis] by reading files in a directory.
@@ -209,6 +382,11 @@ impl NetworkGenesis {
         &self.chain
     }
 
+    /// Return the Telcoin Network parameters from genesis.
+    pub fn tn_chain_spec(&self) -> eyre::Result<TnChainSpec> {
+        TnChainSpec::from_genesis(&self.chain.genesis)
+    }
+
     /// Validate each validator:
     /// - verify proof of possession
     ///
@@ -218,7 +396,7 @@ impl NetworkGenesis {
     pub fn validate(&self) -> eyre::Result<()> {
         for (pubkey, validator) in self.validators.iter() {
             info!(target: "genesis::validate", "verifying validator: {}", pubkey);
-            verify_proof_of_possession_bls(&validator.proof_of_possession, pubkey, &self.chain)?;
+            validator.verify_proof_of_possession(&self.chain)?;
         }
         info!(target: "genesis::validate", "all validators valid for genesis");
         Ok(())
@@ -251,7 +429,8 @@ impl NetworkGenesis {
             .map(|(pubkey, validator)| (*pubkey, validator.primary_info.worker_index.clone()))
             .collect();
 
-        let worker_cache = WorkerCache { epoch: 0, workers: Arc::new(workers) };
+        let worker_cache =
+            WorkerCache { epoch: 0, workers: Arc::new(workers), sequences: Default::default() };
 
         Ok(worker_cache)
     }
@@ -311,6 +490,17 @@ impl ValidatorInfo {
     pub fn worker_index(&self) -> &WorkerIndex {
         self.primary_info.worker_index()
     }
+
+    /// Verify the proof of possession commits to this validator's keys and execution address.
+    pub fn verify_proof_of_possession(&self, chain_spec: &ChainSpec) -> eyre::Result<()> {
+        verify_proof_of_possession_bls(
+            &self.proof_of_possession,
+            &self.bls_public_key,
+            &self.execution_address,
+            &self.primary_info,
+            chain_spec,
+        )
+    }
 }
 
 impl Default for ValidatorInfo {
@@ -372,6 +562,28 @@ impl PartialEq for ValidatorSignatureInfo {
     }
 }
 
+/// Hash the code and storage of the accounts at [SYSTEM_CONTRACT_ADDRESSES].
+///
+/// Missing accounts are skipped, storage is hashed in slot order.
+pub fn system_contracts_hash(genesis: &Genesis) -> B256 {
+    let mut preimage = Vec::new();
+    for address in SYSTEM_CONTRACT_ADDRESSES {
+        let Some(account) = genesis.alloc.get(&address) else {
+            continue;
+        };
+
+        preimage.extend_from_slice(address.as_slice());
+        let code = account.code.as_ref().map(keccak256).unwrap_or_default();
+        preimage.extend_from_slice(code.as_slice());
+        for (slot, value) in account.storage.iter().flatten() {
+            preimage.extend_from_slice(slot.as_slice());
+            preimage.extend_from_slice(value.as_slice());
+        }
+    }
+
+    keccak256(preimage)
+}
+
 struct PubkeyFlags {
     bls_a: B256,
     bls_b: B256,
@@ -455,7 +667,7 @@ pub fn test_fetch_file_content_relative_to_manifest(relative_path: PathBuf) -> S
 
 #[cfg(test)]
 mod tests {
-    use super::NetworkGenesis;
+    use super::{NetworkGenesis, SystemContract, BRIDGE_ADDRESS, FEE_TREASURY_ADDRESS};
     use crate::{
         genesis::ContractStandardJson, test_fetch_file_content_relative_to_manifest, TelcoinDirs,
         ValidatorInfo,
@@ -464,8 +676,8 @@ mod tests {
     use std::collections::BTreeMap;
     use tempfile::tempdir;
     use tn_types::{
-        adiri_chain_spec, generate_proof_of_possession_bls, hex, Address, BlsKeypair, FromHex as _,
-        Multiaddr, NetworkKeypair, PrimaryInfo, WorkerIndex, WorkerInfo,
+        adiri_chain_spec, generate_proof_of_possession_bls, hex, keccak256, Address, BlsKeypair,
+        Bytes, FromHex as _, Multiaddr, NetworkKeypair, PrimaryInfo, WorkerIndex, WorkerInfo, B256,
     };
 
     #[test]
@@ -478,8 +690,6 @@ mod tests {
         let bls_keypair = BlsKeypair::generate(&mut StdRng::from_seed([0; 32]));
         let network_keypair = NetworkKeypair::generate_ed25519();
         let address = Address::from_raw_public_key(&[0; 64]);
-        let proof_of_possession =
-            generate_proof_of_possession_bls(&bls_keypair, &adiri_chain_spec()).unwrap();
         let primary_network_address = Multiaddr::empty();
         let worker_info = WorkerInfo::default();
         let worker_index = WorkerIndex(BTreeMap::from([(0, worker_info)]));
@@ -489,6 +699,13 @@ mod tests {
             network_keypair.public().clone().into(),
             worker_index,
         );
+        let proof_of_possession = generate_proof_of_possession_bls(
+            &bls_keypair,
+            &address,
+            &primary_info,
+            &adiri_chain_spec(),
+        )
+        .unwrap();
         let name = "validator1".to_string();
         // create validator
         let validator = ValidatorInfo::new(
@@ -544,6 +761,70 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_system_contracts() {
+        let mut network_genesis = NetworkGenesis::new();
+        let empty_hash = network_genesis.system_contracts_hash();
+
+        let code = Bytes::from_static(&[0x60, 0x00]);
+        let bridge = SystemContract::new("bridge", BRIDGE_ADDRESS, code.clone())
+            .with_storage(BTreeMap::from([(B256::ZERO, B256::with_last_byte(1))]))
+            .with_code_hash(keccak256(&code));
+
+        // bytecode must match the expected hash
+        let wrong_hash = bridge.clone().with_code_hash(B256::ZERO);
+        assert!(network_genesis.add_system_contract(wrong_hash).is_err());
+
+        network_genesis.add_system_contract(bridge.clone()).expect("bridge added");
+        network_genesis.add_system_contract(bridge.clone()).expect("same bridge added again");
+        let other_bridge = SystemContract::new("bridge", BRIDGE_ADDRESS, Bytes::from_static(&[0]));
+        assert!(network_genesis.add_system_contract(other_bridge).is_err());
+
+        let hash = network_genesis.system_contracts_hash();
+        assert_ne!(hash, empty_hash);
+        network_genesis.verify_system_contracts(hash).expect("system contracts match");
+        assert!(network_genesis.verify_system_contracts(empty_hash).is_err());
+
+        // the same contracts produce the same hash
+        let mut other_genesis = NetworkGenesis::new();
+        other_genesis.add_system_contract(bridge).expect("bridge added");
+        assert_eq!(other_genesis.system_contracts_hash(), hash);
+    }
+
+    #[test]
+    fn test_bridge_and_fee_treasury_in_genesis() {
+        let tmp_dir = tempdir().unwrap();
+        let artifact = tmp_dir.path().join("Bridge.json");
+        std::fs::write(
+            &artifact,
+            r#"{"bytecode":{"object":"0x600160005260206000f3"},"deployedBytecode":{"object":"0x60016000f3"}}"#,
+        )
+        .unwrap();
+        let storage = tmp_dir.path().join("bridge-storage.yaml");
+        let slot = B256::with_last_byte(1);
+        std::fs::write(&storage, format!("\"{}\": \"{}\"\n", B256::ZERO, slot)).unwrap();
+
+        let mut network_genesis = NetworkGenesis::new();
+        network_genesis
+            .construct_bridge_genesis_account(&artifact, Some(&storage))
+            .expect("bridge deployed");
+        network_genesis
+            .construct_fee_treasury_genesis_account(&artifact, None)
+            .expect("fee treasury deployed");
+
+        let alloc = &network_genesis.chain_info().genesis.alloc;
+        let bridge = alloc.get(&BRIDGE_ADDRESS).expect("bridge in genesis");
+        assert_eq!(bridge.code, Some(Bytes::from(hex::decode("60016000f3").unwrap())));
+        assert_eq!(bridge.storage, Some(BTreeMap::from([(B256::ZERO, slot)])));
+        let treasury = alloc.get(&FEE_TREASURY_ADDRESS).expect("fee treasury in genesis");
+        assert_eq!(treasury.code, bridge.code);
+        assert!(treasury.storage.is_none());
+
+        // a missing artifact fails
+        let missing = tmp_dir.path().join("Missing.json");
+        assert!(network_genesis.construct_bridge_genesis_account(&missing, None).is_err());
+    }
+
     #[test]
     fn test_validate_genesis() {
         let mut network_genesis = NetworkGenesis::new();
@@ -552,8 +833,6 @@ mod tests {
             let bls_keypair = BlsKeypair::generate(&mut StdRng::from_seed([0; 32]));
             let network_keypair = NetworkKeypair::generate_ed25519();
             let address = Address::from_raw_public_key(&[0; 64]);
-            let proof_of_possession =
-                generate_proof_of_possession_bls(&bls_keypair, &adiri_chain_spec()).unwrap();
             let primary_network_address = Multiaddr::empty();
             let worker_info = WorkerInfo::default();
             let worker_index = WorkerIndex(BTreeMap::from([(0, worker_info)]));
@@ -563,6 +842,13 @@ mod tests {
                 network_keypair.public().clone().into(),
                 worker_index,
             );
+            let proof_of_possession = generate_proof_of_possession_bls(
+                &bls_keypair,
+                &address,
+                &primary_info,
+                &adiri_chain_spec(),
+            )
+            .unwrap();
             let name = format!("validator-{}", v);
             // create validator
             let validator = ValidatorInfo::new(
@@ -576,7 +862,12 @@ mod tests {
             network_genesis.add_validator(validator.clone());
         }
         // validate
-        assert!(network_genesis.validate().is_ok())
+        assert!(network_genesis.validate().is_ok());
+
+        // proofs commit to the execution address
+        let validator = network_genesis.validators.values_mut().next().expect("validator");
+        validator.execution_address = Address::random();
+        assert!(network_genesis.validate().is_err())
     }
 
     #[test]
@@ -594,8 +885,6 @@ mod tests {
             wrong_chain.genesis.timestamp = 0;
 
             // generate proof with wrong chain spec
-            let proof_of_possession =
-                generate_proof_of_possession_bls(&bls_keypair, &wrong_chain).unwrap();
             let primary_network_address = Multiaddr::empty();
             let worker_info = WorkerInfo::default();
             let worker_index = WorkerIndex(BTreeMap::from([(0, worker_info)]));
@@ -605,6 +894,13 @@ mod tests {
                 network_keypair.public().clone().into(),
                 worker_index,
             );
+            let proof_of_possession = generate_proof_of_possession_bls(
+                &bls_keypair,
+                &address,
+                &primary_info,
+                &wrong_chain,
+            )
+            .unwrap();
             let name = format!("validator-{}", v);
             // create validator
             let validator = ValidatorInfo::new(
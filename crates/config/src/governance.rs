@@ -0,0 +1,323 @@
+//! Consensus parameters set by an on-chain governance contract.
+//!
+//! Networks using [crate::ParameterSource::Governance] read selected [Parameters] from a contract's
+//! storage at each epoch boundary. Every validator reads the state after the last consensus
+//! output of the previous epoch, so the committee switches to the same values at the same output.
+//! The first epoch reads the contract's storage at genesis.
+//!
+//! The contract stores each parameter in a fixed slot described by the `GOVERNANCE_*_SLOT`
+//! constants. A slot holding zero leaves the local value unchanged, and values outside the bounds
+//! in [GovernedParameters::apply] are rejected in favor of the local value.
+//!
+//! The batch gas limit in [GOVERNANCE_MAX_BATCH_GAS_SLOT] and the batch gas target in
+//! [GOVERNANCE_BATCH_GAS_TARGET_SLOT] are not node parameters and never relaunch the node. The
+//! executor reads the limit when each epoch starts and sets it as the gas limit of executed
+//! blocks, see [tn_types::BatchGasSchedule]. Workers read the target after each executed output,
+//! see [governed_batch_gas_target].
+
+use crate::Parameters;
+use serde::{Deserialize, Serialize};
+use std::{ops::RangeInclusive, time::Duration};
+use tn_types::{B256, MAX_BAD_NODES_STAKE_THRESHOLD, U256};
+
+/// The storage slot of the maximum header delay in milliseconds.
+pub const GOVERNANCE_MAX_HEADER_DELAY_SLOT: B256 = B256::ZERO;
+/// The storage slot of the number of batches that triggers a new header.
+pub const GOVERNANCE_HEADER_BATCHES_THRESHOLD_SLOT: B256 = B256::with_last_byte(1);
+/// The storage slot of the maximum number of batches in a header.
+pub const GOVERNANCE_MAX_HEADER_BATCHES_SLOT: B256 = B256::with_last_byte(2);
+/// The storage slot of the garbage collection depth in rounds.
+pub const GOVERNANCE_GC_DEPTH_SLOT: B256 = B256::with_last_byte(3);
+/// The storage slot of the percentage of stake swapped out of the leader schedule.
+pub const GOVERNANCE_BAD_NODES_STAKE_THRESHOLD_SLOT: B256 = B256::with_last_byte(4);
+/// The storage slot of the number of committed sub-dags in each leader schedule.
+pub const GOVERNANCE_SUB_DAGS_PER_SCHEDULE_SLOT: B256 = B256::with_last_byte(5);
+/// The storage slot of the gas limit of executed blocks and the batches they execute.
+pub const GOVERNANCE_MAX_BATCH_GAS_SLOT: B256 = B256::with_last_byte(6);
+/// The storage slot of the gas that triggers sealing a batch.
+pub const GOVERNANCE_BATCH_GAS_TARGET_SLOT: B256 = B256::with_last_byte(7);
+
+/// The accepted range for a governed maximum header delay.
+pub const GOVERNED_MAX_HEADER_DELAY: RangeInclusive<Duration> =
+    Duration::from_millis(100)..=Duration::from_secs(60);
+/// The accepted range for governed header batch counts.
+pub const GOVERNED_HEADER_BATCHES: RangeInclusive<usize> = 1..=1_000;
+/// The accepted range for a governed garbage collection depth.
+pub const GOVERNED_GC_DEPTH: RangeInclusive<u32> = 10..=10_000;
+/// The accepted range for a governed bad nodes stake threshold.
+///
+/// A slot holding zero keeps the local value, so governance can not disable leader swaps once
+/// the chain spec enables them.
+pub const GOVERNED_BAD_NODES_STAKE_THRESHOLD: RangeInclusive<u64> =
+    1..=MAX_BAD_NODES_STAKE_THRESHOLD;
+/// The accepted range for a governed number of sub-dags in each leader schedule.
+pub const GOVERNED_SUB_DAGS_PER_SCHEDULE: RangeInclusive<u32> = 10..=100_000;
+/// The accepted range for a governed batch gas limit.
+pub const GOVERNED_MAX_BATCH_GAS: RangeInclusive<u64> = 1_000_000..=1_000_000_000;
+/// The accepted range for a governed batch gas target.
+pub const GOVERNED_BATCH_GAS_TARGET: RangeInclusive<u64> = 21_000..=*GOVERNED_MAX_BATCH_GAS.end();
+
+/// Consensus parameters read from the governance contract.
+///
+/// `None` keeps the local value.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GovernedParameters {
+    /// The maximum delay between headers.
+    pub max_header_delay: Option<Duration>,
+    /// The number of batches that triggers a new header.
+    pub header_num_of_batches_threshold: Option<usize>,
+    /// The maximum number of batches in a header.
+    pub max_header_num_of_batches: Option<usize>,
+    /// The garbage collection depth in rounds.
+    pub gc_depth: Option<u32>,
+    /// The percentage of stake swapped out of the leader schedule.
+    pub bad_nodes_stake_threshold: Option<u64>,
+    /// The number of committed sub-dags in each leader schedule.
+    pub sub_dags_per_schedule: Option<u32>,
+}
+
+/// A governed value that was not applied.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RejectedParameter {
+    /// The name of the parameter.
+    pub name: &'static str,
+    /// The governed value.
+    pub value: String,
+    /// Why the local value was kept.
+    pub reason: String,
+}
+
+impl GovernedParameters {
+    /// Read the parameters from the governance contract's storage.
+    ///
+    /// `storage` returns the value of a slot in the contract.
+    pub fn from_storage<F>(storage: F) -> eyre::Result<Self>
+    where
+        F: Fn(B256) -> eyre::Result<U256>,
+    {
+        // values too large for the parameter are rejected when applied
+        let read = |slot| -> eyre::Result<Option<u64>> {
+            let value = storage(slot)?;
+            Ok((!value.is_zero()).then(|| value.try_into().unwrap_or(u64::MAX)))
+        };
+        Ok(Self {
+            max_header_delay: read(GOVERNANCE_MAX_HEADER_DELAY_SLOT)?.map(Duration::from_millis),
+            header_num_of_batches_threshold: read(GOVERNANCE_HEADER_BATCHES_THRESHOLD_SLOT)?
+                .map(|value| value.try_into().unwrap_or(usize::MAX)),
+            max_header_num_of_batches: read(GOVERNANCE_MAX_HEADER_BATCHES_SLOT)?
+                .map(|value| value.try_into().unwrap_or(usize::MAX)),
+            gc_depth: read(GOVERNANCE_GC_DEPTH_SLOT)?
+                .map(|value| value.try_into().unwrap_or(u32::MAX)),
+            bad_nodes_stake_threshold: read(GOVERNANCE_BAD_NODES_STAKE_THRESHOLD_SLOT)?,
+            sub_dags_per_schedule: read(GOVERNANCE_SUB_DAGS_PER_SCHEDULE_SLOT)?
+                .map(|value| value.try_into().unwrap_or(u32::MAX)),
+        })
+    }
+
+    /// Apply the governed values to the local parameters.
+    ///
+    /// Each value must be within its `GOVERNED_*` bounds and consistent with the other parameters
+    /// after the update, otherwise the local value is kept. Returns the values that were not
+    /// applied.
+    pub fn apply(&self, parameters: &mut Parameters) -> Vec<RejectedParameter> {
+        let mut rejected = Vec::new();
+        let mut reject = |name, value: String, reason: String| {
+            rejected.push(RejectedParameter { name, value, reason })
+        };
+
+        if let Some(delay) = self.max_header_delay {
+            if !GOVERNED_MAX_HEADER_DELAY.contains(&delay) {
+                reject(
+                    "max_header_delay",
+                    format!("{delay:?}"),
+                    out_of_bounds(&GOVERNED_MAX_HEADER_DELAY),
+                );
+            } else if delay < parameters.min_header_delay {
+                let reason =
+                    format!("less than min_header_delay {:?}", parameters.min_header_delay);
+                reject("max_header_delay", format!("{delay:?}"), reason);
+            } else {
+                parameters.max_header_delay = delay;
+            }
+        }
+
+        if let Some(max) = self.max_header_num_of_batches {
+            // the threshold this update ends with
+            let threshold = self
+                .header_num_of_batches_threshold
+                .filter(|threshold| GOVERNED_HEADER_BATCHES.contains(threshold))
+                .unwrap_or(parameters.header_num_of_batches_threshold);
+            if !GOVERNED_HEADER_BATCHES.contains(&max) {
+                reject(
+                    "max_header_num_of_batches",
+                    max.to_string(),
+                    out_of_bounds(&GOVERNED_HEADER_BATCHES),
+                );
+            } else if max < threshold {
+                let reason = format!("less than header_num_of_batches_threshold {threshold}");
+                reject("max_header_num_of_batches", max.to_string(), reason);
+            } else {
+                parameters.max_header_num_of_batches = max;
+            }
+        }
+
+        if let Some(threshold) = self.header_num_of_batches_threshold {
+            if !GOVERNED_HEADER_BATCHES.contains(&threshold) {
+                reject(
+                    "header_num_of_batches_threshold",
+                    threshold.to_string(),
+                    out_of_bounds(&GOVERNED_HEADER_BATCHES),
+                );
+            } else if threshold > parameters.max_header_num_of_batches {
+                let reason = format!(
+                    "greater than max_header_num_of_batches {}",
+                    parameters.max_header_num_of_batches
+                );
+                reject("header_num_of_batches_threshold", threshold.to_string(), reason);
+            } else {
+                parameters.header_num_of_batches_threshold = threshold;
+            }
+        }
+
+        if let Some(depth) = self.gc_depth {
+            if GOVERNED_GC_DEPTH.contains(&depth) {
+                parameters.gc_depth = depth;
+            } else {
+                reject("gc_depth", depth.to_string(), out_of_bounds(&GOVERNED_GC_DEPTH));
+            }
+        }
+
+        if let Some(threshold) = self.bad_nodes_stake_threshold {
+            if GOVERNED_BAD_NODES_STAKE_THRESHOLD.contains(&threshold) {
+                parameters.leader_schedule.bad_nodes_stake_threshold = threshold;
+            } else {
+                reject(
+                    "bad_nodes_stake_threshold",
+                    threshold.to_string(),
+                    out_of_bounds(&GOVERNED_BAD_NODES_STAKE_THRESHOLD),
+                );
+            }
+        }
+
+        if let Some(sub_dags) = self.sub_dags_per_schedule {
+            if GOVERNED_SUB_DAGS_PER_SCHEDULE.contains(&sub_dags) {
+                parameters.leader_schedule.sub_dags_per_schedule = sub_dags;
+            } else {
+                reject(
+                    "sub_dags_per_schedule",
+                    sub_dags.to_string(),
+                    out_of_bounds(&GOVERNED_SUB_DAGS_PER_SCHEDULE),
+                );
+            }
+        }
+
+        rejected
+    }
+}
+
+/// The governed batch gas target from the value of [GOVERNANCE_BATCH_GAS_TARGET_SLOT].
+///
+/// Returns `None` if the slot holds zero or a value outside [GOVERNED_BATCH_GAS_TARGET], so the
+/// local mining mode applies.
+pub fn governed_batch_gas_target(value: U256) -> Option<u64> {
+    u64::try_from(value).ok().filter(|target| GOVERNED_BATCH_GAS_TARGET.contains(target))
+}
+
+/// The reason for rejecting a value outside `bounds`.
+fn out_of_bounds<T: std::fmt::Debug>(bounds: &RangeInclusive<T>) -> String {
+    format!("outside {:?}..={:?}", bounds.start(), bounds.end())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_governed_parameters_from_storage() {
+        let storage = HashMap::from([
+            (GOVERNANCE_MAX_HEADER_DELAY_SLOT, U256::from(2_000)),
+            (GOVERNANCE_GC_DEPTH_SLOT, U256::MAX),
+            (GOVERNANCE_SUB_DAGS_PER_SCHEDULE_SLOT, U256::from(50)),
+            // read by the executor and the workers
+            (GOVERNANCE_MAX_BATCH_GAS_SLOT, U256::from(60_000_000)),
+            (GOVERNANCE_BATCH_GAS_TARGET_SLOT, U256::from(15_000_000)),
+        ]);
+        let governed = GovernedParameters::from_storage(|slot| {
+            Ok(storage.get(&slot).copied().unwrap_or_default())
+        })
+        .unwrap();
+        assert_eq!(
+            governed,
+            GovernedParameters {
+                max_header_delay: Some(Duration::from_secs(2)),
+                gc_depth: Some(u32::MAX),
+                sub_dags_per_schedule: Some(50),
+                ..Default::default()
+            }
+        );
+    }
+
+    #[test]
+    fn test_governed_parameters_bounds() {
+        let local = Parameters::default();
+        let mut parameters = local.clone();
+        let governed = GovernedParameters {
+            max_header_delay: Some(local.max_header_delay * 2),
+            header_num_of_batches_threshold: Some(20),
+            max_header_num_of_batches: Some(30),
+            gc_depth: Some(100),
+            bad_nodes_stake_threshold: Some(20),
+            sub_dags_per_schedule: Some(50),
+        };
+        assert!(governed.apply(&mut parameters).is_empty());
+        assert_eq!(parameters.max_header_delay, local.max_header_delay * 2);
+        assert_eq!(parameters.header_num_of_batches_threshold, 20);
+        assert_eq!(parameters.max_header_num_of_batches, 30);
+        assert_eq!(parameters.gc_depth, 100);
+        assert_eq!(parameters.leader_schedule.bad_nodes_stake_threshold, 20);
+        assert_eq!(parameters.leader_schedule.sub_dags_per_schedule, 50);
+
+        // invalid values fall back to the local config
+        let mut parameters = local.clone();
+        let governed = GovernedParameters {
+            max_header_delay: Some(local.min_header_delay / 2),
+            header_num_of_batches_threshold: Some(local.max_header_num_of_batches + 1),
+            max_header_num_of_batches: Some(0),
+            gc_depth: Some(u32::MAX),
+            bad_nodes_stake_threshold: Some(34),
+            sub_dags_per_schedule: Some(1),
+        };
+        let rejected: Vec<_> =
+            governed.apply(&mut parameters).into_iter().map(|rejected| rejected.name).collect();
+        assert_eq!(
+            rejected,
+            [
+                "max_header_delay",
+                "max_header_num_of_batches",
+                "header_num_of_batches_threshold",
+                "gc_depth",
+                "bad_nodes_stake_threshold",
+                "sub_dags_per_schedule"
+            ]
+        );
+        assert_eq!(parameters, local);
+
+        // the max can't drop below the local threshold
+        let mut parameters = local.clone();
+        let governed = GovernedParameters {
+            max_header_num_of_batches: Some(local.header_num_of_batches_threshold - 1),
+            ..Default::default()
+        };
+        assert_eq!(governed.apply(&mut parameters)[0].name, "max_header_num_of_batches");
+        assert_eq!(parameters, local);
+    }
+
+    #[test]
+    fn test_governed_batch_gas_target() {
+        assert_eq!(governed_batch_gas_target(U256::from(15_000_000)), Some(15_000_000));
+        assert_eq!(governed_batch_gas_target(U256::ZERO), None);
+        assert_eq!(governed_batch_gas_target(U256::from(1_000)), None);
+        assert_eq!(governed_batch_gas_target(U256::MAX), None);
+    }
+}
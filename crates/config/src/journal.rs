@@ -0,0 +1,48 @@
+//! Configuration for exporting consensus output to journal files.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+/// Appends every committed consensus output to rotating journal files.
+///
+/// The journal lets analytics consume consensus output without a connection to the node. Each
+/// record holds the consensus header and batch digests, and the full batches if `batches` is set.
+/// The binary format is documented in the node's `journal` module.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct JournalConfig {
+    /// The directory for journal files, created if missing.
+    ///
+    /// Relative paths are relative to the node's working directory.
+    pub dir: PathBuf,
+    /// Include the full batches of each output, not only their digests.
+    #[serde(default)]
+    pub batches: bool,
+    /// The size in bytes a journal file grows to before a new file is started.
+    #[serde(default = "JournalConfig::default_max_file_size")]
+    pub max_file_size: u64,
+    /// The number of journal files to keep, the oldest files are deleted on rotation.
+    ///
+    /// Every file is kept if unset.
+    #[serde(default)]
+    pub max_files: Option<usize>,
+}
+
+impl JournalConfig {
+    /// The default file size, 256 MiB.
+    fn default_max_file_size() -> u64 {
+        256 * 1024 * 1024
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::JournalConfig;
+
+    #[test]
+    fn test_journal_config_defaults() {
+        let config: JournalConfig = serde_yaml::from_str("dir: journal\n").expect("config");
+        assert!(!config.batches);
+        assert_eq!(config.max_file_size, 256 * 1024 * 1024);
+        assert_eq!(config.max_files, None);
+    }
+}
@@ -1,17 +1,25 @@
 //! Cryptographic keys used by the node.
+//!
+//! Private key material is zeroed when dropped and never printed by Debug impls. Where the OS
+//! supports it the keys held by [KeyConfig] are locked in memory so they are not written to swap.
 
-use crate::{TelcoinDirs, BLS_KEYFILE, PRIMARY_NETWORK_SEED_FILE, WORKER_NETWORK_SEED_FILE};
+use crate::{
+    write_file_atomic, TelcoinDirs, BLS_KEYFILE, NETWORK_KEY_ROTATION_FILE,
+    PRIMARY_NETWORK_SEED_FILE, WORKER_NETWORK_SEED_FILE,
+};
 use blake2::Digest;
 use rand::{rngs::StdRng, CryptoRng, RngCore, SeedableRng};
 use rand_chacha::ChaCha20Rng;
 use reth_chainspec::ChainSpec;
-use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use std::{fmt, sync::Arc};
 use tn_types::{
-    encode, BlsKeypair, BlsPublicKey, BlsSignature, BlsSigner, DefaultHashFunction, Intent,
-    IntentMessage, IntentScope, NetworkKeypair, NetworkPublicKey, ProtocolSignature as _, Signer,
+    generate_proof_of_possession_bls, Address, BlsKeypair, BlsPublicKey, BlsSignature, BlsSigner,
+    DefaultHashFunction, Epoch, NetworkKeyRotation, NetworkKeypair, NetworkPublicKey, PrimaryInfo,
+    Signer,
 };
+use zeroize::{Zeroize as _, Zeroizing};
 
-#[derive(Debug)]
 struct KeyConfigInner {
     // DO NOT expose the private key to other code.  Tests that need this will provide a primary
     // key. Use the BlsSigner trait for signing for the primary.
@@ -20,6 +28,20 @@ struct KeyConfigInner {
     primary_network_keypair: NetworkKeypair,
     // Derived from the primary_keypair.
     worker_network_keypair: NetworkKeypair,
+    // True if this struct is locked in memory. Pages stay locked after the keys are dropped
+    // because they may still hold other locked keys.
+    locked: bool,
+}
+
+impl fmt::Debug for KeyConfigInner {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KeyConfigInner")
+            .field("primary_public_key", self.primary_keypair.public())
+            .field("primary_network_public_key", &self.primary_network_keypair.public())
+            .field("worker_network_public_key", &self.worker_network_keypair.public())
+            .field("locked", &self.locked)
+            .finish()
+    }
 }
 
 /// Basic implementation of a key manager.  This version will read a BLS key
@@ -46,7 +68,11 @@ impl KeyConfig {
         // load keys to start the primary
         let validator_keypath = tn_datadir.validator_keys_path();
         tracing::info!(target: "telcoin::consensus_config", "loading validator keys at {:?}", validator_keypath);
-        let contents = std::fs::read_to_string(tn_datadir.validator_keys_path().join(BLS_KEYFILE))?;
+        // finish a rotation interrupted before both seed files were written
+        Self::apply_network_key_rotation(tn_datadir)?;
+        let contents = Zeroizing::new(std::fs::read_to_string(
+            tn_datadir.validator_keys_path().join(BLS_KEYFILE),
+        )?);
         let primary_seed = std::fs::read_to_string(
             tn_datadir.validator_keys_path().join(PRIMARY_NETWORK_SEED_FILE),
         )
@@ -55,18 +81,9 @@ impl KeyConfig {
             tn_datadir.validator_keys_path().join(WORKER_NETWORK_SEED_FILE),
         )
         .unwrap_or_else(|_| "worker network keypair".to_string());
-        let bytes = bs58::decode(contents.as_str().trim()).into_vec()?;
+        let bytes = Zeroizing::new(bs58::decode(contents.trim()).into_vec()?);
         let primary_keypair = BlsKeypair::from_bytes(&bytes)?;
-        let primary_network_keypair =
-            Self::generate_network_keypair(&primary_keypair, &primary_seed);
-        let worker_network_keypair = Self::generate_network_keypair(&primary_keypair, &worker_seed);
-        Ok(Self {
-            inner: Arc::new(KeyConfigInner {
-                primary_keypair,
-                primary_network_keypair,
-                worker_network_keypair,
-            }),
-        })
+        Ok(Self::from_keypair(primary_keypair, &primary_seed, &worker_seed))
     }
 
     /// Generate a new random primary BLS key and save to the config file.
@@ -75,13 +92,20 @@ impl KeyConfig {
         let rng = ChaCha20Rng::from_entropy();
         // note: StdRng uses ChaCha12
         let primary_keypair = BlsKeypair::generate(&mut StdRng::from_rng(rng)?);
+        Self::save_keypair(tn_datadir, primary_keypair)
+    }
+
+    /// Save a primary BLS key to the config file, ie - one recovered from a backup phrase.
+    ///
+    /// The network keys are derived with the default seeds.
+    pub fn save_keypair<TND: TelcoinDirs>(
+        tn_datadir: &TND,
+        primary_keypair: BlsKeypair,
+    ) -> eyre::Result<Self> {
         let primary_seed = "primary network keypair";
         let worker_seed = "worker network keypair";
-        let primary_network_keypair =
-            Self::generate_network_keypair(&primary_keypair, primary_seed);
-        let worker_network_keypair = Self::generate_network_keypair(&primary_keypair, worker_seed);
-        let contents = bs58::encode(primary_keypair.to_bytes()).into_string();
-        std::fs::write(tn_datadir.validator_keys_path().join(BLS_KEYFILE), contents)?;
+        let contents = Zeroizing::new(bs58::encode(primary_keypair.to_bytes()).into_string());
+        std::fs::write(tn_datadir.validator_keys_path().join(BLS_KEYFILE), contents.as_bytes())?;
         std::fs::write(
             tn_datadir.validator_keys_path().join(PRIMARY_NETWORK_SEED_FILE),
             primary_seed,
@@ -90,46 +114,38 @@ impl KeyConfig {
             tn_datadir.validator_keys_path().join(WORKER_NETWORK_SEED_FILE),
             worker_seed,
         )?;
-        Ok(Self {
-            inner: Arc::new(KeyConfigInner {
-                primary_keypair,
-                primary_network_keypair,
-                worker_network_keypair,
-            }),
-        })
+        Ok(Self::from_keypair(primary_keypair, primary_seed, worker_seed))
     }
 
     /// Generate random keys with provided RNG.
     ///
     /// Useful for testing.
     pub fn with_random<R: CryptoRng + RngCore>(rng: &mut R) -> Self {
-        let primary_keypair = BlsKeypair::generate(rng);
-        let primary_network_keypair =
-            Self::generate_network_keypair(&primary_keypair, "primary network keypair");
-        let worker_network_keypair =
-            Self::generate_network_keypair(&primary_keypair, "worker network keypair");
-        Self {
-            inner: Arc::new(KeyConfigInner {
-                primary_keypair,
-                primary_network_keypair,
-                worker_network_keypair,
-            }),
-        }
+        Self::new_with_testing_key(BlsKeypair::generate(rng))
     }
 
     /// Create a config with a provided key- this is ONLY for testing.
     pub fn new_with_testing_key(primary_keypair: BlsKeypair) -> Self {
+        Self::from_keypair(primary_keypair, "primary network keypair", "worker network keypair")
+    }
+
+    /// Create a config from the primary BLS key and the seeds for its network keys.
+    ///
+    /// The keys are locked in memory if the OS allows it.
+    fn from_keypair(primary_keypair: BlsKeypair, primary_seed: &str, worker_seed: &str) -> Self {
         let primary_network_keypair =
-            Self::generate_network_keypair(&primary_keypair, "primary network keypair");
-        let worker_network_keypair =
-            Self::generate_network_keypair(&primary_keypair, "worker network keypair");
-        Self {
-            inner: Arc::new(KeyConfigInner {
-                primary_keypair,
-                primary_network_keypair,
-                worker_network_keypair,
-            }),
-        }
+            Self::generate_network_keypair(&primary_keypair, primary_seed);
+        let worker_network_keypair = Self::generate_network_keypair(&primary_keypair, worker_seed);
+        let mut inner = Arc::new(KeyConfigInner {
+            primary_keypair,
+            primary_network_keypair,
+            worker_network_keypair,
+            locked: false,
+        });
+        // lock the keys where they live for the rest of their life
+        let locked = lock_memory(inner.as_ref());
+        Arc::get_mut(&mut inner).expect("new arc is unique").locked = locked;
+        Self { inner }
     }
 
     /// Provide the primaries public key.
@@ -163,31 +179,127 @@ impl KeyConfig {
     /// holder of authority protocol key, and also ensures that the authority
     /// protocol public key exists.
     ///
-    /// The proof of possession is a [BlsSignature] committed over the intent message
-    /// `intent || message` (See more at [IntentMessage] and [Intent]).
-    /// The message is constructed as: [BlsPublicKey] || [Genesis].
+    /// The proof commits to the execution address and every network key in `primary_info`, so it
+    /// must be generated again whenever they change. See [generate_proof_of_possession_bls].
     pub fn generate_proof_of_possession_bls(
         &self,
+        execution_address: &Address,
+        primary_info: &PrimaryInfo,
         chain_spec: &ChainSpec,
     ) -> eyre::Result<BlsSignature> {
-        let mut msg = self.primary_public_key().as_ref().to_vec();
-        let genesis_bytes = encode(&chain_spec.genesis);
-        msg.extend_from_slice(genesis_bytes.as_slice());
-        let sig = BlsSignature::new_secure(
-            &IntentMessage::new(Intent::telcoin(IntentScope::ProofOfPossession), msg),
+        generate_proof_of_possession_bls(
+            &self.inner.primary_keypair,
+            execution_address,
+            primary_info,
+            chain_spec,
+        )
+    }
+
+    /// Rotate the primary and worker network keys.
+    ///
+    /// The new network keys are derived from the primary BLS key and fresh random seeds. Nothing
+    /// is written to disk- callers persist the returned [NetworkKeySeeds] with
+    /// [Self::save_network_seeds]. The [NetworkKeyRotation] is signed by the primary BLS key so
+    /// peers can verify the new keys belong to this authority and take effect at `epoch`.
+    pub fn rotate_network_keys(
+        &self,
+        epoch: Epoch,
+    ) -> eyre::Result<(Self, NetworkKeySeeds, NetworkKeyRotation)> {
+        let primary_keypair =
+            BlsKeypair::from_bytes(self.inner.primary_keypair.to_bytes().as_ref())?;
+        let seeds = NetworkKeySeeds::random();
+        let rotated = Self::from_keypair(primary_keypair, &seeds.primary, &seeds.worker);
+
+        let rotation = NetworkKeyRotation::new(
             &self.inner.primary_keypair,
+            self.primary_public_key(),
+            epoch,
+            self.primary_network_public_key(),
+            rotated.primary_network_public_key(),
+            self.worker_network_public_key(),
+            rotated.worker_network_public_key(),
         );
-        Ok(sig)
+
+        Ok((rotated, seeds, rotation))
+    }
+
+    /// Write the network key seeds to the validator keys directory.
+    ///
+    /// Both seeds are first written to a single pending rotation file, which is the commit point.
+    /// The seed files are then replaced and the pending file removed. If the process stops before
+    /// that finishes, [Self::read_config] applies the pending rotation so the node never loads
+    /// one new and one old network key.
+    pub fn save_network_seeds<TND: TelcoinDirs>(
+        tn_datadir: &TND,
+        seeds: &NetworkKeySeeds,
+    ) -> eyre::Result<()> {
+        let pending = tn_datadir.validator_keys_path().join(NETWORK_KEY_ROTATION_FILE);
+        write_file_atomic(pending, serde_json::to_vec(seeds)?)?;
+        Self::apply_network_key_rotation(tn_datadir)
+    }
+
+    /// Replace the seed files with a pending rotation, if there is one.
+    fn apply_network_key_rotation<TND: TelcoinDirs>(tn_datadir: &TND) -> eyre::Result<()> {
+        let keys_path = tn_datadir.validator_keys_path();
+        let pending = keys_path.join(NETWORK_KEY_ROTATION_FILE);
+        let contents = match std::fs::read(&pending) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e.into()),
+        };
+        let seeds: NetworkKeySeeds = serde_json::from_slice(&contents)?;
+        write_file_atomic(keys_path.join(PRIMARY_NETWORK_SEED_FILE), &seeds.primary)?;
+        write_file_atomic(keys_path.join(WORKER_NETWORK_SEED_FILE), &seeds.worker)?;
+        std::fs::remove_file(pending)?;
+        Ok(())
+    }
+
+    /// True if the keys are locked in memory.
+    pub fn is_memory_locked(&self) -> bool {
+        self.inner.locked
     }
 
     /// Derive a NetworkKeypair from a BLS signature, seed string and [DefaultHashFunction].
     /// This is deterministic for a given keypair and seed_str.
     fn generate_network_keypair(primary_keypair: &BlsKeypair, seed_str: &str) -> NetworkKeypair {
+        let mut signature = primary_keypair.sign(seed_str.as_bytes()).to_bytes();
         let mut hasher = DefaultHashFunction::new();
-        hasher.update(primary_keypair.sign(seed_str.as_bytes()).to_bytes());
-        let hash = hasher.finalize();
-        NetworkKeypair::ed25519_from_bytes(hash[0..32].to_vec()).expect("invalid network key bytes")
+        hasher.update(signature);
+        signature.zeroize();
+        let mut hash = hasher.finalize();
+        // the network key zeroes the bytes it is created from
+        let keypair = NetworkKeypair::ed25519_from_bytes(hash[0..32].to_vec())
+            .expect("invalid network key bytes");
+        hash.as_mut_slice().zeroize();
+        keypair
+    }
+}
+
+/// Lock the memory holding `value` so it is never swapped to disk.
+///
+/// Best effort- returns false if the OS does not support it or the process is over its locked
+/// memory limit (RLIMIT_MEMLOCK).
+#[cfg(unix)]
+fn lock_memory<T>(value: &T) -> bool {
+    // SAFETY: the range is a live allocation of `T`. mlock does not read or write the memory.
+    let locked = unsafe {
+        libc::mlock((value as *const T).cast::<libc::c_void>(), std::mem::size_of::<T>()) == 0
+    };
+    if !locked {
+        let error = std::io::Error::last_os_error();
+        tracing::debug!(
+            target: "telcoin::consensus_config",
+            %error,
+            "failed to lock keys in memory"
+        );
     }
+    locked
+}
+
+/// Memory locking is not supported on this platform.
+#[cfg(not(unix))]
+fn lock_memory<T>(_value: &T) -> bool {
+    false
 }
 
 impl BlsSigner for KeyConfig {
@@ -195,3 +307,90 @@ impl BlsSigner for KeyConfig {
         self.inner.primary_keypair.sign(msg)
     }
 }
+
+/// The seeds used with the primary BLS key to derive the node's network keys.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkKeySeeds {
+    /// Seed for the primary network keypair.
+    pub primary: String,
+    /// Seed for the worker network keypair.
+    pub worker: String,
+}
+
+impl NetworkKeySeeds {
+    /// Generate new random seeds.
+    fn random() -> Self {
+        let mut rng = ChaCha20Rng::from_entropy();
+        let mut random_seed = || {
+            let mut bytes = [0u8; 32];
+            rng.fill_bytes(&mut bytes);
+            bs58::encode(bytes).into_string()
+        };
+        Self { primary: random_seed(), worker: random_seed() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn test_rotate_network_keys() {
+        let tmp = tempdir().expect("tempdir created");
+        let datadir = tmp.path().to_path_buf();
+        std::fs::create_dir_all(datadir.validator_keys_path()).expect("keys dir created");
+        let key_config = KeyConfig::generate_and_save(&datadir).expect("keys generated");
+
+        let (rotated, seeds, rotation) =
+            key_config.rotate_network_keys(1).expect("network keys rotated");
+        assert!(rotation.verify_for_next_epoch(0));
+        assert_eq!(rotated.primary_public_key(), key_config.primary_public_key());
+        assert_ne!(rotated.primary_network_public_key(), key_config.primary_network_public_key());
+        assert_ne!(rotated.worker_network_public_key(), key_config.worker_network_public_key());
+        assert_eq!(rotation.new_primary_network_key, rotated.primary_network_public_key());
+        assert_eq!(rotation.old_worker_network_key, key_config.worker_network_public_key());
+
+        // rotated keys are loaded after saving the new seeds
+        KeyConfig::save_network_seeds(&datadir, &seeds).expect("seeds saved");
+        let loaded = KeyConfig::read_config(&datadir).expect("keys loaded");
+        assert_eq!(loaded.primary_network_public_key(), rotated.primary_network_public_key());
+        assert_eq!(loaded.worker_network_public_key(), rotated.worker_network_public_key());
+        assert!(!datadir.validator_keys_path().join(NETWORK_KEY_ROTATION_FILE).exists());
+    }
+
+    #[test]
+    fn test_interrupted_network_key_rotation() {
+        let tmp = tempdir().expect("tempdir created");
+        let datadir = tmp.path().to_path_buf();
+        std::fs::create_dir_all(datadir.validator_keys_path()).expect("keys dir created");
+        let key_config = KeyConfig::generate_and_save(&datadir).expect("keys generated");
+        let (rotated, seeds, _) = key_config.rotate_network_keys(1).expect("network keys rotated");
+
+        // simulate a crash after the rotation was committed and only one seed file was written
+        let keys_path = datadir.validator_keys_path();
+        std::fs::write(
+            keys_path.join(NETWORK_KEY_ROTATION_FILE),
+            serde_json::to_vec(&seeds).expect("seeds encode"),
+        )
+        .expect("pending rotation written");
+        std::fs::write(keys_path.join(PRIMARY_NETWORK_SEED_FILE), &seeds.primary)
+            .expect("primary seed written");
+
+        // both keys are rotated when loaded
+        let loaded = KeyConfig::read_config(&datadir).expect("keys loaded");
+        assert_eq!(loaded.primary_network_public_key(), rotated.primary_network_public_key());
+        assert_eq!(loaded.worker_network_public_key(), rotated.worker_network_public_key());
+        assert!(!keys_path.join(NETWORK_KEY_ROTATION_FILE).exists());
+    }
+
+    #[test]
+    fn test_debug_hides_private_key() {
+        let key_config = KeyConfig::with_random(&mut StdRng::seed_from_u64(0));
+        let private_key = key_config.inner.primary_keypair.to_bytes();
+        let debug = format!("{key_config:?}");
+        assert!(debug.contains(&key_config.primary_public_key().to_string()));
+        assert!(!debug.contains(&bs58::encode(private_key.as_ref()).into_string()));
+        assert!(!debug.contains(&format!("{:?}", private_key.as_ref())));
+    }
+}
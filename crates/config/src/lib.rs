@@ -2,12 +2,28 @@
 //! Crate for configuring a node.
 //!
 //! Node-specific and network-wide configurations.
+mod archive;
+pub use archive::*;
+mod chain_spec;
+pub use chain_spec::*;
+mod chaos;
+pub use chaos::*;
+mod clock;
+pub use clock::*;
 mod consensus;
 pub use consensus::*;
+mod distribution;
+pub use distribution::*;
+mod env;
+pub use env::*;
 mod keys;
 pub use keys::*;
 mod genesis;
 pub use genesis::*;
+mod governance;
+pub use governance::*;
+mod journal;
+pub use journal::*;
 mod node;
 pub use node::*;
 mod traits;
@@ -16,3 +32,5 @@ mod network;
 pub use network::*;
 mod retry;
 pub use retry::*;
+mod stream;
+pub use stream::*;
@@ -4,6 +4,11 @@ use libp2p::{request_response::ProtocolSupport, StreamProtocol};
 use std::time::Duration;
 use tn_types::Round;
 
+/// The request/response protocol of peers that don't send a correlation id with each request.
+///
+/// Requests on newer protocols start with a 16 byte id chosen by the sender.
+pub const LEGACY_REQ_RES_PROTOCOL: &str = "/telcoin-network/0.0.0";
+
 /// The container for all network configurations.
 #[derive(Debug, Default)]
 pub struct NetworkConfig {
@@ -56,18 +61,37 @@ pub struct LibP2pConfig {
     pub max_gossip_message_size: usize,
     /// The maximum duration to keep an idle connection alive between peers.
     pub max_idle_connection_timeout: Duration,
+    /// The maximum number of signed peer records kept and shared through peer exchange.
+    pub max_peer_exchange_records: usize,
+    /// How long a peer record received through peer exchange is kept.
+    ///
+    /// Records expire unless the peer signs a newer record, so addresses of departed peers are
+    /// not shared forever.
+    pub peer_exchange_record_ttl: Duration,
+    /// Nodes outside the committee dial peers discovered through peer exchange until they are
+    /// connected to this many peers.
+    pub peer_exchange_target_peers: usize,
+    /// The maximum number of peer connectivity events kept in storage for each network.
+    ///
+    /// The oldest events are dropped first. Operators read the history through the
+    /// `tnAdmin_peerHistory` RPC method.
+    pub max_peer_history_events: u64,
 }
 
 impl Default for LibP2pConfig {
     fn default() -> Self {
         Self {
-            supported_req_res_protocols: vec![(
-                StreamProtocol::new("/telcoin-network/0.0.0"),
-                ProtocolSupport::Full,
-            )],
+            supported_req_res_protocols: vec![
+                (StreamProtocol::new("/telcoin-network/0.1.0"), ProtocolSupport::Full),
+                (StreamProtocol::new(LEGACY_REQ_RES_PROTOCOL), ProtocolSupport::Full),
+            ],
             max_rpc_message_size: 1024 * 1024, // 1 MiB
             max_gossip_message_size: 12_000,   // 12kb
             max_idle_connection_timeout: Duration::from_secs(60 * 60), // 60min
+            max_peer_exchange_records: 64,
+            peer_exchange_record_ttl: Duration::from_secs(60 * 60), // 60min
+            peer_exchange_target_peers: 16,
+            max_peer_history_events: 10_000,
         }
     }
 }
@@ -125,6 +149,10 @@ pub struct SyncConfig {
     ///
     /// This value is used by `CertificateValidator::requires_direct_verification`
     pub certificate_verification_chunk_size: usize,
+    /// The number of rounds the node must fall behind before asking peers to push certificates.
+    ///
+    /// Peers only push certificates to nodes that are at least this many rounds behind them.
+    pub catch_up_round_threshold: Round,
 }
 
 impl Default for SyncConfig {
@@ -139,6 +167,7 @@ impl Default for SyncConfig {
             max_num_missing_certs_within_gc_round: 50,
             certificate_verification_round_interval: 50,
             certificate_verification_chunk_size: 50,
+            catch_up_round_threshold: 20,
         }
     }
 }
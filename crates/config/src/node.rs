@@ -1,12 +1,18 @@
 //! Configurations for the Telcoin Network.
 
-use crate::{ConfigTrait, ValidatorInfo};
+use crate::{
+    ArchiveConfig, ChaosConfig, ClockConfig, ConfigTrait, JournalConfig, LeaderScheduleParameters,
+    StreamConfig, TnChainSpec, ValidatorInfo,
+};
+use libp2p::{multiaddr::Protocol, PeerId};
 use reth_chainspec::ChainSpec;
 use serde::{Deserialize, Serialize};
-use std::time::Duration;
+use std::{collections::BTreeMap, time::Duration};
 use tn_types::{
     adiri_genesis, get_available_tcp_port, get_available_udp_port, Address, BlsPublicKey,
     BlsSignature, Genesis, Multiaddr, NetworkPublicKey, WorkerIndex,
+    DEFAULT_CERTIFICATE_CACHE_CAPACITY, DEFAULT_CERTIFICATE_CACHE_SHARDS,
+    DEFAULT_MAX_DEFERRED_TRANSACTIONS, DEFAULT_PRIMARY_PORT, DEFAULT_WORKER_PORT,
 };
 use tracing::info;
 
@@ -16,6 +22,14 @@ pub const BLS_KEYFILE: &str = "bls.key";
 pub const PRIMARY_NETWORK_SEED_FILE: &str = "primary.seed";
 /// The filename to use when reading/writing the network key seed used by all workers.
 pub const WORKER_NETWORK_SEED_FILE: &str = "worker.seed";
+/// The filename of a network key rotation that has not been applied to the seed files.
+pub const NETWORK_KEY_ROTATION_FILE: &str = "network-key-rotation.pending";
+
+/// The port spacing between consecutive node instances on the same host.
+///
+/// The primary uses the first port of each range and workers use the following ports, so an
+/// instance supports up to `INSTANCE_PORT_SPACING - 1` workers.
+pub const INSTANCE_PORT_SPACING: u16 = 10;
 
 /// Configuration for the Telcoin Network node.
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -31,6 +45,32 @@ pub struct Config {
 
     /// Is this an observer node?
     pub observer: bool,
+
+    /// Peers dialed on the primary network in addition to the committee.
+    ///
+    /// Each address ends with the peer's id, ie `/ip4/10.0.0.1/udp/44894/quic-v1/p2p/<PEER_ID>`.
+    #[serde(default)]
+    pub bootnodes: Vec<Multiaddr>,
+
+    /// The object store that epoch summaries are archived to, if any.
+    #[serde(default)]
+    pub archive: Option<ArchiveConfig>,
+
+    /// The journal files that consensus output is exported to, if any.
+    #[serde(default)]
+    pub journal: Option<JournalConfig>,
+
+    /// The streaming platform that executed blocks are published to, if any.
+    #[serde(default)]
+    pub stream: Option<StreamConfig>,
+
+    /// Monitoring of the local clock's offset from NTP time.
+    #[serde(default)]
+    pub clock: ClockConfig,
+
+    /// Fault injection for staging networks.
+    #[serde(default)]
+    pub chaos: ChaosConfig,
 }
 
 impl Default for Config {
@@ -42,6 +82,12 @@ impl Default for Config {
             // specify adiri chain spec
             genesis: adiri_genesis(),
             observer: false,
+            bootnodes: vec![],
+            archive: None,
+            journal: None,
+            stream: None,
+            clock: Default::default(),
+            chaos: Default::default(),
         }
     }
 }
@@ -82,6 +128,33 @@ impl Config {
         Ok(())
     }
 
+    /// Assign deterministic network ports for a local node instance.
+    ///
+    /// Instance 1 uses the default primary and worker ports. Each additional instance offsets
+    /// the ports by [INSTANCE_PORT_SPACING] so multiple nodes can run on the same host. The host
+    /// in each address is unchanged.
+    pub fn adjust_instance_ports(&mut self, instance: u16) -> eyre::Result<()> {
+        let offset = instance
+            .checked_sub(1)
+            .and_then(|i| i.checked_mul(INSTANCE_PORT_SPACING))
+            .ok_or_else(|| eyre::eyre!("invalid instance {instance}"))?;
+
+        let primary_info = &mut self.validator_info.primary_info;
+        primary_info.network_address =
+            with_port(&primary_info.network_address, DEFAULT_PRIMARY_PORT + offset);
+        for (worker_id, worker_info) in primary_info.worker_index.0.iter_mut() {
+            eyre::ensure!(
+                *worker_id < INSTANCE_PORT_SPACING - 1,
+                "worker {worker_id} exceeds the ports reserved for each instance"
+            );
+            let port = DEFAULT_WORKER_PORT + offset + worker_id;
+            worker_info.worker_address = with_port(&worker_info.worker_address, port);
+            worker_info.transactions = with_port(&worker_info.transactions, port);
+        }
+
+        Ok(())
+    }
+
     /// Return a reference to the
     pub fn genesis(&self) -> &Genesis {
         &self.genesis
@@ -92,6 +165,11 @@ impl Config {
         self.genesis.clone().into()
     }
 
+    /// Return the Telcoin Network parameters from the configured Genesis.
+    pub fn tn_chain_spec(&self) -> eyre::Result<TnChainSpec> {
+        TnChainSpec::from_genesis(&self.genesis)
+    }
+
     /// Return a reference to the exeuction address for suggested fee recipient.
     pub fn execution_address(&self) -> &Address {
         &self.validator_info.execution_address
@@ -110,6 +188,25 @@ impl Config {
     }
 }
 
+/// Return the peer id a bootnode address ends with.
+pub fn bootnode_peer_id(addr: &Multiaddr) -> Option<PeerId> {
+    match addr.iter().last()? {
+        Protocol::P2p(peer_id) => Some(peer_id),
+        _ => None,
+    }
+}
+
+/// Replace the TCP or UDP port in a multiaddr.
+fn with_port(addr: &Multiaddr, port: u16) -> Multiaddr {
+    addr.iter()
+        .map(|protocol| match protocol {
+            Protocol::Udp(_) => Protocol::Udp(port),
+            Protocol::Tcp(_) => Protocol::Tcp(port),
+            protocol => protocol,
+        })
+        .collect()
+}
+
 /// Holds all the node properties.
 ///
 /// An example is provided to
@@ -159,9 +256,74 @@ pub struct Parameters {
     /// Worker timeout when request vote from peers.
     #[serde(default = "Parameters::default_batch_vote_timeout")]
     pub batch_vote_timeout: Duration,
+    /// Capacity limits and eviction for each worker's transaction pool.
+    #[serde(default)]
+    pub txpool: TxPoolParameters,
+    /// Delete batches this long after they were committed and executed.
+    ///
+    /// Keeps batches forever when unset. Nodes missing a pruned batch fetch it from peers, so
+    /// some nodes in the network should keep their batches.
+    #[serde(with = "humantime_serde", default)]
+    pub batch_ttl: Option<Duration>,
+    /// When workers seal a batch of pending transactions.
+    #[serde(default)]
+    pub mining_mode: MiningMode,
+    /// How often an idle worker reports to its primary that it is alive.
+    ///
+    /// The primary records the last report from each worker, so it can tell a worker with no
+    /// transactions from a worker that is down.
+    #[serde(with = "humantime_serde", default = "Parameters::default_worker_heartbeat_interval")]
+    pub worker_heartbeat_interval: Duration,
+    /// How often workers ping their peers to sample round trip times.
+    ///
+    /// The samples are reported as metrics and order the peers batches are requested from. The
+    /// times are saved, so they also order committee dials and certificate fetches after a
+    /// restart.
+    #[serde(with = "humantime_serde", default = "Parameters::default_latency_probe_interval")]
+    pub latency_probe_interval: Duration,
+    /// Capacities of the node's internal channels.
+    #[serde(default)]
+    pub channels: ChannelParameters,
+    /// Limits on certificates peers gossip without a request.
+    #[serde(default)]
+    pub certificate_budget: CertificateBudgetParameters,
+    /// Limits on catch up requests answered for each peer.
+    #[serde(default)]
+    pub catch_up_budget: CatchUpBudgetParameters,
+    /// Timeouts and retries of requests sent to peers.
+    #[serde(default)]
+    pub requests: RequestParameters,
+    /// Size of the cache of decoded certificates in front of the certificate store.
+    #[serde(default)]
+    pub certificate_cache: CertificateCacheParameters,
+    /// How the leader schedule adapts to reputation scores.
+    ///
+    /// Every node must use the same values, so they are set from the chain spec and governance
+    /// for the epoch the node launches in rather than the local config. The node relaunches when
+    /// they change at an epoch boundary.
+    #[serde(skip)]
+    pub leader_schedule: LeaderScheduleParameters,
 }
 
 impl Parameters {
+    /// Check the parameters are usable, so a bad config fails when it is loaded rather than
+    /// when the node starts a task.
+    pub fn validate(&self) -> eyre::Result<()> {
+        // tasks tick at these intervals, which must not be zero
+        eyre::ensure!(self.batch_ttl != Some(Duration::ZERO), "batch ttl must not be zero");
+        eyre::ensure!(
+            !self.worker_heartbeat_interval.is_zero(),
+            "worker heartbeat interval must not be zero"
+        );
+        eyre::ensure!(
+            !self.latency_probe_interval.is_zero(),
+            "latency probe interval must not be zero"
+        );
+        self.channels.validate()?;
+        self.certificate_cache.validate()?;
+        self.requests.validate()
+    }
+
     fn default_header_num_of_batches_threshold() -> usize {
         5
     }
@@ -201,6 +363,441 @@ impl Parameters {
     fn default_batch_vote_timeout() -> Duration {
         Duration::from_secs(10)
     }
+
+    fn default_worker_heartbeat_interval() -> Duration {
+        Duration::from_secs(5)
+    }
+
+    fn default_latency_probe_interval() -> Duration {
+        Duration::from_secs(10)
+    }
+}
+
+/// Transaction pool settings that reth's `--txpool.*` arguments don't cover.
+///
+/// Pool capacity and queued transaction lifetime are set with reth's `--txpool.*` arguments.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct TxPoolParameters {
+    /// The maximum number of transactions accepted while the worker's batches can't reach quorum.
+    ///
+    /// Transactions are rejected once the cap is reached until quorum is restored.
+    pub max_deferred_count: usize,
+}
+
+impl Default for TxPoolParameters {
+    fn default() -> Self {
+        Self { max_deferred_count: DEFAULT_MAX_DEFERRED_TRANSACTIONS }
+    }
+}
+
+/// The kinds of internal channels that share a default capacity.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ChannelClass {
+    /// Channels between the primary's consensus tasks.
+    Consensus,
+    /// Broadcast channels to subscribers of consensus output and events.
+    Broadcast,
+    /// Event streams from the libp2p networks.
+    NetworkEvents,
+    /// Channels within a worker.
+    Worker,
+}
+
+/// Capacities of the node's internal channels.
+///
+/// Larger channels absorb longer bursts at the cost of memory. Once an mpsc channel is full its
+/// sender waits, and once a broadcast channel is full slow subscribers miss messages. Larger
+/// committees and higher throughput produce more messages per round, so deployments tune the
+/// capacity for each class of channel and override individual channels by name.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct ChannelParameters {
+    /// The capacity of channels between the primary's consensus tasks.
+    pub consensus: usize,
+    /// The capacity of broadcast channels.
+    pub broadcast: usize,
+    /// The capacity of the primary and worker network event streams.
+    pub network_events: usize,
+    /// The capacity of channels within a worker.
+    pub worker: usize,
+    /// Capacities of individual channels by name, e.g. `new_certificates`, replacing the capacity
+    /// of their class.
+    pub overrides: BTreeMap<String, usize>,
+}
+
+impl ChannelParameters {
+    /// The largest capacity of a channel.
+    ///
+    /// Channels allocate their capacity up front, so larger values only waste memory.
+    pub const MAX_CAPACITY: usize = 1_000_000;
+
+    /// Check every capacity is between one and [Self::MAX_CAPACITY].
+    pub fn validate(&self) -> eyre::Result<()> {
+        let classes = [
+            ("consensus", self.consensus),
+            ("broadcast", self.broadcast),
+            ("network_events", self.network_events),
+            ("worker", self.worker),
+        ];
+        let overrides = self.overrides.iter().map(|(name, capacity)| (name.as_str(), *capacity));
+        for (name, capacity) in classes.into_iter().chain(overrides) {
+            eyre::ensure!(
+                (1..=Self::MAX_CAPACITY).contains(&capacity),
+                "channel capacity for {name} must be between 1 and {}, got {capacity}",
+                Self::MAX_CAPACITY
+            );
+        }
+        Ok(())
+    }
+
+    /// The capacity of the channel `name` of the class.
+    ///
+    /// Channels hold at least one message.
+    pub fn capacity(&self, class: ChannelClass, name: &str) -> usize {
+        let class_capacity = match class {
+            ChannelClass::Consensus => self.consensus,
+            ChannelClass::Broadcast => self.broadcast,
+            ChannelClass::NetworkEvents => self.network_events,
+            ChannelClass::Worker => self.worker,
+        };
+        self.overrides.get(name).copied().unwrap_or(class_capacity).max(1)
+    }
+}
+
+impl Default for ChannelParameters {
+    fn default() -> Self {
+        Self {
+            consensus: 10_000,
+            broadcast: 10_000,
+            network_events: 1_000,
+            worker: 1_000,
+            overrides: BTreeMap::new(),
+        }
+    }
+}
+
+/// Limits on certificates peers gossip without a request.
+///
+/// Verifying a certificate is expensive, so each origin is only allowed a rate of certificates
+/// that scales with its stake. When too many certificates are being processed at once,
+/// certificates from origins outside the committee and origins that recently authored invalid
+/// certificates are shed first.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct CertificateBudgetParameters {
+    /// The certificates per second accepted from a committee member with average stake.
+    pub certificates_per_sec: f64,
+    /// The certificates a committee member with average stake can send in a burst.
+    pub burst: f64,
+    /// The maximum number of unsolicited certificates processed at once.
+    pub max_in_flight: usize,
+}
+
+impl Default for CertificateBudgetParameters {
+    fn default() -> Self {
+        Self { certificates_per_sec: 20.0, burst: 100.0, max_in_flight: 500 }
+    }
+}
+
+/// Limits on catch up requests answered for each peer.
+///
+/// Answering a catch up pushes up to 2,000 certificates to the peer, so each peer has one catch up
+/// answered at a time and earns tokens for more at a fixed rate.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct CatchUpBudgetParameters {
+    /// The catch up requests per second answered for a peer.
+    pub requests_per_sec: f64,
+    /// The catch up requests a peer can send in a burst.
+    pub burst: f64,
+}
+
+impl Default for CatchUpBudgetParameters {
+    fn default() -> Self {
+        Self { requests_per_sec: 0.2, burst: 3.0 }
+    }
+}
+
+/// Size of the cache of decoded certificates in front of the certificate store.
+///
+/// Consensus mostly reads certificates from the last few rounds, so the defaults hold 10 rounds
+/// of a committee of 100 validators with room to spare. The `certificate_cache` benchmark in
+/// tn-storage measures other sizes.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct CertificateCacheParameters {
+    /// The number of certificates cached.
+    pub capacity: usize,
+    /// The number of independently locked shards the capacity is split between.
+    ///
+    /// More shards let more tasks read certificates at once.
+    pub shards: usize,
+}
+
+impl CertificateCacheParameters {
+    /// Check the cache holds at least one certificate per shard.
+    pub fn validate(&self) -> eyre::Result<()> {
+        eyre::ensure!(self.shards > 0, "certificate cache needs at least one shard");
+        eyre::ensure!(
+            self.capacity >= self.shards,
+            "certificate cache capacity {} is smaller than its {} shards",
+            self.capacity,
+            self.shards
+        );
+        Ok(())
+    }
+}
+
+impl Default for CertificateCacheParameters {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_CERTIFICATE_CACHE_CAPACITY,
+            shards: DEFAULT_CERTIFICATE_CACHE_SHARDS,
+        }
+    }
+}
+
+/// Timeouts and retries of requests sent to peers.
+///
+/// Requests that time out or fail are retried, usually with another peer. Networks with higher
+/// latency between validators or larger payloads need longer timeouts, while short timeouts find
+/// a responsive peer sooner when some are down.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct RequestParameters {
+    /// Requests for votes on this primary's headers.
+    pub vote: VoteRequestParameters,
+    /// Requests for certificates missing from this primary's DAG.
+    pub certificate_fetch: CertificateFetchParameters,
+    /// Requests for batches missing from this worker's store.
+    pub batch_fetch: BatchFetchParameters,
+}
+
+impl RequestParameters {
+    /// Check the retry delays of every request.
+    pub fn validate(&self) -> eyre::Result<()> {
+        self.vote.backoff.validate().map_err(|e| e.wrap_err("invalid vote request backoff"))?;
+        self.batch_fetch.backoff.validate().map_err(|e| e.wrap_err("invalid batch fetch backoff"))
+    }
+}
+
+/// Delays between retries of a request that grow exponentially.
+///
+/// The defaults depend on the request: vote requests back off from 100ms by x2 up to 10s and
+/// batch fetches from 100ms by x2 up to 5s.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct RetryBackoff {
+    /// The delay before the first retry.
+    #[serde(with = "humantime_serde")]
+    pub initial_delay: Duration,
+    /// The factor each delay grows by, at least 1.
+    pub multiplier: u32,
+    /// The longest delay between retries.
+    #[serde(with = "humantime_serde")]
+    pub max_delay: Duration,
+}
+
+impl RetryBackoff {
+    /// Check the delays don't shrink between retries.
+    pub fn validate(&self) -> eyre::Result<()> {
+        eyre::ensure!(
+            self.multiplier >= 1,
+            "retry backoff multiplier must be at least 1, got {}",
+            self.multiplier
+        );
+        Ok(())
+    }
+
+    /// The delay before retry number `retry`, starting at 1.
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = self.multiplier.saturating_pow(retry.saturating_sub(1));
+        self.initial_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+/// Timeouts and retries of vote requests.
+///
+/// Votes are requested from each peer until it votes or the round advances. The first retry is
+/// immediate since the peer usually only needed the header's parents, later retries back off.
+/// The header is only resent to peers that haven't voted, and every request a peer lets time out
+/// doubles how long the next one waits.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct VoteRequestParameters {
+    /// How long to wait for a peer's vote. Defaults to 10s.
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+    /// The longest time to wait for a peer's vote after earlier requests timed out. Defaults to
+    /// 40s.
+    #[serde(with = "humantime_serde")]
+    pub max_timeout: Duration,
+    /// The delays between failed vote requests to the same peer. Defaults to 100ms growing x2 up
+    /// to 10s.
+    pub backoff: RetryBackoff,
+}
+
+impl Default for VoteRequestParameters {
+    fn default() -> Self {
+        Self {
+            timeout: Duration::from_secs(10),
+            max_timeout: Duration::from_secs(40),
+            backoff: RetryBackoff {
+                initial_delay: Duration::from_millis(100),
+                multiplier: 2,
+                max_delay: Duration::from_secs(10),
+            },
+        }
+    }
+}
+
+impl VoteRequestParameters {
+    /// How long to wait for a peer's vote after it let `timeouts` earlier requests time out.
+    pub fn timeout_after(&self, timeouts: u32) -> Duration {
+        let factor = 2u32.saturating_pow(timeouts);
+        self.timeout.saturating_mul(factor).min(self.max_timeout.max(self.timeout))
+    }
+}
+
+/// Timeouts and retries of certificate fetch requests.
+///
+/// Certificates are requested from one peer at a time. Every `request_interval` without a
+/// response the request is also sent to another peer, and the first response wins.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct CertificateFetchParameters {
+    /// How long to wait for a response before also asking another peer. Defaults to 5s.
+    ///
+    /// When every peer failed the fetcher waits this long before fetching again.
+    #[serde(with = "humantime_serde")]
+    pub request_interval: Duration,
+    /// How long to wait for a response after the last peer was asked. Defaults to 15s.
+    ///
+    /// A fetch gives up after `request_interval` for each peer plus this timeout.
+    #[serde(with = "humantime_serde")]
+    pub additional_timeout: Duration,
+}
+
+impl Default for CertificateFetchParameters {
+    fn default() -> Self {
+        Self {
+            request_interval: Duration::from_secs(5),
+            additional_timeout: Duration::from_secs(15),
+        }
+    }
+}
+
+/// Timeouts and retries of the batch requests that fetch certificate payloads.
+///
+/// The missing batches are requested from the peer with the lowest round trip time first.
+/// Batches a peer did not return are requested from the next peer, until every peer was asked.
+/// A peer slower than its round trip time plus `hedge_delay` is hedged by also asking the next
+/// peer. Payloads of committed certificates are fetched until every batch arrives.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct BatchFetchParameters {
+    /// How long to wait for a single peer's batches. Defaults to 3s.
+    #[serde(with = "humantime_serde")]
+    pub peer_timeout: Duration,
+    /// How much longer than its round trip time a peer has to respond before the next peer is
+    /// also asked. Defaults to 500ms.
+    #[serde(with = "humantime_serde")]
+    pub hedge_delay: Duration,
+    /// How long to wait for a round of requests to every peer. Defaults to 10s.
+    ///
+    /// Also bounds how long the primary waits for its worker to sync a header's batches.
+    #[serde(with = "humantime_serde")]
+    pub timeout: Duration,
+    /// The delays between rounds of requests that left batches missing. Defaults to 100ms
+    /// growing x2 up to 5s.
+    pub backoff: RetryBackoff,
+}
+
+impl Default for BatchFetchParameters {
+    fn default() -> Self {
+        Self {
+            peer_timeout: Duration::from_secs(3),
+            hedge_delay: Duration::from_millis(500),
+            timeout: Duration::from_secs(10),
+            backoff: RetryBackoff {
+                initial_delay: Duration::from_millis(100),
+                multiplier: 2,
+                max_delay: Duration::from_secs(5),
+            },
+        }
+    }
+}
+
+/// When workers seal a batch of pending transactions.
+///
+/// Sealing as soon as transactions are pending gives the lowest latency. Sealing on an interval
+/// collects more transactions in each batch, which lowers the number of batches each header
+/// carries at the cost of latency.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum MiningMode {
+    /// Seal a batch as soon as transactions are pending.
+    #[default]
+    Instant,
+    /// Seal the pending transactions every `interval`.
+    Interval {
+        /// The time between batches.
+        #[serde(with = "humantime_serde")]
+        interval: Duration,
+    },
+    /// Seal the pending transactions every `interval`, or sooner once they reach a target.
+    Hybrid {
+        /// The longest time between batches.
+        #[serde(with = "humantime_serde")]
+        interval: Duration,
+        /// Seal once the pending transactions' gas limits add up to this much gas.
+        #[serde(default)]
+        gas_target: Option<u64>,
+        /// Seal once the pending transactions' encoded size adds up to this many bytes.
+        #[serde(default)]
+        size_target: Option<usize>,
+    },
+}
+
+impl MiningMode {
+    /// The time between batches, `None` for instant mining.
+    pub fn interval(&self) -> Option<Duration> {
+        match self {
+            Self::Instant => None,
+            Self::Interval { interval } | Self::Hybrid { interval, .. } => Some(*interval),
+        }
+    }
+
+    /// The mode with the gas target replaced by `gas_target`, if any.
+    ///
+    /// Interval mining seals early once the target is reached. Instant mining has no target.
+    pub fn with_gas_target(self, gas_target: Option<u64>) -> Self {
+        let Some(target) = gas_target else {
+            return self;
+        };
+        match self {
+            Self::Instant => Self::Instant,
+            Self::Interval { interval } => {
+                Self::Hybrid { interval, gas_target: Some(target), size_target: None }
+            }
+            Self::Hybrid { interval, size_target, .. } => {
+                Self::Hybrid { interval, gas_target: Some(target), size_target }
+            }
+        }
+    }
+
+    /// Return true if pending transactions with the total `gas` and `size` are sealed without
+    /// waiting for the interval.
+    pub fn target_reached(&self, gas: u64, size: usize) -> bool {
+        match self {
+            Self::Instant => true,
+            Self::Interval { .. } => false,
+            Self::Hybrid { gas_target, size_target, .. } => {
+                gas_target.is_some_and(|target| gas >= target)
+                    || size_target.is_some_and(|target| size >= target)
+            }
+        }
+    }
 }
 
 /// Admin server settings.
@@ -270,6 +867,17 @@ impl Default for Parameters {
             max_concurrent_requests: Parameters::default_max_concurrent_requests(),
             prometheus_metrics: PrometheusMetricsParameters::default(),
             batch_vote_timeout: Parameters::default_batch_vote_timeout(),
+            txpool: TxPoolParameters::default(),
+            batch_ttl: None,
+            mining_mode: MiningMode::default(),
+            worker_heartbeat_interval: Parameters::default_worker_heartbeat_interval(),
+            latency_probe_interval: Parameters::default_latency_probe_interval(),
+            channels: ChannelParameters::default(),
+            certificate_budget: CertificateBudgetParameters::default(),
+            catch_up_budget: CatchUpBudgetParameters::default(),
+            requests: RequestParameters::default(),
+            certificate_cache: CertificateCacheParameters::default(),
+            leader_schedule: LeaderScheduleParameters::default(),
         }
     }
 }
@@ -294,6 +902,208 @@ impl Parameters {
         info!("Sync retry nodes set to {} nodes", self.sync_retry_nodes);
         info!("Max batch delay set to {} ms", self.max_batch_delay.as_millis());
         info!("Max concurrent requests set to {}", self.max_concurrent_requests);
+        info!("Worker heartbeat interval set to {} ms", self.worker_heartbeat_interval.as_millis());
+        info!("Latency probe interval set to {} ms", self.latency_probe_interval.as_millis());
         info!("Prometheus metrics server will run on {}", self.prometheus_metrics.socket_addr);
+        info!(
+            "Request timeouts set to {} ms for votes, {} ms for each batch peer",
+            self.requests.vote.timeout.as_millis(),
+            self.requests.batch_fetch.peer_timeout.as_millis()
+        );
+        info!("Deferred transactions limited to {}", self.txpool.max_deferred_count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_adjust_instance_ports() {
+        let mut config = Config::default();
+        config.validator_info.primary_info.network_address =
+            "/ip4/10.0.0.1/udp/1234/quic-v1".parse().expect("valid multiaddr");
+        config.adjust_instance_ports(3).expect("ports adjusted");
+
+        let primary_info = &config.validator_info.primary_info;
+        let expected: Multiaddr =
+            format!("/ip4/10.0.0.1/udp/{}/quic-v1", DEFAULT_PRIMARY_PORT + 20)
+                .parse()
+                .expect("valid multiaddr");
+        assert_eq!(primary_info.network_address, expected);
+        let worker = primary_info.worker_index.0.get(&0).expect("default worker");
+        assert!(worker
+            .worker_address
+            .iter()
+            .any(|protocol| protocol == Protocol::Udp(DEFAULT_WORKER_PORT + 20)));
+
+        assert!(config.adjust_instance_ports(0).is_err());
+    }
+
+    #[test]
+    fn test_bootnode_peer_id() {
+        let peer_id = PeerId::random();
+        let addr: Multiaddr = format!("/ip4/10.0.0.1/udp/1234/quic-v1/p2p/{peer_id}")
+            .parse()
+            .expect("valid multiaddr");
+        assert_eq!(bootnode_peer_id(&addr), Some(peer_id));
+
+        let addr: Multiaddr = "/ip4/10.0.0.1/udp/1234/quic-v1".parse().expect("valid multiaddr");
+        assert_eq!(bootnode_peer_id(&addr), None);
+    }
+
+    #[test]
+    fn test_txpool_parameters_default_when_missing() {
+        let yaml = serde_yaml::to_string(&Parameters::default()).expect("parameters serialize");
+        let mut value: serde_yaml::Value = serde_yaml::from_str(&yaml).expect("valid yaml");
+        value.as_mapping_mut().expect("mapping").remove("txpool");
+        let params: Parameters = serde_yaml::from_value(value).expect("parameters deserialize");
+        assert_eq!(params.txpool, TxPoolParameters::default());
+
+        let txpool: TxPoolParameters =
+            serde_yaml::from_str("max_deferred_count: 8").expect("partial txpool deserializes");
+        assert_eq!(txpool.max_deferred_count, 8);
+    }
+
+    #[test]
+    fn test_mining_mode() {
+        let yaml = serde_yaml::to_string(&Parameters::default()).expect("parameters serialize");
+        let mut value: serde_yaml::Value = serde_yaml::from_str(&yaml).expect("valid yaml");
+        value.as_mapping_mut().expect("mapping").remove("mining_mode");
+        let params: Parameters = serde_yaml::from_value(value).expect("parameters deserialize");
+        assert_eq!(params.mining_mode, MiningMode::Instant);
+
+        let mode: MiningMode =
+            serde_yaml::from_str("mode: hybrid\ninterval: 500ms\ngas_target: 1000")
+                .expect("hybrid mode deserializes");
+        assert_eq!(
+            mode,
+            MiningMode::Hybrid {
+                interval: Duration::from_millis(500),
+                gas_target: Some(1_000),
+                size_target: None
+            }
+        );
+        assert_eq!(mode.interval(), Some(Duration::from_millis(500)));
+        assert!(!mode.target_reached(999, usize::MAX));
+        assert!(mode.target_reached(1_000, 0));
+
+        let mode = MiningMode::Interval { interval: Duration::from_secs(1) };
+        assert!(!mode.target_reached(u64::MAX, usize::MAX));
+        // a gas target makes interval mining seal early
+        assert_eq!(mode.with_gas_target(None), mode);
+        assert!(mode.with_gas_target(Some(1_000)).target_reached(1_000, 0));
+        assert_eq!(MiningMode::Instant.with_gas_target(Some(1_000)), MiningMode::Instant);
+        assert!(MiningMode::Instant.target_reached(0, 0));
+        assert_eq!(MiningMode::Instant.interval(), None);
+    }
+
+    #[test]
+    fn test_idle_worker_parameters() {
+        let yaml = serde_yaml::to_string(&Parameters::default()).expect("parameters serialize");
+        let mut value: serde_yaml::Value = serde_yaml::from_str(&yaml).expect("valid yaml");
+        let mapping = value.as_mapping_mut().expect("mapping");
+        mapping.remove("worker_heartbeat_interval");
+        let params: Parameters = serde_yaml::from_value(value).expect("parameters deserialize");
+        assert_eq!(params.worker_heartbeat_interval, Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_channel_parameters() {
+        let yaml = serde_yaml::to_string(&Parameters::default()).expect("parameters serialize");
+        let mut value: serde_yaml::Value = serde_yaml::from_str(&yaml).expect("valid yaml");
+        value.as_mapping_mut().expect("mapping").remove("channels");
+        let params: Parameters = serde_yaml::from_value(value).expect("parameters deserialize");
+        assert_eq!(params.channels, ChannelParameters::default());
+
+        let channels: ChannelParameters =
+            serde_yaml::from_str("broadcast: 500\noverrides:\n  new_certificates: 20\n  events: 0")
+                .expect("partial channels deserialize");
+        assert_eq!(channels.capacity(ChannelClass::Broadcast, "consensus_output"), 500);
+        assert_eq!(channels.capacity(ChannelClass::Consensus, "new_certificates"), 20);
+        assert_eq!(channels.capacity(ChannelClass::Consensus, "parents"), 10_000);
+        // channels always hold a message
+        assert_eq!(channels.capacity(ChannelClass::Broadcast, "events"), 1);
+        // but configs with empty channels are rejected
+        assert!(channels.validate().is_err());
+
+        assert!(ChannelParameters::default().validate().is_ok());
+        let channels = ChannelParameters { worker: 0, ..Default::default() };
+        assert!(channels.validate().is_err());
+        let mut channels = ChannelParameters::default();
+        channels.overrides.insert("parents".to_string(), ChannelParameters::MAX_CAPACITY + 1);
+        assert!(channels.validate().is_err());
+        channels.overrides.insert("parents".to_string(), ChannelParameters::MAX_CAPACITY);
+        assert!(channels.validate().is_ok());
+    }
+
+    #[test]
+    fn test_zero_intervals_are_rejected() {
+        assert!(Parameters::default().validate().is_ok());
+        let params = Parameters { batch_ttl: Some(Duration::ZERO), ..Default::default() };
+        assert!(params.validate().is_err());
+        let params = Parameters { worker_heartbeat_interval: Duration::ZERO, ..Default::default() };
+        assert!(params.validate().is_err());
+        let params = Parameters { latency_probe_interval: Duration::ZERO, ..Default::default() };
+        assert!(params.validate().is_err());
+    }
+
+    #[test]
+    fn test_certificate_cache_parameters() {
+        let yaml = serde_yaml::to_string(&Parameters::default()).expect("parameters serialize");
+        let mut value: serde_yaml::Value = serde_yaml::from_str(&yaml).expect("valid yaml");
+        value.as_mapping_mut().expect("mapping").remove("certificate_cache");
+        let params: Parameters = serde_yaml::from_value(value).expect("parameters deserialize");
+        assert_eq!(params.certificate_cache, CertificateCacheParameters::default());
+        assert!(params.validate().is_ok());
+
+        let cache: CertificateCacheParameters =
+            serde_yaml::from_str("capacity: 4000").expect("partial cache deserialize");
+        assert_eq!(cache.capacity, 4_000);
+        assert_eq!(cache.shards, DEFAULT_CERTIFICATE_CACHE_SHARDS);
+
+        assert!(CertificateCacheParameters { shards: 0, ..Default::default() }.validate().is_err());
+        assert!(CertificateCacheParameters { capacity: 4, shards: 8 }.validate().is_err());
+    }
+
+    #[test]
+    fn test_request_parameters() {
+        let yaml = serde_yaml::to_string(&Parameters::default()).expect("parameters serialize");
+        let mut value: serde_yaml::Value = serde_yaml::from_str(&yaml).expect("valid yaml");
+        value.as_mapping_mut().expect("mapping").remove("requests");
+        let params: Parameters = serde_yaml::from_value(value).expect("parameters deserialize");
+        assert_eq!(params.requests, RequestParameters::default());
+
+        let requests: RequestParameters =
+            serde_yaml::from_str("vote:\n  timeout: 3s\nbatch_fetch:\n  peer_timeout: 500ms")
+                .expect("partial requests deserialize");
+        assert_eq!(requests.vote.timeout, Duration::from_secs(3));
+        assert_eq!(requests.vote.backoff, VoteRequestParameters::default().backoff);
+        assert_eq!(requests.batch_fetch.peer_timeout, Duration::from_millis(500));
+        assert_eq!(requests.certificate_fetch, CertificateFetchParameters::default());
+
+        let backoff = requests.vote.backoff;
+        assert_eq!(backoff.delay(1), Duration::from_millis(100));
+        assert_eq!(backoff.delay(3), Duration::from_millis(400));
+        assert_eq!(backoff.delay(100), Duration::from_secs(10));
+
+        // timeouts escalate up to the max timeout
+        let vote = VoteRequestParameters::default();
+        assert_eq!(vote.timeout_after(0), Duration::from_secs(10));
+        assert_eq!(vote.timeout_after(1), Duration::from_secs(20));
+        assert_eq!(vote.timeout_after(2), Duration::from_secs(40));
+        assert_eq!(vote.timeout_after(100), Duration::from_secs(40));
+        assert_eq!(requests.vote.timeout_after(0), Duration::from_secs(3));
+
+        // backoffs that shrink are rejected
+        assert!(RequestParameters::default().validate().is_ok());
+        let mut params = Parameters::default();
+        params.requests.batch_fetch.backoff.multiplier = 0;
+        assert!(params.validate().is_err());
+        let mut requests = RequestParameters::default();
+        requests.vote.backoff.multiplier = 1;
+        assert!(requests.validate().is_ok());
+        requests.vote.backoff.multiplier = 0;
+        assert!(requests.validate().is_err());
     }
 }
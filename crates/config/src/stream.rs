@@ -0,0 +1,118 @@
+//! Configuration for streaming executed blocks to Kafka or NATS.
+
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, path::PathBuf, time::Duration};
+
+/// Publishes executed blocks, receipts, and consensus metadata to a streaming platform.
+///
+/// Messages are published to `<topic_prefix>.blocks`, `<topic_prefix>.receipts` and
+/// `<topic_prefix>.consensus`. The node must be built with the feature for the sink, `kafka` or
+/// `nats`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StreamConfig {
+    /// The platform messages are published to.
+    pub sink: StreamSinkConfig,
+    /// The prefix of every topic.
+    ///
+    /// The stream's progress is saved under the prefix, so changing it starts a new stream.
+    #[serde(default = "StreamConfig::default_topic_prefix")]
+    pub topic_prefix: String,
+    /// The first block to publish if the stream has no saved progress.
+    ///
+    /// A new stream starts with the next executed block if unset.
+    #[serde(default)]
+    pub start_block: Option<u64>,
+    /// The time to wait for the broker to acknowledge a message.
+    #[serde(with = "humantime_serde", default = "StreamConfig::default_publish_timeout")]
+    pub publish_timeout: Duration,
+    /// The time to wait before publishing again after a failure.
+    #[serde(with = "humantime_serde", default = "StreamConfig::default_retry_interval")]
+    pub retry_interval: Duration,
+}
+
+impl StreamConfig {
+    /// The default topic prefix.
+    fn default_topic_prefix() -> String {
+        "telcoin".to_string()
+    }
+
+    /// The default time to wait for acknowledgements.
+    fn default_publish_timeout() -> Duration {
+        Duration::from_secs(10)
+    }
+
+    /// The default time between retries.
+    fn default_retry_interval() -> Duration {
+        Duration::from_secs(5)
+    }
+
+    /// The full name of the topic.
+    pub fn topic(&self, name: &str) -> String {
+        format!("{}.{name}", self.topic_prefix)
+    }
+}
+
+/// The streaming platform and how to connect to it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamSinkConfig {
+    /// Kafka brokers.
+    Kafka {
+        /// The bootstrap servers, ie `broker1:9092,broker2:9092`.
+        brokers: String,
+        /// Additional librdkafka producer properties, ie `security.protocol`.
+        #[serde(default)]
+        properties: BTreeMap<String, String>,
+    },
+    /// A NATS server with JetStream enabled.
+    ///
+    /// Every topic must be captured by a JetStream stream.
+    Nats {
+        /// The server url, ie `nats://localhost:4222`.
+        url: String,
+        /// The credentials file used to authenticate, if any.
+        #[serde(default)]
+        credentials_file: Option<PathBuf>,
+    },
+}
+
+impl StreamSinkConfig {
+    /// The name of the feature the node needs for this sink.
+    pub fn feature(&self) -> &'static str {
+        match self {
+            Self::Kafka { .. } => "kafka",
+            Self::Nats { .. } => "nats",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{StreamConfig, StreamSinkConfig};
+    use std::time::Duration;
+
+    #[test]
+    fn test_stream_config() {
+        let config: StreamConfig = serde_yaml::from_str(
+            "sink:\n  type: kafka\n  brokers: localhost:9092\n  properties:\n    acks: all\n",
+        )
+        .expect("config");
+        assert_eq!(config.topic("blocks"), "telcoin.blocks");
+        assert_eq!(config.start_block, None);
+        assert_eq!(config.publish_timeout, Duration::from_secs(10));
+        assert_eq!(config.sink.feature(), "kafka");
+
+        let config: StreamConfig = serde_yaml::from_str(
+            "sink:\n  type: nats\n  url: nats://localhost:4222\ntopic_prefix: exchange\n",
+        )
+        .expect("config");
+        assert_eq!(
+            config.sink,
+            StreamSinkConfig::Nats {
+                url: "nats://localhost:4222".to_string(),
+                credentials_file: None
+            }
+        );
+        assert_eq!(config.topic("receipts"), "exchange.receipts");
+    }
+}
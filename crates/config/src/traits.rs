@@ -10,12 +10,14 @@ use std::{
 use tracing::info;
 
 /// The serialization format for the config.
-#[derive(PartialEq, Debug)]
+#[derive(PartialEq, Debug, Clone, Copy)]
 pub enum ConfigFmt {
     /// Serialize using YAML.
     YAML,
     /// Serialize using JSON.
     JSON,
+    /// Serialize using TOML.
+    TOML,
 }
 
 impl ConfigFmt {
@@ -23,6 +25,41 @@ impl ConfigFmt {
     pub fn is_json(&self) -> bool {
         *self == Self::JSON
     }
+
+    /// Identify the format from the path's file extension.
+    ///
+    /// Paths ending in `.json` or `.toml` use the matching format. All other paths use YAML.
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some("json") => Self::JSON,
+            Some("toml") => Self::TOML,
+            _ => Self::YAML,
+        }
+    }
+
+    /// Deserialize a config in this format.
+    fn deserialize<T: DeserializeOwned>(&self, contents: &str) -> eyre::Result<T> {
+        match self {
+            Self::YAML => serde_yaml::from_str(contents).with_context(|| "bad yaml data"),
+            Self::JSON => serde_json::from_str(contents).with_context(|| "bad json data"),
+            Self::TOML => toml::from_str(contents).with_context(|| "bad toml data"),
+        }
+    }
+
+    /// Serialize a config in this format.
+    fn serialize<T: Serialize>(&self, cfg: &T) -> eyre::Result<String> {
+        match self {
+            Self::YAML => {
+                serde_yaml::to_string(cfg).with_context(|| "Failed to serialize config to yaml")
+            }
+            Self::JSON => {
+                serde_json::to_string(cfg).with_context(|| "Failed to serialize config to json")
+            }
+            Self::TOML => {
+                toml::to_string(cfg).with_context(|| "Failed to serialize config to toml")
+            }
+        }
+    }
 }
 
 /// Based on `confy` crate.
@@ -44,11 +81,7 @@ pub trait ConfigTrait {
                 file.read_to_string(&mut cfg_string)?;
 
                 // return deserialized data in specified format
-                if fmt.is_json() {
-                    serde_json::from_str(&cfg_string).with_context(|| "bad json data")
-                } else {
-                    serde_yaml::from_str(&cfg_string).with_context(|| "bad yaml data")
-                }
+                fmt.deserialize(&cfg_string)
             }
             Err(ref e) if e.kind() == NotFound => {
                 if let Some(parent) = path.as_ref().parent() {
@@ -81,11 +114,7 @@ pub trait ConfigTrait {
             .with_context(|| "directory creation failed while storing")?;
 
         // serialize in specified fmt
-        let s = if fmt.is_json() {
-            serde_json::to_string(&cfg).with_context(|| "Failed to serialize config to json")?
-        } else {
-            serde_yaml::to_string(&cfg).with_context(|| "Failed to serialize config to yaml")?
-        };
+        let s = fmt.serialize(&cfg)?;
 
         let mut f = OpenOptions::new()
             .write(true)
@@ -97,6 +126,48 @@ pub trait ConfigTrait {
         f.write_all(s.as_bytes()).with_context(|| "Failed to write configuration file")?;
         Ok(())
     }
+
+    /// Save a configuration to the given path, replacing any existing file atomically.
+    fn store_path_atomic<T: Serialize>(
+        path: impl AsRef<Path>,
+        cfg: T,
+        fmt: ConfigFmt,
+    ) -> eyre::Result<()> {
+        let path = path.as_ref();
+        let config_dir =
+            path.parent().with_context(|| format!("{:?} is a root or prefix", path))?;
+        fs::create_dir_all(config_dir)
+            .with_context(|| "directory creation failed while storing")?;
+
+        let s = fmt.serialize(&cfg)?;
+
+        write_file_atomic(path, s)
+    }
+}
+
+/// Replace the file at `path` with `contents` atomically.
+///
+/// The contents are written and synced to a temporary file in the same directory, then renamed over
+/// the destination. Readers see either the previous file or the new file, never a partial write.
+pub fn write_file_atomic(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> eyre::Result<()> {
+    let path = path.as_ref();
+    let file_name = path
+        .file_name()
+        .with_context(|| format!("{:?} does not have a file name", path))?
+        .to_string_lossy();
+    let tmp_path = path.with_file_name(format!(".{file_name}.tmp"));
+
+    let mut f = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(true)
+        .open(&tmp_path)
+        .with_context(|| format!("Failed to open temporary file {:?}", tmp_path))?;
+    f.write_all(contents.as_ref()).with_context(|| "Failed to write temporary file")?;
+    f.sync_all().with_context(|| "Failed to sync temporary file")?;
+    fs::rename(&tmp_path, path)
+        .with_context(|| format!("Failed to move {:?} to {:?}", tmp_path, path))?;
+    Ok(())
 }
 
 /// Telcoin Network specific directories.
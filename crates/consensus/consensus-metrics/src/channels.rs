@@ -0,0 +1,252 @@
+//! Registry of bounded channels for diagnosing backpressure.
+//!
+//! Every metered channel registers its capacity and depth here. The depth of each channel is
+//! already exported by its own gauge; the registry adds the capacity and the highest depth seen so
+//! operators can find the channels that are close to full.
+
+use once_cell::sync::Lazy;
+use parking_lot::RwLock;
+use prometheus::{default_registry, register_int_gauge_vec_with_registry, IntGaugeVec};
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+/// Returns the current depth of a channel or `None` once the channel is closed.
+type DepthProbe = Box<dyn Fn() -> Option<usize> + Send + Sync>;
+
+/// A registered channel.
+struct ChannelEntry {
+    /// The number of messages the channel can hold.
+    capacity: usize,
+    /// Read the current depth.
+    depth: DepthProbe,
+    /// The highest depth recorded.
+    high_water: AtomicUsize,
+}
+
+/// Prometheus gauges for every registered channel by name.
+struct ChannelGauges {
+    /// The capacity of each channel.
+    capacity: IntGaugeVec,
+    /// The highest depth of each channel since it was created.
+    high_water: IntGaugeVec,
+}
+
+impl ChannelGauges {
+    fn try_new() -> Result<Self, prometheus::Error> {
+        Ok(Self {
+            capacity: register_int_gauge_vec_with_registry!(
+                "channel_capacity",
+                "The number of messages a bounded channel can hold.",
+                &["name"],
+                default_registry(),
+            )?,
+            high_water: register_int_gauge_vec_with_registry!(
+                "channel_high_water_mark",
+                "The highest number of messages queued in a bounded channel.",
+                &["name"],
+                default_registry(),
+            )?,
+        })
+    }
+}
+
+/// All registered channels by name.
+static CHANNELS: Lazy<RwLock<BTreeMap<String, Arc<ChannelEntry>>>> = Lazy::new(Default::default);
+
+/// The gauges, if they could be registered.
+static GAUGES: Lazy<Option<ChannelGauges>> = Lazy::new(|| ChannelGauges::try_new().ok());
+
+/// Records the depth of a registered channel.
+#[derive(Clone)]
+pub struct ChannelHandle {
+    /// The channel's name.
+    name: String,
+    /// The registry entry.
+    entry: Arc<ChannelEntry>,
+}
+
+impl std::fmt::Debug for ChannelHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "ChannelHandle({})", self.name)
+    }
+}
+
+impl ChannelHandle {
+    /// Record the channel's current depth, raising the high-water mark if needed.
+    pub fn record(&self, depth: usize) {
+        let previous = self.entry.high_water.fetch_max(depth, Ordering::Relaxed);
+        if depth > previous {
+            if let Some(gauges) = GAUGES.as_ref() {
+                gauges.high_water.with_label_values(&[&self.name]).set(depth as i64);
+            }
+        }
+    }
+}
+
+/// Register a bounded channel by name.
+///
+/// `depth` returns the number of queued messages, or `None` once the channel is closed so the
+/// channel is removed from the registry. A name already used by an open channel gets a numeric
+/// suffix, ie `name_2`, so neither channel hides the other. Closed channels are replaced.
+pub fn register_channel(
+    name: impl Into<String>,
+    capacity: usize,
+    depth: impl Fn() -> Option<usize> + Send + Sync + 'static,
+) -> ChannelHandle {
+    let base = name.into();
+    let entry = Arc::new(ChannelEntry {
+        capacity,
+        depth: Box::new(depth),
+        high_water: AtomicUsize::new(0),
+    });
+
+    let mut registry = CHANNELS.write();
+    let mut name = base.clone();
+    let mut suffix = 1;
+    while registry.get(&name).is_some_and(|entry| (entry.depth)().is_some()) {
+        suffix += 1;
+        name = format!("{base}_{suffix}");
+    }
+    registry.insert(name.clone(), entry.clone());
+    drop(registry);
+
+    if let Some(gauges) = GAUGES.as_ref() {
+        gauges.capacity.with_label_values(&[&name]).set(capacity as i64);
+        gauges.high_water.with_label_values(&[&name]).set(0);
+    }
+    ChannelHandle { name, entry }
+}
+
+/// The backlog of a bounded channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChannelUtilization {
+    /// The channel's name.
+    pub name: String,
+    /// The number of queued messages.
+    pub depth: usize,
+    /// The number of messages the channel can hold.
+    pub capacity: usize,
+    /// The highest number of queued messages since the channel was created.
+    pub high_water: usize,
+}
+
+impl ChannelUtilization {
+    /// The fraction of the channel's capacity in use.
+    pub fn utilization(&self) -> f64 {
+        if self.capacity == 0 {
+            return 0.0;
+        }
+        self.depth as f64 / self.capacity as f64
+    }
+}
+
+/// Sample every open registered channel, sorted by name.
+pub fn channel_utilization() -> Vec<ChannelUtilization> {
+    let mut closed = Vec::new();
+    let channels = CHANNELS
+        .read()
+        .iter()
+        .filter_map(|(name, entry)| {
+            let Some(depth) = (entry.depth)() else {
+                closed.push(name.clone());
+                return None;
+            };
+            let handle = ChannelHandle { name: name.clone(), entry: entry.clone() };
+            handle.record(depth);
+            Some(ChannelUtilization {
+                name: name.clone(),
+                depth,
+                capacity: entry.capacity,
+                high_water: entry.high_water.load(Ordering::Relaxed),
+            })
+        })
+        .collect();
+
+    if !closed.is_empty() {
+        let mut registry = CHANNELS.write();
+        for name in closed {
+            // only remove the entry if it is still closed and was not replaced
+            if registry.get(&name).is_some_and(|entry| (entry.depth)().is_none()) {
+                registry.remove(&name);
+            }
+        }
+    }
+
+    channels
+}
+
+/// Sample the open registered channels with at least `threshold` of their capacity in use.
+pub fn saturated_channels(threshold: f64) -> Vec<ChannelUtilization> {
+    channel_utilization().into_iter().filter(|channel| channel.utilization() >= threshold).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{channel_utilization, register_channel, saturated_channels};
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    #[test]
+    fn test_saturated_channels() {
+        let depth = Arc::new(AtomicUsize::new(9));
+        let probe = depth.clone();
+        let handle = register_channel("test_saturated_channel", 10, move || {
+            Some(probe.load(Ordering::Relaxed))
+        });
+        register_channel("test_closed_channel", 10, || None);
+
+        let saturated = saturated_channels(0.8);
+        let channel = saturated
+            .iter()
+            .find(|channel| channel.name == "test_saturated_channel")
+            .expect("channel is saturated");
+        assert_eq!(channel.depth, 9);
+        assert_eq!(channel.high_water, 9);
+        assert!(!saturated.iter().any(|channel| channel.name == "test_closed_channel"));
+
+        // the high-water mark remains after the backlog drains
+        depth.store(1, Ordering::Relaxed);
+        handle.record(1);
+        assert!(!saturated_channels(0.8).iter().any(|c| c.name == "test_saturated_channel"));
+        let channel = channel_utilization()
+            .into_iter()
+            .find(|channel| channel.name == "test_saturated_channel")
+            .expect("channel registered");
+        assert_eq!(channel.depth, 1);
+        assert_eq!(channel.high_water, 9);
+    }
+
+    #[test]
+    fn test_duplicate_channel_names() {
+        let first = register_channel("test_duplicate_channel", 10, || Some(1));
+        let second = register_channel("test_duplicate_channel", 20, || Some(2));
+        assert_eq!(format!("{first:?}"), "ChannelHandle(test_duplicate_channel)");
+        assert_eq!(format!("{second:?}"), "ChannelHandle(test_duplicate_channel_2)");
+
+        // both channels are listed
+        let channels: Vec<_> = channel_utilization()
+            .into_iter()
+            .filter(|channel| channel.name.starts_with("test_duplicate_channel"))
+            .map(|channel| (channel.name, channel.capacity))
+            .collect();
+        assert_eq!(
+            channels,
+            [
+                ("test_duplicate_channel".to_string(), 10),
+                ("test_duplicate_channel_2".to_string(), 20)
+            ]
+        );
+
+        // closed channels are replaced
+        register_channel("test_replaced_channel", 10, || None);
+        let replaced = register_channel("test_replaced_channel", 10, || Some(0));
+        assert_eq!(format!("{replaced:?}"), "ChannelHandle(test_replaced_channel)");
+    }
+}
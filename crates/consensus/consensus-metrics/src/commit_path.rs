@@ -0,0 +1,89 @@
+//! Latency of each stage of the commit path.
+//!
+//! The time it takes for a batch to be executed is split into the stages below, each recorded in
+//! the `commit_path_latency` histogram with a `stage` label. A performance regression shows up in
+//! the stage of the component that caused it.
+//!
+//! batch sealed → digest in header → header certified → sub-dag committed → output ready → executed
+//!
+//! Stages measured between timestamps in consensus messages have a resolution of one second. The
+//! others are measured within a single component and are precise.
+
+use prometheus::{default_registry, register_histogram_vec_with_registry, HistogramVec, Registry};
+use std::{sync::LazyLock, time::Duration};
+
+/// Buckets in seconds.
+const LATENCY_SEC_BUCKETS: &[f64] =
+    &[0.005, 0.01, 0.02, 0.05, 0.1, 0.25, 0.5, 1.0, 2.0, 3.0, 5.0, 10.0, 20.0, 40.0, 60.0];
+
+/// The commit path metrics shared by the primary, executor, and engine.
+static COMMIT_PATH_METRICS: LazyLock<CommitPathMetrics> = LazyLock::new(CommitPathMetrics::default);
+
+/// A stage of the commit path.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommitStage {
+    /// From a batch being sealed by a worker to its digest being included in a proposed header.
+    BatchToHeader,
+    /// From this node proposing a header to the header being certified.
+    HeaderToCertificate,
+    /// From a certificate being created to its sub-dag being committed.
+    CertificateToCommit,
+    /// From a sub-dag being committed to its batches being fetched for execution.
+    CommitToOutput,
+    /// From the engine receiving consensus output to the output being executed.
+    OutputToExecution,
+}
+
+impl CommitStage {
+    /// The label for the stage.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::BatchToHeader => "batch_to_header",
+            Self::HeaderToCertificate => "header_to_certificate",
+            Self::CertificateToCommit => "certificate_to_commit",
+            Self::CommitToOutput => "commit_to_output",
+            Self::OutputToExecution => "output_to_execution",
+        }
+    }
+
+    /// Record the latency of the stage.
+    pub fn observe(&self, latency: Duration) {
+        COMMIT_PATH_METRICS
+            .commit_path_latency
+            .with_label_values(&[self.as_str()])
+            .observe(latency.as_secs_f64());
+    }
+}
+
+#[derive(Clone, Debug)]
+struct CommitPathMetrics {
+    /// The latency of each stage of the commit path.
+    commit_path_latency: HistogramVec,
+}
+
+impl CommitPathMetrics {
+    fn try_new(registry: &Registry) -> Result<Self, prometheus::Error> {
+        Ok(Self {
+            commit_path_latency: register_histogram_vec_with_registry!(
+                "commit_path_latency",
+                "The latency of each stage from a batch being sealed to its execution",
+                &["stage"],
+                LATENCY_SEC_BUCKETS.to_vec(),
+                registry
+            )?,
+        })
+    }
+}
+
+impl Default for CommitPathMetrics {
+    fn default() -> Self {
+        // registration only fails if the name is already taken, don't panic over metrics
+        match Self::try_new(default_registry()) {
+            Ok(metrics) => metrics,
+            Err(e) => {
+                tracing::warn!(target: "tn::metrics", ?e, "CommitPathMetrics::try_new metrics error");
+                Self::try_new(&Registry::new()).expect("Prometheus error, are you using it wrong?")
+            }
+        }
+    }
+}
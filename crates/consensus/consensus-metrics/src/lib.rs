@@ -19,9 +19,12 @@ use tracing::warn;
 
 pub use scopeguard;
 
+pub mod channels;
+pub mod commit_path;
 mod guards;
 pub mod histogram;
 pub mod metered_channel;
+pub use commit_path::CommitStage;
 pub use guards::*;
 
 pub const TX_TYPE_SINGLE_WRITER_TX: &str = "single_writer";
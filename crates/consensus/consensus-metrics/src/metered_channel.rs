@@ -1,5 +1,6 @@
 //! Wrapper around mpsc channel that also captures useful metrics.
 
+use crate::channels::{register_channel, ChannelHandle};
 use futures::{FutureExt, Stream, TryFutureExt};
 use parking_lot::Mutex;
 use prometheus::{IntCounter, IntGauge};
@@ -16,11 +17,15 @@ mod metered_channel_tests;
 
 /// An [`mpsc::Sender`] with an [`IntGauge`]
 /// counting the number of currently queued items.
+///
+/// The channel is registered by the gauge's name so its backlog is listed by
+/// [`crate::channels::channel_utilization`].
 #[derive(Debug)]
 pub struct MeteredMpscChannel<T> {
     inner: mpsc::Sender<T>,
     gauge: IntGauge,
     receiver: Arc<Mutex<Option<Receiver<T>>>>,
+    stats: ChannelHandle,
 }
 
 impl<T> Clone for MeteredMpscChannel<T> {
@@ -29,6 +34,7 @@ impl<T> Clone for MeteredMpscChannel<T> {
             inner: self.inner.clone(),
             gauge: self.gauge.clone(),
             receiver: self.receiver.clone(),
+            stats: self.stats.clone(),
         }
     }
 }
@@ -108,6 +114,12 @@ impl<T> MeteredMpscChannel<T> {
     pub fn gauge(&self) -> &IntGauge {
         &self.gauge
     }
+
+    /// Increment the gauge after a successful send and record the high-water mark.
+    fn on_send(&self) {
+        self.gauge.inc();
+        self.stats.record(self.gauge.get().max(0) as usize);
+    }
 }
 
 impl<T: Send + 'static> TnSender<T> for MeteredMpscChannel<T> {
@@ -117,7 +129,7 @@ impl<T: Send + 'static> TnSender<T> for MeteredMpscChannel<T> {
         &self,
         value: T,
     ) -> impl std::future::Future<Output = Result<(), tn_types::SendError<T>>> + Send {
-        self.inner.send(value).inspect_ok(|_| self.gauge.inc()).map_err(|e| e.into())
+        self.inner.send(value).inspect_ok(|_| self.on_send()).map_err(|e| e.into())
     }
 
     /// Attempts to immediately send a message on this `Sender`
@@ -128,7 +140,7 @@ impl<T: Send + 'static> TnSender<T> for MeteredMpscChannel<T> {
             .try_send(message)
             // remove this unsightly hack once https://github.com/rust-lang/rust/issues/91345 is resolved
             .inspect(|_| {
-                self.gauge.inc();
+                self.on_send();
             })?)
     }
 
@@ -197,13 +209,35 @@ impl<T> From<Receiver<T>> for ReceiverStream<T> {
 // Constructor
 ////////////////////////////////////////////////////////////////
 
+/// Register the channel by the gauge's name.
+///
+/// The registry only holds a weak sender so it never keeps the channel open.
+fn register<T: Send + 'static>(
+    sender: &mpsc::Sender<T>,
+    size: usize,
+    gauge: &IntGauge,
+) -> ChannelHandle {
+    let name = gauge
+        .desc()
+        .first()
+        .map(|desc| desc.fq_name.clone())
+        .unwrap_or_else(|| std::any::type_name::<T>().to_string());
+    let sender = sender.downgrade();
+    let gauge = gauge.clone();
+    register_channel(name, size, move || sender.upgrade().map(|_| gauge.get().max(0) as usize))
+}
+
 /// Similar to `mpsc::channel`, `channel` creates a pair of `Sender` and `Receiver`
 #[track_caller]
-pub fn channel<T>(size: usize, gauge: &IntGauge) -> (MeteredMpscChannel<T>, Receiver<T>) {
+pub fn channel<T: Send + 'static>(
+    size: usize,
+    gauge: &IntGauge,
+) -> (MeteredMpscChannel<T>, Receiver<T>) {
     gauge.set(0);
     let (sender, receiver) = mpsc::channel(size);
     (
         MeteredMpscChannel {
+            stats: register(&sender, size, gauge),
             inner: sender,
             gauge: gauge.clone(),
             receiver: Arc::new(Mutex::new(None)),
@@ -213,7 +247,7 @@ pub fn channel<T>(size: usize, gauge: &IntGauge) -> (MeteredMpscChannel<T>, Rece
 }
 
 #[track_caller]
-pub fn channel_with_total<T>(
+pub fn channel_with_total<T: Send + 'static>(
     size: usize,
     gauge: &IntGauge,
     total_gauge: &IntCounter,
@@ -222,6 +256,7 @@ pub fn channel_with_total<T>(
     let (sender, receiver) = mpsc::channel(size);
     (
         MeteredMpscChannel {
+            stats: register(&sender, size, gauge),
             inner: sender,
             gauge: gauge.clone(),
             receiver: Arc::new(Mutex::new(None)),
@@ -232,18 +267,19 @@ pub fn channel_with_total<T>(
 
 /// Similar to `mpsc::channel`, `channel` creates a pair of `Sender` and `Receiver`
 /// This version will save the reciever in the sender for one time subscribtion.
-pub fn channel_sender<T>(size: usize, gauge: &IntGauge) -> MeteredMpscChannel<T> {
+pub fn channel_sender<T: Send + 'static>(size: usize, gauge: &IntGauge) -> MeteredMpscChannel<T> {
     gauge.set(0);
     let (sender, receiver) = mpsc::channel(size);
     let rx = Receiver { inner: receiver, gauge: gauge.clone(), total: None };
     MeteredMpscChannel {
+        stats: register(&sender, size, gauge),
         inner: sender,
         gauge: gauge.clone(),
         receiver: Arc::new(Mutex::new(Some(rx))),
     }
 }
 
-pub fn channel_with_total_sender<T>(
+pub fn channel_with_total_sender<T: Send + 'static>(
     size: usize,
     gauge: &IntGauge,
     total_gauge: &IntCounter,
@@ -252,6 +288,7 @@ pub fn channel_with_total_sender<T>(
     let (sender, receiver) = mpsc::channel(size);
     let rx = Receiver { inner: receiver, gauge: gauge.clone(), total: Some(total_gauge.clone()) };
     MeteredMpscChannel {
+        stats: register(&sender, size, gauge),
         inner: sender,
         gauge: gauge.clone(),
         receiver: Arc::new(Mutex::new(Some(rx))),
@@ -144,3 +144,25 @@ async fn test_try_send_full() {
     assert_eq!(received_item, item);
     assert_eq!(counter.get(), 0);
 }
+
+#[tokio::test]
+async fn test_channel_registered() {
+    let counter = IntGauge::new("TEST_REGISTERED_COUNTER", "test").unwrap();
+    let (tx, mut rx) = channel(4, &counter);
+    let registered = |name: &str| {
+        crate::channels::channel_utilization().into_iter().find(|channel| channel.name == name)
+    };
+
+    tx.send(1).await.unwrap();
+    tx.send(2).await.unwrap();
+    tx.send(3).await.unwrap();
+    rx.recv().await.unwrap();
+    let channel = registered("TEST_REGISTERED_COUNTER").expect("channel registered");
+    assert_eq!(channel.capacity, 4);
+    assert_eq!(channel.depth, 2);
+    assert_eq!(channel.high_water, 3);
+
+    // closed channels are removed
+    drop(tx);
+    assert!(registered("TEST_REGISTERED_COUNTER").is_none());
+}
@@ -1,8 +1,8 @@
 //! Subscriber handles consensus output.
 
 use crate::{errors::SubscriberResult, SubscriberError};
-use consensus_metrics::monitored_future;
-use futures::{stream::FuturesOrdered, StreamExt};
+use consensus_metrics::{monitored_future, CommitStage};
+use futures::{stream::FuturesOrdered, FutureExt as _, StreamExt};
 use state_sync::{
     get_missing_consensus, last_executed_consensus_block, save_consensus, spawn_state_sync,
     stream_missing_consensus,
@@ -10,18 +10,20 @@ use state_sync::{
 use std::{
     collections::{HashMap, HashSet, VecDeque},
     sync::Arc,
+    time::{Duration, Instant},
     vec,
 };
 use tn_config::ConsensusConfig;
 use tn_network_types::{local::LocalNetwork, PrimaryToWorkerClient};
 use tn_primary::{
     consensus::ConsensusRound, network::PrimaryNetworkHandle, ConsensusBus, NodeMode,
+    RestoreProgress,
 };
 use tn_storage::CertificateStore;
 use tn_types::{
     AuthorityIdentifier, Batch, BlockHash, CommittedSubDag, Committee, ConsensusHeader,
     ConsensusOutput, Database, Hash as _, Noticer, TaskManager, TaskManagerClone, Timestamp,
-    TnReceiver, TnSender, B256,
+    TnReceiver, TnSender, Withdrawals, B256,
 };
 use tracing::{debug, error, info};
 
@@ -113,6 +115,9 @@ impl<DB: Database> Subscriber<DB> {
     /// Returns the max number of sub-dag to fetch payloads concurrently.
     const MAX_PENDING_PAYLOADS: usize = 1000;
 
+    /// The time between progress logs while restoring consensus output.
+    const RESTORE_LOG_INTERVAL: Duration = Duration::from_secs(10);
+
     /// Turns a ConsensusHeader into a ConsensusOutput and sends it down the consensus_output
     /// channel for execution.
     async fn handle_consensus_header(
@@ -124,6 +129,7 @@ impl<DB: Database> Subscriber<DB> {
                 consensus_header.sub_dag.clone(),
                 consensus_header.parent_hash,
                 consensus_header.number,
+                consensus_header.withdrawals.clone(),
             )
             .await?;
         save_consensus(self.config.node_storage(), consensus_output.clone())?;
@@ -203,13 +209,56 @@ impl<DB: Database> Subscriber<DB> {
         Ok((last_executed_block.digest(), last_executed_block.number))
     }
 
+    /// Send consensus output that was committed but not executed before a restart to execution.
+    ///
+    /// Long restores report progress in logs, the `subscriber_restore_remaining` metric and the
+    /// consensus bus so operators can tell the node is not hung.
+    async fn restore(&self, missing: Vec<ConsensusHeader>) -> SubscriberResult<()> {
+        if missing.is_empty() {
+            return Ok(());
+        }
+        let mut progress = RestoreProgress::new(missing.len() as u64);
+        let remaining = &self.consensus_bus.executor_metrics().subscriber_restore_remaining;
+        info!(
+            target: "subscriber",
+            total = progress.total,
+            "restoring unexecuted consensus output"
+        );
+
+        let mut last_log = Instant::now();
+        for consensus_header in missing {
+            remaining.set(progress.remaining as i64);
+            self.consensus_bus.restore_progress().send_replace(Some(progress));
+            self.handle_consensus_header(consensus_header).await?;
+            progress.restored();
+            if last_log.elapsed() >= Self::RESTORE_LOG_INTERVAL {
+                last_log = Instant::now();
+                info!(
+                    target: "subscriber",
+                    remaining = progress.remaining,
+                    total = progress.total,
+                    eta = ?progress.eta(),
+                    "restoring unexecuted consensus output"
+                );
+            }
+        }
+
+        remaining.set(0);
+        self.consensus_bus.restore_progress().send_replace(None);
+        info!(
+            target: "subscriber",
+            total = progress.total,
+            elapsed = ?progress.started.elapsed(),
+            "restored unexecuted consensus output"
+        );
+        Ok(())
+    }
+
     /// Main loop connecting to the consensus to listen to sequence messages.
     async fn run(self, network: PrimaryNetworkHandle) -> SubscriberResult<()> {
         // Make sure any old consensus that was not executed gets executed.
         let missing = get_missing_consensus(&self.config, &self.consensus_bus).await?;
-        for consensus_header in missing.into_iter() {
-            self.handle_consensus_header(consensus_header).await?;
-        }
+        self.restore(missing).await?;
         // It's important to have the futures in ordered fashion as we want
         // to guarantee that will deliver to the executor the certificates
         // in the same order we received from rx_sequence. So it doesn't
@@ -232,11 +281,14 @@ impl<DB: Database> Subscriber<DB> {
                     // then MAX_PENDING_PAYLOADS is pending
                     let parent_hash = last_parent;
                     let number = last_number + 1;
-                    last_parent = ConsensusHeader::digest_from_parts(parent_hash, &sub_dag, number);
+                    // no subsystem produces protocol withdrawals yet
+                    let withdrawals = Withdrawals::default();
+                    last_parent =
+                        ConsensusHeader::digest_from_parts(parent_hash, &sub_dag, number, &withdrawals);
 
                     // Record the latest ConsensusHeader, we probably don't need this in this mode but keep it up to date anyway.
                     // Note we don't bother sending this to the consensus header channel since not needed when an active CVV.
-                    if let Err(e) = self.consensus_bus.last_consensus_header().send(ConsensusHeader { parent_hash, sub_dag: sub_dag.clone(), number, extra: B256::default() }) {
+                    if let Err(e) = self.consensus_bus.last_consensus_header().send(ConsensusHeader { parent_hash, sub_dag: sub_dag.clone(), number, extra: B256::default(), withdrawals: withdrawals.clone() }) {
                         error!(target: "subscriber", "error sending latest consensus header for authority {}: {}", self.inner.authority_id, e);
                         return Ok(());
                     }
@@ -244,16 +296,21 @@ impl<DB: Database> Subscriber<DB> {
                         error!(target: "subscriber", "error publishing latest consensus to network {}: {}", self.inner.authority_id, e);
                     }
                     last_number += 1;
-                    waiting.push_back(self.fetch_batches(sub_dag, parent_hash, number));
+                    let committed_at = Instant::now();
+                    waiting.push_back(
+                        self.fetch_batches(sub_dag, parent_hash, number, withdrawals)
+                            .map(move |output| (committed_at, output)),
+                    );
                 },
 
                 // Receive consensus messages after all transaction data is downloaded
                 // then send to the execution layer for final block production.
                 //
                 // NOTE: this broadcasts to all subscribers, but lagging receivers will lose messages
-                Some(output) = waiting.next() => {
+                Some((committed_at, output)) = waiting.next() => {
                     match output {
                         Ok(output) => {
+                            CommitStage::CommitToOutput.observe(committed_at.elapsed());
                             debug!(target: "subscriber", output=?output.digest(), "saving next output");
                             save_consensus(self.config.node_storage(), output.clone())?;
                             debug!(target: "subscriber", "broadcasting output...");
@@ -292,6 +349,7 @@ impl<DB: Database> Subscriber<DB> {
         deliver: CommittedSubDag,
         parent_hash: B256,
         number: u64,
+        withdrawals: Withdrawals,
     ) -> SubscriberResult<ConsensusOutput> {
         let num_blocks = deliver.num_primary_blocks();
         let num_certs = deliver.len();
@@ -323,6 +381,7 @@ impl<DB: Database> Subscriber<DB> {
                 number,
                 extra: B256::default(),
                 early_finalize,
+                withdrawals,
             });
         }
 
@@ -336,6 +395,7 @@ impl<DB: Database> Subscriber<DB> {
             number,
             extra: B256::default(),
             early_finalize,
+            withdrawals,
         };
 
         let mut batch_set: HashSet<BlockHash> = HashSet::new();
@@ -452,7 +512,7 @@ mod tests {
     use super::*;
     use indexmap::IndexMap;
     use std::{collections::BTreeSet, ops::RangeInclusive};
-    use tn_network_libp2p::types::{MessageId, NetworkCommand};
+    use tn_network_libp2p::MemoryNetwork;
     use tn_network_types::MockPrimaryToWorkerClient;
     use tn_primary::consensus::{Bullshark, Consensus, LeaderSchedule};
     use tn_primary_metrics::ConsensusMetrics;
@@ -462,7 +522,6 @@ mod tests {
         Certificate, CertificateDigest, ExecHeader, HeaderBuilder, Round, SealedHeader,
         TimestampSec, WorkerId, DEFAULT_BAD_NODES_STAKE_THRESHOLD,
     };
-    use tokio::sync::mpsc;
 
     fn random_batches(
         number_of_batches: usize,
@@ -551,18 +610,7 @@ mod tests {
         let rx_consensus_headers = consensus_bus.last_consensus_header().subscribe();
         let mut consensus_output = consensus_bus.consensus_output().subscribe();
 
-        let (tx, mut rx) = mpsc::channel(5);
-        tokio::spawn(async move {
-            while let Some(com) = rx.recv().await {
-                match com {
-                    NetworkCommand::Publish { topic: _, msg: _, reply } => {
-                        reply.send(Ok(MessageId::new(&[0]))).unwrap();
-                    }
-                    _ => {}
-                }
-            }
-        });
-        let network = PrimaryNetworkHandle::new_for_test(tx);
+        let network = PrimaryNetworkHandle::new(MemoryNetwork::new());
         // spawn the executor
         spawn_subscriber(
             config.clone(),
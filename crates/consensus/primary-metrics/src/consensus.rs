@@ -153,6 +153,8 @@ pub struct ChannelMetrics {
     /// * tx_new_certificates where the newly accepted certificates are sent from
     ///   `primary::Synchronizer` to `Consensus`
     pub tx_sequence: IntGauge,
+    /// consensus events missed by each subscriber on the consensus bus
+    pub consensus_events_lagged: IntCounterVec,
 }
 
 impl ChannelMetrics {
@@ -163,6 +165,12 @@ impl ChannelMetrics {
                 "occupancy of the channel from the `Consensus` to `SubscriberHandler`",
                 registry
             )?,
+            consensus_events_lagged: register_int_counter_vec_with_registry!(
+                "consensus_events_lagged",
+                "consensus events missed by each subscriber on the consensus bus",
+                &["subscriber"],
+                registry
+            )?,
         })
     }
 }
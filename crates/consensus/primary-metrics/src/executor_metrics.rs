@@ -31,6 +31,8 @@ pub struct ExecutorMetrics {
     pub subscriber_recovered_certificates_count: IntCounter,
     /// The number of pending payload downloads
     pub waiting_elements_subscriber: IntGauge,
+    /// The number of committed sub-dags left to restore after a restart
+    pub subscriber_restore_remaining: IntGauge,
     /// Latency between the time when the block has been
     /// created and when it has been fetched for execution
     pub block_execution_latency: Histogram,
@@ -84,6 +86,11 @@ impl ExecutorMetrics {
                 "The number of pending payload downloads",
                 registry
             )?,
+            subscriber_restore_remaining: register_int_gauge_with_registry!(
+                "subscriber_restore_remaining",
+                "The number of committed sub-dags left to restore after a restart",
+                registry
+            )?,
             block_execution_latency: register_histogram_with_registry!(
                 "block_execution_latency",
                 "Latency between the time when the block has been created and when it has been fetched for execution",
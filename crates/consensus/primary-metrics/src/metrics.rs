@@ -285,6 +285,10 @@ pub struct PrimaryMetrics {
     pub proposed_header_round: IntGauge,
     /// The number of received votes for the proposed last round
     pub votes_received_last_round: IntGauge,
+    /// The voting power of the votes received for the proposed last round
+    pub votes_stake_last_round: IntGauge,
+    /// The number of committee members without a vote for the proposed last round
+    pub votes_missing_last_round: IntGauge,
     // total number of parent certificates included in votes.
     pub certificates_in_votes: IntCounter,
     /// The round of the latest created certificate by our node
@@ -297,6 +301,10 @@ pub struct PrimaryMetrics {
     pub certificates_suspended: IntCounterVec,
     /// number of certificates that are currently suspended.
     pub certificates_currently_suspended: IntGauge,
+    /// count number of suspended certificates evicted to bound memory
+    pub certificates_evicted: IntCounter,
+    /// number of missing parents that suspended certificates are waiting for.
+    pub certificates_missing_parents: IntGauge,
     /// count number of duplicate certificates that the node processed (others + own)
     pub duplicate_certificates_processed: IntCounter,
     /// The current Narwhal round in proposer
@@ -334,12 +342,24 @@ pub struct PrimaryMetrics {
     pub header_to_certificate_latency: Histogram,
     /// Millisecs taken to wait for max parent time, when proposing headers.
     pub header_max_parent_wait_ms: IntCounter,
+    /// The offset of the local clock from NTP time in milliseconds, positive if the local clock
+    /// is behind.
+    pub clock_skew_ms: IntGauge,
+    /// The number of clock checks with an offset greater than the configured maximum skew.
+    pub clock_skew_alerts: IntCounter,
     /// Counts when the GC loop in synchronizer times out waiting for consensus commit.
     pub synchronizer_gc_timeout: IntCounter,
     // Total number of fetched certificates verified directly.
     pub fetched_certificates_verified_directly: IntCounter,
     // Total number of fetched certificates verified indirectly.
     pub fetched_certificates_verified_indirectly: IntCounter,
+    /// Total number of certificate signature verifications skipped because the certificate was
+    /// already verified.
+    pub certificate_verification_cache_hits: IntCounter,
+    /// Number of certificates gossiped by peers that were dropped without processing, by reason.
+    pub unsolicited_certificates_shed: IntCounterVec,
+    /// Number of catch up requests gossiped by peers that were not answered, by reason.
+    pub catch_up_requests_shed: IntCounterVec,
 }
 
 impl PrimaryMetrics {
@@ -379,6 +399,16 @@ impl PrimaryMetrics {
                 "The number of received votes for the proposed last round",
                 registry
             )?,
+            votes_stake_last_round: register_int_gauge_with_registry!(
+                "votes_stake_last_round",
+                "The voting power of the votes received for the proposed last round",
+                registry
+            )?,
+            votes_missing_last_round: register_int_gauge_with_registry!(
+                "votes_missing_last_round",
+                "The number of committee members without a vote for the proposed last round",
+                registry
+            )?,
             certificates_in_votes: register_int_counter_with_registry!(
                 "certificates_in_votes",
                 "Total number of parent certificates included in votes.",
@@ -411,6 +441,16 @@ impl PrimaryMetrics {
                 "Number of certificates that are suspended in memory",
                 registry
             )?,
+            certificates_evicted: register_int_counter_with_registry!(
+                "certificates_evicted",
+                "Number of suspended certificates evicted because the pending limit was reached",
+                registry
+            )?,
+            certificates_missing_parents: register_int_gauge_with_registry!(
+                "certificates_missing_parents",
+                "Number of missing parents that suspended certificates are waiting for",
+                registry
+            )?,
             duplicate_certificates_processed: register_int_counter_with_registry!(
                 "duplicate_certificates_processed",
                 "Number of certificates that node processed (others + own)",
@@ -506,6 +546,16 @@ impl PrimaryMetrics {
                 "Millisecs taken to wait for max parent time, when proposing headers.",
                 registry
             )?,
+            clock_skew_ms: register_int_gauge_with_registry!(
+                "clock_skew_ms",
+                "The offset of the local clock from NTP time in milliseconds, positive if the local clock is behind.",
+                registry
+            )?,
+            clock_skew_alerts: register_int_counter_with_registry!(
+                "clock_skew_alerts",
+                "The number of clock checks with an offset greater than the configured maximum skew.",
+                registry
+            )?,
             synchronizer_gc_timeout: register_int_counter_with_registry!(
                 "synchronizer_gc_timeout",
                 "Counts when the GC loop in synchronizer times out waiting for consensus commit.",
@@ -521,6 +571,23 @@ impl PrimaryMetrics {
                 "Total number of fetched certificates verified indirectly.",
                 registry
             )?,
+            certificate_verification_cache_hits: register_int_counter_with_registry!(
+                "certificate_verification_cache_hits",
+                "Total number of certificates already verified by another code path.",
+                registry
+            )?,
+            unsolicited_certificates_shed: register_int_counter_vec_with_registry!(
+                "unsolicited_certificates_shed",
+                "Number of certificates gossiped by peers that were dropped without processing",
+                &["reason"],
+                registry
+            )?,
+            catch_up_requests_shed: register_int_counter_vec_with_registry!(
+                "catch_up_requests_shed",
+                "Number of catch up requests gossiped by peers that were not answered",
+                &["reason"],
+                registry
+            )?,
         })
     }
 }
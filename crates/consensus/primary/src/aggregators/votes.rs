@@ -19,7 +19,9 @@ pub(crate) struct VotesAggregator {
     weight: VotingPower,
     /// The vote received from a peer.
     votes: Vec<(AuthorityIdentifier, BlsSignature)>,
-    /// The collection of authority ids that have already voted.
+    /// The collection of authority ids with a vote in `votes`.
+    ///
+    /// Authorities whose vote fails verification are removed so they can vote again.
     authorities_seen: HashSet<AuthorityIdentifier>,
     /// Metrics for votes aggregator.
     metrics: Arc<PrimaryMetrics>,
@@ -33,6 +35,16 @@ impl VotesAggregator {
         Self { weight: 0, votes: Vec::new(), authorities_seen: HashSet::new(), metrics }
     }
 
+    /// Return true if the authority's vote was appended and has not failed verification.
+    pub(crate) fn has_voted(&self, authority: &AuthorityIdentifier) -> bool {
+        self.authorities_seen.contains(authority)
+    }
+
+    /// The authorities with valid votes.
+    pub(crate) fn voters(&self) -> Vec<AuthorityIdentifier> {
+        self.votes.iter().map(|(id, _)| id.clone()).collect()
+    }
+
     /// Append the vote to the collection.
     ///
     /// This method protects against equivocation by keeping track of peers that have already voted.
@@ -82,11 +94,13 @@ impl VotesAggregator {
                         if !sig.verify_secure(&to_intent_message(certificate_digest), pk) {
                             warn!(target: "primary::votes_aggregator", "Invalid signature on header from authority: {}", id);
                             self.weight -= committee.voting_power(pk);
+                            self.authorities_seen.remove(id);
                             false
                         } else {
                             true
                         }
                     } else {
+                        self.authorities_seen.remove(id);
                         false
                     }
                 });
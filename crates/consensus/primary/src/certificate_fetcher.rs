@@ -10,11 +10,11 @@ use consensus_metrics::{monitored_future, monitored_scope};
 use futures::{stream::FuturesUnordered, StreamExt};
 use rand::{rngs::ThreadRng, seq::SliceRandom};
 use std::{
-    collections::{BTreeMap, BTreeSet},
+    collections::{BTreeMap, BTreeSet, HashMap},
     sync::Arc,
     time::Duration,
 };
-use tn_config::ConsensusConfig;
+use tn_config::{CertificateFetchParameters, ConsensusConfig};
 use tn_network_libp2p::PeerId;
 use tn_network_types::{FetchCertificatesRequest, FetchCertificatesResponse};
 use tn_primary_metrics::PrimaryMetrics;
@@ -35,11 +35,6 @@ pub mod certificate_fetcher_tests;
 
 // Maximum number of certificates to fetch with one request.
 const MAX_CERTIFICATES_TO_FETCH: usize = 2_000;
-// Seconds to wait for a response before issuing another parallel fetch request.
-const PARALLEL_FETCH_REQUEST_INTERVAL_SECS: Duration = Duration::from_secs(5);
-// The timeout for an iteration of parallel fetch requests over all peers would be
-// num peers * PARALLEL_FETCH_REQUEST_INTERVAL_SECS + PARALLEL_FETCH_REQUEST_ADDITIONAL_TIMEOUT
-const PARALLEL_FETCH_REQUEST_ADDITIONAL_TIMEOUT: Duration = Duration::from_secs(15);
 
 #[derive(Clone, Debug)]
 pub enum CertificateFetcherCommand {
@@ -320,9 +315,15 @@ async fn run_fetch_task<DB: Database>(
     let request = FetchCertificatesRequest::default()
         .set_bounds(gc_round, written_rounds)
         .set_max_items(MAX_CERTIFICATES_TO_FETCH);
-    let Some(response) =
-        fetch_certificates_helper(&state.authority_id, state.network.clone(), &committee, request)
-            .await
+    let Some(response) = fetch_certificates_helper(
+        &state.authority_id,
+        state.network.clone(),
+        &committee,
+        request,
+        &state.config.parameters().requests.certificate_fetch,
+        &state.config.authority_latencies(),
+    )
+    .await
     else {
         error!(target: "primary::cert_fetcher", "error awaiting fetch_certificates_helper");
         return Err(CertManagerError::NoCertificateFetched);
@@ -337,28 +338,45 @@ async fn run_fetch_task<DB: Database>(
     Ok(())
 }
 
-/// Fetches certificates from other primaries concurrently, with ~5 sec interval between each
-/// request. Terminates after the 1st successful response is received.
+/// The other primaries in the order they are asked for certificates.
+///
+/// Peers with the lowest saved round trip time are asked first. Peers without a saved time are
+/// asked last, in random order.
+fn fetch_order(
+    name: &AuthorityIdentifier,
+    committee: &Committee,
+    latencies: &HashMap<AuthorityIdentifier, Duration>,
+) -> Vec<PeerId> {
+    let mut authorities: Vec<AuthorityIdentifier> =
+        committee.others_primaries_by_id(name).into_iter().map(|(auth_id, _, _)| auth_id).collect();
+    authorities.shuffle(&mut ThreadRng::default());
+    authorities.sort_by_key(|auth_id| latencies.get(auth_id).copied().unwrap_or(Duration::MAX));
+    authorities.into_iter().map(|auth_id| auth_id.peer_id()).collect()
+}
+
+/// Fetches certificates from other primaries concurrently, with the configured interval between
+/// each request. Terminates after the 1st successful response is received.
+///
+/// Peers are asked in [fetch_order].
 #[instrument(level = "debug", skip_all)]
 async fn fetch_certificates_helper(
     name: &AuthorityIdentifier,
     network: PrimaryNetworkHandle,
     committee: &Committee,
     request: FetchCertificatesRequest,
+    params: &CertificateFetchParameters,
+    latencies: &HashMap<AuthorityIdentifier, Duration>,
 ) -> Option<FetchCertificatesResponse> {
     let _scope = monitored_scope("FetchingCertificatesFromPeers");
     trace!(target: "primary::cert_fetcher", "Start sending fetch certificates requests");
-    // TODO: make this a config parameter.
-    let request_interval = PARALLEL_FETCH_REQUEST_INTERVAL_SECS;
-    let mut peers: Vec<PeerId> = committee
-        .others_primaries_by_id(name)
-        .into_iter()
-        .map(|(auth_id, _, _)| auth_id.peer_id())
-        .collect();
-    peers.shuffle(&mut ThreadRng::default());
-    let fetch_timeout = PARALLEL_FETCH_REQUEST_INTERVAL_SECS
+    let request_interval = params.request_interval;
+    let mut peers = fetch_order(name, committee, latencies);
+    // peers are popped from the back, so the fastest peers go last
+    peers.reverse();
+    // The timeout for an iteration of parallel fetch requests over all peers.
+    let fetch_timeout = request_interval
         * peers.len().try_into().expect("usize into secs duration")
-        + PARALLEL_FETCH_REQUEST_ADDITIONAL_TIMEOUT;
+        + params.additional_timeout;
     let fetch_callback = async move {
         debug!(target: "primary::cert_fetcher", "Starting to fetch certificates");
         let mut fut = FuturesUnordered::new();
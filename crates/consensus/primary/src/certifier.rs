@@ -4,23 +4,27 @@ use crate::{
     aggregators::VotesAggregator,
     network::{PrimaryNetworkHandle, RequestVoteResult},
     state_sync::StateSynchronizer,
-    ConsensusBus,
+    ConsensusBus, VoteAggregation,
 };
-use consensus_metrics::monitored_future;
+use consensus_metrics::{monitored_future, CommitStage};
 use futures::{
     stream::{FuturesOrdered, FuturesUnordered},
     StreamExt,
 };
-use std::{cmp::min, sync::Arc, time::Duration};
-use tn_config::{ConsensusConfig, KeyConfig};
+use std::{
+    cmp::min,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tn_config::{ChannelClass, ConsensusConfig, KeyConfig};
 use tn_network_libp2p::{error::NetworkError, types::NetworkResult};
 use tn_primary_metrics::PrimaryMetrics;
 use tn_storage::CertificateStore;
 use tn_types::{
     ensure,
     error::{DagError, DagResult},
-    AuthorityIdentifier, Certificate, CertificateDigest, Committee, Database, Header, Noticer,
-    TaskManager, TnReceiver, TnSender, Vote, CHANNEL_CAPACITY,
+    AuthorityIdentifier, Certificate, CertificateDigest, Committee, Database, Header, HeaderDigest,
+    Noticer, TaskManager, TnReceiver, TnSender, Vote,
 };
 use tokio::sync::broadcast;
 use tracing::{debug, enabled, error, info, instrument, trace, warn};
@@ -29,6 +33,17 @@ use tracing::{debug, enabled, error, info, instrument, trace, warn};
 #[path = "tests/certifier_tests.rs"]
 pub mod certifier_tests;
 
+/// The votes collected for the header this primary is proposing.
+///
+/// Votes are kept when a proposal is canceled, so proposing the same header again only requests
+/// votes from peers that haven't voted.
+struct PendingVotes {
+    /// The digest of the proposed header.
+    header_digest: HeaderDigest,
+    /// The votes received for the header.
+    votes_aggregator: VotesAggregator,
+}
+
 /// This component is responisble for proposing headers to peers, collecting votes on headers,
 /// and certifying headers into certificates.
 ///
@@ -72,8 +87,10 @@ impl<DB: Database> Certifier<DB> {
         // These channels are used internally to this module (file) and don't need to go in the
         // consensus bus. If this changes they can move.  Note there can be issues receiving
         // certs over the broadcast if not subscribed early.
+        let capacity =
+            config.parameters().channels.capacity(ChannelClass::Broadcast, "own_certificates");
         let (tx_own_certificate_broadcast, _rx_own_certificate_broadcast) =
-            broadcast::channel(CHANNEL_CAPACITY);
+            broadcast::channel(capacity);
 
         // prevents race condition during startup when first proposed header fails during
         // tx_own_certificate_broadcast.send()
@@ -134,7 +151,14 @@ impl<DB: Database> Certifier<DB> {
 
     /// Requests a vote for a Header from the given peer. Retries indefinitely until either a
     /// vote is received, or a permanent error is returned.
-    #[instrument(level = "debug", skip_all, fields(header_digest = ?header.digest()))]
+    ///
+    /// Each request the peer lets time out doubles the timeout of the next one, up to the max
+    /// timeout, so slow peers aren't flooded with the same header.
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(round = header.round(), header_digest = ?header.digest())
+    )]
     async fn request_vote(
         &self,
         authority: AuthorityIdentifier,
@@ -143,8 +167,10 @@ impl<DB: Database> Certifier<DB> {
         debug!(target: "primary::certifier", ?authority, ?header, "requesting vote for header...");
         let peer_id = authority.peer_id();
 
+        let params = &self.config.parameters().requests.vote;
         let mut missing_parents: Vec<CertificateDigest> = Vec::new();
         let mut attempt: u32 = 0;
+        let mut timeouts: u32 = 0;
         let vote: Vote = loop {
             attempt += 1;
 
@@ -170,7 +196,11 @@ impl<DB: Database> Certifier<DB> {
                 parents
             };
 
-            match self.network.request_vote(peer_id, header.clone(), parents).await {
+            let request = self.network.request_vote(peer_id, header.clone(), parents);
+            let response = tokio::time::timeout(params.timeout_after(timeouts), request)
+                .await
+                .unwrap_or(Err(NetworkError::Timeout));
+            match response {
                 Ok(RequestVoteResult::Vote(vote)) => {
                     debug!(target: "primary::certifier", ?authority, ?vote, "Ok response received after request vote");
                     break vote;
@@ -188,23 +218,18 @@ impl<DB: Database> Certifier<DB> {
                     } else {
                         error!(target: "primary::certifier", ?authority, ?error, ?header, "network error requesting vote");
                     }
+                    if matches!(error, NetworkError::Timeout) {
+                        timeouts = timeouts.saturating_add(1);
+                    }
                     missing_parents = Vec::new();
                 }
             }
 
-            // Retry delay. Using custom values here because pure exponential backoff is hard to
-            // configure without it being either too aggressive or too slow. We want the first
-            // retry to be instantaneous, next couple to be fast, and to slow quickly thereafter.
-            tokio::time::sleep(Duration::from_millis(match attempt {
-                1 => 0,
-                2 => 100,
-                3 => 500,
-                4 => 1_000,
-                5 => 2_000,
-                6 => 5_000,
-                _ => 10_000,
-            }))
-            .await;
+            // Retry delay. The first retry is instantaneous since the peer most likely only needed
+            // the missing parents, later retries back off.
+            if attempt > 1 {
+                tokio::time::sleep(params.backoff.delay(attempt - 1)).await;
+            }
         };
 
         // Verify the vote. Note that only the header digest is signed by the vote.
@@ -239,11 +264,16 @@ impl<DB: Database> Certifier<DB> {
         Ok(vote)
     }
 
-    #[instrument(level = "debug", skip_all, fields(header_digest = ?header.digest()))]
+    #[instrument(
+        level = "debug",
+        skip_all,
+        fields(round = header.round(), header_digest = ?header.digest())
+    )]
     async fn propose_header<RXH: TnReceiver<Header>>(
         &self,
         header: Header,
         rx_headers: &mut RXH,
+        pending_votes: &mut Option<PendingVotes>,
     ) -> DagResult<Certificate> {
         let authority_id = &self.authority_id;
         debug!(target: "primary::certifier", ?authority_id, "proposing header");
@@ -261,20 +291,37 @@ impl<DB: Database> Certifier<DB> {
         }
 
         self.metrics.proposed_header_round.set(header.round() as i64);
+        let proposed_at = Instant::now();
 
-        // Reset the votes aggregator and sign our own header.
-        let mut votes_aggregator = VotesAggregator::new(self.metrics.clone());
-        let vote = Vote::new(&header, self.authority_id.clone(), &self.signature_service).await;
-        let mut certificate = votes_aggregator.append(vote, &self.committee, &header)?;
+        // Keep the votes if this header was proposed before, otherwise reset the votes aggregator.
+        let header_digest = header.digest();
+        let mut votes_aggregator = match pending_votes.take() {
+            Some(pending) if pending.header_digest == header_digest => {
+                debug!(target: "primary::certifier", ?authority_id, "reproposing header with votes from previous proposal");
+                pending.votes_aggregator
+            }
+            _ => VotesAggregator::new(self.metrics.clone()),
+        };
 
-        // Trigger vote requests.
-        let peers = self
+        // Sign our own header.
+        let mut certificate = None;
+        if !votes_aggregator.has_voted(&self.authority_id) {
+            let vote = Vote::new(&header, self.authority_id.clone(), &self.signature_service).await;
+            certificate = votes_aggregator.append(vote, &self.committee, &header)?;
+        }
+        self.report_votes(&header, &votes_aggregator, certificate.is_some());
+
+        // Trigger vote requests for peers that haven't voted.
+        let peers: Vec<_> = self
             .committee
             .others_primaries_by_id(&self.authority_id)
             .into_iter()
-            .map(|(name, _, network_key)| (name, network_key));
+            .map(|(name, _, _)| name)
+            .filter(|name| !votes_aggregator.has_voted(name))
+            .collect();
         let mut requests: FuturesUnordered<_> = peers
-            .map(|(name, _target)| {
+            .into_iter()
+            .map(|name| {
                 let header = header.clone();
                 self.request_vote(name, header)
             })
@@ -295,6 +342,7 @@ impl<DB: Database> Certifier<DB> {
                                 &self.committee,
                                 &header,
                             )?;
+                            self.report_votes(&header, &votes_aggregator, certificate.is_some());
                         },
                         Some(Err(e)) => error!(target: "primary::certifier", ?authority_id, "failed to get vote for header {header:?}: {e:?}"),
                         None => {
@@ -304,6 +352,8 @@ impl<DB: Database> Certifier<DB> {
                 },
                 _ = rx_headers.recv() => {
                     warn!(target: "primary::certifier", ?authority_id, "canceling Header proposal {header} for round {}", header.round());
+                    // Keep the votes in case the header is proposed again.
+                    *pending_votes = Some(PendingVotes { header_digest, votes_aggregator });
                     // This allows us to inturupt the propose_header future- just put it back on the headers channel to get picked up in outer select.
                     let _ = self.consensus_bus.headers().send(header).await;
                     return Err(DagError::Canceled)
@@ -334,10 +384,33 @@ impl<DB: Database> Certifier<DB> {
             DagError::CouldNotFormCertificate(header.digest())
         })?;
         debug!(target: "primary::certifier", ?authority_id, "Assembled {certificate:?}");
+        CommitStage::HeaderToCertificate.observe(proposed_at.elapsed());
+        // the aggregate signature was verified while forming the certificate
+        self.consensus_bus.verified_certificates().insert(&certificate);
 
         Ok(certificate)
     }
 
+    /// Publish the votes collected for the header to the consensus bus and metrics.
+    fn report_votes(&self, header: &Header, votes_aggregator: &VotesAggregator, certified: bool) {
+        let header_digest = header.digest();
+        self.consensus_bus.vote_aggregation().send_modify(|aggregation| {
+            // keep the start time while the same header collects votes
+            if aggregation.as_ref().map(|a| a.header_digest) != Some(header_digest) {
+                *aggregation =
+                    Some(VoteAggregation::new(header.round(), header_digest, &self.committee));
+            }
+            if let Some(aggregation) = aggregation {
+                aggregation.set_voters(votes_aggregator.voters(), &self.committee);
+                if certified {
+                    aggregation.mark_certified();
+                }
+                self.metrics.votes_stake_last_round.set(aggregation.stake as i64);
+                self.metrics.votes_missing_last_round.set(aggregation.missing.len() as i64);
+            }
+        });
+    }
+
     /// Pushes new certificates received from the rx_own_certificate_broadcast channel
     /// to the target peer continuously. Only exits when the primary is shutting down.
     async fn push_certificates(
@@ -414,12 +487,13 @@ impl<DB: Database> Certifier<DB> {
     async fn run(self) {
         info!(target: "primary::certifier", "Certifier on node {} has started successfully.", self.authority_id);
         let mut rx_headers = self.consensus_bus.headers().subscribe();
+        let mut pending_votes = None;
         loop {
             tokio::select! {
                 Some(header) = rx_headers.recv() => {
                     debug!(target: "primary::certifier", authority=?self.authority_id, ?header, "header received!");
 
-                    match self.propose_header(header, &mut rx_headers).await {
+                    match self.propose_header(header, &mut rx_headers, &mut pending_votes).await {
                         Ok(certificate) => {
                             let state_sync = self.state_sync.clone();
                             let tx_own_certificate_broadcast = self.tx_own_certificate_broadcast.clone();
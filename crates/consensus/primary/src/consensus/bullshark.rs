@@ -244,7 +244,9 @@ impl<DB: ConsensusStore> Bullshark<DB> {
 
         while let Some(leader) = leaders_to_commit.pop_front() {
             let sub_dag_index = leader.nonce();
-            let _span = error_span!("bullshark_process_sub_dag", sub_dag_index);
+            let _span =
+                error_span!("bullshark_process_sub_dag", round = leader.round(), sub_dag_index)
+                    .entered();
 
             debug!("Leader {:?} has enough support", leader);
 
@@ -11,6 +11,7 @@ use std::{
 use tn_storage::ConsensusStore;
 use tn_types::{
     Authority, AuthorityIdentifier, Certificate, Committee, ReputationScores, Round, VotingPower,
+    MAX_BAD_NODES_STAKE_THRESHOLD,
 };
 use tracing::{debug, trace};
 
@@ -50,16 +51,15 @@ impl LeaderSwapTable {
     /// `bad_nodes_stake_threshold` designates the total (by stake) nodes that will be
     /// considered as "bad" based on their scores and will be replaced by good nodes.
     ///
-    /// The `bad_nodes_stake_threshold` should be in the range of [0 - 33].
-    ///
-    /// For now, we use 0.
+    /// The `bad_nodes_stake_threshold` comes from the epoch's `LeaderScheduleParameters` in the
+    /// chain spec and must be in the range of [0 - `MAX_BAD_NODES_STAKE_THRESHOLD`].
     pub fn new(
         committee: &Committee,
         round: Round,
         reputation_scores: &ReputationScores,
         bad_nodes_stake_threshold: u64,
     ) -> Self {
-        assert!((0..=33).contains(&bad_nodes_stake_threshold), "The bad_nodes_stake_threshold should be in range [0 - 33], out of bounds parameter detected");
+        assert!((0..=MAX_BAD_NODES_STAKE_THRESHOLD).contains(&bad_nodes_stake_threshold), "The bad_nodes_stake_threshold should be in range [0 - 33], out of bounds parameter detected");
         assert!(reputation_scores.final_of_schedule, "Only reputation scores that have been calculated on the end of a schedule are accepted");
 
         // calculating the good nodes
@@ -4,7 +4,7 @@ use crate::{
     consensus::{bullshark::Bullshark, utils::gc_round, ConsensusError, ConsensusMetrics},
     ConsensusBus, NodeMode,
 };
-use consensus_metrics::monitored_future;
+use consensus_metrics::{monitored_future, CommitStage};
 use std::{
     cmp::{max, Ordering},
     collections::{BTreeMap, BTreeSet, HashMap},
@@ -183,10 +183,10 @@ impl ConsensusState {
             .last_committed_round
             .with_label_values(&[])
             .set(self.last_round.committed_round as i64);
-        let elapsed = certificate.created_at().elapsed().as_secs_f64();
-        self.metrics
-            .certificate_commit_latency
-            .observe(certificate.created_at().elapsed().as_secs_f64());
+        let latency = certificate.created_at().elapsed();
+        let elapsed = latency.as_secs_f64();
+        self.metrics.certificate_commit_latency.observe(elapsed);
+        CommitStage::CertificateToCommit.observe(latency);
 
         // NOTE: This log entry is used to compute performance.
         tracing::debug!(target: "telcoin::consensus_state",
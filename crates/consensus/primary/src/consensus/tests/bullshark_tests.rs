@@ -6,7 +6,7 @@ use crate::{
     ConsensusBus,
 };
 use std::collections::{BTreeSet, HashMap};
-use tn_config::ConsensusConfig;
+use tn_config::{ConsensusConfig, LeaderScheduleParameters};
 use tn_storage::{mem_db::MemDatabase, open_db, CertificateStore};
 use tn_test_utils::CommitteeFixture;
 use tn_types::{
@@ -137,6 +137,66 @@ async fn commit_one_with_leader_schedule_change() {
     }
 }
 
+/// The leader schedule only swaps leaders with a non-zero bad nodes stake threshold, and only at
+/// the end of each schedule window.
+#[tokio::test]
+async fn leader_schedule_change_with_configured_parameters() {
+    // the leaders committed for rounds 2 to 10 by index into the committee
+    let test_cases = [
+        (
+            LeaderScheduleParameters { bad_nodes_stake_threshold: 33, sub_dags_per_schedule: 3 },
+            [0, 1, 2, 3, 3],
+        ),
+        // no stake is swapped out
+        (
+            LeaderScheduleParameters { bad_nodes_stake_threshold: 0, sub_dags_per_schedule: 3 },
+            [0, 1, 2, 3, 0],
+        ),
+        // the first schedule has not ended
+        (
+            LeaderScheduleParameters { bad_nodes_stake_threshold: 33, sub_dags_per_schedule: 10 },
+            [0, 1, 2, 3, 0],
+        ),
+    ];
+
+    for (params, expected) in test_cases {
+        params.validate().expect("valid leader schedule parameters");
+        let fixture = CommitteeFixture::builder(MemDatabase::default).build();
+        let committee = fixture.committee();
+        let ids: Vec<_> = fixture.authorities().map(|a| a.id()).collect();
+        let genesis =
+            Certificate::genesis(&committee).iter().map(|x| x.digest()).collect::<BTreeSet<_>>();
+        let (certificates, _next_parents) =
+            tn_test_utils::make_optimal_certificates(&committee, 1..=11, &genesis, &ids);
+
+        let metrics = Arc::new(ConsensusMetrics::default());
+        let mut state = ConsensusState::new(metrics.clone(), 50);
+        let store = open_db(tn_test_utils::temp_dir());
+        let schedule = LeaderSchedule::from_store(
+            committee.clone(),
+            store.clone(),
+            params.bad_nodes_stake_threshold,
+        );
+        let mut bullshark = Bullshark::new(
+            committee,
+            store,
+            metrics,
+            params.sub_dags_per_schedule,
+            schedule,
+            params.bad_nodes_stake_threshold,
+        );
+
+        let mut leaders = Vec::new();
+        for certificate in certificates {
+            let (_, committed) = bullshark.process_certificate(&mut state, certificate).unwrap();
+            leaders.extend(committed.iter().map(|sub_dag| sub_dag.leader.origin().clone()));
+        }
+
+        let expected: Vec<_> = expected.iter().map(|index| ids[*index].clone()).collect();
+        assert_eq!(leaders, expected, "{params:?}");
+    }
+}
+
 /// We test the scenario where a leader is recursively committed and that changes the schedule
 /// because of new reputation scores. More specifically, the leaders of rounds 6, 8 & 10 either do
 /// not receive enough support or they do not get referenced at all by the next round, essentially
@@ -1068,7 +1128,7 @@ async fn restart_with_new_committee() {
             config.node_storage().clone(),
             config.key_config().clone(),
             committee.clone(),
-            config.worker_cache().clone(),
+            config.worker_cache(),
         )
         .unwrap();
         let store = config.node_storage().clone();
@@ -4,18 +4,23 @@
 
 use crate::{
     certificate_fetcher::CertificateFetcherCommand, consensus::ConsensusRound,
-    proposer::OurDigestMessage, state_sync::CertificateManagerCommand, RecentBlocks,
+    proposer::OurDigestMessage, state_sync::CertificateManagerCommand, ConsensusEvent,
+    ConsensusEventFilter, ConsensusEventSubscriber, EventBroadcast, EventWatch, RecentBlocks,
+    RestoreProgress, VerifiedCertificates, VoteAggregation, WorkerActivity,
+};
+use consensus_metrics::{
+    channels::register_channel,
+    metered_channel::{self, channel_with_total_sender, MeteredMpscChannel},
 };
-use consensus_metrics::metered_channel::{self, channel_with_total_sender, MeteredMpscChannel};
 use std::{
     error::Error,
     sync::{atomic::AtomicBool, Arc},
 };
-use tn_config::Parameters;
+use tn_config::{ChannelClass, ChannelParameters, Parameters};
 use tn_primary_metrics::{ChannelMetrics, ConsensusMetrics, ExecutorMetrics, Metrics};
 use tn_types::{
     BlockHash, BlockNumHash, Certificate, CommittedSubDag, ConsensusHeader, ConsensusOutput,
-    Header, Round, TnSender, CHANNEL_CAPACITY,
+    Header, Round, SealedHeader, TnSender,
 };
 use tokio::{
     sync::{
@@ -26,7 +31,7 @@ use tokio::{
 };
 
 /// Has sync completed?
-#[derive(Copy, Clone, Debug, Default)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 pub enum NodeMode {
     /// This is a full CVV that can participate in consensus.
     #[default]
@@ -57,6 +62,16 @@ impl NodeMode {
     }
 }
 
+impl std::fmt::Display for NodeMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            NodeMode::CvvActive => write!(f, "active CVV"),
+            NodeMode::CvvInactive => write!(f, "inactive CVV"),
+            NodeMode::Observer => write!(f, "observer"),
+        }
+    }
+}
+
 #[derive(Debug)]
 struct ConsensusBusInner {
     /// New certificates from the primary. The primary should send us new certificates
@@ -66,14 +81,10 @@ struct ConsensusBusInner {
     committed_certificates: MeteredMpscChannel<(Round, Vec<Certificate>)>,
 
     /// Outputs the highest committed round & corresponding gc_round in the consensus.
-    tx_committed_round_updates: watch::Sender<Round>,
-    /// Hold onto a receiver to keep it "open".
-    _rx_committed_round_updates: watch::Receiver<Round>,
+    tx_committed_round_updates: EventWatch<Round>,
 
     /// Outputs the highest gc_round from the consensus.
-    tx_gc_round_updates: watch::Sender<Round>,
-    /// Hold onto a receiver to keep it "open".
-    _rx_gc_round_updates: watch::Receiver<Round>,
+    tx_gc_round_updates: EventWatch<Round>,
 
     /// Sends missing certificates to the `CertificateFetcher`.
     /// Receives certificates with missing parents from the `Synchronizer`.
@@ -98,33 +109,51 @@ struct ConsensusBusInner {
     certificate_manager: MeteredMpscChannel<CertificateManagerCommand>,
 
     /// Signals a new round
-    tx_primary_round_updates: watch::Sender<Round>,
-    /// Hold onto the primary metrics (allow early creation)
-    _rx_primary_round_updates: watch::Receiver<Round>,
+    tx_primary_round_updates: EventWatch<Round>,
+    /// The round this node fell behind from while waiting for peers to push certificates.
+    tx_catch_up_round: EventWatch<Option<Round>>,
 
     /// Watch tracking most recent blocks
-    tx_recent_blocks: watch::Sender<RecentBlocks>,
-    /// Hold onto the recent blocks watch to keep it "open"
-    _rx_recent_blocks: watch::Receiver<RecentBlocks>,
+    tx_recent_blocks: EventWatch<RecentBlocks>,
 
     /// Watch tracking most recently seen consensus header.
-    tx_last_consensus_header: watch::Sender<ConsensusHeader>,
-    /// Hold onto the consensus header watch to keep it "open"
-    _rx_last_consensus_header: watch::Receiver<ConsensusHeader>,
+    tx_last_consensus_header: EventWatch<ConsensusHeader>,
     /// Watch tracking the last gossipped consensus block number and hash.
     tx_last_published_consensus_num_hash: watch::Sender<(u64, BlockHash)>,
     /// Hold onto the published consensus header watch to keep it "open"
     _rx_last_published_consensus_num_hash: watch::Receiver<(u64, BlockHash)>,
+    /// Watch tracking the latest executed block announced on the gossip network.
+    tx_last_announced_block: watch::Sender<Option<(u64, SealedHeader)>>,
+    /// Hold onto the announced block watch to keep it "open"
+    _rx_last_announced_block: watch::Receiver<Option<(u64, SealedHeader)>>,
+    /// Watch tracking the sub-dag index to stop the node at once it is executed.
+    tx_halt_at_sub_dag: EventWatch<Option<u64>>,
+    /// Watch tracking progress restoring unexecuted consensus output after a restart.
+    tx_restore_progress: watch::Sender<Option<RestoreProgress>>,
+    /// Hold onto the restore progress watch to keep it "open"
+    _rx_restore_progress: watch::Receiver<Option<RestoreProgress>>,
+    /// Watch tracking the last report from each of our workers.
+    tx_worker_activity: watch::Sender<WorkerActivity>,
+    /// Hold onto the worker activity watch to keep it "open"
+    _rx_worker_activity: watch::Receiver<WorkerActivity>,
+    /// Watch tracking the votes collected for our last proposed header.
+    tx_vote_aggregation: watch::Sender<Option<VoteAggregation>>,
+    /// Hold onto the vote aggregation watch to keep it "open"
+    _rx_vote_aggregation: watch::Receiver<Option<VoteAggregation>>,
+    /// Watch counting requests to propose a header without waiting for the header delays.
+    tx_propose_now: watch::Sender<u64>,
+    /// Hold onto the propose now watch to keep it "open"
+    _rx_propose_now: watch::Receiver<u64>,
 
     /// Consensus output with a consensus header.
-    consensus_output: broadcast::Sender<ConsensusOutput>,
+    consensus_output: EventBroadcast<ConsensusOutput>,
     /// Consensus header.  Note this can be used to create consensus output to execute for non
     /// validators.
     consensus_header: broadcast::Sender<ConsensusHeader>,
     /// Status of sync?
-    tx_sync_status: watch::Sender<NodeMode>,
-    /// Hold onto the recent sync_status to keep it "open"
-    _rx_sync_status: watch::Receiver<NodeMode>,
+    tx_sync_status: EventWatch<NodeMode>,
+    /// Typed events for subscribers outside the consensus pipeline.
+    events: broadcast::Sender<ConsensusEvent>,
 
     /// Hold onto the consensus_metrics (mostly for testing)
     consensus_metrics: Arc<ConsensusMetrics>,
@@ -135,6 +164,9 @@ struct ConsensusBusInner {
     /// Hold onto the executor metrics.
     executor_metrics: Arc<ExecutorMetrics>,
 
+    /// Certificates verified by any code path, shared so they are only verified once.
+    verified_certificates: VerifiedCertificates,
+
     /// Flag to indicate a node should restart after a shutdown.
     restart: AtomicBool,
 }
@@ -155,6 +187,8 @@ impl Default for ConsensusBus {
 /// A new bus can be created with new() but there should only ever be one created (except for
 /// tests). This allows us to not create and pass channels all over the place add-hoc.
 /// It also allows makes it much easier to find where channels are fed and consumed.
+/// Subsystems that only observe consensus should use [ConsensusBus::subscribe_events] instead of
+/// adding a new channel. The channels that report consensus progress publish the events.
 impl ConsensusBus {
     /// Create a new consensus bus.
     pub fn new() -> Self {
@@ -163,114 +197,182 @@ impl ConsensusBus {
         // (some testing liked this).  Using the default to not overly complicate
         // creation of the bus.
         // This is basically for testing.
-        Self::new_with_args(Parameters::default_gc_depth())
+        Self::new_with_args(Parameters::default_gc_depth(), &ChannelParameters::default())
     }
 
     /// Create a new consensus bus.
-    /// Store recent_blocks number of the last generated execution blocks.
-    pub fn new_with_args(recent_blocks: u32) -> Self {
+    /// Store recent_blocks number of the last generated execution blocks and size the channels
+    /// with `channels`.
+    pub fn new_with_args(recent_blocks: u32, channels: &ChannelParameters) -> Self {
+        let consensus_capacity = |name: &str| channels.capacity(ChannelClass::Consensus, name);
+        let broadcast_capacity = |name: &str| channels.capacity(ChannelClass::Broadcast, name);
         let consensus_metrics = Arc::new(ConsensusMetrics::default());
         let primary_metrics = Arc::new(Metrics::default()); // Initialize the metrics
         let channel_metrics = Arc::new(ChannelMetrics::default());
         let executor_metrics = Arc::new(ExecutorMetrics::default());
         let new_certificates = metered_channel::channel_sender(
-            CHANNEL_CAPACITY,
+            consensus_capacity("new_certificates"),
             &primary_metrics.primary_channel_metrics.tx_new_certificates,
         );
 
         let committed_certificates = metered_channel::channel_sender(
-            CHANNEL_CAPACITY,
+            consensus_capacity("committed_certificates"),
             &primary_metrics.primary_channel_metrics.tx_committed_certificates,
         );
 
-        let (tx_committed_round_updates, _rx_committed_round_updates) =
-            watch::channel(Round::default());
+        // typed events are published by the watch and broadcast channels that report progress
+        let (events, _rx_events) = broadcast::channel(broadcast_capacity("events"));
+
+        let tx_committed_round_updates =
+            EventWatch::new(Round::default(), events.clone(), |round| {
+                ConsensusEvent::CommittedRound(*round)
+            });
 
-        let (tx_gc_round_updates, _rx_gc_round_updates) = watch::channel(Round::default());
+        let tx_gc_round_updates = EventWatch::new(Round::default(), events.clone(), |round| {
+            ConsensusEvent::GcRound(*round)
+        });
 
         let our_digests = channel_with_total_sender(
-            CHANNEL_CAPACITY,
+            consensus_capacity("our_digests"),
             &primary_metrics.primary_channel_metrics.tx_our_digests,
             &primary_metrics.primary_channel_metrics.tx_our_digests_total,
         );
         let parents = channel_with_total_sender(
-            CHANNEL_CAPACITY,
+            consensus_capacity("parents"),
             &primary_metrics.primary_channel_metrics.tx_parents,
             &primary_metrics.primary_channel_metrics.tx_parents_total,
         );
         let headers = channel_with_total_sender(
-            CHANNEL_CAPACITY,
+            consensus_capacity("headers"),
             &primary_metrics.primary_channel_metrics.tx_headers,
             &primary_metrics.primary_channel_metrics.tx_headers_total,
         );
         let certificate_fetcher = channel_with_total_sender(
-            CHANNEL_CAPACITY,
+            consensus_capacity("certificate_fetcher"),
             &primary_metrics.primary_channel_metrics.tx_certificate_fetcher,
             &primary_metrics.primary_channel_metrics.tx_certificate_fetcher_total,
         );
         let committed_own_headers = channel_with_total_sender(
-            CHANNEL_CAPACITY,
+            consensus_capacity("committed_own_headers"),
             &primary_metrics.primary_channel_metrics.tx_committed_own_headers,
             &primary_metrics.primary_channel_metrics.tx_committed_own_headers_total,
         );
 
         let certificate_manager = channel_with_total_sender(
-            CHANNEL_CAPACITY,
+            consensus_capacity("certificate_manager"),
             &primary_metrics.primary_channel_metrics.tx_certificate_acceptor,
             &primary_metrics.primary_channel_metrics.tx_certificate_acceptor_total,
         );
 
-        let (tx_primary_round_updates, _rx_primary_round_updates) = watch::channel(0u32);
-        let (tx_last_consensus_header, _rx_last_consensus_header) =
-            watch::channel(ConsensusHeader::default());
+        let tx_primary_round_updates =
+            EventWatch::new(0u32, events.clone(), |round| ConsensusEvent::PrimaryRound(*round));
+        let tx_catch_up_round =
+            EventWatch::new(None, events.clone(), |round| ConsensusEvent::CatchUp(*round));
+        let tx_last_consensus_header =
+            EventWatch::new(ConsensusHeader::default(), events.clone(), |header| {
+                ConsensusEvent::ConsensusHeader { number: header.number, digest: header.digest() }
+            });
         let (tx_last_published_consensus_num_hash, _rx_last_published_consensus_num_hash) =
             watch::channel((0, BlockHash::default()));
+        let (tx_last_announced_block, _rx_last_announced_block) = watch::channel(None);
+        let tx_halt_at_sub_dag =
+            EventWatch::new(None, events.clone(), |number| ConsensusEvent::HaltAtSubDag(*number));
+        let (tx_restore_progress, _rx_restore_progress) = watch::channel(None);
+        let (tx_worker_activity, _rx_worker_activity) = watch::channel(WorkerActivity::default());
+        let (tx_vote_aggregation, _rx_vote_aggregation) = watch::channel(None);
+        let (tx_propose_now, _rx_propose_now) = watch::channel(0);
+
+        let tx_recent_blocks =
+            EventWatch::new(RecentBlocks::new(recent_blocks as usize), events.clone(), |blocks| {
+                ConsensusEvent::BlockExecuted(blocks.latest_block_num_hash())
+            });
+        let tx_sync_status = EventWatch::new(NodeMode::default(), events.clone(), |mode| {
+            ConsensusEvent::NodeMode(*mode)
+        });
+
+        let sequence = metered_channel::channel_sender(
+            consensus_capacity("sequence"),
+            &channel_metrics.tx_sequence,
+        );
 
-        let (tx_recent_blocks, _rx_recent_blocks) =
-            watch::channel(RecentBlocks::new(recent_blocks as usize));
-        let (tx_sync_status, _rx_sync_status) = watch::channel(NodeMode::default());
-
-        let sequence =
-            metered_channel::channel_sender(CHANNEL_CAPACITY, &channel_metrics.tx_sequence);
-
-        let (consensus_output, _rx_consensus_output) = broadcast::channel(CHANNEL_CAPACITY);
-        let (consensus_header, _rx_consensus_header) = broadcast::channel(CHANNEL_CAPACITY);
-
-        Self {
-            inner: Arc::new(ConsensusBusInner {
-                new_certificates,
-                committed_certificates,
-                tx_committed_round_updates,
-                _rx_committed_round_updates,
-                tx_gc_round_updates,
-                _rx_gc_round_updates,
-                certificate_fetcher,
-                parents,
-                our_digests,
-                headers,
-                committed_own_headers,
-                sequence,
-                certificate_manager,
-
-                tx_primary_round_updates,
-                _rx_primary_round_updates,
-                tx_recent_blocks,
-                _rx_recent_blocks,
-                tx_last_consensus_header,
-                _rx_last_consensus_header,
-                tx_last_published_consensus_num_hash,
-                _rx_last_published_consensus_num_hash,
-                consensus_output,
-                consensus_header,
-                tx_sync_status,
-                _rx_sync_status,
-                consensus_metrics,
-                primary_metrics,
-                channel_metrics,
-                executor_metrics,
-                restart: AtomicBool::new(false),
-            }),
-        }
+        let consensus_output =
+            EventBroadcast::new(broadcast_capacity("consensus_output"), events.clone(), |output| {
+                ConsensusEvent::ConsensusOutput {
+                    number: output.number,
+                    leader_round: output.leader_round(),
+                    digest: output.consensus_header_hash(),
+                }
+            });
+        let (consensus_header, _rx_consensus_header) =
+            broadcast::channel(broadcast_capacity("consensus_header"));
+
+        let inner = Arc::new(ConsensusBusInner {
+            new_certificates,
+            committed_certificates,
+            tx_committed_round_updates,
+            tx_gc_round_updates,
+            certificate_fetcher,
+            parents,
+            our_digests,
+            headers,
+            committed_own_headers,
+            sequence,
+            certificate_manager,
+
+            tx_primary_round_updates,
+            tx_catch_up_round,
+            tx_recent_blocks,
+            tx_last_consensus_header,
+            tx_last_published_consensus_num_hash,
+            _rx_last_published_consensus_num_hash,
+            tx_last_announced_block,
+            _rx_last_announced_block,
+            tx_halt_at_sub_dag,
+            tx_restore_progress,
+            _rx_restore_progress,
+            tx_worker_activity,
+            _rx_worker_activity,
+            tx_vote_aggregation,
+            _rx_vote_aggregation,
+            tx_propose_now,
+            _rx_propose_now,
+            consensus_output,
+            consensus_header,
+            tx_sync_status,
+            events,
+            consensus_metrics,
+            primary_metrics,
+            channel_metrics,
+            executor_metrics,
+            verified_certificates: VerifiedCertificates::default(),
+            restart: AtomicBool::new(false),
+        });
+        Self::register_broadcasts(&inner, channels);
+        Self { inner }
+    }
+
+    /// Register the broadcast channels so their backlogs are listed with the metered channels.
+    ///
+    /// The depth of a broadcast channel is the number of messages retained for its slowest
+    /// receiver. The registry only holds a weak reference so it never keeps the bus alive.
+    fn register_broadcasts(inner: &Arc<ConsensusBusInner>, channels: &ChannelParameters) {
+        let broadcast_capacity = |name: &str| channels.capacity(ChannelClass::Broadcast, name);
+        let bus = Arc::downgrade(inner);
+        register_channel("consensus_bus_events", broadcast_capacity("events"), move || {
+            bus.upgrade().map(|inner| inner.events.len())
+        });
+        let bus = Arc::downgrade(inner);
+        register_channel(
+            "consensus_bus_consensus_output",
+            broadcast_capacity("consensus_output"),
+            move || bus.upgrade().map(|inner| inner.consensus_output.queued()),
+        );
+        let bus = Arc::downgrade(inner);
+        register_channel(
+            "consensus_bus_consensus_header",
+            broadcast_capacity("consensus_header"),
+            move || bus.upgrade().map(|inner| inner.consensus_header.len()),
+        );
     }
 
     /// New certificates.
@@ -308,20 +410,29 @@ impl ConsensusBus {
     }
 
     /// Contains the highest committed round & corresponding gc_round for consensus.
-    pub fn committed_round_updates(&self) -> &watch::Sender<Round> {
+    pub fn committed_round_updates(&self) -> &EventWatch<Round> {
         &self.inner.tx_committed_round_updates
     }
 
     /// Contains the highest gc_round for consensus.
-    pub fn gc_round_updates(&self) -> &watch::Sender<Round> {
+    pub fn gc_round_updates(&self) -> &EventWatch<Round> {
         &self.inner.tx_gc_round_updates
     }
 
     /// Signals a new round
-    pub fn primary_round_updates(&self) -> &watch::Sender<Round> {
+    pub fn primary_round_updates(&self) -> &EventWatch<Round> {
         &self.inner.tx_primary_round_updates
     }
 
+    /// The round this node fell behind from.
+    ///
+    /// The proposer sets this when parents arrive many rounds ahead of its own round and clears it
+    /// once the node is proposing with the quorum again. Peers push certificates after this round
+    /// while it is set.
+    pub fn catch_up_round(&self) -> &EventWatch<Option<Round>> {
+        &self.inner.tx_catch_up_round
+    }
+
     /// Batches' digests from our workers.
     /// Can only be subscribed to once.
     pub fn our_digests(&self) -> &impl TnSender<OurDigestMessage> {
@@ -357,12 +468,12 @@ impl ConsensusBus {
     }
 
     /// Track recent blocks.
-    pub fn recent_blocks(&self) -> &watch::Sender<RecentBlocks> {
+    pub fn recent_blocks(&self) -> &EventWatch<RecentBlocks> {
         &self.inner.tx_recent_blocks
     }
 
     /// Track the latest consensus header.
-    pub fn last_consensus_header(&self) -> &watch::Sender<ConsensusHeader> {
+    pub fn last_consensus_header(&self) -> &EventWatch<ConsensusHeader> {
         &self.inner.tx_last_consensus_header
     }
 
@@ -373,6 +484,53 @@ impl ConsensusBus {
         &self.inner.tx_last_published_consensus_num_hash
     }
 
+    /// Track the latest executed block announced by the committee, with the number of the
+    /// consensus header it was executed for.
+    ///
+    /// Only observers subscribe to announcements. The header matches its announced hash and extends
+    /// a block this node executed or the previous announced block, but the block itself is not
+    /// executed.
+    pub fn last_announced_block(&self) -> &watch::Sender<Option<(u64, SealedHeader)>> {
+        &self.inner.tx_last_announced_block
+    }
+
+    /// The consensus sub-dag index to stop the node at once it is executed.
+    ///
+    /// The execution engine exits after executing this sub-dag, which shuts down the node without
+    /// a restart. Used to coordinate upgrades across the committee.
+    pub fn halt_at_sub_dag(&self) -> &EventWatch<Option<u64>> {
+        &self.inner.tx_halt_at_sub_dag
+    }
+
+    /// Progress restoring consensus output that was committed but not executed before a restart.
+    ///
+    /// None when the node is not restoring.
+    pub fn restore_progress(&self) -> &watch::Sender<Option<RestoreProgress>> {
+        &self.inner.tx_restore_progress
+    }
+
+    /// The last batch or heartbeat reported by each of our workers.
+    ///
+    /// Used to tell an idle worker from a worker that is down.
+    pub fn worker_activity(&self) -> &watch::Sender<WorkerActivity> {
+        &self.inner.tx_worker_activity
+    }
+
+    /// The votes collected by the certifier for our last proposed header.
+    ///
+    /// None until the first header is proposed. Used to diagnose stalled certification.
+    pub fn vote_aggregation(&self) -> &watch::Sender<Option<VoteAggregation>> {
+        &self.inner.tx_vote_aggregation
+    }
+
+    /// Requests for the proposer to propose its next header as soon as it has parents.
+    ///
+    /// The proposer skips the header delays and batch threshold for the next header after each
+    /// change. Used to mine blocks on demand in single-node dev networks.
+    pub fn propose_now(&self) -> &watch::Sender<u64> {
+        &self.inner.tx_propose_now
+    }
+
     /// Broadcast channel with consensus output (includes the consensus chain block).
     /// This also provides the ConsesusHeader, use this for block execution.
     pub fn consensus_output(&self) -> &impl TnSender<ConsensusOutput> {
@@ -383,7 +541,7 @@ impl ConsensusBus {
     /// This breaks the trait pattern in order to return a concrete receiver to pass to the
     /// execution module.
     pub fn subscribe_consensus_output(&self) -> broadcast::Receiver<ConsensusOutput> {
-        self.inner.consensus_output.subscribe()
+        self.inner.consensus_output.subscribe_broadcast()
     }
 
     /// Broadcast channel with consensus header.
@@ -393,10 +551,31 @@ impl ConsensusBus {
     }
 
     /// Status of initial sync operation.
-    pub fn node_mode(&self) -> &watch::Sender<NodeMode> {
+    pub fn node_mode(&self) -> &EventWatch<NodeMode> {
         &self.inner.tx_sync_status
     }
 
+    /// Publish a typed event to every event subscriber.
+    ///
+    /// Events are dropped if there are no subscribers. Updates to the bus channels publish their
+    /// events already.
+    pub fn publish_event(&self, event: ConsensusEvent) {
+        let _ = self.inner.events.send(event);
+    }
+
+    /// Subscribe to typed consensus events that pass the filter.
+    ///
+    /// The name identifies the subscriber in logs and lag metrics.
+    pub fn subscribe_events(
+        &self,
+        name: impl Into<String>,
+        filter: ConsensusEventFilter,
+    ) -> ConsensusEventSubscriber {
+        let name = name.into();
+        let lagged = self.inner.channel_metrics.consensus_events_lagged.with_label_values(&[&name]);
+        ConsensusEventSubscriber::new(name, filter, self.inner.events.subscribe(), lagged)
+    }
+
     /// Hold onto the consensus_metrics (mostly for testing)
     pub fn consensus_metrics(&self) -> Arc<ConsensusMetrics> {
         self.inner.consensus_metrics.clone()
@@ -417,6 +596,11 @@ impl ConsensusBus {
         &self.inner.executor_metrics
     }
 
+    /// Certificates with verified aggregate signatures.
+    pub fn verified_certificates(&self) -> &VerifiedCertificates {
+        &self.inner.verified_certificates
+    }
+
     /// Set the restart flag to indicate node restart after shutdown.
     pub fn set_restart(&self) {
         self.inner.restart.store(true, std::sync::atomic::Ordering::SeqCst);
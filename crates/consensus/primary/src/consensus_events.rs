@@ -0,0 +1,361 @@
+//! Typed consensus events published on the [ConsensusBus].
+//!
+//! The bus keeps dedicated channels for the consensus pipeline. The channels that report consensus
+//! progress are [EventWatch]es and [EventBroadcast]s, which publish a [ConsensusEvent] with every
+//! update. Subsystems that only observe consensus (alerting, indexing, etc) subscribe to events
+//! with a filter instead of adding another channel to the bus. Events are broadcast, so a slow
+//! subscriber misses events instead of applying backpressure to consensus. Missed events are
+//! counted per subscriber.
+
+use crate::{ConsensusBus, NodeMode};
+use prometheus::IntCounter;
+use std::collections::HashSet;
+use tn_types::{BlockHash, BlockNumHash, Round, SendError, TnReceiver, TnSender, TrySendError};
+use tokio::sync::{
+    broadcast::{self, error::RecvError},
+    watch,
+};
+use tracing::warn;
+
+/// An event from consensus.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConsensusEvent {
+    /// The primary advanced to a new round.
+    PrimaryRound(Round),
+    /// The highest round committed by consensus.
+    CommittedRound(Round),
+    /// The garbage collection round for consensus.
+    GcRound(Round),
+    /// The round this node fell behind from, or `None` once it caught up.
+    CatchUp(Option<Round>),
+    /// The node changed mode.
+    NodeMode(NodeMode),
+    /// The latest consensus header.
+    ConsensusHeader {
+        /// The consensus block number (sub-dag index).
+        number: u64,
+        /// The consensus header's digest.
+        digest: BlockHash,
+    },
+    /// Consensus output was sent to the execution engine.
+    ConsensusOutput {
+        /// The consensus block number (sub-dag index).
+        number: u64,
+        /// The round of the sub-dag's leader.
+        leader_round: Round,
+        /// The digest of the output's consensus header.
+        digest: BlockHash,
+    },
+    /// The latest execution block.
+    BlockExecuted(BlockNumHash),
+    /// The sub-dag the node stops at once it is executed.
+    HaltAtSubDag(Option<u64>),
+}
+
+impl ConsensusEvent {
+    /// The kind of this event.
+    pub fn kind(&self) -> ConsensusEventKind {
+        match self {
+            Self::PrimaryRound(_) => ConsensusEventKind::PrimaryRound,
+            Self::CommittedRound(_) => ConsensusEventKind::CommittedRound,
+            Self::GcRound(_) => ConsensusEventKind::GcRound,
+            Self::CatchUp(_) => ConsensusEventKind::CatchUp,
+            Self::NodeMode(_) => ConsensusEventKind::NodeMode,
+            Self::ConsensusHeader { .. } => ConsensusEventKind::ConsensusHeader,
+            Self::ConsensusOutput { .. } => ConsensusEventKind::ConsensusOutput,
+            Self::BlockExecuted(_) => ConsensusEventKind::BlockExecuted,
+            Self::HaltAtSubDag(_) => ConsensusEventKind::HaltAtSubDag,
+        }
+    }
+}
+
+/// The kinds of [ConsensusEvent] used to filter subscriptions.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum ConsensusEventKind {
+    /// [ConsensusEvent::PrimaryRound]
+    PrimaryRound,
+    /// [ConsensusEvent::CommittedRound]
+    CommittedRound,
+    /// [ConsensusEvent::GcRound]
+    GcRound,
+    /// [ConsensusEvent::CatchUp]
+    CatchUp,
+    /// [ConsensusEvent::NodeMode]
+    NodeMode,
+    /// [ConsensusEvent::ConsensusHeader]
+    ConsensusHeader,
+    /// [ConsensusEvent::ConsensusOutput]
+    ConsensusOutput,
+    /// [ConsensusEvent::BlockExecuted]
+    BlockExecuted,
+    /// [ConsensusEvent::HaltAtSubDag]
+    HaltAtSubDag,
+}
+
+/// The kinds of events a subscriber receives.
+#[derive(Clone, Debug, Default)]
+pub struct ConsensusEventFilter {
+    /// The kinds to receive, or `None` for every event.
+    kinds: Option<HashSet<ConsensusEventKind>>,
+}
+
+impl ConsensusEventFilter {
+    /// Receive every event.
+    pub fn all() -> Self {
+        Self::default()
+    }
+
+    /// Only receive events of the given kinds.
+    pub fn only(kinds: impl IntoIterator<Item = ConsensusEventKind>) -> Self {
+        Self { kinds: Some(kinds.into_iter().collect()) }
+    }
+
+    /// True if the filter passes events of this kind.
+    pub fn matches(&self, kind: ConsensusEventKind) -> bool {
+        self.kinds.as_ref().is_none_or(|kinds| kinds.contains(&kind))
+    }
+}
+
+/// A filtered subscription to [ConsensusEvent]s.
+///
+/// Create with [ConsensusBus::subscribe_events].
+#[derive(Debug)]
+pub struct ConsensusEventSubscriber {
+    /// The subscriber's name used for logs and metrics.
+    name: String,
+    /// The kinds of events returned.
+    filter: ConsensusEventFilter,
+    /// The receiving side of the bus events.
+    rx: broadcast::Receiver<ConsensusEvent>,
+    /// Counts events this subscriber missed by falling behind.
+    lagged: IntCounter,
+}
+
+impl ConsensusEventSubscriber {
+    /// Create a new instance of [Self].
+    pub(crate) fn new(
+        name: String,
+        filter: ConsensusEventFilter,
+        rx: broadcast::Receiver<ConsensusEvent>,
+        lagged: IntCounter,
+    ) -> Self {
+        Self { name, filter, rx, lagged }
+    }
+
+    /// The subscriber's name.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Return the next event that passes the filter.
+    ///
+    /// Events missed by falling behind are counted and skipped. Returns `None` once the bus is
+    /// dropped.
+    pub async fn recv(&mut self) -> Option<ConsensusEvent> {
+        loop {
+            match self.rx.recv().await {
+                Ok(event) if self.filter.matches(event.kind()) => return Some(event),
+                Ok(_) => {}
+                Err(RecvError::Lagged(missed)) => {
+                    warn!(
+                        target: "consensus::events",
+                        subscriber = self.name,
+                        missed,
+                        "consensus event subscriber lagged"
+                    );
+                    self.lagged.inc_by(missed);
+                }
+                Err(RecvError::Closed) => return None,
+            }
+        }
+    }
+}
+
+/// A watch channel on the [ConsensusBus] that publishes a [ConsensusEvent] with every update.
+///
+/// Mirrors the [watch::Sender] methods used by consensus so updates can't bypass the events.
+#[derive(Debug)]
+pub struct EventWatch<T> {
+    /// The watch channel.
+    tx: watch::Sender<T>,
+    /// Hold onto a receiver to keep the channel "open".
+    _rx: watch::Receiver<T>,
+    /// The bus events.
+    events: broadcast::Sender<ConsensusEvent>,
+    /// The event for the current value.
+    to_event: fn(&T) -> ConsensusEvent,
+}
+
+impl<T> EventWatch<T> {
+    /// Create a new instance of [Self].
+    pub(crate) fn new(
+        init: T,
+        events: broadcast::Sender<ConsensusEvent>,
+        to_event: fn(&T) -> ConsensusEvent,
+    ) -> Self {
+        let (tx, _rx) = watch::channel(init);
+        Self { tx, _rx, events, to_event }
+    }
+
+    /// Send a new value, see [watch::Sender::send].
+    pub fn send(&self, value: T) -> Result<(), watch::error::SendError<T>> {
+        self.tx.send(value)?;
+        self.publish();
+        Ok(())
+    }
+
+    /// Replace the value and return the previous value, see [watch::Sender::send_replace].
+    pub fn send_replace(&self, value: T) -> T {
+        let previous = self.tx.send_replace(value);
+        self.publish();
+        previous
+    }
+
+    /// Modify the value in place, see [watch::Sender::send_modify].
+    pub fn send_modify<F: FnOnce(&mut T)>(&self, modify: F) {
+        self.tx.send_modify(modify);
+        self.publish();
+    }
+
+    /// Modify the value in place and notify receivers if `modify` returns true, see
+    /// [watch::Sender::send_if_modified].
+    pub fn send_if_modified<F: FnOnce(&mut T) -> bool>(&self, modify: F) -> bool {
+        let modified = self.tx.send_if_modified(modify);
+        if modified {
+            self.publish();
+        }
+        modified
+    }
+
+    /// Subscribe to the watch channel.
+    pub fn subscribe(&self) -> watch::Receiver<T> {
+        self.tx.subscribe()
+    }
+
+    /// Borrow the current value.
+    pub fn borrow(&self) -> watch::Ref<'_, T> {
+        self.tx.borrow()
+    }
+
+    /// Publish the event for the current value.
+    fn publish(&self) {
+        let event = (self.to_event)(&self.tx.borrow());
+        // events are dropped if there are no subscribers
+        let _ = self.events.send(event);
+    }
+}
+
+/// A broadcast channel on the [ConsensusBus] that publishes a [ConsensusEvent] for every message.
+#[derive(Clone, Debug)]
+pub struct EventBroadcast<T> {
+    /// The broadcast channel.
+    tx: broadcast::Sender<T>,
+    /// The bus events.
+    events: broadcast::Sender<ConsensusEvent>,
+    /// The event for a message.
+    to_event: fn(&T) -> ConsensusEvent,
+}
+
+impl<T: Clone> EventBroadcast<T> {
+    /// Create a new instance of [Self].
+    pub(crate) fn new(
+        capacity: usize,
+        events: broadcast::Sender<ConsensusEvent>,
+        to_event: fn(&T) -> ConsensusEvent,
+    ) -> Self {
+        let (tx, _rx) = broadcast::channel(capacity);
+        Self { tx, events, to_event }
+    }
+
+    /// The number of messages retained for the slowest receiver.
+    pub(crate) fn queued(&self) -> usize {
+        self.tx.len()
+    }
+
+    /// Subscribe to the broadcast channel.
+    pub(crate) fn subscribe_broadcast(&self) -> broadcast::Receiver<T> {
+        self.tx.subscribe()
+    }
+
+    /// Publish the event and broadcast the message.
+    fn broadcast(&self, value: T) {
+        let _ = self.events.send((self.to_event)(&value));
+        // this only fails if there are no receivers, which is fine
+        let _ = self.tx.send(value);
+    }
+}
+
+impl<T: Send + Sync + Clone + 'static> TnSender<T> for EventBroadcast<T> {
+    async fn send(&self, value: T) -> Result<(), SendError<T>> {
+        self.broadcast(value);
+        Ok(())
+    }
+
+    fn try_send(&self, value: T) -> Result<(), TrySendError<T>> {
+        self.broadcast(value);
+        Ok(())
+    }
+
+    fn subscribe(&self) -> impl TnReceiver<T> + 'static {
+        self.tx.subscribe()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tn_types::CHANNEL_CAPACITY;
+
+    #[tokio::test]
+    async fn test_filtered_subscription() {
+        let consensus_bus = ConsensusBus::new();
+        let mut rounds = consensus_bus.subscribe_events(
+            "rounds",
+            ConsensusEventFilter::only([ConsensusEventKind::PrimaryRound]),
+        );
+        let mut all = consensus_bus.subscribe_events("all", ConsensusEventFilter::all());
+
+        consensus_bus.publish_event(ConsensusEvent::CommittedRound(2));
+        consensus_bus.publish_event(ConsensusEvent::PrimaryRound(3));
+
+        assert_eq!(rounds.recv().await, Some(ConsensusEvent::PrimaryRound(3)));
+        assert_eq!(all.recv().await, Some(ConsensusEvent::CommittedRound(2)));
+        assert_eq!(all.recv().await, Some(ConsensusEvent::PrimaryRound(3)));
+    }
+
+    #[tokio::test]
+    async fn test_bus_updates_publish_events() {
+        let consensus_bus = ConsensusBus::new();
+        let mut events = consensus_bus.subscribe_events("all", ConsensusEventFilter::all());
+
+        consensus_bus.primary_round_updates().send(4).expect("watch open");
+        consensus_bus.node_mode().send_modify(|mode| *mode = NodeMode::CvvInactive);
+        // unchanged values are not published
+        assert!(!consensus_bus.catch_up_round().send_if_modified(|_| false));
+        consensus_bus.halt_at_sub_dag().send_replace(Some(9));
+
+        assert_eq!(events.recv().await, Some(ConsensusEvent::PrimaryRound(4)));
+        assert_eq!(events.recv().await, Some(ConsensusEvent::NodeMode(NodeMode::CvvInactive)));
+        assert_eq!(events.recv().await, Some(ConsensusEvent::HaltAtSubDag(Some(9))));
+    }
+
+    #[tokio::test]
+    async fn test_lagged_subscriber() {
+        let consensus_bus = ConsensusBus::new();
+        let mut slow = consensus_bus.subscribe_events("slow", ConsensusEventFilter::all());
+
+        let missed = 5;
+        for round in 0..(CHANNEL_CAPACITY + missed) as Round {
+            consensus_bus.publish_event(ConsensusEvent::PrimaryRound(round));
+        }
+
+        // the oldest events are skipped and counted
+        assert_eq!(slow.recv().await, Some(ConsensusEvent::PrimaryRound(missed as Round)));
+        let lagged = consensus_bus
+            .channel_metrics()
+            .consensus_events_lagged
+            .with_label_values(&["slow"])
+            .get();
+        assert_eq!(lagged, missed as u64);
+    }
+}
@@ -1,6 +1,7 @@
 //! Error types for primary's network task.
 
 use super::CertManagerError;
+use crate::network::{catch_up_budget::CatchUpShed, certificate_budget::CertificateShed};
 use tn_storage::StoreError;
 use tn_types::{error::HeaderError, BcsError, BlockHash};
 
@@ -31,6 +32,18 @@ pub(crate) enum PrimaryNetworkError {
     /// Internal error occurred.
     #[error("Internal error: {0}")]
     Internal(String),
+    /// The peer gossiped an invalid worker info update.
+    #[error("Invalid worker info update: {0}")]
+    InvalidWorkerUpdate(String),
+    /// The peer gossiped an invalid block announcement.
+    #[error("Invalid block announcement: {0}")]
+    InvalidBlockAnnouncement(String),
+    /// The peer's certificate was dropped without processing.
+    #[error("Certificate shed: {0:?}")]
+    CertificateShed(CertificateShed),
+    /// The peer's catch up request was not answered.
+    #[error("Catch up shed: {0:?}")]
+    CatchUpShed(CatchUpShed),
     /// Unknown consensus header.
     #[error("Unknown consensus header: {0}")]
     UnknowConsensusHeaderNumber(u64),
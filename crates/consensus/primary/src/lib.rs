@@ -27,5 +27,20 @@ pub use crate::primary::Primary;
 mod consensus_bus;
 pub use consensus_bus::*;
 
+mod consensus_events;
+pub use consensus_events::*;
+
 mod recent_blocks;
 pub use recent_blocks::*;
+
+mod restore_progress;
+pub use restore_progress::*;
+
+mod verified_certificates;
+pub use verified_certificates::*;
+
+mod vote_aggregation;
+pub use vote_aggregation::*;
+
+mod worker_activity;
+pub use worker_activity::*;
@@ -0,0 +1,222 @@
+//! Budget for catch up requests that peers gossip.
+//!
+//! A catch up request is a single round but the answer can push thousands of certificates to the
+//! peer. Each peer has at most one catch up answered at a time and earns tokens for more catch ups
+//! at a fixed rate, so a peer can't amplify a stream of tiny requests into a stream of large
+//! responses.
+
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tn_config::CatchUpBudgetParameters;
+use tn_network_libp2p::PeerId;
+
+/// The time after which an idle budget is evicted.
+///
+/// By then its tokens are refilled, so a new budget is equivalent.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// How often idle budgets are evicted.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Why a catch up request was not answered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CatchUpShed {
+    /// The peer's previous catch up is still being answered.
+    InFlight,
+    /// The peer sent more catch up requests than its rate allows.
+    RateLimited,
+}
+
+impl CatchUpShed {
+    /// The label for metrics.
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::InFlight => "in_flight",
+            Self::RateLimited => "rate_limited",
+        }
+    }
+}
+
+/// The budget for one peer.
+#[derive(Debug)]
+struct PeerBudget {
+    /// The available tokens.
+    tokens: f64,
+    /// True while a catch up for the peer is being answered.
+    in_flight: bool,
+    /// When the tokens were last updated.
+    updated: Instant,
+}
+
+impl PeerBudget {
+    /// Refill tokens up to `now`.
+    fn update(&mut self, now: Instant, rate: f64, burst: f64) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(burst);
+        self.updated = now;
+    }
+}
+
+/// The budgets of every peer.
+#[derive(Debug)]
+struct Budgets {
+    /// Budgets by peer.
+    peers: HashMap<PeerId, PeerBudget>,
+    /// When idle budgets were last evicted.
+    last_prune: Instant,
+}
+
+impl Budgets {
+    /// Evict budgets that have been idle for [IDLE_TIMEOUT], at most once per [PRUNE_INTERVAL].
+    fn prune(&mut self, now: Instant) {
+        if now.saturating_duration_since(self.last_prune) < PRUNE_INTERVAL {
+            return;
+        }
+        self.peers.retain(|_, budget| {
+            budget.in_flight || now.saturating_duration_since(budget.updated) < IDLE_TIMEOUT
+        });
+        self.last_prune = now;
+    }
+}
+
+/// Rate limits the catch up requests answered for each peer.
+///
+/// Clones share the same budget.
+#[derive(Debug, Clone)]
+pub(crate) struct CatchUpBudget {
+    /// The configured limits.
+    params: CatchUpBudgetParameters,
+    /// Budgets by peer.
+    budgets: Arc<Mutex<Budgets>>,
+}
+
+impl CatchUpBudget {
+    /// Create a new instance of Self.
+    pub(crate) fn new(params: CatchUpBudgetParameters) -> Self {
+        let budgets = Budgets { peers: HashMap::new(), last_prune: Instant::now() };
+        Self { params, budgets: Arc::new(Mutex::new(budgets)) }
+    }
+
+    /// Admit a catch up request from `peer`.
+    ///
+    /// The peer's catch up is in flight until the permit is dropped.
+    pub(crate) fn try_admit(&self, peer: PeerId) -> Result<CatchUpPermit, CatchUpShed> {
+        self.try_admit_at(peer, Instant::now())
+    }
+
+    /// Admit a catch up request from `peer` at `now`.
+    fn try_admit_at(&self, peer: PeerId, now: Instant) -> Result<CatchUpPermit, CatchUpShed> {
+        let rate = self.params.requests_per_sec;
+        let burst = self.params.burst.max(1.0);
+        let mut budgets = self.budgets.lock();
+        budgets.prune(now);
+        let budget = budgets.peers.entry(peer).or_insert_with(|| PeerBudget {
+            tokens: burst,
+            in_flight: false,
+            updated: now,
+        });
+        budget.update(now, rate, burst);
+
+        if budget.in_flight {
+            return Err(CatchUpShed::InFlight);
+        }
+        if budget.tokens < 1.0 {
+            return Err(CatchUpShed::RateLimited);
+        }
+
+        budget.tokens -= 1.0;
+        budget.in_flight = true;
+        Ok(CatchUpPermit { peer, budgets: self.budgets.clone() })
+    }
+
+    /// The number of budgets tracked.
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.budgets.lock().peers.len()
+    }
+}
+
+/// Marks a peer's catch up in flight until dropped.
+#[derive(Debug)]
+pub(crate) struct CatchUpPermit {
+    /// The peer that requested catch up.
+    peer: PeerId,
+    /// The budget's peers.
+    budgets: Arc<Mutex<Budgets>>,
+}
+
+impl Drop for CatchUpPermit {
+    fn drop(&mut self) {
+        if let Some(budget) = self.budgets.lock().peers.get_mut(&self.peer) {
+            budget.in_flight = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CatchUpBudget, CatchUpShed, IDLE_TIMEOUT};
+    use std::time::{Duration, Instant};
+    use tn_config::CatchUpBudgetParameters;
+    use tn_network_libp2p::PeerId;
+
+    #[test]
+    fn test_one_catch_up_in_flight_per_peer() {
+        let params = CatchUpBudgetParameters { requests_per_sec: 1.0, burst: 10.0 };
+        let budget = CatchUpBudget::new(params);
+        let now = Instant::now();
+        let peer = PeerId::random();
+
+        let permit = budget.try_admit_at(peer, now).expect("first catch up");
+        assert_eq!(budget.try_admit_at(peer, now).unwrap_err(), CatchUpShed::InFlight);
+
+        // other peers are not affected
+        let _other = budget.try_admit_at(PeerId::random(), now).expect("other peer");
+
+        // the next catch up is admitted once the first is answered
+        drop(permit);
+        assert!(budget.try_admit_at(peer, now).is_ok());
+    }
+
+    #[test]
+    fn test_catch_up_rate_limit() {
+        let params = CatchUpBudgetParameters { requests_per_sec: 0.5, burst: 2.0 };
+        let budget = CatchUpBudget::new(params);
+        let now = Instant::now();
+        let peer = PeerId::random();
+
+        for _ in 0..2 {
+            drop(budget.try_admit_at(peer, now).expect("within burst"));
+        }
+        assert_eq!(budget.try_admit_at(peer, now).unwrap_err(), CatchUpShed::RateLimited);
+        assert_eq!(
+            budget.try_admit_at(peer, now + Duration::from_secs(1)).unwrap_err(),
+            CatchUpShed::RateLimited
+        );
+
+        // tokens refill at the configured rate
+        assert!(budget.try_admit_at(peer, now + Duration::from_secs(2)).is_ok());
+    }
+
+    #[test]
+    fn test_idle_catch_up_budgets_are_evicted() {
+        let budget = CatchUpBudget::new(CatchUpBudgetParameters::default());
+        let now = Instant::now();
+
+        let idle = PeerId::random();
+        drop(budget.try_admit_at(idle, now));
+        // budgets with a catch up in flight are kept
+        let busy = PeerId::random();
+        let _permit = budget.try_admit_at(busy, now).expect("admitted");
+        assert_eq!(budget.len(), 2);
+
+        let later = now + IDLE_TIMEOUT;
+        let _other = budget.try_admit_at(PeerId::random(), later).expect("admitted");
+        assert_eq!(budget.len(), 2);
+        assert_eq!(budget.try_admit_at(busy, later).unwrap_err(), CatchUpShed::InFlight);
+    }
+}
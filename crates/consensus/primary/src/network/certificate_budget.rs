@@ -0,0 +1,327 @@
+//! Budget for certificates that peers gossip without a request.
+//!
+//! Certificates are budgeted by their origin, so relaying a certificate through many peers doesn't
+//! multiply its author's budget. Each origin earns tokens at a rate that scales with its stake and
+//! spends one token per certificate. The number of certificates processed at once is capped, and
+//! as the node approaches the cap it sheds certificates from low priority origins before it sheds
+//! certificates from the rest of the committee.
+
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+use tn_config::CertificateBudgetParameters;
+use tn_types::{AuthorityIdentifier, Committee};
+
+/// The fraction of the average stake's rate given to peers outside the committee.
+const NON_COMMITTEE_WEIGHT: f64 = 0.1;
+
+/// The fraction of the in-flight limit at which low priority peers are shed.
+const SHED_LOAD: f64 = 0.5;
+
+/// The number of seconds for an origin's penalty to halve.
+const PENALTY_HALF_LIFE_SECS: f64 = 60.0;
+
+/// The time after which an idle budget is evicted.
+///
+/// By then its tokens are refilled and its penalty has decayed, so a new budget is equivalent.
+const IDLE_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// How often idle budgets are evicted.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Why a certificate was not processed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum CertificateShed {
+    /// The origin sent more certificates than its rate allows.
+    RateLimited,
+    /// The node is busy and the origin's priority is too low.
+    Overloaded,
+}
+
+impl CertificateShed {
+    /// The label for metrics.
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::RateLimited => "rate_limited",
+            Self::Overloaded => "overloaded",
+        }
+    }
+}
+
+/// The budget for one origin.
+#[derive(Debug)]
+struct PeerBudget {
+    /// The available tokens.
+    tokens: f64,
+    /// The penalty for invalid certificates, decaying over time.
+    penalty: f64,
+    /// When the tokens and penalty were last updated.
+    updated: Instant,
+}
+
+impl PeerBudget {
+    /// Refill tokens and decay the penalty up to `now`.
+    fn update(&mut self, now: Instant, rate: f64, burst: f64) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(burst);
+        self.penalty *= 0.5_f64.powf(elapsed / PENALTY_HALF_LIFE_SECS);
+        self.updated = now;
+    }
+}
+
+/// The budgets of every origin.
+#[derive(Debug)]
+struct Budgets {
+    /// Budgets by origin.
+    ///
+    /// Origins outside the committee share the `None` budget so forged origins can't grow the map.
+    origins: HashMap<Option<AuthorityIdentifier>, PeerBudget>,
+    /// When idle budgets were last evicted.
+    last_prune: Instant,
+}
+
+impl Budgets {
+    /// Evict budgets that have been idle for [IDLE_TIMEOUT], at most once per [PRUNE_INTERVAL].
+    fn prune(&mut self, now: Instant) {
+        if now.saturating_duration_since(self.last_prune) < PRUNE_INTERVAL {
+            return;
+        }
+        self.origins
+            .retain(|_, budget| now.saturating_duration_since(budget.updated) < IDLE_TIMEOUT);
+        self.last_prune = now;
+    }
+}
+
+/// Rate limits and prioritizes unsolicited certificates by origin.
+///
+/// Clones share the same budget.
+#[derive(Debug, Clone)]
+pub(crate) struct CertificateBudget {
+    /// The configured limits.
+    params: CertificateBudgetParameters,
+    /// Budgets by origin.
+    budgets: Arc<Mutex<Budgets>>,
+    /// The number of certificates being processed.
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl CertificateBudget {
+    /// Create a new instance of Self.
+    pub(crate) fn new(params: CertificateBudgetParameters) -> Self {
+        let budgets = Budgets { origins: HashMap::new(), last_prune: Instant::now() };
+        Self { params, budgets: Arc::new(Mutex::new(budgets)), in_flight: Default::default() }
+    }
+
+    /// Admit a certificate authored by `origin` for processing.
+    ///
+    /// The certificate counts against the in-flight limit until the permit is dropped.
+    pub(crate) fn try_admit(
+        &self,
+        origin: &AuthorityIdentifier,
+        committee: &Committee,
+    ) -> Result<CertificatePermit, CertificateShed> {
+        self.try_admit_at(origin, committee, Instant::now())
+    }
+
+    /// Admit a certificate authored by `origin` at `now`.
+    fn try_admit_at(
+        &self,
+        origin: &AuthorityIdentifier,
+        committee: &Committee,
+        now: Instant,
+    ) -> Result<CertificatePermit, CertificateShed> {
+        let weight = stake_weight(origin, committee);
+        let (rate, burst) = self.limits(weight);
+        let key = (weight > 0.0).then(|| origin.clone());
+        let mut budgets = self.budgets.lock();
+        budgets.prune(now);
+        let budget = budgets.origins.entry(key).or_insert_with(|| PeerBudget {
+            tokens: burst,
+            penalty: 0.0,
+            updated: now,
+        });
+        budget.update(now, rate, burst);
+
+        // the priority required rises from zero at the shed load to one at the in-flight limit
+        let max_in_flight = self.params.max_in_flight.max(1);
+        let load = self.in_flight.load(Ordering::Acquire) as f64 / max_in_flight as f64;
+        let required = ((load - SHED_LOAD) / (1.0 - SHED_LOAD)).max(0.0);
+        let priority = weight / (1.0 + budget.penalty);
+        if load >= 1.0 || priority < required {
+            return Err(CertificateShed::Overloaded);
+        }
+        if budget.tokens < 1.0 {
+            return Err(CertificateShed::RateLimited);
+        }
+
+        budget.tokens -= 1.0;
+        self.in_flight.fetch_add(1, Ordering::AcqRel);
+        Ok(CertificatePermit { in_flight: self.in_flight.clone() })
+    }
+
+    /// Lower the priority of an origin whose certificate was invalid.
+    pub(crate) fn penalize(&self, origin: &AuthorityIdentifier, committee: &Committee) {
+        let key = (stake_weight(origin, committee) > 0.0).then(|| origin.clone());
+        if let Some(budget) = self.budgets.lock().origins.get_mut(&key) {
+            budget.penalty += 1.0;
+        }
+    }
+
+    /// The number of budgets tracked.
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.budgets.lock().origins.len()
+    }
+
+    /// The token rate and burst for an origin with the stake weight.
+    fn limits(&self, weight: f64) -> (f64, f64) {
+        let weight = weight.max(NON_COMMITTEE_WEIGHT);
+        (self.params.certificates_per_sec * weight, (self.params.burst * weight).max(1.0))
+    }
+}
+
+/// The origin's stake relative to the committee's average stake, or zero outside the committee.
+fn stake_weight(origin: &AuthorityIdentifier, committee: &Committee) -> f64 {
+    let voting_power = committee.voting_power_by_id(origin);
+    let total = committee.total_voting_power();
+    if voting_power == 0 || total == 0 {
+        return 0.0;
+    }
+    voting_power as f64 * committee.size() as f64 / total as f64
+}
+
+/// Counts a certificate against the in-flight limit until dropped.
+#[derive(Debug)]
+pub(crate) struct CertificatePermit {
+    /// The budget's in-flight count.
+    in_flight: Arc<AtomicUsize>,
+}
+
+impl Drop for CertificatePermit {
+    fn drop(&mut self) {
+        self.in_flight.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CertificateBudget, CertificateShed, IDLE_TIMEOUT};
+    use std::time::{Duration, Instant};
+    use tn_config::CertificateBudgetParameters;
+    use tn_storage::mem_db::MemDatabase;
+    use tn_test_utils::CommitteeFixture;
+    use tn_types::AuthorityIdentifier;
+
+    fn member(fixture: &CommitteeFixture<MemDatabase>, index: usize) -> AuthorityIdentifier {
+        fixture.authorities().nth(index).expect("authority").id()
+    }
+
+    #[test]
+    fn test_rate_limit_scales_with_stake() {
+        let fixture = CommitteeFixture::builder(MemDatabase::default).build();
+        let committee = fixture.committee();
+        let params = CertificateBudgetParameters {
+            certificates_per_sec: 10.0,
+            burst: 2.0,
+            max_in_flight: 100,
+        };
+        let budget = CertificateBudget::new(params);
+        let now = Instant::now();
+
+        let peer = member(&fixture, 0);
+        let permits: Vec<_> = (0..2)
+            .map(|_| budget.try_admit_at(&peer, &committee, now).expect("within burst"))
+            .collect();
+        assert_eq!(
+            budget.try_admit_at(&peer, &committee, now).unwrap_err(),
+            CertificateShed::RateLimited
+        );
+        // tokens refill at the peer's rate
+        assert!(budget.try_admit_at(&peer, &committee, now + Duration::from_millis(100)).is_ok());
+
+        // origins outside the committee share a fraction of the rate
+        let outsider = AuthorityIdentifier::dummy_for_test(1);
+        let _permit = budget.try_admit_at(&outsider, &committee, now).expect("minimum burst");
+        assert_eq!(
+            budget
+                .try_admit_at(&AuthorityIdentifier::dummy_for_test(2), &committee, now)
+                .unwrap_err(),
+            CertificateShed::RateLimited
+        );
+        drop(permits);
+    }
+
+    #[test]
+    fn test_idle_budgets_are_evicted() {
+        let fixture = CommitteeFixture::builder(MemDatabase::default).build();
+        let committee = fixture.committee();
+        let budget = CertificateBudget::new(CertificateBudgetParameters::default());
+        let now = Instant::now();
+
+        for index in 0..committee.size() {
+            drop(budget.try_admit_at(&member(&fixture, index), &committee, now));
+        }
+        assert_eq!(budget.len(), committee.size());
+
+        // an active origin keeps its budget
+        let active = member(&fixture, 0);
+        let later = now + IDLE_TIMEOUT;
+        drop(budget.try_admit_at(&active, &committee, later - Duration::from_secs(1)));
+        drop(budget.try_admit_at(&active, &committee, later));
+        assert_eq!(budget.len(), 1);
+    }
+
+    #[test]
+    fn test_overload_sheds_low_priority_peers_first() {
+        let fixture = CommitteeFixture::builder(MemDatabase::default).build();
+        let committee = fixture.committee();
+        let params = CertificateBudgetParameters {
+            certificates_per_sec: 10.0,
+            burst: 100.0,
+            max_in_flight: 10,
+        };
+        let budget = CertificateBudget::new(params);
+        let now = Instant::now();
+        let honest = member(&fixture, 0);
+        let faulty = member(&fixture, 1);
+        let outsider = AuthorityIdentifier::dummy_for_test(1);
+
+        // admit the faulty peer's first certificate then penalize it
+        let mut permits = vec![budget.try_admit_at(&faulty, &committee, now).expect("admitted")];
+        budget.penalize(&faulty, &committee);
+        budget.penalize(&faulty, &committee);
+        for _ in 0..6 {
+            permits.push(budget.try_admit_at(&honest, &committee, now).expect("admitted"));
+        }
+
+        // above the shed load the outsider and penalized peer are shed
+        assert_eq!(
+            budget.try_admit_at(&outsider, &committee, now).unwrap_err(),
+            CertificateShed::Overloaded
+        );
+        assert_eq!(
+            budget.try_admit_at(&faulty, &committee, now).unwrap_err(),
+            CertificateShed::Overloaded
+        );
+        for _ in 0..3 {
+            permits.push(budget.try_admit_at(&honest, &committee, now).expect("admitted"));
+        }
+
+        // nothing is admitted at the limit
+        assert_eq!(
+            budget.try_admit_at(&honest, &committee, now).unwrap_err(),
+            CertificateShed::Overloaded
+        );
+
+        // processing certificates frees capacity and penalties decay
+        permits.clear();
+        let later = now + Duration::from_secs(600);
+        assert!(budget.try_admit_at(&faulty, &committee, later).is_ok());
+    }
+}
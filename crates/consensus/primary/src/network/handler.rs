@@ -1,12 +1,18 @@
 //! Handle specific request types received from the network.
 
-use super::{message::MissingCertificatesRequest, PrimaryResponse};
+use super::{
+    catch_up_budget::CatchUpBudget,
+    certificate_budget::CertificateBudget,
+    message::{MissingCertificatesRequest, PrimaryRPCError},
+    PrimaryNetworkHandle, PrimaryRequest, PrimaryRequestHandler, PrimaryResponse,
+};
 use crate::{
     error::{CertManagerError, PrimaryNetworkError, PrimaryNetworkResult},
     network::message::PrimaryGossip,
     state_sync::{CertificateCollector, StateSynchronizer},
     ConsensusBus,
 };
+use async_trait::async_trait;
 use parking_lot::Mutex;
 use std::{
     collections::{BTreeMap, BTreeSet},
@@ -14,7 +20,7 @@ use std::{
     time::Duration,
 };
 use tn_config::ConsensusConfig;
-use tn_network_libp2p::{GossipMessage, PeerId};
+use tn_network_libp2p::{types::IntoResponse as _, GossipMessage, PeerId};
 use tn_storage::{
     tables::{ConsensusBlockNumbersByDigest, ConsensusBlocks},
     VoteDigestStore,
@@ -22,10 +28,20 @@ use tn_storage::{
 use tn_types::{
     ensure,
     error::{CertificateError, HeaderError, HeaderResult},
-    now, try_decode, AuthorityIdentifier, BlockHash, Certificate, CertificateDigest,
+    try_decode, AuthorityIdentifier, BlockAnnouncement, BlockHash, Certificate, CertificateDigest,
     ConsensusHeader, Database, Hash as _, Header, Round, SignatureVerificationState, Vote,
+    WorkerInfoUpdate,
 };
-use tracing::{debug, error, warn};
+use tracing::{debug, error, info, warn};
+
+/// The maximum number of certificates pushed to a peer that requested catch up.
+const MAX_CATCH_UP_CERTIFICATES: usize = 2_000;
+
+/// The maximum number of blocks an announced block may be ahead of this node's execution tip.
+///
+/// Announcements must link to a known block, so honest announcements advance one block at a time.
+/// The bound stops a peer from pinning the announced tip far ahead of the chain.
+pub(crate) const MAX_ANNOUNCED_BLOCK_ADVANCE: u64 = 1_000;
 
 /// The type that handles requests from peers.
 #[derive(Clone)]
@@ -43,6 +59,10 @@ pub(crate) struct RequestHandler<DB> {
     /// header with these parents. The node keeps track of requested Certificates to prevent
     /// unsolicited certificate attacks.
     requested_parents: Arc<Mutex<BTreeMap<(Round, CertificateDigest), AuthorityIdentifier>>>,
+    /// Limits the certificates each peer can gossip.
+    certificate_budget: CertificateBudget,
+    /// Limits the catch up requests answered for each peer.
+    catch_up_budget: CatchUpBudget,
 }
 
 impl<DB> RequestHandler<DB>
@@ -55,7 +75,18 @@ where
         consensus_bus: ConsensusBus,
         state_sync: StateSynchronizer<DB>,
     ) -> Self {
-        Self { consensus_config, consensus_bus, state_sync, requested_parents: Default::default() }
+        let certificate_budget =
+            CertificateBudget::new(consensus_config.parameters().certificate_budget.clone());
+        let catch_up_budget =
+            CatchUpBudget::new(consensus_config.parameters().catch_up_budget.clone());
+        Self {
+            consensus_config,
+            consensus_bus,
+            state_sync,
+            requested_parents: Default::default(),
+            certificate_budget,
+            catch_up_budget,
+        }
     }
 
     /// Process gossip from the committee.
@@ -63,28 +94,230 @@ where
     /// Peers gossip the CertificateDigest so peers can request the Certificate. This waits until
     /// the certificate can be retrieved and timesout after some time. It's important to give up
     /// after enough time to limit the DoS attack surface. Peers who timeout must lose reputation.
-    pub(super) async fn process_gossip(&self, msg: &GossipMessage) -> PrimaryNetworkResult<()> {
+    ///
+    /// Certificates are pushed to peers that gossip they fell behind.
+    pub(super) async fn process_gossip(
+        &self,
+        msg: &GossipMessage,
+        network_handle: &PrimaryNetworkHandle,
+    ) -> PrimaryNetworkResult<()> {
         // deconstruct message
-        let GossipMessage { data, .. } = msg;
+        let GossipMessage { data, source, .. } = msg;
 
         // gossip is uncompressed
         let gossip = try_decode(data)?;
 
         match gossip {
             PrimaryGossip::Certificate(cert) => {
+                // verifying certificates is expensive so each origin has a budget
+                let origin = cert.origin().clone();
+                let committee = self.consensus_config.committee();
+                let _permit =
+                    self.certificate_budget.try_admit(&origin, committee).map_err(|shed| {
+                        self.consensus_bus
+                            .primary_metrics()
+                            .node_metrics
+                            .unsolicited_certificates_shed
+                            .with_label_values(&[shed.as_str()])
+                            .inc();
+                        PrimaryNetworkError::CertificateShed(shed)
+                    })?;
+
                 // process certificate
-                let unverified_cert = cert.validate_received().map_err(CertManagerError::from)?;
-                self.state_sync.process_peer_certificate(unverified_cert).await?;
+                let result = match cert.validate_received() {
+                    Ok(unverified_cert) => {
+                        self.state_sync.process_peer_certificate(unverified_cert).await
+                    }
+                    Err(e) => Err(e.into()),
+                };
+                if let Err(e) = &result {
+                    if is_invalid_certificate(e) {
+                        self.certificate_budget.penalize(&origin, committee);
+                    }
+                }
+                result?;
             }
             PrimaryGossip::Consenus(number, hash) => {
                 // Other side of this needs to verify.
                 let _ = self.consensus_bus.last_published_consensus_num_hash().send((number, hash));
             }
+            PrimaryGossip::CatchUp(round) => {
+                // NOTE: the network ensures the peer id is present before forwarding the msg
+                let Some(peer) = source else {
+                    return Ok(());
+                };
+
+                // answering is expensive so each peer has one catch up at a time and a budget
+                let _permit = self.catch_up_budget.try_admit(*peer).map_err(|shed| {
+                    self.consensus_bus
+                        .primary_metrics()
+                        .node_metrics
+                        .catch_up_requests_shed
+                        .with_label_values(&[shed.as_str()])
+                        .inc();
+                    PrimaryNetworkError::CatchUpShed(shed)
+                })?;
+
+                let certificates = self.catch_up_certs(round).await?;
+                if !certificates.is_empty() {
+                    network_handle
+                        .push_certificates(*peer, certificates)
+                        .await
+                        .map_err(|e| PrimaryNetworkError::Internal(e.to_string()))?;
+                }
+            }
+            PrimaryGossip::WorkerInfoUpdate(update) => {
+                self.apply_worker_info_update(&update)?;
+            }
+            PrimaryGossip::BlockAnnouncement(announcement) => {
+                self.process_block_announcement(&announcement)?;
+            }
         }
 
         Ok(())
     }
 
+    /// Apply a committee member's change to its workers.
+    ///
+    /// The update is verified and persisted by the [ConsensusConfig] before the worker cache
+    /// changes.
+    pub(crate) fn apply_worker_info_update(
+        &self,
+        update: &WorkerInfoUpdate,
+    ) -> PrimaryNetworkResult<()> {
+        self.consensus_config
+            .update_worker_cache(update)
+            .map_err(|e| PrimaryNetworkError::InvalidWorkerUpdate(e.to_string()))?;
+        info!(
+            target: "primary",
+            authority = %update.bls_public_key,
+            sequence = update.sequence,
+            "worker cache updated"
+        );
+        Ok(())
+    }
+
+    /// Track the latest executed block announced by the committee.
+    ///
+    /// The announced header must hash to the announced block hash and announcements for consensus
+    /// headers this node already recorded must match its consensus chain. The block is only
+    /// tracked if it extends the last announced block or a block this node executed, and is at
+    /// most [MAX_ANNOUNCED_BLOCK_ADVANCE] blocks ahead of this node's execution tip. Other
+    /// announcements are ignored.
+    pub(crate) fn process_block_announcement(
+        &self,
+        announcement: &BlockAnnouncement,
+    ) -> PrimaryNetworkResult<()> {
+        let header = announcement
+            .header()
+            .map_err(|e| PrimaryNetworkError::InvalidBlockAnnouncement(e.to_string()))?;
+        let known_number = self
+            .consensus_config
+            .node_storage()
+            .get::<ConsensusBlockNumbersByDigest>(&announcement.consensus_hash)?;
+        ensure!(
+            known_number.is_none_or(|number| number == announcement.consensus_number),
+            PrimaryNetworkError::InvalidBlockAnnouncement(format!(
+                "consensus header {} is not number {}",
+                announcement.consensus_hash, announcement.consensus_number
+            ))
+        );
+
+        let (local_tip, parent_number) = {
+            let recent_blocks = self.consensus_bus.recent_blocks().borrow();
+            (
+                recent_blocks.latest_block_num_hash().number,
+                recent_blocks.block_number(header.parent_hash),
+            )
+        };
+        if header.number > local_tip.saturating_add(MAX_ANNOUNCED_BLOCK_ADVANCE) {
+            debug!(
+                target: "primary",
+                number = header.number,
+                local_tip,
+                "ignoring announced block too far ahead of execution"
+            );
+            return Ok(());
+        }
+
+        self.consensus_bus.last_announced_block().send_if_modified(|last| {
+            if last.as_ref().is_some_and(|(_, last)| last.number >= header.number) {
+                return false;
+            }
+            // the block must extend a known block
+            let extends_last = last.as_ref().is_some_and(|(_, last)| {
+                last.hash() == header.parent_hash && last.number + 1 == header.number
+            });
+            let extends_local = parent_number.is_some_and(|number| number + 1 == header.number);
+            if !extends_last && !extends_local {
+                debug!(
+                    target: "primary",
+                    number = header.number,
+                    parent = %header.parent_hash,
+                    "ignoring announced block with unknown parent"
+                );
+                return false;
+            }
+            *last = Some((announcement.consensus_number, header));
+            true
+        });
+        Ok(())
+    }
+
+    /// Collect the certificates to push to a peer that fell behind at the round.
+    ///
+    /// Certificates are only pushed if this node is far enough ahead of the peer to help. Every
+    /// peer that is ahead pushes, so the number of certificates is capped.
+    pub(crate) async fn catch_up_certs(
+        &self,
+        round: Round,
+    ) -> PrimaryNetworkResult<Vec<Certificate>> {
+        let local_round = *self.consensus_bus.primary_round_updates().borrow();
+        let threshold =
+            self.consensus_config.network_config().sync_config().catch_up_round_threshold;
+        if local_round.saturating_sub(round) < threshold {
+            return Ok(Vec::new());
+        }
+
+        // certificates at or below the gc round are not needed
+        let gc_round = *self.consensus_bus.gc_round_updates().borrow();
+        let origins = self
+            .consensus_config
+            .committee()
+            .authorities()
+            .into_iter()
+            .map(|authority| (authority.id(), BTreeSet::new()))
+            .collect();
+        let request = MissingCertificatesRequest::default()
+            .set_bounds(round.max(gc_round), origins)?
+            .set_max_items(MAX_CATCH_UP_CERTIFICATES);
+
+        self.collect_missing_certs(request).await
+    }
+
+    /// Process certificates pushed by a peer after this node requested catch up.
+    ///
+    /// Certificates are only accepted while this node is catching up.
+    pub(crate) async fn accept_pushed_certs(
+        &self,
+        mut certificates: Vec<Certificate>,
+    ) -> PrimaryNetworkResult<PrimaryResponse> {
+        let Some(catch_up_round) = *self.consensus_bus.catch_up_round().borrow() else {
+            return Err(PrimaryNetworkError::InvalidRequest(
+                "certificates pushed without catch up request".to_string(),
+            ));
+        };
+        ensure!(
+            certificates.len() <= MAX_CATCH_UP_CERTIFICATES,
+            PrimaryNetworkError::InvalidRequest("too many certificates pushed".to_string())
+        );
+
+        certificates.retain(|cert| cert.round() > catch_up_round);
+        self.state_sync.process_fetched_certificates_in_parallel(certificates).await?;
+
+        Ok(PrimaryResponse::Ack)
+    }
+
     /// Evaluate request to possibly issue a vote in support of peer's header.
     pub(crate) async fn vote(
         &self,
@@ -95,8 +328,18 @@ where
         // current committee
         let committee = self.consensus_config.committee();
 
-        // validate header
-        header.validate(committee, self.consensus_config.worker_cache())?;
+        // validate header on the blocking thread pool
+        //
+        // hashing a header with many batch digests should not delay other requests
+        let validation = {
+            let header = header.clone();
+            let committee = committee.clone();
+            let worker_cache = self.consensus_config.worker_cache();
+            tokio::task::spawn_blocking(move || header.validate(&committee, &worker_cache))
+        };
+        validation.await.map_err(|e| {
+            PrimaryNetworkError::Internal(format!("header validation task failed: {e}"))
+        })??;
 
         // validate parents
         let num_parents = parents.len();
@@ -233,7 +476,7 @@ where
         self.state_sync.sync_header_batches(&header, false, 0).await?;
 
         // verify header was created in the past
-        let now = now();
+        let now = self.consensus_config.clock().now();
         if &now < header.created_at() {
             // wait if the difference is small enough
             if *header.created_at() - now
@@ -269,6 +512,18 @@ where
         //   vote digest
         // - if this header is older than the previously voted on header matching the epoch/round
         //
+        // votes at or below the gc round are pruned, so these headers can not be voted on safely
+        let gc_round = *self.consensus_bus.gc_round_updates().borrow();
+        ensure!(
+            header.round() > gc_round,
+            HeaderError::TooOld {
+                digest: header.digest(),
+                header_round: header.round(),
+                max_round: gc_round,
+            }
+            .into()
+        );
+
         // check storage for a previous vote
         //
         // if a vote already exists for this author:
@@ -414,10 +669,8 @@ where
             });
         }
 
-        // try to accept
-        for parent in parents {
-            self.state_sync.process_peer_certificate(parent).await?;
-        }
+        // try to accept - signatures are verified in parallel
+        self.state_sync.process_peer_certificates_in_parallel(parents).await?;
 
         Ok(())
     }
@@ -433,6 +686,15 @@ where
         &self,
         request: MissingCertificatesRequest,
     ) -> PrimaryNetworkResult<PrimaryResponse> {
+        let missing = self.collect_missing_certs(request).await?;
+        Ok(PrimaryResponse::RequestedCertificates(missing))
+    }
+
+    /// Collect certificates from local storage within the request's limits.
+    async fn collect_missing_certs(
+        &self,
+        request: MissingCertificatesRequest,
+    ) -> PrimaryNetworkResult<Vec<Certificate>> {
         // Create a time-bounded iter for collecting certificates
         let mut missing = Vec::with_capacity(request.max_items);
 
@@ -456,7 +718,7 @@ where
             collector.start_time().elapsed().as_millis(),
         );
 
-        Ok(PrimaryResponse::RequestedCertificates(missing))
+        Ok(missing)
     }
 
     /// Retrieve a consensus header from local storage.
@@ -506,3 +768,88 @@ where
             .ok_or(PrimaryNetworkError::InvalidRequest("Consensus headers unavailable".to_string()))
     }
 }
+
+/// The response for a request dispatched to the wrong handler.
+fn unexpected_request(request: &PrimaryRequest) -> PrimaryResponse {
+    PrimaryRPCError(format!("unexpected request for protocol {}", request.protocol())).into()
+}
+
+/// Handle [PrimaryRequest::Vote].
+pub(super) struct VoteHandler<DB>(pub(super) RequestHandler<DB>);
+
+#[async_trait]
+impl<DB: Database> PrimaryRequestHandler for VoteHandler<DB> {
+    async fn handle(&self, peer: PeerId, request: PrimaryRequest) -> PrimaryResponse {
+        match request {
+            PrimaryRequest::Vote { header, parents } => {
+                self.0.vote(peer, Arc::unwrap_or_clone(header), parents).await.into_response()
+            }
+            request => unexpected_request(&request),
+        }
+    }
+}
+
+/// Return true if the error means the peer sent an invalid certificate.
+///
+/// Certificates that are too old or too new are expected while peers sync, so they are not
+/// penalized.
+fn is_invalid_certificate(error: &CertManagerError) -> bool {
+    matches!(
+        error,
+        CertManagerError::Certificate(
+            CertificateError::Header(_)
+                | CertificateError::Inquorate { .. }
+                | CertificateError::InvalidSignature
+                | CertificateError::RecoverBlsAggregateSignatureBytes
+                | CertificateError::Unsigned
+        )
+    )
+}
+
+/// Handle [PrimaryRequest::MissingCertificates].
+pub(super) struct MissingCertificatesHandler<DB>(pub(super) RequestHandler<DB>);
+
+#[async_trait]
+impl<DB: Database> PrimaryRequestHandler for MissingCertificatesHandler<DB> {
+    async fn handle(&self, _peer: PeerId, request: PrimaryRequest) -> PrimaryResponse {
+        match request {
+            // TODO: penalize peer's reputation for bad request
+            PrimaryRequest::MissingCertificates { inner } => {
+                self.0.retrieve_missing_certs(inner).await.into_response()
+            }
+            request => unexpected_request(&request),
+        }
+    }
+}
+
+/// Handle [PrimaryRequest::ConsensusHeader].
+pub(super) struct ConsensusHeaderHandler<DB>(pub(super) RequestHandler<DB>);
+
+#[async_trait]
+impl<DB: Database> PrimaryRequestHandler for ConsensusHeaderHandler<DB> {
+    async fn handle(&self, _peer: PeerId, request: PrimaryRequest) -> PrimaryResponse {
+        match request {
+            // TODO: penalize peer's reputation for bad request
+            PrimaryRequest::ConsensusHeader { number, hash } => {
+                self.0.retrieve_consensus_header(number, hash).await.into_response()
+            }
+            request => unexpected_request(&request),
+        }
+    }
+}
+
+/// Handle [PrimaryRequest::PushCertificates].
+pub(super) struct PushCertificatesHandler<DB>(pub(super) RequestHandler<DB>);
+
+#[async_trait]
+impl<DB: Database> PrimaryRequestHandler for PushCertificatesHandler<DB> {
+    async fn handle(&self, _peer: PeerId, request: PrimaryRequest) -> PrimaryResponse {
+        match request {
+            // TODO: penalize peer's reputation for bad request
+            PrimaryRequest::PushCertificates { certificates } => {
+                self.0.accept_pushed_certs(certificates).await.into_response()
+            }
+            request => unexpected_request(&request),
+        }
+    }
+}
@@ -9,8 +9,8 @@ use std::{
 };
 use tn_network_libp2p::{types::IntoRpcError, TNMessage};
 use tn_types::{
-    AuthorityIdentifier, BlockHash, Certificate, CertificateDigest, ConsensusHeader, Header, Round,
-    Vote,
+    AuthorityIdentifier, BlockAnnouncement, BlockHash, Certificate, CertificateDigest,
+    ConsensusHeader, Header, Round, Vote, WorkerInfoUpdate,
 };
 
 /// Primary messages on the gossip network.
@@ -26,6 +26,13 @@ pub enum PrimaryGossip {
     Certificate(Box<Certificate>),
     /// Consensus output reached- publish the consensus chain height and new block hash.
     Consenus(u64, BlockHash),
+    /// The node fell behind at this round and asks peers to push certificates after it.
+    CatchUp(Round),
+    /// An authority changed its workers.
+    WorkerInfoUpdate(Box<WorkerInfoUpdate>),
+    /// A block was executed, published on [BLOCK_ANNOUNCEMENT_TOPIC] for nodes following the
+    /// chain.
+    BlockAnnouncement(Box<BlockAnnouncement>),
 }
 
 // impl TNMessage trait for types
@@ -58,6 +65,44 @@ pub enum PrimaryRequest {
         /// Block hash requesting if not None.
         hash: Option<BlockHash>,
     },
+    /// Certificates pushed by a peer after this node requested catch up.
+    PushCertificates {
+        /// The certificates after the round this node fell behind from.
+        certificates: Vec<Certificate>,
+    },
+    /// Request for a protocol registered by another crate.
+    ///
+    /// The payload is encoded by the protocol's handler.
+    Custom {
+        /// The name the protocol's handler is registered with.
+        protocol: String,
+        /// The encoded request.
+        payload: Vec<u8>,
+    },
+}
+
+/// The protocol for [PrimaryRequest::Vote].
+pub const VOTE_PROTOCOL: &str = "vote";
+/// The protocol for [PrimaryRequest::MissingCertificates].
+pub const MISSING_CERTIFICATES_PROTOCOL: &str = "missing-certificates";
+/// The protocol for [PrimaryRequest::ConsensusHeader].
+pub const CONSENSUS_HEADER_PROTOCOL: &str = "consensus-header";
+/// The protocol for [PrimaryRequest::PushCertificates].
+pub const PUSH_CERTIFICATES_PROTOCOL: &str = "push-certificates";
+/// The gossip topic for [PrimaryGossip::BlockAnnouncement].
+pub const BLOCK_ANNOUNCEMENT_TOPIC: &str = "tn-blocks";
+
+impl PrimaryRequest {
+    /// The name of the protocol that handles this request.
+    pub fn protocol(&self) -> &str {
+        match self {
+            Self::Vote { .. } => VOTE_PROTOCOL,
+            Self::MissingCertificates { .. } => MISSING_CERTIFICATES_PROTOCOL,
+            Self::ConsensusHeader { .. } => CONSENSUS_HEADER_PROTOCOL,
+            Self::PushCertificates { .. } => PUSH_CERTIFICATES_PROTOCOL,
+            Self::Custom { protocol, .. } => protocol,
+        }
+    }
 }
 
 // unit test for this struct in primary::src::tests::network_tests::test_missing_certs_request
@@ -148,6 +193,12 @@ pub enum PrimaryResponse {
     MissingParents(Vec<CertificateDigest>),
     /// The requested consensus header.
     ConsensusHeader(Arc<ConsensusHeader>),
+    /// The request was processed.
+    Ack,
+    /// Response for a protocol registered by another crate.
+    ///
+    /// The payload is encoded by the protocol's handler.
+    Custom(Vec<u8>),
     /// RPC error while handling request.
     ///
     /// This is an application-layer error response.
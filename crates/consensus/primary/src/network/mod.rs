@@ -6,30 +6,41 @@
 use std::sync::Arc;
 
 use crate::{proposer::OurDigestMessage, state_sync::StateSynchronizer, ConsensusBus};
-use handler::RequestHandler;
-pub use message::{MissingCertificatesRequest, PrimaryRequest, PrimaryResponse};
-use message::{PrimaryGossip, PrimaryRPCError};
+use handler::{
+    ConsensusHeaderHandler, MissingCertificatesHandler, PushCertificatesHandler, RequestHandler,
+    VoteHandler,
+};
+use message::PrimaryGossip;
+pub use message::{
+    MissingCertificatesRequest, PrimaryRPCError, PrimaryRequest, PrimaryResponse,
+    BLOCK_ANNOUNCEMENT_TOPIC, CONSENSUS_HEADER_PROTOCOL, MISSING_CERTIFICATES_PROTOCOL,
+    PUSH_CERTIFICATES_PROTOCOL, VOTE_PROTOCOL,
+};
+pub use registry::{PrimaryHandlerRegistry, PrimaryRequestHandler};
 use tn_config::ConsensusConfig;
 use tn_network_libp2p::{
     error::NetworkError,
     types::{
-        IdentTopic, IntoResponse as _, NetworkCommand, NetworkEvent, NetworkHandle, NetworkResult,
+        CorrelationId, IdentTopic, NetworkCommand, NetworkEvent, NetworkHandle, NetworkResult,
     },
-    GossipMessage, Multiaddr, PeerId, ResponseChannel,
+    GossipMessage, Multiaddr, NetworkClient, PeerId, ResponseChannel,
 };
 use tn_network_types::{
-    FetchCertificatesRequest, WorkerOthersBatchMessage, WorkerOwnBatchMessage,
-    WorkerToPrimaryClient,
+    FetchCertificatesRequest, WorkerHeartbeatMessage, WorkerOthersBatchMessage,
+    WorkerOwnBatchMessage, WorkerToPrimaryClient,
 };
 use tn_storage::PayloadStore;
 use tn_types::{
-    encode, BlockHash, Certificate, CertificateDigest, ConsensusHeader, Database, Header, Noticer,
-    TaskManager, TnSender, Vote,
+    encode, BlockAnnouncement, BlockHash, Certificate, CertificateDigest, ConsensusHeader,
+    Database, Header, Noticer, Round, TaskManager, TnSender, Vote, WorkerInfoUpdate,
 };
-use tokio::sync::{mpsc, oneshot};
-use tracing::warn;
+use tokio::sync::{mpsc, oneshot, watch};
+use tracing::{info_span, warn, Instrument as _};
+pub(crate) mod catch_up_budget;
+pub(crate) mod certificate_budget;
 pub mod handler;
 mod message;
+mod registry;
 
 #[cfg(test)]
 #[path = "../tests/network_tests.rs"]
@@ -41,26 +52,29 @@ pub(crate) type Req = PrimaryRequest;
 pub(crate) type Res = PrimaryResponse;
 
 /// Primary network specific handle.
+///
+/// Backed by the libp2p network in production and by a
+/// [MemoryNetwork](tn_network_libp2p::MemoryNetwork) in tests that don't run a swarm.
 #[derive(Clone)]
 pub struct PrimaryNetworkHandle {
-    handle: NetworkHandle<Req, Res>,
+    handle: Arc<dyn NetworkClient<Req, Res>>,
 }
 
 impl From<NetworkHandle<Req, Res>> for PrimaryNetworkHandle {
     fn from(handle: NetworkHandle<Req, Res>) -> Self {
-        Self { handle }
+        Self::new(handle)
     }
 }
 
 impl PrimaryNetworkHandle {
     /// Create a new instance of Self.
-    pub fn new(handle: NetworkHandle<Req, Res>) -> Self {
-        Self { handle }
+    pub fn new(handle: impl NetworkClient<Req, Res>) -> Self {
+        Self { handle: Arc::new(handle) }
     }
 
     //// Convenience method for creating a new Self for tests.
     pub fn new_for_test(sender: mpsc::Sender<NetworkCommand<Req, Res>>) -> Self {
-        Self { handle: NetworkHandle::new(sender) }
+        Self::new(NetworkHandle::new(sender))
     }
 
     /// Dial a peer.
@@ -70,6 +84,16 @@ impl PrimaryNetworkHandle {
         self.handle.dial(peer_id, peer_addr).await
     }
 
+    /// Return the peers currently connected to the primary network.
+    pub async fn connected_peers(&self) -> NetworkResult<Vec<PeerId>> {
+        self.handle.connected_peers().await
+    }
+
+    /// Return this node's peer id on the primary network.
+    pub async fn local_peer_id(&self) -> NetworkResult<PeerId> {
+        self.handle.local_peer_id().await
+    }
+
     /// Publish a certificate to the consensus network.
     pub async fn publish_certificate(&self, certificate: Certificate) -> NetworkResult<()> {
         let data = encode(&PrimaryGossip::Certificate(Box::new(certificate)));
@@ -88,6 +112,52 @@ impl PrimaryNetworkHandle {
         Ok(())
     }
 
+    /// Publish that this node fell behind at the round so peers push certificates after it.
+    pub async fn publish_catch_up(&self, round: Round) -> NetworkResult<()> {
+        let data = encode(&PrimaryGossip::CatchUp(round));
+        self.handle.publish(IdentTopic::new("tn-primary"), data).await?;
+        Ok(())
+    }
+
+    /// Publish a signed change to this node's workers.
+    pub async fn publish_worker_info_update(&self, update: WorkerInfoUpdate) -> NetworkResult<()> {
+        let data = encode(&PrimaryGossip::WorkerInfoUpdate(Box::new(update)));
+        self.handle.publish(IdentTopic::new("tn-primary"), data).await?;
+        Ok(())
+    }
+
+    /// Publish an executed block for nodes following the chain.
+    pub async fn publish_block_announcement(
+        &self,
+        announcement: BlockAnnouncement,
+    ) -> NetworkResult<()> {
+        let data = encode(&PrimaryGossip::BlockAnnouncement(Box::new(announcement)));
+        self.handle.publish(IdentTopic::new(BLOCK_ANNOUNCEMENT_TOPIC), data).await?;
+        Ok(())
+    }
+
+    /// Subscribe to executed blocks announced by the committee.
+    pub async fn subscribe_block_announcements(&self) -> NetworkResult<()> {
+        self.handle.subscribe(IdentTopic::new(BLOCK_ANNOUNCEMENT_TOPIC)).await?;
+        Ok(())
+    }
+
+    /// Push certificates to a peer that requested catch up.
+    pub async fn push_certificates(
+        &self,
+        peer: PeerId,
+        certificates: Vec<Certificate>,
+    ) -> NetworkResult<()> {
+        let request = PrimaryRequest::PushCertificates { certificates };
+        let res = self.handle.send_request(request, peer).await?;
+        let res = res.await??;
+        match res {
+            PrimaryResponse::Ack => Ok(()),
+            PrimaryResponse::Error(PrimaryRPCError(s)) => Err(NetworkError::RPCError(s)),
+            _ => Err(NetworkError::RPCError("Got wrong response, not an ack!".to_string())),
+        }
+    }
+
     /// Request a vote for header from the peer.
     /// Can return a response of Vote or MissingParents, other responses will be an error.
     pub async fn request_vote(
@@ -111,6 +181,12 @@ impl PrimaryNetworkHandle {
             PrimaryResponse::ConsensusHeader(_consensus_header) => Err(NetworkError::RPCError(
                 "Got wrong response, not a vote is consensus header!".to_string(),
             )),
+            PrimaryResponse::Ack => {
+                Err(NetworkError::RPCError("Got wrong response, not a vote is ack!".to_string()))
+            }
+            PrimaryResponse::Custom(_payload) => Err(NetworkError::RPCError(
+                "Got wrong response, not a vote is custom protocol response!".to_string(),
+            )),
         }
     }
 
@@ -175,8 +251,12 @@ pub struct PrimaryNetwork<DB> {
     network_events: mpsc::Receiver<NetworkEvent<Req, Res>>,
     /// Network handle to send commands.
     network_handle: PrimaryNetworkHandle,
-    /// Request handler to process requests and return responses.
+    /// Request handler to process gossip.
     request_handler: RequestHandler<DB>,
+    /// The handlers for requests by protocol.
+    handlers: PrimaryHandlerRegistry,
+    /// Receiver for the round this node fell behind from.
+    catch_up_rx: watch::Receiver<Option<Round>>,
     /// Shutdown notification.
     shutdown_rx: Noticer,
 }
@@ -186,6 +266,8 @@ where
     DB: Database,
 {
     /// Create a new instance of Self.
+    ///
+    /// Handlers for the consensus protocols are registered by default.
     pub fn new(
         network_events: mpsc::Receiver<NetworkEvent<Req, Res>>,
         network_handle: PrimaryNetworkHandle,
@@ -194,9 +276,33 @@ where
         state_sync: StateSynchronizer<DB>,
     ) -> Self {
         let shutdown_rx = consensus_config.shutdown().subscribe();
+        let catch_up_rx = consensus_bus.catch_up_round().subscribe();
         let request_handler =
             RequestHandler::new(consensus_config, consensus_bus, state_sync.clone());
-        Self { network_events, network_handle, request_handler, shutdown_rx }
+        let mut handlers = PrimaryHandlerRegistry::default();
+        handlers.register(VOTE_PROTOCOL, VoteHandler(request_handler.clone()));
+        handlers.register(
+            MISSING_CERTIFICATES_PROTOCOL,
+            MissingCertificatesHandler(request_handler.clone()),
+        );
+        handlers
+            .register(CONSENSUS_HEADER_PROTOCOL, ConsensusHeaderHandler(request_handler.clone()));
+        handlers
+            .register(PUSH_CERTIFICATES_PROTOCOL, PushCertificatesHandler(request_handler.clone()));
+        Self { network_events, network_handle, request_handler, handlers, catch_up_rx, shutdown_rx }
+    }
+
+    /// Register the handler for a protocol.
+    ///
+    /// Replaces the handler previously registered for the protocol, including the default
+    /// consensus handlers.
+    pub fn with_handler(
+        mut self,
+        protocol: impl Into<String>,
+        handler: impl PrimaryRequestHandler,
+    ) -> Self {
+        self.handlers.register(protocol, handler);
+        self
     }
 
     pub fn handle(&self) -> &PrimaryNetworkHandle {
@@ -215,114 +321,78 @@ where
                             None => break,
                         }
                     }
+                    Ok(()) = self.catch_up_rx.changed() => self.publish_catch_up(),
                 )
             }
         });
     }
 
     /// Handle events concurrently.
+    ///
+    /// Handlers run in a span with the message's [CorrelationId] so logs from every node that
+    /// handles the message can be matched up.
     fn process_network_event(&self, event: NetworkEvent<Req, Res>) {
         // match event
         match event {
-            NetworkEvent::Request { peer, request, channel, cancel } => match request {
-                PrimaryRequest::Vote { header, parents } => {
-                    self.process_vote_request(
-                        peer,
-                        Arc::unwrap_or_clone(header),
-                        parents,
-                        channel,
-                        cancel,
-                    );
-                }
-                PrimaryRequest::MissingCertificates { inner } => {
-                    self.process_request_for_missing_certs(peer, inner, channel, cancel)
-                }
-                PrimaryRequest::ConsensusHeader { number, hash } => {
-                    self.process_consensus_output_request(peer, number, hash, channel, cancel)
-                }
-            },
+            NetworkEvent::Request { peer, correlation_id, request, channel, cancel } => {
+                let _span = info_span!("request", %correlation_id, %peer).entered();
+                self.process_request(peer, request, channel, cancel);
+            }
             NetworkEvent::Gossip(msg) => {
+                let correlation_id = CorrelationId::for_gossip_message(&msg);
+                let _span = info_span!("gossip", %correlation_id).entered();
                 self.process_gossip(msg);
             }
         }
     }
 
-    /// Process vote request.
+    /// Process a request from a peer.
     ///
-    /// Spawn a task to evaluate a peer's proposed header and return a response.
-    fn process_vote_request(
+    /// Spawn a task for the handler registered for the request's protocol and return the response.
+    fn process_request(
         &self,
         peer: PeerId,
-        header: Header,
-        parents: Vec<Certificate>,
+        request: PrimaryRequest,
         channel: ResponseChannel<PrimaryResponse>,
         cancel: oneshot::Receiver<()>,
     ) {
         // clone for spawned tasks
-        let request_handler = self.request_handler.clone();
+        let handler = self.handlers.get(request.protocol());
         let network_handle = self.network_handle.clone();
-        tokio::spawn(async move {
-            tokio::select! {
-                vote = request_handler.vote(peer, header, parents) => {
-                    let response = vote.into_response();
-                    let _ = network_handle.handle.send_response(response, channel).await;
-                }
-                // cancel notification from network layer
-                _ = cancel => (),
-            }
-        });
-    }
-
-    /// Attempt to retrieve certificates for a peer that's missing them.
-    fn process_request_for_missing_certs(
-        &self,
-        _peer: PeerId,
-        request: MissingCertificatesRequest,
-        channel: ResponseChannel<PrimaryResponse>,
-        cancel: oneshot::Receiver<()>,
-    ) {
-        // clone for spawned tasks
-        let request_handler = self.request_handler.clone();
-        let network_handle = self.network_handle.clone();
-        tokio::spawn(async move {
-            tokio::select! {
-                certs = request_handler.retrieve_missing_certs(request) => {
-                    let response = certs.into_response();
-
+        tokio::spawn(
+            async move {
+                let Some(handler) = handler else {
                     // TODO: penalize peer's reputation for bad request
-                    // if response.is_err() { }
-
-                    let _ = network_handle.handle.send_response(response, channel).await;
+                    let error = format!("unsupported protocol {}", request.protocol());
+                    let _ = network_handle
+                        .handle
+                        .send_response(PrimaryRPCError(error).into(), channel)
+                        .await;
+                    return;
+                };
+
+                tokio::select! {
+                    response = handler.handle(peer, request) => {
+                        let _ = network_handle.handle.send_response(response, channel).await;
+                    }
+                    // cancel notification from network layer
+                    _ = cancel => (),
                 }
-                // cancel notification from network layer
-                _ = cancel => (),
             }
-        });
+            .in_current_span(),
+        );
     }
 
-    /// Attempt to retrieve consensus chain header from the database.
-    fn process_consensus_output_request(
-        &self,
-        _peer: PeerId,
-        number: Option<u64>,
-        hash: Option<BlockHash>,
-        channel: ResponseChannel<PrimaryResponse>,
-        cancel: oneshot::Receiver<()>,
-    ) {
-        // clone for spawned tasks
-        let request_handler = self.request_handler.clone();
+    /// Ask peers to push certificates if this node fell behind.
+    fn publish_catch_up(&mut self) {
+        let Some(round) = *self.catch_up_rx.borrow_and_update() else {
+            return;
+        };
+
         let network_handle = self.network_handle.clone();
         tokio::spawn(async move {
-            tokio::select! {
-                header =
-                    request_handler.retrieve_consensus_header(number, hash) => {
-                        let response = header.into_response();
-                        // TODO: penalize peer's reputation for bad request
-                        // if response.is_err() { }
-                        let _ = network_handle.handle.send_response(response, channel).await;
-                    }
-                // cancel notification from network layer
-                _ = cancel => (),
+            if let Err(e) = network_handle.publish_catch_up(round).await {
+                warn!(target: "primary::network", ?e, round, "failed to publish catch up");
             }
         });
     }
@@ -331,28 +401,31 @@ where
     fn process_gossip(&self, msg: GossipMessage) {
         // clone for spawned tasks
         let request_handler = self.request_handler.clone();
-        // let network_handle = self.network_handle.clone();
+        let network_handle = self.network_handle.clone();
 
         // commented out to prevent CertificateError::TooNew from forcing disconnect when peers
         // are trying to resync
-        tokio::spawn(async move {
-            if let Err(e) = request_handler.process_gossip(&msg).await {
-                warn!(target: "primary::network", ?e, "process_gossip");
-                // TODO: peers don't track reputation yet
-                //
-                // NOTE: the network ensures the peer id is present before forwarding the msg
-                // if let Some(peer_id) = msg.source {
-                //     if let Err(e) =
-                //         network_handle.handle.set_application_score(peer_id, -100.0).await
-                //     {
-                //         error!(target: "primary::network", ?e, "failed to penalize malicious
-                // peer")     }
-                // }
-
-                // match on error to lower peer score
-                //todo!();
+        tokio::spawn(
+            async move {
+                if let Err(e) = request_handler.process_gossip(&msg, &network_handle).await {
+                    warn!(target: "primary::network", ?e, "process_gossip");
+                    // TODO: peers don't track reputation yet
+                    //
+                    // NOTE: the network ensures the peer id is present before forwarding the msg
+                    // if let Some(peer_id) = msg.source {
+                    //     if let Err(e) =
+                    //         network_handle.handle.set_application_score(peer_id, -100.0).await
+                    //     {
+                    //         error!(target: "primary::network", ?e, "failed to penalize malicious
+                    // peer")     }
+                    // }
+
+                    // match on error to lower peer score
+                    //todo!();
+                }
             }
-        });
+            .in_current_span(),
+        );
     }
 }
 
@@ -373,6 +446,9 @@ impl<DB: PayloadStore> WorkerReceiverHandler<DB> {
 #[async_trait::async_trait]
 impl<DB: Database> WorkerToPrimaryClient for WorkerReceiverHandler<DB> {
     async fn report_own_batch(&self, message: WorkerOwnBatchMessage) -> eyre::Result<()> {
+        self.consensus_bus
+            .worker_activity()
+            .send_modify(|activity| activity.batch_sealed(message.worker_id, message.timestamp));
         let (tx_ack, rx_ack) = oneshot::channel();
         let response = self
             .consensus_bus
@@ -395,6 +471,13 @@ impl<DB: Database> WorkerToPrimaryClient for WorkerReceiverHandler<DB> {
         self.payload_store.write_payload(&message.digest, &message.worker_id)?;
         Ok(())
     }
+
+    async fn report_heartbeat(&self, message: WorkerHeartbeatMessage) -> eyre::Result<()> {
+        self.consensus_bus
+            .worker_activity()
+            .send_modify(|activity| activity.heartbeat(message.worker_id, message.timestamp));
+        Ok(())
+    }
 }
 
 /// Responses to a vote request.
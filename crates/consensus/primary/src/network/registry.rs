@@ -0,0 +1,55 @@
+//! Registry of handlers for requests from other primaries.
+//!
+//! Each request protocol has one handler. The primary network dispatches requests to the handler
+//! registered for the request's protocol, so other crates can add protocols by registering a
+//! handler for [PrimaryRequest::Custom] requests.
+
+use super::{PrimaryRequest, PrimaryResponse};
+use async_trait::async_trait;
+use std::{collections::HashMap, fmt, sync::Arc};
+use tn_network_libp2p::PeerId;
+
+/// Handle requests for a protocol from other primaries.
+#[async_trait]
+pub trait PrimaryRequestHandler: Send + Sync + 'static {
+    /// Process a peer's request and return the response.
+    ///
+    /// Errors are returned to the peer as [PrimaryResponse::Error].
+    async fn handle(&self, peer: PeerId, request: PrimaryRequest) -> PrimaryResponse;
+}
+
+/// The request handlers by protocol.
+#[derive(Clone, Default)]
+pub struct PrimaryHandlerRegistry {
+    /// The handler for each protocol.
+    handlers: HashMap<String, Arc<dyn PrimaryRequestHandler>>,
+}
+
+impl PrimaryHandlerRegistry {
+    /// Register the handler for a protocol.
+    ///
+    /// Returns the handler that was previously registered for the protocol, if any.
+    pub fn register(
+        &mut self,
+        protocol: impl Into<String>,
+        handler: impl PrimaryRequestHandler,
+    ) -> Option<Arc<dyn PrimaryRequestHandler>> {
+        self.handlers.insert(protocol.into(), Arc::new(handler))
+    }
+
+    /// Return the handler registered for a protocol.
+    pub fn get(&self, protocol: &str) -> Option<Arc<dyn PrimaryRequestHandler>> {
+        self.handlers.get(protocol).cloned()
+    }
+
+    /// Return true if a handler is registered for the protocol.
+    pub fn contains(&self, protocol: &str) -> bool {
+        self.handlers.contains_key(protocol)
+    }
+}
+
+impl fmt::Debug for PrimaryHandlerRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.handlers.keys()).finish()
+    }
+}
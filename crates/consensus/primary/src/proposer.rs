@@ -4,7 +4,7 @@
 //! This is the first task in the primary's header cycle. The Proposer processes messages from the
 //! `Primary::StateHandler` to track which proposed headers were successfully committed. If a header
 //! is not committed before it's round advances, the failed header's block digests are included in a
-//! fresh header in FIFO order.
+//! fresh header in FIFO order for each worker.
 //!
 //! Successfully created Headers are sent to the `Primary::Certifier`, where they are reliably
 //! broadcast to voting peers. Headers are stored in the `ProposerStore` before they are sent to the
@@ -13,14 +13,15 @@
 //! The Proposer is also responsible for processing batch's that reach quorum.
 //! Collections of batches that reach quorum are included in each header. If the Proposer's
 //! header fails to be committed, then block digests from the failed round are included in the next
-//! header once the Proposer's round advances.
+//! header once the Proposer's round advances. When multiple workers feed digests, each header takes
+//! digests from the workers in round-robin order so one busy worker can't starve another's batches.
 
 use crate::{
     consensus::LeaderSchedule,
     error::{ProposerError, ProposerResult},
     ConsensusBus,
 };
-use consensus_metrics::monitored_future;
+use consensus_metrics::{monitored_future, CommitStage};
 use std::{
     cmp::Ordering,
     collections::{BTreeMap, VecDeque},
@@ -30,8 +31,8 @@ use tn_config::ConsensusConfig;
 use tn_primary_metrics::PrimaryMetrics;
 use tn_storage::ProposerStore;
 use tn_types::{
-    now, AuthorityIdentifier, BlockHash, Certificate, Committee, Database, Epoch, Hash as _,
-    Header, Noticer, Round, TaskManager, TimestampSec, TnReceiver, TnSender, WorkerId,
+    AuthorityIdentifier, BlockHash, Certificate, Committee, Database, Epoch, Hash as _, Header,
+    Noticer, Round, SharedClock, TaskManager, TimestampSec, TnReceiver, TnSender, WorkerId,
 };
 use tokio::{
     sync::oneshot,
@@ -79,6 +80,71 @@ struct ProposerDigest {
     pub timestamp: TimestampSec,
 }
 
+/// The digests from this primary's workers waiting to be included in a header.
+///
+/// Each worker's digests are popped in FIFO order. Headers take digests from the workers in
+/// round-robin order, starting after the last worker served by the previous header.
+#[derive(Debug, Default)]
+struct PayloadQueue {
+    /// The digests for each worker, oldest to newest.
+    by_worker: BTreeMap<WorkerId, VecDeque<ProposerDigest>>,
+    /// The first worker to take a digest from for the next header.
+    next_worker: WorkerId,
+    /// The total number of digests.
+    len: usize,
+}
+
+impl PayloadQueue {
+    /// The number of digests waiting to be included.
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Add a new digest behind the worker's other digests.
+    fn push_back(&mut self, digest: ProposerDigest) {
+        self.by_worker.entry(digest.worker_id).or_default().push_back(digest);
+        self.len += 1;
+    }
+
+    /// Add digests from undelivered headers (oldest to newest) in front of each worker's digests.
+    fn push_front(&mut self, digests: VecDeque<ProposerDigest>) {
+        self.len += digests.len();
+        for digest in digests.into_iter().rev() {
+            self.by_worker.entry(digest.worker_id).or_default().push_front(digest);
+        }
+    }
+
+    /// Remove up to `max` digests for the next header.
+    fn take(&mut self, max: usize) -> VecDeque<ProposerDigest> {
+        let mut digests = VecDeque::with_capacity(max.min(self.len));
+        // workers in round-robin order starting with `next_worker`
+        let workers: Vec<_> = self
+            .by_worker
+            .range(self.next_worker..)
+            .chain(self.by_worker.range(..self.next_worker))
+            .map(|(worker_id, _)| *worker_id)
+            .collect();
+
+        while digests.len() < max && self.len > 0 {
+            for worker_id in &workers {
+                if digests.len() == max {
+                    break;
+                }
+                let Some(digest) = self.by_worker.get_mut(worker_id).and_then(VecDeque::pop_front)
+                else {
+                    continue;
+                };
+                digests.push_back(digest);
+                self.len -= 1;
+                self.next_worker = worker_id.wrapping_add(1);
+            }
+        }
+
+        self.by_worker.retain(|_, queue| !queue.is_empty());
+        digests
+    }
+}
+
 #[cfg(test)]
 #[path = "tests/proposer_tests.rs"]
 pub mod proposer_tests;
@@ -124,8 +190,9 @@ pub struct Proposer<DB: ProposerStore> {
     /// Holds the certificate of the last leader (if any).
     last_leader: Option<Certificate>,
     /// Holds the batches' digests waiting to be included in the next header.
-    /// Digests are roughly oldest to newest, and popped in FIFO order from the front.
-    digests: VecDeque<ProposerDigest>,
+    /// Each worker's digests are roughly oldest to newest, and popped in FIFO order from the
+    /// front.
+    digests: PayloadQueue,
     /// Holds the map of proposed previous round headers and their digest messages, to ensure that
     /// all batches' digest included will eventually be re-sent.
     proposed_headers: BTreeMap<Round, Header>,
@@ -134,6 +201,11 @@ pub struct Proposer<DB: ProposerStore> {
     leader_schedule: LeaderSchedule,
     /// Flag if enough conditions are met to advance the round.
     advance_round: bool,
+    /// The number of rounds parents can jump ahead before this node asks peers to push
+    /// certificates.
+    catch_up_round_threshold: Round,
+    /// The time source for header timestamps.
+    clock: SharedClock,
 }
 
 impl<DB: Database> Proposer<DB> {
@@ -169,10 +241,15 @@ impl<DB: Database> Proposer<DB> {
             last_round_timestamp: None,
             last_parents: genesis,
             last_leader: None,
-            digests: VecDeque::with_capacity(2 * config.parameters().max_header_num_of_batches),
+            digests: PayloadQueue::default(),
             proposed_headers: BTreeMap::new(),
             leader_schedule,
             advance_round: true,
+            catch_up_round_threshold: config
+                .network_config()
+                .sync_config()
+                .catch_up_round_threshold,
+            clock: config.clock().clone(),
         }
     }
 
@@ -195,6 +272,7 @@ impl<DB: Database> Proposer<DB> {
         metrics: Arc<PrimaryMetrics>,
         leader_and_support: String,
         max_delay: Duration,
+        clock: SharedClock,
     ) -> ProposerResult<Header> {
         // check that the included timestamp is consistent with the parent's timestamp
         //
@@ -202,26 +280,27 @@ impl<DB: Database> Proposer<DB> {
         //
         // if not: log an error and sleep
         let latest_parent = parents.iter().map(|c| *c.header().created_at()).max().unwrap_or(0);
-        let current_time = now();
+        let current_time = clock.now();
         if current_time < latest_parent {
             let drift_sec = latest_parent - current_time;
             error!(
                 ?current_time,
                 ?latest_parent,
-                "Current time earlier than most recent parent! Sleeping for {}sec until max parent time...",
+                "Current time earlier than most recent parent, check the clock_skew_ms metric! Sleeping for {}sec until max parent time...",
                 drift_sec,
             );
             metrics.header_max_parent_wait_ms.inc_by(drift_sec);
             sleep(Duration::from_secs(drift_sec)).await;
         }
 
-        let header = Header::new(
+        let header = Header::new_at(
             authority_id,
             current_round,
             current_epoch,
             digests.iter().map(|m| (m.digest, (m.worker_id, m.timestamp))).collect(),
             parents.iter().map(|x| x.digest()).collect(),
             consensus_bus.recent_blocks().borrow().latest_block_num_hash(),
+            clock.now().max(latest_parent),
         );
 
         // update metrics before sending/storing header
@@ -242,7 +321,8 @@ impl<DB: Database> Proposer<DB> {
         let mut total_inclusion_secs = 0.0;
         for digest in &digests {
             let batch_inclusion_secs =
-                Duration::from_secs(*header.created_at() - digest.timestamp).as_secs_f64();
+                Duration::from_secs(header.created_at().saturating_sub(digest.timestamp))
+                    .as_secs_f64();
             total_inclusion_secs += batch_inclusion_secs;
 
             // NOTE: this log entry is used to measure performance
@@ -253,12 +333,14 @@ impl<DB: Database> Proposer<DB> {
                 batch_inclusion_secs
             );
             metrics.proposer_batch_latency.observe(batch_inclusion_secs);
+            CommitStage::BatchToHeader.observe(Duration::from_secs_f64(batch_inclusion_secs));
         }
 
         // NOTE: this log entry is used to measure performance
         let (header_creation_secs, avg_inclusion_secs) = if let Some(digest) = digests.front() {
             (
-                Duration::from_secs(*header.created_at() - digest.timestamp).as_secs_f64(),
+                Duration::from_secs(header.created_at().saturating_sub(digest.timestamp))
+                    .as_secs_f64(),
                 total_inclusion_secs / digests.len() as f64,
             )
         } else {
@@ -458,6 +540,18 @@ impl<DB: Database> Proposer<DB> {
                     parent_round=?round,
                     "processing parents from future round - advacing to catch up...",
                 );
+                // ask peers to push certificates instead of waiting on pull-based fetches if
+                // this node fell far behind
+                if round - self.round >= self.catch_up_round_threshold {
+                    debug!(
+                        target: "primary::proposer",
+                        authority=?self.authority_id,
+                        round=?self.round,
+                        parent_round=?round,
+                        "node is behind - requesting catch up",
+                    );
+                    self.consensus_bus.catch_up_round().send_replace(Some(self.round));
+                }
                 // proposer accepts a future round then jumps ahead in case it was
                 // late (or just joined the network).
                 self.round = round;
@@ -504,6 +598,10 @@ impl<DB: Database> Proposer<DB> {
                 // certs arrive from synchronizer once quorum is reached
                 // so these are extra parents
                 self.last_parents.extend(parents);
+                // parents for the current round mean this node is caught up with the quorum
+                self.consensus_bus
+                    .catch_up_round()
+                    .send_if_modified(|catch_up| catch_up.take().is_some());
                 // the schedule can change after an odd round proposal
                 //
                 // need to ensure the interval is reset correctly for the round leader
@@ -585,9 +683,8 @@ impl<DB: Database> Proposer<DB> {
         if !retransmit_rounds.is_empty() {
             let num_digests_to_resend = digests_to_resend.len();
 
-            // prepend missing batches from previous round and update `self`
-            digests_to_resend.append(&mut self.digests);
-            self.digests = digests_to_resend;
+            // prepend missing batches from previous round
+            self.digests.push_front(digests_to_resend);
 
             // remove the old headers that failed
             // the proposed blocks are included in the next header
@@ -633,7 +730,7 @@ impl<DB: Database> Proposer<DB> {
 
         // Update the metrics
         self.consensus_bus.primary_metrics().node_metrics.current_round.set(self.round as i64);
-        let current_timestamp = now();
+        let current_timestamp = self.clock.now();
         if let Some(t) = &self.last_round_timestamp {
             self.consensus_bus
                 .primary_metrics()
@@ -680,8 +777,7 @@ impl<DB: Database> Proposer<DB> {
             // create new header
             None => {
                 // collect values from &mut self for this header
-                let num_of_digests = self.digests.len().min(self.max_header_num_of_batches);
-                let digests: VecDeque<_> = self.digests.drain(..num_of_digests).collect();
+                let digests = self.digests.take(self.max_header_num_of_batches);
                 let parents = std::mem::take(&mut self.last_parents);
                 let authority_id = self.authority_id.clone();
                 let min_delay = self.min_header_delay; // copy
@@ -702,6 +798,7 @@ impl<DB: Database> Proposer<DB> {
                 };
 
                 let consensus_bus = self.consensus_bus.clone();
+                let clock = self.clock.clone();
                 // spawn tokio task to create, store, and send new header to certifier
                 tokio::task::spawn(async move {
                     let proposal = Proposer::propose_header(
@@ -716,6 +813,7 @@ impl<DB: Database> Proposer<DB> {
                         metrics,
                         leader_and_support.to_string(),
                         min_delay,
+                        clock,
                     )
                     .await;
 
@@ -786,10 +884,12 @@ impl<DB: Database> Proposer<DB> {
         let mut rx_our_digests = self.consensus_bus.our_digests().subscribe();
         let mut rx_parents = self.consensus_bus.parents().subscribe();
         let mut rx_committed_own_headers = self.consensus_bus.committed_own_headers().subscribe();
+        let mut rx_propose_now = self.consensus_bus.propose_now().subscribe();
 
         let mut pending_header = None;
         let mut max_delay_timed_out = false;
         let mut min_delay_timed_out = false;
+        let mut propose_now = false;
         loop {
             tokio::select! {
                 _ = &self.rx_shutdown => {
@@ -834,6 +934,9 @@ impl<DB: Database> Proposer<DB> {
                 _ = self.min_delay_interval.tick() => {
                     min_delay_timed_out = true;
                 }
+                Ok(()) = rx_propose_now.changed() => {
+                    propose_now = true;
+                }
             }
             if pending_header.is_some() {
                 // continue the loop, don't try to propose a header since we are already working
@@ -856,12 +959,14 @@ impl<DB: Database> Proposer<DB> {
             // - the worker created enough blocks (header_num_of_batches_threshold)
             //      - this is happy path
             //      - vote for leader or leader already has enough votes to trigger commit
+            // - a header was requested on demand through `ConsensusBus::propose_now`
             let enough_parents = !self.last_parents.is_empty();
             let enough_digests = self.digests.len() >= self.header_num_of_batches_threshold;
 
             // evaluate conditions for bool value
             let should_create_header = enough_parents
                 && (max_delay_timed_out
+                    || propose_now
                     || (self.advance_round && (enough_digests || min_delay_timed_out)));
 
             debug!(
@@ -873,6 +978,7 @@ impl<DB: Database> Proposer<DB> {
                 self.advance_round,
                 min_delay_timed_out,
                 max_delay_timed_out,
+                propose_now,
                 should_create_header,
                 "polled...",
             );
@@ -894,6 +1000,8 @@ impl<DB: Database> Proposer<DB> {
                     "max_timeout"
                 } else if enough_digests {
                     "threshold_size_reached"
+                } else if propose_now {
+                    "propose_now"
                 } else {
                     "min_timeout"
                 };
@@ -904,6 +1012,7 @@ impl<DB: Database> Proposer<DB> {
                 pending_header = Some(self.propose_next_header(reason.to_string())?);
                 max_delay_timed_out = false;
                 min_delay_timed_out = false;
+                propose_now = false;
             }
         }
     }
@@ -1,7 +1,7 @@
 //! Track the most recent execution blocks for the consensus layer.
 
 use std::collections::VecDeque;
-use tn_types::{BlockHash, BlockNumHash, SealedHeader};
+use tn_types::{BlockHash, BlockNumHash, BlockNumber, SealedHeader};
 
 /// Tracks 'num_blocks' most recently executed block hashes and numbers.
 #[derive(Clone, Debug)]
@@ -39,6 +39,11 @@ impl RecentBlocks {
         self.blocks.back().cloned().unwrap_or_else(Default::default)
     }
 
+    /// Return the number of the recent block we executed with hash, if any.
+    pub fn block_number(&self, hash: BlockHash) -> Option<BlockNumber> {
+        self.blocks.iter().find(|block| block.hash() == hash).map(|block| block.number)
+    }
+
     /// Is hash a recent block we have executed?
     pub fn contains_hash(&self, hash: BlockHash) -> bool {
         for block in &self.blocks {
@@ -0,0 +1,68 @@
+//! Track progress restoring consensus output after a restart.
+
+use std::time::{Duration, Instant};
+
+/// Progress re-executing consensus output that was committed but not executed before a restart.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct RestoreProgress {
+    /// The number of sub-dags to restore.
+    pub total: u64,
+    /// The number of sub-dags left to restore.
+    pub remaining: u64,
+    /// When the restore started.
+    pub started: Instant,
+}
+
+impl RestoreProgress {
+    /// Start restoring `total` sub-dags.
+    pub fn new(total: u64) -> Self {
+        Self { total, remaining: total, started: Instant::now() }
+    }
+
+    /// Record that a sub-dag was restored.
+    pub fn restored(&mut self) {
+        self.remaining = self.remaining.saturating_sub(1);
+    }
+
+    /// True once every sub-dag was restored.
+    pub fn is_complete(&self) -> bool {
+        self.remaining == 0
+    }
+
+    /// The estimated time left, based on the rate so far.
+    ///
+    /// None until the first sub-dag is restored.
+    pub fn eta(&self) -> Option<Duration> {
+        self.eta_at(self.started.elapsed())
+    }
+
+    /// The estimated time left after restoring for `elapsed`.
+    fn eta_at(&self, elapsed: Duration) -> Option<Duration> {
+        let done = self.total - self.remaining;
+        if done == 0 {
+            return None;
+        }
+        Some(elapsed.mul_f64(self.remaining as f64 / done as f64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_restore_eta() {
+        let mut progress = RestoreProgress::new(4);
+        assert_eq!(progress.eta_at(Duration::from_secs(1)), None);
+
+        progress.restored();
+        assert_eq!(progress.remaining, 3);
+        assert_eq!(progress.eta_at(Duration::from_secs(2)), Some(Duration::from_secs(6)));
+
+        for _ in 0..5 {
+            progress.restored();
+        }
+        assert!(progress.is_complete());
+        assert_eq!(progress.eta_at(Duration::from_secs(8)), Some(Duration::ZERO));
+    }
+}
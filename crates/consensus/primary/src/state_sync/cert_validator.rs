@@ -139,7 +139,7 @@ where
     /// Validate and verify the certificate.
     ///
     /// This method validates the certificate and verifies signatures.
-    fn validate_and_verify(&self, certificate: Certificate) -> CertManagerResult<Certificate> {
+    fn validate_and_verify(&self, mut certificate: Certificate) -> CertManagerResult<Certificate> {
         // certificates outside gc can never be included in the DAG
         let gc_round = self.gc_round.load();
 
@@ -152,10 +152,21 @@ where
             .into());
         }
 
+        // skip the signature check if another code path already verified this certificate
+        let verified_certificates = self.consensus_bus.verified_certificates();
+        if verified_certificates.mark_verified(&mut certificate) {
+            self.consensus_bus
+                .primary_metrics()
+                .node_metrics
+                .certificate_verification_cache_hits
+                .inc();
+        }
+
         // validate certificate and verify signatures
         // TODO: rename this method too
         let verified_cert =
-            certificate.verify(self.config.committee(), self.config.worker_cache())?;
+            certificate.verify(self.config.committee(), &self.config.worker_cache())?;
+        verified_certificates.insert(&verified_cert);
         Ok(verified_cert)
     }
 
@@ -285,6 +296,38 @@ where
         self.forward_verified_certs("other", highest_round, certificates).await
     }
 
+    /// Validate certificates received from a peer and verify every signature in parallel.
+    ///
+    /// Unlike fetched certificates, these are not expected to form a causal chain so each one is
+    /// verified directly. Certificates that were already processed are skipped.
+    pub(super) async fn process_peer_certificates_in_parallel(
+        &self,
+        certificates: Vec<Certificate>,
+    ) -> CertManagerResult<()> {
+        let _scope = monitored_scope("primary::cert_validator");
+        let mut unknown = Vec::with_capacity(certificates.len());
+        for certificate in certificates {
+            if self.config.node_storage().contains(&certificate.digest())? {
+                self.consensus_bus
+                    .primary_metrics()
+                    .node_metrics
+                    .duplicate_certificates_processed
+                    .inc();
+                continue;
+            }
+            unknown.push((unknown.len(), certificate));
+        }
+
+        if unknown.is_empty() {
+            return Ok(());
+        }
+
+        let certificates: Vec<_> =
+            self.verify_certificate_chunk(unknown).await?.into_iter().map(|(_, c)| c).collect();
+        let highest_round = certificates.iter().map(|c| c.round()).max().unwrap_or(0);
+        self.forward_verified_certs("other", highest_round, certificates).await
+    }
+
     /// Main method to subdivide certificates into groups and verify based on causal relationship.
     async fn verify_collection(
         &self,
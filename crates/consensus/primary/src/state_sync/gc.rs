@@ -11,9 +11,10 @@ use std::sync::{
     Arc,
 };
 use tn_config::ConsensusConfig;
+use tn_storage::VoteDigestStore as _;
 use tn_types::{Database, TnSender as _};
 use tokio::{sync::watch, time::interval};
-use tracing::{error, warn};
+use tracing::{debug, error, warn};
 
 /// Long running task that manages the garbage collection events from consensus.
 ///
@@ -44,7 +45,21 @@ where
         gc_round: AtomicRound,
     ) -> Self {
         let rx_gc_round_updates = consensus_bus.gc_round_updates().subscribe();
-        Self { config, consensus_bus, rx_gc_round_updates, gc_round }
+        let gc = Self { config, consensus_bus, rx_gc_round_updates, gc_round };
+
+        // remove votes left by previous epochs and older versions that never pruned them
+        gc.prune_votes(gc.gc_round.load());
+        gc
+    }
+
+    /// Remove votes at or below the gc round.
+    ///
+    /// Failing to prune is not fatal, votes are pruned again when the gc round advances.
+    fn prune_votes(&self, gc_round: u32) {
+        match self.config.node_storage().prune_votes(self.config.committee(), gc_round) {
+            Ok(pruned) => debug!(target: "primary::gc", pruned, gc_round, "pruned votes"),
+            Err(e) => warn!(target: "primary::gc", ?e, gc_round, "failed to prune votes"),
+        }
     }
 
     /// The round advanced within time. Process the round
@@ -54,6 +69,7 @@ where
         // update gc round
         let new_round = *self.rx_gc_round_updates.borrow_and_update();
         self.gc_round.store(new_round);
+        self.prune_votes(new_round);
 
         Ok(())
     }
@@ -94,6 +94,16 @@ where
         self.certificate_validator.process_fetched_certificates_in_parallel(certificates).await
     }
 
+    /// Process certificates received from a peer with a request.
+    ///
+    /// Signatures are verified in parallel on the blocking thread pool.
+    pub(crate) async fn process_peer_certificates_in_parallel(
+        &self,
+        certificates: Vec<Certificate>,
+    ) -> CertManagerResult<()> {
+        self.certificate_validator.process_peer_certificates_in_parallel(certificates).await
+    }
+
     //
     //=== Header API
     //
@@ -2,15 +2,19 @@
 //!
 //! Pending certificates are waiting to be accepted due to missing parents.
 //! This mod manages and tracks pending certificates for rounds of consensus.
+//!
+//! The number of pending certificates is bounded by the certificates that fit within `gc_depth`
+//! rounds. Certificates from the highest rounds are evicted first so a peer flooding far-future
+//! certificates can not grow memory without bound.
 
 use crate::{
     error::{CertManagerError, CertManagerResult},
     ConsensusBus,
 };
-use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 use tn_config::ConsensusConfig;
 use tn_types::{Certificate, CertificateDigest, Database, Hash as _, Round};
-use tracing::debug;
+use tracing::{debug, warn};
 
 /// A certificate that is missing parents and pending approval.
 ///
@@ -60,6 +64,15 @@ pub struct PendingCertificateManager<DB> {
     ///
     /// The keys are (round, digest) to enable garbage collection by round.
     missing_for_pending: BTreeMap<(Round, CertificateDigest), HashSet<CertificateDigest>>,
+    /// The pending certificates ordered by round.
+    ///
+    /// Used to evict certificates from the highest rounds first.
+    pending_by_round: BTreeSet<(Round, CertificateDigest)>,
+    /// The maximum number of pending certificates.
+    ///
+    /// The DAG holds at most one certificate per authority each round, so this is the number of
+    /// certificates within `gc_depth` rounds.
+    max_pending: usize,
     /// The configuration for consensus.
     config: ConsensusConfig<DB>,
     /// Consensus channels.
@@ -72,9 +85,12 @@ where
 {
     /// Create a new instance of Self.
     pub fn new(config: ConsensusConfig<DB>, consensus_bus: ConsensusBus) -> Self {
+        let max_pending = config.parameters().gc_depth as usize * config.committee().size();
         Self {
             pending: Default::default(),
             missing_for_pending: Default::default(),
+            pending_by_round: Default::default(),
+            max_pending,
             config,
             consensus_bus,
         }
@@ -89,7 +105,8 @@ where
         missing_parents: HashSet<CertificateDigest>,
     ) -> CertManagerResult<()> {
         let digest = certificate.digest();
-        let parent_round = certificate.round() - 1;
+        let round = certificate.round();
+        let parent_round = round - 1;
         debug!(target: "primary::pending_certs", ?digest, "Processing certificate with missing parents");
 
         self.consensus_bus
@@ -111,16 +128,56 @@ where
         for parent in missing_parents {
             self.missing_for_pending.entry((parent_round, parent)).or_default().insert(digest);
         }
+        self.pending_by_round.insert((round, digest));
 
-        self.consensus_bus
-            .primary_metrics()
-            .node_metrics
-            .certificates_currently_suspended
-            .set(self.pending.len() as i64);
+        // evict the highest rounds first - these are the furthest from being accepted
+        while self.pending.len() > self.max_pending {
+            let Some((evicted_round, evicted)) = self.pending_by_round.pop_last() else {
+                break;
+            };
+            self.evict(evicted_round, evicted);
+        }
+
+        self.update_metrics();
 
         Ok(())
     }
 
+    /// Remove a pending certificate and stop tracking its missing parents.
+    ///
+    /// The certificate is requested again if it is needed once the DAG catches up.
+    fn evict(&mut self, round: Round, digest: CertificateDigest) {
+        let Some(evicted) = self.pending.remove(&digest) else {
+            return;
+        };
+
+        warn!(
+            target: "primary::pending_certs",
+            ?digest,
+            round,
+            max_pending = self.max_pending,
+            "evicting pending certificate"
+        );
+        for parent in evicted.missing_parent_digests {
+            let key = (round - 1, parent);
+            if let Some(children) = self.missing_for_pending.get_mut(&key) {
+                children.remove(&digest);
+                if children.is_empty() {
+                    self.missing_for_pending.remove(&key);
+                }
+            }
+        }
+
+        self.consensus_bus.primary_metrics().node_metrics.certificates_evicted.inc();
+    }
+
+    /// Update the metrics for the memory used by pending certificates.
+    fn update_metrics(&self) {
+        let metrics = &self.consensus_bus.primary_metrics().node_metrics;
+        metrics.certificates_currently_suspended.set(self.pending.len() as i64);
+        metrics.certificates_missing_parents.set(self.missing_for_pending.len() as i64);
+    }
+
     /// When a certificate is accepted, returns all of its children that are now ready to be
     /// verified.
     // TODO: remove after tests
@@ -160,6 +217,7 @@ where
                         .pending
                         .remove(pending_digest)
                         .ok_or(CertManagerError::PendingCertificateNotFound(*pending_digest))?;
+                    self.pending_by_round.remove(&(ready.certificate.round(), *pending_digest));
 
                     // update any pending certificates waiting for this certificate
                     certificates_to_process.push_back((ready.certificate.round(), *pending_digest));
@@ -170,6 +228,8 @@ where
             }
         }
 
+        self.update_metrics();
+
         Ok(ready_certificates)
     }
 
@@ -211,6 +271,12 @@ where
         self.pending.len()
     }
 
+    /// Set the maximum number of pending certificates for tests.
+    #[cfg(test)]
+    pub(super) fn set_max_pending_for_test(&mut self, max_pending: usize) {
+        self.max_pending = max_pending;
+    }
+
     /// Filter parents that are pending in place.
     ///
     /// This is used when voting for headers.
@@ -1,7 +1,7 @@
 //! Certificate fetcher tests
 
 use crate::{
-    certificate_fetcher::CertificateFetcher,
+    certificate_fetcher::{fetch_order, CertificateFetcher},
     error::CertManagerError,
     network::{PrimaryRequest, PrimaryResponse},
     state_sync::StateSynchronizer,
@@ -11,10 +11,10 @@ use assert_matches::assert_matches;
 use itertools::Itertools;
 use std::{collections::BTreeSet, time::Duration};
 use tn_network_libp2p::types::{NetworkCommand, NetworkHandle};
-use tn_storage::{mem_db::MemDatabase, CertificateStore, PayloadStore};
+use tn_storage::{mem_db::MemDatabase, CertificateStore, PayloadStore, PeerLatencyStore as _};
 use tn_test_utils::CommitteeFixture;
 use tn_types::{
-    BlsSignature, Certificate, Hash as _, Header, SignatureVerificationState, TaskManager,
+    now, BlsSignature, Certificate, Hash as _, Header, SignatureVerificationState, TaskManager,
 };
 use tokio::{
     sync::mpsc::{self, error::TryRecvError},
@@ -404,3 +404,23 @@ async fn fetch_certificates_basic() {
     )
     .await;
 }
+
+#[test]
+fn fetch_order_prefers_saved_round_trip_times() {
+    let fixture = CommitteeFixture::builder(MemDatabase::default).randomize_ports(true).build();
+    let mut authorities = fixture.authorities();
+    let primary = authorities.next().unwrap();
+    let [near, penalized, unknown] =
+        [(); 3].map(|_| authorities.next().expect("committee of 4").id());
+    let config = primary.consensus_config();
+    let store = config.node_storage();
+    store.record_peer_latency(&near, Duration::from_millis(50), now()).unwrap();
+    // the fastest sample, but the peer stopped answering pings since
+    store.record_peer_latency(&penalized, Duration::from_millis(10), now()).unwrap();
+    for _ in 0..3 {
+        config.record_authority_failure(&penalized);
+    }
+
+    let order = fetch_order(&primary.id(), &fixture.committee(), &config.authority_latencies());
+    assert_eq!(order, vec![near.peer_id(), penalized.peer_id(), unknown.peer_id()]);
+}
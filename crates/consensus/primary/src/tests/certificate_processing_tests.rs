@@ -3,7 +3,10 @@
 //! Certificates are validated and sent to the [CertificateManager].
 //! The [CertificateManager] tracks pending certificates and accepts certificates that are complete.
 
-use super::{cert_manager::CertificateManager, cert_validator::CertificateValidator, AtomicRound};
+use super::{
+    cert_manager::CertificateManager, cert_validator::CertificateValidator,
+    pending_cert_manager::PendingCertificateManager, AtomicRound,
+};
 use crate::{
     consensus::{gc_round, ConsensusRound},
     error::CertManagerError,
@@ -17,8 +20,8 @@ use tn_test_utils::{
     make_optimal_signed_certificates, signed_cert_for_test, AuthorityFixture, CommitteeFixture,
 };
 use tn_types::{
-    error::CertificateError, Certificate, CertificateDigest, Database, Hash as _, Round,
-    TaskManager, TnReceiver as _, TnSender,
+    error::CertificateError, BlsAggregateSignatureBytes, Certificate, CertificateDigest, Database,
+    Hash as _, Round, SignatureVerificationState, TaskManager, TnReceiver as _, TnSender,
 };
 use tokio::time::timeout;
 
@@ -131,6 +134,46 @@ async fn test_accept_valid_certs() -> eyre::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_accept_peer_certs_in_parallel() -> eyre::Result<()> {
+    let TestTypes { validator, manager, cb, fixture, task_manager, .. } = create_all_test_types();
+    // test types uses last authority for config
+    let primary = fixture.authorities().last().unwrap();
+    let certificate_store = primary.consensus_config().node_storage().clone();
+
+    // spawn manager task
+    task_manager.spawn_task("manager", manager.run());
+    let mut rx_new_certificates = cb.new_certificates().subscribe();
+
+    // create 3 certs
+    let certs: Vec<_> = fixture.headers().iter().take(3).map(|h| fixture.certificate(h)).collect();
+    assert!(certs.iter().all(|cert| !cert.is_verified()));
+    validator.process_peer_certificates_in_parallel(certs.clone()).await?;
+
+    // assert certs were verified and stored
+    for _ in &certs {
+        let received = rx_new_certificates.recv().await.unwrap();
+        assert!(received.is_verified());
+        assert!(certs.contains(&received));
+    }
+    for cert in &certs {
+        assert_eq!(certificate_store.read(cert.digest())?, Some(cert.clone()));
+    }
+
+    // duplicates are skipped
+    validator.process_peer_certificates_in_parallel(certs).await?;
+    assert!(rx_new_certificates.try_recv().is_err());
+
+    // invalid signatures are rejected
+    let mut invalid = fixture.certificate(&fixture.headers_next_round()[0]);
+    invalid.set_signature_verification_state(SignatureVerificationState::Unverified(
+        BlsAggregateSignatureBytes::default(),
+    ));
+    assert!(validator.process_peer_certificates_in_parallel(vec![invalid]).await.is_err());
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_accept_pending_certs() -> eyre::Result<()> {
     let TestTypes { validator, manager, cb, fixture, task_manager, .. } = create_all_test_types();
@@ -273,6 +316,48 @@ async fn test_gc_pending_certs() -> eyre::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_pending_certs_evicted_at_capacity() -> eyre::Result<()> {
+    let fixture = CommitteeFixture::builder(MemDatabase::default).randomize_ports(true).build();
+    let config = fixture.authorities().last().unwrap().consensus_config();
+    let mut pending = PendingCertificateManager::new(config, ConsensusBus::new());
+    let num_authorities = fixture.num_authorities();
+    // only one round fits
+    pending.set_max_pending_for_test(num_authorities);
+
+    // make 3 rounds of certificates
+    let committee = fixture.committee();
+    let genesis =
+        Certificate::genesis(&committee).iter().map(|x| x.digest()).collect::<BTreeSet<_>>();
+    let keys: Vec<_> = fixture.authorities().map(|a| (a.id(), a.keypair().copy())).collect();
+    let (mut first_round, _next_parents) =
+        make_optimal_signed_certificates(1..=3, &genesis, &committee, keys.as_slice());
+    let later_rounds = first_round.split_off(num_authorities);
+
+    // rounds 2 and 3 are missing all parents
+    for cert in later_rounds.iter() {
+        let missing = cert.header().parents().iter().copied().collect();
+        pending.insert_pending(cert.clone(), missing)?;
+    }
+
+    // the highest round is evicted
+    assert_eq!(pending.num_pending(), num_authorities);
+    for cert in later_rounds.iter() {
+        assert_eq!(pending.is_pending(&cert.digest()), cert.round() == 2);
+    }
+
+    // accepting round 1 unlocks round 2 without waiting for evicted certificates
+    let mut ready = Vec::new();
+    for cert in first_round {
+        ready.extend(pending.update_pending(cert.round(), cert.digest())?);
+    }
+    assert_eq!(ready.len(), num_authorities);
+    assert!(ready.iter().all(|cert| cert.round() == 2));
+    assert_eq!(pending.num_pending(), 0);
+
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_node_restart_syncs_state() -> eyre::Result<()> {
     let TestTypes { validator, manager, fixture, task_manager, .. } = create_all_test_types();
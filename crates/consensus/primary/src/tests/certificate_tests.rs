@@ -9,6 +9,8 @@ use tn_storage::mem_db::MemDatabase;
 use tn_test_utils::CommitteeFixture;
 use tn_types::{AuthorityIdentifier, BlsKeypair, Certificate, SignatureVerificationState, Vote};
 
+use crate::VerifiedCertificates;
+
 #[test]
 fn test_empty_certificate_verification() {
     let fixture = CommitteeFixture::builder(MemDatabase::default).build();
@@ -51,6 +53,48 @@ fn test_valid_certificate_verification() {
     ));
 }
 
+#[test]
+fn test_verified_certificates_cache() {
+    let fixture = CommitteeFixture::builder(MemDatabase::default).build();
+    let committee = fixture.committee();
+    let header = fixture.header_from_last_authority();
+    let votes = |skip| {
+        fixture
+            .authorities()
+            .skip(skip)
+            .take(3)
+            .map(|a| (a.id(), a.vote(&header).signature().clone()))
+            .collect()
+    };
+
+    let certificate = Certificate::new_unverified(&committee, header.clone(), votes(0)).unwrap();
+    let cache = VerifiedCertificates::new(1);
+
+    // unverified certificates are not cached
+    cache.insert(&certificate);
+    assert!(cache.is_empty());
+
+    let verified = certificate.verify(&committee, &fixture.worker_cache()).unwrap();
+    cache.insert(&verified);
+    assert_eq!(cache.len(), 1);
+
+    // the same certificate received again is already verified
+    let mut received = verified.clone().validate_received().unwrap();
+    assert!(cache.mark_verified(&mut received));
+    assert!(matches!(
+        received.signature_verification_state(),
+        SignatureVerificationState::VerifiedDirectly(_)
+    ));
+
+    // the same header certified by different signers must be verified
+    let mut other = Certificate::new_unverified(&committee, header, votes(1)).unwrap();
+    assert!(!cache.mark_verified(&mut other));
+    assert!(matches!(
+        other.signature_verification_state(),
+        SignatureVerificationState::Unverified(_)
+    ));
+}
+
 #[test]
 fn test_certificate_insufficient_signatures() {
     let fixture = CommitteeFixture::builder(MemDatabase::default).build();
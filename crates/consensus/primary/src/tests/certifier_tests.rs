@@ -81,6 +81,14 @@ async fn propose_header_to_form_certificate() {
         certificate.signature_verification_state(),
         SignatureVerificationState::VerifiedDirectly(_)
     ));
+
+    // The certified header's votes are reported.
+    let aggregation = cb.vote_aggregation().borrow().clone().expect("votes reported");
+    assert_eq!(aggregation.header_digest, proposed_digest);
+    assert!(aggregation.certified());
+    assert!(aggregation.voters.contains(&id));
+    assert!(aggregation.stake >= aggregation.quorum_threshold);
+    assert_eq!(aggregation.voters.len() + aggregation.missing.len(), committee.size());
 }
 
 #[tokio::test(flavor = "current_thread")]
@@ -138,6 +146,128 @@ async fn propose_header_failure() {
     }
 }
 
+#[tokio::test(flavor = "current_thread")]
+async fn repropose_header_requests_missing_votes() {
+    let fixture = CommitteeFixture::builder(MemDatabase::default).randomize_ports(true).build();
+    let committee = fixture.committee();
+    let primary = fixture.authorities().last().unwrap();
+    let id = primary.id();
+
+    // Create a fake header.
+    let proposed_header = primary.header(&committee);
+
+    // Set up network handle- this is all we need to simulate then network for the certifier.
+    let (sender, mut network_rx) = mpsc::channel(100);
+    let network: NetworkHandle<PrimaryRequest, PrimaryResponse> = NetworkHandle::new(sender);
+
+    // Set up remote primaries responding with votes.
+    let mut peer_votes = HashMap::new();
+    for peer in fixture.authorities().filter(|a| a.id() != id) {
+        let name = peer.id();
+        let vote =
+            Vote::new(&proposed_header, name.clone(), peer.consensus_config().key_config()).await;
+        peer_votes.insert(name.peer_id(), vote);
+    }
+
+    let cb = ConsensusBus::new();
+    let mut rx_new_certificates = cb.new_certificates().subscribe();
+    let synchronizer = StateSynchronizer::new(primary.consensus_config(), cb.clone());
+    let task_manager = TaskManager::default();
+    synchronizer.spawn(&task_manager);
+    Certifier::spawn(
+        primary.consensus_config(),
+        cb.clone(),
+        synchronizer,
+        network.clone().into(),
+        &task_manager,
+    );
+
+    // Only the first peer votes, the other requests stay pending.
+    let proposed_digest = proposed_header.digest();
+    cb.headers().send(proposed_header.clone()).await.unwrap();
+    let mut voted = None;
+    let mut voted_author = None;
+    let mut pending_replies = Vec::new();
+    while pending_replies.len() < 2 {
+        if let Some(NetworkCommand::SendRequest {
+            peer,
+            request: PrimaryRequest::Vote { .. },
+            reply,
+        }) = network_rx.recv().await
+        {
+            if voted.is_none() {
+                let vote = peer_votes.remove(&peer).unwrap();
+                voted_author = Some(vote.author().clone());
+                reply.send(Ok(PrimaryResponse::Vote(vote))).unwrap();
+                voted = Some(peer);
+            } else {
+                pending_replies.push(reply);
+            }
+        }
+    }
+
+    // Wait for the certifier to record the vote.
+    let voted_author = voted_author.unwrap();
+    cb.vote_aggregation()
+        .subscribe()
+        .wait_for(|aggregation| {
+            aggregation.as_ref().is_some_and(|a| a.voters.contains(&voted_author))
+        })
+        .await
+        .unwrap();
+
+    // Cancel the proposal so the same header is proposed again.
+    cb.headers().send(proposed_header).await.unwrap();
+
+    // Only the peers that haven't voted are asked again.
+    while !peer_votes.is_empty() {
+        if let Some(NetworkCommand::SendRequest {
+            peer,
+            request: PrimaryRequest::Vote { .. },
+            reply,
+        }) = network_rx.recv().await
+        {
+            assert_ne!(Some(peer), voted);
+            reply.send(Ok(PrimaryResponse::Vote(peer_votes.remove(&peer).unwrap()))).unwrap();
+        }
+    }
+    let certificate = tokio::time::timeout(Duration::from_secs(10), rx_new_certificates.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(certificate.header().digest(), proposed_digest);
+}
+
+#[tokio::test]
+async fn rejected_votes_are_requested_again() {
+    let fixture = CommitteeFixture::builder(MemDatabase::default).build();
+    let committee = fixture.committee();
+    let mut authorities = fixture.authorities();
+    let primary = authorities.next().unwrap();
+    let honest = authorities.next().unwrap();
+    let byzantine = authorities.next().unwrap();
+    let header = primary.header(&committee);
+
+    let mut votes_aggregator = crate::aggregators::VotesAggregator::new(Default::default());
+    let own = Vote::new(&header, primary.id(), primary.consensus_config().key_config()).await;
+    let vote = Vote::new(&header, honest.id(), honest.consensus_config().key_config()).await;
+    let bad_key = BlsKeypair::generate(&mut StdRng::from_seed([0; 32]));
+    let bad_vote = Vote::new_with_signer(&header, byzantine.id(), &bad_key);
+    for vote in [own, vote] {
+        assert!(votes_aggregator.append(vote, &committee, &header).unwrap().is_none());
+    }
+
+    // the invalid vote reaches quorum but fails verification
+    assert!(votes_aggregator.append(bad_vote, &committee, &header).unwrap().is_none());
+    assert!(votes_aggregator.has_voted(&honest.id()));
+    assert!(!votes_aggregator.has_voted(&byzantine.id()));
+
+    // the authority is asked again and its valid vote certifies the header
+    let vote = Vote::new(&header, byzantine.id(), byzantine.consensus_config().key_config()).await;
+    let certificate = votes_aggregator.append(vote, &committee, &header).unwrap();
+    assert_eq!(certificate.unwrap().header().digest(), header.digest());
+}
+
 #[tokio::test(flavor = "current_thread")]
 async fn propose_header_scenario_with_bad_sigs() {
     // expect cert if less than 2 byzantines, otherwise no cert
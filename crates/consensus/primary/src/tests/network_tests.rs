@@ -2,19 +2,29 @@
 
 use crate::{
     error::PrimaryNetworkError,
-    network::{MissingCertificatesRequest, RequestHandler},
+    network::{
+        handler::VoteHandler, MissingCertificatesRequest, PrimaryHandlerRegistry, PrimaryRPCError,
+        PrimaryRequest, PrimaryRequestHandler, PrimaryResponse, RequestHandler, VOTE_PROTOCOL,
+    },
     state_sync::StateSynchronizer,
     ConsensusBus, RecentBlocks,
 };
 use assert_matches::assert_matches;
-use std::collections::{BTreeMap, BTreeSet};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    sync::Arc,
+};
 use tn_network_libp2p::PeerId;
-use tn_storage::mem_db::MemDatabase;
-use tn_test_utils::CommitteeFixture;
+use tn_storage::{
+    mem_db::MemDatabase, tables::ConsensusBlockNumbersByDigest, CertificateStore as _,
+    VoteDigestStore as _,
+};
+use tn_test_utils::{CommitteeFixture, TestClock};
 use tn_types::{
-    error::HeaderError, network_public_key_to_libp2p, now, AuthorityIdentifier, BlockHash,
-    BlockHeader, BlockNumHash, Certificate, CertificateDigest, ExecHeader, Hash as _, SealedHeader,
-    TaskManager,
+    error::HeaderError, network_public_key_to_libp2p, now, AuthorityIdentifier, BlockAnnouncement,
+    BlockHash, BlockHeader, BlockNumHash, Certificate, CertificateDigest, Database as _,
+    ExecHeader, Hash as _, Multiaddr, SealedHeader, SharedClock, SystemClock, TaskManager,
+    WorkerInfoUpdate,
 };
 use tracing::debug;
 
@@ -47,6 +57,8 @@ struct TestTypes<DB = MemDatabase> {
     // authority: &'a AuthorityFixture<DB>,
     /// The handler for requests.
     handler: RequestHandler<DB>,
+    /// The consensus bus used by the handler.
+    consensus_bus: ConsensusBus,
     /// The parent execution result for all primary headers.
     ///
     /// num: 0
@@ -57,9 +69,14 @@ struct TestTypes<DB = MemDatabase> {
 /// Helper function to create an instance of [RequestHandler] for the first authority in the
 /// committee.
 fn create_test_types() -> TestTypes {
+    create_test_types_with_clock(SystemClock::shared())
+}
+
+/// Helper function to create an instance of [RequestHandler] that reads the time from `clock`.
+fn create_test_types_with_clock(clock: SharedClock) -> TestTypes {
     let committee = CommitteeFixture::builder(MemDatabase::default).randomize_ports(true).build();
     let authority = committee.first_authority();
-    let config = authority.consensus_config();
+    let config = authority.consensus_config().with_clock(clock);
     let cb = ConsensusBus::new();
 
     // spawn the synchronizer
@@ -78,7 +95,7 @@ fn create_test_types() -> TestTypes {
         .expect("watch channel updates for default parent in primary handler tests");
 
     let handler = RequestHandler::new(config.clone(), cb.clone(), synchronizer);
-    TestTypes { committee, handler, parent }
+    TestTypes { committee, handler, consensus_bus: cb, parent }
 }
 
 #[tokio::test]
@@ -246,6 +263,34 @@ async fn test_vote_fails_invalid_timestamp() -> eyre::Result<()> {
     Ok(())
 }
 
+#[tokio::test]
+async fn test_vote_timestamp_from_clock() -> eyre::Result<()> {
+    // the node's clock is far behind the system time
+    let clock = TestClock::new(1_000);
+    let TestTypes { committee, handler, parent, .. } = create_test_types_with_clock(clock.shared());
+
+    let parents = Vec::with_capacity(0);
+    let peer_id =
+        network_public_key_to_libp2p(&committee.last_authority().primary_network_public_key());
+
+    // created in the past by the system time, but too far in the future for the node's clock
+    let header = committee
+        .header_builder_last_authority()
+        .latest_execution_block(BlockNumHash::new(parent.number(), parent.hash()))
+        .created_at(100_000)
+        .build();
+
+    let res = handler.vote(peer_id, header, parents).await;
+    assert_matches!(
+        res,
+        Err(PrimaryNetworkError::InvalidHeader(HeaderError::InvalidTimestamp {
+            created: 100_000,
+            received: 1_000
+        }))
+    );
+    Ok(())
+}
+
 #[tokio::test]
 async fn test_vote_fails_wrong_epoch() -> eyre::Result<()> {
     // common types
@@ -295,3 +340,241 @@ async fn test_vote_fails_unknown_authority() -> eyre::Result<()> {
     assert_matches!(res, Err(PrimaryNetworkError::InvalidHeader(HeaderError::UnknownAuthority(wrong))) if wrong == wrong_authority.to_string());
     Ok(())
 }
+
+/// Echo the payload of custom requests.
+struct EchoHandler;
+
+#[async_trait::async_trait]
+impl PrimaryRequestHandler for EchoHandler {
+    async fn handle(&self, _peer: PeerId, request: PrimaryRequest) -> PrimaryResponse {
+        match request {
+            PrimaryRequest::Custom { payload, .. } => PrimaryResponse::Custom(payload),
+            _ => PrimaryRPCError("unexpected request".to_string()).into(),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_handler_registry_dispatches_by_protocol() -> eyre::Result<()> {
+    // common types
+    let TestTypes { committee, handler, parent, .. } = create_test_types();
+
+    let mut registry = PrimaryHandlerRegistry::default();
+    registry.register(VOTE_PROTOCOL, VoteHandler(handler));
+    registry.register("echo", EchoHandler);
+
+    // custom protocols are dispatched to their handler
+    let request = PrimaryRequest::Custom { protocol: "echo".to_string(), payload: vec![1, 2, 3] };
+    let handler = registry.get(request.protocol()).expect("echo handler registered");
+    let res = handler.handle(PeerId::random(), request).await;
+    assert_eq!(res, PrimaryResponse::Custom(vec![1, 2, 3]));
+
+    // consensus protocols use the same registry
+    let peer_id =
+        network_public_key_to_libp2p(&committee.last_authority().primary_network_public_key());
+    let header = committee
+        .header_builder_last_authority()
+        .latest_execution_block(BlockNumHash::new(parent.number(), parent.hash()))
+        .created_at(1) // parent is 0
+        .build();
+    let request = PrimaryRequest::Vote { header: Arc::new(header), parents: Vec::new() };
+    let handler = registry.get(request.protocol()).expect("vote handler registered");
+    let res = handler.handle(peer_id, request).await;
+    assert_matches!(res, PrimaryResponse::Vote(_));
+
+    // handlers reject requests for other protocols
+    let request = PrimaryRequest::ConsensusHeader { number: None, hash: None };
+    let res = handler.handle(peer_id, request).await;
+    assert!(res.is_err());
+
+    // unknown protocols have no handler
+    assert!(registry.get("unknown").is_none());
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_votes_pruned_at_gc_round() -> eyre::Result<()> {
+    // common types
+    let TestTypes { committee, handler, consensus_bus, parent } = create_test_types();
+    let storage = committee.first_authority().consensus_config().node_storage().clone();
+
+    let peer_id =
+        network_public_key_to_libp2p(&committee.last_authority().primary_network_public_key());
+    let author = committee.last_authority().id();
+
+    // create valid header proposed by last peer in the committee for round 1
+    let header = committee
+        .header_builder_last_authority()
+        .latest_execution_block(BlockNumHash::new(parent.number(), parent.hash()))
+        .created_at(1) // parent is 0
+        .build();
+
+    // vote is stored
+    let res = handler.vote(peer_id, header.clone(), Vec::new()).await;
+    assert!(res.is_ok());
+    assert!(storage.read_vote_info(&author)?.is_some());
+
+    // votes above the gc round are kept
+    assert_eq!(storage.prune_votes(&committee.committee(), 0)?, 0);
+    assert!(storage.read_vote_info(&author)?.is_some());
+
+    // votes at or below the gc round are removed
+    assert_eq!(storage.prune_votes(&committee.committee(), 1)?, 1);
+    assert!(storage.read_vote_info(&author)?.is_none());
+
+    // the pruned round can not be voted on again
+    consensus_bus.gc_round_updates().send(1)?;
+    let res = handler.vote(peer_id, header, Vec::new()).await;
+    assert_matches!(
+        res,
+        Err(PrimaryNetworkError::InvalidHeader(HeaderError::TooOld {
+            header_round: 1,
+            max_round: 1,
+            ..
+        }))
+    );
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_catch_up_certs_pushed_to_peers_behind() -> eyre::Result<()> {
+    // common types
+    let TestTypes { committee, handler, consensus_bus, .. } = create_test_types();
+    let storage = committee.first_authority().consensus_config().node_storage().clone();
+
+    // store certificates for round 1
+    let genesis =
+        Certificate::genesis(&committee.committee()).iter().map(|cert| cert.digest()).collect();
+    let (_, headers) = committee.headers_round(0, &genesis);
+    let certificates: Vec<_> = headers.iter().map(|header| committee.certificate(header)).collect();
+    for cert in certificates.iter() {
+        storage.write(cert.clone())?;
+    }
+
+    // nothing is pushed unless this node is far enough ahead of the peer
+    consensus_bus.primary_round_updates().send(1)?;
+    assert!(handler.catch_up_certs(0).await?.is_empty());
+
+    consensus_bus.primary_round_updates().send(25)?;
+    let pushed = handler.catch_up_certs(0).await?;
+    assert_eq!(pushed.len(), certificates.len());
+    assert!(pushed.iter().all(|cert| cert.round() == 1));
+
+    // pushed certificates are rejected unless this node requested catch up
+    let res = handler.accept_pushed_certs(pushed.clone()).await;
+    assert_matches!(res, Err(PrimaryNetworkError::InvalidRequest(_)));
+
+    // certificates at or below the catch up round are ignored
+    consensus_bus.catch_up_round().send_replace(Some(1));
+    let res = handler.accept_pushed_certs(pushed).await?;
+    assert_eq!(res, PrimaryResponse::Ack);
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_worker_info_update() -> eyre::Result<()> {
+    // common types
+    let TestTypes { committee, handler, .. } = create_test_types();
+    let config = committee.first_authority().consensus_config();
+    let mut rx_worker_cache = config.subscribe_worker_cache();
+
+    // the last authority moves its worker
+    let authority = committee.last_authority();
+    let worker_cache = config.worker_cache();
+    let mut worker_index = worker_cache.workers[&authority.primary_public_key()].clone();
+    let new_address: Multiaddr = "/ip4/10.0.0.1/udp/49594/quic-v1".parse()?;
+    worker_index.0.get_mut(&0).expect("worker 0").worker_address = new_address.clone();
+    let update = WorkerInfoUpdate::new(
+        authority.consensus_config().key_config(),
+        authority.primary_public_key(),
+        worker_cache.epoch(),
+        1,
+        worker_index.clone(),
+    );
+
+    handler.apply_worker_info_update(&update)?;
+    assert!(rx_worker_cache.has_changed()?);
+    let worker = config.worker_cache().worker(&authority.primary_public_key(), &0)?;
+    assert_eq!(worker.worker_address, new_address);
+
+    // replayed updates are rejected
+    let res = handler.apply_worker_info_update(&update);
+    assert_matches!(res, Err(PrimaryNetworkError::InvalidWorkerUpdate(_)));
+
+    // updates must be signed by the authority
+    let forged = WorkerInfoUpdate::new(
+        config.key_config(),
+        authority.primary_public_key(),
+        worker_cache.epoch(),
+        2,
+        worker_index,
+    );
+    let res = handler.apply_worker_info_update(&forged);
+    assert_matches!(res, Err(PrimaryNetworkError::InvalidWorkerUpdate(_)));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_block_announcement() -> eyre::Result<()> {
+    // common types
+    let TestTypes { committee, handler, consensus_bus, parent } = create_test_types();
+    let config = committee.first_authority().consensus_config();
+
+    // this node recorded consensus header 5
+    let consensus_hash = BlockHash::with_last_byte(5);
+    config.node_storage().insert::<ConsensusBlockNumbersByDigest>(&consensus_hash, &5)?;
+
+    let block = |number, parent_hash| ExecHeader {
+        number,
+        parent_hash,
+        parent_beacon_block_root: Some(consensus_hash),
+        ..Default::default()
+    };
+    let tip = || consensus_bus.last_announced_block().borrow().clone().expect("block announced");
+
+    // the first block extends this node's execution tip
+    let first = block(1, parent.hash());
+    let announcement = BlockAnnouncement::new(5, &first).expect("executed for consensus");
+    handler.process_block_announcement(&announcement)?;
+    let (consensus_number, header) = tip();
+    assert_eq!(consensus_number, 5);
+    assert_eq!(header.hash(), first.hash_slow());
+
+    // the next block extends the announced tip
+    let second = block(2, first.hash_slow());
+    let announcement = BlockAnnouncement::new(5, &second).expect("executed for consensus");
+    handler.process_block_announcement(&announcement)?;
+    assert_eq!(tip().1.hash(), second.hash_slow());
+
+    // older blocks do not replace the tip
+    let announcement = BlockAnnouncement::new(5, &first).expect("executed for consensus");
+    handler.process_block_announcement(&announcement)?;
+    assert_eq!(tip().1.number, 2);
+
+    // blocks with an unknown parent are ignored
+    let unlinked = block(3, BlockHash::with_last_byte(9));
+    let announcement = BlockAnnouncement::new(5, &unlinked).expect("executed for consensus");
+    handler.process_block_announcement(&announcement)?;
+    assert_eq!(tip().1.number, 2);
+
+    // blocks too far ahead of execution are ignored
+    let far = block(u64::MAX, second.hash_slow());
+    let announcement = BlockAnnouncement::new(5, &far).expect("executed for consensus");
+    handler.process_block_announcement(&announcement)?;
+    assert_eq!(tip().1.number, 2);
+
+    // the header must match the announced hash
+    let mut announcement =
+        BlockAnnouncement::new(5, &block(3, second.hash_slow())).expect("executed for consensus");
+    announcement.block_hash = BlockHash::with_last_byte(1);
+    let res = handler.process_block_announcement(&announcement);
+    assert_matches!(res, Err(PrimaryNetworkError::InvalidBlockAnnouncement(_)));
+
+    // announcements must match this node's consensus chain
+    let announcement =
+        BlockAnnouncement::new(6, &block(3, second.hash_slow())).expect("executed for consensus");
+    let res = handler.process_block_announcement(&announcement);
+    assert_matches!(res, Err(PrimaryNetworkError::InvalidBlockAnnouncement(_)));
+    assert_eq!(tip().1.number, 2);
+    Ok(())
+}
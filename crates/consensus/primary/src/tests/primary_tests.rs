@@ -16,7 +16,7 @@ use std::{
     time::Duration,
 };
 use tn_config::ConsensusConfig;
-use tn_network_libp2p::ConsensusNetwork;
+use tn_network_libp2p::{ConsensusNetwork, NodeVersion};
 use tn_network_types::MockPrimaryToWorkerClient;
 use tn_storage::{mem_db::MemDatabase, CertificateStore, PayloadStore};
 use tn_test_utils::{
@@ -34,7 +34,8 @@ fn get_bus_and_primary<DB: Database>(
 ) -> (ConsensusBus, Primary<DB>) {
     let (event_stream, rx_event_stream) = mpsc::channel(100);
     let consensus_bus = ConsensusBus::new_with_args(config.config().parameters.gc_depth);
-    let consensus_network = ConsensusNetwork::new_for_primary(&config, event_stream)
+    let local_version = NodeVersion::local(env!("CARGO_PKG_VERSION")).expect("crate version");
+    let consensus_network = ConsensusNetwork::new_for_primary(&config, event_stream, local_version)
         .expect("p2p network create failed!");
     let consensus_network_handle = consensus_network.network_handle();
     let state_sync = StateSynchronizer::new(config.clone(), consensus_bus.clone());
@@ -3,7 +3,7 @@
 use super::*;
 use crate::consensus::LeaderSwapTable;
 use tn_storage::mem_db::MemDatabase;
-use tn_test_utils::CommitteeFixture;
+use tn_test_utils::{CommitteeFixture, TestClock};
 use tn_types::B256;
 
 #[tokio::test]
@@ -32,6 +32,60 @@ async fn test_empty_proposal() {
     // TODO: assert header el state present
 }
 
+#[tokio::test]
+async fn test_header_timestamp_from_clock() {
+    let fixture = CommitteeFixture::builder(MemDatabase::default).build();
+    let committee = fixture.committee();
+    let primary = fixture.authorities().next().unwrap();
+    let clock = TestClock::new(1_000);
+
+    let cb = ConsensusBus::new();
+    let mut rx_headers = cb.headers().subscribe();
+    let proposer = Proposer::new(
+        primary.consensus_config().with_clock(clock.shared()),
+        cb.clone(),
+        LeaderSchedule::new(committee.clone(), LeaderSwapTable::default()),
+    );
+    proposer.spawn(&TaskManager::default());
+
+    let header = rx_headers.recv().await.unwrap();
+    assert_eq!(header.round(), 1);
+    assert_eq!(*header.created_at(), 1_000);
+}
+
+#[tokio::test(flavor = "current_thread", start_paused = true)]
+async fn test_clock_behind_parents() {
+    let fixture = CommitteeFixture::builder(MemDatabase::default).build();
+    let committee = fixture.committee();
+    let primary = fixture.authorities().next().unwrap();
+    let clock = TestClock::new(1_000);
+
+    let cb = ConsensusBus::new();
+    let mut rx_headers = cb.headers().subscribe();
+    let proposer = Proposer::new(
+        primary.consensus_config().with_clock(clock.shared()),
+        cb.clone(),
+        LeaderSchedule::new(committee.clone(), LeaderSwapTable::default()),
+    );
+    proposer.spawn(&TaskManager::default());
+    let header = rx_headers.recv().await.unwrap();
+    assert_eq!(header.round(), 1);
+
+    // parents created 10 seconds ahead of this node's clock
+    let parents: Vec<_> = fixture
+        .authorities()
+        .take(3)
+        .map(|a| fixture.certificate(&a.header_builder(&committee).created_at(1_010).build()))
+        .collect();
+    cb.parents().send((parents, 1)).await.unwrap();
+
+    // the proposer waits out the drift and never proposes a header older than its parents
+    let header = rx_headers.recv().await.unwrap();
+    assert_eq!(header.round(), 2);
+    assert_eq!(*header.created_at(), 1_010);
+    assert_eq!(cb.primary_metrics().node_metrics.header_max_parent_wait_ms.get(), 10_000);
+}
+
 #[tokio::test]
 async fn test_equivocation_protection_after_restart() {
     let fixture = CommitteeFixture::builder(MemDatabase::default).build();
@@ -126,3 +180,37 @@ async fn test_equivocation_protection_after_restart() {
         assert_eq!(header, new_header);
     }
 }
+
+#[test]
+fn test_payload_queue_round_robin() {
+    let digest = |worker_id: WorkerId, timestamp: TimestampSec| ProposerDigest {
+        digest: B256::random(),
+        worker_id,
+        timestamp,
+    };
+    let taken = |digests: VecDeque<ProposerDigest>| {
+        digests.into_iter().map(|d| (d.worker_id, d.timestamp)).collect::<Vec<_>>()
+    };
+
+    // a busy worker 0 sends 5 batches before worker 1 and worker 2 send any
+    let mut queue = PayloadQueue::default();
+    for timestamp in 0..5 {
+        queue.push_back(digest(0, timestamp));
+    }
+    queue.push_back(digest(2, 5));
+    queue.push_back(digest(1, 6));
+    queue.push_back(digest(1, 7));
+    assert_eq!(queue.len(), 8);
+
+    // every worker is served before the busy worker's backlog
+    assert_eq!(taken(queue.take(4)), vec![(0, 0), (1, 6), (2, 5), (0, 1)]);
+    // the next header starts after the last worker served
+    assert_eq!(taken(queue.take(2)), vec![(1, 7), (0, 2)]);
+
+    // undelivered digests are proposed again before newer digests from the same worker
+    queue.push_front(VecDeque::from([digest(1, 6), digest(0, 0), digest(1, 7)]));
+    assert_eq!(queue.len(), 5);
+    assert_eq!(taken(queue.take(10)), vec![(1, 6), (0, 0), (1, 7), (0, 3), (0, 4)]);
+    assert_eq!(queue.len(), 0);
+    assert!(queue.take(10).is_empty());
+}
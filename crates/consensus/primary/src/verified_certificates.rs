@@ -0,0 +1,85 @@
+//! Cache of certificates with verified aggregate signatures.
+//!
+//! The same certificate can reach the primary many times while it syncs: gossiped by its author,
+//! as a parent of proposed headers, and in responses to certificate fetch requests. Verifying the
+//! BLS aggregate signature is the most expensive part of processing a certificate, so certificates
+//! verified by one code path are remembered and not verified again by the others.
+
+use lru::LruCache;
+use parking_lot::Mutex;
+use roaring::RoaringBitmap;
+use std::{num::NonZeroUsize, sync::Arc};
+use tn_types::{
+    BlsSignature, Certificate, CertificateDigest, Hash as _, SignatureVerificationState,
+};
+
+/// The default number of verified certificates to remember.
+pub const DEFAULT_VERIFIED_CERTIFICATES_CAPACITY: usize = 4_096;
+
+/// Bounded LRU cache of certificates whose aggregate signature was verified directly.
+///
+/// Entries are keyed by certificate digest and hold the aggregate signature and signers that were
+/// verified. A certificate only matches an entry if it carries the same signature and signers, so
+/// a valid certificate can not be used to skip verification of a forged copy.
+#[derive(Clone, Debug)]
+pub struct VerifiedCertificates {
+    /// The verified signature and signers for each certificate digest.
+    inner: Arc<Mutex<LruCache<CertificateDigest, (BlsSignature, RoaringBitmap)>>>,
+}
+
+impl VerifiedCertificates {
+    /// Create a new cache holding at most `capacity` certificates.
+    ///
+    /// A `capacity` of zero is treated as one.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self { inner: Arc::new(Mutex::new(LruCache::new(capacity))) }
+    }
+
+    /// Remember a certificate if its aggregate signature was verified directly.
+    pub fn insert(&self, certificate: &Certificate) {
+        if let SignatureVerificationState::VerifiedDirectly(signature) =
+            certificate.signature_verification_state()
+        {
+            self.inner
+                .lock()
+                .put(certificate.digest(), (*signature, certificate.signed_authorities().clone()));
+        }
+    }
+
+    /// Mark the certificate verified if the same signature and signers were already verified.
+    ///
+    /// Returns true if the certificate was marked verified.
+    pub fn mark_verified(&self, certificate: &mut Certificate) -> bool {
+        let Some(signature) = certificate.aggregated_signature() else {
+            return false;
+        };
+        let verified = matches!(
+            self.inner.lock().get(&certificate.digest()),
+            Some((verified, signers))
+                if *verified == signature && signers == certificate.signed_authorities()
+        );
+        if verified {
+            certificate.set_signature_verification_state(
+                SignatureVerificationState::VerifiedDirectly(signature),
+            );
+        }
+        verified
+    }
+
+    /// The number of certificates in the cache.
+    pub fn len(&self) -> usize {
+        self.inner.lock().len()
+    }
+
+    /// True if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.inner.lock().is_empty()
+    }
+}
+
+impl Default for VerifiedCertificates {
+    fn default() -> Self {
+        Self::new(DEFAULT_VERIFIED_CERTIFICATES_CAPACITY)
+    }
+}
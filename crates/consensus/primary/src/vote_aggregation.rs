@@ -0,0 +1,107 @@
+//! Track the certifier's progress collecting votes for this primary's header.
+
+use std::time::{Duration, Instant};
+use tn_types::{AuthorityIdentifier, Committee, HeaderDigest, Round, VotingPower};
+
+/// The votes collected so far for the header this primary proposed last.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct VoteAggregation {
+    /// The round of the proposed header.
+    pub round: Round,
+    /// The digest of the proposed header.
+    pub header_digest: HeaderDigest,
+    /// The authorities with a valid vote for the header, including this primary.
+    pub voters: Vec<AuthorityIdentifier>,
+    /// The committee members without a valid vote for the header.
+    pub missing: Vec<AuthorityIdentifier>,
+    /// The voting power of the voters.
+    pub stake: VotingPower,
+    /// The voting power needed to certify the header.
+    pub quorum_threshold: VotingPower,
+    /// When the certifier started collecting votes for the header.
+    pub started: Instant,
+    /// When the votes formed a certificate, `None` until then.
+    pub certified_at: Option<Instant>,
+}
+
+impl VoteAggregation {
+    /// Start collecting votes for a header.
+    pub fn new(round: Round, header_digest: HeaderDigest, committee: &Committee) -> Self {
+        Self {
+            round,
+            header_digest,
+            voters: Vec::new(),
+            missing: committee.authorities().iter().map(|authority| authority.id()).collect(),
+            stake: 0,
+            quorum_threshold: committee.quorum_threshold(),
+            started: Instant::now(),
+            certified_at: None,
+        }
+    }
+
+    /// Replace the voters with the authorities that have valid votes.
+    pub fn set_voters(&mut self, voters: Vec<AuthorityIdentifier>, committee: &Committee) {
+        self.stake = voters.iter().map(|voter| committee.voting_power_by_id(voter)).sum();
+        self.missing = committee
+            .authorities()
+            .iter()
+            .map(|authority| authority.id())
+            .filter(|id| !voters.contains(id))
+            .collect();
+        self.voters = voters;
+    }
+
+    /// Record that the votes formed a certificate.
+    ///
+    /// Later votes for the header don't move the certification time.
+    pub fn mark_certified(&mut self) {
+        self.certified_at.get_or_insert_with(Instant::now);
+    }
+
+    /// True once the votes formed a certificate.
+    pub fn certified(&self) -> bool {
+        self.certified_at.is_some()
+    }
+
+    /// How long the header collected votes, frozen once it is certified.
+    pub fn elapsed(&self) -> Duration {
+        self.certified_at.unwrap_or_else(Instant::now).saturating_duration_since(self.started)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tn_storage::mem_db::MemDatabase;
+    use tn_test_utils::CommitteeFixture;
+
+    #[test]
+    fn test_vote_aggregation() {
+        let fixture = CommitteeFixture::builder(MemDatabase::default).build();
+        let committee = fixture.committee();
+        let ids: Vec<_> = fixture.authorities().map(|a| a.id()).collect();
+
+        let mut aggregation = VoteAggregation::new(5, HeaderDigest::default(), &committee);
+        assert_eq!(aggregation.missing.len(), 4);
+        assert_eq!(aggregation.quorum_threshold, committee.quorum_threshold());
+
+        aggregation.set_voters(ids[..2].to_vec(), &committee);
+        assert_eq!(aggregation.voters, ids[..2].to_vec());
+        assert_eq!(aggregation.missing.len(), 2);
+        assert!(aggregation.missing.iter().all(|id| ids[2..].contains(id)));
+        assert_eq!(
+            aggregation.stake,
+            committee.voting_power_by_id(&ids[0]) + committee.voting_power_by_id(&ids[1])
+        );
+        assert!(aggregation.stake < aggregation.quorum_threshold);
+        assert!(!aggregation.certified());
+
+        // the elapsed time stops at certification
+        aggregation.mark_certified();
+        assert!(aggregation.certified());
+        let elapsed = aggregation.elapsed();
+        std::thread::sleep(Duration::from_millis(5));
+        aggregation.mark_certified();
+        assert_eq!(aggregation.elapsed(), elapsed);
+    }
+}
@@ -0,0 +1,87 @@
+//! Track reports from the primary's own workers.
+
+use std::{collections::BTreeMap, time::Duration};
+use tn_types::{TimestampSec, WorkerId};
+
+/// The number of heartbeats a worker can miss before it is considered down.
+pub const MISSED_HEARTBEATS: u64 = 3;
+
+/// The last report from a worker.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct WorkerReport {
+    /// When the worker last sealed a batch or sent a heartbeat.
+    pub last_seen: TimestampSec,
+    /// True if the last report was a heartbeat, so the worker had no transactions to seal.
+    pub idle: bool,
+}
+
+/// The last report from each of the primary's workers.
+///
+/// Workers report every batch they seal and send a heartbeat while idle. A worker that stops
+/// reporting is down, not idle.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct WorkerActivity {
+    /// The last report by worker.
+    reports: BTreeMap<WorkerId, WorkerReport>,
+}
+
+impl WorkerActivity {
+    /// Record that `worker_id` sealed a batch at `timestamp`.
+    pub fn batch_sealed(&mut self, worker_id: WorkerId, timestamp: TimestampSec) {
+        self.record(worker_id, timestamp, false);
+    }
+
+    /// Record a heartbeat from an idle `worker_id` at `timestamp`.
+    pub fn heartbeat(&mut self, worker_id: WorkerId, timestamp: TimestampSec) {
+        self.record(worker_id, timestamp, true);
+    }
+
+    /// Keep the latest report from `worker_id`.
+    fn record(&mut self, worker_id: WorkerId, timestamp: TimestampSec, idle: bool) {
+        let report = self.reports.entry(worker_id).or_insert(WorkerReport { last_seen: 0, idle });
+        if timestamp >= report.last_seen {
+            *report = WorkerReport { last_seen: timestamp, idle };
+        }
+    }
+
+    /// The last report from `worker_id`, if it reported since the primary started.
+    pub fn report(&self, worker_id: WorkerId) -> Option<WorkerReport> {
+        self.reports.get(&worker_id).copied()
+    }
+
+    /// True if `worker_id` missed [MISSED_HEARTBEATS] heartbeats sent every `heartbeat_interval`
+    /// by `now`.
+    pub fn is_down(
+        &self,
+        worker_id: WorkerId,
+        now: TimestampSec,
+        heartbeat_interval: Duration,
+    ) -> bool {
+        let timeout = MISSED_HEARTBEATS * heartbeat_interval.as_secs().max(1);
+        self.report(worker_id).map_or(true, |report| now.saturating_sub(report.last_seen) > timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_worker_activity() {
+        let interval = Duration::from_secs(5);
+        let mut activity = WorkerActivity::default();
+        assert!(activity.is_down(0, 100, interval));
+
+        activity.batch_sealed(0, 100);
+        assert_eq!(activity.report(0), Some(WorkerReport { last_seen: 100, idle: false }));
+        activity.heartbeat(0, 105);
+        assert_eq!(activity.report(0), Some(WorkerReport { last_seen: 105, idle: true }));
+        assert!(!activity.is_down(0, 120, interval));
+        assert!(activity.is_down(0, 121, interval));
+
+        // late reports do not replace newer ones
+        activity.batch_sealed(0, 101);
+        assert_eq!(activity.report(0), Some(WorkerReport { last_seen: 105, idle: true }));
+        assert_eq!(activity.report(1), None);
+    }
+}
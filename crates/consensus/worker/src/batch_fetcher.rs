@@ -5,12 +5,12 @@ use async_trait::async_trait;
 use std::{
     collections::{HashMap, HashSet},
     sync::Arc,
-    time::Duration,
 };
 use thiserror::Error;
+use tn_config::BatchFetchParameters;
 use tn_network_libp2p::error::NetworkError;
-use tn_storage::tables::Batches;
-use tn_types::{now, Batch, BlockHash, Database, DbTxMut};
+use tn_storage::{insert_batch, tables::Batches};
+use tn_types::{Batch, BlockHash, Database, DbTxMut, SharedClock};
 use tokio::time::error::Elapsed;
 use tracing::debug;
 
@@ -18,11 +18,21 @@ pub struct BatchFetcher<DB> {
     network: Arc<dyn RequestBatchesNetwork>,
     batch_store: DB,
     metrics: Arc<WorkerMetrics>,
+    /// The time source for the received time of fetched batches.
+    clock: SharedClock,
+    /// Timeouts and retries of batch requests to peers.
+    params: BatchFetchParameters,
 }
 
 impl<DB: Database> BatchFetcher<DB> {
-    pub fn new(network: WorkerNetworkHandle, batch_store: DB, metrics: Arc<WorkerMetrics>) -> Self {
-        Self { network: Arc::new(network), batch_store, metrics }
+    pub fn new(
+        network: WorkerNetworkHandle,
+        batch_store: DB,
+        metrics: Arc<WorkerMetrics>,
+        clock: SharedClock,
+        params: BatchFetchParameters,
+    ) -> Self {
+        Self { network: Arc::new(network), batch_store, metrics, clock, params }
     }
 
     /// Bulk fetches payload from local storage and remote workers.
@@ -32,11 +42,16 @@ impl<DB: Database> BatchFetcher<DB> {
 
         let mut remaining_digests = digests;
         let mut fetched_batches = HashMap::new();
+        let mut retry: u32 = 0;
 
         loop {
             if remaining_digests.is_empty() {
                 return fetched_batches;
             }
+            if retry > 0 {
+                tokio::time::sleep(self.params.backoff.delay(retry)).await;
+            }
+            retry = retry.saturating_add(1);
 
             // Fetch from local storage.
             let _timer = self.metrics.worker_local_fetch_latency.start_timer();
@@ -49,9 +64,7 @@ impl<DB: Database> BatchFetcher<DB> {
 
             // Fetch from peers.
             let _timer = self.metrics.worker_remote_fetch_latency.start_timer();
-            if let Ok(new_batches) =
-                self.safe_request_batches(&remaining_digests, Duration::from_secs(10)).await
-            {
+            if let Ok(new_batches) = self.safe_request_batches(&remaining_digests).await {
                 // Set received_at timestamp for remote batches.
                 let mut updated_new_batches = HashMap::new();
                 let mut txn =
@@ -60,10 +73,10 @@ impl<DB: Database> BatchFetcher<DB> {
                     new_batches.iter().filter(|(d, _)| remaining_digests.remove(*d))
                 {
                     let mut batch = (*batch).clone();
-                    batch.set_received_at(now());
+                    batch.set_received_at(self.clock.now());
                     updated_new_batches.insert(*digest, batch.clone());
                     // Also persist the batches, so they are available after restarts.
-                    if let Err(e) = txn.insert::<Batches>(digest, &batch) {
+                    if let Err(e) = insert_batch(&mut txn, digest, &batch) {
                         tracing::error!(target: "batch_fetcher", "failed to insert batch! We can not continue.. {e}");
                         panic!("failed to insert batch! We can not continue.. {e}");
                     }
@@ -108,7 +121,6 @@ impl<DB: Database> BatchFetcher<DB> {
     async fn safe_request_batches(
         &self,
         digests_to_fetch: &HashSet<BlockHash>,
-        timeout: Duration,
     ) -> Result<HashMap<BlockHash, Batch>, RequestBatchesNetworkError> {
         let mut fetched_batches = HashMap::new();
         if digests_to_fetch.is_empty() {
@@ -117,7 +129,7 @@ impl<DB: Database> BatchFetcher<DB> {
 
         let batches = self
             .network
-            .request_batches_from_all(digests_to_fetch.clone().into_iter().collect(), timeout)
+            .request_batches_from_all(digests_to_fetch.clone().into_iter().collect(), &self.params)
             .await?;
         for batch in batches {
             let batch_digest = batch.digest();
@@ -144,7 +156,7 @@ trait RequestBatchesNetwork: Send + Sync {
     async fn request_batches_from_all(
         &self,
         batch_digests: Vec<BlockHash>,
-        timeout: Duration,
+        params: &BatchFetchParameters,
     ) -> Result<Vec<Batch>, RequestBatchesNetworkError>;
 }
 
@@ -153,9 +165,10 @@ impl RequestBatchesNetwork for WorkerNetworkHandle {
     async fn request_batches_from_all(
         &self,
         batch_digests: Vec<BlockHash>,
-        timeout: Duration,
+        params: &BatchFetchParameters,
     ) -> Result<Vec<Batch>, RequestBatchesNetworkError> {
-        let res = tokio::time::timeout(timeout, self.request_batches(batch_digests)).await??;
+        let res = tokio::time::timeout(params.timeout, self.request_batches(batch_digests, params))
+            .await??;
         Ok(res)
     }
 }
@@ -174,7 +187,7 @@ mod tests {
     };
     use tn_storage::open_db;
     use tn_test_utils::transaction;
-    use tn_types::NetworkKeypair;
+    use tn_types::{NetworkKeypair, SystemClock};
     use tokio::sync::{mpsc, Mutex};
 
     #[tokio::test]
@@ -191,6 +204,8 @@ mod tests {
             network: Arc::new(network.handle()),
             batch_store: batch_store.clone(),
             metrics: Arc::new(WorkerMetrics::default()),
+            clock: SystemClock::shared(),
+            params: BatchFetchParameters::default(),
         };
         let mut expected_batches = HashMap::from_iter(vec![
             (batch1.digest(), batch1.clone()),
@@ -238,6 +253,8 @@ mod tests {
             network: Arc::new(network.handle()),
             batch_store,
             metrics: Arc::new(WorkerMetrics::default()),
+            clock: SystemClock::shared(),
+            params: BatchFetchParameters::default(),
         };
         let expected_batches = HashMap::from_iter(vec![
             (batch1.digest(), batch1.clone()),
@@ -266,6 +283,8 @@ mod tests {
             network: Arc::new(network.handle()),
             batch_store,
             metrics: Arc::new(WorkerMetrics::default()),
+            clock: SystemClock::shared(),
+            params: BatchFetchParameters::default(),
         };
         let mut expected_batches = HashMap::from_iter(vec![
             (batch1.digest(), batch1.clone()),
@@ -304,6 +323,8 @@ mod tests {
             network: Arc::new(network.handle()),
             batch_store,
             metrics: Arc::new(WorkerMetrics::default()),
+            clock: SystemClock::shared(),
+            params: BatchFetchParameters::default(),
         };
         let mut expected_batches = HashMap::from_iter(vec![
             (batch1.digest(), batch1.clone()),
@@ -360,6 +381,8 @@ mod tests {
             network: Arc::new(network.handle()),
             batch_store,
             metrics: Arc::new(WorkerMetrics::default()),
+            clock: SystemClock::shared(),
+            params: BatchFetchParameters::default(),
         };
         let mut fetched_batches = fetcher.fetch(digests).await;
 
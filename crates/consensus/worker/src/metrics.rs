@@ -1,12 +1,15 @@
 //! Worker metrics
+//!
+//! Every metric has a `worker_id` label so workers on the same node report separately.
 
 use prometheus::{
-    default_registry, register_histogram_vec_with_registry, register_histogram_with_registry,
-    register_int_counter_vec_with_registry, register_int_counter_with_registry,
-    register_int_gauge_with_registry, Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge,
-    Registry,
+    default_registry, histogram_opts, register_histogram_vec_with_registry,
+    register_histogram_with_registry, register_int_counter_vec_with_registry,
+    register_int_counter_with_registry, register_int_gauge_with_registry, Histogram, HistogramVec,
+    IntCounter, IntCounterVec, IntGauge, Opts, Registry,
 };
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
+use tn_types::WorkerId;
 
 const LATENCY_SEC_BUCKETS: &[f64] = &[
     0.001, 0.005, 0.01, 0.05, 0.1, 0.15, 0.2, 0.3, 0.4, 0.5, 0.6, 0.7, 0.8, 0.9, 1.0, 1.2, 1.4,
@@ -14,6 +17,11 @@ const LATENCY_SEC_BUCKETS: &[f64] = &[
     12.5, 15., 17.5, 20., 25., 30., 60., 90., 120., 180., 300.,
 ];
 
+/// The const labels for a worker's metrics.
+fn worker_labels(worker_id: WorkerId) -> HashMap<String, String> {
+    HashMap::from([("worker_id".to_string(), worker_id.to_string())])
+}
+
 #[derive(Clone)]
 pub struct Metrics {
     pub worker_metrics: Arc<WorkerMetrics>,
@@ -21,28 +29,27 @@ pub struct Metrics {
 }
 
 impl Metrics {
-    fn try_new(registry: &Registry) -> Result<Self, prometheus::Error> {
+    fn try_new(registry: &Registry, worker_id: WorkerId) -> Result<Self, prometheus::Error> {
         // Essential/core metrics across the worker node
-        let worker_metrics = Arc::new(WorkerMetrics::try_new(registry)?);
+        let worker_metrics = Arc::new(WorkerMetrics::try_new(registry, worker_id)?);
 
         // Channel metrics
-        let channel_metrics = Arc::new(WorkerChannelMetrics::try_new(registry)?);
+        let channel_metrics = Arc::new(WorkerChannelMetrics::try_new(registry, worker_id)?);
 
         Ok(Metrics { worker_metrics, channel_metrics })
     }
 
-    pub fn new_with_registry(registry: &Registry) -> Self {
-        Self::try_new(registry).expect("Prometheus error, are you using it wrong?")
+    pub fn new_with_registry(registry: &Registry, worker_id: WorkerId) -> Self {
+        Self::try_new(registry, worker_id).expect("Prometheus error, are you using it wrong?")
     }
-}
 
-impl Default for Metrics {
-    fn default() -> Self {
+    /// Create the metrics for a worker in the default registry.
+    pub fn new_for_worker(worker_id: WorkerId) -> Self {
         // try_new() should not fail except under certain conditions with testing (see comment
         // below). This pushes the panic or retry decision lower and supporting try_new
         // allways a user to deal with errors if desired (have a non-panic option).
         // We always want do use default_registry() when not in test.
-        match Self::try_new(default_registry()) {
+        match Self::try_new(default_registry(), worker_id) {
             Ok(metrics) => metrics,
             Err(_) => {
                 // If we are in a test then don't panic on prometheus errors (usually an already
@@ -50,12 +57,19 @@ impl Default for Metrics {
                 // great for prod code, however should not happen, but will happen in tests due to
                 // how Rust runs them so lets just gloss over it. cfg(test) does not
                 // always work as expected.
-                Self::try_new(&Registry::new()).expect("Prometheus error, are you using it wrong?")
+                Self::try_new(&Registry::new(), worker_id)
+                    .expect("Prometheus error, are you using it wrong?")
             }
         }
     }
 }
 
+impl Default for Metrics {
+    fn default() -> Self {
+        Self::new_for_worker(0)
+    }
+}
+
 #[derive(Clone)]
 pub struct WorkerMetrics {
     /// Number of created batches from the batch_maker
@@ -64,6 +78,14 @@ pub struct WorkerMetrics {
     pub created_batch_latency: HistogramVec,
     /// Latency of broadcasting batches to a quorum in seconds.
     pub batch_broadcast_quorum_latency: Histogram,
+    /// Number of batches that reached quorum and were stored.
+    pub batches_sealed: IntCounter,
+    /// Responses from peers for reported batches by peer and outcome.
+    pub batch_peer_acks: IntCounterVec,
+    /// Latency of each peer's response to a reported batch in seconds.
+    pub batch_peer_ack_latency: HistogramVec,
+    /// Round trip time of each peer's response to a latency probe in seconds.
+    pub peer_latency: HistogramVec,
     /// Counter of remote/local batch fetch statuses.
     pub batch_fetch: IntCounterVec,
     /// Time it takes to download a payload from local worker peer
@@ -75,64 +97,119 @@ pub struct WorkerMetrics {
 }
 
 impl WorkerMetrics {
-    fn try_new(registry: &Registry) -> Result<Self, prometheus::Error> {
+    fn try_new(registry: &Registry, worker_id: WorkerId) -> Result<Self, prometheus::Error> {
+        let labels = worker_labels(worker_id);
         Ok(Self {
             created_batch_size: register_histogram_vec_with_registry!(
-                "created_batch_size",
-                "Size in bytes of the created batches",
+                histogram_opts!(
+                    "created_batch_size",
+                    "Size in bytes of the created batches",
+                    // buckets with size in bytes
+                    vec![
+                        100.0,
+                        500.0,
+                        1_000.0,
+                        5_000.0,
+                        10_000.0,
+                        20_000.0,
+                        50_000.0,
+                        100_000.0,
+                        250_000.0,
+                        500_000.0,
+                        1_000_000.0
+                    ]
+                )
+                .const_labels(labels.clone()),
                 &["reason"],
-                // buckets with size in bytes
-                vec![
-                    100.0,
-                    500.0,
-                    1_000.0,
-                    5_000.0,
-                    10_000.0,
-                    20_000.0,
-                    50_000.0,
-                    100_000.0,
-                    250_000.0,
-                    500_000.0,
-                    1_000_000.0
-                ],
                 registry
             )?,
             created_batch_latency: register_histogram_vec_with_registry!(
-                "created_batch_latency",
-                "The latency of creating (sealing) a batch",
+                histogram_opts!(
+                    "created_batch_latency",
+                    "The latency of creating (sealing) a batch",
+                    // buckets in seconds
+                    LATENCY_SEC_BUCKETS.to_vec()
+                )
+                .const_labels(labels.clone()),
                 &["reason"],
-                // buckets in seconds
-                LATENCY_SEC_BUCKETS.to_vec(),
                 registry
             )?,
             batch_broadcast_quorum_latency: register_histogram_with_registry!(
-                "batch_broadcast_quorum_latency",
-                "The latency of broadcasting batches to a quorum in seconds",
-                // buckets in seconds
-                LATENCY_SEC_BUCKETS.to_vec(),
+                histogram_opts!(
+                    "batch_broadcast_quorum_latency",
+                    "The latency of broadcasting batches to a quorum in seconds",
+                    // buckets in seconds
+                    LATENCY_SEC_BUCKETS.to_vec()
+                )
+                .const_labels(labels.clone()),
+                registry
+            )?,
+            batches_sealed: register_int_counter_with_registry!(
+                Opts::new(
+                    "batches_sealed",
+                    "Number of batches that reached quorum and were stored"
+                )
+                .const_labels(labels.clone()),
+                registry
+            )?,
+            batch_peer_acks: register_int_counter_vec_with_registry!(
+                Opts::new("batch_peer_acks", "Responses from peers for reported batches")
+                    .const_labels(labels.clone()),
+                &["peer", "outcome"],
+                registry
+            )?,
+            batch_peer_ack_latency: register_histogram_vec_with_registry!(
+                histogram_opts!(
+                    "batch_peer_ack_latency",
+                    "The latency of each peer's response to a reported batch in seconds",
+                    // buckets in seconds
+                    LATENCY_SEC_BUCKETS.to_vec()
+                )
+                .const_labels(labels.clone()),
+                &["peer"],
+                registry
+            )?,
+            peer_latency: register_histogram_vec_with_registry!(
+                histogram_opts!(
+                    "worker_peer_latency",
+                    "The round trip time of each peer's response to a latency probe in seconds",
+                    // buckets in seconds
+                    LATENCY_SEC_BUCKETS.to_vec()
+                )
+                .const_labels(labels.clone()),
+                &["peer"],
                 registry
             )?,
             batch_fetch: register_int_counter_vec_with_registry!(
-                "batch_fetch",
-                "Counter of remote/local batch fetch statuses",
+                Opts::new("batch_fetch", "Counter of remote/local batch fetch statuses")
+                    .const_labels(labels.clone()),
                 &["source", "status"],
                 registry
             )?,
             worker_local_fetch_latency: register_histogram_with_registry!(
-                "worker_local_fetch_latency",
-                "Time it takes to download a payload from local storage",
-                LATENCY_SEC_BUCKETS.to_vec(),
+                histogram_opts!(
+                    "worker_local_fetch_latency",
+                    "Time it takes to download a payload from local storage",
+                    LATENCY_SEC_BUCKETS.to_vec()
+                )
+                .const_labels(labels.clone()),
                 registry
             )?,
             worker_remote_fetch_latency: register_histogram_with_registry!(
-                "worker_remote_fetch_latency",
-                "Time it takes to download a payload from remote worker peer",
-                LATENCY_SEC_BUCKETS.to_vec(),
+                histogram_opts!(
+                    "worker_remote_fetch_latency",
+                    "Time it takes to download a payload from remote worker peer",
+                    LATENCY_SEC_BUCKETS.to_vec()
+                )
+                .const_labels(labels.clone()),
                 registry
             )?,
             pending_remote_request_batches: register_int_gauge_with_registry!(
-                "pending_remote_request_batches",
-                "The number of pending remote calls to request_batches",
+                Opts::new(
+                    "pending_remote_request_batches",
+                    "The number of pending remote calls to request_batches"
+                )
+                .const_labels(labels),
                 registry
             )?,
         })
@@ -145,7 +222,7 @@ impl Default for WorkerMetrics {
         // below). This pushes the panic or retry decision lower and supporting try_new
         // allways a user to deal with errors if desired (have a non-panic option).
         // We always want do use default_registry() when not in test.
-        match Self::try_new(default_registry()) {
+        match Self::try_new(default_registry(), 0) {
             Ok(metrics) => metrics,
             Err(e) => {
                 tracing::warn!(target: "tn::metrics", ?e, "Executor::try_new metrics error");
@@ -154,7 +231,8 @@ impl Default for WorkerMetrics {
                 // great for prod code, however should not happen, but will happen in tests due to
                 // how Rust runs them so lets just gloss over it. cfg(test) does not
                 // always work as expected.
-                Self::try_new(&Registry::new()).expect("Prometheus error, are you using it wrong?")
+                Self::try_new(&Registry::new(), 0)
+                    .expect("Prometheus error, are you using it wrong?")
             }
         }
     }
@@ -176,30 +254,82 @@ pub struct WorkerChannelMetrics {
 }
 
 impl WorkerChannelMetrics {
-    fn try_new(registry: &Registry) -> Result<Self, prometheus::Error> {
+    fn try_new(registry: &Registry, worker_id: WorkerId) -> Result<Self, prometheus::Error> {
+        let labels = worker_labels(worker_id);
         Ok(Self {
             tx_batch_maker: register_int_gauge_with_registry!(
-                "tx_batch_maker",
-                "occupancy of the channel from the `worker::TxReceiverhandler` to the `worker::BatchProvider`",
+                Opts::new(
+                    "tx_batch_maker",
+                    "occupancy of the channel from the `worker::TxReceiverhandler` to the `worker::BatchProvider`",
+                )
+                .const_labels(labels.clone()),
                 registry
             )?,
             tx_quorum_waiter: register_int_gauge_with_registry!(
-                "tx_quorum_waiter",
-                "occupancy of the channel from the `worker::BatchProvider` to the `worker::QuorumWaiter`",
+                Opts::new(
+                    "tx_quorum_waiter",
+                    "occupancy of the channel from the `worker::BatchProvider` to the `worker::QuorumWaiter`",
+                )
+                .const_labels(labels.clone()),
                 registry
             )?,
 
             // Totals:
             tx_batch_maker_total: register_int_counter_with_registry!(
-                "tx_batch_maker_total",
-                "total received from the channel from the `worker::TxReceiverhandler` to the `worker::BatchProvider`",
+                Opts::new(
+                    "tx_batch_maker_total",
+                    "total received from the channel from the `worker::TxReceiverhandler` to the `worker::BatchProvider`",
+                )
+                .const_labels(labels.clone()),
                 registry
             )?,
             tx_quorum_waiter_total: register_int_counter_with_registry!(
-                "tx_quorum_waiter_total",
-                "total received from the channel from the `worker::BatchProvider` to the `worker::QuorumWaiter`",
+                Opts::new(
+                    "tx_quorum_waiter_total",
+                    "total received from the channel from the `worker::BatchProvider` to the `worker::QuorumWaiter`",
+                )
+                .const_labels(labels),
                 registry
             )?,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metrics_labeled_by_worker() {
+        let registry = Registry::new();
+        let worker_0 = Metrics::new_with_registry(&registry, 0);
+        let worker_1 = Metrics::new_with_registry(&registry, 1);
+        worker_0.worker_metrics.batches_sealed.inc();
+        worker_1.worker_metrics.batches_sealed.inc_by(2);
+
+        let families = registry.gather();
+        let sealed = families
+            .iter()
+            .find(|family| family.get_name() == "batches_sealed")
+            .expect("batches sealed registered");
+        let by_worker: std::collections::BTreeMap<_, _> = sealed
+            .get_metric()
+            .iter()
+            .map(|metric| {
+                let label = metric
+                    .get_label()
+                    .iter()
+                    .find(|label| label.get_name() == "worker_id")
+                    .expect("worker id label");
+                (label.get_value().to_string(), metric.get_counter().get_value())
+            })
+            .collect();
+        assert_eq!(
+            by_worker,
+            [("0".to_string(), 1.0), ("1".to_string(), 2.0)].into_iter().collect()
+        );
+
+        // the same worker can not register twice
+        assert!(Metrics::try_new(&registry, 1).is_err());
+    }
+}
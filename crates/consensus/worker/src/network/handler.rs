@@ -1,17 +1,17 @@
 use itertools::Itertools;
 use std::sync::Arc;
 use tn_config::ConsensusConfig;
-use tn_network_libp2p::GossipMessage;
+use tn_network_libp2p::{GossipMessage, PeerId};
 use tn_network_types::{WorkerOthersBatchMessage, WorkerToPrimaryClient};
-use tn_storage::tables::Batches;
+use tn_storage::{tables::Batches, BatchStore as _};
 use tn_types::{
-    now, try_decode, Batch, BatchValidation, BlockHash, Database, SealedBatch, WorkerId,
+    try_decode, Batch, BatchValidation, BlockHash, Database, SealedBatch, TxDedupFilter, WorkerId,
 };
 
 use super::{
     error::{WorkerNetworkError, WorkerNetworkResult},
     message::WorkerGossip,
-    WorkerNetworkHandle,
+    WorkerNetworkHandle, MAX_RECENT_BATCHES,
 };
 
 /// The type that handles requests from peers.
@@ -25,6 +25,10 @@ pub struct RequestHandler<DB> {
     consensus_config: ConsensusConfig<DB>,
     /// Network handle- so we can respond to gossip.
     network_handle: WorkerNetworkHandle,
+    /// Transactions in batches accepted from peers.
+    ///
+    /// The batch builder skips these so the same transaction isn't proposed twice.
+    dedup_filter: TxDedupFilter,
 }
 
 impl<DB> RequestHandler<DB>
@@ -37,8 +41,9 @@ where
         validator: Arc<dyn BatchValidation>,
         consensus_config: ConsensusConfig<DB>,
         network_handle: WorkerNetworkHandle,
+        dedup_filter: TxDedupFilter,
     ) -> Self {
-        Self { id, validator, consensus_config, network_handle }
+        Self { id, validator, consensus_config, network_handle, dedup_filter }
     }
 
     /// Process gossip from the committee.
@@ -60,10 +65,15 @@ where
                     // If we don't have this batch already then try to get it.
                     // If we are CVV then we should already have it.
                     // This allows non-CVVs to pre fetch batches they will soon need.
-                    match self.network_handle.request_batches(vec![batch_hash]).await {
+                    let params = &self.consensus_config.parameters().requests.batch_fetch;
+                    match self.network_handle.request_batches(vec![batch_hash], params).await {
                         Ok(batches) => {
-                            if let Some(batch) = batches.first() {
-                                store.insert::<Batches>(&batch.digest(), batch).map_err(|e| {
+                            if let Some(batch) = batches.into_iter().next() {
+                                // the batch comes from any peer, validate before storing
+                                let sealed_batch = batch.seal_slow();
+                                self.validator.validate_batch(sealed_batch.clone())?;
+                                let (batch, digest) = sealed_batch.split();
+                                store.write_batch(&digest, &batch).map_err(|e| {
                                     WorkerNetworkError::Internal(format!(
                                         "failed to write to batch store: {e}"
                                     ))
@@ -92,10 +102,11 @@ where
         self.validator.validate_batch(sealed_batch.clone())?;
 
         let (mut batch, digest) = sealed_batch.split();
+        self.dedup_filter.insert_batch(&batch);
 
         // Set received_at timestamp for remote batch.
-        batch.set_received_at(now());
-        store.insert::<Batches>(&digest, &batch).map_err(|e| {
+        batch.set_received_at(self.consensus_config.clock().now());
+        store.write_batch(&digest, &batch).map_err(|e| {
             WorkerNetworkError::Internal(format!("failed to write to batch store: {e}"))
         })?;
 
@@ -108,13 +119,49 @@ where
         Ok(())
     }
 
+    /// Fetch the announced batches that are missing from the store.
+    ///
+    /// Peers announce recently published batches when this worker connects. Fetched batches are
+    /// validated and reported to the primary like batches reported by their author.
+    pub(crate) async fn process_announce_batches(
+        &self,
+        peer: PeerId,
+        mut batch_digests: Vec<BlockHash>,
+    ) -> WorkerNetworkResult<()> {
+        batch_digests.truncate(MAX_RECENT_BATCHES);
+        let store = self.consensus_config.node_storage();
+        let missing: Vec<BlockHash> = batch_digests
+            .into_iter()
+            .filter(|digest| !matches!(store.get::<Batches>(digest), Ok(Some(_))))
+            .collect();
+        if missing.is_empty() {
+            return Ok(());
+        }
+
+        let timeout = self.consensus_config.parameters().sync_retry_delay;
+        let batches = self.network_handle.request_batches_from_peer(peer, missing, timeout).await?;
+        for batch in batches {
+            self.process_report_batch(batch.seal_slow()).await?;
+        }
+
+        Ok(())
+    }
+
     /// Attempt to return requested batches.
+    ///
+    /// Requests for more than [MAX_RECENT_BATCHES] digests are rejected.
     pub(crate) async fn process_request_batches(
         &self,
         batch_digests: Vec<BlockHash>,
     ) -> WorkerNetworkResult<Vec<Batch>> {
         const MAX_REQUEST_BATCHES_RESPONSE_SIZE: usize = 6_000_000;
         const BATCH_DIGESTS_READ_CHUNK_SIZE: usize = 200;
+        if batch_digests.len() > MAX_RECENT_BATCHES {
+            return Err(WorkerNetworkError::Internal(format!(
+                "requested {} batches, the limit is {MAX_RECENT_BATCHES}",
+                batch_digests.len()
+            )));
+        }
         let store = self.consensus_config.node_storage().clone();
 
         let digests_chunks = batch_digests
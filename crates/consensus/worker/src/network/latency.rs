@@ -0,0 +1,82 @@
+//! Round trip times to peers sampled by latency probes.
+
+use std::{
+    collections::HashMap,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tn_network_libp2p::PeerId;
+
+/// The weight of a new sample in a peer's smoothed round trip time, as a fraction `1/N`.
+///
+/// Matches the smoothing TCP uses for its round trip time estimate.
+const SAMPLE_WEIGHT: u32 = 8;
+
+/// The smoothed round trip time to each peer.
+///
+/// Clones share the same samples.
+#[derive(Clone, Debug, Default)]
+pub struct PeerLatencies {
+    /// The smoothed round trip time by peer.
+    rtts: Arc<Mutex<HashMap<PeerId, Duration>>>,
+}
+
+impl PeerLatencies {
+    /// Record a round trip time sampled for the peer.
+    pub fn record(&self, peer: PeerId, sample: Duration) {
+        let mut rtts = self.rtts.lock().expect("peer latencies lock poisoned");
+        rtts.entry(peer)
+            .and_modify(|rtt| *rtt = (*rtt * (SAMPLE_WEIGHT - 1) + sample) / SAMPLE_WEIGHT)
+            .or_insert(sample);
+    }
+
+    /// Use a round trip time from a previous run until the peer is sampled.
+    pub fn seed(&self, peer: PeerId, rtt: Duration) {
+        self.rtts.lock().expect("peer latencies lock poisoned").entry(peer).or_insert(rtt);
+    }
+
+    /// Return the smoothed round trip time to the peer, `None` if it was never sampled.
+    pub fn rtt(&self, peer: &PeerId) -> Option<Duration> {
+        self.rtts.lock().expect("peer latencies lock poisoned").get(peer).copied()
+    }
+
+    /// Sort peers from the lowest round trip time to the highest.
+    ///
+    /// Peers that were never sampled are last and keep their order.
+    pub fn sort(&self, peers: &mut [PeerId]) {
+        let rtts = self.rtts.lock().expect("peer latencies lock poisoned");
+        peers.sort_by_key(|peer| rtts.get(peer).copied().unwrap_or(Duration::MAX));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peer_latencies() {
+        let latencies = PeerLatencies::default();
+        let fast = PeerId::random();
+        let slow = PeerId::random();
+        let unknown = PeerId::random();
+        latencies.record(slow, Duration::from_millis(80));
+        latencies.record(fast, Duration::from_millis(160));
+        assert_eq!(latencies.rtt(&fast), Some(Duration::from_millis(160)));
+        assert_eq!(latencies.rtt(&unknown), None);
+
+        // seeds never replace samples
+        latencies.seed(fast, Duration::from_millis(1));
+        assert_eq!(latencies.rtt(&fast), Some(Duration::from_millis(160)));
+
+        // samples are smoothed
+        latencies.record(fast, Duration::from_millis(0));
+        assert_eq!(latencies.rtt(&fast), Some(Duration::from_millis(140)));
+        for _ in 0..10 {
+            latencies.record(fast, Duration::from_millis(10));
+        }
+
+        let mut peers = vec![unknown, slow, fast];
+        latencies.sort(&mut peers);
+        assert_eq!(peers, vec![fast, slow, unknown]);
+    }
+}
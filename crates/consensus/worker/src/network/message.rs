@@ -20,6 +20,12 @@ pub enum WorkerRequest {
     ReportBatch { sealed_batch: SealedBatch },
     /// Request batches by digest from a peer.
     RequestBatches { batch_digests: Vec<BlockHash> },
+    /// Announce recently sealed batches to a peer that just connected.
+    ///
+    /// The peer requests any batches it is missing.
+    AnnounceBatches { batch_digests: Vec<BlockHash> },
+    /// Probe the round trip time to a peer.
+    Ping,
 }
 
 //
@@ -33,6 +39,8 @@ pub enum WorkerRequest {
 pub enum WorkerResponse {
     ReportBatch,
     RequestBatches(Vec<Batch>),
+    AnnounceBatches,
+    Pong,
     /// RPC error while handling request.
     ///
     /// This is an application-layer error response.
@@ -1,54 +1,96 @@
-use std::{collections::HashSet, sync::Arc, time::Duration};
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Arc,
+    time::Duration,
+};
 
 use error::WorkerNetworkError;
 use futures::{stream::FuturesUnordered, StreamExt};
 use handler::RequestHandler;
 use message::{WorkerGossip, WorkerRPCError};
 pub use message::{WorkerRequest, WorkerResponse};
-use tn_config::ConsensusConfig;
+use parking_lot::Mutex;
+use tn_config::{BatchFetchParameters, ConsensusConfig};
 use tn_network_libp2p::{
     error::NetworkError,
-    types::{IdentTopic, NetworkEvent, NetworkHandle, NetworkResult},
-    GossipMessage, Multiaddr, PeerId, ResponseChannel,
+    types::{CorrelationId, IdentTopic, NetworkEvent, NetworkResult},
+    GossipMessage, MemoryNetwork, Multiaddr, NetworkClient, PeerId, ResponseChannel,
+    WORKER_PING_FEATURE,
 };
 use tn_network_types::{FetchBatchResponse, PrimaryToWorkerClient, WorkerSynchronizeMessage};
-use tn_storage::tables::Batches;
+use tn_storage::{insert_batch, tables::Batches};
 use tn_types::{
-    encode, now, Batch, BatchValidation, BlockHash, Database, DbTxMut, Noticer, SealedBatch,
-    TaskManager, WorkerId,
+    encode, Batch, BatchValidation, BlockHash, Database, DbTxMut, Noticer, SealedBatch,
+    SharedClock, SystemClock, TaskManager, TimestampSec, TxDedupFilter, WorkerId,
 };
 use tokio::{
     sync::{mpsc, oneshot},
     task::JoinHandle,
+    time::Instant,
 };
-use tracing::{debug, error, trace, warn};
+use tracing::{debug, error, info_span, trace, warn, Instrument as _};
 
 use crate::batch_fetcher::BatchFetcher;
+use latency::PeerLatencies;
 
 mod error;
 mod handler;
+mod latency;
 pub(crate) mod message;
 
+#[cfg(test)]
+#[path = "../tests/latency_tests.rs"]
+mod latency_tests;
+#[cfg(test)]
+#[path = "../tests/recent_batches_tests.rs"]
+mod recent_batches_tests;
+
 /// Convenience type for Primary network.
 pub(crate) type Req = WorkerRequest;
 /// Convenience type for Primary network.
 pub(crate) type Res = WorkerResponse;
 
+/// The maximum number of recently published batches announced to a peer that connects.
+const MAX_RECENT_BATCHES: usize = 1_000;
+
+/// How often to check for newly connected peers.
+const NEW_PEER_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Worker network specific handle.
+///
+/// Backed by the libp2p network in production and by a
+/// [MemoryNetwork](tn_network_libp2p::MemoryNetwork) in tests that don't run a swarm.
 #[derive(Clone)]
 pub struct WorkerNetworkHandle {
-    handle: NetworkHandle<Req, Res>,
+    handle: Arc<dyn NetworkClient<Req, Res>>,
+    /// Digests of batches this worker published and when, oldest first.
+    recent_batches: Arc<Mutex<VecDeque<(BlockHash, TimestampSec)>>>,
+    /// Round trip times to peers sampled by pings.
+    latencies: PeerLatencies,
+    /// The clock that timestamps published batches.
+    clock: SharedClock,
 }
 
 impl WorkerNetworkHandle {
-    pub fn new(handle: NetworkHandle<Req, Res>) -> Self {
-        Self { handle }
+    pub fn new(handle: impl NetworkClient<Req, Res>) -> Self {
+        Self {
+            handle: Arc::new(handle),
+            recent_batches: Default::default(),
+            latencies: Default::default(),
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Timestamp published batches with `clock`, the [ConsensusConfig] clock on nodes.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
     }
 
-    //// Convenience method for creating a new Self for tests- sends events no-where and does
-    //// nothing.
+    //// Convenience method for creating a new Self for tests- backed by an in-memory network
+    //// without peers.
     pub fn new_for_test() -> Self {
-        let (tx, _rx) = mpsc::channel(5);
-        Self { handle: NetworkHandle::new(tx) }
+        Self::new(MemoryNetwork::<Req, Res>::new())
     }
 
     /// Dial a peer.
@@ -58,13 +100,64 @@ impl WorkerNetworkHandle {
         self.handle.dial(peer_id, peer_addr).await
     }
 
+    /// Return the peers currently connected to the worker network.
+    pub async fn connected_peers(&self) -> NetworkResult<Vec<PeerId>> {
+        self.handle.connected_peers().await
+    }
+
+    /// Return this node's peer id on the worker network.
+    pub async fn local_peer_id(&self) -> NetworkResult<PeerId> {
+        self.handle.local_peer_id().await
+    }
+
     /// Publish a batch digest to the worker network.
+    ///
+    /// The digest is also announced to peers that connect later.
     pub async fn publish_batch(&self, batch_digest: BlockHash) -> NetworkResult<()> {
+        {
+            let mut recent = self.recent_batches.lock();
+            if recent.len() >= MAX_RECENT_BATCHES {
+                recent.pop_front();
+            }
+            recent.push_back((batch_digest, self.clock.now()));
+        }
         let data = encode(&WorkerGossip::Batch(batch_digest));
         self.handle.publish(IdentTopic::new("tn-worker"), data).await?;
         Ok(())
     }
 
+    /// Return the digests of batches published within `max_age`, oldest first.
+    ///
+    /// Older batches are forgotten.
+    pub fn recent_batches(&self, max_age: Duration) -> Vec<BlockHash> {
+        let cutoff = self.clock.now().saturating_sub(max_age.as_secs());
+        let mut recent = self.recent_batches.lock();
+        while recent.front().is_some_and(|(_, published)| *published < cutoff) {
+            recent.pop_front();
+        }
+        recent.iter().map(|(digest, _)| *digest).collect()
+    }
+
+    /// Announce batch digests to a peer so it can request the batches it missed.
+    async fn announce_batches(
+        &self,
+        peer_id: PeerId,
+        batch_digests: Vec<BlockHash>,
+    ) -> NetworkResult<()> {
+        let request = WorkerRequest::AnnounceBatches { batch_digests };
+        let res = self.handle.send_request(request, peer_id).await?;
+        let res = res.await??;
+        match res {
+            WorkerResponse::AnnounceBatches => Ok(()),
+            WorkerResponse::ReportBatch
+            | WorkerResponse::RequestBatches(_)
+            | WorkerResponse::Pong => Err(NetworkError::RPCError(
+                "Got wrong response, not an announce batches!".to_string(),
+            )),
+            WorkerResponse::Error(WorkerRPCError(s)) => Err(NetworkError::RPCError(s)),
+        }
+    }
+
     /// Report a new batch to a peer.
     async fn report_batch(&self, peer_id: PeerId, sealed_batch: SealedBatch) -> NetworkResult<()> {
         // TODO- issue 237- should we sign these batches and check the sig before accepting any
@@ -74,7 +167,9 @@ impl WorkerNetworkHandle {
         let res = res.await??;
         match res {
             WorkerResponse::ReportBatch => Ok(()),
-            WorkerResponse::RequestBatches { .. } => Err(NetworkError::RPCError(
+            WorkerResponse::RequestBatches { .. }
+            | WorkerResponse::AnnounceBatches
+            | WorkerResponse::Pong => Err(NetworkError::RPCError(
                 "Got wrong response, not a report batch is request batches!".to_string(),
             )),
             WorkerResponse::Error(WorkerRPCError(s)) => Err(NetworkError::RPCError(s)),
@@ -97,7 +192,7 @@ impl WorkerNetworkHandle {
     }
 
     /// Request a group of batches by hashes.
-    async fn request_batches_from_peer(
+    pub(super) async fn request_batches_from_peer(
         &self,
         peer_id: PeerId,
         batch_digests: Vec<BlockHash>,
@@ -108,7 +203,9 @@ impl WorkerNetworkHandle {
         let res =
             tokio::time::timeout(timeout, res).await.map_err(|_| NetworkError::Timeout)???;
         match res {
-            WorkerResponse::ReportBatch => Err(NetworkError::RPCError(
+            WorkerResponse::ReportBatch
+            | WorkerResponse::AnnounceBatches
+            | WorkerResponse::Pong => Err(NetworkError::RPCError(
                 "Got wrong response, not a request batches is report batch!".to_string(),
             )),
             WorkerResponse::RequestBatches(batches) => {
@@ -129,11 +226,15 @@ impl WorkerNetworkHandle {
     }
 
     /// Request a group of batches by hashes.
-    /// Sends request to all our connected peers at once and returns Ok when we
-    /// get a valid response or Err if no one responds with the batches.
+    ///
+    /// Peers are asked one at a time, starting with the lowest round trip time. The batches
+    /// still missing are requested from the next peer once a peer responds without all of them,
+    /// fails, or is slower than its round trip time plus `hedge_delay`. Returns Ok with the
+    /// batches received or Err if no one responds with any of the batches.
     pub async fn request_batches(
         &self,
         requested_digests: Vec<BlockHash>,
+        params: &BatchFetchParameters,
     ) -> NetworkResult<Vec<Batch>> {
         let mut peers = self.handle.connected_peers().await?;
         if requested_digests.is_empty() || peers.is_empty() {
@@ -141,58 +242,59 @@ impl WorkerNetworkHandle {
             // Return nothing.
             return Ok(vec![]);
         }
+        self.latencies.sort(&mut peers);
+        let mut peers = VecDeque::from(peers);
         let mut remaining_digests = requested_digests.clone();
-        let num_peers = peers.len();
         let mut all_batches = Vec::new();
-        // Attempt to try different batches with different peers.
-        // Ideally this will work first time and spread out the network traffic.
-        // It is possible for this algorithm to send same batches to the same peer,
-        // it is not that precise but should mix up things sufficiently to get batches
-        // if peers have them.
-        for _ in 0..num_peers {
-            let mut batch_of_batches = Vec::with_capacity(num_peers);
-            (0..num_peers).for_each(|_| batch_of_batches.push(vec![]));
-            peers.rotate_left(1); // Change which peers we ask for which batches.
-            for (i, batch) in remaining_digests.iter().enumerate() {
-                batch_of_batches
-                    .get_mut(i % num_peers)
-                    .expect("missing index we just created!")
-                    .push(*batch);
-            }
-            let mut futures = FuturesUnordered::new();
-            for (peer, batch_digests) in peers.iter().zip(batch_of_batches.into_iter()) {
-                if !batch_digests.is_empty() {
-                    futures.push(self.request_batches_from_peer(
-                        *peer,
-                        batch_digests,
-                        Duration::from_secs(3),
+        let mut requests = FuturesUnordered::new();
+        let mut hedge_after = Duration::ZERO;
+        let mut ask_next_peer = true;
+        loop {
+            if ask_next_peer {
+                if let Some(peer) = peers.pop_front() {
+                    hedge_after = self.hedge_delay(&peer, params);
+                    requests.push(self.request_batches_from_peer(
+                        peer,
+                        remaining_digests.clone(),
+                        params.peer_timeout,
                     ));
                 }
+                ask_next_peer = false;
+            }
+            if requests.is_empty() {
+                break;
             }
-            while let Some(res) = futures.next().await {
-                match res {
-                    Ok(batches) => {
-                        for batch in batches {
-                            let batch_digest = batch.digest();
-                            if requested_digests.contains(&batch_digest) {
-                                // Sanity check we actually asked for this digest...
-                                if !all_batches.contains(&batch) {
-                                    remaining_digests.retain(|d| *d != batch_digest);
-                                    all_batches.push(batch);
+            tokio::select! {
+                Some(res) = requests.next() => {
+                    match res {
+                        Ok(batches) => {
+                            for batch in batches {
+                                let batch_digest = batch.digest();
+                                if requested_digests.contains(&batch_digest) {
+                                    // Sanity check we actually asked for this digest...
+                                    if !all_batches.contains(&batch) {
+                                        remaining_digests.retain(|d| *d != batch_digest);
+                                        all_batches.push(batch);
+                                    }
+                                } else {
+                                    // Got a batch we did not ask for...
+                                    warn!(target: "worker::network", "recieved a batch not requested {batch_digest}");
                                 }
-                            } else {
-                                // Got a batch we did not ask for...
-                                warn!(target: "worker::network", "recieved a batch not requested {batch_digest}");
+                            }
+                            if remaining_digests.is_empty() {
+                                return Ok(all_batches);
                             }
                         }
-                        if remaining_digests.is_empty() {
-                            return Ok(all_batches);
+                        Err(e) => {
+                            // Another worker might succeed so just log this.
+                            warn!(target: "worker::network", ?e, "error requesting batches");
                         }
                     }
-                    Err(e) => {
-                        // Another worker might succeed so just log this.
-                        warn!(target: "worker::network", ?e, "error requesting batches");
-                    }
+                    ask_next_peer = true;
+                }
+                _ = tokio::time::sleep(hedge_after), if !peers.is_empty() => {
+                    // The peer is slow, hedge with the next one.
+                    ask_next_peer = true;
                 }
             }
         }
@@ -202,6 +304,54 @@ impl WorkerNetworkHandle {
             Ok(all_batches)
         }
     }
+
+    /// Return how long to wait for a peer's batches before also asking the next peer.
+    fn hedge_delay(&self, peer: &PeerId, params: &BatchFetchParameters) -> Duration {
+        let rtt = self.latencies.rtt(peer).unwrap_or_default();
+        (rtt + params.hedge_delay).min(params.peer_timeout)
+    }
+
+    /// Return the connected peers that advertise support for pings.
+    ///
+    /// Peers running a release without [WORKER_PING_FEATURE] can't decode the request, so they
+    /// are never pinged.
+    pub async fn pingable_peers(&self) -> NetworkResult<Vec<PeerId>> {
+        let versions = self.handle.peer_versions().await?;
+        let mut peers = self.handle.connected_peers().await?;
+        peers.retain(|peer| {
+            versions.get(peer).is_some_and(|version| version.supports(WORKER_PING_FEATURE))
+        });
+        Ok(peers)
+    }
+
+    /// Measure the round trip time to a peer.
+    ///
+    /// The sample is recorded to order the peers batches are requested from.
+    pub async fn ping(&self, peer_id: PeerId, timeout: Duration) -> NetworkResult<Duration> {
+        let start = Instant::now();
+        let res = self.handle.send_request(WorkerRequest::Ping, peer_id).await?;
+        let res =
+            tokio::time::timeout(timeout, res).await.map_err(|_| NetworkError::Timeout)???;
+        match res {
+            WorkerResponse::Pong => {
+                let rtt = start.elapsed();
+                self.latencies.record(peer_id, rtt);
+                Ok(rtt)
+            }
+            WorkerResponse::Error(WorkerRPCError(s)) => Err(NetworkError::RPCError(s)),
+            _ => Err(NetworkError::RPCError("Got wrong response, not a pong!".to_string())),
+        }
+    }
+
+    /// Use a round trip time to a peer from a previous run until the peer is pinged.
+    pub fn seed_peer_latency(&self, peer_id: PeerId, rtt: Duration) {
+        self.latencies.seed(peer_id, rtt);
+    }
+
+    /// Return the smoothed round trip time to a peer, `None` if it was never pinged.
+    pub fn peer_latency(&self, peer_id: &PeerId) -> Option<Duration> {
+        self.latencies.rtt(peer_id)
+    }
 }
 
 /// Handle inter-node communication between primaries.
@@ -214,6 +364,12 @@ pub struct WorkerNetwork<DB> {
     request_handler: RequestHandler<DB>,
     /// Shutdown notification.
     shutdown_rx: Noticer,
+    /// How long published batches are announced to peers that connect.
+    ///
+    /// Batches older than gc_depth rounds can no longer be included in a header.
+    recent_batch_window: Duration,
+    /// The peers connected at the last check.
+    known_peers: HashSet<PeerId>,
 }
 
 impl<DB> WorkerNetwork<DB>
@@ -221,54 +377,141 @@ where
     DB: Database,
 {
     /// Create a new instance of Self.
+    ///
+    /// Transactions in batches accepted from peers are recorded in the `dedup_filter`.
     pub fn new(
         network_events: mpsc::Receiver<NetworkEvent<Req, Res>>,
         network_handle: WorkerNetworkHandle,
         consensus_config: ConsensusConfig<DB>,
         id: WorkerId,
         validator: Arc<dyn BatchValidation>,
+        dedup_filter: TxDedupFilter,
     ) -> Self {
         let shutdown_rx = consensus_config.shutdown().subscribe();
-        let request_handler =
-            RequestHandler::new(id, validator, consensus_config, network_handle.clone());
-        Self { network_events, network_handle, request_handler, shutdown_rx }
+        let parameters = consensus_config.parameters();
+        let recent_batch_window = parameters.max_header_delay * parameters.gc_depth;
+        let request_handler = RequestHandler::new(
+            id,
+            validator,
+            consensus_config,
+            network_handle.clone(),
+            dedup_filter,
+        );
+        Self {
+            network_events,
+            network_handle,
+            request_handler,
+            shutdown_rx,
+            recent_batch_window,
+            known_peers: HashSet::new(),
+        }
     }
 
     /// Run the network.
     pub fn spawn(mut self, task_manager: &TaskManager) {
         task_manager.spawn_task("worker network events", async move {
+            let mut new_peer_check = tokio::time::interval(NEW_PEER_CHECK_INTERVAL);
             loop {
                 tokio::select!(
                     _ = &self.shutdown_rx => break,
                     event = self.network_events.recv() => {
                         match event {
-                            Some(e) => self.process_network_event(e),
+                            Some(e) => self.process_network_event(e).await,
                             None => break,
                         }
                     }
+                    _ = new_peer_check.tick() => self.announce_to_new_peers().await,
                 )
             }
         });
     }
 
+    /// Announce recently published batches to peers that connected since the last check.
+    ///
+    /// Peers that were offline when a batch was published would otherwise only fetch it once a
+    /// certificate requires it.
+    async fn announce_to_new_peers(&mut self) {
+        let connected: HashSet<PeerId> = match self.network_handle.connected_peers().await {
+            Ok(peers) => peers.into_iter().collect(),
+            Err(e) => {
+                warn!(target: "worker::network", ?e, "failed to get connected peers");
+                return;
+            }
+        };
+        let new_peers: Vec<PeerId> = connected.difference(&self.known_peers).copied().collect();
+        self.known_peers = connected;
+        if new_peers.is_empty() {
+            return;
+        }
+
+        let batch_digests = self.network_handle.recent_batches(self.recent_batch_window);
+        if batch_digests.is_empty() {
+            return;
+        }
+        for peer_id in new_peers {
+            debug!(
+                target: "worker::network",
+                ?peer_id,
+                batches = batch_digests.len(),
+                "announcing recent batches to new peer"
+            );
+            let network_handle = self.network_handle.clone();
+            let batch_digests = batch_digests.clone();
+            tokio::spawn(async move {
+                if let Err(e) = network_handle.announce_batches(peer_id, batch_digests).await {
+                    debug!(
+                        target: "worker::network",
+                        ?peer_id,
+                        ?e,
+                        "failed to announce recent batches"
+                    );
+                }
+            });
+        }
+    }
+
     /// Handle events concurrently.
-    fn process_network_event(&self, event: NetworkEvent<Req, Res>) {
+    ///
+    /// Handlers run in a span with the message's [CorrelationId] so logs from every node that
+    /// handles the message can be matched up.
+    async fn process_network_event(&self, event: NetworkEvent<Req, Res>) {
         // match event
         match event {
-            NetworkEvent::Request { peer, request, channel, cancel } => match request {
-                WorkerRequest::ReportBatch { sealed_batch } => {
-                    self.process_report_batch(peer, sealed_batch, channel, cancel);
-                }
-                WorkerRequest::RequestBatches { batch_digests } => {
-                    self.process_request_batches(peer, batch_digests, channel, cancel);
-                }
-            },
+            NetworkEvent::Request { peer, correlation_id, request, channel, cancel } => {
+                self.process_request(peer, request, channel, cancel)
+                    .instrument(info_span!("request", %correlation_id, %peer))
+                    .await;
+            }
             NetworkEvent::Gossip(msg) => {
+                let correlation_id = CorrelationId::for_gossip_message(&msg);
+                let _span = info_span!("gossip", %correlation_id).entered();
                 self.process_gossip(msg);
             }
         }
     }
 
+    /// Dispatch a request from a peer to its handler.
+    async fn process_request(
+        &self,
+        peer: PeerId,
+        request: WorkerRequest,
+        channel: ResponseChannel<WorkerResponse>,
+        cancel: oneshot::Receiver<()>,
+    ) {
+        match request {
+            WorkerRequest::ReportBatch { sealed_batch } => {
+                self.process_report_batch(peer, sealed_batch, channel, cancel);
+            }
+            WorkerRequest::RequestBatches { batch_digests } => {
+                self.process_request_batches(peer, batch_digests, channel, cancel);
+            }
+            WorkerRequest::AnnounceBatches { batch_digests } => {
+                self.process_announce_batches(peer, batch_digests, channel, cancel);
+            }
+            WorkerRequest::Ping => self.process_ping(channel).await,
+        }
+    }
+
     /// Process a new reported batch.
     ///
     /// Spawn a task to evaluate a peer's proposed header and return a response.
@@ -294,7 +537,7 @@ where
                 // cancel notification from network layer
                 _ = cancel => (),
             }
-        });
+        }.in_current_span());
     }
 
     /// Attempt to return requested batches.
@@ -323,7 +566,40 @@ where
                 // cancel notification from network layer
                 _ = cancel => (),
             }
-        });
+        }.in_current_span());
+    }
+
+    /// Fetch announced batches this worker is missing from the announcing peer.
+    fn process_announce_batches(
+        &self,
+        peer: PeerId,
+        batch_digests: Vec<BlockHash>,
+        channel: ResponseChannel<WorkerResponse>,
+        cancel: oneshot::Receiver<()>,
+    ) {
+        // clone for spawned tasks
+        let request_handler = self.request_handler.clone();
+        let network_handle = self.network_handle.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                res = request_handler.process_announce_batches(peer, batch_digests) => {
+                    let response = match res {
+                        Ok(()) => WorkerResponse::AnnounceBatches,
+                        Err(err) => WorkerResponse::Error(message::WorkerRPCError(err.to_string())),
+                    };
+                    let _ = network_handle.handle.send_response(response, channel).await;
+                }
+                // cancel notification from network layer
+                _ = cancel => (),
+            }
+        }.in_current_span());
+    }
+
+    /// Answer a latency probe.
+    ///
+    /// The pong is only queued for the swarm, so it is sent without spawning a task.
+    async fn process_ping(&self, channel: ResponseChannel<WorkerResponse>) {
+        let _ = self.network_handle.handle.send_response(WorkerResponse::Pong, channel).await;
     }
 
     /// Process gossip from a worker.
@@ -348,7 +624,7 @@ where
                 // match on error to lower peer score
                 //todo!();
             }
-        });
+        }.in_current_span());
     }
 }
 
@@ -358,12 +634,16 @@ pub struct PrimaryReceiverHandler<DB> {
     pub store: DB,
     /// Timeout on RequestBatches RPC.
     pub request_batches_timeout: Duration,
+    /// Timeouts of each peer's response to a RequestBatches RPC.
+    pub batch_fetch: BatchFetchParameters,
     /// Synchronize header payloads from other workers.
     pub network: Option<WorkerNetworkHandle>,
     /// Fetch certificate payloads from other workers.
     pub batch_fetcher: Option<BatchFetcher<DB>>,
     /// Validate incoming batches
     pub validator: Arc<dyn BatchValidation>,
+    /// The time source for the received time of synchronized batches.
+    pub clock: SharedClock,
 }
 
 #[async_trait::async_trait]
@@ -396,7 +676,7 @@ impl<DB: Database> PrimaryToWorkerClient for PrimaryReceiverHandler<DB> {
 
         let response = tokio::time::timeout(
             self.request_batches_timeout,
-            network.request_batches(missing.iter().cloned().collect()),
+            network.request_batches(missing.iter().cloned().collect(), &self.batch_fetch),
         )
         .await??;
 
@@ -414,13 +694,13 @@ impl<DB: Database> PrimaryToWorkerClient for PrimaryReceiverHandler<DB> {
             let (mut batch, digest) = sealed_batch.split();
             if missing.remove(&digest) {
                 // Set received_at timestamp for remote batch.
-                batch.set_received_at(now());
+                batch.set_received_at(self.clock.now());
                 let mut tx = self.store.write_txn().map_err(|e| {
                     WorkerNetworkError::Internal(format!(
                         "failed to create batch transaction to commit: {e:?}"
                     ))
                 })?;
-                tx.insert::<Batches>(&digest, &batch).map_err(|e| {
+                insert_batch(&mut tx, &digest, &batch).map_err(|e| {
                     WorkerNetworkError::Internal(format!(
                         "failed to batch transaction to commit: {e:?}"
                     ))
@@ -12,10 +12,10 @@ use std::{
 use thiserror::Error;
 use tn_network_libp2p::error::NetworkError;
 use tn_types::{
-    network_public_key_to_libp2p, Authority, Committee, SealedBatch, VotingPower, WorkerCache,
-    WorkerId,
+    network_public_key_to_libp2p, Authority, BlockHash, BlsPublicKey, Committee, SealedBatch,
+    VotingPower, WorkerCache, WorkerId,
 };
-use tokio::task::JoinHandle;
+use tokio::{sync::broadcast, task::JoinHandle};
 
 #[cfg(test)]
 #[path = "tests/quorum_waiter_tests.rs"]
@@ -45,6 +45,82 @@ pub trait QuorumWaiterTrait: Send + Sync + Clone + Unpin + 'static {
 /// Basically BoxFuture but without the unneeded lifetime.
 type QMBoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
 
+/// The capacity of the channel for quorum progress updates.
+const PROGRESS_CHANNEL_CAPACITY: usize = 100;
+
+/// A peer's response to a reported batch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PeerAckOutcome {
+    /// The peer accepted the batch and its stake counts toward quorum.
+    Accepted,
+    /// The peer explicitly rejected the batch.
+    Rejected,
+    /// The request to the peer failed.
+    Failed,
+}
+
+impl PeerAckOutcome {
+    /// The label for metrics.
+    fn as_str(&self) -> &'static str {
+        match self {
+            Self::Accepted => "accepted",
+            Self::Rejected => "rejected",
+            Self::Failed => "failed",
+        }
+    }
+}
+
+/// The response from one peer for a batch.
+#[derive(Clone, Debug)]
+pub struct PeerAck {
+    /// The peer's authority.
+    pub authority: BlsPublicKey,
+    /// The authority's voting power.
+    pub stake: VotingPower,
+    /// How the peer responded.
+    pub outcome: PeerAckOutcome,
+    /// The time between reporting the batch and the peer's response.
+    pub latency: Duration,
+}
+
+/// The state of a batch's attempt to reach quorum.
+#[derive(Clone, Debug)]
+pub enum QuorumStatus {
+    /// Waiting on peers.
+    Pending,
+    /// The batch reached quorum.
+    Reached,
+    /// The batch failed to reach quorum.
+    Failed(QuorumWaiterError),
+}
+
+/// Progress toward quorum for a batch.
+///
+/// An update is sent after each peer responds and once the attempt completes.
+#[derive(Clone, Debug)]
+pub struct QuorumProgress {
+    /// The digest of the batch.
+    pub digest: BlockHash,
+    /// The stake that accepted the batch, including this authority.
+    pub accumulated_stake: VotingPower,
+    /// The stake required for quorum.
+    pub threshold: VotingPower,
+    /// The peers that responded so far.
+    pub acks: Vec<PeerAck>,
+    /// The state of the attempt.
+    pub status: QuorumStatus,
+}
+
+impl QuorumProgress {
+    /// The peers that rejected the batch.
+    pub fn rejected_by(&self) -> impl Iterator<Item = &BlsPublicKey> {
+        self.acks
+            .iter()
+            .filter(|ack| ack.outcome == PeerAckOutcome::Rejected)
+            .map(|ack| &ack.authority)
+    }
+}
+
 struct QuorumWaiterInner {
     /// This authority.
     authority: Authority,
@@ -58,6 +134,8 @@ struct QuorumWaiterInner {
     network: WorkerNetworkHandle,
     /// Record metrics for quorum waiter.
     metrics: Arc<WorkerMetrics>,
+    /// Progress updates for batches waiting on quorum.
+    progress: broadcast::Sender<QuorumProgress>,
 }
 
 /// The QuorumWaiter waits for 2f authorities to acknowledge reception of a batch.
@@ -76,6 +154,7 @@ impl QuorumWaiter {
         network: WorkerNetworkHandle,
         metrics: Arc<WorkerMetrics>,
     ) -> Self {
+        let (progress, _) = broadcast::channel(PROGRESS_CHANNEL_CAPACITY);
         Self {
             inner: Arc::new(QuorumWaiterInner {
                 authority,
@@ -84,32 +163,54 @@ impl QuorumWaiter {
                 worker_cache,
                 network,
                 metrics,
+                progress,
             }),
         }
     }
 
-    /// Helper function. It waits for a future to complete and then delivers a value.
+    /// Subscribe to progress updates for batches waiting on quorum.
+    pub fn subscribe_progress(&self) -> broadcast::Receiver<QuorumProgress> {
+        self.inner.progress.subscribe()
+    }
+
+    /// Helper function. It waits for a peer's response and records how the peer responded.
     async fn waiter(
         wait_for: JoinHandle<Result<(), NetworkError>>,
-        deliver: VotingPower,
-    ) -> Result<VotingPower, WaiterError> {
-        match wait_for.await {
+        authority: BlsPublicKey,
+        stake: VotingPower,
+        start_time: Instant,
+    ) -> PeerAck {
+        let outcome = match wait_for.await {
             Ok(r) => {
                 match r {
-                    Ok(_) => Ok(deliver),
+                    Ok(_) => PeerAckOutcome::Accepted,
                     Err(NetworkError::RPCError(msg)) => {
                         tracing::error!(target = "worker::quorum_waiter", "RPCError: {msg}");
-                        Err(WaiterError::Rejected(deliver))
+                        PeerAckOutcome::Rejected
                     }
                     // Non-exhaustive enum...
                     Err(err) => {
                         tracing::error!(target = "worker::quorum_waiter", "Network error: {err}");
-                        Err(WaiterError::Network(deliver))
+                        PeerAckOutcome::Failed
                     }
                 }
             }
-            Err(_) => Err(WaiterError::Network(deliver)),
-        }
+            Err(_) => PeerAckOutcome::Failed,
+        };
+
+        PeerAck { authority, stake, outcome, latency: start_time.elapsed() }
+    }
+
+    /// Record a peer's response in metrics.
+    fn record_ack(inner: &QuorumWaiterInner, ack: &PeerAck) {
+        let authority = inner.committee.authority_by_key(&ack.authority);
+        let peer = authority.as_ref().map(|a| a.hostname()).unwrap_or("unknown");
+        inner.metrics.batch_peer_acks.with_label_values(&[peer, ack.outcome.as_str()]).inc();
+        inner
+            .metrics
+            .batch_peer_ack_latency
+            .with_label_values(&[peer])
+            .observe(ack.latency.as_secs_f64());
     }
 }
 
@@ -121,7 +222,19 @@ impl QuorumWaiterTrait for QuorumWaiter {
     ) -> JoinHandle<Result<(), QuorumWaiterError>> {
         let inner = self.inner.clone();
         tokio::spawn(async move {
-            let timeout_res = tokio::time::timeout(timeout, async move {
+            // Wait for the first 2f nodes to send back an Ack. Then we consider the batch
+            // delivered and we send its digest to the primary (that will include it into
+            // the dag). This should reduce the amount of syncing.
+            let threshold = inner.committee.quorum_threshold();
+            let mut progress = QuorumProgress {
+                digest: sealed_batch.digest(),
+                accumulated_stake: inner.authority.voting_power(),
+                threshold,
+                acks: Vec::new(),
+                status: QuorumStatus::Pending,
+            };
+
+            let timeout_res = tokio::time::timeout(timeout, async {
                 let start_time = Instant::now();
                 // Broadcast the batch to the other workers.
                 let workers: Vec<_> = inner
@@ -139,9 +252,8 @@ impl QuorumWaiterTrait for QuorumWaiter {
                 let _timer = inner.metrics.batch_broadcast_quorum_latency.start_timer();
 
                 // Collect all the handlers to receive acknowledgements.
-                let mut wait_for_quorum: FuturesUnordered<
-                    QMBoxFuture<Result<VotingPower, WaiterError>>,
-                > = FuturesUnordered::new();
+                let mut wait_for_quorum: FuturesUnordered<QMBoxFuture<PeerAck>> =
+                    FuturesUnordered::new();
                 // Total stake available for the entire committee.
                 // Can use this to determine anti-quorum more quickly.
                 let mut available_stake = 0;
@@ -153,54 +265,58 @@ impl QuorumWaiterTrait for QuorumWaiter {
                     .map(|(name, handler)| {
                         let stake = inner.committee.voting_power(&name);
                         available_stake += stake;
-                        Box::pin(monitored_future!(Self::waiter(handler, stake)))
+                        Box::pin(monitored_future!(Self::waiter(handler, name, stake, start_time)))
                     })
                     .for_each(|f| wait_for_quorum.push(f));
 
-                // Wait for the first 2f nodes to send back an Ack. Then we consider the batch
-                // delivered and we send its digest to the primary (that will include it into
-                // the dag). This should reduce the amount of syncing.
-                let threshold = inner.committee.quorum_threshold();
-                let mut total_stake = inner.authority.voting_power();
                 // If more stake than this is rejected then the batch will never be accepted.
-                let max_rejected_stake = available_stake - threshold;
+                //
+                // This authority's own stake counts toward quorum, so peers can reject up to the
+                // committee's stake above the threshold. With four equal validators one rejection
+                // is tolerated and a second fails the batch without waiting on the last peer.
+                let max_rejected_stake =
+                    (progress.accumulated_stake + available_stake).saturating_sub(threshold);
 
                 // Wait on the peer responses and produce an Ok(()) for quorum (2/3 stake confirmed
                 // batch) or Error if quorum not reached.
                 loop {
-                    if let Some(res) = wait_for_quorum.next().await {
-                        match res {
-                            Ok(stake) => {
-                                total_stake += stake;
-                                if total_stake >= threshold {
-                                    let remaining_time =
-                                        start_time.elapsed().saturating_sub(timeout);
-                                    if !wait_for_quorum.is_empty() && !remaining_time.is_zero() {
-                                        // Let the remaining waiters have a chance for the remaining
-                                        // time.
-                                        // These are fire and forget, they will timeout soon so no
-                                        // big deal.
-                                        tokio::spawn(async move {
-                                            let _ =
-                                                tokio::time::timeout(remaining_time, async move {
-                                                    while (wait_for_quorum.next().await).is_some() {
-                                                        // do nothing
-                                                    }
-                                                })
-                                                .await;
-                                        });
-                                    }
-                                    break Ok(());
-                                }
+                    if let Some(ack) = wait_for_quorum.next().await {
+                        Self::record_ack(&inner, &ack);
+                        match ack.outcome {
+                            PeerAckOutcome::Accepted => {
+                                progress.accumulated_stake += ack.stake;
                             }
-                            Err(WaiterError::Rejected(stake)) => {
-                                rejected_stake -= stake;
-                                available_stake -= stake;
+                            PeerAckOutcome::Rejected => {
+                                rejected_stake += ack.stake;
+                                available_stake -= ack.stake;
                             }
-                            Err(WaiterError::Network(stake)) => {
-                                available_stake -= stake;
+                            PeerAckOutcome::Failed => {
+                                available_stake -= ack.stake;
+                            }
+                        }
+                        progress.acks.push(ack);
+
+                        if progress.accumulated_stake >= threshold {
+                            let remaining_time = start_time.elapsed().saturating_sub(timeout);
+                            if !wait_for_quorum.is_empty() && !remaining_time.is_zero() {
+                                // Let the remaining waiters have a chance for the remaining
+                                // time.
+                                // These are fire and forget, they will timeout soon so no
+                                // big deal.
+                                tokio::spawn(async move {
+                                    let _ = tokio::time::timeout(remaining_time, async move {
+                                        while (wait_for_quorum.next().await).is_some() {
+                                            // do nothing
+                                        }
+                                    })
+                                    .await;
+                                });
                             }
+                            break Ok(());
                         }
+
+                        // ignore errors if no one is listening
+                        let _ = inner.progress.send(progress.clone());
                     } else {
                         // Ran out of Peers and did not reach quorum...
                         break Err(QuorumWaiterError::AntiQuorum);
@@ -210,7 +326,7 @@ impl QuorumWaiterTrait for QuorumWaiter {
                         // to much stack.
                         break Err(QuorumWaiterError::QuorumRejected);
                     }
-                    if total_stake + available_stake < threshold {
+                    if progress.accumulated_stake + available_stake < threshold {
                         // It is no longer possible to reach quorum...
                         // This is likely because of network/rpc errors and may not be permanent.
                         break Err(QuorumWaiterError::AntiQuorum);
@@ -218,13 +334,17 @@ impl QuorumWaiterTrait for QuorumWaiter {
                 }
             })
             .await;
-            match timeout_res {
-                Ok(res) => match res {
-                    Ok(()) => Ok(()),
-                    Err(e) => Err(e),
-                },
+            let res = match timeout_res {
+                Ok(res) => res,
                 Err(_elapsed) => Err(QuorumWaiterError::Timeout),
-            }
+            };
+
+            progress.status = match &res {
+                Ok(()) => QuorumStatus::Reached,
+                Err(e) => QuorumStatus::Failed(e.clone()),
+            };
+            let _ = inner.progress.send(progress);
+            res
         })
     }
 }
@@ -242,11 +362,3 @@ pub enum QuorumWaiterError {
     #[error("RPC Status Error {0}")]
     Rpc(String),
 }
-
-#[derive(Clone, Debug, Error)]
-enum WaiterError {
-    #[error("Block was rejected by peer")]
-    Rejected(VotingPower),
-    #[error("Network Error")]
-    Network(VotingPower),
-}
@@ -3,10 +3,13 @@ use super::*;
 use crate::quorum_waiter::QuorumWaiterError;
 use std::sync::Mutex;
 use tempfile::TempDir;
-use tn_network_types::MockWorkerToPrimary;
+use tn_network_types::{MockWorkerToPrimary, WorkerOthersBatchMessage};
 use tn_storage::open_db;
-use tn_test_utils::transaction;
-use tn_types::Batch;
+use tn_test_utils::{transaction, TestClock};
+use tn_types::{
+    keccak256, Batch, NetworkKeypair, Notifier, TaskManager, DEFAULT_INCLUSION_PROMISE_CAPACITY,
+};
+use tokio::sync::watch;
 
 #[derive(Clone, Debug)]
 struct TestMakeBlockQuorumWaiter(Arc<Mutex<Option<SealedBatch>>>);
@@ -44,6 +47,12 @@ async fn make_batch() {
     let id = 0;
     let qw = TestMakeBlockQuorumWaiter::new_test();
     let timeout = Duration::from_secs(5);
+    let (_round_tx, round_rx) = watch::channel(3);
+    let inclusion_promises = InclusionPromises::new(
+        NetworkKeypair::generate_ed25519(),
+        round_rx,
+        DEFAULT_INCLUSION_PROMISE_CAPACITY,
+    );
     let batch_provider = Worker::new(
         id,
         qw.clone(),
@@ -51,7 +60,9 @@ async fn make_batch() {
         client,
         store.clone(),
         timeout,
+        CHANNEL_CAPACITY,
         WorkerNetworkHandle::new_for_test(),
+        Some(inclusion_promises.clone()),
     );
 
     // Send enough transactions to seal a batch.
@@ -75,4 +86,105 @@ async fn make_batch() {
 
     // Ensure the batch is stored
     assert!(store.get::<Batches>(&expected_batch.digest()).unwrap().is_some());
+
+    // Ensure the worker promised to include the transaction
+    let promise = inclusion_promises.get(&keccak256(&tx)).expect("inclusion promise recorded");
+    assert_eq!(promise.batch_digest, expected_batch.digest());
+    assert_eq!(promise.round, 3);
+    assert!(promise.verify());
+}
+
+#[tokio::test]
+async fn sealed_batches_are_remembered_for_new_peers() {
+    let client = LocalNetwork::new_with_empty_id();
+    let temp_dir = TempDir::new().unwrap();
+    let store = open_db(temp_dir.path());
+    client.set_worker_to_primary_local_handler(Arc::new(MockWorkerToPrimary()));
+
+    let network = WorkerNetworkHandle::new_for_test();
+    let batch_provider = Worker::new(
+        0,
+        TestMakeBlockQuorumWaiter::new_test(),
+        Arc::new(WorkerMetrics::default()),
+        client,
+        store,
+        Duration::from_secs(5),
+        CHANNEL_CAPACITY,
+        network.clone(),
+        None,
+    );
+
+    let tx = transaction();
+    let first = Batch { transactions: vec![tx.clone()], ..Default::default() }.seal_slow();
+    let second = Batch { transactions: vec![tx.clone(), tx], ..Default::default() }.seal_slow();
+    batch_provider.seal(first.clone()).await.unwrap();
+    batch_provider.seal(second.clone()).await.unwrap();
+
+    // sealed batches are announced oldest first
+    assert_eq!(
+        network.recent_batches(Duration::from_secs(60)),
+        vec![first.digest(), second.digest()]
+    );
+}
+
+/// Records heartbeats sent to the primary.
+#[derive(Default)]
+struct HeartbeatRecorder(Mutex<Vec<WorkerHeartbeatMessage>>);
+
+#[async_trait::async_trait]
+impl WorkerToPrimaryClient for HeartbeatRecorder {
+    async fn report_own_batch(&self, _request: WorkerOwnBatchMessage) -> eyre::Result<()> {
+        Ok(())
+    }
+
+    async fn report_others_batch(&self, _request: WorkerOthersBatchMessage) -> eyre::Result<()> {
+        Ok(())
+    }
+
+    async fn report_heartbeat(&self, request: WorkerHeartbeatMessage) -> eyre::Result<()> {
+        self.0.lock().unwrap().push(request);
+        Ok(())
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn idle_worker_sends_heartbeats() {
+    let client = LocalNetwork::new_with_empty_id();
+    let temp_dir = TempDir::new().unwrap();
+    let store = open_db(temp_dir.path());
+    let recorder = Arc::new(HeartbeatRecorder::default());
+    client.set_worker_to_primary_local_handler(recorder.clone());
+
+    let batch_provider = Worker::new(
+        3,
+        TestMakeBlockQuorumWaiter::new_test(),
+        Arc::new(WorkerMetrics::default()),
+        client,
+        store,
+        Duration::from_secs(5),
+        CHANNEL_CAPACITY,
+        WorkerNetworkHandle::new_for_test(),
+        None,
+    );
+    let shutdown = Notifier::new();
+    let clock = TestClock::new(1_000);
+    let task_manager = TaskManager::default();
+    batch_provider.spawn_heartbeat(
+        Duration::from_millis(100),
+        clock.shared(),
+        &task_manager,
+        shutdown.subscribe(),
+    );
+
+    // one heartbeat per interval while idle
+    tokio::time::sleep(Duration::from_millis(350)).await;
+    let heartbeats = recorder.0.lock().unwrap().clone();
+    assert_eq!(heartbeats.len(), 3);
+    assert!(heartbeats.iter().all(|heartbeat| heartbeat.worker_id == 3));
+    assert!(heartbeats.iter().all(|heartbeat| heartbeat.timestamp == 1_000));
+
+    // heartbeats stop at shutdown
+    shutdown.notify();
+    tokio::time::sleep(Duration::from_millis(1_000)).await;
+    assert_eq!(recorder.0.lock().unwrap().len(), 3);
 }
@@ -0,0 +1,60 @@
+//! Tests for latency probes and the peers batches are requested from.
+
+use super::{WorkerNetworkHandle, WorkerRequest, WorkerResponse};
+use std::time::Duration;
+use tn_config::BatchFetchParameters;
+use tn_network_libp2p::{error::NetworkError, MemoryNetwork, PeerId};
+use tn_types::Batch;
+
+/// A network where every peer answers pings and has `batch`.
+fn network_with_batch(batch: Batch) -> MemoryNetwork<WorkerRequest, WorkerResponse> {
+    MemoryNetwork::new().with_responder(move |_, request| match request {
+        WorkerRequest::Ping => Ok(WorkerResponse::Pong),
+        WorkerRequest::RequestBatches { .. } => {
+            Ok(WorkerResponse::RequestBatches(vec![batch.clone()]))
+        }
+        _ => Err(NetworkError::RPCError("unexpected request".to_string())),
+    })
+}
+
+#[tokio::test]
+async fn test_ping_records_latency() {
+    let network = network_with_batch(Batch::default());
+    let peer = PeerId::random();
+    network.connect(peer);
+    let handle = WorkerNetworkHandle::new(network);
+    assert_eq!(handle.peer_latency(&peer), None);
+
+    let rtt = handle.ping(peer, Duration::from_secs(1)).await.expect("peer answers pings");
+    assert_eq!(handle.peer_latency(&peer), Some(rtt));
+}
+
+#[tokio::test]
+async fn test_batches_requested_from_fastest_peer() {
+    let batch = Batch::default();
+    let network = network_with_batch(batch.clone());
+    let unknown = PeerId::random();
+    let slow = PeerId::random();
+    let fast = PeerId::random();
+    for peer in [unknown, slow, fast] {
+        network.connect(peer);
+    }
+    let handle = WorkerNetworkHandle::new(network.clone());
+    handle.latencies.record(slow, Duration::from_millis(300));
+    handle.latencies.record(fast, Duration::from_millis(20));
+
+    let batches = handle
+        .request_batches(vec![batch.digest()], &BatchFetchParameters::default())
+        .await
+        .expect("batches fetched");
+    assert_eq!(batches, vec![batch]);
+
+    // the fastest peer had the batch so no other peer was asked
+    let asked: Vec<PeerId> = network
+        .requests()
+        .into_iter()
+        .filter(|(_, request)| matches!(request, WorkerRequest::RequestBatches { .. }))
+        .map(|(peer, _)| peer)
+        .collect();
+    assert_eq!(asked, vec![fast]);
+}
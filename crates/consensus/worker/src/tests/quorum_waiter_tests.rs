@@ -3,7 +3,10 @@
 use crate::{WorkerRequest, WorkerResponse};
 
 use super::*;
-use tn_network_libp2p::types::{NetworkCommand, NetworkHandle};
+use tn_network_libp2p::{
+    types::{NetworkCommand, NetworkHandle},
+    MemoryNetwork,
+};
 use tn_storage::mem_db::MemDatabase;
 use tn_test_utils::{batch, CommitteeFixture};
 use tokio::sync::mpsc;
@@ -78,3 +81,146 @@ async fn wait_for_quorum() {
     // Wait for the `QuorumWaiter` to gather enough acknowledgements and output the batch.
     attest2_handle.await.unwrap().unwrap();
 }
+
+#[tokio::test]
+async fn test_quorum_progress_attributes_peers() {
+    let fixture = CommitteeFixture::builder(MemDatabase::default).randomize_ports(true).build();
+    let committee = fixture.committee();
+    let worker_cache = fixture.worker_cache();
+    let my_primary = fixture.authorities().next().unwrap();
+
+    let node_metrics = Arc::new(WorkerMetrics::default());
+
+    // setup network
+    let (sender, mut network_rx) = mpsc::channel(100);
+    let network = WorkerNetworkHandle::new(NetworkHandle::new(sender));
+    let quorum_waiter = QuorumWaiter::new(
+        my_primary.authority().clone(),
+        /* worker_id */ 0,
+        committee.clone(),
+        worker_cache.clone(),
+        network,
+        node_metrics,
+    );
+    let mut progress_rx = quorum_waiter.subscribe_progress();
+
+    let sealed_batch = batch().seal_slow();
+    let attest_handle = quorum_waiter.verify_batch(sealed_batch.clone(), Duration::from_secs(10));
+
+    // the first peer rejects the batch and the rest accept
+    for i in 0..3 {
+        match network_rx.recv().await {
+            Some(NetworkCommand::SendRequest {
+                request: WorkerRequest::ReportBatch { .. },
+                reply,
+                ..
+            }) => {
+                let response = if i == 0 {
+                    Err(NetworkError::RPCError("invalid batch".to_string()))
+                } else {
+                    Ok(WorkerResponse::ReportBatch)
+                };
+                reply.send(response).unwrap();
+            }
+            _ => panic!("failed to get a batch!"),
+        }
+    }
+
+    // one rejection is tolerated with four validators
+    assert!(attest_handle.await.unwrap().is_ok());
+
+    let mut last = None;
+    while let Ok(progress) = progress_rx.try_recv() {
+        assert_eq!(progress.digest, sealed_batch.digest());
+        last = Some(progress);
+    }
+    let last = last.expect("progress reported");
+    assert!(matches!(last.status, QuorumStatus::Reached));
+    assert_eq!(last.accumulated_stake, committee.quorum_threshold());
+    assert_eq!(last.rejected_by().count(), 1);
+}
+
+#[tokio::test]
+async fn test_quorum_rejected_without_swarm() {
+    let fixture = CommitteeFixture::builder(MemDatabase::default).randomize_ports(true).build();
+    let committee = fixture.committee();
+    let worker_cache = fixture.worker_cache();
+    let my_primary = fixture.authorities().next().unwrap();
+
+    // every peer rejects the batch
+    let network = MemoryNetwork::<WorkerRequest, WorkerResponse>::new()
+        .with_responder(|_, _| Err(NetworkError::RPCError("invalid batch".to_string())));
+    let quorum_waiter = QuorumWaiter::new(
+        my_primary.authority().clone(),
+        /* worker_id */ 0,
+        committee.clone(),
+        worker_cache.clone(),
+        WorkerNetworkHandle::new(network.clone()),
+        Arc::new(WorkerMetrics::default()),
+    );
+
+    let sealed_batch = batch().seal_slow();
+    let result = quorum_waiter.verify_batch(sealed_batch.clone(), Duration::from_secs(10)).await;
+    assert!(matches!(result.unwrap(), Err(QuorumWaiterError::QuorumRejected)));
+
+    // the batch was reported to other workers
+    let requests = network.requests();
+    assert!(!requests.is_empty());
+    for (_, request) in requests {
+        assert!(matches!(
+            request,
+            WorkerRequest::ReportBatch { sealed_batch: reported } if reported == sealed_batch
+        ));
+    }
+}
+
+#[tokio::test]
+async fn test_quorum_rejected_once_rejected_stake_exceeds_tolerance() {
+    let fixture = CommitteeFixture::builder(MemDatabase::default).randomize_ports(true).build();
+    let committee = fixture.committee();
+    let worker_cache = fixture.worker_cache();
+    let my_primary = fixture.authorities().next().unwrap();
+
+    // setup network
+    let (sender, mut network_rx) = mpsc::channel(100);
+    let network = WorkerNetworkHandle::new(NetworkHandle::new(sender));
+    let quorum_waiter = QuorumWaiter::new(
+        my_primary.authority().clone(),
+        /* worker_id */ 0,
+        committee.clone(),
+        worker_cache.clone(),
+        network,
+        Arc::new(WorkerMetrics::default()),
+    );
+
+    let sealed_batch = batch().seal_slow();
+    let attest_handle = quorum_waiter.verify_batch(sealed_batch, Duration::from_secs(10));
+
+    // two peers reject the batch and the last peer never responds
+    let mut pending_reply = None;
+    for i in 0..3 {
+        match network_rx.recv().await {
+            Some(NetworkCommand::SendRequest {
+                request: WorkerRequest::ReportBatch { .. },
+                reply,
+                ..
+            }) => {
+                if i < 2 {
+                    let response = Err(NetworkError::RPCError("invalid batch".to_string()));
+                    reply.send(response).unwrap();
+                } else {
+                    pending_reply = Some(reply);
+                }
+            }
+            _ => panic!("failed to get a batch!"),
+        }
+    }
+
+    // the batch fails as soon as the rejected stake exceeds the tolerance, before the timeout
+    let result = tokio::time::timeout(Duration::from_secs(5), attest_handle)
+        .await
+        .expect("quorum waiter finished before the timeout")
+        .unwrap();
+    assert!(matches!(result, Err(QuorumWaiterError::QuorumRejected)));
+    drop(pending_reply);
+}
@@ -0,0 +1,42 @@
+//! Tests for remembering published batches to announce to new peers.
+
+use super::{WorkerNetworkHandle, MAX_RECENT_BATCHES};
+use std::time::Duration;
+use tn_test_utils::TestClock;
+use tn_types::BlockHash;
+
+#[tokio::test]
+async fn test_recent_batches_are_capped() {
+    let network = WorkerNetworkHandle::new_for_test();
+    for i in 0..(MAX_RECENT_BATCHES as u64 + 2) {
+        network.publish_batch(BlockHash::from(digest_bytes(i))).await.unwrap();
+    }
+
+    // the oldest digests are dropped first
+    let recent = network.recent_batches(Duration::from_secs(60));
+    assert_eq!(recent.len(), MAX_RECENT_BATCHES);
+    assert_eq!(recent.first(), Some(&BlockHash::from(digest_bytes(2))));
+    assert_eq!(recent.last(), Some(&BlockHash::from(digest_bytes(MAX_RECENT_BATCHES as u64 + 1))));
+}
+
+#[tokio::test]
+async fn test_recent_batches_forget_old_batches() {
+    let clock = TestClock::new(1_000);
+    let network = WorkerNetworkHandle::new_for_test().with_clock(clock.shared());
+    let old = BlockHash::with_last_byte(1);
+    let fresh = BlockHash::with_last_byte(2);
+    network.publish_batch(old).await.unwrap();
+    clock.advance(Duration::from_secs(120));
+    network.publish_batch(fresh).await.unwrap();
+
+    assert_eq!(network.recent_batches(Duration::from_secs(60)), vec![fresh]);
+    // forgotten batches are not announced even with a longer window
+    assert_eq!(network.recent_batches(Duration::from_secs(600)), vec![fresh]);
+}
+
+/// A distinct digest for each index.
+fn digest_bytes(i: u64) -> [u8; 32] {
+    let mut bytes = [0; 32];
+    bytes[24..].copy_from_slice(&i.to_be_bytes());
+    bytes
+}
@@ -62,7 +62,7 @@ async fn get_network_peers_from_admin_server() {
     tokio::time::sleep(Duration::from_secs(1)).await;
 
     let registry_1 = Registry::new();
-    let metrics_1 = Metrics::new_with_registry(&registry_1);
+    let metrics_1 = Metrics::new_with_registry(&registry_1, worker_id);
 
     let worker_1_parameters = config_1.config().parameters.clone();
 
@@ -126,7 +126,7 @@ async fn get_network_peers_from_admin_server() {
     tokio::time::sleep(Duration::from_secs(1)).await;
 
     let registry_2 = Registry::new();
-    let metrics_2 = Metrics::new_with_registry(&registry_2);
+    let metrics_2 = Metrics::new_with_registry(&registry_2, worker_id);
 
     let worker_2_parameters = config_2.config().parameters.clone();
 
@@ -10,21 +10,32 @@ use crate::{
     quorum_waiter::{QuorumWaiter, QuorumWaiterTrait},
     WorkerNetworkHandle,
 };
-use std::{sync::Arc, time::Duration};
-use tn_config::ConsensusConfig;
-use tn_network_types::{local::LocalNetwork, WorkerOwnBatchMessage, WorkerToPrimaryClient};
-use tn_storage::tables::Batches;
+use consensus_metrics::channels::register_channel;
+use futures::{stream::FuturesUnordered, StreamExt as _};
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tn_config::{ChannelClass, ConsensusConfig};
+use tn_network_libp2p::PeerId;
+use tn_network_types::{
+    local::LocalNetwork, WorkerHeartbeatMessage, WorkerOwnBatchMessage, WorkerToPrimaryClient,
+};
+use tn_storage::{BatchStore as _, PeerLatencyStore as _};
 use tn_types::{
     error::BlockSealError, network_public_key_to_libp2p, BatchSender, BatchValidation, Database,
-    SealedBatch, WorkerId,
+    InclusionPromises, Noticer, SealedBatch, SharedClock, TaskManager, WorkerId,
 };
-use tracing::{error, info};
+use tokio::time::Instant;
+use tracing::{debug, error, info, warn};
 
 #[cfg(test)]
 #[path = "tests/batch_provider_tests.rs"]
 pub mod batch_provider_tests;
 
 /// The default channel capacity for each channel of the worker.
+///
+/// Nodes size worker channels with [tn_config::ChannelParameters].
 pub const CHANNEL_CAPACITY: usize = 1_000;
 
 /// Spawn the worker.
@@ -36,6 +47,8 @@ pub fn new_worker<DB: Database>(
     metrics: Metrics,
     consensus_config: ConsensusConfig<DB>,
     network_handle: WorkerNetworkHandle,
+    inclusion_promises: Option<InclusionPromises>,
+    task_manager: &TaskManager,
 ) -> Worker<DB, QuorumWaiter> {
     let worker_name = consensus_config.key_config().worker_network_public_key();
     let worker_peer_id = network_public_key_to_libp2p(&worker_name);
@@ -47,14 +60,18 @@ pub fn new_worker<DB: Database>(
         network_handle.clone(),
         consensus_config.node_storage().clone(),
         node_metrics.clone(),
+        consensus_config.clock().clone(),
+        consensus_config.parameters().requests.batch_fetch.clone(),
     );
     consensus_config.local_network().set_primary_to_worker_local_handler(Arc::new(
         PrimaryReceiverHandler {
             store: consensus_config.node_storage().clone(),
-            request_batches_timeout: consensus_config.parameters().sync_retry_delay,
+            request_batches_timeout: consensus_config.parameters().requests.batch_fetch.timeout,
+            batch_fetch: consensus_config.parameters().requests.batch_fetch.clone(),
             network: Some(network_handle.clone()),
             batch_fetcher: Some(batch_fetcher),
             validator,
+            clock: consensus_config.clock().clone(),
         },
     ));
     let batch_provider = new_worker_internal(
@@ -63,6 +80,8 @@ pub fn new_worker<DB: Database>(
         node_metrics,
         consensus_config.local_network().clone(),
         network_handle.clone(),
+        inclusion_promises,
+        task_manager,
     );
 
     // NOTE: This log entry is used to compute performance.
@@ -86,6 +105,8 @@ fn new_worker_internal<DB: Database>(
     node_metrics: Arc<WorkerMetrics>,
     client: LocalNetwork,
     network_handle: WorkerNetworkHandle,
+    inclusion_promises: Option<InclusionPromises>,
+    task_manager: &TaskManager,
 ) -> Worker<DB, QuorumWaiter> {
     info!(target: "worker::worker", "Starting handler for transactions");
 
@@ -95,20 +116,30 @@ fn new_worker_internal<DB: Database>(
         consensus_config.authority().clone(),
         id,
         consensus_config.committee().clone(),
-        consensus_config.worker_cache().clone(),
+        consensus_config.worker_cache(),
         network_handle.clone(),
         node_metrics.clone(),
     );
 
-    Worker::new(
+    let worker = Worker::new(
         id,
         quorum_waiter,
         node_metrics,
         client,
         consensus_config.node_storage().clone(),
         consensus_config.parameters().batch_vote_timeout,
+        consensus_config.parameters().channels.capacity(ChannelClass::Worker, "worker_batches"),
         network_handle,
-    )
+        inclusion_promises,
+    );
+    worker.spawn_heartbeat(
+        consensus_config.parameters().worker_heartbeat_interval,
+        consensus_config.clock().clone(),
+        task_manager,
+        consensus_config.shutdown().subscribe(),
+    );
+    worker.spawn_latency_probes(consensus_config.clone(), task_manager);
+    worker
 }
 
 /// Process batch from EL into sealed batches for CL.
@@ -130,6 +161,10 @@ pub struct Worker<DB, QW> {
     timeout: Duration,
     /// Worker network handle.
     network_handle: WorkerNetworkHandle,
+    /// Signs inclusion promises for sealed batches if enabled.
+    inclusion_promises: Option<InclusionPromises>,
+    /// When this worker last reported a batch or heartbeat to the primary.
+    last_report: Arc<Mutex<Instant>>,
 }
 
 impl<DB, QW> std::fmt::Debug for Worker<DB, QW> {
@@ -146,9 +181,15 @@ impl<DB: Database, QW: QuorumWaiterTrait> Worker<DB, QW> {
         client: LocalNetwork,
         store: DB,
         timeout: Duration,
+        channel_capacity: usize,
         network_handle: WorkerNetworkHandle,
+        inclusion_promises: Option<InclusionPromises>,
     ) -> Self {
-        let (tx_batches, mut rx_batches) = tokio::sync::mpsc::channel(1000);
+        let (tx_batches, mut rx_batches) = tokio::sync::mpsc::channel(channel_capacity);
+        let weak_batches = tx_batches.downgrade();
+        register_channel(format!("worker_{id}_batches"), channel_capacity, move || {
+            weak_batches.upgrade().map(|tx| tx.max_capacity() - tx.capacity())
+        });
         let this = Self {
             id,
             quorum_waiter,
@@ -158,6 +199,8 @@ impl<DB: Database, QW: QuorumWaiterTrait> Worker<DB, QW> {
             tx_batches,
             timeout,
             network_handle,
+            inclusion_promises,
+            last_report: Arc::new(Mutex::new(Instant::now())),
         };
         let this_clone = this.clone();
         // Spawn a little task to accept batches from a channel and seal them that way.
@@ -177,6 +220,144 @@ impl<DB: Database, QW: QuorumWaiterTrait> Worker<DB, QW> {
         self.tx_batches.clone()
     }
 
+    /// Spawn a task that sends the primary a heartbeat when no batch was reported for `interval`.
+    ///
+    /// Workers do not seal empty batches by default, so the heartbeat lets the primary tell an
+    /// idle worker from a worker that is down.
+    pub fn spawn_heartbeat(
+        &self,
+        interval: Duration,
+        clock: SharedClock,
+        task_manager: &TaskManager,
+        rx_shutdown: Noticer,
+    ) {
+        let this = self.clone();
+        task_manager.spawn_task("worker heartbeat", async move {
+            let mut ticker = tokio::time::interval_at(Instant::now() + interval, interval);
+            loop {
+                tokio::select!(
+                    _ = &rx_shutdown => break,
+                    _ = ticker.tick() => this.heartbeat(interval, &clock).await,
+                )
+            }
+        });
+    }
+
+    /// Spawn a task that pings every connected peer each `latency_probe_interval`.
+    ///
+    /// The round trip times are recorded as metrics and by the network handle, which requests
+    /// batches from the fastest peers first. Each authority's time is persisted and the network
+    /// handle starts with the saved times, so nearby peers are preferred right after a restart.
+    /// Failed pings penalize the authority's saved time until it answers again.
+    pub fn spawn_latency_probes(
+        &self,
+        consensus_config: ConsensusConfig<DB>,
+        task_manager: &TaskManager,
+    ) {
+        let network_handle = self.network_handle.clone();
+        let metrics = self.node_metrics.clone();
+        let interval = consensus_config.parameters().latency_probe_interval;
+        let rx_shutdown = consensus_config.shutdown().subscribe();
+        let history = consensus_config.authority_latencies();
+        for (peer, _) in consensus_config.worker_cache().all_workers() {
+            if let Some(rtt) =
+                consensus_config.worker_authority(&peer).and_then(|id| history.get(&id).copied())
+            {
+                network_handle.seed_peer_latency(peer, rtt);
+            }
+        }
+        task_manager.spawn_task("worker latency probes", async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                tokio::select!(
+                    _ = &rx_shutdown => break,
+                    _ = ticker.tick() => {
+                        Self::probe_latencies(
+                            &network_handle,
+                            &metrics,
+                            &consensus_config,
+                            interval,
+                        )
+                        .await
+                    }
+                )
+            }
+        });
+    }
+
+    /// Ping every connected peer that supports pings, waiting at most `timeout` for each.
+    async fn probe_latencies(
+        network_handle: &WorkerNetworkHandle,
+        metrics: &WorkerMetrics,
+        consensus_config: &ConsensusConfig<DB>,
+        timeout: Duration,
+    ) {
+        let peers = match network_handle.pingable_peers().await {
+            Ok(peers) => peers,
+            Err(err) => {
+                error!(target: "worker::batch_provider", ?err, "failed to get connected peers");
+                return;
+            }
+        };
+        let mut pings: FuturesUnordered<_> = peers
+            .into_iter()
+            .map(|peer| async move { (peer, network_handle.ping(peer, timeout).await) })
+            .collect();
+        while let Some((peer, res)) = pings.next().await {
+            match res {
+                Ok(rtt) => {
+                    metrics
+                        .peer_latency
+                        .with_label_values(&[&peer.to_string()])
+                        .observe(rtt.as_secs_f64());
+                    Self::save_peer_latency(network_handle, consensus_config, peer);
+                }
+                Err(err) => {
+                    debug!(target: "worker::batch_provider", %peer, ?err, "latency probe failed");
+                    if let Some(authority) = consensus_config.worker_authority(&peer) {
+                        consensus_config.record_authority_failure(&authority);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Persist the smoothed round trip time to the authority running the peer's worker.
+    fn save_peer_latency(
+        network_handle: &WorkerNetworkHandle,
+        consensus_config: &ConsensusConfig<DB>,
+        peer: PeerId,
+    ) {
+        let (Some(authority), Some(rtt)) =
+            (consensus_config.worker_authority(&peer), network_handle.peer_latency(&peer))
+        else {
+            return;
+        };
+        let now = consensus_config.clock().now();
+        if let Err(err) = consensus_config.node_storage().record_peer_latency(&authority, rtt, now)
+        {
+            warn!(target: "worker::batch_provider", %peer, ?err, "failed to save peer latency");
+        }
+    }
+
+    /// Send a heartbeat to the primary unless a batch or heartbeat was reported within `interval`.
+    async fn heartbeat(&self, interval: Duration, clock: &SharedClock) {
+        if self.last_report.lock().expect("last report lock").elapsed() < interval {
+            return;
+        }
+        let message = WorkerHeartbeatMessage { worker_id: self.id, timestamp: clock.now() };
+        debug!(target: "worker::batch_provider", ?message, "worker idle, sending heartbeat");
+        if let Err(err) = self.client.report_heartbeat(message).await {
+            error!(target: "worker::batch_provider", "Failed to report heartbeat: {err:?}");
+        }
+        self.record_report();
+    }
+
+    /// Record that the primary just heard from this worker.
+    fn record_report(&self) {
+        *self.last_report.lock().expect("last report lock") = Instant::now();
+    }
+
     /// Seal and broadcast the current batch.
     pub async fn seal(&self, sealed_batch: SealedBatch) -> Result<(), BlockSealError> {
         let size = sealed_batch.size();
@@ -229,14 +410,20 @@ impl<DB: Database, QW: QuorumWaiterTrait> Worker<DB, QW> {
         // Now save it to disk
         let (batch, digest) = sealed_batch.split();
 
-        if let Err(e) = self.store.insert::<Batches>(&digest, &batch) {
+        if let Err(e) = self.store.write_batch(&digest, &batch) {
             error!(target: "worker::batch_provider", "Store failed with error: {:?}", e);
             return Err(BlockSealError::FatalDBFailure);
         }
+        self.node_metrics.batches_sealed.inc();
+
+        if let Some(inclusion_promises) = &self.inclusion_promises {
+            inclusion_promises.record_batch(&batch, digest);
+        }
 
         // Send the batch to the primary.
         let message =
             WorkerOwnBatchMessage { worker_id: self.id, digest, timestamp: batch.created_at() };
+        self.record_report();
         if let Err(err) = self.client.report_own_batch(message).await {
             error!(target: "worker::batch_provider", "Failed to report our batch: {err:?}");
             // Should we return an error here?  Doing so complicates some tests but also the batch
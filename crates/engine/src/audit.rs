@@ -0,0 +1,223 @@
+//! Trace every executed transaction for forensic analysis.
+//!
+//! Nodes started with `--audit` execute each transaction with the revm tracing inspector and hand
+//! the call frames and state changes of every block to an [AuditSink]. Traces are recorded after a
+//! block is built and before it is inserted into the tree, so a trace exists for every executed
+//! block even if the node stops right after. Recording never fails execution: sink errors are
+//! logged and the block is still executed.
+//!
+//! The [FileAuditSink] appends blocks as JSON lines to gzip files in a directory. Each block is a
+//! separate gzip member so files can be read while the node writes them, ie with `zcat`.
+
+use alloy_rpc_types_trace::geth::{CallConfig, CallFrame};
+use flate2::{write::GzEncoder, Compression};
+use reth_revm::primitives::ResultAndState;
+use revm_inspectors::tracing::{TracingInspector, TracingInspectorConfig};
+use serde::Serialize;
+use std::{
+    collections::BTreeMap,
+    fmt,
+    fs::OpenOptions,
+    io::{self, BufWriter, Write as _},
+    path::PathBuf,
+};
+use tn_types::{Address, BlockHash, TxHash, B256, U256};
+
+/// The number of blocks written to each audit file.
+pub const AUDIT_BLOCKS_PER_FILE: u64 = 10_000;
+
+/// The traces of the transactions executed in one block.
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditedBlock {
+    /// The block number.
+    pub number: u64,
+    /// The block hash.
+    pub hash: BlockHash,
+    /// The digest of the batch executed in the block.
+    pub batch_digest: B256,
+    /// The hash of the consensus header for the output that included the batch.
+    pub consensus_header_hash: B256,
+    /// The executed transactions in block order.
+    pub transactions: Vec<TransactionAudit>,
+}
+
+/// The trace of one executed transaction.
+#[derive(Debug, Clone, Serialize)]
+pub struct TransactionAudit {
+    /// The transaction hash.
+    pub tx_hash: TxHash,
+    /// The recovered sender.
+    pub sender: Address,
+    /// True if the transaction did not revert.
+    pub success: bool,
+    /// The gas used by the transaction.
+    pub gas_used: u64,
+    /// The call frames of the transaction, with logs.
+    pub calls: CallFrame,
+    /// The accounts changed by the transaction.
+    pub state_diff: BTreeMap<Address, AccountDiff>,
+}
+
+/// An account changed by a transaction.
+#[derive(Debug, Clone, Serialize)]
+pub struct AccountDiff {
+    /// The balance after the transaction.
+    pub balance: U256,
+    /// The nonce after the transaction.
+    pub nonce: u64,
+    /// The code hash after the transaction.
+    pub code_hash: B256,
+    /// True if the account self-destructed.
+    pub destroyed: bool,
+    /// The storage slots written by the transaction.
+    pub storage: BTreeMap<U256, StorageDiff>,
+}
+
+/// A storage slot written by a transaction.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct StorageDiff {
+    /// The value before the transaction.
+    pub original: U256,
+    /// The value after the transaction.
+    pub present: U256,
+}
+
+/// Destination for the traces of executed blocks.
+///
+/// Implement this to stream traces to an external system instead of the [FileAuditSink].
+pub trait AuditSink: fmt::Debug + Send + Sync + 'static {
+    /// Record the traces for an executed block.
+    ///
+    /// Called on the blocking thread that executes consensus output, in block order.
+    fn record(&self, block: &AuditedBlock) -> io::Result<()>;
+}
+
+/// Append audited blocks to gzip compressed JSON lines files.
+///
+/// Blocks are grouped into files of [AUDIT_BLOCKS_PER_FILE] named by the first block number in
+/// the file, ie `audit-000000010000.jsonl.gz`.
+#[derive(Debug, Clone)]
+pub struct FileAuditSink {
+    /// The directory for audit files.
+    dir: PathBuf,
+}
+
+impl FileAuditSink {
+    /// Create a sink writing to `dir`, creating the directory if it does not exist.
+    pub fn new(dir: PathBuf) -> io::Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+        Ok(Self { dir })
+    }
+
+    /// The path of the file for a block.
+    pub fn file_for_block(&self, number: u64) -> PathBuf {
+        let first = number - number % AUDIT_BLOCKS_PER_FILE;
+        self.dir.join(format!("audit-{first:012}.jsonl.gz"))
+    }
+}
+
+impl AuditSink for FileAuditSink {
+    fn record(&self, block: &AuditedBlock) -> io::Result<()> {
+        let file =
+            OpenOptions::new().create(true).append(true).open(self.file_for_block(block.number))?;
+        let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+        serde_json::to_writer(&mut encoder, block)?;
+        encoder.write_all(b"\n")?;
+        encoder.finish()?.flush()
+    }
+}
+
+/// Create the inspector used to trace transactions.
+pub(crate) fn audit_inspector() -> TracingInspector {
+    TracingInspector::new(TracingInspectorConfig::from_geth_call_config(&call_config()))
+}
+
+/// Call tracer options for audit traces.
+fn call_config() -> CallConfig {
+    CallConfig::default().with_log()
+}
+
+impl TransactionAudit {
+    /// Take the trace of an executed transaction and reset the inspector for the next one.
+    pub(crate) fn take(
+        inspector: &mut TracingInspector,
+        tx_hash: TxHash,
+        sender: Address,
+        result: &ResultAndState,
+    ) -> Self {
+        let gas_used = result.result.gas_used();
+        let calls = inspector.geth_builder().geth_call_traces(call_config(), gas_used);
+        inspector.fuse();
+
+        let state_diff = result
+            .state
+            .iter()
+            .filter(|(_, account)| account.is_touched())
+            .map(|(address, account)| {
+                let storage = account
+                    .storage
+                    .iter()
+                    .filter(|(_, slot)| slot.is_changed())
+                    .map(|(key, slot)| {
+                        let diff = StorageDiff {
+                            original: slot.original_value(),
+                            present: slot.present_value(),
+                        };
+                        (*key, diff)
+                    })
+                    .collect();
+                let diff = AccountDiff {
+                    balance: account.info.balance,
+                    nonce: account.info.nonce,
+                    code_hash: account.info.code_hash,
+                    destroyed: account.is_selfdestructed(),
+                    storage,
+                };
+                (*address, diff)
+            })
+            .collect();
+
+        Self { tx_hash, sender, success: result.result.is_success(), gas_used, calls, state_diff }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::MultiGzDecoder;
+    use std::io::{BufRead as _, BufReader};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_file_audit_sink() {
+        let dir = TempDir::new().unwrap();
+        let sink = FileAuditSink::new(dir.path().join("audit")).unwrap();
+        let block = |number| AuditedBlock {
+            number,
+            hash: BlockHash::random(),
+            batch_digest: B256::random(),
+            consensus_header_hash: B256::random(),
+            transactions: vec![],
+        };
+
+        // blocks in the same range are appended to the same file
+        sink.record(&block(AUDIT_BLOCKS_PER_FILE)).unwrap();
+        sink.record(&block(AUDIT_BLOCKS_PER_FILE + 1)).unwrap();
+        sink.record(&block(AUDIT_BLOCKS_PER_FILE * 2)).unwrap();
+        assert_eq!(
+            sink.file_for_block(AUDIT_BLOCKS_PER_FILE + 1),
+            dir.path().join("audit/audit-000000010000.jsonl.gz")
+        );
+
+        let file = std::fs::File::open(sink.file_for_block(AUDIT_BLOCKS_PER_FILE)).unwrap();
+        let numbers: Vec<u64> = BufReader::new(MultiGzDecoder::new(file))
+            .lines()
+            .map(|line| {
+                let block: serde_json::Value = serde_json::from_str(&line.unwrap()).unwrap();
+                block["number"].as_u64().unwrap()
+            })
+            .collect();
+        assert_eq!(numbers, vec![AUDIT_BLOCKS_PER_FILE, AUDIT_BLOCKS_PER_FILE + 1]);
+        assert!(sink.file_for_block(AUDIT_BLOCKS_PER_FILE * 2).exists());
+    }
+}
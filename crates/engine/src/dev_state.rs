@@ -0,0 +1,60 @@
+//! Apply state changes queued through the dev RPC.
+//!
+//! Changes are committed to the block's state before its transactions, so they are part of the
+//! block's state root like any other state transition.
+
+use crate::error::EngineResult;
+use reth_provider::ProviderError;
+use reth_revm::{
+    primitives::{Account, Bytecode, EvmState, EvmStorage, EvmStorageSlot},
+    Database, DatabaseCommit as _, State,
+};
+use std::collections::BTreeMap;
+use tn_types::{AccountOverride, Address};
+use tracing::info;
+
+/// Commit the queued account changes to the block's state.
+pub(crate) fn apply_dev_state_changes<DB>(
+    db: &mut State<DB>,
+    changes: BTreeMap<Address, AccountOverride>,
+) -> EngineResult<()>
+where
+    DB: Database<Error = ProviderError>,
+{
+    if changes.is_empty() {
+        return Ok(());
+    }
+
+    info!(target: "engine", accounts = changes.len(), "applying dev state changes");
+    let mut state = EvmState::default();
+    for (address, changes) in changes {
+        let mut info = db.basic(address)?.unwrap_or_default();
+        if let Some(balance) = changes.balance {
+            info.balance = balance;
+        }
+        if let Some(nonce) = changes.nonce {
+            info.nonce = nonce;
+        }
+        if let Some(code) = changes.code {
+            let bytecode =
+                if code.is_empty() { Bytecode::default() } else { Bytecode::new_raw(code) };
+            info.code_hash = bytecode.hash_slow();
+            info.code = Some(bytecode);
+        }
+
+        let mut storage = EvmStorage::default();
+        for (slot, value) in changes.storage {
+            let original = db.storage(address, slot)?;
+            storage.insert(slot, EvmStorageSlot::new_changed(original, value));
+        }
+
+        // empty accounts are removed when the changes are committed (EIP-161)
+        let mut account = Account::from(info);
+        account.storage = storage;
+        account.mark_touch();
+        state.insert(address, account);
+    }
+
+    db.commit(state);
+    Ok(())
+}
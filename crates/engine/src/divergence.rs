@@ -0,0 +1,98 @@
+//! Detect execution results that disagree with the committee.
+//!
+//! Certified headers include the latest block their author executed. Votes are only cast once the
+//! voter executed the same block, so a certified block hash is agreed on by a quorum. If this node
+//! executed a different block at the same height, its state forked from the committee and any
+//! further blocks would build on the fork. The engine stops instead, leaving a diagnostic dump for
+//! operators.
+
+use crate::error::{EngineResult, TnEngineError};
+use reth_provider::HeaderProvider;
+use serde::Serialize;
+use std::path::Path;
+use tn_types::{
+    AuthorityIdentifier, CertificateDigest, ConsensusOutput, ExecHeader, Hash as _, Round,
+    SealedHeader, B256,
+};
+use tracing::{error, warn};
+
+/// A block this node executed that disagrees with a block certified by the committee.
+#[derive(Debug, Clone, Serialize)]
+pub struct ExecutionDivergence {
+    /// The number of the consensus output that included the certificate.
+    pub sub_dag: u64,
+    /// The digest of the certificate with the conflicting block.
+    pub certificate: CertificateDigest,
+    /// The author of the certificate.
+    pub author: AuthorityIdentifier,
+    /// The round of the certificate.
+    pub round: Round,
+    /// The number of the conflicting block.
+    pub block_number: u64,
+    /// The block hash certified by the committee.
+    pub certified_hash: B256,
+    /// The hash of the block this node executed at the same height.
+    pub local_hash: B256,
+    /// The state root of the block this node executed at the same height.
+    pub local_state_root: B256,
+    /// The header of the block this node executed at the same height.
+    pub local_header: ExecHeader,
+}
+
+/// Compare the blocks certified in the output with the blocks this node executed.
+///
+/// Only blocks at or below the canonical tip can be compared. Certificates without an executed
+/// block (the hash is zero) are ignored.
+pub(crate) fn check_execution_divergence<Provider>(
+    provider: &Provider,
+    output: &ConsensusOutput,
+    canonical_header: &SealedHeader,
+) -> EngineResult<()>
+where
+    Provider: HeaderProvider<Header = ExecHeader>,
+{
+    for cert in output.sub_dag.certificates.iter() {
+        let certified = cert.header.latest_execution_block;
+        if certified.hash == B256::ZERO || certified.number > canonical_header.number {
+            continue;
+        }
+
+        // a missing block is reported when finalizing
+        let Some(local) = provider.sealed_header(certified.number)? else {
+            continue;
+        };
+
+        if local.hash() != certified.hash {
+            let divergence = ExecutionDivergence {
+                sub_dag: output.number,
+                certificate: cert.digest(),
+                author: cert.origin().clone(),
+                round: cert.round(),
+                block_number: certified.number,
+                certified_hash: certified.hash,
+                local_hash: local.hash(),
+                local_state_root: local.state_root,
+                local_header: local.header().clone(),
+            };
+            return Err(TnEngineError::ExecutionDivergence(Box::new(divergence)));
+        }
+    }
+
+    Ok(())
+}
+
+/// Write the divergence to `divergence-{sub_dag}.json` in `dir` for operators to investigate.
+///
+/// Failing to write the dump is logged and otherwise ignored, the engine halts either way.
+pub(crate) fn dump_divergence(dir: &Path, divergence: &ExecutionDivergence) {
+    let path = dir.join(format!("divergence-{}.json", divergence.sub_dag));
+    let res = std::fs::create_dir_all(dir)
+        .map_err(|e| e.to_string())
+        .and_then(|_| serde_json::to_vec_pretty(divergence).map_err(|e| e.to_string()))
+        .and_then(|json| std::fs::write(&path, json).map_err(|e| e.to_string()));
+
+    match res {
+        Ok(()) => error!(target: "engine", ?path, "execution divergence written to file"),
+        Err(e) => warn!(target: "engine", ?path, ?e, "failed to write execution divergence"),
+    }
+}
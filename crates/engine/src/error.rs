@@ -1,9 +1,10 @@
 //! Error types for Telcoin Network Engine.
 
+use crate::ExecutionDivergence;
 use reth_blockchain_tree::error::InsertBlockError;
 use reth_errors::{CanonicalError, ProviderError, RethError};
 use reth_revm::primitives::EVMError;
-use reth_rpc_eth_types::EthApiError;
+use tn_types::SenderRecoveryError;
 use tokio::sync::oneshot;
 
 /// Result alias for [`TNEngineError`].
@@ -23,7 +24,7 @@ pub enum TnEngineError {
     EvmExecution(#[from] EVMError<ProviderError>),
     /// Error recovering transaction from bytes.
     #[error(transparent)]
-    RecoverTransactionBytes(#[from] EthApiError),
+    RecoverTransactionBytes(#[from] SenderRecoveryError),
     /// The next block digest is missing.
     #[error("Missing next block digest for recovered sealed block with senders.")]
     NextBlockDigestMissing,
@@ -48,6 +49,14 @@ pub enum TnEngineError {
     // Failed to find the block we need to finalize- forked?.
     #[error("Could not finalize execution block- forked?")]
     MissingFinalBlock,
+    /// A block executed by this node disagrees with a block certified by the committee.
+    #[error(
+        "execution diverged from the committee at block {}: certified {} but executed {}",
+        .0.block_number,
+        .0.certified_hash,
+        .0.local_hash
+    )]
+    ExecutionDivergence(Box<ExecutionDivergence>),
 }
 
 impl From<oneshot::error::RecvError> for TnEngineError {
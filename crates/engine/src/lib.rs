@@ -18,12 +18,25 @@
 #![deny(unused_must_use, rust_2018_idioms)]
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 
+mod audit;
+mod dev_state;
+mod divergence;
 mod error;
+mod metrics;
 mod payload_builder;
+mod system_calls;
+use crate::metrics::ExecutionMetrics;
+pub use audit::{
+    AccountDiff, AuditSink, AuditedBlock, FileAuditSink, StorageDiff, TransactionAudit,
+    AUDIT_BLOCKS_PER_FILE,
+};
+use consensus_metrics::CommitStage;
+use divergence::dump_divergence;
+pub use divergence::ExecutionDivergence;
 use error::{EngineResult, TnEngineError};
 use futures::{Future, StreamExt};
 use futures_util::FutureExt;
-pub use payload_builder::execute_consensus_output;
+pub use payload_builder::{execute_consensus_output, execute_consensus_output_with_audit};
 use reth_blockchain_tree::BlockchainTreeEngine;
 use reth_chainspec::ChainSpec;
 use reth_evm::ConfigureEvm;
@@ -33,14 +46,20 @@ use reth_provider::{
 };
 use std::{
     collections::VecDeque,
+    path::PathBuf,
     pin::{pin, Pin},
+    sync::Arc,
     task::{Context, Poll},
+    time::Instant,
 };
 use tn_node_traits::BuildArguments;
-use tn_types::{ConsensusOutput, ExecHeader, Noticer, SealedHeader, TransactionSigned};
-use tokio::sync::oneshot;
+use tn_types::{
+    BatchGasSchedule, ConsensusOutput, DevStateChanges, ExecHeader, Forks, Noticer, SealedHeader,
+    SenderRecovery, SystemCall, TimestampPolicy, TransactionSigned,
+};
+use tokio::sync::{oneshot, watch};
 use tokio_stream::wrappers::BroadcastStream;
-use tracing::{error, info, trace, warn};
+use tracing::{error, info, info_span, trace, warn};
 
 /// Type alias for the blocking task that executes consensus output and returns the finalized
 /// `SealedHeader`.
@@ -56,8 +75,8 @@ type PendingExecutionTask = oneshot::Receiver<EngineResult<SealedHeader>>;
 /// remaining output that is queued up before shutting itself down gracefully. If the maximum round
 /// is reached, the engine shuts down immediately.
 pub struct ExecutorEngine<BT, CE> {
-    /// The backlog of output from consensus that's ready to be executed.
-    queued: VecDeque<ConsensusOutput>,
+    /// The backlog of output from consensus that's ready to be executed and when it was received.
+    queued: VecDeque<(ConsensusOutput, Instant)>,
     /// Single active future that executes consensus output on a blocking thread and then returns
     /// the result through a oneshot channel.
     pending_task: Option<PendingExecutionTask>,
@@ -71,6 +90,13 @@ pub struct ExecutorEngine<BT, CE> {
     ///
     /// NOTE: this is primarily useful for debugging and testing
     max_round: Option<u64>,
+    /// The consensus output number (one per committed subdag) to halt at once it is executed.
+    ///
+    /// Unlike `max_round`, the target can change while the engine runs. Operators use this to stop
+    /// validators at the same point of consensus before an upgrade.
+    halt_at_sub_dag: watch::Receiver<Option<u64>>,
+    /// The number of the consensus output that is currently executing.
+    pending_output_number: Option<u64>,
     /// Receiving end from CL's `Executor`. The `ConsensusOutput` is sent
     /// to the mining task here.
     consensus_output_stream: BroadcastStream<ConsensusOutput>,
@@ -80,6 +106,24 @@ pub struct ExecutorEngine<BT, CE> {
     parent_header: SealedHeader,
     /// Used to receive shutdown notification.
     rx_shutdown: Noticer,
+    /// The directory to write diagnostics to if execution diverges from the committee.
+    divergence_dump_dir: Option<PathBuf>,
+    /// Trace every executed transaction to this sink.
+    audit_sink: Option<Arc<dyn AuditSink>>,
+    /// Recovers the senders of executed transactions, shared with batch validation.
+    sender_recovery: SenderRecovery,
+    /// How executed blocks are timestamped, from the chain spec.
+    timestamp_policy: TimestampPolicy,
+    /// The epochs that activate protocol changes, from the chain spec.
+    forks: Forks,
+    /// Contract calls made at the start and end of each sub-dag, from the chain spec.
+    system_calls: Vec<SystemCall>,
+    /// How the gas limit of executed blocks is set, from the chain spec.
+    batch_gas: BatchGasSchedule,
+    /// State changes queued through the dev RPC, only set for single-node dev networks.
+    dev_state: Option<DevStateChanges>,
+    /// Metrics for execution.
+    metrics: ExecutionMetrics,
 }
 
 impl<BT, CE> ExecutorEngine<BT, CE>
@@ -114,9 +158,102 @@ where
             blockchain,
             evm_config,
             max_round,
+            halt_at_sub_dag: watch::channel(None).1,
+            pending_output_number: None,
             consensus_output_stream,
             parent_header,
             rx_shutdown,
+            divergence_dump_dir: None,
+            audit_sink: None,
+            sender_recovery: SenderRecovery::default(),
+            timestamp_policy: TimestampPolicy::default(),
+            forks: Forks::NONE,
+            system_calls: Vec::new(),
+            batch_gas: BatchGasSchedule::default(),
+            dev_state: None,
+            metrics: ExecutionMetrics::default(),
+        }
+    }
+
+    /// Halt the engine after executing the consensus output number sent on the watch channel.
+    pub fn with_halt_at_sub_dag(mut self, halt_at_sub_dag: watch::Receiver<Option<u64>>) -> Self {
+        self.halt_at_sub_dag = halt_at_sub_dag;
+        self
+    }
+
+    /// Write diagnostics to `dir` if execution diverges from the committee.
+    pub fn with_divergence_dump_dir(mut self, dir: PathBuf) -> Self {
+        self.divergence_dump_dir = Some(dir);
+        self
+    }
+
+    /// Trace every executed transaction and record the traces to `sink`.
+    pub fn with_audit_sink(mut self, sink: Arc<dyn AuditSink>) -> Self {
+        self.audit_sink = Some(sink);
+        self
+    }
+
+    /// Recover the senders of executed transactions with `sender_recovery`.
+    pub fn with_sender_recovery(mut self, sender_recovery: SenderRecovery) -> Self {
+        self.sender_recovery = sender_recovery;
+        self
+    }
+
+    /// Timestamp executed blocks with `timestamp_policy`.
+    ///
+    /// Every node must use the same policy or execution diverges. The policy only applies from
+    /// the epoch that activates it in [Self::with_forks].
+    pub fn with_timestamp_policy(mut self, timestamp_policy: TimestampPolicy) -> Self {
+        self.timestamp_policy = timestamp_policy;
+        self
+    }
+
+    /// Activate protocol changes at the epochs in `forks`.
+    pub fn with_forks(mut self, forks: Forks) -> Self {
+        self.forks = forks;
+        self
+    }
+
+    /// Make `system_calls` at the start and end of each executed sub-dag.
+    ///
+    /// Every node must make the same calls or execution diverges.
+    pub fn with_system_calls(mut self, system_calls: Vec<SystemCall>) -> Self {
+        self.system_calls = system_calls;
+        self
+    }
+
+    /// Set the gas limit of executed blocks with `batch_gas` once [Forks::batch_gas] activates.
+    ///
+    /// Every node must use the same schedule or execution diverges.
+    pub fn with_batch_gas(mut self, batch_gas: BatchGasSchedule) -> Self {
+        self.batch_gas = batch_gas;
+        self
+    }
+
+    /// Apply state changes queued through the dev RPC before executing each output.
+    ///
+    /// Only single-node dev networks may set this, since other nodes would not apply the changes.
+    pub fn with_dev_state(mut self, dev_state: DevStateChanges) -> Self {
+        self.dev_state = Some(dev_state);
+        self
+    }
+
+    /// Raise alerts for execution that diverged from the committee.
+    ///
+    /// The engine returns the error afterwards, halting block production.
+    fn report_divergence(&self, divergence: &ExecutionDivergence) {
+        error!(
+            target: "engine",
+            sub_dag = divergence.sub_dag,
+            block = divergence.block_number,
+            certified = ?divergence.certified_hash,
+            local = ?divergence.local_hash,
+            author = ?divergence.author,
+            "execution diverged from the committee - halting block production"
+        );
+        self.metrics.execution_divergence.set(divergence.block_number as f64);
+        if let Some(dir) = self.divergence_dump_dir.as_ref() {
+            dump_divergence(dir, divergence);
         }
     }
 
@@ -136,17 +273,44 @@ where
         let (tx, rx) = oneshot::channel();
 
         // pop next output in queue and execute
-        if let Some(output) = self.queued.pop_front() {
+        if let Some((output, received_at)) = self.queued.pop_front() {
+            self.pending_output_number = Some(output.number);
             let provider = self.blockchain.clone();
             let evm_config = self.evm_config.clone();
             let parent = self.parent_header.clone();
-            let build_args = BuildArguments::new(provider, output, parent);
+            let audit_sink = self.audit_sink.clone();
+            let span = info_span!(
+                target: "engine",
+                "execute_output",
+                round = output.leader_round(),
+                sub_dag_index = output.nonce()
+            );
+            let timestamp_policy_active = self.forks.timestamp_policy_active(output.leader_epoch());
+            let batch_gas_active = self.forks.batch_gas_active(output.leader_epoch());
+            let mut build_args = BuildArguments::new(provider, output, parent)
+                .with_sender_recovery(self.sender_recovery.clone())
+                .with_system_calls(self.system_calls.clone());
+            if timestamp_policy_active {
+                build_args = build_args.with_timestamp_policy(self.timestamp_policy);
+            }
+            if batch_gas_active {
+                build_args = build_args.with_batch_gas(self.batch_gas);
+            }
+            if let Some(dev_state) = self.dev_state.clone() {
+                build_args = build_args.with_dev_state(dev_state);
+            }
 
             // spawn blocking task and return future
             tokio::task::spawn_blocking(move || {
+                let _span = span.entered();
                 // this is safe to call on blocking thread without a semaphore bc it's held in
                 // Self::pending_tesk as a single `Option`
-                let result = execute_consensus_output(&evm_config, build_args);
+                let result = execute_consensus_output_with_audit(
+                    &evm_config,
+                    build_args,
+                    audit_sink.as_deref(),
+                );
+                CommitStage::OutputToExecution.observe(received_at.elapsed());
                 match tx.send(result) {
                     Ok(()) => (),
                     Err(e) => {
@@ -162,6 +326,11 @@ where
         rx
     }
 
+    /// Queue output from consensus for execution.
+    fn queue_output(&mut self, output: ConsensusOutput) {
+        self.queued.push_back((output, Instant::now()));
+    }
+
     /// Check if the engine has reached the maximum round of consensus as specified by `max_round`
     /// parameter.
     ///
@@ -179,6 +348,16 @@ where
         }
         has_reached_max_round
     }
+
+    /// Check if the engine has executed the consensus output operators requested to halt at.
+    fn has_reached_halt(&self, progress: u64) -> bool {
+        let halt_at = *self.halt_at_sub_dag.borrow();
+        let has_reached_halt = halt_at.is_some_and(|target| progress >= target);
+        if has_reached_halt {
+            info!(target: "engine", ?progress, ?halt_at, "Consensus engine reached halt target");
+        }
+        has_reached_halt
+    }
 }
 
 /// The [ExecutorEngine] is a future that loops through the following:
@@ -229,7 +408,7 @@ where
             match this.consensus_output_stream.poll_next_unpin(cx) {
                 Poll::Ready(Some(Ok(output))) => {
                     // queue the output for local execution
-                    this.queued.push_back(output)
+                    this.queue_output(output)
                 }
                 Poll::Ready(Some(Err(e))) => {
                     error!(target: "engine", ?e, "for consensus output stream");
@@ -270,7 +449,12 @@ where
             if let Some(mut receiver) = this.pending_task.take() {
                 match receiver.poll_unpin(cx) {
                     Poll::Ready(res) => {
-                        let finalized_header = res.map_err(Into::into).and_then(|res| res)?;
+                        let finalized_header =
+                            res.map_err(Into::into).and_then(|res| res).inspect_err(|e| {
+                                if let TnEngineError::ExecutionDivergence(divergence) = e {
+                                    this.report_divergence(divergence);
+                                }
+                            })?;
                         // store last executed header in memory
                         this.parent_header = finalized_header;
 
@@ -282,6 +466,13 @@ where
                             return Poll::Ready(Ok(()));
                         }
 
+                        // stop before executing queued output beyond the requested subdag
+                        if let Some(executed) = this.pending_output_number.take() {
+                            if this.has_reached_halt(executed) {
+                                return Poll::Ready(Ok(()));
+                            }
+                        }
+
                         // allow loop to continue: poll broadcast stream for next output
                     }
                     Poll::Pending => {
@@ -305,6 +496,7 @@ impl<BT, CE> std::fmt::Debug for ExecutorEngine<BT, CE> {
             .field("queued", &self.queued.len())
             .field("pending_task", &self.pending_task.is_some())
             .field("max_round", &self.max_round)
+            .field("halt_at_sub_dag", &*self.halt_at_sub_dag.borrow())
             .field("parent_header", &self.parent_header)
             .finish_non_exhaustive()
     }
@@ -315,18 +507,24 @@ mod tests {
     use crate::ExecutorEngine;
     use reth_blockchain_tree::BlockchainTreeViewer;
     use reth_chainspec::ChainSpec;
-    use reth_provider::{BlockIdReader, BlockNumReader, BlockReader, TransactionVariant};
+    use reth_provider::{
+        BlockIdReader, BlockNumReader, BlockReader, StateProviderFactory as _, TransactionVariant,
+    };
     use reth_revm::primitives::FixedBytes;
     use std::{collections::VecDeque, str::FromStr as _, sync::Arc, time::Duration};
     use tn_batch_builder::test_utils::execute_test_batch;
     use tn_test_utils::{default_test_execution_node, seeded_genesis_from_random_batches};
     use tn_types::{
-        adiri_chain_spec_arc, adiri_genesis, max_batch_gas, now, Address, BlockHash,
-        BlockHashOrNumber, Bloom, Certificate, CommittedSubDag, ConsensusHeader, ConsensusOutput,
-        Hash as _, Notifier, ReputationScores, TaskManager, B256, EMPTY_OMMER_ROOT_HASH,
-        EMPTY_WITHDRAWALS, MIN_PROTOCOL_BASE_FEE, U256,
+        adiri_chain_spec_arc, adiri_genesis, calculate_withdrawals_root, max_batch_gas, now,
+        Address, BlockHash, BlockHashOrNumber, Bloom, Certificate, CommittedSubDag,
+        ConsensusHeader, ConsensusOutput, Hash as _, Notifier, ReputationScores, TaskManager,
+        Withdrawal, Withdrawals, B256, EMPTY_OMMER_ROOT_HASH, EMPTY_WITHDRAWALS,
+        MIN_PROTOCOL_BASE_FEE, U256,
+    };
+    use tokio::{
+        sync::{oneshot, watch},
+        time::timeout,
     };
-    use tokio::{sync::oneshot, time::timeout};
     use tokio_stream::wrappers::BroadcastStream;
     use tracing::debug;
 
@@ -363,6 +561,7 @@ mod tests {
             number: 0,
             extra: Default::default(),
             early_finalize: true,
+            withdrawals: Default::default(),
         };
         let consensus_output_hash = consensus_output.consensus_header_hash();
 
@@ -485,12 +684,80 @@ mod tests {
         assert_eq!(expected_block.extra_data.as_ref(), &[0; 32]);
         // assert withdrawals are empty
         //
-        // NOTE: this is currently always empty
+        // NOTE: this output has no withdrawals
         assert_eq!(expected_block.withdrawals_root, genesis_header.withdrawals_root);
 
         Ok(())
     }
 
+    /// This tests that withdrawals from consensus output are credited per EIP-4895.
+    #[tokio::test]
+    async fn test_output_withdrawals_are_applied() -> eyre::Result<()> {
+        let recipient = Address::with_last_byte(0x42);
+        let withdrawals = Withdrawals::new(vec![
+            Withdrawal { index: 0, validator_index: 1, address: recipient, amount: 1 },
+            Withdrawal { index: 1, validator_index: 1, address: recipient, amount: 2 },
+        ]);
+        let consensus_output = ConsensusOutput {
+            sub_dag: CommittedSubDag::new(
+                vec![Certificate::default()],
+                Certificate::default(),
+                0,
+                ReputationScores::default(),
+                None,
+            )
+            .into(),
+            batches: Default::default(), // empty
+            beneficiary: Address::with_last_byte(0x55),
+            batch_digests: Default::default(), // empty
+            parent_hash: ConsensusHeader::default().digest(),
+            number: 0,
+            extra: Default::default(),
+            early_finalize: true,
+            withdrawals: withdrawals.clone(),
+        };
+
+        let chain = adiri_chain_spec_arc();
+        let execution_node = default_test_execution_node(Some(chain.clone()), None)?;
+        let (to_engine, from_consensus) = tokio::sync::broadcast::channel(1);
+        let provider = execution_node.get_provider().await;
+        let evm_config = execution_node.get_evm_config().await;
+        let shutdown = Notifier::default();
+        let engine = ExecutorEngine::new(
+            provider.clone(),
+            evm_config,
+            None,
+            BroadcastStream::from(from_consensus),
+            chain.sealed_genesis_header(),
+            shutdown.subscribe(),
+        );
+
+        // send output and drop sending channel to shut engine down
+        to_engine.send(consensus_output)?;
+        drop(to_engine);
+
+        let (tx, rx) = oneshot::channel();
+        TaskManager::default().spawn_blocking(Box::pin(async move {
+            let res = engine.await;
+            let _ = tx.send(res);
+        }));
+        let engine_task = timeout(Duration::from_secs(10), rx).await?;
+        assert!(engine_task.is_ok());
+
+        let block = provider
+            .block_with_senders(BlockHashOrNumber::Number(1), TransactionVariant::NoHash)?
+            .expect("block 1 successfully executed");
+        assert_eq!(block.body.withdrawals, Some(withdrawals.clone()));
+        assert_eq!(block.withdrawals_root, Some(calculate_withdrawals_root(&withdrawals)));
+        assert_ne!(block.withdrawals_root, Some(EMPTY_WITHDRAWALS));
+
+        // amounts are denominated in gwei
+        let balance = provider.latest()?.account_balance(recipient)?;
+        assert_eq!(balance, Some(U256::from(3_000_000_000u64)));
+
+        Ok(())
+    }
+
     /// This tests that a single block is NOT executed if the output from consensus contains no
     /// transactions and we are not setting early finalize.
     #[tokio::test]
@@ -524,6 +791,7 @@ mod tests {
             number: 0,
             extra: Default::default(),
             early_finalize: false,
+            withdrawals: Default::default(),
         };
 
         let chain = adiri_chain_spec_arc();
@@ -568,14 +836,17 @@ mod tests {
 
         let last_block_num = provider.last_block_number()?;
         let canonical_tip = provider.canonical_tip();
-        let final_block = provider.finalized_block_num_hash()?;
-        assert!(final_block.is_none());
+        let final_block = provider.finalized_block_num_hash()?.expect("finalized block");
+        let safe_block = provider.safe_block_num_hash()?.expect("safe block");
 
         let expected_block_height = 1;
         // assert 1 empty block was executed for consensus
         assert_eq!(last_block_num, expected_block_height);
         assert_eq!(canonical_tip.number, expected_block_height);
-        // assert last executed output is not finalized
+        // assert the block tags follow the committed output
+        assert_eq!(canonical_tip, final_block);
+        assert_eq!(canonical_tip, safe_block);
+        // assert last executed output is not finalized in the database
         let last_output = execution_node.last_executed_output().await?;
         assert_eq!(last_output, BlockHash::default());
         Ok(())
@@ -687,6 +958,7 @@ mod tests {
             number: 0,
             extra: Default::default(),
             early_finalize: true,
+            withdrawals: Default::default(),
         };
 
         // create second output
@@ -717,6 +989,7 @@ mod tests {
             number: 1,
             extra: Default::default(),
             early_finalize: true,
+            withdrawals: Default::default(),
         };
         let consensus_output_2_hash = consensus_output_2.consensus_header_hash();
 
@@ -744,7 +1017,7 @@ mod tests {
         );
 
         // queue the first output - simulate already received from channel
-        engine.queued.push_back(consensus_output_1.clone());
+        engine.queue_output(consensus_output_1.clone());
 
         // send second output
         let broadcast_result = to_engine.send(consensus_output_2.clone());
@@ -877,7 +1150,7 @@ mod tests {
             assert_eq!(&block.extra_data, all_batch_digests[idx].as_slice());
             // assert batch's withdrawals match
             //
-            // NOTE: this is currently always empty
+            // NOTE: this output has no withdrawals
             assert_eq!(block.withdrawals_root, Some(EMPTY_WITHDRAWALS));
         }
 
@@ -1017,6 +1290,7 @@ mod tests {
             number: 0,
             extra: Default::default(),
             early_finalize: true,
+            withdrawals: Default::default(),
         };
 
         // create second output
@@ -1049,6 +1323,7 @@ mod tests {
             number: 1,
             extra: Default::default(),
             early_finalize: true,
+            withdrawals: Default::default(),
         };
         let consensus_output_2_hash = consensus_output_2.consensus_header_hash();
 
@@ -1076,7 +1351,7 @@ mod tests {
         );
 
         // queue the first output - simulate already received from channel
-        engine.queued.push_back(consensus_output_1.clone());
+        engine.queue_output(consensus_output_1.clone());
 
         // send second output
         let broadcast_result = to_engine.send(consensus_output_2.clone());
@@ -1224,7 +1499,7 @@ mod tests {
             assert_eq!(&block.extra_data, all_batch_digests[idx].as_slice());
             // assert batch's withdrawals match
             //
-            // NOTE: this is currently always empty
+            // NOTE: this output has no withdrawals
             assert_eq!(block.withdrawals_root, Some(EMPTY_WITHDRAWALS));
         }
 
@@ -1320,6 +1595,7 @@ mod tests {
             number: 0,
             extra: Default::default(),
             early_finalize: true,
+            withdrawals: Default::default(),
         };
         let consensus_output_1_hash = consensus_output_1.consensus_header_hash();
 
@@ -1351,6 +1627,7 @@ mod tests {
             number: 1,
             extra: Default::default(),
             early_finalize: true,
+            withdrawals: Default::default(),
         };
 
         //=== Execution
@@ -1374,8 +1651,8 @@ mod tests {
         );
 
         // queue both output - simulate already received from channel
-        engine.queued.push_back(consensus_output_1);
-        engine.queued.push_back(consensus_output_2);
+        engine.queue_output(consensus_output_1);
+        engine.queue_output(consensus_output_2);
 
         // NOTE: sending channel is NOT dropped in this test, so engine should continue listening
         // until max block reached
@@ -1416,4 +1693,618 @@ mod tests {
 
         Ok(())
     }
+
+    #[tokio::test]
+    async fn test_halt_at_sub_dag_terminates_early() -> eyre::Result<()> {
+        // create batches for two rounds of consensus output
+        let mut batches_1 = tn_test_utils::batches(4);
+        let mut batches_2 = tn_test_utils::batches(4);
+        let all_batches = [batches_1.clone(), batches_2.clone()].concat();
+
+        // use default genesis and seed accounts to execute batches
+        let genesis = adiri_genesis();
+        let (genesis, _txs_by_block, _signers_by_block) =
+            seeded_genesis_from_random_batches(genesis, all_batches.iter());
+        let chain: Arc<ChainSpec> = Arc::new(genesis.into());
+
+        // create execution node components
+        let execution_node = default_test_execution_node(Some(chain.clone()), None)?;
+        let parent = chain.sealed_genesis_header();
+
+        // execute batches to update headers with valid data
+        let mut inc_base_fee = MIN_PROTOCOL_BASE_FEE;
+        for (idx, batch) in batches_1.iter_mut().chain(batches_2.iter_mut()).enumerate() {
+            inc_base_fee += idx as u64;
+            batch.beneficiary = Address::random();
+            batch.base_fee_per_gas = Some(inc_base_fee);
+            execute_test_batch(batch, &parent);
+        }
+
+        //=== Consensus
+        let timestamp = now();
+        let mut leader_1 = Certificate::default();
+        leader_1.update_created_at_for_test(timestamp);
+        leader_1.header.round = 1;
+        let subdag_1 = Arc::new(CommittedSubDag::new(
+            vec![Certificate::default()],
+            leader_1,
+            1,
+            ReputationScores::default(),
+            None,
+        ));
+        let consensus_output_1 = ConsensusOutput {
+            sub_dag: subdag_1.clone(),
+            batch_digests: batches_1.iter().map(|b| b.digest()).collect(),
+            batches: vec![batches_1],
+            beneficiary: Address::with_last_byte(1),
+            parent_hash: ConsensusHeader::default().digest(),
+            number: 0,
+            extra: Default::default(),
+            early_finalize: true,
+            withdrawals: Default::default(),
+        };
+        let consensus_output_1_hash = consensus_output_1.consensus_header_hash();
+
+        let mut leader_2 = Certificate::default();
+        leader_2.update_created_at_for_test(timestamp + 2);
+        leader_2.header.round = 2;
+        let subdag_2 = CommittedSubDag::new(
+            vec![Certificate::default()],
+            leader_2,
+            2,
+            ReputationScores::default(),
+            Some(subdag_1.as_ref()),
+        )
+        .into();
+        let consensus_output_2 = ConsensusOutput {
+            sub_dag: subdag_2,
+            batch_digests: batches_2.iter().map(|b| b.digest()).collect(),
+            batches: vec![batches_2],
+            beneficiary: Address::with_last_byte(2),
+            parent_hash: consensus_output_1_hash,
+            number: 1,
+            extra: Default::default(),
+            early_finalize: true,
+            withdrawals: Default::default(),
+        };
+
+        //=== Execution
+
+        let (_to_engine, from_consensus) = tokio::sync::broadcast::channel(1);
+        let blockchain = execution_node.get_provider().await;
+        let evm_config = execution_node.get_evm_config().await;
+        let shutdown = Notifier::default();
+        // halt after executing the first output
+        let (_halt_at, rx_halt_at) = watch::channel(Some(0));
+        let mut engine = ExecutorEngine::new(
+            blockchain.clone(),
+            evm_config,
+            None,
+            BroadcastStream::from(from_consensus),
+            chain.sealed_genesis_header(),
+            shutdown.subscribe(),
+        )
+        .with_halt_at_sub_dag(rx_halt_at);
+
+        // queue both output - simulate already received from channel
+        engine.queue_output(consensus_output_1);
+        engine.queue_output(consensus_output_2);
+
+        // NOTE: sending channel is NOT dropped, so the engine only exits because of the halt
+        let (tx, rx) = oneshot::channel();
+        TaskManager::default().spawn_blocking(Box::pin(async move {
+            let res = engine.await;
+            let _ = tx.send(res);
+        }));
+
+        let engine_task = timeout(Duration::from_secs(10), rx).await?;
+        assert!(engine_task.is_ok());
+
+        // only the 4 batches from the first output are executed
+        assert_eq!(blockchain.last_block_number()?, 4);
+        let last_output = execution_node.last_executed_output().await?;
+        assert_eq!(last_output, consensus_output_1_hash);
+
+        Ok(())
+    }
+
+    /// Test a transaction executes at most once per output, and a copy after one the EVM
+    /// rejected still executes.
+    #[tokio::test]
+    async fn test_duplicate_of_rejected_transaction_executes() -> eyre::Result<()> {
+        use crate::execute_consensus_output;
+        use reth_provider::AccountReader as _;
+        use tn_node_traits::BuildArguments;
+        use tn_test_utils::{test_genesis, TransactionFactory};
+        use tn_types::{Batch, Bytes};
+
+        let chain: Arc<ChainSpec> = Arc::new(test_genesis().into());
+        let execution_node = default_test_execution_node(Some(chain.clone()), None)?;
+        let provider = execution_node.get_provider().await;
+        let evm_config = execution_node.get_evm_config().await;
+
+        let mut factory = TransactionFactory::default();
+        let sender = factory.address();
+        let mut transfer = || {
+            factory.create_eip1559_encoded(
+                chain.clone(),
+                None,
+                MIN_PROTOCOL_BASE_FEE as u128,
+                Some(Address::with_last_byte(0x99)),
+                U256::from(1),
+                Bytes::new(),
+            )
+        };
+        let nonce_0 = transfer();
+        let nonce_1 = transfer();
+
+        // the first copy of nonce 1 is rejected because nonce 0 has not executed yet, the second
+        // copy executes after nonce 0 and the third is a duplicate
+        let batch = |transactions| Batch {
+            transactions,
+            base_fee_per_gas: Some(MIN_PROTOCOL_BASE_FEE),
+            ..Default::default()
+        };
+        let batches = vec![
+            batch(vec![nonce_1.clone()]),
+            batch(vec![nonce_0, nonce_1.clone()]),
+            batch(vec![nonce_1]),
+        ];
+
+        let mut leader = Certificate::default();
+        leader.header.created_at = now();
+        let output = ConsensusOutput {
+            sub_dag: CommittedSubDag::new(
+                vec![Certificate::default()],
+                leader,
+                1,
+                ReputationScores::default(),
+                None,
+            )
+            .into(),
+            batch_digests: batches.iter().map(|b| b.digest()).collect(),
+            batches: vec![batches],
+            beneficiary: Address::with_last_byte(0x55),
+            parent_hash: ConsensusHeader::default().digest(),
+            number: 1,
+            extra: Default::default(),
+            early_finalize: true,
+            withdrawals: Default::default(),
+        };
+        let args = BuildArguments::new(provider.clone(), output, chain.sealed_genesis_header());
+        execute_consensus_output(&evm_config, args)?;
+
+        let executed_blocks = provider.block_with_senders_range(1..=3)?;
+        let tx_counts: Vec<_> =
+            executed_blocks.iter().map(|block| block.body.transactions.len()).collect();
+        assert_eq!(tx_counts, [0, 2, 0]);
+        assert_eq!(provider.latest()?.basic_account(sender)?.map(|account| account.nonce), Some(2));
+
+        Ok(())
+    }
+
+    /// Test the engine halts without executing output that certifies a block this node did not
+    /// execute and writes a diagnostic dump.
+    #[tokio::test]
+    async fn test_execution_divergence_halts_engine() -> eyre::Result<()> {
+        use crate::error::TnEngineError;
+        use tn_types::BlockNumHash;
+
+        let chain = adiri_chain_spec_arc();
+        let genesis_header = chain.sealed_genesis_header();
+
+        // the committee certified a different genesis
+        let certified = BlockNumHash::new(0, B256::random());
+        assert_ne!(certified.hash, genesis_header.hash());
+        let mut cert = Certificate::default();
+        cert.header.latest_execution_block = certified;
+        let consensus_output = ConsensusOutput {
+            sub_dag: CommittedSubDag::new(
+                vec![cert],
+                Certificate::default(),
+                0,
+                ReputationScores::default(),
+                None,
+            )
+            .into(),
+            batches: Default::default(),
+            beneficiary: Address::random(),
+            batch_digests: Default::default(),
+            parent_hash: ConsensusHeader::default().digest(),
+            number: 0,
+            extra: Default::default(),
+            early_finalize: true,
+            withdrawals: Default::default(),
+        };
+
+        let execution_node = default_test_execution_node(Some(chain.clone()), None)?;
+        let (to_engine, from_consensus) = tokio::sync::broadcast::channel(1);
+        let provider = execution_node.get_provider().await;
+        let evm_config = execution_node.get_evm_config().await;
+        let dump_dir = tempfile::tempdir()?;
+        let shutdown = Notifier::default();
+        let engine = ExecutorEngine::new(
+            provider.clone(),
+            evm_config,
+            None,
+            BroadcastStream::from(from_consensus),
+            genesis_header.clone(),
+            shutdown.subscribe(),
+        )
+        .with_divergence_dump_dir(dump_dir.path().to_path_buf());
+
+        // send output and drop sending channel to shut engine down
+        to_engine.send(consensus_output)?;
+        drop(to_engine);
+
+        let (tx, rx) = oneshot::channel();
+        TaskManager::default().spawn_blocking(Box::pin(async move {
+            let res = engine.await;
+            let _ = tx.send(res);
+        }));
+        let engine_task = timeout(Duration::from_secs(10), rx).await??;
+        let Err(TnEngineError::ExecutionDivergence(divergence)) = engine_task else {
+            panic!("expected execution divergence, got {engine_task:?}");
+        };
+        assert_eq!(divergence.block_number, 0);
+        assert_eq!(divergence.certified_hash, certified.hash);
+        assert_eq!(divergence.local_hash, genesis_header.hash());
+
+        // nothing was executed on top of the diverged chain
+        assert_eq!(provider.last_block_number()?, 0);
+        assert!(dump_dir.path().join("divergence-0.json").exists());
+
+        Ok(())
+    }
+
+    /// Test every validator produces identical headers for the same consensus output and block
+    /// timestamps never go backwards once the timestamp policy is active.
+    #[tokio::test]
+    async fn test_validators_produce_identical_headers() -> eyre::Result<()> {
+        use crate::execute_consensus_output;
+        use tn_node_traits::BuildArguments;
+        use tn_types::TimestampPolicy;
+
+        let chain = adiri_chain_spec_arc();
+        let commit_timestamp = now();
+        // the second leader's clock is behind the first
+        let outputs: Vec<_> = [commit_timestamp, commit_timestamp - 10]
+            .into_iter()
+            .enumerate()
+            .map(|(index, created_at)| {
+                let mut leader = Certificate::default();
+                leader.header.round = index as u32;
+                leader.header.created_at = created_at;
+                ConsensusOutput {
+                    sub_dag: CommittedSubDag::new(
+                        vec![Certificate::default()],
+                        leader,
+                        index as u64,
+                        ReputationScores::default(),
+                        None,
+                    )
+                    .into(),
+                    batches: Default::default(), // empty
+                    beneficiary: Address::with_last_byte(0x55),
+                    batch_digests: Default::default(), // empty
+                    parent_hash: ConsensusHeader::default().digest(),
+                    number: index as u64,
+                    extra: Default::default(),
+                    early_finalize: true,
+                    withdrawals: Default::default(),
+                }
+            })
+            .collect();
+
+        for policy in [
+            None,
+            Some(TimestampPolicy::CommitTimestamp),
+            Some(TimestampPolicy::StrictlyIncreasing),
+        ] {
+            let mut validator_headers = Vec::new();
+            for _ in 0..2 {
+                let execution_node = default_test_execution_node(Some(chain.clone()), None)?;
+                let provider = execution_node.get_provider().await;
+                let evm_config = execution_node.get_evm_config().await;
+                let mut parent = chain.sealed_genesis_header();
+                let mut headers = Vec::new();
+                for output in outputs.iter().cloned() {
+                    let mut args = BuildArguments::new(provider.clone(), output, parent);
+                    if let Some(policy) = policy {
+                        args = args.with_timestamp_policy(policy);
+                    }
+                    parent = execute_consensus_output(&evm_config, args)?;
+                    headers.push(parent.clone());
+                }
+                validator_headers.push(headers);
+            }
+
+            assert_eq!(validator_headers[0], validator_headers[1]);
+            let headers = &validator_headers[0];
+            assert_eq!(headers[0].timestamp, commit_timestamp);
+            match policy {
+                // before the fork blocks use the leader's timestamp as is
+                None => assert_eq!(headers[1].timestamp, commit_timestamp - 10),
+                Some(TimestampPolicy::CommitTimestamp) => {
+                    assert_eq!(headers[1].timestamp, commit_timestamp)
+                }
+                Some(TimestampPolicy::StrictlyIncreasing) => {
+                    assert_eq!(headers[1].timestamp, commit_timestamp + 1)
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Test state changes queued by a dev network are applied in the next executed block.
+    #[tokio::test]
+    async fn test_dev_state_changes_applied() -> eyre::Result<()> {
+        use crate::execute_consensus_output;
+        use reth_provider::{AccountReader as _, StateProvider as _};
+        use tn_node_traits::BuildArguments;
+        use tn_types::{AccountOverride, Bytes, DevStateChanges};
+
+        let chain = adiri_chain_spec_arc();
+        let execution_node = default_test_execution_node(Some(chain.clone()), None)?;
+        let provider = execution_node.get_provider().await;
+        let evm_config = execution_node.get_evm_config().await;
+        let outputs: Vec<_> = (0..2)
+            .map(|index| {
+                let mut leader = Certificate::default();
+                leader.header.round = index as u32;
+                leader.header.created_at = now();
+                ConsensusOutput {
+                    sub_dag: CommittedSubDag::new(
+                        vec![Certificate::default()],
+                        leader,
+                        index,
+                        ReputationScores::default(),
+                        None,
+                    )
+                    .into(),
+                    batches: Default::default(), // empty
+                    beneficiary: Address::with_last_byte(0x55),
+                    batch_digests: Default::default(), // empty
+                    parent_hash: ConsensusHeader::default().digest(),
+                    number: index,
+                    extra: Default::default(),
+                    early_finalize: true,
+                    withdrawals: Default::default(),
+                }
+            })
+            .collect();
+
+        let dev_state = DevStateChanges::default();
+        let address = Address::with_last_byte(0x77);
+        let code = Bytes::from_static(&[0x60, 0x00]);
+        dev_state.queue(
+            address,
+            AccountOverride {
+                balance: Some(U256::from(100)),
+                code: Some(code.clone()),
+                storage: [(U256::from(1), U256::from(2))].into(),
+                ..Default::default()
+            },
+        );
+        let parent = chain.sealed_genesis_header();
+        let args = BuildArguments::new(provider.clone(), outputs[0].clone(), parent)
+            .with_dev_state(dev_state.clone());
+        let parent = execute_consensus_output(&evm_config, args)?;
+        assert!(dev_state.is_empty());
+
+        let state = provider.latest()?;
+        assert_eq!(state.account_balance(address)?, Some(U256::from(100)));
+        assert_eq!(state.account_code(address)?.map(|code| code.original_bytes()), Some(code));
+        assert_eq!(state.storage(address, B256::with_last_byte(1))?, Some(U256::from(2)));
+
+        // emptying the account removes it
+        dev_state.queue(
+            address,
+            AccountOverride {
+                balance: Some(U256::ZERO),
+                code: Some(Bytes::new()),
+                storage: [(U256::from(1), U256::ZERO)].into(),
+                ..Default::default()
+            },
+        );
+        let args = BuildArguments::new(provider.clone(), outputs[1].clone(), parent)
+            .with_dev_state(dev_state);
+        execute_consensus_output(&evm_config, args)?;
+        assert_eq!(provider.latest()?.basic_account(address)?, None);
+
+        Ok(())
+    }
+
+    /// Test system calls are made for every sub-dag with deterministic calldata and do not use
+    /// block gas.
+    #[tokio::test]
+    async fn test_system_calls_at_sub_dag_boundaries() -> eyre::Result<()> {
+        use crate::execute_consensus_output;
+        use reth_provider::{AccountReader as _, StateProvider as _};
+        use tn_node_traits::BuildArguments;
+        use tn_types::{
+            system_call_failure_slots, AccountOverride, Bytes, DevStateChanges, SystemCall,
+            SystemCallPhase, SYSTEM_ADDRESS, SYSTEM_CALL_FAILURES_ADDRESS,
+        };
+
+        let chain = adiri_chain_spec_arc();
+        let execution_node = default_test_execution_node(Some(chain.clone()), None)?;
+        let provider = execution_node.get_provider().await;
+        let evm_config = execution_node.get_evm_config().await;
+        let outputs: Vec<_> = (0..2)
+            .map(|index| {
+                let mut leader = Certificate::default();
+                leader.header.round = index as u32;
+                leader.header.created_at = now();
+                ConsensusOutput {
+                    sub_dag: CommittedSubDag::new(
+                        vec![Certificate::default()],
+                        leader,
+                        index,
+                        ReputationScores::default(),
+                        None,
+                    )
+                    .into(),
+                    batches: Default::default(), // empty
+                    beneficiary: Address::with_last_byte(0x55),
+                    batch_digests: Default::default(), // empty
+                    parent_hash: ConsensusHeader::default().digest(),
+                    number: index + 10,
+                    extra: Default::default(),
+                    early_finalize: true,
+                    withdrawals: Default::default(),
+                }
+            })
+            .collect();
+
+        // records the sub-dag in slot 0, counts calls in slot 1, and records the caller in slot 2
+        let recorder = Bytes::from_static(&[
+            0x60, 0x04, 0x35, 0x60, 0x00, 0x55, 0x60, 0x01, 0x54, 0x60, 0x01, 0x01, 0x60, 0x01,
+            0x55, 0x33, 0x60, 0x02, 0x55, 0x00,
+        ]);
+        let reverts = Bytes::from_static(&[0x60, 0x00, 0x60, 0x00, 0xfd]);
+        let start = Address::with_last_byte(0x71);
+        let end = Address::with_last_byte(0x72);
+        let failing = Address::with_last_byte(0x73);
+        let dev_state = DevStateChanges::default();
+        for (address, code) in [(start, &recorder), (end, &recorder), (failing, &reverts)] {
+            dev_state
+                .queue(address, AccountOverride { code: Some(code.clone()), ..Default::default() });
+        }
+        let call = |address, phase| SystemCall {
+            address,
+            selector: [1, 2, 3, 4].into(),
+            phase,
+            gas_limit: 200_000,
+        };
+        let system_calls = vec![
+            call(failing, SystemCallPhase::SubDagStart),
+            call(start, SystemCallPhase::SubDagStart),
+            call(end, SystemCallPhase::SubDagEnd),
+        ];
+
+        let mut parent = chain.sealed_genesis_header();
+        for output in outputs {
+            let args = BuildArguments::new(provider.clone(), output, parent)
+                .with_system_calls(system_calls.clone())
+                .with_dev_state(dev_state.clone());
+            parent = execute_consensus_output(&evm_config, args)?;
+            // system calls are not transactions
+            assert_eq!(parent.gas_used, 0);
+        }
+
+        let state = provider.latest()?;
+        for address in [start, end] {
+            assert_eq!(state.storage(address, B256::ZERO)?, Some(U256::from(11)));
+            assert_eq!(state.storage(address, B256::with_last_byte(1))?, Some(U256::from(2)));
+            assert_eq!(
+                state.storage(address, B256::with_last_byte(2))?,
+                Some(U256::from_be_slice(SYSTEM_ADDRESS.as_slice()))
+            );
+        }
+        // the failed call changed nothing and the caller is not part of the state
+        assert_eq!(state.storage(failing, B256::ZERO)?, None);
+        assert_eq!(state.basic_account(SYSTEM_ADDRESS)?, None);
+        // each failure of the first call is recorded with its sub-dag
+        let (count_slot, sub_dag_slot) = system_call_failure_slots(0);
+        assert_eq!(
+            state.storage(SYSTEM_CALL_FAILURES_ADDRESS, count_slot.into())?,
+            Some(U256::from(2))
+        );
+        assert_eq!(
+            state.storage(SYSTEM_CALL_FAILURES_ADDRESS, sub_dag_slot.into())?,
+            Some(U256::from(11))
+        );
+        let (count_slot, _) = system_call_failure_slots(1);
+        assert_eq!(state.storage(SYSTEM_CALL_FAILURES_ADDRESS, count_slot.into())?, None);
+
+        Ok(())
+    }
+
+    /// Test a governed gas limit is read when each epoch starts and kept for the whole epoch.
+    #[tokio::test]
+    async fn test_governed_batch_gas_limit() -> eyre::Result<()> {
+        use crate::execute_consensus_output;
+        use tn_node_traits::BuildArguments;
+        use tn_types::{
+            AccountOverride, BatchGasSchedule, Bytes, DevStateChanges, GovernedBatchGas,
+        };
+
+        let chain = adiri_chain_spec_arc();
+        let execution_node = default_test_execution_node(Some(chain.clone()), None)?;
+        let provider = execution_node.get_provider().await;
+        let evm_config = execution_node.get_evm_config().await;
+        let outputs: Vec<_> = (1..6)
+            .map(|index| {
+                let mut leader = Certificate::default();
+                leader.header.round = index as u32;
+                leader.header.created_at = now();
+                ConsensusOutput {
+                    sub_dag: CommittedSubDag::new(
+                        vec![Certificate::default()],
+                        leader,
+                        index,
+                        ReputationScores::default(),
+                        None,
+                    )
+                    .into(),
+                    batches: Default::default(), // empty
+                    beneficiary: Address::with_last_byte(0x55),
+                    batch_digests: Default::default(), // empty
+                    parent_hash: ConsensusHeader::default().digest(),
+                    number: index,
+                    extra: Default::default(),
+                    early_finalize: true,
+                    withdrawals: Default::default(),
+                }
+            })
+            .collect();
+
+        // the governance contract raises the gas limit during the first epoch
+        let governance = GovernedBatchGas {
+            address: Address::with_last_byte(0x88),
+            slot: B256::with_last_byte(6),
+            min: 1_000_000,
+            max: 1_000_000_000,
+        };
+        let dev_state = DevStateChanges::default();
+        dev_state.queue(
+            governance.address,
+            AccountOverride {
+                code: Some(Bytes::from_static(&[0x00])),
+                storage: [(U256::from_be_bytes(governance.slot.0), U256::from(60_000_000))].into(),
+                ..Default::default()
+            },
+        );
+        let schedule = BatchGasSchedule {
+            governance: Some(governance),
+            epoch_length: 3,
+            ..Default::default()
+        };
+
+        let mut parent = chain.sealed_genesis_header();
+        let mut gas_limits = Vec::new();
+        for (index, output) in outputs.into_iter().enumerate() {
+            let mut args = BuildArguments::new(provider.clone(), output, parent);
+            // the last output executes without the schedule
+            if index < 4 {
+                args = args.with_batch_gas(schedule);
+            }
+            if index == 0 {
+                args = args.with_dev_state(dev_state.clone());
+            }
+            parent = execute_consensus_output(&evm_config, args)?;
+            gas_limits.push(parent.gas_limit);
+        }
+
+        // the new limit applies from the first output of the next epoch and empty outputs keep
+        // the parent's limit without the schedule
+        assert_eq!(
+            gas_limits,
+            [max_batch_gas(0), max_batch_gas(0), 60_000_000, 60_000_000, 60_000_000]
+        );
+
+        Ok(())
+    }
 }
@@ -0,0 +1,22 @@
+//! Metrics for executing output from consensus.
+
+use reth_metrics::{
+    metrics::{Counter, Gauge},
+    Metrics,
+};
+
+/// Transactions left out of executed blocks and disagreements with the committee.
+#[derive(Metrics, Clone)]
+#[metrics(scope = "engine")]
+pub(crate) struct ExecutionMetrics {
+    /// Transactions skipped because an earlier copy in the same output executed.
+    pub(crate) duplicate_transactions: Counter,
+    /// Transactions skipped because the EVM rejected them (ie - nonce already used).
+    pub(crate) rejected_transactions: Counter,
+    /// System calls that reverted or halted.
+    pub(crate) failed_system_calls: Counter,
+    /// Set to the block number where this node's execution diverged from the committee.
+    ///
+    /// Zero while execution agrees with the committee.
+    pub(crate) execution_divergence: Gauge,
+}
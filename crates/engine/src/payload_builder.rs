@@ -1,28 +1,52 @@
 //! Payload builder function for extending the canonical tip using the output from consensus.
 //!
 //! This approach heavily inspired by reth's `default_ethereum_payload_builder`.
-
-use crate::error::{EngineResult, TnEngineError};
+//!
+//! The same transaction can be included in more than one worker's batch. Batches execute in the
+//! order of the output and a transaction executes at most once. Occurrences after the executed one
+//! are skipped and recorded: skipped transactions are not included in the block, use no gas, and
+//! have no receipt. The receipt for a duplicated transaction is always the one from its executed
+//! occurrence. Transactions the EVM rejects (ie - the nonce was used by an earlier output) are
+//! skipped the same way, but a later occurrence of a rejected transaction still executes since
+//! the state may have changed in between. The outcome only depends on the output.
+//!
+//! Committed sub-dags never revert, so the `safe` and `finalized` block tags both point to the last
+//! block executed for the latest committed sub-dag. The tags are kept on the [CanonChainTracker]
+//! for RPC instead of following beacon forkchoice updates. The finalized block in the database
+//! only moves to blocks signed off by the committee (or immediately with `early_finalize`) since
+//! it is used to recover execution progress on restart.
+
+use crate::{
+    audit::{audit_inspector, AuditSink, AuditedBlock, TransactionAudit},
+    dev_state::apply_dev_state_changes,
+    divergence::check_execution_divergence,
+    error::{EngineResult, TnEngineError},
+    metrics::ExecutionMetrics,
+    system_calls::{apply_system_calls, PreparedSystemCall, SubDagSystemCalls},
+};
 use reth_blockchain_tree::{BlockValidationKind, BlockchainTreeEngine};
 use reth_chainspec::ChainSpec;
-use reth_evm::ConfigureEvm;
+use reth_evm::{state_change::post_block_withdrawals_balance_increments, ConfigureEvm};
 use reth_execution_types::ExecutionOutcome;
-use reth_provider::{CanonChainTracker, ChainSpecProvider, HeaderProvider, StateProviderFactory};
+use reth_provider::{
+    CanonChainTracker, ChainSpecProvider, HeaderProvider, ProviderError, StateProvider as _,
+    StateProviderFactory,
+};
 use reth_revm::{
     cached::CachedReads,
     database::StateProviderDatabase,
     db::states::bundle_state::BundleRetention,
     primitives::{EVMError, EnvWithHandlerCfg, FixedBytes, ResultAndState, TxEnv},
-    DatabaseCommit, State,
+    Database, DatabaseCommit, Evm, State,
 };
-use reth_rpc_eth_types::utils::recover_raw_transaction;
-use std::sync::Arc;
+use std::{collections::HashMap, sync::Arc};
 use tn_node_traits::{BuildArguments, TNPayload, TNPayloadAttributes};
 use tn_types::{
-    calculate_transaction_root, max_batch_gas, Batch, Block, BlockBody, BlockExt as _,
-    ConsensusOutput, ExecHeader, Hash as _, Receipt, SealedBlockWithSenders, SealedHeader,
-    TransactionSigned, Withdrawals, B256, EMPTY_OMMER_ROOT_HASH, EMPTY_RECEIPTS,
-    EMPTY_TRANSACTIONS, EMPTY_WITHDRAWALS, U256,
+    batch_gas_limit, calculate_transaction_root, calculate_withdrawals_root, keccak256,
+    max_batch_gas, Address, Batch, Block, BlockBody, BlockExt as _, ConsensusOutput,
+    DevStateChanges, ExecHeader, Hash as _, Receipt, RecoveredTx, SealedBlockWithSenders,
+    SealedHeader, SenderRecovery, SenderRecoveryError, TransactionSigned, TxHash, Withdrawals,
+    B256, EMPTY_OMMER_ROOT_HASH, EMPTY_RECEIPTS, EMPTY_TRANSACTIONS, EMPTY_WITHDRAWALS, U256,
 };
 use tracing::{debug, error, info, warn};
 
@@ -47,16 +71,11 @@ where
     }
     if last_executed.number <= canonical_header.number {
         if let Some(block) = provider.sealed_header_by_hash(last_executed.hash)? {
-            // finalize the last block from a cert in consensus output and update chain info
+            // finalize the last block from a cert in consensus output
             //
-            // this removes canonical blocks from the tree, stores the finalized block number in the
-            // database, but still need to set_finalized afterwards for utilization in-memory for
-            // components, like RPC
+            // this removes canonical blocks from the tree and stores the finalized block number in
+            // the database - the in-memory tags are already set to the canonical head
             provider.finalize_block(block.header().number)?;
-            provider.set_finalized(block.clone());
-
-            // update safe block last because this is less time sensitive but still needs to happen
-            provider.set_safe(block);
         } else {
             error!(target: "engine", ?output, "missing the block to finalize!");
             return Err(TnEngineError::MissingFinalBlock);
@@ -81,12 +100,55 @@ where
         + HeaderProvider<Header = ExecHeader>
         + CanonChainTracker<Header = ExecHeader>,
 {
-    let BuildArguments { provider, mut output, parent_header } = args;
+    execute_consensus_output_with_audit(evm_config, args, None)
+}
+
+/// Execute output from consensus and trace every transaction to the audit sink.
+///
+/// Transactions are only traced if `audit` is set. See [execute_consensus_output].
+pub fn execute_consensus_output_with_audit<EvmConfig, Provider>(
+    evm_config: &EvmConfig,
+    args: BuildArguments<Provider>,
+    audit: Option<&dyn AuditSink>,
+) -> EngineResult<SealedHeader>
+where
+    EvmConfig: ConfigureEvm<Transaction = TransactionSigned>,
+    Provider: StateProviderFactory
+        + ChainSpecProvider<ChainSpec = ChainSpec>
+        + BlockchainTreeEngine
+        + HeaderProvider<Header = ExecHeader>
+        + CanonChainTracker<Header = ExecHeader>,
+{
+    let BuildArguments {
+        provider,
+        mut output,
+        parent_header,
+        sender_recovery,
+        timestamp_policy,
+        system_calls,
+        batch_gas,
+        dev_state,
+    } = args;
     debug!(target: "engine", ?output, "executing output");
 
+    // never extend a chain the committee did not certify
+    check_execution_divergence(&provider, &output, &parent_header)?;
+
     // output digest returns the `ConsensusHeader` digest
     let output_digest: B256 = output.digest().into();
     let batches = output.flatten_batches();
+    let system_calls = SubDagSystemCalls::new(&system_calls, &output);
+
+    // once the batch gas schedule activates, every block executed for the output has the same
+    // gas limit
+    let gas_limit = batch_gas
+        .map(|batch_gas| {
+            batch_gas.gas_limit(&parent_header, output.number, |address, slot| {
+                let state = provider.state_by_block_hash(parent_header.hash())?;
+                Ok::<_, ProviderError>(state.storage(address, slot)?.unwrap_or_default())
+            })
+        })
+        .transpose()?;
 
     // assert vecs match
     debug_assert_eq!(
@@ -105,10 +167,10 @@ where
         //
         // use parent values for next block (these values would come from the worker's block)
         let base_fee_per_gas = canonical_header.base_fee_per_gas.unwrap_or_default();
-        let gas_limit = canonical_header.gas_limit;
+        let gas_limit = gas_limit.unwrap_or(canonical_header.gas_limit);
 
-        // empty withdrawals
-        let withdrawals = Withdrawals::new(vec![]);
+        // the only block for this output applies all withdrawals
+        let withdrawals = output.withdrawals.clone();
         let payload_attributes = TNPayloadAttributes::new(
             canonical_header,
             0,
@@ -119,15 +181,19 @@ where
             gas_limit,
             output_digest, // use output digest for mix hash
             withdrawals,
+            timestamp_policy,
         );
         let payload = TNPayload::new(payload_attributes);
 
         // execute
         let next_canonical_block = build_block_from_empty_payload(
+            evm_config,
             payload,
             &provider,
             provider.chain_spec(),
             output.consensus_header_hash(),
+            &system_calls,
+            dev_state.as_ref(),
         )?;
 
         debug!(target: "engine", ?next_canonical_block, "empty block");
@@ -141,18 +207,43 @@ where
                 error!(target: "engine", header=?canonical_header, ?e, "failed to insert next canonical block");
             })?;
     } else {
+        // the same transaction can be included in more than one worker's batch
+        //
+        // it executes at most once for the output
+        let mut output_txs = OutputTransactions::default();
+
         // loop and construct blocks with transactions
+        let last_index = batches.len() - 1;
         for (block_index, block) in batches.into_iter().enumerate() {
             let batch_digest =
                 output.next_batch_digest().ok_or(TnEngineError::NextBlockDigestMissing)?;
-            // use batch's base fee, gas limit, and withdrawals
+            // use batch's base fee
             let base_fee_per_gas = block.base_fee_per_gas.unwrap_or_default();
-            let gas_limit = max_batch_gas(block.timestamp);
+            let (gas_limit, execution_gas_limit) = match gas_limit {
+                // workers validated the batch against the limit of the block it extends, so a
+                // limit lowered when an epoch starts never drops transactions they accepted
+                Some(gas_limit) => {
+                    let validated = provider
+                        .header(&block.parent_hash)?
+                        .map(|parent| batch_gas_limit(&parent))
+                        .unwrap_or(gas_limit);
+                    (gas_limit, gas_limit.max(validated))
+                }
+                None => {
+                    let gas_limit = max_batch_gas(block.timestamp);
+                    (gas_limit, gas_limit)
+                }
+            };
 
             // apply XOR bitwise operator with worker's digest to ensure unique mixed hash per block
             // for round
             let mix_hash = output_digest ^ block.digest();
-            let withdrawals = Withdrawals::new(vec![]);
+            // withdrawals are applied once, after the output's last batch
+            let withdrawals = if block_index == last_index {
+                output.withdrawals.clone()
+            } else {
+                Withdrawals::default()
+            };
             let payload_attributes = TNPayloadAttributes::new(
                 canonical_header,
                 block_index as u64,
@@ -163,8 +254,12 @@ where
                 gas_limit,
                 mix_hash,
                 withdrawals,
+                timestamp_policy,
             );
             let payload = TNPayload::new(payload_attributes);
+            // system calls are made before the sub-dag's first transaction and after its last
+            let start_calls = if block_index == 0 { system_calls.start() } else { &[] };
+            let end_calls = if block_index == last_index { system_calls.end() } else { &[] };
 
             // execute
             let next_canonical_block = build_block_from_batch_payload(
@@ -173,7 +268,13 @@ where
                 &provider,
                 provider.chain_spec(),
                 block,
+                execution_gas_limit,
                 output.consensus_header_hash(),
+                &mut output_txs,
+                &sender_recovery,
+                (start_calls, end_calls),
+                dev_state.as_ref(),
+                audit,
             )?;
 
             debug!(target: "engine", ?next_canonical_block, "worker's block executed");
@@ -209,17 +310,20 @@ where
     provider.set_canonical_head(canonical_header.clone());
     info!(target: "engine", "canonical head for round {:?}: {:?} - {:?}", <FixedBytes<8> as Into<u64>>::into(canonical_header.nonce), canonical_header.number, canonical_header.hash());
 
+    // the output was committed by consensus so its blocks are final once executed
+    //
+    // set the tags in-memory for components, like RPC
+    provider.set_finalized(canonical_header.clone());
+
+    // update safe block last because this is less time sensitive but still needs to happen
+    provider.set_safe(canonical_header.clone());
+
     if output.early_finalize {
-        // finalize the last block executed from consensus output and update chain info
+        // finalize the last block executed from consensus output
         //
-        // this removes canonical blocks from the tree, stores the finalized block number in the
-        // database, but still need to set_finalized afterwards for utilization in-memory for
-        // components, like RPC
+        // this removes canonical blocks from the tree and stores the finalized block number in the
+        // database
         provider.finalize_block(canonical_header.number)?;
-        provider.set_finalized(canonical_header.clone());
-
-        // update safe block last because this is less time sensitive but still needs to happen
-        provider.set_safe(canonical_header.clone());
     } else {
         finalize_signed_blocks(&provider, &output, &canonical_header)?;
     }
@@ -228,15 +332,49 @@ where
     Ok(canonical_header)
 }
 
+/// The transactions executed for an output and metrics for skipped transactions.
+#[derive(Default)]
+struct OutputTransactions {
+    /// The index of the batch each transaction executed in, by transaction hash.
+    executed: HashMap<TxHash, usize>,
+    /// Metrics for skipped transactions.
+    metrics: ExecutionMetrics,
+}
+
+impl OutputTransactions {
+    /// Return the index of the batch the transaction executed in, if it executed.
+    fn executed_in(&self, tx_hash: &TxHash) -> Option<usize> {
+        self.executed.get(tx_hash).copied()
+    }
+
+    /// Record that the transaction executed in the batch.
+    fn record_executed(&mut self, tx_hash: TxHash, batch_index: usize) {
+        self.executed.insert(tx_hash, batch_index);
+    }
+}
+
 /// Construct a canonical block from a worker's block that reached consensus.
+///
+/// Transactions already executed for the output are skipped. Senders are recovered in parallel with
+/// `sender_recovery` before execution. Changes queued on `dev_state` are applied before the
+/// transactions, followed by the system calls for the start of the sub-dag. The system calls for
+/// the end of the sub-dag are made after the transactions. Every executed transaction is traced if
+/// `audit` is set.
 #[inline]
+#[allow(clippy::too_many_arguments)]
 fn build_block_from_batch_payload<EvmConfig, Provider>(
     evm_config: &EvmConfig,
     payload: TNPayload,
     provider: &Provider,
     chain_spec: Arc<ChainSpec>,
     batch: Batch,
+    execution_gas_limit: u64,
     consensus_header_hash: B256,
+    output_txs: &mut OutputTransactions,
+    sender_recovery: &SenderRecovery,
+    (start_calls, end_calls): (&[PreparedSystemCall], &[PreparedSystemCall]),
+    dev_state: Option<&DevStateChanges>,
+    audit: Option<&dyn AuditSink>,
 ) -> EngineResult<SealedBlockWithSenders>
 where
     EvmConfig: ConfigureEvm<Transaction = TransactionSigned>,
@@ -255,20 +393,15 @@ where
         State::builder().with_database(cached_reads.as_db_mut(state)).with_bundle_update().build();
 
     debug!(target: "payload_builder", parent_hash = ?payload.attributes.parent_header.hash(), parent_number = payload.attributes.parent_header.number, "building new payload");
-    // collect these totals to report at the end
-    let mut cumulative_gas_used = 0;
-    let mut total_fees = U256::ZERO;
-    let mut executed_txs = Vec::new();
-    let mut senders = Vec::new();
-    let mut receipts = Vec::new();
 
     // initialize values for execution from block env
     //
     // note: uses the worker's sealed header for "parent" values
     // note the sealed header below is more or less junk but payload trait requires it.
-    let (cfg, block_env) = payload.cfg_and_block_env(chain_spec.as_ref());
-
-    let block_gas_limit: u64 = block_env.gas_limit.try_into().unwrap_or(u64::MAX);
+    let (cfg, mut block_env) = payload.cfg_and_block_env(chain_spec.as_ref());
+    // transactions may use the gas the batch was validated with, the header records the limit
+    // carried forward by the following blocks
+    block_env.gas_limit = U256::from(execution_gas_limit);
     let base_fee = block_env.basefee.to::<u64>();
     let block_number = block_env.number.to::<u64>();
 
@@ -292,80 +425,52 @@ where
     // )
     // .map_err(|err| PayloadBuilderError::Internal(err.into()))?;
 
-    // TODO: parallelize tx recovery when it's worth it (see TransactionSigned::recover_signers())
-
-    let env = EnvWithHandlerCfg::new_with_cfg_env(cfg.clone(), block_env.clone(), TxEnv::default());
-    let mut evm = evm_config.evm_with_env(&mut db, env);
-
-    for tx_bytes in &batch.transactions {
-        let recovered =
-            recover_raw_transaction::<TransactionSigned>(tx_bytes).inspect_err(|e| {
-                error!(
-                target: "engine",
-                batch=?batch.digest(),
-                ?tx_bytes,
-                "failed to recover signer: {e}")
-            })?;
-
-        // Configure the environment for the tx.
-        *evm.tx_mut() = evm_config.tx_env(recovered.tx(), recovered.signer());
-
-        let ResultAndState { result, state } = match evm.transact() {
-            Ok(res) => res,
-            Err(err) => {
-                match err {
-                    // allow transaction errors (ie - duplicates)
-                    //
-                    // it's possible that another worker's batch included this transaction
-                    EVMError::Transaction(err) => {
-                        warn!(target: "engine", tx_hash=?recovered.hash(), ?err);
-                        continue;
-                    }
-                    err => {
-                        // this is an error that we should treat as fatal
-                        // - invalid header resulting from misconfigured BlockEnv
-                        // - Database error
-                        // - custom error (unsure)
-                        return Err(err.into());
-                    }
-                }
-            }
-        };
-
-        // commit changes
-        evm.db_mut().commit(state);
-
-        let gas_used = result.gas_used();
-
-        // add gas used by the transaction to cumulative gas used, before creating the receipt
-        cumulative_gas_used += gas_used;
-
-        // Push transaction changeset and calculate header bloom filter for receipt.
-        receipts.push(Some(Receipt {
-            tx_type: recovered.tx_type(),
-            success: result.is_success(),
-            cumulative_gas_used,
-            logs: result.into_logs().into_iter().collect(),
-        }));
-
-        // update add to total fees
-        let miner_fee = recovered
-            .effective_tip_per_gas(Some(base_fee))
-            .expect("fee is always valid; execution succeeded");
-        total_fees += U256::from(miner_fee) * U256::from(gas_used);
-
-        // append transaction to the list of executed transactions and keep signers
-        senders.push(recovered.signer());
-        executed_txs.push(recovered.into_tx());
+    // changes from a dev network's RPC are applied before the transactions
+    if let Some(dev_state) = dev_state {
+        apply_dev_state_changes(&mut db, dev_state.take())?;
     }
 
-    // Release db
-    drop(evm);
+    let env = EnvWithHandlerCfg::new_with_cfg_env(cfg.clone(), block_env.clone(), TxEnv::default());
+    apply_system_calls(evm_config, &mut db, &env, start_calls)?;
+
+    // senders of transactions the worker validated are already cached
+    let recovered = sender_recovery.recover_all(&batch.transactions);
+
+    let batch_index = payload.attributes.batch_index as usize;
+    let mut audited = Vec::new();
+    let executed = if audit.is_some() {
+        let mut evm =
+            evm_config.evm_with_env_and_inspector(&mut db, env.clone(), audit_inspector());
+        execute_batch_transactions(
+            evm_config,
+            &mut evm,
+            &batch,
+            recovered,
+            batch_index,
+            base_fee,
+            output_txs,
+            |inspector, tx_hash, sender, result| {
+                audited.push(TransactionAudit::take(inspector, tx_hash, sender, result));
+            },
+        )?
+    } else {
+        let mut evm = evm_config.evm_with_env(&mut db, env.clone());
+        execute_batch_transactions(
+            evm_config,
+            &mut evm,
+            &batch,
+            recovered,
+            batch_index,
+            base_fee,
+            output_txs,
+            |_, _, _, _| {},
+        )?
+    };
+    let ExecutedTransactions { cumulative_gas_used, executed_txs, senders, receipts } = executed;
+    apply_system_calls(evm_config, &mut db, &env, end_calls)?;
 
-    // TODO: logic for withdrawals
-    //
-    // let WithdrawalsOutcome { withdrawals_root, withdrawals } =
-    //     commit_withdrawals(&mut db, &chain_spec, attributes.timestamp, attributes.withdrawals)?;
+    let withdrawals_root =
+        commit_withdrawals(&mut db, &chain_spec, payload.timestamp(), payload.withdrawals())?;
 
     // merge all transitions into bundle state, this would apply the withdrawal balance changes
     // and 4788 contract call
@@ -403,14 +508,14 @@ where
         state_root,
         transactions_root,
         receipts_root,
-        withdrawals_root: Some(EMPTY_WITHDRAWALS),
+        withdrawals_root: Some(withdrawals_root),
         logs_bloom,
         timestamp: payload.timestamp(),
         mix_hash: payload.prev_randao(),
         nonce: payload.attributes.nonce.into(),
         base_fee_per_gas: Some(base_fee),
         number: payload.attributes.parent_header.number + 1, // ensure this matches the block env
-        gas_limit: block_gas_limit,
+        gas_limit: payload.attributes.gas_limit,
         difficulty: U256::from(payload.attributes.batch_index),
         gas_used: cumulative_gas_used,
         extra_data: payload.attributes.batch_digest.into(),
@@ -430,22 +535,172 @@ where
     };
 
     let sealed_block = block.seal_slow();
+
+    if let Some(audit) = audit {
+        let audited = AuditedBlock {
+            number: sealed_block.number,
+            hash: sealed_block.hash(),
+            batch_digest: payload.attributes.batch_digest,
+            consensus_header_hash,
+            transactions: audited,
+        };
+        if let Err(e) = audit.record(&audited) {
+            error!(target: "engine", block = audited.number, ?e, "failed to record audit traces");
+        }
+    }
+
     let sealed_block_with_senders = SealedBlockWithSenders::new(sealed_block, senders)
         .ok_or(TnEngineError::SealBlockWithSenders)?;
 
     Ok(sealed_block_with_senders)
 }
 
+/// The transactions executed for a batch.
+struct ExecutedTransactions {
+    /// The gas used by all executed transactions.
+    cumulative_gas_used: u64,
+    /// The executed transactions in order.
+    executed_txs: Vec<TransactionSigned>,
+    /// The recovered senders of the executed transactions.
+    senders: Vec<Address>,
+    /// The receipts of the executed transactions.
+    receipts: Vec<Option<Receipt>>,
+}
+
+/// Execute the transactions in a batch and commit the changes to the EVM's database.
+///
+/// `recovered` holds the decoded transactions of the batch with their senders, in order.
+/// Transactions already executed for the output and transactions rejected by the EVM are skipped,
+/// executed transactions are recorded in `output_txs`. `on_executed` is called with the EVM's
+/// inspector after each transaction is executed and before its changes are committed.
+fn execute_batch_transactions<EvmConfig, EXT, DB>(
+    evm_config: &EvmConfig,
+    evm: &mut Evm<'_, EXT, DB>,
+    batch: &Batch,
+    recovered: Vec<Result<RecoveredTx, SenderRecoveryError>>,
+    batch_index: usize,
+    base_fee: u64,
+    output_txs: &mut OutputTransactions,
+    mut on_executed: impl FnMut(&mut EXT, TxHash, Address, &ResultAndState),
+) -> EngineResult<ExecutedTransactions>
+where
+    EvmConfig: ConfigureEvm<Transaction = TransactionSigned>,
+    DB: Database<Error = ProviderError> + DatabaseCommit,
+{
+    // collect these totals to report at the end
+    let mut cumulative_gas_used = 0;
+    let mut total_fees = U256::ZERO;
+    let mut executed_txs = Vec::new();
+    let mut senders = Vec::new();
+    let mut receipts = Vec::new();
+
+    for (tx_index, recovered) in recovered.into_iter().enumerate() {
+        // hashes are computed from the encoded transaction, so duplicates are skipped without
+        // recovering the signer
+        let tx_hash = keccak256(&batch.transactions[tx_index]);
+        if let Some(first_batch_index) = output_txs.executed_in(&tx_hash) {
+            info!(
+                target: "engine",
+                batch = ?batch.digest(),
+                ?tx_hash,
+                batch_index,
+                first_batch_index,
+                "skipping duplicate transaction"
+            );
+            output_txs.metrics.duplicate_transactions.increment(1);
+            continue;
+        }
+
+        let recovered = recovered.inspect_err(|e| {
+            error!(
+                target: "engine",
+                batch = ?batch.digest(),
+                tx_bytes = ?batch.transactions[tx_index],
+                "failed to recover signer: {e}"
+            )
+        })?;
+
+        // Configure the environment for the tx.
+        *evm.tx_mut() = evm_config.tx_env(recovered.tx(), recovered.signer());
+
+        let result_and_state = match evm.transact() {
+            Ok(res) => res,
+            Err(err) => {
+                match err {
+                    // allow transaction errors (ie - duplicates)
+                    //
+                    // it's possible that another worker's batch included this transaction
+                    EVMError::Transaction(err) => {
+                        warn!(target: "engine", tx_hash=?recovered.hash(), ?err);
+                        output_txs.metrics.rejected_transactions.increment(1);
+                        continue;
+                    }
+                    err => {
+                        // this is an error that we should treat as fatal
+                        // - invalid header resulting from misconfigured BlockEnv
+                        // - Database error
+                        // - custom error (unsure)
+                        return Err(err.into());
+                    }
+                }
+            }
+        };
+
+        on_executed(
+            &mut evm.context.external,
+            recovered.hash(),
+            recovered.signer(),
+            &result_and_state,
+        );
+
+        // commit changes
+        let ResultAndState { result, state } = result_and_state;
+        evm.db_mut().commit(state);
+        output_txs.record_executed(tx_hash, batch_index);
+
+        let gas_used = result.gas_used();
+
+        // add gas used by the transaction to cumulative gas used, before creating the receipt
+        cumulative_gas_used += gas_used;
+
+        // Push transaction changeset and calculate header bloom filter for receipt.
+        receipts.push(Some(Receipt {
+            tx_type: recovered.tx_type(),
+            success: result.is_success(),
+            cumulative_gas_used,
+            logs: result.into_logs().into_iter().collect(),
+        }));
+
+        // update add to total fees
+        let miner_fee = recovered
+            .effective_tip_per_gas(Some(base_fee))
+            .expect("fee is always valid; execution succeeded");
+        total_fees += U256::from(miner_fee) * U256::from(gas_used);
+
+        // append transaction to the list of executed transactions and keep signers
+        senders.push(recovered.signer());
+        executed_txs.push(recovered.into_tx());
+    }
+
+    Ok(ExecutedTransactions { cumulative_gas_used, executed_txs, senders, receipts })
+}
+
 /// Extend the canonical tip with one block, despite no blocks from workers are included in the
 /// output from consensus.
+///
+/// The block makes the system calls for both the start and the end of the sub-dag.
 #[inline]
-fn build_block_from_empty_payload<Provider>(
+fn build_block_from_empty_payload<EvmConfig, Provider>(
+    evm_config: &EvmConfig,
     payload: TNPayload,
     provider: &Provider,
     chain_spec: Arc<ChainSpec>,
     consensus_header_digest: B256,
+    system_calls: &SubDagSystemCalls,
+    dev_state: Option<&DevStateChanges>,
 ) -> EngineResult<SealedBlockWithSenders>
 where
+    EvmConfig: ConfigureEvm<Transaction = TransactionSigned>,
     Provider: StateProviderFactory,
 {
     let state =
@@ -466,7 +721,18 @@ where
     // initialize values for execution from block env
     //
     // use the parent's header bc there are no batches and the header arg is not used
-    let (_cfg, block_env) = payload.cfg_and_block_env(chain_spec.as_ref());
+    let (cfg, block_env) = payload.cfg_and_block_env(chain_spec.as_ref());
+
+    if let Some(dev_state) = dev_state {
+        apply_dev_state_changes(&mut db, dev_state.take())?;
+    }
+
+    let env = EnvWithHandlerCfg::new_with_cfg_env(cfg, block_env.clone(), TxEnv::default());
+    apply_system_calls(evm_config, &mut db, &env, system_calls.start())?;
+    apply_system_calls(evm_config, &mut db, &env, system_calls.end())?;
+
+    let withdrawals_root =
+        commit_withdrawals(&mut db, &chain_spec, payload.timestamp(), payload.withdrawals())?;
 
     // merge all transitions into bundle state, this would apply the withdrawal balance
     // changes and 4788 contract call
@@ -494,7 +760,7 @@ where
         state_root,
         transactions_root: EMPTY_TRANSACTIONS,
         receipts_root: EMPTY_RECEIPTS,
-        withdrawals_root: Some(EMPTY_WITHDRAWALS),
+        withdrawals_root: Some(withdrawals_root),
         logs_bloom: Default::default(),
         timestamp: payload.timestamp(),
         mix_hash: payload.prev_randao(),
@@ -525,3 +791,27 @@ where
 
     Ok(sealed_block_with_senders)
 }
+
+/// Credit withdrawals to their recipients and return the block's withdrawals root.
+///
+/// Per EIP-4895, withdrawals are applied after all transactions in the block and do not consume
+/// gas. Each withdrawal's amount is denominated in gwei.
+fn commit_withdrawals<DB>(
+    db: &mut State<DB>,
+    chain_spec: &ChainSpec,
+    timestamp: u64,
+    withdrawals: &Withdrawals,
+) -> EngineResult<B256>
+where
+    DB: Database<Error = ProviderError>,
+{
+    if withdrawals.is_empty() {
+        return Ok(EMPTY_WITHDRAWALS);
+    }
+
+    let balance_increments =
+        post_block_withdrawals_balance_increments(chain_spec, timestamp, withdrawals);
+    db.increment_balances(balance_increments)?;
+
+    Ok(calculate_withdrawals_root(withdrawals))
+}
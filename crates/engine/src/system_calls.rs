@@ -0,0 +1,155 @@
+//! Make the chain spec's system calls at the boundaries of committed sub-dags.
+//!
+//! Calls for the start of a sub-dag are made before the transactions of its first block and calls
+//! for the end after the transactions of its last block. An output without batches executes one
+//! empty block with both. Calls are not transactions: they have no receipt, use no block gas, and
+//! their changes are part of the block's state root like withdrawals. Failed calls are recorded in
+//! the state of [SYSTEM_CALL_FAILURES_ADDRESS] instead of a receipt.
+
+use crate::{error::EngineResult, metrics::ExecutionMetrics};
+use reth_evm::ConfigureEvm;
+use reth_provider::ProviderError;
+use reth_revm::{
+    primitives::{Account, EnvWithHandlerCfg, EvmState, EvmStorageSlot, ResultAndState},
+    Database, DatabaseCommit as _, State,
+};
+use tn_types::{
+    system_call_failure_slots, Address, Bytes, ConsensusOutput, SystemCall, SystemCallPhase,
+    TransactionSigned, SYSTEM_ADDRESS, SYSTEM_CALL_FAILURES_ADDRESS, U256,
+};
+use tracing::{debug, warn};
+
+/// A system call for a sub-dag with its calldata.
+#[derive(Debug, Clone)]
+pub(crate) struct PreparedSystemCall {
+    /// The index of the call in the chain spec.
+    index: usize,
+    /// The number of the sub-dag the call is made for.
+    sub_dag: u64,
+    /// The contract to call.
+    address: Address,
+    /// The gas available to the call.
+    gas_limit: u64,
+    /// The calldata for the sub-dag.
+    calldata: Bytes,
+}
+
+/// The system calls made for the sub-dag of a consensus output.
+#[derive(Debug, Default)]
+pub(crate) struct SubDagSystemCalls {
+    /// The calls made before the sub-dag's transactions, in order.
+    start: Vec<PreparedSystemCall>,
+    /// The calls made after the sub-dag's transactions, in order.
+    end: Vec<PreparedSystemCall>,
+}
+
+impl SubDagSystemCalls {
+    /// Prepare the calls for the output's sub-dag.
+    pub(crate) fn new(system_calls: &[SystemCall], output: &ConsensusOutput) -> Self {
+        let mut calls = Self::default();
+        for (index, call) in system_calls.iter().enumerate() {
+            let prepared = PreparedSystemCall {
+                index,
+                sub_dag: output.number,
+                address: call.address,
+                gas_limit: call.gas_limit,
+                calldata: call
+                    .calldata(output.number, output.consensus_header_hash(), output.committed_at())
+                    .into(),
+            };
+            match call.phase {
+                SystemCallPhase::SubDagStart => calls.start.push(prepared),
+                SystemCallPhase::SubDagEnd => calls.end.push(prepared),
+            }
+        }
+        calls
+    }
+
+    /// The calls made before the sub-dag's transactions.
+    pub(crate) fn start(&self) -> &[PreparedSystemCall] {
+        &self.start
+    }
+
+    /// The calls made after the sub-dag's transactions.
+    pub(crate) fn end(&self) -> &[PreparedSystemCall] {
+        &self.end
+    }
+}
+
+/// Make the system calls in order and commit the changes of each successful call.
+///
+/// Calls are made from [SYSTEM_ADDRESS] without a base fee and the caller is removed from the
+/// state afterwards. A call that reverts or halts only changes its record in
+/// [SYSTEM_CALL_FAILURES_ADDRESS]. Errors from the database are returned.
+pub(crate) fn apply_system_calls<EvmConfig, DB>(
+    evm_config: &EvmConfig,
+    db: &mut State<DB>,
+    env: &EnvWithHandlerCfg,
+    calls: &[PreparedSystemCall],
+) -> EngineResult<()>
+where
+    EvmConfig: ConfigureEvm<Transaction = TransactionSigned>,
+    DB: Database<Error = ProviderError>,
+{
+    if calls.is_empty() {
+        return Ok(());
+    }
+
+    let mut evm = evm_config.evm_with_env(db, env.clone());
+    for call in calls {
+        evm_config.fill_tx_env_system_contract_call(
+            &mut evm.context.evm.env,
+            SYSTEM_ADDRESS,
+            call.address,
+            call.calldata.clone(),
+        );
+        evm.context.evm.env.tx.gas_limit = call.gas_limit;
+        evm.context.evm.env.block.gas_limit = U256::from(call.gas_limit);
+
+        let ResultAndState { result, mut state } = evm.transact()?;
+        if !result.is_success() {
+            warn!(target: "engine", contract = ?call.address, ?result, "system call failed");
+            ExecutionMetrics::default().failed_system_calls.increment(1);
+            record_system_call_failure(evm.db_mut(), call)?;
+            continue;
+        }
+        debug!(
+            target: "engine",
+            contract = ?call.address,
+            gas_used = result.gas_used(),
+            "system call made"
+        );
+
+        // the caller's nonce is not part of the chain's state
+        state.remove(&SYSTEM_ADDRESS);
+        evm.db_mut().commit(state);
+    }
+    Ok(())
+}
+
+/// Count the failure of the call and save its sub-dag in [SYSTEM_CALL_FAILURES_ADDRESS].
+fn record_system_call_failure<DB>(db: &mut State<DB>, call: &PreparedSystemCall) -> EngineResult<()>
+where
+    DB: Database<Error = ProviderError>,
+{
+    let (count_slot, sub_dag_slot) = system_call_failure_slots(call.index);
+    let count = db.storage(SYSTEM_CALL_FAILURES_ADDRESS, count_slot)?;
+    let sub_dag = db.storage(SYSTEM_CALL_FAILURES_ADDRESS, sub_dag_slot)?;
+
+    // a nonce keeps the account from being removed as empty when the changes are committed
+    let mut info = db.basic(SYSTEM_CALL_FAILURES_ADDRESS)?.unwrap_or_default();
+    info.nonce = info.nonce.max(1);
+    let mut account = Account::from(info);
+    account.storage.insert(
+        count_slot,
+        EvmStorageSlot::new_changed(count, count.saturating_add(U256::from(1))),
+    );
+    account
+        .storage
+        .insert(sub_dag_slot, EvmStorageSlot::new_changed(sub_dag, U256::from(call.sub_dag)));
+    account.mark_touch();
+    let mut state = EvmState::default();
+    state.insert(SYSTEM_CALL_FAILURES_ADDRESS, account);
+    db.commit(state);
+    Ok(())
+}
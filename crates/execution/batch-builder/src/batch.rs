@@ -10,7 +10,7 @@ use crate::error::BatchBuilderError;
 use reth_primitives_traits::InMemorySize as _;
 use reth_transaction_pool::{error::InvalidPoolTransactionError, PoolTransaction, TransactionPool};
 use tn_types::{
-    max_batch_gas, max_batch_size, now, Batch, BatchBuilderArgs, Encodable2718 as _,
+    batch_gas_limit, max_batch_size, Batch, BatchBuilderArgs, Encodable2718 as _,
     PendingBlockConfig, TransactionSigned, TransactionTrait as _, TxHash,
 };
 use tracing::{debug, warn};
@@ -38,7 +38,7 @@ pub struct BatchBuilderOutput {
 ///
 /// Returns the [`BatchBuilderOutput`] and cannot fail. The batch continues to add
 /// transactions to the proposed block until either:
-/// - accumulated transaction gas limit reached (measured by tx.gas_limit())
+/// - accumulated transaction gas limit reached the tip's gas limit (measured by tx.gas_limit())
 /// - max byte size of transactions (measured by tx.size())
 ///
 /// NOTE: it's possible to under utilize resources if users submit transactions
@@ -50,8 +50,8 @@ where
     P: TransactionPool,
     P::Transaction: PoolTransaction<Consensus = TransactionSigned>,
 {
-    let BatchBuilderArgs { pool, batch_config } = args;
-    let gas_limit = max_batch_gas(batch_config.parent_info.tip.timestamp);
+    let BatchBuilderArgs { pool, batch_config, dedup_filter, clock } = args;
+    let gas_limit = batch_gas_limit(&batch_config.parent_info.tip.header);
     let max_size = max_batch_size(batch_config.parent_info.tip.timestamp);
     let PendingBlockConfig { beneficiary, parent_info } = batch_config;
 
@@ -72,7 +72,19 @@ where
     // begin loop through sorted "best" transactions in pending pool
     // and execute them to build the block
     while let Some(pool_tx) = best_txs.next() {
-        // filter best transactions against Arc<hashset<TxHash>>
+        // skip transactions a peer already included in a batch
+        //
+        // dependents are skipped too - their nonce is only valid after the duplicate executes
+        if dedup_filter.as_ref().is_some_and(|filter| filter.contains(pool_tx.hash())) {
+            best_txs.mark_invalid(
+                &pool_tx,
+                InvalidPoolTransactionError::Other(Box::new(BatchBuilderError::IncludedByPeer(
+                    *pool_tx.hash(),
+                ))),
+            );
+            debug!(target: "worker::batch_builder", ?pool_tx, "skipping tx included in peer batch");
+            continue;
+        }
 
         // ensure block has capacity (in gas) for this transaction
         if total_possible_gas + pool_tx.gas_limit() > gas_limit {
@@ -119,13 +131,17 @@ where
         transactions.push(tx.into_tx().encoded_2718());
     }
 
-    // sometimes batch are produced too quickly in certain configs (<1s diff)
-    // resulting in batch timestamp == parent timestamp
-    //
-    // TODO: check for this error at the quorum waiter level?
-    let mut timestamp = now();
-    if timestamp == parent_info.tip.timestamp {
-        warn!(target: "worker::batch_builder", "new block timestamp same as parent - setting offset by 1sec");
+    // batches must be after their parent, but the parent can be ahead of the local clock when
+    // batches are produced quickly (<1s diff) or the timestamp policy runs blocks ahead of the
+    // commit timestamp
+    let mut timestamp = clock.now();
+    if timestamp <= parent_info.tip.timestamp {
+        warn!(
+            target: "worker::batch_builder",
+            parent = parent_info.tip.timestamp,
+            timestamp,
+            "new batch timestamp not after parent - setting offset by 1sec"
+        );
         timestamp = parent_info.tip.timestamp + 1;
     }
 
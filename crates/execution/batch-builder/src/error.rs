@@ -2,6 +2,7 @@
 
 use reth_errors::{CanonicalError, ProviderError, RethError};
 use reth_transaction_pool::error::PoolTransactionError;
+use tn_types::TxHash;
 use tokio::sync::{mpsc, oneshot};
 
 /// Result alias for [`TNEngineError`].
@@ -40,6 +41,9 @@ pub enum BatchBuilderError {
         "The transaction was not included becuase it would exceed the max batch size. Tx size: {0} bytes - max size: {1} bytes."
     )]
     MaxBatchSize(usize, usize),
+    /// The transaction was already included in a batch from a peer.
+    #[error("The transaction {0} was already included in a peer's batch.")]
+    IncludedByPeer(TxHash),
 }
 
 impl From<oneshot::error::RecvError> for BatchBuilderError {
@@ -29,6 +29,7 @@ use reth_execution_types::ChangedAccount;
 use reth_provider::{CanonStateNotification, CanonStateNotificationStream, Chain};
 use reth_transaction_pool::{
     CanonicalStateUpdate, PoolTransaction, PoolUpdateKind, TransactionPool, TransactionPoolExt,
+    ValidPoolTransaction,
 };
 use std::{
     future::Future,
@@ -37,11 +38,16 @@ use std::{
     task::{Context, Poll},
     time::Duration,
 };
+use tn_config::MiningMode;
 use tn_types::{
-    error::BlockSealError, Address, BatchBuilderArgs, BatchSender, LastCanonicalUpdate,
-    PendingBlockConfig, TransactionSigned, TxHash, MIN_PROTOCOL_BASE_FEE,
+    error::BlockSealError, Address, BatchBuilderArgs, BatchSender, DeferredTransactions,
+    LastCanonicalUpdate, PendingBlockConfig, SealedBatch, SharedClock, SystemClock,
+    TransactionSigned, TxDedupFilter, TxHash, MIN_PROTOCOL_BASE_FEE,
+};
+use tokio::{
+    sync::{mpsc, oneshot},
+    time::{Instant, Interval, MissedTickBehavior},
 };
-use tokio::{sync::oneshot, time::Interval};
 use tracing::{debug, error, trace, warn};
 
 mod batch;
@@ -50,7 +56,31 @@ mod error;
 pub mod test_utils;
 
 /// Type alias for the blocking task that locks the tx pool and builds the next batch.
-type BuildResult = oneshot::Receiver<BatchBuilderResult<Vec<TxHash>>>;
+type BuildResult = oneshot::Receiver<BatchBuilderResult<SealResult>>;
+
+/// The result of sending a batch to the worker.
+#[derive(Debug)]
+struct SealResult {
+    /// The transactions mined once the batch reached quorum, empty if it did not.
+    mined_transactions: Vec<TxHash>,
+    /// The batch to send again if it failed to reach quorum but peers may still accept it.
+    retry: Option<RetryBatch>,
+}
+
+/// A batch that failed to reach quorum and the transactions it mines.
+#[derive(Debug)]
+struct RetryBatch {
+    /// The sealed batch, peers that already have it treat it as the same batch.
+    batch: SealedBatch,
+    /// The transactions mined once the batch reaches quorum.
+    mined_transactions: Vec<TxHash>,
+}
+
+/// Reads the batch gas target set by governance.
+pub trait GasTargetSource: std::fmt::Debug + Send + Sync {
+    /// The gas target after the latest executed output, `None` to keep the configured target.
+    fn gas_target(&self) -> Option<u64>;
+}
 
 /// The type that builds blocks for workers to propose.
 ///
@@ -99,6 +129,41 @@ pub struct BatchBuilder<BT, Pool> {
     /// This interval wakes the task periodically to check on the progress of the latest built
     /// block and the pending transaction pool.
     max_delay_interval: Interval,
+    /// The amount of time a remote transaction can remain queued before it is evicted.
+    ///
+    /// Queued transactions are never evicted by age if this is `None`.
+    max_queued_lifetime: Option<Duration>,
+    /// Transactions already included in batches from peers.
+    ///
+    /// These transactions are left out of new batches. All transactions are considered if this is
+    /// `None`.
+    dedup_filter: Option<TxDedupFilter>,
+    /// Transactions accepted while the worker's batches can't reach quorum.
+    ///
+    /// Seals are retried every max delay while quorum is lost and deferred transactions are
+    /// flushed once it is restored. Failed seals wait for a canonical update if this is `None`.
+    deferred: Option<DeferredTransactions>,
+    /// The last batch that timed out or lost quorum.
+    ///
+    /// The worker may have broadcast it already, so seals retried during a consensus outage send
+    /// it again instead of sealing its transactions into a new batch that would duplicate them.
+    /// Dropped on the next canonical update.
+    retry_batch: Option<RetryBatch>,
+    /// When to seal the pending transactions.
+    mining_mode: MiningMode,
+    /// The mining mode set by the node's config, before any governed gas target is applied.
+    configured_mining_mode: MiningMode,
+    /// Replaces the configured gas target after each canonical update, if set.
+    gas_target_source: Option<Arc<dyn GasTargetSource>>,
+    /// The interval for sealing batches if the mining mode has one.
+    seal_interval: Option<Interval>,
+    /// Seal an empty batch every max delay while the pool is idle.
+    seal_empty_batches: bool,
+    /// Requests to seal the pending transactions immediately, or an empty batch if none are
+    /// pending.
+    seal_requests: Option<mpsc::Receiver<()>>,
+    /// The time source for batch timestamps.
+    clock: SharedClock,
 }
 
 impl<BT, Pool> BatchBuilder<BT, Pool>
@@ -126,6 +191,168 @@ where
             to_worker,
             address,
             max_delay_interval,
+            max_queued_lifetime: None,
+            dedup_filter: None,
+            deferred: None,
+            retry_batch: None,
+            mining_mode: MiningMode::Instant,
+            configured_mining_mode: MiningMode::Instant,
+            gas_target_source: None,
+            seal_interval: None,
+            seal_empty_batches: false,
+            seal_requests: None,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Evict remote transactions that remain queued longer than `lifetime`.
+    ///
+    /// Stale transactions are evicted after each canonical state update.
+    pub fn with_max_queued_lifetime(mut self, lifetime: Duration) -> Self {
+        self.max_queued_lifetime = Some(lifetime);
+        self
+    }
+
+    /// Leave transactions that peers already included in a batch out of new batches.
+    pub fn with_dedup_filter(mut self, dedup_filter: TxDedupFilter) -> Self {
+        self.dedup_filter = Some(dedup_filter);
+        self
+    }
+
+    /// Report quorum for sealed batches to `deferred` and flush its transactions once quorum is
+    /// restored.
+    pub fn with_deferred_transactions(mut self, deferred: DeferredTransactions) -> Self {
+        self.deferred = Some(deferred);
+        self
+    }
+
+    /// Seal batches according to `mining_mode` instead of as soon as transactions are pending.
+    pub fn with_mining_mode(mut self, mining_mode: MiningMode) -> Self {
+        self.seal_interval = mining_mode.interval().map(|period| {
+            let mut interval = tokio::time::interval_at(Instant::now() + period, period);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            interval
+        });
+        self.configured_mining_mode = mining_mode;
+        self.update_gas_target();
+        self
+    }
+
+    /// Seal batches at the gas target read from `source` instead of the configured target.
+    ///
+    /// The target is read again after each canonical update. The seal interval always comes from
+    /// the configured mining mode.
+    pub fn with_gas_target_source(mut self, source: Arc<dyn GasTargetSource>) -> Self {
+        self.gas_target_source = Some(source);
+        self.update_gas_target();
+        self
+    }
+
+    /// Apply the gas target from the source, if any, to the configured mining mode.
+    fn update_gas_target(&mut self) {
+        let gas_target = self.gas_target_source.as_ref().and_then(|source| source.gas_target());
+        self.mining_mode = self.configured_mining_mode.with_gas_target(gas_target);
+    }
+
+    /// Timestamp batches with `clock` instead of the system time.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    /// Seal an empty batch every max delay while there are no pending transactions.
+    ///
+    /// Peers must accept empty batches, otherwise they never reach quorum.
+    pub fn with_empty_batches(mut self, seal_empty_batches: bool) -> Self {
+        self.seal_empty_batches = seal_empty_batches;
+        self
+    }
+
+    /// Seal a batch immediately for each request received on `seal_requests`.
+    ///
+    /// The batch has the pending transactions regardless of the mining mode, or no transactions
+    /// if none are pending. Used to mine blocks on demand in dev networks.
+    pub fn with_seal_requests(mut self, seal_requests: mpsc::Receiver<()>) -> Self {
+        self.seal_requests = Some(seal_requests);
+        self
+    }
+
+    /// Return true if a batch was requested through `seal_requests`.
+    ///
+    /// Takes one request at a time so each request seals its own batch.
+    fn seal_requested(&mut self, cx: &mut Context<'_>) -> bool {
+        let Some(seal_requests) = self.seal_requests.as_mut() else {
+            return false;
+        };
+        match seal_requests.poll_recv(cx) {
+            Poll::Ready(Some(())) => true,
+            Poll::Ready(None) => {
+                self.seal_requests = None;
+                false
+            }
+            Poll::Pending => false,
+        }
+    }
+
+    /// Return true if the pending transactions should be sealed now.
+    ///
+    /// Registers the waker for the next seal interval if they should not.
+    fn ready_to_seal(
+        &mut self,
+        pending: &[Arc<ValidPoolTransaction<Pool::Transaction>>],
+        cx: &mut Context<'_>,
+    ) -> bool {
+        let Some(interval) = self.seal_interval.as_mut() else {
+            return true;
+        };
+        // flush transactions deferred during a consensus outage
+        if self.deferred.as_ref().is_some_and(|deferred| {
+            !deferred.is_quorum_lost() && deferred.any_deferred(pending.iter().map(|tx| tx.hash()))
+        }) {
+            trace!(target: "block-builder", "flushing deferred transactions");
+            interval.reset();
+            return true;
+        }
+        if interval.poll_tick(cx).is_ready() {
+            return true;
+        }
+
+        let (gas, size) = pending.iter().fold((0u64, 0usize), |(gas, size), tx| {
+            (gas.saturating_add(tx.gas_limit()), size.saturating_add(tx.encoded_length()))
+        });
+        if self.mining_mode.target_reached(gas, size) {
+            trace!(target: "block-builder", gas, size, "pending transactions reached target");
+            // the next interval starts with this batch
+            interval.reset();
+            return true;
+        }
+        false
+    }
+
+    /// Remove remote transactions that have been queued longer than the max lifetime.
+    ///
+    /// Queued transactions can not be included in a batch until their nonce gap is filled. Local
+    /// transactions are never evicted.
+    fn evict_stale_queued_transactions(&self) {
+        let Some(lifetime) = self.max_queued_lifetime else {
+            return;
+        };
+
+        let stale: Vec<TxHash> = self
+            .pool
+            .queued_transactions()
+            .into_iter()
+            .filter(|tx| !tx.origin.is_local() && tx.timestamp.elapsed() > lifetime)
+            .map(|tx| *tx.hash())
+            .collect();
+
+        if !stale.is_empty() {
+            debug!(
+                target: "block-builder",
+                evicted = stale.len(),
+                "evicting stale queued transactions"
+            );
+            self.pool.remove_transactions(stale);
         }
     }
 
@@ -186,6 +413,21 @@ where
 
         // sync fn so self will block until all pool updates are complete
         self.pool.on_canonical_state_change(update);
+
+        self.evict_stale_queued_transactions();
+        self.retain_deferred();
+
+        // consensus is making progress, so pending transactions are sealed on the new tip
+        if self.retry_batch.take().is_some() {
+            debug!(target: "block-builder", "dropping batch that failed to reach quorum");
+        }
+    }
+
+    /// Forget deferred transactions that left the pool.
+    fn retain_deferred(&self) {
+        if let Some(deferred) = self.deferred.as_ref() {
+            deferred.retain(|tx_hash| self.pool.contains(tx_hash));
+        }
     }
 
     /// Spawns a task to build the batch and proposer to peers.
@@ -199,14 +441,19 @@ where
     /// - convert result to fatal/non-fatal
     /// - return result
     ///
-    /// Workers only propose one block at a time.
-    fn spawn_execution_task(&self) -> BuildResult {
+    /// Workers only propose one block at a time. A batch that failed to reach quorum is sent
+    /// again before a new batch is built.
+    fn spawn_execution_task(&mut self) -> BuildResult {
         let pool = self.pool.clone();
         let to_worker = self.to_worker.clone();
+        let deferred = self.deferred.clone();
+        let retry = self.retry_batch.take();
 
         // configure params for next block to build
         let config = PendingBlockConfig::new(self.address, self.latest_canon_state.clone());
-        let build_args = BatchBuilderArgs::new(pool.clone(), config);
+        let build_args = BatchBuilderArgs::new(pool.clone(), config)
+            .with_dedup_filter(self.dedup_filter.clone())
+            .with_clock(self.clock.clone());
         let (result, done) = oneshot::channel();
 
         // spawn block building task and forward to worker
@@ -214,11 +461,20 @@ where
             // ack once worker reaches quorum
             let (ack, rx) = oneshot::channel();
 
-            // this is safe to call without a semaphore bc it's held as a single `Option`
-            let BatchBuilderOutput { batch, mined_transactions } = build_batch(build_args);
+            let RetryBatch { batch, mined_transactions } = match retry {
+                Some(retry) => {
+                    debug!(target: "block-builder", "sending batch that failed to reach quorum");
+                    retry
+                }
+                None => {
+                    // this is safe to call without a semaphore bc it's held as a single `Option`
+                    let BatchBuilderOutput { batch, mined_transactions } = build_batch(build_args);
+                    RetryBatch { batch: batch.seal_slow(), mined_transactions }
+                }
+            };
 
             // forward to worker and wait for ack that quorum was reached
-            if let Err(e) = to_worker.send((batch.seal_slow(), ack)).await {
+            if let Err(e) = to_worker.send((batch.clone(), ack)).await {
                 error!(target: "worker::batch_builder", ?e, "failed to send next batch to worker");
                 // try to return error if worker channel closed
                 let _ = result.send(Err(e.into()));
@@ -231,13 +487,28 @@ where
                     match res {
                         Ok(_) => {
                             debug!(target: "block-builder", ?res, "received ack");
+                            if let Some(deferred) = deferred.as_ref() {
+                                deferred.quorum_restored();
+                            }
                             // signal to Self that this task is complete
-                            if let Err(e) = result.send(Ok(mined_transactions)) {
+                            let sealed = SealResult { mined_transactions, retry: None };
+                            if let Err(e) = result.send(Ok(sealed)) {
                                 error!(target: "worker::batch_builder", ?e, "failed to send block builder result to block builder task");
                             }
                         }
                         Err(error) => {
                             error!(target: "worker::batch_builder", ?error, "error while sealing batch");
+                            // peers rejecting this batch doesn't mean quorum is lost
+                            let quorum_lost = !matches!(
+                                error,
+                                BlockSealError::QuorumRejected | BlockSealError::FatalDBFailure
+                            );
+                            if let (true, Some(deferred)) = (quorum_lost, deferred.as_ref()) {
+                                deferred.quorum_lost();
+                            }
+                            // peers may still accept a batch that lost quorum
+                            let retry =
+                                quorum_lost.then_some(RetryBatch { batch, mined_transactions });
                             let converted = match error {
                                 BlockSealError::FatalDBFailure => {
                                     // fatal - return error
@@ -251,7 +522,7 @@ where
                                     //
                                     // return empty vec to indicate no transactions mined
                                     // NOTE: this will apply no changes to transaction pool
-                                    Ok(vec![])
+                                    Ok(SealResult { mined_transactions: vec![], retry })
                                 }
                             };
 
@@ -312,6 +583,7 @@ where
                 match canon_update {
                     CanonStateNotification::Commit { new } => {
                         this.process_canon_state_update(new);
+                        this.update_gas_target();
                     }
                     _ => unreachable!("TN reorgs are impossible"),
                 }
@@ -326,7 +598,18 @@ where
                 //
                 // considered using: pool.pool_size().pending
                 // but that calculates size for all sub-pools
-                if this.pool.pending_transactions().is_empty() {
+                let pending = this.pool.pending_transactions();
+                if this.seal_requested(cx) {
+                    trace!(target: "block-builder", "sealing requested batch");
+                    this.pending_task = Some(this.spawn_execution_task());
+                } else if pending.is_empty() && this.seal_empty_batches {
+                    // keep a steady cadence with an empty batch every max delay
+                    if this.max_delay_interval.poll_tick(cx).is_pending() {
+                        break;
+                    }
+                    trace!(target: "block-builder", "sealing empty batch");
+                    this.pending_task = Some(this.spawn_execution_task());
+                } else if pending.is_empty() {
                     // reset interval to wake up after some time
                     //
                     // only need to reset here if there is no pending block being built
@@ -337,10 +620,15 @@ where
 
                     // nothing pending
                     break;
-                }
+                } else {
+                    // wait for the seal interval or a target in interval and hybrid modes
+                    if !this.ready_to_seal(&pending, cx) {
+                        break;
+                    }
 
-                // start building the next block
-                this.pending_task = Some(this.spawn_execution_task());
+                    // start building the next block
+                    this.pending_task = Some(this.spawn_execution_task());
+                }
 
                 // don't break so pending_task receiver gets polled
             }
@@ -354,10 +642,28 @@ where
                         // TODO: update tree's pending block?
 
                         // ensure no fatal errors
-                        let mined_transactions = res??;
+                        let SealResult { mined_transactions, retry } = res??;
+                        if retry.is_some() {
+                            this.retry_batch = retry;
+                        }
 
                         // NOTE: empty vec returned for non-fatal error during block proposal
                         if mined_transactions.is_empty() {
+                            if this.seal_empty_batches {
+                                // the max delay interval wakes the task for the next batch
+                                continue;
+                            }
+                            // poll for seal requests so the next request wakes the task
+                            if this.seal_requested(cx) {
+                                this.pending_task = Some(this.spawn_execution_task());
+                                continue;
+                            }
+                            // canonical updates stop during a consensus outage, so retry the seal
+                            // after the max delay
+                            if this.deferred.as_ref().is_some_and(|d| d.is_quorum_lost()) {
+                                this.max_delay_interval.reset();
+                                let _ = this.max_delay_interval.poll_tick(cx);
+                            }
                             // return pending and wait for canonical update to wake up again
                             break;
                         }
@@ -383,6 +689,7 @@ where
                         //
                         // update pool to remove mined transactions
                         this.pool.on_canonical_state_change(update);
+                        this.retain_deferred();
 
                         // loop again to check for any other pending transactions
                         // and possibly start building the next block
@@ -444,8 +751,9 @@ mod tests {
     use tn_storage::{open_db, tables::Batches};
     use tn_test_utils::{adiri_genesis_seeded, get_gas_price, TransactionFactory};
     use tn_types::{
-        adiri_genesis, BlockBody, Bytes, CommittedSubDag, ConsensusHeader, ConsensusOutput,
-        Database, GenesisAccount, SealedBatch, SealedBlock, TaskManager, U160, U256,
+        adiri_genesis, keccak256, BlockBody, Bytes, CommittedSubDag, ConsensusHeader,
+        ConsensusOutput, Database, GenesisAccount, SealedBatch, SealedBlock, TaskManager, U160,
+        U256,
     };
     use tn_worker::{
         metrics::WorkerMetrics,
@@ -531,7 +839,9 @@ mod tests {
             client,
             store.clone(),
             timeout,
+            tn_worker::CHANNEL_CAPACITY,
             WorkerNetworkHandle::new_for_test(),
+            None,
         );
 
         let tx_pool_latest = txpool.block_info();
@@ -862,6 +1172,7 @@ mod tests {
                 number: 0,
                 extra: Default::default(),
                 early_finalize: true,
+                withdrawals: Default::default(),
             };
             // execute output to trigger canonical update
             let args = BuildArguments::new(blockchain_db.clone(), output, parent);
@@ -1022,4 +1333,352 @@ mod tests {
         let pending_pool_len = txpool.pool_size().pending;
         assert_eq!(pending_pool_len, 0);
     }
+
+    /// Test interval and hybrid modes wait to seal pending transactions.
+    #[tokio::test]
+    async fn test_mining_modes() {
+        let TestTools { mut tx_factory, last_canonical_update, execution_components } =
+            get_test_tools();
+        let TestExecutionComponents { blockchain_db, txpool, chain, .. } = execution_components;
+        let address = Address::from(U160::from(33));
+        let gas_price = get_gas_price(&blockchain_db);
+        let value = U256::from(10).checked_pow(U256::from(18)).expect("1e18 doesn't overflow U256");
+
+        // interval mode waits for the interval although transactions are pending
+        let (to_worker, mut from_batch_builder) = tokio::sync::mpsc::channel(2);
+        let batch_builder = BatchBuilder::new(
+            blockchain_db.clone(),
+            txpool.clone(),
+            blockchain_db.canonical_state_stream(),
+            last_canonical_update.clone(),
+            to_worker,
+            address,
+            Duration::from_millis(10),
+        )
+        .with_mining_mode(MiningMode::Interval { interval: Duration::from_secs(2) });
+        tx_factory
+            .create_and_submit_eip1559_pool_tx(
+                chain.clone(),
+                gas_price,
+                Address::ZERO,
+                value,
+                &txpool,
+            )
+            .await;
+        let batch_builder_task = tokio::spawn(Box::pin(batch_builder));
+        assert!(timeout(Duration::from_secs(1), from_batch_builder.recv()).await.is_err());
+        let (sealed_batch, _ack) = timeout(Duration::from_secs(3), from_batch_builder.recv())
+            .await
+            .expect("batch sealed after interval")
+            .expect("batch was built");
+        assert_eq!(sealed_batch.batch().transactions().len(), 1);
+        batch_builder_task.abort();
+
+        // hybrid mode seals before the interval once pending transactions reach the gas target
+        let gas_limit = txpool.pending_transactions()[0].gas_limit();
+        let (to_worker, mut from_batch_builder) = tokio::sync::mpsc::channel(2);
+        let batch_builder = BatchBuilder::new(
+            blockchain_db.clone(),
+            txpool.clone(),
+            blockchain_db.canonical_state_stream(),
+            last_canonical_update,
+            to_worker,
+            address,
+            Duration::from_millis(10),
+        )
+        .with_mining_mode(MiningMode::Hybrid {
+            interval: Duration::from_secs(60),
+            gas_target: Some(gas_limit * 2),
+            size_target: None,
+        });
+        let _batch_builder = tokio::spawn(Box::pin(batch_builder));
+        assert!(timeout(Duration::from_secs(1), from_batch_builder.recv()).await.is_err());
+        tx_factory
+            .create_and_submit_eip1559_pool_tx(
+                chain.clone(),
+                gas_price,
+                Address::ZERO,
+                value,
+                &txpool,
+            )
+            .await;
+        let (sealed_batch, _ack) = timeout(Duration::from_secs(3), from_batch_builder.recv())
+            .await
+            .expect("batch sealed at gas target")
+            .expect("batch was built");
+        assert_eq!(sealed_batch.batch().transactions().len(), 2);
+    }
+
+    /// Test a governed gas target replaces the configured target.
+    #[tokio::test]
+    async fn test_gas_target_source() {
+        #[derive(Debug)]
+        struct FixedGasTarget(Option<u64>);
+        impl GasTargetSource for FixedGasTarget {
+            fn gas_target(&self) -> Option<u64> {
+                self.0
+            }
+        }
+
+        let TestTools { last_canonical_update, execution_components, .. } = get_test_tools();
+        let TestExecutionComponents { blockchain_db, txpool, .. } = execution_components;
+        let interval = Duration::from_secs(2);
+        let (to_worker, _from_batch_builder) = tokio::sync::mpsc::channel(2);
+        let batch_builder = BatchBuilder::new(
+            blockchain_db.clone(),
+            txpool,
+            blockchain_db.canonical_state_stream(),
+            last_canonical_update,
+            to_worker,
+            Address::from(U160::from(33)),
+            Duration::from_millis(10),
+        )
+        .with_mining_mode(MiningMode::Interval { interval });
+
+        // no governed target keeps the configured mode
+        let batch_builder = batch_builder.with_gas_target_source(Arc::new(FixedGasTarget(None)));
+        assert_eq!(batch_builder.mining_mode, MiningMode::Interval { interval });
+
+        // the governed target turns interval mining into hybrid mining
+        let batch_builder =
+            batch_builder.with_gas_target_source(Arc::new(FixedGasTarget(Some(1_000_000))));
+        assert_eq!(
+            batch_builder.mining_mode,
+            MiningMode::Hybrid { interval, gas_target: Some(1_000_000), size_target: None }
+        );
+        assert_eq!(batch_builder.configured_mining_mode, MiningMode::Interval { interval });
+    }
+
+    /// Test empty batches are only sealed when enabled.
+    #[tokio::test(start_paused = true)]
+    async fn test_empty_batches() {
+        let TestTools { last_canonical_update, execution_components, .. } = get_test_tools();
+        let TestExecutionComponents { blockchain_db, txpool, .. } = execution_components;
+        let address = Address::from(U160::from(33));
+
+        // idle pool never seals by default
+        let (to_worker, mut from_batch_builder) = tokio::sync::mpsc::channel(2);
+        let batch_builder = BatchBuilder::new(
+            blockchain_db.clone(),
+            txpool.clone(),
+            blockchain_db.canonical_state_stream(),
+            last_canonical_update.clone(),
+            to_worker,
+            address,
+            Duration::from_millis(10),
+        );
+        let batch_builder_task = tokio::spawn(Box::pin(batch_builder));
+        assert!(timeout(Duration::from_millis(500), from_batch_builder.recv()).await.is_err());
+        batch_builder_task.abort();
+
+        // empty batches are sealed every max delay once enabled
+        let (to_worker, mut from_batch_builder) = tokio::sync::mpsc::channel(2);
+        let batch_builder = BatchBuilder::new(
+            blockchain_db.clone(),
+            txpool,
+            blockchain_db.canonical_state_stream(),
+            last_canonical_update,
+            to_worker,
+            address,
+            Duration::from_millis(10),
+        )
+        .with_empty_batches(true);
+        let _batch_builder = tokio::spawn(Box::pin(batch_builder));
+        for _ in 0..2 {
+            let (sealed_batch, ack) = timeout(Duration::from_secs(1), from_batch_builder.recv())
+                .await
+                .expect("empty batch sealed")
+                .expect("batch was built");
+            assert!(sealed_batch.batch().transactions().is_empty());
+            let _ = ack.send(Ok(()));
+        }
+    }
+
+    /// Test transactions peers already included in a batch are left out of new batches.
+    #[tokio::test]
+    async fn test_dedup_filter_skips_peer_transactions() {
+        let TestTools { mut tx_factory, last_canonical_update, execution_components } =
+            get_test_tools();
+        let TestExecutionComponents { blockchain_db, txpool, chain, .. } = execution_components;
+        let address = Address::from(U160::from(33));
+        let gas_price = get_gas_price(&blockchain_db);
+        let value = U256::from(10).checked_pow(U256::from(18)).expect("1e18 doesn't overflow U256");
+
+        let mut tx_hashes = Vec::new();
+        for _ in 0..2 {
+            let tx_hash = tx_factory
+                .create_and_submit_eip1559_pool_tx(
+                    chain.clone(),
+                    gas_price,
+                    Address::ZERO,
+                    value,
+                    &txpool,
+                )
+                .await;
+            tx_hashes.push(tx_hash);
+        }
+
+        // a peer's batch already included the second transaction
+        let dedup_filter = TxDedupFilter::default();
+        dedup_filter.insert(&tx_hashes[1]);
+
+        let (to_worker, mut from_batch_builder) = tokio::sync::mpsc::channel(2);
+        let batch_builder = BatchBuilder::new(
+            blockchain_db.clone(),
+            txpool.clone(),
+            blockchain_db.canonical_state_stream(),
+            last_canonical_update,
+            to_worker,
+            address,
+            Duration::from_millis(10),
+        )
+        .with_dedup_filter(dedup_filter);
+        let _batch_builder = tokio::spawn(Box::pin(batch_builder));
+
+        let (sealed_batch, _ack) = timeout(Duration::from_secs(3), from_batch_builder.recv())
+            .await
+            .expect("batch sealed")
+            .expect("batch was built");
+        let included: Vec<TxHash> =
+            sealed_batch.batch().transactions().iter().map(keccak256).collect();
+        assert_eq!(included, vec![tx_hashes[0]]);
+    }
+
+    /// Test seal requests seal a batch before the interval elapses.
+    #[tokio::test]
+    async fn test_seal_requests() {
+        let TestTools { mut tx_factory, last_canonical_update, execution_components } =
+            get_test_tools();
+        let TestExecutionComponents { blockchain_db, txpool, chain, .. } = execution_components;
+        let address = Address::from(U160::from(33));
+        let gas_price = get_gas_price(&blockchain_db);
+        let value = U256::from(10).checked_pow(U256::from(18)).expect("1e18 doesn't overflow U256");
+
+        let (to_worker, mut from_batch_builder) = tokio::sync::mpsc::channel(2);
+        let (seal_tx, seal_rx) = tokio::sync::mpsc::channel(2);
+        let batch_builder = BatchBuilder::new(
+            blockchain_db.clone(),
+            txpool.clone(),
+            blockchain_db.canonical_state_stream(),
+            last_canonical_update,
+            to_worker,
+            address,
+            Duration::from_millis(10),
+        )
+        .with_mining_mode(MiningMode::Interval { interval: Duration::from_secs(60) })
+        .with_seal_requests(seal_rx);
+        tx_factory
+            .create_and_submit_eip1559_pool_tx(chain, gas_price, Address::ZERO, value, &txpool)
+            .await;
+        let _batch_builder = tokio::spawn(Box::pin(batch_builder));
+        assert!(timeout(Duration::from_millis(500), from_batch_builder.recv()).await.is_err());
+
+        // a request seals the pending transaction immediately
+        seal_tx.send(()).await.expect("batch builder running");
+        let (sealed_batch, ack) = timeout(Duration::from_secs(1), from_batch_builder.recv())
+            .await
+            .expect("batch sealed on request")
+            .expect("batch was built");
+        assert_eq!(sealed_batch.batch().transactions().len(), 1);
+        let _ = ack.send(Ok(()));
+    }
+
+    /// Test seals are retried while quorum is lost and deferred transactions are flushed once it
+    /// is restored.
+    #[tokio::test]
+    async fn test_consensus_outage() {
+        let TestTools { mut tx_factory, last_canonical_update, execution_components } =
+            get_test_tools();
+        let TestExecutionComponents { blockchain_db, txpool, chain, .. } = execution_components;
+        let address = Address::from(U160::from(33));
+        let gas_price = get_gas_price(&blockchain_db);
+        let value = U256::from(10).checked_pow(U256::from(18)).expect("1e18 doesn't overflow U256");
+        let deferred = DeferredTransactions::new(10);
+
+        // a failed seal is retried without a canonical update
+        let (to_worker, mut from_batch_builder) = tokio::sync::mpsc::channel(2);
+        let batch_builder = BatchBuilder::new(
+            blockchain_db.clone(),
+            txpool.clone(),
+            blockchain_db.canonical_state_stream(),
+            last_canonical_update.clone(),
+            to_worker,
+            address,
+            Duration::from_millis(10),
+        )
+        .with_deferred_transactions(deferred.clone());
+        tx_factory
+            .create_and_submit_eip1559_pool_tx(
+                chain.clone(),
+                gas_price,
+                Address::ZERO,
+                value,
+                &txpool,
+            )
+            .await;
+        let batch_builder_task = tokio::spawn(Box::pin(batch_builder));
+        let (timed_out, ack) = timeout(Duration::from_secs(1), from_batch_builder.recv())
+            .await
+            .expect("batch sealed")
+            .expect("batch was built");
+        let _ = ack.send(Err(BlockSealError::Timeout));
+
+        // the same batch is sent again so its transactions are never in two batches
+        let (sealed_batch, ack) = timeout(Duration::from_secs(1), from_batch_builder.recv())
+            .await
+            .expect("seal retried")
+            .expect("batch was built");
+        assert!(deferred.is_quorum_lost());
+        assert_eq!(sealed_batch.digest(), timed_out.digest());
+        assert_eq!(sealed_batch.batch().transactions().len(), 1);
+
+        // quorum is restored once a batch is acknowledged
+        let _ = ack.send(Ok(()));
+        wait_for(|| !deferred.is_quorum_lost()).await;
+        wait_for(|| txpool.pool_size().pending == 0).await;
+        batch_builder_task.abort();
+
+        // deferred transactions are flushed without waiting for the interval
+        deferred.quorum_lost();
+        tx_factory
+            .create_and_submit_eip1559_pool_tx(chain, gas_price, Address::ZERO, value, &txpool)
+            .await;
+        deferred
+            .reserve(None)
+            .expect("accepted")
+            .expect("reserved")
+            .commit(*txpool.pending_transactions()[0].hash());
+        deferred.quorum_restored();
+        let (to_worker, mut from_batch_builder) = tokio::sync::mpsc::channel(2);
+        let batch_builder = BatchBuilder::new(
+            blockchain_db.clone(),
+            txpool.clone(),
+            blockchain_db.canonical_state_stream(),
+            last_canonical_update,
+            to_worker,
+            address,
+            Duration::from_millis(10),
+        )
+        .with_mining_mode(MiningMode::Interval { interval: Duration::from_secs(60) })
+        .with_deferred_transactions(deferred.clone());
+        let _batch_builder = tokio::spawn(Box::pin(batch_builder));
+        let (sealed_batch, ack) = timeout(Duration::from_secs(1), from_batch_builder.recv())
+            .await
+            .expect("deferred transactions flushed")
+            .expect("batch was built");
+        assert_eq!(sealed_batch.batch().transactions().len(), 1);
+        let _ = ack.send(Ok(()));
+        wait_for(|| deferred.status().deferred == 0).await;
+    }
+
+    /// Wait for the batch builder to make `condition` hold.
+    async fn wait_for(condition: impl Fn() -> bool) {
+        timeout(Duration::from_secs(1), async {
+            while !condition() {
+                tokio::task::yield_now().await;
+            }
+        })
+        .await
+        .expect("condition holds");
+    }
 }
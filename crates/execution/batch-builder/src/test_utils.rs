@@ -37,7 +37,7 @@ pub fn execute_test_batch(test_batch: &mut Batch, parent: &SealedHeader) {
     };
 
     let batch_config = PendingBlockConfig::new(test_batch.beneficiary, parent_info);
-    let args = BatchBuilderArgs { pool, batch_config };
+    let args = BatchBuilderArgs::new(pool, batch_config);
     let BatchBuilderOutput { batch, .. } = build_batch(args);
     test_batch.parent_hash = batch.parent_hash;
     test_batch.beneficiary = batch.beneficiary;
@@ -80,7 +80,9 @@ async fn test_make_batch_el_to_cl() {
         network_client,
         store.clone(),
         timeout,
+        tn_worker::CHANNEL_CAPACITY,
         WorkerNetworkHandle::new_for_test(),
+        None,
     );
 
     //
@@ -631,6 +633,7 @@ async fn test_canonical_notification_updates_pool() {
         number: 0,
         extra: Default::default(),
         early_finalize: true,
+        withdrawals: Default::default(),
     };
 
     // execute output to trigger canonical update
@@ -2,7 +2,9 @@
 //! Batch validation
 
 mod validator;
+mod verdicts;
 pub use validator::BatchValidator;
+pub use verdicts::{BatchVerdicts, DEFAULT_BATCH_VERDICTS_CAPACITY};
 
 #[cfg(any(test, feature = "test-utils"))]
 pub use validator::NoopBatchValidator;
@@ -1,16 +1,16 @@
 //! Block validator
 
-use rayon::iter::{IntoParallelRefIterator as _, ParallelIterator as _};
+use crate::BatchVerdicts;
 use reth_node_types::NodeTypesWithDB;
 use reth_provider::{
     providers::{BlockchainProvider, TreeNodeTypes},
     BlockIdReader, HeaderProvider,
 };
-use reth_rpc_eth_types::utils::recover_raw_transaction;
 use tn_types::{
-    max_batch_gas, max_batch_size, BatchValidation, BatchValidationError, BlockHash, ExecHeader,
-    SealedBatch, TransactionSigned, TransactionTrait as _, PARALLEL_SENDER_RECOVERY_THRESHOLD,
+    batch_gas_limit, max_batch_size, Batch, BatchValidation, BatchValidationError, BlockHash,
+    ExecHeader, RecoveredTx, SealedBatch, SenderRecovery, TransactionSigned, TransactionTrait as _,
 };
+use tracing::trace;
 
 /// Type convenience for implementing block validation errors.
 type BatchValidationResult<T> = Result<T, BatchValidationError>;
@@ -23,6 +23,12 @@ where
 {
     /// Database provider to encompass tree and provider factory.
     blockchain_db: BlockchainProvider<N>,
+    /// Recovers transaction senders, shared with the engine.
+    sender_recovery: SenderRecovery,
+    /// The verdicts for batches that were already validated.
+    verdicts: BatchVerdicts,
+    /// Accept batches without transactions.
+    accept_empty_batches: bool,
 }
 
 impl<N> BatchValidation for BatchValidator<N>
@@ -32,6 +38,9 @@ where
     /// Validate a peer's batch.
     ///
     /// Workers do not execute full batches. This method validates the required information.
+    /// Verdicts are cached by digest so a batch received more than once is only validated once.
+    /// Verdicts checked against a fallback because the batch's parent is not known yet are not
+    /// cached, the batch is validated against its parent once it arrives.
     fn validate_batch(&self, sealed_batch: SealedBatch) -> BatchValidationResult<()> {
         // ensure digest matches batch
         let (batch, digest) = sealed_batch.split();
@@ -40,35 +49,99 @@ where
             return Err(BatchValidationError::InvalidDigest);
         }
 
+        if let Some(verdict) = self.verdicts.get(&digest) {
+            trace!(target: "batch_validator", ?digest, "cached batch verdict");
+            return verdict;
+        }
+        let parent = self.blockchain_db.header(&batch.parent_hash).unwrap_or_default();
+        let parent_known = parent.is_some();
+        let parent = parent.unwrap_or_else(|| self.fallback_parent());
+        let verdict = self.validate_batch_contents(&batch, digest, &parent);
+        if parent_known {
+            self.verdicts.insert(digest, &verdict);
+        }
+        verdict
+    }
+}
+
+impl<N> BatchValidator<N>
+where
+    N: TreeNodeTypes + NodeTypesWithDB,
+{
+    /// Create a new instance of [Self]
+    pub fn new(blockchain_db: BlockchainProvider<N>) -> Self {
+        Self {
+            blockchain_db,
+            sender_recovery: SenderRecovery::default(),
+            verdicts: BatchVerdicts::default(),
+            accept_empty_batches: false,
+        }
+    }
+
+    /// Recover transaction senders with `sender_recovery`.
+    ///
+    /// Share it with the engine so senders recovered during validation are not recovered again
+    /// when the batch is executed.
+    pub fn with_sender_recovery(mut self, sender_recovery: SenderRecovery) -> Self {
+        self.sender_recovery = sender_recovery;
+        self
+    }
+
+    /// Remember verdicts in `verdicts`.
+    ///
+    /// Share it between validators so a batch is validated once, however it reaches the worker.
+    pub fn with_verdicts(mut self, verdicts: BatchVerdicts) -> Self {
+        self.verdicts = verdicts;
+        self
+    }
+
+    /// Accept batches without transactions.
+    ///
+    /// Set this on networks where workers seal empty batches to keep a steady block cadence.
+    pub fn with_empty_batches(mut self, accept_empty_batches: bool) -> Self {
+        self.accept_empty_batches = accept_empty_batches;
+        self
+    }
+
+    /// The header to validate a batch against when its parent is not known.
+    ///
+    /// Note this is really a "best effort" check.  If we have not
+    /// executed parent_hash yet then it will use the last executed batch if
+    /// available.  Making it manditory would require waiting to see
+    /// if we execute it soon to avoid false failures.
+    /// The primary header should get checked so this should be ok.
+    fn fallback_parent(&self) -> ExecHeader {
+        let finalized_block_num_hash =
+            self.blockchain_db.finalized_block_num_hash().unwrap_or_default();
+        if let Some(finalized_block_num_hash) = finalized_block_num_hash {
+            self.blockchain_db
+                .header(&finalized_block_num_hash.hash)
+                .unwrap_or_default()
+                .unwrap_or_default()
+        } else {
+            ExecHeader::default()
+        }
+    }
+
+    /// Validate the contents of a batch whose digest was verified against its parent's header.
+    fn validate_batch_contents(
+        &self,
+        batch: &Batch,
+        digest: BlockHash,
+        parent: &ExecHeader,
+    ) -> BatchValidationResult<()> {
         // TODO: validate individual transactions against parent
 
         // obtain info for validation
         let transactions = batch.transactions();
 
-        // first step towards validating parent's header
-        // Note this is really a "best effort" check.  If we have not
-        // executed parent_hash yet then it will use the last executed batch if
-        // available.  Making it manditory would require waiting to see
-        // if we execute it soon to avoid false failures.
-        // The primary header should get checked so this should be ok.
-        let parent =
-            self.blockchain_db.header(&batch.parent_hash).unwrap_or_default().unwrap_or_else(
-                || {
-                    let finalized_block_num_hash =
-                        self.blockchain_db.finalized_block_num_hash().unwrap_or_default();
-                    if let Some(finalized_block_num_hash) = finalized_block_num_hash {
-                        self.blockchain_db
-                            .header(&finalized_block_num_hash.hash)
-                            .unwrap_or_default()
-                            .unwrap_or_default()
-                    } else {
-                        ExecHeader::default()
-                    }
-                },
-            );
-
         // validate timestamp vs parent
-        self.validate_against_parent_timestamp(batch.timestamp, &parent)?;
+        self.validate_against_parent_timestamp(batch.timestamp, parent)?;
+
+        // empty batches have nothing else to validate
+        if transactions.is_empty() && self.accept_empty_batches {
+            return Ok(());
+        }
 
         // validate batch size (bytes)
         self.validate_batch_size_bytes(transactions, batch.timestamp)?;
@@ -77,22 +150,12 @@ where
         let decoded_txs = self.decode_transactions(transactions, digest)?;
 
         // validate gas limit
-        self.validate_batch_gas(&decoded_txs, batch.timestamp)?;
+        self.validate_batch_gas(&decoded_txs, parent)?;
 
         // no-op
         self.validate_basefee()?;
         Ok(())
     }
-}
-
-impl<N> BatchValidator<N>
-where
-    N: TreeNodeTypes + NodeTypesWithDB,
-{
-    /// Create a new instance of [Self]
-    pub fn new(blockchain_db: BlockchainProvider<N>) -> Self {
-        Self { blockchain_db }
-    }
 
     /// Validates the timestamp against the parent to make sure it is in the past.
     #[inline]
@@ -141,27 +204,25 @@ where
         transactions: &Vec<Vec<u8>>,
         digest: BlockHash,
     ) -> BatchValidationResult<Vec<TransactionSigned>> {
-        if transactions.len() < *PARALLEL_SENDER_RECOVERY_THRESHOLD {
-            transactions
-                .iter()
-                .map(|tx| Self::recover_and_validate(tx, digest))
-                .collect::<BatchValidationResult<Vec<_>>>()
-        } else {
-            transactions
-                .par_iter()
-                .map(|tx| Self::recover_and_validate(tx, digest))
-                .collect::<BatchValidationResult<Vec<_>>>()
-        }
+        self.sender_recovery
+            .recover_all(transactions)
+            .into_iter()
+            .map(|recovered| {
+                recovered
+                    .map(RecoveredTx::into_tx)
+                    .map_err(|e| BatchValidationError::RecoverTransaction(digest, e.to_string()))
+            })
+            .collect()
     }
 
-    /// Possible gas used needs to be less than block's gas limit.
+    /// Possible gas used needs to be less than the parent block's gas limit.
     ///
     /// Actual amount of gas used cannot be determined until execution.
     #[inline]
     fn validate_batch_gas(
         &self,
         transactions: &[TransactionSigned],
-        timestamp: u64,
+        parent: &ExecHeader,
     ) -> BatchValidationResult<()> {
         // calculate total using tx gas limit
         let total_possible_gas = transactions
@@ -171,7 +232,7 @@ where
             .ok_or(BatchValidationError::EmptyBatch)?;
 
         // ensure total tx gas limit fits into block's gas limit
-        let max_tx_gas = max_batch_gas(timestamp);
+        let max_tx_gas = batch_gas_limit(parent);
         if total_possible_gas > max_tx_gas {
             return Err(BatchValidationError::HeaderMaxGasExceedsGasLimit {
                 total_possible_gas,
@@ -186,17 +247,6 @@ where
     fn validate_basefee(&self) -> BatchValidationResult<()> {
         Ok(())
     }
-
-    /// Helper function for decoding and recovering transactions.
-    #[inline]
-    fn recover_and_validate(
-        tx: &[u8],
-        digest: BlockHash,
-    ) -> BatchValidationResult<TransactionSigned> {
-        recover_raw_transaction::<TransactionSigned>(tx)
-            .map(|recovered| recovered.into_tx())
-            .map_err(|e| BatchValidationError::RecoverTransaction(digest, e.to_string()))
-    }
 }
 
 /// Noop validation struct that validates any block.
@@ -221,18 +271,20 @@ mod tests {
     use reth_chainspec::ChainSpec;
     use reth_consensus::FullConsensus;
     use reth_db::{
+        tables,
         test_utils::{create_test_rw_db, tempdir_path, TempDatabase},
+        transaction::DbTxMut as _,
         DatabaseEnv,
     };
     use reth_db_common::init::init_genesis;
     use reth_node_types::NodeTypesWithDBAdapter;
-    use reth_provider::{providers::StaticFileProvider, ProviderFactory};
+    use reth_provider::{providers::StaticFileProvider, DBProvider as _, ProviderFactory};
     use std::{str::FromStr, sync::Arc};
     use tn_node_traits::{TNExecution, TelcoinNode};
     use tn_test_utils::{test_genesis, TransactionFactory};
     use tn_types::{
         adiri_genesis, hex_literal::hex, max_batch_gas, Address, Batch, Bytes, Encodable2718 as _,
-        GenesisAccount, B256, MIN_PROTOCOL_BASE_FEE, U256,
+        ExecHeader, GenesisAccount, B256, MIN_PROTOCOL_BASE_FEE, U256,
     };
     use tracing::debug;
 
@@ -304,6 +356,8 @@ mod tests {
         valid_batch: SealedBatch,
         /// Validator
         validator: BatchValidator<TestProvider>,
+        /// Writes headers the validator reads.
+        provider_factory: ProviderFactory<TestProvider>,
     }
 
     /// Create an instance of block validator for tests.
@@ -345,12 +399,12 @@ mod tests {
         let valid_batch = next_valid_sealed_batch();
 
         // block validator
-        TestTools { valid_batch, validator }
+        TestTools { valid_batch, validator, provider_factory }
     }
 
     #[tokio::test]
     async fn test_valid_batch() {
-        let TestTools { valid_batch, validator } = test_tools().await;
+        let TestTools { valid_batch, validator, .. } = test_tools().await;
         let result = validator.validate_batch(valid_batch.clone());
         assert!(result.is_ok());
 
@@ -362,13 +416,30 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_validation_caches_senders() {
+        let TestTools { valid_batch, validator, .. } = test_tools().await;
+        let sender_recovery = SenderRecovery::new(2, 16).expect("thread pool");
+        let validator = validator.with_sender_recovery(sender_recovery.clone());
+        assert!(validator.validate_batch(valid_batch.clone()).is_ok());
+
+        // the engine recovers the same transactions from the cache
+        let transactions = &valid_batch.batch().transactions;
+        assert_eq!(sender_recovery.cached_senders(), transactions.len());
+        let sender = TransactionFactory::new().address();
+        for recovered in sender_recovery.recover_all(transactions) {
+            assert_eq!(recovered.expect("valid transaction").signer(), sender);
+        }
+        assert_eq!(sender_recovery.cached_senders(), transactions.len());
+    }
+
     //#[tokio::test]
     // This is not checked currently, leaving test for bit to make sure we want this.
     // This check will lead to occasional false errors and should not be critical since
     // we should be validating parentage when building actual blocks (including any
     // needed waits for execution).
     async fn _test_invalid_batch_wrong_parent_hash() {
-        let TestTools { valid_batch, validator } = test_tools().await;
+        let TestTools { valid_batch, validator, .. } = test_tools().await;
         let (batch, _) = valid_batch.split();
         let Batch { transactions, beneficiary, timestamp, base_fee_per_gas, received_at, .. } =
             batch;
@@ -389,7 +460,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_invalid_batch_wrong_timestamp() {
-        let TestTools { valid_batch, validator } = test_tools().await;
+        let TestTools { valid_batch, validator, .. } = test_tools().await;
         let (mut batch, _) = valid_batch.split();
 
         // test batch timestamp same as parent
@@ -413,7 +484,7 @@ mod tests {
     #[tokio::test]
     async fn test_invalid_batch_excess_gas_used() {
         // Set excessive gas limit.
-        let TestTools { valid_batch, validator } = test_tools().await;
+        let TestTools { valid_batch, validator, .. } = test_tools().await;
         let (batch, _) = valid_batch.split();
 
         // sign excessive transaction
@@ -447,18 +518,23 @@ mod tests {
             .decode_transactions(invalid_batch.transactions(), invalid_batch.digest())
             .expect("txs decode correctly");
 
+        let parent = ExecHeader { gas_limit: max_batch_gas(0), ..Default::default() };
         assert_matches!(
-            validator.validate_batch_gas(&decoded_txs, invalid_batch.timestamp),
+            validator.validate_batch_gas(&decoded_txs, &parent),
             Err(BatchValidationError::HeaderMaxGasExceedsGasLimit {
                 total_possible_gas: _,
                 gas_limit: _
             })
         );
+
+        // the parent's gas limit applies, even when it is raised
+        let parent = ExecHeader { gas_limit: max_batch_gas(0) * 2, ..Default::default() };
+        assert!(validator.validate_batch_gas(&decoded_txs, &parent).is_ok());
     }
 
     #[tokio::test]
     async fn test_invalid_batch_wrong_size_in_bytes() {
-        let TestTools { valid_batch, validator } = test_tools().await;
+        let TestTools { valid_batch, validator, .. } = test_tools().await;
         // create enough transactions to exceed 1MB
         // because validator uses provided with same genesis
         // and tx_factory needs funds
@@ -548,7 +624,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_invalid_batch_empty_transactions() {
-        let TestTools { valid_batch, validator } = test_tools().await;
+        let TestTools { valid_batch, validator, .. } = test_tools().await;
         let (mut batch, _) = valid_batch.split();
 
         // test batch with no transactions
@@ -558,11 +634,15 @@ mod tests {
             validator.validate_batch(batch.clone().seal_slow()),
             Err(BatchValidationError::EmptyBatch)
         );
+
+        // empty batches are valid once accepted
+        let validator = validator.with_verdicts(BatchVerdicts::new(16)).with_empty_batches(true);
+        assert!(validator.validate_batch(batch.clone().seal_slow()).is_ok());
     }
 
     #[tokio::test]
     async fn test_invalid_batch_decode_transactions() {
-        let TestTools { valid_batch, validator } = test_tools().await;
+        let TestTools { valid_batch, validator, .. } = test_tools().await;
         let (mut batch, _) = valid_batch.split();
 
         // test batch with bad decode
@@ -573,4 +653,81 @@ mod tests {
             Err(BatchValidationError::RecoverTransaction(_, _))
         );
     }
+
+    #[tokio::test]
+    async fn test_batch_verdicts_cached_by_digest() {
+        let TestTools { valid_batch, validator, .. } = test_tools().await;
+        let verdicts = BatchVerdicts::new(16);
+        let validator = validator.with_verdicts(verdicts.clone());
+
+        // valid and invalid verdicts are remembered
+        assert!(validator.validate_batch(valid_batch.clone()).is_ok());
+        assert_eq!(verdicts.len(), 1);
+        let (mut batch, _) = valid_batch.split();
+        batch.transactions = vec![b"this is a bad batch".to_vec()];
+        let invalid_batch = batch.clone().seal_slow();
+        assert_matches!(
+            validator.validate_batch(invalid_batch.clone()),
+            Err(BatchValidationError::RecoverTransaction(_, _))
+        );
+        assert_matches!(verdicts.get(&invalid_batch.digest()), Some(Err(_)));
+
+        // the cached verdict is returned without validating again
+        assert!(validator.validate_batch(valid_batch.clone()).is_ok());
+        assert_matches!(
+            validator.validate_batch(invalid_batch),
+            Err(BatchValidationError::RecoverTransaction(_, _))
+        );
+        assert_eq!(verdicts.len(), 2);
+
+        // the digest is verified before the cache is used
+        let (valid, _) = valid_batch.split();
+        let forged = SealedBatch::new(batch, valid.seal_slow().digest());
+        assert_matches!(validator.validate_batch(forged), Err(BatchValidationError::InvalidDigest));
+
+        // verdicts that depend on execution progress are not remembered
+        let (mut batch, _) = valid_batch.split();
+        batch.timestamp = adiri_genesis().timestamp;
+        assert_matches!(
+            validator.validate_batch(batch.seal_slow()),
+            Err(BatchValidationError::TimestampIsInPast { .. })
+        );
+        assert_eq!(verdicts.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_batch_verdict_cached_once_parent_arrives() {
+        let TestTools { valid_batch, validator, provider_factory } = test_tools().await;
+        let verdicts = BatchVerdicts::new(16);
+        let validator = validator.with_verdicts(verdicts.clone());
+
+        // the parent's gas limit is too low for the batch's transactions
+        let (mut batch, _) = valid_batch.split();
+        let parent = ExecHeader {
+            number: 1,
+            timestamp: batch.timestamp - 1,
+            gas_limit: 1,
+            ..Default::default()
+        };
+        let parent_hash = parent.hash_slow();
+        batch.parent_hash = parent_hash;
+        let batch = batch.seal_slow();
+
+        // the fallback header accepts the batch, but the verdict is not remembered
+        assert!(validator.validate_batch(batch.clone()).is_ok());
+        assert!(verdicts.is_empty());
+
+        // the parent arrives
+        let provider = provider_factory.provider_rw().expect("provider rw");
+        provider.tx_ref().put::<tables::HeaderNumbers>(parent_hash, 1).expect("number written");
+        provider.tx_ref().put::<tables::Headers>(1, parent).expect("header written");
+        provider.commit().expect("headers committed");
+
+        // the batch is validated against its parent and the verdict is remembered
+        assert_matches!(
+            validator.validate_batch(batch.clone()),
+            Err(BatchValidationError::HeaderMaxGasExceedsGasLimit { .. })
+        );
+        assert_matches!(verdicts.get(&batch.digest()), Some(Err(_)));
+    }
 }
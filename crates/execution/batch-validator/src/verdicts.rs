@@ -0,0 +1,73 @@
+//! Cache of batch validation verdicts.
+//!
+//! A peer's batch can reach the worker more than once: gossiped by its author, fetched while
+//! syncing a header's payload, and submitted again after a certificate is synced. The batch digest
+//! commits to its contents, so the verdict for a digest is remembered and the batch is not
+//! validated again.
+//!
+//! Errors that depend on the node's execution progress are not remembered, since the batch may be
+//! valid once the node executes its parent. Neither are verdicts checked against a fallback header
+//! before the batch's parent is known.
+
+use lru::LruCache;
+use parking_lot::Mutex;
+use std::{num::NonZeroUsize, sync::Arc};
+use tn_types::{BatchValidationError, BlockHash};
+
+/// The default number of batch verdicts to remember.
+pub const DEFAULT_BATCH_VERDICTS_CAPACITY: usize = 10_000;
+
+/// Shared, bounded LRU cache of validation verdicts keyed by batch digest.
+///
+/// Clones share the cache.
+#[derive(Clone, Debug)]
+pub struct BatchVerdicts {
+    /// The verdict for each batch digest.
+    inner: Arc<Mutex<LruCache<BlockHash, Result<(), BatchValidationError>>>>,
+}
+
+impl BatchVerdicts {
+    /// Create a new cache holding at most `capacity` verdicts.
+    ///
+    /// A `capacity` of zero is treated as one.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self { inner: Arc::new(Mutex::new(LruCache::new(capacity))) }
+    }
+
+    /// Return the remembered verdict for a batch digest.
+    pub fn get(&self, digest: &BlockHash) -> Option<Result<(), BatchValidationError>> {
+        self.inner.lock().get(digest).cloned()
+    }
+
+    /// Remember the verdict for a batch digest.
+    ///
+    /// Only insert verdicts checked against the batch's parent header. Errors that depend on the
+    /// node's execution progress are ignored.
+    pub fn insert(&self, digest: BlockHash, verdict: &Result<(), BatchValidationError>) {
+        if let Err(
+            BatchValidationError::TimestampIsInPast { .. }
+            | BatchValidationError::CanonicalChain { .. },
+        ) = verdict
+        {
+            return;
+        }
+        self.inner.lock().put(digest, verdict.clone());
+    }
+
+    /// The number of verdicts in the cache.
+    pub fn len(&self) -> usize {
+        self.inner.lock().len()
+    }
+
+    /// True if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.inner.lock().is_empty()
+    }
+}
+
+impl Default for BatchVerdicts {
+    fn default() -> Self {
+        Self::new(DEFAULT_BATCH_VERDICTS_CAPACITY)
+    }
+}
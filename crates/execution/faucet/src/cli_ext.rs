@@ -3,7 +3,7 @@
 //! CLI supports adding extensions to the main components for the node.
 //! The only extension supported right now is the `faucet` for testnet.
 
-use crate::{FaucetConfig, FaucetRpcExt, FaucetWallet};
+use crate::{ExecutionSigner, FaucetConfig, FaucetRpcExt, GoogleKmsSigner, RemoteSigner};
 use clap::Args;
 use ecdsa::elliptic_curve::{pkcs8::DecodePublicKey as _, sec1::ToEncodedPoint};
 use eyre::ContextCompat;
@@ -12,7 +12,7 @@ use reth_cli_util::parse_duration_from_secs;
 use reth_provider::{BlockReaderIdExt, StateProviderFactory};
 use reth_transaction_pool::{EthPooledTransaction, TransactionPool};
 use secp256k1::PublicKey;
-use std::{str::FromStr, time::Duration};
+use std::{str::FromStr, sync::Arc, time::Duration};
 use tn_types::{public_key_to_address, Address, U256};
 use tracing::{info, warn};
 
@@ -54,10 +54,31 @@ pub struct FaucetArgs {
     /// - crypto_keys
     /// - crypto_keys_versions
     ///
-    /// If set to false, the faucet endpoint isn't merged with the configured RPC modules.
+    /// If set to false and no remote signer is set, the faucet endpoint isn't merged with the
+    /// configured RPC modules.
     #[clap(long)]
     pub(crate) google_kms: bool,
 
+    /// The URL of an external signing API for faucet transactions.
+    ///
+    /// The API must hold the key for `public_key` and support `eth_signTransaction`, ie -
+    /// web3signer or clef backed by a Ledger or HSM.
+    #[clap(
+        long,
+        env = "FAUCET_REMOTE_SIGNER_URL",
+        conflicts_with = "google_kms",
+        value_name = "URL"
+    )]
+    pub(crate) remote_signer_url: Option<String>,
+
+    /// The amount of time to wait for the remote signer to sign a transaction.
+    ///
+    /// Hardware wallets need time for the transaction to be approved on the device.
+    ///
+    /// Specified in seconds.
+    #[clap(long, default_value = "60", value_parser = parse_duration_from_secs, value_name = "TIMEOUT")]
+    pub(crate) remote_signer_timeout: Duration,
+
     /// Google KMS Project ID.
     ///
     /// Used by `name` to make API call.
@@ -104,10 +125,9 @@ impl FaucetArgs {
         Provider: BlockReaderIdExt + StateProviderFactory + Unpin + Clone + 'static,
         Pool: TransactionPool<Transaction = EthPooledTransaction> + Unpin + Clone + 'static,
     {
-        // only support google kms for now
-        if self.google_kms {
-            // calculate address from uncompressed public key
-            let address = public_key_to_address(self.public_key);
+        // calculate address from uncompressed public key
+        let address = public_key_to_address(self.public_key);
+        let signer: Arc<dyn ExecutionSigner> = if self.google_kms {
             // compressed public key bytes
             let public_key_bytes = self.public_key.serialize();
 
@@ -134,24 +154,25 @@ impl FaucetArgs {
                 google_project_id, locations, key_rings, crypto_keys, crypto_key_versions
             );
 
-            let wallet = FaucetWallet { address, public_key_bytes, name };
-            let config = FaucetConfig {
-                wait_period: self.wait_period,
-                chain_id: self.chain_id,
-                wallet,
-                contract_address: self.contract_address,
-            };
-
-            let ext = FaucetRpcExt::new(provider, pool, config);
-
             info!(target: "faucet", "Google KMS active - merging faucet extension.");
-            return Ok(ext);
-        }
+            Arc::new(GoogleKmsSigner { address, public_key_bytes, name })
+        } else if let Some(url) = &self.remote_signer_url {
+            info!(target: "faucet", ?url, "Remote signer active - merging faucet extension.");
+            Arc::new(RemoteSigner::new(url, address, self.remote_signer_timeout)?)
+        } else {
+            // never sign with hot keys from the node config
+            warn!(target: "faucet", "No faucet signer - skipping faucet extension.");
+            return Err(eyre::Report::msg("No faucet signer - skipping faucet extension."));
+        };
 
-        // TODO: support local/hardcoded hot wallet signatures
-        warn!(target: "faucet", "Google KMS inactive - skipping faucet extension.");
-        Err(eyre::Report::msg("Google KMS inactive - skipping faucet extension."))
-        //todo!("Only Google KMS supported right now.")
+        let config = FaucetConfig {
+            wait_period: self.wait_period,
+            chain_id: self.chain_id,
+            signer,
+            contract_address: self.contract_address,
+        };
+
+        Ok(FaucetRpcExt::new(provider, pool, config))
     }
 }
 
@@ -231,4 +252,30 @@ mod tests {
         .unwrap();
         assert_eq!(pem_parsed.args.public_key, expected);
     }
+
+    #[test]
+    fn test_remote_signer_args() {
+        let public_key = "029bef8d556d80e43ae7e0becb3a7e6838b95defe45896ed6075bb9035d06c9964";
+        let parsed = CommandParser::<FaucetArgs>::try_parse_from([
+            "tn",
+            "--public-key",
+            public_key,
+            "--remote-signer-url",
+            "http://127.0.0.1:9000",
+        ])
+        .expect("parsed remote signer args");
+        assert_eq!(parsed.args.remote_signer_url.as_deref(), Some("http://127.0.0.1:9000"));
+        assert_eq!(parsed.args.remote_signer_timeout, std::time::Duration::from_secs(60));
+
+        // only one signer can be used
+        let both_signers = CommandParser::<FaucetArgs>::try_parse_from([
+            "tn",
+            "--public-key",
+            public_key,
+            "--google-kms",
+            "--remote-signer-url",
+            "http://127.0.0.1:9000",
+        ]);
+        assert!(both_signers.is_err());
+    }
 }
@@ -20,7 +20,7 @@ use reth_provider::{BlockReaderIdExt, StateProviderFactory};
 use reth_tasks::{TaskSpawner, TokioTaskExecutor};
 use reth_transaction_pool::{EthPooledTransaction, TransactionPool};
 use secp256k1::constants::PUBLIC_KEY_SIZE;
-use std::time::Duration;
+use std::{sync::Arc, time::Duration};
 use tn_types::{Address, TxHash};
 use tokio::sync::{
     mpsc::{unbounded_channel, UnboundedSender},
@@ -30,9 +30,11 @@ use tokio_stream::wrappers::UnboundedReceiverStream;
 mod cli_ext;
 mod rpc_ext;
 mod service;
+mod signer;
 pub use cli_ext::{parse_u256_from_decimal_value, FaucetArgs};
 pub use rpc_ext::{FaucetRpcExt, FaucetRpcExtApiServer};
 pub(crate) use service::FaucetService;
+pub use signer::{ExecutionSigner, GoogleKmsSigner, RemoteSigner};
 
 /// Client to send API requests to Google KMS.
 pub type GoogleKMSClient = GoogleApi<KeyManagementServiceClient<GoogleAuthMiddleware>>;
@@ -50,8 +52,10 @@ pub struct FaucetConfig {
     pub wait_period: Duration,
     /// The chain id
     pub chain_id: u64,
-    /// Sensitive information regarding the wallet hot-signing transactions
-    pub wallet: FaucetWallet,
+    /// The signer for the faucet's transactions.
+    ///
+    /// The faucet wallet's key is held by the signer, never by the node.
+    pub signer: Arc<dyn ExecutionSigner>,
     /// Onchain faucet contract address for testing
     /// The faucet manages the stablecoin and native token drip amounts
     /// as well as whether or not a given stablecoin or the native token is enabled
@@ -59,43 +63,6 @@ pub struct FaucetConfig {
     pub contract_address: Address,
 }
 
-/// The account details used by the faucet to create and sign transactions.
-///
-/// The FaucetWallet sends a request to Google KMS service for a signature.
-pub struct FaucetWallet {
-    /// The faucet's address.
-    ///
-    /// Used for verifying nonces and estimating gas.
-    pub address: Address,
-
-    /// The faucet's compressed (serialized) public key as bytes.
-    ///
-    /// The key is serialized as a byte-encoded pair of values. In compressed form the y-coordinate
-    /// is represented by only a single bit, as x determines it up to one bit.
-    ///
-    /// Used for creating transactions signed by Google KMS.
-    pub public_key_bytes: Secp256k1PubKeyBytes,
-
-    /// The "name" used by Google KMS to identify the key.
-    ///
-    /// The key needs to be "global" and in the format:
-    /// "projects/{}/locations/{}/keyRings/{}/cryptoKeys/{}/cryptoKeyVersions/{}".
-    pub name: String,
-}
-
-impl FaucetWallet {
-    /// Clone the Google KMS "name".
-    ///
-    /// The "name" refers to the cloud KMS.
-    pub fn name(&self) -> String {
-        self.name.clone()
-    }
-    /// Google KMS serialized public key (bytes).
-    pub fn kms_public_key(&self) -> Secp256k1PubKeyBytes {
-        self.public_key_bytes
-    }
-}
-
 /// Provides async access to the cached addresses.
 ///
 /// This is the frontend for the async caching service which manages cached data
@@ -114,7 +81,7 @@ impl Faucet {
         config: FaucetConfig,
     ) -> (Self, FaucetService<Provider, Pool, Tasks>) {
         let (to_service, rx) = unbounded_channel();
-        let FaucetConfig { wait_period, chain_id, wallet, contract_address } = config;
+        let FaucetConfig { wait_period, chain_id, signer, contract_address } = config;
 
         // Construct an `LruCache` of `<String, SystemTime>`s, limited by 24hr expiry time
         let success_cache = LruCache::with_expiry_duration(wait_period);
@@ -135,7 +102,7 @@ impl Faucet {
             chain_id,
             wait_period,
             executor,
-            wallet,
+            signer,
             add_to_success_cache_tx,
             update_success_cache_rx,
             next_nonce: 0, // start at 0 - service checks db
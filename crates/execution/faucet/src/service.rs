@@ -6,15 +6,8 @@
 //! address if the address hasn't received from the faucet
 //! wallet within the time period.
 
-use crate::{Drip, FaucetWallet, GoogleKMSClient, Secp256k1PubKeyBytes};
+use crate::{signer::verify_signature, Drip, ExecutionSigner};
 use futures::StreamExt;
-use gcloud_sdk::{
-    google::cloud::kms::v1::{
-        digest::Digest, key_management_service_client::KeyManagementServiceClient,
-        AsymmetricSignRequest, Digest as KMSDigest,
-    },
-    GoogleApi,
-};
 use humantime::format_duration;
 use lru_time_cache::LruCache;
 use reth::rpc::server_types::eth::{EthApiError, EthResult, RpcInvalidTransactionError};
@@ -24,19 +17,16 @@ use reth_tasks::TaskSpawner;
 use reth_transaction_pool::{
     EthPooledTransaction, PoolTransaction, TransactionEvent, TransactionOrigin, TransactionPool,
 };
-use secp256k1::{
-    ecdsa::{RecoverableSignature, RecoveryId, Signature},
-    Message, SECP256K1,
-};
 use std::{
     future::Future,
     pin::Pin,
+    sync::Arc,
     task::{ready, Context, Poll},
     time::{Duration, SystemTime},
 };
 use tn_types::{
-    Address, EthSignature, SolType, Transaction, TransactionSigned, TransactionTrait as _,
-    TxEip1559, TxHash, TxKind, B256, U256,
+    Address, SolType, Transaction, TransactionSigned, TransactionTrait as _, TxEip1559, TxHash,
+    TxKind, U256,
 };
 use tokio::sync::{
     mpsc::{UnboundedReceiver, UnboundedSender},
@@ -105,8 +95,8 @@ pub(crate) struct FaucetService<Provider, Pool, Tasks> {
     pub(crate) wait_period: Duration,
     /// The type that can spawn tasks onto the runtime.
     pub(crate) executor: Tasks,
-    /// The transaction signer's information.
-    pub(crate) wallet: FaucetWallet,
+    /// The signer for the faucet's transactions.
+    pub(crate) signer: Arc<dyn ExecutionSigner>,
     /// Sending half of the cache channel.
     ///
     /// The user's address and contract address are sent through this channel
@@ -153,10 +143,7 @@ where
     ) -> EthResult<()> {
         // create transaction based on request type
         let transaction = self.create_transaction_to_sign(user, contract)?;
-        // request signature from kms
-        let kms_name = self.wallet.name();
-        let public_key = self.wallet.kms_public_key();
-        let chain_id = self.chain_id;
+        let signer = self.signer.clone();
         let pool = self.pool.clone();
         let add_to_success_cache = self.add_to_success_cache_tx.clone();
 
@@ -168,9 +155,11 @@ where
 
         // request signature and submit to txpool
         self.executor.spawn(Box::pin(async move {
-            let digest = transaction.signature_hash();
-            let response =
-                Self::request_kms_signature(kms_name, digest, chain_id, public_key).await;
+            // the only check of the signature, signers return it unverified
+            let response = signer.sign_transaction(&transaction).await.and_then(|signature| {
+                verify_signature(&transaction, &signature, signer.address())?;
+                Ok(signature)
+            });
 
             // submit tx to pool
             match response {
@@ -182,7 +171,7 @@ where
                     // reply to rpc
                     let _ = reply.send(res);
                 }
-                Err(e) => error!(target: "faucet", ?e, "Error requesting KMS signature"),
+                Err(e) => error!(target: "faucet", ?e, "Error requesting signature"),
             }
         }));
 
@@ -233,7 +222,7 @@ where
     ///
     /// The account nonce read from the database returns the account's CURRENT nonce.
     fn next_nonce(&self) -> EthResult<u64> {
-        let address = self.wallet.address;
+        let address = self.signer.address();
         debug!(?address, "Faucet address");
         // lookup transactions in pool
         let address_txs = self.pool.get_transactions_by_sender(address);
@@ -279,132 +268,6 @@ where
         debug!(target: "faucet", ?pool_info, "checking gas price");
         pool_info.pending_basefee.into()
     }
-
-    /// Send a request to Google KMS and convert it to EVM compatible.
-    async fn request_kms_signature(
-        name: String,
-        digest: B256,
-        chain_id: u64,
-        public_key_bytes: Secp256k1PubKeyBytes,
-    ) -> eyre::Result<EthSignature> {
-        // create client
-        //
-        // note: this is reusable, but challenging to figure out how
-        // to call the .await from inside sync function (spawn, create, etc.)
-        let client: GoogleKMSClient = GoogleApi::from_function(
-            KeyManagementServiceClient::new,
-            "https://cloudkms.googleapis.com",
-            None,
-        )
-        .await?;
-
-        // create message from slice before consuming digest
-        // this is needed to calculate `v` below
-        let message = Message::from_digest_slice(&digest.0)?;
-
-        // assemble digest for signature
-        let digest = Some(Digest::Sha256(digest.0.to_vec()));
-        let digest = Some(KMSDigest { digest });
-        let signed_data = client
-            .get()
-            .asymmetric_sign(AsymmetricSignRequest {
-                name: name.clone(),
-                digest,
-                ..Default::default()
-            })
-            .await?
-            .into_inner()
-            .signature;
-
-        // ensure signature is compatible with ethereum (see EIP-155)
-        let mut signature = Signature::from_der(&signed_data)?;
-        signature.normalize_s();
-        // retrieve r, s, and v values for EthSignature
-        let compact = signature.serialize_compact();
-
-        // calculate `v` for eth signature's `y_parity`
-        let y_parity = Self::calculate_v(&message, chain_id, &compact, &public_key_bytes)?;
-
-        // r and s are 32 bytes each
-        let (r, s) = compact.split_at(32);
-
-        let r = U256::from_be_slice(r);
-        let s = U256::from_be_slice(s);
-        let eth_signature = EthSignature::new(r, s, y_parity);
-
-        Ok(eth_signature)
-    }
-
-    /// Try both recovery ids (0 or 1) to find the correct v value
-    ///
-    /// NOTE: this compares the compressed public keys for convenience
-    ///       uncompressed approach is commented out
-    ///
-    /// How and why this works:
-    /// Calculating the v value from r, s, the original hash, and the public key, especially for
-    /// use in Ethereum transactions, involves trying to recover the public key from the
-    /// signature and comparing it to the known public key to determine the correct v value.
-    /// Ethereum uses the v value to encode the recovery id and some blockchain-specific
-    /// information (like chain id in EIP-155). In Ethereum, v can typically be 27 or 28
-    /// (or higher if adjusted for chain id as per EIP-155), corresponding to the two possible
-    /// recovery ids (0 or 1) that can result from the ECDSA signature recovery process.
-    /// In Ethereum, a signature consists of three components: r, s, and v. The r and s values
-    /// are part of the ECDSA signature, and the v value is a recovery id that indicates which of
-    /// the two possible public keys is the correct one (since a signature does not uniquely
-    /// identify a public key).
-    ///
-    /// The signature from Google Cloud KMS using the secp256k1 curve is in DER format.
-    /// This 64-byte format consists of the r and s components of the ECDSA signature, each being
-    /// 32 bytes long.
-    ///
-    /// The `v` value is Ethereum-specific and must be calculated to use the KMS signature with
-    /// EVM. The `v` value can be either 27 or 28 (or 35 or 36 when adding the chain ID to
-    /// prevent replay attacks on different networks as per EIP-155).
-    ///
-    /// The `v` value in the signature not only indicates the chain ID (for replay
-    /// protection) but also encodes information about the "y parity" of the point on the
-    /// elliptic curve that corresponds to the public key recovered from the signature. The y
-    /// parity (odd or even) is a critical component used to recover the correct public key from
-    /// a given signature (r, s) and message hash.
-    ///
-    /// Relationship Between y_odd_parity and v:
-    /// The v value for Ethereum signatures traditionally starts at 27 (or 28), where the
-    /// difference (27 or 28) essentially encodes the y parity.
-    ///
-    /// Specifically:
-    /// - If v = 35 + 2*chain_id), the y parity is even.
-    /// - If v = 36 + 2*chain_id), the y parity is odd.
-    ///
-    /// The y_odd_parity is false if v is 35 (even y) and true if v is 36 (odd y).
-    fn calculate_v(
-        message: &Message,
-        chain_id: u64,
-        compact_signature: &[u8; 64],
-        public_key_bytes: &Secp256k1PubKeyBytes, // [u8; 33]
-    ) -> EthResult<bool> {
-        // recovery id must be 0 or 1
-        for recovery_id in [0, 1] {
-            let recid = RecoveryId::from_i32(recovery_id).expect("Invalid recovery id");
-            let recoverable_signature =
-                RecoverableSignature::from_compact(compact_signature, recid).map_err(|e| {
-                    EthApiError::InvalidParams(format!("failed to recover signature: {}", e))
-                })?;
-            if let Ok(recovered_key) = SECP256K1.recover_ecdsa(message, &recoverable_signature) {
-                let recovered_pubkey = recovered_key.serialize();
-                if recovered_pubkey == public_key_bytes.as_ref() {
-                    // v is found when the recovered key matches the known public key
-                    //
-                    // calculate v based on EIP-155
-                    let v = recovery_id as u64 + chain_id * 2 + 35;
-                    let y_odd_parity = v % 2 == 0;
-                    return Ok(y_odd_parity);
-                }
-            }
-        }
-
-        // TODO: better error type here
-        Err(EthApiError::FailedToDecodeSignedTransaction)
-    }
 }
 
 impl<Provider, Pool, Tasks> Future for FaucetService<Provider, Pool, Tasks>
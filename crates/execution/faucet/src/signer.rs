@@ -0,0 +1,286 @@
+//! Signers for transactions created by the node.
+//!
+//! The faucet never holds the private key for its wallet. Transactions are signed by an
+//! [ExecutionSigner] that forwards them to wherever the key lives:
+//! - [GoogleKmsSigner]: a secp256k1 key in Google Cloud KMS signs the transaction's hash
+//! - [RemoteSigner]: an external signing API (ie - a Ledger or HSM behind web3signer or clef) signs
+//!   the transaction with `eth_signTransaction`
+//!
+//! Every signature is recovered against the signer's address before the transaction is submitted.
+
+use crate::{GoogleKMSClient, Secp256k1PubKeyBytes};
+use alloy::{consensus::TxEnvelope, eips::eip2718::Decodable2718 as _};
+use async_trait::async_trait;
+use gcloud_sdk::{
+    google::cloud::kms::v1::{
+        digest::Digest, key_management_service_client::KeyManagementServiceClient,
+        AsymmetricSignRequest, Digest as KMSDigest,
+    },
+    GoogleApi,
+};
+use jsonrpsee::{
+    core::client::ClientT as _,
+    http_client::{HttpClient, HttpClientBuilder},
+    rpc_params,
+};
+use secp256k1::{
+    ecdsa::{RecoverableSignature, RecoveryId, Signature},
+    Message, SECP256K1,
+};
+use std::time::Duration;
+use tn_types::{
+    Address, Bytes, EthSignature, Transaction, TransactionRequest, TransactionTrait as _, U256,
+};
+
+/// Signs transactions created by the node with a key held outside of the node.
+#[async_trait]
+pub trait ExecutionSigner: Send + Sync + 'static {
+    /// The address of the signing key.
+    ///
+    /// Used for nonces and to verify signatures.
+    fn address(&self) -> Address;
+
+    /// Sign the transaction.
+    ///
+    /// Implementations don't verify the signature, the caller checks it recovers [Self::address].
+    async fn sign_transaction(&self, transaction: &Transaction) -> eyre::Result<EthSignature>;
+}
+
+/// Ensure the signature over the transaction was made by the expected address.
+pub(crate) fn verify_signature(
+    transaction: &Transaction,
+    signature: &EthSignature,
+    address: Address,
+) -> eyre::Result<()> {
+    let signer = signature.recover_address_from_prehash(&transaction.signature_hash())?;
+    if signer != address {
+        eyre::bail!("transaction signed by {signer} instead of {address}");
+    }
+    Ok(())
+}
+
+/// Signs transaction hashes with a key in Google Cloud KMS.
+#[derive(Debug, Clone)]
+pub struct GoogleKmsSigner {
+    /// The faucet's address.
+    ///
+    /// Used for verifying nonces and estimating gas.
+    pub address: Address,
+
+    /// The faucet's compressed (serialized) public key as bytes.
+    ///
+    /// The key is serialized as a byte-encoded pair of values. In compressed form the y-coordinate
+    /// is represented by only a single bit, as x determines it up to one bit.
+    ///
+    /// Used for creating transactions signed by Google KMS.
+    pub public_key_bytes: Secp256k1PubKeyBytes,
+
+    /// The "name" used by Google KMS to identify the key.
+    ///
+    /// The key needs to be "global" and in the format:
+    /// "projects/{}/locations/{}/keyRings/{}/cryptoKeys/{}/cryptoKeyVersions/{}".
+    pub name: String,
+}
+
+impl GoogleKmsSigner {
+    /// Try both recovery ids (0 or 1) to find the signature's y parity.
+    ///
+    /// NOTE: this compares the compressed public keys for convenience
+    ///
+    /// The signature from Google Cloud KMS using the secp256k1 curve is in DER format and only
+    /// contains the r and s components of the ECDSA signature. A signature does not uniquely
+    /// identify a public key, so the recovery id that recovers the known public key is the y
+    /// parity of the point on the elliptic curve needed by EVM to recover the signer.
+    ///
+    /// With EIP-155, `v = 35 + 2 * chain_id + recovery_id` so the y parity is odd when the recovery
+    /// id is 1.
+    fn calculate_y_parity(
+        &self,
+        message: &Message,
+        compact_signature: &[u8; 64],
+    ) -> eyre::Result<bool> {
+        // recovery id must be 0 or 1
+        for recovery_id in [0, 1] {
+            let recid = RecoveryId::from_i32(recovery_id)?;
+            let recoverable_signature =
+                RecoverableSignature::from_compact(compact_signature, recid)?;
+            if let Ok(recovered_key) = SECP256K1.recover_ecdsa(message, &recoverable_signature) {
+                // y parity is found when the recovered key matches the known public key
+                if recovered_key.serialize() == self.public_key_bytes {
+                    return Ok(recovery_id == 1);
+                }
+            }
+        }
+
+        eyre::bail!("KMS signature does not recover the faucet's public key")
+    }
+}
+
+#[async_trait]
+impl ExecutionSigner for GoogleKmsSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    /// Send a request to Google KMS and convert it to EVM compatible.
+    async fn sign_transaction(&self, transaction: &Transaction) -> eyre::Result<EthSignature> {
+        let digest = transaction.signature_hash();
+
+        // create client
+        //
+        // note: this is reusable, but challenging to figure out how
+        // to call the .await from inside sync function (spawn, create, etc.)
+        let client: GoogleKMSClient = GoogleApi::from_function(
+            KeyManagementServiceClient::new,
+            "https://cloudkms.googleapis.com",
+            None,
+        )
+        .await?;
+
+        // create message from slice before consuming digest
+        // this is needed to calculate `v` below
+        let message = Message::from_digest_slice(&digest.0)?;
+
+        // assemble digest for signature
+        let digest = Some(Digest::Sha256(digest.0.to_vec()));
+        let digest = Some(KMSDigest { digest });
+        let signed_data = client
+            .get()
+            .asymmetric_sign(AsymmetricSignRequest {
+                name: self.name.clone(),
+                digest,
+                ..Default::default()
+            })
+            .await?
+            .into_inner()
+            .signature;
+
+        // ensure signature is compatible with ethereum (see EIP-2)
+        let mut signature = Signature::from_der(&signed_data)?;
+        signature.normalize_s();
+        // retrieve r, s, and v values for EthSignature
+        let compact = signature.serialize_compact();
+        let y_parity = self.calculate_y_parity(&message, &compact)?;
+
+        // r and s are 32 bytes each
+        let (r, s) = compact.split_at(32);
+        let r = U256::from_be_slice(r);
+        let s = U256::from_be_slice(s);
+
+        Ok(EthSignature::new(r, s, y_parity))
+    }
+}
+
+/// Signs transactions with an external signing API.
+///
+/// The API must support `eth_signTransaction` for the signer's address and return the EIP-2718
+/// encoded signed transaction. Hardware wallets and HSMs that require approval for each
+/// transaction are supported as long as approval happens within the request timeout.
+#[derive(Debug, Clone)]
+pub struct RemoteSigner {
+    /// The address of the key held by the signing API.
+    address: Address,
+    /// The client for the signing API.
+    client: HttpClient,
+}
+
+impl RemoteSigner {
+    /// Create a new instance of [Self] for the signing API at `url`.
+    pub fn new(url: &str, address: Address, timeout: Duration) -> eyre::Result<Self> {
+        let client = HttpClientBuilder::default().request_timeout(timeout).build(url)?;
+        Ok(Self { address, client })
+    }
+
+    /// The `eth_signTransaction` request for the transaction.
+    fn transaction_request(&self, transaction: &Transaction) -> TransactionRequest {
+        TransactionRequest {
+            from: Some(self.address),
+            to: Some(transaction.kind()),
+            nonce: Some(transaction.nonce()),
+            gas: Some(transaction.gas_limit()),
+            max_fee_per_gas: Some(transaction.max_fee_per_gas()),
+            max_priority_fee_per_gas: transaction.max_priority_fee_per_gas(),
+            value: Some(transaction.value()),
+            input: transaction.input().clone().into(),
+            access_list: transaction.access_list().cloned(),
+            chain_id: transaction.chain_id(),
+            transaction_type: Some(transaction.ty()),
+            ..Default::default()
+        }
+    }
+
+    /// Return the signature of the signed transaction returned by the signing API.
+    ///
+    /// The signature is not checked here. The faucet verifies it against the transaction that was
+    /// sent before submitting, so transactions the signing API changed (ie - filled different
+    /// fees) are rejected.
+    fn signature_from_raw(raw: &Bytes) -> eyre::Result<EthSignature> {
+        let signed = TxEnvelope::decode_2718(&mut raw.as_ref())?;
+        Ok(*signed.signature())
+    }
+}
+
+#[async_trait]
+impl ExecutionSigner for RemoteSigner {
+    fn address(&self) -> Address {
+        self.address
+    }
+
+    async fn sign_transaction(&self, transaction: &Transaction) -> eyre::Result<EthSignature> {
+        let request = self.transaction_request(transaction);
+        let raw: Bytes = self.client.request("eth_signTransaction", rpc_params![request]).await?;
+        Self::signature_from_raw(&raw)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::signers::SignerSync as _;
+    use tn_test_utils::TransactionFactory;
+    use tn_types::{Encodable2718 as _, TransactionSigned, TxEip1559, TxKind};
+
+    fn transaction(nonce: u64) -> Transaction {
+        Transaction::Eip1559(TxEip1559 {
+            chain_id: 2017,
+            nonce,
+            max_priority_fee_per_gas: 7,
+            max_fee_per_gas: 7,
+            gas_limit: 1_000_000,
+            to: TxKind::Call(Address::repeat_byte(1)),
+            value: U256::ZERO,
+            input: Bytes::from_static(&[235, 56, 57, 167]),
+            access_list: Default::default(),
+        })
+    }
+
+    #[test]
+    fn test_remote_signer_signatures_are_verified() {
+        let signer =
+            TransactionFactory::default().get_default_signer().expect("default signer created");
+        let remote = RemoteSigner::new("http://127.0.0.1:8545", signer.address(), Duration::ZERO)
+            .expect("remote signer created");
+        let transaction = transaction(3);
+
+        // the request includes every field of the transaction
+        let request = remote.transaction_request(&transaction);
+        assert_eq!(request.from, Some(signer.address()));
+        assert_eq!(request.nonce, Some(3));
+        assert_eq!(request.chain_id, Some(2017));
+        assert_eq!(request.transaction_type, Some(2));
+        assert_eq!(request.input.input(), Some(transaction.input()));
+
+        // the signing API's signature is used for the transaction that was sent
+        let signature =
+            signer.sign_hash_sync(&transaction.signature_hash()).expect("transaction signed");
+        let raw: Bytes =
+            TransactionSigned::new_unhashed(transaction.clone(), signature).encoded_2718().into();
+        let recovered = RemoteSigner::signature_from_raw(&raw).expect("signed transaction decoded");
+        assert_eq!(recovered, signature);
+        verify_signature(&transaction, &recovered, remote.address()).expect("valid signature");
+
+        // signatures for a changed transaction or from a different key are rejected
+        assert!(verify_signature(&self::transaction(4), &recovered, remote.address()).is_err());
+        assert!(verify_signature(&transaction, &recovered, Address::repeat_byte(2)).is_err());
+    }
+}
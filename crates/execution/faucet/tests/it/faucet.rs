@@ -32,7 +32,7 @@ use tn_test_utils::{
 };
 use tn_types::{
     adiri_genesis, error::BlockSealError, hex, public_key_to_address, sol, Address, GenesisAccount,
-    Notifier, SealedBatch, SolType, SolValue, TaskManager, TransactionSigned,
+    Notifier, SealedBatch, SolType, SolValue, SystemClock, TaskManager, TransactionSigned,
     TransactionTrait as _, B256, U160, U256,
 };
 use tn_worker::{
@@ -263,7 +263,9 @@ async fn test_with_creds_faucet_transfers_tel_with_google_kms() -> eyre::Result<
         client,
         store.clone(),
         timeout,
+        tn_worker::CHANNEL_CAPACITY,
         WorkerNetworkHandle::new_for_test(),
+        None,
     );
 
     let shutdown = Notifier::default();
@@ -272,6 +274,7 @@ async fn test_with_creds_faucet_transfers_tel_with_google_kms() -> eyre::Result<
         .start_batch_builder(
             worker_id,
             batch_provider.batches_tx(),
+            SystemClock::shared(),
             &TaskManager::default(),
             shutdown.subscribe(),
         )
@@ -583,7 +586,13 @@ async fn test_with_creds_faucet_transfers_stablecoin_with_google_kms() -> eyre::
     let worker_id = 0;
     let (to_worker, mut next_batch) = tokio::sync::mpsc::channel(2);
     execution_node
-        .start_batch_builder(worker_id, to_worker, &TaskManager::default(), shutdown.subscribe())
+        .start_batch_builder(
+            worker_id,
+            to_worker,
+            SystemClock::shared(),
+            &TaskManager::default(),
+            shutdown.subscribe(),
+        )
         .await?;
 
     let user_address = Address::random();
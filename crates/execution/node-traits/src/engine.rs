@@ -9,8 +9,9 @@ use reth_revm::primitives::{
 };
 use serde::{Deserialize, Serialize};
 use tn_types::{
-    Address, BlockExt as _, BlockWithSenders, ConsensusOutput, NodePrimitives, SealedBlock,
-    SealedHeader, Withdrawals, B256, U256,
+    Address, BatchGasSchedule, BlockExt as _, BlockWithSenders, ConsensusOutput, DevStateChanges,
+    NodePrimitives, SealedBlock, SealedHeader, SenderRecovery, SystemCall, TimestampPolicy,
+    Withdrawals, B256, U256,
 };
 
 /// Compatibility type to easily integrate with reth.
@@ -95,12 +96,61 @@ pub struct BuildArguments<Provider> {
     pub output: ConsensusOutput,
     /// Last executed block from the previous consensus output.
     pub parent_header: SealedHeader,
+    /// Recovers the senders of the transactions to execute.
+    pub sender_recovery: SenderRecovery,
+    /// How executed blocks are timestamped, `None` to use the sub-dag's commit timestamp.
+    pub timestamp_policy: Option<TimestampPolicy>,
+    /// Contract calls made at the start and end of the output's sub-dag.
+    pub system_calls: Vec<SystemCall>,
+    /// How the gas limit of executed blocks is set, `None` to use the batch's timestamp.
+    pub batch_gas: Option<BatchGasSchedule>,
+    /// State changes from a dev network applied before the first block's transactions.
+    pub dev_state: Option<DevStateChanges>,
 }
 
 impl<P> BuildArguments<P> {
     /// Initialize new instance of [Self].
     pub fn new(provider: P, output: ConsensusOutput, parent_header: SealedHeader) -> Self {
-        Self { provider, output, parent_header }
+        Self {
+            provider,
+            output,
+            parent_header,
+            sender_recovery: SenderRecovery::default(),
+            timestamp_policy: None,
+            system_calls: Vec::new(),
+            batch_gas: None,
+            dev_state: None,
+        }
+    }
+
+    /// Recover transaction senders with `sender_recovery`.
+    pub fn with_sender_recovery(mut self, sender_recovery: SenderRecovery) -> Self {
+        self.sender_recovery = sender_recovery;
+        self
+    }
+
+    /// Timestamp executed blocks with `timestamp_policy`.
+    pub fn with_timestamp_policy(mut self, timestamp_policy: TimestampPolicy) -> Self {
+        self.timestamp_policy = Some(timestamp_policy);
+        self
+    }
+
+    /// Make `system_calls` at the start and end of the output's sub-dag.
+    pub fn with_system_calls(mut self, system_calls: Vec<SystemCall>) -> Self {
+        self.system_calls = system_calls;
+        self
+    }
+
+    /// Set the gas limit of executed blocks with `batch_gas`.
+    pub fn with_batch_gas(mut self, batch_gas: BatchGasSchedule) -> Self {
+        self.batch_gas = Some(batch_gas);
+        self
+    }
+
+    /// Apply the state changes queued by a single-node dev network.
+    pub fn with_dev_state(mut self, dev_state: DevStateChanges) -> Self {
+        self.dev_state = Some(dev_state);
+        self
     }
 }
 
@@ -180,7 +230,7 @@ impl TNPayload {
         Some(self.attributes.consensus_output_digest)
     }
 
-    /// Taken from worker's block, but currently always empty.
+    /// Protocol withdrawals from consensus output to apply in this block.
     pub fn withdrawals(&self) -> &Withdrawals {
         &self.attributes.withdrawals
     }
@@ -205,7 +255,9 @@ pub struct TNPayloadAttributes {
     ///
     /// Used as executed block header's `difficulty`.
     pub batch_index: u64,
-    /// Value for the `timestamp` field of the new payload
+    /// Value for the `timestamp` field of the new payload.
+    ///
+    /// Derived from the sub-dag's commit timestamp with a [TimestampPolicy].
     pub timestamp: u64,
     /// Value for the `extra_data` field in the new block.
     pub batch_digest: B256,
@@ -216,13 +268,13 @@ pub struct TNPayloadAttributes {
     pub base_fee_per_gas: u64,
     /// The gas limit for the constructed block.
     ///
-    /// The value comes from the worker's block.
+    /// The value comes from the [BatchGasSchedule].
     pub gas_limit: u64,
     /// The mix hash used for prev_randao.
     pub mix_hash: B256,
-    /// TODO: support withdrawals
+    /// Protocol withdrawals from consensus output to apply after the block's transactions.
     ///
-    /// This is currently always empty vec. This comes from the batch block's withdrawals.
+    /// Only the last block executed for an output carries the output's withdrawals.
     pub withdrawals: Withdrawals,
 }
 
@@ -239,13 +291,19 @@ impl TNPayloadAttributes {
         gas_limit: u64,
         mix_hash: B256,
         withdrawals: Withdrawals,
+        timestamp_policy: Option<TimestampPolicy>,
     ) -> Self {
+        let timestamp = match timestamp_policy {
+            Some(policy) => policy.block_timestamp(output.committed_at(), parent_header.timestamp),
+            // blocks keep the commit timestamp until the policy's fork activates
+            None => output.committed_at(),
+        };
         Self {
             parent_header,
             beneficiary: output.beneficiary(),
             nonce: output.nonce(),
             batch_index,
-            timestamp: output.committed_at(),
+            timestamp,
             batch_digest,
             consensus_output_digest,
             base_fee_per_gas,
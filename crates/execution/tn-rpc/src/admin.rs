@@ -0,0 +1,529 @@
+//! RPC extension for node operators.
+//!
+//! The `tnAdmin` namespace exposes local node information that is useful for operators but should
+//! not be publicly available. Operators should only enable this namespace on a local interface.
+
+use crate::error::TelcoinNetworkRpcResult;
+use async_trait::async_trait;
+use jsonrpsee::proc_macros::rpc;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, fmt, net::SocketAddr, sync::Arc};
+use tn_types::{BlockHash, BlockNumber, PrimaryInfo, Round, WorkerId, B256};
+
+/// Snapshot of the node's current status.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeStatus {
+    /// The node's current mode (active CVV, inactive CVV, or observer).
+    pub node_mode: String,
+    /// The number of consensus blocks this node is behind the latest consensus block number seen
+    /// on the gossip network.
+    pub sync_distance: u64,
+    /// The number of connected peers on the primary network.
+    pub primary_peers: usize,
+    /// The number of connected peers on the worker network.
+    pub worker_peers: usize,
+    /// The number of the last consensus header (one per committed sub-dag) recorded by this node.
+    pub last_sub_dag_index: u64,
+    /// The leader round of the last committed sub-dag.
+    pub last_committed_round: Round,
+    /// The number of the last executed block.
+    pub last_executed_block_number: BlockNumber,
+    /// The hash of the last executed block.
+    pub last_executed_block_hash: BlockHash,
+    /// The number of the latest block announced by the committee, if this node follows
+    /// announcements.
+    pub announced_block_number: Option<BlockNumber>,
+    /// The hash of the latest block announced by the committee.
+    pub announced_block_hash: Option<BlockHash>,
+    /// The RPC endpoints for this node's workers.
+    pub worker_rpc_endpoints: Vec<WorkerRpcEndpoint>,
+    /// The sub-dag the node will halt at after executing, if any.
+    pub halt_at_sub_dag: Option<u64>,
+    /// The number of sub-dags left to restore while re-executing consensus output after a restart.
+    pub restore_remaining_sub_dags: Option<u64>,
+    /// The estimated seconds until the restore completes, once the rate is known.
+    pub restore_eta_secs: Option<u64>,
+}
+
+/// The RPC endpoint for one of the node's workers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkerRpcEndpoint {
+    /// The worker's id.
+    pub worker_id: WorkerId,
+    /// The local address of the worker's HTTP RPC server, if it is running.
+    pub http: Option<SocketAddr>,
+    /// True if the worker stopped reporting batches and heartbeats to the primary.
+    #[serde(default)]
+    pub down: bool,
+}
+
+impl fmt::Display for NodeStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "node mode:            {}", self.node_mode)?;
+        writeln!(f, "sync distance:        {}", self.sync_distance)?;
+        writeln!(f, "primary peers:        {}", self.primary_peers)?;
+        writeln!(f, "worker peers:         {}", self.worker_peers)?;
+        writeln!(f, "last sub-dag index:   {}", self.last_sub_dag_index)?;
+        writeln!(f, "last committed round: {}", self.last_committed_round)?;
+        writeln!(
+            f,
+            "last executed block:  {} ({})",
+            self.last_executed_block_number, self.last_executed_block_hash
+        )?;
+        if let (Some(number), Some(hash)) = (self.announced_block_number, self.announced_block_hash)
+        {
+            writeln!(f, "announced block:      {number} ({hash})")?;
+        }
+        if let Some(halt_at) = self.halt_at_sub_dag {
+            writeln!(f, "halt at sub-dag:      {halt_at}")?;
+        }
+        if let Some(remaining) = self.restore_remaining_sub_dags {
+            write!(f, "restoring:            {remaining} sub-dags left")?;
+            match self.restore_eta_secs {
+                Some(eta) => writeln!(f, " (eta {eta}s)")?,
+                None => writeln!(f)?,
+            }
+        }
+        write!(f, "worker rpc endpoints:")?;
+        if self.worker_rpc_endpoints.is_empty() {
+            write!(f, " none")?;
+        }
+        for endpoint in self.worker_rpc_endpoints.iter() {
+            match endpoint.http {
+                Some(addr) => write!(f, "\n  worker {}: http://{addr}", endpoint.worker_id)?,
+                None => write!(f, "\n  worker {}: not running", endpoint.worker_id)?,
+            }
+            if endpoint.down {
+                write!(f, " (down)")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The result of dialing a peer from this node.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerConnectivity {
+    /// Whether the peer accepted a connection.
+    pub reachable: bool,
+    /// The reason the peer is unreachable.
+    pub error: Option<String>,
+}
+
+impl PeerConnectivity {
+    /// The peer is reachable.
+    pub fn reachable() -> Self {
+        Self { reachable: true, error: None }
+    }
+
+    /// The peer is unreachable for the provided reason.
+    pub fn unreachable(error: impl ToString) -> Self {
+        Self { reachable: false, error: Some(error.to_string()) }
+    }
+}
+
+/// Connectivity from this node to a validator's primary and workers.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ValidatorConnectivity {
+    /// The validator's primary.
+    pub primary: PeerConnectivity,
+    /// The validator's workers by id.
+    pub workers: BTreeMap<WorkerId, PeerConnectivity>,
+}
+
+impl ValidatorConnectivity {
+    /// Return true if the primary and every worker are reachable.
+    pub fn all_reachable(&self) -> bool {
+        self.primary.reachable && self.workers.values().all(|worker| worker.reachable)
+    }
+}
+
+/// The type of a Prometheus metric family.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum MetricKind {
+    /// A value that only increases.
+    Counter,
+    /// A value that can go up and down.
+    Gauge,
+    /// Observations counted in buckets.
+    Histogram,
+    /// Observations summarized by quantiles.
+    Summary,
+    /// A value without a declared type.
+    Untyped,
+}
+
+/// One histogram bucket.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricBucket {
+    /// The inclusive upper bound of the bucket.
+    pub upper_bound: f64,
+    /// The number of observations less than or equal to the upper bound.
+    pub cumulative_count: u64,
+}
+
+/// One summary quantile.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricQuantile {
+    /// The quantile, between 0 and 1.
+    pub quantile: f64,
+    /// The observed value at the quantile.
+    pub value: f64,
+}
+
+/// The current value of a metric for one set of labels.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricSample {
+    /// The metric's labels.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub labels: BTreeMap<String, String>,
+    /// The value of a counter, gauge, or untyped metric.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+    /// The number of observations of a histogram or summary.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_count: Option<u64>,
+    /// The sum of observations of a histogram or summary.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_sum: Option<f64>,
+    /// The buckets of a histogram.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub buckets: Vec<MetricBucket>,
+    /// The quantiles of a summary.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub quantiles: Vec<MetricQuantile>,
+}
+
+/// A snapshot of a metric family from the node's Prometheus registry.
+///
+/// Mirrors the Prometheus exposition format for monitors that can't scrape the metrics endpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricFamilySnapshot {
+    /// The metric's name.
+    pub name: String,
+    /// The metric's description.
+    pub help: String,
+    /// The type of the metric.
+    #[serde(rename = "type")]
+    pub kind: MetricKind,
+    /// The current value for each set of labels.
+    pub metrics: Vec<MetricSample>,
+}
+
+/// The default fraction of a channel's capacity in use for it to be reported as backlogged.
+pub const DEFAULT_CHANNEL_BACKLOG_THRESHOLD: f64 = 0.8;
+
+/// The backlog of one of the node's bounded internal channels.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChannelBacklog {
+    /// The channel's name.
+    pub name: String,
+    /// The number of queued messages.
+    pub depth: usize,
+    /// The number of messages the channel can hold.
+    pub capacity: usize,
+    /// The highest number of queued messages since the channel was created.
+    pub high_water_mark: usize,
+    /// The fraction of the channel's capacity in use.
+    pub utilization: f64,
+}
+
+/// A change in the connectivity to a peer recorded by one of the node's networks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PeerHistoryEvent {
+    /// The UNIX timestamp of the event in milliseconds.
+    pub timestamp_ms: u64,
+    /// The network the event was observed on, `primary` or `worker`.
+    pub network: String,
+    /// The peer's id.
+    pub peer_id: String,
+    /// What happened: `connected`, `disconnected`, or `dialFailed`.
+    pub event: String,
+    /// The error that closed the connection or failed the dial, if any.
+    pub reason: Option<String>,
+}
+
+/// The votes collected for the header this node proposed last.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VoteAggregationStatus {
+    /// The round of the proposed header.
+    pub round: Round,
+    /// The digest of the proposed header.
+    pub header_digest: B256,
+    /// The authorities with a valid vote for the header, including this node.
+    pub voters: Vec<String>,
+    /// The committee members without a valid vote for the header.
+    pub missing: Vec<String>,
+    /// The voting power of the voters.
+    pub stake: u64,
+    /// The voting power needed to certify the header.
+    pub quorum_threshold: u64,
+    /// True once the votes formed a certificate.
+    pub certified: bool,
+    /// The milliseconds the node collected votes for the header, up to its certification.
+    pub elapsed_ms: u64,
+}
+
+/// Source of node information for the `tnAdmin` namespace.
+///
+/// The node implements this trait to report state from consensus and execution without the RPC
+/// crate depending on either.
+#[async_trait]
+pub trait NodeStatusProvider: Send + Sync + 'static {
+    /// Collect the current status of the node.
+    async fn node_status(&self) -> TelcoinNetworkRpcResult<NodeStatus>;
+
+    /// Dial a validator's primary and workers from this node's networks.
+    async fn check_connectivity(
+        &self,
+        primary_info: PrimaryInfo,
+    ) -> TelcoinNetworkRpcResult<ValidatorConnectivity>;
+
+    /// Stop the node after executing the sub-dag.
+    ///
+    /// The target is saved so it survives relaunches and restarts until the node reaches it. Fails
+    /// if the node has already reached the sub-dag.
+    async fn halt_at(&self, sub_dag: u64) -> TelcoinNetworkRpcResult<()>;
+
+    /// Replace the node's log filter.
+    ///
+    /// Fails if the filter is invalid or the node's logging can't be changed at runtime.
+    async fn set_log_filter(&self, filter: String) -> TelcoinNetworkRpcResult<()>;
+
+    /// Gather the metric families whose names start with `prefix`.
+    async fn metrics_snapshot(
+        &self,
+        prefix: Option<String>,
+    ) -> TelcoinNetworkRpcResult<Vec<MetricFamilySnapshot>>;
+
+    /// List the internal channels with at least `threshold` of their capacity in use.
+    async fn channel_backlogs(
+        &self,
+        threshold: f64,
+    ) -> TelcoinNetworkRpcResult<Vec<ChannelBacklog>>;
+
+    /// Read the peer connectivity events recorded in the last `window_secs` seconds, oldest
+    /// first.
+    async fn peer_history(
+        &self,
+        window_secs: u64,
+    ) -> TelcoinNetworkRpcResult<Vec<PeerHistoryEvent>>;
+
+    /// The votes collected for the last header this node proposed, if any.
+    async fn vote_aggregation(&self) -> TelcoinNetworkRpcResult<Option<VoteAggregationStatus>>;
+}
+
+/// Changes the log filter of the running process.
+///
+/// The binary implements this trait because it owns the tracing subscriber.
+pub trait LogFilterHandle: Send + Sync + 'static {
+    /// Replace the log filter with `filter` directives, e.g.
+    /// `telcoin::node=debug,tn_primary=trace`.
+    fn set_log_filter(&self, filter: &str) -> TelcoinNetworkRpcResult<()>;
+}
+
+/// Telcoin Network admin RPC namespace.
+///
+/// Local node information for operators.
+#[rpc(server, client, namespace = "tnAdmin")]
+pub trait TelcoinNetworkAdminApi {
+    /// Return the current status of the node.
+    #[method(name = "nodeStatus")]
+    async fn node_status(&self) -> TelcoinNetworkRpcResult<NodeStatus>;
+
+    /// Dial a validator's primary and workers and report which are reachable from this node.
+    #[method(name = "checkConnectivity")]
+    async fn check_connectivity(
+        &self,
+        primary_info: PrimaryInfo,
+    ) -> TelcoinNetworkRpcResult<ValidatorConnectivity>;
+
+    /// Stop the node cleanly after executing the sub-dag.
+    ///
+    /// Validators halted at the same sub-dag have identical state, so upgrades can be coordinated
+    /// across the committee with minimal missed rounds. The target is kept across restarts until
+    /// the node reaches it, `--halt-at-sub-dag` takes precedence.
+    #[method(name = "haltAt")]
+    async fn halt_at(&self, sub_dag: u64) -> TelcoinNetworkRpcResult<()>;
+
+    /// Replace the log filter without restarting the node.
+    ///
+    /// The filter uses `RUST_LOG` syntax, e.g. `telcoin::node=debug,tn_primary=trace`.
+    #[method(name = "setLogFilter")]
+    async fn set_log_filter(&self, filter: String) -> TelcoinNetworkRpcResult<()>;
+
+    /// Return the node's current Prometheus metrics as JSON.
+    ///
+    /// For monitors that can't scrape the metrics endpoint. Only metric families whose names
+    /// start with `prefix` are returned, if set.
+    #[method(name = "metricsSnapshot")]
+    async fn metrics_snapshot(
+        &self,
+        prefix: Option<String>,
+    ) -> TelcoinNetworkRpcResult<Vec<MetricFamilySnapshot>>;
+
+    /// List the internal channels that are close to full, most utilized first.
+    ///
+    /// Channels with at least `threshold` of their capacity in use are returned, 80% if not set.
+    /// A full channel applies backpressure to the task sending on it, so this shows where the node
+    /// is falling behind.
+    #[method(name = "channelBacklogs")]
+    async fn channel_backlogs(
+        &self,
+        threshold: Option<f64>,
+    ) -> TelcoinNetworkRpcResult<Vec<ChannelBacklog>>;
+
+    /// Return the peer connects, disconnects, and dial failures from the last `window` seconds.
+    ///
+    /// Events are persisted, so the history includes events from before the node restarted. Each
+    /// network keeps a bounded number of its most recent events.
+    #[method(name = "peerHistory")]
+    async fn peer_history(&self, window: u64) -> TelcoinNetworkRpcResult<Vec<PeerHistoryEvent>>;
+
+    /// Return the votes collected for the last header this node proposed.
+    ///
+    /// Shows the voting power collected so far and which committee members haven't voted, to
+    /// diagnose stalled certification. Returns null until the node proposes a header.
+    #[method(name = "voteAggregation")]
+    async fn vote_aggregation(&self) -> TelcoinNetworkRpcResult<Option<VoteAggregationStatus>>;
+}
+
+/// The type that implements `tnAdmin` namespace trait.
+pub struct TelcoinNetworkAdminExt {
+    /// The node's status provider.
+    provider: Arc<dyn NodeStatusProvider>,
+}
+
+impl TelcoinNetworkAdminExt {
+    /// Create new instance of the Telcoin Network admin RPC extension.
+    pub fn new(provider: Arc<dyn NodeStatusProvider>) -> Self {
+        Self { provider }
+    }
+}
+
+#[async_trait]
+impl TelcoinNetworkAdminApiServer for TelcoinNetworkAdminExt {
+    async fn node_status(&self) -> TelcoinNetworkRpcResult<NodeStatus> {
+        self.provider.node_status().await
+    }
+
+    async fn check_connectivity(
+        &self,
+        primary_info: PrimaryInfo,
+    ) -> TelcoinNetworkRpcResult<ValidatorConnectivity> {
+        self.provider.check_connectivity(primary_info).await
+    }
+
+    async fn halt_at(&self, sub_dag: u64) -> TelcoinNetworkRpcResult<()> {
+        self.provider.halt_at(sub_dag).await
+    }
+
+    async fn set_log_filter(&self, filter: String) -> TelcoinNetworkRpcResult<()> {
+        self.provider.set_log_filter(filter).await
+    }
+
+    async fn metrics_snapshot(
+        &self,
+        prefix: Option<String>,
+    ) -> TelcoinNetworkRpcResult<Vec<MetricFamilySnapshot>> {
+        self.provider.metrics_snapshot(prefix).await
+    }
+
+    async fn channel_backlogs(
+        &self,
+        threshold: Option<f64>,
+    ) -> TelcoinNetworkRpcResult<Vec<ChannelBacklog>> {
+        let threshold = threshold.unwrap_or(DEFAULT_CHANNEL_BACKLOG_THRESHOLD);
+        let mut backlogs = self.provider.channel_backlogs(threshold).await?;
+        backlogs.sort_by(|a, b| b.utilization.total_cmp(&a.utilization));
+        Ok(backlogs)
+    }
+
+    async fn peer_history(&self, window: u64) -> TelcoinNetworkRpcResult<Vec<PeerHistoryEvent>> {
+        self.provider.peer_history(window).await
+    }
+
+    async fn vote_aggregation(&self) -> TelcoinNetworkRpcResult<Option<VoteAggregationStatus>> {
+        self.provider.vote_aggregation().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_node_status_json_roundtrip() {
+        let status = NodeStatus {
+            // the node reports the mode's `Display` output
+            node_mode: "active CVV".to_string(),
+            sync_distance: 2,
+            primary_peers: 3,
+            worker_peers: 3,
+            last_sub_dag_index: 10,
+            last_committed_round: 20,
+            last_executed_block_number: 15,
+            last_executed_block_hash: BlockHash::with_last_byte(7),
+            announced_block_number: Some(18),
+            announced_block_hash: Some(BlockHash::with_last_byte(8)),
+            worker_rpc_endpoints: vec![WorkerRpcEndpoint {
+                worker_id: 0,
+                http: Some("127.0.0.1:8545".parse().expect("valid socket addr")),
+                down: true,
+            }],
+            halt_at_sub_dag: Some(12),
+            restore_remaining_sub_dags: Some(30),
+            restore_eta_secs: Some(60),
+        };
+
+        let json = serde_json::to_string(&status).expect("status serializes");
+        assert!(json.contains("\"syncDistance\":2"));
+        let decoded: NodeStatus = serde_json::from_str(&json).expect("status deserializes");
+        assert_eq!(decoded, status);
+
+        let human = status.to_string();
+        assert!(human.contains("node mode:            active CVV"));
+        assert!(human.contains("worker 0: http://127.0.0.1:8545 (down)"));
+        assert!(human.contains("halt at sub-dag:      12"));
+        assert!(
+            human.contains(&format!("announced block:      18 ({})", BlockHash::with_last_byte(8)))
+        );
+        assert!(human.contains("restoring:            30 sub-dags left (eta 60s)"));
+    }
+
+    #[test]
+    fn test_metrics_snapshot_json() {
+        let family = MetricFamilySnapshot {
+            name: "batch_seal_latency".to_string(),
+            help: "The time to seal a batch".to_string(),
+            kind: MetricKind::Histogram,
+            metrics: vec![MetricSample {
+                labels: BTreeMap::from([("worker".to_string(), "0".to_string())]),
+                sample_count: Some(3),
+                sample_sum: Some(1.5),
+                buckets: vec![MetricBucket { upper_bound: 0.5, cumulative_count: 2 }],
+                ..Default::default()
+            }],
+        };
+
+        let json = serde_json::to_string(&family).expect("snapshot serializes");
+        assert!(json.contains("\"type\":\"histogram\""));
+        assert!(json.contains("\"cumulativeCount\":2"));
+        // absent fields are omitted
+        assert!(!json.contains("value"));
+        let decoded: MetricFamilySnapshot = serde_json::from_str(&json).expect("deserializes");
+        assert_eq!(decoded, family);
+    }
+}
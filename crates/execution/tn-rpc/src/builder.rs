@@ -0,0 +1,253 @@
+//! RPC extension for external batch builders.
+//!
+//! The `tnBuilder` namespace lets an authorized external builder submit the transactions for the
+//! next batch instead of the worker pulling them from its own pool. The worker validates the
+//! candidate like a peer's batch before sealing it.
+//!
+//! Submissions are signed by the builder's secp256k1 key and only accepted from addresses the
+//! operator authorized when starting the node. A signed submission is sealed at most once: it
+//! commits to the finalized block it was built on, and submissions already seen for a block are
+//! rejected.
+
+use crate::error::{TNRpcError, TelcoinNetworkRpcResult};
+use async_trait::async_trait;
+use jsonrpsee::proc_macros::rpc;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashSet, VecDeque},
+    sync::Arc,
+};
+use tn_types::{keccak256, Address, Bytes, EthSignature, SolValue as _, B256, U256};
+
+/// The number of recent submissions remembered to reject replays.
+///
+/// Older submissions are built on a block that is no longer finalized and are rejected by the
+/// worker.
+const MAX_RECENT_SUBMISSIONS: usize = 1024;
+
+/// A batch candidate from an external builder.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchSubmission {
+    /// The hash of the finalized execution block the batch was built on.
+    ///
+    /// The worker rejects candidates built on any other block.
+    pub parent_hash: B256,
+    /// The EIP-2718 encoded transactions for the batch, in order.
+    pub transactions: Vec<Bytes>,
+    /// The builder's signature over [BatchSubmission::signing_hash].
+    pub signature: EthSignature,
+}
+
+impl BatchSubmission {
+    /// The hash the builder signs for a submission.
+    ///
+    /// Commits to the chain id, the parent block, and the transactions so a submission can not be
+    /// replayed on another chain or on top of a later block.
+    pub fn signing_hash(chain_id: u64, parent_hash: B256, transactions: &[Bytes]) -> B256 {
+        keccak256((U256::from(chain_id), parent_hash, transactions.to_vec()).abi_encode())
+    }
+
+    /// Recover the address of the builder that signed the submission.
+    pub fn recover_builder(&self, chain_id: u64) -> TelcoinNetworkRpcResult<Address> {
+        let hash = Self::signing_hash(chain_id, self.parent_hash, &self.transactions);
+        self.signature
+            .recover_address_from_prehash(&hash)
+            .map_err(|e| TNRpcError::UnauthorizedBuilder(e.to_string()))
+    }
+}
+
+/// Validates and seals batch candidates from external builders.
+///
+/// The node implements this trait with the worker's batch validator and batch channel.
+#[async_trait]
+pub trait ExternalBatchSubmitter: Send + Sync + 'static {
+    /// Validate the candidate and send it to the worker to seal.
+    ///
+    /// Returns the digest of the sealed batch once the worker has stored it.
+    async fn submit_batch(
+        &self,
+        builder: Address,
+        parent_hash: B256,
+        transactions: Vec<Vec<u8>>,
+    ) -> TelcoinNetworkRpcResult<B256>;
+}
+
+/// Telcoin Network builder RPC namespace.
+///
+/// Batch submission for external builders.
+#[rpc(server, client, namespace = "tnBuilder")]
+pub trait TelcoinNetworkBuilderApi {
+    /// Submit a signed batch candidate for the worker to validate and seal.
+    ///
+    /// Returns the digest of the sealed batch.
+    #[method(name = "submitBatch")]
+    async fn submit_batch(&self, submission: BatchSubmission) -> TelcoinNetworkRpcResult<B256>;
+}
+
+/// The type that implements `tnBuilder` namespace trait.
+pub struct TelcoinNetworkBuilderExt {
+    /// The chain id submissions must be signed for.
+    chain_id: u64,
+    /// The addresses of builders allowed to submit batches.
+    authorized_builders: HashSet<Address>,
+    /// The worker's batch submitter.
+    submitter: Arc<dyn ExternalBatchSubmitter>,
+    /// The signing hashes of recent submissions, oldest first.
+    recent_submissions: Mutex<VecDeque<B256>>,
+}
+
+impl TelcoinNetworkBuilderExt {
+    /// Create new instance of the Telcoin Network builder RPC extension.
+    pub fn new(
+        chain_id: u64,
+        authorized_builders: impl IntoIterator<Item = Address>,
+        submitter: Arc<dyn ExternalBatchSubmitter>,
+    ) -> Self {
+        Self {
+            chain_id,
+            authorized_builders: authorized_builders.into_iter().collect(),
+            submitter,
+            recent_submissions: Mutex::new(VecDeque::with_capacity(MAX_RECENT_SUBMISSIONS)),
+        }
+    }
+
+    /// Return the builder that signed the submission if it is authorized.
+    fn authenticate(&self, submission: &BatchSubmission) -> TelcoinNetworkRpcResult<Address> {
+        let builder = submission.recover_builder(self.chain_id)?;
+        if !self.authorized_builders.contains(&builder) {
+            return Err(TNRpcError::UnauthorizedBuilder(format!("{builder} is not authorized")));
+        }
+        Ok(builder)
+    }
+
+    /// Remember the submission's signing hash.
+    ///
+    /// Returns an error if the submission was already seen.
+    fn claim_submission(&self, signing_hash: B256) -> TelcoinNetworkRpcResult<()> {
+        let mut recent = self.recent_submissions.lock();
+        if recent.contains(&signing_hash) {
+            return Err(TNRpcError::InvalidBatch("submission was already received".to_string()));
+        }
+        if recent.len() == MAX_RECENT_SUBMISSIONS {
+            recent.pop_front();
+        }
+        recent.push_back(signing_hash);
+        Ok(())
+    }
+
+    /// Forget a submission that was not sealed so the builder can submit it again.
+    fn release_submission(&self, signing_hash: B256) {
+        self.recent_submissions.lock().retain(|hash| *hash != signing_hash);
+    }
+}
+
+#[async_trait]
+impl TelcoinNetworkBuilderApiServer for TelcoinNetworkBuilderExt {
+    async fn submit_batch(&self, submission: BatchSubmission) -> TelcoinNetworkRpcResult<B256> {
+        let builder = self.authenticate(&submission)?;
+        let signing_hash = BatchSubmission::signing_hash(
+            self.chain_id,
+            submission.parent_hash,
+            &submission.transactions,
+        );
+        self.claim_submission(signing_hash)?;
+        let BatchSubmission { parent_hash, transactions, .. } = submission;
+        let transactions = transactions.into_iter().map(Into::into).collect();
+        let res = self.submitter.submit_batch(builder, parent_hash, transactions).await;
+        if res.is_err() {
+            self.release_submission(signing_hash);
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloy::signers::{local::PrivateKeySigner, SignerSync as _};
+
+    /// Counts sealed batches and fails while `fail` is set.
+    #[derive(Default)]
+    struct CountingSubmitter {
+        sealed: Mutex<usize>,
+        fail: Mutex<bool>,
+    }
+
+    #[async_trait]
+    impl ExternalBatchSubmitter for CountingSubmitter {
+        async fn submit_batch(
+            &self,
+            _builder: Address,
+            _parent_hash: B256,
+            _transactions: Vec<Vec<u8>>,
+        ) -> TelcoinNetworkRpcResult<B256> {
+            if *self.fail.lock() {
+                return Err(TNRpcError::BatchSeal("worker is not running".to_string()));
+            }
+            *self.sealed.lock() += 1;
+            Ok(B256::ZERO)
+        }
+    }
+
+    fn submission(signer: &PrivateKeySigner, chain_id: u64) -> BatchSubmission {
+        let parent_hash = B256::with_last_byte(1);
+        let transactions = vec![Bytes::from_static(&[1, 2, 3])];
+        let hash = BatchSubmission::signing_hash(chain_id, parent_hash, &transactions);
+        let signature = signer.sign_hash_sync(&hash).expect("signs hash");
+        BatchSubmission { parent_hash, transactions, signature }
+    }
+
+    #[test]
+    fn test_authenticate_builder_submission() {
+        let builder = PrivateKeySigner::random();
+        let ext = TelcoinNetworkBuilderExt::new(
+            2017,
+            [builder.address()],
+            Arc::new(CountingSubmitter::default()),
+        );
+
+        let valid = submission(&builder, 2017);
+        assert_eq!(ext.authenticate(&valid).unwrap(), builder.address());
+
+        // signed by an unknown builder
+        let unknown = submission(&PrivateKeySigner::random(), 2017);
+        assert!(matches!(ext.authenticate(&unknown), Err(TNRpcError::UnauthorizedBuilder(_))));
+
+        // signed for another chain
+        let other_chain = submission(&builder, 1);
+        assert!(ext.authenticate(&other_chain).is_err());
+
+        // transactions changed after signing
+        let mut tampered = valid;
+        tampered.transactions.push(Bytes::from_static(&[4]));
+        assert!(ext.authenticate(&tampered).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_submission_is_sealed_once() {
+        let builder = PrivateKeySigner::random();
+        let submitter = Arc::new(CountingSubmitter::default());
+        let ext = TelcoinNetworkBuilderExt::new(2017, [builder.address()], submitter.clone());
+        let valid = submission(&builder, 2017);
+
+        // a failed submission can be retried
+        *submitter.fail.lock() = true;
+        assert!(matches!(ext.submit_batch(valid.clone()).await, Err(TNRpcError::BatchSeal(_))));
+        *submitter.fail.lock() = false;
+        ext.submit_batch(valid.clone()).await.expect("sealed");
+        assert_eq!(*submitter.sealed.lock(), 1);
+
+        // the same submission is not sealed again
+        assert!(matches!(ext.submit_batch(valid).await, Err(TNRpcError::InvalidBatch(_))));
+        assert_eq!(*submitter.sealed.lock(), 1);
+
+        // the oldest submissions are forgotten
+        for i in 0..MAX_RECENT_SUBMISSIONS {
+            ext.claim_submission(B256::from(U256::from(i))).expect("new submission");
+        }
+        assert_eq!(ext.recent_submissions.lock().len(), MAX_RECENT_SUBMISSIONS);
+        assert!(ext.claim_submission(B256::from(U256::from(0))).is_ok());
+    }
+}
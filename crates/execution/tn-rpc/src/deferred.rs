@@ -0,0 +1,183 @@
+//! Transactions accepted while the worker's batches can't reach quorum.
+//!
+//! The methods that submit transactions keep adding them to the pool during a consensus outage
+//! and mark them as deferred until the cap is reached. The batch builder flushes them once quorum
+//! is restored.
+
+use crate::{error::TNRpcError, sync_status::SEND_TRANSACTION_METHODS};
+use jsonrpsee::{server::middleware::rpc::RpcServiceT, types::Request, MethodResponse};
+use serde::Deserialize;
+use serde_json::value::RawValue;
+use std::{future::Future, pin::Pin};
+use tn_types::{keccak256, Bytes, DeferredTransactions, TxHash};
+use tower::Layer;
+
+/// Return the hash of the transaction in `eth_sendRawTransaction` params.
+fn raw_transaction_hash(params: Option<&RawValue>) -> Option<TxHash> {
+    let (raw,) = serde_json::from_str::<(Bytes,)>(params?.get()).ok()?;
+    Some(keccak256(raw))
+}
+
+/// Return the transaction hash a successful send method responded with.
+fn response_transaction_hash(response: &MethodResponse) -> Option<TxHash> {
+    #[derive(Deserialize)]
+    struct SendResponse {
+        result: TxHash,
+    }
+    serde_json::from_str::<SendResponse>(response.as_result()).ok().map(|response| response.result)
+}
+
+/// Layer that wraps RPC services with [DeferTransactions].
+#[derive(Clone, Debug)]
+pub struct DeferTransactionsLayer {
+    /// The worker's deferred transactions, requests are passed through unchanged without them.
+    deferred: Option<DeferredTransactions>,
+}
+
+impl DeferTransactionsLayer {
+    /// Create a new instance of [Self].
+    pub fn new(deferred: Option<DeferredTransactions>) -> Self {
+        Self { deferred }
+    }
+}
+
+impl<S> Layer<S> for DeferTransactionsLayer {
+    type Service = DeferTransactions<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        DeferTransactions { service, deferred: self.deferred.clone() }
+    }
+}
+
+/// RPC middleware that defers transactions while the worker's batches can't reach quorum.
+#[derive(Clone, Debug)]
+pub struct DeferTransactions<S> {
+    /// The next service in the middleware stack.
+    service: S,
+    /// The worker's deferred transactions, requests are passed through unchanged without them.
+    deferred: Option<DeferredTransactions>,
+}
+
+impl<'a, S> RpcServiceT<'a> for DeferTransactions<S>
+where
+    S: RpcServiceT<'a> + Clone + Send + Sync + 'static,
+{
+    type Future = Pin<Box<dyn Future<Output = MethodResponse> + Send + 'a>>;
+
+    fn call(&self, request: Request<'a>) -> Self::Future {
+        let service = self.service.clone();
+        let deferred = self.deferred.clone().filter(|deferred| {
+            SEND_TRANSACTION_METHODS.contains(&request.method_name()) && deferred.is_quorum_lost()
+        });
+
+        Box::pin(async move {
+            let Some(deferred) = deferred else {
+                return service.call(request).await;
+            };
+            // the hash of a raw transaction is known before it is added to the pool
+            let tx_hash = if request.method_name() == "eth_sendRawTransaction" {
+                match raw_transaction_hash(request.params.as_deref()) {
+                    Some(tx_hash) => Some(tx_hash),
+                    // the method rejects malformed transactions
+                    None => return service.call(request).await,
+                }
+            } else {
+                None
+            };
+            // the slot is held until the pool answers so concurrent requests can't pass the cap
+            let slot = match deferred.reserve(tx_hash.as_ref()) {
+                Ok(slot) => slot,
+                Err(capacity) => {
+                    return MethodResponse::error(
+                        request.id,
+                        TNRpcError::DeferredTransactionsFull(capacity),
+                    )
+                }
+            };
+            let response = service.call(request).await;
+            if let Some(slot) = slot.filter(|_| response.is_success()) {
+                if let Some(tx_hash) = tx_hash.or_else(|| response_transaction_hash(&response)) {
+                    slot.commit(tx_hash);
+                }
+            }
+            response
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpsee::types::{Id, ResponsePayload};
+
+    /// Adds every transaction to the pool if `accept` and responds with its hash.
+    #[derive(Clone)]
+    struct Pool {
+        accept: bool,
+        tx_hash: TxHash,
+    }
+
+    impl<'a> RpcServiceT<'a> for Pool {
+        type Future = std::future::Ready<MethodResponse>;
+
+        fn call(&self, request: Request<'a>) -> Self::Future {
+            let response = if self.accept {
+                let payload = ResponsePayload::success(self.tx_hash);
+                MethodResponse::response(request.id, payload, usize::MAX)
+            } else {
+                MethodResponse::error(request.id, TNRpcError::DeferredTransactionsDisabled)
+            };
+            std::future::ready(response)
+        }
+    }
+
+    /// Send a transaction with `method` through the middleware.
+    async fn send(deferred: &DeferredTransactions, pool: Pool, method: &str) -> MethodResponse {
+        let service = DeferTransactionsLayer::new(Some(deferred.clone())).layer(pool);
+        let params = RawValue::from_string(r#"["0x02f870"]"#.to_string()).expect("valid json");
+        service.call(Request::new(method.into(), Some(&params), Id::Number(1))).await
+    }
+
+    #[tokio::test]
+    async fn test_defer_transactions() {
+        let raw_hash = keccak256([0x02, 0xf8, 0x70]);
+        let accepted = Pool { accept: true, tx_hash: raw_hash };
+        let deferred = DeferredTransactions::new(2);
+
+        // nothing is deferred while batches reach quorum
+        assert!(send(&deferred, accepted.clone(), "eth_sendRawTransaction").await.is_success());
+        assert_eq!(deferred.status().deferred, 0);
+
+        // transactions the pool rejects release their slot
+        deferred.quorum_lost();
+        let rejected = Pool { accept: false, tx_hash: raw_hash };
+        assert!(send(&deferred, rejected, "eth_sendRawTransaction").await.is_error());
+        assert_eq!(deferred.status().deferred, 0);
+
+        // raw transactions are deferred by the hash of their params
+        assert!(send(&deferred, accepted, "eth_sendRawTransaction").await.is_success());
+        assert!(deferred.any_deferred([raw_hash].iter()));
+
+        // eth_sendTransaction is deferred by the hash it responds with
+        let tx_hash = keccak256([1]);
+        let signed = Pool { accept: true, tx_hash };
+        assert!(send(&deferred, signed, "eth_sendTransaction").await.is_success());
+        assert!(deferred.any_deferred([tx_hash].iter()));
+
+        // new transactions are rejected once the cap is reached
+        let full = Pool { accept: true, tx_hash: keccak256([2]) };
+        let response = send(&deferred, full, "eth_sendTransaction").await;
+        assert!(response.is_error());
+        assert_eq!(deferred.status().deferred, 2);
+    }
+
+    #[test]
+    fn test_raw_transaction_hash() {
+        let params = RawValue::from_string(r#"["0x02f870"]"#.to_string()).expect("valid json");
+        assert_eq!(raw_transaction_hash(Some(&params)), Some(keccak256([0x02, 0xf8, 0x70])));
+
+        let params = RawValue::from_string(r#"["not hex"]"#.to_string()).expect("valid json");
+        assert_eq!(raw_transaction_hash(Some(&params)), None);
+        assert_eq!(raw_transaction_hash(None), None);
+    }
+}
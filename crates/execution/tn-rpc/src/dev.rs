@@ -0,0 +1,263 @@
+//! RPC extension for mining blocks and changing state on demand in a single-node dev network.
+//!
+//! Contract test suites like hardhat and foundry expect a transaction to be mined as soon as it is
+//! sent. The `tn_dev` namespace lets them mine a block instead of waiting for the worker's batch
+//! interval and the primary's header delays.
+//!
+//! The namespace also mirrors anvil's state manipulation methods so the node can replace a local
+//! chain in dapp development. State only changes by executing blocks, so each change mines a block
+//! before the method returns. Like anvil, `eth_sendTransaction` from an impersonated account is
+//! sent without a signature, see [ImpersonatedTransactions].
+
+use crate::error::TelcoinNetworkRpcResult;
+use async_trait::async_trait;
+use jsonrpsee::{
+    proc_macros::rpc,
+    server::middleware::rpc::RpcServiceT,
+    types::{Request, ResponsePayload},
+    MethodResponse,
+};
+use serde_json::value::RawValue;
+use std::{future::Future, pin::Pin, sync::Arc};
+use tn_types::{Address, BlockNumHash, Bytes, TransactionRequest, TxHash, U256};
+use tower::Layer;
+
+/// Mines a block on demand.
+///
+/// The node implements this trait with the worker's batch builder and the primary's proposer.
+#[async_trait]
+pub trait DevBlockMiner: Send + Sync + 'static {
+    /// Seal the pending transactions in a batch and wait for consensus to commit it and the
+    /// engine to execute it.
+    ///
+    /// Seals an empty batch if no transactions are pending. Returns the executed block.
+    async fn mine_block(&self) -> TelcoinNetworkRpcResult<BlockNumHash>;
+}
+
+/// Changes state on demand.
+///
+/// The node implements this trait by queueing changes for the engine and mining a block.
+#[async_trait]
+pub trait DevStateEditor: Send + Sync + 'static {
+    /// Set the balance of an account.
+    async fn set_balance(&self, address: Address, balance: U256) -> TelcoinNetworkRpcResult<()>;
+
+    /// Set the code of an account. Empty code removes the account's code.
+    async fn set_code(&self, address: Address, code: Bytes) -> TelcoinNetworkRpcResult<()>;
+
+    /// Accept transactions from an account without a signature.
+    fn impersonate_account(&self, address: Address);
+
+    /// Require signatures for transactions from an account again.
+    fn stop_impersonating_account(&self, address: Address);
+
+    /// Return true if transactions from the account are accepted without a signature.
+    fn is_impersonated(&self, address: &Address) -> bool;
+
+    /// Submit a transaction from an impersonated account to the transaction pool.
+    ///
+    /// Missing fields are filled in like `eth_sendTransaction`.
+    async fn send_impersonated_transaction(
+        &self,
+        request: TransactionRequest,
+    ) -> TelcoinNetworkRpcResult<TxHash>;
+
+    /// Record the current state and return an id to revert to it.
+    async fn snapshot(&self) -> TelcoinNetworkRpcResult<U256>;
+
+    /// Restore the state recorded by a snapshot.
+    ///
+    /// Pending transactions are dropped and the accounts impersonated at the snapshot are
+    /// restored. The snapshot and every later snapshot are removed. Returns false if the snapshot
+    /// does not exist.
+    async fn revert(&self, id: U256) -> TelcoinNetworkRpcResult<bool>;
+}
+
+/// Telcoin Network dev RPC namespace.
+///
+/// Only served by single-node dev networks.
+#[rpc(server, client, namespace = "tn_dev")]
+pub trait TelcoinNetworkDevApi {
+    /// Seal the pending transactions and return the block once it is executed.
+    #[method(name = "mineBlock")]
+    async fn mine_block(&self) -> TelcoinNetworkRpcResult<BlockNumHash>;
+
+    /// Set the balance of an account.
+    #[method(name = "setBalance")]
+    async fn set_balance(&self, address: Address, balance: U256) -> TelcoinNetworkRpcResult<()>;
+
+    /// Set the code of an account.
+    #[method(name = "setCode")]
+    async fn set_code(&self, address: Address, code: Bytes) -> TelcoinNetworkRpcResult<()>;
+
+    /// Accept transactions from an account through `tn_dev_sendTransaction` and
+    /// `eth_sendTransaction` without a signature.
+    #[method(name = "impersonateAccount")]
+    async fn impersonate_account(&self, address: Address) -> TelcoinNetworkRpcResult<()>;
+
+    /// Stop impersonating an account.
+    #[method(name = "stopImpersonatingAccount")]
+    async fn stop_impersonating_account(&self, address: Address) -> TelcoinNetworkRpcResult<()>;
+
+    /// Send a transaction from an impersonated account.
+    #[method(name = "sendTransaction")]
+    async fn send_transaction(
+        &self,
+        request: TransactionRequest,
+    ) -> TelcoinNetworkRpcResult<TxHash>;
+
+    /// Record the current state and return an id to revert to it.
+    #[method(name = "snapshot")]
+    async fn snapshot(&self) -> TelcoinNetworkRpcResult<U256>;
+
+    /// Restore the state recorded by a snapshot.
+    ///
+    /// Blocks are final once executed, so the chain keeps growing and only the state of the
+    /// accounts changed since the snapshot is restored.
+    #[method(name = "revert")]
+    async fn revert(&self, id: U256) -> TelcoinNetworkRpcResult<bool>;
+}
+
+/// The type that implements `tn_dev` namespace trait.
+pub struct TelcoinNetworkDevExt {
+    /// Mines blocks for the node.
+    miner: Arc<dyn DevBlockMiner>,
+    /// Changes state for the node.
+    editor: Arc<dyn DevStateEditor>,
+}
+
+impl TelcoinNetworkDevExt {
+    /// Create new instance of the Telcoin Network dev RPC extension.
+    pub fn new(miner: Arc<dyn DevBlockMiner>, editor: Arc<dyn DevStateEditor>) -> Self {
+        Self { miner, editor }
+    }
+}
+
+#[async_trait]
+impl TelcoinNetworkDevApiServer for TelcoinNetworkDevExt {
+    async fn mine_block(&self) -> TelcoinNetworkRpcResult<BlockNumHash> {
+        self.miner.mine_block().await
+    }
+
+    async fn set_balance(&self, address: Address, balance: U256) -> TelcoinNetworkRpcResult<()> {
+        self.editor.set_balance(address, balance).await
+    }
+
+    async fn set_code(&self, address: Address, code: Bytes) -> TelcoinNetworkRpcResult<()> {
+        self.editor.set_code(address, code).await
+    }
+
+    async fn impersonate_account(&self, address: Address) -> TelcoinNetworkRpcResult<()> {
+        self.editor.impersonate_account(address);
+        Ok(())
+    }
+
+    async fn stop_impersonating_account(&self, address: Address) -> TelcoinNetworkRpcResult<()> {
+        self.editor.stop_impersonating_account(address);
+        Ok(())
+    }
+
+    async fn send_transaction(
+        &self,
+        request: TransactionRequest,
+    ) -> TelcoinNetworkRpcResult<TxHash> {
+        self.editor.send_impersonated_transaction(request).await
+    }
+
+    async fn snapshot(&self) -> TelcoinNetworkRpcResult<U256> {
+        self.editor.snapshot().await
+    }
+
+    async fn revert(&self, id: U256) -> TelcoinNetworkRpcResult<bool> {
+        self.editor.revert(id).await
+    }
+}
+
+/// Return the transaction in `eth_sendTransaction` params.
+fn transaction_request(params: Option<&RawValue>) -> Option<TransactionRequest> {
+    let (request,) = serde_json::from_str::<(TransactionRequest,)>(params?.get()).ok()?;
+    Some(request)
+}
+
+/// Layer that wraps RPC services with [ImpersonatedTransactions].
+#[derive(Clone)]
+pub struct ImpersonatedTransactionsLayer {
+    /// Sends impersonated transactions, requests are passed through unchanged without it.
+    editor: Option<Arc<dyn DevStateEditor>>,
+}
+
+impl ImpersonatedTransactionsLayer {
+    /// Create a new instance of [Self].
+    pub fn new(editor: Option<Arc<dyn DevStateEditor>>) -> Self {
+        Self { editor }
+    }
+}
+
+impl<S> Layer<S> for ImpersonatedTransactionsLayer {
+    type Service = ImpersonatedTransactions<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        ImpersonatedTransactions { service, editor: self.editor.clone() }
+    }
+}
+
+/// RPC middleware that sends `eth_sendTransaction` from impersonated accounts without a signature.
+///
+/// Transactions from other accounts reach the `eth` namespace unchanged.
+#[derive(Clone)]
+pub struct ImpersonatedTransactions<S> {
+    /// The next service in the middleware stack.
+    service: S,
+    /// Sends impersonated transactions, requests are passed through unchanged without it.
+    editor: Option<Arc<dyn DevStateEditor>>,
+}
+
+impl<'a, S> RpcServiceT<'a> for ImpersonatedTransactions<S>
+where
+    S: RpcServiceT<'a> + Clone + Send + Sync + 'static,
+{
+    type Future = Pin<Box<dyn Future<Output = MethodResponse> + Send + 'a>>;
+
+    fn call(&self, request: Request<'a>) -> Self::Future {
+        let service = self.service.clone();
+        let editor = self.editor.clone().filter(|_| request.method_name() == "eth_sendTransaction");
+
+        Box::pin(async move {
+            let Some(editor) = editor else {
+                return service.call(request).await;
+            };
+            let Some(tx) = transaction_request(request.params.as_deref())
+                .filter(|tx| tx.from.is_some_and(|from| editor.is_impersonated(&from)))
+            else {
+                return service.call(request).await;
+            };
+            match editor.send_impersonated_transaction(tx).await {
+                Ok(tx_hash) => MethodResponse::response(
+                    request.id,
+                    ResponsePayload::success(tx_hash),
+                    usize::MAX,
+                ),
+                Err(e) => MethodResponse::error(request.id, e),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::transaction_request;
+    use serde_json::value::RawValue;
+    use tn_types::Address;
+
+    #[test]
+    fn test_transaction_request() {
+        let from = Address::with_last_byte(1);
+        let params = RawValue::from_string(format!(r#"[{{"from":"{from}","value":"0x1"}}]"#))
+            .expect("valid json");
+        assert_eq!(transaction_request(Some(&params)).and_then(|tx| tx.from), Some(from));
+
+        let params = RawValue::from_string(r#"["0x02f870"]"#.to_string()).expect("valid json");
+        assert_eq!(transaction_request(Some(&params)), None);
+        assert_eq!(transaction_request(None), None);
+    }
+}
@@ -14,14 +14,117 @@ pub enum TNRpcError {
     /// Handshake client provided an invalid signature for network key.
     #[error("Invalid proof of possession for provided network key or genesis.")]
     InvalidProofOfPossession,
+    /// The node failed to collect its status for the admin namespace.
+    #[error("Failed to collect node status: {0}")]
+    NodeStatus(String),
+    /// The requested halt target has already been reached.
+    #[error("Can not halt at sub-dag {target}: the node has already reached sub-dag {reached}")]
+    InvalidHaltTarget {
+        /// The requested sub-dag.
+        target: u64,
+        /// The last sub-dag the node has reached.
+        reached: u64,
+    },
+    /// The log filter directives are invalid.
+    #[error("Invalid log filter: {0}")]
+    InvalidLogFilter(String),
+    /// The node's log filter can not be changed at runtime.
+    #[error("Log filter can not be changed: {0}")]
+    LogFilterUnavailable(String),
+    /// The requested range of sub-dags is empty or too large.
+    #[error(
+        "Invalid sub-dag range {start}..={end}: at most {} sub-dags can be requested",
+        crate::MAX_SUB_DAG_STATS_RANGE
+    )]
+    InvalidSubDagRange {
+        /// The first sub-dag requested.
+        start: u64,
+        /// The last sub-dag requested.
+        end: u64,
+    },
+    /// The node failed to read sub-dag statistics.
+    #[error("Failed to read sub-dag statistics: {0}")]
+    SubDagStats(String),
+    /// The batch submission is not signed by an authorized builder.
+    #[error("Unauthorized builder: {0}")]
+    UnauthorizedBuilder(String),
+    /// The submitted batch candidate is invalid.
+    #[error("Invalid batch: {0}")]
+    InvalidBatch(String),
+    /// The worker failed to seal the submitted batch.
+    #[error("Failed to seal batch: {0}")]
+    BatchSeal(String),
+    /// The node does not sign inclusion promises.
+    #[error("Inclusion promises are not enabled for this node")]
+    InclusionPromisesDisabled,
+    /// The requested sub-dag has not been executed.
+    #[error("Sub-dag {0} has not been executed")]
+    SubDagNotExecuted(u64),
+    /// The node failed to find the execution block for a sub-dag.
+    #[error("Failed to find execution block for sub-dag: {0}")]
+    SubDagBlock(String),
+    /// The node failed to build a transaction inclusion proof.
+    #[error("Failed to build inclusion proof: {0}")]
+    InclusionProof(String),
+    /// The node failed to compute a state diff.
+    #[error("Failed to compute state diff: {0}")]
+    StateDiff(String),
+    /// The node failed to find the consensus provenance of a block.
+    #[error("Failed to find block provenance: {0}")]
+    BlockProvenance(String),
+    /// The node failed to read transactions by address.
+    #[error("Failed to read transactions by address: {0}")]
+    AddressIndex(String),
+    /// The node failed to mine a block on demand.
+    #[error("Failed to mine block: {0}")]
+    DevMining(String),
+    /// The node failed to change state on demand.
+    #[error("Failed to change state: {0}")]
+    DevState(String),
+    /// The account is not impersonated.
+    #[error("Account {0} is not impersonated")]
+    NotImpersonated(tn_types::Address),
+    /// The node can not currently get transactions included.
+    #[error("Transactions are not accepted in {0} mode")]
+    TransactionsNotAccepted(String),
+    /// The node failed to collect its sync status.
+    #[error("Failed to collect sync status: {0}")]
+    SyncStatus(String),
+    /// The worker's batches are not reaching quorum and no more transactions can be deferred.
+    #[error("Batches are not reaching quorum and {0} transactions are already deferred")]
+    DeferredTransactionsFull(usize),
+    /// The node does not track deferred transactions.
+    #[error("Deferred transactions are not tracked by this node")]
+    DeferredTransactionsDisabled,
 }
 
 impl From<TNRpcError> for jsonrpsee_types::ErrorObject<'static> {
     fn from(error: TNRpcError) -> Self {
-        // TODO: update this when adding errors
         match error {
             TNRpcError::InvalidProofOfPossession => rpc_error(401, error.to_string(), None),
-            // _ => rpc_error(500, error.to_string(), None),
+            TNRpcError::NodeStatus(_) => rpc_error(500, error.to_string(), None),
+            TNRpcError::InvalidHaltTarget { .. } => rpc_error(400, error.to_string(), None),
+            TNRpcError::InvalidLogFilter(_) => rpc_error(400, error.to_string(), None),
+            TNRpcError::LogFilterUnavailable(_) => rpc_error(500, error.to_string(), None),
+            TNRpcError::InvalidSubDagRange { .. } => rpc_error(400, error.to_string(), None),
+            TNRpcError::SubDagStats(_) => rpc_error(500, error.to_string(), None),
+            TNRpcError::UnauthorizedBuilder(_) => rpc_error(401, error.to_string(), None),
+            TNRpcError::InvalidBatch(_) => rpc_error(400, error.to_string(), None),
+            TNRpcError::BatchSeal(_) => rpc_error(500, error.to_string(), None),
+            TNRpcError::InclusionPromisesDisabled => rpc_error(400, error.to_string(), None),
+            TNRpcError::SubDagNotExecuted(_) => rpc_error(400, error.to_string(), None),
+            TNRpcError::SubDagBlock(_) => rpc_error(500, error.to_string(), None),
+            TNRpcError::InclusionProof(_) => rpc_error(500, error.to_string(), None),
+            TNRpcError::StateDiff(_) => rpc_error(500, error.to_string(), None),
+            TNRpcError::BlockProvenance(_) => rpc_error(500, error.to_string(), None),
+            TNRpcError::AddressIndex(_) => rpc_error(500, error.to_string(), None),
+            TNRpcError::DevMining(_) => rpc_error(500, error.to_string(), None),
+            TNRpcError::DevState(_) => rpc_error(500, error.to_string(), None),
+            TNRpcError::NotImpersonated(_) => rpc_error(400, error.to_string(), None),
+            TNRpcError::TransactionsNotAccepted(_) => rpc_error(503, error.to_string(), None),
+            TNRpcError::SyncStatus(_) => rpc_error(500, error.to_string(), None),
+            TNRpcError::DeferredTransactionsFull(_) => rpc_error(503, error.to_string(), None),
+            TNRpcError::DeferredTransactionsDisabled => rpc_error(400, error.to_string(), None),
         }
     }
 }
@@ -1,9 +1,43 @@
 // SPDX-License-Identifier: MIT or Apache-2.0
 //! RPC request handle for state sync requests from peers.
 
+mod admin;
+mod builder;
+mod deferred;
+mod dev;
 mod error;
 mod handshake;
 mod rpc_ext;
+mod sub_dag_tag;
+mod sync_status;
 
+pub use admin::{
+    ChannelBacklog, LogFilterHandle, MetricBucket, MetricFamilySnapshot, MetricKind,
+    MetricQuantile, MetricSample, NodeStatus, NodeStatusProvider, PeerConnectivity,
+    PeerHistoryEvent, TelcoinNetworkAdminApiClient, TelcoinNetworkAdminApiServer,
+    TelcoinNetworkAdminExt, ValidatorConnectivity, VoteAggregationStatus, WorkerRpcEndpoint,
+    DEFAULT_CHANNEL_BACKLOG_THRESHOLD,
+};
+pub use builder::{
+    BatchSubmission, ExternalBatchSubmitter, TelcoinNetworkBuilderApiClient,
+    TelcoinNetworkBuilderApiServer, TelcoinNetworkBuilderExt,
+};
+pub use deferred::{DeferTransactions, DeferTransactionsLayer};
+pub use dev::{
+    DevBlockMiner, DevStateEditor, ImpersonatedTransactions, ImpersonatedTransactionsLayer,
+    TelcoinNetworkDevApiClient, TelcoinNetworkDevApiServer, TelcoinNetworkDevExt,
+};
+pub use error::{rpc_error, TNRpcError, TelcoinNetworkRpcResult};
 pub use handshake::{Handshake, HandshakeBuilder};
-pub use rpc_ext::{TelcoinNetworkRpcExt, TelcoinNetworkRpcExtApiServer};
+pub use rpc_ext::{
+    AccountChange, AccountState, AddressTransaction, AddressTransactionCursor,
+    AddressTransactionsPage, BlockProvenance, BlockProvenanceProvider, InclusionProofProvider,
+    StateDiff, StateDiffProvider, StorageChange, SubDagStatsEntry, SubDagStatsProvider,
+    TelcoinNetworkRpcExt, TelcoinNetworkRpcExtApiServer, TransactionInclusionProof,
+    TransactionsByAddressProvider, MAX_SUB_DAG_STATS_RANGE, MAX_TRANSACTIONS_BY_ADDRESS,
+};
+pub use sub_dag_tag::{
+    parse_sub_dag_tag, SubDagBlockResolver, SubDagBlockTags, SubDagBlockTagsLayer,
+    SUB_DAG_TAG_PREFIX,
+};
+pub use sync_status::{NodeModeAware, NodeModeAwareLayer, NodeSyncStatus, SyncStatusProvider};
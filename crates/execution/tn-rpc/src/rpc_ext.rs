@@ -2,12 +2,277 @@
 
 use crate::{
     error::{TNRpcError, TelcoinNetworkRpcResult},
-    Handshake,
+    Handshake, NodeSyncStatus, SubDagBlockResolver, SyncStatusProvider,
 };
 use async_trait::async_trait;
 use jsonrpsee::proc_macros::rpc;
 use reth_chainspec::ChainSpec;
-use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use std::{collections::BTreeMap, sync::Arc};
+use tn_types::{
+    Address, AuthorityIdentifier, BlockHash, BlockNumHash, Bytes, CertificateDigest,
+    DeferredStatus, DeferredTransactions, InclusionPromise, InclusionPromises, IndexedTransaction,
+    PayloadProof, Round, SubDagStats, TxHash, B256, U256,
+};
+
+/// The largest number of sub-dags that can be requested from `tn_getSubDagStats` at once.
+pub const MAX_SUB_DAG_STATS_RANGE: u64 = 1_000;
+
+/// The largest number of transactions returned by `tn_getTransactionsByAddress` at once.
+pub const MAX_TRANSACTIONS_BY_ADDRESS: u64 = 1_000;
+
+/// Execution statistics for a committed sub-dag.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SubDagStatsEntry {
+    /// The number of the consensus header for the sub-dag.
+    pub number: u64,
+    /// The total gas used by the blocks executed for the sub-dag.
+    pub gas_used: u64,
+    /// The number of transactions executed for the sub-dag.
+    pub transaction_count: u64,
+    /// The number of batches in the sub-dag.
+    pub batch_count: u64,
+}
+
+impl From<(u64, SubDagStats)> for SubDagStatsEntry {
+    fn from((number, stats): (u64, SubDagStats)) -> Self {
+        Self {
+            number,
+            gas_used: stats.gas_used,
+            transaction_count: stats.transactions,
+            batch_count: stats.batches,
+        }
+    }
+}
+
+/// Source of execution statistics for committed sub-dags.
+///
+/// The node implements this trait with its consensus storage.
+pub trait SubDagStatsProvider: Send + Sync + 'static {
+    /// Return the statistics recorded for sub-dags `start..=end`.
+    fn sub_dag_stats(
+        &self,
+        start: u64,
+        end: u64,
+    ) -> TelcoinNetworkRpcResult<Vec<(u64, SubDagStats)>>;
+}
+
+/// Proof that an executed transaction was committed by consensus.
+///
+/// To verify, decode `batch` and check it contains the transaction and hashes to `batchDigest`,
+/// check `payloadProof` for `batchDigest` against the `payload_root` of the certificate's header,
+/// check the certificate's aggregate signature against the committee, and check the certificate
+/// is in the sub-dag of `consensusHeader`. Consensus headers link to their parent by hash so the
+/// header can be chained to a trusted consensus header.
+///
+/// Header digests only commit to the payload root from the epoch of the payload root fork. For
+/// earlier headers the digest commits to the full payload, so check `batchDigest` is in the
+/// payload of the certificate's header instead of checking `payloadProof`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionInclusionProof {
+    /// The hash of the transaction.
+    pub tx_hash: TxHash,
+    /// The hash of the execution block with the transaction.
+    pub block_hash: BlockHash,
+    /// The number of the execution block with the transaction.
+    pub block_number: u64,
+    /// The digest of the batch with the transaction.
+    pub batch_digest: BlockHash,
+    /// The encoded batch, `null` if this node pruned it.
+    pub batch: Option<Bytes>,
+    /// The Merkle path from the batch digest to the header's payload root.
+    pub payload_proof: PayloadProof,
+    /// The encoded certificate for the header that included the batch.
+    pub certificate: Bytes,
+    /// The number of the consensus header that committed the certificate.
+    pub consensus_number: u64,
+    /// The hash of the consensus header.
+    pub consensus_hash: BlockHash,
+    /// The encoded consensus header.
+    pub consensus_header: Bytes,
+}
+
+/// Source of transaction inclusion proofs.
+///
+/// The node implements this trait with its consensus and execution storage.
+pub trait InclusionProofProvider: Send + Sync + 'static {
+    /// Return the proof that an executed transaction was committed by consensus.
+    ///
+    /// Returns `None` if the transaction has not been executed by this node.
+    fn transaction_inclusion_proof(
+        &self,
+        tx_hash: TxHash,
+    ) -> TelcoinNetworkRpcResult<Option<TransactionInclusionProof>>;
+}
+
+/// The consensus data that produced an execution block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockProvenance {
+    /// The hash of the execution block.
+    pub block_hash: BlockHash,
+    /// The number of the execution block.
+    pub block_number: u64,
+    /// The index of the committed sub-dag, which is the number of its consensus header.
+    pub sub_dag_index: u64,
+    /// The hash of the consensus header.
+    pub consensus_hash: BlockHash,
+    /// The authority that led the sub-dag.
+    pub leader: AuthorityIdentifier,
+    /// The round of the sub-dag's leader.
+    pub leader_round: Round,
+    /// The digests of the certificates in the sub-dag, in commit order.
+    pub certificate_digests: Vec<CertificateDigest>,
+    /// The digests of the worker batches in the sub-dag, in execution order.
+    pub batch_digests: Vec<BlockHash>,
+    /// The digest of the batch executed by this block, `null` for the empty block executed for a
+    /// sub-dag without batches.
+    pub batch_digest: Option<BlockHash>,
+}
+
+/// Source of the consensus provenance of execution blocks.
+///
+/// The node implements this trait with its consensus and execution storage.
+pub trait BlockProvenanceProvider: Send + Sync + 'static {
+    /// Return the consensus data that produced the execution block.
+    ///
+    /// Returns `None` if the block has not been executed by this node or is genesis.
+    fn block_provenance(
+        &self,
+        block_hash: BlockHash,
+    ) -> TelcoinNetworkRpcResult<Option<BlockProvenance>>;
+}
+
+/// The state of an account at a block.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountState {
+    /// The account balance.
+    pub balance: U256,
+    /// The account nonce.
+    pub nonce: u64,
+    /// The hash of the account's code.
+    pub code_hash: B256,
+}
+
+/// A storage slot changed by execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageChange {
+    /// The value before the blocks.
+    pub from: U256,
+    /// The value after the blocks.
+    pub to: U256,
+}
+
+/// An account changed by execution.
+///
+/// `from` is `null` for accounts created by the blocks and `to` is `null` for accounts that were
+/// destroyed.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountChange {
+    /// The account before the blocks.
+    pub from: Option<AccountState>,
+    /// The account after the blocks.
+    pub to: Option<AccountState>,
+    /// The storage slots whose value changed.
+    pub storage: BTreeMap<B256, StorageChange>,
+}
+
+/// The accounts and storage changed by executing a range of blocks.
+///
+/// Only net changes are included: a value changed and restored by the blocks is omitted.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StateDiff {
+    /// The first executed block.
+    pub from_block: u64,
+    /// The last executed block.
+    pub to_block: u64,
+    /// The changed accounts.
+    pub accounts: BTreeMap<Address, AccountChange>,
+}
+
+/// Source of the state changed by executed blocks.
+///
+/// The node implements this trait with its execution storage.
+pub trait StateDiffProvider: Send + Sync + 'static {
+    /// Return the state changed by executing `block`.
+    ///
+    /// Returns `None` if the block has not been executed by this node.
+    fn block_state_diff(&self, block: u64) -> TelcoinNetworkRpcResult<Option<StateDiff>>;
+
+    /// Return the state changed by executing the consensus output of `sub_dag`.
+    ///
+    /// Returns `None` if the sub-dag has not been executed by this node.
+    fn sub_dag_state_diff(&self, sub_dag: u64) -> TelcoinNetworkRpcResult<Option<StateDiff>>;
+}
+
+/// An executed transaction sent by or to an address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressTransaction {
+    /// The hash of the transaction.
+    pub hash: TxHash,
+    /// The number of the execution block with the transaction.
+    pub block_number: u64,
+    /// The index of the transaction in the block.
+    pub transaction_index: u32,
+    /// The sender.
+    pub from: Address,
+    /// The recipient, `null` for contract creation.
+    pub to: Option<Address>,
+}
+
+impl From<IndexedTransaction> for AddressTransaction {
+    fn from(tx: IndexedTransaction) -> Self {
+        Self {
+            hash: tx.hash,
+            block_number: tx.block_number,
+            transaction_index: tx.index,
+            from: tx.from,
+            to: tx.to,
+        }
+    }
+}
+
+/// The position of a transaction in execution order, used to page through
+/// `tn_getTransactionsByAddress`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressTransactionCursor {
+    /// The number of the execution block.
+    pub block_number: u64,
+    /// The index of the transaction in the block.
+    pub transaction_index: u32,
+}
+
+/// A page of transactions sent by or to an address.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AddressTransactionsPage {
+    /// The transactions in execution order.
+    pub transactions: Vec<AddressTransaction>,
+    /// The cursor of the next page, `null` if this is the last page.
+    pub next: Option<AddressTransactionCursor>,
+}
+
+/// Source of executed transactions indexed by address.
+///
+/// The node implements this trait with its consensus storage when it indexes addresses.
+pub trait TransactionsByAddressProvider: Send + Sync + 'static {
+    /// Return up to `limit` transactions sent by or to `address` in execution order, starting
+    /// with the transaction at `from`.
+    fn transactions_by_address(
+        &self,
+        address: Address,
+        from: AddressTransactionCursor,
+        limit: usize,
+    ) -> TelcoinNetworkRpcResult<Vec<IndexedTransaction>>;
+}
 
 /// Telcoin Network RPC namespace.
 ///
@@ -17,6 +282,94 @@ pub trait TelcoinNetworkRpcExtApi {
     /// Transfer TEL to an address
     #[method(name = "validatorHandshake")]
     async fn handshake(&self, handshake: Handshake) -> TelcoinNetworkRpcResult<()>;
+
+    /// Return the gas used, transaction count, and batch count for each executed sub-dag in the
+    /// inclusive range `start..=end`.
+    ///
+    /// Sub-dags this node has not executed are omitted. At most [MAX_SUB_DAG_STATS_RANGE]
+    /// sub-dags can be requested at once.
+    #[method(name = "getSubDagStats")]
+    async fn sub_dag_stats(
+        &self,
+        start: u64,
+        end: u64,
+    ) -> TelcoinNetworkRpcResult<Vec<SubDagStatsEntry>>;
+
+    /// Return the worker's signed promise that the transaction is in one of its sealed batches.
+    ///
+    /// Returns `null` if the transaction is not in a batch sealed by this node's worker or the
+    /// promise is no longer remembered.
+    #[method(name = "getInclusionPromise")]
+    async fn inclusion_promise(
+        &self,
+        tx_hash: TxHash,
+    ) -> TelcoinNetworkRpcResult<Option<InclusionPromise>>;
+
+    /// Return the last execution block for the consensus output of a sub-dag.
+    ///
+    /// The state after this block is the state at the sub-dag's consensus boundary. `eth` methods
+    /// also accept the block tag `tn:subdag:<n>` for it.
+    #[method(name = "getSubDagBlock")]
+    async fn sub_dag_block(&self, sub_dag: u64) -> TelcoinNetworkRpcResult<Option<BlockNumHash>>;
+
+    /// Return the proof that an executed transaction was included in a certified header and
+    /// committed by consensus.
+    ///
+    /// Returns `null` if the transaction has not been executed by this node.
+    #[method(name = "getTransactionInclusionProof")]
+    async fn transaction_inclusion_proof(
+        &self,
+        tx_hash: TxHash,
+    ) -> TelcoinNetworkRpcResult<Option<TransactionInclusionProof>>;
+
+    /// Return the accounts and storage changed by executing the block.
+    ///
+    /// Returns `null` if the block has not been executed by this node. Genesis has no diff.
+    #[method(name = "getStateDiff")]
+    async fn state_diff(&self, block: u64) -> TelcoinNetworkRpcResult<Option<StateDiff>>;
+
+    /// Return the accounts and storage changed by executing every block for the consensus output
+    /// of a sub-dag.
+    ///
+    /// Returns `null` if the sub-dag has not been executed by this node.
+    #[method(name = "getSubDagStateDiff")]
+    async fn sub_dag_state_diff(&self, sub_dag: u64) -> TelcoinNetworkRpcResult<Option<StateDiff>>;
+
+    /// Return the sub-dag, leader, certificates, and worker batches that produced an execution
+    /// block.
+    ///
+    /// Returns `null` if the block has not been executed by this node or is genesis.
+    #[method(name = "getBlockProvenance")]
+    async fn block_provenance(
+        &self,
+        block_hash: BlockHash,
+    ) -> TelcoinNetworkRpcResult<Option<BlockProvenance>>;
+
+    /// Return the executed transactions sent by or to an address, in execution order.
+    ///
+    /// Transactions are returned starting with the one at `cursor`, at most `limit` and never more
+    /// than [MAX_TRANSACTIONS_BY_ADDRESS]. Pass the page's `next` cursor to read the next page.
+    /// Only available if the node indexes addresses.
+    #[method(name = "getTransactionsByAddress")]
+    async fn transactions_by_address(
+        &self,
+        address: Address,
+        cursor: Option<AddressTransactionCursor>,
+        limit: Option<u64>,
+    ) -> TelcoinNetworkRpcResult<AddressTransactionsPage>;
+
+    /// Return the node's mode and how far its execution is behind consensus.
+    ///
+    /// `eth_syncing` reports the same progress for nodes that follow consensus.
+    #[method(name = "syncStatus")]
+    async fn sync_status(&self) -> TelcoinNetworkRpcResult<NodeSyncStatus>;
+
+    /// Return whether the worker's batches are reaching quorum and how many transactions were
+    /// accepted without it.
+    ///
+    /// Deferred transactions stay in the pool and are flushed once quorum is restored.
+    #[method(name = "getDeferredStatus")]
+    async fn deferred_status(&self) -> TelcoinNetworkRpcResult<DeferredStatus>;
 }
 
 /// The type that implements `tn` namespace trait.
@@ -27,6 +380,24 @@ pub struct TelcoinNetworkRpcExt<N> {
     ///
     /// The interface that handles primary <-> engine network communication.
     _inner_node_network: N,
+    /// The source of sub-dag execution statistics, if the node records them.
+    sub_dag_stats: Option<Arc<dyn SubDagStatsProvider>>,
+    /// The worker's inclusion promises, if the node signs them.
+    inclusion_promises: Option<InclusionPromises>,
+    /// Resolves sub-dags to execution blocks, if the node has consensus storage.
+    sub_dag_blocks: Option<Arc<dyn SubDagBlockResolver>>,
+    /// The source of transaction inclusion proofs, if the node has consensus storage.
+    inclusion_proofs: Option<Arc<dyn InclusionProofProvider>>,
+    /// The source of state diffs, if the node has execution storage.
+    state_diffs: Option<Arc<dyn StateDiffProvider>>,
+    /// The source of block provenance, if the node has consensus storage.
+    block_provenance: Option<Arc<dyn BlockProvenanceProvider>>,
+    /// The source of transactions by address, if the node indexes addresses.
+    transactions_by_address: Option<Arc<dyn TransactionsByAddressProvider>>,
+    /// The source of the node's sync status, if the node runs consensus.
+    sync_status: Option<Arc<dyn SyncStatusProvider>>,
+    /// The worker's deferred transactions, if the node runs a batch builder.
+    deferred_transactions: Option<DeferredTransactions>,
 }
 
 #[async_trait]
@@ -47,11 +418,187 @@ where
         // self.inner_node_network.new_peer
         Ok(())
     }
+
+    async fn sub_dag_stats(
+        &self,
+        start: u64,
+        end: u64,
+    ) -> TelcoinNetworkRpcResult<Vec<SubDagStatsEntry>> {
+        if start > end || end - start >= MAX_SUB_DAG_STATS_RANGE {
+            return Err(TNRpcError::InvalidSubDagRange { start, end });
+        }
+        let provider = self.sub_dag_stats.as_ref().ok_or_else(|| {
+            TNRpcError::SubDagStats("sub-dag statistics are not recorded".to_string())
+        })?;
+        Ok(provider.sub_dag_stats(start, end)?.into_iter().map(Into::into).collect())
+    }
+
+    async fn inclusion_promise(
+        &self,
+        tx_hash: TxHash,
+    ) -> TelcoinNetworkRpcResult<Option<InclusionPromise>> {
+        let promises =
+            self.inclusion_promises.as_ref().ok_or(TNRpcError::InclusionPromisesDisabled)?;
+        Ok(promises.get(&tx_hash))
+    }
+
+    async fn sub_dag_block(&self, sub_dag: u64) -> TelcoinNetworkRpcResult<Option<BlockNumHash>> {
+        let resolver = self.sub_dag_blocks.as_ref().ok_or_else(|| {
+            TNRpcError::SubDagBlock("sub-dag blocks are not available".to_string())
+        })?;
+        resolver.sub_dag_block(sub_dag)
+    }
+
+    async fn transaction_inclusion_proof(
+        &self,
+        tx_hash: TxHash,
+    ) -> TelcoinNetworkRpcResult<Option<TransactionInclusionProof>> {
+        let provider = self.inclusion_proofs.as_ref().ok_or_else(|| {
+            TNRpcError::InclusionProof("inclusion proofs are not available".to_string())
+        })?;
+        provider.transaction_inclusion_proof(tx_hash)
+    }
+
+    async fn state_diff(&self, block: u64) -> TelcoinNetworkRpcResult<Option<StateDiff>> {
+        self.state_diff_provider()?.block_state_diff(block)
+    }
+
+    async fn sub_dag_state_diff(&self, sub_dag: u64) -> TelcoinNetworkRpcResult<Option<StateDiff>> {
+        self.state_diff_provider()?.sub_dag_state_diff(sub_dag)
+    }
+
+    async fn block_provenance(
+        &self,
+        block_hash: BlockHash,
+    ) -> TelcoinNetworkRpcResult<Option<BlockProvenance>> {
+        let provider = self.block_provenance.as_ref().ok_or_else(|| {
+            TNRpcError::BlockProvenance("block provenance is not available".to_string())
+        })?;
+        provider.block_provenance(block_hash)
+    }
+
+    async fn transactions_by_address(
+        &self,
+        address: Address,
+        cursor: Option<AddressTransactionCursor>,
+        limit: Option<u64>,
+    ) -> TelcoinNetworkRpcResult<AddressTransactionsPage> {
+        let provider = self.transactions_by_address.as_ref().ok_or_else(|| {
+            TNRpcError::AddressIndex("addresses are not indexed by this node".to_string())
+        })?;
+        let limit =
+            limit.unwrap_or(MAX_TRANSACTIONS_BY_ADDRESS).clamp(1, MAX_TRANSACTIONS_BY_ADDRESS);
+        let transactions = provider.transactions_by_address(
+            address,
+            cursor.unwrap_or_default(),
+            limit as usize,
+        )?;
+        // a full page may be followed by more transactions
+        let next = transactions.last().filter(|_| transactions.len() == limit as usize).map(|tx| {
+            AddressTransactionCursor {
+                block_number: tx.block_number,
+                transaction_index: tx.index + 1,
+            }
+        });
+        Ok(AddressTransactionsPage {
+            transactions: transactions.into_iter().map(Into::into).collect(),
+            next,
+        })
+    }
+
+    async fn sync_status(&self) -> TelcoinNetworkRpcResult<NodeSyncStatus> {
+        let provider = self.sync_status.as_ref().ok_or_else(|| {
+            TNRpcError::SyncStatus("sync status is not available on this node".to_string())
+        })?;
+        provider.sync_status()
+    }
+
+    async fn deferred_status(&self) -> TelcoinNetworkRpcResult<DeferredStatus> {
+        let deferred =
+            self.deferred_transactions.as_ref().ok_or(TNRpcError::DeferredTransactionsDisabled)?;
+        Ok(deferred.status())
+    }
 }
 
 impl<N> TelcoinNetworkRpcExt<N> {
     /// Create new instance of the Telcoin Network RPC extension.
     pub fn new(chain: Arc<ChainSpec>, _inner_node_network: N) -> Self {
-        Self { chain, _inner_node_network }
+        Self {
+            chain,
+            _inner_node_network,
+            sub_dag_stats: None,
+            inclusion_promises: None,
+            sub_dag_blocks: None,
+            inclusion_proofs: None,
+            state_diffs: None,
+            block_provenance: None,
+            transactions_by_address: None,
+            sync_status: None,
+            deferred_transactions: None,
+        }
+    }
+
+    /// Serve sub-dag execution statistics from the provider.
+    pub fn with_sub_dag_stats(mut self, provider: Arc<dyn SubDagStatsProvider>) -> Self {
+        self.sub_dag_stats = Some(provider);
+        self
+    }
+
+    /// Serve the worker's inclusion promises.
+    pub fn with_inclusion_promises(mut self, inclusion_promises: InclusionPromises) -> Self {
+        self.inclusion_promises = Some(inclusion_promises);
+        self
+    }
+
+    /// Serve execution blocks for sub-dags from the resolver.
+    pub fn with_sub_dag_blocks(mut self, resolver: Arc<dyn SubDagBlockResolver>) -> Self {
+        self.sub_dag_blocks = Some(resolver);
+        self
+    }
+
+    /// Serve transaction inclusion proofs from the provider.
+    pub fn with_inclusion_proofs(mut self, provider: Arc<dyn InclusionProofProvider>) -> Self {
+        self.inclusion_proofs = Some(provider);
+        self
+    }
+
+    /// Serve state diffs from the provider.
+    pub fn with_state_diffs(mut self, provider: Arc<dyn StateDiffProvider>) -> Self {
+        self.state_diffs = Some(provider);
+        self
+    }
+
+    /// Serve block provenance from the provider.
+    pub fn with_block_provenance(mut self, provider: Arc<dyn BlockProvenanceProvider>) -> Self {
+        self.block_provenance = Some(provider);
+        self
+    }
+
+    /// Serve transactions by address from the provider.
+    pub fn with_transactions_by_address(
+        mut self,
+        provider: Arc<dyn TransactionsByAddressProvider>,
+    ) -> Self {
+        self.transactions_by_address = Some(provider);
+        self
+    }
+
+    /// Serve the node's sync status from the provider.
+    pub fn with_sync_status(mut self, provider: Arc<dyn SyncStatusProvider>) -> Self {
+        self.sync_status = Some(provider);
+        self
+    }
+
+    /// Serve the worker's deferred transactions.
+    pub fn with_deferred_transactions(mut self, deferred: DeferredTransactions) -> Self {
+        self.deferred_transactions = Some(deferred);
+        self
+    }
+
+    /// The state diff provider, or an error if state diffs are not served.
+    fn state_diff_provider(&self) -> TelcoinNetworkRpcResult<&Arc<dyn StateDiffProvider>> {
+        self.state_diffs
+            .as_ref()
+            .ok_or_else(|| TNRpcError::StateDiff("state diffs are not available".to_string()))
     }
 }
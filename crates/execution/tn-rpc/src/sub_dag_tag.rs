@@ -0,0 +1,173 @@
+//! Block tags for consensus sub-dags.
+//!
+//! Any `eth` namespace method that takes a block parameter also accepts `tn:subdag:<n>`. The tag
+//! is resolved to the last execution block for the consensus output of sub-dag `n`, so state can
+//! be audited at consensus boundaries, ie - `eth_call` at the end of a sub-dag.
+//!
+//! Tags are replaced with an EIP-1898 block hash before the request reaches the method.
+
+use crate::error::{TNRpcError, TelcoinNetworkRpcResult};
+use jsonrpsee::{server::middleware::rpc::RpcServiceT, types::Request, MethodResponse};
+use serde_json::{value::RawValue, Value};
+use std::{borrow::Cow, future::Future, pin::Pin, sync::Arc};
+use tn_types::BlockNumHash;
+use tower::Layer;
+
+/// The prefix for sub-dag block tags.
+pub const SUB_DAG_TAG_PREFIX: &str = "tn:subdag:";
+
+/// Resolves committed sub-dags to the execution blocks built for them.
+///
+/// The node implements this trait with its consensus and execution storage.
+pub trait SubDagBlockResolver: Send + Sync + 'static {
+    /// Return the last execution block for the sub-dag's consensus output.
+    ///
+    /// Returns `None` if the sub-dag has not been executed.
+    fn sub_dag_block(&self, sub_dag: u64) -> TelcoinNetworkRpcResult<Option<BlockNumHash>>;
+}
+
+/// Parse the sub-dag number from a `tn:subdag:<n>` block tag.
+pub fn parse_sub_dag_tag(tag: &str) -> Option<u64> {
+    tag.strip_prefix(SUB_DAG_TAG_PREFIX)?.parse().ok()
+}
+
+/// Replace every sub-dag block tag in the request params with the resolved block hash.
+///
+/// Returns `None` if the params do not contain a tag.
+fn resolve_sub_dag_tags(
+    params: &RawValue,
+    resolver: &dyn SubDagBlockResolver,
+) -> TelcoinNetworkRpcResult<Option<Box<RawValue>>> {
+    if !params.get().contains(SUB_DAG_TAG_PREFIX) {
+        return Ok(None);
+    }
+    // tags are only resolved for positional params
+    let Ok(mut values) = serde_json::from_str::<Vec<Value>>(params.get()) else {
+        return Ok(None);
+    };
+
+    let mut resolved = false;
+    for value in values.iter_mut() {
+        let Some(sub_dag) = value.as_str().and_then(parse_sub_dag_tag) else {
+            continue;
+        };
+        let block =
+            resolver.sub_dag_block(sub_dag)?.ok_or(TNRpcError::SubDagNotExecuted(sub_dag))?;
+        *value = serde_json::json!({ "blockHash": block.hash, "requireCanonical": true });
+        resolved = true;
+    }
+
+    if !resolved {
+        return Ok(None);
+    }
+    serde_json::value::to_raw_value(&values)
+        .map(Some)
+        .map_err(|e| TNRpcError::SubDagBlock(e.to_string()))
+}
+
+/// Layer that wraps RPC services with [SubDagBlockTags].
+#[derive(Clone)]
+pub struct SubDagBlockTagsLayer {
+    /// Resolves tags, requests are passed through unchanged without one.
+    resolver: Option<Arc<dyn SubDagBlockResolver>>,
+}
+
+impl SubDagBlockTagsLayer {
+    /// Create a new instance of [Self].
+    pub fn new(resolver: Option<Arc<dyn SubDagBlockResolver>>) -> Self {
+        Self { resolver }
+    }
+}
+
+impl<S> Layer<S> for SubDagBlockTagsLayer {
+    type Service = SubDagBlockTags<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        SubDagBlockTags { service, resolver: self.resolver.clone() }
+    }
+}
+
+/// RPC middleware that resolves sub-dag block tags in `eth` namespace requests.
+#[derive(Clone)]
+pub struct SubDagBlockTags<S> {
+    /// The next service in the middleware stack.
+    service: S,
+    /// Resolves tags, requests are passed through unchanged without one.
+    resolver: Option<Arc<dyn SubDagBlockResolver>>,
+}
+
+impl<'a, S> RpcServiceT<'a> for SubDagBlockTags<S>
+where
+    S: RpcServiceT<'a> + Clone + Send + Sync + 'static,
+{
+    type Future = Pin<Box<dyn Future<Output = MethodResponse> + Send + 'a>>;
+
+    fn call(&self, mut request: Request<'a>) -> Self::Future {
+        let service = self.service.clone();
+        let resolver = self.resolver.clone().filter(|_| request.method_name().starts_with("eth_"));
+
+        Box::pin(async move {
+            if let Some(resolver) = resolver {
+                let resolved = match request.params.as_deref() {
+                    Some(params) => resolve_sub_dag_tags(params, resolver.as_ref()),
+                    None => Ok(None),
+                };
+                match resolved {
+                    Ok(Some(params)) => request.params = Some(Cow::Owned(params)),
+                    Ok(None) => {}
+                    Err(e) => return MethodResponse::error(request.id, e),
+                }
+            }
+            service.call(request).await
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tn_types::B256;
+
+    /// Sub-dag `n` was executed in block `n * 10`.
+    struct TestResolver;
+
+    impl SubDagBlockResolver for TestResolver {
+        fn sub_dag_block(&self, sub_dag: u64) -> TelcoinNetworkRpcResult<Option<BlockNumHash>> {
+            Ok((sub_dag < 5).then(|| BlockNumHash::new(sub_dag * 10, B256::with_last_byte(1))))
+        }
+    }
+
+    fn raw(json: &str) -> Box<RawValue> {
+        RawValue::from_string(json.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_parse_sub_dag_tag() {
+        assert_eq!(parse_sub_dag_tag("tn:subdag:42"), Some(42));
+        assert_eq!(parse_sub_dag_tag("tn:subdag:"), None);
+        assert_eq!(parse_sub_dag_tag("tn:subdag:-1"), None);
+        assert_eq!(parse_sub_dag_tag("latest"), None);
+    }
+
+    #[test]
+    fn test_resolve_sub_dag_tags() {
+        // params without a tag are unchanged
+        let params = raw(r#"[{"to":"0x0000000000000000000000000000000000000001"},"latest"]"#);
+        assert!(resolve_sub_dag_tags(&params, &TestResolver).unwrap().is_none());
+
+        let params = raw(r#"[{"to":"0x0000000000000000000000000000000000000001"},"tn:subdag:3"]"#);
+        let resolved = resolve_sub_dag_tags(&params, &TestResolver).unwrap().unwrap();
+        let values: Vec<Value> = serde_json::from_str(resolved.get()).unwrap();
+        assert_eq!(
+            values[1],
+            serde_json::json!({ "blockHash": B256::with_last_byte(1), "requireCanonical": true })
+        );
+
+        // sub-dags that have not been executed are an error
+        let params = raw(r#"["0x0000000000000000000000000000000000000001","tn:subdag:7"]"#);
+        assert!(matches!(
+            resolve_sub_dag_tags(&params, &TestResolver),
+            Err(TNRpcError::SubDagNotExecuted(7))
+        ));
+    }
+}
@@ -0,0 +1,260 @@
+//! Node mode aware `eth` namespace behavior.
+//!
+//! Inactive CVVs and observers follow consensus output, so `eth_syncing` reports how far execution
+//! is behind the highest consensus header the node knows about. Observers never get transactions
+//! included, so `eth_sendRawTransaction` is rejected instead of the transaction waiting in a pool
+//! that is never sealed into a batch. Inactive CVVs are catching up to rejoin consensus and keep
+//! the transactions they accept until they do.
+//!
+//! Progress is measured in consensus headers (one per committed sub-dag) since the number of
+//! execution blocks for each header is only known once it executes.
+
+use crate::error::{TNRpcError, TelcoinNetworkRpcResult};
+use alloy::rpc::types::{Stage, SyncInfo, SyncStatus};
+use jsonrpsee::{
+    server::middleware::rpc::RpcServiceT,
+    types::{Request, ResponsePayload},
+    MethodResponse,
+};
+use serde::{Deserialize, Serialize};
+use std::{future::Future, pin::Pin, sync::Arc};
+use tn_types::U256;
+use tower::Layer;
+
+/// The methods that submit transactions for inclusion.
+pub(crate) const SEND_TRANSACTION_METHODS: [&str; 2] =
+    ["eth_sendRawTransaction", "eth_sendTransaction"];
+
+/// How far the node's execution is behind consensus.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NodeSyncStatus {
+    /// The node's current mode (active CVV, inactive CVV, or observer).
+    pub node_mode: String,
+    /// True if transactions sent to this node can be included in a batch.
+    pub accepts_transactions: bool,
+    /// True if the node executes consensus output without taking part in consensus.
+    pub follows_consensus: bool,
+    /// The number of the last executed consensus header when the node started.
+    pub starting_consensus_number: u64,
+    /// The number of the last executed consensus header.
+    pub executed_consensus_number: u64,
+    /// The number of the highest consensus header seen by the node.
+    pub highest_consensus_number: u64,
+    /// The number of the last executed block.
+    pub executed_block_number: u64,
+}
+
+impl NodeSyncStatus {
+    /// Return true if the node follows consensus and has consensus headers left to execute.
+    ///
+    /// Active CVVs are never syncing, they produce the consensus headers they execute.
+    pub fn is_syncing(&self) -> bool {
+        self.follows_consensus && self.executed_consensus_number < self.highest_consensus_number
+    }
+
+    /// The status reported by `eth_syncing`.
+    ///
+    /// Block numbers are consensus header numbers. The `Consensus` stage is the highest consensus
+    /// header and the `Execution` stage is the last executed block.
+    pub fn eth_sync_status(&self) -> SyncStatus {
+        if !self.is_syncing() {
+            return SyncStatus::None;
+        }
+        SyncStatus::Info(Box::new(SyncInfo {
+            starting_block: U256::from(self.starting_consensus_number),
+            current_block: U256::from(self.executed_consensus_number),
+            highest_block: U256::from(self.highest_consensus_number),
+            warp_chunks_amount: None,
+            warp_chunks_processed: None,
+            stages: Some(vec![
+                Stage { name: "Consensus".to_string(), block: self.highest_consensus_number },
+                Stage { name: "Execution".to_string(), block: self.executed_block_number },
+            ]),
+        }))
+    }
+
+    /// Return an error if transactions sent to this node can not be included.
+    pub fn ensure_accepts_transactions(&self) -> TelcoinNetworkRpcResult<()> {
+        if self.accepts_transactions {
+            return Ok(());
+        }
+        Err(TNRpcError::TransactionsNotAccepted(self.node_mode.clone()))
+    }
+}
+
+/// Source of the node's sync status.
+///
+/// The node implements this trait with its consensus bus and storage.
+pub trait SyncStatusProvider: Send + Sync + 'static {
+    /// Return the current sync status.
+    fn sync_status(&self) -> TelcoinNetworkRpcResult<NodeSyncStatus>;
+}
+
+/// Layer that wraps RPC services with [NodeModeAware].
+#[derive(Clone)]
+pub struct NodeModeAwareLayer {
+    /// The node's sync status, requests are passed through unchanged without one.
+    provider: Option<Arc<dyn SyncStatusProvider>>,
+}
+
+impl NodeModeAwareLayer {
+    /// Create a new instance of [Self].
+    pub fn new(provider: Option<Arc<dyn SyncStatusProvider>>) -> Self {
+        Self { provider }
+    }
+}
+
+impl<S> Layer<S> for NodeModeAwareLayer {
+    type Service = NodeModeAware<S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        NodeModeAware { service, provider: self.provider.clone() }
+    }
+}
+
+/// RPC middleware that answers `eth_syncing` and rejects transactions based on the node's mode.
+#[derive(Clone)]
+pub struct NodeModeAware<S> {
+    /// The next service in the middleware stack.
+    service: S,
+    /// The node's sync status, requests are passed through unchanged without one.
+    provider: Option<Arc<dyn SyncStatusProvider>>,
+}
+
+impl<'a, S> RpcServiceT<'a> for NodeModeAware<S>
+where
+    S: RpcServiceT<'a> + Clone + Send + Sync + 'static,
+{
+    type Future = Pin<Box<dyn Future<Output = MethodResponse> + Send + 'a>>;
+
+    fn call(&self, request: Request<'a>) -> Self::Future {
+        let service = self.service.clone();
+        let method = request.method_name();
+        let provider = self
+            .provider
+            .clone()
+            .filter(|_| method == "eth_syncing" || SEND_TRANSACTION_METHODS.contains(&method));
+
+        Box::pin(async move {
+            let Some(provider) = provider else {
+                return service.call(request).await;
+            };
+            let status = match provider.sync_status() {
+                Ok(status) => status,
+                Err(e) => return MethodResponse::error(request.id, e),
+            };
+            if request.method_name() == "eth_syncing" {
+                let payload = ResponsePayload::success(status.eth_sync_status());
+                return MethodResponse::response(request.id, payload, usize::MAX);
+            }
+            match status.ensure_accepts_transactions() {
+                Ok(()) => service.call(request).await,
+                Err(e) => MethodResponse::error(request.id, e),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpsee::types::Id;
+
+    fn status(mode: &str, executed: u64, highest: u64) -> NodeSyncStatus {
+        NodeSyncStatus {
+            node_mode: mode.to_string(),
+            accepts_transactions: mode != "observer",
+            follows_consensus: mode != "active CVV",
+            starting_consensus_number: 2,
+            executed_consensus_number: executed,
+            highest_consensus_number: highest,
+            executed_block_number: executed * 3,
+        }
+    }
+
+    #[test]
+    fn test_eth_sync_status() {
+        // active CVVs and caught up followers are not syncing
+        assert_eq!(status("active CVV", 5, 10).eth_sync_status(), SyncStatus::None);
+        assert_eq!(status("observer", 10, 10).eth_sync_status(), SyncStatus::None);
+
+        let SyncStatus::Info(info) = status("inactive CVV", 5, 10).eth_sync_status() else {
+            panic!("inactive CVV behind consensus is syncing");
+        };
+        assert_eq!(info.starting_block, U256::from(2));
+        assert_eq!(info.current_block, U256::from(5));
+        assert_eq!(info.highest_block, U256::from(10));
+        assert_eq!(
+            info.stages,
+            Some(vec![
+                Stage { name: "Consensus".to_string(), block: 10 },
+                Stage { name: "Execution".to_string(), block: 15 },
+            ])
+        );
+    }
+
+    #[test]
+    fn test_ensure_accepts_transactions() {
+        assert!(status("active CVV", 5, 10).ensure_accepts_transactions().is_ok());
+        // CVVs catching up keep transactions until they rejoin
+        assert!(status("inactive CVV", 5, 10).ensure_accepts_transactions().is_ok());
+        assert!(matches!(
+            status("observer", 10, 10).ensure_accepts_transactions(),
+            Err(TNRpcError::TransactionsNotAccepted(m)) if m == "observer"
+        ));
+    }
+
+    /// Answers every request with "passed".
+    #[derive(Clone)]
+    struct Passthrough;
+
+    impl<'a> RpcServiceT<'a> for Passthrough {
+        type Future = std::future::Ready<MethodResponse>;
+
+        fn call(&self, request: Request<'a>) -> Self::Future {
+            let payload = ResponsePayload::success("passed");
+            std::future::ready(MethodResponse::response(request.id, payload, usize::MAX))
+        }
+    }
+
+    impl SyncStatusProvider for NodeSyncStatus {
+        fn sync_status(&self) -> TelcoinNetworkRpcResult<NodeSyncStatus> {
+            Ok(self.clone())
+        }
+    }
+
+    /// Call `method` through the middleware with the node in `status`.
+    async fn call(status: Option<NodeSyncStatus>, method: &str) -> MethodResponse {
+        let provider = status.map(|status| Arc::new(status) as Arc<dyn SyncStatusProvider>);
+        let service = NodeModeAwareLayer::new(provider).layer(Passthrough);
+        service.call(Request::new(method.into(), None, Id::Number(1))).await
+    }
+
+    #[tokio::test]
+    async fn test_node_mode_aware() {
+        // requests pass through unchanged without a provider
+        let response = call(None, "eth_sendRawTransaction").await;
+        assert!(response.as_result().contains("passed"));
+
+        // observers reject transactions, CVVs pass them on
+        let response = call(Some(status("observer", 5, 10)), "eth_sendRawTransaction").await;
+        assert!(response.is_error());
+        assert!(response.as_result().contains("observer"));
+        for mode in ["active CVV", "inactive CVV"] {
+            let response = call(Some(status(mode, 5, 10)), "eth_sendTransaction").await;
+            assert!(response.as_result().contains("passed"));
+        }
+
+        // other methods are never intercepted
+        let response = call(Some(status("observer", 5, 10)), "eth_blockNumber").await;
+        assert!(response.as_result().contains("passed"));
+
+        // eth_syncing is answered from the node's status
+        let response = call(Some(status("active CVV", 5, 10)), "eth_syncing").await;
+        assert!(response.is_success());
+        assert!(response.as_result().contains("false"));
+        let response = call(Some(status("inactive CVV", 5, 10)), "eth_syncing").await;
+        assert!(response.as_result().contains("highestBlock"));
+    }
+}
@@ -0,0 +1,13 @@
+//! Generate the protobuf messages and service for `proto/consensus.proto`.
+//!
+//! The proto file is compiled with `protox` so building the node does not require `protoc`.
+
+use std::error::Error;
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let proto = "proto/consensus.proto";
+    println!("cargo:rerun-if-changed={proto}");
+    let descriptors = protox::compile([proto], ["proto"])?;
+    tonic_build::configure().build_client(true).build_server(true).compile_fds(descriptors)?;
+    Ok(())
+}
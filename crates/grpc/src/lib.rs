@@ -0,0 +1,13 @@
+// SPDX-License-Identifier: Apache-2.0
+//! gRPC streaming of consensus data.
+//!
+//! Indexers and bridges follow consensus with server streaming calls instead of polling JSON-RPC.
+//! The `tn.consensus.v1.ConsensusData` service in `proto/consensus.proto` streams committed
+//! sub-dags, their certificates, and the node's status. Sub-dag and certificate streams replay
+//! history from the consensus DB before following new commits, so clients resume after a
+//! disconnect by requesting the next sub-dag they expect.
+
+pub mod proto;
+mod service;
+
+pub use service::{spawn_grpc_server, ConsensusDataService};
@@ -0,0 +1,7 @@
+//! Protobuf messages, client, and server for `tn.consensus.v1`.
+//!
+//! Generated by `tonic-build` from `proto/consensus.proto`, see `build.rs`.
+
+#![allow(missing_docs, clippy::all)]
+
+tonic::include_proto!("tn.consensus.v1");
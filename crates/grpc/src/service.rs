@@ -0,0 +1,422 @@
+//! The [ConsensusData] service backed by the node's consensus DB and bus.
+
+use crate::proto::{
+    self,
+    consensus_data_server::{ConsensusData, ConsensusDataServer},
+};
+use futures::stream::{self, BoxStream, StreamExt as _};
+use parking_lot::Mutex;
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, SocketAddr, TcpListener},
+    sync::Arc,
+    time::Duration,
+};
+use tn_primary::ConsensusBus;
+use tn_rpc::{NodeStatus, NodeStatusProvider};
+use tn_storage::tables::ConsensusBlocks;
+use tn_types::{encode, Certificate, ConsensusHeader, Database, Hash as _, Noticer, TaskManager};
+use tokio::sync::{broadcast::error::RecvError, mpsc};
+use tokio_stream::wrappers::{ReceiverStream, TcpListenerStream};
+use tonic::{transport::Server, Request, Response, Status};
+use tracing::{error, info};
+
+/// The interval between node status updates if the client does not set one.
+const DEFAULT_STATUS_INTERVAL: Duration = Duration::from_secs(1);
+/// The shortest interval between node status updates.
+const MIN_STATUS_INTERVAL: Duration = Duration::from_millis(100);
+/// The number of messages buffered for each client.
+const STREAM_BUFFER: usize = 64;
+/// The number of streams a client address can have open at once.
+const MAX_STREAMS_PER_CLIENT: usize = 8;
+
+/// A stream of messages to a client.
+type GrpcStream<T> = BoxStream<'static, Result<T, Status>>;
+
+/// Streams committed sub-dags, certificates, and node status to indexers.
+pub struct ConsensusDataService<DB> {
+    /// The consensus DB used to replay sub-dags.
+    db: DB,
+    /// The bus publishing new consensus output.
+    consensus_bus: ConsensusBus,
+    /// Collects the node's status.
+    node_status: Arc<dyn NodeStatusProvider>,
+    /// Limits the streams open for each client.
+    limiter: StreamLimiter,
+}
+
+impl<DB: Database> ConsensusDataService<DB> {
+    /// Create a new instance of [Self].
+    pub fn new(
+        db: DB,
+        consensus_bus: ConsensusBus,
+        node_status: Arc<dyn NodeStatusProvider>,
+    ) -> Self {
+        Self { db, consensus_bus, node_status, limiter: StreamLimiter::default() }
+    }
+}
+
+/// Counts the open streams of each client address.
+///
+/// Every stream is served by a task, so a client opening streams without limit would exhaust the
+/// node.
+#[derive(Debug, Default, Clone)]
+struct StreamLimiter {
+    /// The number of open streams by client address.
+    open: Arc<Mutex<HashMap<IpAddr, usize>>>,
+}
+
+impl StreamLimiter {
+    /// Reserve a stream for the client that sent the request.
+    ///
+    /// Returns `RESOURCE_EXHAUSTED` if the client has [MAX_STREAMS_PER_CLIENT] streams open.
+    fn acquire<T>(&self, request: &Request<T>) -> Result<StreamPermit, Status> {
+        let client = request
+            .remote_addr()
+            .map(|addr| addr.ip())
+            .unwrap_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED));
+        let mut open = self.open.lock();
+        let count = open.entry(client).or_default();
+        if *count >= MAX_STREAMS_PER_CLIENT {
+            return Err(Status::resource_exhausted(format!(
+                "at most {MAX_STREAMS_PER_CLIENT} streams can be open per client"
+            )));
+        }
+        *count += 1;
+        Ok(StreamPermit { client, open: self.open.clone() })
+    }
+}
+
+/// A reserved stream, released when the stream's task ends.
+#[derive(Debug)]
+struct StreamPermit {
+    /// The client the stream belongs to.
+    client: IpAddr,
+    /// The limiter's open streams.
+    open: Arc<Mutex<HashMap<IpAddr, usize>>>,
+}
+
+impl Drop for StreamPermit {
+    fn drop(&mut self) {
+        let mut open = self.open.lock();
+        if let Some(count) = open.get_mut(&self.client) {
+            *count = count.saturating_sub(1);
+            if *count == 0 {
+                open.remove(&self.client);
+            }
+        }
+    }
+}
+
+/// Spawn a task streaming committed sub-dags to a client.
+///
+/// Sub-dags are replayed from the DB starting at `start`, or the next commit if not set, followed
+/// by new commits from the bus. The permit is released when the client disconnects.
+fn spawn_sub_dag_stream<DB: Database>(
+    db: DB,
+    consensus_bus: &ConsensusBus,
+    start: Option<u64>,
+    permit: StreamPermit,
+) -> mpsc::Receiver<Result<proto::CommittedSubDag, Status>> {
+    let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+    // subscribe before replaying so commits during the replay are not missed
+    let mut outputs = consensus_bus.subscribe_consensus_output();
+
+    tokio::spawn(async move {
+        let _permit = permit;
+        let mut next = match start {
+            Some(start) => start,
+            None => db.last_record::<ConsensusBlocks>().map(|(number, _)| number + 1).unwrap_or(0),
+        };
+        if !replay_sub_dags(&db, &mut next, &tx).await {
+            return;
+        }
+
+        loop {
+            tokio::select!(
+                _ = tx.closed() => break,
+                output = outputs.recv() => match output {
+                    Ok(output) => {
+                        // anything committed while the client was behind is in the DB
+                        if !replay_sub_dags(&db, &mut next, &tx).await {
+                            break;
+                        }
+                        if output.number < next {
+                            continue;
+                        }
+                        let sub_dag = proto::CommittedSubDag::from(&output.consensus_header());
+                        next = output.number + 1;
+                        if tx.send(Ok(sub_dag)).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(RecvError::Lagged(_)) => {
+                        if !replay_sub_dags(&db, &mut next, &tx).await {
+                            break;
+                        }
+                    }
+                    Err(RecvError::Closed) => break,
+                },
+            );
+        }
+    });
+
+    rx
+}
+
+/// Send sub-dags from the DB starting at `next` until one is missing.
+///
+/// Returns false if the stream ended.
+async fn replay_sub_dags<DB: Database>(
+    db: &DB,
+    next: &mut u64,
+    tx: &mpsc::Sender<Result<proto::CommittedSubDag, Status>>,
+) -> bool {
+    loop {
+        let header = match db.get::<ConsensusBlocks>(next) {
+            Ok(Some(header)) => header,
+            Ok(None) => return true,
+            Err(e) => {
+                error!(target: "tn::grpc", ?e, number = *next, "failed to read consensus header");
+                let _ = tx.send(Err(Status::internal(e.to_string()))).await;
+                return false;
+            }
+        };
+        if tx.send(Ok(proto::CommittedSubDag::from(&header))).await.is_err() {
+            return false;
+        }
+        *next += 1;
+    }
+}
+
+#[async_trait::async_trait]
+impl<DB: Database> ConsensusData for ConsensusDataService<DB> {
+    type SubscribeSubDagsStream = GrpcStream<proto::CommittedSubDag>;
+    type SubscribeCertificatesStream = GrpcStream<proto::Certificate>;
+    type SubscribeNodeStatusStream = GrpcStream<proto::NodeStatus>;
+
+    async fn subscribe_sub_dags(
+        &self,
+        request: Request<proto::SubscribeSubDagsRequest>,
+    ) -> Result<Response<Self::SubscribeSubDagsStream>, Status> {
+        let permit = self.limiter.acquire(&request)?;
+        let start = request.into_inner().start;
+        let rx = spawn_sub_dag_stream(self.db.clone(), &self.consensus_bus, start, permit);
+        Ok(Response::new(ReceiverStream::new(rx).boxed()))
+    }
+
+    async fn subscribe_certificates(
+        &self,
+        request: Request<proto::SubscribeSubDagsRequest>,
+    ) -> Result<Response<Self::SubscribeCertificatesStream>, Status> {
+        let permit = self.limiter.acquire(&request)?;
+        let start = request.into_inner().start;
+        let rx = spawn_sub_dag_stream(self.db.clone(), &self.consensus_bus, start, permit);
+        let certificates = ReceiverStream::new(rx).flat_map(|sub_dag| {
+            let certificates: Vec<_> = match sub_dag {
+                Ok(sub_dag) => sub_dag.certificates.into_iter().map(Ok).collect(),
+                Err(status) => vec![Err(status)],
+            };
+            stream::iter(certificates)
+        });
+        Ok(Response::new(certificates.boxed()))
+    }
+
+    async fn subscribe_node_status(
+        &self,
+        request: Request<proto::SubscribeNodeStatusRequest>,
+    ) -> Result<Response<Self::SubscribeNodeStatusStream>, Status> {
+        let permit = self.limiter.acquire(&request)?;
+        let period = match request.into_inner().interval_ms {
+            0 => DEFAULT_STATUS_INTERVAL,
+            ms => Duration::from_millis(ms).max(MIN_STATUS_INTERVAL),
+        };
+        let (tx, rx) = mpsc::channel(STREAM_BUFFER);
+        let node_status = self.node_status.clone();
+
+        tokio::spawn(async move {
+            let _permit = permit;
+            let mut interval = tokio::time::interval(period);
+            loop {
+                tokio::select!(
+                    _ = tx.closed() => break,
+                    _ = interval.tick() => {
+                        let status = node_status
+                            .node_status()
+                            .await
+                            .map(|status| proto::NodeStatus::from(&status))
+                            .map_err(|e| Status::unavailable(e.to_string()));
+                        if tx.send(status).await.is_err() {
+                            break;
+                        }
+                    }
+                );
+            }
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx).boxed()))
+    }
+}
+
+impl From<&ConsensusHeader> for proto::CommittedSubDag {
+    fn from(header: &ConsensusHeader) -> Self {
+        let sub_dag = &header.sub_dag;
+        Self {
+            number: header.number,
+            digest: header.digest().to_vec(),
+            parent_hash: header.parent_hash.to_vec(),
+            leader_round: sub_dag.leader_round(),
+            leader: sub_dag.leader.digest().as_ref().to_vec(),
+            commit_timestamp: sub_dag.commit_timestamp(),
+            certificates: sub_dag
+                .certificates
+                .iter()
+                .map(|certificate| certificate_to_proto(header.number, certificate))
+                .collect(),
+            encoded: encode(header),
+        }
+    }
+}
+
+/// Convert a certificate committed in sub-dag `number`.
+fn certificate_to_proto(number: u64, certificate: &Certificate) -> proto::Certificate {
+    let header = certificate.header();
+    proto::Certificate {
+        digest: certificate.digest().as_ref().to_vec(),
+        sub_dag: number,
+        round: certificate.round(),
+        epoch: certificate.epoch(),
+        author: header.author().to_string(),
+        created_at: *header.created_at(),
+        batch_digests: header.payload().keys().map(|digest| digest.to_vec()).collect(),
+        parents: header.parents().iter().map(|digest| digest.as_ref().to_vec()).collect(),
+        encoded: encode(certificate),
+    }
+}
+
+impl From<&NodeStatus> for proto::NodeStatus {
+    fn from(status: &NodeStatus) -> Self {
+        Self {
+            node_mode: status.node_mode.clone(),
+            sync_distance: status.sync_distance,
+            primary_peers: status.primary_peers as u64,
+            worker_peers: status.worker_peers as u64,
+            last_sub_dag_index: status.last_sub_dag_index,
+            last_committed_round: status.last_committed_round,
+            last_executed_block_number: status.last_executed_block_number,
+            last_executed_block_hash: status.last_executed_block_hash.to_vec(),
+            halt_at_sub_dag: status.halt_at_sub_dag,
+        }
+    }
+}
+
+/// Bind the server's listener.
+fn bind_listener(addr: SocketAddr) -> std::io::Result<tokio::net::TcpListener> {
+    let listener = TcpListener::bind(addr)?;
+    listener.set_nonblocking(true)?;
+    tokio::net::TcpListener::from_std(listener)
+}
+
+/// Spawn a task serving the [ConsensusData] service at `addr`.
+///
+/// The address is bound before the task is spawned so a port in use fails the node's launch.
+/// The server stops accepting requests and closes open streams on shutdown.
+pub fn spawn_grpc_server<DB: Database>(
+    addr: SocketAddr,
+    service: ConsensusDataService<DB>,
+    task_manager: &TaskManager,
+    rx_shutdown: Noticer,
+) -> std::io::Result<()> {
+    let listener = bind_listener(addr)?;
+    let addr = listener.local_addr()?;
+
+    task_manager.spawn_task("grpc server", async move {
+        info!(target: "tn::grpc", %addr, "serving consensus data over gRPC");
+        if let Err(e) = Server::builder()
+            .add_service(ConsensusDataServer::new(service))
+            .serve_with_incoming_shutdown(TcpListenerStream::new(listener), rx_shutdown)
+            .await
+        {
+            error!(target: "tn::grpc", ?e, %addr, "gRPC server failed");
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::VecDeque;
+    use tn_storage::mem_db::MemDatabase;
+    use tn_types::{Address, ConsensusOutput, TnSender as _, B256};
+
+    fn header(number: u64) -> ConsensusHeader {
+        ConsensusHeader { number, ..Default::default() }
+    }
+
+    fn output(number: u64) -> ConsensusOutput {
+        ConsensusOutput {
+            sub_dag: Arc::new(header(number).sub_dag),
+            batches: vec![],
+            beneficiary: Address::ZERO,
+            batch_digests: VecDeque::new(),
+            parent_hash: B256::default(),
+            number,
+            extra: B256::default(),
+            early_finalize: false,
+            withdrawals: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_replay_then_live_sub_dags() {
+        let db = MemDatabase::default();
+        for number in 0..3 {
+            db.insert::<ConsensusBlocks>(&number, &header(number)).expect("header stored");
+        }
+        let consensus_bus = ConsensusBus::new();
+
+        let permit = StreamLimiter::default().acquire(&Request::new(())).expect("permit");
+        let mut sub_dags = spawn_sub_dag_stream(db.clone(), &consensus_bus, Some(1), permit);
+        for number in 1..3 {
+            let sub_dag = sub_dags.recv().await.expect("stream open").expect("sub dag");
+            assert_eq!(sub_dag.number, number);
+            assert_eq!(sub_dag.digest, header(number).digest().to_vec());
+        }
+
+        // a commit written to the DB before it is published is only sent once
+        db.insert::<ConsensusBlocks>(&3, &header(3)).expect("header stored");
+        consensus_bus.consensus_output().send(output(3)).await.expect("output sent");
+        let sub_dag = sub_dags.recv().await.expect("stream open").expect("sub dag");
+        assert_eq!(sub_dag.number, 3);
+
+        consensus_bus.consensus_output().send(output(4)).await.expect("output sent");
+        let sub_dag = sub_dags.recv().await.expect("stream open").expect("sub dag");
+        assert_eq!(sub_dag.number, 4);
+        assert!(sub_dags.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_streams_limited_per_client() {
+        let limiter = StreamLimiter::default();
+        let request = Request::new(());
+        let permits: Vec<_> = (0..MAX_STREAMS_PER_CLIENT)
+            .map(|_| limiter.acquire(&request).expect("permit"))
+            .collect();
+        let status = limiter.acquire(&request).expect_err("limit reached");
+        assert_eq!(status.code(), tonic::Code::ResourceExhausted);
+
+        // a closed stream frees its slot
+        drop(permits.into_iter().next());
+        let permit = limiter.acquire(&request).expect("permit");
+        drop(permit);
+        assert_eq!(limiter.open.lock().values().sum::<usize>(), MAX_STREAMS_PER_CLIENT - 1);
+    }
+
+    #[tokio::test]
+    async fn test_bind_error_is_returned() {
+        let listener = bind_listener("127.0.0.1:0".parse().expect("addr")).expect("bound");
+        let addr = listener.local_addr().expect("local addr");
+        assert!(bind_listener(addr).is_err());
+    }
+}
@@ -0,0 +1,155 @@
+//! Fault injection for outbound messages on staging networks.
+//!
+//! Only nodes built with the `chaos` feature can enable it.
+
+use crate::{codec::TNMessage, send_or_log_error, types::NetworkCommand};
+use futures::{future::BoxFuture, stream::FuturesUnordered, StreamExt as _};
+use libp2p::gossipsub::MessageId;
+use rand::Rng as _;
+use std::time::Duration;
+#[cfg(feature = "chaos")]
+use tn_config::ChaosConfig;
+use tn_types::keccak256;
+use tracing::{debug, error};
+
+#[cfg(test)]
+#[path = "tests/chaos_tests.rs"]
+mod chaos_tests;
+
+/// Randomly delays and drops outbound requests, responses and gossip.
+///
+/// Consensus must tolerate lost and late messages, so staging networks enable this to keep the
+/// retry and sync paths exercised.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NetworkChaos {
+    /// The largest random delay before a message is sent.
+    max_delay: Duration,
+    /// The fraction of messages dropped.
+    drop_rate: f64,
+}
+
+impl NetworkChaos {
+    /// Create a new instance of Self.
+    ///
+    /// The rate is clamped to `0..=1`.
+    pub fn new(max_delay: Duration, drop_rate: f64) -> Self {
+        let drop_rate = if drop_rate.is_nan() { 0.0 } else { drop_rate.clamp(0.0, 1.0) };
+        Self { max_delay, drop_rate }
+    }
+
+    /// Create an instance from the node's chaos config, if enabled.
+    #[cfg(feature = "chaos")]
+    pub fn from_config(config: &ChaosConfig) -> Option<Self> {
+        config.bounded().map(|config| Self::new(config.max_message_delay, config.message_drop_rate))
+    }
+
+    /// The delay before the next outbound message is sent, or `None` if it should be dropped.
+    fn delay(&self) -> Option<Duration> {
+        let mut rng = rand::thread_rng();
+        if rng.gen_bool(self.drop_rate) {
+            return None;
+        }
+
+        if self.max_delay.is_zero() {
+            Some(Duration::ZERO)
+        } else {
+            Some(rng.gen_range(Duration::ZERO..=self.max_delay))
+        }
+    }
+}
+
+/// Outbound commands held back by [NetworkChaos].
+///
+/// The network loop polls the delayed commands alongside new ones, so neither the callers of a
+/// [crate::types::NetworkHandle] nor the loop itself wait for a delay to pass.
+pub(crate) struct ChaosQueue<Req, Res>
+where
+    Req: TNMessage,
+    Res: TNMessage,
+{
+    /// The disruption applied to outbound messages, `None` unless chaos is enabled.
+    chaos: Option<NetworkChaos>,
+    /// Commands waiting for their delay to pass.
+    delayed: FuturesUnordered<BoxFuture<'static, NetworkCommand<Req, Res>>>,
+}
+
+impl<Req, Res> ChaosQueue<Req, Res>
+where
+    Req: TNMessage,
+    Res: TNMessage,
+{
+    /// Create a new instance of Self.
+    pub(crate) fn new(chaos: Option<NetworkChaos>) -> Self {
+        Self { chaos, delayed: FuturesUnordered::new() }
+    }
+
+    /// Apply chaos, if enabled, to the command.
+    ///
+    /// Returns the command if it should be processed now. Delayed commands are returned by
+    /// [Self::next_delayed] once their delay passes.
+    pub(crate) fn disrupt(
+        &mut self,
+        command: NetworkCommand<Req, Res>,
+    ) -> Option<NetworkCommand<Req, Res>> {
+        let Some(chaos) = &self.chaos else {
+            return Some(command);
+        };
+        if !matches!(
+            command,
+            NetworkCommand::Publish { .. }
+                | NetworkCommand::SendRequest { .. }
+                | NetworkCommand::SendRequestAny { .. }
+                | NetworkCommand::SendResponse { .. }
+        ) {
+            return Some(command);
+        }
+
+        match chaos.delay() {
+            None => {
+                drop_command(command);
+                None
+            }
+            Some(delay) if delay.is_zero() => Some(command),
+            Some(delay) => {
+                self.delayed.push(Box::pin(async move {
+                    tokio::time::sleep(delay).await;
+                    command
+                }));
+                None
+            }
+        }
+    }
+
+    /// The next command whose delay passed.
+    ///
+    /// Pending forever while no commands are delayed.
+    pub(crate) async fn next_delayed(&mut self) -> NetworkCommand<Req, Res> {
+        match self.delayed.next().await {
+            Some(command) => command,
+            None => std::future::pending().await,
+        }
+    }
+}
+
+/// Drop an outbound message the way a lossy network would.
+fn drop_command<Req, Res>(command: NetworkCommand<Req, Res>)
+where
+    Req: TNMessage,
+    Res: TNMessage,
+{
+    debug!(target: "network::chaos", "dropping outbound message");
+    match command {
+        NetworkCommand::Publish { msg, reply, .. } => {
+            // gossip is fire-and-forget, the caller can't tell the message was lost
+            let id = MessageId::new(keccak256(&msg).as_slice());
+            send_or_log_error!(reply, Ok(id), "Publish");
+        }
+        NetworkCommand::SendResponse { channel, reply, .. } => {
+            // dropping the channel fails the peer's request
+            drop(channel);
+            send_or_log_error!(reply, Ok(()), "SendResponse");
+        }
+        // dropping the reply looks like a lost connection to the caller
+        _ => (),
+    }
+}
@@ -0,0 +1,126 @@
+//! The network operations behind the primary and worker network handles.
+//!
+//! [NetworkHandle] implements [NetworkClient] by sending commands to the libp2p swarm.
+//! [MemoryNetwork](crate::MemoryNetwork) implements it in memory so consensus components can be
+//! tested without running a swarm.
+
+use crate::{
+    codec::TNMessage,
+    types::{IdentTopic, MessageId, NetworkHandle, NetworkResult},
+    NodeVersion,
+};
+use async_trait::async_trait;
+use libp2p::{request_response::ResponseChannel, Multiaddr, PeerId};
+use std::collections::HashMap;
+use tokio::sync::oneshot;
+
+/// The network operations used by the primary and worker network handles.
+#[async_trait]
+pub trait NetworkClient<Req, Res>: Send + Sync + 'static
+where
+    Req: TNMessage,
+    Res: TNMessage,
+{
+    /// Dial a peer.
+    async fn dial(&self, peer_id: PeerId, peer_addr: Multiaddr) -> NetworkResult<()>;
+
+    /// Retrieve a collection of connected peers.
+    async fn connected_peers(&self) -> NetworkResult<Vec<PeerId>>;
+
+    /// Get local peer id.
+    async fn local_peer_id(&self) -> NetworkResult<PeerId>;
+
+    /// Subscribe to a topic.
+    async fn subscribe(&self, topic: IdentTopic) -> NetworkResult<bool>;
+
+    /// Publish a message on a certain topic.
+    async fn publish(&self, topic: IdentTopic, msg: Vec<u8>) -> NetworkResult<MessageId>;
+
+    /// Send a request to a peer.
+    ///
+    /// Returns a handle for the caller to await the peer's response.
+    async fn send_request(
+        &self,
+        request: Req,
+        peer: PeerId,
+    ) -> NetworkResult<oneshot::Receiver<NetworkResult<Res>>>;
+
+    /// Send a request to a peer- any peer will do.
+    ///
+    /// Returns a handle for the caller to await the peer's response.
+    async fn send_request_any(
+        &self,
+        request: Req,
+    ) -> NetworkResult<oneshot::Receiver<NetworkResult<Res>>>;
+
+    /// Respond to a peer's request.
+    async fn send_response(
+        &self,
+        response: Res,
+        channel: ResponseChannel<Res>,
+    ) -> NetworkResult<()>;
+
+    /// Set the peer's application score.
+    async fn set_application_score(&self, peer_id: PeerId, new_score: f64) -> NetworkResult<bool>;
+
+    /// Retrieve the software versions peers advertised.
+    async fn peer_versions(&self) -> NetworkResult<HashMap<PeerId, NodeVersion>>;
+}
+
+#[async_trait]
+impl<Req, Res> NetworkClient<Req, Res> for NetworkHandle<Req, Res>
+where
+    Req: TNMessage,
+    Res: TNMessage,
+{
+    async fn dial(&self, peer_id: PeerId, peer_addr: Multiaddr) -> NetworkResult<()> {
+        NetworkHandle::dial(self, peer_id, peer_addr).await
+    }
+
+    async fn connected_peers(&self) -> NetworkResult<Vec<PeerId>> {
+        NetworkHandle::connected_peers(self).await
+    }
+
+    async fn local_peer_id(&self) -> NetworkResult<PeerId> {
+        NetworkHandle::local_peer_id(self).await
+    }
+
+    async fn subscribe(&self, topic: IdentTopic) -> NetworkResult<bool> {
+        NetworkHandle::subscribe(self, topic).await
+    }
+
+    async fn publish(&self, topic: IdentTopic, msg: Vec<u8>) -> NetworkResult<MessageId> {
+        NetworkHandle::publish(self, topic, msg).await
+    }
+
+    async fn send_request(
+        &self,
+        request: Req,
+        peer: PeerId,
+    ) -> NetworkResult<oneshot::Receiver<NetworkResult<Res>>> {
+        NetworkHandle::send_request(self, request, peer).await
+    }
+
+    async fn send_request_any(
+        &self,
+        request: Req,
+    ) -> NetworkResult<oneshot::Receiver<NetworkResult<Res>>> {
+        NetworkHandle::send_request_any(self, request).await
+    }
+
+    async fn send_response(
+        &self,
+        response: Res,
+        channel: ResponseChannel<Res>,
+    ) -> NetworkResult<()> {
+        NetworkHandle::send_response(self, response, channel).await
+    }
+
+    async fn set_application_score(&self, peer_id: PeerId, new_score: f64) -> NetworkResult<bool> {
+        NetworkHandle::set_application_score(self, peer_id, new_score).await
+    }
+
+    async fn peer_versions(&self) -> NetworkResult<HashMap<PeerId, NodeVersion>> {
+        NetworkHandle::peer_versions(self).await
+    }
+}
@@ -1,5 +1,6 @@
 //! Codec for encoding/decoding consensus network messages.
 
+use crate::types::CorrelationId;
 use async_trait::async_trait;
 use futures::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
 use libp2p::{request_response::Codec, StreamProtocol};
@@ -10,6 +11,7 @@ use std::{
     io::{Read as _, Write as _},
     marker::PhantomData,
 };
+use tn_config::LEGACY_REQ_RES_PROTOCOL;
 use tn_types::encode_into_buffer;
 
 #[cfg(test)]
@@ -199,3 +201,102 @@ where
         self.encode_message(io, res).await
     }
 }
+
+/// A consensus request and the id that correlates it in the logs of both peers.
+#[derive(Clone, Debug)]
+pub struct CorrelatedRequest<Req> {
+    /// The id chosen by the sender.
+    ///
+    /// `None` if the request was received on the [LEGACY_REQ_RES_PROTOCOL].
+    pub correlation_id: Option<CorrelationId>,
+    /// The request.
+    pub request: Req,
+}
+
+/// The codec for consensus requests, which writes the sender's [CorrelationId] before each
+/// request.
+///
+/// Peers that only speak the [LEGACY_REQ_RES_PROTOCOL] exchange requests without the id.
+#[derive(Clone, Debug)]
+pub struct CorrelatedCodec<Req, Res> {
+    /// The codec for the messages.
+    inner: TNCodec<Req, Res>,
+}
+
+impl<Req, Res> CorrelatedCodec<Req, Res> {
+    /// Create a new instance of Self.
+    pub fn new(max_chunk_size: usize) -> Self {
+        Self { inner: TNCodec::new(max_chunk_size) }
+    }
+}
+
+#[async_trait]
+impl<Req, Res> Codec for CorrelatedCodec<Req, Res>
+where
+    Req: TNMessage,
+    Res: TNMessage,
+{
+    type Protocol = StreamProtocol;
+    type Request = CorrelatedRequest<Req>;
+    type Response = Res;
+
+    async fn read_request<T>(
+        &mut self,
+        protocol: &Self::Protocol,
+        io: &mut T,
+    ) -> std::io::Result<Self::Request>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        let correlation_id = if protocol.as_ref() == LEGACY_REQ_RES_PROTOCOL {
+            None
+        } else {
+            let mut id = [0; 16];
+            io.read_exact(&mut id).await?;
+            Some(CorrelationId::from_be_bytes(id))
+        };
+        let request = self.inner.decode_message(io).await?;
+        Ok(CorrelatedRequest { correlation_id, request })
+    }
+
+    async fn read_response<T>(
+        &mut self,
+        _: &Self::Protocol,
+        io: &mut T,
+    ) -> std::io::Result<Self::Response>
+    where
+        T: AsyncRead + Unpin + Send,
+    {
+        self.inner.decode_message(io).await
+    }
+
+    async fn write_request<T>(
+        &mut self,
+        protocol: &Self::Protocol,
+        io: &mut T,
+        req: Self::Request,
+    ) -> std::io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        if protocol.as_ref() != LEGACY_REQ_RES_PROTOCOL {
+            let id = req
+                .correlation_id
+                .ok_or_else(|| std::io::Error::other("request without correlation id"))?;
+            io.write_all(&id.to_be_bytes()).await?;
+        }
+        self.inner.encode_message(io, req.request).await
+    }
+
+    async fn write_response<T>(
+        &mut self,
+        _: &Self::Protocol,
+        io: &mut T,
+        res: Self::Response,
+    ) -> std::io::Result<()>
+    where
+        T: AsyncWrite + Unpin + Send,
+    {
+        self.inner.encode_message(io, res).await
+    }
+}
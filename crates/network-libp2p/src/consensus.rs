@@ -3,35 +3,44 @@
 //! This network is used by workers and primaries to reliably send consensus messages.
 
 use crate::{
-    codec::{TNCodec, TNMessage},
+    chaos::{ChaosQueue, NetworkChaos},
+    codec::{CorrelatedCodec, CorrelatedRequest, TNMessage},
     error::NetworkError,
+    metrics::NETWORK_METRICS,
+    peer_exchange::{
+        decode_peer_record, sign_peer_record, PeerExchange, PeerExchangeCodec, PeerExchangeRequest,
+        PeerExchangeResponse, PEER_EXCHANGE_PROTOCOL,
+    },
+    peer_history::PeerHistory,
     send_or_log_error,
-    types::{NetworkCommand, NetworkEvent, NetworkHandle, NetworkResult},
+    types::{CorrelationId, NetworkCommand, NetworkEvent, NetworkHandle, NetworkResult},
+    version::{NodeVersion, PROTOCOL_VERSION},
 };
 use futures::StreamExt as _;
 use libp2p::{
     gossipsub::{
         self, Event as GossipEvent, IdentTopic, Message as GossipMessage, MessageAcceptance,
     },
+    identify::{self, Event as IdentifyEvent},
     multiaddr::Protocol,
     request_response::{
         self, Codec, Event as ReqResEvent, InboundFailure as ReqResInboundFailure,
-        InboundRequestId, OutboundRequestId,
+        InboundRequestId, OutboundRequestId, ProtocolSupport,
     },
     swarm::{NetworkBehaviour, SwarmEvent},
-    PeerId, Swarm, SwarmBuilder,
+    Multiaddr, PeerId, Swarm, SwarmBuilder, TransportError,
 };
 use std::{
     collections::{hash_map, HashMap, HashSet, VecDeque},
     time::Duration,
 };
 use tn_config::{ConsensusConfig, LibP2pConfig};
-use tn_types::NetworkKeypair;
+use tn_types::{listen_multiaddrs, NetworkKeypair, PeerEventKind, PeerNetwork};
 use tokio::sync::{
     mpsc::{self, Receiver, Sender},
     oneshot,
 };
-use tracing::{error, info, instrument, trace, warn};
+use tracing::{debug, error, info, instrument, trace, warn, Level};
 
 #[cfg(test)]
 #[path = "tests/network_tests.rs"]
@@ -39,7 +48,7 @@ mod network_tests;
 
 /// Custom network libp2p behaviour type for Telcoin Network.
 ///
-/// The behavior includes gossipsub and request-response.
+/// The behavior includes gossipsub, request-response, identify, and peer exchange.
 #[derive(NetworkBehaviour)]
 pub struct TNBehavior<C>
 where
@@ -49,6 +58,14 @@ where
     pub(crate) gossipsub: gossipsub::Behaviour,
     /// The request-response network behavior.
     pub(crate) req_res: request_response::Behaviour<C>,
+    /// The identify network behavior.
+    ///
+    /// Peers exchange their software version and protocol features.
+    pub(crate) identify: identify::Behaviour,
+    /// The peer exchange behavior.
+    ///
+    /// Peers share signed records of the addresses they know.
+    pub(crate) pex: request_response::Behaviour<PeerExchangeCodec>,
 }
 
 impl<C> TNBehavior<C>
@@ -56,8 +73,13 @@ where
     C: Codec + Send + Clone + 'static,
 {
     /// Create a new instance of Self.
-    pub fn new(gossipsub: gossipsub::Behaviour, req_res: request_response::Behaviour<C>) -> Self {
-        Self { gossipsub, req_res }
+    pub fn new(
+        gossipsub: gossipsub::Behaviour,
+        req_res: request_response::Behaviour<C>,
+        identify: identify::Behaviour,
+        pex: request_response::Behaviour<PeerExchangeCodec>,
+    ) -> Self {
+        Self { gossipsub, req_res, identify, pex }
     }
 }
 
@@ -74,7 +96,7 @@ where
     Res: TNMessage,
 {
     /// The gossip network for flood publishing sealed batches.
-    swarm: Swarm<TNBehavior<TNCodec<Req, Res>>>,
+    swarm: Swarm<TNBehavior<CorrelatedCodec<Req, Res>>>,
     /// The subscribed gossip network topics.
     topics: Vec<IdentTopic>,
     /// The stream for forwarding network events.
@@ -109,6 +131,22 @@ where
     /// This explicitly tracked and is a VecDeque so we can use to round robin requests without an
     /// explicit peer.
     connected_peers: VecDeque<PeerId>,
+    /// The software version this node advertises.
+    local_version: NodeVersion,
+    /// The software versions connected peers advertised.
+    peer_versions: HashMap<PeerId, NodeVersion>,
+    /// The label each connected peer is counted under in the peer versions metric.
+    peer_version_labels: HashMap<PeerId, String>,
+    /// Whether a quorum of the committee runs a newer version incompatible with this node.
+    upgrade_required: bool,
+    /// The node's network keypair used to sign its peer record.
+    keypair: NetworkKeypair,
+    /// Signed peer records collected through peer exchange.
+    peer_exchange: PeerExchange,
+    /// Outbound messages delayed by chaos mode, only enabled on staging networks.
+    chaos: ChaosQueue<Req, Res>,
+    /// Records peer connectivity events to storage, if set.
+    peer_history: Option<PeerHistory>,
 }
 
 impl<Req, Res> ConsensusNetwork<Req, Res>
@@ -120,6 +158,7 @@ where
     pub fn new_for_primary<DB>(
         config: &ConsensusConfig<DB>,
         event_stream: mpsc::Sender<NetworkEvent<Req, Res>>,
+        local_version: NodeVersion,
     ) -> NetworkResult<Self>
     where
         DB: tn_types::database_traits::Database,
@@ -127,13 +166,27 @@ where
         let topics = vec![IdentTopic::new("tn-primary")];
         let network_key = config.key_config().primary_network_keypair().clone();
         let authorized_publishers = config.committee_peer_ids();
-        Self::new(config, event_stream, topics, network_key, authorized_publishers)
+        let peer_history = PeerHistory::new(
+            config.node_storage().clone(),
+            PeerNetwork::Primary,
+            config.network_config().libp2p_config().max_peer_history_events,
+        );
+        Ok(Self::new(
+            config,
+            event_stream,
+            topics,
+            network_key,
+            authorized_publishers,
+            local_version,
+        )?
+        .with_peer_history(peer_history))
     }
 
     /// Convenience method for spawning a worker network instance.
     pub fn new_for_worker<DB>(
         config: &ConsensusConfig<DB>,
         event_stream: mpsc::Sender<NetworkEvent<Req, Res>>,
+        local_version: NodeVersion,
     ) -> NetworkResult<Self>
     where
         DB: tn_types::database_traits::Database,
@@ -142,16 +195,32 @@ where
         let network_key = config.key_config().worker_network_keypair().clone();
         let authorized_publishers =
             config.worker_cache().all_workers().iter().map(|(id, _)| *id).collect();
-        Self::new(config, event_stream, topics, network_key, authorized_publishers)
+        let peer_history = PeerHistory::new(
+            config.node_storage().clone(),
+            PeerNetwork::Worker,
+            config.network_config().libp2p_config().max_peer_history_events,
+        );
+        Ok(Self::new(
+            config,
+            event_stream,
+            topics,
+            network_key,
+            authorized_publishers,
+            local_version,
+        )?
+        .with_peer_history(peer_history))
     }
 
     /// Create a new instance of Self.
+    ///
+    /// The network advertises `local_version` to peers.
     pub fn new<DB>(
         consensus_config: &ConsensusConfig<DB>,
         event_stream: mpsc::Sender<NetworkEvent<Req, Res>>,
         topics: Vec<IdentTopic>,
         keypair: NetworkKeypair,
         authorized_publishers: HashSet<PeerId>,
+        local_version: NodeVersion,
     ) -> NetworkResult<Self>
     where
         DB: tn_types::database_traits::Database,
@@ -172,7 +241,7 @@ where
         )
         .map_err(NetworkError::GossipBehavior)?;
 
-        let tn_codec = TNCodec::<Req, Res>::new(
+        let tn_codec = CorrelatedCodec::<Req, Res>::new(
             consensus_config.network_config().libp2p_config().max_rpc_message_size,
         );
 
@@ -182,11 +251,24 @@ where
             request_response::Config::default(),
         );
 
+        let identify = identify::Behaviour::new(
+            identify::Config::new(PROTOCOL_VERSION.to_string(), keypair.public())
+                .with_agent_version(local_version.agent_version()),
+        );
+
+        let pex = request_response::Behaviour::with_codec(
+            PeerExchangeCodec::new(
+                consensus_config.network_config().libp2p_config().max_rpc_message_size,
+            ),
+            [(PEER_EXCHANGE_PROTOCOL, ProtocolSupport::Full)],
+            request_response::Config::default(),
+        );
+
         // create custom behavior
-        let behavior = TNBehavior::new(gossipsub, req_res);
+        let behavior = TNBehavior::new(gossipsub, req_res, identify, pex);
 
         // create swarm
-        let swarm = SwarmBuilder::with_existing_identity(keypair)
+        let swarm = SwarmBuilder::with_existing_identity(keypair.clone())
             .with_tokio()
             .with_quic_config(|mut config| {
                 config.handshake_timeout =
@@ -203,6 +285,9 @@ where
                     consensus_config.network_config().quic_config().max_connection_data;
                 config
             })
+            // resolve `/dns` and `/dnsaddr` addresses each time a peer is dialed
+            .with_dns()
+            .map_err(|_| NetworkError::BuildSwarm)?
             .with_behaviour(|_| behavior)
             .map_err(|_| NetworkError::BuildSwarm)?
             .with_swarm_config(|c| {
@@ -214,6 +299,15 @@ where
 
         let (handle, commands) = tokio::sync::mpsc::channel(100);
         let config = consensus_config.network_config().libp2p_config().clone();
+        let peer_exchange =
+            PeerExchange::new(config.max_peer_exchange_records, config.peer_exchange_record_ttl);
+        #[cfg(feature = "chaos")]
+        let chaos = NetworkChaos::from_config(&consensus_config.config().chaos);
+        #[cfg(not(feature = "chaos"))]
+        let chaos: Option<NetworkChaos> = None;
+        if chaos.is_some() {
+            warn!(target: "network", ?chaos, "chaos mode delays and drops outbound messages");
+        }
 
         Ok(Self {
             swarm,
@@ -227,9 +321,30 @@ where
             inbound_requests: Default::default(),
             config,
             connected_peers: VecDeque::new(),
+            local_version,
+            peer_versions: Default::default(),
+            peer_version_labels: Default::default(),
+            upgrade_required: false,
+            keypair,
+            peer_exchange,
+            chaos: ChaosQueue::new(chaos),
+            peer_history: None,
         })
     }
 
+    /// Record peer connectivity events to storage.
+    pub fn with_peer_history(mut self, peer_history: PeerHistory) -> Self {
+        self.peer_history = Some(peer_history);
+        self
+    }
+
+    /// Record a peer connectivity event if the history is enabled.
+    fn record_peer_event(&self, peer: PeerId, kind: PeerEventKind) {
+        if let Some(peer_history) = self.peer_history.as_ref() {
+            peer_history.record(peer, kind);
+        }
+    }
+
     /// Return a [NetworkHandle] to send commands to this network.
     pub fn network_handle(&self) -> NetworkHandle<Req, Res> {
         NetworkHandle::new(self.handle.clone())
@@ -241,12 +356,17 @@ where
             tokio::select! {
                 event = self.swarm.select_next_some() => self.process_event(event).await?,
                 command = self.commands.recv() => match command {
-                    Some(c) => self.process_command(c),
+                    Some(c) => {
+                        if let Some(c) = self.chaos.disrupt(c) {
+                            self.process_command(c);
+                        }
+                    }
                     None => {
                         info!(target: "network", topics=?self.topics, "subscriber shutting down...");
                         return Ok(())
                     }
-                }
+                },
+                command = self.chaos.next_delayed() => self.process_command(command),
             }
         }
     }
@@ -255,12 +375,14 @@ where
     #[instrument(level = "trace", target = "network::events", skip(self), fields(topics = ?self.topics))]
     async fn process_event(
         &mut self,
-        event: SwarmEvent<TNBehaviorEvent<TNCodec<Req, Res>>>,
+        event: SwarmEvent<TNBehaviorEvent<CorrelatedCodec<Req, Res>>>,
     ) -> NetworkResult<()> {
         match event {
             SwarmEvent::Behaviour(behavior) => match behavior {
                 TNBehaviorEvent::Gossipsub(event) => self.process_gossip_event(event)?,
                 TNBehaviorEvent::ReqRes(event) => self.process_reqres_event(event)?,
+                TNBehaviorEvent::Identify(event) => self.process_identify_event(event),
+                TNBehaviorEvent::Pex(event) => self.process_pex_event(event),
             },
             SwarmEvent::ConnectionEstablished {
                 peer_id,
@@ -270,10 +392,19 @@ where
                 concurrent_dial_errors,
                 established_in,
             } => {
+                // record the event before resolving dials so callers see it
+                if num_established.get() == 1 {
+                    self.record_peer_event(peer_id, PeerEventKind::Connected);
+                }
                 if endpoint.is_dialer() {
                     if let Some(sender) = self.pending_dials.remove(&peer_id) {
                         send_or_log_error!(sender, Ok(()), "ConnectionEstablished", peer = peer_id);
                     }
+                    // the dialer starts peer exchange so each connection only exchanges once
+                    if num_established.get() == 1 {
+                        let request = PeerExchangeRequest { record: self.local_peer_record() };
+                        self.swarm.behaviour_mut().pex.send_request(&peer_id, request);
+                    }
                 }
                 if !self.connected_peers.contains(&peer_id) {
                     self.connected_peers.push_back(peer_id);
@@ -318,6 +449,8 @@ where
 
                 // handle complete peer disconnect
                 if num_established == 0 {
+                    let cause = cause.map(|error| error.to_string());
+                    self.record_peer_event(peer_id, PeerEventKind::Disconnected(cause));
                     tracing::debug!(target:"network::events", pending=?self.outbound_requests.len());
                     // clean up any pending requests for this peer
                     //
@@ -326,6 +459,7 @@ where
                     // sanity check to prevent the HashMap from growing indefinitely when peers
                     // disconnect after a request is made and the PeerId is lost.
                     self.outbound_requests.retain(|_, sender| !sender.is_closed());
+                    self.remove_peer_version(&peer_id);
 
                     // TODO: schedule reconnection attempt?
                     if self.authorized_publishers.contains(&peer_id) {
@@ -334,6 +468,7 @@ where
                 }
             }
             SwarmEvent::OutgoingConnectionError { peer_id: Some(peer_id), error, .. } => {
+                self.record_peer_event(peer_id, PeerEventKind::DialFailed(error.to_string()));
                 if let Some(sender) = self.pending_dials.remove(&peer_id) {
                     send_or_log_error!(sender, Err(error.into()), "OutgoingConnectionError");
                 }
@@ -379,7 +514,18 @@ where
                 send_or_log_error!(reply, Ok(()), "UpdateAuthorizedPublishers");
             }
             NetworkCommand::StartListening { multiaddr, reply } => {
-                let res = self.swarm.listen_on(multiaddr);
+                // nodes announce DNS names but listen on local interfaces
+                let mut addrs = listen_multiaddrs(&multiaddr).into_iter();
+                let res = match addrs.next() {
+                    Some(addr) => self.swarm.listen_on(addr),
+                    None => Err(TransportError::MultiaddrNotSupported(multiaddr)),
+                };
+                // dual-stack listeners are best effort since hosts may not support IPv6
+                for addr in addrs {
+                    if let Err(e) = self.swarm.listen_on(addr.clone()) {
+                        warn!(target: "network", ?addr, ?e, "failed to listen on address");
+                    }
+                }
                 send_or_log_error!(reply, res, "StartListening");
             }
             NetworkCommand::GetListener { reply } => {
@@ -422,6 +568,11 @@ where
                 send_or_log_error!(reply, peer_id, "LocalPeerId");
             }
             NetworkCommand::Publish { topic, msg, reply } => {
+                if tracing::enabled!(target: "network", Level::DEBUG) {
+                    let correlation_id =
+                        CorrelationId::for_gossip(self.swarm.local_peer_id(), &msg);
+                    debug!(target: "network", %correlation_id, %topic, "publishing gossip");
+                }
                 let res = self.swarm.behaviour_mut().gossipsub.publish(topic, msg);
                 send_or_log_error!(reply, res, "Publish");
             }
@@ -464,13 +615,16 @@ where
                 send_or_log_error!(reply, collection, "MeshPeers");
             }
             NetworkCommand::SendRequest { peer, request, reply } => {
+                let request = self.correlate_outbound_request(&peer, request);
                 let request_id = self.swarm.behaviour_mut().req_res.send_request(&peer, request);
                 self.outbound_requests.insert(request_id, reply);
             }
             NetworkCommand::SendRequestAny { request, reply } => {
                 self.connected_peers.rotate_left(1);
-                if let Some(peer) = self.connected_peers.front() {
-                    let request_id = self.swarm.behaviour_mut().req_res.send_request(peer, request);
+                if let Some(peer) = self.connected_peers.front().copied() {
+                    let request = self.correlate_outbound_request(&peer, request);
+                    let request_id =
+                        self.swarm.behaviour_mut().req_res.send_request(&peer, request);
                     self.outbound_requests.insert(request_id, reply);
                 } else {
                     // Ignore error since this means other end lost interest and we don't really
@@ -486,9 +640,26 @@ where
                 let count = self.outbound_requests.len();
                 send_or_log_error!(reply, count, "SendResponse");
             }
+            NetworkCommand::PeerVersions { reply } => {
+                let versions = self.peer_versions.clone();
+                send_or_log_error!(reply, versions, "PeerVersions");
+            }
+            NetworkCommand::KnownPeers { reply } => {
+                let peers = self.peer_exchange.known_peers();
+                send_or_log_error!(reply, peers, "KnownPeers");
+            }
         }
     }
 
+    /// Assign a new correlation id to a request and log it before the request is sent.
+    ///
+    /// The peer that handles the request logs the same id.
+    fn correlate_outbound_request(&self, peer: &PeerId, request: Req) -> CorrelatedRequest<Req> {
+        let correlation_id = CorrelationId::random();
+        debug!(target: "network", %correlation_id, ?peer, "sending request");
+        CorrelatedRequest { correlation_id: Some(correlation_id), request }
+    }
+
     /// Process gossip events.
     fn process_gossip_event(&mut self, event: GossipEvent) -> NetworkResult<()> {
         match event {
@@ -535,15 +706,23 @@ where
     }
 
     /// Process req/res events.
-    fn process_reqres_event(&mut self, event: ReqResEvent<Req, Res>) -> NetworkResult<()> {
+    fn process_reqres_event(
+        &mut self,
+        event: ReqResEvent<CorrelatedRequest<Req>, Res>,
+    ) -> NetworkResult<()> {
         match event {
             ReqResEvent::Message { peer, message, connection_id: _ } => {
                 match message {
                     request_response::Message::Request { request_id, request, channel } => {
+                        let CorrelatedRequest { correlation_id, request } = request;
+                        // peers on the legacy protocol don't send an id
+                        let correlation_id = correlation_id
+                            .unwrap_or_else(|| CorrelationId::for_request(&peer, &request));
                         let (notify, cancel) = oneshot::channel();
                         // forward request to handler without blocking other events
                         if let Err(e) = self.event_stream.try_send(NetworkEvent::Request {
                             peer,
+                            correlation_id,
                             request,
                             channel,
                             cancel,
@@ -599,6 +778,190 @@ where
         Ok(())
     }
 
+    /// Process peer exchange events.
+    ///
+    /// Requests are answered with this node's record and the records it knows. Nodes outside the
+    /// committee dial peers from responses until they reach the target number of peers.
+    fn process_pex_event(&mut self, event: ReqResEvent<PeerExchangeRequest, PeerExchangeResponse>) {
+        match event {
+            ReqResEvent::Message { peer, message, .. } => match message {
+                request_response::Message::Request { request, channel, .. } => {
+                    // only keep the requester's own record
+                    if let Some(record) = request.record {
+                        if decode_peer_record(&record).is_some_and(|r| r.peer_id() == peer) {
+                            self.peer_exchange.insert(&record);
+                        }
+                    }
+                    let mut records: Vec<_> = self.local_peer_record().into_iter().collect();
+                    records.extend(self.peer_exchange.records_for(&peer));
+                    let response = PeerExchangeResponse { records };
+                    if self.swarm.behaviour_mut().pex.send_response(channel, response).is_err() {
+                        debug!(target: "network::pex", ?peer, "peer exchange request dropped");
+                    }
+                }
+                request_response::Message::Response { response, .. } => {
+                    let local_peer_id = *self.swarm.local_peer_id();
+                    for record in response.records {
+                        let Some(record) = self.peer_exchange.insert(&record) else {
+                            continue;
+                        };
+                        if record.peer_id() != local_peer_id {
+                            self.dial_discovered_peer(record.peer_id(), record.addresses());
+                        }
+                    }
+                }
+            },
+            ReqResEvent::OutboundFailure { peer, error, .. } => {
+                // peers running older versions do not support peer exchange
+                debug!(target: "network::pex", ?peer, ?error, "peer exchange failed");
+            }
+            ReqResEvent::InboundFailure { peer, error, .. } => {
+                debug!(target: "network::pex", ?peer, ?error, "peer exchange request failed");
+            }
+            ReqResEvent::ResponseSent { .. } => {}
+        }
+    }
+
+    /// Sign a peer record for the addresses this node is reachable at.
+    ///
+    /// Dual-stack nodes listen on both address families, so the record advertises both.
+    fn local_peer_record(&self) -> Option<Vec<u8>> {
+        let addresses = self
+            .swarm
+            .external_addresses()
+            .chain(self.swarm.listeners())
+            .cloned()
+            .collect::<Vec<_>>();
+        sign_peer_record(&self.keypair, addresses)
+    }
+
+    /// Dial a peer discovered through peer exchange.
+    ///
+    /// Committee members already dial each other, so only nodes outside the committee dial
+    /// discovered peers.
+    fn dial_discovered_peer(&mut self, peer_id: PeerId, addresses: &[Multiaddr]) {
+        let local_peer_id = self.swarm.local_peer_id();
+        if self.authorized_publishers.contains(local_peer_id)
+            || self.swarm.is_connected(&peer_id)
+            || self.connected_peers.len() >= self.config.peer_exchange_target_peers
+        {
+            return;
+        }
+        for addr in addresses {
+            self.swarm.add_peer_address(peer_id, addr.clone());
+        }
+        if let Err(e) = self.swarm.dial(peer_id) {
+            debug!(target: "network::pex", ?peer_id, ?e, "failed to dial discovered peer");
+        }
+    }
+
+    /// Process identify events.
+    ///
+    /// Peers' software versions are tracked to warn operators when the committee upgrades to a
+    /// version this node can't follow.
+    fn process_identify_event(&mut self, event: IdentifyEvent) {
+        let IdentifyEvent::Received { peer_id, info, .. } = event else {
+            return;
+        };
+
+        if info.protocol_version != PROTOCOL_VERSION {
+            warn!(
+                target: "network::version",
+                ?peer_id,
+                theirs = %info.protocol_version,
+                ours = PROTOCOL_VERSION,
+                "peer uses a different protocol version"
+            );
+        }
+
+        let version = NodeVersion::from_agent_version(&info.agent_version);
+        self.set_peer_version_label(peer_id, NodeVersion::metrics_label(version.as_ref()));
+        let Some(version) = version else {
+            debug!(
+                target: "network::version",
+                ?peer_id,
+                agent = %info.agent_version,
+                "peer runs unknown software"
+            );
+            if self.peer_versions.remove(&peer_id).is_some() {
+                self.check_committee_versions();
+            }
+            return;
+        };
+
+        if self.local_version.is_outdated_by(&version) {
+            warn!(
+                target: "network::version",
+                ?peer_id,
+                theirs = %version,
+                ours = %self.local_version,
+                "peer runs a newer incompatible version"
+            );
+        }
+
+        self.peer_versions.insert(peer_id, version);
+        self.check_committee_versions();
+    }
+
+    /// Count the peer under `label` in the peer versions metric.
+    fn set_peer_version_label(&mut self, peer_id: PeerId, label: String) {
+        let network = self.network_name();
+        NETWORK_METRICS.peer_versions.with_label_values(&[&network, &label]).inc();
+        if let Some(old) = self.peer_version_labels.insert(peer_id, label) {
+            NETWORK_METRICS.peer_versions.with_label_values(&[&network, &old]).dec();
+        }
+    }
+
+    /// Stop tracking the version of a disconnected peer.
+    fn remove_peer_version(&mut self, peer_id: &PeerId) {
+        if let Some(old) = self.peer_version_labels.remove(peer_id) {
+            NETWORK_METRICS.peer_versions.with_label_values(&[&self.network_name(), &old]).dec();
+        }
+        if self.peer_versions.remove(peer_id).is_some() {
+            self.check_committee_versions();
+        }
+    }
+
+    /// Warn if a quorum of the committee runs a newer version incompatible with this node.
+    fn check_committee_versions(&mut self) {
+        let outdated_by = self
+            .peer_versions
+            .iter()
+            .filter(|(peer_id, version)| {
+                self.authorized_publishers.contains(peer_id)
+                    && self.local_version.is_outdated_by(version)
+            })
+            .count();
+        // authorized publishers are the committee (including this node)
+        let quorum = self.authorized_publishers.len() * 2 / 3 + 1;
+        let upgrade_required = outdated_by >= quorum;
+
+        NETWORK_METRICS
+            .incompatible_committee_peers
+            .with_label_values(&[&self.network_name()])
+            .set(outdated_by as i64);
+        NETWORK_METRICS
+            .upgrade_required
+            .with_label_values(&[&self.network_name()])
+            .set(upgrade_required as i64);
+
+        if upgrade_required && !self.upgrade_required {
+            error!(
+                target: "network::version",
+                outdated_by,
+                quorum,
+                ours = %self.local_version,
+                "a quorum of the committee runs a newer incompatible version - upgrade this node"
+            );
+        }
+        self.upgrade_required = upgrade_required;
+    }
+
+    /// The name of this network for metrics.
+    fn network_name(&self) -> String {
+        self.topics.first().map(ToString::to_string).unwrap_or_default()
+    }
+
     /// Specific logic to accept gossip messages.
     ///
     /// Messages are only published by current committee nodes and must be within max size.
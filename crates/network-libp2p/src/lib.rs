@@ -1,14 +1,27 @@
 // SPDX-License-Identifier: MIT or Apache-2.0
 //! Peer-to-peer network interface for Telcoin Network built using libp2p.
 
+mod chaos;
+mod client;
 mod codec;
 mod consensus;
 pub mod error;
+mod memory;
+mod metrics;
+mod peer_exchange;
+mod peer_history;
 pub mod types;
+mod version;
 
 // export types
-pub use codec::{TNCodec, TNMessage};
+pub use chaos::NetworkChaos;
+pub use client::NetworkClient;
+pub use codec::{CorrelatedCodec, CorrelatedRequest, TNCodec, TNMessage};
 pub use consensus::ConsensusNetwork;
+pub use memory::MemoryNetwork;
+pub use peer_exchange::{PeerExchangeRequest, PeerExchangeResponse, PEER_EXCHANGE_PROTOCOL};
+pub use peer_history::PeerHistory;
+pub use version::{NodeVersion, PROTOCOL_FEATURES, PROTOCOL_VERSION, WORKER_PING_FEATURE};
 
 // re-export specific libp2p types
 pub use libp2p::{
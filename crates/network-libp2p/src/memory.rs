@@ -0,0 +1,240 @@
+//! In-memory network for tests that don't run a swarm.
+
+use crate::{
+    client::NetworkClient,
+    codec::TNMessage,
+    error::NetworkError,
+    types::{IdentTopic, MessageId, NetworkResult},
+    NodeVersion,
+};
+use async_trait::async_trait;
+use libp2p::{gossipsub::TopicHash, request_response::ResponseChannel, Multiaddr, PeerId};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+    sync::{Arc, Mutex},
+};
+use tn_types::keccak256;
+use tokio::sync::oneshot;
+
+/// Answers the requests sent through a [MemoryNetwork] on behalf of the peer.
+type Responder<Req, Res> = Arc<dyn Fn(PeerId, Req) -> NetworkResult<Res> + Send + Sync>;
+
+/// The messages and peers seen by a [MemoryNetwork].
+struct MemoryNetworkState<Req> {
+    /// The connected peers, in the order they connected.
+    connected_peers: Vec<PeerId>,
+    /// The subscribed topics.
+    subscriptions: HashSet<TopicHash>,
+    /// The published gossip by topic, oldest first.
+    published: Vec<(TopicHash, Vec<u8>)>,
+    /// The requests sent and the peer each was sent to, oldest first.
+    requests: Vec<(PeerId, Req)>,
+    /// The application score set for each peer.
+    scores: HashMap<PeerId, f64>,
+    /// The index of the next peer to send a request to any peer.
+    next_peer: usize,
+}
+
+/// A network that never leaves the process.
+///
+/// Dialing a peer connects it, gossip is recorded, and requests are answered by the responder.
+/// Clones share the same state, so tests keep a clone to inspect what a component sent.
+pub struct MemoryNetwork<Req, Res> {
+    /// This node's peer id.
+    local_peer_id: PeerId,
+    /// Answers requests, requests fail if not set.
+    responder: Option<Responder<Req, Res>>,
+    /// The version every peer reports, this build's.
+    local_version: NodeVersion,
+    /// The shared state.
+    state: Arc<Mutex<MemoryNetworkState<Req>>>,
+}
+
+impl<Req, Res> MemoryNetwork<Req, Res>
+where
+    Req: TNMessage,
+    Res: TNMessage,
+{
+    /// Create a new instance of Self with a random local peer id and no connected peers.
+    pub fn new() -> Self {
+        let state = MemoryNetworkState {
+            connected_peers: Vec::new(),
+            subscriptions: HashSet::new(),
+            published: Vec::new(),
+            requests: Vec::new(),
+            scores: HashMap::new(),
+            next_peer: 0,
+        };
+        Self {
+            local_peer_id: PeerId::random(),
+            responder: None,
+            local_version: NodeVersion::local(env!("CARGO_PKG_VERSION"))
+                .expect("crate version is valid semver"),
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    /// Answer every request with `responder`.
+    pub fn with_responder<F>(mut self, responder: F) -> Self
+    where
+        F: Fn(PeerId, Req) -> NetworkResult<Res> + Send + Sync + 'static,
+    {
+        self.responder = Some(Arc::new(responder));
+        self
+    }
+
+    /// Connect a peer.
+    pub fn connect(&self, peer_id: PeerId) {
+        let mut state = self.state.lock().expect("memory network lock poisoned");
+        if !state.connected_peers.contains(&peer_id) {
+            state.connected_peers.push(peer_id);
+        }
+    }
+
+    /// Disconnect a peer.
+    pub fn disconnect(&self, peer_id: &PeerId) {
+        let mut state = self.state.lock().expect("memory network lock poisoned");
+        state.connected_peers.retain(|peer| peer != peer_id);
+    }
+
+    /// Return the gossip published so far, oldest first.
+    pub fn published(&self) -> Vec<(TopicHash, Vec<u8>)> {
+        self.state.lock().expect("memory network lock poisoned").published.clone()
+    }
+
+    /// Return the requests sent so far with the peer each was sent to, oldest first.
+    pub fn requests(&self) -> Vec<(PeerId, Req)> {
+        self.state.lock().expect("memory network lock poisoned").requests.clone()
+    }
+
+    /// Return the application score set for a peer.
+    pub fn application_score(&self, peer_id: &PeerId) -> Option<f64> {
+        self.state.lock().expect("memory network lock poisoned").scores.get(peer_id).copied()
+    }
+
+    /// Record the request and answer it.
+    fn respond(&self, peer: PeerId, request: Req) -> oneshot::Receiver<NetworkResult<Res>> {
+        self.state
+            .lock()
+            .expect("memory network lock poisoned")
+            .requests
+            .push((peer, request.clone()));
+        let response = match self.responder.as_ref() {
+            Some(responder) => responder(peer, request),
+            None => Err(NetworkError::RPCError("memory network has no responder".to_string())),
+        };
+        let (reply, to_caller) = oneshot::channel();
+        let _ = reply.send(response);
+        to_caller
+    }
+}
+
+impl<Req, Res> Default for MemoryNetwork<Req, Res>
+where
+    Req: TNMessage,
+    Res: TNMessage,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Req, Res> Clone for MemoryNetwork<Req, Res> {
+    fn clone(&self) -> Self {
+        Self {
+            local_peer_id: self.local_peer_id,
+            responder: self.responder.clone(),
+            local_version: self.local_version.clone(),
+            state: self.state.clone(),
+        }
+    }
+}
+
+impl<Req, Res> fmt::Debug for MemoryNetwork<Req, Res> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MemoryNetwork")
+            .field("local_peer_id", &self.local_peer_id)
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<Req, Res> NetworkClient<Req, Res> for MemoryNetwork<Req, Res>
+where
+    Req: TNMessage,
+    Res: TNMessage,
+{
+    async fn dial(&self, peer_id: PeerId, _peer_addr: Multiaddr) -> NetworkResult<()> {
+        self.connect(peer_id);
+        Ok(())
+    }
+
+    async fn connected_peers(&self) -> NetworkResult<Vec<PeerId>> {
+        Ok(self.state.lock().expect("memory network lock poisoned").connected_peers.clone())
+    }
+
+    async fn local_peer_id(&self) -> NetworkResult<PeerId> {
+        Ok(self.local_peer_id)
+    }
+
+    async fn subscribe(&self, topic: IdentTopic) -> NetworkResult<bool> {
+        let mut state = self.state.lock().expect("memory network lock poisoned");
+        Ok(state.subscriptions.insert(topic.hash()))
+    }
+
+    async fn publish(&self, topic: IdentTopic, msg: Vec<u8>) -> NetworkResult<MessageId> {
+        let id = MessageId::new(keccak256(&msg).as_slice());
+        self.state
+            .lock()
+            .expect("memory network lock poisoned")
+            .published
+            .push((topic.hash(), msg));
+        Ok(id)
+    }
+
+    async fn send_request(
+        &self,
+        request: Req,
+        peer: PeerId,
+    ) -> NetworkResult<oneshot::Receiver<NetworkResult<Res>>> {
+        Ok(self.respond(peer, request))
+    }
+
+    async fn send_request_any(
+        &self,
+        request: Req,
+    ) -> NetworkResult<oneshot::Receiver<NetworkResult<Res>>> {
+        let peer = {
+            let mut state = self.state.lock().expect("memory network lock poisoned");
+            if state.connected_peers.is_empty() {
+                return Err(NetworkError::NoPeers);
+            }
+            // round robin like the swarm
+            let index = state.next_peer % state.connected_peers.len();
+            state.next_peer = index + 1;
+            state.connected_peers[index]
+        };
+        Ok(self.respond(peer, request))
+    }
+
+    async fn send_response(
+        &self,
+        _response: Res,
+        _channel: ResponseChannel<Res>,
+    ) -> NetworkResult<()> {
+        // response channels only come from the swarm
+        Ok(())
+    }
+
+    async fn set_application_score(&self, peer_id: PeerId, new_score: f64) -> NetworkResult<bool> {
+        self.state.lock().expect("memory network lock poisoned").scores.insert(peer_id, new_score);
+        Ok(true)
+    }
+
+    async fn peer_versions(&self) -> NetworkResult<HashMap<PeerId, NodeVersion>> {
+        // every peer runs this build
+        let state = self.state.lock().expect("memory network lock poisoned");
+        Ok(state.connected_peers.iter().map(|peer| (*peer, self.local_version.clone())).collect())
+    }
+}
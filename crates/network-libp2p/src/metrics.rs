@@ -0,0 +1,59 @@
+//! Metrics for the consensus networks.
+
+use prometheus::{default_registry, register_int_gauge_vec_with_registry, IntGaugeVec, Registry};
+use std::sync::LazyLock;
+
+/// The metrics shared by the primary and worker networks.
+///
+/// Both networks run in the same process, so metrics are registered once and labeled by the
+/// network's gossip topic.
+pub(crate) static NETWORK_METRICS: LazyLock<NetworkMetrics> =
+    LazyLock::new(NetworkMetrics::default);
+
+#[derive(Clone, Debug)]
+pub(crate) struct NetworkMetrics {
+    /// The number of connected peers running each software version.
+    pub(crate) peer_versions: IntGaugeVec,
+    /// The number of committee peers running a newer version incompatible with this node.
+    pub(crate) incompatible_committee_peers: IntGaugeVec,
+    /// Set to 1 when a quorum of the committee runs a newer incompatible version.
+    pub(crate) upgrade_required: IntGaugeVec,
+}
+
+impl NetworkMetrics {
+    fn try_new(registry: &Registry) -> Result<Self, prometheus::Error> {
+        Ok(Self {
+            peer_versions: register_int_gauge_vec_with_registry!(
+                "network_peer_versions",
+                "The number of connected peers running each software version",
+                &["network", "version"],
+                registry
+            )?,
+            incompatible_committee_peers: register_int_gauge_vec_with_registry!(
+                "network_incompatible_committee_peers",
+                "The number of committee peers running a newer version incompatible with this node",
+                &["network"],
+                registry
+            )?,
+            upgrade_required: register_int_gauge_vec_with_registry!(
+                "network_upgrade_required",
+                "Set to 1 when a quorum of the committee runs a newer incompatible version",
+                &["network"],
+                registry
+            )?,
+        })
+    }
+}
+
+impl Default for NetworkMetrics {
+    fn default() -> Self {
+        // registration only fails if the names are already taken, don't panic over metrics
+        match Self::try_new(default_registry()) {
+            Ok(metrics) => metrics,
+            Err(e) => {
+                tracing::warn!(target: "tn::metrics", ?e, "NetworkMetrics::try_new metrics error");
+                Self::try_new(&Registry::new()).expect("Prometheus error, are you using it wrong?")
+            }
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! Peer exchange (PEX) between nodes.
+//!
+//! Nodes that only know a seed address discover the rest of the network by asking connected peers
+//! for the addresses they know. Each address is shared as a libp2p [PeerRecord] signed by the
+//! peer it describes, so a node relaying records can not forge or redirect another peer's
+//! addresses. The dialer of every new connection sends a request with its own record and the
+//! other side responds with its record and the records it has collected.
+
+use crate::codec::{TNCodec, TNMessage};
+use libp2p::{
+    core::{PeerRecord, SignedEnvelope},
+    identity::Keypair,
+    multiaddr::Protocol,
+    Multiaddr, PeerId, StreamProtocol,
+};
+use serde::{Deserialize, Serialize};
+use std::{collections::HashMap, time::Duration};
+use tokio::time::Instant;
+use tracing::debug;
+
+#[cfg(test)]
+#[path = "tests/peer_exchange_tests.rs"]
+mod peer_exchange_tests;
+
+/// The request-response protocol for peer exchange.
+pub const PEER_EXCHANGE_PROTOCOL: StreamProtocol = StreamProtocol::new("/telcoin/pex/1.0.0");
+
+/// The codec for peer exchange messages.
+pub(crate) type PeerExchangeCodec = TNCodec<PeerExchangeRequest, PeerExchangeResponse>;
+
+/// Request the signed peer records a peer knows.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PeerExchangeRequest {
+    /// The requester's signed peer record, if it has a dialable address.
+    pub record: Option<Vec<u8>>,
+}
+
+/// Signed peer records known by the responder.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PeerExchangeResponse {
+    /// Protobuf encoded signed envelopes of [PeerRecord]s.
+    pub records: Vec<Vec<u8>>,
+}
+
+impl TNMessage for PeerExchangeRequest {}
+impl TNMessage for PeerExchangeResponse {}
+
+/// Decode and verify a signed peer record.
+///
+/// Returns `None` if the envelope is malformed, the signature is invalid, or the record has no
+/// addresses.
+pub(crate) fn decode_peer_record(bytes: &[u8]) -> Option<PeerRecord> {
+    let envelope = SignedEnvelope::from_protobuf_encoding(bytes).ok()?;
+    let record = PeerRecord::from_signed_envelope(envelope).ok()?;
+    (!record.addresses().is_empty()).then_some(record)
+}
+
+/// Sign a peer record for this node's dialable addresses.
+///
+/// Returns `None` if there are no dialable addresses.
+pub(crate) fn sign_peer_record(
+    keypair: &Keypair,
+    addresses: impl IntoIterator<Item = Multiaddr>,
+) -> Option<Vec<u8>> {
+    let addresses: Vec<_> = addresses.into_iter().filter(is_dialable).collect();
+    if addresses.is_empty() {
+        return None;
+    }
+    let record = PeerRecord::new(keypair, addresses)
+        .inspect_err(|e| debug!(target: "network::pex", ?e, "failed to sign peer record"))
+        .ok()?;
+    Some(record.into_signed_envelope().into_protobuf_encoding())
+}
+
+/// True if peers can dial the address.
+///
+/// Listeners bound to the unspecified address are not dialable.
+fn is_dialable(addr: &Multiaddr) -> bool {
+    !addr.iter().any(|protocol| match protocol {
+        Protocol::Ip4(ip) => ip.is_unspecified(),
+        Protocol::Ip6(ip) => ip.is_unspecified(),
+        _ => false,
+    })
+}
+
+/// A verified peer record and when it was received.
+#[derive(Debug)]
+struct KnownRecord {
+    /// The signed record.
+    record: PeerRecord,
+    /// When the record was received.
+    received: Instant,
+}
+
+/// The verified peer records collected from the network.
+#[derive(Debug)]
+pub(crate) struct PeerExchange {
+    /// The newest record for each peer.
+    records: HashMap<PeerId, KnownRecord>,
+    /// The maximum number of records kept and shared.
+    max_records: usize,
+    /// How long a record is kept after it is received.
+    ttl: Duration,
+}
+
+impl PeerExchange {
+    /// Create a new instance of [Self].
+    pub(crate) fn new(max_records: usize, ttl: Duration) -> Self {
+        Self { records: HashMap::new(), max_records, ttl }
+    }
+
+    /// Verify and keep a signed peer record.
+    ///
+    /// Returns the record if it is the newest for a peer. Expired records are removed first, and
+    /// the record received longest ago is evicted to make room for a new peer once `max_records`
+    /// are known.
+    pub(crate) fn insert(&mut self, bytes: &[u8]) -> Option<PeerRecord> {
+        let record = decode_peer_record(bytes)?;
+        let now = Instant::now();
+        self.records.retain(|_, known| now.duration_since(known.received) < self.ttl);
+        match self.records.get(&record.peer_id()) {
+            Some(known) if known.record.seq() >= record.seq() => return None,
+            None if self.records.len() >= self.max_records => {
+                let oldest = self
+                    .records
+                    .iter()
+                    .min_by_key(|(_, known)| known.received)
+                    .map(|(peer_id, _)| *peer_id)?;
+                self.records.remove(&oldest);
+            }
+            _ => {}
+        }
+        self.records
+            .insert(record.peer_id(), KnownRecord { record: record.clone(), received: now });
+        Some(record)
+    }
+
+    /// The records that have not expired.
+    fn live_records(&self) -> impl Iterator<Item = &PeerRecord> {
+        let now = Instant::now();
+        self.records
+            .values()
+            .filter(move |known| now.duration_since(known.received) < self.ttl)
+            .map(|known| &known.record)
+    }
+
+    /// The encoded records to share with `peer`, excluding its own record.
+    pub(crate) fn records_for(&self, peer: &PeerId) -> Vec<Vec<u8>> {
+        self.live_records()
+            .filter(|record| record.peer_id() != *peer)
+            .map(|record| record.to_signed_envelope().into_protobuf_encoding())
+            .collect()
+    }
+
+    /// The addresses of every known peer.
+    pub(crate) fn known_peers(&self) -> HashMap<PeerId, Vec<Multiaddr>> {
+        self.live_records().map(|record| (record.peer_id(), record.addresses().to_vec())).collect()
+    }
+}
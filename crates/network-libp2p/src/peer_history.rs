@@ -0,0 +1,41 @@
+//! Persisted history of peer connectivity.
+//!
+//! Each network records when peers connect, disconnect, and fail to dial in a ring buffer in the
+//! node's storage so the history survives the restarts that usually follow an incident.
+
+use libp2p::PeerId;
+use std::fmt;
+use tn_storage::PeerHistoryStore as _;
+use tn_types::{Database, PeerEvent, PeerEventKind, PeerNetwork};
+use tracing::warn;
+
+/// Records a network's peer connectivity events to the node's storage.
+pub struct PeerHistory {
+    /// The network the events are recorded for.
+    network: PeerNetwork,
+    /// Writes an event to storage.
+    write: Box<dyn Fn(&PeerEvent) + Send>,
+}
+
+impl PeerHistory {
+    /// Create a new instance of Self keeping at most `capacity` of the network's events in `db`.
+    pub fn new<DB: Database>(db: DB, network: PeerNetwork, capacity: u64) -> Self {
+        let write = move |event: &PeerEvent| {
+            if let Err(error) = db.record_peer_event(event, capacity) {
+                warn!(target: "network", ?error, ?event, "failed to record peer event");
+            }
+        };
+        Self { network, write: Box::new(write) }
+    }
+
+    /// Record an event for the peer that happened now.
+    pub(crate) fn record(&self, peer: PeerId, kind: PeerEventKind) {
+        (self.write)(&PeerEvent::new(self.network, peer, kind));
+    }
+}
+
+impl fmt::Debug for PeerHistory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PeerHistory").field("network", &self.network).finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,74 @@
+//! Tests for fault injection on outbound messages.
+
+mod common;
+use super::{ChaosQueue, NetworkChaos};
+use crate::types::NetworkCommand;
+use common::{TestWorkerRequest, TestWorkerResponse};
+use libp2p::gossipsub::IdentTopic;
+use std::time::Duration;
+use tokio::{sync::oneshot, time::Instant};
+
+#[cfg(feature = "chaos")]
+#[test]
+fn test_chaos_from_config() {
+    use tn_config::ChaosConfig;
+
+    assert_eq!(NetworkChaos::from_config(&ChaosConfig::default()), None);
+
+    let config = ChaosConfig {
+        enabled: true,
+        max_message_delay: Duration::from_secs(60),
+        message_drop_rate: 1.0,
+        ..Default::default()
+    };
+    assert_eq!(
+        NetworkChaos::from_config(&config),
+        Some(NetworkChaos::new(ChaosConfig::MAX_MESSAGE_DELAY, ChaosConfig::MAX_MESSAGE_DROP_RATE))
+    );
+}
+
+#[test]
+fn test_chaos_drops_messages() {
+    let chaos = NetworkChaos::new(Duration::ZERO, 1.0);
+    let mut queue = ChaosQueue::<TestWorkerRequest, TestWorkerResponse>::new(Some(chaos));
+
+    // the caller sees the request fail
+    let (reply, mut response) = oneshot::channel();
+    let request = TestWorkerRequest::MissingBatches(vec![]);
+    assert!(queue.disrupt(NetworkCommand::SendRequestAny { request, reply }).is_none());
+    assert!(response.try_recv().is_err());
+
+    // gossip looks published
+    let (reply, mut published) = oneshot::channel();
+    let topic = IdentTopic::new("test");
+    assert!(queue.disrupt(NetworkCommand::Publish { topic, msg: vec![1], reply }).is_none());
+    assert!(published.try_recv().expect("reply").is_ok());
+
+    // commands that don't send messages are never disrupted
+    let (reply, _peer) = oneshot::channel();
+    assert!(queue.disrupt(NetworkCommand::LocalPeerId { reply }).is_some());
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_chaos_delays_messages() {
+    // without chaos every command is processed immediately
+    let mut queue = ChaosQueue::<TestWorkerRequest, TestWorkerResponse>::new(None);
+    let (reply, _response) = oneshot::channel();
+    let request = TestWorkerRequest::MissingBatches(vec![]);
+    let command = NetworkCommand::SendRequestAny { request: request.clone(), reply };
+    assert!(queue.disrupt(command).is_some());
+
+    // delayed commands are held by the queue instead of blocking the caller
+    let chaos = NetworkChaos::new(Duration::from_millis(10), 0.0);
+    let mut queue = ChaosQueue::<TestWorkerRequest, TestWorkerResponse>::new(Some(chaos));
+    let start = Instant::now();
+    let (reply, _response) = oneshot::channel();
+    if let Some(command) = queue.disrupt(NetworkCommand::SendRequestAny { request, reply }) {
+        // the random delay was zero
+        assert!(matches!(command, NetworkCommand::SendRequestAny { .. }));
+        return;
+    }
+    let command = queue.next_delayed().await;
+    assert!(matches!(command, NetworkCommand::SendRequestAny { .. }));
+    assert!(start.elapsed() <= Duration::from_millis(10));
+}
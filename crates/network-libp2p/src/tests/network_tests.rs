@@ -5,7 +5,7 @@ use super::*;
 use assert_matches::assert_matches;
 use common::{TestPrimaryRequest, TestPrimaryResponse, TestWorkerRequest, TestWorkerResponse};
 use tn_config::ConsensusConfig;
-use tn_storage::mem_db::MemDatabase;
+use tn_storage::{mem_db::MemDatabase, PeerHistoryStore as _};
 use tn_test_utils::{fixture_batch_with_transactions, CommitteeFixture};
 use tn_types::{Certificate, Header};
 use tokio::{sync::mpsc, time::timeout};
@@ -64,6 +64,7 @@ where
         topics.clone(),
         network_key_1,
         authorized_publishers,
+        NodeVersion::local("0.1.0").expect("valid version"),
     )
     .expect("peer1 network created");
     let network_handle_1 = peer1_network.network_handle();
@@ -83,6 +84,7 @@ where
         topics.clone(),
         network_key_2,
         authorized_publishers,
+        NodeVersion::local("0.1.1").expect("valid version"),
     )
     .expect("peer2 network created");
     let network_handle_2 = peer2_network.network_handle();
@@ -137,16 +139,34 @@ async fn test_valid_req_res() -> eyre::Result<()> {
         timeout(max_time, network_events_2.recv()).await?.expect("first network event received");
 
     // expect network event
-    if let NetworkEvent::Request { request, channel, .. } = event {
-        assert_eq!(request, batch_req);
+    let first_id =
+        if let NetworkEvent::Request { peer, correlation_id, request, channel, .. } = event {
+            assert_eq!(request, batch_req);
+            // the sender chose the id instead of deriving it from the request
+            assert_ne!(correlation_id, CorrelationId::for_request(&peer, &request));
+
+            // send response
+            peer2.send_response(batch_res.clone(), channel).await?;
+            correlation_id
+        } else {
+            panic!("unexpected network event received");
+        };
 
-        // send response
+    // expect response
+    let response = timeout(max_time, response_from_peer).await?.expect("outbound id recv")?;
+    assert_eq!(response, batch_res);
+
+    // identical requests have distinct ids
+    let response_from_peer = peer1.send_request(batch_req.clone(), peer2_id).await?;
+    let event =
+        timeout(max_time, network_events_2.recv()).await?.expect("second network event received");
+    if let NetworkEvent::Request { correlation_id, request, channel, .. } = event {
+        assert_eq!(request, batch_req);
+        assert_ne!(correlation_id, first_id);
         peer2.send_response(batch_res.clone(), channel).await?;
     } else {
         panic!("unexpected network event received");
     }
-
-    // expect response
     let response = timeout(max_time, response_from_peer).await?.expect("outbound id recv")?;
     assert_eq!(response, batch_res);
 
@@ -462,6 +482,10 @@ async fn test_publish_to_one_peer() -> eyre::Result<()> {
     // assert gossip message
     if let NetworkEvent::Gossip(msg) = event {
         assert_eq!(msg.data, expected_result);
+        assert_eq!(
+            CorrelationId::for_gossip_message(&msg),
+            CorrelationId::for_gossip(&cvv_id, &expected_result)
+        );
     } else {
         panic!("unexpected network event received");
     }
@@ -540,3 +564,175 @@ async fn test_msg_verification_ignores_unauthorized_publisher() -> eyre::Result<
 
     Ok(())
 }
+
+#[tokio::test]
+async fn test_peer_versions_exchanged() -> eyre::Result<()> {
+    let TestTypes { peer1, peer2 } = create_test_types::<TestWorkerRequest, TestWorkerResponse>();
+    let NetworkPeer { config: config_1, network_handle: peer1, network, .. } = peer1;
+    tokio::spawn(async move {
+        network.run().await.expect("network run failed!");
+    });
+    let NetworkPeer { config: config_2, network_handle: peer2, network, .. } = peer2;
+    tokio::spawn(async move {
+        network.run().await.expect("network run failed!");
+    });
+
+    peer1.start_listening(config_1.authority().primary_network_address().clone()).await?;
+    peer2.start_listening(config_2.authority().primary_network_address().clone()).await?;
+    let peer2_id = peer2.local_peer_id().await?;
+    let peer2_addr = peer2.listeners().await?.first().expect("peer2 listen addr").clone();
+    peer1.dial(peer2_id, peer2_addr).await?;
+
+    // identify runs as soon as the connection is established
+    let versions = timeout(Duration::from_secs(5), async {
+        loop {
+            let versions = peer1.peer_versions().await?;
+            if !versions.is_empty() {
+                return eyre::Ok(versions);
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await??;
+    // peers see the software version the node was created with
+    assert_eq!(versions.get(&peer2_id), NodeVersion::local("0.1.1").as_ref());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_peer_exchange() -> eyre::Result<()> {
+    let TestTypes { peer1, peer2 } = create_test_types::<TestWorkerRequest, TestWorkerResponse>();
+    let NetworkPeer { config: config_1, network_handle: peer1, network, .. } = peer1;
+    tokio::spawn(async move {
+        network.run().await.expect("network run failed!");
+    });
+    let NetworkPeer { config: config_2, network_handle: peer2, network, .. } = peer2;
+    tokio::spawn(async move {
+        network.run().await.expect("network run failed!");
+    });
+
+    peer1.start_listening(config_1.authority().primary_network_address().clone()).await?;
+    peer2.start_listening(config_2.authority().primary_network_address().clone()).await?;
+    let peer1_id = peer1.local_peer_id().await?;
+    let peer2_id = peer2.local_peer_id().await?;
+    let peer2_addr = peer2.listeners().await?.first().expect("peer2 listen addr").clone();
+    peer1.dial(peer2_id, peer2_addr.clone()).await?;
+
+    // the dialer sends its record and receives the responder's record
+    timeout(Duration::from_secs(5), async {
+        loop {
+            let known_by_1 = peer1.known_peers().await?;
+            let known_by_2 = peer2.known_peers().await?;
+            if known_by_1.contains_key(&peer2_id) && known_by_2.contains_key(&peer1_id) {
+                assert!(known_by_1[&peer2_id].contains(&peer2_addr));
+                return eyre::Ok(());
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await??;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_peer_history_recorded() -> eyre::Result<()> {
+    let TestTypes { peer1, peer2 } = create_test_types::<TestWorkerRequest, TestWorkerResponse>();
+    let NetworkPeer { config: config_1, network_handle: peer1, network, .. } = peer1;
+    let db = config_1.node_storage().clone();
+    let network = network.with_peer_history(PeerHistory::new(db.clone(), PeerNetwork::Worker, 10));
+    tokio::spawn(async move {
+        network.run().await.expect("network run failed!");
+    });
+    let NetworkPeer { config: config_2, network_handle: peer2, network, .. } = peer2;
+    tokio::spawn(async move {
+        network.run().await.expect("network run failed!");
+    });
+
+    peer1.start_listening(config_1.authority().primary_network_address().clone()).await?;
+    peer2.start_listening(config_2.authority().primary_network_address().clone()).await?;
+    let peer2_id = peer2.local_peer_id().await?;
+    let peer2_addr = peer2.listeners().await?.first().expect("peer2 listen addr").clone();
+    peer1.dial(peer2_id, peer2_addr.clone()).await?;
+
+    // the connection is recorded before the dial resolves
+    let events = db.read_peer_events_since(0)?;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].network, PeerNetwork::Worker);
+    assert_eq!(events[0].peer, peer2_id);
+    assert_eq!(events[0].kind, PeerEventKind::Connected);
+
+    // dialing the wrong peer at the address fails
+    let wrong_peer = PeerId::random();
+    assert!(peer1.dial(wrong_peer, peer2_addr).await.is_err());
+    let events = db.read_peer_events_since(events[0].timestamp_ms)?;
+    let failed = events.iter().find(|event| event.peer == wrong_peer).expect("dial failure");
+    assert_matches!(failed.kind, PeerEventKind::DialFailed(_));
+
+    Ok(())
+}
+
+/// True if the host can bind IPv6 sockets.
+///
+/// Some CI runners and containers have IPv6 disabled.
+fn ipv6_available() -> bool {
+    std::net::UdpSocket::bind("[::1]:0").is_ok()
+}
+
+#[tokio::test]
+async fn test_mixed_family_peers() -> eyre::Result<()> {
+    // hosts without IPv6 can't run this test
+    if !ipv6_available() {
+        return Ok(());
+    }
+
+    let TestTypes { peer1, peer2 } = create_test_types::<TestWorkerRequest, TestWorkerResponse>();
+    let NetworkPeer { network_handle: peer1, network, .. } = peer1;
+    tokio::spawn(async move {
+        network.run().await.expect("network run failed!");
+    });
+    let NetworkPeer { network_handle: peer2, network, .. } = peer2;
+    tokio::spawn(async move {
+        network.run().await.expect("network run failed!");
+    });
+
+    // peer1 is dual-stack and peer2 only listens on IPv4
+    peer1.start_listening("/dns/localhost/udp/0/quic-v1".parse()?).await?;
+    peer2.start_listening("/ip4/127.0.0.1/udp/0/quic-v1".parse()?).await?;
+
+    // wait for both listeners
+    let is_ip6 = |addr: &Multiaddr| matches!(addr.iter().next(), Some(Protocol::Ip6(_)));
+    let has_both_families =
+        |addrs: &[Multiaddr]| addrs.iter().any(is_ip6) && addrs.iter().any(|addr| !is_ip6(addr));
+    let listeners = timeout(Duration::from_secs(5), async {
+        loop {
+            let listeners = peer1.listeners().await?;
+            if has_both_families(&listeners) {
+                return eyre::Ok(listeners);
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await??;
+
+    // peer2 reaches the dual-stack peer over IPv6
+    let peer1_id = peer1.local_peer_id().await?;
+    let peer1_addr = listeners.into_iter().find(is_ip6).expect("ipv6 listener");
+    peer2.dial(peer1_id, peer1_addr).await?;
+    assert!(peer2.connected_peers().await?.contains(&peer1_id));
+
+    // the dual-stack peer advertises addresses of both families through peer exchange
+    timeout(Duration::from_secs(5), async {
+        loop {
+            let known = peer2.known_peers().await?;
+            if known.get(&peer1_id).is_some_and(|addrs| has_both_families(addrs)) {
+                return eyre::Ok(());
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await??;
+
+    Ok(())
+}
@@ -0,0 +1,128 @@
+//! Tests for peer exchange records.
+
+use super::*;
+
+/// The record lifetime used by tests.
+const TTL: Duration = Duration::from_secs(60);
+
+fn addr(port: u16) -> Multiaddr {
+    format!("/ip4/127.0.0.1/udp/{port}/quic-v1").parse().expect("valid multiaddr")
+}
+
+/// Sign a peer record with an explicit sequence number.
+///
+/// [PeerRecord::new] sequences records by the second they are signed, so tests build the record's
+/// protobuf themselves instead of waiting for the clock.
+fn record_with_seq(keypair: &Keypair, seq: u64, addr: &Multiaddr) -> Vec<u8> {
+    fn varint(mut value: u64, out: &mut Vec<u8>) {
+        while value >= 0x80 {
+            out.push((value as u8) | 0x80);
+            value >>= 7;
+        }
+        out.push(value as u8);
+    }
+    fn field(tag: u8, bytes: &[u8], out: &mut Vec<u8>) {
+        out.push(tag);
+        varint(bytes.len() as u64, out);
+        out.extend_from_slice(bytes);
+    }
+
+    let mut address = Vec::new();
+    field(0x0a, &addr.to_vec(), &mut address);
+    let mut payload = Vec::new();
+    field(0x0a, &keypair.public().to_peer_id().to_bytes(), &mut payload);
+    payload.push(0x10);
+    varint(seq, &mut payload);
+    field(0x1a, &address, &mut payload);
+
+    SignedEnvelope::new(
+        keypair,
+        "libp2p-routing-state".to_string(),
+        b"/libp2p/routing-state-record".to_vec(),
+        payload,
+    )
+    .expect("record signed")
+    .into_protobuf_encoding()
+}
+
+#[test]
+fn test_signed_record_roundtrip() {
+    let keypair = Keypair::generate_ed25519();
+    let record = sign_peer_record(&keypair, [addr(1)]).expect("record signed");
+    let decoded = decode_peer_record(&record).expect("record verified");
+    assert_eq!(decoded.peer_id(), keypair.public().to_peer_id());
+    assert_eq!(decoded.addresses(), &[addr(1)]);
+
+    // garbage and truncated records are rejected
+    assert!(decode_peer_record(b"not a record").is_none());
+    assert!(decode_peer_record(&record[..record.len() - 1]).is_none());
+}
+
+#[test]
+fn test_undialable_addresses_filtered() {
+    let keypair = Keypair::generate_ed25519();
+    let unspecified: Multiaddr = "/ip4/0.0.0.0/udp/1/quic-v1".parse().expect("valid multiaddr");
+    assert!(sign_peer_record(&keypair, [unspecified.clone()]).is_none());
+
+    let record = sign_peer_record(&keypair, [unspecified, addr(2)]).expect("record signed");
+    let decoded = decode_peer_record(&record).expect("record verified");
+    assert_eq!(decoded.addresses(), &[addr(2)]);
+}
+
+#[test]
+fn test_newest_record_kept() {
+    let keypair = Keypair::generate_ed25519();
+    let peer_id = keypair.public().to_peer_id();
+    let mut pex = PeerExchange::new(10, TTL);
+
+    let old = record_with_seq(&keypair, 1, &addr(1));
+    let new = record_with_seq(&keypair, 2, &addr(2));
+    assert_eq!(decode_peer_record(&new).expect("record verified").seq(), 2);
+
+    assert!(pex.insert(&new).is_some());
+    // an older or repeated record does not replace the newest
+    assert!(pex.insert(&old).is_none());
+    assert!(pex.insert(&new).is_none());
+    assert_eq!(pex.known_peers().get(&peer_id), Some(&vec![addr(2)]));
+
+    // the record is not shared with the peer it describes
+    assert!(pex.records_for(&peer_id).is_empty());
+    let other = Keypair::generate_ed25519().public().to_peer_id();
+    assert_eq!(pex.records_for(&other), vec![new]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_oldest_record_evicted() {
+    let mut pex = PeerExchange::new(2, TTL);
+    let keypairs: Vec<_> = (0..3).map(|_| Keypair::generate_ed25519()).collect();
+    for (i, keypair) in keypairs.iter().enumerate() {
+        let record = sign_peer_record(keypair, [addr(i as u16)]).expect("record signed");
+        assert!(pex.insert(&record).is_some());
+        tokio::time::advance(Duration::from_secs(1)).await;
+    }
+
+    // the record received first made room for the newest peer
+    let known = pex.known_peers();
+    assert_eq!(known.len(), 2);
+    assert!(!known.contains_key(&keypairs[0].public().to_peer_id()));
+    assert!(known.contains_key(&keypairs[2].public().to_peer_id()));
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_records_expire() {
+    let mut pex = PeerExchange::new(10, TTL);
+    let keypair = Keypair::generate_ed25519();
+    let record = sign_peer_record(&keypair, [addr(1)]).expect("record signed");
+    assert!(pex.insert(&record).is_some());
+
+    tokio::time::advance(TTL - Duration::from_secs(1)).await;
+    assert_eq!(pex.known_peers().len(), 1);
+
+    // expired records are no longer shared and the same record is accepted again
+    tokio::time::advance(Duration::from_secs(1)).await;
+    assert!(pex.known_peers().is_empty());
+    let other = Keypair::generate_ed25519().public().to_peer_id();
+    assert!(pex.records_for(&other).is_empty());
+    assert!(pex.insert(&record).is_some());
+    assert_eq!(pex.records.len(), 1);
+}
@@ -1,9 +1,10 @@
 //! TNCodec tests used by the consensus network libp2p req/res protocol.
 
 use super::*;
-use crate::TNCodec;
+use crate::{types::CorrelationId, TNCodec};
 use libp2p::StreamProtocol;
 use serde::Deserialize;
+use tn_config::LEGACY_REQ_RES_PROTOCOL;
 use tn_types::{Certificate, CertificateDigest, Header, Vote};
 
 // For some reason, clippy doesn't like importing these from common mod.
@@ -243,3 +244,36 @@ async fn test_malicious_prefix_deceives_peer_to_read_message_and_fails() {
     let res = honest_peer.read_response(&protocol, &mut encoded.as_ref()).await;
     assert!(res.is_err());
 }
+
+#[tokio::test]
+async fn test_correlated_codec_request_ids() {
+    let mut codec = CorrelatedCodec::<TestPrimaryRequest, TestPrimaryResponse>::new(1024 * 1024);
+    let request = TestPrimaryRequest::Vote { header: Header::default(), parents: vec![] };
+    let correlation_id = CorrelationId::random();
+
+    // the id is sent before the request
+    let protocol = StreamProtocol::new("/telcoin-network/0.1.0");
+    let mut encoded = Vec::new();
+    let correlated =
+        CorrelatedRequest { correlation_id: Some(correlation_id), request: request.clone() };
+    codec.write_request(&protocol, &mut encoded, correlated).await.expect("write request");
+    let decoded =
+        codec.read_request(&protocol, &mut encoded.as_ref()).await.expect("read valid request");
+    assert_eq!(decoded.correlation_id, Some(correlation_id));
+    assert_eq!(decoded.request, request);
+
+    // legacy peers exchange requests without the id
+    let legacy = StreamProtocol::new(LEGACY_REQ_RES_PROTOCOL);
+    let mut encoded = Vec::new();
+    let correlated =
+        CorrelatedRequest { correlation_id: Some(correlation_id), request: request.clone() };
+    codec.write_request(&legacy, &mut encoded, correlated).await.expect("write request");
+    let mut plain = Vec::new();
+    let mut tn_codec = TNCodec::<TestPrimaryRequest, TestPrimaryResponse>::new(1024 * 1024);
+    tn_codec.write_request(&legacy, &mut plain, request.clone()).await.expect("write request");
+    assert_eq!(encoded, plain);
+    let decoded =
+        codec.read_request(&legacy, &mut encoded.as_ref()).await.expect("read valid request");
+    assert_eq!(decoded.correlation_id, None);
+    assert_eq!(decoded.request, request);
+}
@@ -0,0 +1,51 @@
+//! Tests for node versions.
+
+use super::{NodeVersion, UNKNOWN_VERSION_LABEL, WORKER_PING_FEATURE};
+
+#[test]
+fn test_agent_version_roundtrip() {
+    let local = NodeVersion::local("1.2.3-rc.1").expect("valid version");
+    assert_eq!(local.to_string(), "1.2.3");
+    assert!(local.supports(WORKER_PING_FEATURE));
+    assert_eq!(NodeVersion::from_agent_version(&local.agent_version()), Some(local));
+
+    let version = NodeVersion::from_agent_version("telcoin-network/1.2.3 ()").expect("parsed");
+    assert_eq!(version.to_string(), "1.2.3");
+    assert!(version.features.is_empty());
+    assert!(!version.supports(WORKER_PING_FEATURE));
+
+    assert!(NodeVersion::from_agent_version("rust-libp2p/0.55.0").is_none());
+    assert!(NodeVersion::from_agent_version("telcoin-network/1.2").is_none());
+    assert!(NodeVersion::local("1.2").is_none());
+}
+
+#[test]
+fn test_version_metrics_label() {
+    let version = NodeVersion::from_agent_version("telcoin-network/1.2.3 (catch-up)");
+    assert_eq!(NodeVersion::metrics_label(version.as_ref()), "1.2.3");
+
+    // peers choose their agent version, anything unparseable shares one label
+    for agent in ["rust-libp2p/0.55.0", "telcoin-network/not-a-version", "telcoin-network/1.2.3.4"]
+    {
+        let version = NodeVersion::from_agent_version(agent);
+        assert_eq!(NodeVersion::metrics_label(version.as_ref()), UNKNOWN_VERSION_LABEL);
+    }
+}
+
+#[test]
+fn test_version_compatibility() {
+    let parse = |v: &str| NodeVersion::from_agent_version(v).expect("valid version");
+    let v0_1 = parse("telcoin-network/0.1.0");
+    let v0_1_5 = parse("telcoin-network/0.1.5");
+    let v0_2 = parse("telcoin-network/0.2.0");
+    let v1_0 = parse("telcoin-network/1.0.0");
+    let v1_4 = parse("telcoin-network/1.4.0");
+    let v2_0 = parse("telcoin-network/2.0.0-rc.1");
+
+    assert!(v0_1.is_compatible(&v0_1_5));
+    assert!(!v0_1.is_outdated_by(&v0_1_5));
+    assert!(v0_1.is_outdated_by(&v0_2));
+    assert!(!v0_2.is_outdated_by(&v0_1));
+    assert!(v1_0.is_compatible(&v1_4));
+    assert!(v1_4.is_outdated_by(&v2_0));
+}
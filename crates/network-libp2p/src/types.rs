@@ -1,13 +1,17 @@
 //! Constants and trait implementations for network compatibility.
 
-use crate::{codec::TNMessage, error::NetworkError, GossipMessage};
+use crate::{codec::TNMessage, error::NetworkError, GossipMessage, NodeVersion};
 use libp2p::{
     core::transport::ListenerId,
     gossipsub::{PublishError, SubscriptionError, TopicHash},
     request_response::ResponseChannel,
     Multiaddr, PeerId, TransportError,
 };
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+use tn_types::{encode, keccak256};
 use tokio::sync::{mpsc, oneshot};
 
 pub use libp2p::gossipsub::{IdentTopic, MessageId};
@@ -46,6 +50,66 @@ pub const PRIMARY_CERT_TOPIC: &str = "tn_certificates";
 /// The topic for NVVs to subscribe to for published consensus chain.
 pub const CONSENSUS_HEADER_TOPIC: &str = "tn_consensus_headers";
 
+/// Identifies a request or gossip message in the logs of every node that handles it.
+///
+/// The sender of a request picks a random id and sends it before the request, see
+/// [CorrelatedCodec](crate::codec::CorrelatedCodec). Gossip ids are derived from the message and
+/// its source, so every receiver computes the same id without adding it to the wire format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CorrelationId(u128);
+
+impl CorrelationId {
+    /// A new random id for an outbound request.
+    pub fn random() -> Self {
+        Self(rand::random())
+    }
+
+    /// The id for a request sent by `peer` on the legacy protocol, without an id.
+    ///
+    /// Identical requests from the same peer share this id.
+    pub fn for_request<Req: TNMessage>(peer: &PeerId, request: &Req) -> Self {
+        Self::new(&peer.to_bytes(), &encode(request))
+    }
+
+    /// The id for gossip published by `source`.
+    pub fn for_gossip(source: &PeerId, data: &[u8]) -> Self {
+        Self::new(&source.to_bytes(), data)
+    }
+
+    /// The id for a received gossip message.
+    pub fn for_gossip_message(message: &GossipMessage) -> Self {
+        // the network only forwards gossip with a known source
+        let source = message.source.map(|peer| peer.to_bytes()).unwrap_or_default();
+        Self::new(&source, &message.data)
+    }
+
+    /// The id from its wire format.
+    pub fn from_be_bytes(bytes: [u8; 16]) -> Self {
+        Self(u128::from_be_bytes(bytes))
+    }
+
+    /// The wire format of the id.
+    pub fn to_be_bytes(self) -> [u8; 16] {
+        self.0.to_be_bytes()
+    }
+
+    /// Hash the sender and the message bytes.
+    fn new(sender: &[u8], data: &[u8]) -> Self {
+        let mut preimage = sender.to_vec();
+        preimage.extend_from_slice(data);
+        let hash = keccak256(preimage);
+        let mut id = [0; 16];
+        id.copy_from_slice(&hash[..16]);
+        Self::from_be_bytes(id)
+    }
+}
+
+impl fmt::Display for CorrelationId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:032x}", self.0)
+    }
+}
+
 /// Events created from network activity.
 #[derive(Debug)]
 pub enum NetworkEvent<Req, Res> {
@@ -53,6 +117,8 @@ pub enum NetworkEvent<Req, Res> {
     Request {
         /// The peer that made the request.
         peer: PeerId,
+        /// The id the peer logged for the request.
+        correlation_id: CorrelationId,
         /// The network request type.
         request: Req,
         /// The network response channel.
@@ -169,6 +235,10 @@ where
     SetApplicationScore { peer_id: PeerId, new_score: f64, reply: oneshot::Sender<bool> },
     /// Return the number of pending outbound requests.
     PendingRequestCount { reply: oneshot::Sender<usize> },
+    /// The software versions connected peers advertised.
+    PeerVersions { reply: oneshot::Sender<HashMap<PeerId, NodeVersion>> },
+    /// The addresses of peers learned through peer exchange.
+    KnownPeers { reply: oneshot::Sender<HashMap<PeerId, Vec<Multiaddr>>> },
 }
 
 /// Network handle.
@@ -213,6 +283,9 @@ where
     /// Start swarm listening on the given address. Returns an error if the address is not
     /// supported.
     ///
+    /// DNS names listen on all interfaces of the families they resolve to. The listener id is for
+    /// the IPv4 listener of a dual-stack `/dns` address.
+    ///
     /// Return swarm error to caller.
     pub async fn start_listening(&self, multiaddr: Multiaddr) -> NetworkResult<ListenerId> {
         let (reply, ack) = oneshot::channel();
@@ -364,6 +437,20 @@ where
         self.sender.send(NetworkCommand::PendingRequestCount { reply }).await?;
         count.await.map_err(Into::into)
     }
+
+    /// Retrieve the software versions connected peers advertised.
+    pub async fn peer_versions(&self) -> NetworkResult<HashMap<PeerId, NodeVersion>> {
+        let (reply, versions) = oneshot::channel();
+        self.sender.send(NetworkCommand::PeerVersions { reply }).await?;
+        versions.await.map_err(Into::into)
+    }
+
+    /// Retrieve the addresses of peers learned through peer exchange.
+    pub async fn known_peers(&self) -> NetworkResult<HashMap<PeerId, Vec<Multiaddr>>> {
+        let (reply, peers) = oneshot::channel();
+        self.sender.send(NetworkCommand::KnownPeers { reply }).await?;
+        peers.await.map_err(Into::into)
+    }
 }
 
 /// Helper macro for sending oneshot replies and logging errors.
@@ -0,0 +1,133 @@
+//! Node software versions exchanged with peers.
+//!
+//! Each node advertises its software version and the protocol features it supports in the libp2p
+//! identify agent version, for example `telcoin-network/0.1.0 (catch-up,worker-info-update)`.
+//! Peers track the versions so operators learn when the committee upgrades before this node is
+//! left behind.
+
+use std::{cmp::Ordering, collections::BTreeSet, fmt};
+
+#[cfg(test)]
+#[path = "tests/version_tests.rs"]
+mod version_tests;
+
+/// The identify protocol version for Telcoin Network.
+pub const PROTOCOL_VERSION: &str = "/telcoin/1.0.0";
+
+/// The feature of nodes whose workers answer latency pings.
+pub const WORKER_PING_FEATURE: &str = "worker-ping";
+
+/// The protocol features supported by this node.
+///
+/// Add a feature when a change to the network protocol is rolled out so peers can tell which
+/// messages a node understands.
+pub const PROTOCOL_FEATURES: &[&str] =
+    &["catch-up", "custom-protocols", "peer-exchange", "worker-info-update", WORKER_PING_FEATURE];
+
+/// The prefix of the agent version advertised by Telcoin Network nodes.
+const AGENT_NAME: &str = "telcoin-network";
+
+/// The metrics label of peers whose agent version can't be parsed.
+pub(crate) const UNKNOWN_VERSION_LABEL: &str = "unknown";
+
+/// The software version and protocol features of a node.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NodeVersion {
+    /// The major version.
+    pub major: u64,
+    /// The minor version.
+    pub minor: u64,
+    /// The patch version.
+    pub patch: u64,
+    /// The protocol features the node supports.
+    pub features: BTreeSet<String>,
+}
+
+impl NodeVersion {
+    /// The version of this node running `software_version`, with all [PROTOCOL_FEATURES].
+    ///
+    /// The software version is the node binary's `major.minor.patch` version, not this crate's.
+    /// Returns `None` if it can't be parsed.
+    pub fn local(software_version: &str) -> Option<Self> {
+        let mut version = Self::parse_version(software_version)?;
+        version.features = PROTOCOL_FEATURES.iter().map(ToString::to_string).collect();
+        Some(version)
+    }
+
+    /// The agent version advertised to peers.
+    pub fn agent_version(&self) -> String {
+        let features: Vec<_> = self.features.iter().map(String::as_str).collect();
+        format!("{AGENT_NAME}/{self} ({})", features.join(","))
+    }
+
+    /// Parse a peer's agent version.
+    ///
+    /// Returns `None` if the peer is not a Telcoin Network node.
+    pub fn from_agent_version(agent_version: &str) -> Option<Self> {
+        let rest = agent_version.strip_prefix(AGENT_NAME)?.strip_prefix('/')?;
+        let (version, features) = match rest.split_once(' ') {
+            Some((version, features)) => (version, features),
+            None => (rest, ""),
+        };
+
+        let mut version = Self::parse_version(version)?;
+        version.features = features
+            .trim_start_matches('(')
+            .trim_end_matches(')')
+            .split(',')
+            .filter(|feature| !feature.is_empty())
+            .map(ToString::to_string)
+            .collect();
+        Some(version)
+    }
+
+    /// The metrics label for a peer's agent version.
+    ///
+    /// Agent versions are chosen by the peer, so anything that isn't a Telcoin Network version
+    /// shares the [UNKNOWN_VERSION_LABEL] to bound the number of label values.
+    pub(crate) fn metrics_label(version: Option<&Self>) -> String {
+        version.map_or_else(|| UNKNOWN_VERSION_LABEL.to_string(), ToString::to_string)
+    }
+
+    /// Return true if the node supports the protocol feature.
+    pub fn supports(&self, feature: &str) -> bool {
+        self.features.contains(feature)
+    }
+
+    /// Return true if both versions speak the same protocol.
+    ///
+    /// Versions follow semver: before 1.0 the minor version must match, afterwards the major
+    /// version must match.
+    pub fn is_compatible(&self, other: &Self) -> bool {
+        self.major == other.major && (self.major > 0 || self.minor == other.minor)
+    }
+
+    /// Return true if `peer` runs a newer version that is incompatible with this one.
+    pub fn is_outdated_by(&self, peer: &Self) -> bool {
+        !self.is_compatible(peer) && self.cmp_release(peer) == Ordering::Less
+    }
+
+    /// Compare the release numbers, ignoring features.
+    fn cmp_release(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch).cmp(&(other.major, other.minor, other.patch))
+    }
+
+    /// Parse `major.minor.patch`, ignoring pre-release and build metadata.
+    fn parse_version(version: &str) -> Option<Self> {
+        let release = version.split(['-', '+']).next()?;
+        let mut parts = release.split('.').map(str::parse::<u64>);
+        let version = Self {
+            major: parts.next()?.ok()?,
+            minor: parts.next()?.ok()?,
+            patch: parts.next()?.ok()?,
+            features: BTreeSet::new(),
+        };
+        parts.next().is_none().then_some(version)
+    }
+}
+
+impl fmt::Display for NodeVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
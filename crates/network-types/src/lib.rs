@@ -17,6 +17,8 @@ pub trait WorkerToPrimaryClient: Send + Sync + 'static {
     async fn report_own_batch(&self, request: WorkerOwnBatchMessage) -> eyre::Result<()>;
 
     async fn report_others_batch(&self, request: WorkerOthersBatchMessage) -> eyre::Result<()>;
+
+    async fn report_heartbeat(&self, request: WorkerHeartbeatMessage) -> eyre::Result<()>;
 }
 
 /// Dumb mock to just return Ok on calls for tests.
@@ -31,6 +33,10 @@ impl WorkerToPrimaryClient for MockWorkerToPrimary {
     async fn report_others_batch(&self, _request: WorkerOthersBatchMessage) -> eyre::Result<()> {
         Ok(())
     }
+
+    async fn report_heartbeat(&self, _request: WorkerHeartbeatMessage) -> eyre::Result<()> {
+        Ok(())
+    }
 }
 
 /// Dumb mock to just pends forever on calls for tests.
@@ -45,6 +51,10 @@ impl WorkerToPrimaryClient for MockWorkerToPrimaryHang {
     async fn report_others_batch(&self, _request: WorkerOthersBatchMessage) -> eyre::Result<()> {
         std::future::pending().await
     }
+
+    async fn report_heartbeat(&self, _request: WorkerHeartbeatMessage) -> eyre::Result<()> {
+        std::future::pending().await
+    }
 }
 
 // async_trait for object safety, get rid of when possible.
@@ -1,7 +1,7 @@
 //! Client implementation for local network messages between primary and worker.
 use crate::{
-    FetchBatchResponse, PrimaryToWorkerClient, WorkerOthersBatchMessage, WorkerOwnBatchMessage,
-    WorkerSynchronizeMessage, WorkerToPrimaryClient,
+    FetchBatchResponse, PrimaryToWorkerClient, WorkerHeartbeatMessage, WorkerOthersBatchMessage,
+    WorkerOwnBatchMessage, WorkerSynchronizeMessage, WorkerToPrimaryClient,
 };
 use libp2p::PeerId;
 use parking_lot::RwLock;
@@ -131,4 +131,13 @@ impl WorkerToPrimaryClient for LocalNetwork {
         }
         Ok(())
     }
+
+    async fn report_heartbeat(&self, request: WorkerHeartbeatMessage) -> eyre::Result<()> {
+        if let Some(c) = self.get_worker_to_primary_handler().await {
+            c.report_heartbeat(request).await?;
+        } else {
+            tracing::warn!(target = "local_network", "working to primary handler not set yet!");
+        }
+        Ok(())
+    }
 }
@@ -38,6 +38,18 @@ pub struct WorkerOthersBatchMessage {
     pub worker_id: WorkerId,
 }
 
+/// Used by an idle worker to inform primary it is alive.
+///
+/// Workers send a heartbeat when they did not seal a batch within the heartbeat interval, so the
+/// primary can tell a worker without transactions from a worker that is down.
+#[derive(Clone, Serialize, Deserialize, Eq, PartialEq, Debug)]
+pub struct WorkerHeartbeatMessage {
+    /// The worker's id.
+    pub worker_id: WorkerId,
+    /// The time the heartbeat was sent.
+    pub timestamp: TimestampSec,
+}
+
 /// Used by workers to send a new batch to peers.
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct BatchMessage {
@@ -0,0 +1,307 @@
+// SPDX-License-Identifier: Apache-2.0
+//! Embed a Telcoin Network node in another binary.
+//!
+//! [TelcoinNodeBuilder] configures a full node from a data directory and launches it on its own
+//! thread. The returned [TelcoinNodeHandle] serves the node's RPC address, typed consensus events,
+//! and shutdown, so downstream projects do not need to run the `telcoin-network` binary.
+//!
+//! ```ignore
+//! let node = TelcoinNodeBuilder::new("/var/lib/telcoin")
+//!     .with_rpc(rpc_args)
+//!     .with_inclusion_promises(true)
+//!     .launch()?;
+//! node.node_handle().wait_until_running().await;
+//! let filter = ConsensusEventFilter::only([ConsensusEventKind::ConsensusOutput]);
+//! let mut events = node.node_handle().subscribe_events("indexer", filter).expect("node running");
+//! ...
+//! node.shutdown();
+//! node.wait()?;
+//! ```
+//!
+//! Process setup, like tracing, the fd limit, and the global rayon pool, is left to the embedding
+//! binary.
+
+use reth::{
+    args::{DatadirArgs, RpcServerArgs},
+    builder::NodeConfig,
+    dirs::MaybePlatformPath,
+};
+use reth_chainspec::ChainSpec;
+use reth_db::DatabaseEnv;
+use std::{net::SocketAddr, path::PathBuf, sync::Arc, thread::JoinHandle};
+use tn_config::{Config, ConfigFmt, ConfigTrait, KeyConfig, TelcoinDirs as _};
+use tn_node::{
+    dirs::{default_datadir_args, DataDirChainPath, DataDirPath},
+    engine::TnBuilder,
+    launch_node_with_handle,
+    migrations::open_execution_db,
+};
+use tn_types::Address;
+use tracing::info;
+
+pub use tn_node::handle::NodeHandle;
+pub use tn_primary::{
+    ConsensusEvent, ConsensusEventFilter, ConsensusEventKind, ConsensusEventSubscriber,
+};
+
+/// Configure and launch a Telcoin Network node in this process.
+#[derive(Debug)]
+pub struct TelcoinNodeBuilder {
+    /// The node's data directory.
+    datadir: PathBuf,
+    /// The node config, loaded from the data directory if not set.
+    config: Option<Config>,
+    /// The chain, the chain from the node config if not set.
+    chain: Option<Arc<ChainSpec>>,
+    /// Generate validator keys in the data directory if they do not exist.
+    generate_keys: bool,
+    /// The execution RPC configuration.
+    rpc: RpcServerArgs,
+    /// The instance used to offset ports for multiple nodes on one host.
+    instance: u16,
+    /// Follow consensus without participating in the committee.
+    observer: bool,
+    /// Sign inclusion promises for the worker's sealed batches.
+    inclusion_promises: bool,
+    /// External builders allowed to submit batches.
+    authorized_builders: Vec<Address>,
+    /// Stop the node after executing this sub-dag.
+    halt_at_sub_dag: Option<u64>,
+    /// Serve Prometheus consensus metrics at this address.
+    consensus_metrics: Option<SocketAddr>,
+    /// Serve the `tn_dev` RPC namespace to mine blocks and change state on demand.
+    dev_mining: bool,
+    /// Index the sender and recipient of every executed transaction.
+    address_index: bool,
+}
+
+impl TelcoinNodeBuilder {
+    /// Create a new builder for a node with data in `datadir`.
+    ///
+    /// The data directory holds the node config, validator keys, genesis, and both databases. It
+    /// uses the same layout as the `--datadir` of the `telcoin-network` binary.
+    pub fn new(datadir: impl Into<PathBuf>) -> Self {
+        Self {
+            datadir: datadir.into(),
+            config: None,
+            chain: None,
+            generate_keys: false,
+            rpc: RpcServerArgs::default(),
+            instance: 1,
+            observer: false,
+            inclusion_promises: false,
+            authorized_builders: Vec::new(),
+            halt_at_sub_dag: None,
+            consensus_metrics: None,
+            dev_mining: false,
+            address_index: false,
+        }
+    }
+
+    /// Use this node config instead of loading it from the data directory.
+    pub fn with_config(mut self, config: Config) -> Self {
+        self.config = Some(config);
+        self
+    }
+
+    /// Use this chain instead of the chain in the node config.
+    pub fn with_chain(mut self, chain: Arc<ChainSpec>) -> Self {
+        self.chain = Some(chain);
+        self
+    }
+
+    /// Generate validator keys in the data directory if they do not exist.
+    ///
+    /// By default the node only reads existing keys.
+    pub fn with_generated_keys(mut self, generate_keys: bool) -> Self {
+        self.generate_keys = generate_keys;
+        self
+    }
+
+    /// Configure the execution RPC servers.
+    ///
+    /// RPC is disabled by default, the same as the binary without `--http`.
+    pub fn with_rpc(mut self, rpc: RpcServerArgs) -> Self {
+        self.rpc = rpc;
+        self
+    }
+
+    /// Offset RPC ports for running multiple nodes on one host.
+    pub fn with_instance(mut self, instance: u16) -> Self {
+        self.instance = instance;
+        self
+    }
+
+    /// Follow consensus without participating in the committee.
+    pub fn with_observer(mut self, observer: bool) -> Self {
+        self.observer = observer;
+        self
+    }
+
+    /// Sign inclusion promises for the worker's sealed batches.
+    pub fn with_inclusion_promises(mut self, inclusion_promises: bool) -> Self {
+        self.inclusion_promises = inclusion_promises;
+        self
+    }
+
+    /// Allow these external builders to submit batches through the `tnBuilder` RPC namespace.
+    pub fn with_authorized_builders(mut self, authorized_builders: Vec<Address>) -> Self {
+        self.authorized_builders = authorized_builders;
+        self
+    }
+
+    /// Stop the node after executing this sub-dag.
+    pub fn with_halt_at_sub_dag(mut self, sub_dag: u64) -> Self {
+        self.halt_at_sub_dag = Some(sub_dag);
+        self
+    }
+
+    /// Serve Prometheus consensus metrics at this address.
+    pub fn with_consensus_metrics(mut self, socket: SocketAddr) -> Self {
+        self.consensus_metrics = Some(socket);
+        self
+    }
+
+    /// Serve the `tn_dev` RPC namespace to mine blocks and change state on demand.
+    ///
+    /// Only allowed for single-node committees.
+    pub fn with_dev_mining(mut self, dev_mining: bool) -> Self {
+        self.dev_mining = dev_mining;
+        self
+    }
+
+    /// Index the sender and recipient of every executed transaction.
+    ///
+    /// The index is served through `tn_getTransactionsByAddress`.
+    pub fn with_address_index(mut self, address_index: bool) -> Self {
+        self.address_index = address_index;
+        self
+    }
+
+    /// Launch the node on a new thread.
+    ///
+    /// The node runs until it is shutdown through the returned handle or a task fails.
+    pub fn launch(self) -> eyre::Result<TelcoinNodeHandle> {
+        let (builder, tn_datadir) = self.build()?;
+
+        let handle = NodeHandle::new();
+        let node = handle.clone();
+        let thread = std::thread::Builder::new()
+            .name("telcoin-node".to_string())
+            .spawn(move || launch_node_with_handle(builder, tn_datadir, node))?;
+
+        Ok(TelcoinNodeHandle { handle, thread })
+    }
+
+    /// Generate keys if enabled and missing.
+    fn ensure_keys(&self) -> eyre::Result<()> {
+        if self.generate_keys && !self.datadir.validator_keys_path().exists() {
+            std::fs::create_dir_all(self.datadir.validator_keys_path())?;
+            KeyConfig::generate_and_save(&self.datadir)?;
+            info!(target: "tn::node", datadir = ?self.datadir, "generated validator keys");
+        }
+        Ok(())
+    }
+
+    /// Create the node's builder and data directory.
+    fn build(self) -> eyre::Result<(TnBuilder<Arc<DatabaseEnv>>, DataDirChainPath)> {
+        self.ensure_keys()?;
+
+        let mut tn_config = match self.config {
+            Some(config) => config,
+            None => Config::load_from_path(self.datadir.node_config_path(), ConfigFmt::YAML)?,
+        };
+        tn_config.observer = self.observer;
+        // fail before starting anything if the node or chain parameters are invalid
+        tn_config.parameters.validate()?;
+        tn_config.tn_chain_spec()?;
+        let chain = self.chain.unwrap_or_else(|| Arc::new(tn_config.chain_spec()));
+
+        let tn_datadir: DataDirChainPath = MaybePlatformPath::<DataDirPath>::from(self.datadir)
+            .unwrap_or_chain_default(chain.chain, default_datadir_args())
+            .into();
+
+        let mut node_config = NodeConfig::new(chain);
+        node_config.datadir = DatadirArgs {
+            datadir: MaybePlatformPath::from(PathBuf::from(tn_datadir.clone())),
+            static_files_path: None,
+        };
+        node_config.rpc = self.rpc;
+        node_config.instance = self.instance;
+
+        let database = open_execution_db(&tn_datadir.db(), node_config.db.database_args(), false)?;
+
+        let builder = TnBuilder {
+            consensus_metrics: self.consensus_metrics,
+            halt_at_sub_dag: self.halt_at_sub_dag,
+            authorized_builders: self.authorized_builders,
+            inclusion_promises: self.inclusion_promises,
+            dev_mining: self.dev_mining,
+            address_index: self.address_index,
+            ..TnBuilder::new(database, node_config, tn_config)
+        };
+
+        Ok((builder, tn_datadir))
+    }
+}
+
+/// Handle to a node launched with [TelcoinNodeBuilder].
+#[derive(Debug)]
+pub struct TelcoinNodeHandle {
+    /// Handle to the running node.
+    handle: NodeHandle,
+    /// The thread running the node.
+    thread: JoinHandle<eyre::Result<()>>,
+}
+
+impl TelcoinNodeHandle {
+    /// The handle to follow the running node.
+    ///
+    /// Clones can be passed to other tasks.
+    pub fn node_handle(&self) -> &NodeHandle {
+        &self.handle
+    }
+
+    /// Ask the node to shutdown.
+    pub fn shutdown(&self) {
+        self.handle.shutdown();
+    }
+
+    /// True if the node stopped running.
+    pub fn is_finished(&self) -> bool {
+        self.thread.is_finished()
+    }
+
+    /// Block until the node stops.
+    ///
+    /// Returns an error if the node failed.
+    pub fn wait(self) -> eyre::Result<()> {
+        self.thread.join().map_err(|_| eyre::eyre!("telcoin node panicked"))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TelcoinNodeBuilder;
+    use tempfile::tempdir;
+    use tn_config::{KeyConfig, TelcoinDirs as _};
+
+    #[test]
+    fn test_generated_keys() {
+        let tempdir = tempdir().expect("tempdir created");
+        let datadir = tempdir.path().to_path_buf();
+
+        // keys are only read by default
+        TelcoinNodeBuilder::new(&datadir).ensure_keys().expect("keys checked");
+        assert!(!datadir.validator_keys_path().exists());
+
+        let builder = TelcoinNodeBuilder::new(&datadir).with_generated_keys(true);
+        builder.ensure_keys().expect("keys generated");
+        let keys = KeyConfig::read_config(&datadir).expect("keys saved");
+
+        // existing keys are kept
+        builder.ensure_keys().expect("keys checked");
+        let kept = KeyConfig::read_config(&datadir).expect("keys saved");
+        assert_eq!(keys.primary_public_key(), kept.primary_public_key());
+    }
+}
@@ -0,0 +1,157 @@
+//! Executed transactions indexed by sender and recipient.
+//!
+//! Nodes that enable the index record every executed transaction under its sender and recipient
+//! so wallets can read an address's history through `tn_getTransactionsByAddress` without an
+//! external indexer.
+
+use futures::StreamExt as _;
+use reth_provider::{
+    BlockNumReader, BlockReader, CanonStateNotificationStream, TransactionVariant,
+};
+use std::time::Duration;
+use tn_rpc::{
+    AddressTransactionCursor, TNRpcError, TelcoinNetworkRpcResult, TransactionsByAddressProvider,
+};
+use tn_storage::AddressIndexStore as _;
+use tn_types::{
+    Address, Block, Database, IndexedTransaction, Noticer, SealedBlockWithSenders, TaskManager,
+    TransactionTrait as _,
+};
+use tracing::{error, info};
+
+/// The most blocks indexed before the recorder checks for shutdown.
+const MAX_BLOCKS_PER_PASS: u64 = 1_000;
+
+/// The time to wait before indexing again after a failure.
+const RETRY_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Serves transactions by address from the consensus DB.
+#[derive(Debug, Clone)]
+pub(crate) struct AddressIndexReader<DB> {
+    /// The consensus DB.
+    db: DB,
+}
+
+impl<DB: Database> AddressIndexReader<DB> {
+    /// Create a new instance of [Self].
+    pub(crate) fn new(db: DB) -> Self {
+        Self { db }
+    }
+}
+
+impl<DB: Database> TransactionsByAddressProvider for AddressIndexReader<DB> {
+    fn transactions_by_address(
+        &self,
+        address: Address,
+        from: AddressTransactionCursor,
+        limit: usize,
+    ) -> TelcoinNetworkRpcResult<Vec<IndexedTransaction>> {
+        self.db
+            .read_transactions_by_address(
+                address,
+                (from.block_number, from.transaction_index),
+                limit,
+            )
+            .map_err(|e| TNRpcError::AddressIndex(e.to_string()))
+    }
+}
+
+/// Spawn a task that indexes the transactions of blocks as they are executed.
+///
+/// Blocks are read from the execution DB starting after the last indexed block, so blocks executed
+/// before the index was enabled, while the node was down, or while the recorder fell behind the
+/// canonical state notifications are indexed too.
+pub(crate) fn spawn_address_index_recorder<DB, P>(
+    db: DB,
+    provider: P,
+    mut canon_state: CanonStateNotificationStream,
+    task_manager: &TaskManager,
+    rx_shutdown: Noticer,
+) where
+    DB: Database,
+    P: BlockNumReader + BlockReader<Block = Block> + Send + Sync + 'static,
+{
+    task_manager.spawn_task("address index", async move {
+        let mut next = match db.last_indexed_block() {
+            Ok(last) => last.map_or(0, |last| last + 1),
+            Err(e) => {
+                error!(target: "telcoin::node", ?e, "failed to read the address index cursor");
+                return;
+            }
+        };
+        info!(target: "telcoin::node", next, "indexing executed transactions by address");
+        loop {
+            match index_blocks(&db, &provider, &mut next) {
+                // more blocks to index, give other tasks and shutdown a chance to run first
+                Ok(false) => {
+                    tokio::select!(
+                        _ = &rx_shutdown => break,
+                        _ = tokio::task::yield_now() => continue,
+                    );
+                }
+                Ok(true) => (),
+                Err(e) => {
+                    error!(target: "telcoin::node", ?e, block = next, "failed to index block");
+                    tokio::select!(
+                        _ = &rx_shutdown => break,
+                        _ = tokio::time::sleep(RETRY_INTERVAL) => continue,
+                    );
+                }
+            }
+            // notifications only signal new blocks, they are read from the provider
+            tokio::select!(
+                _ = &rx_shutdown => break,
+                notification = canon_state.next() => {
+                    if notification.is_none() {
+                        break;
+                    }
+                }
+            )
+        }
+    });
+}
+
+/// Index up to [MAX_BLOCKS_PER_PASS] executed blocks from `next`.
+///
+/// Returns true once every executed block is indexed.
+fn index_blocks<DB, P>(db: &DB, provider: &P, next: &mut u64) -> eyre::Result<bool>
+where
+    DB: Database,
+    P: BlockNumReader + BlockReader<Block = Block>,
+{
+    let tip = provider.last_block_number()?;
+    let last = tip.min(next.saturating_add(MAX_BLOCKS_PER_PASS - 1));
+    if *next > last {
+        return Ok(true);
+    }
+
+    let mut transactions = Vec::new();
+    for number in *next..=last {
+        let block = provider
+            .sealed_block_with_senders(number.into(), TransactionVariant::WithHash)?
+            .ok_or_else(|| eyre::eyre!("executed block {number} not found"))?;
+        transactions.extend(indexed_transactions(&block));
+    }
+    db.index_transactions(&transactions, last)?;
+    *next = last + 1;
+    Ok(last == tip)
+}
+
+/// Return the transactions of the block in execution order.
+fn indexed_transactions(block: &SealedBlockWithSenders) -> Vec<IndexedTransaction> {
+    let senders = block.senders.iter();
+    block
+        .body
+        .transactions
+        .iter()
+        .zip(senders)
+        .enumerate()
+        .map(|(index, (tx, from))| IndexedTransaction {
+            block_number: block.header.number,
+            index: index as u32,
+            hash: tx.hash(),
+            from: *from,
+            to: tx.to(),
+        })
+        .collect()
+}
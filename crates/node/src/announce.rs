@@ -0,0 +1,75 @@
+//! Announce executed blocks to nodes following the chain.
+//!
+//! Active CVVs publish the header of every executed block on the `tn-blocks` gossip topic with
+//! the number of the consensus header it was executed for. Observers and light clients subscribe
+//! to follow the execution tip without an RPC connection to a validator.
+
+use futures::StreamExt as _;
+use reth_provider::CanonStateNotificationStream;
+use tn_primary::{network::PrimaryNetworkHandle, ConsensusBus};
+use tn_storage::tables::ConsensusBlockNumbersByDigest;
+use tn_types::{BlockAnnouncement, Database, Noticer, TaskManager};
+use tracing::{error, warn};
+
+/// Spawn a task that announces blocks as they are executed.
+///
+/// Only committee members are authorized to publish, so blocks are only announced while the node
+/// is an active CVV.
+pub(crate) fn spawn_block_announcer<DB: Database>(
+    db: DB,
+    consensus_bus: ConsensusBus,
+    network: PrimaryNetworkHandle,
+    mut canon_state: CanonStateNotificationStream,
+    task_manager: &TaskManager,
+    rx_shutdown: Noticer,
+) {
+    task_manager.spawn_task("block announcements", async move {
+        loop {
+            tokio::select!(
+                _ = &rx_shutdown => break,
+                notification = canon_state.next() => {
+                    let Some(notification) = notification else {
+                        break;
+                    };
+                    if !consensus_bus.node_mode().borrow().is_active_cvv() {
+                        continue;
+                    }
+                    for block in notification.committed().blocks_iter() {
+                        let Some(consensus_hash) = block.header.parent_beacon_block_root else {
+                            continue;
+                        };
+                        let consensus_number =
+                            match db.get::<ConsensusBlockNumbersByDigest>(&consensus_hash) {
+                                Ok(Some(number)) => number,
+                                Ok(None) => {
+                                    warn!(
+                                        target: "telcoin::node",
+                                        ?consensus_hash,
+                                        number = block.header.number,
+                                        "executed block for unknown consensus header"
+                                    );
+                                    continue;
+                                }
+                                Err(e) => {
+                                    error!(
+                                        target: "telcoin::node",
+                                        ?e,
+                                        "failed to read consensus header number"
+                                    );
+                                    continue;
+                                }
+                            };
+                        let Some(announcement) =
+                            BlockAnnouncement::new(consensus_number, block.header.header())
+                        else {
+                            continue;
+                        };
+                        if let Err(e) = network.publish_block_announcement(announcement).await {
+                            warn!(target: "telcoin::node", ?e, "failed to announce block");
+                        }
+                    }
+                }
+            )
+        }
+    });
+}
@@ -0,0 +1,152 @@
+//! Upload epoch summaries to an S3 compatible object store.
+//!
+//! Requests are signed with AWS signature version 4 using the credentials from the environment,
+//! see [ArchiveConfig].
+
+use aws_credential_types::Credentials;
+use aws_sigv4::{
+    http_request::{
+        sign, PayloadChecksumKind, PercentEncodingMode, SignableBody, SignableRequest,
+        SigningSettings, UriPathNormalizationMode,
+    },
+    sign::v4,
+};
+use eyre::{ensure, Context as _};
+use std::time::{Duration, SystemTime};
+use tn_config::{ArchiveConfig, ARCHIVE_ACCESS_KEY_ID_ENV, ARCHIVE_SECRET_ACCESS_KEY_ENV};
+use tn_types::EpochSummary;
+
+/// Uploads epoch summaries to the configured object store.
+#[derive(Debug, Clone)]
+pub(crate) struct EpochArchive {
+    /// The object store.
+    config: ArchiveConfig,
+    /// The credentials that sign requests.
+    credentials: Credentials,
+    /// The http client.
+    client: reqwest::Client,
+}
+
+impl EpochArchive {
+    /// Create a new instance of [Self] with credentials from the environment.
+    pub(crate) fn new(config: ArchiveConfig) -> eyre::Result<Self> {
+        let access_key_id = std::env::var(ARCHIVE_ACCESS_KEY_ID_ENV)
+            .with_context(|| format!("{ARCHIVE_ACCESS_KEY_ID_ENV} is required to archive"))?;
+        let secret_access_key = std::env::var(ARCHIVE_SECRET_ACCESS_KEY_ENV)
+            .with_context(|| format!("{ARCHIVE_SECRET_ACCESS_KEY_ENV} is required to archive"))?;
+        let credentials = Credentials::new(access_key_id, secret_access_key, None, None, "env");
+        let client = reqwest::Client::new();
+        Ok(Self { config, credentials, client })
+    }
+
+    /// The time to wait before uploading again after a failure.
+    pub(crate) fn retry_interval(&self) -> Duration {
+        self.config.retry_interval
+    }
+
+    /// Upload the summary as JSON, replacing any existing object for the epoch.
+    pub(crate) async fn upload(&self, summary: &EpochSummary) -> eyre::Result<()> {
+        let body = serde_json::to_vec_pretty(summary)?;
+        let key = self.config.epoch_key(summary.epoch);
+        let request = self.put_request(&key, body, SystemTime::now())?;
+        let response = self.client.execute(request).await?;
+        let status = response.status();
+        ensure!(status.is_success(), "archive upload of {key} failed: {status}");
+        Ok(())
+    }
+
+    /// The signed request that puts `body` at `key` in the bucket.
+    fn put_request(
+        &self,
+        key: &str,
+        body: Vec<u8>,
+        time: SystemTime,
+    ) -> eyre::Result<reqwest::Request> {
+        let mut url = reqwest::Url::parse(self.config.endpoint.trim_end_matches('/'))?;
+        ensure!(url.host_str().is_some(), "archive endpoint {url} has no host");
+        let path = format!(
+            "{}/{}/{}",
+            url.path().trim_end_matches('/'),
+            uri_encode(&self.config.bucket),
+            key.split('/').map(uri_encode).collect::<Vec<_>>().join("/")
+        );
+        url.set_path(&path);
+
+        // S3 signs the encoded path as is and requires the payload hash header
+        let mut settings = SigningSettings::default();
+        settings.payload_checksum_kind = PayloadChecksumKind::XAmzSha256;
+        settings.percent_encoding_mode = PercentEncodingMode::Single;
+        settings.uri_path_normalization_mode = UriPathNormalizationMode::Disabled;
+        let identity = self.credentials.clone().into();
+        let params = v4::SigningParams::builder()
+            .identity(&identity)
+            .region(&self.config.region)
+            .name("s3")
+            .time(time)
+            .settings(settings)
+            .build()?
+            .into();
+
+        let headers = [("content-type", "application/json")];
+        let signable = SignableRequest::new(
+            "PUT",
+            url.as_str(),
+            headers.into_iter(),
+            SignableBody::Bytes(&body),
+        )?;
+        let (instructions, _signature) = sign(signable, &params)?.into_parts();
+
+        let mut request = self.client.put(url).body(body);
+        for (name, value) in headers.into_iter().chain(instructions.headers()) {
+            request = request.header(name, value);
+        }
+        Ok(request.build()?)
+    }
+}
+
+/// Percent encode a path segment, keeping only unreserved characters.
+fn uri_encode(segment: &str) -> String {
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (byte as char).to_string()
+            }
+            byte => format!("%{byte:02X}"),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{uri_encode, EpochArchive};
+    use aws_credential_types::Credentials;
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_put_request() {
+        let archive = EpochArchive {
+            config: serde_yaml::from_str("endpoint: http://localhost:9000/\nbucket: tel coin\n")
+                .expect("config"),
+            credentials: Credentials::new("AKIDEXAMPLE", "secret", None, None, "test"),
+            client: reqwest::Client::new(),
+        };
+        let time = SystemTime::UNIX_EPOCH + Duration::from_secs(1_369_353_600);
+        let request = archive.put_request("epochs/1.json", b"{}".to_vec(), time).unwrap();
+
+        assert_eq!(request.url().as_str(), "http://localhost:9000/tel%20coin/epochs/1.json");
+        let header = |name: &str| request.headers()[name].to_str().unwrap();
+        assert_eq!(header("content-type"), "application/json");
+        assert_eq!(header("x-amz-date"), "20130524T000000Z");
+        assert!(header("authorization").starts_with(
+            "AWS4-HMAC-SHA256 Credential=AKIDEXAMPLE/20130524/us-east-1/s3/aws4_request, \
+             SignedHeaders=content-type;host;x-amz-content-sha256;x-amz-date, Signature="
+        ));
+    }
+
+    #[test]
+    fn test_uri_encode() {
+        assert_eq!(uri_encode("epoch 1.json"), "epoch%201.json");
+        assert_eq!(uri_encode("a/b"), "a%2Fb");
+    }
+}
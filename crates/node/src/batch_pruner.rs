@@ -0,0 +1,68 @@
+//! Prune batches of executed consensus output.
+//!
+//! Workers keep every batch they store forever by default. High-throughput deployments set
+//! `batch_ttl` to delete the batches of consensus headers that were executed and committed more
+//! than the TTL ago. Consensus output is final once executed, so pruned batches are only needed by
+//! nodes syncing from behind, which fetch missing batches from peers. Some nodes in the network
+//! should leave `batch_ttl` unset to keep serving old batches.
+
+use std::time::Duration;
+use tn_primary::ConsensusBus;
+use tn_storage::{tables::ConsensusBlockNumbersByDigest, ConsensusStore as _};
+use tn_types::{now, Database, Noticer, TaskManager};
+use tracing::{debug, error};
+
+/// The maximum time between pruning runs.
+const MAX_PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// The maximum number of consensus headers to prune batches for in one run.
+const MAX_HEADERS_PER_PRUNE: usize = 1_000;
+
+/// Spawn a task that deletes batches `ttl` after they were committed and executed.
+pub(crate) fn spawn_batch_pruner<DB: Database>(
+    db: DB,
+    consensus_bus: ConsensusBus,
+    ttl: Duration,
+    task_manager: &TaskManager,
+    rx_shutdown: Noticer,
+) {
+    task_manager.spawn_task("batch pruner", async move {
+        let mut interval = tokio::time::interval(ttl.min(MAX_PRUNE_INTERVAL));
+        loop {
+            tokio::select!(
+                _ = &rx_shutdown => break,
+                _ = interval.tick() => prune(&db, &consensus_bus, ttl).await,
+            )
+        }
+    });
+}
+
+/// Prune the batches of executed consensus headers committed more than `ttl` ago.
+async fn prune<DB: Database>(db: &DB, consensus_bus: &ConsensusBus, ttl: Duration) {
+    // the consensus header of the last executed block and every header before it are final
+    let consensus_hash =
+        consensus_bus.recent_blocks().borrow().latest_block().parent_beacon_block_root;
+    let Some(consensus_hash) = consensus_hash else {
+        return;
+    };
+    let executed = match db.get::<ConsensusBlockNumbersByDigest>(&consensus_hash) {
+        Ok(Some(number)) => number,
+        Ok(None) => return,
+        Err(e) => {
+            error!(target: "telcoin::node", ?e, "failed to read executed consensus number");
+            return;
+        }
+    };
+
+    let cutoff = now().saturating_sub(ttl.as_secs());
+    let db = db.clone();
+    let result = tokio::task::spawn_blocking(move || {
+        db.prune_batches(executed, cutoff, MAX_HEADERS_PER_PRUNE)
+    })
+    .await;
+    match result {
+        Ok(Ok(pruned)) => debug!(target: "telcoin::node", pruned, executed, "pruned batches"),
+        Ok(Err(e)) => error!(target: "telcoin::node", ?e, "failed to prune batches"),
+        Err(e) => error!(target: "telcoin::node", ?e, "batch pruning task failed"),
+    }
+}
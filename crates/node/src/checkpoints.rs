@@ -0,0 +1,564 @@
+//! Execution state at consensus checkpoints.
+//!
+//! Every block executed for a consensus output records the leader's nonce as its `nonce` and the
+//! consensus header's hash in `parent_beacon_block_root`. Nonces never decrease along the chain so
+//! the last block for a sub-dag is found with a binary search over block numbers.
+//!
+//! Blocks also record the digest of the batch they executed in `extra_data`, which links executed
+//! transactions back to the certified header that included their batch for inclusion proofs and
+//! blocks back to the sub-dag that produced them.
+//!
+//! State diffs are read from the account and storage changesets written for each block, with the
+//! values before and after the blocks read from historical state.
+
+use reth_provider::{
+    AccountExtReader, AccountReader as _, BlockNumReader, DatabaseProviderFactory, HeaderProvider,
+    StateProvider as _, StateProviderBox, StateProviderFactory, StorageReader,
+    TransactionsProvider,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    ops::RangeInclusive,
+};
+use tn_rpc::{
+    AccountChange, AccountState, BlockProvenance, BlockProvenanceProvider, InclusionProofProvider,
+    StateDiff, StateDiffProvider, StorageChange, SubDagBlockResolver, TNRpcError,
+    TelcoinNetworkRpcResult, TransactionInclusionProof,
+};
+use tn_storage::tables::{Batches, ConsensusBlockNumbersByDigest, ConsensusBlocks};
+use tn_types::{
+    encode, Account, BlockHash, BlockNumHash, Database, ExecHeader, Hash as _, SealedHeader, TxHash,
+};
+
+/// Maps committed sub-dags to the execution blocks and state built for them.
+#[derive(Debug, Clone)]
+pub struct ConsensusCheckpoints<DB, P> {
+    /// The consensus DB with the committed sub-dags.
+    db: DB,
+    /// The execution provider.
+    provider: P,
+}
+
+impl<DB, P> ConsensusCheckpoints<DB, P>
+where
+    DB: Database,
+    P: BlockNumReader + HeaderProvider<Header = ExecHeader> + StateProviderFactory,
+{
+    /// Create a new instance of [Self].
+    pub fn new(db: DB, provider: P) -> Self {
+        Self { db, provider }
+    }
+
+    /// Return the last execution block for the consensus output of `sub_dag`.
+    ///
+    /// Returns `None` if the sub-dag has not been committed or executed by this node.
+    pub fn execution_header(&self, sub_dag: u64) -> eyre::Result<Option<SealedHeader>> {
+        let Some(consensus_header) = self.db.get::<ConsensusBlocks>(&sub_dag)? else {
+            return Ok(None);
+        };
+        let number = self.last_block_for_nonce(consensus_header.sub_dag.leader.nonce())?;
+
+        let digest = consensus_header.digest();
+        Ok(self
+            .provider
+            .sealed_header(number)?
+            .filter(|header| header.parent_beacon_block_root == Some(digest)))
+    }
+
+    /// Return the range of execution blocks for the consensus output of `sub_dag`.
+    ///
+    /// Returns `None` if the sub-dag has not been committed or executed by this node.
+    pub fn execution_blocks(&self, sub_dag: u64) -> eyre::Result<Option<RangeInclusive<u64>>> {
+        let Some(header) = self.execution_header(sub_dag)? else {
+            return Ok(None);
+        };
+        // blocks for earlier sub-dags have smaller nonces
+        let first = match u64::from(header.nonce).checked_sub(1) {
+            Some(previous) => self.last_block_for_nonce(previous)? + 1,
+            None => 1,
+        };
+        Ok(Some(first..=header.number))
+    }
+
+    /// Return the last execution block with a nonce at most `nonce`.
+    fn last_block_for_nonce(&self, nonce: u64) -> eyre::Result<u64> {
+        let last = self.provider.last_block_number()?;
+        last_block_at_or_before(last, nonce, |number| {
+            let header = self
+                .provider
+                .header_by_number(number)?
+                .ok_or_else(|| eyre::eyre!("missing execution block {number}"))?;
+            Ok(header.nonce.into())
+        })
+    }
+
+    /// Return the state after executing the consensus output of `sub_dag`.
+    ///
+    /// Returns `None` if the sub-dag has not been committed or executed by this node.
+    pub fn state_by_sub_dag(&self, sub_dag: u64) -> eyre::Result<Option<StateProviderBox>> {
+        let Some(header) = self.execution_header(sub_dag)? else {
+            return Ok(None);
+        };
+        Ok(Some(self.provider.history_by_block_hash(header.hash())?))
+    }
+}
+
+impl<DB, P> SubDagBlockResolver for ConsensusCheckpoints<DB, P>
+where
+    DB: Database,
+    P: BlockNumReader
+        + HeaderProvider<Header = ExecHeader>
+        + StateProviderFactory
+        + Send
+        + Sync
+        + 'static,
+{
+    fn sub_dag_block(&self, sub_dag: u64) -> TelcoinNetworkRpcResult<Option<BlockNumHash>> {
+        self.execution_header(sub_dag)
+            .map(|header| header.map(|header| header.num_hash()))
+            .map_err(|e| TNRpcError::SubDagBlock(e.to_string()))
+    }
+}
+
+impl<DB, P> ConsensusCheckpoints<DB, P>
+where
+    DB: Database,
+    P: HeaderProvider<Header = ExecHeader> + TransactionsProvider,
+{
+    /// Return the proof that the executed transaction `tx_hash` was committed by consensus.
+    ///
+    /// Returns `None` if the transaction has not been executed by this node.
+    pub fn inclusion_proof(
+        &self,
+        tx_hash: TxHash,
+    ) -> eyre::Result<Option<TransactionInclusionProof>> {
+        let Some((_, meta)) = self.provider.transaction_by_hash_with_meta(tx_hash)? else {
+            return Ok(None);
+        };
+        let header = self
+            .provider
+            .sealed_header(meta.block_number)?
+            .ok_or_else(|| eyre::eyre!("missing execution block {}", meta.block_number))?;
+        let batch_digest = BlockHash::try_from(header.extra_data.as_ref())
+            .map_err(|_| eyre::eyre!("block {} has no batch digest", header.number))?;
+        let consensus_hash = header
+            .parent_beacon_block_root
+            .ok_or_else(|| eyre::eyre!("block {} has no consensus header", header.number))?;
+
+        let consensus_number = self
+            .db
+            .get::<ConsensusBlockNumbersByDigest>(&consensus_hash)?
+            .ok_or_else(|| eyre::eyre!("unknown consensus header {consensus_hash}"))?;
+        let consensus_header = self
+            .db
+            .get::<ConsensusBlocks>(&consensus_number)?
+            .ok_or_else(|| eyre::eyre!("missing consensus header {consensus_number}"))?;
+        let (certificate, payload_proof) = consensus_header
+            .sub_dag
+            .certificates
+            .iter()
+            .find_map(|certificate| {
+                let proof = certificate.header().payload_proof(&batch_digest)?;
+                Some((certificate, proof))
+            })
+            .ok_or_else(|| {
+                eyre::eyre!("batch {batch_digest} is not in consensus header {consensus_number}")
+            })?;
+        // batches may have been pruned after execution
+        let batch = self.db.get::<Batches>(&batch_digest)?.map(|batch| encode(&batch).into());
+
+        Ok(Some(TransactionInclusionProof {
+            tx_hash,
+            block_hash: header.hash(),
+            block_number: header.number,
+            batch_digest,
+            batch,
+            payload_proof,
+            certificate: encode(certificate).into(),
+            consensus_number,
+            consensus_hash,
+            consensus_header: encode(&consensus_header).into(),
+        }))
+    }
+}
+
+impl<DB, P> InclusionProofProvider for ConsensusCheckpoints<DB, P>
+where
+    DB: Database,
+    P: HeaderProvider<Header = ExecHeader> + TransactionsProvider + Send + Sync + 'static,
+{
+    fn transaction_inclusion_proof(
+        &self,
+        tx_hash: TxHash,
+    ) -> TelcoinNetworkRpcResult<Option<TransactionInclusionProof>> {
+        self.inclusion_proof(tx_hash).map_err(|e| TNRpcError::InclusionProof(e.to_string()))
+    }
+}
+
+impl<DB, P> ConsensusCheckpoints<DB, P>
+where
+    DB: Database,
+    P: HeaderProvider<Header = ExecHeader>,
+{
+    /// Return the sub-dag, leader, certificates, and batches that produced the execution block.
+    ///
+    /// Returns `None` if the block has not been executed by this node or is genesis.
+    pub fn block_provenance(&self, block_hash: BlockHash) -> eyre::Result<Option<BlockProvenance>> {
+        let Some(header) = self.provider.header(&block_hash)? else {
+            return Ok(None);
+        };
+        // genesis was not produced by consensus
+        let Some(consensus_hash) = header.parent_beacon_block_root.filter(|_| header.number > 0)
+        else {
+            return Ok(None);
+        };
+
+        let sub_dag_index = self
+            .db
+            .get::<ConsensusBlockNumbersByDigest>(&consensus_hash)?
+            .ok_or_else(|| eyre::eyre!("unknown consensus header {consensus_hash}"))?;
+        let consensus_header = self
+            .db
+            .get::<ConsensusBlocks>(&sub_dag_index)?
+            .ok_or_else(|| eyre::eyre!("missing consensus header {sub_dag_index}"))?;
+        let sub_dag = &consensus_header.sub_dag;
+        // the empty block for a sub-dag without batches records a zero digest
+        let batch_digest =
+            BlockHash::try_from(header.extra_data.as_ref()).ok().filter(|digest| !digest.is_zero());
+
+        Ok(Some(BlockProvenance {
+            block_hash,
+            block_number: header.number,
+            sub_dag_index,
+            consensus_hash,
+            leader: sub_dag.leader.origin().clone(),
+            leader_round: sub_dag.leader_round(),
+            certificate_digests: sub_dag.certificates.iter().map(|cert| cert.digest()).collect(),
+            batch_digests: sub_dag
+                .certificates
+                .iter()
+                .flat_map(|cert| cert.header().payload().keys().copied())
+                .collect(),
+            batch_digest,
+        }))
+    }
+}
+
+impl<DB, P> BlockProvenanceProvider for ConsensusCheckpoints<DB, P>
+where
+    DB: Database,
+    P: HeaderProvider<Header = ExecHeader> + Send + Sync + 'static,
+{
+    fn block_provenance(
+        &self,
+        block_hash: BlockHash,
+    ) -> TelcoinNetworkRpcResult<Option<BlockProvenance>> {
+        ConsensusCheckpoints::block_provenance(self, block_hash)
+            .map_err(|e| TNRpcError::BlockProvenance(e.to_string()))
+    }
+}
+
+impl<DB, P> ConsensusCheckpoints<DB, P>
+where
+    DB: Database,
+    P: BlockNumReader
+        + HeaderProvider<Header = ExecHeader>
+        + StateProviderFactory
+        + DatabaseProviderFactory,
+    P::Provider: AccountExtReader + StorageReader,
+{
+    /// Return the accounts and storage changed by executing `blocks`.
+    ///
+    /// The range must not include genesis.
+    pub fn state_diff(&self, blocks: RangeInclusive<u64>) -> eyre::Result<StateDiff> {
+        let (first, last) = (*blocks.start(), *blocks.end());
+        if first == 0 {
+            eyre::bail!("genesis has no state diff");
+        }
+        let provider = self.provider.database_provider_ro()?;
+        let mut changed: BTreeSet<_> = provider.changed_accounts_with_range(blocks.clone())?;
+        let changed_storage = provider.changed_storages_with_range(blocks)?;
+        changed.extend(changed_storage.keys().copied());
+
+        let before = self.provider.history_by_block_number(first - 1)?;
+        let after = self.provider.history_by_block_number(last)?;
+        let mut accounts = BTreeMap::new();
+        for address in changed {
+            let mut storage = BTreeMap::new();
+            for key in changed_storage.get(&address).into_iter().flatten() {
+                let from = before.storage(address, *key)?.unwrap_or_default();
+                let to = after.storage(address, *key)?.unwrap_or_default();
+                if from != to {
+                    storage.insert(*key, StorageChange { from, to });
+                }
+            }
+            let from = before.basic_account(address)?.map(account_state);
+            let to = after.basic_account(address)?.map(account_state);
+            if from != to || !storage.is_empty() {
+                accounts.insert(address, AccountChange { from, to, storage });
+            }
+        }
+
+        Ok(StateDiff { from_block: first, to_block: last, accounts })
+    }
+}
+
+impl<DB, P> StateDiffProvider for ConsensusCheckpoints<DB, P>
+where
+    DB: Database,
+    P: BlockNumReader
+        + HeaderProvider<Header = ExecHeader>
+        + StateProviderFactory
+        + DatabaseProviderFactory
+        + Send
+        + Sync
+        + 'static,
+    P::Provider: AccountExtReader + StorageReader,
+{
+    fn block_state_diff(&self, block: u64) -> TelcoinNetworkRpcResult<Option<StateDiff>> {
+        let diff = || {
+            if block > self.provider.last_block_number()? {
+                return Ok(None);
+            }
+            self.state_diff(block..=block).map(Some)
+        };
+        diff().map_err(|e: eyre::Report| TNRpcError::StateDiff(e.to_string()))
+    }
+
+    fn sub_dag_state_diff(&self, sub_dag: u64) -> TelcoinNetworkRpcResult<Option<StateDiff>> {
+        let diff = || match self.execution_blocks(sub_dag)? {
+            Some(blocks) => self.state_diff(blocks).map(Some),
+            None => Ok(None),
+        };
+        diff().map_err(|e: eyre::Report| TNRpcError::StateDiff(e.to_string()))
+    }
+}
+
+/// The RPC representation of an account.
+fn account_state(account: Account) -> AccountState {
+    AccountState {
+        balance: account.balance,
+        nonce: account.nonce,
+        code_hash: account.get_bytecode_hash(),
+    }
+}
+
+/// Return the highest block number in `0..=last` whose nonce is at most `nonce`.
+///
+/// Block 0 is returned if every block has a larger nonce.
+fn last_block_at_or_before<F>(last: u64, nonce: u64, nonce_at: F) -> eyre::Result<u64>
+where
+    F: Fn(u64) -> eyre::Result<u64>,
+{
+    let (mut low, mut high) = (0, last);
+    while low < high {
+        let mid = low + (high - low).div_ceil(2);
+        if nonce_at(mid)? <= nonce {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    Ok(low)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{last_block_at_or_before, ConsensusCheckpoints};
+    use reth_provider::test_utils::MockEthProvider;
+    use std::collections::BTreeSet;
+    use tn_storage::{
+        mem_db::MemDatabase,
+        tables::{Batches, ConsensusBlockNumbersByDigest, ConsensusBlocks},
+    };
+    use tn_types::{
+        encode, Address, AuthorityIdentifier, Batch, Block, BlockBody, BlockHash, Bytes,
+        Certificate, CommittedSubDag, ConsensusHeader, Database as _, ExecHeader, Hash as _,
+        HeaderBuilder, ReputationScores, TransactionSigned, B256,
+    };
+
+    #[test]
+    fn test_last_block_at_or_before() {
+        // genesis, then two blocks for nonce 5, one for nonce 6, and three for nonce 9
+        let nonces = [0, 5, 5, 6, 9, 9, 9];
+        let last = nonces.len() as u64 - 1;
+        let search = |nonce| {
+            last_block_at_or_before(last, nonce, |number| Ok(nonces[number as usize])).unwrap()
+        };
+
+        assert_eq!(search(0), 0);
+        assert_eq!(search(5), 2);
+        assert_eq!(search(6), 3);
+        assert_eq!(search(9), 6);
+        // nonces without blocks find the block before them
+        assert_eq!(search(7), 3);
+        assert_eq!(search(20), 6);
+    }
+
+    /// A provider with block 1 executing `tx` from the batch for the consensus header.
+    fn provider_with_block(
+        tx: &TransactionSigned,
+        batch_digest: BlockHash,
+        consensus_hash: B256,
+    ) -> (MockEthProvider, BlockHash) {
+        let header = ExecHeader {
+            number: 1,
+            extra_data: batch_digest.to_vec().into(),
+            parent_beacon_block_root: Some(consensus_hash),
+            ..Default::default()
+        };
+        let hash = header.hash_slow();
+        let provider = MockEthProvider::default();
+        provider.add_header(hash, header.clone());
+        let body = BlockBody { transactions: vec![tx.clone()], ..Default::default() };
+        provider.add_block(hash, Block { header, body });
+        (provider, hash)
+    }
+
+    #[test]
+    fn test_inclusion_proof() {
+        let db = MemDatabase::default();
+        let batch = Batch { beneficiary: Address::with_last_byte(1), ..Default::default() };
+        let batch_digest = batch.digest();
+        let header = HeaderBuilder::default()
+            .author(AuthorityIdentifier::dummy_for_test(1))
+            .round(2)
+            .epoch(0)
+            .parents(BTreeSet::new())
+            .payload(
+                [(BlockHash::with_last_byte(9), (0, 0)), (batch_digest, (0, 0))]
+                    .into_iter()
+                    .collect(),
+            )
+            .build();
+        let mut certificate = Certificate::default();
+        certificate.header = header.clone();
+        let sub_dag = CommittedSubDag::new(
+            vec![certificate.clone()],
+            certificate.clone(),
+            1,
+            ReputationScores::default(),
+            None,
+        );
+        let consensus_header = ConsensusHeader { number: 1, sub_dag, ..Default::default() };
+        let consensus_hash = consensus_header.digest();
+        db.insert::<ConsensusBlocks>(&1, &consensus_header).unwrap();
+        db.insert::<ConsensusBlockNumbersByDigest>(&consensus_hash, &1).unwrap();
+        db.insert::<Batches>(&batch_digest, &batch).unwrap();
+
+        // the block executed for the batch
+        let tx = TransactionSigned::default();
+        let (provider, block_hash) = provider_with_block(&tx, batch_digest, consensus_hash);
+        let checkpoints = ConsensusCheckpoints::new(db.clone(), provider);
+
+        let proof = checkpoints.inclusion_proof(tx.hash()).unwrap().expect("proof");
+        assert_eq!((proof.block_number, proof.block_hash), (1, block_hash));
+        assert_eq!(proof.batch_digest, batch_digest);
+        assert_eq!(proof.batch, Some(Bytes::from(encode(&batch))));
+        assert!(proof.payload_proof.verify(&batch_digest, header.payload_root()));
+        assert_eq!(proof.certificate, Bytes::from(encode(&certificate)));
+        assert_eq!((proof.consensus_number, proof.consensus_hash), (1, consensus_hash));
+        assert_eq!(proof.consensus_header, Bytes::from(encode(&consensus_header)));
+
+        // pruned batches are left out of the proof
+        db.remove::<Batches>(&batch_digest).unwrap();
+        let proof = checkpoints.inclusion_proof(tx.hash()).unwrap().expect("proof");
+        assert_eq!(proof.batch, None);
+
+        // transactions that were not executed have no proof
+        assert!(checkpoints.inclusion_proof(BlockHash::with_last_byte(7)).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_inclusion_proof_missing_batch_in_header() {
+        let db = MemDatabase::default();
+        let consensus_header = ConsensusHeader { number: 1, ..Default::default() };
+        let consensus_hash = consensus_header.digest();
+        db.insert::<ConsensusBlocks>(&1, &consensus_header).unwrap();
+        db.insert::<ConsensusBlockNumbersByDigest>(&consensus_hash, &1).unwrap();
+
+        let tx = TransactionSigned::default();
+        let (provider, _) = provider_with_block(&tx, BlockHash::with_last_byte(3), consensus_hash);
+        let checkpoints = ConsensusCheckpoints::new(db, provider);
+
+        // the executed batch must be in the committed sub-dag
+        assert!(checkpoints.inclusion_proof(tx.hash()).is_err());
+    }
+
+    #[test]
+    fn test_block_provenance() {
+        let db = MemDatabase::default();
+        let batch_digest = BlockHash::with_last_byte(5);
+        let header = HeaderBuilder::default()
+            .author(AuthorityIdentifier::dummy_for_test(2))
+            .round(4)
+            .epoch(0)
+            .parents(BTreeSet::new())
+            .payload(
+                [(BlockHash::with_last_byte(9), (0, 0)), (batch_digest, (0, 0))]
+                    .into_iter()
+                    .collect(),
+            )
+            .build();
+        let mut leader = Certificate::default();
+        leader.header = header.clone();
+        let certificates = vec![Certificate::default(), leader.clone()];
+        let sub_dag = CommittedSubDag::new(
+            certificates.clone(),
+            leader,
+            3,
+            ReputationScores::default(),
+            None,
+        );
+        let consensus_header = ConsensusHeader { number: 3, sub_dag, ..Default::default() };
+        let consensus_hash = consensus_header.digest();
+        db.insert::<ConsensusBlocks>(&3, &consensus_header).unwrap();
+        db.insert::<ConsensusBlockNumbersByDigest>(&consensus_hash, &3).unwrap();
+
+        let (provider, block_hash) =
+            provider_with_block(&TransactionSigned::default(), batch_digest, consensus_hash);
+        // the empty block for a sub-dag without batches records a zero digest
+        let empty = ExecHeader {
+            number: 2,
+            extra_data: B256::ZERO.to_vec().into(),
+            parent_beacon_block_root: Some(consensus_hash),
+            ..Default::default()
+        };
+        let empty_hash = empty.hash_slow();
+        provider.add_header(empty_hash, empty);
+        // genesis was not produced by consensus
+        let genesis =
+            ExecHeader { parent_beacon_block_root: Some(consensus_hash), ..Default::default() };
+        let genesis_hash = genesis.hash_slow();
+        provider.add_header(genesis_hash, genesis);
+        // a block for a consensus header this node doesn't have
+        let unknown = ExecHeader {
+            number: 4,
+            parent_beacon_block_root: Some(B256::with_last_byte(1)),
+            ..Default::default()
+        };
+        let unknown_hash = unknown.hash_slow();
+        provider.add_header(unknown_hash, unknown);
+        let checkpoints = ConsensusCheckpoints::new(db, provider);
+
+        let provenance = checkpoints.block_provenance(block_hash).unwrap().expect("provenance");
+        assert_eq!((provenance.block_hash, provenance.block_number), (block_hash, 1));
+        assert_eq!((provenance.sub_dag_index, provenance.consensus_hash), (3, consensus_hash));
+        assert_eq!(provenance.leader, AuthorityIdentifier::dummy_for_test(2));
+        assert_eq!(provenance.leader_round, 4);
+        assert_eq!(
+            provenance.certificate_digests,
+            certificates.iter().map(|cert| cert.digest()).collect::<Vec<_>>()
+        );
+        assert_eq!(provenance.batch_digests, header.payload().keys().copied().collect::<Vec<_>>());
+        assert_eq!(provenance.batch_digest, Some(batch_digest));
+
+        let provenance = checkpoints.block_provenance(empty_hash).unwrap().expect("provenance");
+        assert_eq!(provenance.block_number, 2);
+        assert_eq!(provenance.batch_digest, None);
+
+        assert!(checkpoints.block_provenance(genesis_hash).unwrap().is_none());
+        assert!(checkpoints.block_provenance(BlockHash::with_last_byte(7)).unwrap().is_none());
+        assert!(checkpoints.block_provenance(unknown_hash).is_err());
+    }
+}
@@ -0,0 +1,198 @@
+//! Monitor the local clock's offset from NTP time.
+//!
+//! Each check sends a simple network time protocol (SNTP) request to the configured servers in
+//! order until one responds. The offset is recorded in the `clock_skew_ms` metric and an error is
+//! logged when it exceeds the configured maximum, see [ClockConfig].
+
+use eyre::{ensure, eyre, OptionExt as _};
+use std::{
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
+use tn_config::ClockConfig;
+use tn_primary_metrics::PrimaryMetrics;
+use tn_types::{Noticer, TaskManager};
+use tokio::net::UdpSocket;
+use tracing::{debug, error, warn};
+
+/// The size of an NTP packet without extensions.
+const NTP_PACKET_SIZE: usize = 48;
+/// The seconds between the NTP epoch (1900) and the UNIX epoch.
+const NTP_UNIX_OFFSET_SECS: u64 = 2_208_988_800;
+/// Leap indicator 0, version 4, client mode.
+const NTP_CLIENT_REQUEST: u8 = 0b00_100_011;
+/// The server mode of a response.
+const NTP_SERVER_MODE: u8 = 4;
+
+/// Spawn a task that checks the local clock against NTP time every `check_interval`.
+///
+/// The task exits immediately if no NTP servers are configured.
+pub(crate) fn spawn_clock_monitor(
+    config: ClockConfig,
+    metrics: Arc<PrimaryMetrics>,
+    task_manager: &TaskManager,
+    rx_shutdown: Noticer,
+) {
+    if config.ntp_servers.is_empty() {
+        debug!(target: "telcoin::node", "no NTP servers configured, clock is not monitored");
+        return;
+    }
+    task_manager.spawn_task("clock monitor", async move {
+        let mut interval = tokio::time::interval(config.check_interval);
+        loop {
+            tokio::select!(
+                _ = &rx_shutdown => break,
+                _ = interval.tick() => check_clock(&config, &metrics).await,
+            )
+        }
+    });
+}
+
+/// Measure the offset from the first NTP server that responds and report it.
+async fn check_clock(config: &ClockConfig, metrics: &PrimaryMetrics) {
+    let mut offset = None;
+    for server in config.ntp_servers.iter() {
+        match query_offset(server, config.query_timeout).await {
+            Ok(ms) => {
+                offset = Some(ms);
+                break;
+            }
+            Err(e) => debug!(target: "telcoin::node", server, ?e, "NTP query failed"),
+        }
+    }
+    let Some(offset) = offset else {
+        warn!(target: "telcoin::node", "no NTP server responded, clock skew is unknown");
+        return;
+    };
+
+    metrics.clock_skew_ms.set(offset);
+    if offset.unsigned_abs() > config.max_skew.as_millis() as u64 {
+        metrics.clock_skew_alerts.inc();
+        error!(
+            target: "telcoin::node",
+            offset_ms = offset,
+            max_skew = ?config.max_skew,
+            "local clock is out of sync with NTP time, headers and batches will be mistimed"
+        );
+    } else {
+        debug!(target: "telcoin::node", offset_ms = offset, "local clock in sync with NTP time");
+    }
+}
+
+/// Query `server` and return the offset of NTP time from the local clock in milliseconds.
+///
+/// The offset is positive if the local clock is behind.
+async fn query_offset(server: &str, timeout: Duration) -> eyre::Result<i64> {
+    let addr = tokio::net::lookup_host(server)
+        .await?
+        .next()
+        .ok_or_eyre(format!("{server} did not resolve"))?;
+    let socket = UdpSocket::bind(if addr.is_ipv4() { "0.0.0.0:0" } else { "[::]:0" }).await?;
+    socket.connect(addr).await?;
+
+    let sent = ntp_timestamp(SystemTime::now());
+    let mut request = [0u8; NTP_PACKET_SIZE];
+    request[0] = NTP_CLIENT_REQUEST;
+    request[40..48].copy_from_slice(&sent.to_be_bytes());
+
+    tokio::time::timeout(timeout, async {
+        socket.send(&request).await?;
+        let mut response = [0u8; NTP_PACKET_SIZE];
+        let len = socket.recv(&mut response).await?;
+        let received = ntp_timestamp(SystemTime::now());
+        parse_offset(&response[..len], sent, received)
+    })
+    .await
+    .map_err(|_| eyre!("{server} did not respond within {timeout:?}"))?
+}
+
+/// The clock offset in milliseconds from a server's response.
+///
+/// `sent` and `received` are the local NTP timestamps of the request and response.
+fn parse_offset(response: &[u8], sent: u64, received: u64) -> eyre::Result<i64> {
+    ensure!(response.len() >= NTP_PACKET_SIZE, "NTP response too short");
+    ensure!(response[0] & 0b111 == NTP_SERVER_MODE, "NTP response is not from a server");
+    ensure!(response[1] != 0, "NTP server sent kiss-o'-death");
+    let read = |at: usize| {
+        u64::from_be_bytes(response[at..at + 8].try_into().expect("eight bytes in packet"))
+    };
+    ensure!(read(24) == sent, "NTP response does not match request");
+
+    let request_sent = ntp_millis(sent);
+    let request_received = ntp_millis(read(32));
+    let response_sent = ntp_millis(read(40));
+    let response_received = ntp_millis(received);
+    Ok(((request_received - request_sent) + (response_sent - response_received)) / 2)
+}
+
+/// The NTP timestamp of `time`, seconds since 1900 in the upper 32 bits and the fraction of a
+/// second in the lower 32 bits.
+fn ntp_timestamp(time: SystemTime) -> u64 {
+    let since_unix = time.duration_since(SystemTime::UNIX_EPOCH).unwrap_or_default();
+    let secs = since_unix.as_secs() + NTP_UNIX_OFFSET_SECS;
+    let fraction = ((since_unix.subsec_nanos() as u64) << 32) / 1_000_000_000;
+    (secs << 32) | fraction
+}
+
+/// Milliseconds since the NTP epoch for an NTP timestamp.
+fn ntp_millis(timestamp: u64) -> i64 {
+    let secs = (timestamp >> 32) as i64;
+    let fraction = ((timestamp & 0xffff_ffff) * 1_000) >> 32;
+    secs * 1_000 + fraction as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A server response with the local timestamps shifted by `offset_ms`.
+    fn response(sent: u64, offset_ms: u64) -> [u8; NTP_PACKET_SIZE] {
+        let shifted = sent + (offset_ms << 32) / 1_000;
+        let mut response = [0u8; NTP_PACKET_SIZE];
+        response[0] = 0b00_100_100;
+        response[1] = 2;
+        response[24..32].copy_from_slice(&sent.to_be_bytes());
+        response[32..40].copy_from_slice(&shifted.to_be_bytes());
+        response[40..48].copy_from_slice(&shifted.to_be_bytes());
+        response
+    }
+
+    #[test]
+    fn test_ntp_timestamp() {
+        let time = SystemTime::UNIX_EPOCH + Duration::from_millis(1_500);
+        let timestamp = ntp_timestamp(time);
+        assert_eq!(timestamp >> 32, NTP_UNIX_OFFSET_SECS + 1);
+        assert_eq!(ntp_millis(timestamp), (NTP_UNIX_OFFSET_SECS * 1_000 + 1_500) as i64);
+    }
+
+    #[test]
+    fn test_parse_offset() {
+        let sent = ntp_timestamp(SystemTime::now());
+        let offset = parse_offset(&response(sent, 2_000), sent, sent).unwrap();
+        assert!((1_999..=2_000).contains(&offset), "offset {offset}");
+
+        // the response must echo the request
+        assert!(parse_offset(&response(sent, 0), sent + 1, sent).is_err());
+        // kiss-o'-death
+        let mut kod = response(sent, 0);
+        kod[1] = 0;
+        assert!(parse_offset(&kod, sent, sent).is_err());
+        assert!(parse_offset(&[0u8; 12], sent, sent).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_query_offset() {
+        // a server three seconds ahead of the local clock
+        let server = UdpSocket::bind("127.0.0.1:0").await.unwrap();
+        let addr = server.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut request = [0u8; NTP_PACKET_SIZE];
+            let (_, peer) = server.recv_from(&mut request).await.unwrap();
+            let sent = u64::from_be_bytes(request[40..48].try_into().unwrap());
+            server.send_to(&response(sent, 3_000), peer).await.unwrap();
+        });
+
+        let offset = query_offset(&addr.to_string(), Duration::from_secs(5)).await.unwrap();
+        assert!((2_500..=3_000).contains(&offset), "offset {offset}");
+    }
+}
@@ -0,0 +1,155 @@
+//! Crash-loop protection for relaunching the node.
+//!
+//! A node that crashes shortly after every launch would otherwise restart as fast as it can
+//! boot. Once too many crashes happen within the window, each relaunch waits twice as long as
+//! the last. The node gives up with [CRASH_LOOP_EXIT_CODE] when crashes keep happening after the
+//! longest backoff so the process supervisor sees a distinct failure.
+//!
+//! Restarts caused by a mode change are expected and are never counted.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+use thiserror::Error;
+
+/// The process exit code when the node gives up because it keeps crashing.
+pub const CRASH_LOOP_EXIT_CODE: i32 = 3;
+
+/// The number of crashes within [CRASH_WINDOW] that are considered a crash loop.
+const MAX_CRASHES: usize = 5;
+/// The window crashes are counted in.
+const CRASH_WINDOW: Duration = Duration::from_secs(10 * 60);
+/// The backoff before the first relaunch once the node is crash-looping.
+const INITIAL_BACKOFF: Duration = Duration::from_secs(1);
+/// The longest backoff between relaunches.
+const MAX_BACKOFF: Duration = Duration::from_secs(64);
+
+/// The node kept crashing after backing off.
+#[derive(Debug, Error)]
+#[error("node crashed {crashes} times within {window:?}, last exit: {reason}")]
+pub struct CrashLoopError {
+    /// The number of crashes within the window.
+    pub crashes: usize,
+    /// The window crashes are counted in.
+    pub window: Duration,
+    /// Why the node last exited.
+    pub reason: String,
+}
+
+/// Tracks recent crashes and decides how long to wait before relaunching.
+#[derive(Debug)]
+pub(crate) struct CrashLoopGuard {
+    /// When recent crashes happened, oldest first.
+    crashes: VecDeque<Instant>,
+    /// The number of crashes within the window that starts the backoff.
+    max_crashes: usize,
+    /// The window crashes are counted in.
+    window: Duration,
+    /// The backoff before the next relaunch while crash-looping.
+    backoff: Duration,
+    /// The longest backoff, crashing again after waiting this long ends the loop.
+    max_backoff: Duration,
+    /// The backoff waited before the last relaunch.
+    last_backoff: Duration,
+}
+
+impl Default for CrashLoopGuard {
+    fn default() -> Self {
+        Self::new(MAX_CRASHES, CRASH_WINDOW, MAX_BACKOFF)
+    }
+}
+
+impl CrashLoopGuard {
+    /// Create a new instance of Self.
+    pub(crate) fn new(max_crashes: usize, window: Duration, max_backoff: Duration) -> Self {
+        Self {
+            crashes: VecDeque::with_capacity(max_crashes),
+            max_crashes,
+            window,
+            backoff: INITIAL_BACKOFF.min(max_backoff),
+            max_backoff,
+            last_backoff: Duration::ZERO,
+        }
+    }
+
+    /// Record a crash at `now`.
+    ///
+    /// Returns how long to wait before relaunching the node, or an error once the node has
+    /// crashed again after waiting the longest backoff.
+    pub(crate) fn record_crash(
+        &mut self,
+        now: Instant,
+        reason: &str,
+    ) -> Result<Duration, CrashLoopError> {
+        while self.crashes.front().is_some_and(|crash| now.duration_since(*crash) > self.window) {
+            self.crashes.pop_front();
+        }
+        self.crashes.push_back(now);
+
+        if self.crashes.len() < self.max_crashes {
+            // the node recovered, start over if it crash-loops again later
+            self.backoff = INITIAL_BACKOFF.min(self.max_backoff);
+            self.last_backoff = Duration::ZERO;
+            return Ok(Duration::ZERO);
+        }
+
+        if self.last_backoff >= self.max_backoff {
+            return Err(CrashLoopError {
+                crashes: self.crashes.len(),
+                window: self.window,
+                reason: reason.to_string(),
+            });
+        }
+
+        self.last_backoff = self.backoff;
+        self.backoff = (self.backoff * 2).min(self.max_backoff);
+        Ok(self.last_backoff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CrashLoopGuard, INITIAL_BACKOFF};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_crash_loop_backoff_then_exit() {
+        let window = Duration::from_secs(60);
+        let max_backoff = Duration::from_secs(4);
+        let mut guard = CrashLoopGuard::new(3, window, max_backoff);
+        let now = Instant::now();
+
+        // crashes below the limit relaunch immediately
+        assert_eq!(guard.record_crash(now, "crash").unwrap(), Duration::ZERO);
+        assert_eq!(guard.record_crash(now, "crash").unwrap(), Duration::ZERO);
+
+        // crash loop backs off exponentially
+        assert_eq!(guard.record_crash(now, "crash").unwrap(), INITIAL_BACKOFF);
+        assert_eq!(guard.record_crash(now, "crash").unwrap(), INITIAL_BACKOFF * 2);
+        assert_eq!(guard.record_crash(now, "crash").unwrap(), max_backoff);
+
+        // crashing again after the longest backoff gives up
+        let err = guard.record_crash(now, "engine").unwrap_err();
+        assert_eq!(err.crashes, 6);
+        assert_eq!(err.reason, "engine");
+    }
+
+    #[test]
+    fn test_crashes_outside_window_are_forgotten() {
+        let window = Duration::from_secs(60);
+        let mut guard = CrashLoopGuard::new(3, window, Duration::from_secs(4));
+        let start = Instant::now();
+
+        assert_eq!(guard.record_crash(start, "crash").unwrap(), Duration::ZERO);
+        assert_eq!(guard.record_crash(start, "crash").unwrap(), Duration::ZERO);
+        assert_eq!(guard.record_crash(start, "crash").unwrap(), INITIAL_BACKOFF);
+
+        // the earlier crashes fell out of the window
+        let later = start + window + Duration::from_secs(1);
+        assert_eq!(guard.record_crash(later, "crash").unwrap(), Duration::ZERO);
+        assert_eq!(guard.record_crash(later, "crash").unwrap(), Duration::ZERO);
+        // the backoff restarts from the beginning
+        assert_eq!(guard.record_crash(later, "crash").unwrap(), INITIAL_BACKOFF);
+    }
+}
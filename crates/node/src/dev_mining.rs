@@ -0,0 +1,73 @@
+//! Mine blocks on demand for the `tn_dev` RPC namespace.
+//!
+//! A block is only executed once consensus commits the header with its batch, which takes at
+//! least two rounds. The miner asks the batch builder to seal a batch and then asks the proposer
+//! for a header each round until the engine executes a new block, so callers don't wait for the
+//! header delays.
+
+use async_trait::async_trait;
+use std::time::Duration;
+use tn_primary::ConsensusBus;
+use tn_rpc::{DevBlockMiner, TNRpcError, TelcoinNetworkRpcResult};
+use tn_types::BlockNumHash;
+use tokio::sync::mpsc;
+
+/// The capacity of the batch builder's seal request channel.
+pub(crate) const DEV_SEAL_REQUEST_CAPACITY: usize = 16;
+
+/// How long to wait for a block before proposing another header.
+const PROPOSE_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long to wait for the block to execute.
+const MINE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Mines blocks on demand in a single-node committee.
+#[derive(Debug)]
+pub(crate) struct DevMiner {
+    /// The consensus bus for requesting headers and watching executed blocks.
+    consensus_bus: ConsensusBus,
+    /// Requests the batch builder to seal a batch.
+    seal_requests: mpsc::Sender<()>,
+}
+
+impl DevMiner {
+    /// Create a new instance of [Self].
+    pub(crate) fn new(consensus_bus: ConsensusBus, seal_requests: mpsc::Sender<()>) -> Self {
+        Self { consensus_bus, seal_requests }
+    }
+}
+
+#[async_trait]
+impl DevBlockMiner for DevMiner {
+    async fn mine_block(&self) -> TelcoinNetworkRpcResult<BlockNumHash> {
+        let mut executed = self.consensus_bus.recent_blocks().subscribe();
+        let start = executed.borrow_and_update().latest_block_num_hash().number;
+        self.seal_requests
+            .send(())
+            .await
+            .map_err(|_| TNRpcError::DevMining("batch builder is not running".to_string()))?;
+
+        let mine = async {
+            loop {
+                // leaders are committed by headers in later rounds, so keep proposing
+                self.consensus_bus.propose_now().send_modify(|requests| *requests += 1);
+                match tokio::time::timeout(PROPOSE_INTERVAL, executed.changed()).await {
+                    Ok(Ok(())) => {
+                        let latest = executed.borrow_and_update().latest_block_num_hash();
+                        if latest.number > start {
+                            return Ok(latest);
+                        }
+                    }
+                    Ok(Err(_)) => {
+                        return Err(TNRpcError::DevMining("node is shutting down".to_string()))
+                    }
+                    Err(_) => (),
+                }
+            }
+        };
+
+        tokio::time::timeout(MINE_TIMEOUT, mine).await.map_err(|_| {
+            TNRpcError::DevMining(format!("block not executed within {MINE_TIMEOUT:?}"))
+        })?
+    }
+}
@@ -16,11 +16,15 @@ use reth_provider::{
     providers::{BlockchainProvider, StaticFileProvider},
     EthStorage, ProviderFactory,
 };
-use std::{collections::HashMap, sync::Arc};
+use std::{collections::HashMap, path::PathBuf, sync::Arc};
+use tn_batch_validator::BatchVerdicts;
 use tn_config::Config;
+use tn_engine::{AuditSink, FileAuditSink};
 use tn_faucet::FaucetArgs;
 use tn_node_traits::TNExecution;
-use tn_types::TaskManager;
+use tn_types::{
+    Address, SenderRecovery, TaskManager, TxDedupFilter, DEFAULT_SENDER_CACHE_CAPACITY,
+};
 use tokio::sync::mpsc::unbounded_channel;
 use tracing::debug;
 
@@ -46,6 +50,11 @@ where
 
     // Optional components
     opt_faucet_args: Option<FaucetArgs>,
+    authorized_builders: Vec<Address>,
+    /// Open static files read-only for RPC replicas.
+    read_only: bool,
+    /// Trace executed transactions to files in this directory.
+    audit_dir: Option<PathBuf>,
 }
 
 impl<N> ExecutionNodeBuilder<N>
@@ -55,8 +64,25 @@ where
 {
     /// Start the builder with required components
     pub fn new(tn_builder: &TnBuilder<N::DB>) -> Self {
-        let TnBuilder { database, node_config, tn_config, opt_faucet_args, consensus_metrics: _ } =
-            tn_builder;
+        let TnBuilder {
+            database,
+            node_config,
+            tn_config,
+            opt_faucet_args,
+            consensus_metrics: _,
+            halt_at_sub_dag: _,
+            log_filter: _,
+            ephemeral: _,
+            authorized_builders,
+            inclusion_promises: _,
+            rpc_replica,
+            grpc: _,
+            audit_dir,
+            dev_mining: _,
+            address_index: _,
+            storage_cipher: _,
+            software_version: _,
+        } = tn_builder;
 
         Self {
             node_config: node_config.clone(),
@@ -67,6 +93,9 @@ where
             evm_executor: None,
             evm_config: None,
             opt_faucet_args: opt_faucet_args.clone(),
+            authorized_builders: authorized_builders.clone(),
+            read_only: *rpc_replica,
+            audit_dir: audit_dir.clone(),
         }
     }
 
@@ -74,14 +103,22 @@ where
     pub fn init_provider_factory(mut self) -> eyre::Result<Self> {
         // Initialize provider factory with static files
         let datadir = self.node_config.datadir();
+        // replicas watch the static files for blocks moved by the node that owns them
+        let static_file_provider = if self.read_only {
+            StaticFileProvider::read_only(datadir.static_files(), true)?
+        } else {
+            StaticFileProvider::read_write(datadir.static_files())?
+        };
         let provider_factory = ProviderFactory::new(
             self.database.clone(),
             Arc::clone(&self.node_config.chain),
-            StaticFileProvider::read_write(datadir.static_files())?,
+            static_file_provider,
         )
         .with_static_files_metrics();
 
         // Initialize genesis if needed
+        //
+        // A replica's DB is already initialized so this only checks the genesis hash.
         let genesis_hash = init_genesis(&provider_factory)?;
         debug!(target: "tn::execution", chain=%self.node_config.chain.chain, ?genesis_hash, "Initialized genesis");
 
@@ -142,6 +179,10 @@ where
             self.evm_config.ok_or_else(|| eyre::eyre!("EVM config not initialized"))?;
         let evm_executor =
             self.evm_executor.ok_or_else(|| eyre::eyre!("EVM executor not initialized"))?;
+        let opt_audit_sink = match self.audit_dir {
+            Some(dir) => Some(Arc::new(FileAuditSink::new(dir)?) as Arc<dyn AuditSink>),
+            None => None,
+        };
 
         Ok(ExecutionNodeInner {
             address: *self.tn_config.execution_address(),
@@ -151,8 +192,26 @@ where
             evm_config,
             evm_executor,
             opt_faucet_args: self.opt_faucet_args,
+            authorized_builders: self.authorized_builders,
+            tn_chain_spec: self.tn_config.tn_chain_spec()?,
             tn_config: self.tn_config,
             workers: HashMap::default(),
+            opt_node_status: None,
+            opt_sub_dag_stats: None,
+            opt_inclusion_promises: None,
+            opt_sub_dag_blocks: None,
+            opt_inclusion_proofs: None,
+            opt_state_diffs: None,
+            opt_block_provenance: None,
+            opt_transactions_by_address: None,
+            opt_sync_status: None,
+            opt_dev_miner: None,
+            opt_seal_requests: None,
+            opt_dev_state: None,
+            opt_audit_sink,
+            tx_dedup_filter: TxDedupFilter::default(),
+            sender_recovery: SenderRecovery::new(0, DEFAULT_SENDER_CACHE_CAPACITY)?,
+            batch_verdicts: BatchVerdicts::default(),
         })
     }
 }
@@ -0,0 +1,323 @@
+//! State manipulation for single-node dev networks.
+//!
+//! Changes are queued for the engine and a block is mined so they are visible once the RPC method
+//! returns. Transactions from impersonated accounts are submitted to the worker's pool with their
+//! sender already cached, so the engine never recovers their placeholder signature.
+//!
+//! Executed blocks are final, so reverting to a snapshot restores the accounts changed since the
+//! snapshot's block in a new block instead of removing blocks. Pending transactions, queued changes
+//! and impersonated accounts are restored with the state.
+
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use reth_chainspec::ChainSpec;
+use reth_provider::{
+    AccountExtReader, AccountReader as _, BlockNumReader, ChainSpecProvider,
+    DatabaseProviderFactory, HeaderProvider, StateProvider as _, StateProviderFactory,
+    StorageReader,
+};
+use reth_transaction_pool::{EthPooledTransaction, TransactionOrigin, TransactionPool};
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    sync::Arc,
+};
+use tn_rpc::{DevBlockMiner, DevStateEditor, TNRpcError, TelcoinNetworkRpcResult};
+use tn_types::{
+    AccountOverride, Address, BlockNumber, Bytes, DevStateChanges, EthSignature, ExecHeader,
+    RecoveredTx, SenderRecovery, Transaction, TransactionRequest, TransactionSigned, TxEip1559,
+    TxHash, TxKind, MIN_PROTOCOL_BASE_FEE, U256,
+};
+use tracing::info;
+
+/// The gas limit for transactions from impersonated accounts that don't set one.
+const DEFAULT_IMPERSONATED_GAS_LIMIT: u64 = 5_000_000;
+
+/// A recorded state to revert to.
+#[derive(Debug)]
+struct Snapshot {
+    /// The last block executed when the snapshot was taken.
+    block: BlockNumber,
+    /// The impersonated accounts when the snapshot was taken.
+    impersonated: HashSet<Address>,
+}
+
+/// Snapshots of the chain's state by id.
+#[derive(Debug, Default)]
+struct Snapshots {
+    /// The id of the last snapshot.
+    last_id: u64,
+    /// The recorded snapshots.
+    snapshots: BTreeMap<u64, Snapshot>,
+}
+
+/// Changes the state of a single-node dev network for the `tn_dev` RPC namespace.
+pub(super) struct DevStateController<P, Pool> {
+    /// The execution provider.
+    provider: P,
+    /// The worker's transaction pool.
+    pool: Pool,
+    /// Caches the senders of impersonated transactions for the engine.
+    sender_recovery: SenderRecovery,
+    /// Mines a block after each change.
+    miner: Arc<dyn DevBlockMiner>,
+    /// The changes applied by the engine in the next block.
+    changes: DevStateChanges,
+    /// Accounts that send transactions without signatures.
+    impersonated: Mutex<HashSet<Address>>,
+    /// The recorded snapshots.
+    snapshots: Mutex<Snapshots>,
+}
+
+impl<P, Pool> DevStateController<P, Pool>
+where
+    P: BlockNumReader
+        + HeaderProvider<Header = ExecHeader>
+        + StateProviderFactory
+        + DatabaseProviderFactory
+        + ChainSpecProvider<ChainSpec = ChainSpec>,
+    P::Provider: AccountExtReader + StorageReader,
+    Pool: TransactionPool<Transaction = EthPooledTransaction>,
+{
+    /// Create a new instance of [Self].
+    pub(super) fn new(
+        provider: P,
+        pool: Pool,
+        sender_recovery: SenderRecovery,
+        miner: Arc<dyn DevBlockMiner>,
+        changes: DevStateChanges,
+    ) -> Self {
+        Self {
+            provider,
+            pool,
+            sender_recovery,
+            miner,
+            changes,
+            impersonated: Default::default(),
+            snapshots: Default::default(),
+        }
+    }
+
+    /// Queue changes to an account and mine a block to apply them.
+    async fn apply(
+        &self,
+        address: Address,
+        changes: AccountOverride,
+    ) -> TelcoinNetworkRpcResult<()> {
+        self.changes.queue(address, changes);
+        self.miner.mine_block().await?;
+        Ok(())
+    }
+
+    /// The changes that restore every account changed after `block` to its state at `block`.
+    fn restore_changes(
+        &self,
+        block: BlockNumber,
+        latest: BlockNumber,
+    ) -> eyre::Result<BTreeMap<Address, AccountOverride>> {
+        let provider = self.provider.database_provider_ro()?;
+        let blocks = block + 1..=latest;
+        let mut changed: BTreeSet<_> = provider.changed_accounts_with_range(blocks.clone())?;
+        let changed_storage = provider.changed_storages_with_range(blocks)?;
+        changed.extend(changed_storage.keys().copied());
+
+        let state = self.provider.history_by_block_number(block)?;
+        let mut restored = BTreeMap::new();
+        for address in changed {
+            // accounts created after the snapshot are emptied and removed by the engine
+            let account = state.basic_account(address)?.unwrap_or_default();
+            let code = state.account_code(address)?.map(|code| code.original_bytes());
+            let mut storage = BTreeMap::new();
+            for key in changed_storage.get(&address).into_iter().flatten() {
+                let value = state.storage(address, *key)?.unwrap_or_default();
+                storage.insert(U256::from_be_bytes(key.0), value);
+            }
+            let changes = AccountOverride {
+                balance: Some(account.balance),
+                nonce: Some(account.nonce),
+                code: Some(code.unwrap_or_default()),
+                storage,
+            };
+            restored.insert(address, changes);
+        }
+
+        Ok(restored)
+    }
+
+    /// The next nonce for an account, including its transactions in the pool.
+    fn next_nonce(&self, address: Address) -> eyre::Result<u64> {
+        if let Some(tx) = self.pool.get_highest_transaction_by_sender(address) {
+            return Ok(tx.nonce() + 1);
+        }
+        let account = self.provider.latest()?.basic_account(address)?;
+        Ok(account.map(|account| account.nonce).unwrap_or_default())
+    }
+
+    /// The base fee of the latest block.
+    fn latest_base_fee(&self) -> eyre::Result<u64> {
+        let latest = self.provider.last_block_number()?;
+        let base_fee = self.provider.sealed_header(latest)?.and_then(|h| h.base_fee_per_gas);
+        Ok(base_fee.unwrap_or(MIN_PROTOCOL_BASE_FEE))
+    }
+
+    /// Build a transaction from an impersonated account, filling in missing fields.
+    fn impersonated_transaction(
+        &self,
+        from: Address,
+        request: TransactionRequest,
+    ) -> eyre::Result<TransactionSigned> {
+        let chain_id = self.provider.chain_spec().chain.id();
+        if request.chain_id.is_some_and(|id| id != chain_id) {
+            eyre::bail!("chain id must be {chain_id}");
+        }
+        let nonce = match request.nonce {
+            Some(nonce) => nonce,
+            None => self.next_nonce(from)?,
+        };
+        let max_fee_per_gas = match request.max_fee_per_gas.or(request.gas_price) {
+            Some(max_fee) => max_fee,
+            None => self.latest_base_fee()? as u128 * 2,
+        };
+
+        let transaction = Transaction::Eip1559(TxEip1559 {
+            chain_id,
+            nonce,
+            max_priority_fee_per_gas: request.max_priority_fee_per_gas.unwrap_or_default(),
+            max_fee_per_gas,
+            gas_limit: request.gas.unwrap_or(DEFAULT_IMPERSONATED_GAS_LIMIT),
+            to: request.to.unwrap_or(TxKind::Create),
+            value: request.value.unwrap_or_default(),
+            input: request.input.into_input().unwrap_or_default(),
+            access_list: request.access_list.unwrap_or_default(),
+        });
+
+        // the signature is never recovered since the sender is cached, but the hash commits to it
+        // so transactions from different senders with the same fields get different hashes
+        let signature =
+            EthSignature::new(U256::from_be_slice(from.as_slice()), U256::from(1), false);
+        Ok(TransactionSigned::new_unhashed(transaction, signature))
+    }
+}
+
+#[async_trait]
+impl<P, Pool> DevStateEditor for DevStateController<P, Pool>
+where
+    P: BlockNumReader
+        + HeaderProvider<Header = ExecHeader>
+        + StateProviderFactory
+        + DatabaseProviderFactory
+        + ChainSpecProvider<ChainSpec = ChainSpec>
+        + Send
+        + Sync
+        + 'static,
+    P::Provider: AccountExtReader + StorageReader,
+    Pool: TransactionPool<Transaction = EthPooledTransaction> + 'static,
+{
+    async fn set_balance(&self, address: Address, balance: U256) -> TelcoinNetworkRpcResult<()> {
+        let changes = AccountOverride { balance: Some(balance), ..Default::default() };
+        self.apply(address, changes).await
+    }
+
+    async fn set_code(&self, address: Address, code: Bytes) -> TelcoinNetworkRpcResult<()> {
+        let changes = AccountOverride { code: Some(code), ..Default::default() };
+        self.apply(address, changes).await
+    }
+
+    fn impersonate_account(&self, address: Address) {
+        info!(target: "tn::execution", ?address, "impersonating account");
+        self.impersonated.lock().insert(address);
+    }
+
+    fn stop_impersonating_account(&self, address: Address) {
+        self.impersonated.lock().remove(&address);
+    }
+
+    fn is_impersonated(&self, address: &Address) -> bool {
+        self.impersonated.lock().contains(address)
+    }
+
+    async fn send_impersonated_transaction(
+        &self,
+        request: TransactionRequest,
+    ) -> TelcoinNetworkRpcResult<TxHash> {
+        let from = request
+            .from
+            .ok_or_else(|| TNRpcError::DevState("transaction is missing `from`".to_string()))?;
+        if !self.impersonated.lock().contains(&from) {
+            return Err(TNRpcError::NotImpersonated(from));
+        }
+
+        let transaction = self
+            .impersonated_transaction(from, request)
+            .map_err(|e| TNRpcError::DevState(e.to_string()))?;
+        let tx_hash = transaction.hash();
+        let pooled = transaction
+            .try_into_pooled()
+            .map_err(|_| TNRpcError::DevState("blob transactions are not supported".to_string()))?;
+
+        // cache the sender before the batch builder can include the transaction
+        self.sender_recovery.insert_sender(tx_hash, from);
+        let recovered = RecoveredTx::from_signed_transaction(pooled, from);
+        self.pool
+            .add_transaction(TransactionOrigin::Local, recovered.into())
+            .await
+            .map_err(|e| TNRpcError::DevState(e.to_string()))
+    }
+
+    async fn snapshot(&self) -> TelcoinNetworkRpcResult<U256> {
+        let block =
+            self.provider.last_block_number().map_err(|e| TNRpcError::DevState(e.to_string()))?;
+        let impersonated = self.impersonated.lock().clone();
+        let mut snapshots = self.snapshots.lock();
+        snapshots.last_id += 1;
+        let id = snapshots.last_id;
+        snapshots.snapshots.insert(id, Snapshot { block, impersonated });
+        Ok(U256::from(id))
+    }
+
+    async fn revert(&self, id: U256) -> TelcoinNetworkRpcResult<bool> {
+        let Ok(id) = u64::try_from(id) else {
+            return Ok(false);
+        };
+        let Snapshot { block, impersonated } = {
+            let mut snapshots = self.snapshots.lock();
+            if !snapshots.snapshots.contains_key(&id) {
+                return Ok(false);
+            }
+            // the snapshot and every later snapshot are removed
+            let mut removed = snapshots.snapshots.split_off(&id);
+            removed.remove(&id).expect("snapshot exists")
+        };
+        *self.impersonated.lock() = impersonated;
+
+        // transactions and changes made after the snapshot must not be mined with the restored
+        // state
+        self.changes.take();
+        let pending = self.pool.all_transaction_hashes();
+        let removed = self.pool.remove_transactions(pending).len();
+
+        let restore = || {
+            let latest = self.provider.last_block_number()?;
+            if latest <= block {
+                return Ok(BTreeMap::new());
+            }
+            self.restore_changes(block, latest)
+        };
+        let restored = restore().map_err(|e: eyre::Report| TNRpcError::DevState(e.to_string()))?;
+        info!(
+            target: "tn::execution",
+            id,
+            block,
+            accounts = restored.len(),
+            removed,
+            "reverting to snapshot"
+        );
+        if !restored.is_empty() {
+            for (address, changes) in restored {
+                self.changes.queue(address, changes);
+            }
+            self.miner.mine_block().await?;
+        }
+
+        Ok(true)
+    }
+}
@@ -0,0 +1,110 @@
+//! Batch candidates from external builders.
+//!
+//! Candidates are built into a batch on the finalized block like the worker's own batches, then
+//! validated like a peer's batch before the worker seals them.
+
+use async_trait::async_trait;
+use reth_chainspec::ChainSpec;
+use reth_provider::{BlockIdReader, ChainSpecProvider, HeaderProvider};
+use std::sync::Arc;
+use tn_rpc::{ExternalBatchSubmitter, TNRpcError, TelcoinNetworkRpcResult};
+use tn_types::{
+    now, Address, Batch, BatchSender, BatchValidation, ExecHeader, SealedHeader, B256,
+    MIN_PROTOCOL_BASE_FEE,
+};
+use tokio::sync::oneshot;
+use tracing::info;
+
+/// Seals batch candidates from external builders with a worker.
+pub(super) struct WorkerBatchSubmitter<P> {
+    /// Provider for the finalized block batches build on.
+    provider: P,
+    /// Validates candidates like a peer's batch.
+    validator: Arc<dyn BatchValidation>,
+    /// The sending side to the worker's batch maker.
+    to_worker: BatchSender,
+    /// The beneficiary for batches, the same as the worker's own batches.
+    beneficiary: Address,
+}
+
+impl<P> WorkerBatchSubmitter<P>
+where
+    P: BlockIdReader
+        + HeaderProvider<Header = ExecHeader>
+        + ChainSpecProvider<ChainSpec = ChainSpec>,
+{
+    /// Create a new instance of [Self].
+    pub(super) fn new(
+        provider: P,
+        validator: Arc<dyn BatchValidation>,
+        to_worker: BatchSender,
+        beneficiary: Address,
+    ) -> Self {
+        Self { provider, validator, to_worker, beneficiary }
+    }
+
+    /// The finalized block, or genesis before anything is executed.
+    fn finalized_header(&self) -> TelcoinNetworkRpcResult<SealedHeader> {
+        let lookup = || -> eyre::Result<Option<SealedHeader>> {
+            match self.provider.finalized_block_number()? {
+                Some(number) => Ok(self.provider.sealed_header(number)?),
+                None => Ok(Some(self.provider.chain_spec().sealed_genesis_header())),
+            }
+        };
+        lookup()
+            .map_err(|e| TNRpcError::BatchSeal(e.to_string()))?
+            .ok_or_else(|| TNRpcError::BatchSeal("finalized block not found".to_string()))
+    }
+}
+
+#[async_trait]
+impl<P> ExternalBatchSubmitter for WorkerBatchSubmitter<P>
+where
+    P: BlockIdReader
+        + HeaderProvider<Header = ExecHeader>
+        + ChainSpecProvider<ChainSpec = ChainSpec>
+        + 'static,
+{
+    async fn submit_batch(
+        &self,
+        builder: Address,
+        parent_hash: B256,
+        transactions: Vec<Vec<u8>>,
+    ) -> TelcoinNetworkRpcResult<B256> {
+        let parent = self.finalized_header()?;
+        if parent_hash != parent.hash() {
+            return Err(TNRpcError::InvalidBatch(format!(
+                "parent {parent_hash} is not the finalized block {}",
+                parent.hash()
+            )));
+        }
+
+        let batch = Batch {
+            transactions,
+            parent_hash,
+            beneficiary: self.beneficiary,
+            // batch timestamps must increase
+            timestamp: now().max(parent.timestamp + 1),
+            base_fee_per_gas: Some(parent.base_fee_per_gas.unwrap_or(MIN_PROTOCOL_BASE_FEE)),
+            received_at: None,
+        }
+        .seal_slow();
+        let digest = batch.digest();
+        self.validator
+            .validate_batch(batch.clone())
+            .map_err(|e| TNRpcError::InvalidBatch(e.to_string()))?;
+
+        let (ack, rx_ack) = oneshot::channel();
+        self.to_worker
+            .send((batch, ack))
+            .await
+            .map_err(|_| TNRpcError::BatchSeal("worker is not running".to_string()))?;
+        rx_ack
+            .await
+            .map_err(|_| TNRpcError::BatchSeal("worker dropped the batch".to_string()))?
+            .map_err(|e| TNRpcError::BatchSeal(e.to_string()))?;
+
+        info!(target: "tn::execution", %builder, %digest, "sealed batch from external builder");
+        Ok(digest)
+    }
+}
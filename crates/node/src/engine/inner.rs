@@ -2,14 +2,18 @@
 //!
 //! This module contains the logic for execution.
 
-use super::{WorkerComponents, WorkerTxPool};
-use crate::{engine::WorkerNetwork, error::ExecutionError};
+use super::{
+    dev_state::DevStateController, external_batch::WorkerBatchSubmitter, WorkerComponents,
+    WorkerTxPool,
+};
+use crate::{engine::WorkerNetwork, error::ExecutionError, governance::GovernedGasTarget};
 use eyre::eyre;
-use jsonrpsee::http_client::HttpClient;
+use futures::StreamExt as _;
+use jsonrpsee::{http_client::HttpClient, server::middleware::rpc::RpcServiceBuilder};
 use reth::{
     primitives::EthPrimitives,
     rpc::{
-        builder::{config::RethRpcServerConfig, RpcModuleBuilder, RpcServerHandle},
+        builder::{config::RethRpcServerConfig, RethRpcModule, RpcModuleBuilder, RpcServerHandle},
         eth::EthApi,
     },
 };
@@ -20,29 +24,67 @@ use reth_db::{
 };
 use reth_node_builder::{NodeConfig, RethTransactionPoolConfig};
 use reth_provider::{
-    providers::BlockchainProvider, BlockIdReader, BlockNumReader, BlockReader,
+    providers::BlockchainProvider, BlockIdReader, BlockNumReader, BlockReader, CanonChainTracker,
     CanonStateSubscriptions as _, ChainSpecProvider, ChainStateBlockReader,
     DatabaseProviderFactory, EthStorage, HeaderProvider, ProviderFactory, TransactionVariant,
 };
+use reth_prune::{Pruner, PrunerBuilder};
+use reth_static_file::{HighestStaticFiles, StaticFileProducer};
 use reth_transaction_pool::{
-    blobstore::DiskFileBlobStore, TransactionPool, TransactionValidationTaskExecutor,
+    blobstore::DiskFileBlobStore, noop::NoopTransactionPool, TransactionPool,
+    TransactionValidationTaskExecutor,
 };
-use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+use std::{collections::HashMap, net::SocketAddr, sync::Arc, time::Duration};
 use tn_batch_builder::BatchBuilder;
-use tn_batch_validator::BatchValidator;
-use tn_config::Config;
-use tn_engine::ExecutorEngine;
+use tn_batch_validator::{BatchValidator, BatchVerdicts};
+use tn_config::{Config, TnChainSpec};
+use tn_engine::{AuditSink, ExecutorEngine};
 use tn_faucet::{FaucetArgs, FaucetRpcExtApiServer as _};
 use tn_node_traits::{TNExecution, TelcoinNodeTypes};
-use tn_rpc::{TelcoinNetworkRpcExt, TelcoinNetworkRpcExtApiServer};
+use tn_rpc::{
+    BlockProvenanceProvider, DeferTransactionsLayer, DevBlockMiner, DevStateEditor,
+    ImpersonatedTransactionsLayer, InclusionProofProvider, NodeModeAwareLayer, NodeStatusProvider,
+    StateDiffProvider, SubDagBlockResolver, SubDagBlockTagsLayer, SubDagStatsProvider,
+    SyncStatusProvider, TelcoinNetworkAdminApiServer as _, TelcoinNetworkAdminExt,
+    TelcoinNetworkBuilderApiServer as _, TelcoinNetworkBuilderExt, TelcoinNetworkDevApiServer as _,
+    TelcoinNetworkDevExt, TelcoinNetworkRpcExt, TelcoinNetworkRpcExtApiServer,
+    TransactionsByAddressProvider,
+};
 use tn_types::{
-    Address, BatchSender, BatchValidation, BlockBody, ConsensusOutput, EnvKzgSettings, ExecHeader,
-    LastCanonicalUpdate, Noticer, SealedBlock, SealedBlockWithSenders, SealedHeader, TaskManager,
-    WorkerId, B256, MIN_PROTOCOL_BASE_FEE,
+    Address, BatchSender, BatchValidation, BlockBody, BlockNumber, ConsensusOutput,
+    DeferredTransactions, DevStateChanges, EnvKzgSettings, ExecHeader, InclusionPromises,
+    LastCanonicalUpdate, Noticer, Notifier, SealedBlock, SealedBlockWithSenders, SealedHeader,
+    SenderRecovery, SharedClock, TaskManager, TxDedupFilter, WorkerId, B256, MIN_PROTOCOL_BASE_FEE,
 };
-use tokio::sync::broadcast;
+use tokio::sync::{broadcast, mpsc, watch};
 use tokio_stream::wrappers::BroadcastStream;
-use tracing::{error, info};
+use tracing::{error, info, warn};
+
+/// The subdirectory of the data directory for diagnostics written when execution diverges from the
+/// committee.
+const DIVERGENCE_DIR: &str = "divergence";
+
+/// How often a read-only replica checks the DB for new blocks.
+const REPLICA_HEAD_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Set the replica's canonical, safe, and finalized heads to the latest block in the DB.
+///
+/// Blocks are only written once the consensus output they were executed for is committed, so the
+/// latest block is final.
+fn update_replica_head<N>(blockchain_db: &BlockchainProvider<N>) -> eyre::Result<()>
+where
+    N: TelcoinNodeTypes<ChainSpec = ChainSpec, Primitives = EthPrimitives, Storage = EthStorage>,
+    N::DB: Database + DatabaseMetrics + DatabaseMetadata + Clone + Unpin + 'static,
+{
+    let provider = blockchain_db.database_provider_ro()?;
+    let latest = provider.last_block_number()?;
+    if let Some(header) = provider.sealed_header(latest)? {
+        blockchain_db.set_canonical_head(header.clone());
+        blockchain_db.set_finalized(header.clone());
+        blockchain_db.set_safe(header);
+    }
+    Ok(())
+}
 
 /// Inner type for holding execution layer types.
 pub(super) struct ExecutionNodeInner<N>
@@ -57,6 +99,8 @@ where
     pub(super) address: Address,
     /// The validator node config.
     pub(super) tn_config: Config,
+    /// The parameters every node on the network agrees on, read from genesis.
+    pub(super) tn_chain_spec: TnChainSpec,
     /// The type that holds all information needed to launch the node's engine.
     ///
     /// The [NodeConfig] is reth-specific and holds many helper functions that
@@ -74,9 +118,74 @@ where
     pub(super) evm_config: N::EvmConfig,
     /// TODO: temporary solution until upstream reth supports public rpc hooks
     pub(super) opt_faucet_args: Option<FaucetArgs>,
+    /// External builders allowed to submit batches through the `tnBuilder` RPC namespace.
+    pub(super) authorized_builders: Vec<Address>,
     /// Collection of execution components by worker.
     pub(super) workers: HashMap<WorkerId, WorkerComponents<N>>,
     // TODO: add Pool to self.workers for direct access (tests)
+    /// The provider for the `tnAdmin` RPC namespace.
+    ///
+    /// The namespace is only available if the node sets a provider before the RPC starts.
+    pub(super) opt_node_status: Option<Arc<dyn NodeStatusProvider>>,
+    /// The provider for `tn_getSubDagStats`.
+    ///
+    /// The method returns an error if the node doesn't set a provider before the RPC starts.
+    pub(super) opt_sub_dag_stats: Option<Arc<dyn SubDagStatsProvider>>,
+    /// The worker's inclusion promises served by `tn_getInclusionPromise`.
+    ///
+    /// The method returns an error if the node doesn't set them before the RPC starts.
+    pub(super) opt_inclusion_promises: Option<InclusionPromises>,
+    /// Resolves sub-dags to execution blocks for `tn_getSubDagBlock` and `tn:subdag:<n>` tags.
+    ///
+    /// Tags are not resolved if the node doesn't set a resolver before the RPC starts.
+    pub(super) opt_sub_dag_blocks: Option<Arc<dyn SubDagBlockResolver>>,
+    /// The provider for `tn_getTransactionInclusionProof`.
+    ///
+    /// The method returns an error if the node doesn't set a provider before the RPC starts.
+    pub(super) opt_inclusion_proofs: Option<Arc<dyn InclusionProofProvider>>,
+    /// The provider for `tn_getStateDiff` and `tn_getSubDagStateDiff`.
+    ///
+    /// The methods return an error if the node doesn't set a provider before the RPC starts.
+    pub(super) opt_state_diffs: Option<Arc<dyn StateDiffProvider>>,
+    /// The provider for `tn_getBlockProvenance`.
+    ///
+    /// The method returns an error if the node doesn't set a provider before the RPC starts.
+    pub(super) opt_block_provenance: Option<Arc<dyn BlockProvenanceProvider>>,
+    /// The provider for `tn_getTransactionsByAddress`.
+    ///
+    /// The method returns an error if the node doesn't set a provider before the RPC starts.
+    pub(super) opt_transactions_by_address: Option<Arc<dyn TransactionsByAddressProvider>>,
+    /// The provider for `tn_syncStatus` and the node mode aware `eth_syncing`.
+    ///
+    /// Without a provider `eth_syncing` is answered by the eth namespace and transactions are
+    /// always accepted.
+    pub(super) opt_sync_status: Option<Arc<dyn SyncStatusProvider>>,
+    /// Mines blocks on demand for the `tn_dev` RPC namespace.
+    ///
+    /// The namespace is only available if the node sets a miner before the RPC starts.
+    pub(super) opt_dev_miner: Option<Arc<dyn DevBlockMiner>>,
+    /// Requests for the batch builder to seal a batch immediately, set with the dev miner.
+    pub(super) opt_seal_requests: Option<mpsc::Receiver<()>>,
+    /// State changes from the `tn_dev` RPC namespace applied by the engine, set with the dev
+    /// miner.
+    pub(super) opt_dev_state: Option<DevStateChanges>,
+    /// Traces every transaction executed by the engine.
+    ///
+    /// Transactions are not traced if the node doesn't set a sink before the engine starts.
+    pub(super) opt_audit_sink: Option<Arc<dyn AuditSink>>,
+    /// Transactions already included in batches from peers.
+    ///
+    /// Shared by the worker network, which records peer batches, and the batch builder.
+    pub(super) tx_dedup_filter: TxDedupFilter,
+    /// Recovers transaction senders on a dedicated thread pool.
+    ///
+    /// Shared by batch validation and the engine so each sender is only recovered once.
+    pub(super) sender_recovery: SenderRecovery,
+    /// The verdicts of validated batches.
+    ///
+    /// Shared by every batch validator so a batch received by gossip, fetched, or submitted to
+    /// the builder is only validated once.
+    pub(super) batch_verdicts: BatchVerdicts,
 }
 
 impl<N> ExecutionNodeInner<N>
@@ -91,30 +200,51 @@ where
     pub(super) async fn start_engine(
         &self,
         from_consensus: broadcast::Receiver<ConsensusOutput>,
+        halt_at_sub_dag: watch::Receiver<Option<u64>>,
         task_manager: &TaskManager,
-        rx_shutdown: Noticer,
+        shutdown: &Notifier,
     ) -> eyre::Result<()> {
         let head = self.node_config.lookup_head(&self.provider_factory)?;
 
         // TODO: call hooks?
 
+        let tn_chain_spec = self.tn_chain_spec.clone();
         let parent_header = self.blockchain_db.sealed_header(head.number)?.expect("Failed to retrieve sealed header from head's block number while starting executor engine");
 
         // spawn execution engine to extend canonical tip
-        let tn_engine = ExecutorEngine::new(
+        let mut tn_engine = ExecutorEngine::new(
             self.blockchain_db.clone(),
             self.evm_config.clone(),
             self.node_config.debug.max_block,
             BroadcastStream::new(from_consensus),
             parent_header,
-            rx_shutdown,
-        );
+            shutdown.subscribe(),
+        )
+        .with_halt_at_sub_dag(halt_at_sub_dag)
+        .with_divergence_dump_dir(self.node_config.datadir().data_dir().join(DIVERGENCE_DIR))
+        .with_sender_recovery(self.sender_recovery.clone())
+        .with_timestamp_policy(tn_chain_spec.timestamp_policy)
+        .with_forks(tn_chain_spec.forks)
+        .with_batch_gas(tn_chain_spec.batch_gas_schedule())
+        .with_system_calls(tn_chain_spec.system_calls);
+        if let Some(sink) = self.opt_audit_sink.clone() {
+            info!(target: "engine", ?sink, "tracing executed transactions");
+            tn_engine = tn_engine.with_audit_sink(sink);
+        }
+        if let Some(dev_state) = self.opt_dev_state.clone() {
+            tn_engine = tn_engine.with_dev_state(dev_state);
+        }
 
         // spawn tn engine
+        let shutdown = shutdown.clone();
         task_manager.spawn_task("consensus engine", async move {
             let res = tn_engine.await;
             match res {
-                Ok(_) => info!(target: "engine", "TN Engine exited gracefully"),
+                Ok(_) => {
+                    info!(target: "engine", "TN Engine exited gracefully");
+                    // the engine halted or consensus ended, this is not a crash
+                    shutdown.notify();
+                }
                 Err(e) => error!(target: "engine", ?e, "TN Engine error"),
             }
         });
@@ -122,11 +252,77 @@ where
         Ok(())
     }
 
+    /// Spawn a task that moves finalized blocks from the database to static files.
+    ///
+    /// Headers, transactions, and receipts are copied to static files as blocks are finalized,
+    /// then the pruner removes the copies from the database. Historical queries read from the
+    /// static files instead. Receipts are not copied if the node prunes them.
+    pub(super) fn start_static_file_producer(
+        &self,
+        task_manager: &TaskManager,
+        rx_shutdown: Noticer,
+    ) {
+        let prune_config = self.node_config.prune_config().unwrap_or_default();
+        let static_file_producer =
+            StaticFileProducer::new(self.provider_factory.clone(), prune_config.segments.clone());
+        let mut pruner = PrunerBuilder::new(prune_config)
+            .build_with_provider_factory(self.provider_factory.clone());
+        let blockchain_db = self.blockchain_db.clone();
+        let mut canon_state = self.blockchain_db.canonical_state_stream();
+
+        task_manager.spawn_task("static file producer", async move {
+            loop {
+                tokio::select!(
+                    _ = &rx_shutdown => break,
+                    notification = canon_state.next() => {
+                        if notification.is_none() {
+                            break;
+                        }
+
+                        // consensus output is final once executed
+                        let finalized = match blockchain_db.finalized_block_number() {
+                            Ok(Some(finalized)) => finalized,
+                            Ok(None) => continue,
+                            Err(e) => {
+                                error!(target: "engine", ?e, "failed to read finalized block");
+                                continue;
+                            }
+                        };
+
+                        let producer = static_file_producer.clone();
+                        let task = tokio::task::spawn_blocking(move || {
+                            let res = move_to_static_files(&producer, &mut pruner, finalized);
+                            (pruner, res)
+                        });
+                        match task.await {
+                            Ok((returned, res)) => {
+                                pruner = returned;
+                                if let Err(e) = res {
+                                    error!(
+                                        target: "engine",
+                                        ?e,
+                                        finalized,
+                                        "failed to move blocks to static files"
+                                    );
+                                }
+                            }
+                            Err(e) => {
+                                error!(target: "engine", ?e, "static file producer panicked");
+                                break;
+                            }
+                        }
+                    }
+                )
+            }
+        });
+    }
+
     /// The worker's RPC, TX pool, and block builder
     pub(super) async fn start_batch_builder(
         &mut self,
         worker_id: WorkerId,
         block_provider_sender: BatchSender,
+        clock: SharedClock,
         task_manager: &TaskManager,
         rx_shutdown: Noticer,
     ) -> eyre::Result<()> {
@@ -227,15 +423,33 @@ where
             pending_block_blob_fee: tx_pool_latest.pending_blob_fee,
         };
 
+        // transactions accepted by the RPC while the worker's batches can't reach quorum
+        let deferred_transactions =
+            DeferredTransactions::new(self.tn_config.parameters.txpool.max_deferred_count);
         let batch_builder = BatchBuilder::new(
             self.blockchain_db.clone(),
             transaction_pool.clone(),
             self.blockchain_db.canonical_state_stream(),
             latest_canon_state,
-            block_provider_sender,
+            block_provider_sender.clone(),
             self.address,
             self.tn_config.parameters.max_batch_delay,
-        );
+        )
+        .with_max_queued_lifetime(self.node_config.txpool.max_queued_lifetime)
+        .with_dedup_filter(self.tx_dedup_filter.clone())
+        .with_mining_mode(self.tn_config.parameters.mining_mode)
+        .with_empty_batches(self.tn_chain_spec.seal_empty_batches)
+        .with_clock(clock)
+        .with_deferred_transactions(deferred_transactions.clone());
+        let batch_builder =
+            match GovernedGasTarget::new(&self.tn_chain_spec, self.blockchain_db.clone()) {
+                Some(gas_target) => batch_builder.with_gas_target_source(Arc::new(gas_target)),
+                None => batch_builder,
+            };
+        let batch_builder = match self.opt_seal_requests.take() {
+            Some(seal_requests) => batch_builder.with_seal_requests(seal_requests),
+            None => batch_builder,
+        };
 
         // spawn block builder task
         task_manager.spawn_task("batch builder", async move {
@@ -270,13 +484,95 @@ where
 
         // extend TN namespace
         let engine_to_primary = (); // TODO: pass client/server here
-        let tn_ext = TelcoinNetworkRpcExt::new(self.blockchain_db.chain_spec(), engine_to_primary);
+        let mut tn_ext =
+            TelcoinNetworkRpcExt::new(self.blockchain_db.chain_spec(), engine_to_primary);
+        if let Some(sub_dag_stats) = self.opt_sub_dag_stats.clone() {
+            tn_ext = tn_ext.with_sub_dag_stats(sub_dag_stats);
+        }
+        if let Some(sub_dag_blocks) = self.opt_sub_dag_blocks.clone() {
+            tn_ext = tn_ext.with_sub_dag_blocks(sub_dag_blocks);
+        }
+        if let Some(inclusion_proofs) = self.opt_inclusion_proofs.clone() {
+            tn_ext = tn_ext.with_inclusion_proofs(inclusion_proofs);
+        }
+        if let Some(state_diffs) = self.opt_state_diffs.clone() {
+            tn_ext = tn_ext.with_state_diffs(state_diffs);
+        }
+        if let Some(block_provenance) = self.opt_block_provenance.clone() {
+            tn_ext = tn_ext.with_block_provenance(block_provenance);
+        }
+        if let Some(transactions_by_address) = self.opt_transactions_by_address.clone() {
+            tn_ext = tn_ext.with_transactions_by_address(transactions_by_address);
+        }
+        if let Some(sync_status) = self.opt_sync_status.clone() {
+            tn_ext = tn_ext.with_sync_status(sync_status);
+        }
+        tn_ext = tn_ext.with_deferred_transactions(deferred_transactions.clone());
+        if let Some(inclusion_promises) = self.opt_inclusion_promises.clone() {
+            tn_ext = tn_ext.with_inclusion_promises(inclusion_promises);
+        }
         if let Err(e) = server.merge_configured(tn_ext.into_rpc()) {
             error!(target: "tn::execution", "Error merging TN rpc module: {e:?}");
         }
 
         info!(target: "tn::execution", "tn rpc extension successfully merged");
 
+        // extend TN admin namespace if the admin module is enabled for the rpc
+        if let Some(node_status) = self.opt_node_status.clone() {
+            let admin_ext = TelcoinNetworkAdminExt::new(node_status);
+            match server.merge_if_module_configured(RethRpcModule::Admin, admin_ext.into_rpc()) {
+                Ok(_) => info!(target: "tn::execution", "tn admin rpc extension merged"),
+                Err(e) => {
+                    error!(target: "tn::execution", "Error merging TN admin rpc module: {e:?}")
+                }
+            }
+        }
+
+        // extend builder namespace if any external builders are authorized
+        if !self.authorized_builders.is_empty() {
+            let submitter = WorkerBatchSubmitter::new(
+                self.blockchain_db.clone(),
+                self.new_batch_validator(),
+                block_provider_sender,
+                self.address,
+            );
+            let builder_ext = TelcoinNetworkBuilderExt::new(
+                self.blockchain_db.chain_spec().chain.id(),
+                self.authorized_builders.iter().copied(),
+                Arc::new(submitter),
+            );
+            if let Err(e) = server.merge_configured(builder_ext.into_rpc()) {
+                error!(target: "tn::execution", "Error merging TN builder rpc module: {e:?}");
+            }
+
+            info!(
+                target: "tn::execution",
+                builders = ?self.authorized_builders,
+                "tn builder rpc extension merged"
+            );
+        }
+
+        // extend dev namespace if the node mines blocks on demand
+        let mut dev_editor: Option<Arc<dyn DevStateEditor>> = None;
+        if let (Some(dev_miner), Some(dev_state)) =
+            (self.opt_dev_miner.clone(), self.opt_dev_state.clone())
+        {
+            let editor = Arc::new(DevStateController::new(
+                self.blockchain_db.clone(),
+                transaction_pool.clone(),
+                self.sender_recovery.clone(),
+                dev_miner.clone(),
+                dev_state,
+            ));
+            dev_editor = Some(editor.clone());
+            let dev_ext = TelcoinNetworkDevExt::new(dev_miner, editor);
+            if let Err(e) = server.merge_configured(dev_ext.into_rpc()) {
+                error!(target: "tn::execution", "Error merging TN dev rpc module: {e:?}");
+            }
+
+            info!(target: "tn::execution", "tn dev rpc extension merged");
+        }
+
         // extend faucet namespace if included
         if let Some(faucet_args) = self.opt_faucet_args.take() {
             // create extension from CLI args
@@ -298,7 +594,16 @@ where
         }
 
         // start the RPC server
-        let server_config = self.node_config.rpc.rpc_server_config();
+        // resolve `tn:subdag:<n>` block tags before requests reach the eth namespace, answer
+        // `eth_syncing` and reject transactions based on the node's mode, defer transactions
+        // while batches can't reach quorum, and send transactions from impersonated accounts
+        let server_config = self.node_config.rpc.rpc_server_config().set_rpc_middleware(
+            RpcServiceBuilder::new()
+                .layer(SubDagBlockTagsLayer::new(self.opt_sub_dag_blocks.clone()))
+                .layer(NodeModeAwareLayer::new(self.opt_sync_status.clone()))
+                .layer(DeferTransactionsLayer::new(Some(deferred_transactions)))
+                .layer(ImpersonatedTransactionsLayer::new(dev_editor)),
+        );
         let rpc_handle = server_config.start(&server).await?;
 
         // take ownership of worker components
@@ -308,10 +613,165 @@ where
         Ok(())
     }
 
+    /// Serve RPC from the execution DB without a transaction pool or batch builder.
+    ///
+    /// Used by read-only replicas. Transactions submitted to the replica are rejected. The
+    /// canonical head is refreshed from the DB as the node that owns it executes blocks.
+    pub(super) async fn start_replica_rpc(
+        &self,
+        task_manager: &TaskManager,
+        rx_shutdown: Noticer,
+    ) -> eyre::Result<RpcServerHandle> {
+        let tn_execution = Arc::new(TNExecution {});
+        let rpc_builder = RpcModuleBuilder::default()
+            .with_provider(self.blockchain_db.clone())
+            .with_pool(NoopTransactionPool::default())
+            .with_network(WorkerNetwork::new(self.node_config.chain.clone()))
+            .with_executor(task_manager.get_spawner())
+            .with_evm_config(self.evm_config.clone())
+            .with_events(self.blockchain_db.clone())
+            .with_block_executor(self.evm_executor.clone())
+            .with_consensus(tn_execution.clone());
+
+        let modules_config = self.node_config.rpc.transport_rpc_module_config();
+        let mut server =
+            rpc_builder.build(modules_config, Box::new(EthApi::with_spawner), tn_execution);
+
+        // extend TN namespace
+        let mut tn_ext = TelcoinNetworkRpcExt::new(self.blockchain_db.chain_spec(), ());
+        if let Some(sub_dag_stats) = self.opt_sub_dag_stats.clone() {
+            tn_ext = tn_ext.with_sub_dag_stats(sub_dag_stats);
+        }
+        if let Some(sub_dag_blocks) = self.opt_sub_dag_blocks.clone() {
+            tn_ext = tn_ext.with_sub_dag_blocks(sub_dag_blocks);
+        }
+        if let Some(inclusion_proofs) = self.opt_inclusion_proofs.clone() {
+            tn_ext = tn_ext.with_inclusion_proofs(inclusion_proofs);
+        }
+        if let Some(state_diffs) = self.opt_state_diffs.clone() {
+            tn_ext = tn_ext.with_state_diffs(state_diffs);
+        }
+        if let Some(block_provenance) = self.opt_block_provenance.clone() {
+            tn_ext = tn_ext.with_block_provenance(block_provenance);
+        }
+        if let Some(transactions_by_address) = self.opt_transactions_by_address.clone() {
+            tn_ext = tn_ext.with_transactions_by_address(transactions_by_address);
+        }
+        if let Err(e) = server.merge_configured(tn_ext.into_rpc()) {
+            error!(target: "tn::execution", "Error merging TN rpc module: {e:?}");
+        }
+
+        // resolve `tn:subdag:<n>` block tags before requests reach the eth namespace
+        let server_config = self.node_config.rpc.rpc_server_config().set_rpc_middleware(
+            RpcServiceBuilder::new()
+                .layer(SubDagBlockTagsLayer::new(self.opt_sub_dag_blocks.clone())),
+        );
+        let rpc_handle = server_config.start(&server).await?;
+
+        // follow the blocks written by the node that owns the DB
+        let blockchain_db = self.blockchain_db.clone();
+        task_manager.spawn_task("replica head updates", async move {
+            let mut interval = tokio::time::interval(REPLICA_HEAD_INTERVAL);
+            loop {
+                tokio::select!(
+                    _ = &rx_shutdown => break,
+                    _ = interval.tick() => {
+                        if let Err(e) = update_replica_head(&blockchain_db) {
+                            warn!(target: "tn::execution", ?e, "failed to update replica head");
+                        }
+                    }
+                )
+            }
+        });
+
+        info!(target: "tn::execution", "replica rpc started");
+        Ok(rpc_handle)
+    }
+
+    /// Set the provider for the `tnAdmin` RPC namespace.
+    pub(super) fn set_node_status_provider(&mut self, provider: Arc<dyn NodeStatusProvider>) {
+        self.opt_node_status = Some(provider);
+    }
+
+    /// Set the provider for sub-dag execution statistics served by the `tn` RPC namespace.
+    pub(super) fn set_sub_dag_stats_provider(&mut self, provider: Arc<dyn SubDagStatsProvider>) {
+        self.opt_sub_dag_stats = Some(provider);
+    }
+
+    /// Set the resolver for sub-dag execution blocks served by the `tn` RPC namespace.
+    pub(super) fn set_sub_dag_block_resolver(&mut self, resolver: Arc<dyn SubDagBlockResolver>) {
+        self.opt_sub_dag_blocks = Some(resolver);
+    }
+
+    /// Set the provider for transaction inclusion proofs served by the `tn` RPC namespace.
+    pub(super) fn set_inclusion_proof_provider(
+        &mut self,
+        provider: Arc<dyn InclusionProofProvider>,
+    ) {
+        self.opt_inclusion_proofs = Some(provider);
+    }
+
+    /// Set the provider for state diffs served by the `tn` RPC namespace.
+    pub(super) fn set_state_diff_provider(&mut self, provider: Arc<dyn StateDiffProvider>) {
+        self.opt_state_diffs = Some(provider);
+    }
+
+    /// Set the provider for block provenance served by the `tn` RPC namespace.
+    pub(super) fn set_block_provenance_provider(
+        &mut self,
+        provider: Arc<dyn BlockProvenanceProvider>,
+    ) {
+        self.opt_block_provenance = Some(provider);
+    }
+
+    /// Set the provider for transactions by address served by the `tn` RPC namespace.
+    pub(super) fn set_transactions_by_address_provider(
+        &mut self,
+        provider: Arc<dyn TransactionsByAddressProvider>,
+    ) {
+        self.opt_transactions_by_address = Some(provider);
+    }
+
+    /// Set the provider for the node's sync status served by the worker's RPC.
+    pub(super) fn set_sync_status_provider(&mut self, provider: Arc<dyn SyncStatusProvider>) {
+        self.opt_sync_status = Some(provider);
+    }
+
+    /// Set the miner for the `tn_dev` RPC namespace and the batch builder's seal requests.
+    pub(super) fn set_dev_miner(
+        &mut self,
+        miner: Arc<dyn DevBlockMiner>,
+        seal_requests: mpsc::Receiver<()>,
+    ) {
+        self.opt_dev_miner = Some(miner);
+        self.opt_seal_requests = Some(seal_requests);
+        self.opt_dev_state = Some(DevStateChanges::default());
+    }
+
+    /// Trace every executed transaction to the sink.
+    pub(super) fn set_audit_sink(&mut self, sink: Arc<dyn AuditSink>) {
+        self.opt_audit_sink = Some(sink);
+    }
+
+    /// Set the worker's inclusion promises served by the `tn` RPC namespace.
+    pub(super) fn set_inclusion_promises(&mut self, inclusion_promises: InclusionPromises) {
+        self.opt_inclusion_promises = Some(inclusion_promises);
+    }
+
     /// Create a new block validator.
     pub(super) fn new_batch_validator(&self) -> Arc<dyn BatchValidation> {
         // batch validator
-        Arc::new(BatchValidator::<N>::new(self.blockchain_db.clone()))
+        Arc::new(
+            BatchValidator::<N>::new(self.blockchain_db.clone())
+                .with_sender_recovery(self.sender_recovery.clone())
+                .with_verdicts(self.batch_verdicts.clone())
+                .with_empty_batches(self.tn_chain_spec.seal_empty_batches),
+        )
+    }
+
+    /// Return the filter of transactions already included in peer batches.
+    pub(super) fn tx_dedup_filter(&self) -> TxDedupFilter {
+        self.tx_dedup_filter.clone()
     }
 
     /// Fetch the last executed state from the database.
@@ -464,3 +924,33 @@ where
         Ok(addr)
     }
 }
+
+/// Copy blocks up to `finalized` to static files and prune the copies from the database.
+fn move_to_static_files<N>(
+    producer: &StaticFileProducer<ProviderFactory<N>>,
+    pruner: &mut Pruner<
+        <ProviderFactory<N> as DatabaseProviderFactory>::ProviderRW,
+        ProviderFactory<N>,
+    >,
+    finalized: BlockNumber,
+) -> eyre::Result<()>
+where
+    N: TelcoinNodeTypes<ChainSpec = ChainSpec, Primitives = EthPrimitives, Storage = EthStorage>,
+    N::DB: Database + DatabaseMetrics + DatabaseMetadata + Clone + Unpin + 'static,
+{
+    let producer = producer.lock();
+    let targets = producer.get_static_file_targets(HighestStaticFiles {
+        headers: Some(finalized),
+        receipts: Some(finalized),
+        transactions: Some(finalized),
+    })?;
+    if targets.any() {
+        producer.run(targets)?;
+    }
+
+    if pruner.is_pruning_needed(finalized) {
+        pruner.run(finalized)?;
+    }
+
+    Ok(())
+}
@@ -12,6 +12,7 @@
 
 use self::inner::ExecutionNodeInner;
 use builder::ExecutionNodeBuilder;
+use reth::rpc::builder::RpcServerHandle;
 use reth_chainspec::ChainSpec;
 use reth_db::{
     database_metrics::{DatabaseMetadata, DatabaseMetrics},
@@ -20,17 +21,26 @@ use reth_db::{
 use reth_node_builder::NodeConfig;
 use reth_node_ethereum::{BasicBlockExecutorProvider, EthEvmConfig, EthExecutionStrategyFactory};
 use reth_provider::providers::BlockchainProvider;
-use std::{net::SocketAddr, sync::Arc};
+use std::{net::SocketAddr, path::PathBuf, sync::Arc};
 use tn_config::Config;
+use tn_engine::AuditSink;
 use tn_faucet::FaucetArgs;
 use tn_node_traits::{TelcoinNode, TelcoinNodeTypes};
+use tn_rpc::{
+    BlockProvenanceProvider, DevBlockMiner, InclusionProofProvider, LogFilterHandle,
+    NodeStatusProvider, StateDiffProvider, SubDagBlockResolver, SubDagStatsProvider,
+    SyncStatusProvider, TransactionsByAddressProvider,
+};
+use tn_storage::StorageCipher;
 use tn_types::{
-    BatchSender, BatchValidation, ConsensusOutput, ExecHeader, Noticer, SealedHeader, TaskManager,
-    WorkerId, B256,
+    Address, BatchSender, BatchValidation, ConsensusOutput, ExecHeader, InclusionPromises, Noticer,
+    Notifier, SealedHeader, SharedClock, TaskManager, TxDedupFilter, WorkerId, B256,
 };
-use tokio::sync::{broadcast, RwLock};
+use tokio::sync::{broadcast, mpsc, watch, RwLock};
 pub use worker::*;
 mod builder;
+mod dev_state;
+mod external_batch;
 mod inner;
 mod worker;
 
@@ -54,6 +64,73 @@ pub struct TnBuilder<DB> {
     ///
     /// The metrics will be served at the given interface and port.
     pub consensus_metrics: Option<SocketAddr>,
+    /// Stop the node after executing this consensus sub-dag.
+    pub halt_at_sub_dag: Option<u64>,
+    /// Handle to change the process's log filter through the admin RPC.
+    pub log_filter: Option<Arc<dyn LogFilterHandle>>,
+    /// Keep consensus data in memory instead of the node's data dir.
+    pub ephemeral: bool,
+    /// External builders allowed to submit batches through the `tnBuilder` RPC namespace.
+    ///
+    /// The namespace is disabled if this is empty.
+    pub authorized_builders: Vec<Address>,
+    /// Sign a promise for each transaction in the worker's sealed batches.
+    ///
+    /// Promises are served through `tn_getInclusionPromise`.
+    pub inclusion_promises: bool,
+    /// Serve RPC only, from databases opened read-only.
+    ///
+    /// The node does not join the network or run consensus. Another node must own the databases.
+    pub rpc_replica: bool,
+    /// Serve the consensus data gRPC service at this address.
+    ///
+    /// Streams committed sub-dags, certificates, and node status to indexers.
+    pub grpc: Option<SocketAddr>,
+    /// Trace every executed transaction to gzip compressed files in this directory.
+    pub audit_dir: Option<PathBuf>,
+    /// Serve the `tn_dev` RPC namespace to mine blocks and change state on demand.
+    ///
+    /// Only allowed for single-node committees.
+    pub dev_mining: bool,
+    /// Index the sender and recipient of every executed transaction.
+    ///
+    /// The index is served through `tn_getTransactionsByAddress`.
+    pub address_index: bool,
+    /// Encrypt the payload and batch tables of the consensus DB.
+    ///
+    /// The DB must always be opened with the same cipher once it is encrypted.
+    pub storage_cipher: Option<StorageCipher>,
+    /// The node software's `major.minor.patch` version advertised to peers.
+    ///
+    /// Defaults to this crate's version, binaries set their own.
+    pub software_version: String,
+}
+
+impl<DB> TnBuilder<DB> {
+    /// Create a builder for a node with the default options.
+    ///
+    /// Every optional service is disabled and consensus data is kept in the node's data dir.
+    pub fn new(database: DB, node_config: NodeConfig<ChainSpec>, tn_config: Config) -> Self {
+        Self {
+            database,
+            node_config,
+            tn_config,
+            opt_faucet_args: None,
+            consensus_metrics: None,
+            halt_at_sub_dag: None,
+            log_filter: None,
+            ephemeral: false,
+            authorized_builders: Vec::new(),
+            inclusion_promises: false,
+            rpc_replica: false,
+            grpc: None,
+            audit_dir: None,
+            dev_mining: false,
+            address_index: false,
+            storage_cipher: None,
+            software_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
 }
 
 /// Wrapper for the inner execution node components.
@@ -83,14 +160,28 @@ where
     }
 
     /// Execution engine to produce blocks after consensus.
+    ///
+    /// The engine exits after executing the consensus output number sent on `halt_at_sub_dag`.
+    /// A graceful exit notifies `shutdown` so the node stops instead of restarting.
     pub async fn start_engine(
         &self,
         from_consensus: broadcast::Receiver<ConsensusOutput>,
+        halt_at_sub_dag: watch::Receiver<Option<u64>>,
         task_manager: &TaskManager,
-        rx_shutdown: Noticer,
+        shutdown: &Notifier,
     ) -> eyre::Result<()> {
         let guard = self.internal.read().await;
-        guard.start_engine(from_consensus, task_manager, rx_shutdown).await
+        guard.start_engine(from_consensus, halt_at_sub_dag, task_manager, shutdown).await
+    }
+
+    /// Move finalized blocks to static files as the engine executes consensus output.
+    pub async fn start_static_file_producer(
+        &self,
+        task_manager: &TaskManager,
+        rx_shutdown: Noticer,
+    ) {
+        let guard = self.internal.read().await;
+        guard.start_static_file_producer(task_manager, rx_shutdown)
     }
 
     /// Batch maker
@@ -98,11 +189,138 @@ where
         &self,
         worker_id: WorkerId,
         block_provider_sender: BatchSender,
+        clock: SharedClock,
         task_manager: &TaskManager,
         rx_shutdown: Noticer,
     ) -> eyre::Result<()> {
         let mut guard = self.internal.write().await;
-        guard.start_batch_builder(worker_id, block_provider_sender, task_manager, rx_shutdown).await
+        guard
+            .start_batch_builder(worker_id, block_provider_sender, clock, task_manager, rx_shutdown)
+            .await
+    }
+
+    /// Serve RPC from the execution DB for a read-only replica.
+    ///
+    /// The RPC runs until the returned handle is dropped or stopped.
+    pub async fn start_replica_rpc(
+        &self,
+        task_manager: &TaskManager,
+        rx_shutdown: Noticer,
+    ) -> eyre::Result<RpcServerHandle> {
+        let guard = self.internal.read().await;
+        guard.start_replica_rpc(task_manager, rx_shutdown).await
+    }
+
+    /// Set the provider used to report node status through the `tnAdmin` RPC namespace.
+    ///
+    /// The namespace is merged into the worker's RPC by [Self::start_batch_builder], and only if
+    /// the `admin` module is enabled. Without a provider by then the namespace is not served.
+    pub async fn set_node_status_provider(&self, provider: Arc<dyn NodeStatusProvider>) {
+        let mut guard = self.internal.write().await;
+        guard.set_node_status_provider(provider)
+    }
+
+    /// Set the provider used to serve sub-dag execution statistics through `tn_getSubDagStats`.
+    ///
+    /// The statistics are read from the consensus DB, so both the worker's RPC and a replica's
+    /// RPC serve them. The provider is cloned into the RPC when it starts.
+    pub async fn set_sub_dag_stats_provider(&self, provider: Arc<dyn SubDagStatsProvider>) {
+        let mut guard = self.internal.write().await;
+        guard.set_sub_dag_stats_provider(provider)
+    }
+
+    /// Set the resolver used to serve sub-dag execution blocks and `tn:subdag:<n>` block tags.
+    ///
+    /// The resolver is also installed as RPC middleware when the RPC server starts. Tags reach
+    /// the `eth` namespace unresolved if it is set afterwards.
+    pub async fn set_sub_dag_block_resolver(&self, resolver: Arc<dyn SubDagBlockResolver>) {
+        let mut guard = self.internal.write().await;
+        guard.set_sub_dag_block_resolver(resolver)
+    }
+
+    /// Set the provider used to serve `tn_getTransactionInclusionProof`.
+    ///
+    /// Proofs chain the transaction to the certificate and consensus header that committed it,
+    /// read from the consensus DB. Requests fail with "inclusion proofs are not available" if the
+    /// RPC started without a provider.
+    pub async fn set_inclusion_proof_provider(&self, provider: Arc<dyn InclusionProofProvider>) {
+        let mut guard = self.internal.write().await;
+        guard.set_inclusion_proof_provider(provider)
+    }
+
+    /// Set the provider used to serve `tn_getStateDiff` and `tn_getSubDagStateDiff`.
+    ///
+    /// Both methods share the provider and report that state diffs are not available if the RPC
+    /// started without one.
+    pub async fn set_state_diff_provider(&self, provider: Arc<dyn StateDiffProvider>) {
+        let mut guard = self.internal.write().await;
+        guard.set_state_diff_provider(provider)
+    }
+
+    /// Set the provider used to serve `tn_getBlockProvenance`.
+    ///
+    /// Provenance links an executed block to its batch and consensus header. Replicas set the
+    /// provider before [Self::start_replica_rpc] and validators before
+    /// [Self::start_batch_builder].
+    pub async fn set_block_provenance_provider(&self, provider: Arc<dyn BlockProvenanceProvider>) {
+        let mut guard = self.internal.write().await;
+        guard.set_block_provenance_provider(provider)
+    }
+
+    /// Set the provider used to serve `tn_getTransactionsByAddress`.
+    ///
+    /// Only nodes that index addresses set a provider. The method returns an error on nodes
+    /// without the index.
+    pub async fn set_transactions_by_address_provider(
+        &self,
+        provider: Arc<dyn TransactionsByAddressProvider>,
+    ) {
+        let mut guard = self.internal.write().await;
+        guard.set_transactions_by_address_provider(provider)
+    }
+
+    /// Set the provider used to serve `tn_syncStatus` and `eth_syncing`, and to reject
+    /// transactions the node can't include.
+    ///
+    /// The node mode is only known once the node decided whether it can vote, so this is the last
+    /// provider set before [Self::start_batch_builder]. Replicas never set it, their `eth_syncing`
+    /// is answered by the `eth` namespace.
+    pub async fn set_sync_status_provider(&self, provider: Arc<dyn SyncStatusProvider>) {
+        let mut guard = self.internal.write().await;
+        guard.set_sync_status_provider(provider)
+    }
+
+    /// Serve the `tn_dev` RPC namespace with `miner`.
+    ///
+    /// The batch builder seals a batch immediately for each request on `seal_requests` and the
+    /// engine applies state changes requested through the namespace. The engine picks up the
+    /// queued state changes in [Self::start_engine] and the batch builder takes the seal requests
+    /// in [Self::start_batch_builder], so the miner is set before either starts. Only single-node
+    /// dev committees mine on demand.
+    pub async fn set_dev_miner(
+        &self,
+        miner: Arc<dyn DevBlockMiner>,
+        seal_requests: mpsc::Receiver<()>,
+    ) {
+        let mut guard = self.internal.write().await;
+        guard.set_dev_miner(miner, seal_requests)
+    }
+
+    /// Trace every executed transaction to `sink` instead of the audit dir.
+    ///
+    /// This must be called before the engine starts.
+    pub async fn set_audit_sink(&self, sink: Arc<dyn AuditSink>) {
+        let mut guard = self.internal.write().await;
+        guard.set_audit_sink(sink)
+    }
+
+    /// Set the worker's inclusion promises served through `tn_getInclusionPromise`.
+    ///
+    /// The same promises must be given to the worker that signs them for sealed batches, the RPC
+    /// only reads the signed promises. Nodes without inclusion promises enabled leave it unset.
+    pub async fn set_inclusion_promises(&self, inclusion_promises: InclusionPromises) {
+        let mut guard = self.internal.write().await;
+        guard.set_inclusion_promises(inclusion_promises)
     }
 
     /// Batch validator
@@ -111,6 +329,12 @@ where
         guard.new_batch_validator()
     }
 
+    /// Filter of transactions already included in peer batches.
+    pub async fn tx_dedup_filter(&self) -> TxDedupFilter {
+        let guard = self.internal.read().await;
+        guard.tx_dedup_filter()
+    }
+
     /// Retrieve the last executed block from the database to restore consensus.
     pub async fn last_executed_output(&self) -> eyre::Result<B256> {
         let guard = self.internal.read().await;
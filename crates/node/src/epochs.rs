@@ -0,0 +1,318 @@
+//! Summaries of completed epochs.
+//!
+//! Once the last consensus output of an epoch is executed the node records an [EpochSummary] with
+//! the committee, reputation scores, consensus outputs and execution blocks of the epoch. Summaries
+//! are stored in the consensus DB and uploaded to the configured [EpochArchive].
+//!
+//! Epochs are summarized in order, so epochs completed while the node was down are summarized
+//! when it executes the next consensus output. The last archived epoch is saved as well, so
+//! summaries that failed to upload are retried until the archive accepts them.
+
+use crate::{archive::EpochArchive, checkpoints::ConsensusCheckpoints};
+use futures::StreamExt as _;
+use reth_provider::{
+    BlockNumReader, CanonStateNotificationStream, HeaderProvider, StateProviderFactory,
+};
+use std::sync::Arc;
+use tn_config::TnChainSpec;
+use tn_storage::{
+    tables::{ConsensusBlockNumbersByDigest, ConsensusBlocks},
+    ConsensusStore as _, StreamOffsetStore as _,
+};
+use tn_types::{Database, EpochSummary, EpochValidator, ExecHeader, Noticer, TaskManager};
+use tokio::time::Instant;
+use tracing::{error, info, warn};
+
+/// The name the next epoch to archive is saved under.
+const ARCHIVE_OFFSET: &str = "epoch-archive";
+
+/// Builds the summary of each epoch as it completes.
+#[derive(Debug)]
+pub(crate) struct EpochSummarizer<DB, P> {
+    /// The consensus DB.
+    db: DB,
+    /// Execution state at consensus checkpoints.
+    checkpoints: Arc<ConsensusCheckpoints<DB, P>>,
+    /// The chain spec with the epoch length.
+    spec: TnChainSpec,
+}
+
+impl<DB, P> EpochSummarizer<DB, P>
+where
+    DB: Database,
+    P: BlockNumReader + HeaderProvider<Header = ExecHeader> + StateProviderFactory,
+{
+    /// Create a new instance of [Self].
+    pub(crate) fn new(
+        db: DB,
+        checkpoints: Arc<ConsensusCheckpoints<DB, P>>,
+        spec: TnChainSpec,
+    ) -> Self {
+        Self { db, checkpoints, spec }
+    }
+
+    /// The next epoch to summarize.
+    fn next_epoch(&self) -> u64 {
+        self.db.last_epoch_summary().map_or(0, |summary| summary.epoch + 1)
+    }
+
+    /// The last consensus output of `epoch`.
+    fn last_sub_dag(&self, epoch: u64) -> u64 {
+        (epoch + 1) * self.spec.epoch_length - 1
+    }
+
+    /// Build the summary of `epoch`.
+    ///
+    /// Returns `None` if the epoch's last consensus output is not in the consensus DB. The
+    /// validators are the committee recorded for the consensus epoch of the last output's leader.
+    pub(crate) fn summarize(&self, epoch: u64) -> eyre::Result<Option<EpochSummary>> {
+        let first_sub_dag = epoch * self.spec.epoch_length;
+        let last_sub_dag = self.last_sub_dag(epoch);
+        let Some(last_header) = self.db.get::<ConsensusBlocks>(&last_sub_dag)? else {
+            return Ok(None);
+        };
+        let total_sub_dags = self
+            .db
+            .skip_to::<ConsensusBlocks>(&first_sub_dag)?
+            .take_while(|(number, _)| *number <= last_sub_dag)
+            .count() as u64;
+
+        // outputs without batches do not build blocks, search for the ends of the range
+        let mut first_block = None;
+        for sub_dag in first_sub_dag..=last_sub_dag {
+            if let Some(blocks) = self.checkpoints.execution_blocks(sub_dag)? {
+                first_block = Some(*blocks.start());
+                break;
+            }
+        }
+        let last_header_block = self.checkpoints.execution_header(last_sub_dag)?;
+        let last_block = last_header_block.as_ref().map(|header| header.number);
+        let last_block_hash = last_header_block.map(|header| header.hash());
+
+        let committee_epoch = last_header.sub_dag.leader.epoch();
+        let committee = self.db.read_committee(committee_epoch)?.ok_or_else(|| {
+            eyre::eyre!("no committee recorded for consensus epoch {committee_epoch}")
+        })?;
+        let scores = &last_header.sub_dag.reputation_score.scores_per_authority;
+        let validators = committee
+            .authorities()
+            .into_iter()
+            .map(|authority| EpochValidator {
+                reputation_score: scores.get(&authority.id()).copied().unwrap_or_default(),
+                id: authority.id(),
+                protocol_key: *authority.protocol_key(),
+            })
+            .collect();
+
+        Ok(Some(EpochSummary {
+            epoch,
+            validators,
+            first_sub_dag,
+            last_sub_dag,
+            total_sub_dags,
+            first_block,
+            last_block,
+            last_block_hash,
+            checkpoint_hash: last_header.digest(),
+        }))
+    }
+
+    /// Summarize the epochs completed by executing consensus output `executed`.
+    fn summarize_completed(&self, executed: u64) -> eyre::Result<Vec<EpochSummary>> {
+        let mut summaries = Vec::new();
+        let mut epoch = self.next_epoch();
+        while self.last_sub_dag(epoch) <= executed {
+            let Some(summary) = self.summarize(epoch)? else {
+                break;
+            };
+            self.db.write_epoch_summary(&summary)?;
+            info!(
+                target: "telcoin::node",
+                epoch,
+                checkpoint = ?summary.checkpoint_hash,
+                "recorded epoch summary"
+            );
+            summaries.push(summary);
+            epoch += 1;
+        }
+        Ok(summaries)
+    }
+
+    /// The consensus output an executed block was built for.
+    fn executed_output(&self, header: &ExecHeader) -> eyre::Result<Option<u64>> {
+        let Some(digest) = header.parent_beacon_block_root else {
+            return Ok(None);
+        };
+        Ok(self.db.get::<ConsensusBlockNumbersByDigest>(&digest)?)
+    }
+
+    /// Upload the summaries that have not been archived yet, oldest first.
+    ///
+    /// Stops at the first failure, the failed summary is uploaded again by the next call.
+    async fn archive_summaries(&self, archive: &EpochArchive) -> eyre::Result<()> {
+        let mut epoch = self.db.read_stream_offset(ARCHIVE_OFFSET)?.unwrap_or_default();
+        while let Some(summary) = self.db.read_epoch_summary(epoch)? {
+            archive.upload(&summary).await?;
+            epoch += 1;
+            self.db.write_stream_offset(ARCHIVE_OFFSET, epoch)?;
+        }
+        Ok(())
+    }
+}
+
+impl<DB, P> EpochSummarizer<DB, P>
+where
+    DB: Database,
+    P: BlockNumReader
+        + HeaderProvider<Header = ExecHeader>
+        + StateProviderFactory
+        + Send
+        + Sync
+        + 'static,
+{
+    /// Spawn a task that summarizes epochs as their last consensus output is executed.
+    ///
+    /// Summaries are uploaded to `archive` if set. Failed uploads are logged and retried after the
+    /// archive's retry interval, the summary is still available in the consensus DB meanwhile.
+    pub(crate) fn spawn(
+        self,
+        archive: Option<EpochArchive>,
+        mut canon_state: CanonStateNotificationStream,
+        task_manager: &TaskManager,
+        rx_shutdown: Noticer,
+    ) {
+        task_manager.spawn_task("epoch summaries", async move {
+            // upload summaries left over from the last run first
+            let mut upload = archive.is_some();
+            let mut failed = false;
+            let retry = tokio::time::sleep(std::time::Duration::ZERO);
+            tokio::pin!(retry);
+            loop {
+                if let Some(archive) = archive.as_ref().filter(|_| upload) {
+                    upload = false;
+                    failed = match self.archive_summaries(archive).await {
+                        Ok(()) => false,
+                        Err(e) => {
+                            warn!(
+                                target: "telcoin::node",
+                                ?e,
+                                retry_in = ?archive.retry_interval(),
+                                "failed to archive epoch summaries"
+                            );
+                            retry.as_mut().reset(Instant::now() + archive.retry_interval());
+                            true
+                        }
+                    };
+                }
+                tokio::select!(
+                    _ = &rx_shutdown => break,
+                    _ = &mut retry, if failed => upload = true,
+                    notification = canon_state.next() => {
+                        let Some(notification) = notification else {
+                            break;
+                        };
+                        let summaries = self
+                            .executed_output(&notification.tip().header)
+                            .and_then(|executed| match executed {
+                                Some(executed) => self.summarize_completed(executed),
+                                None => Ok(Vec::new()),
+                            });
+                        match summaries {
+                            // failed uploads wait for the retry interval
+                            Ok(summaries) => upload |= !summaries.is_empty() && !failed,
+                            Err(e) => {
+                                error!(target: "telcoin::node", ?e, "failed to summarize epoch");
+                            }
+                        }
+                    }
+                )
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EpochSummarizer;
+    use crate::checkpoints::ConsensusCheckpoints;
+    use reth_provider::test_utils::MockEthProvider;
+    use std::{collections::BTreeSet, sync::Arc};
+    use tn_config::TnChainSpec;
+    use tn_storage::{mem_db::MemDatabase, tables::ConsensusBlocks, ConsensusStore as _};
+    use tn_test_utils::CommitteeFixture;
+    use tn_types::{
+        AuthorityIdentifier, Block, Certificate, CommittedSubDag, ConsensusHeader, Database as _,
+        Epoch, ExecHeader, HeaderBuilder, ReputationScores,
+    };
+
+    /// A summarizer with two consensus outputs per epoch and only the genesis block executed.
+    fn summarizer(db: &MemDatabase) -> EpochSummarizer<MemDatabase, MockEthProvider> {
+        let provider = MockEthProvider::default();
+        let genesis = ExecHeader::default();
+        let hash = genesis.hash_slow();
+        provider.add_header(hash, genesis.clone());
+        provider.add_block(hash, Block { header: genesis, body: Default::default() });
+        let checkpoints = Arc::new(ConsensusCheckpoints::new(db.clone(), provider));
+        EpochSummarizer::new(
+            db.clone(),
+            checkpoints,
+            TnChainSpec { epoch_length: 2, ..Default::default() },
+        )
+    }
+
+    /// Record consensus output `number` with a leader from consensus epoch `epoch`.
+    fn insert_output(db: &MemDatabase, number: u64, epoch: Epoch, scores: ReputationScores) {
+        let mut leader = Certificate::default();
+        leader.header = HeaderBuilder::default()
+            .author(AuthorityIdentifier::dummy_for_test(1))
+            .round(2)
+            .epoch(epoch)
+            .parents(BTreeSet::new())
+            .payload(Default::default())
+            .build();
+        let sub_dag = CommittedSubDag::new(vec![leader.clone()], leader, number, scores, None);
+        let header = ConsensusHeader { number, sub_dag, ..Default::default() };
+        db.insert::<ConsensusBlocks>(&number, &header).unwrap();
+    }
+
+    #[test]
+    fn test_summarize_with_recorded_committees() {
+        let db = MemDatabase::default();
+        let summarizer = summarizer(&db);
+        let mut fixture = CommitteeFixture::builder(MemDatabase::default).build();
+        let first = fixture.committee();
+        let scored = first.authorities()[0].id();
+        let mut scores = ReputationScores::new(&first);
+        scores.add_score(&scored, 7);
+        insert_output(&db, 0, 0, ReputationScores::default());
+        insert_output(&db, 1, 0, scores);
+
+        // the last output of the epoch has not been committed
+        assert_eq!(summarizer.summarize(1).unwrap(), None);
+        // the committee of the leader's epoch is required
+        assert!(summarizer.summarize(0).is_err());
+
+        // the committee changes between the two epochs
+        db.write_committee(&first).unwrap();
+        fixture.add_authority(MemDatabase::default());
+        let second = fixture.committee();
+        db.write_committee(&second).unwrap();
+        insert_output(&db, 2, second.epoch(), ReputationScores::default());
+        insert_output(&db, 3, second.epoch(), ReputationScores::default());
+
+        let summaries = summarizer.summarize_completed(3).unwrap();
+        assert_eq!(summaries.iter().map(|summary| summary.epoch).collect::<Vec<_>>(), [0, 1]);
+        assert_eq!(summaries[0].validators.len(), first.size());
+        assert_eq!(summaries[1].validators.len(), second.size());
+        let validator = summaries[0].validators.iter().find(|v| v.id == scored).unwrap();
+        assert_eq!(validator.reputation_score, 7);
+        assert_eq!((summaries[1].first_sub_dag, summaries[1].last_sub_dag), (2, 3));
+        assert_eq!(summaries[1].total_sub_dags, 2);
+        // only the genesis block is executed
+        assert_eq!(summaries[1].first_block, None);
+
+        // completed epochs are summarized once
+        assert_eq!(db.last_epoch_summary(), Some(summaries[1].clone()));
+        assert!(summarizer.summarize_completed(3).unwrap().is_empty());
+    }
+}
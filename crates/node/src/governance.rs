@@ -0,0 +1,359 @@
+//! Consensus parameters from the governance contract.
+//!
+//! When the chain spec reads parameters from a governance contract, every validator must switch
+//! to new values at the same consensus output. Values executed by the last output of an epoch
+//! take effect at the start of the epoch after the next one, so parameters for epoch `e` are read
+//! from the state after the last output of epoch `e - 2`. Nodes have a full epoch to execute that
+//! state before they need it.
+//!
+//! Parameters are read at launch, and the node is relaunched when it commits the last output of
+//! an epoch if the next epoch has different parameters.
+
+use reth_provider::{BlockNumReader, HeaderProvider, StateProvider as _, StateProviderFactory};
+use std::sync::Arc;
+use tn_batch_builder::GasTargetSource;
+use tn_config::{
+    governed_batch_gas_target, GovernedParameters, LeaderScheduleParameters, ParameterSource,
+    Parameters, TnChainSpec, GOVERNANCE_BATCH_GAS_TARGET_SLOT,
+};
+use tn_primary::ConsensusBus;
+use tn_types::{Address, Database, ExecHeader, Notifier, TaskManager};
+use tracing::{info, warn};
+
+use crate::checkpoints::ConsensusCheckpoints;
+
+/// Reads consensus parameters from the governance contract.
+#[derive(Debug, Clone)]
+pub(crate) struct ParameterGovernor<DB, P> {
+    /// The address of the governance contract.
+    address: Address,
+    /// The chain spec with the epoch length.
+    spec: TnChainSpec,
+    /// The node's local parameters.
+    local: Parameters,
+    /// The consensus DB.
+    db: DB,
+    /// Execution state at consensus checkpoints.
+    checkpoints: Arc<ConsensusCheckpoints<DB, P>>,
+    /// The execution provider for the genesis state.
+    provider: P,
+}
+
+impl<DB, P> ParameterGovernor<DB, P>
+where
+    DB: Database,
+    P: BlockNumReader + HeaderProvider<Header = ExecHeader> + StateProviderFactory,
+{
+    /// Create a new instance of [Self].
+    ///
+    /// Returns `None` if the chain spec does not read parameters from a governance contract.
+    pub(crate) fn new(
+        spec: TnChainSpec,
+        local: Parameters,
+        db: DB,
+        checkpoints: Arc<ConsensusCheckpoints<DB, P>>,
+        provider: P,
+    ) -> Option<Self> {
+        let ParameterSource::Governance { address } = spec.parameter_source else {
+            return None;
+        };
+        Some(Self { address, spec, local, db, checkpoints, provider })
+    }
+
+    /// Read the governed parameters for the epoch of consensus output `output`.
+    ///
+    /// Returns `None` if the state the parameters are read from has not been executed by this
+    /// node.
+    fn read(&self, output: u64) -> eyre::Result<Option<GovernedParameters>> {
+        let state = match activation_boundary(&self.spec, output) {
+            Some(boundary) => match self.checkpoints.state_by_sub_dag(boundary)? {
+                Some(state) => state,
+                None => return Ok(None),
+            },
+            None => self.provider.history_by_block_number(0)?,
+        };
+        let governed = GovernedParameters::from_storage(|slot| {
+            Ok(state.storage(self.address, slot)?.unwrap_or_default())
+        })?;
+        Ok(Some(governed))
+    }
+
+    /// Return the parameters for the epoch of consensus output `output`.
+    ///
+    /// Governed values that fail bounds checks are logged and replaced by the local value.
+    /// Governed leader schedule values only apply once the chain spec activates the leader
+    /// schedule. Returns `None` if the governed parameters can not be read yet.
+    pub(crate) fn parameters(&self, output: u64) -> Option<Parameters> {
+        let governed = match self.read(output) {
+            Ok(governed) => governed?,
+            Err(e) => {
+                warn!(target: "telcoin::node", ?e, "failed to read governed consensus parameters");
+                return None;
+            }
+        };
+        let mut parameters = self.local.clone();
+        parameters.leader_schedule = self.spec.leader_schedule_at(output);
+        for rejected in governed.apply(&mut parameters) {
+            warn!(
+                target: "telcoin::node",
+                parameter = rejected.name,
+                value = rejected.value,
+                reason = rejected.reason,
+                "rejected governed consensus parameter"
+            );
+        }
+        if !self.spec.leader_schedule_active(output) {
+            parameters.leader_schedule = LeaderScheduleParameters::default();
+        }
+        Some(parameters)
+    }
+}
+
+impl<DB, P> ParameterGovernor<DB, P>
+where
+    DB: Database,
+    P: BlockNumReader
+        + HeaderProvider<Header = ExecHeader>
+        + StateProviderFactory
+        + Send
+        + Sync
+        + 'static,
+{
+    /// Spawn a task that relaunches the node when it commits the last consensus output of an
+    /// epoch and the next epoch has different parameters than the node launched with.
+    ///
+    /// `governed` is true if `applied` was read for the epoch of consensus output `output`.
+    pub(crate) fn spawn_watcher(
+        self,
+        output: u64,
+        applied: Parameters,
+        governed: bool,
+        consensus_bus: ConsensusBus,
+        task_manager: &TaskManager,
+        shutdown: Notifier,
+    ) {
+        let rx_shutdown = shutdown.subscribe();
+        let mut rx_headers = consensus_bus.last_consensus_header().subscribe();
+        // the epoch the applied parameters were read for
+        let mut epoch = governed.then(|| self.epoch(output));
+        task_manager.spawn_task("parameter governance", async move {
+            loop {
+                tokio::select!(
+                    _ = &rx_shutdown => break,
+                    res = rx_headers.changed() => {
+                        if res.is_err() {
+                            break;
+                        }
+                        // parameters for the next output
+                        let next = rx_headers.borrow_and_update().number + 1;
+                        if epoch == Some(self.epoch(next)) {
+                            continue;
+                        }
+                        let Some(parameters) = self.parameters(next) else {
+                            continue;
+                        };
+                        epoch = Some(self.epoch(next));
+                        if parameters != applied {
+                            info!(
+                                target: "telcoin::node",
+                                next,
+                                "governed consensus parameters changed, relaunching"
+                            );
+                            consensus_bus.set_restart();
+                            shutdown.notify();
+                            break;
+                        }
+                    }
+                )
+            }
+        });
+    }
+
+    /// The epoch of consensus output `output`.
+    fn epoch(&self, output: u64) -> u64 {
+        output / self.spec.epoch_length
+    }
+}
+
+/// Spawn a task that relaunches the node when the chain spec's leader schedule activates.
+///
+/// Nodes that read parameters from governance relaunch through
+/// [ParameterGovernor::spawn_watcher] instead. Nothing is spawned if the leader schedule
+/// parameters can no longer change after consensus output `output`.
+pub(crate) fn spawn_leader_schedule_watcher(
+    spec: TnChainSpec,
+    output: u64,
+    consensus_bus: ConsensusBus,
+    task_manager: &TaskManager,
+    shutdown: Notifier,
+) {
+    if spec.leader_schedule_active(output) || spec.forks.leader_schedule.is_none() {
+        return;
+    }
+    let rx_shutdown = shutdown.subscribe();
+    let mut rx_headers = consensus_bus.last_consensus_header().subscribe();
+    task_manager.spawn_task("leader schedule activation", async move {
+        loop {
+            tokio::select!(
+                _ = &rx_shutdown => break,
+                res = rx_headers.changed() => {
+                    if res.is_err() {
+                        break;
+                    }
+                    // parameters for the next output
+                    let next = rx_headers.borrow_and_update().number + 1;
+                    if spec.leader_schedule_active(next) {
+                        info!(
+                            target: "telcoin::node",
+                            next,
+                            "leader schedule activated, relaunching"
+                        );
+                        consensus_bus.set_restart();
+                        shutdown.notify();
+                        break;
+                    }
+                }
+            )
+        }
+    });
+}
+
+/// Reads the batch gas target from the governance contract in the latest executed state.
+///
+/// The target only changes when this worker seals batches, so it is read after each executed
+/// output instead of relaunching the node at an epoch boundary.
+#[derive(Debug)]
+pub(crate) struct GovernedGasTarget<P> {
+    /// The address of the governance contract.
+    address: Address,
+    /// The execution provider for the latest state.
+    provider: P,
+}
+
+impl<P> GovernedGasTarget<P> {
+    /// Create a new instance of [Self].
+    ///
+    /// Returns `None` if the chain spec does not read parameters from a governance contract.
+    pub(crate) fn new(spec: &TnChainSpec, provider: P) -> Option<Self> {
+        let ParameterSource::Governance { address } = spec.parameter_source else {
+            return None;
+        };
+        Some(Self { address, provider })
+    }
+}
+
+impl<P> GasTargetSource for GovernedGasTarget<P>
+where
+    P: StateProviderFactory + std::fmt::Debug + Send + Sync,
+{
+    fn gas_target(&self) -> Option<u64> {
+        let value = self
+            .provider
+            .latest()
+            .and_then(|state| state.storage(self.address, GOVERNANCE_BATCH_GAS_TARGET_SLOT))
+            .inspect_err(
+                |e| warn!(target: "telcoin::node", ?e, "failed to read governed batch gas target"),
+            )
+            .ok()??;
+        governed_batch_gas_target(value)
+    }
+}
+
+/// The last consensus output whose state sets the parameters for the epoch of `output`.
+///
+/// Returns `None` for the first two epochs, which use the genesis state.
+fn activation_boundary(spec: &TnChainSpec, output: u64) -> Option<u64> {
+    output.checked_sub(spec.epoch_length).and_then(|previous| spec.epoch_boundary(previous))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{activation_boundary, ParameterGovernor};
+    use crate::checkpoints::ConsensusCheckpoints;
+    use reth_provider::test_utils::{ExtendedAccount, MockEthProvider};
+    use std::sync::Arc;
+    use tn_config::{
+        LeaderScheduleParameters, ParameterSource, Parameters, TnChainSpec,
+        GOVERNANCE_GC_DEPTH_SLOT, GOVERNANCE_MAX_HEADER_DELAY_SLOT,
+        GOVERNANCE_SUB_DAGS_PER_SCHEDULE_SLOT,
+    };
+    use tn_storage::mem_db::MemDatabase;
+    use tn_types::{Address, Forks, U256};
+
+    fn governor(
+        spec: TnChainSpec,
+        provider: MockEthProvider,
+    ) -> Option<ParameterGovernor<MemDatabase, MockEthProvider>> {
+        let db = MemDatabase::default();
+        let checkpoints = Arc::new(ConsensusCheckpoints::new(db.clone(), provider.clone()));
+        ParameterGovernor::new(spec, Parameters::default(), db, checkpoints, provider)
+    }
+
+    #[test]
+    fn test_activation_boundary() {
+        let spec = TnChainSpec { epoch_length: 10, ..Default::default() };
+        // the first two epochs use genesis
+        assert_eq!(activation_boundary(&spec, 0), None);
+        assert_eq!(activation_boundary(&spec, 19), None);
+        // then the last output of the epoch before the previous epoch
+        assert_eq!(activation_boundary(&spec, 20), Some(9));
+        assert_eq!(activation_boundary(&spec, 29), Some(9));
+        assert_eq!(activation_boundary(&spec, 30), Some(19));
+    }
+
+    #[test]
+    fn test_governed_parameters() {
+        let address = Address::with_last_byte(7);
+        let provider = MockEthProvider::default();
+        provider.add_account(
+            address,
+            ExtendedAccount::new(0, U256::ZERO).extend_storage([
+                (GOVERNANCE_GC_DEPTH_SLOT, U256::from(100)),
+                (GOVERNANCE_MAX_HEADER_DELAY_SLOT, U256::MAX),
+            ]),
+        );
+        let spec = TnChainSpec {
+            epoch_length: 10,
+            parameter_source: ParameterSource::Governance { address },
+            ..Default::default()
+        };
+        let governor = governor(spec, provider).expect("governed");
+
+        let local = Parameters::default();
+        let parameters = governor.parameters(19).expect("genesis parameters");
+        assert_eq!(parameters.gc_depth, 100);
+        // values out of bounds keep the local value
+        assert_eq!(parameters.max_header_delay, local.max_header_delay);
+
+        // later epochs wait until this node executed the state they are read from
+        assert!(governor.parameters(20).is_none());
+    }
+
+    #[test]
+    fn test_governed_leader_schedule_waits_for_fork() {
+        let address = Address::with_last_byte(7);
+        let provider = MockEthProvider::default();
+        provider.add_account(
+            address,
+            ExtendedAccount::new(0, U256::ZERO)
+                .extend_storage([(GOVERNANCE_SUB_DAGS_PER_SCHEDULE_SLOT, U256::from(50))]),
+        );
+        let spec = TnChainSpec {
+            epoch_length: 10,
+            parameter_source: ParameterSource::Governance { address },
+            forks: Forks { leader_schedule: Some(1), ..Forks::NONE },
+            ..Default::default()
+        };
+        let governor = governor(spec, provider).expect("governed");
+
+        let parameters = governor.parameters(9).expect("genesis parameters");
+        assert_eq!(parameters.leader_schedule, LeaderScheduleParameters::default());
+        let parameters = governor.parameters(10).expect("genesis parameters");
+        assert_eq!(parameters.leader_schedule.sub_dags_per_schedule, 50);
+    }
+
+    #[test]
+    fn test_local_parameters() {
+        assert!(governor(TnChainSpec::default(), MockEthProvider::default()).is_none());
+    }
+}
@@ -0,0 +1,169 @@
+//! Handle to a node launched in the same process.
+//!
+//! The handle is shared between the caller and the node. It survives relaunches after a mode
+//! change or crash and always refers to the node's current run.
+
+use std::{
+    net::SocketAddr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+use tn_primary::{ConsensusBus, ConsensusEventFilter, ConsensusEventSubscriber};
+use tn_types::Notifier;
+use tokio::sync::watch;
+
+/// The components of the node's current run.
+#[derive(Clone, Debug)]
+struct NodeRun {
+    /// The consensus bus, `None` for RPC replicas.
+    consensus_bus: Option<ConsensusBus>,
+    /// The local address serving HTTP RPC.
+    rpc_address: Option<SocketAddr>,
+    /// Shuts down the run.
+    shutdown: Notifier,
+}
+
+/// Shared state behind [NodeHandle].
+#[derive(Debug)]
+struct NodeHandleInner {
+    /// True once shutdown is requested.
+    shutdown_requested: AtomicBool,
+    /// The current run, `None` while the node is launching.
+    run: watch::Sender<Option<NodeRun>>,
+}
+
+/// Handle to a running node.
+///
+/// Clones refer to the same node.
+#[derive(Clone, Debug)]
+pub struct NodeHandle {
+    inner: Arc<NodeHandleInner>,
+}
+
+impl Default for NodeHandle {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl NodeHandle {
+    /// Create a new handle for a node that has not launched yet.
+    pub fn new() -> Self {
+        let (run, _) = watch::channel(None);
+        Self {
+            inner: Arc::new(NodeHandleInner { shutdown_requested: AtomicBool::new(false), run }),
+        }
+    }
+
+    /// Ask the node to shutdown.
+    ///
+    /// The node is not relaunched after shutdown is requested. Requests before the node is
+    /// running stop it as soon as it starts.
+    pub fn shutdown(&self) {
+        self.inner.shutdown_requested.store(true, Ordering::SeqCst);
+        if let Some(run) = self.inner.run.borrow().as_ref() {
+            run.shutdown.notify();
+        }
+    }
+
+    /// True if shutdown was requested.
+    pub fn is_shutdown(&self) -> bool {
+        self.inner.shutdown_requested.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once the node is running.
+    ///
+    /// This resolves again after a relaunch since the node is running the whole time.
+    pub async fn wait_until_running(&self) {
+        let mut rx_run = self.inner.run.subscribe();
+        // the sender is held by the handle so this never fails
+        let _ = rx_run.wait_for(Option::is_some).await;
+    }
+
+    /// True if the node is running.
+    pub fn is_running(&self) -> bool {
+        self.inner.run.borrow().is_some()
+    }
+
+    /// The local address serving HTTP RPC, if the node is running with HTTP enabled.
+    pub fn rpc_address(&self) -> Option<SocketAddr> {
+        self.inner.run.borrow().as_ref().and_then(|run| run.rpc_address)
+    }
+
+    /// Subscribe to typed events from the node's current consensus bus.
+    ///
+    /// Returns `None` if the node is not running or does not run consensus. The subscription ends
+    /// when the node stops or relaunches.
+    pub fn subscribe_events(
+        &self,
+        name: impl Into<String>,
+        filter: ConsensusEventFilter,
+    ) -> Option<ConsensusEventSubscriber> {
+        let run = self.inner.run.borrow();
+        let consensus_bus = run.as_ref()?.consensus_bus.as_ref()?;
+        Some(consensus_bus.subscribe_events(name, filter))
+    }
+
+    /// Record the components of a new run.
+    ///
+    /// The run is shutdown immediately if shutdown was already requested.
+    pub(crate) fn set_running(
+        &self,
+        consensus_bus: Option<ConsensusBus>,
+        rpc_address: Option<SocketAddr>,
+        shutdown: Notifier,
+    ) {
+        self.inner.run.send_replace(Some(NodeRun {
+            consensus_bus,
+            rpc_address,
+            shutdown: shutdown.clone(),
+        }));
+        if self.is_shutdown() {
+            shutdown.notify();
+        }
+    }
+
+    /// Clear the current run once it stops.
+    pub(crate) fn set_stopped(&self) {
+        self.inner.run.send_replace(None);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NodeHandle;
+    use tn_primary::{ConsensusBus, ConsensusEvent, ConsensusEventFilter};
+    use tn_types::Notifier;
+
+    #[tokio::test]
+    async fn test_node_handle() {
+        let handle = NodeHandle::new();
+        assert!(!handle.is_running());
+        assert!(handle.subscribe_events("test", ConsensusEventFilter::all()).is_none());
+
+        let consensus_bus = ConsensusBus::new();
+        let shutdown = Notifier::new();
+        let rx_shutdown = shutdown.subscribe();
+        handle.set_running(Some(consensus_bus.clone()), None, shutdown);
+        handle.wait_until_running().await;
+
+        let mut events =
+            handle.subscribe_events("test", ConsensusEventFilter::all()).expect("node running");
+        consensus_bus.publish_event(ConsensusEvent::PrimaryRound(1));
+        assert_eq!(events.recv().await, Some(ConsensusEvent::PrimaryRound(1)));
+
+        // shutdown notifies the current run
+        handle.shutdown();
+        rx_shutdown.await;
+        handle.set_stopped();
+        assert!(!handle.is_running());
+
+        // runs started after shutdown stop immediately
+        let shutdown = Notifier::new();
+        let rx_shutdown = shutdown.subscribe();
+        handle.set_running(None, None, shutdown);
+        rx_shutdown.await;
+    }
+}
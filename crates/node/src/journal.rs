@@ -0,0 +1,582 @@
+//! Export consensus output to journal files.
+//!
+//! Every committed [ConsensusOutput] is appended to a journal file in the configured directory,
+//! see [JournalConfig]. Analytics read the files instead of depending on the node being up.
+//!
+//! Files are named `consensus-<number>.journal`, where `<number>` is the zero padded consensus
+//! number of the first record, so files sort in consensus order. A new file is started once the
+//! current file reaches `max_file_size`.
+//!
+//! Each file starts with an 8 byte header:
+//! - magic: the ASCII bytes `TNJL`
+//! - version: u32 big endian, currently [JOURNAL_VERSION]
+//!
+//! followed by records:
+//! - length: u32 big endian, the length of the payload
+//! - checksum: the first 4 bytes of the SHA-256 of the payload
+//! - payload: the BCS encoded [JournalRecord]
+//!
+//! The last record of the newest file may be incomplete while the node is writing it or after a
+//! crash. Readers stop at the first incomplete record or checksum mismatch, and the node
+//! truncates the file there before appending on restart. Outputs that were already written, ie -
+//! replayed after a restart, are skipped.
+//!
+//! Outputs committed while the node was down, while the journal was disabled or while the journal
+//! fell behind are read back from the consensus chain, so the journal has no gaps. Their batches
+//! are read from the consensus DB and are missing from the record if they were pruned.
+
+use eyre::{ensure, WrapErr as _};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest as _, Sha256};
+use std::{
+    fs::{self, File, OpenOptions},
+    io::Write as _,
+    path::{Path, PathBuf},
+};
+use tn_config::JournalConfig;
+use tn_storage::{
+    tables::{Batches, ConsensusBlocks},
+    ConsensusStore as _,
+};
+use tn_types::{
+    encode, try_decode, Address, Batch, BlockHash, ConsensusHeader, ConsensusOutput, Database,
+    Noticer, TaskManager, B256,
+};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tracing::{error, info, warn};
+
+/// The magic bytes at the start of every journal file.
+pub const JOURNAL_MAGIC: [u8; 4] = *b"TNJL";
+
+/// The version of the journal format.
+pub const JOURNAL_VERSION: u32 = 1;
+
+/// The length of the file header.
+const FILE_HEADER_LEN: usize = 8;
+
+/// The length of the length and checksum before each payload.
+const RECORD_HEADER_LEN: usize = 8;
+
+/// The prefix of journal file names.
+const FILE_PREFIX: &str = "consensus-";
+
+/// The extension of journal file names.
+const FILE_EXTENSION: &str = "journal";
+
+/// A committed consensus output in the journal.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct JournalRecord {
+    /// The hash of the consensus header.
+    pub digest: B256,
+    /// The consensus header, with the committed sub dag.
+    pub header: ConsensusHeader,
+    /// The beneficiary for block rewards.
+    pub beneficiary: Address,
+    /// The digests of the batches in execution order.
+    pub batch_digests: Vec<BlockHash>,
+    /// The batches for each certificate in the sub dag, if the journal includes batches.
+    pub batches: Option<Vec<Vec<Batch>>>,
+}
+
+impl JournalRecord {
+    /// Create a record for the output, with its batches if `batches` is true.
+    pub fn new(output: &ConsensusOutput, batches: bool) -> Self {
+        Self {
+            digest: output.consensus_header_hash(),
+            header: output.consensus_header(),
+            beneficiary: output.beneficiary,
+            batch_digests: output.batch_digests.iter().copied().collect(),
+            batches: batches.then(|| output.batches.clone()),
+        }
+    }
+
+    /// Create a record for a header of the consensus chain in `db`.
+    ///
+    /// The beneficiary is the execution address of the leader in the committee recorded for its
+    /// epoch. Batches are included if `batches` is true and all of them are still in `db`.
+    fn from_header<DB: Database>(
+        db: &DB,
+        header: ConsensusHeader,
+        batches: bool,
+    ) -> eyre::Result<Self> {
+        let leader = &header.sub_dag.leader;
+        let committee = db
+            .read_committee(leader.epoch())?
+            .ok_or_else(|| eyre::eyre!("committee of epoch {} is unknown", leader.epoch()))?;
+        let beneficiary = committee
+            .authority(leader.origin())
+            .ok_or_else(|| eyre::eyre!("leader {} is not in the committee", leader.origin()))?
+            .execution_address();
+        let batch_digests = header
+            .sub_dag
+            .certificates
+            .iter()
+            .flat_map(|certificate| certificate.header().payload().keys().copied())
+            .collect();
+        let batches = if batches {
+            let certificate_batches: Option<Vec<Vec<Batch>>> = header
+                .sub_dag
+                .certificates
+                .iter()
+                .map(|certificate| {
+                    certificate
+                        .header()
+                        .payload()
+                        .keys()
+                        .map(|digest| db.get::<Batches>(digest).ok().flatten())
+                        .collect()
+                })
+                .collect();
+            if certificate_batches.is_none() {
+                warn!(
+                    target: "telcoin::node",
+                    number = header.number,
+                    "batches were pruned, journaling consensus output without them"
+                );
+            }
+            certificate_batches
+        } else {
+            None
+        };
+        Ok(Self { digest: header.digest(), header, beneficiary, batch_digests, batches })
+    }
+}
+
+/// Return the journal files in the directory in consensus order.
+pub fn journal_files<P: AsRef<Path>>(dir: P) -> eyre::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(dir.as_ref())? {
+        let path = entry?.path();
+        let is_journal = path.extension().is_some_and(|ext| ext == FILE_EXTENSION)
+            && path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with(FILE_PREFIX));
+        if is_journal {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// Read the complete records of a journal file.
+pub fn read_journal_file<P: AsRef<Path>>(path: P) -> eyre::Result<Vec<JournalRecord>> {
+    let bytes = fs::read(path.as_ref())?;
+    let (payloads, _) = scan_records(&bytes)?;
+    payloads.into_iter().map(|payload| Ok(try_decode(payload)?)).collect()
+}
+
+/// Split the file's bytes into record payloads.
+///
+/// Returns the payloads and the length of the file up to the end of the last valid record.
+fn scan_records(bytes: &[u8]) -> eyre::Result<(Vec<&[u8]>, usize)> {
+    ensure!(
+        bytes.len() >= FILE_HEADER_LEN && bytes[..4] == JOURNAL_MAGIC,
+        "not a consensus journal file"
+    );
+    let version = u32::from_be_bytes(bytes[4..8].try_into().expect("4 bytes"));
+    ensure!(version == JOURNAL_VERSION, "unsupported journal version {version}");
+
+    let mut payloads = Vec::new();
+    let mut offset = FILE_HEADER_LEN;
+    while let Some(header) = bytes.get(offset..offset + RECORD_HEADER_LEN) {
+        let len = u32::from_be_bytes(header[..4].try_into().expect("4 bytes")) as usize;
+        let start = offset + RECORD_HEADER_LEN;
+        let Some(payload) = bytes.get(start..start + len) else {
+            break;
+        };
+        if checksum(payload) != header[4..] {
+            break;
+        }
+        payloads.push(payload);
+        offset = start + len;
+    }
+    Ok((payloads, offset))
+}
+
+/// The checksum of a record's payload.
+fn checksum(payload: &[u8]) -> [u8; 4] {
+    let hash = Sha256::digest(payload);
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+/// The name of the journal file starting with the consensus number.
+fn file_name(number: u64) -> String {
+    format!("{FILE_PREFIX}{number:020}.{FILE_EXTENSION}")
+}
+
+/// Appends consensus output to the journal files.
+#[derive(Debug)]
+pub(crate) struct ConsensusJournal {
+    /// The journal configuration.
+    config: JournalConfig,
+    /// The file being appended to and its length.
+    file: Option<(File, u64)>,
+    /// The consensus number of the last record written.
+    last_number: Option<u64>,
+}
+
+impl ConsensusJournal {
+    /// Open the journal in the configured directory, continuing the newest file.
+    pub(crate) fn open(config: JournalConfig) -> eyre::Result<Self> {
+        fs::create_dir_all(&config.dir).wrap_err_with(|| {
+            format!("failed to create journal directory {}", config.dir.display())
+        })?;
+        let mut journal = Self { config, file: None, last_number: None };
+        let mut files = journal_files(&journal.config.dir)?;
+        while let Some(path) = files.pop() {
+            let bytes = fs::read(&path)?;
+            let (payloads, valid_len) = scan_records(&bytes)
+                .wrap_err_with(|| format!("invalid journal {}", path.display()))?;
+            let Some(last) = payloads.last() else {
+                // the node stopped before the first record was written
+                fs::remove_file(&path)?;
+                continue;
+            };
+            let record: JournalRecord = try_decode(last)?;
+            journal.last_number = Some(record.header.number);
+            if valid_len < bytes.len() {
+                warn!(
+                    target: "telcoin::node",
+                    path = %path.display(),
+                    discarded = bytes.len() - valid_len,
+                    "truncating incomplete journal record"
+                );
+            }
+            let file = OpenOptions::new().append(true).open(&path)?;
+            file.set_len(valid_len as u64)?;
+            journal.file = Some((file, valid_len as u64));
+            break;
+        }
+        Ok(journal)
+    }
+
+    /// Append the output unless it was already written.
+    ///
+    /// Returns true if the output was written.
+    pub(crate) fn append(&mut self, output: &ConsensusOutput) -> eyre::Result<bool> {
+        if self.last_number.is_some_and(|last| output.number <= last) {
+            return Ok(false);
+        }
+        self.write_record(&JournalRecord::new(output, self.config.batches))?;
+        Ok(true)
+    }
+
+    /// Append the outputs after the last record from the consensus chain in `db`, then `output`.
+    ///
+    /// Without an output every header in the consensus chain after the last record is appended,
+    /// otherwise only the headers before the output. Returns the number of headers appended from
+    /// `db`.
+    pub(crate) fn catch_up<DB: Database>(
+        &mut self,
+        db: &DB,
+        output: Option<&ConsensusOutput>,
+    ) -> eyre::Result<usize> {
+        let start = self.last_number.map_or(0, |last| last + 1);
+        let end = output.map_or(u64::MAX, |output| output.number);
+        let mut backfilled = 0;
+        if start < end {
+            for (number, header) in db.skip_to::<ConsensusBlocks>(&start)? {
+                if number >= end {
+                    break;
+                }
+                self.write_record(&JournalRecord::from_header(db, header, self.config.batches)?)?;
+                backfilled += 1;
+            }
+        }
+        if let Some(output) = output {
+            self.append(output)?;
+        }
+        Ok(backfilled)
+    }
+
+    /// Append the record to the current file, starting a new file if it is full.
+    fn write_record(&mut self, record: &JournalRecord) -> eyre::Result<()> {
+        let number = record.header.number;
+        let payload = encode(record);
+        let len: u32 = payload.len().try_into().wrap_err("journal record too large")?;
+
+        let file = match self.file.take() {
+            Some((file, file_len)) if file_len < self.config.max_file_size => (file, file_len),
+            previous => self.rotate(previous.map(|(file, _)| file), number)?,
+        };
+        let (mut file, file_len) = file;
+        let mut record = Vec::with_capacity(RECORD_HEADER_LEN + payload.len());
+        record.extend_from_slice(&len.to_be_bytes());
+        record.extend_from_slice(&checksum(&payload));
+        record.extend_from_slice(&payload);
+        // a failed write leaves an incomplete record, the next output starts a new file
+        file.write_all(&record)?;
+        self.file = Some((file, file_len + record.len() as u64));
+        self.last_number = Some(number);
+        Ok(())
+    }
+
+    /// Start a new journal file for records from `number` and delete the oldest files.
+    fn rotate(&mut self, previous: Option<File>, number: u64) -> eyre::Result<(File, u64)> {
+        if let Some(previous) = previous {
+            previous.sync_all()?;
+        }
+        let path = self.config.dir.join(file_name(number));
+        let mut file = OpenOptions::new()
+            .create(true)
+            .write(true)
+            .truncate(true)
+            .open(&path)
+            .wrap_err_with(|| format!("failed to create journal file {}", path.display()))?;
+        let mut header = [0u8; FILE_HEADER_LEN];
+        header[..4].copy_from_slice(&JOURNAL_MAGIC);
+        header[4..].copy_from_slice(&JOURNAL_VERSION.to_be_bytes());
+        file.write_all(&header)?;
+
+        if let Some(max_files) = self.config.max_files {
+            let files = journal_files(&self.config.dir)?;
+            let excess = files.len().saturating_sub(max_files.max(1));
+            for old in files.into_iter().take(excess) {
+                fs::remove_file(&old)?;
+            }
+        }
+        Ok((file, FILE_HEADER_LEN as u64))
+    }
+}
+
+/// Spawn a task that appends consensus output to the journal.
+///
+/// Outputs in the consensus chain of `db` that are missing from the journal are appended first,
+/// and again whenever the receiver lags. Files are written on the blocking thread pool.
+pub(crate) fn spawn_consensus_journal<DB: Database>(
+    config: JournalConfig,
+    db: DB,
+    mut rx_output: broadcast::Receiver<ConsensusOutput>,
+    task_manager: &TaskManager,
+    rx_shutdown: Noticer,
+) -> eyre::Result<()> {
+    let mut journal = ConsensusJournal::open(config)?;
+    task_manager.spawn_task("consensus journal", async move {
+        // the first pass appends outputs committed while the node was down or the journal was
+        // disabled
+        let mut output = None;
+        loop {
+            let db = db.clone();
+            let write = tokio::task::spawn_blocking(move || {
+                let res = journal.catch_up(&db, output.as_ref());
+                (journal, output, res)
+            });
+            let res = match write.await {
+                Ok((written, written_output, res)) => {
+                    journal = written;
+                    output = written_output;
+                    res
+                }
+                Err(e) => {
+                    error!(target: "telcoin::node", ?e, "consensus journal writer failed");
+                    break;
+                }
+            };
+            match res {
+                Ok(0) => (),
+                Ok(backfilled) => info!(
+                    target: "telcoin::node",
+                    backfilled,
+                    "appended missing consensus output to the journal"
+                ),
+                Err(e) => error!(
+                    target: "telcoin::node",
+                    ?e,
+                    number = output.as_ref().map(|output| output.number),
+                    "failed to journal consensus output"
+                ),
+            }
+
+            output = loop {
+                tokio::select!(
+                    _ = &rx_shutdown => return,
+                    next = rx_output.recv() => match next {
+                        Ok(next) => break Some(next),
+                        // the next output appends the missed outputs from the consensus chain
+                        Err(RecvError::Lagged(missed)) => warn!(
+                            target: "telcoin::node",
+                            missed,
+                            "consensus journal fell behind, backfilling from the consensus chain"
+                        ),
+                        Err(RecvError::Closed) => return,
+                    }
+                )
+            };
+        }
+    });
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        collections::{BTreeSet, VecDeque},
+        sync::Arc,
+    };
+    use tn_storage::mem_db::MemDatabase;
+    use tn_test_utils::{AuthorityFixture, CommitteeFixture};
+    use tn_types::{Certificate, CommittedSubDag, HeaderBuilder, ReputationScores};
+
+    fn output(number: u64) -> ConsensusOutput {
+        let sub_dag = CommittedSubDag::new(
+            vec![Certificate::default()],
+            Certificate::default(),
+            number,
+            ReputationScores::default(),
+            None,
+        );
+        let batches = vec![vec![Batch { timestamp: number, ..Default::default() }]];
+        let batch_digests: VecDeque<_> = batches[0].iter().map(Batch::digest).collect();
+        ConsensusOutput {
+            sub_dag: Arc::new(sub_dag),
+            batches,
+            beneficiary: Address::repeat_byte(1),
+            batch_digests,
+            parent_hash: B256::repeat_byte(2),
+            number,
+            extra: B256::ZERO,
+            early_finalize: false,
+            withdrawals: Default::default(),
+        }
+    }
+
+    /// Commit output `number` led by `leader` to the consensus chain in `db`.
+    fn commit(
+        db: &MemDatabase,
+        number: u64,
+        leader: &AuthorityFixture<MemDatabase>,
+        epoch: u32,
+    ) -> ConsensusOutput {
+        let mut output = output(number);
+        let batch = output.batches[0][0].clone();
+        let mut certificate = Certificate::default();
+        certificate.header = HeaderBuilder::default()
+            .author(leader.id())
+            .round(2)
+            .epoch(epoch)
+            .parents(BTreeSet::new())
+            .with_payload_batch(batch.clone(), 0, 0)
+            .build();
+        let sub_dag = CommittedSubDag::new(
+            vec![certificate.clone()],
+            certificate,
+            number,
+            ReputationScores::default(),
+            None,
+        );
+        output.sub_dag = Arc::new(sub_dag);
+        output.beneficiary = leader.execution_address();
+        db.insert::<ConsensusBlocks>(&number, &output.consensus_header()).expect("header stored");
+        db.insert::<Batches>(&batch.digest(), &batch).expect("batch stored");
+        output
+    }
+
+    fn numbers(dir: &Path) -> Vec<Vec<u64>> {
+        journal_files(dir)
+            .expect("journal files")
+            .iter()
+            .map(|path| {
+                read_journal_file(path)
+                    .expect("journal file")
+                    .iter()
+                    .map(|record| record.header.number)
+                    .collect()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_consensus_journal() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let record_len = encode(&JournalRecord::new(&output(1), false)).len() as u64;
+        let config = JournalConfig {
+            dir: dir.path().join("journal"),
+            batches: false,
+            // two records per file
+            max_file_size: (FILE_HEADER_LEN as u64) + 2 * (RECORD_HEADER_LEN as u64 + record_len),
+            max_files: None,
+        };
+
+        let mut journal = ConsensusJournal::open(config.clone()).expect("journal opened");
+        for number in 1..=5 {
+            assert!(journal.append(&output(number)).expect("appended"));
+        }
+        assert_eq!(numbers(&config.dir), vec![vec![1, 2], vec![3, 4], vec![5]]);
+        let files = journal_files(&config.dir).expect("journal files");
+        assert!(files[2].ends_with(file_name(5)));
+        let record = &read_journal_file(&files[0]).expect("journal file")[0];
+        assert_eq!(record, &JournalRecord::new(&output(1), false));
+        assert_eq!(record.digest, output(1).consensus_header_hash());
+        assert_eq!(record.batches, None);
+        drop(journal);
+
+        // a partial record is truncated and replayed outputs are skipped after a restart
+        let mut file = OpenOptions::new().append(true).open(&files[2]).expect("file");
+        file.write_all(&[0, 0, 1]).expect("partial record");
+        let mut journal = ConsensusJournal::open(config.clone()).expect("journal reopened");
+        assert!(!journal.append(&output(5)).expect("skipped"));
+        assert!(journal.append(&output(6)).expect("appended"));
+        assert_eq!(numbers(&config.dir), vec![vec![1, 2], vec![3, 4], vec![5, 6]]);
+        drop(journal);
+
+        // batches are included and the oldest files are deleted
+        let config = JournalConfig { batches: true, max_files: Some(2), ..config };
+        let mut journal = ConsensusJournal::open(config.clone()).expect("journal reopened");
+        assert!(journal.append(&output(7)).expect("appended"));
+        assert_eq!(numbers(&config.dir), vec![vec![5, 6], vec![7]]);
+        let files = journal_files(&config.dir).expect("journal files");
+        let record = &read_journal_file(&files[1]).expect("journal file")[0];
+        assert_eq!(record.batches, Some(output(7).batches));
+
+        // files that are not journals are rejected
+        fs::write(config.dir.join(file_name(8)), b"not a journal").expect("write");
+        assert!(ConsensusJournal::open(config).is_err());
+    }
+
+    #[test]
+    fn test_consensus_journal_backfill() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let config = JournalConfig {
+            dir: dir.path().join("journal"),
+            batches: true,
+            max_file_size: u64::MAX,
+            max_files: None,
+        };
+        let db = MemDatabase::default();
+        let fixture = CommitteeFixture::builder(MemDatabase::default).build();
+        let committee = fixture.committee();
+        db.write_committee(&committee).expect("committee stored");
+        let leader = fixture.authorities().nth(1).expect("leader");
+        let epoch = committee.epoch();
+
+        // outputs committed before the journal was enabled are appended on open
+        let outputs: Vec<_> = (1..=3).map(|number| commit(&db, number, leader, epoch)).collect();
+        let mut journal = ConsensusJournal::open(config.clone()).expect("journal opened");
+        assert_eq!(journal.catch_up(&db, None).expect("caught up"), 3);
+        assert_eq!(numbers(&config.dir), vec![vec![1, 2, 3]]);
+        let files = journal_files(&config.dir).expect("journal files");
+        let records = read_journal_file(&files[0]).expect("journal file");
+        assert_eq!(records[1], JournalRecord::new(&outputs[1], true));
+
+        // outputs missed by a lagging receiver are appended before the next output
+        commit(&db, 4, leader, epoch);
+        commit(&db, 5, leader, epoch);
+        let next = commit(&db, 6, leader, epoch);
+        assert_eq!(journal.catch_up(&db, Some(&next)).expect("caught up"), 2);
+        assert_eq!(journal.catch_up(&db, Some(&next)).expect("caught up"), 0);
+        assert_eq!(numbers(&config.dir), vec![vec![1, 2, 3, 4, 5, 6]]);
+
+        // outputs with pruned batches are appended without them
+        let pruned = commit(&db, 7, leader, epoch);
+        db.remove::<Batches>(&pruned.batch_digests[0]).expect("batch pruned");
+        let next = commit(&db, 8, leader, epoch);
+        assert_eq!(journal.catch_up(&db, Some(&next)).expect("caught up"), 1);
+        let records = read_journal_file(&files[0]).expect("journal file");
+        assert_eq!(records.iter().map(|record| record.header.number).max(), Some(8));
+        assert_eq!(records[6].batches, None);
+        assert_eq!(records[7], JournalRecord::new(&next, true));
+    }
+}
@@ -2,15 +2,35 @@
 //! Library for managing all components used by a full-node in a single process.
 
 use std::{
+    collections::{HashMap, HashSet},
     str::FromStr as _,
     sync::{
         atomic::{AtomicU32, Ordering},
         Arc,
     },
-    time::Duration,
+    time::{Duration, Instant},
 };
 
-use crate::{primary::PrimaryNode, worker::WorkerNode};
+use crate::{
+    address_index::{spawn_address_index_recorder, AddressIndexReader},
+    announce::spawn_block_announcer,
+    archive::EpochArchive,
+    batch_pruner::spawn_batch_pruner,
+    checkpoints::ConsensusCheckpoints,
+    clock::spawn_clock_monitor,
+    crash_loop::CrashLoopGuard,
+    dev_mining::{DevMiner, DEV_SEAL_REQUEST_CAPACITY},
+    epochs::EpochSummarizer,
+    governance::{spawn_leader_schedule_watcher, ParameterGovernor},
+    handle::NodeHandle,
+    journal::spawn_consensus_journal,
+    primary::PrimaryNode,
+    stats::{spawn_sub_dag_stats_recorder, SubDagStatsReader},
+    status::NodeStatusReporter,
+    stream::{connect_sink, BlockStreamer},
+    sync_status::SyncStatusReporter,
+    worker::WorkerNode,
+};
 use consensus_metrics::start_prometheus_server;
 use engine::{ExecutionNode, TnBuilder};
 use futures::StreamExt;
@@ -19,37 +39,96 @@ use reth_db::{
     Database,
 };
 use reth_provider::CanonStateSubscriptions;
-use tn_config::{ConsensusConfig, KeyConfig, TelcoinDirs};
-use tn_network_libp2p::{types::IdentTopic, ConsensusNetwork, PeerId};
+use reth_transaction_pool::{EthPooledTransaction, TransactionOrigin, TransactionPool as _};
+use tn_config::{bootnode_peer_id, ChannelClass, ConsensusConfig, KeyConfig, TelcoinDirs};
+use tn_grpc::{spawn_grpc_server, ConsensusDataService};
+use tn_network_libp2p::{types::IdentTopic, ConsensusNetwork, NodeVersion, PeerId};
 use tn_node_traits::TelcoinNode;
 use tn_primary::{
     network::{PrimaryNetwork, PrimaryNetworkHandle},
     ConsensusBus, NodeMode, StateSynchronizer,
 };
-use tn_storage::{open_db, tables::ConsensusBlocks, DatabaseType};
-use tn_types::{BatchValidation, ConsensusHeader, Database as TNDatabase, Multiaddr, TaskManager};
+use tn_storage::{
+    migrations::{migrate, ConsensusStore},
+    open_db, open_encrypted_db, open_memory_db,
+    tables::ConsensusBlocks,
+    ConsensusStore as _, DatabaseType, HaltTargetStore as _,
+};
+use tn_types::{
+    now, set_forks, AuthorityIdentifier, BatchValidation, CertificateStoreCache,
+    CertificateStoreCacheMetrics, ConsensusHeader, Database as TNDatabase, InclusionPromises,
+    Multiaddr, TaskManager, TaskManagerExit, TxDedupFilter, WorkerInfoUpdate,
+    DEFAULT_INCLUSION_PROMISE_CAPACITY,
+};
 use tn_worker::{WorkerNetwork, WorkerNetworkHandle};
 use tokio::{runtime::Builder, sync::mpsc};
-use tracing::{info, instrument, warn};
+use tracing::{debug, error, field, info, info_span, instrument, warn, Instrument as _, Span};
 
+pub use crash_loop::{CrashLoopError, CRASH_LOOP_EXIT_CODE};
+
+/// Why the node stopped running.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeExit {
+    /// The node was asked to shutdown.
+    Shutdown,
+    /// The node changed modes and must be relaunched.
+    ModeChange,
+    /// A task stopped unexpectedly.
+    Crashed(String),
+}
+
+mod address_index;
+mod announce;
+mod archive;
+mod batch_pruner;
+pub mod checkpoints;
+mod clock;
+mod crash_loop;
+mod dev_mining;
 pub mod dirs;
 pub mod engine;
+mod epochs;
 mod error;
+mod governance;
+pub mod handle;
+pub mod journal;
+pub mod migrations;
 pub mod primary;
+mod replica;
+mod stats;
+mod status;
+mod stream;
+mod sync_status;
 pub mod worker;
 
+/// The delay between dials for each step of dial priority.
+///
+/// Peers are dialed in order of their last known round trip time, so the closest peers are
+/// connected (and retried) first.
+const DIAL_STAGGER: Duration = Duration::from_millis(25);
+
 /// Spawn a task to dial a primary peer and to keep trying on failure.
+///
+/// DNS addresses are resolved again on every attempt, so a peer whose IP changed is reached
+/// without editing the committee. The first dial and every retry are delayed by
+/// `priority * DIAL_STAGGER`, use 0 for the highest priority. `on_failure` is called after each
+/// failed attempt.
 fn dial_primary(
     handle: PrimaryNetworkHandle,
     peer_id: PeerId,
     peer_addr: tn_network_libp2p::Multiaddr,
     connected_count: Arc<AtomicU32>,
+    priority: u32,
+    on_failure: impl Fn() + Send + 'static,
 ) {
+    let stagger = DIAL_STAGGER * priority;
     tokio::spawn(async move {
+        tokio::time::sleep(stagger).await;
         let mut backoff = 1;
         while let Err(e) = handle.dial(peer_id, peer_addr.clone()).await {
             tracing::warn!(target: "telcoin::node", "failed to dial primary {peer_id} at {peer_addr}: {e}");
-            tokio::time::sleep(Duration::from_secs(backoff)).await;
+            on_failure();
+            tokio::time::sleep(Duration::from_secs(backoff) + stagger).await;
             if backoff < 120 {
                 backoff += backoff;
             }
@@ -59,17 +138,25 @@ fn dial_primary(
 }
 
 /// Spawn a task to dial a worker peer and to keep trying on failure.
+///
+/// Like [dial_primary], each attempt resolves DNS addresses again, is delayed by the priority and
+/// calls `on_failure` if it fails.
 fn dial_worker(
     handle: WorkerNetworkHandle,
     peer_id: PeerId,
     peer_addr: tn_network_libp2p::Multiaddr,
     connected_count: Arc<AtomicU32>,
+    priority: u32,
+    on_failure: impl Fn() + Send + 'static,
 ) {
+    let stagger = DIAL_STAGGER * priority;
     tokio::spawn(async move {
+        tokio::time::sleep(stagger).await;
         let mut backoff = 1;
         while let Err(e) = handle.dial(peer_id, peer_addr.clone()).await {
             tracing::warn!(target: "telcoin::node", "failed to dial worker {peer_id} at {peer_addr}: {e}");
-            tokio::time::sleep(Duration::from_secs(backoff)).await;
+            on_failure();
+            tokio::time::sleep(Duration::from_secs(backoff) + stagger).await;
             if backoff < 120 {
                 backoff += backoff;
             }
@@ -78,22 +165,143 @@ fn dial_worker(
     });
 }
 
+/// Record a failed dial to `authority` against its saved round trip time.
+///
+/// Peers that keep failing are dialed and asked for certificates after reachable peers, see
+/// [ConsensusConfig::authority_latencies].
+fn record_dial_failure<DB: TNDatabase>(
+    consensus_config: &ConsensusConfig<DB>,
+    authority: Option<AuthorityIdentifier>,
+) -> impl Fn() + Send + 'static {
+    let consensus_config = consensus_config.clone();
+    move || {
+        if let Some(authority) = &authority {
+            consensus_config.record_authority_failure(authority);
+        }
+    }
+}
+
+/// Sort `peers` by the saved round trip time to the authority running each.
+///
+/// Peers without an authority or a saved time keep their order after the others.
+fn sort_by_latency<T>(
+    peers: &mut [T],
+    latencies: &HashMap<AuthorityIdentifier, Duration>,
+    authority: impl Fn(&T) -> Option<AuthorityIdentifier>,
+) {
+    peers.sort_by_key(|peer| {
+        authority(peer).and_then(|id| latencies.get(&id).copied()).unwrap_or(Duration::MAX)
+    });
+}
+
+/// Spawn a task to dial workers that moved to a new address.
+///
+/// Authorities announce worker changes over the primary network and the updates are applied to
+/// the worker cache in [ConsensusConfig].
+fn spawn_worker_cache_updates<DB: TNDatabase>(
+    consensus_config: &ConsensusConfig<DB>,
+    task_manager: &TaskManager,
+    handle: WorkerNetworkHandle,
+    our_address: Multiaddr,
+    connected_count: Arc<AtomicU32>,
+) {
+    let mut rx_worker_cache = consensus_config.subscribe_worker_cache();
+    let rx_shutdown = consensus_config.shutdown().subscribe();
+    let consensus_config = consensus_config.clone();
+    task_manager.spawn_task("worker cache updates", async move {
+        let mut known: HashSet<_> =
+            rx_worker_cache.borrow_and_update().all_workers().into_iter().collect();
+        loop {
+            tokio::select!(
+                _ = &rx_shutdown => break,
+                res = rx_worker_cache.changed() => {
+                    if res.is_err() {
+                        break;
+                    }
+                }
+            );
+
+            let workers: HashSet<_> =
+                rx_worker_cache.borrow_and_update().all_workers().into_iter().collect();
+            for (peer_id, addr) in workers.difference(&known) {
+                if *addr != our_address {
+                    info!(target: "telcoin::node", %peer_id, %addr, "dialing updated worker");
+                    dial_worker(
+                        handle.clone(),
+                        *peer_id,
+                        addr.clone(),
+                        connected_count.clone(),
+                        0,
+                        record_dial_failure(
+                            &consensus_config,
+                            consensus_config.worker_authority(peer_id),
+                        ),
+                    );
+                }
+            }
+            known = workers;
+        }
+    });
+}
+
+/// Apply this node's configured workers to the worker cache if they changed.
+///
+/// Returns the signed update so it can be published to the committee once the primary network
+/// is connected. Peers apply it with [ConsensusConfig::update_worker_cache] and dial the new
+/// addresses.
+fn local_worker_update<DB: TNDatabase>(
+    consensus_config: &ConsensusConfig<DB>,
+) -> eyre::Result<Option<WorkerInfoUpdate>> {
+    let config = consensus_config.config();
+    if config.observer {
+        return Ok(None);
+    }
+
+    let bls_public_key = consensus_config.authority().protocol_key();
+    let worker_cache = consensus_config.worker_cache();
+    let local = &config.validator_info.primary_info.worker_index;
+    if local.0.is_empty() || worker_cache.workers.get(bls_public_key) == Some(local) {
+        return Ok(None);
+    }
+
+    // peers may have applied updates this node missed, so the sequence also follows the clock
+    let last = worker_cache.sequences.get(bls_public_key).copied().unwrap_or_default();
+    let update = WorkerInfoUpdate::new(
+        consensus_config.key_config(),
+        *bls_public_key,
+        worker_cache.epoch(),
+        (last + 1).max(now()),
+        local.clone(),
+    );
+    consensus_config.update_worker_cache(&update)?;
+    info!(target: "telcoin::node", sequence = update.sequence, "worker info changed");
+    Ok(Some(update))
+}
+
 /// Start up the primary and worker libp2p networks and return handles to use it.
 /// This will also dial initial peers and the networks should be ready to use once it resolves.
+#[allow(clippy::too_many_arguments)]
 async fn start_networks<DB: TNDatabase>(
     consensus_config: &ConsensusConfig<DB>,
     consensus_bus: &ConsensusBus,
     task_manager: &TaskManager,
     worker_id: &u16,
     validator: Arc<dyn BatchValidation>,
+    dedup_filter: TxDedupFilter,
     state_sync: StateSynchronizer<DB>,
+    local_version: NodeVersion,
 ) -> eyre::Result<(PrimaryNetworkHandle, WorkerNetworkHandle)> {
-    let (event_stream, rx_event_stream) = mpsc::channel(1000);
-    let (worker_event_stream, rx_worker_event_stream) = mpsc::channel(1000);
-    let primary_network = ConsensusNetwork::new_for_primary(consensus_config, event_stream)
-        .expect("primry p2p network create failed!");
-    let worker_network = ConsensusNetwork::new_for_worker(consensus_config, worker_event_stream)
-        .expect("worker p2p network create failed!");
+    let channels = &consensus_config.parameters().channels;
+    let (event_stream, rx_event_stream) =
+        mpsc::channel(channels.capacity(ChannelClass::NetworkEvents, "primary_network_events"));
+    let (worker_event_stream, rx_worker_event_stream) =
+        mpsc::channel(channels.capacity(ChannelClass::NetworkEvents, "worker_network_events"));
+    let primary_network =
+        ConsensusNetwork::new_for_primary(consensus_config, event_stream, local_version.clone())
+            .expect("primry p2p network create failed!");
+    let worker_network =
+        ConsensusNetwork::new_for_worker(consensus_config, worker_event_stream, local_version)
+            .expect("worker p2p network create failed!");
     let primary_network_handle = primary_network.network_handle();
     let worker_network_handle = worker_network.network_handle();
     let rx_shutdown = consensus_config.shutdown().subscribe();
@@ -127,25 +335,94 @@ async fn start_networks<DB: TNDatabase>(
     );
     primary_network_handle.start_listening(primary_multiaddr).await?;
 
+    // listen on the configured worker addresses, not the addresses peers knew
+    let worker_update = local_worker_update(consensus_config)?;
+
+    // each worker listens on its own address from the worker cache
+    //
+    // `WORKER_MULTIADDR` is still supported for the first worker
     let worker_address = consensus_config.worker_address(worker_id);
+    let fallback = if *worker_id == 0 {
+        get_multiaddr_from_env_or_config("WORKER_MULTIADDR", worker_address.clone())
+    } else {
+        worker_address.clone()
+    };
     let worker_multiaddr =
-        get_multiaddr_from_env_or_config("WORKER_MULTIADDR", worker_address.clone());
+        get_multiaddr_from_env_or_config(&format!("WORKER_{worker_id}_MULTIADDR"), fallback);
     worker_network_handle.start_listening(worker_multiaddr).await?;
     let primary_network_handle = PrimaryNetworkHandle::new(primary_network_handle);
-    let worker_network_handle = WorkerNetworkHandle::new(worker_network_handle);
+    let worker_network_handle = WorkerNetworkHandle::new(worker_network_handle)
+        .with_clock(consensus_config.clock().clone());
     let peers_connected = Arc::new(AtomicU32::new(0));
     let workers_connected = Arc::new(AtomicU32::new(0));
-    for (authority_id, addr, _) in
-        consensus_config.committee().others_primaries_by_id(&consensus_config.authority().id())
-    {
+    // bootnodes replace the address of committee members since the committee may be stale
+    let mut bootnodes: HashMap<_, _> = consensus_config
+        .config()
+        .bootnodes
+        .iter()
+        .filter_map(|addr| Some((bootnode_peer_id(addr)?, addr.clone())))
+        .collect();
+    // dial the committee in order of the round trip times saved by the worker's latency probes
+    //
+    // peers without a saved time are dialed last
+    let latencies = consensus_config.authority_latencies();
+    let mut primaries =
+        consensus_config.committee().others_primaries_by_id(&consensus_config.authority().id());
+    sort_by_latency(&mut primaries, &latencies, |(authority_id, _, _)| Some(authority_id.clone()));
+    let mut priority = 0;
+    for (authority_id, addr, _) in primaries {
         let peer_id = authority_id.peer_id();
-        dial_primary(primary_network_handle.clone(), peer_id, addr, peers_connected.clone());
+        let addr = bootnodes.remove(&peer_id).unwrap_or(addr);
+        dial_primary(
+            primary_network_handle.clone(),
+            peer_id,
+            addr,
+            peers_connected.clone(),
+            priority,
+            record_dial_failure(consensus_config, Some(authority_id)),
+        );
+        priority += 1;
     }
-    for (peer_id, addr) in consensus_config.worker_cache().all_workers() {
-        if addr != worker_address {
-            dial_worker(worker_network_handle.clone(), peer_id, addr, workers_connected.clone());
-        }
+    // other bootnodes do not count towards the quorum of connected peers
+    let local_peer_id = consensus_config.authority().peer_id();
+    for (peer_id, addr) in bootnodes.into_iter().filter(|(peer_id, _)| *peer_id != local_peer_id) {
+        info!(target: "telcoin::node", %peer_id, %addr, "dialing bootnode");
+        dial_primary(
+            primary_network_handle.clone(),
+            peer_id,
+            addr,
+            Arc::new(AtomicU32::new(0)),
+            priority,
+            || (),
+        );
+        priority += 1;
     }
+    let mut workers: Vec<_> = consensus_config
+        .worker_cache()
+        .all_workers()
+        .into_iter()
+        .filter(|(_, addr)| *addr != worker_address)
+        .collect();
+    sort_by_latency(&mut workers, &latencies, |(peer_id, _)| {
+        consensus_config.worker_authority(peer_id)
+    });
+    for (priority, (peer_id, addr)) in (0..).zip(workers) {
+        dial_worker(
+            worker_network_handle.clone(),
+            peer_id,
+            addr,
+            workers_connected.clone(),
+            priority,
+            record_dial_failure(consensus_config, consensus_config.worker_authority(&peer_id)),
+        );
+    }
+    spawn_worker_cache_updates(
+        consensus_config,
+        task_manager,
+        worker_network_handle.clone(),
+        worker_address,
+        workers_connected.clone(),
+    );
     let quorum = consensus_config.committee().quorum_threshold() as u32;
     // Wait until we are connected to a quorum of peers (note this assumes we are a validator...).
     while peers_connected.load(Ordering::Relaxed) < quorum
@@ -153,6 +430,11 @@ async fn start_networks<DB: TNDatabase>(
     {
         tokio::time::sleep(Duration::from_millis(500)).await;
     }
+    if let Some(update) = worker_update {
+        if let Err(e) = primary_network_handle.publish_worker_info_update(update).await {
+            warn!(target: "telcoin::node", ?e, "failed to publish worker info update");
+        }
+    }
     let primary_network = PrimaryNetwork::new(
         rx_event_stream,
         primary_network_handle.clone(),
@@ -169,6 +451,7 @@ async fn start_networks<DB: TNDatabase>(
         consensus_config.clone(),
         *worker_id,
         validator,
+        dedup_filter,
     )
     .spawn(task_manager);
 
@@ -182,13 +465,18 @@ async fn start_networks<DB: TNDatabase>(
 /// sure any lefteover tasks are ended.  This allows it to be called more
 /// than once per program execution to support changing modes of the
 /// running node.
-/// If it returns Ok(true) this indicates a mode change occurred and a restart
-/// is required.
+/// Returns why the node stopped, a mode change or crash requires a relaunch.
+///
+/// `pending_transactions` are added to the worker's pool at launch and replaced by the pool's
+/// transactions when the node stops for a mode change, so transactions accepted while a CVV
+/// catches up are sealed once it rejoins consensus.
 pub fn launch_node_inner<DB, P>(
     builder: &TnBuilder<DB>,
     tn_datadir: &P,
     db: DatabaseType,
-) -> eyre::Result<bool>
+    handle: &NodeHandle,
+    pending_transactions: &mut Vec<EthPooledTransaction>,
+) -> eyre::Result<NodeExit>
 where
     DB: Database + DatabaseMetrics + DatabaseMetadata + Clone + Unpin + 'static,
     P: TelcoinDirs + 'static,
@@ -203,41 +491,134 @@ where
         .build()
         .expect("failed to build a tokio runtime");
 
+    // every log from this node's tasks includes the node id
+    let node_span = info_span!("node", node_id = field::Empty);
     let res = runtime.block_on(async move {
         if let Some(metrics_socket) = builder.consensus_metrics {
             start_prometheus_server(metrics_socket);
         }
 
         // config for validator keys
-        let config = builder.tn_config.clone();
+        let mut config = builder.tn_config.clone();
         let mut task_manager = TaskManager::new("Task Manager");
         let mut engine_task_manager = TaskManager::new("Engine Task Manager");
         let engine = ExecutionNode::<TelcoinNode<DB>>::new(builder, &engine_task_manager)?;
         let validator = engine.new_batch_validator().await;
+        let dedup_filter = engine.tx_dedup_filter().await;
 
         info!(target: "telcoin::node", "execution engine created");
 
+        let checkpoints =
+            Arc::new(ConsensusCheckpoints::new(db.clone(), engine.get_provider().await));
+
+        // apply the governed consensus parameters for the next consensus output
+        let next_output = db.last_record::<ConsensusBlocks>().map_or(0, |(number, _)| number + 1);
+        let tn_chain_spec = builder.tn_config.tn_chain_spec()?;
+        config.parameters.leader_schedule = tn_chain_spec.leader_schedule_at(next_output);
+        let governor = ParameterGovernor::new(
+            tn_chain_spec.clone(),
+            config.parameters.clone(),
+            db.clone(),
+            checkpoints.clone(),
+            engine.get_provider().await,
+        );
+        let governed = match governor.as_ref().map(|governor| governor.parameters(next_output)) {
+            Some(Some(parameters)) => {
+                info!(
+                    target: "telcoin::node",
+                    ?parameters,
+                    "applying governed consensus parameters"
+                );
+                config.parameters = parameters;
+                true
+            }
+            Some(None) => {
+                warn!(target: "telcoin::node", next_output, "using local consensus parameters");
+                false
+            }
+            None => false,
+        };
+
         let node_storage = db.clone();
         tracing::info!(target: "telcoin::cli", "node storage open");
         let key_config = KeyConfig::read_config(tn_datadir)?;
+        if !key_config.is_memory_locked() {
+            warn!(
+                target: "telcoin::node",
+                "validator keys are not locked in memory and may be swapped to disk"
+            );
+        }
         let consensus_config = ConsensusConfig::new(config, tn_datadir, node_storage, key_config)?;
+        Span::current().record("node_id", field::display(consensus_config.authority().id()));
+        // epoch summaries name the committee of the epoch, not the committee at launch
+        db.write_committee(consensus_config.committee())?;
 
         let (worker_id, _worker_info) = consensus_config.config().workers().first_worker()?;
         let worker = WorkerNode::new(*worker_id, consensus_config.clone());
-        let consensus_bus =
-                    ConsensusBus::new_with_args(consensus_config.config().parameters.gc_depth);
+        let parameters = &consensus_config.config().parameters;
+        let consensus_bus = ConsensusBus::new_with_args(parameters.gc_depth, &parameters.channels);
         let state_sync = StateSynchronizer::new(consensus_config.clone(), consensus_bus.clone());
 
+        let local_version = NodeVersion::local(&builder.software_version).ok_or_else(|| {
+            eyre::eyre!("software version {} is not major.minor.patch", builder.software_version)
+        })?;
         let (primary_network_handle, worker_network_handle) =
-            start_networks(&consensus_config, &consensus_bus, &task_manager, worker_id, validator.clone(), state_sync.clone()).await?;
+            start_networks(&consensus_config, &consensus_bus, &task_manager, worker_id, validator.clone(), dedup_filter, state_sync.clone(), local_version).await?;
 
         let primary = PrimaryNode::new(
                 consensus_config.clone(),
                 consensus_bus.clone(),
-                primary_network_handle,
+                primary_network_handle.clone(),
                 state_sync,
             );
 
+        // report node status through the admin rpc
+        let node_status = NodeStatusReporter::new(
+            consensus_bus.clone(),
+            primary_network_handle.clone(),
+            worker_network_handle.clone(),
+            engine.clone(),
+            vec![*worker_id],
+            parameters.worker_heartbeat_interval,
+            builder.log_filter.clone(),
+            db.clone(),
+        );
+        let node_status = Arc::new(node_status);
+        engine.set_node_status_provider(node_status.clone()).await;
+        engine.set_sub_dag_stats_provider(Arc::new(SubDagStatsReader::new(db.clone()))).await;
+        engine.set_sub_dag_block_resolver(checkpoints.clone()).await;
+        engine.set_inclusion_proof_provider(checkpoints.clone()).await;
+        engine.set_state_diff_provider(checkpoints.clone()).await;
+        engine.set_block_provenance_provider(checkpoints.clone()).await;
+        if builder.address_index {
+            engine
+                .set_transactions_by_address_provider(Arc::new(AddressIndexReader::new(db.clone())))
+                .await;
+        }
+
+        // mine blocks on demand for contract test suites in single-node dev networks
+        if builder.dev_mining {
+            eyre::ensure!(
+                consensus_config.committee().size() == 1,
+                "dev mining requires a single-node committee"
+            );
+            let (tx_seal_requests, rx_seal_requests) = mpsc::channel(DEV_SEAL_REQUEST_CAPACITY);
+            let miner = DevMiner::new(consensus_bus.clone(), tx_seal_requests);
+            engine.set_dev_miner(Arc::new(miner), rx_seal_requests).await;
+        }
+
+        // sign inclusion promises for the worker's sealed batches if enabled
+        let inclusion_promises = builder.inclusion_promises.then(|| {
+            InclusionPromises::new(
+                consensus_config.key_config().worker_network_keypair().clone(),
+                consensus_bus.primary_round_updates().subscribe(),
+                DEFAULT_INCLUSION_PROMISE_CAPACITY,
+            )
+        });
+        if let Some(inclusion_promises) = inclusion_promises.clone() {
+            engine.set_inclusion_promises(inclusion_promises).await;
+        }
+
         let mut engine_state = engine.get_provider().await.canonical_state_stream();
 
         // Prime the recent_blocks watch with latest executed blocks.
@@ -252,10 +633,35 @@ where
         let (_, last_db_block) = db
             .last_record::<ConsensusBlocks>()
             .unwrap_or_else(|| (0, ConsensusHeader::default()));
+        // A target saved by `tnAdmin_haltAt` is kept until the node reaches it, the node halted
+        // there if it already has.
+        let saved_halt_at = match db.read_halt_target()? {
+            Some(halt_at) if halt_at <= last_db_block.number => {
+                info!(target: "telcoin::node", halt_at, "clearing reached halt target");
+                db.clear_halt_target()?;
+                None
+            }
+            saved => saved,
+        };
+        // Stop at the requested sub-dag, which must not have been reached yet.
+        if let Some(halt_at) = builder.halt_at_sub_dag {
+            eyre::ensure!(
+                halt_at > last_db_block.number,
+                "can not halt at sub-dag {halt_at}: the node has already reached sub-dag {}",
+                last_db_block.number
+            );
+            info!(target: "telcoin::node", halt_at, "node will halt after executing sub-dag");
+            consensus_bus.halt_at_sub_dag().send_replace(Some(halt_at));
+        } else if let Some(halt_at) = saved_halt_at {
+            info!(target: "telcoin::node", halt_at, "node will halt after executing saved sub-dag");
+            consensus_bus.halt_at_sub_dag().send_replace(Some(halt_at));
+        }
         consensus_bus.last_consensus_header().send(last_db_block)?;
 
         if builder.tn_config.observer {
             consensus_bus.node_mode().send_modify(|v| *v = NodeMode::Observer);
+            // follow the execution tip announced by the committee
+            primary_network_handle.subscribe_block_announcements().await?;
         } else  if state_sync::can_cvv(
             consensus_bus.clone(),
             consensus_config.clone(),
@@ -267,6 +673,9 @@ where
         } else {
             consensus_bus.node_mode().send_modify(|v| *v = NodeMode::CvvInactive);
         }
+        // report sync progress by node mode, the recent blocks must be primed first
+        let sync_status = SyncStatusReporter::new(consensus_bus.clone(), db.clone())?;
+        engine.set_sync_status_provider(Arc::new(sync_status)).await;
 
         // Spawn a task to update the consensus bus with new execution blocks as they are produced.
         let latest_block_shutdown = consensus_config.shutdown().subscribe();
@@ -289,6 +698,115 @@ where
         });
 
 
+        // relaunch at the end of an epoch when the next epoch's governed parameters change
+        if let Some(governor) = governor {
+            governor.spawn_watcher(
+                next_output,
+                consensus_config.parameters().clone(),
+                governed,
+                consensus_bus.clone(),
+                &task_manager,
+                consensus_config.shutdown().clone(),
+            );
+        } else {
+            spawn_leader_schedule_watcher(
+                tn_chain_spec.clone(),
+                next_output,
+                consensus_bus.clone(),
+                &task_manager,
+                consensus_config.shutdown().clone(),
+            );
+        }
+
+        // summarize and archive each epoch once its last consensus output executes
+        let archive = builder.tn_config.archive.clone().map(EpochArchive::new).transpose()?;
+        EpochSummarizer::new(db.clone(), checkpoints.clone(), tn_chain_spec).spawn(
+            archive,
+            engine.get_provider().await.canonical_state_stream(),
+            &task_manager,
+            consensus_config.shutdown().subscribe(),
+        );
+
+        // report the local clock's offset from NTP time
+        spawn_clock_monitor(
+            consensus_config.config().clock.clone(),
+            consensus_bus.primary_metrics().node_metrics.clone(),
+            &task_manager,
+            consensus_config.shutdown().subscribe(),
+        );
+
+        // record gas and transactions for each sub-dag as its blocks are executed
+        spawn_sub_dag_stats_recorder(
+            db.clone(),
+            engine.get_provider().await.canonical_state_stream(),
+            &task_manager,
+            consensus_config.shutdown().subscribe(),
+        );
+
+        // index executed transactions by sender and recipient if enabled
+        if builder.address_index {
+            let provider = engine.get_provider().await;
+            spawn_address_index_recorder(
+                db.clone(),
+                provider.clone(),
+                provider.canonical_state_stream(),
+                &task_manager,
+                consensus_config.shutdown().subscribe(),
+            );
+        }
+
+        // announce executed blocks to observers while this node is an active CVV
+        spawn_block_announcer(
+            db.clone(),
+            consensus_bus.clone(),
+            primary_network_handle,
+            engine.get_provider().await.canonical_state_stream(),
+            &task_manager,
+            consensus_config.shutdown().subscribe(),
+        );
+
+        // delete batches of executed consensus output once they expire
+        if let Some(ttl) = consensus_config.parameters().batch_ttl {
+            spawn_batch_pruner(
+                db.clone(),
+                consensus_bus.clone(),
+                ttl,
+                &task_manager,
+                consensus_config.shutdown().subscribe(),
+            );
+        }
+
+        // stream consensus data to indexers if enabled
+        if let Some(addr) = builder.grpc {
+            let service = ConsensusDataService::new(db.clone(), consensus_bus.clone(), node_status);
+            let rx_shutdown = consensus_config.shutdown().subscribe();
+            spawn_grpc_server(addr, service, &task_manager, rx_shutdown)
+                .map_err(|e| eyre::eyre!("failed to serve gRPC at {addr}: {e}"))?;
+        }
+
+        // append consensus output to journal files if enabled
+        if let Some(journal) = builder.tn_config.journal.clone() {
+            spawn_consensus_journal(
+                journal,
+                db.clone(),
+                consensus_bus.subscribe_consensus_output(),
+                &task_manager,
+                consensus_config.shutdown().subscribe(),
+            )?;
+        }
+
+        // publish executed blocks to kafka or nats if enabled
+        if let Some(stream) = builder.tn_config.stream.clone() {
+            let sink = connect_sink(&stream).await?;
+            let provider = engine.get_provider().await;
+            let canon_state = provider.canonical_state_stream();
+            BlockStreamer::new(db.clone(), provider, sink, stream).spawn(
+                canon_state,
+                &task_manager,
+                consensus_config.shutdown().subscribe(),
+            )?;
+        }
+
         // create receiving channel before spawning primary to ensure messages are not lost
         let consensus_output_rx = consensus_bus.subscribe_consensus_output();
 
@@ -296,39 +814,90 @@ where
         let mut primary_task_manager = primary.start().await?;
 
         // start the worker
-        let batch_provider = worker.start(validator, worker_network_handle).await?;
+        let mut worker_task_manager = TaskManager::new("Worker Task Manager");
+        let batch_provider = worker
+            .start(validator, worker_network_handle, inclusion_promises, &worker_task_manager)
+            .await?;
 
         // start engine
         engine
             .start_engine(
                 consensus_output_rx,
+                consensus_bus.halt_at_sub_dag().subscribe(),
                 &engine_task_manager,
-                consensus_config.shutdown().subscribe(),
+                consensus_config.shutdown(),
             )
             .await?;
+
+        // move finalized blocks to static files
+        engine
+            .start_static_file_producer(&engine_task_manager, consensus_config.shutdown().subscribe())
+            .await;
+
         // spawn block maker for worker
         engine
             .start_batch_builder(
                 *worker_id,
                 batch_provider.batches_tx(),
+                consensus_config.clock().clone(),
                 &engine_task_manager,
                 consensus_config.shutdown().subscribe(),
             )
             .await?;
 
+        // restore the transactions accepted before the last mode change
+        let transaction_pool = engine.get_worker_transaction_pool(worker_id).await?;
+        let restored = std::mem::take(pending_transactions);
+        if !restored.is_empty() {
+            let count = restored.len();
+            info!(target: "telcoin::node", count, "restoring pending transactions");
+            for res in transaction_pool.add_transactions(TransactionOrigin::Local, restored).await {
+                if let Err(e) = res {
+                    // mined or replaced before the mode change
+                    debug!(target: "telcoin::node", ?e, "dropping restored transaction");
+                }
+            }
+        }
+
         primary_task_manager.update_tasks();
         task_manager.add_task_manager(primary_task_manager);
+        worker_task_manager.update_tasks();
+        task_manager.add_task_manager(worker_task_manager);
         engine_task_manager.update_tasks();
         task_manager.add_task_manager(engine_task_manager);
 
         info!(target:"telcoin::node", tasks=?task_manager, "TASKS");
 
-        task_manager.join_until_exit(consensus_config.shutdown().clone()).await;
-        let running = consensus_bus.restart();
+        // the node is running once the worker serves rpc
+        let rpc_address = engine.worker_http_local_address(worker_id).await?;
+        handle.set_running(
+            Some(consensus_bus.clone()),
+            rpc_address,
+            consensus_config.shutdown().clone(),
+        );
+
+        let exit = task_manager.join_until_exit(consensus_config.shutdown().clone()).await;
+        handle.set_stopped();
+        // a mode change also notifies shutdown, check the restart flag first
+        let exit = if consensus_bus.restart() {
+            NodeExit::ModeChange
+        } else {
+            match exit {
+                TaskManagerExit::Signal | TaskManagerExit::Shutdown => NodeExit::Shutdown,
+                TaskManagerExit::TaskExited(task) => NodeExit::Crashed(task),
+            }
+        };
         consensus_bus.clear_restart();
-        info!(target:"tn", "TASKS complete, restart: {running}");
-        Ok(running)
-    });
+        if matches!(exit, NodeExit::ModeChange) {
+            *pending_transactions = transaction_pool
+                .pooled_transactions()
+                .iter()
+                .map(|tx| tx.transaction.clone())
+                .collect();
+        }
+        info!(target:"tn", ?exit, "TASKS complete");
+        Ok(exit)
+    }.instrument(node_span));
     // Kick over the runtime- don't let errant tasks block the Drop.
     runtime.shutdown_background();
     res
@@ -340,8 +909,27 @@ where
 /// This will possibly "loop" to launch multiple times in response to
 /// a nodes mode changes.  This ensures a clean state and fresh tasks
 /// when switching modes.
+/// The node is also relaunched after a crash, backing off when it keeps
+/// crashing and eventually returning a [CrashLoopError].
 #[instrument(level = "info", skip_all)]
-pub fn launch_node<DB, P>(mut builder: TnBuilder<DB>, tn_datadir: P) -> eyre::Result<()>
+pub fn launch_node<DB, P>(builder: TnBuilder<DB>, tn_datadir: P) -> eyre::Result<()>
+where
+    DB: Database + DatabaseMetadata + DatabaseMetrics + Clone + Unpin + 'static,
+    P: TelcoinDirs + 'static,
+{
+    launch_node_with_handle(builder, tn_datadir, NodeHandle::new())
+}
+
+/// Launch all components for the node and control it with a [NodeHandle].
+///
+/// The same as [launch_node], but the caller keeps a clone of the handle to follow the running
+/// node and shut it down. Blocks until the node shuts down.
+#[instrument(level = "info", skip_all)]
+pub fn launch_node_with_handle<DB, P>(
+    mut builder: TnBuilder<DB>,
+    tn_datadir: P,
+    handle: NodeHandle,
+) -> eyre::Result<()>
 where
     DB: Database + DatabaseMetadata + DatabaseMetrics + Clone + Unpin + 'static,
     P: TelcoinDirs + 'static,
@@ -349,18 +937,70 @@ where
     // adjust rpc instance ports
     builder.node_config.adjust_instance_ports();
 
-    let consensus_db_path = tn_datadir.consensus_db_path();
+    // every node activates protocol changes at the epochs in genesis
+    set_forks(builder.tn_config.tn_chain_spec()?.forks);
 
-    tracing::info!(target: "telcoin::node", "opening node storage at {:?}", consensus_db_path);
+    // replicas only serve rpc from another node's data
+    if builder.rpc_replica {
+        return replica::launch_rpc_replica(&builder, &tn_datadir, &handle);
+    }
 
     // open storage for consensus
-    // In case the DB dir does not yet exist.
-    let _ = std::fs::create_dir_all(&consensus_db_path);
-    let db = open_db(&consensus_db_path);
+    let db = if builder.ephemeral {
+        tracing::info!(target: "telcoin::node", "opening in-memory node storage");
+        open_memory_db()
+    } else {
+        let consensus_db_path = tn_datadir.consensus_db_path();
+
+        tracing::info!(target: "telcoin::node", "opening node storage at {:?}", consensus_db_path);
 
-    let mut running = true;
-    while running {
-        running = launch_node_inner(&builder, &tn_datadir, db.clone())?;
+        // In case the DB dir does not yet exist.
+        let _ = std::fs::create_dir_all(&consensus_db_path);
+        match builder.storage_cipher.clone() {
+            Some(cipher) => {
+                tracing::info!(target: "telcoin::node", "encrypting payload and batch storage");
+                open_encrypted_db(&consensus_db_path, cipher)?
+            }
+            None => open_db(&consensus_db_path),
+        }
+    };
+    let cache = &builder.tn_config.parameters.certificate_cache;
+    let db = db.with_certificate_cache(CertificateStoreCache::new(
+        cache.capacity,
+        cache.shards,
+        CertificateStoreCacheMetrics::default(),
+    ));
+    migrate(&ConsensusStore::new(db.clone()), false)?;
+    #[cfg(feature = "chaos")]
+    if let Some(chaos) = builder.tn_config.chaos.bounded() {
+        warn!(target: "telcoin::node", ?chaos, "chaos mode enabled - never use this in production");
+        db.inject_slow_commits(chaos.slow_commit_rate, chaos.max_commit_delay)?;
+    }
+    #[cfg(not(feature = "chaos"))]
+    if builder.tn_config.chaos.enabled {
+        return Err(eyre::eyre!("chaos mode requires a node built with the `chaos` feature"));
+    }
+
+    let mut crash_loop = CrashLoopGuard::default();
+    let mut pending_transactions = Vec::new();
+    while !handle.is_shutdown() {
+        match launch_node_inner(
+            &builder,
+            &tn_datadir,
+            db.clone(),
+            &handle,
+            &mut pending_transactions,
+        )? {
+            NodeExit::Shutdown => break,
+            // mode changes are expected and never count as crashes
+            NodeExit::ModeChange => info!(target: "telcoin::node", "relaunching after mode change"),
+            NodeExit::Crashed(task) => {
+                let backoff = crash_loop.record_crash(Instant::now(), &task)?;
+                error!(target: "telcoin::node", ?task, ?backoff, "node crashed, relaunching");
+                // not running in a runtime between launches
+                std::thread::sleep(backoff);
+            }
+        }
     }
     Ok(())
 }
@@ -374,3 +1014,41 @@ fn get_multiaddr_from_env_or_config(env_var: &str, fallback: Multiaddr) -> Multi
     info!(target: "telcoin::node", ?multiaddr, env_var);
     multiaddr
 }
+
+#[cfg(test)]
+mod tests {
+    use super::sort_by_latency;
+    use std::time::Duration;
+    use tn_storage::{mem_db::MemDatabase, PeerLatencyStore as _};
+    use tn_test_utils::CommitteeFixture;
+    use tn_types::now;
+
+    #[test]
+    fn test_dial_order() {
+        let fixture = CommitteeFixture::builder(MemDatabase::default).randomize_ports(true).build();
+        let local = fixture.first_authority();
+        let others: Vec<_> = fixture.authorities().skip(1).collect();
+        let [near, penalized, unknown] = [0, 1, 2].map(|i| others[i].id());
+        let config = local.consensus_config();
+        let store = config.node_storage();
+        store.record_peer_latency(&near, Duration::from_millis(50), now()).unwrap();
+        // the fastest sample, but dials failed since
+        store.record_peer_latency(&penalized, Duration::from_millis(10), now()).unwrap();
+        for _ in 0..3 {
+            config.record_authority_failure(&penalized);
+        }
+        let latencies = config.authority_latencies();
+
+        let mut primaries = config.committee().others_primaries_by_id(&local.id());
+        sort_by_latency(&mut primaries, &latencies, |(id, _, _)| Some(id.clone()));
+        let primaries: Vec<_> = primaries.into_iter().map(|(id, _, _)| id).collect();
+        assert_eq!(primaries, vec![near, penalized, unknown]);
+
+        // workers are ordered by the time to the authority running them
+        let [near, penalized, unknown] =
+            [0, 1, 2].map(|i| others[i].worker().info().name.to_peer_id());
+        let mut workers = vec![unknown, penalized, near];
+        sort_by_latency(&mut workers, &latencies, |peer_id| config.worker_authority(peer_id));
+        assert_eq!(workers, vec![near, penalized, unknown]);
+    }
+}
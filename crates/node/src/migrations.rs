@@ -0,0 +1,109 @@
+//! Schema migrations for the execution DB.
+//!
+//! Reth owns the execution DB's tables and checks its own format version. The schema version of
+//! data Telcoin Network writes to it is recorded in a file next to the DB so migrations run with
+//! the same runner as the consensus DB, see [tn_storage::migrations].
+
+use reth_db::{init_db, mdbx::DatabaseArguments, open_db_read_only, DatabaseEnv};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tn_storage::migrations::{Migration, VersionedStore};
+use tracing::{info, warn};
+
+pub use tn_storage::migrations::{migrate, ConsensusStore, MigrationReport};
+
+/// The schema version of the execution DB written by this software.
+pub const EXECUTION_SCHEMA_VERSION: u64 = 1;
+
+/// The file in the execution DB directory that records the schema version.
+pub const EXECUTION_SCHEMA_VERSION_FILE: &str = "tn-schema.version";
+
+/// Open the execution DB at `db_path` and apply pending migrations.
+///
+/// A `read_only` DB belongs to another node, so migrations are only reported.
+pub fn open_execution_db(
+    db_path: &Path,
+    args: DatabaseArguments,
+    read_only: bool,
+) -> eyre::Result<Arc<DatabaseEnv>> {
+    info!(target: "tn::engine", path = ?db_path, read_only, "opening database");
+    let database = if read_only {
+        Arc::new(open_db_read_only(db_path, args)?.with_metrics())
+    } else {
+        Arc::new(init_db(db_path, args)?.with_metrics())
+    };
+    let report = migrate(&ExecutionStore::new(database.clone(), db_path), read_only)?;
+    if !report.is_current() && !report.applied {
+        warn!(target: "tn::engine", ?report, "execution DB has pending migrations");
+    }
+    Ok(database)
+}
+
+/// The execution DB and the file recording its schema version.
+#[derive(Debug)]
+pub struct ExecutionStore<DB> {
+    /// The execution DB.
+    db: DB,
+    /// The path to the schema version file.
+    version_file: PathBuf,
+}
+
+impl<DB> ExecutionStore<DB> {
+    /// Create a new instance of [Self] for the execution DB at `db_path`.
+    pub fn new(db: DB, db_path: &Path) -> Self {
+        Self { db, version_file: db_path.join(EXECUTION_SCHEMA_VERSION_FILE) }
+    }
+
+    /// The execution DB.
+    pub fn db(&self) -> &DB {
+        &self.db
+    }
+}
+
+impl<DB> VersionedStore for ExecutionStore<DB> {
+    const NAME: &'static str = "execution";
+    const SCHEMA_VERSION: u64 = EXECUTION_SCHEMA_VERSION;
+
+    fn schema_version(&self) -> eyre::Result<Option<u64>> {
+        match std::fs::read_to_string(&self.version_file) {
+            Ok(version) => Ok(Some(version.trim().parse()?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn set_schema_version(&self, version: u64) -> eyre::Result<()> {
+        std::fs::write(&self.version_file, version.to_string())?;
+        Ok(())
+    }
+
+    fn migrations(&self) -> Vec<Migration<Self>> {
+        // version 1 records the schema version of existing data and changes nothing else
+        vec![]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_execution_schema_version_file() {
+        let dir = tempfile::tempdir().expect("temp dir");
+        let store = ExecutionStore::new((), dir.path());
+        assert_eq!(store.schema_version().unwrap(), None);
+
+        // a dry run does not write the version
+        let report = migrate(&store, true).expect("dry run");
+        assert_eq!((report.from, report.to), (0, EXECUTION_SCHEMA_VERSION));
+        assert_eq!(store.schema_version().unwrap(), None);
+
+        migrate(&store, false).expect("migrated");
+        assert_eq!(store.schema_version().unwrap(), Some(EXECUTION_SCHEMA_VERSION));
+
+        std::fs::write(dir.path().join(EXECUTION_SCHEMA_VERSION_FILE), "99").unwrap();
+        assert!(migrate(&store, false).is_err());
+    }
+}
@@ -8,7 +8,7 @@ use tn_primary::{
     ConsensusBus, Primary, StateSynchronizer,
 };
 use tn_primary_metrics::Metrics;
-use tn_types::{Database as ConsensusDatabase, TaskManager, DEFAULT_BAD_NODES_STAKE_THRESHOLD};
+use tn_types::{Database as ConsensusDatabase, TaskManager};
 use tokio::sync::RwLock;
 use tracing::instrument;
 
@@ -22,11 +22,6 @@ struct PrimaryNodeInner<CDB> {
 }
 
 impl<CDB: ConsensusDatabase> PrimaryNodeInner<CDB> {
-    /// The window where the schedule change takes place in consensus. It represents number
-    /// of committed sub dags.
-    /// TODO: move this to node properties
-    const CONSENSUS_SCHEDULE_CHANGE_SUB_DAGS: u32 = 300;
-
     /// Starts the primary node with the provided info. If the node is already running then this
     /// method will return an error instead.
     #[instrument(name = "primary_node", skip_all)]
@@ -60,10 +55,11 @@ impl<CDB: ConsensusDatabase> PrimaryNodeInner<CDB> {
         consensus_bus: &ConsensusBus,
         task_manager: &TaskManager,
     ) -> SubscriberResult<LeaderSchedule> {
+        let schedule_params = self.consensus_config.parameters().leader_schedule;
         let leader_schedule = LeaderSchedule::from_store(
             self.consensus_config.committee().clone(),
             self.consensus_config.node_storage().clone(),
-            DEFAULT_BAD_NODES_STAKE_THRESHOLD,
+            schedule_params.bad_nodes_stake_threshold,
         );
 
         // Spawn the consensus core who only sequences transactions.
@@ -71,9 +67,9 @@ impl<CDB: ConsensusDatabase> PrimaryNodeInner<CDB> {
             self.consensus_config.committee().clone(),
             self.consensus_config.node_storage().clone(),
             self.consensus_bus.consensus_metrics().clone(),
-            Self::CONSENSUS_SCHEDULE_CHANGE_SUB_DAGS,
+            schedule_params.sub_dags_per_schedule,
             leader_schedule.clone(),
-            DEFAULT_BAD_NODES_STAKE_THRESHOLD,
+            schedule_params.bad_nodes_stake_threshold,
         );
         Consensus::spawn(
             self.consensus_config.clone(),
@@ -0,0 +1,122 @@
+//! Read-only RPC replicas.
+//!
+//! A replica opens the consensus and execution DBs of another node without write access and only
+//! serves RPC. It does not start networking or any consensus tasks so operators can scale read
+//! traffic with replicas pointing at snapshot volumes.
+//!
+//! The consensus DB is loaded into memory when the replica starts and reloaded every
+//! [REFRESH_INTERVAL] so RPC follows the node that owns the DB. The execution DB is read directly.
+
+use crate::{
+    address_index::AddressIndexReader,
+    checkpoints::ConsensusCheckpoints,
+    engine::{ExecutionNode, TnBuilder},
+    handle::NodeHandle,
+    stats::SubDagStatsReader,
+};
+use reth_db::{
+    database_metrics::{DatabaseMetadata, DatabaseMetrics},
+    Database,
+};
+use std::{sync::Arc, time::Duration};
+use tn_config::TelcoinDirs;
+use tn_node_traits::TelcoinNode;
+use tn_storage::{
+    migrations::{migrate, ConsensusStore},
+    open_read_only_db, refresh_read_only_db, DatabaseType,
+};
+use tn_types::{Noticer, Notifier, TaskManager, TaskManagerExit};
+use tokio::runtime::Builder;
+use tracing::{error, info, warn};
+
+/// How often the consensus DB is reloaded from disk.
+const REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Spawn a task that reloads the consensus DB every [REFRESH_INTERVAL].
+///
+/// A failed reload keeps serving the previous records and is retried at the next interval.
+fn spawn_refresh(db: DatabaseType, task_manager: &TaskManager, rx_shutdown: Noticer) {
+    task_manager.spawn_task("replica refresh", async move {
+        let mut interval = tokio::time::interval(REFRESH_INTERVAL);
+        // the DB was loaded when it was opened
+        interval.tick().await;
+        loop {
+            tokio::select!(
+                _ = &rx_shutdown => break,
+                _ = interval.tick() => {
+                    let db = db.clone();
+                    match tokio::task::spawn_blocking(move || refresh_read_only_db(&db)).await {
+                        Ok(Ok(())) => {}
+                        Ok(Err(e)) => {
+                            error!(target: "telcoin::node", ?e, "failed to refresh consensus DB")
+                        }
+                        Err(e) => {
+                            error!(target: "telcoin::node", ?e, "consensus DB refresh panicked")
+                        }
+                    }
+                }
+            );
+        }
+    });
+}
+
+/// Serve RPC from read-only DBs until the process is asked to exit.
+pub(crate) fn launch_rpc_replica<DB, P>(
+    builder: &TnBuilder<DB>,
+    tn_datadir: &P,
+    handle: &NodeHandle,
+) -> eyre::Result<()>
+where
+    DB: Database + DatabaseMetadata + DatabaseMetrics + Clone + Unpin + 'static,
+    P: TelcoinDirs + 'static,
+{
+    let consensus_db_path = tn_datadir.consensus_db_path();
+    info!(target: "telcoin::node", "opening read-only node storage at {:?}", consensus_db_path);
+    let db = open_read_only_db(&consensus_db_path, builder.storage_cipher.clone())?;
+    // replicas can not migrate another node's data
+    let report = migrate(&ConsensusStore::new(db.clone()), true)?;
+    if !report.is_current() {
+        warn!(target: "telcoin::node", ?report, "consensus DB has pending migrations");
+    }
+
+    let runtime = Builder::new_multi_thread()
+        .thread_name("telcoin-replica")
+        .enable_io()
+        .enable_time()
+        .build()
+        .expect("failed to build a tokio runtime");
+
+    let res = runtime.block_on(async move {
+        let shutdown = Notifier::new();
+        let mut task_manager = TaskManager::new("Replica Task Manager");
+        let engine = ExecutionNode::<TelcoinNode<DB>>::new(builder, &task_manager)?;
+        engine.set_sub_dag_stats_provider(Arc::new(SubDagStatsReader::new(db.clone()))).await;
+        if builder.address_index {
+            engine
+                .set_transactions_by_address_provider(Arc::new(AddressIndexReader::new(db.clone())))
+                .await;
+        }
+        spawn_refresh(db.clone(), &task_manager, shutdown.subscribe());
+        let checkpoints = Arc::new(ConsensusCheckpoints::new(db, engine.get_provider().await));
+        engine.set_sub_dag_block_resolver(checkpoints.clone()).await;
+        engine.set_inclusion_proof_provider(checkpoints.clone()).await;
+        engine.set_state_diff_provider(checkpoints.clone()).await;
+        engine.set_block_provenance_provider(checkpoints).await;
+
+        // the server stops when the handle is dropped
+        let rpc_handle = engine.start_replica_rpc(&task_manager, shutdown.subscribe()).await?;
+        task_manager.update_tasks();
+        info!(target: "telcoin::node", "rpc replica started");
+        handle.set_running(None, rpc_handle.http_local_addr(), shutdown.clone());
+
+        let exit = task_manager.join_until_exit(shutdown).await;
+        handle.set_stopped();
+        match exit {
+            TaskManagerExit::Signal | TaskManagerExit::Shutdown => Ok(()),
+            TaskManagerExit::TaskExited(task) => Err(eyre::eyre!("replica task {task} exited")),
+        }
+    });
+    // Kick over the runtime- don't let errant tasks block the Drop.
+    runtime.shutdown_background();
+    res
+}
@@ -0,0 +1,82 @@
+//! Execution statistics for committed sub-dags.
+//!
+//! Every executed block records the consensus header it was built for in
+//! `parent_beacon_block_root`. The recorder adds each block's gas and transactions to the
+//! statistics for that consensus header so they can be served by `tn_getSubDagStats`.
+
+use futures::StreamExt as _;
+use reth_provider::CanonStateNotificationStream;
+use tn_rpc::{SubDagStatsProvider, TNRpcError, TelcoinNetworkRpcResult};
+use tn_storage::ConsensusStore as _;
+use tn_types::{Database, Noticer, SubDagStats, TaskManager};
+use tracing::{error, warn};
+
+/// Serves sub-dag statistics from the consensus DB.
+#[derive(Debug, Clone)]
+pub(crate) struct SubDagStatsReader<DB> {
+    /// The consensus DB.
+    db: DB,
+}
+
+impl<DB: Database> SubDagStatsReader<DB> {
+    /// Create a new instance of [Self].
+    pub(crate) fn new(db: DB) -> Self {
+        Self { db }
+    }
+}
+
+impl<DB: Database> SubDagStatsProvider for SubDagStatsReader<DB> {
+    fn sub_dag_stats(
+        &self,
+        start: u64,
+        end: u64,
+    ) -> TelcoinNetworkRpcResult<Vec<(u64, SubDagStats)>> {
+        self.db.read_sub_dag_stats(start, end).map_err(|e| TNRpcError::SubDagStats(e.to_string()))
+    }
+}
+
+/// Spawn a task that records sub-dag statistics as blocks are executed.
+pub(crate) fn spawn_sub_dag_stats_recorder<DB: Database>(
+    db: DB,
+    mut canon_state: CanonStateNotificationStream,
+    task_manager: &TaskManager,
+    rx_shutdown: Noticer,
+) {
+    task_manager.spawn_task("sub dag stats", async move {
+        loop {
+            tokio::select!(
+                _ = &rx_shutdown => break,
+                notification = canon_state.next() => {
+                    let Some(notification) = notification else {
+                        break;
+                    };
+                    for block in notification.committed().blocks_iter() {
+                        let Some(consensus_hash) = block.header.parent_beacon_block_root else {
+                            continue;
+                        };
+                        let transactions = block.body.transactions.len() as u64;
+                        match db.record_executed_block(
+                            &consensus_hash,
+                            block.header.number,
+                            block.header.gas_used,
+                            transactions,
+                        ) {
+                            Ok(Some(_)) => {}
+                            Ok(None) => warn!(
+                                target: "telcoin::node",
+                                ?consensus_hash,
+                                number = block.header.number,
+                                "executed block for unknown consensus header"
+                            ),
+                            Err(e) => error!(
+                                target: "telcoin::node",
+                                ?e,
+                                "failed to record sub dag stats"
+                            ),
+                        }
+                    }
+                }
+            )
+        }
+    });
+}
@@ -0,0 +1,390 @@
+//! Node status reporting for the `tnAdmin` RPC namespace.
+
+use crate::engine::ExecutionNode;
+use async_trait::async_trait;
+use consensus_metrics::channels::saturated_channels;
+use prometheus::proto::{MetricFamily, MetricType};
+use reth_db::{
+    database_metrics::{DatabaseMetadata, DatabaseMetrics},
+    Database,
+};
+use std::{collections::BTreeMap, future::Future, sync::Arc, time::Duration};
+use tn_network_libp2p::{error::NetworkError, types::NetworkResult, PeerId};
+use tn_node_traits::TelcoinNode;
+use tn_primary::{network::PrimaryNetworkHandle, ConsensusBus, VoteAggregation};
+use tn_rpc::{
+    ChannelBacklog, LogFilterHandle, MetricBucket, MetricFamilySnapshot, MetricKind,
+    MetricQuantile, MetricSample, NodeStatus, NodeStatusProvider, PeerConnectivity,
+    PeerHistoryEvent, TNRpcError, TelcoinNetworkRpcResult, ValidatorConnectivity,
+    VoteAggregationStatus, WorkerRpcEndpoint,
+};
+use tn_storage::{HaltTargetStore as _, PeerHistoryStore as _};
+use tn_types::{
+    now, now_ms, Database as TNDatabase, PeerEvent, PeerEventKind, PrimaryInfo, WorkerId, B256,
+};
+use tn_worker::WorkerNetworkHandle;
+use tracing::info;
+
+/// The amount of time to wait for a peer to accept a connection.
+const DIAL_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Collects the node's status from consensus, the networks, and the execution engine.
+pub(crate) struct NodeStatusReporter<DB, CDB>
+where
+    DB: Database + DatabaseMetrics + DatabaseMetadata + Clone + Unpin + 'static,
+{
+    /// The consensus bus for watching consensus and execution progress.
+    consensus_bus: ConsensusBus,
+    /// Handle to the primary network.
+    primary_network: PrimaryNetworkHandle,
+    /// Handle to the worker network.
+    worker_network: WorkerNetworkHandle,
+    /// The execution engine for retrieving worker RPC addresses.
+    engine: ExecutionNode<TelcoinNode<DB>>,
+    /// The ids of the workers running on this node.
+    worker_ids: Vec<WorkerId>,
+    /// How often idle workers send the primary a heartbeat.
+    worker_heartbeat_interval: Duration,
+    /// Handle to change the log filter, if logging supports it.
+    log_filter: Option<Arc<dyn LogFilterHandle>>,
+    /// The consensus DB with the networks' peer history.
+    consensus_db: CDB,
+}
+
+impl<DB, CDB> NodeStatusReporter<DB, CDB>
+where
+    DB: Database + DatabaseMetrics + DatabaseMetadata + Clone + Unpin + 'static,
+{
+    /// Create a new instance of [Self].
+    pub(crate) fn new(
+        consensus_bus: ConsensusBus,
+        primary_network: PrimaryNetworkHandle,
+        worker_network: WorkerNetworkHandle,
+        engine: ExecutionNode<TelcoinNode<DB>>,
+        worker_ids: Vec<WorkerId>,
+        worker_heartbeat_interval: Duration,
+        log_filter: Option<Arc<dyn LogFilterHandle>>,
+        consensus_db: CDB,
+    ) -> Self {
+        Self {
+            consensus_bus,
+            primary_network,
+            worker_network,
+            engine,
+            worker_ids,
+            worker_heartbeat_interval,
+            log_filter,
+            consensus_db,
+        }
+    }
+}
+
+#[async_trait]
+impl<DB, CDB> NodeStatusProvider for NodeStatusReporter<DB, CDB>
+where
+    DB: Database + DatabaseMetrics + DatabaseMetadata + Clone + Unpin + 'static,
+    CDB: TNDatabase,
+{
+    async fn node_status(&self) -> TelcoinNetworkRpcResult<NodeStatus> {
+        let node_mode = self.consensus_bus.node_mode().borrow().to_string();
+        let last_consensus_header = self.consensus_bus.last_consensus_header().borrow().clone();
+        let (last_published_number, _) =
+            *self.consensus_bus.last_published_consensus_num_hash().borrow();
+        let last_executed = self.consensus_bus.recent_blocks().borrow().latest_block_num_hash();
+        let restore = *self.consensus_bus.restore_progress().borrow();
+        let announced = self
+            .consensus_bus
+            .last_announced_block()
+            .borrow()
+            .as_ref()
+            .map(|(_, header)| header.num_hash());
+
+        let primary_peers = self
+            .primary_network
+            .connected_peers()
+            .await
+            .map_err(|e| TNRpcError::NodeStatus(e.to_string()))?
+            .len();
+        let worker_peers = self
+            .worker_network
+            .connected_peers()
+            .await
+            .map_err(|e| TNRpcError::NodeStatus(e.to_string()))?
+            .len();
+
+        let activity = self.consensus_bus.worker_activity().borrow().clone();
+        let mut worker_rpc_endpoints = Vec::with_capacity(self.worker_ids.len());
+        for worker_id in self.worker_ids.iter() {
+            // the worker's rpc may not be running yet
+            let http = self.engine.worker_http_local_address(worker_id).await.unwrap_or_default();
+            let down = activity.is_down(*worker_id, now(), self.worker_heartbeat_interval);
+            worker_rpc_endpoints.push(WorkerRpcEndpoint { worker_id: *worker_id, http, down });
+        }
+
+        Ok(NodeStatus {
+            node_mode,
+            sync_distance: last_published_number.saturating_sub(last_consensus_header.number),
+            primary_peers,
+            worker_peers,
+            last_sub_dag_index: last_consensus_header.number,
+            last_committed_round: last_consensus_header.sub_dag.leader_round(),
+            last_executed_block_number: last_executed.number,
+            last_executed_block_hash: last_executed.hash,
+            announced_block_number: announced.map(|block| block.number),
+            announced_block_hash: announced.map(|block| block.hash),
+            worker_rpc_endpoints,
+            halt_at_sub_dag: *self.consensus_bus.halt_at_sub_dag().borrow(),
+            restore_remaining_sub_dags: restore.map(|progress| progress.remaining),
+            restore_eta_secs: restore.and_then(|progress| progress.eta()).map(|eta| eta.as_secs()),
+        })
+    }
+
+    async fn check_connectivity(
+        &self,
+        primary_info: PrimaryInfo,
+    ) -> TelcoinNetworkRpcResult<ValidatorConnectivity> {
+        let to_rpc_err = |e: NetworkError| TNRpcError::NodeStatus(e.to_string());
+
+        let local = self.primary_network.local_peer_id().await.map_err(to_rpc_err)?;
+        let connected = self.primary_network.connected_peers().await.map_err(to_rpc_err)?;
+        let peer_id = primary_info.network_key.to_peer_id();
+        let dial = self.primary_network.dial(peer_id, primary_info.network_address.clone());
+        let primary = probe(local, &connected, peer_id, dial).await;
+
+        let local = self.worker_network.local_peer_id().await.map_err(to_rpc_err)?;
+        let connected = self.worker_network.connected_peers().await.map_err(to_rpc_err)?;
+        let mut workers = BTreeMap::new();
+        for (worker_id, worker_info) in primary_info.worker_index.0.iter() {
+            let peer_id = worker_info.name.to_peer_id();
+            let dial = self.worker_network.dial(peer_id, worker_info.worker_address.clone());
+            workers.insert(*worker_id, probe(local, &connected, peer_id, dial).await);
+        }
+
+        Ok(ValidatorConnectivity { primary, workers })
+    }
+
+    async fn halt_at(&self, sub_dag: u64) -> TelcoinNetworkRpcResult<()> {
+        // output up to the last recorded consensus header may already be executed
+        let reached = self.consensus_bus.last_consensus_header().borrow().number;
+        if sub_dag <= reached {
+            return Err(TNRpcError::InvalidHaltTarget { target: sub_dag, reached });
+        }
+
+        // saved so the target survives relaunches and restarts
+        self.consensus_db
+            .write_halt_target(sub_dag)
+            .map_err(|e| TNRpcError::NodeStatus(e.to_string()))?;
+        info!(target: "tn::admin", sub_dag, "node will halt after executing sub-dag");
+        self.consensus_bus.halt_at_sub_dag().send_replace(Some(sub_dag));
+        Ok(())
+    }
+
+    async fn set_log_filter(&self, filter: String) -> TelcoinNetworkRpcResult<()> {
+        let log_filter = self.log_filter.as_ref().ok_or_else(|| {
+            TNRpcError::LogFilterUnavailable("logging was not initialized by the node".to_string())
+        })?;
+        log_filter.set_log_filter(&filter)?;
+        info!(target: "tn::admin", %filter, "log filter updated");
+        Ok(())
+    }
+
+    async fn metrics_snapshot(
+        &self,
+        prefix: Option<String>,
+    ) -> TelcoinNetworkRpcResult<Vec<MetricFamilySnapshot>> {
+        let prefix = prefix.unwrap_or_default();
+        Ok(prometheus::default_registry()
+            .gather()
+            .iter()
+            .filter(|family| family.get_name().starts_with(&prefix))
+            .map(metric_family_snapshot)
+            .collect())
+    }
+
+    async fn channel_backlogs(
+        &self,
+        threshold: f64,
+    ) -> TelcoinNetworkRpcResult<Vec<ChannelBacklog>> {
+        Ok(saturated_channels(threshold)
+            .into_iter()
+            .map(|channel| ChannelBacklog {
+                utilization: channel.utilization(),
+                name: channel.name,
+                depth: channel.depth,
+                capacity: channel.capacity,
+                high_water_mark: channel.high_water,
+            })
+            .collect())
+    }
+
+    async fn peer_history(
+        &self,
+        window_secs: u64,
+    ) -> TelcoinNetworkRpcResult<Vec<PeerHistoryEvent>> {
+        let since_ms = now_ms().saturating_sub(window_secs.saturating_mul(1000));
+        let events = self
+            .consensus_db
+            .read_peer_events_since(since_ms)
+            .map_err(|e| TNRpcError::NodeStatus(e.to_string()))?;
+        Ok(events.into_iter().map(peer_history_event).collect())
+    }
+
+    async fn vote_aggregation(&self) -> TelcoinNetworkRpcResult<Option<VoteAggregationStatus>> {
+        Ok(self.consensus_bus.vote_aggregation().borrow().as_ref().map(vote_aggregation_status))
+    }
+}
+
+/// Convert the certifier's vote aggregation for the admin RPC.
+fn vote_aggregation_status(aggregation: &VoteAggregation) -> VoteAggregationStatus {
+    VoteAggregationStatus {
+        round: aggregation.round,
+        header_digest: B256::from(aggregation.header_digest.0),
+        voters: aggregation.voters.iter().map(ToString::to_string).collect(),
+        missing: aggregation.missing.iter().map(ToString::to_string).collect(),
+        stake: aggregation.stake,
+        quorum_threshold: aggregation.quorum_threshold,
+        certified: aggregation.certified(),
+        elapsed_ms: aggregation.elapsed().as_millis() as u64,
+    }
+}
+
+/// Convert a recorded peer event for the admin RPC.
+fn peer_history_event(event: PeerEvent) -> PeerHistoryEvent {
+    let (kind, reason) = match event.kind {
+        PeerEventKind::Connected => ("connected", None),
+        PeerEventKind::Disconnected(cause) => ("disconnected", cause),
+        PeerEventKind::DialFailed(error) => ("dialFailed", Some(error)),
+    };
+    PeerHistoryEvent {
+        timestamp_ms: event.timestamp_ms,
+        network: event.network.as_str().to_string(),
+        peer_id: event.peer.to_string(),
+        event: kind.to_string(),
+        reason,
+    }
+}
+
+/// Convert a gathered Prometheus metric family for the admin RPC.
+fn metric_family_snapshot(family: &MetricFamily) -> MetricFamilySnapshot {
+    let kind = match family.get_field_type() {
+        MetricType::COUNTER => MetricKind::Counter,
+        MetricType::GAUGE => MetricKind::Gauge,
+        MetricType::HISTOGRAM => MetricKind::Histogram,
+        MetricType::SUMMARY => MetricKind::Summary,
+        MetricType::UNTYPED => MetricKind::Untyped,
+    };
+
+    let metrics = family
+        .get_metric()
+        .iter()
+        .map(|metric| {
+            let labels = metric
+                .get_label()
+                .iter()
+                .map(|label| (label.get_name().to_string(), label.get_value().to_string()))
+                .collect();
+            let mut sample = MetricSample { labels, ..Default::default() };
+            match kind {
+                MetricKind::Counter => sample.value = Some(metric.get_counter().get_value()),
+                MetricKind::Gauge => sample.value = Some(metric.get_gauge().get_value()),
+                MetricKind::Untyped => sample.value = Some(metric.get_untyped().get_value()),
+                MetricKind::Histogram => {
+                    let histogram = metric.get_histogram();
+                    sample.sample_count = Some(histogram.get_sample_count());
+                    sample.sample_sum = Some(histogram.get_sample_sum());
+                    sample.buckets = histogram
+                        .get_bucket()
+                        .iter()
+                        .map(|bucket| MetricBucket {
+                            upper_bound: bucket.get_upper_bound(),
+                            cumulative_count: bucket.get_cumulative_count(),
+                        })
+                        .collect();
+                }
+                MetricKind::Summary => {
+                    let summary = metric.get_summary();
+                    sample.sample_count = Some(summary.get_sample_count());
+                    sample.sample_sum = Some(summary.get_sample_sum());
+                    sample.quantiles = summary
+                        .get_quantile()
+                        .iter()
+                        .map(|quantile| MetricQuantile {
+                            quantile: quantile.get_quantile(),
+                            value: quantile.get_value(),
+                        })
+                        .collect();
+                }
+            }
+            sample
+        })
+        .collect();
+
+    MetricFamilySnapshot {
+        name: family.get_name().to_string(),
+        help: family.get_help().to_string(),
+        kind,
+        metrics,
+    }
+}
+
+/// Dial a peer unless it is this node or already connected.
+async fn probe(
+    local: PeerId,
+    connected: &[PeerId],
+    peer_id: PeerId,
+    dial: impl Future<Output = NetworkResult<()>>,
+) -> PeerConnectivity {
+    if peer_id == local || connected.contains(&peer_id) {
+        return PeerConnectivity::reachable();
+    }
+
+    match tokio::time::timeout(DIAL_TIMEOUT, dial).await {
+        Ok(Ok(())) => PeerConnectivity::reachable(),
+        Ok(Err(e)) => PeerConnectivity::unreachable(e),
+        Err(_) => PeerConnectivity::unreachable("dial timed out"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::metric_family_snapshot;
+    use prometheus::{HistogramOpts, HistogramVec, IntCounter, Registry};
+    use std::collections::BTreeMap;
+    use tn_rpc::{MetricBucket, MetricKind};
+
+    #[test]
+    fn test_metric_family_snapshot() {
+        let registry = Registry::new();
+        let counter = IntCounter::new("batches_sealed", "The number of sealed batches")
+            .expect("valid counter");
+        registry.register(Box::new(counter.clone())).expect("registered");
+        let histogram = HistogramVec::new(
+            HistogramOpts::new("seal_latency", "The time to seal").buckets(vec![0.5, 1.0]),
+            &["worker"],
+        )
+        .expect("valid histogram");
+        registry.register(Box::new(histogram.clone())).expect("registered");
+
+        counter.inc_by(3);
+        histogram.with_label_values(&["0"]).observe(0.75);
+
+        // families are gathered in name order
+        let snapshot: Vec<_> = registry.gather().iter().map(metric_family_snapshot).collect();
+        assert_eq!(snapshot[0].name, "batches_sealed");
+        assert_eq!(snapshot[0].kind, MetricKind::Counter);
+        assert_eq!(snapshot[0].metrics[0].value, Some(3.0));
+
+        let latency = &snapshot[1].metrics[0];
+        assert_eq!(snapshot[1].kind, MetricKind::Histogram);
+        assert_eq!(latency.labels, BTreeMap::from([("worker".to_string(), "0".to_string())]));
+        assert_eq!(latency.sample_count, Some(1));
+        assert_eq!(latency.value, None);
+        assert_eq!(
+            latency.buckets,
+            vec![
+                MetricBucket { upper_bound: 0.5, cumulative_count: 0 },
+                MetricBucket { upper_bound: 1.0, cumulative_count: 1 },
+            ]
+        );
+    }
+}
@@ -0,0 +1,625 @@
+//! Publish executed blocks to Kafka or NATS.
+//!
+//! Nodes built with the `kafka` or `nats` feature publish every executed block to the topics of
+//! the configured [StreamConfig]:
+//! - `<prefix>.blocks`: the block header and transaction hashes, see [StreamBlock]
+//! - `<prefix>.receipts`: the receipts of the block's transactions, see [StreamReceipts]
+//! - `<prefix>.consensus`: the consensus output the block was executed for, see [StreamConsensus]
+//!
+//! Messages are JSON and carry the block number. Every Kafka message of a topic has the same key,
+//! so the topic stays in one partition and consumers read blocks in order.
+//!
+//! Delivery is at least once. The last block the broker acknowledged is saved in the consensus DB
+//! and publishing resumes with the next block after a restart or a broker outage, so consumers
+//! should deduplicate by block hash. Blocks are read from the execution DB, so blocks executed
+//! while the broker was down are published once it recovers.
+
+use async_trait::async_trait;
+use eyre::eyre;
+use futures::StreamExt as _;
+use reth_provider::{
+    BlockNumReader, BlockReader, CanonStateNotificationStream, TransactionVariant,
+};
+use serde::Serialize;
+use tn_config::StreamConfig;
+use tn_storage::{
+    tables::{ConsensusBlockNumbersByDigest, ConsensusBlocks},
+    StreamOffsetStore as _,
+};
+use tn_types::{
+    Address, Block, BlockHash, Bytes, Database, Noticer, Receipt, SealedBlockWithSenders,
+    TaskManager, TxHash, B256,
+};
+use tracing::{info, warn};
+
+/// An executed block.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct StreamBlock {
+    /// The block number.
+    pub number: u64,
+    /// The block hash.
+    pub hash: BlockHash,
+    /// The hash of the parent block.
+    pub parent_hash: BlockHash,
+    /// The block timestamp in seconds.
+    pub timestamp: u64,
+    /// The beneficiary for block rewards.
+    pub beneficiary: Address,
+    /// The gas used by the block's transactions.
+    pub gas_used: u64,
+    /// The block's gas limit.
+    pub gas_limit: u64,
+    /// The base fee per gas, if any.
+    pub base_fee_per_gas: Option<u64>,
+    /// The hashes of the block's transactions in execution order.
+    pub transactions: Vec<TxHash>,
+}
+
+impl StreamBlock {
+    /// Create a new instance of [Self].
+    pub(crate) fn new(block: &SealedBlockWithSenders) -> Self {
+        Self {
+            number: block.header.number,
+            hash: block.hash(),
+            parent_hash: block.header.parent_hash,
+            timestamp: block.header.timestamp,
+            beneficiary: block.header.beneficiary,
+            gas_used: block.header.gas_used,
+            gas_limit: block.header.gas_limit,
+            base_fee_per_gas: block.header.base_fee_per_gas,
+            transactions: block.body.transactions.iter().map(|tx| tx.hash()).collect(),
+        }
+    }
+}
+
+/// The receipts of an executed block.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct StreamReceipts {
+    /// The block number.
+    pub block_number: u64,
+    /// The block hash.
+    pub block_hash: BlockHash,
+    /// The receipts in execution order.
+    pub receipts: Vec<StreamReceipt>,
+}
+
+/// The receipt of an executed transaction.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct StreamReceipt {
+    /// The transaction hash.
+    pub transaction_hash: TxHash,
+    /// The index of the transaction in the block.
+    pub transaction_index: u32,
+    /// True if the transaction succeeded.
+    pub success: bool,
+    /// The gas used by the block up to and including this transaction.
+    pub cumulative_gas_used: u64,
+    /// The logs emitted by the transaction.
+    pub logs: Vec<StreamLog>,
+}
+
+/// A log emitted by a transaction.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct StreamLog {
+    /// The contract that emitted the log.
+    pub address: Address,
+    /// The log topics.
+    pub topics: Vec<B256>,
+    /// The log data.
+    pub data: Bytes,
+}
+
+impl StreamReceipts {
+    /// Create a new instance of [Self].
+    pub(crate) fn new(block: &SealedBlockWithSenders, receipts: &[Receipt]) -> Self {
+        let receipts = block
+            .body
+            .transactions
+            .iter()
+            .zip(receipts)
+            .enumerate()
+            .map(|(index, (tx, receipt))| StreamReceipt {
+                transaction_hash: tx.hash(),
+                transaction_index: index as u32,
+                success: receipt.success,
+                cumulative_gas_used: receipt.cumulative_gas_used,
+                logs: receipt
+                    .logs
+                    .iter()
+                    .map(|log| StreamLog {
+                        address: log.address,
+                        topics: log.data.topics().to_vec(),
+                        data: log.data.data.clone(),
+                    })
+                    .collect(),
+            })
+            .collect();
+        Self { block_number: block.header.number, block_hash: block.hash(), receipts }
+    }
+}
+
+/// The consensus output a block was executed for.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct StreamConsensus {
+    /// The block number.
+    pub block_number: u64,
+    /// The block hash.
+    pub block_hash: BlockHash,
+    /// The number of the consensus header.
+    pub consensus_number: u64,
+    /// The hash of the consensus header.
+    pub consensus_hash: B256,
+    /// The epoch of the committed sub dag.
+    pub epoch: u32,
+    /// The round of the committed sub dag's leader.
+    pub round: u32,
+    /// The authority that led the round.
+    pub leader: String,
+    /// The commit timestamp in seconds.
+    pub committed_at: u64,
+}
+
+/// A streaming platform that acknowledges published messages.
+#[async_trait]
+pub(crate) trait StreamSink: Send + Sync + 'static {
+    /// Publish the message for the block and wait for the broker to acknowledge it.
+    ///
+    /// Messages are published in block order and must stay in that order within the topic.
+    async fn publish(&self, topic: &str, block_number: u64, payload: Vec<u8>) -> eyre::Result<()>;
+}
+
+/// The executed blocks a [BlockStreamer] publishes.
+pub(crate) trait ExecutedBlocks: Send + Sync + 'static {
+    /// The number of the last executed block.
+    fn last_block_number(&self) -> eyre::Result<u64>;
+
+    /// The executed block and its receipts, `None` if the block is unknown.
+    fn executed_block(
+        &self,
+        number: u64,
+    ) -> eyre::Result<Option<(SealedBlockWithSenders, Vec<Receipt>)>>;
+}
+
+impl<P> ExecutedBlocks for P
+where
+    P: BlockNumReader + BlockReader<Block = Block, Receipt = Receipt> + Send + Sync + 'static,
+{
+    fn last_block_number(&self) -> eyre::Result<u64> {
+        Ok(BlockNumReader::last_block_number(self)?)
+    }
+
+    fn executed_block(
+        &self,
+        number: u64,
+    ) -> eyre::Result<Option<(SealedBlockWithSenders, Vec<Receipt>)>> {
+        let Some(block) =
+            self.sealed_block_with_senders(number.into(), TransactionVariant::WithHash)?
+        else {
+            return Ok(None);
+        };
+        let receipts = self.receipts_by_block(number.into())?.unwrap_or_default();
+        Ok(Some((block, receipts)))
+    }
+}
+
+/// Publishes to Kafka with an idempotent producer.
+#[cfg(feature = "kafka")]
+struct KafkaSink {
+    /// The producer.
+    producer: rdkafka::producer::FutureProducer,
+    /// The time to wait for the message to be queued.
+    timeout: std::time::Duration,
+}
+
+#[cfg(feature = "kafka")]
+impl KafkaSink {
+    /// Create a producer for the brokers.
+    ///
+    /// The producer waits for every in-sync replica and retries without duplicates. The
+    /// properties are applied last and override these defaults.
+    fn new(
+        brokers: &str,
+        properties: &std::collections::BTreeMap<String, String>,
+        timeout: std::time::Duration,
+    ) -> eyre::Result<Self> {
+        let mut config = rdkafka::ClientConfig::new();
+        config
+            .set("bootstrap.servers", brokers)
+            .set("enable.idempotence", "true")
+            .set("acks", "all")
+            .set("message.timeout.ms", timeout.as_millis().to_string());
+        for (key, value) in properties {
+            config.set(key, value);
+        }
+        Ok(Self { producer: config.create()?, timeout })
+    }
+}
+
+#[cfg(feature = "kafka")]
+#[async_trait]
+impl StreamSink for KafkaSink {
+    /// Messages are keyed by topic, so every message of a topic goes to the same partition. The
+    /// block number is in the `block-number` header.
+    async fn publish(&self, topic: &str, block_number: u64, payload: Vec<u8>) -> eyre::Result<()> {
+        use rdkafka::message::{Header, OwnedHeaders};
+
+        let block_number = block_number.to_string();
+        let headers = OwnedHeaders::new()
+            .insert(Header { key: "block-number", value: Some(block_number.as_str()) });
+        let record = rdkafka::producer::FutureRecord::to(topic)
+            .key(topic)
+            .headers(headers)
+            .payload(&payload);
+        self.producer.send(record, self.timeout).await.map_err(|(e, _)| e)?;
+        Ok(())
+    }
+}
+
+/// Publishes to NATS JetStream.
+#[cfg(feature = "nats")]
+struct NatsSink {
+    /// The JetStream context.
+    jetstream: async_nats::jetstream::Context,
+}
+
+#[cfg(feature = "nats")]
+impl NatsSink {
+    /// Connect to the server.
+    ///
+    /// The client keeps reconnecting in the background if the server is down.
+    async fn connect(
+        url: &str,
+        credentials_file: Option<&std::path::Path>,
+        timeout: std::time::Duration,
+    ) -> eyre::Result<Self> {
+        let mut options = async_nats::ConnectOptions::new().retry_on_initial_connect();
+        if let Some(path) = credentials_file {
+            options = options.credentials_file(path).await?;
+        }
+        let client = options.connect(url).await?;
+        let mut jetstream = async_nats::jetstream::new(client);
+        jetstream.set_timeout(timeout);
+        Ok(Self { jetstream })
+    }
+}
+
+#[cfg(feature = "nats")]
+#[async_trait]
+impl StreamSink for NatsSink {
+    /// Messages use `<topic>.<block number>` as the JetStream message id, so the server drops
+    /// duplicates published within the stream's duplicate window.
+    async fn publish(&self, topic: &str, block_number: u64, payload: Vec<u8>) -> eyre::Result<()> {
+        let publish = async_nats::jetstream::context::Publish::build()
+            .payload(payload.into())
+            .message_id(format!("{topic}.{block_number}"));
+        self.jetstream.send_publish(topic.to_string(), publish).await?.await?;
+        Ok(())
+    }
+}
+
+/// Connect to the configured sink.
+///
+/// Fails if the node was built without the sink's feature.
+pub(crate) async fn connect_sink(config: &StreamConfig) -> eyre::Result<Box<dyn StreamSink>> {
+    #[cfg(feature = "kafka")]
+    if let tn_config::StreamSinkConfig::Kafka { brokers, properties } = &config.sink {
+        return Ok(Box::new(KafkaSink::new(brokers, properties, config.publish_timeout)?));
+    }
+    #[cfg(feature = "nats")]
+    if let tn_config::StreamSinkConfig::Nats { url, credentials_file } = &config.sink {
+        let sink =
+            NatsSink::connect(url, credentials_file.as_deref(), config.publish_timeout).await?;
+        return Ok(Box::new(sink));
+    }
+    Err(eyre!("streaming requires a node built with the `{}` feature", config.sink.feature()))
+}
+
+/// Publishes executed blocks to the configured sink.
+pub(crate) struct BlockStreamer<DB, P> {
+    /// The consensus DB with the stream's offset.
+    db: DB,
+    /// The execution provider.
+    provider: P,
+    /// The streaming platform.
+    sink: Box<dyn StreamSink>,
+    /// The stream configuration.
+    config: StreamConfig,
+}
+
+impl<DB, P> std::fmt::Debug for BlockStreamer<DB, P> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlockStreamer").field("config", &self.config).finish_non_exhaustive()
+    }
+}
+
+impl<DB, P> BlockStreamer<DB, P>
+where
+    DB: Database,
+    P: ExecutedBlocks,
+{
+    /// Create a new instance of [Self].
+    pub(crate) fn new(
+        db: DB,
+        provider: P,
+        sink: Box<dyn StreamSink>,
+        config: StreamConfig,
+    ) -> Self {
+        Self { db, provider, sink, config }
+    }
+
+    /// The next block to publish.
+    fn next_block(&self) -> eyre::Result<u64> {
+        if let Some(last) = self.db.read_stream_offset(&self.config.topic_prefix)? {
+            return Ok(last + 1);
+        }
+        match self.config.start_block {
+            Some(start) => Ok(start),
+            None => Ok(self.provider.last_block_number()? + 1),
+        }
+    }
+
+    /// Publish every executed block from `next`, saving the offset after each block.
+    async fn publish_to_tip(&self, next: &mut u64) -> eyre::Result<()> {
+        let tip = self.provider.last_block_number()?;
+        while *next <= tip {
+            self.publish_block(*next).await?;
+            self.db.write_stream_offset(&self.config.topic_prefix, *next)?;
+            *next += 1;
+        }
+        Ok(())
+    }
+
+    /// Publish the block, its receipts and its consensus output.
+    async fn publish_block(&self, number: u64) -> eyre::Result<()> {
+        let (block, receipts) = self
+            .provider
+            .executed_block(number)?
+            .ok_or_else(|| eyre!("executed block {number} not found"))?;
+
+        let message = serde_json::to_vec(&StreamBlock::new(&block))?;
+        self.sink.publish(&self.config.topic("blocks"), number, message).await?;
+        let message = serde_json::to_vec(&StreamReceipts::new(&block, &receipts))?;
+        self.sink.publish(&self.config.topic("receipts"), number, message).await?;
+        if let Some(consensus) = self.consensus(&block)? {
+            let message = serde_json::to_vec(&consensus)?;
+            self.sink.publish(&self.config.topic("consensus"), number, message).await?;
+        }
+        Ok(())
+    }
+
+    /// The consensus output the block was executed for.
+    ///
+    /// Returns `None` for blocks that were not built from consensus output, ie - genesis.
+    fn consensus(&self, block: &SealedBlockWithSenders) -> eyre::Result<Option<StreamConsensus>> {
+        let Some(consensus_hash) = block.header.parent_beacon_block_root else {
+            return Ok(None);
+        };
+        let Some(consensus_number) =
+            self.db.get::<ConsensusBlockNumbersByDigest>(&consensus_hash)?
+        else {
+            return Ok(None);
+        };
+        let Some(header) = self.db.get::<ConsensusBlocks>(&consensus_number)? else {
+            return Ok(None);
+        };
+        Ok(Some(StreamConsensus {
+            block_number: block.header.number,
+            block_hash: block.hash(),
+            consensus_number,
+            consensus_hash,
+            epoch: header.sub_dag.leader.epoch(),
+            round: header.sub_dag.leader.round(),
+            leader: header.sub_dag.leader.origin().to_string(),
+            committed_at: header.sub_dag.commit_timestamp(),
+        }))
+    }
+
+    /// Spawn a task that publishes blocks as they are executed.
+    ///
+    /// Failures are retried after `retry_interval` starting with the first unacknowledged block.
+    pub(crate) fn spawn(
+        self,
+        mut canon_state: CanonStateNotificationStream,
+        task_manager: &TaskManager,
+        rx_shutdown: Noticer,
+    ) -> eyre::Result<()> {
+        let mut next = self.next_block()?;
+        info!(
+            target: "telcoin::node",
+            topic_prefix = self.config.topic_prefix,
+            next,
+            "streaming executed blocks"
+        );
+        task_manager.spawn_task("block stream", async move {
+            loop {
+                let res = tokio::select!(
+                    _ = &rx_shutdown => break,
+                    res = self.publish_to_tip(&mut next) => res,
+                );
+                if let Err(e) = res {
+                    warn!(target: "telcoin::node", ?e, block = next, "failed to stream block");
+                    tokio::select!(
+                        _ = &rx_shutdown => break,
+                        _ = tokio::time::sleep(self.config.retry_interval) => continue,
+                    );
+                }
+                tokio::select!(
+                    _ = &rx_shutdown => break,
+                    notification = canon_state.next() => {
+                        if notification.is_none() {
+                            break;
+                        }
+                    }
+                );
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlockStreamer, ExecutedBlocks, StreamSink};
+    use async_trait::async_trait;
+    use eyre::eyre;
+    use parking_lot::Mutex;
+    use std::{sync::Arc, time::Duration};
+    use tn_config::{StreamConfig, StreamSinkConfig};
+    use tn_storage::{mem_db::MemDatabase, StreamOffsetStore as _};
+    use tn_types::{Block, BlockExt as _, ExecHeader, Receipt, SealedBlockWithSenders};
+
+    /// Executed blocks `0..=tip`.
+    struct MemoryBlocks {
+        tip: u64,
+    }
+
+    impl ExecutedBlocks for MemoryBlocks {
+        fn last_block_number(&self) -> eyre::Result<u64> {
+            Ok(self.tip)
+        }
+
+        fn executed_block(
+            &self,
+            number: u64,
+        ) -> eyre::Result<Option<(SealedBlockWithSenders, Vec<Receipt>)>> {
+            if number > self.tip {
+                return Ok(None);
+            }
+            let header = ExecHeader { number, ..Default::default() };
+            let block = Block { header, body: Default::default() }.seal_slow();
+            Ok(SealedBlockWithSenders::new(block, vec![]).map(|block| (block, vec![])))
+        }
+    }
+
+    /// Records the topic and block of each published message.
+    #[derive(Debug, Clone, Default)]
+    struct MemorySink {
+        /// The acknowledged messages in publish order.
+        published: Arc<Mutex<Vec<(String, u64)>>>,
+        /// The publish that fails, counted from the first.
+        fail_at: Arc<Mutex<Option<usize>>>,
+        /// The publishes attempted.
+        attempts: Arc<Mutex<usize>>,
+    }
+
+    impl MemorySink {
+        /// The blocks acknowledged on the topic.
+        fn blocks(&self, topic: &str) -> Vec<u64> {
+            self.published
+                .lock()
+                .iter()
+                .filter(|(published, _)| published == topic)
+                .map(|(_, block)| *block)
+                .collect()
+        }
+    }
+
+    #[async_trait]
+    impl StreamSink for MemorySink {
+        async fn publish(
+            &self,
+            topic: &str,
+            block_number: u64,
+            _payload: Vec<u8>,
+        ) -> eyre::Result<()> {
+            let attempt = {
+                let mut attempts = self.attempts.lock();
+                *attempts += 1;
+                *attempts - 1
+            };
+            if *self.fail_at.lock() == Some(attempt) {
+                return Err(eyre!("broker unavailable"));
+            }
+            self.published.lock().push((topic.to_string(), block_number));
+            Ok(())
+        }
+    }
+
+    fn config(start_block: Option<u64>) -> StreamConfig {
+        StreamConfig {
+            sink: StreamSinkConfig::Nats {
+                url: "nats://localhost:4222".to_string(),
+                credentials_file: None,
+            },
+            topic_prefix: "telcoin".to_string(),
+            start_block,
+            publish_timeout: Duration::from_secs(1),
+            retry_interval: Duration::from_millis(10),
+        }
+    }
+
+    fn block_streamer(
+        db: &MemDatabase,
+        tip: u64,
+        sink: &MemorySink,
+        start_block: Option<u64>,
+    ) -> BlockStreamer<MemDatabase, MemoryBlocks> {
+        BlockStreamer::new(
+            db.clone(),
+            MemoryBlocks { tip },
+            Box::new(sink.clone()),
+            config(start_block),
+        )
+    }
+
+    #[test]
+    fn test_stream_start_block() {
+        let db = MemDatabase::default();
+        let sink = MemorySink::default();
+
+        // new streams start after the tip unless a start block is configured
+        assert_eq!(block_streamer(&db, 5, &sink, None).next_block().unwrap(), 6);
+        assert_eq!(block_streamer(&db, 5, &sink, Some(2)).next_block().unwrap(), 2);
+        assert_eq!(block_streamer(&db, 5, &sink, Some(0)).next_block().unwrap(), 0);
+
+        // the saved offset takes precedence over the start block
+        db.write_stream_offset("telcoin", 3).unwrap();
+        assert_eq!(block_streamer(&db, 5, &sink, Some(1)).next_block().unwrap(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_stream_resumes_from_saved_offset() {
+        let db = MemDatabase::default();
+        let sink = MemorySink::default();
+        let streamer = block_streamer(&db, 3, &sink, Some(0));
+        let mut next = streamer.next_block().unwrap();
+        streamer.publish_to_tip(&mut next).await.unwrap();
+        assert_eq!(sink.blocks("telcoin.blocks"), vec![0, 1, 2, 3]);
+        assert_eq!(db.read_stream_offset("telcoin").unwrap(), Some(3));
+
+        // after a restart publishing continues with the block after the offset
+        let restarted = MemorySink::default();
+        let streamer = block_streamer(&db, 5, &restarted, Some(0));
+        let mut next = streamer.next_block().unwrap();
+        assert_eq!(next, 4);
+        streamer.publish_to_tip(&mut next).await.unwrap();
+        assert_eq!(restarted.blocks("telcoin.blocks"), vec![4, 5]);
+        assert_eq!(restarted.blocks("telcoin.receipts"), vec![4, 5]);
+        assert_eq!(db.read_stream_offset("telcoin").unwrap(), Some(5));
+    }
+
+    #[tokio::test]
+    async fn test_stream_retries_failed_publish() {
+        let db = MemDatabase::default();
+        let sink = MemorySink::default();
+        // block 1's receipts are not acknowledged
+        *sink.fail_at.lock() = Some(3);
+        let streamer = block_streamer(&db, 2, &sink, Some(0));
+        let mut next = streamer.next_block().unwrap();
+        assert!(streamer.publish_to_tip(&mut next).await.is_err());
+        assert_eq!(next, 1);
+        assert_eq!(db.read_stream_offset("telcoin").unwrap(), Some(0));
+
+        // the retry starts over with the unacknowledged block, so block 1 is published twice
+        streamer.publish_to_tip(&mut next).await.unwrap();
+        assert_eq!(next, 3);
+        assert_eq!(sink.blocks("telcoin.blocks"), vec![0, 1, 1, 2]);
+        assert_eq!(sink.blocks("telcoin.receipts"), vec![0, 1, 2]);
+        assert_eq!(db.read_stream_offset("telcoin").unwrap(), Some(2));
+    }
+}
@@ -0,0 +1,65 @@
+//! Sync status for the node mode aware RPC.
+
+use tn_primary::ConsensusBus;
+use tn_rpc::{NodeSyncStatus, SyncStatusProvider, TNRpcError, TelcoinNetworkRpcResult};
+use tn_storage::tables::ConsensusBlockNumbersByDigest;
+use tn_types::Database;
+
+/// Reports how far the node's execution is behind consensus.
+#[derive(Debug, Clone)]
+pub(crate) struct SyncStatusReporter<DB> {
+    /// The consensus bus for watching consensus and execution progress.
+    consensus_bus: ConsensusBus,
+    /// The consensus DB.
+    db: DB,
+    /// The last executed consensus header when the node started.
+    starting_consensus_number: u64,
+}
+
+impl<DB: Database> SyncStatusReporter<DB> {
+    /// Create a new instance of [Self].
+    ///
+    /// The consensus bus must be primed with the last executed blocks.
+    pub(crate) fn new(consensus_bus: ConsensusBus, db: DB) -> eyre::Result<Self> {
+        let mut reporter = Self { consensus_bus, db, starting_consensus_number: 0 };
+        reporter.starting_consensus_number = reporter.executed_consensus_number()?;
+        Ok(reporter)
+    }
+
+    /// The number of the consensus header the last executed block was built for.
+    ///
+    /// Returns zero if no consensus output has been executed.
+    fn executed_consensus_number(&self) -> eyre::Result<u64> {
+        let latest = self.consensus_bus.recent_blocks().borrow().latest_block();
+        let Some(digest) = latest.parent_beacon_block_root else {
+            return Ok(0);
+        };
+        Ok(self.db.get::<ConsensusBlockNumbersByDigest>(&digest)?.unwrap_or_default())
+    }
+}
+
+impl<DB: Database> SyncStatusProvider for SyncStatusReporter<DB> {
+    fn sync_status(&self) -> TelcoinNetworkRpcResult<NodeSyncStatus> {
+        let node_mode = *self.consensus_bus.node_mode().borrow();
+        let executed_consensus_number =
+            self.executed_consensus_number().map_err(|e| TNRpcError::SyncStatus(e.to_string()))?;
+        let (last_published_number, _) =
+            *self.consensus_bus.last_published_consensus_num_hash().borrow();
+        let highest_consensus_number = last_published_number
+            .max(self.consensus_bus.last_consensus_header().borrow().number)
+            .max(executed_consensus_number);
+        let executed_block_number =
+            self.consensus_bus.recent_blocks().borrow().latest_block_num_hash().number;
+
+        Ok(NodeSyncStatus {
+            node_mode: node_mode.to_string(),
+            // inactive CVVs hold transactions until they rejoin consensus
+            accepts_transactions: node_mode.is_cvv(),
+            follows_consensus: !node_mode.is_active_cvv(),
+            starting_consensus_number: self.starting_consensus_number,
+            executed_consensus_number,
+            highest_consensus_number,
+            executed_block_number,
+        })
+    }
+}
@@ -1,7 +1,9 @@
 //! Hierarchical type to hold tasks spawned for a worker in the network.
 use std::sync::Arc;
 use tn_config::ConsensusConfig;
-use tn_types::{BatchValidation, Database as ConsensusDatabase, WorkerId};
+use tn_types::{
+    BatchValidation, Database as ConsensusDatabase, InclusionPromises, TaskManager, WorkerId,
+};
 use tn_worker::{
     metrics::Metrics, new_worker, quorum_waiter::QuorumWaiter, Worker, WorkerNetworkHandle,
 };
@@ -23,11 +25,20 @@ impl<CDB: ConsensusDatabase> WorkerNodeInner<CDB> {
         &mut self,
         validator: Arc<dyn BatchValidation>,
         network_handle: WorkerNetworkHandle,
+        inclusion_promises: Option<InclusionPromises>,
+        task_manager: &TaskManager,
     ) -> eyre::Result<Worker<CDB, QuorumWaiter>> {
-        let metrics = Metrics::default();
+        let metrics = Metrics::new_for_worker(self.id);
 
-        let batch_provider =
-            new_worker(self.id, validator, metrics, self.consensus_config.clone(), network_handle);
+        let batch_provider = new_worker(
+            self.id,
+            validator,
+            metrics,
+            self.consensus_config.clone(),
+            network_handle,
+            inclusion_promises,
+            task_manager,
+        );
 
         Ok(batch_provider)
     }
@@ -49,8 +60,10 @@ impl<CDB: ConsensusDatabase> WorkerNode<CDB> {
         &self,
         validator: Arc<dyn BatchValidation>,
         network_handle: WorkerNetworkHandle,
+        inclusion_promises: Option<InclusionPromises>,
+        task_manager: &TaskManager,
     ) -> eyre::Result<Worker<CDB, QuorumWaiter>> {
         let mut guard = self.internal.write().await;
-        guard.start(validator, network_handle).await
+        guard.start(validator, network_handle, inclusion_promises, task_manager).await
     }
 }
@@ -11,7 +11,10 @@ use tn_network_libp2p::PeerId;
 use tn_primary::{
     consensus::ConsensusRound, network::PrimaryNetworkHandle, ConsensusBus, NodeMode,
 };
-use tn_storage::tables::{Batches, ConsensusBlockNumbersByDigest, ConsensusBlocks};
+use tn_storage::{
+    insert_batch, reference_batches,
+    tables::{ConsensusBlockNumbersByDigest, ConsensusBlocks},
+};
 use tn_types::{ConsensusHeader, ConsensusOutput, Database, DbTxMut, TaskManagerClone, TnSender};
 use tracing::info;
 
@@ -113,12 +116,25 @@ pub fn save_consensus<DB: Database>(
     match db.write_txn() {
         Ok(mut txn) => {
             for batch in consensus_output.batches.iter().flatten() {
-                if let Err(e) = txn.insert::<Batches>(&batch.digest(), batch) {
+                if let Err(e) = insert_batch(&mut txn, &batch.digest(), batch) {
                     tracing::error!(target: "telcoin::state-sync", ?e, "error saving a batch to persistant storage!");
                     return Err(e);
                 }
             }
             let header: ConsensusHeader = consensus_output.into();
+            // count each header's references once when output is replayed after a restart
+            let saved = txn.contains_key::<ConsensusBlocks>(&header.number)?;
+            if !saved {
+                let digests = header
+                    .sub_dag
+                    .certificates
+                    .iter()
+                    .flat_map(|certificate| certificate.header().payload().keys());
+                if let Err(e) = reference_batches(&mut txn, digests) {
+                    tracing::error!(target: "telcoin::state-sync", ?e, "error saving batch references to persistant storage!");
+                    return Err(e);
+                }
+            }
             if let Err(e) = txn.insert::<ConsensusBlocks>(&header.number, &header) {
                 tracing::error!(target: "telcoin::state-sync", ?e, "error saving a consensus header to persistant storage!");
                 return Err(e);
@@ -403,8 +419,12 @@ async fn catch_up_consensus_from_to<DB: Database>(
             }
         };
         let parent_hash = last_parent;
-        last_parent =
-            ConsensusHeader::digest_from_parts(parent_hash, &consensus_header.sub_dag, number);
+        last_parent = ConsensusHeader::digest_from_parts(
+            parent_hash,
+            &consensus_header.sub_dag,
+            number,
+            &consensus_header.withdrawals,
+        );
         if last_parent != consensus_header.digest() {
             tracing::error!(target: "telcoin::state-sync", "consensus header digest mismatch!");
             return Err(eyre::eyre!("consensus header digest mismatch!"));
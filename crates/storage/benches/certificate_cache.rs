@@ -0,0 +1,69 @@
+//! Benchmark certificate reads through the certificate store cache.
+//!
+//! Consensus mostly reads the certificates of the last few rounds. Each benchmark writes 50 rounds
+//! for a committee of 4 to 100 validators and reads the certificates of the last 10 rounds with
+//! no cache, a cache holding half of them and the default cache.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::{collections::BTreeSet, num::NonZeroUsize};
+use tn_storage::{mem_db::MemDatabase, open_db, CertificateStore as _};
+use tn_test_utils::{make_optimal_certificates, temp_dir, CommitteeFixture};
+use tn_types::{
+    Certificate, CertificateStoreCache, CertificateStoreCacheMetrics,
+    DEFAULT_CERTIFICATE_CACHE_CAPACITY, DEFAULT_CERTIFICATE_CACHE_SHARDS,
+};
+
+/// The rounds of certificates written to the store.
+const ROUNDS: u32 = 50;
+/// The most recent rounds read from the store.
+const READ_ROUNDS: u32 = 10;
+
+fn certificate_reads(c: &mut Criterion) {
+    let mut group = c.benchmark_group("certificate_reads");
+    for size in [4, 10, 50, 100] {
+        let fixture = CommitteeFixture::builder(MemDatabase::default)
+            .committee_size(NonZeroUsize::new(size).expect("committee size"))
+            .build();
+        let committee = fixture.committee();
+        let ids: Vec<_> = fixture.authorities().map(|a| a.id()).collect();
+        let genesis = Certificate::genesis(&committee)
+            .iter()
+            .map(|cert| cert.digest())
+            .collect::<BTreeSet<_>>();
+        let (certificates, _) = make_optimal_certificates(&committee, 1..=ROUNDS, &genesis, &ids);
+        let recent: Vec<_> = certificates
+            .iter()
+            .filter(|cert| cert.round() > ROUNDS - READ_ROUNDS)
+            .map(|cert| cert.digest())
+            .collect();
+        group.throughput(Throughput::Elements(recent.len() as u64));
+
+        let caches = [
+            ("no_cache", None),
+            ("half_cache", Some(recent.len() / 2)),
+            ("default_cache", Some(DEFAULT_CERTIFICATE_CACHE_CAPACITY)),
+        ];
+        for (name, capacity) in caches {
+            let mut store = open_db(temp_dir());
+            if let Some(capacity) = capacity {
+                store = store.with_certificate_cache(CertificateStoreCache::new(
+                    capacity,
+                    DEFAULT_CERTIFICATE_CACHE_SHARDS,
+                    CertificateStoreCacheMetrics::default(),
+                ));
+            }
+            store.write_all(certificates.iter().cloned()).expect("certificates written");
+            group.bench_with_input(BenchmarkId::new(name, size), &recent, |b, recent| {
+                b.iter(|| {
+                    for digest in recent {
+                        store.read(*digest).expect("certificate read");
+                    }
+                })
+            });
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, certificate_reads);
+criterion_main!(benches);
@@ -0,0 +1,202 @@
+//! Encryption at rest for the consensus DB.
+//!
+//! Values in the payload and batch tables can be encrypted with AES-256-GCM before they are
+//! written to disk. The key is derived from an operator-supplied secret with HKDF-SHA256, so the
+//! secret should be random (ie - `openssl rand -hex 32`) rather than a password. Keys are never
+//! encrypted since the backends sort by them.
+//!
+//! Each encrypted value is stored as a random 12 byte nonce followed by the ciphertext and tag.
+//! The table name and encoded key are authenticated with the value, so a value copied to another
+//! row or table fails to decrypt.
+//! The DB records a check value encrypted with the key the first time it is opened with a secret.
+//! Opening it again with a different secret, or without one, fails instead of panicking on the
+//! first encrypted read.
+
+use crate::StoreResult;
+use aes_gcm::{
+    aead::{Aead as _, AeadCore as _, KeyInit as _, OsRng, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use hkdf::Hkdf;
+use serde::{de::DeserializeOwned, Serialize};
+use sha2::Sha256;
+use std::path::Path;
+use tn_types::{encode, try_decode};
+use zeroize::Zeroizing;
+
+/// The tables with encrypted values.
+pub const ENCRYPTED_TABLES: [&str; 2] = [crate::PAYLOAD_CF, crate::BATCHES_CF];
+
+/// The minimum length of the operator-supplied secret in bytes.
+pub const MIN_SECRET_LEN: usize = 32;
+
+/// The HKDF info for the storage key, bump the version to derive a different key.
+const KEY_INFO: &[u8] = b"telcoin-network consensus storage v1";
+
+/// The length of the nonce prepended to each encrypted value.
+const NONCE_LEN: usize = 12;
+
+/// The plaintext of the check value that verifies the key.
+#[cfg_attr(not(feature = "reth-libmdbx"), allow(dead_code))]
+pub(crate) const KEY_CHECK: &[u8] = b"telcoin-network encrypted storage";
+
+/// Encrypts and decrypts values for the tables in [ENCRYPTED_TABLES].
+#[derive(Clone)]
+pub struct StorageCipher {
+    cipher: Aes256Gcm,
+}
+
+impl StorageCipher {
+    /// Derive the storage key from the operator-supplied secret.
+    pub fn from_secret(secret: &[u8]) -> eyre::Result<Self> {
+        eyre::ensure!(
+            secret.len() >= MIN_SECRET_LEN,
+            "storage encryption secret must be at least {MIN_SECRET_LEN} bytes"
+        );
+        let mut key = Zeroizing::new([0u8; 32]);
+        Hkdf::<Sha256>::new(None, secret)
+            .expand(KEY_INFO, key.as_mut())
+            .map_err(|e| eyre::eyre!("failed to derive storage key: {e}"))?;
+        let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(key.as_ref()));
+        Ok(Self { cipher })
+    }
+
+    /// Read the secret from a file and derive the storage key.
+    ///
+    /// Surrounding whitespace, like a trailing newline, is not part of the secret.
+    pub fn from_secret_file<P: AsRef<Path>>(path: P) -> eyre::Result<Self> {
+        let path = path.as_ref();
+        let contents = Zeroizing::new(std::fs::read(path).map_err(|e| {
+            eyre::eyre!("failed to read storage encryption secret {}: {e}", path.display())
+        })?);
+        Self::from_secret(contents.trim_ascii())
+    }
+
+    /// Return true if values in the table are encrypted.
+    pub fn encrypts(table: &str) -> bool {
+        ENCRYPTED_TABLES.contains(&table)
+    }
+
+    /// Encrypt the value of the row `key` in `table` with a random nonce.
+    pub fn encrypt(&self, table: &str, key: &[u8], plaintext: &[u8]) -> Vec<u8> {
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let aad = associated_data(table, key);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, Payload { msg: plaintext, aad: &aad })
+            .expect("encrypting a value should not fail");
+        let mut bytes = Vec::with_capacity(NONCE_LEN + ciphertext.len());
+        bytes.extend_from_slice(&nonce);
+        bytes.extend_from_slice(&ciphertext);
+        bytes
+    }
+
+    /// Decrypt bytes created by [Self::encrypt] for the row `key` in `table`.
+    ///
+    /// Fails if the bytes were encrypted with another key, for another row or were changed.
+    pub fn decrypt(&self, table: &str, key: &[u8], bytes: &[u8]) -> eyre::Result<Vec<u8>> {
+        eyre::ensure!(bytes.len() > NONCE_LEN, "encrypted value is too short");
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LEN);
+        let aad = associated_data(table, key);
+        self.cipher.decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad: &aad }).map_err(
+            |_| {
+                eyre::eyre!(
+                    "failed to decrypt {table} value, wrong storage encryption secret or corrupt row?"
+                )
+            },
+        )
+    }
+}
+
+/// The data authenticated with a value, the length prefixed table name followed by the key.
+fn associated_data(table: &str, key: &[u8]) -> Vec<u8> {
+    let mut aad = Vec::with_capacity(4 + table.len() + key.len());
+    aad.extend_from_slice(&(table.len() as u32).to_le_bytes());
+    aad.extend_from_slice(table.as_bytes());
+    aad.extend_from_slice(key);
+    aad
+}
+
+impl std::fmt::Debug for StorageCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("StorageCipher").finish_non_exhaustive()
+    }
+}
+
+/// Encode the value of the row `key` in `table`, encrypting it if there is a cipher for the table.
+#[cfg_attr(not(feature = "reth-libmdbx"), allow(dead_code))]
+pub(crate) fn encode_value<V: Serialize>(
+    cipher: Option<&StorageCipher>,
+    table: &str,
+    key: &[u8],
+    value: &V,
+) -> Vec<u8> {
+    match cipher {
+        Some(cipher) => cipher.encrypt(table, key, &encode(value)),
+        None => encode(value),
+    }
+}
+
+/// Decode the value of the row `key` in `table`, decrypting it if there is a cipher for the table.
+///
+/// Fails instead of panicking on a corrupt row or one encrypted with another key.
+#[cfg_attr(not(feature = "reth-libmdbx"), allow(dead_code))]
+pub(crate) fn decode_value<V: DeserializeOwned>(
+    cipher: Option<&StorageCipher>,
+    table: &str,
+    key: &[u8],
+    bytes: &[u8],
+) -> StoreResult<V> {
+    match cipher {
+        Some(cipher) => Ok(try_decode(&cipher.decrypt(table, key, bytes)?)?),
+        None => Ok(try_decode(bytes)?),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_storage_cipher() {
+        let cipher = StorageCipher::from_secret(&[7; MIN_SECRET_LEN]).expect("valid secret");
+        assert!(StorageCipher::from_secret(&[7; MIN_SECRET_LEN - 1]).is_err());
+        assert!(StorageCipher::encrypts(crate::BATCHES_CF));
+        assert!(!StorageCipher::encrypts(crate::CERTIFICATES_CF));
+
+        // values round trip and the same value encrypts differently each time
+        let (table, key) = (crate::BATCHES_CF, b"key".as_slice());
+        let value = "batch".to_string();
+        let encrypted = encode_value(Some(&cipher), table, key, &value);
+        assert_ne!(encrypted, encode_value(Some(&cipher), table, key, &value));
+        assert_ne!(encrypted, encode(&value));
+        assert_eq!(
+            decode_value::<String>(Some(&cipher), table, key, &encrypted).expect("decrypted"),
+            value
+        );
+
+        // other keys and changed values are rejected
+        let other = StorageCipher::from_secret(&[8; MIN_SECRET_LEN]).expect("valid secret");
+        assert!(decode_value::<String>(Some(&other), table, key, &encrypted).is_err());
+        let mut changed = encrypted.clone();
+        *changed.last_mut().expect("not empty") ^= 1;
+        assert!(decode_value::<String>(Some(&cipher), table, key, &changed).is_err());
+        assert!(decode_value::<String>(Some(&cipher), table, key, &encode(&value)).is_err());
+
+        // values can not be moved to another row or table
+        assert!(decode_value::<String>(Some(&cipher), table, b"other", &encrypted).is_err());
+        assert!(decode_value::<String>(Some(&cipher), crate::PAYLOAD_CF, key, &encrypted).is_err());
+
+        // the secret file is trimmed
+        let dir = tempfile::tempdir().expect("temp dir");
+        let path = dir.path().join("secret");
+        std::fs::write(&path, format!("{}\n", "a".repeat(MIN_SECRET_LEN))).expect("write secret");
+        let from_file = StorageCipher::from_secret_file(&path).expect("valid secret file");
+        let from_secret = StorageCipher::from_secret(&[b'a'; MIN_SECRET_LEN]).expect("secret");
+        let encrypted = encode_value(Some(&from_file), table, key, &value);
+        assert_eq!(
+            decode_value::<String>(Some(&from_secret), table, key, &encrypted).expect("decrypted"),
+            value
+        );
+    }
+}
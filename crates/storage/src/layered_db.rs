@@ -10,7 +10,9 @@ use std::{
 };
 
 use crate::mem_db::MemDatabase;
-use tn_types::{DBIter, Database, DbTx, DbTxMut, Table};
+#[cfg(feature = "chaos")]
+use rand::Rng as _;
+use tn_types::{CertificateStoreCache, DBIter, Database, DbTx, DbTxMut, Table};
 
 #[derive(Clone, Debug)]
 pub struct LayeredDbTx {
@@ -74,6 +76,8 @@ impl<DB: Database> DbTxMut for LayeredDbTxMut<DB> {
 fn db_run<DB: Database>(db: DB, rx: Receiver<DBMessage<DB>>) {
     let mut txn = None;
     let mut last_compact = Instant::now();
+    #[cfg(feature = "chaos")]
+    let mut slow_commits = None;
     if let Err(e) = db.compact() {
         tracing::error!("DB ERROR compacting DB on startup (background): {e}");
     }
@@ -92,6 +96,10 @@ fn db_run<DB: Database>(db: DB, rx: Receiver<DBMessage<DB>>) {
             DBMessage::CommitTxn => {
                 if let Some((current_txn, count)) = txn.take() {
                     if count <= 1 {
+                        #[cfg(feature = "chaos")]
+                        if let Some((rate, max_delay)) = slow_commits {
+                            slow_commit(rate, max_delay);
+                        }
                         if let Err(e) = current_txn.commit() {
                             tracing::error!("DB TXN Commit: {e}")
                         }
@@ -127,6 +135,8 @@ fn db_run<DB: Database>(db: DB, rx: Receiver<DBMessage<DB>>) {
                     tracing::error!("DB Clear: {e}")
                 }
             }
+            #[cfg(feature = "chaos")]
+            DBMessage::SlowCommits { rate, max_delay } => slow_commits = Some((rate, max_delay)),
             DBMessage::Shutdown => break,
         }
         // if it has been 24 hours since last compaction then do it again.
@@ -140,6 +150,17 @@ fn db_run<DB: Database>(db: DB, rx: Receiver<DBMessage<DB>>) {
     tracing::info!("Layerd DB thread Shutdown complete");
 }
 
+/// Block the DB thread for up to `max_delay` on a `rate` fraction of commits.
+#[cfg(feature = "chaos")]
+fn slow_commit(rate: f64, max_delay: Duration) {
+    let mut rng = rand::thread_rng();
+    if !max_delay.is_zero() && rng.gen_bool(rate) {
+        let delay = rng.gen_range(Duration::ZERO..=max_delay);
+        tracing::debug!(target: "db::chaos", ?delay, "slowing down commit");
+        std::thread::sleep(delay);
+    }
+}
+
 /// Implement the Database trait with an in-memory store.
 /// This means no persistance.
 /// This DB also plays loose with transactions, but since it is in-memory and we do not do
@@ -147,10 +168,13 @@ fn db_run<DB: Database>(db: DB, rx: Receiver<DBMessage<DB>>) {
 #[derive(Clone, Debug)]
 pub struct LayeredDatabase<DB: Database> {
     mem_db: MemDatabase,
-    db: DB,
+    /// The persistent DB, None when the data only lives in memory.
+    db: Option<DB>,
     tx: Sender<DBMessage<DB>>,
     thread: Option<Arc<JoinHandle<()>>>, /* Use as a ref count for shuting down the background
                                           * thread and it's handle. */
+    /// Decoded certificates read through the certificate store, shared by clones.
+    certificate_cache: Option<CertificateStoreCache>,
 }
 
 impl<DB: Database> Drop for LayeredDatabase<DB> {
@@ -180,14 +204,90 @@ impl<DB: Database> LayeredDatabase<DB> {
         let (tx, rx) = mpsc::channel();
         let db_cloned = db.clone();
         let thread = Some(Arc::new(std::thread::spawn(move || db_run(db_cloned, rx))));
-        Self { mem_db: MemDatabase::new(), db, tx, thread }
+        Self { mem_db: MemDatabase::new(), db: Some(db), tx, thread, certificate_cache: None }
+    }
+
+    /// Open a layered DB without a persistent DB, nothing is written to disk.
+    ///
+    /// Writes are still sent to the background thread, which drops them, so the DB behaves the
+    /// same as a persistent one other than losing its data when dropped.
+    pub fn open_in_memory() -> Self {
+        let (tx, thread) = Self::discard_writes();
+        Self { mem_db: MemDatabase::new(), db: None, tx, thread, certificate_cache: None }
+    }
+
+    /// Open a layered DB that loads its tables from `db` but never writes to it.
+    ///
+    /// Writes only change the in-memory copy, which is lost when dropped. Used by RPC replicas
+    /// that serve a snapshot of another node's DB.
+    pub fn open_read_only(db: DB) -> Self {
+        let (tx, thread) = Self::discard_writes();
+        Self { mem_db: MemDatabase::new(), db: Some(db), tx, thread, certificate_cache: None }
+    }
+
+    /// Spawn a background thread that drops every write until shutdown.
+    fn discard_writes() -> (Sender<DBMessage<DB>>, Option<Arc<JoinHandle<()>>>) {
+        let (tx, rx) = mpsc::channel::<DBMessage<DB>>();
+        let thread = Some(Arc::new(std::thread::spawn(move || {
+            while let Ok(msg) = rx.recv() {
+                if matches!(msg, DBMessage::Shutdown) {
+                    break;
+                }
+            }
+        })));
+        (tx, thread)
+    }
+
+    /// Delay a random `rate` fraction of commits by up to `max_delay`.
+    ///
+    /// Used by chaos mode on staging networks to simulate slow disks. Reads are served from memory
+    /// so only the background writes fall behind. Requires the `chaos` feature.
+    #[cfg(feature = "chaos")]
+    pub fn inject_slow_commits(&self, rate: f64, max_delay: Duration) -> eyre::Result<()> {
+        let rate = if rate.is_nan() { 0.0 } else { rate.clamp(0.0, 1.0) };
+        self.tx
+            .send(DBMessage::SlowCommits { rate, max_delay })
+            .map_err(|_| eyre::eyre!("DB thread gone, FATAL!"))
+    }
+
+    /// Read certificates through `cache`.
+    ///
+    /// The cache is shared by every clone made after this is called, so set it right after
+    /// opening the DB.
+    pub fn with_certificate_cache(mut self, cache: CertificateStoreCache) -> Self {
+        self.certificate_cache = Some(cache);
+        self
+    }
+
+    /// The persistent DB, `None` for an in-memory DB.
+    pub fn persistent(&self) -> Option<&DB> {
+        self.db.as_ref()
+    }
+
+    /// Replace the in-memory copy of the table with the records in the persistent DB.
+    ///
+    /// Used by RPC replicas to pick up records written by the node that owns the DB since it was
+    /// opened.
+    pub fn reload_table<T: Table>(&self) {
+        if let Some(db) = &self.db {
+            self.mem_db.replace_table::<T>(db.iter::<T>());
+        }
+    }
+
+    /// Open the table in memory without loading it from the persistent DB.
+    ///
+    /// Used for tables a read-only persistent DB does not have, reads find no records.
+    pub fn open_memory_table<T: Table>(&self) {
+        self.mem_db.open_table::<T>();
     }
 
     pub fn open_table<T: Table>(&self) {
         self.mem_db.open_table::<T>();
-        for (key, value) in self.db.iter::<T>() {
-            // mem db insert should not fail.
-            let _ = self.mem_db.insert::<T>(&key, &value);
+        if let Some(db) = &self.db {
+            for (key, value) in db.iter::<T>() {
+                // mem db insert should not fail.
+                let _ = self.mem_db.insert::<T>(&key, &value);
+            }
         }
     }
 }
@@ -267,6 +367,10 @@ impl<DB: Database> Database for LayeredDatabase<DB> {
     fn last_record<T: Table>(&self) -> Option<(T::Key, T::Value)> {
         self.mem_db.last_record::<T>()
     }
+
+    fn certificate_cache(&self) -> Option<&CertificateStoreCache> {
+        self.certificate_cache.as_ref()
+    }
 }
 
 trait InsertTrait<DB: Database>: Send + 'static {
@@ -332,6 +436,12 @@ enum DBMessage<DB: Database> {
     Insert(Box<dyn InsertTrait<DB>>),
     Remove(Box<dyn RemoveTrait<DB>>),
     Clear(Box<dyn ClearTrait<DB>>),
+    /// Delay a fraction of commits, see [LayeredDatabase::inject_slow_commits].
+    #[cfg(feature = "chaos")]
+    SlowCommits {
+        rate: f64,
+        max_delay: Duration,
+    },
     Shutdown,
 }
 
@@ -343,6 +453,8 @@ impl<DB: Database> Debug for DBMessage<DB> {
             DBMessage::Insert(_) => write!(f, "Insert"),
             DBMessage::Remove(_) => write!(f, "Remove"),
             DBMessage::Clear(_) => write!(f, "Clear"),
+            #[cfg(feature = "chaos")]
+            DBMessage::SlowCommits { .. } => write!(f, "SlowCommits"),
             DBMessage::Shutdown => write!(f, "Shutdown"),
         }
     }
@@ -544,4 +656,92 @@ mod test {
         let db = open_mdbx(temp_dir.path());
         db_simp_bench(db, "LayeredDB<MdbxDatabase>");
     }
+
+    #[test]
+    fn test_layereddb_in_memory() {
+        let open_in_memory = || {
+            let db = LayeredDatabase::<MdbxDatabase>::open_in_memory();
+            db.open_table::<TestTable>();
+            db
+        };
+        test_get(open_in_memory());
+        test_iter(open_in_memory());
+        test_multi_remove(open_in_memory());
+        test_clear(open_in_memory());
+    }
+
+    #[test]
+    fn test_layereddb_read_only() {
+        use tn_types::Database as _;
+
+        let temp_dir = tempdir().expect("failed to create temp dir");
+        let db = open_mdbx(temp_dir.path());
+        db.insert::<TestTable>(&1, &"one".to_string()).expect("insert");
+        // drop to flush the write to disk
+        drop(db);
+
+        let persistent =
+            MdbxDatabase::open_read_only(temp_dir.path(), None).expect("open read only");
+        let db = LayeredDatabase::open_read_only(persistent);
+        db.open_table::<TestTable>();
+        assert_eq!(db.get::<TestTable>(&1).expect("get"), Some("one".to_string()));
+
+        // writes are only kept in memory
+        db.insert::<TestTable>(&2, &"two".to_string()).expect("insert");
+        assert!(db.contains_key::<TestTable>(&2).expect("contains"));
+
+        // reloading replaces the in-memory copy with the records on disk
+        db.reload_table::<TestTable>();
+        assert!(!db.contains_key::<TestTable>(&2).expect("contains"));
+        assert_eq!(db.get::<TestTable>(&1).expect("get"), Some("one".to_string()));
+        drop(db);
+        let db = open_mdbx(temp_dir.path());
+        assert!(!db.contains_key::<TestTable>(&2).expect("contains"));
+    }
+
+    #[cfg(all(feature = "reth-libmdbx", not(feature = "redb"), not(feature = "rocksdb")))]
+    #[test]
+    fn test_read_only_db_missing_tables() {
+        use crate::tables::{ConsensusBlocks, StreamOffsets};
+        use tn_types::Database as _;
+
+        // a DB without the node's tables, ie - written by an older node
+        let temp_dir = tempdir().expect("failed to create temp dir");
+        drop(MdbxDatabase::open(temp_dir.path()).expect("db created"));
+
+        let db = crate::open_read_only_db(temp_dir.path(), None).expect("open read only");
+        assert_eq!(db.get::<ConsensusBlocks>(&0).expect("get"), None);
+        assert!(db.is_empty::<StreamOffsets>());
+
+        // single records are read from the table without loading the DB
+        assert!(crate::read_only_get::<ConsensusBlocks, _>(temp_dir.path(), None, &0).is_err());
+        drop(db);
+        let writer = open_mdbx(temp_dir.path());
+        writer.insert::<TestTable>(&1, &"one".to_string()).expect("insert");
+        drop(writer);
+        let record = crate::read_only_get::<TestTable, _>(temp_dir.path(), None, &1).expect("get");
+        assert_eq!(record, Some("one".to_string()));
+    }
+
+    #[cfg(feature = "chaos")]
+    #[test]
+    fn test_layereddb_slow_commits() {
+        use std::time::Duration;
+        use tn_types::{Database as _, DbTxMut as _};
+
+        let temp_dir = tempdir().expect("failed to create temp dir");
+        let db = open_mdbx(temp_dir.path());
+        db.inject_slow_commits(1.0, Duration::from_millis(20)).expect("chaos");
+
+        let mut txn = db.write_txn().expect("txn");
+        txn.insert::<TestTable>(&1, &"one".to_string()).expect("insert");
+        txn.commit().expect("commit");
+        // reads are served from memory and don't wait for the commit
+        assert_eq!(db.get::<TestTable>(&1).expect("get"), Some("one".to_string()));
+
+        // the commit still reaches disk
+        drop(db);
+        let db = open_mdbx(temp_dir.path());
+        assert_eq!(db.get::<TestTable>(&1).expect("get"), Some("one".to_string()));
+    }
 }
@@ -3,7 +3,10 @@
 
 #![warn(future_incompatible, nonstandard_style, rust_2018_idioms, rust_2021_compatibility)]
 
+pub mod encryption;
+pub mod migrations;
 mod stores;
+pub use encryption::StorageCipher;
 use layered_db::LayeredDatabase;
 #[cfg(feature = "reth-libmdbx")]
 use mdbx::MdbxDatabase;
@@ -13,8 +16,11 @@ pub use redb::database::ReDB;
 #[cfg(feature = "rocksdb")]
 use rocks::database::RocksDatabase;
 use tables::{
-    Batches, CertificateDigestByOrigin, CertificateDigestByRound, Certificates,
-    ConsensusBlockNumbersByDigest, ConsensusBlocks, LastProposed, Payload, Votes,
+    AddressIndexCursor, BatchPruneCursor, BatchReferences, Batches, CertificateDigestByOrigin,
+    CertificateDigestByRound, Certificates, Committees, ConsensusBlockNumbersByDigest,
+    ConsensusBlocks, EpochSummaries, HaltTarget, LastProposed, Payload, PeerEvents, PeerLatencies,
+    SchemaVersion, StreamOffsets, SubDagStatsByNumber, TransactionsByRecipient,
+    TransactionsBySender, Votes,
 };
 // Always build redb, we use it as the default for persistant consensus data.
 pub mod layered_db;
@@ -49,6 +55,19 @@ const PAYLOAD_CF: &str = "payload";
 const BATCHES_CF: &str = "batches";
 const CONSENSUS_BLOCK_CF: &str = "consensus_block";
 const CONSENSUS_BLOCK_NUMBER_BY_DIGEST_CF: &str = "consensus_block_number_by_digest";
+const SUB_DAG_STATS_CF: &str = "sub_dag_stats";
+const SCHEMA_VERSION_CF: &str = "schema_version";
+const BATCH_PRUNE_CURSOR_CF: &str = "batch_prune_cursor";
+const BATCH_REFERENCES_CF: &str = "batch_references";
+const EPOCH_SUMMARIES_CF: &str = "epoch_summaries";
+const COMMITTEES_CF: &str = "committees";
+const PEER_EVENTS_CF: &str = "peer_events";
+const PEER_LATENCIES_CF: &str = "peer_latencies";
+const TRANSACTIONS_BY_SENDER_CF: &str = "transactions_by_sender";
+const TRANSACTIONS_BY_RECIPIENT_CF: &str = "transactions_by_recipient";
+const STREAM_OFFSETS_CF: &str = "stream_offsets";
+const ADDRESS_INDEX_CURSOR_CF: &str = "address_index_cursor";
+const HALT_TARGET_CF: &str = "halt_target";
 
 macro_rules! tables {
     ( $($table:ident;$name:expr;<$K:ty, $V:ty>),*) => {
@@ -68,8 +87,9 @@ macro_rules! tables {
 pub mod tables {
     use super::{PayloadToken, ProposerKey};
     use tn_types::{
-        AuthorityIdentifier, Batch, BlockHash, Certificate, CertificateDigest, ConsensusHeader,
-        Header, Round, VoteInfo, WorkerId,
+        Address, AuthorityIdentifier, Batch, BlockHash, Certificate, CertificateDigest, Committee,
+        ConsensusHeader, Epoch, EpochSummary, Header, IndexedTransaction, PeerEvent, PeerLatency,
+        PeerNetwork, Round, SubDagStats, VoteInfo, WorkerId,
     };
 
     tables!(
@@ -83,7 +103,33 @@ pub mod tables {
         Batches;crate::BATCHES_CF;<BlockHash, Batch>,
         // These tables are for the consensus chain not the normal consensus.
         ConsensusBlocks;crate::CONSENSUS_BLOCK_CF;<u64, ConsensusHeader>,
-        ConsensusBlockNumbersByDigest;crate::CONSENSUS_BLOCK_NUMBER_BY_DIGEST_CF;<BlockHash, u64>
+        ConsensusBlockNumbersByDigest;crate::CONSENSUS_BLOCK_NUMBER_BY_DIGEST_CF;<BlockHash, u64>,
+        // Execution statistics for each consensus chain block.
+        SubDagStatsByNumber;crate::SUB_DAG_STATS_CF;<u64, SubDagStats>,
+        // The schema version of the DB, see [crate::migrations].
+        SchemaVersion;crate::SCHEMA_VERSION_CF;<u8, u64>,
+        // The next consensus chain block to prune the batches of.
+        BatchPruneCursor;crate::BATCH_PRUNE_CURSOR_CF;<u8, u64>,
+        // The number of consensus chain blocks referencing each stored batch.
+        BatchReferences;crate::BATCH_REFERENCES_CF;<BlockHash, u32>,
+        // The summary of each completed epoch.
+        EpochSummaries;crate::EPOCH_SUMMARIES_CF;<u64, EpochSummary>,
+        // The committee of each consensus epoch the node ran in.
+        Committees;crate::COMMITTEES_CF;<Epoch, Committee>,
+        // Recent peer connectivity events by network and sequence number.
+        PeerEvents;crate::PEER_EVENTS_CF;<(PeerNetwork, u64), PeerEvent>,
+        // The smoothed round trip time to each authority's worker.
+        PeerLatencies;crate::PEER_LATENCIES_CF;<AuthorityIdentifier, PeerLatency>,
+        // Executed transactions by sender, block number, and index in the block, if indexed.
+        TransactionsBySender;crate::TRANSACTIONS_BY_SENDER_CF;<(Address, u64, u32), IndexedTransaction>,
+        // Executed transactions by recipient, block number, and index in the block, if indexed.
+        TransactionsByRecipient;crate::TRANSACTIONS_BY_RECIPIENT_CF;<(Address, u64, u32), IndexedTransaction>,
+        // The last execution block published by each stream, keyed by the stream's topic prefix.
+        StreamOffsets;crate::STREAM_OFFSETS_CF;<String, u64>,
+        // The last execution block with indexed transactions, if addresses are indexed.
+        AddressIndexCursor;crate::ADDRESS_INDEX_CURSOR_CF;<u8, u64>,
+        // The consensus sub-dag an operator asked the node to halt after.
+        HaltTarget;crate::HALT_TARGET_CF;<u8, u64>
     );
 }
 
@@ -111,12 +157,192 @@ pub fn open_db<Path: AsRef<std::path::Path> + Send>(store_path: Path) -> Databas
     panic!("No DB configured!")
 }
 
+/// Open the configured DB with the required tables and encrypt the values of the
+/// [encryption::ENCRYPTED_TABLES].
+///
+/// The DB must always be opened with the same cipher once it is encrypted. Only the MDBX backend
+/// supports encryption.
+pub fn open_encrypted_db<Path: AsRef<std::path::Path> + Send>(
+    store_path: Path,
+    cipher: StorageCipher,
+) -> eyre::Result<DatabaseType> {
+    #[cfg(all(feature = "reth-libmdbx", not(feature = "redb"), not(feature = "rocksdb")))]
+    {
+        let db = MdbxDatabase::open_with_cipher(store_path, Some(cipher))?;
+        Ok(_open_mdbx_tables(db))
+    }
+    #[cfg(not(all(feature = "reth-libmdbx", not(feature = "redb"), not(feature = "rocksdb"))))]
+    {
+        let _ = (store_path, cipher);
+        eyre::bail!("storage encryption requires the MDBX backend")
+    }
+}
+
+/// Open the configured DB type with the required tables without persistent storage.
+///
+/// Nothing is written to disk and all data is lost when the DB is dropped. Used to run ephemeral
+/// nodes for CI and local experiments.
+pub fn open_memory_db() -> DatabaseType {
+    let db = DatabaseType::open_in_memory();
+    db.open_table::<LastProposed>();
+    db.open_table::<Votes>();
+    db.open_table::<Certificates>();
+    db.open_table::<CertificateDigestByRound>();
+    db.open_table::<CertificateDigestByOrigin>();
+    db.open_table::<Payload>();
+    db.open_table::<Batches>();
+    db.open_table::<ConsensusBlocks>();
+    db.open_table::<ConsensusBlockNumbersByDigest>();
+    db.open_table::<SubDagStatsByNumber>();
+    db.open_table::<SchemaVersion>();
+    db.open_table::<BatchPruneCursor>();
+    db.open_table::<BatchReferences>();
+    db.open_table::<EpochSummaries>();
+    db.open_table::<Committees>();
+    db.open_table::<PeerEvents>();
+    db.open_table::<PeerLatencies>();
+    db.open_table::<TransactionsBySender>();
+    db.open_table::<TransactionsByRecipient>();
+    db.open_table::<StreamOffsets>();
+    db.open_table::<AddressIndexCursor>();
+    db.open_table::<HaltTarget>();
+    db
+}
+
+/// Open an existing DB without write access and load its tables into memory.
+///
+/// Used by RPC replicas that serve a snapshot of a node's consensus DB. Writes only change the
+/// in-memory copy, see [refresh_read_only_db] to load records written since. Tables missing from
+/// the DB, ie - written by an older node, are opened empty. Only the MDBX backend can be opened
+/// read-only. An encrypted DB must be opened with its cipher.
+pub fn open_read_only_db<Path: AsRef<std::path::Path> + Send>(
+    store_path: Path,
+    cipher: Option<StorageCipher>,
+) -> eyre::Result<DatabaseType> {
+    #[cfg(all(feature = "reth-libmdbx", not(feature = "redb"), not(feature = "rocksdb")))]
+    {
+        let db = MdbxDatabase::open_read_only(store_path, cipher)?;
+        let db = LayeredDatabase::open_read_only(db);
+        db.open_memory_table::<LastProposed>();
+        db.open_memory_table::<Votes>();
+        db.open_memory_table::<Certificates>();
+        db.open_memory_table::<CertificateDigestByRound>();
+        db.open_memory_table::<CertificateDigestByOrigin>();
+        db.open_memory_table::<Payload>();
+        db.open_memory_table::<Batches>();
+        db.open_memory_table::<ConsensusBlocks>();
+        db.open_memory_table::<ConsensusBlockNumbersByDigest>();
+        db.open_memory_table::<SubDagStatsByNumber>();
+        db.open_memory_table::<SchemaVersion>();
+        db.open_memory_table::<BatchPruneCursor>();
+        db.open_memory_table::<BatchReferences>();
+        db.open_memory_table::<EpochSummaries>();
+        db.open_memory_table::<Committees>();
+        db.open_memory_table::<PeerEvents>();
+        db.open_memory_table::<PeerLatencies>();
+        db.open_memory_table::<TransactionsBySender>();
+        db.open_memory_table::<TransactionsByRecipient>();
+        db.open_memory_table::<StreamOffsets>();
+        db.open_memory_table::<AddressIndexCursor>();
+        db.open_memory_table::<HaltTarget>();
+        refresh_read_only_db(&db)?;
+        Ok(db)
+    }
+    #[cfg(not(all(feature = "reth-libmdbx", not(feature = "redb"), not(feature = "rocksdb"))))]
+    {
+        let _ = (store_path, cipher);
+        eyre::bail!("read-only storage requires the MDBX backend")
+    }
+}
+
+/// Read a record from an existing DB without write access.
+///
+/// Only the table is read, nothing is loaded into memory, so a node's DB can be inspected while
+/// the node runs. Returns an error if the DB does not have the table or the record can not be
+/// decoded. An encrypted DB must be read with its cipher.
+pub fn read_only_get<T: tn_types::Table, Path: AsRef<std::path::Path> + Send>(
+    store_path: Path,
+    cipher: Option<StorageCipher>,
+    key: &T::Key,
+) -> eyre::Result<Option<T::Value>> {
+    #[cfg(all(feature = "reth-libmdbx", not(feature = "redb"), not(feature = "rocksdb")))]
+    {
+        use tn_types::Database as _;
+
+        let db = MdbxDatabase::open_read_only(store_path, cipher)?;
+        eyre::ensure!(db.has_table(T::NAME)?, "the database has no {} table", T::NAME);
+        db.get::<T>(key)
+    }
+    #[cfg(not(all(feature = "reth-libmdbx", not(feature = "redb"), not(feature = "rocksdb"))))]
+    {
+        let _ = (store_path, cipher, key);
+        eyre::bail!("read-only storage requires the MDBX backend")
+    }
+}
+
+/// Reload the tables of a DB opened with [open_read_only_db] from disk.
+///
+/// Picks up the records the node that owns the DB wrote since the last load. Each table is
+/// replaced at once so readers never see a partially loaded table.
+pub fn refresh_read_only_db(db: &DatabaseType) -> eyre::Result<()> {
+    #[cfg(all(feature = "reth-libmdbx", not(feature = "redb"), not(feature = "rocksdb")))]
+    {
+        _reload_read_only_table::<LastProposed>(db)?;
+        _reload_read_only_table::<Votes>(db)?;
+        _reload_read_only_table::<Certificates>(db)?;
+        _reload_read_only_table::<CertificateDigestByRound>(db)?;
+        _reload_read_only_table::<CertificateDigestByOrigin>(db)?;
+        _reload_read_only_table::<Payload>(db)?;
+        _reload_read_only_table::<Batches>(db)?;
+        _reload_read_only_table::<ConsensusBlocks>(db)?;
+        _reload_read_only_table::<ConsensusBlockNumbersByDigest>(db)?;
+        _reload_read_only_table::<SubDagStatsByNumber>(db)?;
+        _reload_read_only_table::<SchemaVersion>(db)?;
+        _reload_read_only_table::<BatchPruneCursor>(db)?;
+        _reload_read_only_table::<BatchReferences>(db)?;
+        _reload_read_only_table::<EpochSummaries>(db)?;
+        _reload_read_only_table::<Committees>(db)?;
+        _reload_read_only_table::<PeerEvents>(db)?;
+        _reload_read_only_table::<PeerLatencies>(db)?;
+        _reload_read_only_table::<TransactionsBySender>(db)?;
+        _reload_read_only_table::<TransactionsByRecipient>(db)?;
+        _reload_read_only_table::<StreamOffsets>(db)?;
+        _reload_read_only_table::<AddressIndexCursor>(db)?;
+        _reload_read_only_table::<HaltTarget>(db)?;
+        Ok(())
+    }
+    #[cfg(not(all(feature = "reth-libmdbx", not(feature = "redb"), not(feature = "rocksdb"))))]
+    {
+        let _ = db;
+        eyre::bail!("read-only storage requires the MDBX backend")
+    }
+}
+
+/// Reload a table of a read-only MDBX DB, tables the DB does not have stay empty.
+#[cfg(feature = "reth-libmdbx")]
+fn _reload_read_only_table<T: tn_types::Table>(
+    db: &LayeredDatabase<MdbxDatabase>,
+) -> eyre::Result<()> {
+    if let Some(mdbx) = db.persistent() {
+        if mdbx.has_table(T::NAME)? {
+            db.reload_table::<T>();
+        }
+    }
+    Ok(())
+}
+
 // The open functions below are the way they are so we can use if cfg!... on open_db.
 
 /// Open or reopen all the storage of the node backed by MDBX.
 #[cfg(feature = "reth-libmdbx")]
 fn _open_mdbx<P: AsRef<std::path::Path> + Send>(store_path: P) -> LayeredDatabase<MdbxDatabase> {
     let db = MdbxDatabase::open(store_path).expect("Cannot open database");
+    _open_mdbx_tables(db)
+}
+
+/// Open all the tables of the node in an MDBX DB.
+#[cfg(feature = "reth-libmdbx")]
+fn _open_mdbx_tables(db: MdbxDatabase) -> LayeredDatabase<MdbxDatabase> {
     db.open_table::<LastProposed>().expect("failed to open table!");
     db.open_table::<Votes>().expect("failed to open table!");
     db.open_table::<Certificates>().expect("failed to open table!");
@@ -126,6 +352,19 @@ fn _open_mdbx<P: AsRef<std::path::Path> + Send>(store_path: P) -> LayeredDatabas
     db.open_table::<Batches>().expect("failed to open table!");
     db.open_table::<ConsensusBlocks>().expect("failed to open table!");
     db.open_table::<ConsensusBlockNumbersByDigest>().expect("failed to open table!");
+    db.open_table::<SubDagStatsByNumber>().expect("failed to open table!");
+    db.open_table::<SchemaVersion>().expect("failed to open table!");
+    db.open_table::<BatchPruneCursor>().expect("failed to open table!");
+    db.open_table::<BatchReferences>().expect("failed to open table!");
+    db.open_table::<EpochSummaries>().expect("failed to open table!");
+    db.open_table::<Committees>().expect("failed to open table!");
+    db.open_table::<PeerEvents>().expect("failed to open table!");
+    db.open_table::<PeerLatencies>().expect("failed to open table!");
+    db.open_table::<TransactionsBySender>().expect("failed to open table!");
+    db.open_table::<TransactionsByRecipient>().expect("failed to open table!");
+    db.open_table::<StreamOffsets>().expect("failed to open table!");
+    db.open_table::<AddressIndexCursor>().expect("failed to open table!");
+    db.open_table::<HaltTarget>().expect("failed to open table!");
 
     let db = LayeredDatabase::open(db);
     db.open_table::<LastProposed>();
@@ -137,6 +376,19 @@ fn _open_mdbx<P: AsRef<std::path::Path> + Send>(store_path: P) -> LayeredDatabas
     db.open_table::<Batches>();
     db.open_table::<ConsensusBlocks>();
     db.open_table::<ConsensusBlockNumbersByDigest>();
+    db.open_table::<SubDagStatsByNumber>();
+    db.open_table::<SchemaVersion>();
+    db.open_table::<BatchPruneCursor>();
+    db.open_table::<BatchReferences>();
+    db.open_table::<EpochSummaries>();
+    db.open_table::<Committees>();
+    db.open_table::<PeerEvents>();
+    db.open_table::<PeerLatencies>();
+    db.open_table::<TransactionsBySender>();
+    db.open_table::<TransactionsByRecipient>();
+    db.open_table::<StreamOffsets>();
+    db.open_table::<AddressIndexCursor>();
+    db.open_table::<HaltTarget>();
     db
 }
 
@@ -154,6 +406,19 @@ fn _open_rocks<P: AsRef<std::path::Path> + Send>(store_path: P) -> LayeredDataba
     db.open_table::<Batches>();
     db.open_table::<ConsensusBlocks>();
     db.open_table::<ConsensusBlockNumbersByDigest>();
+    db.open_table::<SubDagStatsByNumber>();
+    db.open_table::<SchemaVersion>();
+    db.open_table::<BatchPruneCursor>();
+    db.open_table::<BatchReferences>();
+    db.open_table::<EpochSummaries>();
+    db.open_table::<Committees>();
+    db.open_table::<PeerEvents>();
+    db.open_table::<PeerLatencies>();
+    db.open_table::<TransactionsBySender>();
+    db.open_table::<TransactionsByRecipient>();
+    db.open_table::<StreamOffsets>();
+    db.open_table::<AddressIndexCursor>();
+    db.open_table::<HaltTarget>();
     db
 }
 
@@ -170,6 +435,19 @@ fn _open_redb<P: AsRef<std::path::Path> + Send>(store_path: P) -> LayeredDatabas
     db.open_table::<Batches>().expect("failed to open table!");
     db.open_table::<ConsensusBlocks>().expect("failed to open table!");
     db.open_table::<ConsensusBlockNumbersByDigest>().expect("failed to open table!");
+    db.open_table::<SubDagStatsByNumber>().expect("failed to open table!");
+    db.open_table::<SchemaVersion>().expect("failed to open table!");
+    db.open_table::<BatchPruneCursor>().expect("failed to open table!");
+    db.open_table::<BatchReferences>().expect("failed to open table!");
+    db.open_table::<EpochSummaries>().expect("failed to open table!");
+    db.open_table::<Committees>().expect("failed to open table!");
+    db.open_table::<PeerEvents>().expect("failed to open table!");
+    db.open_table::<PeerLatencies>().expect("failed to open table!");
+    db.open_table::<TransactionsBySender>().expect("failed to open table!");
+    db.open_table::<TransactionsByRecipient>().expect("failed to open table!");
+    db.open_table::<StreamOffsets>().expect("failed to open table!");
+    db.open_table::<AddressIndexCursor>().expect("failed to open table!");
+    db.open_table::<HaltTarget>().expect("failed to open table!");
 
     let db = LayeredDatabase::open(db);
     db.open_table::<LastProposed>();
@@ -181,6 +459,19 @@ fn _open_redb<P: AsRef<std::path::Path> + Send>(store_path: P) -> LayeredDatabas
     db.open_table::<Batches>();
     db.open_table::<ConsensusBlocks>();
     db.open_table::<ConsensusBlockNumbersByDigest>();
+    db.open_table::<SubDagStatsByNumber>();
+    db.open_table::<SchemaVersion>();
+    db.open_table::<BatchPruneCursor>();
+    db.open_table::<BatchReferences>();
+    db.open_table::<EpochSummaries>();
+    db.open_table::<Committees>();
+    db.open_table::<PeerEvents>();
+    db.open_table::<PeerLatencies>();
+    db.open_table::<TransactionsBySender>();
+    db.open_table::<TransactionsByRecipient>();
+    db.open_table::<StreamOffsets>();
+    db.open_table::<AddressIndexCursor>();
+    db.open_table::<HaltTarget>();
     db
 }
 
@@ -11,20 +11,53 @@ use std::{
 };
 
 use reth_libmdbx::{
-    ffi::MDBX_dbi, Cursor, DatabaseFlags, Environment, Geometry, PageSize, Transaction, WriteFlags,
-    RO, RW,
+    ffi::MDBX_dbi, Cursor, DatabaseFlags, Environment, EnvironmentFlags, Geometry, Mode, PageSize,
+    Transaction, WriteFlags, RO, RW,
 };
-use tn_types::{
-    decode, decode_key, encode, encode_key, DBIter, Database, DbTx, DbTxMut, KeyT, Table, ValueT,
+use tn_types::{decode_key, encode_key, DBIter, Database, DbTx, DbTxMut, KeyT, Table, ValueT};
+
+use crate::{
+    encryption::{decode_value, encode_value, StorageCipher, ENCRYPTED_TABLES, KEY_CHECK},
+    mdbx::metrics::MdbxMetrics,
 };
 
-use crate::mdbx::metrics::MdbxMetrics;
+/// The MDBX sub-database with the check value for encrypted storage.
+const ENCRYPTION_CHECK_DB: &str = "encryption_check";
+
+/// The key of the check value for encrypted storage.
+const ENCRYPTION_CHECK_KEY: &[u8] = b"key_check";
+
+/// The cipher for the table's values, if they are encrypted.
+fn table_cipher<T: Table>(cipher: &Option<Arc<StorageCipher>>) -> Option<Arc<StorageCipher>> {
+    cipher.clone().filter(|_| StorageCipher::encrypts(T::NAME))
+}
+
+/// Decode a record of `table` read with a cursor.
+///
+/// A value that fails to decrypt or decode is logged and ends the iteration, as documented for
+/// [Database::iter], instead of panicking.
+fn decode_record<K: KeyT, V: ValueT>(
+    cipher: Option<&StorageCipher>,
+    table: &str,
+    key: &[u8],
+    value: &[u8],
+) -> Option<(K, V)> {
+    match decode_value::<V>(cipher, table, key, value) {
+        Ok(value) => Some((decode_key::<K>(key), value)),
+        Err(e) => {
+            tracing::error!(target: "telcoin::mdbx", "failed to read {table} record: {e}");
+            None
+        }
+    }
+}
 
 /// Wrapper for the libmdbx transaction.
 #[derive(Debug)]
 pub struct MdbxTx {
     /// Libmdbx-sys transaction.
     inner: Transaction<RO>,
+    /// The cipher for encrypted tables.
+    cipher: Option<Arc<StorageCipher>>,
 }
 
 impl MdbxTx {
@@ -41,11 +74,11 @@ impl MdbxTx {
 impl DbTx for MdbxTx {
     fn get<T: Table>(&self, key: &T::Key) -> eyre::Result<Option<T::Value>> {
         let key_buf = encode_key(key);
-        let v = self
-            .inner
-            .get::<Vec<u8>>(self.get_dbi::<T>()?, &key_buf[..])
-            .map(|res| res.map(|bytes| decode::<T::Value>(&bytes)))?;
-        Ok(v)
+        let cipher = table_cipher::<T>(&self.cipher);
+        self.inner
+            .get::<Vec<u8>>(self.get_dbi::<T>()?, &key_buf[..])?
+            .map(|bytes| decode_value::<T::Value>(cipher.as_deref(), T::NAME, &key_buf, &bytes))
+            .transpose()
     }
 }
 
@@ -54,6 +87,8 @@ impl DbTx for MdbxTx {
 pub struct MdbxTxMut {
     /// Libmdbx-sys transaction.
     inner: Transaction<RW>,
+    /// The cipher for encrypted tables.
+    cipher: Option<Arc<StorageCipher>>,
 }
 
 impl MdbxTxMut {
@@ -66,18 +101,19 @@ impl MdbxTxMut {
 impl DbTx for MdbxTxMut {
     fn get<T: Table>(&self, key: &T::Key) -> eyre::Result<Option<T::Value>> {
         let key_buf = encode_key(key);
-        let v = self
-            .inner
-            .get::<Vec<u8>>(self.get_dbi::<T>()?, &key_buf[..])
-            .map(|res| res.map(|bytes| decode::<T::Value>(&bytes)))?;
-        Ok(v)
+        let cipher = table_cipher::<T>(&self.cipher);
+        self.inner
+            .get::<Vec<u8>>(self.get_dbi::<T>()?, &key_buf[..])?
+            .map(|bytes| decode_value::<T::Value>(cipher.as_deref(), T::NAME, &key_buf, &bytes))
+            .transpose()
     }
 }
 
 impl DbTxMut for MdbxTxMut {
     fn insert<T: Table>(&mut self, key: &T::Key, value: &T::Value) -> eyre::Result<()> {
         let key_buf = encode_key(key);
-        let value_buf = encode(value);
+        let value_buf =
+            encode_value(table_cipher::<T>(&self.cipher).as_deref(), T::NAME, &key_buf, value);
         self.inner.put(self.get_dbi::<T>()?, key_buf, value_buf, WriteFlags::UPSERT)?;
         Ok(())
     }
@@ -104,6 +140,8 @@ pub struct MdbxDatabase {
     /// Libmdbx-sys environment.
     inner: Environment,
     shutdown_tx: Arc<SyncSender<()>>,
+    /// The cipher for encrypted tables, values are stored in plaintext without one.
+    cipher: Option<Arc<StorageCipher>>,
 }
 
 impl Drop for MdbxDatabase {
@@ -140,6 +178,18 @@ impl MdbxDatabase {
     /// Creates a new database at the specified path if it doesn't exist. Does NOT create tables.
     /// Check [`init_db`].
     pub fn open<P: AsRef<Path>>(path: P) -> eyre::Result<Self> {
+        Self::open_with_cipher(path, None)
+    }
+
+    /// Creates a new database at the specified path if it doesn't exist. Does NOT create tables.
+    ///
+    /// With a cipher, values of the [ENCRYPTED_TABLES] are encrypted. A database must always be
+    /// opened with the cipher it was first opened with, and existing plaintext data can not be
+    /// encrypted.
+    pub fn open_with_cipher<P: AsRef<Path>>(
+        path: P,
+        cipher: Option<StorageCipher>,
+    ) -> eyre::Result<Self> {
         let env = Environment::builder()
             .set_max_dbs(32)
             .write_map()
@@ -154,6 +204,31 @@ impl MdbxDatabase {
             })
             .open(path.as_ref())?;
 
+        let db = Self::with_metrics(env, cipher);
+        db.check_cipher()?;
+        Ok(db)
+    }
+
+    /// Opens an existing database at the specified path without write access.
+    ///
+    /// Tables must already exist, [`Self::open_table`] fails for a read-only database. An
+    /// encrypted database must be opened with its cipher.
+    pub fn open_read_only<P: AsRef<Path>>(
+        path: P,
+        cipher: Option<StorageCipher>,
+    ) -> eyre::Result<Self> {
+        let env = Environment::builder()
+            .set_max_dbs(32)
+            .set_flags(EnvironmentFlags { mode: Mode::ReadOnly, ..Default::default() })
+            .open(path.as_ref())?;
+
+        let db = Self::with_metrics(env, cipher);
+        db.check_cipher()?;
+        Ok(db)
+    }
+
+    /// Wrap the environment and spawn the thread that reports its metrics.
+    fn with_metrics(env: Environment, cipher: Option<StorageCipher>) -> Self {
         let (shutdown_tx, rx) = mpsc::sync_channel::<()>(0);
 
         let db_cloned = env.clone();
@@ -183,7 +258,74 @@ impl MdbxDatabase {
             tracing::info!(target: "telcoin::mdbx", "Ending MDBX metrics thread");
         });
 
-        Ok(MdbxDatabase { inner: env, shutdown_tx: Arc::new(shutdown_tx) })
+        MdbxDatabase {
+            inner: env,
+            shutdown_tx: Arc::new(shutdown_tx),
+            cipher: cipher.map(Arc::new),
+        }
+    }
+
+    /// Ensure the cipher matches the one the database was encrypted with.
+    ///
+    /// Records the check value the first time the database is opened with a cipher.
+    fn check_cipher(&self) -> eyre::Result<()> {
+        let key_check = {
+            let txn = self.inner.begin_ro_txn()?;
+            match txn.open_db(Some(ENCRYPTION_CHECK_DB)) {
+                Ok(db) => txn.get::<Vec<u8>>(db.dbi(), ENCRYPTION_CHECK_KEY)?,
+                Err(_) => None,
+            }
+        };
+        match (self.cipher.as_deref(), key_check) {
+            (None, None) => Ok(()),
+            (None, Some(_)) => {
+                eyre::bail!("database is encrypted, a storage encryption secret is required")
+            }
+            (Some(cipher), Some(key_check)) => {
+                eyre::ensure!(
+                    cipher.decrypt(ENCRYPTION_CHECK_DB, ENCRYPTION_CHECK_KEY, &key_check)?
+                        == KEY_CHECK,
+                    "database was encrypted with a different storage encryption secret"
+                );
+                Ok(())
+            }
+            (Some(cipher), None) => {
+                for table in ENCRYPTED_TABLES {
+                    eyre::ensure!(
+                        self.is_table_empty(table)?,
+                        "database has unencrypted {table} data and can not be encrypted"
+                    );
+                }
+                let txn = self.inner.begin_rw_txn()?;
+                let db = txn.create_db(Some(ENCRYPTION_CHECK_DB), DatabaseFlags::default())?;
+                txn.put(
+                    db.dbi(),
+                    ENCRYPTION_CHECK_KEY,
+                    cipher.encrypt(ENCRYPTION_CHECK_DB, ENCRYPTION_CHECK_KEY, KEY_CHECK),
+                    WriteFlags::UPSERT,
+                )?;
+                txn.commit()?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Return true if the table exists.
+    ///
+    /// Tables are created when a writable database is opened, so a read-only database written by
+    /// an older version of the node may be missing newer tables.
+    pub fn has_table(&self, table: &str) -> eyre::Result<bool> {
+        let txn = self.inner.begin_ro_txn()?;
+        Ok(txn.open_db(Some(table)).is_ok())
+    }
+
+    /// Return true if the table does not exist or has no records.
+    fn is_table_empty(&self, table: &str) -> eyre::Result<bool> {
+        let txn = self.inner.begin_ro_txn()?;
+        let Ok(db) = txn.open_db(Some(table)) else {
+            return Ok(true);
+        };
+        Ok(txn.cursor_with_dbi(db.dbi())?.first::<Vec<u8>, Vec<u8>>()?.is_none())
     }
 
     pub fn open_table<T: Table>(&self) -> eyre::Result<()> {
@@ -206,11 +348,11 @@ impl Database for MdbxDatabase {
         Self: 'txn;
 
     fn read_txn(&self) -> eyre::Result<Self::TX<'_>> {
-        Ok(MdbxTx { inner: self.inner.begin_ro_txn()? })
+        Ok(MdbxTx { inner: self.inner.begin_ro_txn()?, cipher: self.cipher.clone() })
     }
 
     fn write_txn(&self) -> eyre::Result<Self::TXMut<'_>> {
-        Ok(MdbxTxMut { inner: self.inner.begin_rw_txn()? })
+        Ok(MdbxTxMut { inner: self.inner.begin_rw_txn()?, cipher: self.cipher.clone() })
     }
 
     fn contains_key<T: Table>(&self, key: &T::Key) -> eyre::Result<bool> {
@@ -252,7 +394,8 @@ impl Database for MdbxDatabase {
             .expect("Failed to get cursor!")
             .cursor::<T>()
             .expect("Failed to get cursor!");
-        Box::new(MdbxIter { cursor, _key: PhantomData, _val: PhantomData })
+        let cipher = table_cipher::<T>(&self.cipher);
+        Box::new(MdbxIter { cursor, cipher, table: T::NAME, _key: PhantomData, _val: PhantomData })
     }
 
     fn skip_to<T: Table>(&self, key: &T::Key) -> eyre::Result<DBIter<'_, T>> {
@@ -261,7 +404,8 @@ impl Database for MdbxDatabase {
             .expect("Failed to get cursor!")
             .cursor::<T>()
             .expect("Failed to get cursor!");
-        let i = MdbxIter { cursor, _key: PhantomData, _val: PhantomData };
+        let cipher = table_cipher::<T>(&self.cipher);
+        let i = MdbxIter { cursor, cipher, table: T::NAME, _key: PhantomData, _val: PhantomData };
         let key = key.clone();
         Ok(Box::new(i.skip_while(move |(k, _)| k < &key)))
     }
@@ -272,7 +416,15 @@ impl Database for MdbxDatabase {
             .expect("Failed to get cursor!")
             .cursor::<T>()
             .expect("Failed to get cursor!");
-        Box::new(MdbxRevIter { cursor, started: false, _key: PhantomData, _val: PhantomData })
+        let cipher = table_cipher::<T>(&self.cipher);
+        Box::new(MdbxRevIter {
+            cursor,
+            cipher,
+            table: T::NAME,
+            started: false,
+            _key: PhantomData,
+            _val: PhantomData,
+        })
     }
 
     fn record_prior_to<T: Table>(&self, key: &T::Key) -> Option<(T::Key, T::Value)> {
@@ -287,13 +439,10 @@ impl Database for MdbxDatabase {
     }
 
     fn last_record<T: Table>(&self) -> Option<(T::Key, T::Value)> {
-        self.read_txn()
-            .ok()?
-            .cursor::<T>()
-            .ok()?
-            .last::<Vec<u8>, Vec<u8>>()
-            .ok()?
-            .map(|(k, v)| (decode_key::<T::Key>(&k), decode::<T::Value>(&v)))
+        let cipher = table_cipher::<T>(&self.cipher);
+        let (k, v) =
+            self.read_txn().ok()?.cursor::<T>().ok()?.last::<Vec<u8>, Vec<u8>>().ok()??;
+        decode_record(cipher.as_deref(), T::NAME, &k, &v)
     }
 }
 
@@ -303,6 +452,10 @@ where
     V: ValueT,
 {
     cursor: Cursor<RO>,
+    /// The cipher for the table's values, if they are encrypted.
+    cipher: Option<Arc<StorageCipher>>,
+    /// The name of the table, authenticated with encrypted values.
+    table: &'static str,
     _key: PhantomData<K>,
     _val: PhantomData<V>,
 }
@@ -315,11 +468,8 @@ where
     type Item = (K, V);
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Ok(result) = self.cursor.next::<Vec<u8>, Vec<u8>>() {
-            result.map(|(k, v)| (decode_key::<K>(&k), decode::<V>(&v)))
-        } else {
-            None
-        }
+        let (k, v) = self.cursor.next::<Vec<u8>, Vec<u8>>().ok()??;
+        decode_record(self.cipher.as_deref(), self.table, &k, &v)
     }
 }
 
@@ -329,6 +479,10 @@ where
     V: ValueT,
 {
     cursor: Cursor<RO>,
+    /// The cipher for the table's values, if they are encrypted.
+    cipher: Option<Arc<StorageCipher>>,
+    /// The name of the table, authenticated with encrypted values.
+    table: &'static str,
     started: bool,
     _key: PhantomData<K>,
     _val: PhantomData<V>,
@@ -342,28 +496,28 @@ where
     type Item = (K, V);
 
     fn next(&mut self) -> Option<Self::Item> {
-        if !self.started {
-            self.started = true;
-            return self
-                .cursor
-                .last::<Vec<u8>, Vec<u8>>()
-                .ok()?
-                .map(|(k, v)| (decode_key::<K>(&k), decode::<V>(&v)));
-        }
-        if let Ok(result) = self.cursor.prev::<Vec<u8>, Vec<u8>>() {
-            result.map(|(k, v)| (decode_key::<K>(&k), decode::<V>(&v)))
+        let (k, v) = if self.started {
+            self.cursor.prev::<Vec<u8>, Vec<u8>>().ok()??
         } else {
-            None
-        }
+            self.started = true;
+            self.cursor.last::<Vec<u8>, Vec<u8>>().ok()??
+        };
+        decode_record(self.cipher.as_deref(), self.table, &k, &v)
     }
 }
 
 #[cfg(test)]
 mod test {
     use super::MdbxDatabase;
-    use crate::test::*;
+    use crate::{
+        encryption::{StorageCipher, MIN_SECRET_LEN},
+        tables::Batches,
+        test::*,
+    };
+    use reth_libmdbx::WriteFlags;
     use std::path::Path;
     use tempfile::tempdir;
+    use tn_types::{encode_key, BlockHash, Database as _, Table as _};
 
     fn open_db(path: &Path) -> MdbxDatabase {
         let db = MdbxDatabase::open(path).expect("Cannot open database");
@@ -464,6 +618,58 @@ mod test {
         test_multi_remove(db)
     }
 
+    #[test]
+    fn test_mdbx_encrypted() {
+        let temp_dir = tempdir().expect("failed to create temp dir");
+        let secret = [3; MIN_SECRET_LEN];
+        let cipher = || Some(StorageCipher::from_secret(&secret).expect("valid secret"));
+        let batch = tn_test_utils::batch();
+        let digest = batch.digest();
+
+        let db = MdbxDatabase::open_with_cipher(temp_dir.path(), cipher()).expect("db opened");
+        db.open_table::<Batches>().expect("failed to open table!");
+        db.insert::<Batches>(&digest, &batch).expect("batch inserted");
+        drop(db);
+
+        // the database must be reopened with the same secret
+        let db = MdbxDatabase::open_with_cipher(temp_dir.path(), cipher()).expect("db reopened");
+        assert_eq!(db.get::<Batches>(&digest).expect("batch read"), Some(batch.clone()));
+        assert_eq!(db.iter::<Batches>().next(), Some((digest, batch.clone())));
+        assert_eq!(db.last_record::<Batches>(), Some((digest, batch.clone())));
+        drop(db);
+        assert!(MdbxDatabase::open(temp_dir.path()).is_err());
+        let other = StorageCipher::from_secret(&[4; MIN_SECRET_LEN]).expect("valid secret");
+        assert!(MdbxDatabase::open_with_cipher(temp_dir.path(), Some(other)).is_err());
+        let db = MdbxDatabase::open_read_only(temp_dir.path(), cipher()).expect("db opened");
+        assert_eq!(db.get::<Batches>(&digest).expect("batch read"), Some(batch.clone()));
+        drop(db);
+
+        // a value copied to another row fails to read instead of panicking
+        let db = MdbxDatabase::open_with_cipher(temp_dir.path(), cipher()).expect("db reopened");
+        let moved = BlockHash::ZERO;
+        let txn = db.inner.begin_rw_txn().expect("write txn");
+        let dbi = txn.open_db(Some(Batches::NAME)).expect("batches table").dbi();
+        let raw = txn.get::<Vec<u8>>(dbi, &encode_key(&digest)).expect("raw read").expect("row");
+        txn.put(dbi, encode_key(&moved), raw, WriteFlags::UPSERT).expect("raw write");
+        txn.commit().expect("commit");
+        assert!(db.get::<Batches>(&moved).is_err());
+        // iterators end at the bad row
+        assert_eq!(db.iter::<Batches>().count(), 0);
+        assert_eq!(db.reverse_iter::<Batches>().collect::<Vec<_>>(), vec![(digest, batch.clone())]);
+        assert_eq!(db.last_record::<Batches>(), Some((digest, batch.clone())));
+        db.remove::<Batches>(&digest).expect("batch removed");
+        assert_eq!(db.last_record::<Batches>(), None);
+        drop(db);
+
+        // unencrypted data can not be encrypted in place
+        let temp_dir = tempdir().expect("failed to create temp dir");
+        let db = MdbxDatabase::open(temp_dir.path()).expect("db opened");
+        db.open_table::<Batches>().expect("failed to open table!");
+        db.insert::<Batches>(&digest, &batch).expect("batch inserted");
+        drop(db);
+        assert!(MdbxDatabase::open_with_cipher(temp_dir.path(), cipher()).is_err());
+    }
+
     #[test]
     fn test_mdbx_dbsimpbench() {
         // Init a DB
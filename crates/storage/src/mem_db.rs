@@ -153,6 +153,20 @@ impl MemDatabase {
             }
         }
     }
+
+    /// Replace every record of the table at once.
+    ///
+    /// Readers see either the old or the new records, never a partially loaded table.
+    pub fn replace_table<T: Table>(&self, records: impl IntoIterator<Item = (T::Key, T::Value)>) {
+        let records: BTreeMap<_, _> =
+            records.into_iter().map(|(key, value)| (encode_key(&key), encode(&value))).collect();
+        if !self.store.contains_key(T::NAME) {
+            self.open_table::<T>();
+        }
+        if let Some(table) = self.store.get(T::NAME) {
+            *table.write() = records;
+        }
+    }
 }
 
 impl Default for MemDatabase {
@@ -167,6 +181,19 @@ impl Default for MemDatabase {
         db.open_table::<crate::tables::Batches>();
         db.open_table::<crate::tables::ConsensusBlocks>();
         db.open_table::<crate::tables::ConsensusBlockNumbersByDigest>();
+        db.open_table::<crate::tables::SubDagStatsByNumber>();
+        db.open_table::<crate::tables::SchemaVersion>();
+        db.open_table::<crate::tables::BatchPruneCursor>();
+        db.open_table::<crate::tables::BatchReferences>();
+        db.open_table::<crate::tables::EpochSummaries>();
+        db.open_table::<crate::tables::Committees>();
+        db.open_table::<crate::tables::PeerEvents>();
+        db.open_table::<crate::tables::PeerLatencies>();
+        db.open_table::<crate::tables::TransactionsBySender>();
+        db.open_table::<crate::tables::TransactionsByRecipient>();
+        db.open_table::<crate::tables::StreamOffsets>();
+        db.open_table::<crate::tables::AddressIndexCursor>();
+        db.open_table::<crate::tables::HaltTarget>();
         db
     }
 }
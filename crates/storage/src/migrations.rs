@@ -0,0 +1,687 @@
+//! Versioned schema migrations for the node's databases.
+//!
+//! Every store records the version of its schema. On startup the node runs the migrations
+//! between the recorded version and the version this software writes, and refuses to open a
+//! store written by a newer version. Data written before versioning was introduced is version 0.
+
+use crate::tables::{Certificates, ConsensusBlocks, LastProposed, PeerLatencies, SchemaVersion};
+use tn_types::{Database, DbTxMut as _, Table};
+use tracing::info;
+
+/// The schema version of the consensus DB written by this software.
+pub const CONSENSUS_SCHEMA_VERSION: u64 = 4;
+
+/// The key of the schema version in the [SchemaVersion] table.
+const SCHEMA_VERSION_KEY: u8 = 0;
+
+/// The number of records read at a time while rewriting a table.
+const MIGRATION_CHUNK: usize = 1000;
+
+/// A step that upgrades a store's schema from `version - 1` to `version`.
+pub struct Migration<S: ?Sized> {
+    /// The schema version after the migration.
+    pub version: u64,
+    /// What the migration changes, for logs and `db migrate --check`.
+    pub description: &'static str,
+    /// Apply the migration.
+    pub apply: fn(&S) -> eyre::Result<()>,
+}
+
+impl<S: ?Sized> std::fmt::Debug for Migration<S> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Migration")
+            .field("version", &self.version)
+            .field("description", &self.description)
+            .finish()
+    }
+}
+
+/// A store with a versioned schema.
+pub trait VersionedStore {
+    /// The name of the store in logs and errors.
+    const NAME: &'static str;
+    /// The schema version written by this software.
+    const SCHEMA_VERSION: u64;
+
+    /// The recorded schema version, `None` if the store predates versioning.
+    fn schema_version(&self) -> eyre::Result<Option<u64>>;
+
+    /// Record the schema version.
+    fn set_schema_version(&self, version: u64) -> eyre::Result<()>;
+
+    /// The migrations to reach [Self::SCHEMA_VERSION], in any order.
+    fn migrations(&self) -> Vec<Migration<Self>>;
+}
+
+/// The result of migrating a store.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationReport {
+    /// The name of the store.
+    pub store: &'static str,
+    /// The schema version before migrating.
+    pub from: u64,
+    /// The schema version written by this software.
+    pub to: u64,
+    /// The version and description of each migration to apply, in order.
+    pub pending: Vec<(u64, &'static str)>,
+    /// True if the migrations were applied, false for a dry run.
+    pub applied: bool,
+}
+
+impl MigrationReport {
+    /// True if the store's schema is current.
+    pub fn is_current(&self) -> bool {
+        self.from == self.to
+    }
+}
+
+/// Bring a store's schema up to date.
+///
+/// With `check` set nothing is written and the report lists the pending migrations. Returns an
+/// error if the store was written by a newer version of the software.
+pub fn migrate<S: VersionedStore>(store: &S, check: bool) -> eyre::Result<MigrationReport> {
+    let from = store.schema_version()?.unwrap_or(0);
+    let to = S::SCHEMA_VERSION;
+    eyre::ensure!(
+        from <= to,
+        "{} schema version {from} is newer than version {to} supported by this node, upgrade the \
+         node software",
+        S::NAME
+    );
+
+    let mut migrations: Vec<_> =
+        store.migrations().into_iter().filter(|m| m.version > from && m.version <= to).collect();
+    migrations.sort_by_key(|m| m.version);
+    let pending = migrations.iter().map(|m| (m.version, m.description)).collect();
+    let mut report = MigrationReport { store: S::NAME, from, to, pending, applied: false };
+    if check || report.is_current() {
+        return Ok(report);
+    }
+
+    for migration in migrations {
+        info!(
+            target: "tn::storage",
+            store = S::NAME,
+            version = migration.version,
+            description = migration.description,
+            "applying migration"
+        );
+        (migration.apply)(store)?;
+        // record each step so an interrupted run resumes after the last applied migration
+        store.set_schema_version(migration.version)?;
+    }
+    store.set_schema_version(to)?;
+    report.applied = true;
+    info!(target: "tn::storage", store = S::NAME, from, to, "schema migrated");
+
+    Ok(report)
+}
+
+/// The consensus DB, with its schema version in the [SchemaVersion] table.
+#[derive(Debug, Clone)]
+pub struct ConsensusStore<DB> {
+    /// The consensus DB.
+    db: DB,
+}
+
+impl<DB> ConsensusStore<DB> {
+    /// Create a new instance of [Self].
+    pub fn new(db: DB) -> Self {
+        Self { db }
+    }
+
+    /// The consensus DB.
+    pub fn db(&self) -> &DB {
+        &self.db
+    }
+}
+
+impl<DB: Database> VersionedStore for ConsensusStore<DB> {
+    const NAME: &'static str = "consensus";
+    const SCHEMA_VERSION: u64 = CONSENSUS_SCHEMA_VERSION;
+
+    fn schema_version(&self) -> eyre::Result<Option<u64>> {
+        self.db.get::<SchemaVersion>(&SCHEMA_VERSION_KEY)
+    }
+
+    fn set_schema_version(&self, version: u64) -> eyre::Result<()> {
+        self.db.insert::<SchemaVersion>(&SCHEMA_VERSION_KEY, &version)
+    }
+
+    fn migrations(&self) -> Vec<Migration<Self>> {
+        // version 1 records the schema version of existing data and changes nothing else
+        vec![
+            Migration {
+                version: 2,
+                description: "record protocol withdrawals in consensus headers",
+                apply: add_header_withdrawals::<DB>,
+            },
+            Migration {
+                version: 3,
+                description: "record payload roots in headers",
+                apply: add_payload_roots::<DB>,
+            },
+            Migration {
+                version: 4,
+                description: "record when peer latencies were sampled",
+                apply: clear_peer_latencies::<DB>,
+            },
+        ]
+    }
+}
+
+/// Rewrite every consensus header with an empty list of withdrawals.
+///
+/// The headers are rewritten in one transaction so an interrupted migration leaves the old format.
+/// Digests are unchanged because headers without withdrawals hash the same as before.
+fn add_header_withdrawals<DB: Database>(store: &ConsensusStore<DB>) -> eyre::Result<()> {
+    let db = store.db();
+    let mut txn = db.write_txn()?;
+    rewrite_table::<v1::ConsensusBlocks, v2::ConsensusBlocks, _>(db, &mut txn, |header| {
+        Ok(header.into())
+    })?;
+    txn.commit()
+}
+
+/// Rewrite every stored header, certificate and consensus header with the payload root.
+///
+/// The tables are rewritten in one transaction so an interrupted migration leaves the old format.
+/// Digests are unchanged because headers hash the same as before until the payload root fork.
+fn add_payload_roots<DB: Database>(store: &ConsensusStore<DB>) -> eyre::Result<()> {
+    let db = store.db();
+    let mut txn = db.write_txn()?;
+    rewrite_table::<v2::LastProposed, LastProposed, _>(db, &mut txn, |header| Ok(header.into()))?;
+    rewrite_table::<v2::Certificates, Certificates, _>(db, &mut txn, v2::Certificate::upgrade)?;
+    rewrite_table::<v2::ConsensusBlocks, ConsensusBlocks, _>(
+        db,
+        &mut txn,
+        v2::ConsensusHeader::upgrade,
+    )?;
+    txn.commit()
+}
+
+/// Drop the saved peer latencies.
+///
+/// Version 3 saved bare round trip times without the time they were sampled, so their age is
+/// unknown. The worker's latency probes sample every peer again shortly after startup.
+fn clear_peer_latencies<DB: Database>(store: &ConsensusStore<DB>) -> eyre::Result<()> {
+    let mut txn = store.db().write_txn()?;
+    txn.clear_table::<PeerLatencies>()?;
+    txn.commit()
+}
+
+/// Rewrite every record of the `Old` table in the format of the `New` table.
+///
+/// Records are read from the committed data in chunks and written with `txn`.
+fn rewrite_table<'a, Old, New, DB>(
+    db: &'a DB,
+    txn: &mut DB::TXMut<'a>,
+    convert: impl Fn(Old::Value) -> eyre::Result<New::Value>,
+) -> eyre::Result<()>
+where
+    Old: Table,
+    New: Table<Key = Old::Key>,
+    DB: Database,
+{
+    let mut last: Option<Old::Key> = None;
+    loop {
+        // read a chunk from the committed data before writing the new format
+        let chunk: Vec<_> = match &last {
+            None => db.iter::<Old>().take(MIGRATION_CHUNK).collect(),
+            Some(last) => db
+                .skip_to::<Old>(last)?
+                .skip_while(|(key, _)| key == last)
+                .take(MIGRATION_CHUNK)
+                .collect(),
+        };
+        let Some((key, _)) = chunk.last() else { break };
+        last = Some(key.clone());
+        for (key, value) in chunk {
+            txn.insert::<New>(&key, &convert(value)?)?;
+        }
+    }
+    Ok(())
+}
+
+/// Tables and types as written by schema version 1.
+mod v1 {
+    use super::v2;
+    use serde::{Deserialize, Serialize};
+    use tn_types::{Withdrawals, B256};
+
+    /// A consensus header before withdrawals were recorded.
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(super) struct ConsensusHeader {
+        pub(super) parent_hash: B256,
+        pub(super) sub_dag: v2::CommittedSubDag<v2::Header>,
+        pub(super) number: u64,
+        pub(super) extra: B256,
+    }
+
+    impl From<ConsensusHeader> for v2::ConsensusHeader {
+        fn from(header: ConsensusHeader) -> Self {
+            let ConsensusHeader { parent_hash, sub_dag, number, extra } = header;
+            Self { parent_hash, sub_dag, number, extra, withdrawals: Withdrawals::default() }
+        }
+    }
+
+    /// The consensus chain table with version 1 headers.
+    #[derive(Debug)]
+    pub(super) struct ConsensusBlocks {}
+
+    impl tn_types::Table for ConsensusBlocks {
+        type Key = u64;
+        type Value = ConsensusHeader;
+
+        const NAME: &'static str = crate::CONSENSUS_BLOCK_CF;
+    }
+}
+
+/// Tables and types as written by schema version 2.
+///
+/// Certificates and sub dags are generic over the header, with the current header they have the
+/// layout of the current types.
+mod v2 {
+    use crate::ProposerKey;
+    use serde::{Deserialize, Serialize};
+    use std::collections::BTreeSet;
+    use tn_types::{
+        encode, try_decode, AuthorityIdentifier, BlockHash, BlockNumHash, CertificateDigest, Epoch,
+        ReputationScores, Round, SignatureVerificationState, TimestampSec, Withdrawals, WorkerId,
+        B256,
+    };
+
+    /// A header before the payload root was recorded.
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(super) struct Header {
+        pub(super) author: AuthorityIdentifier,
+        pub(super) round: Round,
+        pub(super) epoch: Epoch,
+        pub(super) created_at: TimestampSec,
+        pub(super) payload: Vec<(BlockHash, (WorkerId, TimestampSec))>,
+        pub(super) parents: BTreeSet<CertificateDigest>,
+        pub(super) latest_execution_block: BlockNumHash,
+    }
+
+    impl From<Header> for tn_types::Header {
+        fn from(header: Header) -> Self {
+            let Header {
+                author,
+                round,
+                epoch,
+                created_at,
+                payload,
+                parents,
+                latest_execution_block,
+            } = header;
+            Self::new_at(
+                author,
+                round,
+                epoch,
+                payload.into_iter().collect(),
+                parents,
+                latest_execution_block,
+                created_at,
+            )
+        }
+    }
+
+    /// A certificate for a header of type `H`.
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(super) struct Certificate<H> {
+        pub(super) header: H,
+        pub(super) signature_verification_state: SignatureVerificationState,
+        /// The serialized bitmap of the authorities that signed, encoded as bytes.
+        pub(super) signed_authorities: Vec<u8>,
+        pub(super) created_at: TimestampSec,
+    }
+
+    impl Certificate<Header> {
+        /// The certificate with the current header.
+        fn with_payload_root(self) -> Certificate<tn_types::Header> {
+            let Self { header, signature_verification_state, signed_authorities, created_at } =
+                self;
+            Certificate {
+                header: header.into(),
+                signature_verification_state,
+                signed_authorities,
+                created_at,
+            }
+        }
+
+        /// The certificate in the current format.
+        pub(super) fn upgrade(self) -> eyre::Result<tn_types::Certificate> {
+            // the current certificate's signatures are private, convert through its encoding
+            Ok(try_decode(&encode(&self.with_payload_root()))?)
+        }
+    }
+
+    /// A committed sub dag of certificates for headers of type `H`.
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(super) struct CommittedSubDag<H> {
+        pub(super) certificates: Vec<Certificate<H>>,
+        pub(super) leader: Certificate<H>,
+        pub(super) reputation_score: ReputationScores,
+        pub(super) commit_timestamp: TimestampSec,
+    }
+
+    impl CommittedSubDag<Header> {
+        /// The sub dag in the current format.
+        fn upgrade(self) -> eyre::Result<tn_types::CommittedSubDag> {
+            let Self { certificates, leader, reputation_score, commit_timestamp } = self;
+            let sub_dag = CommittedSubDag {
+                certificates: certificates
+                    .into_iter()
+                    .map(Certificate::with_payload_root)
+                    .collect(),
+                leader: leader.with_payload_root(),
+                reputation_score,
+                commit_timestamp,
+            };
+            // the current commit timestamp is private, convert through its encoding
+            Ok(try_decode(&encode(&sub_dag))?)
+        }
+    }
+
+    /// A consensus header with version 2 certificates.
+    #[derive(Debug, Clone, Serialize, Deserialize)]
+    pub(super) struct ConsensusHeader {
+        pub(super) parent_hash: B256,
+        pub(super) sub_dag: CommittedSubDag<Header>,
+        pub(super) number: u64,
+        pub(super) extra: B256,
+        pub(super) withdrawals: Withdrawals,
+    }
+
+    impl ConsensusHeader {
+        /// The consensus header in the current format.
+        pub(super) fn upgrade(self) -> eyre::Result<tn_types::ConsensusHeader> {
+            let Self { parent_hash, sub_dag, number, extra, withdrawals } = self;
+            Ok(tn_types::ConsensusHeader {
+                parent_hash,
+                sub_dag: sub_dag.upgrade()?,
+                number,
+                extra,
+                withdrawals,
+            })
+        }
+    }
+
+    /// The last proposed header table with version 2 headers.
+    #[derive(Debug)]
+    pub(super) struct LastProposed {}
+
+    impl tn_types::Table for LastProposed {
+        type Key = ProposerKey;
+        type Value = Header;
+
+        const NAME: &'static str = crate::LAST_PROPOSED_CF;
+    }
+
+    /// The certificates table with version 2 headers.
+    #[derive(Debug)]
+    pub(super) struct Certificates {}
+
+    impl tn_types::Table for Certificates {
+        type Key = CertificateDigest;
+        type Value = Certificate<Header>;
+
+        const NAME: &'static str = crate::CERTIFICATES_CF;
+    }
+
+    /// The consensus chain table with version 2 headers.
+    #[derive(Debug)]
+    pub(super) struct ConsensusBlocks {}
+
+    impl tn_types::Table for ConsensusBlocks {
+        type Key = u64;
+        type Value = ConsensusHeader;
+
+        const NAME: &'static str = crate::CONSENSUS_BLOCK_CF;
+    }
+}
+
+/// Tables as written by schema version 3.
+mod v3 {
+    use tn_types::AuthorityIdentifier;
+
+    /// The peer latencies table with round trip times in microseconds.
+    #[derive(Debug)]
+    pub(super) struct PeerLatencies {}
+
+    impl tn_types::Table for PeerLatencies {
+        type Key = AuthorityIdentifier;
+        type Value = u64;
+
+        const NAME: &'static str = crate::PEER_LATENCIES_CF;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mem_db::MemDatabase;
+    use std::{
+        collections::BTreeSet,
+        sync::atomic::{AtomicU64, Ordering},
+    };
+    use tn_types::{
+        decode, encode, payload_root, AuthorityIdentifier, BlockHash, Certificate,
+        CertificateDigest, CommittedSubDag, ConsensusHeader, Hash as _, Header, ReputationScores,
+        B256,
+    };
+
+    /// A store with two migrations that counts how many ran.
+    struct TestStore {
+        version: AtomicU64,
+        applied: AtomicU64,
+    }
+
+    impl TestStore {
+        fn new(version: u64) -> Self {
+            Self { version: AtomicU64::new(version), applied: AtomicU64::new(0) }
+        }
+    }
+
+    fn count(store: &TestStore) -> eyre::Result<()> {
+        store.applied.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    impl VersionedStore for TestStore {
+        const NAME: &'static str = "test";
+        const SCHEMA_VERSION: u64 = 3;
+
+        fn schema_version(&self) -> eyre::Result<Option<u64>> {
+            Ok(Some(self.version.load(Ordering::Relaxed)))
+        }
+
+        fn set_schema_version(&self, version: u64) -> eyre::Result<()> {
+            self.version.store(version, Ordering::Relaxed);
+            Ok(())
+        }
+
+        fn migrations(&self) -> Vec<Migration<Self>> {
+            vec![
+                Migration { version: 3, description: "third", apply: count },
+                Migration { version: 2, description: "second", apply: count },
+            ]
+        }
+    }
+
+    #[test]
+    fn test_migrate() {
+        let store = TestStore::new(1);
+        let report = migrate(&store, true).expect("dry run");
+        assert_eq!(report.pending, vec![(2, "second"), (3, "third")]);
+        assert!(!report.applied);
+        assert_eq!(store.applied.load(Ordering::Relaxed), 0);
+        assert_eq!(store.schema_version().unwrap(), Some(1));
+
+        let report = migrate(&store, false).expect("migrated");
+        assert!(report.applied);
+        assert_eq!(store.applied.load(Ordering::Relaxed), 2);
+        assert_eq!(store.schema_version().unwrap(), Some(3));
+
+        // only pending migrations run
+        let store = TestStore::new(2);
+        let report = migrate(&store, false).expect("migrated");
+        assert_eq!(report.pending, vec![(3, "third")]);
+        assert_eq!(store.applied.load(Ordering::Relaxed), 1);
+        assert!(migrate(&store, false).expect("current").is_current());
+    }
+
+    #[test]
+    fn test_refuse_newer_schema() {
+        let store = TestStore::new(4);
+        assert!(migrate(&store, true).is_err());
+        assert!(migrate(&store, false).is_err());
+        assert_eq!(store.schema_version().unwrap(), Some(4));
+    }
+
+    #[test]
+    fn test_consensus_schema_version() {
+        let db = ConsensusStore::new(MemDatabase::default());
+        assert_eq!(db.schema_version().unwrap(), None);
+
+        let report = migrate(&db, false).expect("migrated");
+        assert_eq!(report.store, "consensus");
+        assert_eq!((report.from, report.to), (0, CONSENSUS_SCHEMA_VERSION));
+        assert_eq!(db.schema_version().unwrap(), Some(CONSENSUS_SCHEMA_VERSION));
+
+        db.set_schema_version(CONSENSUS_SCHEMA_VERSION + 1).unwrap();
+        assert!(migrate(&db, false).is_err());
+    }
+
+    #[test]
+    fn test_migrate_header_withdrawals() {
+        let store = ConsensusStore::new(MemDatabase::default());
+        let db = store.db();
+        store.set_schema_version(1).unwrap();
+        let mut digests = Vec::new();
+        for number in 0..3 {
+            let header = ConsensusHeader {
+                number,
+                parent_hash: B256::with_last_byte(1),
+                ..Default::default()
+            };
+            digests.push(header.digest());
+            db.insert::<v1::ConsensusBlocks>(&number, &v1_header(&header)).unwrap();
+        }
+
+        let report = migrate(&store, false).expect("migrated");
+        assert_eq!(report.pending.iter().map(|(v, _)| *v).collect::<Vec<_>>(), vec![2, 3, 4]);
+        for (number, digest) in digests.into_iter().enumerate() {
+            let header = db.get::<ConsensusBlocks>(&(number as u64)).unwrap().expect("header");
+            assert!(header.withdrawals.is_empty());
+            assert_eq!(header.digest(), digest);
+        }
+    }
+
+    #[test]
+    fn test_migrate_payload_roots() {
+        let store = ConsensusStore::new(MemDatabase::default());
+        let db = store.db();
+        store.set_schema_version(2).unwrap();
+        let header = Header::new_at(
+            AuthorityIdentifier::dummy_for_test(1),
+            4,
+            1,
+            [(BlockHash::with_last_byte(1), (0, 10)), (BlockHash::with_last_byte(2), (0, 11))]
+                .into_iter()
+                .collect(),
+            BTreeSet::from([CertificateDigest::new([3; 32])]),
+            Default::default(),
+            12,
+        );
+        let mut certificate = Certificate::default();
+        certificate.header = header.clone();
+        let sub_dag = CommittedSubDag::new(
+            vec![certificate.clone()],
+            certificate.clone(),
+            1,
+            ReputationScores::default(),
+            None,
+        );
+        let consensus_header = ConsensusHeader { number: 1, sub_dag, ..Default::default() };
+        db.insert::<v2::LastProposed>(&0, &v2_header(&header)).unwrap();
+        db.insert::<v2::Certificates>(&certificate.digest(), &v2_certificate(&certificate))
+            .unwrap();
+        db.insert::<v2::ConsensusBlocks>(&1, &v2_consensus_header(&consensus_header)).unwrap();
+
+        let report = migrate(&store, false).expect("migrated");
+        assert_eq!(report.pending.iter().map(|(v, _)| *v).collect::<Vec<_>>(), vec![3, 4]);
+
+        let migrated = db.get::<LastProposed>(&0).unwrap().expect("header");
+        assert_eq!(*migrated.payload_root(), payload_root(header.payload().keys()));
+        assert_eq!(migrated.digest(), header.digest());
+        let migrated = db.get::<Certificates>(&certificate.digest()).unwrap().expect("cert");
+        assert_eq!(encode(&migrated), encode(&certificate));
+        let migrated = db.get::<ConsensusBlocks>(&1).unwrap().expect("consensus header");
+        assert_eq!(encode(&migrated), encode(&consensus_header));
+        assert_eq!(migrated.digest(), consensus_header.digest());
+    }
+
+    #[test]
+    fn test_migrate_peer_latencies() {
+        let store = ConsensusStore::new(MemDatabase::default());
+        let db = store.db();
+        store.set_schema_version(3).unwrap();
+        db.insert::<v3::PeerLatencies>(&AuthorityIdentifier::dummy_for_test(1), &30_000).unwrap();
+
+        let report = migrate(&store, false).expect("migrated");
+        assert_eq!(report.pending.iter().map(|(v, _)| *v).collect::<Vec<_>>(), vec![4]);
+        assert!(db.is_empty::<PeerLatencies>());
+    }
+
+    fn v1_header(header: &ConsensusHeader) -> v1::ConsensusHeader {
+        v1::ConsensusHeader {
+            parent_hash: header.parent_hash,
+            sub_dag: v2_sub_dag(&header.sub_dag),
+            number: header.number,
+            extra: header.extra,
+        }
+    }
+
+    fn v2_header(header: &Header) -> v2::Header {
+        v2::Header {
+            author: header.author.clone(),
+            round: header.round,
+            epoch: header.epoch,
+            created_at: header.created_at,
+            payload: header.payload.iter().map(|(digest, batch)| (*digest, *batch)).collect(),
+            parents: header.parents.clone(),
+            latest_execution_block: header.latest_execution_block,
+        }
+    }
+
+    fn v2_certificate(certificate: &Certificate) -> v2::Certificate<v2::Header> {
+        // the generic certificate with the current header has the current layout
+        let certificate: v2::Certificate<Header> = decode(&encode(certificate));
+        v2::Certificate {
+            header: v2_header(&certificate.header),
+            signature_verification_state: certificate.signature_verification_state,
+            signed_authorities: certificate.signed_authorities,
+            created_at: certificate.created_at,
+        }
+    }
+
+    fn v2_sub_dag(sub_dag: &CommittedSubDag) -> v2::CommittedSubDag<v2::Header> {
+        v2::CommittedSubDag {
+            certificates: sub_dag.certificates.iter().map(v2_certificate).collect(),
+            leader: v2_certificate(&sub_dag.leader),
+            reputation_score: sub_dag.reputation_score.clone(),
+            commit_timestamp: sub_dag.commit_timestamp(),
+        }
+    }
+
+    fn v2_consensus_header(header: &ConsensusHeader) -> v2::ConsensusHeader {
+        v2::ConsensusHeader {
+            parent_hash: header.parent_hash,
+            sub_dag: v2_sub_dag(&header.sub_dag),
+            number: header.number,
+            extra: header.extra,
+            withdrawals: header.withdrawals.clone(),
+        }
+    }
+}
@@ -0,0 +1,95 @@
+//! NOTE: tests for this module are in test-utils storage_tests.rs to avoid circular dependancies.
+
+use crate::{
+    tables::{AddressIndexCursor, TransactionsByRecipient, TransactionsBySender},
+    StoreResult,
+};
+use tn_types::{Address, Database, DbTxMut, IndexedTransaction, Table};
+
+/// The key of the last indexed block in the [AddressIndexCursor] table.
+const ADDRESS_INDEX_CURSOR_KEY: u8 = 0;
+
+/// Executed transactions indexed by their sender and recipient.
+pub trait AddressIndexStore {
+    /// Index the executed transactions of the blocks up to and including `last_block` by sender
+    /// and recipient.
+    ///
+    /// The transactions and the last indexed block are written atomically, so an index that
+    /// resumes after [Self::last_indexed_block] never misses or repeats a block.
+    fn index_transactions(
+        &self,
+        transactions: &[IndexedTransaction],
+        last_block: u64,
+    ) -> StoreResult<()>;
+
+    /// Return the last block with indexed transactions, `None` if no block was indexed.
+    fn last_indexed_block(&self) -> StoreResult<Option<u64>>;
+
+    /// Read up to `limit` transactions sent by or to `address` in execution order, starting with
+    /// the transaction at `(block_number, index)`.
+    fn read_transactions_by_address(
+        &self,
+        address: Address,
+        from: (u64, u32),
+        limit: usize,
+    ) -> StoreResult<Vec<IndexedTransaction>>;
+}
+
+impl<DB: Database> AddressIndexStore for DB {
+    fn index_transactions(
+        &self,
+        transactions: &[IndexedTransaction],
+        last_block: u64,
+    ) -> StoreResult<()> {
+        let mut txn = self.write_txn()?;
+        for tx in transactions {
+            txn.insert::<TransactionsBySender>(&(tx.from, tx.block_number, tx.index), tx)?;
+            if let Some(to) = tx.to {
+                txn.insert::<TransactionsByRecipient>(&(to, tx.block_number, tx.index), tx)?;
+            }
+        }
+        txn.insert::<AddressIndexCursor>(&ADDRESS_INDEX_CURSOR_KEY, &last_block)?;
+        txn.commit()
+    }
+
+    fn last_indexed_block(&self) -> StoreResult<Option<u64>> {
+        self.get::<AddressIndexCursor>(&ADDRESS_INDEX_CURSOR_KEY)
+    }
+
+    fn read_transactions_by_address(
+        &self,
+        address: Address,
+        from: (u64, u32),
+        limit: usize,
+    ) -> StoreResult<Vec<IndexedTransaction>> {
+        let mut transactions = read_address::<TransactionsBySender, _>(self, address, from, limit)?;
+        transactions
+            .extend(read_address::<TransactionsByRecipient, _>(self, address, from, limit)?);
+        transactions.sort_by_key(|tx| (tx.block_number, tx.index));
+        // transactions sent to the sender are in both tables
+        transactions.dedup_by_key(|tx| (tx.block_number, tx.index));
+        transactions.truncate(limit);
+        Ok(transactions)
+    }
+}
+
+/// Read up to `limit` transactions for `address` from one of the index tables.
+fn read_address<T, DB>(
+    db: &DB,
+    address: Address,
+    (block_number, index): (u64, u32),
+    limit: usize,
+) -> StoreResult<Vec<IndexedTransaction>>
+where
+    T: Table<Key = (Address, u64, u32), Value = IndexedTransaction>,
+    DB: Database,
+{
+    Ok(db
+        .skip_to::<T>(&(address, block_number, index))?
+        .take_while(|((key_address, _, _), _)| *key_address == address)
+        .take(limit)
+        .map(|(_, tx)| tx)
+        .collect())
+}
+
+// NOTE: tests for this module are in test-utils storage_tests.rs to avoid circular dependancies.
@@ -0,0 +1,82 @@
+//! Content-addressed storage of batches shared by the worker and the consensus chain.
+//!
+//! A batch is stored once under its digest no matter how often it is received, fetched or
+//! included in consensus output. Each committed consensus header holds a reference to the batches
+//! in its payload and a batch is only deleted when its last reference is released.
+//!
+//! NOTE: tests for this module are in test-utils storage_tests.rs to avoid circular dependancies.
+
+use crate::{
+    tables::{BatchReferences, Batches},
+    StoreResult,
+};
+use tn_types::{Batch, BlockHash, Database, DbTxMut};
+
+/// Store a batch under its digest unless it is already stored.
+///
+/// The digest commits to the batch contents so an existing batch is never rewritten. Returns true
+/// if the batch was written.
+pub fn insert_batch<TX: DbTxMut>(
+    txn: &mut TX,
+    digest: &BlockHash,
+    batch: &Batch,
+) -> StoreResult<bool> {
+    if txn.contains_key::<Batches>(digest)? {
+        return Ok(false);
+    }
+    txn.insert::<Batches>(digest, batch)?;
+    Ok(true)
+}
+
+/// Add a reference to each batch in `digests`.
+pub fn reference_batches<'a, TX: DbTxMut>(
+    txn: &mut TX,
+    digests: impl IntoIterator<Item = &'a BlockHash>,
+) -> StoreResult<()> {
+    for digest in digests {
+        let references = txn.get::<BatchReferences>(digest)?.unwrap_or_default();
+        txn.insert::<BatchReferences>(digest, &(references + 1))?;
+    }
+    Ok(())
+}
+
+/// Release a reference to a batch and delete the batch with its last reference.
+///
+/// Batches stored before references were counted have one implicit reference. Returns true if the
+/// batch was deleted.
+pub fn release_batch<TX: DbTxMut>(txn: &mut TX, digest: &BlockHash) -> StoreResult<bool> {
+    match txn.get::<BatchReferences>(digest)? {
+        Some(references) if references > 1 => {
+            txn.insert::<BatchReferences>(digest, &(references - 1))?;
+            Ok(false)
+        }
+        _ => {
+            txn.remove::<BatchReferences>(digest)?;
+            txn.remove::<Batches>(digest)?;
+            Ok(true)
+        }
+    }
+}
+
+/// Access the content-addressed batch store.
+pub trait BatchStore {
+    /// Store a batch under its digest unless it is already stored, see [insert_batch].
+    fn write_batch(&self, digest: &BlockHash, batch: &Batch) -> StoreResult<bool>;
+
+    /// The number of consensus headers referencing the batch.
+    fn batch_references(&self, digest: &BlockHash) -> StoreResult<u32>;
+}
+
+impl<DB: Database> BatchStore for DB {
+    fn write_batch(&self, digest: &BlockHash, batch: &Batch) -> StoreResult<bool> {
+        if self.contains_key::<Batches>(digest)? {
+            return Ok(false);
+        }
+        self.insert::<Batches>(digest, batch)?;
+        Ok(true)
+    }
+
+    fn batch_references(&self, digest: &BlockHash) -> StoreResult<u32> {
+        Ok(self.get::<BatchReferences>(digest)?.unwrap_or_default())
+    }
+}
@@ -1,4 +1,8 @@
 //! NOTE: tests for this module are in test-utils storage_tests.rs to avoid circular dependancies.
+//!
+//! Certificates are read through the DB's [tn_types::CertificateStoreCache] when it has one.
+//! Every write, delete and garbage collection updates the cache so it never serves a certificate
+//! the store no longer has.
 
 use std::{
     cmp::{max, Ordering},
@@ -13,7 +17,7 @@ use crate::{
     StoreResult, ROUNDS_TO_KEEP,
 };
 use tn_types::{
-    AuthorityIdentifier, Certificate, CertificateDigest, Database, DbTx, DbTxMut, Hash, Round,
+    AuthorityIdentifier, Certificate, CertificateDigest, Database, DbTxMut, Hash, Round,
 };
 use tn_utils::sync::notify_read::NotifyRead;
 
@@ -145,6 +149,24 @@ fn save_cert<TX: DbTxMut>(
     Ok(())
 }
 
+/// Read a certificate through the DB's certificate cache, if it has one.
+fn read_cert<DB: Database>(
+    db: &DB,
+    digest: &CertificateDigest,
+) -> StoreResult<Option<Certificate>> {
+    let Some(cache) = db.certificate_cache() else {
+        return db.get::<Certificates>(digest);
+    };
+    if let Some(certificate) = cache.get(digest) {
+        return Ok(Some(certificate));
+    }
+    let certificate = db.get::<Certificates>(digest)?;
+    if let Some(certificate) = &certificate {
+        cache.insert(*digest, certificate.clone());
+    }
+    Ok(certificate)
+}
+
 /// Deletes all certs for a round before round.
 fn gc_rounds<DB: Database>(db: &DB, target_round: Round) -> StoreResult<()> {
     if target_round <= ROUNDS_TO_KEEP {
@@ -165,6 +187,9 @@ fn gc_rounds<DB: Database>(db: &DB, target_round: Round) -> StoreResult<()> {
         txn.remove::<Certificates>(&digest)?;
         txn.remove::<CertificateDigestByRound>(&(round, origin.clone()))?;
         txn.remove::<CertificateDigestByOrigin>(&(origin, round))?;
+        if let Some(cache) = db.certificate_cache() {
+            cache.remove(&digest);
+        }
     }
     txn.commit()?;
     Ok(())
@@ -178,9 +203,13 @@ impl<DB: Database> CertificateStore for DB {
 
         let id = certificate.digest();
         let round = certificate.round();
+        let cached = self.certificate_cache().map(|cache| (cache, certificate.clone()));
         save_cert(&mut txn, id, certificate)?;
 
         txn.commit()?;
+        if let Some((cache, certificate)) = cached {
+            cache.insert(id, certificate);
+        }
         fail_point!("certificate-store-after-write");
         gc_rounds(self, round)?;
         Ok(())
@@ -194,9 +223,13 @@ impl<DB: Database> CertificateStore for DB {
 
         let mut txn = self.write_txn()?;
         let mut round = 0;
+        let mut cached = Vec::new();
         for certificate in certificates {
             let digest = certificate.digest();
             round = max(round, certificate.round());
+            if self.certificate_cache().is_some() {
+                cached.push((digest, certificate.clone()));
+            }
             if let Err(e) = save_cert(&mut txn, digest, certificate) {
                 tracing::error!("Failed to write certificate for {digest} due to error {e}.");
                 return Err(e);
@@ -204,6 +237,11 @@ impl<DB: Database> CertificateStore for DB {
         }
 
         txn.commit()?;
+        if let Some(cache) = self.certificate_cache() {
+            for (digest, certificate) in cached {
+                cache.insert(digest, certificate);
+            }
+        }
         gc_rounds(self, round)?;
         fail_point!("certificate-store-after-write");
         Ok(())
@@ -212,7 +250,7 @@ impl<DB: Database> CertificateStore for DB {
     /// Retrieves a certificate from the store. If not found
     /// then None is returned as result.
     fn read(&self, id: CertificateDigest) -> StoreResult<Option<Certificate>> {
-        self.get::<Certificates>(&id)
+        read_cert(self, &id)
     }
 
     /// Retrieves a certificate from the store by round and authority.
@@ -245,7 +283,7 @@ impl<DB: Database> CertificateStore for DB {
         &self,
         ids: impl IntoIterator<Item = CertificateDigest>,
     ) -> StoreResult<Vec<Option<Certificate>>> {
-        ids.into_iter().map(|digest| self.get::<Certificates>(&digest)).collect()
+        ids.into_iter().map(|digest| read_cert(self, &digest)).collect()
     }
 
     /// Waits to get notified until the requested certificate becomes available
@@ -289,6 +327,9 @@ impl<DB: Database> CertificateStore for DB {
         txn.remove::<CertificateDigestByRound>(&key)?;
 
         txn.commit()?;
+        if let Some(cache) = self.certificate_cache() {
+            cache.remove(&id);
+        }
         fail_point!("certificate-store-after-write");
         Ok(())
     }
@@ -311,6 +352,9 @@ impl<DB: Database> CertificateStore for DB {
 
             // delete the certificates by its ids
             txn.remove::<Certificates>(&id)?;
+            if let Some(cache) = self.certificate_cache() {
+                cache.remove(&id);
+            }
         }
 
         txn.commit()?;
@@ -321,7 +365,6 @@ impl<DB: Database> CertificateStore for DB {
     /// Retrieves all the certificates with round >= the provided round.
     /// The result is returned with certificates sorted in round asc order
     fn after_round(&self, round: Round) -> StoreResult<Vec<Certificate>> {
-        let txn = self.read_txn()?;
         // Skip to a row at or before the requested round.
         // TODO: Add a more efficient seek method to typed store.
         let iter = if round > 0 {
@@ -336,7 +379,7 @@ impl<DB: Database> CertificateStore for DB {
                 Ordering::Equal | Ordering::Greater => {
                     // Fetch all those certificates from main storage, return an error if any one is
                     // missing.
-                    if let Some(cert) = txn.get::<Certificates>(&d)? {
+                    if let Some(cert) = read_cert(self, &d)? {
                         certs.push(cert);
                     } else {
                         return Err(eyre::Report::msg(format!(
@@ -397,7 +440,7 @@ impl<DB: Database> CertificateStore for DB {
                 break;
             }
 
-            let certificate = self.get::<Certificates>(&digest)?.ok_or_else(|| {
+            let certificate = read_cert(self, &digest)?.ok_or_else(|| {
                 eyre::Report::msg(format!(
                     "Certificate with id {} not found in main storage although it should",
                     digest
@@ -470,6 +513,9 @@ impl<DB: Database> CertificateStore for DB {
         txn.clear_table::<CertificateDigestByOrigin>()?;
 
         txn.commit()?;
+        if let Some(cache) = self.certificate_cache() {
+            cache.clear();
+        }
         fail_point!("certificate-store-after-write");
         Ok(())
     }
@@ -1,15 +1,23 @@
 //! NOTE: tests for this module are in test-utils storage_tests.rs to avoid circular dependancies.
 
 use crate::{
-    tables::{ConsensusBlockNumbersByDigest, ConsensusBlocks},
+    release_batch,
+    tables::{
+        BatchPruneCursor, BatchReferences, Committees, ConsensusBlockNumbersByDigest,
+        ConsensusBlocks, EpochSummaries, SubDagStatsByNumber,
+    },
     StoreResult,
 };
 use std::{cmp::max, collections::HashMap};
 use tn_types::{
-    AuthorityIdentifier, CommittedSubDag, ConsensusHeader, Database, DbTxMut, Round, SequenceNumber,
+    AuthorityIdentifier, BlockHash, CommittedSubDag, Committee, ConsensusHeader, Database,
+    DbTx as _, DbTxMut, Epoch, EpochSummary, Round, SequenceNumber, SubDagStats, TimestampSec,
 };
 use tracing::debug;
 
+/// The key of the next consensus header to prune in the [BatchPruneCursor] table.
+const BATCH_PRUNE_CURSOR_KEY: u8 = 0;
+
 /// Implement persistent storage of the sequencer.
 /// Uses DB tables:
 ///   - LastCommitted<AuthorityIdentifier, Round>: The latest committed round of each validator.
@@ -41,6 +49,51 @@ pub trait ConsensusStore: Clone {
     /// Reads from storage the latest commit sub dag where its ReputationScores are marked as
     /// "final". If none exists yet then this method will return None.
     fn read_latest_commit_with_final_reputation_scores(&self) -> Option<CommittedSubDag>;
+
+    /// Add an executed block to the statistics of the consensus header it was executed for.
+    ///
+    /// Blocks at or below the last recorded block of the sub-dag were already counted and are
+    /// ignored, so replayed blocks can be recorded again. Returns the number of the consensus
+    /// header or None if `consensus_hash` is not in the consensus chain.
+    fn record_executed_block(
+        &self,
+        consensus_hash: &BlockHash,
+        block_number: u64,
+        gas_used: u64,
+        transactions: u64,
+    ) -> StoreResult<Option<u64>>;
+
+    /// Read the execution statistics for consensus headers `start..=end`.
+    ///
+    /// Headers without recorded statistics are skipped.
+    fn read_sub_dag_stats(&self, start: u64, end: u64) -> StoreResult<Vec<(u64, SubDagStats)>>;
+
+    /// Release the batches of executed consensus headers committed at or before `cutoff`.
+    ///
+    /// Batches are deleted with their last reference, see [crate::release_batch]. Resumes after
+    /// the last pruned header and visits at most `max_headers` headers up to the consensus header
+    /// `executed`. Returns the number of batches deleted.
+    fn prune_batches(
+        &self,
+        executed: u64,
+        cutoff: TimestampSec,
+        max_headers: usize,
+    ) -> StoreResult<usize>;
+
+    /// Persist the summary of a completed epoch, replacing any summary for the same epoch.
+    fn write_epoch_summary(&self, summary: &EpochSummary) -> StoreResult<()>;
+
+    /// Read the summary of `epoch`.
+    fn read_epoch_summary(&self, epoch: u64) -> StoreResult<Option<EpochSummary>>;
+
+    /// Read the summary of the last summarized epoch.
+    fn last_epoch_summary(&self) -> Option<EpochSummary>;
+
+    /// Persist the committee for its epoch, replacing any committee for the same epoch.
+    fn write_committee(&self, committee: &Committee) -> StoreResult<()>;
+
+    /// Read the committee of consensus epoch `epoch`, if the node ran in that epoch.
+    fn read_committee(&self, epoch: Epoch) -> StoreResult<Option<Committee>>;
 }
 impl<DB: Database> ConsensusStore for DB {
     fn write_subdag_for_test(&self, number: u64, sub_dag: CommittedSubDag) {
@@ -59,6 +112,10 @@ impl<DB: Database> ConsensusStore for DB {
         txn.clear_table::<ConsensusBlocks>().expect("failed to clear consensus blocks");
         txn.clear_table::<ConsensusBlockNumbersByDigest>()
             .expect("failed to clear consensus block indexes");
+        txn.clear_table::<SubDagStatsByNumber>().expect("failed to clear sub dag stats");
+        txn.clear_table::<BatchPruneCursor>().expect("failed to clear batch prune cursor");
+        txn.clear_table::<BatchReferences>().expect("failed to clear batch references");
+        txn.clear_table::<EpochSummaries>().expect("failed to clear epoch summaries");
 
         txn.commit().expect("failed to clear consensus blocks");
     }
@@ -112,6 +169,106 @@ impl<DB: Database> ConsensusStore for DB {
         debug!("No final reputation scores have been found");
         None
     }
+
+    fn record_executed_block(
+        &self,
+        consensus_hash: &BlockHash,
+        block_number: u64,
+        gas_used: u64,
+        transactions: u64,
+    ) -> StoreResult<Option<u64>> {
+        // read and update the stats in one transaction
+        let mut txn = self.write_txn()?;
+        let Some(number) = txn.get::<ConsensusBlockNumbersByDigest>(consensus_hash)? else {
+            return Ok(None);
+        };
+        let stats = match txn.get::<SubDagStatsByNumber>(&number)? {
+            Some(stats) if stats.last_block >= block_number => return Ok(Some(number)),
+            Some(stats) => stats,
+            None => {
+                let batches = txn
+                    .get::<ConsensusBlocks>(&number)?
+                    .map(|header| header.sub_dag.num_primary_blocks() as u64)
+                    .unwrap_or_default();
+                SubDagStats { batches, ..Default::default() }
+            }
+        };
+        let stats = SubDagStats {
+            gas_used: stats.gas_used + gas_used,
+            transactions: stats.transactions + transactions,
+            last_block: block_number,
+            ..stats
+        };
+        txn.insert::<SubDagStatsByNumber>(&number, &stats)?;
+        txn.commit()?;
+        Ok(Some(number))
+    }
+
+    fn read_sub_dag_stats(&self, start: u64, end: u64) -> StoreResult<Vec<(u64, SubDagStats)>> {
+        Ok(self
+            .skip_to::<SubDagStatsByNumber>(&start)?
+            .take_while(|(number, _)| *number <= end)
+            .collect())
+    }
+
+    fn prune_batches(
+        &self,
+        executed: u64,
+        cutoff: TimestampSec,
+        max_headers: usize,
+    ) -> StoreResult<usize> {
+        let start = self.get::<BatchPruneCursor>(&BATCH_PRUNE_CURSOR_KEY)?.unwrap_or_default();
+        let mut next = start;
+        let mut digests = Vec::new();
+        for (number, header) in self.skip_to::<ConsensusBlocks>(&start)?.take(max_headers) {
+            if number > executed || header.sub_dag.commit_timestamp() > cutoff {
+                break;
+            }
+            for certificate in &header.sub_dag.certificates {
+                digests.extend(certificate.header().payload().keys().copied());
+            }
+            next = number + 1;
+        }
+        if next == start {
+            return Ok(0);
+        }
+
+        let mut txn = self.write_txn()?;
+        let mut pruned = 0;
+        for digest in &digests {
+            // batches still referenced by a later consensus header are kept
+            if release_batch(&mut txn, digest)? {
+                pruned += 1;
+            }
+        }
+        txn.insert::<BatchPruneCursor>(&BATCH_PRUNE_CURSOR_KEY, &next)?;
+        txn.commit()?;
+        debug!(target: "tn::storage", pruned, next, "pruned batches");
+        Ok(pruned)
+    }
+
+    fn write_epoch_summary(&self, summary: &EpochSummary) -> StoreResult<()> {
+        self.insert::<EpochSummaries>(&summary.epoch, summary)
+    }
+
+    fn read_epoch_summary(&self, epoch: u64) -> StoreResult<Option<EpochSummary>> {
+        self.get::<EpochSummaries>(&epoch)
+    }
+
+    fn last_epoch_summary(&self) -> Option<EpochSummary> {
+        self.last_record::<EpochSummaries>().map(|(_, summary)| summary)
+    }
+
+    fn write_committee(&self, committee: &Committee) -> StoreResult<()> {
+        self.insert::<Committees>(&committee.epoch(), committee)
+    }
+
+    fn read_committee(&self, epoch: Epoch) -> StoreResult<Option<Committee>> {
+        let committee = self.get::<Committees>(&epoch)?;
+        // the secondary indexes are not stored
+        committee.iter().for_each(Committee::load);
+        Ok(committee)
+    }
 }
 
 // NOTE: tests for this module are in test-utils storage_tests.rs to avoid circular dependancies.
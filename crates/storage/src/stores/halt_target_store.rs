@@ -0,0 +1,37 @@
+//! NOTE: tests for this module are in test-utils storage_tests.rs to avoid circular dependancies.
+
+use crate::{tables::HaltTarget, StoreResult};
+use tn_types::Database;
+
+/// The key of the halt target in the [HaltTarget] table.
+const HALT_TARGET_KEY: u8 = 0;
+
+/// The consensus sub-dag an operator asked the running node to halt after.
+///
+/// The target is saved so it survives relaunches and restarts until the node reaches it.
+pub trait HaltTargetStore {
+    /// Return the saved halt target, `None` if there is none.
+    fn read_halt_target(&self) -> StoreResult<Option<u64>>;
+
+    /// Save the halt target, replacing any earlier target.
+    fn write_halt_target(&self, sub_dag: u64) -> StoreResult<()>;
+
+    /// Remove the saved halt target.
+    fn clear_halt_target(&self) -> StoreResult<()>;
+}
+
+impl<DB: Database> HaltTargetStore for DB {
+    fn read_halt_target(&self) -> StoreResult<Option<u64>> {
+        self.get::<HaltTarget>(&HALT_TARGET_KEY)
+    }
+
+    fn write_halt_target(&self, sub_dag: u64) -> StoreResult<()> {
+        self.insert::<HaltTarget>(&HALT_TARGET_KEY, &sub_dag)
+    }
+
+    fn clear_halt_target(&self) -> StoreResult<()> {
+        self.remove::<HaltTarget>(&HALT_TARGET_KEY)
+    }
+}
+
+// NOTE: tests for this module are in test-utils storage_tests.rs to avoid circular dependancies.
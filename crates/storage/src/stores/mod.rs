@@ -1,14 +1,26 @@
 // SPDX-License-Identifier: Apache-2.0
 //! Specific store implementations used by the network.
 
+mod address_index_store;
+mod batch_store;
 mod certificate_store;
 mod consensus_store;
+mod halt_target_store;
 mod payload_store;
+mod peer_history_store;
+mod peer_latency_store;
 mod proposer_store;
+mod stream_offset_store;
 mod vote_digest_store;
 
+pub use address_index_store::*;
+pub use batch_store::*;
 pub use certificate_store::*;
 pub use consensus_store::*;
+pub use halt_target_store::*;
 pub use payload_store::*;
+pub use peer_history_store::*;
+pub use peer_latency_store::*;
 pub use proposer_store::*;
+pub use stream_offset_store::*;
 pub use vote_digest_store::*;
@@ -0,0 +1,54 @@
+//! NOTE: tests for this module are in test-utils storage_tests.rs to avoid circular dependancies.
+
+use crate::{tables::PeerEvents, StoreResult};
+use tn_types::{Database, DbTxMut, PeerEvent, PeerNetwork};
+
+/// Persistent ring buffers of peer connectivity events, one for each consensus network.
+///
+/// Each network writes its own events, so sequence numbers are assigned without coordinating
+/// between the networks.
+pub trait PeerHistoryStore {
+    /// Append an event to its network's history, dropping the oldest events beyond `capacity`.
+    fn record_peer_event(&self, event: &PeerEvent, capacity: u64) -> StoreResult<()>;
+
+    /// Read the events of every network recorded at or after `since_ms`, oldest first.
+    fn read_peer_events_since(&self, since_ms: u64) -> StoreResult<Vec<PeerEvent>>;
+}
+
+impl<DB: Database> PeerHistoryStore for DB {
+    fn record_peer_event(&self, event: &PeerEvent, capacity: u64) -> StoreResult<()> {
+        let network = event.network;
+        let next = self
+            .record_prior_to::<PeerEvents>(&(network, u64::MAX))
+            .filter(|((last_network, _), _)| *last_network == network)
+            .map(|((_, sequence), _)| sequence + 1)
+            .unwrap_or_default();
+        let expired: Vec<(PeerNetwork, u64)> = self
+            .skip_to::<PeerEvents>(&(network, 0))?
+            .map(|(key, _)| key)
+            .take_while(|(key_network, sequence)| {
+                *key_network == network && sequence + capacity <= next
+            })
+            .collect();
+
+        let mut txn = self.write_txn()?;
+        for key in expired.iter() {
+            txn.remove::<PeerEvents>(key)?;
+        }
+        txn.insert::<PeerEvents>(&(network, next), event)?;
+        txn.commit()
+    }
+
+    fn read_peer_events_since(&self, since_ms: u64) -> StoreResult<Vec<PeerEvent>> {
+        let mut events: Vec<PeerEvent> = self
+            .iter::<PeerEvents>()
+            .map(|(_, event)| event)
+            .filter(|event| event.timestamp_ms >= since_ms)
+            .collect();
+        // each network's events are in order, merge them by time
+        events.sort_by_key(|event| event.timestamp_ms);
+        Ok(events)
+    }
+}
+
+// NOTE: tests for this module are in test-utils storage_tests.rs to avoid circular dependancies.
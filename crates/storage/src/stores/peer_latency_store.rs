@@ -0,0 +1,78 @@
+//! NOTE: tests for this module are in test-utils storage_tests.rs to avoid circular dependancies.
+
+use crate::{tables::PeerLatencies, StoreResult};
+use std::{collections::HashMap, time::Duration};
+use tn_types::{AuthorityIdentifier, Database, PeerLatency, TimestampSec};
+
+/// How long a saved round trip time is used after it was sampled.
+///
+/// Older samples say little about the peer, it may have moved or gone away.
+pub const PEER_LATENCY_TTL: Duration = Duration::from_secs(6 * 60 * 60);
+
+/// The round trip times to other authorities sampled by the worker's latency probes.
+///
+/// The history survives restarts so the node dials and fetches from nearby peers first before any
+/// peer is connected.
+pub trait PeerLatencyStore {
+    /// Save the smoothed round trip time to the authority's worker sampled at `now`.
+    ///
+    /// Clears the failures recorded for the authority.
+    fn record_peer_latency(
+        &self,
+        authority: &AuthorityIdentifier,
+        rtt: Duration,
+        now: TimestampSec,
+    ) -> StoreResult<()>;
+
+    /// Record a failed ping or dial to the authority.
+    ///
+    /// Each failure doubles the authority's round trip time until the next sample. Authorities
+    /// without a saved round trip time are unchanged.
+    fn record_peer_failure(&self, authority: &AuthorityIdentifier) -> StoreResult<()>;
+
+    /// Read the round trip time to each authority, penalized for failures since it was sampled.
+    ///
+    /// Times sampled more than `ttl` before `now` are missing.
+    fn read_peer_latencies(
+        &self,
+        now: TimestampSec,
+        ttl: Duration,
+    ) -> StoreResult<HashMap<AuthorityIdentifier, Duration>>;
+}
+
+impl<DB: Database> PeerLatencyStore for DB {
+    fn record_peer_latency(
+        &self,
+        authority: &AuthorityIdentifier,
+        rtt: Duration,
+        now: TimestampSec,
+    ) -> StoreResult<()> {
+        let rtt_micros = rtt.as_micros().try_into().unwrap_or(u64::MAX);
+        self.insert::<PeerLatencies>(
+            authority,
+            &PeerLatency { rtt_micros, sampled_at: now, failures: 0 },
+        )
+    }
+
+    fn record_peer_failure(&self, authority: &AuthorityIdentifier) -> StoreResult<()> {
+        let Some(mut latency) = self.get::<PeerLatencies>(authority)? else {
+            return Ok(());
+        };
+        latency.failures = latency.failures.saturating_add(1);
+        self.insert::<PeerLatencies>(authority, &latency)
+    }
+
+    fn read_peer_latencies(
+        &self,
+        now: TimestampSec,
+        ttl: Duration,
+    ) -> StoreResult<HashMap<AuthorityIdentifier, Duration>> {
+        Ok(self
+            .iter::<PeerLatencies>()
+            .filter(|(_, latency)| now.saturating_sub(latency.sampled_at) <= ttl.as_secs())
+            .map(|(authority, latency)| (authority, latency.penalized_rtt()))
+            .collect())
+    }
+}
+
+// NOTE: tests for this module are in test-utils storage_tests.rs to avoid circular dependancies.
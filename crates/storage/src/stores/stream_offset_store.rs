@@ -0,0 +1,28 @@
+//! NOTE: tests for this module are in test-utils storage_tests.rs to avoid circular dependancies.
+
+use crate::{tables::StreamOffsets, StoreResult};
+use tn_types::Database;
+
+/// The progress of streams that publish executed blocks to an external broker.
+///
+/// Offsets are saved once the broker acknowledges a block, so a restarted stream resumes with the
+/// next block.
+pub trait StreamOffsetStore {
+    /// Return the last block published by the stream, `None` if it never published.
+    fn read_stream_offset(&self, stream: &str) -> StoreResult<Option<u64>>;
+
+    /// Save the last block published by the stream.
+    fn write_stream_offset(&self, stream: &str, block: u64) -> StoreResult<()>;
+}
+
+impl<DB: Database> StreamOffsetStore for DB {
+    fn read_stream_offset(&self, stream: &str) -> StoreResult<Option<u64>> {
+        self.get::<StreamOffsets>(&stream.to_string())
+    }
+
+    fn write_stream_offset(&self, stream: &str, block: u64) -> StoreResult<()> {
+        self.insert::<StreamOffsets>(&stream.to_string(), &block)
+    }
+}
+
+// NOTE: tests for this module are in test-utils storage_tests.rs to avoid circular dependancies.
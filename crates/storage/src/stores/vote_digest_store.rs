@@ -1,5 +1,5 @@
 use crate::tables::Votes;
-use tn_types::{AuthorityIdentifier, Database, Vote, VoteInfo};
+use tn_types::{AuthorityIdentifier, Committee, Database, Round, Vote, VoteInfo};
 use tn_utils::fail_point;
 
 /// The impl for the last votes digests per authority
@@ -11,6 +11,12 @@ pub trait VoteDigestStore {
     /// Read the vote info based on the provided corresponding header author key
     fn read_vote_info(&self, header_author: &AuthorityIdentifier)
         -> eyre::Result<Option<VoteInfo>>;
+
+    /// Remove votes that no longer protect against equivocation.
+    ///
+    /// Votes for authorities outside the committee, from other epochs, or at or below the gc round
+    /// are removed. Returns the number of votes removed.
+    fn prune_votes(&self, committee: &Committee, gc_round: Round) -> eyre::Result<usize>;
 }
 
 impl<DB: Database> VoteDigestStore for DB {
@@ -33,4 +39,24 @@ impl<DB: Database> VoteDigestStore for DB {
     ) -> eyre::Result<Option<VoteInfo>> {
         self.get::<Votes>(header_author)
     }
+
+    /// Remove votes that no longer protect against equivocation.
+    fn prune_votes(&self, committee: &Committee, gc_round: Round) -> eyre::Result<usize> {
+        // the table holds at most one vote per authority
+        let stale: Vec<_> = self
+            .iter::<Votes>()
+            .filter(|(author, info)| {
+                committee.authority(author).is_none()
+                    || info.epoch() != committee.epoch()
+                    || info.round() <= gc_round
+            })
+            .map(|(author, _)| author)
+            .collect();
+
+        for author in stale.iter() {
+            self.remove::<Votes>(author)?;
+        }
+
+        Ok(stale.len())
+    }
 }
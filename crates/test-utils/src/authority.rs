@@ -100,6 +100,25 @@ impl<DB: Database> AuthorityFixture<DB> {
         self.consensus_config.clone()
     }
 
+    /// Rebuild the authority's consensus config for a new committee and worker cache.
+    ///
+    /// The config keeps the authority's storage and keys but has a new shutdown
+    /// [tn_types::Notifier].
+    pub(crate) fn reconfigure(&mut self, committee: Committee, worker_cache: WorkerCache) {
+        let config = &self.consensus_config;
+        self.authority = committee
+            .authority_by_key(self.authority.protocol_key())
+            .expect("authority in the new committee");
+        self.consensus_config = ConsensusConfig::new_with_committee_for_test(
+            config.config().clone(),
+            config.node_storage().clone(),
+            config.key_config().clone(),
+            committee,
+            worker_cache,
+        )
+        .expect("failed to generate config!");
+    }
+
     /// Generate a new [AuthorityFixture].
     pub(crate) fn generate(
         number_of_workers: NonZeroUsize,
@@ -165,6 +165,7 @@ where
                     })
                     .collect(),
             ),
+            sequences: Default::default(),
         };
         // All the authorities use the same worker cache.
         let authorities: BTreeMap<AuthorityIdentifier, AuthorityFixture<DB>> = committee_info
@@ -185,6 +186,7 @@ where
             })
             .collect();
 
-        CommitteeFixture { authorities, committee }
+        let next_index = authorities.len();
+        CommitteeFixture { authorities, committee, next_index }
     }
 }
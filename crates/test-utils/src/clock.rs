@@ -0,0 +1,56 @@
+//! A controllable [Clock] for time-dependent tests.
+
+use std::{
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use tn_types::{Clock, SharedClock, TimestampSec};
+
+/// A [Clock] that only moves when the test moves it.
+///
+/// Clones share the same time, so a test can keep a handle to the clock passed to a component
+/// with [ConsensusConfig::with_clock](tn_config::ConsensusConfig::with_clock). Pair with
+/// `tokio::time::pause` so sleeps and intervals also run without waiting.
+#[derive(Debug, Clone, Default)]
+pub struct TestClock {
+    /// The current time in seconds.
+    now: Arc<AtomicU64>,
+}
+
+impl TestClock {
+    /// Create a clock starting at `now`.
+    pub fn new(now: TimestampSec) -> Self {
+        Self { now: Arc::new(AtomicU64::new(now)) }
+    }
+
+    /// Set the current time.
+    pub fn set(&self, now: TimestampSec) {
+        self.now.store(now, Ordering::SeqCst);
+    }
+
+    /// Move the clock forward by whole seconds of `duration`.
+    pub fn advance(&self, duration: Duration) {
+        self.now.fetch_add(duration.as_secs(), Ordering::SeqCst);
+    }
+
+    /// Move the clock back by whole seconds of `duration`.
+    pub fn rewind(&self, duration: Duration) {
+        let _ = self.now.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |now| {
+            Some(now.saturating_sub(duration.as_secs()))
+        });
+    }
+
+    /// A [SharedClock] reading this clock's time.
+    pub fn shared(&self) -> SharedClock {
+        Arc::new(self.clone())
+    }
+}
+
+impl Clock for TestClock {
+    fn now(&self) -> TimestampSec {
+        self.now.load(Ordering::SeqCst)
+    }
+}
@@ -1,11 +1,20 @@
 //! Committe fixture for all authorities and their workers within a committee for a specific epoch.
 
 use super::{AuthorityFixture, Builder};
-use crate::fixture_batch_with_transactions;
-use std::collections::{BTreeMap, BTreeSet};
+use crate::{fixture_batch_with_transactions, WorkerFixture};
+use rand::{
+    rngs::{OsRng, StdRng},
+    SeedableRng,
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    num::NonZeroUsize,
+    sync::Arc,
+};
+use tn_config::KeyConfig;
 use tn_types::{
-    AuthorityIdentifier, Certificate, CertificateDigest, Committee, Database, Hash as _, Header,
-    HeaderBuilder, Round, Vote, WorkerCache,
+    Address, Authority, AuthorityIdentifier, BlsKeypair, Certificate, CertificateDigest, Committee,
+    Database, Hash as _, Header, HeaderBuilder, Multiaddr, Round, Vote, WorkerCache, WorkerIndex,
 };
 
 /// Fixture representing a committee to reach consensus.
@@ -18,6 +27,10 @@ pub struct CommitteeFixture<DB> {
     pub(crate) authorities: BTreeMap<AuthorityIdentifier, AuthorityFixture<DB>>,
     /// The [Committee] used in production.
     pub(crate) committee: Committee,
+    /// The index of the next authority added, used for its name and worker ports.
+    ///
+    /// Never reused, so an authority added after another was removed gets a fresh index.
+    pub(crate) next_index: usize,
 }
 
 impl<DB: Database> CommitteeFixture<DB> {
@@ -53,7 +66,6 @@ impl<DB: Database> CommitteeFixture<DB> {
             .expect("no authorities so no worker cache!")
             .consensus_config()
             .worker_cache()
-            .clone()
     }
 
     /// Return a reference to the first authority in the committee.
@@ -175,6 +187,88 @@ impl<DB: Database> CommitteeFixture<DB> {
         self.committee = committee;
     }
 
+    /// Add a new authority to the committee and return its id.
+    ///
+    /// The committee advances to the next epoch and every authority's consensus config is rebuilt
+    /// with the new committee and worker cache. Nodes started with the previous configs must be
+    /// restarted with [AuthorityFixture::consensus_config] to use the new committee.
+    pub fn add_authority(&mut self, db: DB) -> AuthorityIdentifier {
+        let mut rng = StdRng::from_rng(OsRng).unwrap();
+        let primary_keypair = BlsKeypair::generate(&mut rng);
+        let key_config = KeyConfig::new_with_testing_key(primary_keypair.copy());
+        let index = self.next_index;
+        self.next_index += 1;
+        let primary_network_address: Multiaddr = "/ip4/127.0.0.1/udp/0/quic-v1".parse().unwrap();
+        let authority = Authority::new_for_test(
+            key_config.primary_public_key(),
+            1,
+            primary_network_address,
+            Address::random_with(&mut rng),
+            key_config.primary_network_public_key(),
+            format!("authority{index}"),
+        );
+        let worker = WorkerFixture::generate(key_config.clone(), index as u16, |_| 0);
+
+        // generate the fixture with the committee it joins, reconfigured with the rest below
+        let mut authorities: BTreeMap<_, _> =
+            self.committee.authorities().into_iter().map(|a| (*a.protocol_key(), a)).collect();
+        authorities.insert(*authority.protocol_key(), authority.clone());
+        let committee = Committee::new_for_test(authorities, self.committee.epoch());
+        let id = authority.id();
+        let fixture = AuthorityFixture::generate(
+            NonZeroUsize::new(1).unwrap(),
+            authority,
+            (primary_keypair, key_config),
+            committee,
+            db,
+            worker,
+            self.worker_cache(),
+        );
+        self.authorities.insert(id, fixture);
+        self.reconfigure();
+        id
+    }
+
+    /// Remove an authority from the committee.
+    ///
+    /// The committee advances to the next epoch and the remaining authorities' consensus configs
+    /// are rebuilt, see [Self::add_authority]. The removed fixture keeps its previous config.
+    pub fn remove_authority(&mut self, id: &AuthorityIdentifier) -> Option<AuthorityFixture<DB>> {
+        let removed = self.authorities.remove(id)?;
+        self.reconfigure();
+        Some(removed)
+    }
+
+    /// Advance the committee to the next epoch with the current authorities and propagate the new
+    /// committee and worker cache to every authority.
+    fn reconfigure(&mut self) {
+        let epoch = self.committee.epoch() + 1;
+        let committee = Committee::new_for_test(
+            self.authorities
+                .values()
+                .map(|a| (*a.authority().protocol_key(), a.authority().clone()))
+                .collect(),
+            epoch,
+        );
+        let worker_cache = WorkerCache {
+            epoch,
+            workers: Arc::new(
+                self.authorities
+                    .values()
+                    .map(|a| {
+                        let worker_index = BTreeMap::from([(0, a.worker().info().clone())]);
+                        (*a.authority().protocol_key(), WorkerIndex(worker_index))
+                    })
+                    .collect(),
+            ),
+            sequences: Default::default(),
+        };
+        for authority in self.authorities.values_mut() {
+            authority.reconfigure(committee.clone(), worker_cache.clone());
+        }
+        self.committee = committee;
+    }
+
     /// Send a shutdown notfication to all authorities.
     pub fn notify_shutdown(&self) {
         for a in self.authorities.values() {
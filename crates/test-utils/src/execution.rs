@@ -138,11 +138,7 @@ pub fn execution_builder<CliExt: clap::Args + fmt::Debug>(
     // update execution address
     tn_config.validator_info.execution_address = address;
 
-    // TODO: this a temporary approach until upstream reth supports public rpc hooks
-    let opt_faucet_args = None;
-
-    let builder =
-        TnBuilder { database, node_config, tn_config, opt_faucet_args, consensus_metrics: None };
+    let builder = TnBuilder::new(database, node_config, tn_config);
 
     Ok((builder, ext))
 }
@@ -172,14 +168,7 @@ pub fn faucet_test_execution_node(
     let (builder, faucet) = execution_builder::<FaucetArgs>(opt_chain, opt_address, extended_args)?;
 
     // replace default builder's faucet args
-    let TnBuilder { database, node_config, tn_config, .. } = builder;
-    let builder = TnBuilder {
-        database,
-        node_config,
-        tn_config,
-        opt_faucet_args: Some(faucet),
-        consensus_metrics: None,
-    };
+    let builder = TnBuilder { opt_faucet_args: Some(faucet), ..builder };
 
     // create engine node
     let engine = ExecutionNode::new(&builder, &TaskManager::default())?;
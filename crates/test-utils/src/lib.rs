@@ -2,8 +2,12 @@
 
 mod authority;
 pub use authority::*;
+mod clock;
+pub use clock::*;
 mod execution;
 pub use execution::*;
+mod mock_execution;
+pub use mock_execution::*;
 mod worker;
 pub use worker::*;
 
@@ -27,9 +31,21 @@ pub use temp_dirs::*;
 mod tracing;
 pub use tracing::init_test_tracing;
 
+#[cfg(test)]
+#[path = "tests/clock_tests.rs"]
+mod clock_tests;
+#[cfg(test)]
+#[path = "tests/committee_tests.rs"]
+mod committee_tests;
+#[cfg(test)]
+#[path = "tests/mock_execution_tests.rs"]
+mod mock_execution_tests;
 #[cfg(test)]
 #[path = "tests/output_tests.rs"]
 mod output_tests;
 #[cfg(test)]
+#[path = "tests/static_file_tests.rs"]
+mod static_file_tests;
+#[cfg(test)]
 #[path = "tests/storage_tests.rs"]
 mod storage_tests;
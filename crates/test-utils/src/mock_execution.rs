@@ -0,0 +1,292 @@
+//! Mock execution node for consensus tests.
+//!
+//! [MockExecutionNode] exposes the parts of the execution node that consensus uses without the
+//! reth stack. Tests script how the node responds to consensus output, peer batches, and batch
+//! building so faults like slow execution or invalid headers can be injected.
+
+use std::{
+    collections::VecDeque,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tn_types::{
+    Batch, BatchSender, BatchValidation, BatchValidationError, ConsensusOutput, ExecHeader,
+    Hash as _, Noticer, Notifier, SealedBatch, SealedHeader, TaskManager, WorkerId, B256,
+};
+use tokio::sync::{broadcast, oneshot, watch};
+use tracing::{debug, error, info};
+
+/// How the mock engine responds to the next consensus output.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MockBuildResponse {
+    /// Build a block for each batch in the output.
+    Build,
+    /// Wait before building the blocks.
+    Slow(Duration),
+    /// Build blocks with headers that don't extend the canonical tip or match the output.
+    InvalidHeader,
+    /// Fail to execute the output, the engine exits with an error.
+    Fail,
+}
+
+/// How the mock validator responds to the next peer batch.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MockValidationResponse {
+    /// The batch is valid.
+    Accept,
+    /// Wait before accepting the batch.
+    Slow(Duration),
+    /// The batch is invalid.
+    Reject,
+}
+
+/// The scripted responses and the chain built by the mock.
+#[derive(Debug, Default)]
+struct MockExecutionState {
+    /// Responses for the next consensus outputs, [MockBuildResponse::Build] once empty.
+    build_responses: VecDeque<MockBuildResponse>,
+    /// Responses for the next peer batches, [MockValidationResponse::Accept] once empty.
+    validation_responses: VecDeque<MockValidationResponse>,
+    /// Batches for the batch builder to send to the worker.
+    pending_batches: VecDeque<Batch>,
+    /// Every block built, in order.
+    blocks: Vec<SealedHeader>,
+    /// The last block built for each consensus output.
+    output_blocks: Vec<SealedHeader>,
+    /// The digests of peer batches validated.
+    validated_batches: Vec<B256>,
+    /// The consensus header hash of the last executed output.
+    last_executed_output: B256,
+}
+
+impl MockExecutionState {
+    /// Build the blocks for a consensus output.
+    fn build_blocks(&mut self, output: &ConsensusOutput, invalid: bool) {
+        let output_digest = B256::from(output.digest());
+        let mut mix_hashes: Vec<_> =
+            output.flatten_batches().iter().map(|batch| output_digest ^ batch.digest()).collect();
+        // outputs without batches still build one block
+        if mix_hashes.is_empty() {
+            mix_hashes.push(output_digest);
+        }
+
+        for mix_hash in mix_hashes {
+            let (parent_hash, number) =
+                self.blocks.last().map(|tip| (tip.hash(), tip.number + 1)).unwrap_or_default();
+            let mut header = ExecHeader {
+                parent_hash,
+                number,
+                beneficiary: output.beneficiary(),
+                timestamp: output.committed_at(),
+                mix_hash,
+                nonce: output.nonce().into(),
+                parent_beacon_block_root: Some(output.consensus_header_hash()),
+                ..Default::default()
+            };
+            if invalid {
+                header.parent_hash = B256::repeat_byte(0xff);
+                header.nonce = (output.nonce() + 1).into();
+                header.parent_beacon_block_root = None;
+            }
+            let hash = header.hash_slow();
+            self.blocks.push(SealedHeader::new(header, hash));
+        }
+
+        if let Some(last) = self.blocks.last() {
+            self.output_blocks.push(last.clone());
+        }
+        self.last_executed_output = output.consensus_header_hash();
+    }
+}
+
+/// A lightweight execution node for consensus tests.
+///
+/// Implements the consensus facing methods of the execution node. Clones share state, so a test
+/// can keep a clone to script responses and inspect the chain after handing one to consensus.
+#[derive(Clone, Debug, Default)]
+pub struct MockExecutionNode {
+    state: Arc<Mutex<MockExecutionState>>,
+}
+
+impl MockExecutionNode {
+    /// Create a new instance of Self.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue the response for the next consensus output.
+    pub fn queue_build_response(&self, response: MockBuildResponse) {
+        self.state().build_responses.push_back(response);
+    }
+
+    /// Queue the response for the next peer batch.
+    pub fn queue_validation_response(&self, response: MockValidationResponse) {
+        self.state().validation_responses.push_back(response);
+    }
+
+    /// Queue a batch for the batch builder to send to the worker.
+    pub fn queue_batch(&self, batch: Batch) {
+        self.state().pending_batches.push_back(batch);
+    }
+
+    /// Execute consensus output until `rx_shutdown` resolves or the halt target is executed.
+    ///
+    /// The engine notifies `shutdown` when it exits gracefully, like the real engine.
+    pub async fn start_engine(
+        &self,
+        mut from_consensus: broadcast::Receiver<ConsensusOutput>,
+        halt_at_sub_dag: watch::Receiver<Option<u64>>,
+        task_manager: &TaskManager,
+        shutdown: &Notifier,
+    ) -> eyre::Result<()> {
+        let node = self.clone();
+        let rx_shutdown = shutdown.subscribe();
+        let shutdown = shutdown.clone();
+        task_manager.spawn_task("mock consensus engine", async move {
+            let res = tokio::select! {
+                res = node.run_engine(&mut from_consensus, halt_at_sub_dag) => res,
+                _ = rx_shutdown => Ok(()),
+            };
+            match res {
+                Ok(_) => {
+                    info!(target: "engine", "mock engine exited gracefully");
+                    shutdown.notify();
+                }
+                Err(e) => error!(target: "engine", ?e, "mock engine error"),
+            }
+        });
+        Ok(())
+    }
+
+    /// Send queued batches to the worker until `rx_shutdown` resolves.
+    ///
+    /// Each batch waits for the worker to seal it before the next is sent.
+    pub async fn start_batch_builder(
+        &self,
+        worker_id: WorkerId,
+        to_worker: BatchSender,
+        task_manager: &TaskManager,
+        rx_shutdown: Noticer,
+    ) -> eyre::Result<()> {
+        let node = self.clone();
+        task_manager.spawn_task(format!("mock batch builder {worker_id}"), async move {
+            tokio::select! {
+                _ = node.run_batch_builder(to_worker) => {}
+                _ = rx_shutdown => {}
+            }
+        });
+        Ok(())
+    }
+
+    /// Batch validator following the scripted validation responses.
+    pub async fn new_batch_validator(&self) -> Arc<dyn BatchValidation> {
+        Arc::new(self.clone())
+    }
+
+    /// The consensus header hash of the last executed output.
+    pub async fn last_executed_output(&self) -> eyre::Result<B256> {
+        Ok(self.state().last_executed_output)
+    }
+
+    /// Return a vector of the last 'number' executed block headers.
+    pub async fn last_executed_blocks(&self, number: u64) -> eyre::Result<Vec<ExecHeader>> {
+        let state = self.state();
+        let skip = state.blocks.len().saturating_sub(number as usize);
+        Ok(state.blocks[skip..].iter().map(|block| block.header().clone()).collect())
+    }
+
+    /// Return a vector of the last 'number' blocks finalized after each consensus output.
+    pub async fn last_executed_output_blocks(
+        &self,
+        number: u64,
+    ) -> eyre::Result<Vec<SealedHeader>> {
+        let state = self.state();
+        let skip = state.output_blocks.len().saturating_sub(number as usize);
+        Ok(state.output_blocks[skip..].to_vec())
+    }
+
+    /// The digests of peer batches validated, in order.
+    pub fn validated_batches(&self) -> Vec<B256> {
+        self.state().validated_batches.clone()
+    }
+
+    /// Lock the shared state.
+    fn state(&self) -> std::sync::MutexGuard<'_, MockExecutionState> {
+        self.state.lock().expect("mock execution state poisoned")
+    }
+
+    /// Build blocks for consensus output as it arrives.
+    async fn run_engine(
+        &self,
+        from_consensus: &mut broadcast::Receiver<ConsensusOutput>,
+        halt_at_sub_dag: watch::Receiver<Option<u64>>,
+    ) -> eyre::Result<()> {
+        loop {
+            let output = match from_consensus.recv().await {
+                Ok(output) => output,
+                Err(broadcast::error::RecvError::Lagged(missed)) => {
+                    eyre::bail!("mock engine missed {missed} consensus outputs")
+                }
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            };
+
+            let response =
+                self.state().build_responses.pop_front().unwrap_or(MockBuildResponse::Build);
+            debug!(target: "engine", ?response, sub_dag_index = output.nonce(), "mock engine");
+            match response {
+                MockBuildResponse::Build => {
+                    self.state().build_blocks(&output, false);
+                }
+                MockBuildResponse::Slow(delay) => {
+                    tokio::time::sleep(delay).await;
+                    self.state().build_blocks(&output, false);
+                }
+                MockBuildResponse::InvalidHeader => {
+                    self.state().build_blocks(&output, true);
+                }
+                MockBuildResponse::Fail => {
+                    eyre::bail!("mock engine failed to execute output {}", output.nonce())
+                }
+            }
+
+            if halt_at_sub_dag.borrow().is_some_and(|target| output.nonce() >= target) {
+                return Ok(());
+            }
+        }
+    }
+
+    /// Send queued batches to the worker.
+    async fn run_batch_builder(&self, to_worker: BatchSender) {
+        loop {
+            let next = self.state().pending_batches.pop_front();
+            let Some(batch) = next else {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                continue;
+            };
+
+            let (ack, rx_ack) = oneshot::channel();
+            if to_worker.send((batch.seal_slow(), ack)).await.is_err() {
+                return;
+            }
+            if let Ok(Err(e)) = rx_ack.await {
+                error!(target: "worker", ?e, "mock batch builder failed to seal batch");
+            }
+        }
+    }
+}
+
+impl BatchValidation for MockExecutionNode {
+    fn validate_batch(&self, batch: SealedBatch) -> Result<(), BatchValidationError> {
+        let response =
+            self.state().validation_responses.pop_front().unwrap_or(MockValidationResponse::Accept);
+        self.state().validated_batches.push(batch.digest());
+        match response {
+            MockValidationResponse::Accept => Ok(()),
+            MockValidationResponse::Slow(delay) => {
+                std::thread::sleep(delay);
+                Ok(())
+            }
+            MockValidationResponse::Reject => Err(BatchValidationError::InvalidDigest),
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use std::time::Duration;
+use tn_storage::mem_db::MemDatabase;
+
+use crate::{CommitteeFixture, TestClock};
+
+#[test]
+fn test_clock_moves_only_when_told() {
+    let clock = TestClock::new(1_000);
+    let shared = clock.shared();
+    assert_eq!(shared.now(), 1_000);
+
+    clock.advance(Duration::from_millis(2_500));
+    assert_eq!(shared.now(), 1_002);
+
+    clock.rewind(Duration::from_secs(2));
+    assert_eq!(shared.now(), 1_000);
+    clock.rewind(Duration::from_secs(5_000));
+    assert_eq!(shared.now(), 0);
+
+    clock.set(42);
+    assert_eq!(shared.now(), 42);
+}
+
+#[test]
+fn test_consensus_config_with_clock() {
+    let fixture = CommitteeFixture::builder(MemDatabase::default).build();
+    let clock = TestClock::new(7);
+    let config =
+        fixture.authorities().next().unwrap().consensus_config().with_clock(clock.shared());
+    assert_eq!(config.clock().now(), 7);
+
+    // clones of the config read the same clock
+    let cloned = config.clone();
+    clock.advance(Duration::from_secs(3));
+    assert_eq!(cloned.clock().now(), 10);
+}
@@ -0,0 +1,46 @@
+use std::num::NonZeroUsize;
+use tn_storage::mem_db::MemDatabase;
+
+use crate::CommitteeFixture;
+
+#[test]
+fn test_authority_join_and_leave() {
+    let mut fixture = CommitteeFixture::builder(MemDatabase::default)
+        .committee_size(NonZeroUsize::new(4).unwrap())
+        .build();
+    let epoch = fixture.committee().epoch();
+
+    let joined = fixture.add_authority(MemDatabase::default());
+    let committee = fixture.committee();
+    assert_eq!(fixture.num_authorities(), 5);
+    assert_eq!(committee.size(), 5);
+    assert_eq!(committee.epoch(), epoch + 1);
+    assert!(committee.is_authority(&joined));
+    // every authority sees the new committee and worker cache
+    for authority in fixture.authorities() {
+        let config = authority.consensus_config();
+        assert_eq!(config.committee().epoch(), epoch + 1);
+        assert!(config.in_committee(&joined));
+        assert_eq!(config.worker_cache().all_workers().len(), 5);
+    }
+
+    let left = fixture.first_authority().id();
+    let removed = fixture.remove_authority(&left).expect("authority in committee");
+    assert_eq!(removed.id(), left);
+    assert_eq!(fixture.committee().size(), 4);
+    assert_eq!(fixture.committee().epoch(), epoch + 2);
+    for authority in fixture.authorities() {
+        let config = authority.consensus_config();
+        assert!(!config.in_committee(&left));
+        assert!(config.in_committee(&joined));
+        assert_eq!(config.worker_cache().all_workers().len(), 4);
+    }
+    assert!(fixture.remove_authority(&left).is_none());
+
+    // an authority added after one left gets a fresh index
+    let rejoined = fixture.add_authority(MemDatabase::default());
+    let hostnames: std::collections::HashSet<_> =
+        fixture.authorities().map(|authority| authority.authority().hostname()).collect();
+    assert_eq!(hostnames.len(), 5);
+    assert_eq!(fixture.committee().authority(&rejoined).unwrap().hostname(), "authority5");
+}
@@ -0,0 +1,110 @@
+use indexmap::IndexMap;
+use std::{collections::BTreeSet, sync::Arc, time::Duration};
+use tn_storage::mem_db::MemDatabase;
+use tn_types::{
+    AuthorityIdentifier, BatchValidation as _, Certificate, CommittedSubDag, ConsensusOutput,
+    HeaderBuilder, Notifier, ReputationScores, TaskManager, B256,
+};
+use tokio::sync::{broadcast, watch};
+
+use crate::{
+    batch, CommitteeFixture, MockBuildResponse, MockExecutionNode, MockValidationResponse,
+};
+
+/// Create consensus output for the sub dag index with a single batch.
+fn output(committee_fixture: &CommitteeFixture<MemDatabase>, index: u64) -> ConsensusOutput {
+    let header = HeaderBuilder::default()
+        .author(AuthorityIdentifier::default())
+        .round(index * 2)
+        .epoch(0)
+        .created_at(index)
+        .payload(IndexMap::new())
+        .parents(BTreeSet::new())
+        .build();
+    let certificate =
+        Certificate::new_unsigned(&committee_fixture.committee(), header, Vec::new()).unwrap();
+    let sub_dag = CommittedSubDag::new(
+        vec![certificate.clone()],
+        certificate,
+        index,
+        ReputationScores::default(),
+        None,
+    );
+    let batch = batch();
+    ConsensusOutput {
+        sub_dag: Arc::new(sub_dag),
+        batch_digests: [batch.digest()].into(),
+        batches: vec![vec![batch]],
+        beneficiary: Default::default(),
+        parent_hash: B256::default(),
+        number: index,
+        extra: B256::default(),
+        early_finalize: true,
+        withdrawals: Default::default(),
+    }
+}
+
+#[tokio::test]
+async fn test_mock_engine_scripted_builds() {
+    let fixture = CommitteeFixture::builder(MemDatabase::default).build();
+    let node = MockExecutionNode::new();
+    node.queue_build_response(MockBuildResponse::Slow(Duration::from_millis(10)));
+    node.queue_build_response(MockBuildResponse::InvalidHeader);
+
+    let task_manager = TaskManager::default();
+    let shutdown = Notifier::new();
+    let engine_done = shutdown.subscribe();
+    let (to_engine, from_consensus) = broadcast::channel(10);
+    let (_halt_tx, halt_at_sub_dag) = watch::channel(Some(6));
+    node.start_engine(from_consensus, halt_at_sub_dag, &task_manager, &shutdown).await.unwrap();
+
+    let outputs: Vec<_> = (1..=3).map(|index| output(&fixture, index)).collect();
+    for output in outputs.iter().cloned() {
+        to_engine.send(output).unwrap();
+    }
+
+    // the engine notifies shutdown once it executes the halt target (the last output's round)
+    engine_done.await;
+
+    let blocks = node.last_executed_blocks(10).await.unwrap();
+    assert_eq!(blocks.len(), 3);
+    assert_eq!(blocks[0].parent_beacon_block_root, Some(outputs[0].consensus_header_hash()));
+    // the invalid header doesn't extend the tip or match its output
+    assert_ne!(blocks[1].parent_hash, blocks[0].hash_slow());
+    assert_eq!(blocks[1].parent_beacon_block_root, None);
+    // building goes back to normal once the script is empty
+    assert_eq!(blocks[2].parent_hash, blocks[1].hash_slow());
+    assert_eq!(node.last_executed_output().await.unwrap(), outputs[2].consensus_header_hash());
+    assert_eq!(node.last_executed_output_blocks(1).await.unwrap()[0].header(), &blocks[2]);
+}
+
+#[tokio::test]
+async fn test_mock_engine_failure() {
+    let fixture = CommitteeFixture::builder(MemDatabase::default).build();
+    let node = MockExecutionNode::new();
+    node.queue_build_response(MockBuildResponse::Fail);
+
+    let mut task_manager = TaskManager::default();
+    let shutdown = Notifier::new();
+    let (to_engine, from_consensus) = broadcast::channel(10);
+    let (_halt_tx, halt_at_sub_dag) = watch::channel(None);
+    node.start_engine(from_consensus, halt_at_sub_dag, &task_manager, &shutdown).await.unwrap();
+    to_engine.send(output(&fixture, 1)).unwrap();
+
+    // the engine task exits without notifying shutdown
+    let exit = task_manager.join(shutdown).await;
+    assert!(matches!(exit, tn_types::TaskManagerExit::TaskExited(_)));
+    assert!(node.last_executed_blocks(10).await.unwrap().is_empty());
+}
+
+#[test]
+fn test_mock_batch_validation() {
+    let node = MockExecutionNode::new();
+    node.queue_validation_response(MockValidationResponse::Reject);
+
+    let rejected = batch().seal_slow();
+    let accepted = batch().seal_slow();
+    assert!(node.validate_batch(rejected.clone()).is_err());
+    assert!(node.validate_batch(accepted.clone()).is_ok());
+    assert_eq!(node.validated_batches(), vec![rejected.digest(), accepted.digest()]);
+}
@@ -0,0 +1,87 @@
+use reth_provider::{BlockNumReader as _, HeaderProvider as _, StaticFileProviderFactory as _};
+use std::{str::FromStr as _, sync::Arc, time::Duration};
+use tn_types::{
+    adiri_chain_spec_arc, Address, Certificate, CommittedSubDag, ConsensusHeader, ConsensusOutput,
+    Notifier, ReputationScores, TaskManager,
+};
+use tokio::sync::{broadcast, watch};
+
+use crate::default_test_execution_node;
+
+/// Empty consensus outputs that finalize their blocks as soon as they execute.
+fn empty_outputs(count: u64) -> Vec<ConsensusOutput> {
+    let beneficiary = Address::from_str("0x5555555555555555555555555555555555555555")
+        .expect("beneficiary address from str");
+    let mut outputs: Vec<ConsensusOutput> = Vec::new();
+    let mut previous: Option<Arc<CommittedSubDag>> = None;
+    for index in 0..count {
+        let mut leader = Certificate::default();
+        leader.update_created_at_for_test(index + 1);
+        leader.header.round = (index * 2) as u32;
+        let sub_dag = Arc::new(CommittedSubDag::new(
+            vec![Certificate::default()],
+            leader,
+            index,
+            ReputationScores::default(),
+            previous.as_deref(),
+        ));
+        let parent_hash = outputs
+            .last()
+            .map(|output| output.consensus_header_hash())
+            .unwrap_or_else(|| ConsensusHeader::default().digest());
+        outputs.push(ConsensusOutput {
+            sub_dag: sub_dag.clone(),
+            batches: Default::default(),
+            beneficiary,
+            batch_digests: Default::default(),
+            parent_hash,
+            number: index,
+            extra: Default::default(),
+            early_finalize: true,
+            withdrawals: Default::default(),
+        });
+        previous = Some(sub_dag);
+    }
+    outputs
+}
+
+#[tokio::test]
+async fn test_finalized_blocks_move_to_static_files() -> eyre::Result<()> {
+    let execution_node = default_test_execution_node(Some(adiri_chain_spec_arc()), None)?;
+    let provider = execution_node.get_provider().await;
+    let task_manager = TaskManager::default();
+    let shutdown = Notifier::default();
+
+    execution_node.start_static_file_producer(&task_manager, shutdown.subscribe()).await;
+    let (to_engine, from_consensus) = broadcast::channel(10);
+    let (_halt, halt_at_sub_dag) = watch::channel(None);
+    execution_node.start_engine(from_consensus, halt_at_sub_dag, &task_manager, &shutdown).await?;
+
+    let outputs = empty_outputs(4);
+    for output in outputs {
+        to_engine.send(output)?;
+    }
+
+    // the producer runs after each canonical update, so every block but the newest is copied
+    let static_files = provider.static_file_provider();
+    tokio::time::timeout(Duration::from_secs(10), async {
+        loop {
+            let highest = static_files.get_highest_static_files();
+            if highest.headers.is_some_and(|number| number >= 3) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    })
+    .await?;
+
+    // historical headers are still served after they move out of the database
+    assert_eq!(provider.last_block_number()?, 4);
+    for number in 1..=4 {
+        let header = provider.header_by_number(number)?.expect("executed header");
+        assert_eq!(header.number, number);
+    }
+
+    shutdown.notify();
+    Ok(())
+}
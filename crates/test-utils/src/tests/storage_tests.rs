@@ -2,17 +2,23 @@
 //! Put them here to avoid circular dependancies with storage/test-utils (via ConsensusConfig).
 
 use std::{
-    collections::{BTreeSet, HashSet},
-    time::Instant,
+    collections::{BTreeSet, HashMap, HashSet},
+    time::{Duration, Instant},
 };
 
 use crate::{fixture_batch_with_transactions, temp_dir, CommitteeFixture};
 use futures::future::join_all;
 use tempfile::TempDir;
-use tn_storage::{mem_db::MemDatabase, open_db, CertificateStore, ConsensusStore, ProposerStore};
+use tn_storage::{
+    mem_db::MemDatabase, open_db, reference_batches, tables::Batches, AddressIndexStore,
+    BatchStore, CertificateStore, ConsensusStore, HaltTargetStore, PeerHistoryStore,
+    PeerLatencyStore, ProposerStore, StreamOffsetStore,
+};
 use tn_types::{
-    AuthorityIdentifier, Certificate, CertificateDigest, CommittedSubDag, Hash as _, Header,
-    HeaderBuilder, ReputationScores, Round,
+    Address, AuthorityIdentifier, BlockHash, Certificate, CertificateDigest, CertificateStoreCache,
+    CertificateStoreCacheMetrics, CommittedSubDag, ConsensusHeader, Database as _, DbTxMut as _,
+    EpochSummary, Hash as _, Header, HeaderBuilder, IndexedTransaction, PeerEvent, PeerEventKind,
+    PeerNetwork, ReputationScores, Round, SubDagStats, TxHash, B256,
 };
 
 pub fn create_header_for_round(round: Round) -> Header {
@@ -135,6 +141,376 @@ async fn test_consensus_store_read_latest_final_reputation_scores() {
     assert!(commit.reputation_score.final_of_schedule);
 }
 
+#[tokio::test]
+async fn test_consensus_store_sub_dag_stats() {
+    let temp_dir = TempDir::new().unwrap();
+    let store = open_db(temp_dir.path());
+    let fixture = CommitteeFixture::builder(MemDatabase::default).build();
+    let committee = fixture.committee();
+
+    // consensus headers with two batches each
+    let mut headers = Vec::new();
+    for number in 1..=3 {
+        let header = HeaderBuilder::default()
+            .author(AuthorityIdentifier::default())
+            .round(number as Round)
+            .epoch(0)
+            .created_at(number)
+            .payload(
+                [(BlockHash::random(), (0, 0)), (BlockHash::random(), (0, 0))]
+                    .into_iter()
+                    .collect(),
+            )
+            .parents(BTreeSet::new())
+            .build();
+        let certificate = Certificate::new_unsigned(&committee, header, Vec::new()).unwrap();
+        let sub_dag = CommittedSubDag::new(
+            vec![certificate.clone()],
+            certificate,
+            number,
+            ReputationScores::new(&committee),
+            None,
+        );
+        headers.push(ConsensusHeader { number, sub_dag: sub_dag.clone(), ..Default::default() });
+        store.write_subdag_for_test(number, sub_dag);
+    }
+    let hashes: Vec<_> = headers.iter().map(ConsensusHeader::digest).collect();
+
+    // blocks for unknown consensus headers are ignored
+    assert_eq!(store.record_executed_block(&BlockHash::random(), 1, 21_000, 1).unwrap(), None);
+
+    // one block per batch
+    assert_eq!(store.record_executed_block(&hashes[0], 1, 21_000, 1).unwrap(), Some(1));
+    assert_eq!(store.record_executed_block(&hashes[0], 2, 42_000, 2).unwrap(), Some(1));
+    assert_eq!(store.record_executed_block(&hashes[2], 3, 0, 0).unwrap(), Some(3));
+
+    // blocks replayed after a restart are only counted once
+    assert_eq!(store.record_executed_block(&hashes[0], 2, 42_000, 2).unwrap(), Some(1));
+
+    let stats = store.read_sub_dag_stats(0, 10).unwrap();
+    assert_eq!(
+        stats,
+        vec![
+            (1, SubDagStats { gas_used: 63_000, transactions: 3, batches: 2, last_block: 2 }),
+            (3, SubDagStats { gas_used: 0, transactions: 0, batches: 2, last_block: 3 }),
+        ]
+    );
+    assert_eq!(store.read_sub_dag_stats(2, 3).unwrap(), stats[1..].to_vec());
+    assert!(store.read_sub_dag_stats(4, 10).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_consensus_store_epoch_summaries() {
+    let temp_dir = TempDir::new().unwrap();
+    let store = open_db(temp_dir.path());
+    assert_eq!(store.last_epoch_summary(), None);
+
+    let summary = |epoch: u64| EpochSummary {
+        epoch,
+        first_sub_dag: epoch * 10,
+        last_sub_dag: epoch * 10 + 9,
+        total_sub_dags: 10,
+        checkpoint_hash: B256::random(),
+        ..Default::default()
+    };
+    let first = summary(0);
+    let second = summary(1);
+    store.write_epoch_summary(&first).unwrap();
+    store.write_epoch_summary(&second).unwrap();
+
+    assert_eq!(store.read_epoch_summary(0).unwrap(), Some(first));
+    assert_eq!(store.read_epoch_summary(2).unwrap(), None);
+    assert_eq!(store.last_epoch_summary(), Some(second));
+}
+
+#[tokio::test]
+async fn test_consensus_store_committees() {
+    let temp_dir = TempDir::new().unwrap();
+    let store = open_db(temp_dir.path());
+    let fixture = CommitteeFixture::builder(MemDatabase::default).build();
+    let committee = fixture.committee();
+    store.write_committee(&committee).unwrap();
+
+    let read = store.read_committee(committee.epoch()).unwrap().expect("committee");
+    assert_eq!(read, committee);
+    // authorities are indexed by id after reading
+    let id = fixture.authorities().next().unwrap().id();
+    assert!(read.authority(&id).is_some());
+    assert_eq!(store.read_committee(committee.epoch() + 1).unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_peer_history_store_ring_buffer() {
+    let temp_dir = TempDir::new().unwrap();
+    let store = open_db(temp_dir.path());
+    let fixture = CommitteeFixture::builder(MemDatabase::default).build();
+    let peer = fixture.authorities().next().unwrap().id().peer_id();
+    let event = |network, timestamp_ms| PeerEvent {
+        timestamp_ms,
+        network,
+        peer,
+        kind: PeerEventKind::DialFailed(format!("attempt {timestamp_ms}")),
+    };
+
+    // each network keeps its own most recent events
+    for timestamp_ms in 0..5 {
+        store.record_peer_event(&event(PeerNetwork::Primary, timestamp_ms), 3).unwrap();
+    }
+    store.record_peer_event(&event(PeerNetwork::Worker, 1), 3).unwrap();
+    let events = store.read_peer_events_since(0).unwrap();
+    assert_eq!(
+        events,
+        vec![
+            event(PeerNetwork::Worker, 1),
+            event(PeerNetwork::Primary, 2),
+            event(PeerNetwork::Primary, 3),
+            event(PeerNetwork::Primary, 4),
+        ]
+    );
+
+    // the history survives a restart
+    drop(store);
+    let store = open_db(temp_dir.path());
+    let events = store.read_peer_events_since(3).unwrap();
+    assert_eq!(events, vec![event(PeerNetwork::Primary, 3), event(PeerNetwork::Primary, 4)]);
+}
+
+#[tokio::test]
+async fn test_peer_latency_store() {
+    let temp_dir = TempDir::new().unwrap();
+    let store = open_db(temp_dir.path());
+    let near = AuthorityIdentifier::dummy_for_test(1);
+    let far = AuthorityIdentifier::dummy_for_test(2);
+    let ttl = Duration::from_secs(100);
+    assert!(store.read_peer_latencies(1_000, ttl).unwrap().is_empty());
+
+    // the last round trip time is kept for each authority
+    store.record_peer_latency(&near, Duration::from_millis(30), 1_000).unwrap();
+    store.record_peer_latency(&far, Duration::from_millis(200), 1_000).unwrap();
+    store.record_peer_latency(&near, Duration::from_micros(12_345), 1_010).unwrap();
+
+    // the history survives a restart
+    drop(store);
+    let store = open_db(temp_dir.path());
+    assert_eq!(
+        store.read_peer_latencies(1_050, ttl).unwrap(),
+        HashMap::from([(near, Duration::from_micros(12_345)), (far, Duration::from_millis(200))])
+    );
+
+    // samples older than the ttl are dropped
+    assert_eq!(
+        store.read_peer_latencies(1_105, ttl).unwrap(),
+        HashMap::from([(near, Duration::from_micros(12_345))])
+    );
+}
+
+#[tokio::test]
+async fn test_peer_latency_failures() {
+    let temp_dir = TempDir::new().unwrap();
+    let store = open_db(temp_dir.path());
+    let near = AuthorityIdentifier::dummy_for_test(1);
+    let far = AuthorityIdentifier::dummy_for_test(2);
+    let unknown = AuthorityIdentifier::dummy_for_test(3);
+    let ttl = Duration::from_secs(100);
+    store.record_peer_latency(&near, Duration::from_millis(30), 1_000).unwrap();
+    store.record_peer_latency(&far, Duration::from_millis(100), 1_000).unwrap();
+
+    // each failure doubles the round trip time, so a dead peer sorts behind reachable ones
+    for _ in 0..3 {
+        store.record_peer_failure(&near).unwrap();
+    }
+    store.record_peer_failure(&unknown).unwrap();
+    assert_eq!(
+        store.read_peer_latencies(1_000, ttl).unwrap(),
+        HashMap::from([(near, Duration::from_millis(240)), (far, Duration::from_millis(100))])
+    );
+
+    // a new sample clears the failures
+    store.record_peer_latency(&near, Duration::from_millis(40), 1_010).unwrap();
+    assert_eq!(store.read_peer_latencies(1_010, ttl).unwrap()[&near], Duration::from_millis(40));
+}
+
+#[tokio::test]
+async fn test_stream_offset_store() {
+    let temp_dir = TempDir::new().unwrap();
+    let store = open_db(temp_dir.path());
+    assert_eq!(store.read_stream_offset("telcoin").unwrap(), None);
+
+    // each stream keeps its own offset
+    store.write_stream_offset("telcoin", 10).unwrap();
+    store.write_stream_offset("exchange", 3).unwrap();
+    store.write_stream_offset("telcoin", 11).unwrap();
+
+    // offsets survive a restart
+    drop(store);
+    let store = open_db(temp_dir.path());
+    assert_eq!(store.read_stream_offset("telcoin").unwrap(), Some(11));
+    assert_eq!(store.read_stream_offset("exchange").unwrap(), Some(3));
+}
+
+#[tokio::test]
+async fn test_halt_target_store() {
+    let temp_dir = TempDir::new().unwrap();
+    let store = open_db(temp_dir.path());
+    assert_eq!(store.read_halt_target().unwrap(), None);
+
+    // a new target replaces the old one
+    store.write_halt_target(10).unwrap();
+    store.write_halt_target(12).unwrap();
+
+    // the target survives a restart
+    drop(store);
+    let store = open_db(temp_dir.path());
+    assert_eq!(store.read_halt_target().unwrap(), Some(12));
+
+    store.clear_halt_target().unwrap();
+    assert_eq!(store.read_halt_target().unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_address_index_store() {
+    let temp_dir = TempDir::new().unwrap();
+    let store = open_db(temp_dir.path());
+    let alice = Address::repeat_byte(1);
+    let bob = Address::repeat_byte(2);
+    let carol = Address::repeat_byte(3);
+    let tx = |block_number, index, from, to| IndexedTransaction {
+        block_number,
+        index,
+        hash: TxHash::repeat_byte(block_number as u8 * 10 + index as u8),
+        from,
+        to,
+    };
+
+    assert_eq!(store.last_indexed_block().unwrap(), None);
+    store.index_transactions(&[tx(1, 0, alice, Some(bob)), tx(1, 1, bob, None)], 1).unwrap();
+    store
+        .index_transactions(&[tx(2, 0, carol, Some(alice)), tx(3, 0, alice, Some(alice))], 4)
+        .unwrap();
+    assert_eq!(store.last_indexed_block().unwrap(), Some(4));
+
+    // sent and received transactions are merged in execution order without duplicates
+    let alice_txs = store.read_transactions_by_address(alice, (0, 0), 10).unwrap();
+    assert_eq!(
+        alice_txs,
+        vec![
+            tx(1, 0, alice, Some(bob)),
+            tx(2, 0, carol, Some(alice)),
+            tx(3, 0, alice, Some(alice))
+        ]
+    );
+    let bob_txs = store.read_transactions_by_address(bob, (0, 0), 10).unwrap();
+    assert_eq!(bob_txs, vec![tx(1, 0, alice, Some(bob)), tx(1, 1, bob, None)]);
+
+    // pages start at a transaction in a block and are limited
+    let page = store.read_transactions_by_address(alice, (2, 0), 1).unwrap();
+    assert_eq!(page, vec![tx(2, 0, carol, Some(alice))]);
+    let page = store.read_transactions_by_address(bob, (1, 1), 10).unwrap();
+    assert_eq!(page, vec![tx(1, 1, bob, None)]);
+    assert!(store.read_transactions_by_address(Address::ZERO, (0, 0), 10).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_consensus_store_prune_batches() {
+    let temp_dir = TempDir::new().unwrap();
+    let store = open_db(temp_dir.path());
+    let fixture = CommitteeFixture::builder(MemDatabase::default).build();
+    let committee = fixture.committee();
+
+    // consensus headers committed at 10, 20, 30 and 40 with two batches each
+    let mut digests = Vec::new();
+    for number in 1..=4 {
+        let batches = [BlockHash::random(), BlockHash::random()];
+        for digest in &batches {
+            store.insert::<Batches>(digest, &fixture_batch_with_transactions(1)).unwrap();
+        }
+        let header = HeaderBuilder::default()
+            .author(AuthorityIdentifier::default())
+            .round(number as Round)
+            .epoch(0)
+            .created_at(number * 10)
+            .payload(batches.iter().map(|digest| (*digest, (0, 0))).collect())
+            .parents(BTreeSet::new())
+            .build();
+        let certificate = Certificate::new_unsigned(&committee, header, Vec::new()).unwrap();
+        let sub_dag = CommittedSubDag::new(
+            vec![certificate.clone()],
+            certificate,
+            number,
+            ReputationScores::new(&committee),
+            None,
+        );
+        store.write_subdag_for_test(number, sub_dag);
+        digests.push(batches);
+    }
+    let stored = |number: usize| {
+        digests[number - 1].iter().all(|digest| store.get::<Batches>(digest).unwrap().is_some())
+    };
+
+    // batches are only pruned once executed
+    assert_eq!(store.prune_batches(0, 100, 10).unwrap(), 0);
+    assert!(stored(1));
+
+    // stop at the first header committed after the cutoff
+    assert_eq!(store.prune_batches(4, 25, 10).unwrap(), 4);
+    assert!(!stored(1) && !stored(2) && stored(3) && stored(4));
+
+    // resume after the last pruned header and respect the header limit
+    assert_eq!(store.prune_batches(4, 100, 1).unwrap(), 2);
+    assert!(!stored(3) && stored(4));
+    assert_eq!(store.prune_batches(3, 100, 10).unwrap(), 0);
+    assert_eq!(store.prune_batches(4, 100, 10).unwrap(), 2);
+    assert!(!stored(4));
+    assert_eq!(store.prune_batches(4, 100, 10).unwrap(), 0);
+}
+
+#[tokio::test]
+async fn test_batch_store_references() {
+    let temp_dir = TempDir::new().unwrap();
+    let store = open_db(temp_dir.path());
+    let fixture = CommitteeFixture::builder(MemDatabase::default).build();
+    let committee = fixture.committee();
+
+    // batches are stored once under their digest
+    let shared = fixture_batch_with_transactions(1);
+    let digest = shared.digest();
+    assert!(store.write_batch(&digest, &shared).unwrap());
+    assert!(!store.write_batch(&digest, &shared).unwrap());
+
+    // two consensus headers reference the batch
+    for number in 1..=2 {
+        let header = HeaderBuilder::default()
+            .author(AuthorityIdentifier::default())
+            .round(number as Round)
+            .epoch(0)
+            .created_at(number)
+            .payload([(digest, (0, 0))].into_iter().collect())
+            .parents(BTreeSet::new())
+            .build();
+        let certificate = Certificate::new_unsigned(&committee, header, Vec::new()).unwrap();
+        let sub_dag = CommittedSubDag::new(
+            vec![certificate.clone()],
+            certificate,
+            number,
+            ReputationScores::new(&committee),
+            None,
+        );
+        let mut txn = store.write_txn().unwrap();
+        reference_batches(&mut txn, [&digest]).unwrap();
+        txn.commit().unwrap();
+        store.write_subdag_for_test(number, sub_dag);
+    }
+    assert_eq!(store.batch_references(&digest).unwrap(), 2);
+
+    // the batch is deleted with its last reference
+    assert_eq!(store.prune_batches(1, 100, 10).unwrap(), 0);
+    assert_eq!(store.batch_references(&digest).unwrap(), 1);
+    assert!(store.get::<Batches>(&digest).unwrap().is_some());
+    assert_eq!(store.prune_batches(2, 100, 10).unwrap(), 1);
+    assert_eq!(store.batch_references(&digest).unwrap(), 0);
+    assert!(store.get::<Batches>(&digest).unwrap().is_none());
+}
+
 #[tokio::test]
 async fn test_certificate_store_write_and_read() {
     let db = open_db(temp_dir());
@@ -177,6 +553,52 @@ async fn test_write_and_read_by_store_type<DB: CertificateStore>(store: DB) {
     }
 }
 
+#[tokio::test]
+async fn test_certificate_store_cache() {
+    let cache = CertificateStoreCache::new(100, 4, CertificateStoreCacheMetrics::default());
+    let store = open_db(temp_dir()).with_certificate_cache(cache.clone());
+    // the default test committee has 4 authorities
+    let certs = certificates(10);
+    store.write_all(certs.clone()).unwrap();
+    assert_eq!(cache.len(), certs.len());
+
+    // written certificates are read from the cache
+    for cert in &certs {
+        assert_eq!(store.read(cert.digest()).unwrap().as_ref(), Some(cert));
+    }
+    assert_eq!(cache.metrics().hits.get(), certs.len() as u64);
+    assert_eq!(cache.metrics().misses.get(), 0);
+    assert_eq!(store.last_two_rounds_certs().unwrap().len(), 8);
+    assert_eq!(cache.metrics().misses.get(), 0);
+
+    // deleted certificates are never served from the cache
+    let deleted = certs[0].digest();
+    store.delete(deleted).unwrap();
+    assert!(store.read(deleted).unwrap().is_none());
+    store.delete_all([certs[1].digest()]).unwrap();
+    assert!(store.read(certs[1].digest()).unwrap().is_none());
+    assert_eq!(cache.len(), certs.len() - 2);
+
+    // a cleared cache reads through to the store and caches the certificate again
+    cache.clear();
+    assert_eq!(store.read(certs[2].digest()).unwrap().as_ref(), Some(&certs[2]));
+    assert_eq!(cache.len(), 1);
+    assert_eq!(store.read(certs[2].digest()).unwrap().as_ref(), Some(&certs[2]));
+    assert_eq!(cache.metrics().misses.get(), 3);
+
+    // full shards evict their least recently used certificates
+    let cache = CertificateStoreCache::new(4, 1, CertificateStoreCacheMetrics::default());
+    let store = open_db(temp_dir()).with_certificate_cache(cache.clone());
+    store.write_all(certs.clone()).unwrap();
+    assert_eq!(cache.len(), 4);
+    assert_eq!(cache.metrics().evictions.get(), certs.len() as u64 - 4);
+    assert_eq!(store.read(certs[0].digest()).unwrap().as_ref(), Some(&certs[0]));
+
+    // clearing the store clears the cache
+    store.clear().unwrap();
+    assert!(cache.is_empty());
+}
+
 #[tokio::test]
 async fn test_certificate_store_write_all_and_read_all() {
     let db = open_db(temp_dir());
@@ -0,0 +1,22 @@
+//! Executed transactions indexed by address.
+//!
+//! Nodes can index the sender and recipient of every executed transaction so wallets on small
+//! deployments can read an account's history without running an external indexer.
+
+use crate::{Address, TxHash};
+use serde::{Deserialize, Serialize};
+
+/// An executed transaction in the address index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IndexedTransaction {
+    /// The number of the block the transaction was executed in.
+    pub block_number: u64,
+    /// The index of the transaction in the block.
+    pub index: u32,
+    /// The transaction hash.
+    pub hash: TxHash,
+    /// The sender.
+    pub from: Address,
+    /// The recipient, `None` for contract creation.
+    pub to: Option<Address>,
+}
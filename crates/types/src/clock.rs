@@ -0,0 +1,33 @@
+//! The time source for consensus timestamps.
+
+use crate::{now, TimestampSec};
+use std::{fmt::Debug, sync::Arc};
+
+/// The source of the current time for headers and batches.
+///
+/// Components read the time through this trait instead of the system clock so nodes can run with
+/// a controlled time source.
+pub trait Clock: Debug + Send + Sync + 'static {
+    /// The current time as a UNIX timestamp in seconds.
+    fn now(&self) -> TimestampSec;
+}
+
+/// A [Clock] shared between components.
+pub type SharedClock = Arc<dyn Clock>;
+
+/// The [Clock] backed by the system time.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SystemClock;
+
+impl SystemClock {
+    /// Create a [SharedClock] reading the system time.
+    pub fn shared() -> SharedClock {
+        Arc::new(Self)
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> TimestampSec {
+        now()
+    }
+}
@@ -2,14 +2,20 @@ use rand::{CryptoRng, RngCore};
 
 use super::{BlsPublicKey, BlsSignature, Signer};
 use blst::min_sig::SecretKey as BlsPrivateKey;
+use std::fmt;
+use zeroize::Zeroizing;
 
 /// Validator's main protocol keypair.
-#[derive(Debug)]
+///
+/// The private key is zeroed when the keypair is dropped and is never printed.
 pub struct BlsKeypair {
     public: BlsPublicKey,
     private: BlsPrivateKey,
 }
 
+/// The EIP-2334 path of a validator's signing key, `m/12381/3600/0/0/0`.
+pub const EIP2334_SIGNING_KEY_PATH: [u32; 5] = [12381, 3600, 0, 0, 0];
+
 pub const DST_G1: &[u8] = b"BLS_SIG_BLS12381G1_XMD:SHA-256_SSWU_RO_NUL_"; // min sig
 const _DST_G2: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_"; // min pk
 impl BlsKeypair {
@@ -18,17 +24,19 @@ impl BlsKeypair {
     }
 
     pub fn generate<R: CryptoRng + RngCore>(rng: &mut R) -> Self {
-        let mut ikm = [0u8; 32];
-        rng.fill_bytes(&mut ikm);
-        let private = BlsPrivateKey::key_gen(&ikm, &[]).expect("ikm length should be higher");
+        let mut ikm = Zeroizing::new([0u8; 32]);
+        rng.fill_bytes(ikm.as_mut());
+        let private =
+            BlsPrivateKey::key_gen(ikm.as_ref(), &[]).expect("ikm length should be higher");
         let pubkey = private.sk_to_pk();
         let mut bytes = [0_u8; 96];
         bytes.copy_from_slice(&pubkey.to_bytes());
         Self { public: pubkey.into(), private }
     }
 
-    pub fn to_bytes(&self) -> [u8; 32] {
-        self.private.to_bytes()
+    /// The private key bytes, zeroed when dropped.
+    pub fn to_bytes(&self) -> Zeroizing<[u8; 32]> {
+        Zeroizing::new(self.private.to_bytes())
     }
 
     pub fn from_bytes(bytes: &[u8]) -> eyre::Result<Self> {
@@ -38,11 +46,31 @@ impl BlsKeypair {
         Ok(Self { public: pubkey.into(), private })
     }
 
+    /// Derive a keypair from `seed` with the EIP-2333 key tree.
+    ///
+    /// The master key is derived from the seed, ie - a BIP-39 seed, and each index of `path`
+    /// derives a child key. Use [EIP2334_SIGNING_KEY_PATH] for a validator's signing key.
+    pub fn derive_eip2333(seed: &[u8], path: &[u32]) -> eyre::Result<Self> {
+        let mut private = BlsPrivateKey::derive_master_eip2333(seed)
+            .map_err(|e| eyre::eyre!("invalid seed for bls key derivation: {e:?}"))?;
+        for index in path {
+            private = private.derive_child_eip2333(*index);
+        }
+        let pubkey = private.sk_to_pk();
+        Ok(Self { public: pubkey.into(), private })
+    }
+
     pub fn copy(&self) -> Self {
         Self { public: self.public, private: self.private.clone() }
     }
 }
 
+impl fmt::Debug for BlsKeypair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BlsKeypair").field("public", &self.public).finish_non_exhaustive()
+    }
+}
+
 impl Signer for BlsKeypair {
     fn sign(&self, msg: &[u8]) -> BlsSignature {
         self.private.sign(msg, DST_G1, &[]).into()
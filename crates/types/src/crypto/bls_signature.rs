@@ -6,7 +6,7 @@ use blst::min_sig::{
 use reth_chainspec::ChainSpec;
 use serde::{Deserialize, Serialize};
 
-use crate::encode;
+use crate::{encode, Address, PrimaryInfo};
 
 use super::{BlsKeypair, BlsPublicKey, Intent, IntentMessage, IntentScope, Signer, DST_G1};
 
@@ -140,14 +140,18 @@ impl std::fmt::Display for BlsAggregateSignature {
 ///
 /// The proof of possession is a [BlsSignature] committed over the intent message
 /// `intent || message` (See more at [IntentMessage] and [Intent]).
-/// The message is constructed as: [BlsPublicKey] || [Genesis].
+/// The message is constructed as:
+/// [BlsPublicKey] || execution [Address] || primary and worker network keys || [Genesis].
+///
+/// Committing to every key prevents keys from different validators being combined.
 pub fn generate_proof_of_possession_bls(
     keypair: &BlsKeypair,
+    execution_address: &Address,
+    primary_info: &PrimaryInfo,
     chain_spec: &ChainSpec,
 ) -> eyre::Result<BlsSignature> {
-    let mut msg = keypair.public().to_bytes().to_vec();
-    let genesis_bytes = encode(&chain_spec.genesis);
-    msg.extend_from_slice(genesis_bytes.as_slice());
+    let msg =
+        proof_of_possession_message(keypair.public(), execution_address, primary_info, chain_spec);
     let sig = BlsSignature::new_secure(
         &IntentMessage::new(Intent::telcoin(IntentScope::ProofOfPossession), msg),
         keypair,
@@ -157,17 +161,17 @@ pub fn generate_proof_of_possession_bls(
 
 /// Verify proof of possession against the expected intent message,
 ///
-/// The intent message is expected to contain the validator's public key
-/// and the [Genesis] for the network.
+/// The intent message is expected to contain the validator's public key, execution address,
+/// network keys, and the [Genesis] for the network.
 pub fn verify_proof_of_possession_bls(
     proof: &BlsSignature,
     public_key: &BlsPublicKey,
+    execution_address: &Address,
+    primary_info: &PrimaryInfo,
     chain_spec: &ChainSpec,
 ) -> eyre::Result<()> {
     public_key.validate().map_err(|_| eyre::eyre!("Bls Publkic Key not valid!"))?;
-    let mut msg = public_key.to_bytes().to_vec();
-    let genesis_bytes = encode(&chain_spec.genesis);
-    msg.extend_from_slice(genesis_bytes.as_slice());
+    let msg = proof_of_possession_message(public_key, execution_address, primary_info, chain_spec);
     if proof.verify_secure(
         &IntentMessage::new(Intent::telcoin(IntentScope::ProofOfPossession), msg),
         public_key,
@@ -178,6 +182,31 @@ pub fn verify_proof_of_possession_bls(
     }
 }
 
+/// The message signed for a proof of possession.
+///
+/// Worker keys are included in worker id order.
+fn proof_of_possession_message(
+    public_key: &BlsPublicKey,
+    execution_address: &Address,
+    primary_info: &PrimaryInfo,
+    chain_spec: &ChainSpec,
+) -> Vec<u8> {
+    let worker_keys: Vec<_> = primary_info
+        .worker_index
+        .0
+        .iter()
+        .map(|(worker_id, info)| (*worker_id, &info.name))
+        .collect();
+
+    let mut msg = public_key.to_bytes().to_vec();
+    msg.extend_from_slice(execution_address.as_slice());
+    msg.extend_from_slice(&encode(&primary_info.network_key));
+    msg.extend_from_slice(&encode(&primary_info.worker_network_key));
+    msg.extend_from_slice(&encode(&worker_keys));
+    msg.extend_from_slice(&encode(&chain_spec.genesis));
+    msg
+}
+
 /// A trait for sign and verify over an intent message, instead of the message itself. See more at
 /// [struct IntentMessage].
 pub trait ProtocolSignature {
@@ -65,6 +65,9 @@ pub enum IntentScope {
     EpochBoundary = 1,     // Used for authority signature on a checkpoint at epochs boundaries.
     ConsensusDigest = 2,   // Used for authority signature on consensus digests.
     SystemMessage = 3,     // Used for signing system messages.
+    NetworkKeyRotation = 4, // Used for authority signature on network key rotations.
+    WorkerInfoUpdate = 5,  // Used for authority signature on worker info updates.
+    InclusionPromise = 6,  // Used for worker signature on transaction inclusion promises.
 }
 
 impl TryFrom<u8> for IntentScope {
@@ -163,46 +163,204 @@ pub fn network_public_key_to_libp2p(public_key: &NetworkPublicKey) -> PeerId {
 
 #[cfg(test)]
 mod tests {
-    use super::{generate_proof_of_possession_bls, verify_proof_of_possession_bls};
-    use crate::{adiri_chain_spec_arc, adiri_genesis, BlsKeypair};
+    use super::{
+        generate_proof_of_possession_bls, verify_proof_of_possession_bls, NetworkKeyRotation,
+    };
+    use crate::{
+        adiri_chain_spec_arc, adiri_genesis, hex, Address, BlsKeypair, Multiaddr, NetworkKeypair,
+        NetworkPublicKey, PrimaryInfo, WorkerIndex, WorkerInfo, U256,
+    };
     use rand::{
         rngs::{OsRng, StdRng},
         SeedableRng,
     };
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_derive_eip2333() {
+        // test case 0 from EIP-2333
+        let seed = hex::decode(
+            "c55257c360c07c72029aebc1b53c05ed0362ada38ead3e3e9efa3708e53495531f09a6987599d18264c1e1\
+             c92f2cf141630c7a3c4ab7c81b2f001698e7463b04",
+        )
+        .expect("valid hex");
+        let master_sk = U256::from_str_radix(
+            "6083874454709270928345386274498605044986640685124978867557563392430687146096",
+            10,
+        )
+        .expect("valid integer");
+        let child_sk = U256::from_str_radix(
+            "20397789859736650942317412262472558107875392172444076792671091975210932703118",
+            10,
+        )
+        .expect("valid integer");
+
+        let master = BlsKeypair::derive_eip2333(&seed, &[]).expect("master key derived");
+        assert_eq!(U256::from_be_slice(master.to_bytes().as_ref()), master_sk);
+        let child = BlsKeypair::derive_eip2333(&seed, &[0]).expect("child key derived");
+        assert_eq!(U256::from_be_slice(child.to_bytes().as_ref()), child_sk);
+
+        // seeds must have enough entropy
+        assert!(BlsKeypair::derive_eip2333(&[0; 16], &[]).is_err());
+    }
 
     #[test]
     fn test_proof_of_possession_success() {
         let keypair = BlsKeypair::generate(&mut StdRng::from_rng(OsRng).unwrap());
+        let (address, primary_info) = validator_keys();
         let chain_spec = adiri_chain_spec_arc();
-        let proof = generate_proof_of_possession_bls(&keypair, &chain_spec).unwrap();
-        assert!(verify_proof_of_possession_bls(&proof, keypair.public(), &chain_spec).is_ok())
+        let proof =
+            generate_proof_of_possession_bls(&keypair, &address, &primary_info, &chain_spec)
+                .unwrap();
+        assert!(verify_proof_of_possession_bls(
+            &proof,
+            keypair.public(),
+            &address,
+            &primary_info,
+            &chain_spec
+        )
+        .is_ok())
     }
 
     #[test]
     fn test_proof_of_possession_fails_wrong_signature() {
         let keypair = BlsKeypair::generate(&mut StdRng::from_rng(OsRng).unwrap());
         let malicious_key = BlsKeypair::generate(&mut StdRng::from_rng(OsRng).unwrap());
+        let (address, primary_info) = validator_keys();
         let chain_spec = adiri_chain_spec_arc();
-        let proof = generate_proof_of_possession_bls(&malicious_key, &chain_spec).unwrap();
-        assert!(verify_proof_of_possession_bls(&proof, keypair.public(), &chain_spec).is_err())
+        let proof =
+            generate_proof_of_possession_bls(&malicious_key, &address, &primary_info, &chain_spec)
+                .unwrap();
+        assert!(verify_proof_of_possession_bls(
+            &proof,
+            keypair.public(),
+            &address,
+            &primary_info,
+            &chain_spec
+        )
+        .is_err())
     }
 
     #[test]
     fn test_proof_of_possession_fails_wrong_public_key() {
         let keypair = BlsKeypair::generate(&mut StdRng::from_rng(OsRng).unwrap());
         let malicious_key = BlsKeypair::generate(&mut StdRng::from_rng(OsRng).unwrap());
+        let (address, primary_info) = validator_keys();
         let chain_spec = adiri_chain_spec_arc();
-        let proof = generate_proof_of_possession_bls(&keypair, &chain_spec).unwrap();
-        assert!(verify_proof_of_possession_bls(&proof, malicious_key.public(), &chain_spec).is_err())
+        let proof =
+            generate_proof_of_possession_bls(&keypair, &address, &primary_info, &chain_spec)
+                .unwrap();
+        assert!(verify_proof_of_possession_bls(
+            &proof,
+            malicious_key.public(),
+            &address,
+            &primary_info,
+            &chain_spec
+        )
+        .is_err())
     }
 
     #[test]
     fn test_proof_of_possession_fails_wrong_message() {
         let keypair = BlsKeypair::generate(&mut StdRng::from_rng(OsRng).unwrap());
+        let (address, primary_info) = validator_keys();
         let chain_spec = adiri_chain_spec_arc();
         let mut wrong = adiri_genesis();
         wrong.timestamp = 0;
-        let proof = generate_proof_of_possession_bls(&keypair, &wrong.into()).unwrap();
-        assert!(verify_proof_of_possession_bls(&proof, keypair.public(), &chain_spec).is_err())
+        let proof =
+            generate_proof_of_possession_bls(&keypair, &address, &primary_info, &wrong.into())
+                .unwrap();
+        assert!(verify_proof_of_possession_bls(
+            &proof,
+            keypair.public(),
+            &address,
+            &primary_info,
+            &chain_spec
+        )
+        .is_err())
+    }
+
+    #[test]
+    fn test_proof_of_possession_fails_mixed_keys() {
+        let keypair = BlsKeypair::generate(&mut StdRng::from_rng(OsRng).unwrap());
+        let (address, primary_info) = validator_keys();
+        let (other_address, other_primary_info) = validator_keys();
+        let chain_spec = adiri_chain_spec_arc();
+        let proof =
+            generate_proof_of_possession_bls(&keypair, &address, &primary_info, &chain_spec)
+                .unwrap();
+        let verify = |address: &Address, primary_info: &PrimaryInfo| {
+            verify_proof_of_possession_bls(
+                &proof,
+                keypair.public(),
+                address,
+                primary_info,
+                &chain_spec,
+            )
+        };
+
+        // another validator's execution address
+        assert!(verify(&other_address, &primary_info).is_err());
+
+        // another validator's primary network key
+        let mut mixed = primary_info.clone();
+        mixed.network_key = other_primary_info.network_key.clone();
+        assert!(verify(&address, &mixed).is_err());
+
+        // another validator's worker keys
+        let mut mixed = primary_info.clone();
+        mixed.worker_network_key = other_primary_info.worker_network_key.clone();
+        assert!(verify(&address, &mixed).is_err());
+        let mut mixed = primary_info.clone();
+        mixed.worker_index = other_primary_info.worker_index.clone();
+        assert!(verify(&address, &mixed).is_err());
+
+        // addresses are not part of the proof
+        let mut moved = primary_info.clone();
+        moved.network_address = other_primary_info.network_address.clone();
+        assert!(verify(&address, &moved).is_ok());
+    }
+
+    /// Random execution address and network keys for a validator.
+    fn validator_keys() -> (Address, PrimaryInfo) {
+        let primary_key: NetworkPublicKey = NetworkKeypair::generate_ed25519().public().into();
+        let worker_key: NetworkPublicKey = NetworkKeypair::generate_ed25519().public().into();
+        let worker_info = WorkerInfo { name: worker_key.clone(), ..Default::default() };
+        let primary_info = PrimaryInfo::new(
+            primary_key,
+            Multiaddr::empty(),
+            worker_key,
+            WorkerIndex(BTreeMap::from([(0, worker_info)])),
+        );
+        (Address::random(), primary_info)
+    }
+
+    #[test]
+    fn test_network_key_rotation() {
+        let keypair = BlsKeypair::generate(&mut StdRng::from_rng(OsRng).unwrap());
+        let key = || NetworkPublicKey::from(NetworkKeypair::generate_ed25519().public());
+        let rotation =
+            NetworkKeyRotation::new(&keypair, *keypair.public(), 3, key(), key(), key(), key());
+        assert!(rotation.verify());
+
+        // the rotation is only accepted before its epoch starts
+        assert!(rotation.verify_for_next_epoch(2));
+        assert!(!rotation.verify_for_next_epoch(3));
+
+        // changing the epoch to replay the rotation invalidates the signature
+        let mut replayed = rotation.clone();
+        replayed.epoch = 10;
+        assert!(!replayed.verify_for_next_epoch(9));
+
+        // tampering with the new key invalidates the signature
+        let mut tampered = rotation.clone();
+        tampered.new_primary_network_key = key();
+        assert!(!tampered.verify());
+
+        // signature from a different authority fails
+        let malicious_key = BlsKeypair::generate(&mut StdRng::from_rng(OsRng).unwrap());
+        let mut wrong_signer = rotation;
+        wrong_signer.bls_public_key = *malicious_key.public();
+        assert!(!wrong_signer.verify());
     }
 }
@@ -4,8 +4,10 @@ use std::{fmt, ops::Deref};
 
 use serde::{Deserialize, Serialize};
 
-use super::{Intent, IntentMessage, IntentScope};
-use crate::{encode, Genesis};
+use super::{
+    BlsPublicKey, BlsSignature, Intent, IntentMessage, IntentScope, ProtocolSignature as _, Signer,
+};
+use crate::{encode, Epoch, Genesis};
 
 /// Public key used to sign network messages between peers during consensus.
 #[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
@@ -128,3 +130,102 @@ pub fn verify_proof_of_possession_network(
     let message = encode(&IntentMessage::new(Intent::telcoin(IntentScope::ProofOfPossession), msg));
     public_key.verify(&message, proof)
 }
+
+/// A signed announcement that an authority rotated its network keys.
+///
+/// The announcement is signed by the authority's [BlsPublicKey] so peers can verify the new
+/// network keys belong to the authority before updating their committee and worker cache files.
+/// The new keys take effect at the start of `epoch`. Peers only apply an announcement for an
+/// epoch that has not started, so an old announcement can not be replayed to restore old keys.
+///
+/// The signature is a [BlsSignature] committed over the intent message `intent || message`.
+/// The message is constructed as:
+/// [BlsPublicKey] || [Epoch] || old primary [NetworkPublicKey] || new primary [NetworkPublicKey] ||
+/// old worker [NetworkPublicKey] || new worker [NetworkPublicKey].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NetworkKeyRotation {
+    /// The authority's protocol key.
+    pub bls_public_key: BlsPublicKey,
+    /// The epoch the new keys take effect.
+    pub epoch: Epoch,
+    /// The primary network key being replaced.
+    pub old_primary_network_key: NetworkPublicKey,
+    /// The new primary network key.
+    pub new_primary_network_key: NetworkPublicKey,
+    /// The worker network key being replaced.
+    pub old_worker_network_key: NetworkPublicKey,
+    /// The new worker network key.
+    pub new_worker_network_key: NetworkPublicKey,
+    /// The authority's signature over the rotation.
+    pub signature: BlsSignature,
+}
+
+impl NetworkKeyRotation {
+    /// Create a new, signed rotation announcement.
+    pub fn new(
+        signer: &dyn Signer,
+        bls_public_key: BlsPublicKey,
+        epoch: Epoch,
+        old_primary_network_key: NetworkPublicKey,
+        new_primary_network_key: NetworkPublicKey,
+        old_worker_network_key: NetworkPublicKey,
+        new_worker_network_key: NetworkPublicKey,
+    ) -> Self {
+        let message = Self::intent_message(
+            &bls_public_key,
+            epoch,
+            &old_primary_network_key,
+            &new_primary_network_key,
+            &old_worker_network_key,
+            &new_worker_network_key,
+        );
+        let signature = BlsSignature::new_secure(&message, signer);
+        Self {
+            bls_public_key,
+            epoch,
+            old_primary_network_key,
+            new_primary_network_key,
+            old_worker_network_key,
+            new_worker_network_key,
+            signature,
+        }
+    }
+
+    /// Verify the authority signed this rotation.
+    pub fn verify(&self) -> bool {
+        let message = Self::intent_message(
+            &self.bls_public_key,
+            self.epoch,
+            &self.old_primary_network_key,
+            &self.new_primary_network_key,
+            &self.old_worker_network_key,
+            &self.new_worker_network_key,
+        );
+        self.signature.verify_secure(&message, &self.bls_public_key)
+    }
+
+    /// Verify the authority signed this rotation and it takes effect after `current_epoch`.
+    ///
+    /// Peers must use this before applying a rotation so announcements can not be replayed.
+    pub fn verify_for_next_epoch(&self, current_epoch: Epoch) -> bool {
+        self.epoch > current_epoch && self.verify()
+    }
+
+    /// The intent message the authority signs for a rotation.
+    fn intent_message(
+        bls_public_key: &BlsPublicKey,
+        epoch: Epoch,
+        old_primary_network_key: &NetworkPublicKey,
+        new_primary_network_key: &NetworkPublicKey,
+        old_worker_network_key: &NetworkPublicKey,
+        new_worker_network_key: &NetworkPublicKey,
+    ) -> IntentMessage<Vec<u8>> {
+        let mut msg = bls_public_key.to_bytes().to_vec();
+        msg.extend_from_slice(&epoch.to_le_bytes());
+        msg.extend_from_slice(&old_primary_network_key.encode_protobuf());
+        msg.extend_from_slice(&new_primary_network_key.encode_protobuf());
+        msg.extend_from_slice(&old_worker_network_key.encode_protobuf());
+        msg.extend_from_slice(&new_worker_network_key.encode_protobuf());
+        IntentMessage::new(Intent::telcoin(IntentScope::NetworkKeyRotation), msg)
+    }
+}
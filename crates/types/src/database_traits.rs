@@ -1,5 +1,6 @@
 //! Database traits for compatibility.
 
+use crate::CertificateStoreCache;
 use serde::{de::DeserializeOwned, Serialize};
 use std::{borrow::Borrow, fmt::Debug};
 
@@ -115,4 +116,11 @@ pub trait Database: Send + Sync + Clone + Unpin + 'static {
     fn compact(&self) -> eyre::Result<()> {
         Ok(())
     }
+
+    /// The cache of decoded certificates the certificate store reads through, if any.
+    ///
+    /// None for most backends, the node sets one on its consensus DB.
+    fn certificate_cache(&self) -> Option<&CertificateStoreCache> {
+        None
+    }
 }
@@ -52,6 +52,9 @@ pub enum DagError {
 
     #[error("Invalid header digest")]
     InvalidHeaderDigest,
+    /// The payload root does not commit to the header's payload.
+    #[error("Invalid payload root")]
+    InvalidPayloadRoot,
 
     #[error("Invalid system message")]
     InvalidSystemMessage,
@@ -185,6 +188,9 @@ pub enum ConfigError {
     #[error("Unknown worker id {0}")]
     UnknownWorker(WorkerId),
 
+    #[error("Invalid worker info update: {0}")]
+    InvalidWorkerUpdate(String),
+
     #[error("Failed to read config file '{file}': {message}")]
     ImportError { file: String, message: String },
 }
@@ -0,0 +1,89 @@
+//! Protocol changes activated at an agreed epoch.
+//!
+//! Changes to what nodes hash or validate would fork the network if nodes applied them as soon as
+//! they upgraded. Each change is activated at an epoch set in the chain spec instead, so every
+//! node switches at the same point. Changes without an activation epoch are inactive and nodes
+//! keep the behaviour the network launched with.
+
+use crate::Epoch;
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+
+/// The epochs that activate protocol changes, `None` if a change is inactive.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Forks {
+    /// Header digests commit to the payload's Merkle root instead of the payload.
+    pub payload_root: Option<Epoch>,
+    /// Executed blocks are timestamped with the chain spec's timestamp policy instead of the
+    /// sub-dag's commit timestamp.
+    pub timestamp_policy: Option<Epoch>,
+    /// Consensus uses the chain spec's leader schedule parameters instead of the defaults.
+    ///
+    /// Counted in chain spec epochs of consensus output, the epochs governed parameters change in.
+    pub leader_schedule: Option<Epoch>,
+    /// Executed blocks take their gas limit from the chain spec's batch gas schedule instead of
+    /// the batch's timestamp, or the parent block for outputs without batches.
+    pub batch_gas: Option<Epoch>,
+}
+
+impl Forks {
+    /// No protocol changes are active.
+    pub const NONE: Self =
+        Self { payload_root: None, timestamp_policy: None, leader_schedule: None, batch_gas: None };
+
+    /// True if header digests commit to the payload root in `epoch`.
+    pub fn payload_root_active(&self, epoch: Epoch) -> bool {
+        active(self.payload_root, epoch)
+    }
+
+    /// True if executed blocks are timestamped with the chain spec's policy in `epoch`.
+    pub fn timestamp_policy_active(&self, epoch: Epoch) -> bool {
+        active(self.timestamp_policy, epoch)
+    }
+
+    /// True if consensus uses the chain spec's leader schedule parameters in `epoch`.
+    pub fn leader_schedule_active(&self, epoch: Epoch) -> bool {
+        active(self.leader_schedule, epoch)
+    }
+
+    /// True if executed blocks take their gas limit from the batch gas schedule in `epoch`.
+    pub fn batch_gas_active(&self, epoch: Epoch) -> bool {
+        active(self.batch_gas, epoch)
+    }
+}
+
+/// True if a change activated at `activation` is active in `epoch`.
+fn active(activation: Option<Epoch>, epoch: Epoch) -> bool {
+    activation.is_some_and(|activation| epoch >= activation)
+}
+
+/// The forks of the network this process runs.
+static FORKS: RwLock<Forks> = RwLock::new(Forks::NONE);
+
+/// Set the forks of the network, called once when the node launches.
+pub fn set_forks(forks: Forks) {
+    *FORKS.write().unwrap_or_else(|e| e.into_inner()) = forks;
+}
+
+/// The forks of the network this process runs.
+pub fn forks() -> Forks {
+    *FORKS.read().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Forks;
+
+    #[test]
+    fn test_fork_activation() {
+        assert!(!Forks::default().payload_root_active(u32::MAX));
+        let forks = Forks { payload_root: Some(3), ..Forks::NONE };
+        assert!(!forks.payload_root_active(2));
+        assert!(forks.payload_root_active(3));
+        assert!(forks.payload_root_active(4));
+        assert!(!forks.timestamp_policy_active(4));
+        assert!(!forks.leader_schedule_active(4));
+        assert!(!forks.batch_gas_active(4));
+    }
+}
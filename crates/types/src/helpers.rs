@@ -1,6 +1,8 @@
 //! Helpers for starting a node
 
-use std::net::{TcpListener, UdpSocket};
+use crate::Multiaddr;
+use libp2p::multiaddr::Protocol;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, TcpListener, UdpSocket};
 
 const MAX_RETRIES: u32 = 1000;
 
@@ -77,3 +79,97 @@ pub fn get_available_udp_port(host: &str) -> Option<u16> {
 
     get_available_port(&config).ok()
 }
+
+/// Return the addresses to listen on for an address peers dial.
+///
+/// A node can not listen on a DNS name, so the name is replaced by all interfaces of the address
+/// families it resolves to with the same port: IPv4 for `/dns4`, IPv6 for `/dns6`, and both for
+/// `/dns` so a name with `A` and `AAAA` records is reachable over either family. The peer id is
+/// removed. Returns no addresses for `/dnsaddr` since the port is only known after resolving the
+/// TXT record.
+pub fn listen_multiaddrs(addr: &Multiaddr) -> Vec<Multiaddr> {
+    let ip4 = Protocol::Ip4(Ipv4Addr::UNSPECIFIED);
+    let ip6 = Protocol::Ip6(Ipv6Addr::UNSPECIFIED);
+    let hosts = match addr.iter().next() {
+        Some(Protocol::Dns(_)) => vec![Some(ip4), Some(ip6)],
+        Some(Protocol::Dns4(_)) => vec![Some(ip4)],
+        Some(Protocol::Dns6(_)) => vec![Some(ip6)],
+        Some(Protocol::Dnsaddr(_)) => return vec![],
+        _ => vec![None],
+    };
+    hosts
+        .into_iter()
+        .map(|host| {
+            let protocols = addr.iter().filter(|p| !matches!(p, Protocol::P2p(_)));
+            match host {
+                // replace the DNS name
+                Some(host) => std::iter::once(host).chain(protocols.skip(1)).collect(),
+                None => protocols.collect(),
+            }
+        })
+        .collect()
+}
+
+/// Return the multiaddr for a host, which is an IPv4 or IPv6 address or a DNS name.
+pub fn host_multiaddr(host: &str) -> Multiaddr {
+    let protocol = match host.parse::<IpAddr>() {
+        Ok(IpAddr::V4(ip)) => Protocol::Ip4(ip),
+        Ok(IpAddr::V6(ip)) => Protocol::Ip6(ip),
+        Err(_) => Protocol::Dns(host.into()),
+    };
+    Multiaddr::empty().with(protocol)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// True if the address is resolved through DNS when dialed.
+    fn is_dns_multiaddr(addr: &Multiaddr) -> bool {
+        addr.iter().any(|protocol| {
+            matches!(
+                protocol,
+                Protocol::Dns(_) | Protocol::Dns4(_) | Protocol::Dns6(_) | Protocol::Dnsaddr(_)
+            )
+        })
+    }
+
+    #[test]
+    fn test_listen_multiaddrs() {
+        let parse = |addr: &str| addr.parse::<Multiaddr>().expect("valid multiaddr");
+
+        let ip = parse("/ip4/10.0.0.1/udp/49590/quic-v1");
+        assert!(!is_dns_multiaddr(&ip));
+        assert_eq!(listen_multiaddrs(&ip), vec![ip]);
+
+        let ip6 = parse("/ip6/fd00::1/udp/49590/quic-v1");
+        assert_eq!(listen_multiaddrs(&ip6), vec![ip6]);
+
+        let dns4 = parse("/dns4/validator.example.com/udp/49590/quic-v1");
+        assert!(is_dns_multiaddr(&dns4));
+        assert_eq!(listen_multiaddrs(&dns4), vec![parse("/ip4/0.0.0.0/udp/49590/quic-v1")]);
+
+        let dns6 = parse("/dns6/validator.example.com/udp/49590/quic-v1");
+        assert_eq!(listen_multiaddrs(&dns6), vec![parse("/ip6/::/udp/49590/quic-v1")]);
+
+        // dual-stack
+        let peer_id = libp2p::PeerId::random();
+        let dns = parse(&format!("/dns/validator.example.com/udp/49590/quic-v1/p2p/{peer_id}"));
+        assert_eq!(
+            listen_multiaddrs(&dns),
+            vec![parse("/ip4/0.0.0.0/udp/49590/quic-v1"), parse("/ip6/::/udp/49590/quic-v1")]
+        );
+
+        let dnsaddr = parse("/dnsaddr/bootstrap.example.com");
+        assert!(is_dns_multiaddr(&dnsaddr));
+        assert!(listen_multiaddrs(&dnsaddr).is_empty());
+    }
+
+    #[test]
+    fn test_host_multiaddr() {
+        let parse = |addr: &str| addr.parse::<Multiaddr>().expect("valid multiaddr");
+        assert_eq!(host_multiaddr("127.0.0.1"), parse("/ip4/127.0.0.1"));
+        assert_eq!(host_multiaddr("::1"), parse("/ip6/::1"));
+        assert_eq!(host_multiaddr("validator.example.com"), parse("/dns/validator.example.com"));
+    }
+}
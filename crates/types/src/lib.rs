@@ -1,27 +1,37 @@
 // SPDX-License-Identifier: Apache-2.0
 
+mod address_index;
+mod clock;
 mod codec;
 #[allow(clippy::mutable_key_type)]
 mod committee;
 mod crypto;
 pub mod database_traits;
+mod forks;
 mod genesis;
 mod helpers;
 mod notifier;
+mod peer_history;
 mod primary;
 mod serde;
 mod sync;
 mod task_manager;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod test_utils;
 mod worker;
 #[macro_use]
 pub mod error;
+pub use address_index::*;
+pub use clock::*;
 pub use codec::*;
 pub use committee::*;
 pub use crypto::*;
 pub use database_traits::*;
+pub use forks::*;
 pub use genesis::*;
 pub use helpers::*;
 pub use notifier::*;
+pub use peer_history::*;
 pub use primary::*;
 pub use sync::*;
 pub use task_manager::*;
@@ -31,7 +41,7 @@ pub use worker::*;
 pub use alloy::{
     consensus::{
         constants::{EMPTY_OMMER_ROOT_HASH, EMPTY_RECEIPTS, EMPTY_TRANSACTIONS, EMPTY_WITHDRAWALS},
-        proofs::calculate_transaction_root,
+        proofs::{calculate_transaction_root, calculate_withdrawals_root},
         BlockHeader, Header as ExecHeader, Transaction as TransactionTrait, TxEip1559,
     },
     eips::{
@@ -46,7 +56,7 @@ pub use alloy::{
         hex_literal, keccak256, Address, BlockHash, BlockNumber, Bloom, Bytes, Sealable, TxHash,
         TxKind, B256, U160, U256,
     },
-    rpc::types::{AccessList, Withdrawals},
+    rpc::types::{AccessList, TransactionRequest, Withdrawal, Withdrawals},
     signers::Signature as EthSignature,
     sol,
     sol_types::{SolType, SolValue},
@@ -55,7 +65,7 @@ pub use libp2p::Multiaddr;
 pub use reth_primitives::{
     public_key_to_address,
     transaction::{SignedTransactionIntoRecoveredExt, PARALLEL_SENDER_RECOVERY_THRESHOLD},
-    Block, BlockBody, BlockExt, BlockWithSenders, EthPrimitives, NodePrimitives, PooledTransaction,
-    Receipt, RecoveredTx, SealedBlock, SealedBlockWithSenders, SealedHeader, Transaction,
-    TransactionSigned,
+    Account, Block, BlockBody, BlockExt, BlockWithSenders, EthPrimitives, NodePrimitives,
+    PooledTransaction, Receipt, RecoveredTx, SealedBlock, SealedBlockWithSenders, SealedHeader,
+    Transaction, TransactionSigned,
 };
@@ -0,0 +1,92 @@
+//! Peer connectivity events.
+//!
+//! The consensus networks record when peers connect, disconnect, and fail to dial so operators
+//! can reconstruct the node's connectivity after an incident without scraping logs.
+
+use crate::TimestampSec;
+use libp2p::PeerId;
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// The consensus network a peer event was observed on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum PeerNetwork {
+    /// The primary network.
+    Primary,
+    /// The worker network.
+    Worker,
+}
+
+impl PeerNetwork {
+    /// The network's name.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Primary => "primary",
+            Self::Worker => "worker",
+        }
+    }
+}
+
+/// A change in the connectivity to a peer.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PeerEventKind {
+    /// The first connection to the peer was established.
+    Connected,
+    /// The last connection to the peer was closed, with the error that closed it if any.
+    Disconnected(Option<String>),
+    /// Dialing the peer failed.
+    DialFailed(String),
+}
+
+/// A peer connectivity event recorded by a consensus network.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerEvent {
+    /// The UNIX timestamp of the event in milliseconds.
+    pub timestamp_ms: u64,
+    /// The network the event was observed on.
+    pub network: PeerNetwork,
+    /// The peer.
+    pub peer: PeerId,
+    /// What happened.
+    pub kind: PeerEventKind,
+}
+
+impl PeerEvent {
+    /// Create a new event that happened now.
+    pub fn new(network: PeerNetwork, peer: PeerId, kind: PeerEventKind) -> Self {
+        Self { timestamp_ms: now_ms(), network, peer, kind }
+    }
+}
+
+/// The most failures a saved round trip time is doubled for.
+const MAX_LATENCY_PENALTY_DOUBLINGS: u32 = 10;
+
+/// The round trip time to an authority's worker saved by the worker's latency probes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PeerLatency {
+    /// The smoothed round trip time in microseconds.
+    pub rtt_micros: u64,
+    /// The UNIX timestamp in seconds of the sample.
+    pub sampled_at: TimestampSec,
+    /// The failed pings and dials since the sample.
+    pub failures: u32,
+}
+
+impl PeerLatency {
+    /// The round trip time, doubled for each failure since the sample.
+    ///
+    /// A peer that stopped answering sorts behind reachable peers instead of being tried first
+    /// because of its old sample.
+    pub fn penalized_rtt(&self) -> Duration {
+        let doublings = self.failures.min(MAX_LATENCY_PENALTY_DOUBLINGS);
+        Duration::from_micros(self.rtt_micros.saturating_mul(1 << doublings))
+    }
+}
+
+/// Returns the current time expressed as UNIX timestamp in milliseconds.
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_millis() as u64)
+        .unwrap_or_default()
+}
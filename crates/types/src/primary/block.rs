@@ -7,7 +7,11 @@
 //! if not directly participating in consesus.
 
 use super::{CommittedSubDag, ConsensusOutput};
-use crate::{crypto, error::CertificateResult, BlockHash, Certificate, Committee, Hash, B256};
+use crate::{
+    calculate_withdrawals_root, crypto, error::CertificateResult, AuthorityIdentifier, BlockHash,
+    BlsPublicKey, Bytes, Certificate, Committee, ExecHeader, Hash, SealedHeader, Withdrawals, B256,
+};
+use alloy_rlp::Decodable as _;
 use blake2::Digest as _;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
@@ -31,36 +35,147 @@ pub struct ConsensusHeader {
     /// Temp extra data field - currently unused.
     /// This is included for now for testnet purposes only.
     pub extra: B256,
+
+    /// Protocol-level withdrawals (EIP-4895) credited after executing this block.
+    pub withdrawals: Withdrawals,
 }
 
 impl ConsensusHeader {
     /// Return the digest for this ConsensusHeader.
     pub fn digest(&self) -> BlockHash {
-        Self::digest_from_parts(self.parent_hash, &self.sub_dag, self.number)
+        Self::digest_from_parts(self.parent_hash, &self.sub_dag, self.number, &self.withdrawals)
     }
 
     /// Produce the digest that result from a ConsensusHeader with this data.
     /// This allows digesting in some cases with out cloning a CommittedSubDag.
+    ///
+    /// The withdrawals root is only hashed if there are withdrawals, so headers without
+    /// withdrawals keep the digest they had before withdrawals were added.
     pub fn digest_from_parts(
         parent_hash: B256,
         sub_dag: &CommittedSubDag,
         number: u64,
+        withdrawals: &Withdrawals,
     ) -> BlockHash {
         let mut hasher = crypto::DefaultHashFunction::new();
         hasher.update(parent_hash);
         hasher.update(sub_dag.digest());
         hasher.update(number.to_le_bytes());
+        if !withdrawals.is_empty() {
+            hasher.update(calculate_withdrawals_root(withdrawals));
+        }
         BlockHash::from_slice(&hasher.finalize()[..])
     }
 
     /// Verify that all of the contained certificates are valid and signed by a quorum of committee.
     pub fn verify_certificates(self, committee: &Committee) -> CertificateResult<Self> {
-        let Self { parent_hash, sub_dag, number, extra } = self;
+        let Self { parent_hash, sub_dag, number, extra, withdrawals } = self;
         let sub_dag = sub_dag.verify_certificates(committee)?;
-        Ok(Self { parent_hash, sub_dag, number, extra })
+        Ok(Self { parent_hash, sub_dag, number, extra, withdrawals })
     }
 }
 
+/// An executed block announced to nodes following the chain without running consensus.
+///
+/// Executed blocks record the digest of their consensus header in `parent_beacon_block_root`. The
+/// announcement adds the consensus header's number so observers and light clients can follow the
+/// execution tip and link it to committed sub-dags without an RPC connection to a validator.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockAnnouncement {
+    /// The number of the consensus header (sub-dag index) the block was executed for.
+    pub consensus_number: u64,
+    /// The digest of the consensus header the block was executed for.
+    pub consensus_hash: BlockHash,
+    /// The hash of the announced block.
+    pub block_hash: BlockHash,
+    /// The RLP encoded execution header.
+    ///
+    /// The header's serde format skips empty fields, which BCS can not decode.
+    header: Bytes,
+}
+
+impl BlockAnnouncement {
+    /// Create an announcement for a block executed for consensus header `consensus_number`.
+    ///
+    /// Returns `None` if the block was not executed for a consensus header.
+    pub fn new(consensus_number: u64, header: &ExecHeader) -> Option<Self> {
+        let consensus_hash = header.parent_beacon_block_root?;
+        Some(Self {
+            consensus_number,
+            consensus_hash,
+            block_hash: header.hash_slow(),
+            header: alloy_rlp::encode(header).into(),
+        })
+    }
+
+    /// Decode the announced execution header.
+    ///
+    /// Fails if the header does not decode, does not hash to the announced block hash or was not
+    /// executed for the announced consensus header.
+    pub fn header(&self) -> alloy_rlp::Result<SealedHeader> {
+        let header = ExecHeader::decode(&mut self.header.as_ref())?;
+        if header.parent_beacon_block_root != Some(self.consensus_hash) {
+            return Err(alloy_rlp::Error::Custom("header not executed for consensus hash"));
+        }
+        let header = SealedHeader::seal(header);
+        if header.hash() != self.block_hash {
+            return Err(alloy_rlp::Error::Custom("header does not match block hash"));
+        }
+        Ok(header)
+    }
+}
+
+/// Execution statistics for a committed sub-dag.
+///
+/// Recorded as the blocks for the sub-dag's consensus output are executed.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubDagStats {
+    /// The total gas used by the blocks executed for the sub-dag.
+    pub gas_used: u64,
+    /// The number of transactions executed for the sub-dag.
+    pub transactions: u64,
+    /// The number of batches in the sub-dag.
+    pub batches: u64,
+    /// The number of the last block executed for the sub-dag.
+    pub last_block: u64,
+}
+
+/// A summary of the consensus output and execution of one epoch.
+///
+/// Generated once the last consensus output of the epoch is executed and kept for archival.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpochSummary {
+    /// The epoch number.
+    pub epoch: u64,
+    /// The committee that produced the epoch, with reputation scores.
+    pub validators: Vec<EpochValidator>,
+    /// The number of the first consensus output in the epoch.
+    pub first_sub_dag: u64,
+    /// The number of the last consensus output in the epoch.
+    pub last_sub_dag: u64,
+    /// The number of consensus outputs in the epoch.
+    pub total_sub_dags: u64,
+    /// The first execution block built for the epoch, if any.
+    pub first_block: Option<u64>,
+    /// The last execution block built for the epoch, if any.
+    pub last_block: Option<u64>,
+    /// The hash of the last execution block of the epoch, if any.
+    pub last_block_hash: Option<BlockHash>,
+    /// The digest of the epoch's last consensus header.
+    pub checkpoint_hash: B256,
+}
+
+/// A committee member in an [EpochSummary].
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EpochValidator {
+    /// The authority id.
+    pub id: AuthorityIdentifier,
+    /// The authority's BLS public key.
+    pub protocol_key: BlsPublicKey,
+    /// The reputation score in the epoch's last consensus output.
+    pub reputation_score: u64,
+}
+
 impl Default for ConsensusHeader {
     fn default() -> Self {
         let sub_dag = CommittedSubDag::new(
@@ -70,7 +185,13 @@ impl Default for ConsensusHeader {
             crate::ReputationScores::default(),
             None,
         );
-        Self { parent_hash: B256::default(), sub_dag, number: 0, extra: B256::default() }
+        Self {
+            parent_hash: B256::default(),
+            sub_dag,
+            number: 0,
+            extra: B256::default(),
+            withdrawals: Withdrawals::default(),
+        }
     }
 }
 
@@ -81,6 +202,7 @@ impl From<ConsensusOutput> for ConsensusHeader {
             sub_dag: Arc::unwrap_or_clone(value.sub_dag),
             number: value.number,
             extra: value.extra,
+            withdrawals: value.withdrawals,
         }
     }
 }
@@ -96,3 +218,66 @@ impl From<&ConsensusHeader> for Vec<u8> {
         crate::encode(value)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{BlockAnnouncement, ConsensusHeader};
+    use crate::{decode, encode, Address, ExecHeader, Withdrawal, Withdrawals, B256};
+    use blake2::Digest as _;
+
+    #[test]
+    fn test_consensus_header_commits_to_withdrawals() {
+        let header = ConsensusHeader { number: 4, ..Default::default() };
+        // headers without withdrawals hash the same as before withdrawals were recorded
+        let mut hasher = crate::crypto::DefaultHashFunction::new();
+        hasher.update(header.parent_hash);
+        hasher.update(crate::Hash::digest(&header.sub_dag));
+        hasher.update(header.number.to_le_bytes());
+        assert_eq!(header.digest(), B256::from_slice(&hasher.finalize()[..]));
+
+        let withdrawal = Withdrawal {
+            index: 0,
+            validator_index: 1,
+            address: Address::with_last_byte(7),
+            amount: 9,
+        };
+        let with_withdrawals =
+            ConsensusHeader { withdrawals: Withdrawals::new(vec![withdrawal]), ..header.clone() };
+        assert_ne!(with_withdrawals.digest(), header.digest());
+
+        // withdrawals survive storage encoding
+        let decoded: ConsensusHeader = decode(&encode(&with_withdrawals));
+        assert_eq!(decoded.withdrawals, with_withdrawals.withdrawals);
+        assert_eq!(decoded.digest(), with_withdrawals.digest());
+    }
+
+    #[test]
+    fn test_block_announcement() {
+        let consensus_hash = B256::with_last_byte(1);
+        let header = ExecHeader {
+            number: 7,
+            base_fee_per_gas: Some(7),
+            parent_beacon_block_root: Some(consensus_hash),
+            ..Default::default()
+        };
+        let announcement = BlockAnnouncement::new(3, &header).expect("executed for consensus");
+        assert_eq!(announcement.consensus_hash, consensus_hash);
+
+        // survives gossip encoding
+        let announcement: BlockAnnouncement = decode(&encode(&announcement));
+        let sealed = announcement.header().expect("valid header");
+        assert_eq!(sealed.hash(), header.hash_slow());
+
+        // the announced consensus hash must match the header
+        let forged =
+            BlockAnnouncement { consensus_hash: B256::with_last_byte(2), ..announcement.clone() };
+        assert!(forged.header().is_err());
+
+        // the announced block hash must match the header
+        let forged = BlockAnnouncement { block_hash: B256::with_last_byte(2), ..announcement };
+        assert!(forged.header().is_err());
+
+        // blocks not executed for consensus are not announced
+        assert!(BlockAnnouncement::new(3, &ExecHeader::default()).is_none());
+    }
+}
@@ -382,6 +382,16 @@ impl Certificate {
     pub fn update_created_at_for_test(&mut self, timestamp: TimestampSec) {
         self.created_at = timestamp;
     }
+
+    /// Change the authorities that signed the certificate.
+    ///
+    /// Only Used for testing.
+    pub fn update_signed_authorities_for_test(
+        &mut self,
+        signed_authorities: roaring::RoaringBitmap,
+    ) {
+        self.signed_authorities = signed_authorities;
+    }
 }
 
 impl From<&[u8]> for Certificate {
@@ -0,0 +1,151 @@
+//! Cache of decoded certificates in front of the certificate store.
+//!
+//! The consensus DB keeps its tables in memory as encoded bytes, so every certificate read pays
+//! to decode the header and signature again. Consensus reads the last few rounds far more often
+//! than older ones, so a small LRU of decoded certificates serves most reads.
+
+use super::{Certificate, CertificateDigest};
+use lru::LruCache;
+use parking_lot::Mutex;
+use prometheus::{default_registry, register_int_counter_with_registry, IntCounter, Registry};
+use std::{num::NonZeroUsize, sync::Arc};
+
+/// The default number of certificates cached.
+///
+/// Twice the last 10 rounds of a committee of 100 validators, so shards that fill unevenly still
+/// hold their part of the recent rounds. See the `certificate_cache` benchmark in tn-storage.
+pub const DEFAULT_CERTIFICATE_CACHE_CAPACITY: usize = 2_000;
+
+/// The default number of independently locked shards of the cache.
+pub const DEFAULT_CERTIFICATE_CACHE_SHARDS: usize = 8;
+
+/// Hit, miss and eviction counts of a [CertificateStoreCache].
+#[derive(Clone, Debug)]
+pub struct CertificateStoreCacheMetrics {
+    /// Certificate reads served from the cache.
+    pub hits: IntCounter,
+    /// Certificate reads that had to decode the certificate from the store.
+    pub misses: IntCounter,
+    /// Certificates dropped from the cache to make room for another.
+    pub evictions: IntCounter,
+}
+
+impl CertificateStoreCacheMetrics {
+    /// Create the metrics and register them with `registry`.
+    pub fn try_new(registry: &Registry) -> Result<Self, prometheus::Error> {
+        Ok(Self {
+            hits: register_int_counter_with_registry!(
+                "certificate_store_cache_hits",
+                "Certificate reads served from the certificate store cache",
+                registry
+            )?,
+            misses: register_int_counter_with_registry!(
+                "certificate_store_cache_misses",
+                "Certificate reads that missed the certificate store cache",
+                registry
+            )?,
+            evictions: register_int_counter_with_registry!(
+                "certificate_store_cache_evictions",
+                "Certificates evicted from the certificate store cache",
+                registry
+            )?,
+        })
+    }
+}
+
+impl Default for CertificateStoreCacheMetrics {
+    fn default() -> Self {
+        // Tests open several caches in one process, which fails to register the metrics again.
+        // Fall back to a private registry so they still count.
+        match Self::try_new(default_registry()) {
+            Ok(metrics) => metrics,
+            Err(_) => {
+                Self::try_new(&Registry::new()).expect("Prometheus error, are you using it wrong?")
+            }
+        }
+    }
+}
+
+/// A sharded LRU of decoded certificates by digest.
+///
+/// Certificates are spread over the shards by digest so concurrent readers rarely wait on the
+/// same lock. Clones share the cache.
+#[derive(Clone, Debug)]
+pub struct CertificateStoreCache {
+    /// The shards, each holding an equal part of the capacity.
+    shards: Arc<Vec<Mutex<LruCache<CertificateDigest, Certificate>>>>,
+    /// The cache metrics.
+    metrics: Arc<CertificateStoreCacheMetrics>,
+}
+
+impl CertificateStoreCache {
+    /// Create a new instance of [Self] holding about `capacity` certificates in `shards` shards.
+    ///
+    /// The capacity is split evenly between the shards, each shard holds at least one
+    /// certificate.
+    pub fn new(capacity: usize, shards: usize, metrics: CertificateStoreCacheMetrics) -> Self {
+        let shards = shards.max(1);
+        let shard_capacity =
+            NonZeroUsize::new(capacity.div_ceil(shards)).unwrap_or(NonZeroUsize::MIN);
+        let shards = (0..shards).map(|_| Mutex::new(LruCache::new(shard_capacity))).collect();
+        Self { shards: Arc::new(shards), metrics: Arc::new(metrics) }
+    }
+
+    /// The shard holding `digest`.
+    fn shard(
+        &self,
+        digest: &CertificateDigest,
+    ) -> &Mutex<LruCache<CertificateDigest, Certificate>> {
+        let bytes = digest.as_ref();
+        let index = u64::from_le_bytes(bytes[..8].try_into().expect("digest longer than 8 bytes"));
+        &self.shards[(index % self.shards.len() as u64) as usize]
+    }
+
+    /// Return the cached certificate for `digest` and mark it recently used.
+    pub fn get(&self, digest: &CertificateDigest) -> Option<Certificate> {
+        let certificate = self.shard(digest).lock().get(digest).cloned();
+        match certificate {
+            Some(_) => self.metrics.hits.inc(),
+            None => self.metrics.misses.inc(),
+        }
+        certificate
+    }
+
+    /// Cache `certificate` under `digest`, evicting the least recently used certificate of its
+    /// shard when full.
+    pub fn insert(&self, digest: CertificateDigest, certificate: Certificate) {
+        if let Some((evicted, _)) = self.shard(&digest).lock().push(digest, certificate) {
+            // push returns the old entry when the digest was already cached
+            if evicted != digest {
+                self.metrics.evictions.inc();
+            }
+        }
+    }
+
+    /// Drop the certificate for `digest`, if cached.
+    pub fn remove(&self, digest: &CertificateDigest) {
+        self.shard(digest).lock().pop(digest);
+    }
+
+    /// Drop every cached certificate.
+    pub fn clear(&self) {
+        for shard in self.shards.iter() {
+            shard.lock().clear();
+        }
+    }
+
+    /// The number of cached certificates.
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.lock().len()).sum()
+    }
+
+    /// True if no certificate is cached.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The cache metrics.
+    pub fn metrics(&self) -> &CertificateStoreCacheMetrics {
+        &self.metrics
+    }
+}
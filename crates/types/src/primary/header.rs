@@ -1,8 +1,9 @@
 use crate::{
     crypto, encode,
     error::{HeaderError, HeaderResult},
-    now, AuthorityIdentifier, Batch, BlockHash, BlockNumHash, CertificateDigest, Committee, Digest,
-    Epoch, Hash, Round, TimestampSec, VoteDigest, WorkerCache, WorkerId,
+    forks, now, payload_root, AuthorityIdentifier, Batch, BlockHash, BlockNumHash,
+    CertificateDigest, Committee, Digest, Epoch, Forks, Hash, PayloadProof, Round, TimestampSec,
+    VoteDigest, WorkerCache, WorkerId,
 };
 use base64::{engine::general_purpose, Engine};
 use blake2::Digest as _;
@@ -27,6 +28,9 @@ pub struct Header {
     /// IndexMap of the [BatchDigest] to the [WorkerId] and [TimestampSec]
     #[serde(with = "indexmap::map::serde_seq")]
     pub payload: IndexMap<BlockHash, (WorkerId, TimestampSec)>,
+    /// The Merkle root of the ordered batch digests in the payload, see [payload_root].
+    #[builder(setter(skip))]
+    pub payload_root: BlockHash,
     /// Parent certificates for this Header.
     pub parents: BTreeSet<CertificateDigest>,
     /// Hash and number of the latest known execution block when this Header was build.
@@ -48,12 +52,26 @@ impl Header {
         payload: IndexMap<BlockHash, (WorkerId, TimestampSec)>,
         parents: BTreeSet<CertificateDigest>,
         latest_execution_block: BlockNumHash,
+    ) -> Self {
+        Self::new_at(author, round, epoch, payload, parents, latest_execution_block, now())
+    }
+
+    /// Initialize a new instance of [HeaderV1] created at `created_at`.
+    pub fn new_at(
+        author: AuthorityIdentifier,
+        round: Round,
+        epoch: Epoch,
+        payload: IndexMap<BlockHash, (WorkerId, TimestampSec)>,
+        parents: BTreeSet<CertificateDigest>,
+        latest_execution_block: BlockNumHash,
+        created_at: TimestampSec,
     ) -> Self {
         let header = Self {
             author,
             round,
             epoch,
-            created_at: now(),
+            created_at,
+            payload_root: payload_root(payload.keys()),
             payload,
             parents,
             digest: OnceCell::default(),
@@ -83,6 +101,11 @@ impl Header {
             return Err(HeaderError::InvalidHeaderDigest);
         }
 
+        // Ensure the payload root commits to the payload.
+        if payload_root(self.payload.keys()) != self.payload_root {
+            return Err(HeaderError::InvalidPayloadRoot);
+        }
+
         // Ensure the authority has voting rights.
         let voting_rights = committee.voting_power_by_id(&self.author);
         if voting_rights == 0 {
@@ -125,6 +148,16 @@ impl Header {
     pub fn payload(&self) -> &IndexMap<BlockHash, (WorkerId, TimestampSec)> {
         &self.payload
     }
+    /// The Merkle root of the payload's batch digests.
+    pub fn payload_root(&self) -> &BlockHash {
+        &self.payload_root
+    }
+    /// Proof that the batch with `digest` is in the payload, None if it is not.
+    pub fn payload_proof(&self, digest: &BlockHash) -> Option<PayloadProof> {
+        let index = self.payload.get_index_of(digest)?;
+        let digests: Vec<_> = self.payload.keys().copied().collect();
+        PayloadProof::new(&digests, index)
+    }
     /// The parents for the header.
     pub fn parents(&self) -> &BTreeSet<CertificateDigest> {
         &self.parents
@@ -139,6 +172,7 @@ impl Header {
         &mut self,
         new_payload: IndexMap<BlockHash, (WorkerId, TimestampSec)>,
     ) {
+        self.payload_root = payload_root(new_payload.keys());
         self.payload = new_payload;
     }
 
@@ -171,12 +205,14 @@ impl HeaderBuilder {
     /// This is used for tests, if used for "real" code then at least latest_execution_block will
     /// need to be visited.
     pub fn build(self) -> Header {
+        let payload = self.payload.unwrap_or_default();
         let h = Header {
             author: self.author.expect("author set for header builder"),
             round: self.round.expect("round set for header builder"),
             epoch: self.epoch.expect("epoch set for header builder"),
             created_at: self.created_at.unwrap_or(0),
-            payload: self.payload.unwrap_or_default(),
+            payload_root: payload_root(payload.keys()),
+            payload,
             parents: self.parents.expect("parents set for header builder"),
             digest: OnceCell::default(),
             latest_execution_block: self.latest_execution_block.unwrap_or_default(),
@@ -252,12 +288,69 @@ impl Hash<{ crypto::DIGEST_LENGTH }> for Header {
     type TypedDigest = HeaderDigest;
 
     fn digest(&self) -> HeaderDigest {
+        self.digest_with(&forks())
+    }
+}
+
+impl Header {
+    /// The header's digest on a network with `forks`.
+    ///
+    /// Headers commit to the payload root once the fork is active for their epoch. Earlier
+    /// headers hash the fields they had before the payload root was added, so their digests are
+    /// unchanged.
+    fn digest_with(&self, forks: &Forks) -> HeaderDigest {
         let mut hasher = crypto::DefaultHashFunction::new();
-        hasher.update(encode(&self));
+        if forks.payload_root_active(self.epoch) {
+            hasher.update(encode(&HeaderCommitment {
+                author: &self.author,
+                round: self.round,
+                epoch: self.epoch,
+                created_at: self.created_at,
+                payload_root: &self.payload_root,
+                parents: &self.parents,
+                latest_execution_block: &self.latest_execution_block,
+            }));
+        } else {
+            hasher.update(encode(&LegacyHeader {
+                author: &self.author,
+                round: self.round,
+                epoch: self.epoch,
+                created_at: self.created_at,
+                payload: self.payload.iter().collect(),
+                parents: &self.parents,
+                latest_execution_block: &self.latest_execution_block,
+            }));
+        }
         HeaderDigest(hasher.finalize().into())
     }
 }
 
+/// The fields a header's digest commits to once the payload root fork is active.
+#[derive(Serialize)]
+struct HeaderCommitment<'a> {
+    author: &'a AuthorityIdentifier,
+    round: Round,
+    epoch: Epoch,
+    created_at: TimestampSec,
+    payload_root: &'a BlockHash,
+    parents: &'a BTreeSet<CertificateDigest>,
+    latest_execution_block: &'a BlockNumHash,
+}
+
+/// The fields a header's digest commits to before the payload root fork.
+///
+/// Encodes the same as headers written before the payload root was added.
+#[derive(Serialize)]
+struct LegacyHeader<'a> {
+    author: &'a AuthorityIdentifier,
+    round: Round,
+    epoch: Epoch,
+    created_at: TimestampSec,
+    payload: Vec<(&'a BlockHash, &'a (WorkerId, TimestampSec))>,
+    parents: &'a BTreeSet<CertificateDigest>,
+    latest_execution_block: &'a BlockNumHash,
+}
+
 impl fmt::Debug for Header {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> Result<(), fmt::Error> {
         write!(
@@ -284,3 +377,48 @@ impl PartialEq for Header {
         self.digest() == other.digest()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(epoch: Epoch) -> Header {
+        let mut header = HeaderBuilder::default()
+            .author(AuthorityIdentifier::dummy_for_test(1))
+            .round(3)
+            .epoch(epoch)
+            .parents(BTreeSet::new())
+            .build();
+        header.update_payload_for_test(IndexMap::from([(BlockHash::with_last_byte(1), (0, 5))]));
+        header
+    }
+
+    #[test]
+    fn test_digest_before_fork() {
+        let header = header(1);
+        let digest = header.digest_with(&Forks::NONE);
+        assert_eq!(digest, header.digest_with(&Forks { payload_root: Some(2), ..Forks::NONE }));
+
+        // the payload root is not part of the digest
+        let mut changed = header.clone();
+        changed.payload_root = BlockHash::ZERO;
+        assert_eq!(changed.digest_with(&Forks::NONE), digest);
+        changed.payload.insert(BlockHash::with_last_byte(2), (0, 5));
+        assert_ne!(changed.digest_with(&Forks::NONE), digest);
+    }
+
+    #[test]
+    fn test_digest_commits_to_payload_root() {
+        let forks = Forks { payload_root: Some(1), ..Forks::NONE };
+        let header = header(1);
+        let digest = header.digest_with(&forks);
+        assert_ne!(digest, header.digest_with(&Forks::NONE));
+
+        // the payload is only committed to through its root
+        let mut changed = header.clone();
+        changed.payload.insert(BlockHash::with_last_byte(2), (0, 5));
+        assert_eq!(changed.digest_with(&forks), digest);
+        changed.payload_root = payload_root(changed.payload.keys());
+        assert_ne!(changed.digest_with(&forks), digest);
+    }
+}
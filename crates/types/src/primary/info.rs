@@ -1,5 +1,9 @@
 //! Primary information for peers.
-use crate::{get_available_udp_port, Multiaddr, NetworkKeypair, NetworkPublicKey, WorkerIndex};
+use crate::{
+    get_available_udp_port, host_multiaddr, Multiaddr, NetworkKeypair, NetworkPublicKey,
+    WorkerIndex,
+};
+use libp2p::multiaddr::Protocol;
 use serde::{Deserialize, Serialize};
 
 /// Information for the Primary.
@@ -42,13 +46,13 @@ impl PrimaryInfo {
 impl Default for PrimaryInfo {
     fn default() -> Self {
         let host = std::env::var("NARWHAL_HOST").unwrap_or("127.0.0.1".to_string());
-        let primary_udp_port = get_available_udp_port(&host).unwrap_or(49590).to_string();
+        let primary_udp_port = get_available_udp_port(&host).unwrap_or(49590);
 
         Self {
             network_key: NetworkKeypair::generate_ed25519().public().into(),
-            network_address: format!("/ip4/{}/udp/{}/quic-v1", &host, primary_udp_port)
-                .parse()
-                .expect("multiaddr parsed for primary"),
+            network_address: host_multiaddr(&host)
+                .with(Protocol::Udp(primary_udp_port))
+                .with(Protocol::QuicV1),
             worker_network_key: NetworkKeypair::generate_ed25519().public().into(),
             worker_index: Default::default(),
         }
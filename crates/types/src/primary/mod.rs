@@ -3,18 +3,26 @@
 use std::time::{Duration, SystemTime};
 mod block;
 mod certificate;
+mod certificate_cache;
 mod header;
 mod info;
 mod output;
+mod payload_commitment;
 mod reputation;
+mod system_call;
+mod timestamp_policy;
 mod vote;
 
 pub use block::*;
 pub use certificate::*;
+pub use certificate_cache::*;
 pub use header::*;
 pub use info::*;
 pub use output::*;
+pub use payload_commitment::*;
 pub use reputation::*;
+pub use system_call::*;
+pub use timestamp_policy::*;
 pub use vote::*;
 
 /// For now, use 0 to prevent any removal of bad nodes since validator sets are static.
@@ -28,6 +36,13 @@ pub use vote::*;
 /// have higher confidence."
 pub const DEFAULT_BAD_NODES_STAKE_THRESHOLD: u64 = 0;
 
+/// The largest percentage of stake that can be swapped out of the leader schedule.
+pub const MAX_BAD_NODES_STAKE_THRESHOLD: u64 = 33;
+
+/// The number of committed sub-dags in each leader schedule. Reputation scores reset and the
+/// schedule can change after each window.
+pub const DEFAULT_SUB_DAGS_PER_SCHEDULE: u32 = 300;
+
 /// The round number.
 /// Becomes the lower 32 bits of a nonce (with epoch the high bits).
 pub type Round = u32;
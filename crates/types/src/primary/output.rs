@@ -6,7 +6,7 @@ use crate::{
     crypto, encode,
     error::{CertificateError, CertificateResult},
     Address, Batch, BlockHash, Certificate, Committee, Digest, Epoch, Hash, ReputationScores,
-    Round, TimestampSec, B256,
+    Round, TimestampSec, Withdrawals, B256,
 };
 use blake2::Digest as _;
 use serde::{Deserialize, Serialize};
@@ -51,6 +51,12 @@ pub struct ConsensusOutput {
     /// be false unless running a node with the potential to advertise a forked block or
     /// two before quitting.
     pub early_finalize: bool,
+    /// Protocol-level withdrawals (EIP-4895) to credit after executing this output.
+    ///
+    /// The withdrawals are applied in the last block executed for the output. They are recorded
+    /// in the [ConsensusHeader] and included in [Self] digest, so nodes that replay or sync the
+    /// consensus chain credit the same withdrawals.
+    pub withdrawals: Withdrawals,
 }
 
 impl ConsensusOutput {
@@ -101,12 +107,18 @@ impl ConsensusOutput {
             sub_dag: (*self.sub_dag).clone(),
             number: self.number,
             extra: self.extra,
+            withdrawals: self.withdrawals.clone(),
         }
     }
 
     /// Return the hash of the consensus header that matches this output.
     pub fn consensus_header_hash(&self) -> B256 {
-        ConsensusHeader::digest_from_parts(self.parent_hash, &self.sub_dag, self.number)
+        ConsensusHeader::digest_from_parts(
+            self.parent_hash,
+            &self.sub_dag,
+            self.number,
+            &self.withdrawals,
+        )
     }
 }
 
@@ -0,0 +1,161 @@
+//! Merkle commitment to a header's payload.
+//!
+//! Headers commit to the ordered batch digests in their payload with a binary Merkle tree so light
+//! clients and bridges can verify a batch is included in a certified header from the header's
+//! `payload_root` and a proof of `log2(n)` hashes, without the whole payload. Nodes are hashed
+//! with keccak256 so proofs can be verified by contracts on the execution chain.
+//!
+//! Leaves and inner nodes use different prefixes so an inner node can not be passed off as a leaf.
+//! A node without a sibling is carried up to the next level unchanged. The root of an empty
+//! payload is zero.
+
+use crate::{keccak256, BlockHash};
+use serde::{Deserialize, Serialize};
+
+/// Prefix for hashing a batch digest into a leaf.
+const LEAF_PREFIX: u8 = 0;
+
+/// Prefix for hashing two nodes into their parent.
+const NODE_PREFIX: u8 = 1;
+
+/// The leaf for a batch digest.
+fn leaf(digest: &BlockHash) -> BlockHash {
+    keccak256([&[LEAF_PREFIX], digest.as_slice()].concat())
+}
+
+/// The parent of two nodes.
+fn parent(left: &BlockHash, right: &BlockHash) -> BlockHash {
+    keccak256([&[NODE_PREFIX], left.as_slice(), right.as_slice()].concat())
+}
+
+/// The next level of the tree.
+fn next_level(level: &[BlockHash]) -> Vec<BlockHash> {
+    level
+        .chunks(2)
+        .map(|pair| match pair {
+            [left, right] => parent(left, right),
+            [single] => *single,
+            _ => unreachable!("chunks of two"),
+        })
+        .collect()
+}
+
+/// The Merkle root of the ordered batch digests in a payload.
+pub fn payload_root<'a>(digests: impl IntoIterator<Item = &'a BlockHash>) -> BlockHash {
+    let mut level: Vec<_> = digests.into_iter().map(leaf).collect();
+    if level.is_empty() {
+        return BlockHash::ZERO;
+    }
+    while level.len() > 1 {
+        level = next_level(&level);
+    }
+    level[0]
+}
+
+/// Proof that a batch digest is included in a header's payload.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PayloadProof {
+    /// The position of the batch in the payload.
+    pub index: u64,
+    /// The number of batches in the payload.
+    pub len: u64,
+    /// The siblings from the leaf to the root, skipping levels where the node has no sibling.
+    pub siblings: Vec<BlockHash>,
+}
+
+impl PayloadProof {
+    /// Create the proof for the batch at `index` of the ordered payload `digests`.
+    ///
+    /// Returns None if `index` is out of bounds.
+    pub fn new(digests: &[BlockHash], index: usize) -> Option<Self> {
+        if index >= digests.len() {
+            return None;
+        }
+        let mut siblings = Vec::new();
+        let mut level: Vec<_> = digests.iter().map(leaf).collect();
+        let mut position = index;
+        while level.len() > 1 {
+            if let Some(sibling) = level.get(position ^ 1) {
+                siblings.push(*sibling);
+            }
+            level = next_level(&level);
+            position /= 2;
+        }
+        Some(Self { index: index as u64, len: digests.len() as u64, siblings })
+    }
+
+    /// True if the proof shows `digest` is included in the payload committed to by `root`.
+    pub fn verify(&self, digest: &BlockHash, root: &BlockHash) -> bool {
+        if self.index >= self.len {
+            return false;
+        }
+        let mut siblings = self.siblings.iter();
+        let mut node = leaf(digest);
+        let (mut position, mut len) = (self.index, self.len);
+        while len > 1 {
+            // the last node of an odd level has no sibling
+            if position % 2 == 1 || position + 1 < len {
+                let Some(sibling) = siblings.next() else {
+                    return false;
+                };
+                node =
+                    if position % 2 == 0 { parent(&node, sibling) } else { parent(sibling, &node) };
+            }
+            position /= 2;
+            len = len.div_ceil(2);
+        }
+        siblings.next().is_none() && node == *root
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn digests(n: u8) -> Vec<BlockHash> {
+        (0..n).map(|i| keccak256([i])).collect()
+    }
+
+    #[test]
+    fn test_payload_root() {
+        assert_eq!(payload_root(&[] as &[BlockHash]), BlockHash::ZERO);
+
+        let one = digests(1);
+        assert_eq!(payload_root(&one), leaf(&one[0]));
+
+        let three = digests(3);
+        let expected = parent(&parent(&leaf(&three[0]), &leaf(&three[1])), &leaf(&three[2]));
+        assert_eq!(payload_root(&three), expected);
+
+        // the root commits to the order of the payload
+        let reversed: Vec<_> = three.iter().rev().copied().collect();
+        assert_ne!(payload_root(&reversed), expected);
+    }
+
+    #[test]
+    fn test_payload_proof() {
+        for n in 1..=9 {
+            let digests = digests(n);
+            let root = payload_root(&digests);
+            for (index, digest) in digests.iter().enumerate() {
+                let proof = PayloadProof::new(&digests, index).expect("index in bounds");
+                assert!(proof.verify(digest, &root), "proof for {index} of {n}");
+
+                // a proof only verifies its own batch and root
+                assert!(!proof.verify(&keccak256([u8::MAX]), &root));
+                assert!(!proof.verify(digest, &BlockHash::ZERO));
+                let moved = PayloadProof { index: (proof.index + 1) % proof.len, ..proof.clone() };
+                assert!(n == 1 || !moved.verify(digest, &root));
+            }
+            assert!(PayloadProof::new(&digests, n as usize).is_none());
+        }
+
+        // an inner node is not a valid leaf
+        let digests = digests(4);
+        let root = payload_root(&digests);
+        let inner = parent(&leaf(&digests[0]), &leaf(&digests[1]));
+        let right = parent(&leaf(&digests[2]), &leaf(&digests[3]));
+        let proof = PayloadProof { index: 0, len: 2, siblings: vec![right] };
+        assert!(!proof.verify(&inner, &root));
+    }
+}
@@ -0,0 +1,106 @@
+//! System calls executed at the boundaries of committed sub-dags.
+
+use crate::{hex_literal, Address, SolValue as _, B256, U256};
+use alloy::primitives::Selector;
+use serde::{Deserialize, Serialize};
+
+/// The caller of system calls, from EIP-4788.
+///
+/// The account is removed from the state after each call so it never holds a balance or nonce.
+pub const SYSTEM_ADDRESS: Address =
+    Address::new(hex_literal::hex!("fffffffffffffffffffffffffffffffffffffffe"));
+
+/// The account that records failed system calls.
+///
+/// Calls have no receipt, so failures are recorded in this account's storage where they are part
+/// of the block's state root and readable with `eth_getStorageAt`. For the system call at index
+/// `i` of the chain spec, slot `2 * i` counts its failures and slot `2 * i + 1` holds the last
+/// sub-dag it failed for. See [system_call_failure_slots].
+pub const SYSTEM_CALL_FAILURES_ADDRESS: Address =
+    Address::new(hex_literal::hex!("fffffffffffffffffffffffffffffffffffffffd"));
+
+/// The storage slots in [SYSTEM_CALL_FAILURES_ADDRESS] with the number of failures and the last
+/// failed sub-dag of the system call at `index` in the chain spec.
+pub fn system_call_failure_slots(index: usize) -> (U256, U256) {
+    let count = U256::from(index) * U256::from(2);
+    (count, count + U256::from(1))
+}
+
+/// The most gas a system call can be given.
+pub const MAX_SYSTEM_CALL_GAS: u64 = 30_000_000;
+
+/// When a system call runs relative to the transactions of a committed sub-dag.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SystemCallPhase {
+    /// Before the transactions of the first block executed for the sub-dag.
+    SubDagStart,
+    /// After the transactions of the last block executed for the sub-dag, before withdrawals.
+    SubDagEnd,
+}
+
+/// A contract call the protocol makes for every committed sub-dag.
+///
+/// Used for protocol upkeep like epoch rollover, reward distribution, or flushing bridge queues.
+/// The call is made from [SYSTEM_ADDRESS] with no value and no gas price. Its calldata is the
+/// selector followed by the ABI encoded `(uint256 subDag, bytes32 consensusHeaderHash, uint256
+/// commitTimestamp)`, so every node makes identical calls for the same consensus output.
+///
+/// The gas used by system calls is not part of the block's gas used and is not limited by the
+/// block's gas limit. A call that reverts or runs out of gas changes nothing but its record in
+/// [SYSTEM_CALL_FAILURES_ADDRESS].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SystemCall {
+    /// The contract to call.
+    pub address: Address,
+    /// The selector of the called function.
+    pub selector: Selector,
+    /// When the call runs.
+    pub phase: SystemCallPhase,
+    /// The gas available to the call, at most [MAX_SYSTEM_CALL_GAS].
+    pub gas_limit: u64,
+}
+
+impl SystemCall {
+    /// The calldata for the call made for sub-dag `sub_dag`.
+    pub fn calldata(
+        &self,
+        sub_dag: u64,
+        consensus_header_hash: B256,
+        commit_timestamp: u64,
+    ) -> Vec<u8> {
+        let args = (U256::from(sub_dag), consensus_header_hash, U256::from(commit_timestamp));
+        let mut calldata = self.selector.to_vec();
+        calldata.extend(args.abi_encode_params());
+        calldata
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{system_call_failure_slots, SystemCall, SystemCallPhase};
+    use crate::{Address, B256, U256};
+
+    #[test]
+    fn test_system_call_calldata() {
+        let call = SystemCall {
+            address: Address::with_last_byte(1),
+            selector: [0xde, 0xad, 0xbe, 0xef].into(),
+            phase: SystemCallPhase::SubDagEnd,
+            gas_limit: 100_000,
+        };
+        let calldata = call.calldata(7, B256::repeat_byte(0xaa), 1_000);
+        assert_eq!(calldata.len(), 4 + 3 * 32);
+        assert_eq!(&calldata[..4], &[0xde, 0xad, 0xbe, 0xef]);
+        assert_eq!(calldata[35], 7);
+        assert_eq!(&calldata[36..68], B256::repeat_byte(0xaa).as_slice());
+        assert_eq!(&calldata[98..100], &1_000u16.to_be_bytes());
+    }
+
+    #[test]
+    fn test_system_call_failure_slots() {
+        assert_eq!(system_call_failure_slots(0), (U256::ZERO, U256::from(1)));
+        assert_eq!(system_call_failure_slots(3), (U256::from(6), U256::from(7)));
+    }
+}
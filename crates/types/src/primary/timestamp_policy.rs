@@ -0,0 +1,60 @@
+//! Timestamps for executed blocks.
+
+use super::TimestampSec;
+use serde::{Deserialize, Serialize};
+
+/// How executed blocks are timestamped.
+///
+/// Timestamps only come from the committed sub-dag and the parent block, never the executor's
+/// local clock, so every validator produces identical headers for the same consensus output.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum TimestampPolicy {
+    /// Every block for a sub-dag uses the sub-dag's commit timestamp, or the parent's timestamp
+    /// if the commit timestamp is earlier.
+    ///
+    /// Blocks from the same sub-dag share a timestamp.
+    #[default]
+    CommitTimestamp,
+    /// Like [TimestampPolicy::CommitTimestamp] but each block is at least one second after its
+    /// parent, for tooling that expects strictly increasing timestamps.
+    ///
+    /// Timestamps run ahead of the commit timestamp while the chain produces more than one block
+    /// per second.
+    StrictlyIncreasing,
+}
+
+impl TimestampPolicy {
+    /// The timestamp for a block executed for a sub-dag committed at `commit_timestamp` on top of
+    /// a parent block with `parent_timestamp`.
+    pub fn block_timestamp(
+        &self,
+        commit_timestamp: TimestampSec,
+        parent_timestamp: TimestampSec,
+    ) -> TimestampSec {
+        match self {
+            Self::CommitTimestamp => commit_timestamp.max(parent_timestamp),
+            Self::StrictlyIncreasing => commit_timestamp.max(parent_timestamp.saturating_add(1)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TimestampPolicy;
+
+    #[test]
+    fn test_block_timestamps_are_monotonic() {
+        let policy = TimestampPolicy::CommitTimestamp;
+        assert_eq!(policy.block_timestamp(100, 90), 100);
+        // blocks for the same sub-dag share a timestamp
+        assert_eq!(policy.block_timestamp(100, 100), 100);
+        // never earlier than the parent
+        assert_eq!(policy.block_timestamp(80, 90), 90);
+
+        let policy = TimestampPolicy::StrictlyIncreasing;
+        assert_eq!(policy.block_timestamp(100, 90), 100);
+        assert_eq!(policy.block_timestamp(100, 100), 101);
+        assert_eq!(policy.block_timestamp(80, 90), 91);
+    }
+}
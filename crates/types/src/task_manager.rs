@@ -13,6 +13,7 @@ use tokio::{
     sync::mpsc,
     task::{JoinError, JoinHandle},
 };
+use tracing::Instrument as _;
 
 /// Used for the futures that will resolve when tasks do.
 /// Allows us to hold a FuturesUnordered in directly in the TaskManager struct.
@@ -39,6 +40,17 @@ impl Future for TaskHandle {
     }
 }
 
+/// Why a [TaskManager] stopped joining its tasks.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskManagerExit {
+    /// The process received ctrl-c or SIGTERM.
+    Signal,
+    /// Shutdown was requested with the [Notifier].
+    Shutdown,
+    /// A task or sub-task manager stopped before shutdown was requested.
+    TaskExited(String),
+}
+
 /// A basic task manager.
 ///
 /// Allows new tasks to be be started on the tokio runtime and tracks
@@ -64,9 +76,12 @@ impl TaskManagerClone {
         F::Output: Send + 'static,
     {
         let name = name.to_string();
-        let handle = tokio::spawn(async move {
-            future.await;
-        });
+        let handle = tokio::spawn(
+            async move {
+                future.await;
+            }
+            .in_current_span(),
+        );
         if let Err(err) = self.new_task_tx.try_send(TaskHandle { name, handle }) {
             tracing::error!(target: "tn::tasks", "Task error sending joiner: {err}");
         }
@@ -93,15 +108,20 @@ impl TaskManager {
     }
 
     /// Spawns a task on tokio and records it's JoinHandle and name.
+    ///
+    /// The task runs in the current tracing span, so logs keep the node's span fields.
     pub fn spawn_task<F, S: ToString>(&self, name: S, future: F)
     where
         F: Future + Send + 'static,
         F::Output: Send + 'static,
     {
         let name = name.to_string();
-        let handle = tokio::spawn(async move {
-            future.await;
-        });
+        let handle = tokio::spawn(
+            async move {
+                future.await;
+            }
+            .in_current_span(),
+        );
         self.tasks.push(TaskHandle { name, handle });
     }
 
@@ -119,8 +139,8 @@ impl TaskManager {
     ///
     /// Note the manager is based on the assumption that all tasks added via spawn_task
     /// are critical and and one stopping is problem.
-    pub async fn join(&mut self, shutdown: Notifier) {
-        self.join_internal(shutdown, false).await;
+    pub async fn join(&mut self, shutdown: Notifier) -> TaskManagerExit {
+        self.join_internal(shutdown, false).await
     }
 
     /// Will resolve once one of the tasks for the manager resolves.
@@ -128,8 +148,8 @@ impl TaskManager {
     /// Note the manager is based on the assumption that all tasks added via spawn_task
     /// are critical and and one stopping is problem.
     /// Also will end if the user hits ctrl-c or sends a SIGTERM to the app.
-    pub async fn join_until_exit(&mut self, shutdown: Notifier) {
-        self.join_internal(shutdown, true).await;
+    pub async fn join_until_exit(&mut self, shutdown: Notifier) -> TaskManagerExit {
+        self.join_internal(shutdown, true).await
     }
 
     /// Abort all of our direct tasks (not sub task managers though).
@@ -158,7 +178,10 @@ impl TaskManager {
     }
 
     /// Implements the join logic for the manager.
-    async fn join_internal(&mut self, shutdown: Notifier, do_exit: bool) {
+    ///
+    /// Shutdown is checked first so a task that requests shutdown before returning is not reported
+    /// as exiting early.
+    async fn join_internal(&mut self, shutdown: Notifier, do_exit: bool) -> TaskManagerExit {
         let shutdown_ref = &shutdown;
         let mut future_managers: FuturesUnordered<_> = self
             .submanagers
@@ -166,15 +189,16 @@ impl TaskManager {
             .map(|mut sub| async move { (sub.join(shutdown_ref.clone()).await, sub.name.clone()) })
             .collect();
         let rx_shutdown = shutdown.subscribe();
-        loop {
+        let exit = loop {
             tokio::select! {
-                _ = Self::exit(do_exit) => {
-                    tracing::info!(target: "tn::tasks", "{}: Node exiting", self.name);
-                    break;
-                },
+                biased;
                 _ = &rx_shutdown => {
                     tracing::info!(target: "tn::tasks", "{}: Node exiting, received shutdown notification", self.name);
-                    break;
+                    break TaskManagerExit::Shutdown;
+                },
+                _ = Self::exit(do_exit) => {
+                    tracing::info!(target: "tn::tasks", "{}: Node exiting", self.name);
+                    break TaskManagerExit::Signal;
                 },
                 Some(task) = self.new_task_rx.recv() => {
                     self.tasks.push(task);
@@ -188,22 +212,24 @@ impl TaskManager {
                     match res {
                         Some(Ok(name)) => {
                             tracing::error!(target: "tn::tasks", "{}: {name} returned Ok, node exiting", self.name);
+                            break TaskManagerExit::TaskExited(name);
                         }
                         Some(Err((name, join_err))) => {
                             tracing::error!(target: "tn::tasks", "{}: {name} returned error {join_err}, node exiting", self.name);
+                            break TaskManagerExit::TaskExited(name);
                         }
                         None => {
                             tracing::error!(target: "tn::tasks", "{}: Out of tasks! node exiting", self.name);
+                            break TaskManagerExit::TaskExited(self.name.clone());
                         }
                     }
-                    break;
                 }
-                Some((_, name)) = future_managers.next() => {
+                Some((exit, name)) = future_managers.next() => {
                     tracing::error!(target: "tn::tasks", "{}: Sub-Task Manager {name} returned exited, node exiting", self.name);
-                    break;
+                    break exit;
                 }
             }
-        }
+        };
         // No matter how we exit notify shutdown and allow a chance for other tasks to exit
         // cleanly.
         shutdown.notify();
@@ -256,6 +282,8 @@ impl TaskManager {
         {
             tracing::error!(target = "tn::tasks", "{}: All tasks managers NOT shutdown", task_name);
         }
+
+        exit
     }
 
     /// Will resolve when ctrl-c is pressed or a SIGTERM is received.
@@ -311,7 +339,7 @@ impl Debug for TaskManager {
 
 impl reth_tasks::TaskSpawner for TaskManagerClone {
     fn spawn(&self, fut: BoxFuture<'static, ()>) -> JoinHandle<()> {
-        tokio::spawn(fut)
+        tokio::spawn(fut.in_current_span())
     }
 
     fn spawn_critical(&self, name: &'static str, fut: BoxFuture<'static, ()>) -> JoinHandle<()> {
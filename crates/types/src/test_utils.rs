@@ -0,0 +1,250 @@
+//! Property-based test generators for consensus types.
+//!
+//! The strategies build structurally valid values without a committee, signatures are real but
+//! don't verify. They are used to check that digests and the wire encoding stay stable.
+
+use crate::{
+    payload_root, AuthorityIdentifier, Batch, BlockNumHash, BlsKeypair, BlsSignature, Certificate,
+    CertificateDigest, CommittedSubDag, ConsensusOutput, Header, ReputationScores,
+    SignatureVerificationState, Signer as _, B256,
+};
+use indexmap::IndexMap;
+use proptest::{
+    arbitrary::Arbitrary,
+    collection,
+    prelude::{any, Just, Strategy},
+    prop_oneof,
+    strategy::BoxedStrategy,
+};
+use rand::{rngs::StdRng, SeedableRng as _};
+use std::{collections::VecDeque, sync::Arc};
+
+/// The largest number of certificates in a generated sub dag.
+const MAX_SUB_DAG_CERTIFICATES: usize = 6;
+
+/// An authority id from a small set so generated values share authors.
+pub fn arb_authority_id() -> impl Strategy<Value = AuthorityIdentifier> {
+    (0..16_u8).prop_map(AuthorityIdentifier::dummy_for_test)
+}
+
+/// A 32 byte hash.
+pub fn arb_b256() -> impl Strategy<Value = B256> {
+    any::<[u8; 32]>().prop_map(B256::from)
+}
+
+/// A consensus header with a random payload and parents.
+pub fn arb_header() -> impl Strategy<Value = Header> {
+    (
+        arb_authority_id(),
+        any::<u32>(),
+        any::<u32>(),
+        any::<u64>(),
+        collection::vec((arb_b256(), any::<u16>(), any::<u64>()), 0..4),
+        collection::btree_set(any::<[u8; 32]>().prop_map(CertificateDigest::new), 0..4),
+        (any::<u64>(), arb_b256()),
+    )
+        .prop_map(|(author, round, epoch, created_at, payload, parents, (number, hash))| {
+            let payload = payload
+                .into_iter()
+                .map(|(digest, worker_id, timestamp)| (digest, (worker_id, timestamp)))
+                .collect::<IndexMap<_, _>>();
+            Header {
+                author,
+                round,
+                epoch,
+                created_at,
+                payload_root: payload_root(payload.keys()),
+                payload,
+                parents,
+                latest_execution_block: BlockNumHash::new(number, hash),
+                digest: Default::default(),
+            }
+        })
+}
+
+/// A BLS signature from a keypair generated with the seed.
+pub fn arb_signature() -> impl Strategy<Value = BlsSignature> {
+    (any::<u64>(), any::<[u8; 32]>())
+        .prop_map(|(seed, msg)| BlsKeypair::generate(&mut StdRng::seed_from_u64(seed)).sign(&msg))
+}
+
+/// Any signature verification state.
+pub fn arb_signature_verification_state() -> impl Strategy<Value = SignatureVerificationState> {
+    prop_oneof![
+        arb_signature().prop_map(SignatureVerificationState::Unsigned),
+        arb_signature().prop_map(SignatureVerificationState::Unverified),
+        arb_signature().prop_map(SignatureVerificationState::VerifiedDirectly),
+        arb_signature().prop_map(SignatureVerificationState::VerifiedIndirectly),
+        Just(SignatureVerificationState::Genesis),
+    ]
+}
+
+/// A certificate for a random header.
+pub fn arb_certificate() -> impl Strategy<Value = Certificate> {
+    (
+        arb_header(),
+        arb_signature_verification_state(),
+        collection::btree_set(0..64_u32, 0..16),
+        any::<u64>(),
+    )
+        .prop_map(|(header, state, signed_authorities, created_at)| {
+            let mut certificate = Certificate::default();
+            certificate.update_header_for_test(header);
+            certificate.set_signature_verification_state(state);
+            certificate
+                .update_signed_authorities_for_test(signed_authorities.into_iter().collect());
+            certificate.update_created_at_for_test(created_at);
+            certificate
+        })
+}
+
+/// Reputation scores for authorities from [arb_authority_id].
+pub fn arb_reputation_scores() -> impl Strategy<Value = ReputationScores> {
+    (collection::hash_map(arb_authority_id(), any::<u64>(), 0..8), any::<bool>()).prop_map(
+        |(scores_per_authority, final_of_schedule)| ReputationScores {
+            scores_per_authority,
+            final_of_schedule,
+        },
+    )
+}
+
+/// A committed sub dag, the leader is the last certificate.
+pub fn arb_committed_sub_dag() -> impl Strategy<Value = CommittedSubDag> {
+    (
+        collection::vec(arb_certificate(), 1..MAX_SUB_DAG_CERTIFICATES),
+        any::<u64>(),
+        arb_reputation_scores(),
+    )
+        .prop_map(|(certificates, sub_dag_index, reputation_score)| {
+            let leader = certificates.last().expect("at least one certificate").clone();
+            CommittedSubDag::new(certificates, leader, sub_dag_index, reputation_score, None)
+        })
+}
+
+/// A batch with random transaction bytes.
+pub fn arb_batch() -> impl Strategy<Value = Batch> {
+    (
+        collection::vec(collection::vec(any::<u8>(), 0..64), 0..4),
+        arb_b256(),
+        any::<[u8; 20]>(),
+        any::<u64>(),
+        any::<Option<u64>>(),
+    )
+        .prop_map(|(transactions, parent_hash, beneficiary, timestamp, base_fee_per_gas)| {
+            Batch {
+                transactions,
+                parent_hash,
+                beneficiary: beneficiary.into(),
+                timestamp,
+                base_fee_per_gas,
+                received_at: None,
+            }
+        })
+}
+
+/// Consensus output with batches for every certificate in the sub dag.
+pub fn arb_consensus_output() -> impl Strategy<Value = ConsensusOutput> {
+    arb_committed_sub_dag()
+        .prop_flat_map(|sub_dag| {
+            let batches = collection::vec(collection::vec(arb_batch(), 0..3), sub_dag.len());
+            (Just(sub_dag), batches, any::<[u8; 20]>(), arb_b256(), any::<u64>(), arb_b256())
+        })
+        .prop_map(|(sub_dag, batches, beneficiary, parent_hash, number, extra)| {
+            let batch_digests: VecDeque<_> =
+                batches.iter().flat_map(|batches| batches.iter().map(Batch::digest)).collect();
+            ConsensusOutput {
+                sub_dag: Arc::new(sub_dag),
+                batches,
+                beneficiary: beneficiary.into(),
+                batch_digests,
+                parent_hash,
+                number,
+                extra,
+                early_finalize: false,
+                withdrawals: Default::default(),
+            }
+        })
+}
+
+impl Arbitrary for Certificate {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        arb_certificate().boxed()
+    }
+}
+
+impl Arbitrary for CommittedSubDag {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        arb_committed_sub_dag().boxed()
+    }
+}
+
+impl Arbitrary for ConsensusOutput {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        arb_consensus_output().boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        decode, encode, Certificate, CommittedSubDag, ConsensusHeader, ConsensusOutput, Hash as _,
+        ReputationScores, B256,
+    };
+    use proptest::{prelude::any, prop_assert_eq, proptest};
+
+    proptest! {
+        #[test]
+        fn test_certificate_round_trip(certificate in any::<Certificate>()) {
+            let bytes = encode(&certificate);
+            let decoded: Certificate = decode(&bytes);
+            prop_assert_eq!(decoded.digest(), certificate.digest());
+            prop_assert_eq!(encode(&decoded), bytes);
+        }
+
+        #[test]
+        fn test_committed_sub_dag_round_trip(sub_dag in any::<CommittedSubDag>()) {
+            let bytes = encode(&sub_dag);
+            let decoded: CommittedSubDag = decode(&bytes);
+            prop_assert_eq!(decoded.digest(), sub_dag.digest());
+            prop_assert_eq!(encode(&decoded), bytes);
+        }
+
+        #[test]
+        fn test_committed_sub_dag_digest_skips_reputation(
+            sub_dag in any::<CommittedSubDag>(),
+            final_of_schedule in any::<bool>(),
+        ) {
+            let mut rescored = sub_dag.clone();
+            rescored.reputation_score = ReputationScores {
+                final_of_schedule,
+                ..Default::default()
+            };
+            prop_assert_eq!(rescored.digest(), sub_dag.digest());
+        }
+
+        #[test]
+        fn test_consensus_output_digest(output in any::<ConsensusOutput>()) {
+            // the output digest commits to the consensus header sent over the wire
+            let header = output.consensus_header();
+            let bytes = encode(&header);
+            let decoded: ConsensusHeader = decode(&bytes);
+            prop_assert_eq!(encode(&decoded), bytes);
+            prop_assert_eq!(decoded.digest(), output.consensus_header_hash());
+            prop_assert_eq!(B256::from(output.digest()), output.consensus_header_hash());
+
+            // batches are not part of the digest
+            let mut without_batches = output.clone();
+            without_batches.batches.clear();
+            prop_assert_eq!(without_batches.digest(), output.digest());
+        }
+    }
+}
@@ -0,0 +1,115 @@
+//! The gas limit for batches and the blocks executed from them.
+//!
+//! The executor sets the gas limit of every block it executes from consensus output. Workers
+//! build and validate batches against the gas limit of the block they extend, so the batch maker,
+//! the batch validator, and the executor enforce the same limit.
+
+use super::max_batch_gas;
+use crate::{Address, ExecHeader, B256, U256};
+
+/// The storage slot in a governance contract that holds the batch gas limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GovernedBatchGas {
+    /// The governance contract.
+    pub address: Address,
+    /// The storage slot of the batch gas limit.
+    pub slot: B256,
+    /// The smallest accepted limit.
+    pub min: u64,
+    /// The largest accepted limit.
+    pub max: u64,
+}
+
+/// How the executor sets the gas limit of executed blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BatchGasSchedule {
+    /// The gas limit when it is not governed, or the governed value is rejected.
+    pub max_batch_gas: u64,
+    /// Where the governed batch gas limit is stored, `None` if the limit is not governed.
+    pub governance: Option<GovernedBatchGas>,
+    /// The number of consensus outputs in each epoch.
+    pub epoch_length: u64,
+}
+
+impl BatchGasSchedule {
+    /// Return the gas limit for blocks executed from consensus output `output`.
+    ///
+    /// A governed limit is read when an epoch starts from the state after `parent`, the last
+    /// block executed for the previous epoch, and carried forward by every block of the epoch.
+    /// `storage` reads a slot of the governance contract in that state. Values of zero or outside
+    /// the governed bounds fall back to `max_batch_gas`.
+    pub fn gas_limit<F, E>(&self, parent: &ExecHeader, output: u64, storage: F) -> Result<u64, E>
+    where
+        F: FnOnce(Address, B256) -> Result<U256, E>,
+    {
+        let Some(governance) = self.governance else {
+            return Ok(self.max_batch_gas);
+        };
+        // the first output executes on top of genesis
+        let epoch_start = parent.number == 0 || output % self.epoch_length.max(1) == 0;
+        if !epoch_start {
+            return Ok(parent.gas_limit);
+        }
+        let governed = storage(governance.address, governance.slot)?;
+        Ok(u64::try_from(governed)
+            .ok()
+            .filter(|gas| (governance.min..=governance.max).contains(gas))
+            .unwrap_or(self.max_batch_gas))
+    }
+}
+
+impl Default for BatchGasSchedule {
+    fn default() -> Self {
+        Self { max_batch_gas: max_batch_gas(0), governance: None, epoch_length: 1 }
+    }
+}
+
+/// Return the max gas for the transactions of a batch that extends `parent`.
+///
+/// Falls back to [max_batch_gas] if the parent is unknown.
+pub fn batch_gas_limit(parent: &ExecHeader) -> u64 {
+    if parent.gas_limit == 0 {
+        max_batch_gas(parent.timestamp)
+    } else {
+        parent.gas_limit
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_gas_schedule() {
+        let parent = ExecHeader { number: 5, gas_limit: 20_000_000, ..Default::default() };
+        let slot = B256::with_last_byte(6);
+        let read = |value: u64| {
+            move |_, read_slot| {
+                assert_eq!(read_slot, slot);
+                Ok::<_, ()>(U256::from(value))
+            }
+        };
+
+        // not governed
+        let mut schedule = BatchGasSchedule::default();
+        assert_eq!(schedule.gas_limit(&parent, 10, read(50_000_000)), Ok(max_batch_gas(0)));
+
+        // governed values only change when an epoch starts
+        schedule.governance = Some(GovernedBatchGas {
+            address: Address::random(),
+            slot,
+            min: 1_000_000,
+            max: 1_000_000_000,
+        });
+        schedule.epoch_length = 10;
+        assert_eq!(schedule.gas_limit(&parent, 11, read(50_000_000)), Ok(20_000_000));
+        assert_eq!(schedule.gas_limit(&parent, 10, read(50_000_000)), Ok(50_000_000));
+        let genesis = ExecHeader::default();
+        assert_eq!(schedule.gas_limit(&genesis, 1, read(50_000_000)), Ok(50_000_000));
+
+        // unset and out of bounds values use the chain spec
+        assert_eq!(schedule.gas_limit(&parent, 10, read(0)), Ok(max_batch_gas(0)));
+        assert_eq!(schedule.gas_limit(&parent, 10, read(1_000)), Ok(max_batch_gas(0)));
+        assert_eq!(schedule.gas_limit(&parent, 10, read(u64::MAX)), Ok(max_batch_gas(0)));
+    }
+}
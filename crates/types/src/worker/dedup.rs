@@ -0,0 +1,159 @@
+//! Rolling filter of transactions that peers already included in batches.
+//!
+//! The same transaction can be submitted to more than one worker. Each worker records the
+//! transactions in batches it accepts from peers, and the batch builder skips them so block space
+//! isn't wasted on duplicates. The filter forgets old transactions so a transaction from a peer's
+//! batch that never reaches consensus is eventually proposed again.
+
+use crate::{keccak256, Batch, TxHash};
+use parking_lot::Mutex;
+use std::sync::Arc;
+
+/// The default number of transactions remembered by each generation of a [TxDedupFilter].
+pub const DEFAULT_TX_DEDUP_CAPACITY: usize = 100_000;
+
+/// The number of bits per transaction (~1% false positives).
+const BITS_PER_TX: usize = 10;
+
+/// The number of bits set for each transaction.
+const NUM_HASHES: u64 = 7;
+
+/// A shared, rolling bloom filter keyed by transaction hash.
+///
+/// The filter keeps two generations. Once the current generation holds `capacity` transactions
+/// it becomes the previous generation and the oldest generation is dropped, so a transaction is
+/// remembered for at least `capacity` insertions.
+///
+/// False positives only delay a transaction until its generation rolls over.
+#[derive(Clone, Debug)]
+pub struct TxDedupFilter {
+    inner: Arc<Mutex<RollingBloom>>,
+}
+
+impl TxDedupFilter {
+    /// Create a new instance of [Self] that remembers at least `capacity` transactions.
+    pub fn new(capacity: usize) -> Self {
+        Self { inner: Arc::new(Mutex::new(RollingBloom::new(capacity.max(1)))) }
+    }
+
+    /// Record a transaction hash.
+    pub fn insert(&self, tx_hash: &TxHash) {
+        self.inner.lock().insert(tx_hash);
+    }
+
+    /// Record every transaction in a batch.
+    pub fn insert_batch(&self, batch: &Batch) {
+        let mut inner = self.inner.lock();
+        for tx in batch.transactions.iter() {
+            inner.insert(&keccak256(tx));
+        }
+    }
+
+    /// Return true if the transaction hash was probably recorded.
+    pub fn contains(&self, tx_hash: &TxHash) -> bool {
+        self.inner.lock().contains(tx_hash)
+    }
+}
+
+impl Default for TxDedupFilter {
+    fn default() -> Self {
+        Self::new(DEFAULT_TX_DEDUP_CAPACITY)
+    }
+}
+
+/// The two generations of bits behind a [TxDedupFilter].
+#[derive(Debug)]
+struct RollingBloom {
+    /// The generation receiving new transactions.
+    current: Vec<u64>,
+    /// The generation before `current`.
+    previous: Vec<u64>,
+    /// The number of transactions inserted into `current`.
+    inserted: usize,
+    /// The number of transactions per generation.
+    capacity: usize,
+}
+
+impl RollingBloom {
+    fn new(capacity: usize) -> Self {
+        let words = (capacity * BITS_PER_TX).div_ceil(64);
+        Self { current: vec![0; words], previous: vec![0; words], inserted: 0, capacity }
+    }
+
+    fn insert(&mut self, tx_hash: &TxHash) {
+        if self.contains_in(&self.current, tx_hash) {
+            return;
+        }
+
+        if self.inserted >= self.capacity {
+            self.previous = std::mem::replace(&mut self.current, vec![0; self.previous.len()]);
+            self.inserted = 0;
+        }
+
+        for bit in self.bits(tx_hash) {
+            self.current[bit / 64] |= 1 << (bit % 64);
+        }
+        self.inserted += 1;
+    }
+
+    fn contains(&self, tx_hash: &TxHash) -> bool {
+        self.contains_in(&self.current, tx_hash) || self.contains_in(&self.previous, tx_hash)
+    }
+
+    fn contains_in(&self, words: &[u64], tx_hash: &TxHash) -> bool {
+        self.bits(tx_hash).all(|bit| words[bit / 64] & (1 << (bit % 64)) != 0)
+    }
+
+    /// The bit positions for a transaction hash.
+    ///
+    /// Transaction hashes are already uniformly distributed, so positions are derived from the
+    /// hash directly with double hashing.
+    fn bits(&self, tx_hash: &TxHash) -> impl Iterator<Item = usize> {
+        let num_bits = (self.current.len() * 64) as u64;
+        let h1 = u64::from_le_bytes(tx_hash[..8].try_into().expect("8 bytes"));
+        let h2 = u64::from_le_bytes(tx_hash[8..16].try_into().expect("8 bytes")) | 1;
+        (0..NUM_HASHES).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % num_bits) as usize)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TxDedupFilter;
+    use crate::{keccak256, Batch, TxHash};
+
+    #[test]
+    fn test_tx_dedup_filter_rolls_over() {
+        let filter = TxDedupFilter::new(10);
+        let hashes: Vec<TxHash> = (0..30u32).map(|i| keccak256(i.to_le_bytes())).collect();
+
+        for hash in hashes[..10].iter() {
+            filter.insert(hash);
+        }
+        assert!(hashes[..10].iter().all(|hash| filter.contains(hash)));
+
+        // the first generation is still remembered while the second fills
+        for hash in hashes[10..20].iter() {
+            filter.insert(hash);
+        }
+        assert!(hashes[..20].iter().all(|hash| filter.contains(hash)));
+
+        // the first generation is forgotten
+        for hash in hashes[20..].iter() {
+            filter.insert(hash);
+        }
+        assert!(hashes[10..].iter().all(|hash| filter.contains(hash)));
+        assert!(hashes[..10].iter().filter(|hash| filter.contains(hash)).count() < 10);
+    }
+
+    #[test]
+    fn test_tx_dedup_filter_batch() {
+        let filter = TxDedupFilter::default();
+        let batch =
+            Batch { transactions: vec![vec![1, 2, 3], vec![4, 5, 6]], ..Default::default() };
+        filter.insert_batch(&batch);
+
+        assert!(filter.contains(&keccak256([1, 2, 3])));
+        assert!(filter.contains(&keccak256([4, 5, 6])));
+        assert!(!filter.contains(&keccak256([7, 8, 9])));
+    }
+}
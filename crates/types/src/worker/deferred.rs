@@ -0,0 +1,224 @@
+//! Transactions accepted while the worker's batches can't reach quorum.
+//!
+//! During a consensus outage the batch builder can't get batches acknowledged by peers. The
+//! worker's RPC keeps accepting transactions into the pool up to a cap and marks them as deferred
+//! instead of depending on what the blocked batch builder does. Once a batch reaches quorum again,
+//! the batch builder flushes the deferred transactions without waiting for its seal interval.
+
+use crate::TxHash;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{collections::HashSet, sync::Arc};
+use tracing::{info, warn};
+
+/// The default number of transactions accepted while batches can't reach quorum.
+pub const DEFAULT_MAX_DEFERRED_TRANSACTIONS: usize = 10_000;
+
+/// The worker's deferred transactions served by the RPC.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeferredStatus {
+    /// True if the worker's last batch failed to reach quorum.
+    pub quorum_lost: bool,
+    /// The number of deferred transactions still in the pool.
+    pub deferred: usize,
+    /// The maximum number of transactions accepted while quorum is lost.
+    pub capacity: usize,
+}
+
+/// Tracks quorum for the worker's batches and the transactions accepted without it.
+///
+/// Clones share the same state so the batch builder can report quorum and the RPC can defer
+/// transactions.
+#[derive(Clone, Debug)]
+pub struct DeferredTransactions {
+    inner: Arc<Mutex<DeferredState>>,
+}
+
+/// The state behind [DeferredTransactions].
+#[derive(Debug)]
+struct DeferredState {
+    /// True if the worker's last batch failed to reach quorum.
+    quorum_lost: bool,
+    /// The transactions accepted while quorum was lost that are still in the pool.
+    deferred: HashSet<TxHash>,
+    /// The number of slots held by transactions that are being added to the pool.
+    reserved: usize,
+    /// The maximum number of deferred transactions.
+    capacity: usize,
+}
+
+impl DeferredState {
+    /// Return true if every slot is deferred or reserved.
+    fn is_full(&self) -> bool {
+        self.deferred.len() + self.reserved >= self.capacity
+    }
+}
+
+/// A slot reserved for a transaction that is being added to the pool.
+///
+/// The slot counts toward the cap until it is committed with the transaction's hash or dropped,
+/// so concurrent requests can never defer more than the cap.
+#[derive(Debug)]
+pub struct DeferredSlot {
+    /// The deferred transactions the slot is reserved in.
+    deferred: DeferredTransactions,
+    /// True once the slot holds a deferred transaction.
+    committed: bool,
+}
+
+impl DeferredSlot {
+    /// Mark the transaction added to the pool as deferred.
+    pub fn commit(mut self, tx_hash: TxHash) {
+        let mut inner = self.deferred.inner.lock();
+        inner.reserved -= 1;
+        if inner.deferred.insert(tx_hash) {
+            warn!(
+                target: "worker::deferred",
+                %tx_hash,
+                deferred = inner.deferred.len(),
+                capacity = inner.capacity,
+                "batches are not reaching quorum, transaction deferred"
+            );
+        }
+        self.committed = true;
+    }
+}
+
+impl Drop for DeferredSlot {
+    fn drop(&mut self) {
+        // the pool rejected the transaction
+        if !self.committed {
+            self.deferred.inner.lock().reserved -= 1;
+        }
+    }
+}
+
+impl DeferredTransactions {
+    /// Create a new instance of [Self] that defers at most `capacity` transactions.
+    pub fn new(capacity: usize) -> Self {
+        let state =
+            DeferredState { quorum_lost: false, deferred: HashSet::new(), reserved: 0, capacity };
+        Self { inner: Arc::new(Mutex::new(state)) }
+    }
+
+    /// Record that a batch failed to reach quorum.
+    pub fn quorum_lost(&self) {
+        let mut inner = self.inner.lock();
+        if !inner.quorum_lost {
+            warn!(
+                target: "worker::deferred",
+                capacity = inner.capacity,
+                "batches are not reaching quorum, deferring new transactions"
+            );
+            inner.quorum_lost = true;
+        }
+    }
+
+    /// Record that a batch reached quorum.
+    ///
+    /// Deferred transactions are flushed by the batch builder once quorum is restored.
+    pub fn quorum_restored(&self) {
+        let mut inner = self.inner.lock();
+        if inner.quorum_lost {
+            info!(
+                target: "worker::deferred",
+                deferred = inner.deferred.len(),
+                "batches are reaching quorum again, flushing deferred transactions"
+            );
+            inner.quorum_lost = false;
+        }
+    }
+
+    /// Return true if the worker's last batch failed to reach quorum.
+    pub fn is_quorum_lost(&self) -> bool {
+        self.inner.lock().quorum_lost
+    }
+
+    /// Reserve a slot for a transaction before it is added to the pool.
+    ///
+    /// Returns `Ok(None)` if the transaction doesn't need a slot because batches reach quorum or
+    /// it is already deferred, and `Err` with the cap once every slot is taken. `tx_hash` is
+    /// `None` for transactions whose hash is only known once the pool accepts them.
+    pub fn reserve(&self, tx_hash: Option<&TxHash>) -> Result<Option<DeferredSlot>, usize> {
+        let mut inner = self.inner.lock();
+        if !inner.quorum_lost || tx_hash.is_some_and(|tx_hash| inner.deferred.contains(tx_hash)) {
+            return Ok(None);
+        }
+        if inner.is_full() {
+            return Err(inner.capacity);
+        }
+        inner.reserved += 1;
+        Ok(Some(DeferredSlot { deferred: self.clone(), committed: false }))
+    }
+
+    /// Return true if any of the transactions are deferred.
+    pub fn any_deferred<'a>(&self, mut tx_hashes: impl Iterator<Item = &'a TxHash>) -> bool {
+        let inner = self.inner.lock();
+        !inner.deferred.is_empty() && tx_hashes.any(|tx_hash| inner.deferred.contains(tx_hash))
+    }
+
+    /// Keep the deferred transactions that are still in the pool.
+    pub fn retain(&self, in_pool: impl Fn(&TxHash) -> bool) {
+        self.inner.lock().deferred.retain(|tx_hash| in_pool(tx_hash));
+    }
+
+    /// Return the current status.
+    pub fn status(&self) -> DeferredStatus {
+        let inner = self.inner.lock();
+        DeferredStatus {
+            quorum_lost: inner.quorum_lost,
+            deferred: inner.deferred.len(),
+            capacity: inner.capacity,
+        }
+    }
+}
+
+impl Default for DeferredTransactions {
+    fn default() -> Self {
+        Self::new(DEFAULT_MAX_DEFERRED_TRANSACTIONS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keccak256;
+
+    #[test]
+    fn test_deferred_transactions() {
+        let deferred = DeferredTransactions::new(2);
+        let txs: Vec<TxHash> = (0..3u32).map(|i| keccak256(i.to_le_bytes())).collect();
+
+        // nothing is deferred while batches reach quorum
+        assert!(deferred.reserve(Some(&txs[0])).expect("accepted").is_none());
+        assert!(!deferred.any_deferred(txs.iter()));
+
+        // transactions are deferred up to the cap once quorum is lost
+        deferred.quorum_lost();
+        deferred.reserve(Some(&txs[0])).expect("accepted").expect("reserved").commit(txs[0]);
+        let slot = deferred.reserve(None).expect("accepted").expect("reserved");
+
+        // reserved slots count toward the cap
+        assert_eq!(deferred.reserve(Some(&txs[2])).err(), Some(2));
+        assert!(deferred.reserve(Some(&txs[0])).expect("already deferred").is_none());
+
+        // slots of transactions the pool rejects are released
+        drop(slot);
+        deferred.reserve(None).expect("accepted").expect("reserved").commit(txs[1]);
+        assert_eq!(
+            deferred.status(),
+            DeferredStatus { quorum_lost: true, deferred: 2, capacity: 2 }
+        );
+
+        // restoring quorum accepts new transactions and keeps deferred ones to flush
+        deferred.quorum_restored();
+        assert!(deferred.reserve(Some(&txs[2])).expect("accepted").is_none());
+        assert!(deferred.any_deferred(txs[1..].iter()));
+
+        // flushed transactions leave the pool
+        deferred.retain(|tx_hash| *tx_hash == txs[1]);
+        assert!(!deferred.any_deferred(txs[..1].iter()));
+        assert_eq!(deferred.status().deferred, 1);
+    }
+}
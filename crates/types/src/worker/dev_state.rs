@@ -0,0 +1,105 @@
+//! State changes for single-node dev networks.
+//!
+//! Tools like anvil let developers set balances and code directly. Telcoin Network only changes
+//! state by executing consensus output, so changes requested through the `tn_dev` RPC namespace are
+//! queued here and the engine applies them before the transactions of the next block it executes.
+
+use crate::{Address, Bytes, U256};
+use parking_lot::Mutex;
+use std::{collections::BTreeMap, sync::Arc};
+
+/// The changes to one account.
+///
+/// Fields that are `None` keep the account's current value.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct AccountOverride {
+    /// The account's balance.
+    pub balance: Option<U256>,
+    /// The account's nonce.
+    pub nonce: Option<u64>,
+    /// The account's code. Empty code removes the account's code.
+    pub code: Option<Bytes>,
+    /// The values of storage slots by key.
+    pub storage: BTreeMap<U256, U256>,
+}
+
+impl AccountOverride {
+    /// Apply the changes in `other` on top of these changes.
+    fn merge(&mut self, other: AccountOverride) {
+        if other.balance.is_some() {
+            self.balance = other.balance;
+        }
+        if other.nonce.is_some() {
+            self.nonce = other.nonce;
+        }
+        if other.code.is_some() {
+            self.code = other.code;
+        }
+        self.storage.extend(other.storage);
+    }
+}
+
+/// Account changes waiting for the engine to execute the next block.
+///
+/// Clones share the same queue. Only single-node dev networks may change state this way, since
+/// the changes are not part of consensus output and other validators would not apply them.
+#[derive(Debug, Clone, Default)]
+pub struct DevStateChanges {
+    inner: Arc<Mutex<BTreeMap<Address, AccountOverride>>>,
+}
+
+impl DevStateChanges {
+    /// Queue changes to an account, replacing queued values for the same fields.
+    pub fn queue(&self, address: Address, changes: AccountOverride) {
+        self.inner.lock().entry(address).or_default().merge(changes);
+    }
+
+    /// Take every queued change.
+    pub fn take(&self) -> BTreeMap<Address, AccountOverride> {
+        std::mem::take(&mut *self.inner.lock())
+    }
+
+    /// Return true if no changes are queued.
+    pub fn is_empty(&self) -> bool {
+        self.inner.lock().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AccountOverride, DevStateChanges};
+    use crate::{Address, Bytes, U256};
+
+    #[test]
+    fn test_queued_changes_merge() {
+        let changes = DevStateChanges::default();
+        let address = Address::repeat_byte(1);
+        changes.queue(
+            address,
+            AccountOverride {
+                balance: Some(U256::from(1)),
+                storage: [(U256::from(1), U256::from(1))].into(),
+                ..Default::default()
+            },
+        );
+        changes.queue(
+            address,
+            AccountOverride {
+                code: Some(Bytes::from_static(&[0x60])),
+                storage: [(U256::from(1), U256::from(2)), (U256::from(2), U256::ZERO)].into(),
+                ..Default::default()
+            },
+        );
+
+        let queued = changes.take();
+        assert!(changes.is_empty());
+        let account = queued.get(&address).expect("queued account");
+        assert_eq!(account.balance, Some(U256::from(1)));
+        assert_eq!(account.nonce, None);
+        assert_eq!(account.code, Some(Bytes::from_static(&[0x60])));
+        assert_eq!(
+            account.storage,
+            [(U256::from(1), U256::from(2)), (U256::from(2), U256::ZERO)].into()
+        );
+    }
+}
@@ -0,0 +1,189 @@
+//! Signed promises that a worker sealed a transaction in a batch.
+//!
+//! When a worker seals one of its own batches, every transaction in it has reached a quorum of
+//! workers and will be proposed by the primary. The worker signs a promise for each transaction
+//! with its network key so clients get evidence of acceptance before the batch is committed.
+//!
+//! Promises are kept in memory for the most recent transactions only.
+
+use crate::{
+    encode, keccak256, Batch, BlockHash, Bytes, Intent, IntentMessage, IntentScope, NetworkKeypair,
+    NetworkPublicKey, Round, TxHash,
+};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+use tokio::sync::watch;
+
+/// The default number of promises kept by [InclusionPromises].
+pub const DEFAULT_INCLUSION_PROMISE_CAPACITY: usize = 100_000;
+
+/// A worker's signed promise that a transaction is in one of its sealed batches.
+///
+/// The signature is a network signature committed over the intent message `intent || message`.
+/// The message is constructed as: transaction hash || batch digest || round.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct InclusionPromise {
+    /// The hash of the transaction.
+    pub tx_hash: TxHash,
+    /// The digest of the sealed batch that contains the transaction.
+    pub batch_digest: BlockHash,
+    /// The primary's round when the batch was sealed.
+    ///
+    /// The batch is proposed in a header for this round or a later one.
+    pub round: Round,
+    /// The network key of the worker that sealed the batch.
+    pub worker_network_key: NetworkPublicKey,
+    /// The worker's signature over the promise.
+    pub signature: Bytes,
+}
+
+impl InclusionPromise {
+    /// Create a new, signed promise.
+    pub fn new(
+        keypair: &NetworkKeypair,
+        tx_hash: TxHash,
+        batch_digest: BlockHash,
+        round: Round,
+    ) -> Self {
+        let message = encode(&Self::intent_message(tx_hash, batch_digest, round));
+        let signature = keypair.sign(&message).expect("failed to sign inclusion promise");
+        Self {
+            tx_hash,
+            batch_digest,
+            round,
+            worker_network_key: keypair.public().into(),
+            signature: signature.into(),
+        }
+    }
+
+    /// Verify the worker signed this promise.
+    pub fn verify(&self) -> bool {
+        let message = encode(&Self::intent_message(self.tx_hash, self.batch_digest, self.round));
+        self.worker_network_key.verify(&message, &self.signature)
+    }
+
+    /// The intent message the worker signs for a promise.
+    fn intent_message(
+        tx_hash: TxHash,
+        batch_digest: BlockHash,
+        round: Round,
+    ) -> IntentMessage<Vec<u8>> {
+        let mut msg = tx_hash.to_vec();
+        msg.extend_from_slice(batch_digest.as_slice());
+        msg.extend_from_slice(&round.to_le_bytes());
+        IntentMessage::new(Intent::telcoin(IntentScope::InclusionPromise), msg)
+    }
+}
+
+/// Signs and remembers inclusion promises for the worker's sealed batches.
+///
+/// Clones share the same promises so the worker can record them and the RPC can serve them.
+#[derive(Clone, Debug)]
+pub struct InclusionPromises {
+    /// The worker's network key.
+    keypair: NetworkKeypair,
+    /// The primary's current round.
+    round: watch::Receiver<Round>,
+    /// The recorded promises.
+    inner: Arc<Mutex<PromiseCache>>,
+}
+
+impl InclusionPromises {
+    /// Create a new instance of [Self] that keeps at most `capacity` promises.
+    pub fn new(keypair: NetworkKeypair, round: watch::Receiver<Round>, capacity: usize) -> Self {
+        let cache = PromiseCache {
+            promises: HashMap::new(),
+            order: VecDeque::new(),
+            capacity: capacity.max(1),
+        };
+        Self { keypair, round, inner: Arc::new(Mutex::new(cache)) }
+    }
+
+    /// Sign and record a promise for every transaction in a sealed batch.
+    pub fn record_batch(&self, batch: &Batch, batch_digest: BlockHash) {
+        let round = *self.round.borrow();
+        let promises: Vec<_> = batch
+            .transactions
+            .iter()
+            .map(|tx| InclusionPromise::new(&self.keypair, keccak256(tx), batch_digest, round))
+            .collect();
+
+        let mut inner = self.inner.lock();
+        for promise in promises {
+            inner.insert(promise);
+        }
+    }
+
+    /// Return the promise for a transaction if it is still remembered.
+    pub fn get(&self, tx_hash: &TxHash) -> Option<InclusionPromise> {
+        self.inner.lock().promises.get(tx_hash).cloned()
+    }
+}
+
+/// The promises behind [InclusionPromises], oldest evicted first.
+#[derive(Debug)]
+struct PromiseCache {
+    /// Promises by transaction hash.
+    promises: HashMap<TxHash, InclusionPromise>,
+    /// Transaction hashes in the order they were recorded.
+    order: VecDeque<TxHash>,
+    /// The maximum number of promises.
+    capacity: usize,
+}
+
+impl PromiseCache {
+    fn insert(&mut self, promise: InclusionPromise) {
+        let tx_hash = promise.tx_hash;
+        if self.promises.insert(tx_hash, promise).is_none() {
+            self.order.push_back(tx_hash);
+        }
+        while self.order.len() > self.capacity {
+            if let Some(oldest) = self.order.pop_front() {
+                self.promises.remove(&oldest);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::B256;
+
+    fn batch(transactions: Vec<Vec<u8>>) -> Batch {
+        Batch { transactions, ..Default::default() }
+    }
+
+    #[test]
+    fn test_inclusion_promises() {
+        let keypair = NetworkKeypair::generate_ed25519();
+        let (round_tx, round_rx) = watch::channel(7);
+        let promises = InclusionPromises::new(keypair.clone(), round_rx, 2);
+
+        let digest = B256::with_last_byte(1);
+        promises.record_batch(&batch(vec![vec![1], vec![2]]), digest);
+
+        let promise = promises.get(&keccak256([1])).expect("promise recorded");
+        assert_eq!(promise.batch_digest, digest);
+        assert_eq!(promise.round, 7);
+        assert_eq!(promise.worker_network_key, keypair.public().into());
+        assert!(promise.verify());
+
+        // changing any field invalidates the signature
+        let mut forged = promise.clone();
+        forged.round = 8;
+        assert!(!forged.verify());
+
+        // the oldest promise is evicted once full
+        round_tx.send_replace(9);
+        promises.record_batch(&batch(vec![vec![3]]), B256::with_last_byte(2));
+        assert!(promises.get(&keccak256([1])).is_none());
+        assert!(promises.get(&keccak256([2])).is_some());
+        assert_eq!(promises.get(&keccak256([3])).expect("promise recorded").round, 9);
+    }
+}
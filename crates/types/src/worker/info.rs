@@ -1,11 +1,12 @@
 //! Worker peer information.
 
 use crate::{
-    error::ConfigError, get_available_tcp_port, get_available_udp_port, BlsPublicKey, Epoch,
-    Multiaddr, NetworkKeypair, NetworkPublicKey,
+    encode, error::ConfigError, get_available_tcp_port, get_available_udp_port, host_multiaddr,
+    BlsPublicKey, BlsSignature, BlsSigner, Epoch, Intent, IntentMessage, IntentScope, Multiaddr,
+    NetworkKeypair, NetworkPublicKey, ProtocolSignature as _,
 };
 use eyre::ContextCompat;
-use libp2p::PeerId;
+use libp2p::{multiaddr::Protocol, PeerId};
 use serde::{
     de::{self, MapAccess, Visitor},
     ser::SerializeMap,
@@ -45,20 +46,16 @@ impl Default for WorkerInfo {
     fn default() -> Self {
         // TODO: env vars should be applied at the CLI level, not here
         let host = std::env::var("NARWHAL_HOST").unwrap_or("127.0.0.1".to_string());
-        let worker_udp_port = get_available_udp_port(&host).unwrap_or(49594).to_string();
+        let worker_udp_port = get_available_udp_port(&host).unwrap_or(49594);
 
         Self {
             name: NetworkKeypair::generate_ed25519().public().into(),
-            transactions: format!(
-                "/ip4/{}/tcp/{}/http",
-                &host,
-                get_available_tcp_port(&host).unwrap_or(DEFAULT_WORKER_PORT)
-            )
-            .parse()
-            .expect("multiaddress parsed for worker txs"),
-            worker_address: format!("/ip4/{}/udp/{}/quic-v1", &host, worker_udp_port)
-                .parse()
-                .expect("multiaddr parsed for worker consensus"),
+            transactions: host_multiaddr(&host)
+                .with(Protocol::Tcp(get_available_tcp_port(&host).unwrap_or(DEFAULT_WORKER_PORT)))
+                .with(Protocol::Http),
+            worker_address: host_multiaddr(&host)
+                .with(Protocol::Udp(worker_udp_port))
+                .with(Protocol::QuicV1),
         }
     }
 }
@@ -166,6 +163,73 @@ pub struct WorkerCache {
     pub epoch: Epoch,
     /// The authority to worker index.
     pub workers: Arc<BTreeMap<BlsPublicKey, WorkerIndex>>,
+    /// The sequence number of the latest [WorkerInfoUpdate] applied for each authority.
+    ///
+    /// Persisted with the cache so old updates can't be replayed after a restart.
+    #[serde(default)]
+    pub sequences: Arc<BTreeMap<BlsPublicKey, u64>>,
+}
+
+/// A signed announcement that an authority changed its workers.
+///
+/// Authorities gossip updates to the committee when a worker moves to a new address so peers
+/// can update their [WorkerCache] without a restart. The update replaces the authority's
+/// [WorkerIndex] and is only accepted for the cache's epoch with a sequence number greater than the
+/// last update applied for the authority.
+///
+/// The signature is a [BlsSignature] committed over the intent message `intent || message`.
+/// The message is constructed as:
+/// [BlsPublicKey] || epoch || sequence || [WorkerIndex].
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WorkerInfoUpdate {
+    /// The authority's protocol key.
+    pub bls_public_key: BlsPublicKey,
+    /// The epoch of the worker cache the update applies to.
+    pub epoch: Epoch,
+    /// Increases with every update from the authority.
+    pub sequence: u64,
+    /// The authority's workers.
+    pub worker_index: WorkerIndex,
+    /// The authority's signature over the update.
+    pub signature: BlsSignature,
+}
+
+impl WorkerInfoUpdate {
+    /// Create a new, signed update.
+    pub fn new<BLS: BlsSigner>(
+        signer: &BLS,
+        bls_public_key: BlsPublicKey,
+        epoch: Epoch,
+        sequence: u64,
+        worker_index: WorkerIndex,
+    ) -> Self {
+        let message = Self::intent_message(&bls_public_key, epoch, sequence, &worker_index);
+        let signature = signer.request_signature_direct(&encode(&message));
+        Self { bls_public_key, epoch, sequence, worker_index, signature }
+    }
+
+    /// Verify the authority signed this update.
+    pub fn verify(&self) -> bool {
+        let message = Self::intent_message(
+            &self.bls_public_key,
+            self.epoch,
+            self.sequence,
+            &self.worker_index,
+        );
+        self.signature.verify_secure(&message, &self.bls_public_key)
+    }
+
+    /// The intent message the authority signs for an update.
+    fn intent_message(
+        bls_public_key: &BlsPublicKey,
+        epoch: Epoch,
+        sequence: u64,
+        worker_index: &WorkerIndex,
+    ) -> IntentMessage<Vec<u8>> {
+        let mut msg = bls_public_key.to_bytes().to_vec();
+        msg.extend_from_slice(&encode(&(epoch, sequence, worker_index)));
+        IntentMessage::new(Intent::telcoin(IntentScope::WorkerInfoUpdate), msg)
+    }
 }
 
 impl std::fmt::Display for WorkerIndex {
@@ -275,6 +339,42 @@ impl WorkerCache {
             .collect()
     }
 
+    /// Return a new cache with the authority's workers replaced by the update.
+    ///
+    /// Fails if the update is for another epoch, the authority isn't in the cache, the update is
+    /// not newer than the last update applied for the authority, or the signature is invalid.
+    pub fn apply_update(&self, update: &WorkerInfoUpdate) -> Result<Self, ConfigError> {
+        let authority = &update.bls_public_key;
+        if update.epoch != self.epoch {
+            return Err(ConfigError::InvalidWorkerUpdate(format!(
+                "update for epoch {} but worker cache is epoch {}",
+                update.epoch, self.epoch
+            )));
+        }
+        if !self.workers.contains_key(authority) {
+            return Err(ConfigError::NotInWorkerCache(authority.encode_base58()));
+        }
+        let last = self.sequences.get(authority).copied().unwrap_or_default();
+        if update.sequence <= last {
+            return Err(ConfigError::InvalidWorkerUpdate(format!(
+                "stale sequence {} (last applied {last})",
+                update.sequence
+            )));
+        }
+        if update.worker_index.0.is_empty() {
+            return Err(ConfigError::InvalidWorkerUpdate("update has no workers".to_string()));
+        }
+        if !update.verify() {
+            return Err(ConfigError::InvalidWorkerUpdate("invalid signature".to_string()));
+        }
+
+        let mut workers = (*self.workers).clone();
+        workers.insert(*authority, update.worker_index.clone());
+        let mut sequences = (*self.sequences).clone();
+        sequences.insert(*authority, update.sequence);
+        Ok(Self { epoch: self.epoch, workers: Arc::new(workers), sequences: Arc::new(sequences) })
+    }
+
     /// Return the network addresses that are present in the current worker cache
     /// that are from a primary key that are no longer in the committee. Current
     /// committee keys provided as an argument.
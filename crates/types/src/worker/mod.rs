@@ -4,11 +4,23 @@ use tokio::sync::{mpsc::Sender, oneshot};
 #[allow(clippy::mutable_key_type)]
 mod info;
 pub use info::*;
+mod batch_gas;
+pub use batch_gas::*;
 mod sealed_batch;
 pub use sealed_batch::*;
 mod pending_batch;
 use crate::error::BlockSealError;
 pub use pending_batch::*;
+mod dedup;
+pub use dedup::*;
+mod deferred;
+pub use deferred::*;
+mod dev_state;
+pub use dev_state::*;
+mod inclusion;
+pub use inclusion::*;
+mod sender_recovery;
+pub use sender_recovery::*;
 
 /// Type for the channel sender to submit sealed batches to the block provider.
 ///
@@ -2,7 +2,7 @@
 //!
 //! This is an experimental approach to supporting pending blocks for workers.
 
-use crate::{Address, SealedBlock};
+use crate::{Address, SealedBlock, SharedClock, SystemClock, TxDedupFilter};
 
 /// The arguments passed to the worker's block builder.
 #[derive(Debug)]
@@ -11,12 +11,28 @@ pub struct BatchBuilderArgs<Pool> {
     pub pool: Pool,
     /// The attributes for the next block.
     pub batch_config: PendingBlockConfig,
+    /// Transactions already included in peer batches, which are left out of the batch.
+    pub dedup_filter: Option<TxDedupFilter>,
+    /// The time source for the batch timestamp.
+    pub clock: SharedClock,
 }
 
 impl<Pool> BatchBuilderArgs<Pool> {
     /// Create a new instance of [Self].
     pub fn new(pool: Pool, batch_config: PendingBlockConfig) -> Self {
-        Self { pool, batch_config }
+        Self { pool, batch_config, dedup_filter: None, clock: SystemClock::shared() }
+    }
+
+    /// Skip transactions already included in peer batches.
+    pub fn with_dedup_filter(mut self, dedup_filter: Option<TxDedupFilter>) -> Self {
+        self.dedup_filter = dedup_filter;
+        self
+    }
+
+    /// Timestamp the batch with `clock` instead of the system time.
+    pub fn with_clock(mut self, clock: SharedClock) -> Self {
+        self.clock = clock;
+        self
     }
 }
 
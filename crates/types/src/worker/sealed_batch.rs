@@ -207,7 +207,7 @@ pub trait BatchValidation: Send + Sync {
 }
 
 /// Block validation error types
-#[derive(Error, Debug)]
+#[derive(Error, Debug, Clone)]
 pub enum BatchValidationError {
     /// The sealed batch hash does not match this worker's calculated digest.
     #[error("Invalid digest for sealed batch.")]
@@ -0,0 +1,139 @@
+//! Parallel recovery of transaction senders.
+//!
+//! Recovering the ECDSA signer is the most expensive part of decoding a transaction. Workers
+//! recover every transaction in a peer's batch to validate it and the engine recovers them again
+//! when the batch is executed. [SenderRecovery] recovers large batches on a thread pool and
+//! remembers recovered senders by transaction hash, so each signature is only recovered once.
+//!
+//! The transaction hash commits to the signature, so a cached sender is only used for the exact
+//! transaction it was recovered from.
+
+use crate::{
+    keccak256, Address, RecoveredTx, SignedTransactionIntoRecoveredExt as _, TransactionSigned,
+    TxHash, PARALLEL_SENDER_RECOVERY_THRESHOLD,
+};
+use alloy::eips::eip2718::Decodable2718 as _;
+use lru::LruCache;
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+use rayon::{
+    iter::{IntoParallelRefIterator as _, ParallelIterator as _},
+    ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder,
+};
+use std::{num::NonZeroUsize, sync::Arc};
+use thiserror::Error;
+
+/// The default number of recovered senders to remember.
+pub const DEFAULT_SENDER_CACHE_CAPACITY: usize = 100_000;
+
+/// The instance shared by [SenderRecovery::default].
+static DEFAULT_SENDER_RECOVERY: Lazy<SenderRecovery> =
+    Lazy::new(|| SenderRecovery::with_pool(None, DEFAULT_SENDER_CACHE_CAPACITY));
+
+/// Errors recovering a transaction from its encoded bytes.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SenderRecoveryError {
+    /// The encoded transaction is empty.
+    #[error("Empty transaction data")]
+    EmptyTransaction,
+    /// The bytes are not an EIP-2718 encoded transaction.
+    #[error("Failed to decode transaction: {0}")]
+    Decode(String),
+    /// The signer could not be recovered from the signature.
+    #[error("Invalid transaction signature")]
+    InvalidSignature,
+}
+
+/// Shared, parallel sender recovery with a cache keyed by transaction hash.
+///
+/// Clones share the thread pool and the cache.
+#[derive(Clone, Debug)]
+pub struct SenderRecovery {
+    inner: Arc<Inner>,
+}
+
+/// The pool and cache behind a [SenderRecovery].
+#[derive(Debug)]
+struct Inner {
+    /// The pool for recovering large batches, rayon's global pool if `None`.
+    pool: Option<ThreadPool>,
+    /// The recovered sender for each transaction hash.
+    senders: Mutex<LruCache<TxHash, Address>>,
+}
+
+impl SenderRecovery {
+    /// Create a new instance of [Self] with a dedicated pool of `threads` and a cache holding at
+    /// most `capacity` senders.
+    ///
+    /// A pool of zero threads uses one thread per CPU.
+    pub fn new(threads: usize, capacity: usize) -> Result<Self, ThreadPoolBuildError> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|index| format!("sender-recovery-{index}"))
+            .build()?;
+        Ok(Self::with_pool(Some(pool), capacity))
+    }
+
+    /// Create a new instance of [Self] recovering on `pool`.
+    fn with_pool(pool: Option<ThreadPool>, capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        Self { inner: Arc::new(Inner { pool, senders: Mutex::new(LruCache::new(capacity)) }) }
+    }
+
+    /// Decode an EIP-2718 encoded transaction and recover its sender.
+    pub fn recover(&self, tx: &[u8]) -> Result<RecoveredTx, SenderRecoveryError> {
+        if tx.is_empty() {
+            return Err(SenderRecoveryError::EmptyTransaction);
+        }
+        let tx_hash = keccak256(tx);
+        let transaction = TransactionSigned::decode_2718(&mut &tx[..])
+            .map_err(|e| SenderRecoveryError::Decode(e.to_string()))?;
+
+        if let Some(sender) = self.inner.senders.lock().get(&tx_hash).copied() {
+            return Ok(RecoveredTx::from_signed_transaction(transaction, sender));
+        }
+        let recovered = transaction
+            .try_into_ecrecovered()
+            .map_err(|_| SenderRecoveryError::InvalidSignature)?;
+        self.inner.senders.lock().put(tx_hash, recovered.signer());
+        Ok(recovered)
+    }
+
+    /// Decode and recover the sender of every transaction, in order.
+    ///
+    /// Transactions are recovered in parallel once there are at least
+    /// [PARALLEL_SENDER_RECOVERY_THRESHOLD].
+    pub fn recover_all<T>(&self, txs: &[T]) -> Vec<Result<RecoveredTx, SenderRecoveryError>>
+    where
+        T: AsRef<[u8]> + Sync,
+    {
+        if txs.len() < *PARALLEL_SENDER_RECOVERY_THRESHOLD {
+            return txs.iter().map(|tx| self.recover(tx.as_ref())).collect();
+        }
+        let recover = || txs.par_iter().map(|tx| self.recover(tx.as_ref())).collect();
+        match self.inner.pool.as_ref() {
+            Some(pool) => pool.install(recover),
+            None => recover(),
+        }
+    }
+
+    /// Remember `sender` as the sender of a transaction without recovering its signature.
+    ///
+    /// The sender is trusted for as long as it stays in the cache. Only dev networks that
+    /// impersonate accounts insert senders.
+    pub fn insert_sender(&self, tx_hash: TxHash, sender: Address) {
+        self.inner.senders.lock().put(tx_hash, sender);
+    }
+
+    /// The number of senders in the cache.
+    pub fn cached_senders(&self) -> usize {
+        self.inner.senders.lock().len()
+    }
+}
+
+impl Default for SenderRecovery {
+    /// The process wide instance, recovering on rayon's global pool.
+    fn default() -> Self {
+        DEFAULT_SENDER_RECOVERY.clone()
+    }
+}
@@ -93,7 +93,8 @@ else
         echo "creating validator keys"
         target/${RELEASE}/telcoin-network keytool generate validator \
             --datadir "${DATADIR}" \
-            --address "${ADDRESS}"
+            --address "${ADDRESS}" \
+            --instance "$((i+1))"
 
         echo "creating validator info for genesis"
         target/${RELEASE}/telcoin-network genesis add-validator --datadir "${DATADIR}"
@@ -124,8 +125,9 @@ if [ "$START" = true ]; then
         DATADIR="${ROOTDIR}/${VALIDATOR}"
         INSTANCE=$((i+1))
         RPC_PORT=$((8545-i))
-        METRICS="127.0.0.1:909$i"
-        CONSENSUS_METRICS="127.0.0.1:910$i"
+        # metrics ports are offset by the instance
+        METRICS="127.0.0.1:9090"
+        CONSENSUS_METRICS="127.0.0.1:9100"
 
         echo "Starting ${VALIDATOR} in background, rpc endpoint http://localhost:$RPC_PORT"
         # -vvv for INFO, -vvvvv for TRACE, etc